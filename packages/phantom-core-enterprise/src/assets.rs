@@ -0,0 +1,537 @@
+//! Asset Inventory
+//!
+//! Per-tenant inventory of hosts, users and services with a criticality
+//! level, owner, network zone and tags. Cores resolve the asset references
+//! they come across (a hostname in an event, an affected system named in an
+//! incident, a containment target) against it by id, name, alias or address,
+//! case-insensitively; a fully qualified host name also resolves by its first
+//! label and a `DOMAIN\user` or `user@domain` account by the bare user name.
+//!
+//! Assets may name the assets they depend on. `impact` follows those edges
+//! backwards to find everything an incident on a set of assets can reach,
+//! which containment uses as its blast radius. Inventories are filled through
+//! CRUD calls or a CSV import.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::sync::RwLock;
+use uuid::Uuid;
+
+/// Share of its weight an asset adds to the impact when it is only reachable
+/// through a dependency rather than affected itself
+const DEPENDENT_WEIGHT: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetKind {
+    Host,
+    User,
+    Service,
+}
+
+impl std::str::FromStr for AssetKind {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.trim().to_ascii_lowercase().as_str() {
+            "host" | "server" | "workstation" | "endpoint" | "device" => Ok(AssetKind::Host),
+            "user" | "account" | "identity" => Ok(AssetKind::User),
+            "service" | "application" | "app" => Ok(AssetKind::Service),
+            other => Err(format!("Unknown asset kind {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Criticality {
+    Low,
+    #[default]
+    Medium,
+    High,
+    Critical,
+}
+
+impl Criticality {
+    /// Share of the maximum impact an incident on the asset has
+    pub fn weight(&self) -> f64 {
+        match self {
+            Criticality::Low => 0.25,
+            Criticality::Medium => 0.5,
+            Criticality::High => 0.75,
+            Criticality::Critical => 1.0,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Criticality::Low => "low",
+            Criticality::Medium => "medium",
+            Criticality::High => "high",
+            Criticality::Critical => "critical",
+        }
+    }
+}
+
+impl std::str::FromStr for Criticality {
+    type Err = String;
+
+    /// `low` to `critical`, or tiers `4` (low) to `1` (critical)
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.trim().to_ascii_lowercase().as_str() {
+            "low" | "4" | "tier4" => Ok(Criticality::Low),
+            "medium" | "moderate" | "3" | "tier3" => Ok(Criticality::Medium),
+            "high" | "2" | "tier2" => Ok(Criticality::High),
+            "critical" | "1" | "tier1" | "crown_jewel" => Ok(Criticality::Critical),
+            other => Err(format!("Unknown criticality {}", other)),
+        }
+    }
+}
+
+/// An asset as submitted for creation or update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetRecord {
+    /// Updates the asset with this id; otherwise the asset of the same kind and name
+    #[serde(default)]
+    pub id: Option<String>,
+    pub kind: AssetKind,
+    pub name: String,
+    #[serde(default)]
+    pub criticality: Criticality,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub network_zone: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Other names: FQDNs, service accounts, email addresses
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// IP or MAC addresses of a host
+    #[serde(default)]
+    pub addresses: Vec<String>,
+    #[serde(default)]
+    pub os: Option<String>,
+    /// References of the assets this one needs to work
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Asset {
+    pub id: String,
+    pub kind: AssetKind,
+    pub name: String,
+    pub criticality: Criticality,
+    pub owner: Option<String>,
+    pub network_zone: Option<String>,
+    pub tags: Vec<String>,
+    pub aliases: Vec<String>,
+    pub addresses: Vec<String>,
+    pub os: Option<String>,
+    pub depends_on: Vec<String>,
+    pub attributes: HashMap<String, String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Asset {
+    fn keys(&self) -> impl Iterator<Item = String> + '_ {
+        std::iter::once(&self.id)
+            .chain(std::iter::once(&self.name))
+            .chain(&self.aliases)
+            .chain(&self.addresses)
+            .map(|key| key.trim().to_lowercase())
+            .filter(|key| !key.is_empty())
+    }
+}
+
+/// Outcome of a CSV import
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AssetImportReport {
+    pub created: usize,
+    pub updated: usize,
+    /// `line N: reason` for every row that was skipped
+    pub errors: Vec<String>,
+}
+
+/// What an incident on a set of assets reaches
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AssetImpact {
+    /// Assets the references resolved to
+    pub affected: Vec<Asset>,
+    /// Assets depending on an affected asset, directly or transitively
+    pub dependents: Vec<Asset>,
+    pub unresolved: Vec<String>,
+    pub highest_criticality: Option<Criticality>,
+    pub network_zones: Vec<String>,
+    pub owners: Vec<String>,
+    /// 0 to 1, from the criticality of the affected assets
+    pub impact_score: f64,
+    /// 0 to 1, also counting the dependents at half weight
+    pub blast_radius_score: f64,
+}
+
+#[derive(Debug, Default)]
+struct ScopeAssets {
+    assets: HashMap<String, Asset>,
+    /// Lowercased id, name, alias or address -> asset id
+    index: HashMap<String, String>,
+}
+
+impl ScopeAssets {
+    fn reindex(&mut self) {
+        self.index.clear();
+        let mut assets: Vec<&Asset> = self.assets.values().collect();
+        // Older assets win a contested key
+        assets.sort_by_key(|asset| asset.created_at);
+        for asset in assets {
+            for key in asset.keys() {
+                self.index.entry(key).or_insert_with(|| asset.id.clone());
+            }
+        }
+    }
+
+    fn resolve(&self, reference: &str) -> Option<&Asset> {
+        let reference = reference.trim().to_lowercase();
+        if reference.is_empty() {
+            return None;
+        }
+        let mut candidates = vec![reference.clone()];
+        if let Some((_, user)) = reference.rsplit_once('\\') {
+            candidates.push(user.to_string());
+        }
+        if let Some((user, _)) = reference.split_once('@') {
+            candidates.push(user.to_string());
+        }
+        if let Some((label, _)) = reference.split_once('.') {
+            if reference.parse::<std::net::IpAddr>().is_err() {
+                candidates.push(label.to_string());
+            }
+        }
+        candidates.iter().find_map(|key| self.index.get(key)).and_then(|id| self.assets.get(id))
+    }
+
+    fn find_existing(&self, record: &AssetRecord) -> Option<String> {
+        match &record.id {
+            Some(id) if self.assets.contains_key(id) => Some(id.clone()),
+            _ => self
+                .assets
+                .values()
+                .find(|asset| asset.kind == record.kind && asset.name.eq_ignore_ascii_case(record.name.trim()))
+                .map(|asset| asset.id.clone()),
+        }
+    }
+}
+
+fn clean_list(values: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    values
+        .into_iter()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty() && seen.insert(value.to_lowercase()))
+        .collect()
+}
+
+fn clean_text(value: Option<String>) -> Option<String> {
+    value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+/// Assets per scope, usually a tenant
+#[derive(Debug, Default)]
+pub struct AssetInventory {
+    scopes: RwLock<HashMap<String, ScopeAssets>>,
+}
+
+impl AssetInventory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an asset, or update the one the record's id or kind and name select
+    pub fn put(&self, scope: &str, record: AssetRecord) -> Result<Asset, String> {
+        self.upsert(scope, record).map(|(asset, _)| asset)
+    }
+
+    fn upsert(&self, scope: &str, record: AssetRecord) -> Result<(Asset, bool), String> {
+        let name = record.name.trim().to_string();
+        if name.is_empty() {
+            return Err("Asset name is required".to_string());
+        }
+        let mut scopes = self.scopes.write().unwrap_or_else(|e| e.into_inner());
+        let assets = scopes.entry(scope.to_string()).or_default();
+        let existing = assets.find_existing(&record);
+        if existing.is_none() && record.id.as_deref().is_some_and(|id| !id.trim().is_empty()) {
+            return Err(format!("Asset {} not found", record.id.unwrap_or_default()));
+        }
+        let now = Utc::now();
+        let created = existing.is_none();
+        let id = existing.unwrap_or_else(|| format!("ast_{}", Uuid::new_v4().simple()));
+        let created_at = assets.assets.get(&id).map_or(now, |asset| asset.created_at);
+        let asset = Asset {
+            id: id.clone(),
+            kind: record.kind,
+            name,
+            criticality: record.criticality,
+            owner: clean_text(record.owner),
+            network_zone: clean_text(record.network_zone),
+            tags: clean_list(record.tags),
+            aliases: clean_list(record.aliases),
+            addresses: clean_list(record.addresses),
+            os: clean_text(record.os),
+            depends_on: clean_list(record.depends_on),
+            attributes: record.attributes,
+            created_at,
+            updated_at: now,
+        };
+        assets.assets.insert(id, asset.clone());
+        assets.reindex();
+        Ok((asset, created))
+    }
+
+    pub fn remove(&self, scope: &str, id: &str) -> bool {
+        let mut scopes = self.scopes.write().unwrap_or_else(|e| e.into_inner());
+        let Some(assets) = scopes.get_mut(scope) else {
+            return false;
+        };
+        let removed = assets.assets.remove(id).is_some();
+        if removed {
+            assets.reindex();
+        }
+        removed
+    }
+
+    pub fn get(&self, scope: &str, id: &str) -> Option<Asset> {
+        self.scopes.read().unwrap_or_else(|e| e.into_inner()).get(scope)?.assets.get(id).cloned()
+    }
+
+    /// Assets of `scope` by kind and name
+    pub fn list(&self, scope: &str) -> Vec<Asset> {
+        let mut assets: Vec<Asset> = self
+            .scopes
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(scope)
+            .map(|assets| assets.assets.values().cloned().collect())
+            .unwrap_or_default();
+        assets.sort_by_key(|asset| (asset.kind as u8, asset.name.to_lowercase()));
+        assets
+    }
+
+    /// Asset an id, name, alias or address refers to
+    pub fn resolve(&self, scope: &str, reference: &str) -> Option<Asset> {
+        self.scopes.read().unwrap_or_else(|e| e.into_inner()).get(scope)?.resolve(reference).cloned()
+    }
+
+    /// Affected assets, their dependents and the impact of an incident on `references`
+    pub fn impact(&self, scope: &str, references: &[String]) -> AssetImpact {
+        let scopes = self.scopes.read().unwrap_or_else(|e| e.into_inner());
+        let empty = ScopeAssets::default();
+        let assets = scopes.get(scope).unwrap_or(&empty);
+
+        let mut impact = AssetImpact::default();
+        let mut affected_ids = HashSet::new();
+        for reference in references {
+            match assets.resolve(reference) {
+                Some(asset) => {
+                    if affected_ids.insert(asset.id.clone()) {
+                        impact.affected.push(asset.clone());
+                    }
+                }
+                None => impact.unresolved.push(reference.clone()),
+            }
+        }
+
+        // dependency id -> ids of the assets depending on it
+        let mut dependents: HashMap<&str, Vec<&str>> = HashMap::new();
+        for asset in assets.assets.values() {
+            for dependency in &asset.depends_on {
+                if let Some(target) = assets.resolve(dependency) {
+                    dependents.entry(target.id.as_str()).or_default().push(asset.id.as_str());
+                }
+            }
+        }
+        let mut reached: HashSet<&str> = affected_ids.iter().map(String::as_str).collect();
+        let mut queue: VecDeque<&str> = reached.iter().copied().collect();
+        while let Some(id) = queue.pop_front() {
+            for dependent in dependents.get(id).into_iter().flatten() {
+                if reached.insert(dependent) {
+                    queue.push_back(dependent);
+                    impact.dependents.push(assets.assets[*dependent].clone());
+                }
+            }
+        }
+        impact.dependents.sort_by(|a, b| b.criticality.cmp(&a.criticality).then_with(|| a.name.cmp(&b.name)));
+
+        let everything = || impact.affected.iter().chain(&impact.dependents);
+        impact.highest_criticality = everything().map(|asset| asset.criticality).max();
+        impact.network_zones = everything().filter_map(|asset| asset.network_zone.clone()).collect::<BTreeSet<_>>().into_iter().collect();
+        impact.owners = everything().filter_map(|asset| asset.owner.clone()).collect::<BTreeSet<_>>().into_iter().collect();
+        let unaffected = |assets: &[Asset], share: f64| assets.iter().map(|asset| 1.0 - asset.criticality.weight() * share).product::<f64>();
+        let affected_share = unaffected(&impact.affected, 1.0);
+        impact.impact_score = 1.0 - affected_share;
+        impact.blast_radius_score = 1.0 - affected_share * unaffected(&impact.dependents, DEPENDENT_WEIGHT);
+        impact
+    }
+
+    /// Import assets from CSV with a header row. `kind` and `name` are
+    /// required; `id`, `criticality`, `owner`, `network_zone` (or `zone`),
+    /// `os` and the `;`-separated `tags`, `aliases`, `addresses` and
+    /// `depends_on` are recognized, other columns become attributes. Rows
+    /// update the asset of the same kind and name.
+    pub fn import_csv(&self, scope: &str, data: &str) -> Result<AssetImportReport, String> {
+        let mut rows = parse_csv(data)?.into_iter();
+        let header: Vec<String> = rows
+            .next()
+            .ok_or("CSV has no header row")?
+            .into_iter()
+            .map(|column| column.trim().to_ascii_lowercase().replace([' ', '-'], "_"))
+            .collect();
+        for required in ["kind", "name"] {
+            if !header.iter().any(|column| column == required) {
+                return Err(format!("CSV has no {} column", required));
+            }
+        }
+
+        let mut report = AssetImportReport::default();
+        for (index, row) in rows.enumerate() {
+            let line = index + 2;
+            match record_from_row(&header, row) {
+                Ok(record) => match self.upsert(scope, record) {
+                    Ok((_, true)) => report.created += 1,
+                    Ok((_, false)) => report.updated += 1,
+                    Err(e) => report.errors.push(format!("line {}: {}", line, e)),
+                },
+                Err(e) => report.errors.push(format!("line {}: {}", line, e)),
+            }
+        }
+        Ok(report)
+    }
+
+    pub fn forget_scope(&self, scope: &str) -> usize {
+        self.scopes.write().unwrap_or_else(|e| e.into_inner()).remove(scope).map_or(0, |assets| assets.assets.len())
+    }
+}
+
+fn record_from_row(header: &[String], row: Vec<String>) -> Result<AssetRecord, String> {
+    let list = |value: &str| value.split([';', '|']).map(str::to_string).collect::<Vec<_>>();
+    let mut record = AssetRecord {
+        id: None,
+        kind: AssetKind::Host,
+        name: String::new(),
+        criticality: Criticality::default(),
+        owner: None,
+        network_zone: None,
+        tags: vec![],
+        aliases: vec![],
+        addresses: vec![],
+        os: None,
+        depends_on: vec![],
+        attributes: HashMap::new(),
+    };
+    for (column, value) in header.iter().zip(row) {
+        let value = value.trim().to_string();
+        match column.as_str() {
+            "kind" | "type" => record.kind = value.parse()?,
+            "name" => record.name = value,
+            "id" => record.id = Some(value).filter(|id| !id.is_empty()),
+            "criticality" if !value.is_empty() => record.criticality = value.parse()?,
+            "criticality" => {}
+            "owner" => record.owner = Some(value),
+            "network_zone" | "zone" => record.network_zone = Some(value),
+            "os" => record.os = Some(value),
+            "tags" => record.tags = list(&value),
+            "aliases" => record.aliases = list(&value),
+            "addresses" | "ip" | "ips" => record.addresses = list(&value),
+            "depends_on" => record.depends_on = list(&value),
+            _ if !value.is_empty() => {
+                record.attributes.insert(column.clone(), value);
+            }
+            _ => {}
+        }
+    }
+    Ok(record)
+}
+
+/// RFC 4180 records: quoted fields may hold commas, newlines and doubled quotes
+fn parse_csv(data: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = data.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\r') if chars.peek() == Some(&'\n') => {}
+            (false, '\n') => {
+                record.push(std::mem::take(&mut field));
+                if record.len() > 1 || !record[0].is_empty() {
+                    records.push(std::mem::take(&mut record));
+                }
+                record.clear();
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("Unterminated quoted field".to_string());
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_import_resolution_and_blast_radius() {
+        let inventory = AssetInventory::new();
+        let csv = "kind,name,criticality,owner,zone,aliases,addresses,depends_on,tags,location\n\
+                   host,db-01,critical,dba-team,datacenter,db-01.corp.example,10.0.0.5,,pci;prod,Frankfurt\n\
+                   service,payments,high,payments-team,dmz,,,db-01,prod,\n\
+                   service,checkout,medium,web-team,dmz,,,payments,,\n\
+                   user,jdoe,low,,,\"jdoe@corp.example\",,,,\n\
+                   server,bad,extreme,,,,,,,\n";
+        let report = inventory.import_csv("acme", csv).unwrap();
+        assert_eq!((report.created, report.updated), (4, 0));
+        assert_eq!(report.errors, vec!["line 6: Unknown criticality extreme".to_string()]);
+
+        let db = inventory.resolve("acme", "DB-01.corp.example").unwrap();
+        assert_eq!(db.criticality, Criticality::Critical);
+        assert_eq!(db.attributes["location"], "Frankfurt");
+        assert_eq!(inventory.resolve("acme", "10.0.0.5").unwrap().id, db.id);
+        assert_eq!(inventory.resolve("acme", "db-01.other.example").unwrap().id, db.id);
+        assert_eq!(inventory.resolve("acme", "CORP\\jdoe").unwrap().kind, AssetKind::User);
+        assert!(inventory.resolve("globex", "db-01").is_none());
+
+        let impact = inventory.impact("acme", &["db-01".to_string(), "ghost".to_string()]);
+        let dependents: Vec<&str> = impact.dependents.iter().map(|asset| asset.name.as_str()).collect();
+        assert_eq!(dependents, vec!["payments", "checkout"]);
+        assert_eq!(impact.unresolved, vec!["ghost".to_string()]);
+        assert_eq!(impact.highest_criticality, Some(Criticality::Critical));
+        assert_eq!(impact.network_zones, vec!["datacenter".to_string(), "dmz".to_string()]);
+        assert_eq!(impact.impact_score, 1.0);
+        assert!(inventory.impact("acme", &["checkout".to_string()]).blast_radius_score < 0.6);
+
+        let reimport = inventory.import_csv("acme", "kind,name,criticality\nhost,DB-01,high\n").unwrap();
+        assert_eq!((reimport.created, reimport.updated), (0, 1));
+        assert_eq!(inventory.get("acme", &db.id).unwrap().criticality, Criticality::High);
+        assert!(inventory.remove("acme", &db.id));
+        assert!(inventory.resolve("acme", "10.0.0.5").is_none());
+    }
+}
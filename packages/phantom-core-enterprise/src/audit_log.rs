@@ -0,0 +1,379 @@
+//! Tamper-evident audit log
+//!
+//! An append-only record of mutating operations. Each entry stores the
+//! SHA-256 hash of its predecessor and a hash over its own contents, so
+//! editing, dropping or reordering any entry breaks the chain from that
+//! point on. Parameters are kept only as a digest of their JSON form.
+//!
+//! A log can be backed by a JSON-lines file: existing entries are loaded and
+//! verified on open, and every append is written through before returning.
+
+use crate::compliance::AuditEvent;
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+/// `previous_hash` of the first entry in a chain
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+    Denied,
+}
+
+impl AuditOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditOutcome::Success => "success",
+            AuditOutcome::Failure => "failure",
+            AuditOutcome::Denied => "denied",
+        }
+    }
+}
+
+/// One link in the audit chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub sequence: u64,
+    pub timestamp: DateTime<Utc>,
+    /// Core that performed the operation (`secop`, `sandbox`, ...)
+    pub source: String,
+    pub actor: String,
+    pub operation: String,
+    pub resource: String,
+    pub params_digest: String,
+    pub outcome: AuditOutcome,
+    pub previous_hash: String,
+    pub hash: String,
+}
+
+impl AuditRecord {
+    fn compute_hash(&self) -> String {
+        let mut hasher = Sha256::new();
+        for field in [
+            self.previous_hash.as_str(),
+            &self.sequence.to_string(),
+            &self.timestamp.to_rfc3339(),
+            &self.source,
+            &self.actor,
+            &self.operation,
+            &self.resource,
+            &self.params_digest,
+            self.outcome.as_str(),
+        ] {
+            // Length-prefix every field so boundaries cannot be shifted
+            hasher.update((field.len() as u64).to_be_bytes());
+            hasher.update(field.as_bytes());
+        }
+        hex_digest(&hasher.finalize())
+    }
+
+    /// Express the record as a compliance audit event
+    pub fn to_audit_event(&self) -> AuditEvent {
+        let mut details = HashMap::new();
+        details.insert("sequence".to_string(), serde_json::json!(self.sequence));
+        details.insert("params_digest".to_string(), serde_json::json!(self.params_digest));
+        details.insert("hash".to_string(), serde_json::json!(self.hash));
+        AuditEvent {
+            event_id: self.hash.clone(),
+            timestamp: self.timestamp,
+            event_type: format!("{}.{}", self.source, self.operation),
+            user_id: self.actor.clone(),
+            resource: self.resource.clone(),
+            action: self.operation.clone(),
+            result: self.outcome.as_str().to_string(),
+            details,
+        }
+    }
+}
+
+/// Result of walking a chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditVerification {
+    pub valid: bool,
+    pub entries_checked: u64,
+    /// Sequence number of the first entry that fails verification
+    pub first_invalid: Option<u64>,
+    pub reason: Option<String>,
+    pub head_hash: String,
+}
+
+/// Filter for reading entries back out of the log
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditQuery {
+    pub actor: Option<String>,
+    pub operation: Option<String>,
+    pub resource: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+}
+
+impl AuditQuery {
+    fn matches(&self, record: &AuditRecord) -> bool {
+        self.actor.as_ref().is_none_or(|actor| &record.actor == actor)
+            && self.operation.as_ref().is_none_or(|operation| &record.operation == operation)
+            && self.resource.as_ref().is_none_or(|resource| &record.resource == resource)
+            && self.since.is_none_or(|since| record.timestamp >= since)
+            && self.until.is_none_or(|until| record.timestamp <= until)
+    }
+}
+
+struct AuditState {
+    records: Vec<AuditRecord>,
+    file: Option<File>,
+}
+
+/// Append-only, hash-chained audit log for one core
+pub struct AuditLog {
+    source: String,
+    state: Mutex<AuditState>,
+}
+
+impl AuditLog {
+    /// In-memory log for `source`
+    pub fn new(source: impl Into<String>) -> Self {
+        Self {
+            source: source.into(),
+            state: Mutex::new(AuditState { records: Vec::new(), file: None }),
+        }
+    }
+
+    /// Open (or create) a JSON-lines log file and continue its chain.
+    ///
+    /// Fails if the existing file does not verify, rather than appending to a
+    /// chain that has already been tampered with.
+    pub fn open(source: impl Into<String>, path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let mut records = Vec::new();
+        if path.exists() {
+            let reader = BufReader::new(
+                File::open(path).map_err(|e| format!("Failed to open audit log {}: {}", path.display(), e))?,
+            );
+            for (index, line) in reader.lines().enumerate() {
+                let line = line.map_err(|e| format!("Failed to read audit log: {}", e))?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let record: AuditRecord = serde_json::from_str(&line)
+                    .map_err(|e| format!("Malformed audit record on line {}: {}", index + 1, e))?;
+                records.push(record);
+            }
+            let verification = verify_records(&records);
+            if !verification.valid {
+                return Err(format!(
+                    "Audit log {} failed verification at entry {}: {}",
+                    path.display(),
+                    verification.first_invalid.unwrap_or_default(),
+                    verification.reason.unwrap_or_default()
+                ));
+            }
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("Failed to open audit log {}: {}", path.display(), e))?;
+        Ok(Self {
+            source: source.into(),
+            state: Mutex::new(AuditState { records, file: Some(file) }),
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Append an entry; `params` is reduced to a digest before storing
+    pub fn append(
+        &self,
+        actor: &str,
+        operation: &str,
+        resource: &str,
+        params: &serde_json::Value,
+        outcome: AuditOutcome,
+    ) -> Result<AuditRecord, String> {
+        let mut state = self.state.lock();
+        let (sequence, previous_hash) = match state.records.last() {
+            Some(last) => (last.sequence + 1, last.hash.clone()),
+            None => (0, GENESIS_HASH.to_string()),
+        };
+        let mut record = AuditRecord {
+            sequence,
+            timestamp: Utc::now(),
+            source: self.source.clone(),
+            actor: actor.to_string(),
+            operation: operation.to_string(),
+            resource: resource.to_string(),
+            params_digest: params_digest(params),
+            outcome,
+            previous_hash,
+            hash: String::new(),
+        };
+        record.hash = record.compute_hash();
+
+        if let Some(file) = state.file.as_mut() {
+            let line = serde_json::to_string(&record).map_err(|e| format!("Failed to encode audit record: {}", e))?;
+            writeln!(file, "{}", line)
+                .and_then(|_| file.flush())
+                .map_err(|e| format!("Failed to write audit record: {}", e))?;
+        }
+        state.records.push(record.clone());
+        Ok(record)
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().records.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Hash of the latest entry, or the genesis hash for an empty log
+    pub fn head_hash(&self) -> String {
+        self.state
+            .lock()
+            .records
+            .last()
+            .map(|record| record.hash.clone())
+            .unwrap_or_else(|| GENESIS_HASH.to_string())
+    }
+
+    /// Entries matching `query`, oldest first
+    pub fn entries(&self, query: &AuditQuery) -> Vec<AuditRecord> {
+        let state = self.state.lock();
+        let matching = state.records.iter().filter(|record| query.matches(record)).cloned();
+        match query.limit {
+            Some(limit) => matching.take(limit).collect(),
+            None => matching.collect(),
+        }
+    }
+
+    pub fn verify(&self) -> AuditVerification {
+        verify_records(&self.state.lock().records)
+    }
+
+    /// Export matching entries as JSON lines, ready for [`verify_jsonl`]
+    /// when the export covers the whole chain
+    pub fn export_jsonl(&self, query: &AuditQuery) -> Result<String, String> {
+        let mut out = String::new();
+        for record in self.entries(query) {
+            out.push_str(&serde_json::to_string(&record).map_err(|e| format!("Failed to encode audit record: {}", e))?);
+            out.push('\n');
+        }
+        Ok(out)
+    }
+}
+
+/// Verify a complete chain starting from the genesis entry
+pub fn verify_records(records: &[AuditRecord]) -> AuditVerification {
+    let mut previous_hash = GENESIS_HASH.to_string();
+    for (index, record) in records.iter().enumerate() {
+        let failure = if record.sequence != index as u64 {
+            Some(format!("expected sequence {}, found {}", index, record.sequence))
+        } else if record.previous_hash != previous_hash {
+            Some("previous hash does not match the preceding entry".to_string())
+        } else if record.compute_hash() != record.hash {
+            Some("entry contents do not match its hash".to_string())
+        } else {
+            None
+        };
+        if let Some(reason) = failure {
+            return AuditVerification {
+                valid: false,
+                entries_checked: index as u64,
+                first_invalid: Some(index as u64),
+                reason: Some(reason),
+                head_hash: previous_hash,
+            };
+        }
+        previous_hash = record.hash.clone();
+    }
+    AuditVerification {
+        valid: true,
+        entries_checked: records.len() as u64,
+        first_invalid: None,
+        reason: None,
+        head_hash: previous_hash,
+    }
+}
+
+/// Verify a JSON-lines export produced by [`AuditLog::export_jsonl`]
+pub fn verify_jsonl(data: &str) -> Result<AuditVerification, String> {
+    let records = data
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<AuditRecord>, _>>()
+        .map_err(|e| format!("Malformed audit record: {}", e))?;
+    Ok(verify_records(&records))
+}
+
+/// SHA-256 over the JSON encoding of `params`
+pub fn params_digest(params: &serde_json::Value) -> String {
+    hex_digest(&Sha256::digest(params.to_string().as_bytes()))
+}
+
+fn hex_digest(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn chain_detects_tampering() {
+        let log = AuditLog::new("secop");
+        log.append("alice", "create_incident", "inc-1", &json!({"title": "x"}), AuditOutcome::Success).unwrap();
+        log.append("bob", "close_incident", "inc-1", &json!({}), AuditOutcome::Success).unwrap();
+        log.append("eve", "close_incident", "inc-2", &json!({}), AuditOutcome::Denied).unwrap();
+        assert!(log.verify().valid);
+        assert_eq!(log.verify().head_hash, log.head_hash());
+
+        let by_bob = log.entries(&AuditQuery { actor: Some("bob".into()), ..Default::default() });
+        assert_eq!(by_bob.len(), 1);
+        assert_eq!(by_bob[0].previous_hash, log.entries(&AuditQuery::default())[0].hash);
+
+        let export = log.export_jsonl(&AuditQuery::default()).unwrap();
+        assert!(verify_jsonl(&export).unwrap().valid);
+
+        let tampered = export.replacen("\"actor\":\"bob\"", "\"actor\":\"mallory\"", 1);
+        let verification = verify_jsonl(&tampered).unwrap();
+        assert!(!verification.valid);
+        assert_eq!(verification.first_invalid, Some(1));
+
+        let mut records = log.entries(&AuditQuery::default());
+        records.remove(1);
+        assert_eq!(verify_records(&records).first_invalid, Some(1));
+    }
+
+    #[test]
+    fn file_backed_log_resumes_and_rejects_edits() {
+        let path = std::env::temp_dir().join(format!("phantom-audit-{}.jsonl", uuid::Uuid::new_v4()));
+        {
+            let log = AuditLog::open("sandbox", &path).unwrap();
+            log.append("alice", "submit_sample", "a.exe", &json!({"priority": "high"}), AuditOutcome::Success).unwrap();
+        }
+        let log = AuditLog::open("sandbox", &path).unwrap();
+        let second = log.append("alice", "cancel_analysis", "a.exe", &json!({}), AuditOutcome::Failure).unwrap();
+        assert_eq!(second.sequence, 1);
+        drop(log);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, contents.replace("cancel_analysis", "delete_analysis")).unwrap();
+        assert!(AuditLog::open("sandbox", &path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -12,6 +12,7 @@ use std::collections::HashMap;
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum ComplianceFramework {
     SOX,
+    SOC2,
     GDPR,
     HIPAA,
     NIST,
@@ -0,0 +1,551 @@
+//! Connector HTTP Framework
+//!
+//! Transport abstraction shared by every external integration (MISP,
+//! VirusTotal, Jira, ...), plus a record-and-replay layer that captures
+//! live HTTP interactions to cassette files and serves them back in test
+//! mode so integrations can be exercised deterministically without live
+//! services. Secrets are scrubbed before anything is written to disk.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Placeholder written in place of scrubbed secret values
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Environment variable selecting the cassette mode (`record`, `replay`, `passthrough`)
+pub const CASSETTE_MODE_ENV: &str = "PHANTOM_CONNECTOR_CASSETTE_MODE";
+
+/// Outbound HTTP request issued by a connector
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConnectorRequest {
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+impl ConnectorRequest {
+    pub fn new(method: &str, url: &str) -> Self {
+        Self {
+            method: method.to_uppercase(),
+            url: url.to_string(),
+            headers: BTreeMap::new(),
+            body: None,
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    pub fn json_body(mut self, body: &serde_json::Value) -> Self {
+        self.headers.insert("Content-Type".to_string(), "application/json".to_string());
+        self.body = Some(body.to_string());
+        self
+    }
+}
+
+/// HTTP response returned to a connector
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConnectorResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: String,
+}
+
+impl ConnectorResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn json(&self) -> Result<serde_json::Value, ConnectorError> {
+        serde_json::from_str(&self.body).map_err(|e| ConnectorError::Serialization(e.to_string()))
+    }
+}
+
+/// Connector transport errors
+#[derive(Debug, thiserror::Error)]
+pub enum ConnectorError {
+    #[error("Transport error: {0}")]
+    Transport(String),
+
+    #[error("Request timed out: {0}")]
+    Timeout(String),
+
+    #[error("No recorded interaction for {0}")]
+    CassetteMiss(String),
+
+    #[error("Cassette error: {0}")]
+    Cassette(String),
+
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+}
+
+/// Transport used by connectors to reach external services
+#[async_trait]
+pub trait HttpTransport: Send + Sync {
+    async fn send(&self, request: &ConnectorRequest) -> Result<ConnectorResponse, ConnectorError>;
+}
+
+/// Live transport backed by reqwest
+#[cfg(feature = "http-client")]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "http-client")]
+impl ReqwestTransport {
+    pub fn new(timeout: std::time::Duration) -> Result<Self, ConnectorError> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| ConnectorError::Transport(e.to_string()))?;
+        Ok(Self { client })
+    }
+}
+
+#[cfg(feature = "http-client")]
+#[async_trait]
+impl HttpTransport for ReqwestTransport {
+    async fn send(&self, request: &ConnectorRequest) -> Result<ConnectorResponse, ConnectorError> {
+        let method = reqwest::Method::from_bytes(request.method.as_bytes())
+            .map_err(|e| ConnectorError::Transport(e.to_string()))?;
+        let mut builder = self.client.request(method, &request.url);
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(body) = &request.body {
+            builder = builder.body(body.clone());
+        }
+
+        let response = builder.send().await.map_err(|e| {
+            if e.is_timeout() {
+                ConnectorError::Timeout(request.url.clone())
+            } else {
+                ConnectorError::Transport(e.to_string())
+            }
+        })?;
+
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(k, v)| v.to_str().ok().map(|v| (k.to_string(), v.to_string())))
+            .collect();
+        let body = response.text().await.map_err(|e| ConnectorError::Transport(e.to_string()))?;
+
+        Ok(ConnectorResponse { status, headers, body })
+    }
+}
+
+/// Removes credentials from requests and responses before they are persisted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretScrubber {
+    /// Header names (case-insensitive) whose values are replaced
+    pub header_names: Vec<String>,
+    /// Query parameter names (case-insensitive) whose values are replaced
+    pub query_params: Vec<String>,
+    /// JSON object keys (case-insensitive) whose values are replaced in bodies
+    pub json_keys: Vec<String>,
+    /// Known secret values replaced wherever they appear
+    pub literal_secrets: Vec<String>,
+}
+
+impl Default for SecretScrubber {
+    fn default() -> Self {
+        let names = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        Self {
+            header_names: names(&[
+                "authorization",
+                "proxy-authorization",
+                "cookie",
+                "set-cookie",
+                "x-api-key",
+                "x-apikey",
+                "api-key",
+                "x-auth-token",
+            ]),
+            query_params: names(&["apikey", "api_key", "key", "token", "access_token", "password", "secret"]),
+            json_keys: names(&[
+                "password",
+                "passwd",
+                "secret",
+                "client_secret",
+                "token",
+                "access_token",
+                "refresh_token",
+                "api_key",
+                "apikey",
+                "authkey",
+            ]),
+            literal_secrets: Vec::new(),
+        }
+    }
+}
+
+impl SecretScrubber {
+    /// Register a known secret value (e.g. the API key a connector was configured with)
+    pub fn with_secret(mut self, secret: &str) -> Self {
+        if !secret.is_empty() {
+            self.literal_secrets.push(secret.to_string());
+        }
+        self
+    }
+
+    pub fn scrub_request(&self, request: &ConnectorRequest) -> ConnectorRequest {
+        ConnectorRequest {
+            method: request.method.to_uppercase(),
+            url: self.scrub_url(&request.url),
+            headers: self.scrub_headers(&request.headers),
+            body: request.body.as_deref().map(|b| self.scrub_body(b)),
+        }
+    }
+
+    pub fn scrub_response(&self, response: &ConnectorResponse) -> ConnectorResponse {
+        ConnectorResponse {
+            status: response.status,
+            headers: self.scrub_headers(&response.headers),
+            body: self.scrub_body(&response.body),
+        }
+    }
+
+    fn scrub_headers(&self, headers: &BTreeMap<String, String>) -> BTreeMap<String, String> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                if self.header_names.iter().any(|h| h.eq_ignore_ascii_case(name)) {
+                    (name.clone(), REDACTED.to_string())
+                } else {
+                    (name.clone(), self.scrub_literals(value))
+                }
+            })
+            .collect()
+    }
+
+    fn scrub_url(&self, raw: &str) -> String {
+        let Ok(mut parsed) = url::Url::parse(raw) else {
+            return self.scrub_literals(raw);
+        };
+        if parsed.query().is_some() {
+            let pairs: Vec<(String, String)> = parsed
+                .query_pairs()
+                .map(|(k, v)| {
+                    if self.query_params.iter().any(|p| p.eq_ignore_ascii_case(&k)) {
+                        (k.into_owned(), REDACTED.to_string())
+                    } else {
+                        (k.into_owned(), v.into_owned())
+                    }
+                })
+                .collect();
+            parsed.query_pairs_mut().clear().extend_pairs(pairs);
+        }
+        if !parsed.username().is_empty() || parsed.password().is_some() {
+            let _ = parsed.set_username("");
+            let _ = parsed.set_password(None);
+        }
+        self.scrub_literals(parsed.as_str())
+    }
+
+    fn scrub_body(&self, body: &str) -> String {
+        match serde_json::from_str::<serde_json::Value>(body) {
+            Ok(mut value) => {
+                self.scrub_json(&mut value);
+                self.scrub_literals(&value.to_string())
+            }
+            Err(_) => self.scrub_literals(body),
+        }
+    }
+
+    fn scrub_json(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, entry) in map.iter_mut() {
+                    if self.json_keys.iter().any(|k| k.eq_ignore_ascii_case(key)) {
+                        *entry = serde_json::Value::String(REDACTED.to_string());
+                    } else {
+                        self.scrub_json(entry);
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(|item| self.scrub_json(item)),
+            _ => {}
+        }
+    }
+
+    fn scrub_literals(&self, text: &str) -> String {
+        self.literal_secrets
+            .iter()
+            .fold(text.to_string(), |acc, secret| acc.replace(secret.as_str(), REDACTED))
+    }
+}
+
+/// How a `RecordingTransport` treats traffic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CassetteMode {
+    /// Forward to the live transport and capture every interaction
+    Record,
+    /// Serve responses from the cassette; never touch the network
+    Replay,
+    /// Forward to the live transport without capturing
+    Passthrough,
+}
+
+impl CassetteMode {
+    /// Read the mode from `PHANTOM_CONNECTOR_CASSETTE_MODE`, defaulting to passthrough
+    pub fn from_env() -> Self {
+        match std::env::var(CASSETTE_MODE_ENV).unwrap_or_default().to_lowercase().as_str() {
+            "record" => CassetteMode::Record,
+            "replay" => CassetteMode::Replay,
+            _ => CassetteMode::Passthrough,
+        }
+    }
+}
+
+/// A single captured request/response pair
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CassetteInteraction {
+    pub request: ConnectorRequest,
+    pub response: ConnectorResponse,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Ordered set of interactions persisted as JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cassette {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub interactions: Vec<CassetteInteraction>,
+}
+
+impl Cassette {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            created_at: Utc::now(),
+            interactions: Vec::new(),
+        }
+    }
+
+    pub async fn load(path: &Path) -> Result<Self, ConnectorError> {
+        let raw = tokio::fs::read_to_string(path)
+            .await
+            .map_err(|e| ConnectorError::Cassette(format!("{}: {}", path.display(), e)))?;
+        serde_json::from_str(&raw).map_err(|e| ConnectorError::Serialization(e.to_string()))
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<(), ConnectorError> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| ConnectorError::Cassette(e.to_string()))?;
+        }
+        let raw = serde_json::to_string_pretty(self).map_err(|e| ConnectorError::Serialization(e.to_string()))?;
+        tokio::fs::write(path, raw)
+            .await
+            .map_err(|e| ConnectorError::Cassette(format!("{}: {}", path.display(), e)))
+    }
+}
+
+struct CassetteState {
+    cassette: Cassette,
+    consumed: Vec<bool>,
+}
+
+/// Transport wrapper that records live interactions to a cassette or replays them
+pub struct RecordingTransport {
+    inner: Option<Arc<dyn HttpTransport>>,
+    mode: CassetteMode,
+    path: PathBuf,
+    scrubber: SecretScrubber,
+    state: Mutex<CassetteState>,
+}
+
+impl RecordingTransport {
+    /// Capture interactions made through `inner` into a new cassette at `path`
+    pub fn record(inner: Arc<dyn HttpTransport>, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let name = cassette_name(&path);
+        Self {
+            inner: Some(inner),
+            mode: CassetteMode::Record,
+            path,
+            scrubber: SecretScrubber::default(),
+            state: Mutex::new(CassetteState {
+                cassette: Cassette::new(&name),
+                consumed: Vec::new(),
+            }),
+        }
+    }
+
+    /// Serve responses from the cassette at `path` without a live transport
+    pub async fn replay(path: impl Into<PathBuf>) -> Result<Self, ConnectorError> {
+        let path = path.into();
+        let cassette = Cassette::load(&path).await?;
+        let consumed = vec![false; cassette.interactions.len()];
+        Ok(Self {
+            inner: None,
+            mode: CassetteMode::Replay,
+            path,
+            scrubber: SecretScrubber::default(),
+            state: Mutex::new(CassetteState { cassette, consumed }),
+        })
+    }
+
+    /// Forward to `inner` without capturing anything
+    pub fn passthrough(inner: Arc<dyn HttpTransport>) -> Self {
+        Self {
+            inner: Some(inner),
+            mode: CassetteMode::Passthrough,
+            path: PathBuf::new(),
+            scrubber: SecretScrubber::default(),
+            state: Mutex::new(CassetteState {
+                cassette: Cassette::new("passthrough"),
+                consumed: Vec::new(),
+            }),
+        }
+    }
+
+    /// Build a transport for the mode selected by `PHANTOM_CONNECTOR_CASSETTE_MODE`
+    pub async fn from_env(inner: Arc<dyn HttpTransport>, path: impl Into<PathBuf>) -> Result<Self, ConnectorError> {
+        match CassetteMode::from_env() {
+            CassetteMode::Record => Ok(Self::record(inner, path)),
+            CassetteMode::Replay => Self::replay(path).await,
+            CassetteMode::Passthrough => Ok(Self::passthrough(inner)),
+        }
+    }
+
+    pub fn with_scrubber(mut self, scrubber: SecretScrubber) -> Self {
+        self.scrubber = scrubber;
+        self
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    pub async fn interaction_count(&self) -> usize {
+        self.state.lock().await.cassette.interactions.len()
+    }
+
+    /// Persist recorded interactions; a no-op outside record mode
+    pub async fn save(&self) -> Result<(), ConnectorError> {
+        if self.mode != CassetteMode::Record {
+            return Ok(());
+        }
+        let state = self.state.lock().await;
+        state.cassette.save(&self.path).await
+    }
+
+    fn live(&self) -> Result<&Arc<dyn HttpTransport>, ConnectorError> {
+        self.inner
+            .as_ref()
+            .ok_or_else(|| ConnectorError::Transport("No live transport configured".to_string()))
+    }
+}
+
+#[async_trait]
+impl HttpTransport for RecordingTransport {
+    async fn send(&self, request: &ConnectorRequest) -> Result<ConnectorResponse, ConnectorError> {
+        match self.mode {
+            CassetteMode::Passthrough => self.live()?.send(request).await,
+            CassetteMode::Record => {
+                let response = self.live()?.send(request).await?;
+                let mut state = self.state.lock().await;
+                state.cassette.interactions.push(CassetteInteraction {
+                    request: self.scrubber.scrub_request(request),
+                    response: self.scrubber.scrub_response(&response),
+                    recorded_at: Utc::now(),
+                });
+                state.consumed.push(false);
+                Ok(response)
+            }
+            CassetteMode::Replay => {
+                let wanted = self.scrubber.scrub_request(request);
+                let mut state = self.state.lock().await;
+                let CassetteState { cassette, consumed } = &mut *state;
+                // Identical requests are served in recorded order
+                let index = cassette
+                    .interactions
+                    .iter()
+                    .enumerate()
+                    .position(|(i, interaction)| !consumed[i] && requests_match(&interaction.request, &wanted))
+                    .ok_or_else(|| ConnectorError::CassetteMiss(format!("{} {}", wanted.method, wanted.url)))?;
+                consumed[index] = true;
+                Ok(cassette.interactions[index].response.clone())
+            }
+        }
+    }
+}
+
+/// Requests match on method, URL and body; headers are not compared so that
+/// user agents, dates and request ids do not break replay.
+fn requests_match(recorded: &ConnectorRequest, incoming: &ConnectorRequest) -> bool {
+    recorded.method.eq_ignore_ascii_case(&incoming.method)
+        && recorded.url == incoming.url
+        && normalize_body(recorded.body.as_deref()) == normalize_body(incoming.body.as_deref())
+}
+
+fn normalize_body(body: Option<&str>) -> Option<serde_json::Value> {
+    body.filter(|b| !b.is_empty()).map(|b| {
+        serde_json::from_str(b).unwrap_or_else(|_| serde_json::Value::String(b.to_string()))
+    })
+}
+
+fn cassette_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "cassette".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StaticTransport;
+
+    #[async_trait]
+    impl HttpTransport for StaticTransport {
+        async fn send(&self, request: &ConnectorRequest) -> Result<ConnectorResponse, ConnectorError> {
+            Ok(ConnectorResponse {
+                status: 200,
+                headers: BTreeMap::from([("Set-Cookie".to_string(), "session=abc".to_string())]),
+                body: serde_json::json!({ "echo": request.url, "token": "live-token" }).to_string(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay_scrubs_secrets() {
+        let path = std::env::temp_dir().join(format!("phantom-cassette-{}.json", uuid::Uuid::new_v4()));
+        let request = ConnectorRequest::new("get", "https://misp.example/events?apikey=s3cr3t&limit=5")
+            .header("Authorization", "s3cr3t");
+
+        let recorder = RecordingTransport::record(Arc::new(StaticTransport), &path)
+            .with_scrubber(SecretScrubber::default().with_secret("s3cr3t"));
+        let live = recorder.send(&request).await.unwrap();
+        recorder.save().await.unwrap();
+
+        let raw = std::fs::read_to_string(&path).unwrap();
+        assert!(!raw.contains("s3cr3t"));
+        assert!(!raw.contains("live-token"));
+        assert!(!raw.contains("session=abc"));
+
+        let replayer = RecordingTransport::replay(&path).await.unwrap();
+        let replayed = replayer.send(&request).await.unwrap();
+        assert_eq!(replayed.status, live.status);
+        assert!(matches!(replayer.send(&request).await, Err(ConnectorError::CassetteMiss(_))));
+
+        let _ = std::fs::remove_file(&path);
+    }
+}
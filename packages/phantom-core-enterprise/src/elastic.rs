@@ -0,0 +1,537 @@
+//! Elasticsearch Bulk Indexing
+//!
+//! Streams documents (hunt results, sandbox verdict summaries) into
+//! Elasticsearch through the `_bulk` API for Kibana dashboards. Each stream
+//! gets an index template and monthly indices named
+//! `<prefix>-<stream>-YYYY.MM` from the document timestamp, so ILM policies
+//! and retention can work per month. Documents are buffered and sent in
+//! batches; throttled requests (HTTP 429, whole-request or per item) are
+//! retried with exponential backoff, other item failures are counted and
+//! reported. Requests go through the connector [`HttpTransport`], so tests
+//! can replay recorded Elasticsearch responses.
+
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::connectors::{ConnectorRequest, HttpTransport};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElasticConfig {
+    /// Cluster URL, e.g. `https://es.example:9200`
+    pub base_url: String,
+    /// Encoded API key, sent as `Authorization: ApiKey ...`
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default = "default_index_prefix")]
+    pub index_prefix: String,
+    /// ILM policy set on every index created from the templates
+    #[serde(default)]
+    pub ilm_policy: Option<String>,
+    /// Documents per `_bulk` request
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// Interval of the background flusher
+    #[serde(default = "default_flush_interval_secs")]
+    pub flush_interval_secs: u64,
+    /// Attempts for a throttled request or document before it is dropped
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+    /// Documents held while Elasticsearch is unavailable; the oldest are dropped beyond this
+    #[serde(default = "default_max_buffered")]
+    pub max_buffered: usize,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_index_prefix() -> String {
+    "phantom".to_string()
+}
+
+fn default_batch_size() -> usize {
+    500
+}
+
+fn default_flush_interval_secs() -> u64 {
+    5
+}
+
+fn default_max_retries() -> u32 {
+    5
+}
+
+fn default_retry_backoff_ms() -> u64 {
+    500
+}
+
+fn default_max_buffered() -> usize {
+    50_000
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+impl ElasticConfig {
+    /// Configuration with credentials removed, for display and auditing
+    pub fn redacted(&self) -> Self {
+        let redact = |value: &Option<String>| value.as_ref().map(|_| crate::connectors::REDACTED.to_string());
+        Self {
+            api_key: redact(&self.api_key),
+            password: redact(&self.password),
+            ..self.clone()
+        }
+    }
+
+    /// Monthly index of `stream` holding documents from `timestamp`
+    pub fn index_name(&self, stream: &str, timestamp: DateTime<Utc>) -> String {
+        format!("{}-{}-{}", self.index_prefix, stream, timestamp.format("%Y.%m"))
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url.trim_end_matches('/'), path.trim_start_matches('/'))
+    }
+
+    fn request(&self, method: &str, path: &str) -> ConnectorRequest {
+        let request = ConnectorRequest::new(method, &self.url(path));
+        match (&self.api_key, &self.username) {
+            (Some(key), _) => request.header("Authorization", &format!("ApiKey {}", key)),
+            (None, Some(user)) => {
+                let credentials = format!("{}:{}", user, self.password.as_deref().unwrap_or_default());
+                let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
+                request.header("Authorization", &format!("Basic {}", encoded))
+            }
+            (None, None) => request,
+        }
+    }
+}
+
+/// Composable index template for the monthly indices of `stream`
+pub fn index_template(config: &ElasticConfig, stream: &str, properties: &Value) -> Value {
+    let mut settings = json!({ "number_of_shards": 1 });
+    if let Some(policy) = &config.ilm_policy {
+        settings["index.lifecycle.name"] = json!(policy);
+    }
+    json!({
+        "index_patterns": [format!("{}-{}-*", config.index_prefix, stream)],
+        "priority": 200,
+        "template": {
+            "settings": settings,
+            "mappings": {
+                "dynamic": true,
+                "properties": properties
+            }
+        },
+        "_meta": { "managed_by": "phantom-spire" }
+    })
+}
+
+#[derive(Debug, Clone)]
+struct PendingDocument {
+    index: String,
+    id: String,
+    source: Value,
+    attempts: u32,
+}
+
+/// Indexing counters
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IndexerMetrics {
+    pub queued: u64,
+    pub indexed: u64,
+    /// Documents Elasticsearch rejected or that ran out of retries
+    pub failed: u64,
+    /// Documents discarded because the buffer was full
+    pub dropped: u64,
+    /// Throttled (429) responses and items that were retried
+    pub throttled: u64,
+    pub bulk_requests: u64,
+    pub buffered: usize,
+    pub templates_installed: Vec<String>,
+    pub last_error: Option<String>,
+    pub last_flush_at: Option<DateTime<Utc>>,
+}
+
+/// Outcome of one flush
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BulkReport {
+    pub indexed: usize,
+    pub failed: usize,
+    pub retried: usize,
+    /// Item errors by Elasticsearch error type
+    pub errors: BTreeMap<String, usize>,
+}
+
+pub struct BulkIndexer {
+    config: ElasticConfig,
+    transport: Arc<dyn HttpTransport>,
+    templates: Mutex<BTreeMap<String, Value>>,
+    queue: Mutex<VecDeque<PendingDocument>>,
+    metrics: Mutex<IndexerMetrics>,
+    flush_lock: tokio::sync::Mutex<()>,
+}
+
+impl BulkIndexer {
+    pub fn new(config: ElasticConfig, transport: Arc<dyn HttpTransport>) -> Result<Self, String> {
+        let parsed = url::Url::parse(&config.base_url).map_err(|e| format!("Invalid Elasticsearch URL: {}", e))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(format!("Unsupported Elasticsearch scheme: {}", parsed.scheme()));
+        }
+        if config.batch_size == 0 || config.max_buffered == 0 {
+            return Err("Batch size and buffer size must be at least 1".to_string());
+        }
+        Ok(Self {
+            config,
+            transport,
+            templates: Mutex::new(BTreeMap::new()),
+            queue: Mutex::new(VecDeque::new()),
+            metrics: Mutex::new(IndexerMetrics::default()),
+            flush_lock: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Indexer using the live HTTP transport
+    #[cfg(feature = "http-client")]
+    pub fn connect(config: ElasticConfig) -> Result<Self, String> {
+        let transport = crate::connectors::ReqwestTransport::new(Duration::from_secs(config.timeout_secs))
+            .map_err(|e| e.to_string())?;
+        Self::new(config, Arc::new(transport))
+    }
+
+    #[cfg(not(feature = "http-client"))]
+    pub fn connect(config: ElasticConfig) -> Result<Self, String> {
+        let _ = config;
+        Err("Elasticsearch indexing requires the http-client feature".to_string())
+    }
+
+    pub fn config(&self) -> &ElasticConfig {
+        &self.config
+    }
+
+    pub fn metrics(&self) -> IndexerMetrics {
+        let mut metrics = self.metrics.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        metrics.buffered = self.queue.lock().unwrap_or_else(|e| e.into_inner()).len();
+        metrics
+    }
+
+    /// Declare a stream and the field mappings of its index template
+    pub fn register_stream(&self, stream: &str, properties: Value) {
+        let template = index_template(&self.config, stream, &properties);
+        self.templates.lock().unwrap_or_else(|e| e.into_inner()).insert(stream.to_string(), template);
+    }
+
+    /// Create or update the index templates of every registered stream
+    pub async fn install_templates(&self) -> Result<Vec<String>, String> {
+        let templates = self.templates.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let mut installed = Vec::new();
+        for (stream, template) in templates {
+            let name = format!("{}-{}", self.config.index_prefix, stream);
+            let request = self.config.request("PUT", &format!("_index_template/{}", name)).json_body(&template);
+            let response = self.transport.send(&request).await.map_err(|e| e.to_string())?;
+            if !response.is_success() {
+                return Err(format!("Installing index template {} failed with HTTP {}: {}", name, response.status, response.body));
+            }
+            installed.push(name);
+        }
+        self.metrics.lock().unwrap_or_else(|e| e.into_inner()).templates_installed = installed.clone();
+        Ok(installed)
+    }
+
+    /// Buffer a document for the monthly index of `stream`; `id` makes
+    /// re-indexing the same record idempotent. Returns true once a full batch
+    /// is waiting.
+    pub fn enqueue(&self, stream: &str, id: &str, timestamp: DateTime<Utc>, source: Value) -> bool {
+        let document = PendingDocument {
+            index: self.config.index_name(stream, timestamp),
+            id: id.to_string(),
+            source,
+            attempts: 0,
+        };
+        let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+        let mut metrics = self.metrics.lock().unwrap_or_else(|e| e.into_inner());
+        if queue.len() >= self.config.max_buffered {
+            queue.pop_front();
+            metrics.dropped += 1;
+        }
+        queue.push_back(document);
+        metrics.queued += 1;
+        queue.len() >= self.config.batch_size
+    }
+
+    /// Send every buffered document, a batch at a time
+    pub async fn flush(&self) -> BulkReport {
+        let _flushing = self.flush_lock.lock().await;
+        let mut report = BulkReport::default();
+        loop {
+            let batch: Vec<PendingDocument> = {
+                let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+                let size = queue.len().min(self.config.batch_size);
+                queue.drain(..size).collect()
+            };
+            if batch.is_empty() {
+                break;
+            }
+            if !self.send_batch(batch, &mut report).await {
+                break;
+            }
+        }
+        self.metrics.lock().unwrap_or_else(|e| e.into_inner()).last_flush_at = Some(Utc::now());
+        report
+    }
+
+    /// Send one batch, retrying throttled documents; false when the batch
+    /// could not be delivered and was put back
+    async fn send_batch(&self, mut batch: Vec<PendingDocument>, report: &mut BulkReport) -> bool {
+        let mut attempt = 0;
+        while !batch.is_empty() {
+            let mut body = String::new();
+            for document in &batch {
+                body.push_str(&json!({ "index": { "_index": document.index, "_id": document.id } }).to_string());
+                body.push('\n');
+                body.push_str(&document.source.to_string());
+                body.push('\n');
+            }
+            let mut request = self.config.request("POST", "_bulk").header("Content-Type", "application/x-ndjson");
+            request.body = Some(body);
+            self.metrics.lock().unwrap_or_else(|e| e.into_inner()).bulk_requests += 1;
+
+            let response = match self.transport.send(&request).await {
+                Ok(response) => response,
+                Err(e) => {
+                    self.requeue(batch, format!("Bulk request failed: {}", e));
+                    return false;
+                }
+            };
+            if response.status == 429 {
+                self.metrics.lock().unwrap_or_else(|e| e.into_inner()).throttled += 1;
+                if attempt >= self.config.max_retries {
+                    self.requeue(batch, "Bulk request throttled (HTTP 429)".to_string());
+                    return false;
+                }
+                report.retried += batch.len();
+                self.backoff(attempt).await;
+                attempt += 1;
+                continue;
+            }
+            if !response.is_success() {
+                self.requeue(batch, format!("Bulk request failed with HTTP {}: {}", response.status, response.body));
+                return false;
+            }
+
+            let items = response
+                .json()
+                .ok()
+                .and_then(|body| body.get("items").and_then(Value::as_array).cloned())
+                .unwrap_or_default();
+            if items.len() != batch.len() {
+                let error = format!("Bulk response has {} items for {} documents", items.len(), batch.len());
+                self.requeue(batch, error);
+                return false;
+            }
+            let mut indexed = 0;
+            let mut throttled = Vec::new();
+            for (document, item) in batch.into_iter().zip(items.iter().map(|item| item.get("index").unwrap_or(item))) {
+                let status = item.get("status").and_then(Value::as_u64).unwrap_or(0);
+                if (200..300).contains(&status) {
+                    indexed += 1;
+                } else if status == 429 && document.attempts < self.config.max_retries {
+                    throttled.push(PendingDocument { attempts: document.attempts + 1, ..document });
+                } else {
+                    let kind = item
+                        .pointer("/error/type")
+                        .and_then(Value::as_str)
+                        .unwrap_or(if status == 429 { "throttled" } else { "unknown" })
+                        .to_string();
+                    let reason = item.pointer("/error/reason").and_then(Value::as_str).unwrap_or_default();
+                    self.metrics.lock().unwrap_or_else(|e| e.into_inner()).last_error =
+                        Some(format!("{} rejected by {}: {} {}", document.id, document.index, kind, reason));
+                    *report.errors.entry(kind).or_default() += 1;
+                    report.failed += 1;
+                    self.metrics.lock().unwrap_or_else(|e| e.into_inner()).failed += 1;
+                }
+            }
+            report.indexed += indexed;
+            {
+                let mut metrics = self.metrics.lock().unwrap_or_else(|e| e.into_inner());
+                metrics.indexed += indexed as u64;
+                metrics.throttled += throttled.len() as u64;
+            }
+            if !throttled.is_empty() {
+                report.retried += throttled.len();
+                self.backoff(attempt).await;
+                attempt += 1;
+            }
+            batch = throttled;
+        }
+        true
+    }
+
+    /// Put an undelivered batch back at the head of the queue
+    fn requeue(&self, batch: Vec<PendingDocument>, error: String) {
+        log::warn!("Elasticsearch indexing: {}", error);
+        let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+        for document in batch.into_iter().rev() {
+            queue.push_front(document);
+        }
+        let mut metrics = self.metrics.lock().unwrap_or_else(|e| e.into_inner());
+        while queue.len() > self.config.max_buffered {
+            queue.pop_back();
+            metrics.dropped += 1;
+        }
+        metrics.last_error = Some(error);
+    }
+
+    async fn backoff(&self, attempt: u32) {
+        let delay = self.config.retry_backoff_ms.saturating_mul(1 << attempt.min(10));
+        tokio::time::sleep(Duration::from_millis(delay)).await;
+    }
+
+    /// Flush every `flush_interval_secs` until the returned task is aborted
+    pub fn spawn_flusher(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let indexer = Arc::downgrade(self);
+        let interval = Duration::from_secs(self.config.flush_interval_secs.max(1));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let Some(indexer) = indexer.upgrade() else { break };
+                indexer.flush().await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connectors::{ConnectorError, ConnectorResponse};
+    use async_trait::async_trait;
+    use chrono::TimeZone;
+
+    /// Replies with queued bulk responses and records every request
+    #[derive(Default)]
+    struct BulkTransport {
+        responses: Mutex<VecDeque<ConnectorResponse>>,
+        requests: Mutex<Vec<ConnectorRequest>>,
+    }
+
+    impl BulkTransport {
+        fn reply(&self, status: u16, body: Value) {
+            self.responses.lock().unwrap().push_back(ConnectorResponse {
+                status,
+                headers: BTreeMap::new(),
+                body: body.to_string(),
+            });
+        }
+    }
+
+    #[async_trait]
+    impl HttpTransport for BulkTransport {
+        async fn send(&self, request: &ConnectorRequest) -> Result<ConnectorResponse, ConnectorError> {
+            self.requests.lock().unwrap().push(request.clone());
+            Ok(self.responses.lock().unwrap().pop_front().unwrap_or(ConnectorResponse {
+                status: 200,
+                headers: BTreeMap::new(),
+                body: "{}".to_string(),
+            }))
+        }
+    }
+
+    fn config() -> ElasticConfig {
+        serde_json::from_value(json!({
+            "base_url": "https://es.example:9200/",
+            "api_key": "c2VjcmV0",
+            "ilm_policy": "phantom-90d",
+            "retry_backoff_ms": 1
+        }))
+        .unwrap()
+    }
+
+    fn item(status: u16, error: Option<&str>) -> Value {
+        match error {
+            Some(kind) => json!({ "index": { "status": status, "error": { "type": kind, "reason": "rejected" } } }),
+            None => json!({ "index": { "status": status } }),
+        }
+    }
+
+    #[test]
+    fn test_monthly_index_names_and_templates() {
+        let config = config();
+        let at = Utc.with_ymd_and_hms(2025, 3, 14, 9, 0, 0).unwrap();
+        assert_eq!(config.index_name("hunts", at), "phantom-hunts-2025.03");
+
+        let template = index_template(&config, "hunts", &json!({ "@timestamp": { "type": "date" } }));
+        assert_eq!(template["index_patterns"][0], "phantom-hunts-*");
+        assert_eq!(template["template"]["settings"]["index.lifecycle.name"], "phantom-90d");
+        assert_eq!(config.redacted().api_key.as_deref(), Some(crate::connectors::REDACTED));
+    }
+
+    #[tokio::test]
+    async fn test_bulk_flush_retries_throttled_requests_and_items() {
+        let transport = Arc::new(BulkTransport::default());
+        let indexer = BulkIndexer::new(config(), transport.clone()).unwrap();
+        indexer.register_stream("hunts", json!({ "@timestamp": { "type": "date" } }));
+        assert_eq!(indexer.install_templates().await.unwrap(), vec!["phantom-hunts"]);
+
+        let at = Utc.with_ymd_and_hms(2025, 3, 14, 9, 0, 0).unwrap();
+        for id in ["h1", "h2", "h3"] {
+            indexer.enqueue("hunts", id, at, json!({ "hunt_id": id }));
+        }
+        transport.reply(429, json!({ "error": "too many requests" }));
+        transport.reply(200, json!({ "errors": true, "items": [
+            item(201, None), item(429, Some("es_rejected_execution_exception")), item(400, Some("mapper_parsing_exception"))
+        ] }));
+        transport.reply(200, json!({ "errors": false, "items": [item(201, None)] }));
+
+        let report = indexer.flush().await;
+        assert_eq!(report.indexed, 2);
+        assert_eq!(report.failed, 1);
+        assert_eq!(report.errors["mapper_parsing_exception"], 1);
+
+        let requests = transport.requests.lock().unwrap().clone();
+        assert_eq!(requests[0].url, "https://es.example:9200/_index_template/phantom-hunts");
+        assert_eq!(requests[1].headers["Authorization"], "ApiKey c2VjcmV0");
+        let bulk = requests[1].body.as_deref().unwrap();
+        assert!(bulk.contains(r#"{"index":{"_id":"h1","_index":"phantom-hunts-2025.03"}}"#));
+        assert!(requests[3].body.as_deref().unwrap().contains(r#""_id":"h2""#));
+        assert!(!requests[3].body.as_deref().unwrap().contains(r#""_id":"h1""#));
+
+        let metrics = indexer.metrics();
+        assert_eq!(metrics.indexed, 2);
+        assert_eq!(metrics.failed, 1);
+        assert_eq!(metrics.throttled, 2);
+        assert_eq!(metrics.bulk_requests, 3);
+        assert_eq!(metrics.buffered, 0);
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_cluster_keeps_documents_buffered() {
+        let transport = Arc::new(BulkTransport::default());
+        let indexer = BulkIndexer::new(ElasticConfig { max_buffered: 2, ..config() }, transport.clone()).unwrap();
+        for id in ["a", "b", "c"] {
+            indexer.enqueue("sandbox", id, Utc::now(), json!({}));
+        }
+        transport.reply(503, json!({ "error": "unavailable" }));
+        let report = indexer.flush().await;
+        assert_eq!(report.indexed, 0);
+
+        let metrics = indexer.metrics();
+        assert_eq!(metrics.buffered, 2);
+        assert_eq!(metrics.dropped, 1);
+        assert!(metrics.last_error.unwrap().contains("HTTP 503"));
+
+        transport.reply(200, json!({ "items": [item(200, None), item(200, None)] }));
+        assert_eq!(indexer.flush().await.indexed, 2);
+    }
+}
@@ -0,0 +1,1084 @@
+//! IOC Enrichment Pipeline
+//!
+//! Cores hand indicators to an [`EnrichmentPipeline`], which fans each one
+//! out to the registered [`Enricher`] providers with a global concurrency
+//! limit, a per-call timeout and a TTL cache per provider and indicator.
+//!
+//! Built-in providers:
+//! - GeoIP from MaxMind City/ASN databases (`geoip` feature)
+//! - WHOIS over port 43, following IANA referrals
+//! - Passive DNS, a caller-fed store standing in for a pDNS service
+//! - Local reputation lists of values, parent domains and CIDR ranges
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+// Indicators
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndicatorKind {
+    Ip,
+    Domain,
+    Url,
+    Hash,
+    Email,
+    Other,
+}
+
+impl IndicatorKind {
+    /// Map an IOC type label as used by the cores (`"IP"`, `"Domain"`,
+    /// `"sha256"`, ...) to a kind, inferring from the value when the label
+    /// is not recognised
+    pub fn from_ioc_type(ioc_type: &str, value: &str) -> Self {
+        match ioc_type.trim().to_lowercase().as_str() {
+            "ip" | "ipv4" | "ipv6" | "ip-src" | "ip-dst" | "ip_address" => IndicatorKind::Ip,
+            "domain" | "hostname" | "fqdn" => IndicatorKind::Domain,
+            "url" | "uri" => IndicatorKind::Url,
+            "hash" | "md5" | "sha1" | "sha256" | "sha512" => IndicatorKind::Hash,
+            "email" | "email-src" | "email-dst" => IndicatorKind::Email,
+            _ => Self::infer(value),
+        }
+    }
+
+    pub fn infer(value: &str) -> Self {
+        let value = value.trim();
+        if value.parse::<IpAddr>().is_ok() {
+            IndicatorKind::Ip
+        } else if value.contains("://") {
+            IndicatorKind::Url
+        } else if matches!(value.len(), 32 | 40 | 64 | 128) && value.chars().all(|c| c.is_ascii_hexdigit()) {
+            IndicatorKind::Hash
+        } else if value.contains('@') {
+            IndicatorKind::Email
+        } else if is_domain(value) {
+            IndicatorKind::Domain
+        } else {
+            IndicatorKind::Other
+        }
+    }
+}
+
+fn is_domain(value: &str) -> bool {
+    let labels: Vec<&str> = value.trim_end_matches('.').split('.').collect();
+    labels.len() >= 2
+        && labels.iter().all(|l| !l.is_empty() && l.len() <= 63 && l.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+        && labels.last().is_some_and(|tld| tld.chars().all(|c| c.is_ascii_alphabetic()))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Indicator {
+    pub kind: IndicatorKind,
+    pub value: String,
+}
+
+impl Indicator {
+    pub fn new(kind: IndicatorKind, value: &str) -> Self {
+        let value = value.trim();
+        let value = match kind {
+            IndicatorKind::Domain => value.trim_end_matches('.').to_lowercase(),
+            IndicatorKind::Hash | IndicatorKind::Email => value.to_lowercase(),
+            _ => value.to_string(),
+        };
+        Self { kind, value }
+    }
+
+    pub fn from_ioc(ioc_type: &str, value: &str) -> Self {
+        Self::new(IndicatorKind::from_ioc_type(ioc_type, value), value)
+    }
+
+    /// Host part of a URL or email indicator, as a domain or IP indicator
+    pub fn host(&self) -> Option<Indicator> {
+        let host = match self.kind {
+            IndicatorKind::Url => url::Url::parse(&self.value).ok()?.host_str()?.trim_matches(['[', ']']).to_string(),
+            IndicatorKind::Email => self.value.rsplit_once('@')?.1.to_string(),
+            _ => return None,
+        };
+        Some(Indicator::new(IndicatorKind::infer(&host), &host))
+    }
+}
+
+// Results
+
+/// Reputation verdicts, from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReputationVerdict {
+    Benign,
+    Unknown,
+    Suspicious,
+    Malicious,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnrichmentCategory {
+    Geolocation,
+    Registration,
+    PassiveDns,
+    Reputation,
+    Other,
+}
+
+/// What a provider found for one indicator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnricherOutput {
+    pub data: Value,
+    #[serde(default)]
+    pub verdict: Option<ReputationVerdict>,
+}
+
+impl EnricherOutput {
+    pub fn new(data: Value) -> Self {
+        Self { data, verdict: None }
+    }
+
+    pub fn with_verdict(mut self, verdict: ReputationVerdict) -> Self {
+        self.verdict = Some(verdict);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnrichmentRecord {
+    pub provider: String,
+    pub category: EnrichmentCategory,
+    pub data: Value,
+    pub verdict: Option<ReputationVerdict>,
+    pub retrieved_at: DateTime<Utc>,
+    /// Served from the pipeline cache rather than the provider
+    pub cached: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndicatorEnrichment {
+    pub indicator: Indicator,
+    pub records: Vec<EnrichmentRecord>,
+    /// Provider name to error, for lookups that failed or timed out
+    pub errors: BTreeMap<String, String>,
+}
+
+impl IndicatorEnrichment {
+    /// Most severe verdict any provider returned
+    pub fn verdict(&self) -> Option<ReputationVerdict> {
+        self.records.iter().filter_map(|r| r.verdict).max()
+    }
+
+    /// One-line description for places that only hold a string
+    pub fn summary(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        for record in &self.records {
+            let field = |name: &str| record.data.get(name).and_then(Value::as_str);
+            let part = match record.category {
+                EnrichmentCategory::Reputation => {
+                    let lists = record.data.get("lists").and_then(Value::as_array).map(|lists| {
+                        lists.iter().filter_map(Value::as_str).collect::<Vec<_>>().join(", ")
+                    });
+                    let verdict = record.verdict.map(|v| format!("{:?}", v).to_lowercase());
+                    verdict.map(|v| match lists {
+                        Some(lists) if !lists.is_empty() => format!("{} ({})", v, lists),
+                        _ => v,
+                    })
+                }
+                EnrichmentCategory::Geolocation => {
+                    let location = [field("country"), field("city")].into_iter().flatten().collect::<Vec<_>>().join("/");
+                    let asn = record.data.get("asn").and_then(Value::as_u64).map(|asn| match field("as_organization") {
+                        Some(org) => format!("AS{} {}", asn, org),
+                        None => format!("AS{}", asn),
+                    });
+                    let text = [Some(location).filter(|l| !l.is_empty()), asn].into_iter().flatten().collect::<Vec<_>>().join(", ");
+                    (!text.is_empty()).then(|| format!("geo: {}", text))
+                }
+                EnrichmentCategory::Registration => match (field("registrar").or(field("org")), field("created")) {
+                    (Some(registrar), Some(created)) => Some(format!("registered {} via {}", created, registrar)),
+                    (Some(registrar), None) => Some(format!("registrar: {}", registrar)),
+                    (None, Some(created)) => Some(format!("registered {}", created)),
+                    (None, None) => None,
+                },
+                EnrichmentCategory::PassiveDns => record
+                    .data
+                    .get("resolutions")
+                    .and_then(Value::as_array)
+                    .map(|r| format!("pDNS: {} resolutions", r.len())),
+                EnrichmentCategory::Other => None,
+            };
+            parts.extend(part);
+        }
+        (!parts.is_empty()).then(|| parts.join("; "))
+    }
+}
+
+// Providers
+
+/// An enrichment source
+#[async_trait]
+pub trait Enricher: Send + Sync {
+    fn name(&self) -> &str;
+    fn category(&self) -> EnrichmentCategory;
+    fn supports(&self, kind: IndicatorKind) -> bool;
+    /// `Ok(None)` when the provider knows nothing about the indicator
+    async fn enrich(&self, indicator: &Indicator) -> Result<Option<EnricherOutput>, String>;
+}
+
+/// Named list of known values, parent domains and CIDR ranges sharing a verdict
+#[derive(Debug, Clone, Default)]
+pub struct ReputationList {
+    pub name: String,
+    pub verdict: Option<ReputationVerdict>,
+    values: HashSet<String>,
+    networks: Vec<(IpAddr, u8)>,
+}
+
+impl ReputationList {
+    pub fn new(name: &str, verdict: ReputationVerdict) -> Self {
+        Self { name: name.to_string(), verdict: Some(verdict), ..Default::default() }
+    }
+
+    /// Add an entry: a CIDR range, or any value matched case-insensitively
+    pub fn insert(&mut self, entry: &str) {
+        let entry = entry.trim();
+        if entry.is_empty() {
+            return;
+        }
+        if let Some((addr, prefix)) = entry.split_once('/') {
+            if let (Ok(addr), Ok(prefix)) = (addr.parse::<IpAddr>(), prefix.parse::<u8>()) {
+                let max = if addr.is_ipv4() { 32 } else { 128 };
+                if prefix <= max {
+                    self.networks.push((addr, prefix));
+                    return;
+                }
+            }
+        }
+        self.values.insert(entry.trim_end_matches('.').to_lowercase());
+    }
+
+    /// One entry per line; `#` starts a comment
+    pub fn from_text(name: &str, verdict: ReputationVerdict, text: &str) -> Self {
+        let mut list = Self::new(name, verdict);
+        for line in text.lines() {
+            list.insert(line.split('#').next().unwrap_or_default());
+        }
+        list
+    }
+
+    pub fn load(name: &str, verdict: ReputationVerdict, path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read reputation list {}: {}", path, e))?;
+        Ok(Self::from_text(name, verdict, &text))
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len() + self.networks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, indicator: &Indicator) -> bool {
+        let value = indicator.value.to_lowercase();
+        if self.values.contains(&value) {
+            return true;
+        }
+        match indicator.kind {
+            IndicatorKind::Ip => value
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| self.networks.iter().any(|(net, prefix)| in_network(ip, *net, *prefix))),
+            // A listed domain covers its subdomains
+            IndicatorKind::Domain => value
+                .match_indices('.')
+                .any(|(i, _)| self.values.contains(&value[i + 1..])),
+            IndicatorKind::Url | IndicatorKind::Email => indicator.host().is_some_and(|host| self.contains(&host)),
+            _ => false,
+        }
+    }
+}
+
+fn in_network(ip: IpAddr, network: IpAddr, prefix: u8) -> bool {
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(net)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(net) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(net)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(net) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Matches indicators against local reputation lists
+#[derive(Default)]
+pub struct ReputationListEnricher {
+    lists: RwLock<Vec<ReputationList>>,
+}
+
+impl ReputationListEnricher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a list, replacing any list with the same name
+    pub fn add_list(&self, list: ReputationList) {
+        let mut lists = self.lists.write();
+        lists.retain(|l| l.name != list.name);
+        lists.push(list);
+    }
+}
+
+#[async_trait]
+impl Enricher for ReputationListEnricher {
+    fn name(&self) -> &str {
+        "reputation_lists"
+    }
+
+    fn category(&self) -> EnrichmentCategory {
+        EnrichmentCategory::Reputation
+    }
+
+    fn supports(&self, kind: IndicatorKind) -> bool {
+        kind != IndicatorKind::Other
+    }
+
+    async fn enrich(&self, indicator: &Indicator) -> Result<Option<EnricherOutput>, String> {
+        let lists = self.lists.read();
+        let matched: Vec<&ReputationList> = lists.iter().filter(|l| l.contains(indicator)).collect();
+        let Some(verdict) = matched.iter().filter_map(|l| l.verdict).max() else {
+            return Ok(None);
+        };
+        let names: Vec<&str> = matched.iter().map(|l| l.name.as_str()).collect();
+        Ok(Some(EnricherOutput::new(json!({ "lists": names })).with_verdict(verdict)))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PassiveDnsRecord {
+    pub rrname: String,
+    #[serde(default = "default_rrtype")]
+    pub rrtype: String,
+    pub rdata: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    #[serde(default = "default_count")]
+    pub count: u64,
+}
+
+fn default_rrtype() -> String {
+    "A".to_string()
+}
+
+fn default_count() -> u64 {
+    1
+}
+
+/// Resolution history fed by the caller (sensor exports, sandbox DNS
+/// captures), answering forward lookups for domains and reverse for IPs
+#[derive(Default)]
+pub struct PassiveDnsEnricher {
+    records: RwLock<Vec<PassiveDnsRecord>>,
+}
+
+impl PassiveDnsEnricher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Merge an observation into the history, widening the seen window of
+    /// an existing resolution
+    pub fn insert(&self, mut record: PassiveDnsRecord) {
+        record.rrname = record.rrname.trim_end_matches('.').to_lowercase();
+        let mut records = self.records.write();
+        match records
+            .iter_mut()
+            .find(|r| r.rrname == record.rrname && r.rrtype == record.rrtype && r.rdata == record.rdata)
+        {
+            Some(existing) => {
+                existing.first_seen = existing.first_seen.min(record.first_seen);
+                existing.last_seen = existing.last_seen.max(record.last_seen);
+                existing.count += record.count;
+            }
+            None => records.push(record),
+        }
+    }
+}
+
+#[async_trait]
+impl Enricher for PassiveDnsEnricher {
+    fn name(&self) -> &str {
+        "passive_dns"
+    }
+
+    fn category(&self) -> EnrichmentCategory {
+        EnrichmentCategory::PassiveDns
+    }
+
+    fn supports(&self, kind: IndicatorKind) -> bool {
+        matches!(kind, IndicatorKind::Ip | IndicatorKind::Domain)
+    }
+
+    async fn enrich(&self, indicator: &Indicator) -> Result<Option<EnricherOutput>, String> {
+        let records = self.records.read();
+        let matched: Vec<&PassiveDnsRecord> = records
+            .iter()
+            .filter(|r| match indicator.kind {
+                IndicatorKind::Ip => r.rdata == indicator.value,
+                _ => r.rrname == indicator.value,
+            })
+            .collect();
+        if matched.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(EnricherOutput::new(json!({
+            "resolutions": matched,
+            "first_seen": matched.iter().map(|r| r.first_seen).min(),
+            "last_seen": matched.iter().map(|r| r.last_seen).max(),
+        }))))
+    }
+}
+
+/// WHOIS lookups over TCP port 43
+///
+/// Queries start at the bootstrap server (IANA by default) and follow its
+/// `refer:` answer, then any registrar referral a thin registry returns.
+/// Domains are reduced to their last two labels first. Registrations
+/// younger than `new_domain_days` are marked suspicious.
+pub struct WhoisEnricher {
+    bootstrap: String,
+    servers: BTreeMap<String, String>,
+    new_domain_days: i64,
+}
+
+/// Largest WHOIS response read, in bytes
+const WHOIS_MAX_RESPONSE: u64 = 64 * 1024;
+const WHOIS_MAX_REFERRALS: usize = 3;
+
+impl Default for WhoisEnricher {
+    fn default() -> Self {
+        Self { bootstrap: "whois.iana.org".to_string(), servers: BTreeMap::new(), new_domain_days: 30 }
+    }
+}
+
+impl WhoisEnricher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_bootstrap(mut self, server: &str) -> Self {
+        self.bootstrap = server.to_string();
+        self
+    }
+
+    /// Query `server` directly for names under `suffix` (e.g. `"com"`)
+    pub fn with_server(mut self, suffix: &str, server: &str) -> Self {
+        self.servers.insert(suffix.trim_start_matches('.').to_lowercase(), server.to_string());
+        self
+    }
+
+    fn first_server(&self, query: &str) -> &str {
+        query
+            .match_indices('.')
+            .find_map(|(i, _)| self.servers.get(&query[i + 1..]))
+            .unwrap_or(&self.bootstrap)
+    }
+
+    async fn query(server: &str, query: &str) -> Result<String, String> {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let address = if server.contains(':') { server.to_string() } else { format!("{}:43", server) };
+        let mut stream = tokio::net::TcpStream::connect(&address)
+            .await
+            .map_err(|e| format!("Failed to connect to {}: {}", address, e))?;
+        stream.write_all(format!("{}\r\n", query).as_bytes()).await.map_err(|e| e.to_string())?;
+        let mut response = Vec::new();
+        stream.take(WHOIS_MAX_RESPONSE).read_to_end(&mut response).await.map_err(|e| e.to_string())?;
+        Ok(String::from_utf8_lossy(&response).into_owned())
+    }
+}
+
+fn whois_fields(response: &str) -> BTreeMap<String, Vec<String>> {
+    let mut fields: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for line in response.lines() {
+        let line = line.trim();
+        if line.starts_with(['%', '#']) || line.starts_with(">>>") {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            let value = value.trim();
+            if !value.is_empty() {
+                fields.entry(key.trim().to_lowercase()).or_default().push(value.to_string());
+            }
+        }
+    }
+    fields
+}
+
+fn whois_field<'a>(fields: &'a BTreeMap<String, Vec<String>>, keys: &[&str]) -> Option<&'a str> {
+    keys.iter().find_map(|key| fields.get(*key).and_then(|v| v.first()).map(String::as_str))
+}
+
+fn parse_whois_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|d| d.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            let date = value.get(..10)?.replace('.', "-");
+            NaiveDate::parse_from_str(&date, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0).map(|d| d.and_utc())
+        })
+}
+
+#[async_trait]
+impl Enricher for WhoisEnricher {
+    fn name(&self) -> &str {
+        "whois"
+    }
+
+    fn category(&self) -> EnrichmentCategory {
+        EnrichmentCategory::Registration
+    }
+
+    fn supports(&self, kind: IndicatorKind) -> bool {
+        matches!(kind, IndicatorKind::Ip | IndicatorKind::Domain)
+    }
+
+    async fn enrich(&self, indicator: &Indicator) -> Result<Option<EnricherOutput>, String> {
+        let query = match indicator.kind {
+            IndicatorKind::Domain => {
+                let labels: Vec<&str> = indicator.value.split('.').collect();
+                labels[labels.len().saturating_sub(2)..].join(".")
+            }
+            _ => indicator.value.clone(),
+        };
+
+        // Keep the most detailed answer along the referral chain
+        let mut server = self.first_server(&query).to_string();
+        let mut visited = BTreeSet::new();
+        let mut fields = BTreeMap::new();
+        let mut answered_by = server.clone();
+        for _ in 0..=WHOIS_MAX_REFERRALS {
+            if !visited.insert(server.to_lowercase()) {
+                break;
+            }
+            let response = Self::query(&server, &query).await?;
+            let answer = whois_fields(&response);
+            let referral = whois_field(&answer, &["refer", "whois", "registrar whois server", "referralserver"])
+                .map(|s| s.trim_start_matches("whois://").trim_end_matches('/').to_string());
+            if answer.len() >= fields.len() {
+                fields = answer;
+                answered_by = server.clone();
+            }
+            match referral {
+                Some(next) if !next.is_empty() => server = next,
+                _ => break,
+            }
+        }
+        if fields.is_empty() {
+            return Ok(None);
+        }
+
+        let name_servers: BTreeSet<String> = ["name server", "nserver"]
+            .iter()
+            .filter_map(|key| fields.get(*key))
+            .flatten()
+            .map(|ns| ns.split_whitespace().next().unwrap_or_default().trim_end_matches('.').to_lowercase())
+            .collect();
+        let created = whois_field(&fields, &["creation date", "created", "registered on", "registration time", "regdate"]);
+        let data = json!({
+            "server": answered_by,
+            "query": query,
+            "registrar": whois_field(&fields, &["registrar", "sponsoring registrar"]),
+            "created": created,
+            "updated": whois_field(&fields, &["updated date", "last-modified", "changed", "last updated", "updated"]),
+            "expires": whois_field(&fields, &["registry expiry date", "registrar registration expiration date", "expiration date", "expires", "paid-till"]),
+            "status": whois_field(&fields, &["domain status", "status"]),
+            "org": whois_field(&fields, &["registrant organization", "org-name", "orgname", "organization", "org"]),
+            "country": whois_field(&fields, &["registrant country", "country"]),
+            "netname": whois_field(&fields, &["netname"]),
+            "cidr": whois_field(&fields, &["cidr", "inetnum", "inet6num", "netrange"]),
+            "name_servers": name_servers,
+        });
+
+        let mut output = EnricherOutput::new(data);
+        if let Some(age_days) = created.and_then(parse_whois_date).map(|c| (Utc::now() - c).num_days()) {
+            output.data["age_days"] = json!(age_days);
+            if indicator.kind == IndicatorKind::Domain && age_days < self.new_domain_days {
+                output = output.with_verdict(ReputationVerdict::Suspicious);
+            }
+        }
+        Ok(Some(output))
+    }
+}
+
+#[cfg(feature = "geoip")]
+pub use geoip::GeoIpEnricher;
+
+#[cfg(feature = "geoip")]
+mod geoip {
+    use super::*;
+    use maxminddb::{geoip2, MaxMindDBError, Reader};
+
+    /// Country, city, coordinates and ASN from MaxMind databases
+    pub struct GeoIpEnricher {
+        city: Reader<Vec<u8>>,
+        asn: Option<Reader<Vec<u8>>>,
+    }
+
+    fn not_found<T>(result: Result<T, MaxMindDBError>) -> Result<Option<T>, String> {
+        match result {
+            Ok(record) => Ok(Some(record)),
+            Err(MaxMindDBError::AddressNotFoundError(_)) => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    impl GeoIpEnricher {
+        pub fn open(city_db: &str, asn_db: Option<&str>) -> Result<Self, String> {
+            let city = Reader::open_readfile(city_db).map_err(|e| format!("Failed to open GeoIP database {}: {}", city_db, e))?;
+            let asn = asn_db
+                .map(|path| Reader::open_readfile(path).map_err(|e| format!("Failed to open ASN database {}: {}", path, e)))
+                .transpose()?;
+            Ok(Self { city, asn })
+        }
+    }
+
+    #[async_trait]
+    impl Enricher for GeoIpEnricher {
+        fn name(&self) -> &str {
+            "geoip"
+        }
+
+        fn category(&self) -> EnrichmentCategory {
+            EnrichmentCategory::Geolocation
+        }
+
+        fn supports(&self, kind: IndicatorKind) -> bool {
+            kind == IndicatorKind::Ip
+        }
+
+        async fn enrich(&self, indicator: &Indicator) -> Result<Option<EnricherOutput>, String> {
+            let ip: IpAddr = indicator.value.parse().map_err(|_| format!("Invalid IP address {}", indicator.value))?;
+            let mut data = serde_json::Map::new();
+
+            if let Some(city) = not_found(self.city.lookup::<geoip2::City>(ip))? {
+                let english = |names: Option<&BTreeMap<&str, &str>>| names.and_then(|n| n.get("en")).map(|n| n.to_string());
+                if let Some(country) = &city.country {
+                    data.insert("country".to_string(), json!(country.iso_code));
+                    data.insert("country_name".to_string(), json!(english(country.names.as_ref())));
+                }
+                if let Some(name) = city.city.as_ref().and_then(|c| english(c.names.as_ref())) {
+                    data.insert("city".to_string(), json!(name));
+                }
+                if let Some(location) = &city.location {
+                    data.insert("latitude".to_string(), json!(location.latitude));
+                    data.insert("longitude".to_string(), json!(location.longitude));
+                    data.insert("accuracy_radius_km".to_string(), json!(location.accuracy_radius));
+                }
+            }
+            if let Some(reader) = &self.asn {
+                if let Some(asn) = not_found(reader.lookup::<geoip2::Asn>(ip))? {
+                    data.insert("asn".to_string(), json!(asn.autonomous_system_number));
+                    data.insert("as_organization".to_string(), json!(asn.autonomous_system_organization));
+                }
+            }
+
+            Ok((!data.is_empty()).then(|| EnricherOutput::new(Value::Object(data))))
+        }
+    }
+}
+
+// Pipeline
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnrichmentConfig {
+    /// Provider lookups in flight at once, across all callers
+    pub max_concurrency: usize,
+    pub cache_ttl_secs: u64,
+    /// How long "nothing known" answers are cached
+    pub negative_cache_ttl_secs: u64,
+    pub provider_timeout_ms: u64,
+    pub max_cache_entries: usize,
+}
+
+impl Default for EnrichmentConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrency: 8,
+            cache_ttl_secs: 3600,
+            negative_cache_ttl_secs: 300,
+            provider_timeout_ms: 5000,
+            max_cache_entries: 100_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnrichmentStats {
+    pub providers: Vec<String>,
+    pub cache_entries: usize,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    pub provider_errors: u64,
+}
+
+struct CacheEntry {
+    output: Option<EnricherOutput>,
+    retrieved_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+}
+
+pub struct EnrichmentPipeline {
+    config: EnrichmentConfig,
+    providers: RwLock<Vec<Arc<dyn Enricher>>>,
+    cache: DashMap<(String, Indicator), CacheEntry>,
+    limiter: Semaphore,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl Default for EnrichmentPipeline {
+    fn default() -> Self {
+        Self::new(EnrichmentConfig::default())
+    }
+}
+
+impl EnrichmentPipeline {
+    pub fn new(config: EnrichmentConfig) -> Self {
+        let limiter = Semaphore::new(config.max_concurrency.max(1));
+        Self {
+            config,
+            providers: RwLock::new(Vec::new()),
+            cache: DashMap::new(),
+            limiter,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+        }
+    }
+
+    /// Build a pipeline with the built-in providers described by `setup`
+    pub fn from_setup(setup: EnrichmentSetup) -> Result<Self, String> {
+        let pipeline = Self::new(setup.config);
+
+        if let Some(city_db) = &setup.geoip_city_db {
+            #[cfg(feature = "geoip")]
+            pipeline.register(Arc::new(GeoIpEnricher::open(city_db, setup.geoip_asn_db.as_deref())?));
+            #[cfg(not(feature = "geoip"))]
+            return Err(format!("GeoIP enrichment from {} requires the `geoip` feature", city_db));
+        }
+
+        if setup.whois {
+            let mut whois = WhoisEnricher::new();
+            for (suffix, server) in &setup.whois_servers {
+                whois = whois.with_server(suffix, server);
+            }
+            pipeline.register(Arc::new(whois));
+        }
+
+        if !setup.passive_dns.is_empty() {
+            let pdns = PassiveDnsEnricher::new();
+            for record in setup.passive_dns {
+                pdns.insert(record);
+            }
+            pipeline.register(Arc::new(pdns));
+        }
+
+        if !setup.reputation_lists.is_empty() {
+            let reputation = ReputationListEnricher::new();
+            for list_setup in setup.reputation_lists {
+                let mut list = match &list_setup.path {
+                    Some(path) => ReputationList::load(&list_setup.name, list_setup.verdict, path)?,
+                    None => ReputationList::new(&list_setup.name, list_setup.verdict),
+                };
+                for entry in &list_setup.entries {
+                    list.insert(entry);
+                }
+                reputation.add_list(list);
+            }
+            pipeline.register(Arc::new(reputation));
+        }
+
+        Ok(pipeline)
+    }
+
+    /// Add a provider, replacing one with the same name
+    pub fn register(&self, provider: Arc<dyn Enricher>) {
+        let mut providers = self.providers.write();
+        providers.retain(|p| p.name() != provider.name());
+        providers.push(provider);
+    }
+
+    pub fn unregister(&self, name: &str) -> bool {
+        let mut providers = self.providers.write();
+        let before = providers.len();
+        providers.retain(|p| p.name() != name);
+        self.cache.retain(|(provider, _), _| provider != name);
+        providers.len() != before
+    }
+
+    pub fn provider_names(&self) -> Vec<String> {
+        self.providers.read().iter().map(|p| p.name().to_string()).collect()
+    }
+
+    pub async fn enrich(&self, indicator: &Indicator) -> IndicatorEnrichment {
+        let providers: Vec<Arc<dyn Enricher>> =
+            self.providers.read().iter().filter(|p| p.supports(indicator.kind)).cloned().collect();
+        let lookups = providers.iter().map(|provider| self.lookup(provider.as_ref(), indicator));
+        let outcomes = futures::future::join_all(lookups).await;
+
+        let mut enrichment = IndicatorEnrichment {
+            indicator: indicator.clone(),
+            records: Vec::new(),
+            errors: BTreeMap::new(),
+        };
+        for (provider, outcome) in providers.iter().zip(outcomes) {
+            match outcome {
+                Ok(Some((output, retrieved_at, cached))) => enrichment.records.push(EnrichmentRecord {
+                    provider: provider.name().to_string(),
+                    category: provider.category(),
+                    data: output.data,
+                    verdict: output.verdict,
+                    retrieved_at,
+                    cached,
+                }),
+                Ok(None) => {}
+                Err(e) => {
+                    enrichment.errors.insert(provider.name().to_string(), e);
+                }
+            }
+        }
+        enrichment
+    }
+
+    /// Enrich each distinct indicator once; the concurrency limit bounds
+    /// provider calls however large the batch
+    pub async fn enrich_batch(&self, indicators: &[Indicator]) -> Vec<IndicatorEnrichment> {
+        let mut seen = HashSet::new();
+        let unique: Vec<&Indicator> = indicators.iter().filter(|i| seen.insert(*i)).collect();
+        futures::future::join_all(unique.into_iter().map(|indicator| self.enrich(indicator))).await
+    }
+
+    async fn lookup(
+        &self,
+        provider: &dyn Enricher,
+        indicator: &Indicator,
+    ) -> Result<Option<(EnricherOutput, DateTime<Utc>, bool)>, String> {
+        let key = (provider.name().to_string(), indicator.clone());
+        if let Some(entry) = self.cache.get(&key) {
+            if entry.expires_at > Utc::now() {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(entry.output.clone().map(|output| (output, entry.retrieved_at, true)));
+            }
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let output = {
+            let _permit = self.limiter.acquire().await.map_err(|e| e.to_string())?;
+            let timeout = Duration::from_millis(self.config.provider_timeout_ms);
+            match tokio::time::timeout(timeout, provider.enrich(indicator)).await {
+                Ok(Ok(output)) => output,
+                Ok(Err(e)) => {
+                    self.errors.fetch_add(1, Ordering::Relaxed);
+                    return Err(e);
+                }
+                Err(_) => {
+                    self.errors.fetch_add(1, Ordering::Relaxed);
+                    return Err(format!("timed out after {}ms", self.config.provider_timeout_ms));
+                }
+            }
+        };
+
+        let retrieved_at = Utc::now();
+        let ttl = if output.is_some() { self.config.cache_ttl_secs } else { self.config.negative_cache_ttl_secs };
+        if self.cache.len() >= self.config.max_cache_entries {
+            self.purge_expired();
+        }
+        if self.cache.len() < self.config.max_cache_entries {
+            self.cache.insert(
+                key,
+                CacheEntry {
+                    output: output.clone(),
+                    retrieved_at,
+                    expires_at: retrieved_at + chrono::Duration::seconds(ttl as i64),
+                },
+            );
+        }
+        Ok(output.map(|output| (output, retrieved_at, false)))
+    }
+
+    pub fn purge_expired(&self) -> usize {
+        let now = Utc::now();
+        let before = self.cache.len();
+        self.cache.retain(|_, entry| entry.expires_at > now);
+        before - self.cache.len()
+    }
+
+    pub fn clear_cache(&self) {
+        self.cache.clear();
+    }
+
+    pub fn stats(&self) -> EnrichmentStats {
+        EnrichmentStats {
+            providers: self.provider_names(),
+            cache_entries: self.cache.len(),
+            cache_hits: self.hits.load(Ordering::Relaxed),
+            cache_misses: self.misses.load(Ordering::Relaxed),
+            provider_errors: self.errors.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// JSON description of a pipeline built from the built-in providers
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnrichmentSetup {
+    #[serde(flatten)]
+    pub config: EnrichmentConfig,
+    #[serde(default)]
+    pub geoip_city_db: Option<String>,
+    #[serde(default)]
+    pub geoip_asn_db: Option<String>,
+    #[serde(default)]
+    pub whois: bool,
+    /// Suffix to WHOIS server overrides, skipping the IANA bootstrap
+    #[serde(default)]
+    pub whois_servers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub passive_dns: Vec<PassiveDnsRecord>,
+    #[serde(default)]
+    pub reputation_lists: Vec<ReputationListSetup>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReputationListSetup {
+    pub name: String,
+    #[serde(default = "default_list_verdict")]
+    pub verdict: ReputationVerdict,
+    /// File with one entry per line
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub entries: Vec<String>,
+}
+
+fn default_list_verdict() -> ReputationVerdict {
+    ReputationVerdict::Malicious
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    struct CountingEnricher {
+        calls: AtomicUsize,
+        in_flight: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Enricher for CountingEnricher {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn category(&self) -> EnrichmentCategory {
+            EnrichmentCategory::Other
+        }
+
+        fn supports(&self, _kind: IndicatorKind) -> bool {
+            true
+        }
+
+        async fn enrich(&self, indicator: &Indicator) -> Result<Option<EnricherOutput>, String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let now = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(Some(EnricherOutput::new(json!({ "value": indicator.value }))))
+        }
+    }
+
+    #[test]
+    fn reputation_lists_match_values_subdomains_and_ranges() {
+        let list = ReputationList::from_text(
+            "feodo",
+            ReputationVerdict::Malicious,
+            "# botnet C2\n203.0.113.0/24\nevil.example\n2001:db8::/32\n",
+        );
+        assert_eq!(list.len(), 3);
+        assert!(list.contains(&Indicator::from_ioc("IP", "203.0.113.77")));
+        assert!(!list.contains(&Indicator::from_ioc("IP", "203.0.114.1")));
+        assert!(list.contains(&Indicator::from_ioc("ipv6", "2001:db8::1")));
+        assert!(list.contains(&Indicator::from_ioc("Domain", "CDN.Evil.Example.")));
+        assert!(!list.contains(&Indicator::from_ioc("Domain", "notevil.example")));
+        assert!(list.contains(&Indicator::from_ioc("url", "https://cdn.evil.example/payload.bin")));
+    }
+
+    #[tokio::test]
+    async fn pipeline_caches_and_limits_concurrency() {
+        let pipeline = EnrichmentPipeline::new(EnrichmentConfig { max_concurrency: 2, ..Default::default() });
+        let counting = Arc::new(CountingEnricher { calls: AtomicUsize::new(0), in_flight: AtomicUsize::new(0), peak: AtomicUsize::new(0) });
+        pipeline.register(counting.clone());
+
+        let indicators: Vec<Indicator> = (0..6)
+            .map(|i| Indicator::from_ioc("IP", &format!("198.51.100.{}", i % 5)))
+            .collect();
+        let results = pipeline.enrich_batch(&indicators).await;
+        assert_eq!(results.len(), 5);
+        assert_eq!(counting.calls.load(Ordering::SeqCst), 5);
+        assert!(counting.peak.load(Ordering::SeqCst) <= 2);
+
+        let again = pipeline.enrich(&indicators[0]).await;
+        assert!(again.records[0].cached);
+        assert_eq!(counting.calls.load(Ordering::SeqCst), 5);
+        assert_eq!(pipeline.stats().cache_hits, 1);
+    }
+
+    #[tokio::test]
+    async fn whois_follows_referrals_and_flags_new_domains() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        async fn serve(response: String) -> String {
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap().to_string();
+            tokio::spawn(async move {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut query = [0u8; 256];
+                let _ = socket.read(&mut query).await;
+                socket.write_all(response.as_bytes()).await.unwrap();
+            });
+            address
+        }
+
+        let created = (Utc::now() - chrono::Duration::days(3)).format("%Y-%m-%dT%H:%M:%SZ");
+        let registry = serve(format!(
+            "Domain Name: FRESH.EXAMPLE\nRegistrar: Example Registrar, Inc.\nCreation Date: {}\nName Server: NS1.FRESH.EXAMPLE\n",
+            created
+        ))
+        .await;
+        let bootstrap = serve(format!("% IANA WHOIS server\nrefer:        {}\n", registry)).await;
+
+        let pipeline = EnrichmentPipeline::default();
+        pipeline.register(Arc::new(WhoisEnricher::new().with_bootstrap(&bootstrap)));
+        let enrichment = pipeline.enrich(&Indicator::from_ioc("Domain", "www.fresh.example")).await;
+
+        assert!(enrichment.errors.is_empty(), "{:?}", enrichment.errors);
+        let record = &enrichment.records[0];
+        assert_eq!(record.data["registrar"], "Example Registrar, Inc.");
+        assert_eq!(record.data["query"], "fresh.example");
+        assert_eq!(record.data["name_servers"], json!(["ns1.fresh.example"]));
+        assert_eq!(enrichment.verdict(), Some(ReputationVerdict::Suspicious));
+        assert!(enrichment.summary().unwrap().contains("via Example Registrar"));
+    }
+}
@@ -0,0 +1,166 @@
+//! Per-call guards for the cores' NAPI surfaces
+//!
+//! Every core wraps its NAPI methods in the same two guards: [`AccessGuard`]
+//! checks the token passed with a call against [`AccessControl`], and
+//! [`AuditTrail`] appends mutating calls with their actor, a digest of their
+//! parameters and their outcome to a hash-chained [`AuditLog`].
+//!
+//! A core built without its `phantom-enterprise-standards` feature uses the
+//! open guards, [`AccessGuard::open`] and [`AuditTrail::disabled`]. They fail
+//! open: every call is allowed and attributed to [`ANONYMOUS`], no caller is
+//! bound to a tenant, and nothing is recorded. Deployments that need access
+//! control or an audit trail must enable the feature.
+
+use std::fmt::Display;
+use std::sync::{Arc, RwLock};
+
+use crate::audit_log::{AuditLog, AuditOutcome};
+use crate::rbac::{AccessControl, Permission, Principal};
+
+/// Actor recorded when no credential was supplied
+pub const ANONYMOUS: &str = "anonymous";
+
+#[derive(Clone)]
+pub struct AccessGuard {
+    control: Arc<AccessControl>,
+    open: bool,
+}
+
+impl AccessGuard {
+    /// Guard backed by RBAC; enforcement starts disabled until
+    /// [`AccessControl::set_enforced`] turns it on
+    pub fn rbac() -> Self {
+        Self { control: Arc::new(AccessControl::new()), open: false }
+    }
+
+    /// Guard that allows every call; see the module docs
+    pub fn open() -> Self {
+        Self { control: Arc::new(AccessControl::new()), open: true }
+    }
+
+    pub fn control(&self) -> &AccessControl {
+        &self.control
+    }
+
+    /// Check that `token` grants `permission` (e.g. `"incident:write"`) on
+    /// `resource` and return the acting principal
+    pub fn check(&self, token: Option<&str>, permission: &str, resource: &str) -> Result<String, String> {
+        if self.open {
+            return Ok(ANONYMOUS.to_string());
+        }
+        let permission: Permission = permission.parse()?;
+        self.control
+            .authorize(token, permission, resource)
+            .map(|principal| principal.map_or_else(|| ANONYMOUS.to_string(), |p| p.principal_id))
+            .map_err(|e| e.to_string())
+    }
+
+    /// Principal behind `token`, for operations that are recorded but not gated
+    pub fn actor(&self, token: Option<&str>) -> String {
+        self.principal(token).map_or_else(|| ANONYMOUS.to_string(), |p| p.principal_id)
+    }
+
+    /// Tenant the credential behind `token` is bound to, if any
+    pub fn tenant(&self, token: Option<&str>) -> Option<String> {
+        self.principal(token).and_then(|p| p.tenant_id)
+    }
+
+    fn principal(&self, token: Option<&str>) -> Option<Principal> {
+        if self.open {
+            return None;
+        }
+        token.and_then(|token| self.control.authenticate(token))
+    }
+}
+
+/// Keep credentials issued by a tenant-bound administrator within that tenant
+pub fn bind_principal_tenant(caller_tenant: Option<String>, principal: &mut Principal) -> Result<(), String> {
+    let Some(tenant_id) = caller_tenant else { return Ok(()) };
+    match &principal.tenant_id {
+        Some(requested) if *requested != tenant_id => Err(format!("Cannot issue credentials for tenant {}", requested)),
+        _ => {
+            principal.tenant_id = Some(tenant_id);
+            Ok(())
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AuditTrail {
+    source: String,
+    log: Arc<RwLock<Arc<AuditLog>>>,
+    disabled: bool,
+}
+
+impl AuditTrail {
+    /// In-memory trail whose entries name `source`; see [`AuditTrail::open`]
+    pub fn new(source: impl Into<String>) -> Self {
+        let source = source.into();
+        let log = Arc::new(RwLock::new(Arc::new(AuditLog::new(source.clone()))));
+        Self { source, log, disabled: false }
+    }
+
+    /// Trail that records nothing; see the module docs
+    pub fn disabled(source: impl Into<String>) -> Self {
+        Self { disabled: true, ..Self::new(source) }
+    }
+
+    pub fn log(&self) -> Arc<AuditLog> {
+        self.log.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Switch to a file-backed log, continuing the chain already in that file
+    pub fn open(&self, path: &str) -> Result<(), String> {
+        let log = AuditLog::open(self.source.clone(), path)?;
+        *self.log.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(log);
+        Ok(())
+    }
+
+    /// Record `operation` with the outcome of `result`, passing the result through
+    pub fn record<T, E: Display>(
+        &self,
+        actor: &str,
+        operation: &str,
+        resource: &str,
+        params: serde_json::Value,
+        result: Result<T, E>,
+    ) -> Result<T, E> {
+        if !self.disabled {
+            let outcome = if result.is_ok() { AuditOutcome::Success } else { AuditOutcome::Failure };
+            if let Err(e) = self.log().append(actor, operation, resource, &params, outcome) {
+                log::error!("Failed to record audit entry for {}: {}", operation, e);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rbac::Role;
+
+    #[test]
+    fn open_guards_allow_everything_and_record_nothing() {
+        let guard = AccessGuard::open();
+        guard.control().set_enforced(true);
+        let issued = guard.control().issue_api_key(Principal::new("alice", vec![Role::Viewer]), None);
+        assert_eq!(guard.check(None, "access:manage", "access").unwrap(), ANONYMOUS);
+        assert_eq!(guard.actor(Some(&issued.token)), ANONYMOUS);
+
+        let trail = AuditTrail::disabled("test");
+        trail.record(ANONYMOUS, "noop", "x", serde_json::json!({}), Ok::<_, String>(())).unwrap();
+        assert!(trail.log().entries(&Default::default()).is_empty());
+
+        let guard = AccessGuard::rbac();
+        guard.control().set_enforced(true);
+        let issued = guard.control().issue_api_key(Principal::new("alice", vec![Role::Viewer]), None);
+        assert!(guard.check(None, "access:manage", "access").is_err());
+        assert!(guard.check(Some(&issued.token), "access:manage", "access").is_err());
+        assert_eq!(guard.actor(Some(&issued.token)), "alice");
+
+        let trail = AuditTrail::new("test");
+        trail.record("alice", "op", "x", serde_json::json!({}), Err::<(), _>("boom")).unwrap_err();
+        assert_eq!(trail.log().entries(&Default::default()).len(), 1);
+    }
+}
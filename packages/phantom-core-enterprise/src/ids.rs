@@ -0,0 +1,360 @@
+//! Typed entity identifiers and cross-core reference resolution
+//!
+//! Entities that are referenced across cores carry a short type prefix in
+//! front of their UUID (`anl_…`, `inc_…`, `hunt_…`, `ioc_…`), so a bare
+//! reference is enough to tell which core owns it. Legacy hyphenated UUIDs
+//! are still accepted; they simply resolve without a known kind.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Kinds of entity that can be referenced across cores
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityKind {
+    Analysis,
+    Sample,
+    Incident,
+    Alert,
+    Hunt,
+    Ioc,
+}
+
+impl EntityKind {
+    pub const ALL: [EntityKind; 6] = [
+        EntityKind::Analysis,
+        EntityKind::Sample,
+        EntityKind::Incident,
+        EntityKind::Alert,
+        EntityKind::Hunt,
+        EntityKind::Ioc,
+    ];
+
+    /// Identifier prefix, without the trailing underscore
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            EntityKind::Analysis => "anl",
+            EntityKind::Sample => "smp",
+            EntityKind::Incident => "inc",
+            EntityKind::Alert => "alrt",
+            EntityKind::Hunt => "hunt",
+            EntityKind::Ioc => "ioc",
+        }
+    }
+
+    pub fn from_prefix(prefix: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|kind| kind.prefix() == prefix)
+    }
+}
+
+impl fmt::Display for EntityKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            EntityKind::Analysis => "analysis",
+            EntityKind::Sample => "sample",
+            EntityKind::Incident => "incident",
+            EntityKind::Alert => "alert",
+            EntityKind::Hunt => "hunt",
+            EntityKind::Ioc => "ioc",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Identifier errors
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum IdError {
+    #[error("Invalid entity id '{0}'")]
+    Invalid(String),
+
+    #[error("Unknown entity prefix '{0}'")]
+    UnknownPrefix(String),
+
+    #[error("No resolver registered for {0} entities")]
+    NoResolver(EntityKind),
+
+    #[error("Entity '{0}' not found")]
+    NotFound(String),
+}
+
+/// A parsed entity reference
+///
+/// `kind` is `None` for legacy bare UUIDs. Equality is exact; use
+/// [`EntityId::same_entity`] to let a legacy reference match the prefixed
+/// id of the same entity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EntityId {
+    kind: Option<EntityKind>,
+    uuid: Uuid,
+}
+
+impl EntityId {
+    /// Generate a fresh prefixed id for `kind`
+    pub fn new(kind: EntityKind) -> Self {
+        Self {
+            kind: Some(kind),
+            uuid: Uuid::new_v4(),
+        }
+    }
+
+    pub fn from_parts(kind: Option<EntityKind>, uuid: Uuid) -> Self {
+        Self { kind, uuid }
+    }
+
+    /// Parse a prefixed id (`inc_<uuid>`) or a legacy bare UUID
+    pub fn parse(value: &str) -> Result<Self, IdError> {
+        let value = value.trim();
+        if let Ok(uuid) = Uuid::parse_str(value) {
+            return Ok(Self { kind: None, uuid });
+        }
+
+        let (prefix, rest) = value
+            .split_once('_')
+            .ok_or_else(|| IdError::Invalid(value.to_string()))?;
+        let kind = EntityKind::from_prefix(prefix)
+            .ok_or_else(|| IdError::UnknownPrefix(prefix.to_string()))?;
+        let uuid = Uuid::parse_str(rest).map_err(|_| IdError::Invalid(value.to_string()))?;
+
+        Ok(Self {
+            kind: Some(kind),
+            uuid,
+        })
+    }
+
+    pub fn kind(&self) -> Option<EntityKind> {
+        self.kind
+    }
+
+    pub fn uuid(&self) -> Uuid {
+        self.uuid
+    }
+
+    pub fn is_legacy(&self) -> bool {
+        self.kind.is_none()
+    }
+
+    /// Hyphenated UUID form, as stored by records created before prefixes
+    pub fn legacy_form(&self) -> String {
+        self.uuid.hyphenated().to_string()
+    }
+
+    /// UUIDs match and the kinds do not contradict each other
+    pub fn same_entity(&self, other: &EntityId) -> bool {
+        if self.uuid != other.uuid {
+            return false;
+        }
+        match (self.kind, other.kind) {
+            (Some(a), Some(b)) => a == b,
+            _ => true,
+        }
+    }
+
+    /// Whether a stored identifier string refers to this entity
+    pub fn matches(&self, stored: &str) -> bool {
+        EntityId::parse(stored).is_ok_and(|other| self.same_entity(&other))
+    }
+}
+
+impl fmt::Display for EntityId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            Some(kind) => write!(f, "{}_{}", kind.prefix(), self.uuid.simple()),
+            None => write!(f, "{}", self.uuid.hyphenated()),
+        }
+    }
+}
+
+impl FromStr for EntityId {
+    type Err = IdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        EntityId::parse(s)
+    }
+}
+
+impl Serialize for EntityId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for EntityId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        EntityId::parse(&value).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Generate a new prefixed identifier string for `kind`
+pub fn new_entity_id(kind: EntityKind) -> String {
+    EntityId::new(kind).to_string()
+}
+
+/// Short description of a resolved entity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntitySummary {
+    pub id: String,
+    pub kind: EntityKind,
+    pub owner: String,
+    pub title: String,
+    pub status: Option<String>,
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub attributes: HashMap<String, serde_json::Value>,
+}
+
+/// Implemented by each core for the entities it owns
+#[async_trait]
+pub trait EntityResolver: Send + Sync {
+    /// Name of the owning core, e.g. "phantom-sandbox-core"
+    fn owner(&self) -> &str;
+
+    /// Entity kinds this resolver is authoritative for
+    fn kinds(&self) -> Vec<EntityKind>;
+
+    /// Look up an entity. Legacy ids carry no kind and may be offered to
+    /// every resolver, so implementations should return `None` rather than
+    /// erroring for ids they do not own.
+    async fn resolve_entity(&self, id: &EntityId) -> Option<EntitySummary>;
+}
+
+/// Dispatches references to the core that owns them
+#[derive(Default, Clone)]
+pub struct ReferenceResolver {
+    resolvers: Vec<Arc<dyn EntityResolver>>,
+}
+
+impl ReferenceResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, resolver: Arc<dyn EntityResolver>) {
+        self.resolvers.push(resolver);
+    }
+
+    pub fn owners(&self) -> Vec<String> {
+        self.resolvers.iter().map(|r| r.owner().to_string()).collect()
+    }
+
+    /// Resolve a prefixed or legacy reference to its summary
+    pub async fn resolve(&self, id: &str) -> Result<EntitySummary, IdError> {
+        let parsed = EntityId::parse(id)?;
+
+        match parsed.kind() {
+            Some(kind) => {
+                let owners: Vec<_> = self
+                    .resolvers
+                    .iter()
+                    .filter(|r| r.kinds().contains(&kind))
+                    .collect();
+                if owners.is_empty() {
+                    return Err(IdError::NoResolver(kind));
+                }
+                for resolver in owners {
+                    if let Some(summary) = resolver.resolve_entity(&parsed).await {
+                        return Ok(summary);
+                    }
+                }
+            }
+            None => {
+                for resolver in &self.resolvers {
+                    if let Some(summary) = resolver.resolve_entity(&parsed).await {
+                        return Ok(summary);
+                    }
+                }
+            }
+        }
+
+        Err(IdError::NotFound(id.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StaticResolver {
+        kind: EntityKind,
+        known: EntityId,
+    }
+
+    #[async_trait]
+    impl EntityResolver for StaticResolver {
+        fn owner(&self) -> &str {
+            "test-core"
+        }
+
+        fn kinds(&self) -> Vec<EntityKind> {
+            vec![self.kind]
+        }
+
+        async fn resolve_entity(&self, id: &EntityId) -> Option<EntitySummary> {
+            id.same_entity(&self.known).then(|| EntitySummary {
+                id: self.known.to_string(),
+                kind: self.kind,
+                owner: self.owner().to_string(),
+                title: "known".to_string(),
+                status: None,
+                created_at: None,
+                attributes: HashMap::new(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_prefixed_and_legacy_ids_round_trip() {
+        let id = EntityId::new(EntityKind::Incident);
+        let text = id.to_string();
+        assert!(text.starts_with("inc_"));
+        assert_eq!(EntityId::parse(&text).unwrap(), id);
+
+        let legacy = EntityId::parse(&id.legacy_form()).unwrap();
+        assert!(legacy.is_legacy());
+        assert!(legacy.same_entity(&id));
+        assert!(id.matches(&id.legacy_form()));
+
+        let other_kind = EntityId::from_parts(Some(EntityKind::Hunt), id.uuid());
+        assert!(!other_kind.same_entity(&id));
+
+        assert_eq!(
+            EntityId::parse("xyz_123"),
+            Err(IdError::UnknownPrefix("xyz".to_string()))
+        );
+        assert!(matches!(EntityId::parse("inc_nope"), Err(IdError::Invalid(_))));
+    }
+
+    #[tokio::test]
+    async fn test_resolver_dispatches_by_prefix_and_falls_back_for_legacy() {
+        let incident = EntityId::new(EntityKind::Incident);
+        let mut resolver = ReferenceResolver::new();
+        resolver.register(Arc::new(StaticResolver {
+            kind: EntityKind::Incident,
+            known: incident,
+        }));
+
+        let summary = resolver.resolve(&incident.to_string()).await.unwrap();
+        assert_eq!(summary.kind, EntityKind::Incident);
+
+        let summary = resolver.resolve(&incident.legacy_form()).await.unwrap();
+        assert_eq!(summary.id, incident.to_string());
+
+        let hunt = EntityId::new(EntityKind::Hunt).to_string();
+        assert_eq!(
+            resolver.resolve(&hunt).await.unwrap_err(),
+            IdError::NoResolver(EntityKind::Hunt)
+        );
+        let missing = EntityId::new(EntityKind::Incident).to_string();
+        assert!(matches!(
+            resolver.resolve(&missing).await,
+            Err(IdError::NotFound(_))
+        ));
+    }
+}
@@ -8,22 +8,77 @@
 //! - Enterprise multi-tenancy patterns
 //! - Cross-plugin intelligence interfaces
 //! - Compliance and audit standards
+//! - Scheduled, hash-sealed compliance evidence packs mapped to framework controls
+//! - Tamper-evident, hash-chained audit logging
 //! - Performance and scalability benchmarks
-
+//! - Metrics history with 1m/1h/1d rollups for charting
+//! - Prometheus text exposition with an embedded `/metrics` endpoint
+//! - Connector HTTP transport with record-and-replay for deterministic tests
+//! - IOC enrichment pipeline with pluggable, cached providers
+//! - Typed entity identifiers and cross-core reference resolution
+//! - Role-based access control with API keys, sessions and denial auditing
+//! - Access and audit guards wrapping each core's NAPI calls
+//! - IOC allowlists and suppression with hit counters and expiry
+//! - CEF/LEEF syslog forwarding of security events to SIEMs
+//! - Elasticsearch bulk indexing with monthly, ILM-managed indices
+//! - Asset inventory with criticality, ownership and dependency blast radius
+//! - Unified component health with platform-wide status rollup
+//! - Live WebSocket/SSE event feed for dashboards with per-client backpressure
+//! - Git-backed detections-as-code sync with per-file validation reports
+//! - Sealed connector secrets with `secret://` references and pluggable key providers
+//! - Keyset cursor pagination with filters and projection for list APIs
+//! - The MITRE ATT&CK Enterprise matrix shared by cores mapping evidence to it
+
+pub mod assets;
+pub mod audit_log;
 pub mod business_readiness;
 pub mod compliance;
+pub mod compliance_reports;
+pub mod connectors;
 pub mod cross_plugin;
+pub mod elastic;
+pub mod enrichment;
+pub mod guard;
+pub mod ids;
+pub mod live_feed;
+pub mod metrics_history;
+pub mod mitre;
 pub mod multi_tenancy;
+pub mod pagination;
 pub mod performance;
+pub mod prometheus;
+pub mod rbac;
+pub mod rule_sync;
+pub mod secrets;
+pub mod status;
+pub mod suppression;
+pub mod syslog;
 pub mod testing;
 pub mod unified_data;
 
 // Re-export core traits and types
+pub use audit_log::*;
 pub use business_readiness::*;
 pub use compliance::*;
+pub use compliance_reports::*;
+pub use connectors::*;
 pub use cross_plugin::*;
+pub use elastic::*;
+pub use enrichment::*;
+pub use guard::*;
+pub use ids::*;
+pub use live_feed::*;
+pub use metrics_history::*;
 pub use multi_tenancy::*;
+pub use pagination::*;
 pub use performance::*;
+pub use prometheus::*;
+pub use rbac::*;
+pub use rule_sync::*;
+pub use secrets::*;
+pub use status::*;
+pub use suppression::*;
+pub use syslog::*;
 pub use testing::*;
 pub use unified_data::*;
 
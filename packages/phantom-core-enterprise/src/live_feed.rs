@@ -0,0 +1,651 @@
+//! Live Event Feed
+//!
+//! Pushes incident updates, new alerts, hunt matches and sandbox verdicts to
+//! dashboards as they happen, instead of having them poll the NAPI surface.
+//! Cores publish [`FeedEvent`]s into a [`FeedHub`]; every connected client
+//! has its own bounded queue and topic subscription, so a slow client only
+//! ever loses its own events. Dropped events are reported to the client as a
+//! `lagged` notice, and a client that keeps falling behind is disconnected.
+//! Clients only receive events of the tenant their credential is bound to;
+//! platform administrators see every tenant.
+//!
+//! With the `live-feed` feature, [`serve_feed`] embeds the feed server:
+//! `GET /feed/ws` upgrades to a WebSocket and `GET /feed/sse` streams
+//! Server-Sent Events. Both take `topics=alerts,incidents,…` and the auth
+//! token as `token=` or an `Authorization: Bearer` header, since browsers
+//! cannot set headers on either.
+
+use crate::rbac::{AccessControl, Permission};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+
+/// Resource name recorded when feed connections are authorized
+pub const FEED_RESOURCE: &str = "live-feed";
+
+/// Streams a client can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedTopic {
+    Incidents,
+    Alerts,
+    HuntMatches,
+    SandboxVerdicts,
+}
+
+impl FeedTopic {
+    pub const ALL: [FeedTopic; 4] = [FeedTopic::Incidents, FeedTopic::Alerts, FeedTopic::HuntMatches, FeedTopic::SandboxVerdicts];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FeedTopic::Incidents => "incidents",
+            FeedTopic::Alerts => "alerts",
+            FeedTopic::HuntMatches => "hunt_matches",
+            FeedTopic::SandboxVerdicts => "sandbox_verdicts",
+        }
+    }
+
+    /// Parse a comma-separated topic list; an empty list means every topic
+    pub fn parse_list(list: &str) -> Result<HashSet<FeedTopic>, String> {
+        let topics: HashSet<FeedTopic> = list
+            .split(',')
+            .map(str::trim)
+            .filter(|topic| !topic.is_empty())
+            .map(str::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(if topics.is_empty() { FeedTopic::ALL.into_iter().collect() } else { topics })
+    }
+}
+
+impl fmt::Display for FeedTopic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for FeedTopic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FeedTopic::ALL
+            .into_iter()
+            .find(|topic| topic.as_str() == s)
+            .ok_or_else(|| format!("unknown feed topic '{}'", s))
+    }
+}
+
+/// One update pushed to subscribers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedEvent {
+    /// Assigned by the hub on publish, increasing per hub
+    #[serde(default)]
+    pub sequence: u64,
+    pub topic: FeedTopic,
+    /// e.g. "incident_closed", "alert_created", "verdict"
+    pub event_type: String,
+    pub tenant_id: String,
+    /// Publishing core, e.g. "phantom-sandbox-core"
+    pub source: String,
+    #[serde(default)]
+    pub entity_id: Option<String>,
+    pub occurred_at: DateTime<Utc>,
+    #[serde(default)]
+    pub payload: serde_json::Value,
+}
+
+impl FeedEvent {
+    pub fn new(topic: FeedTopic, event_type: &str, tenant_id: &str, source: &str, payload: serde_json::Value) -> Self {
+        Self {
+            sequence: 0,
+            topic,
+            event_type: event_type.to_string(),
+            tenant_id: tenant_id.to_string(),
+            source: source.to_string(),
+            entity_id: None,
+            occurred_at: Utc::now(),
+            payload,
+        }
+    }
+
+    pub fn entity(mut self, entity_id: &str) -> Self {
+        self.entity_id = Some(entity_id.to_string());
+        self
+    }
+}
+
+/// What a client receives, in order
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FeedMessage {
+    Event(FeedEvent),
+    /// Events were dropped because the client's queue was full
+    Lagged { dropped: u64 },
+    /// The client's topic subscription changed
+    Subscribed { topics: Vec<FeedTopic> },
+}
+
+/// Identity a feed connection acts as
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeedPrincipal {
+    pub actor: String,
+    /// Tenant whose events the client receives; `None` for every tenant
+    pub tenant_id: Option<String>,
+}
+
+impl FeedPrincipal {
+    fn sees(&self, tenant_id: &str) -> bool {
+        self.tenant_id.as_deref().is_none_or(|t| t == tenant_id)
+    }
+}
+
+/// Resolves the token of a connecting client, or rejects it
+pub type FeedAuthenticator = Arc<dyn Fn(Option<&str>) -> Result<FeedPrincipal, String> + Send + Sync>;
+
+/// Feed principal for `token` under the shared RBAC layer
+///
+/// The token needs `read`. Tenant-bound credentials see their tenant;
+/// unbound credentials with `access:manage` see every tenant, and anything
+/// else (including anonymous access while enforcement is off) sees
+/// `default_tenant`.
+pub fn feed_principal(control: &AccessControl, token: Option<&str>, default_tenant: &str) -> Result<FeedPrincipal, String> {
+    let principal = control.authorize(token, Permission::Read, FEED_RESOURCE).map_err(|e| e.to_string())?;
+    Ok(match principal {
+        Some(principal) => FeedPrincipal {
+            tenant_id: match principal.tenant_id {
+                Some(tenant_id) => Some(tenant_id),
+                None if principal.has_permission(Permission::AccessManage) => None,
+                None => Some(default_tenant.to_string()),
+            },
+            actor: principal.principal_id,
+        },
+        None => FeedPrincipal {
+            actor: "anonymous".to_string(),
+            tenant_id: Some(default_tenant.to_string()),
+        },
+    })
+}
+
+/// Per-client buffering and limits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeedConfig {
+    /// Events queued per client before new ones are dropped
+    pub client_buffer: usize,
+    /// Consecutive drops after which a client is disconnected as too slow
+    pub max_consecutive_drops: u64,
+    pub max_clients: usize,
+    /// Interval of WebSocket pings and SSE keep-alive comments
+    pub heartbeat_secs: u64,
+}
+
+impl Default for FeedConfig {
+    fn default() -> Self {
+        Self {
+            client_buffer: 256,
+            max_consecutive_drops: 1_000,
+            max_clients: 500,
+            heartbeat_secs: 30,
+        }
+    }
+}
+
+struct FeedClient {
+    principal: FeedPrincipal,
+    topics: HashSet<FeedTopic>,
+    sender: mpsc::Sender<FeedEvent>,
+    /// Drops not yet reported to the client
+    lag: Arc<AtomicU64>,
+    evicted: Arc<AtomicBool>,
+    connected_at: DateTime<Utc>,
+    delivered: u64,
+    dropped: u64,
+    consecutive_drops: u64,
+}
+
+/// Snapshot of one connected client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedClientStats {
+    pub client_id: u64,
+    pub actor: String,
+    pub tenant_id: Option<String>,
+    pub topics: Vec<FeedTopic>,
+    pub connected_at: DateTime<Utc>,
+    pub queued: usize,
+    pub delivered: u64,
+    pub dropped: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FeedStats {
+    pub published: u64,
+    pub slow_clients_disconnected: u64,
+    pub clients: Vec<FeedClientStats>,
+}
+
+/// Fans published events out to subscribed clients
+pub struct FeedHub {
+    config: FeedConfig,
+    sequence: AtomicU64,
+    next_client: AtomicU64,
+    slow_disconnects: AtomicU64,
+    clients: Mutex<HashMap<u64, FeedClient>>,
+}
+
+impl Default for FeedHub {
+    fn default() -> Self {
+        Self::new(FeedConfig::default())
+    }
+}
+
+impl FeedHub {
+    pub fn new(config: FeedConfig) -> Self {
+        Self {
+            config,
+            sequence: AtomicU64::new(0),
+            next_client: AtomicU64::new(1),
+            slow_disconnects: AtomicU64::new(0),
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &FeedConfig {
+        &self.config
+    }
+
+    /// Register a client for `topics`
+    pub fn subscribe(self: &Arc<Self>, principal: FeedPrincipal, topics: HashSet<FeedTopic>) -> Result<FeedSubscription, String> {
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        if clients.len() >= self.config.max_clients {
+            return Err(format!("Live feed is at its limit of {} clients", self.config.max_clients));
+        }
+        let (sender, receiver) = mpsc::channel(self.config.client_buffer.max(1));
+        let client_id = self.next_client.fetch_add(1, Ordering::SeqCst);
+        let lag = Arc::new(AtomicU64::new(0));
+        let evicted = Arc::new(AtomicBool::new(false));
+        clients.insert(
+            client_id,
+            FeedClient {
+                principal,
+                topics,
+                sender,
+                lag: lag.clone(),
+                evicted: evicted.clone(),
+                connected_at: Utc::now(),
+                delivered: 0,
+                dropped: 0,
+                consecutive_drops: 0,
+            },
+        );
+        Ok(FeedSubscription {
+            client_id,
+            hub: self.clone(),
+            receiver,
+            topics: clients[&client_id].topics.clone(),
+            lag,
+            evicted,
+        })
+    }
+
+    fn set_topics(&self, client_id: u64, topics: HashSet<FeedTopic>) -> bool {
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        clients.get_mut(&client_id).map(|client| client.topics = topics).is_some()
+    }
+
+    fn unsubscribe(&self, client_id: u64) {
+        self.clients.lock().unwrap_or_else(|e| e.into_inner()).remove(&client_id);
+    }
+
+    /// Queue `event` for every subscribed client that may see it and return
+    /// how many accepted it. Never waits on a client.
+    pub fn publish(&self, mut event: FeedEvent) -> usize {
+        event.sequence = self.sequence.fetch_add(1, Ordering::SeqCst) + 1;
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        let mut delivered = 0;
+        let mut evict = Vec::new();
+
+        for (client_id, client) in clients.iter_mut() {
+            if !client.topics.contains(&event.topic) || !client.principal.sees(&event.tenant_id) {
+                continue;
+            }
+            match client.sender.try_send(event.clone()) {
+                Ok(()) => {
+                    client.delivered += 1;
+                    client.consecutive_drops = 0;
+                    delivered += 1;
+                }
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    client.dropped += 1;
+                    client.consecutive_drops += 1;
+                    client.lag.fetch_add(1, Ordering::SeqCst);
+                    if client.consecutive_drops > self.config.max_consecutive_drops {
+                        client.evicted.store(true, Ordering::SeqCst);
+                        evict.push(*client_id);
+                    }
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => evict.push(*client_id),
+            }
+        }
+
+        for client_id in evict {
+            if clients.remove(&client_id).is_some_and(|client| client.evicted.load(Ordering::SeqCst)) {
+                self.slow_disconnects.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        delivered
+    }
+
+    /// Disconnect every client, e.g. when the server in front of the hub stops
+    pub fn close_all(&self) -> usize {
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        let closed = clients.len();
+        clients.clear();
+        closed
+    }
+
+    pub fn client_count(&self) -> usize {
+        self.clients.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn stats(&self) -> FeedStats {
+        let clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stats: Vec<FeedClientStats> = clients
+            .iter()
+            .map(|(client_id, client)| {
+                let mut topics: Vec<FeedTopic> = client.topics.iter().copied().collect();
+                topics.sort();
+                FeedClientStats {
+                    client_id: *client_id,
+                    actor: client.principal.actor.clone(),
+                    tenant_id: client.principal.tenant_id.clone(),
+                    topics,
+                    connected_at: client.connected_at,
+                    queued: client.sender.max_capacity() - client.sender.capacity(),
+                    delivered: client.delivered,
+                    dropped: client.dropped,
+                }
+            })
+            .collect();
+        stats.sort_by_key(|client| client.client_id);
+        FeedStats {
+            published: self.sequence.load(Ordering::SeqCst),
+            slow_clients_disconnected: self.slow_disconnects.load(Ordering::SeqCst),
+            clients: stats,
+        }
+    }
+}
+
+/// A connected client's end of the feed; unsubscribes when dropped
+pub struct FeedSubscription {
+    client_id: u64,
+    hub: Arc<FeedHub>,
+    receiver: mpsc::Receiver<FeedEvent>,
+    topics: HashSet<FeedTopic>,
+    lag: Arc<AtomicU64>,
+    evicted: Arc<AtomicBool>,
+}
+
+impl FeedSubscription {
+    pub fn client_id(&self) -> u64 {
+        self.client_id
+    }
+
+    /// Next message for the client; `None` once the hub has let it go
+    pub async fn next(&mut self) -> Option<FeedMessage> {
+        let dropped = self.lag.swap(0, Ordering::SeqCst);
+        if dropped > 0 {
+            return Some(FeedMessage::Lagged { dropped });
+        }
+        self.receiver.recv().await.map(FeedMessage::Event)
+    }
+
+    /// Whether the hub disconnected this client for falling behind
+    pub fn evicted(&self) -> bool {
+        self.evicted.load(Ordering::SeqCst)
+    }
+
+    pub fn topics(&self) -> &HashSet<FeedTopic> {
+        &self.topics
+    }
+
+    /// Replace the subscribed topics; `None` if the hub has let the client go
+    pub fn set_topics(&mut self, topics: HashSet<FeedTopic>) -> Option<FeedMessage> {
+        if !self.hub.set_topics(self.client_id, topics.clone()) {
+            return None;
+        }
+        self.topics = topics;
+        let mut topics: Vec<FeedTopic> = self.topics.iter().copied().collect();
+        topics.sort();
+        Some(FeedMessage::Subscribed { topics })
+    }
+}
+
+impl Drop for FeedSubscription {
+    fn drop(&mut self) {
+        self.hub.unsubscribe(self.client_id);
+    }
+}
+
+#[cfg(feature = "live-feed")]
+pub use server::serve_feed;
+
+#[cfg(feature = "live-feed")]
+mod server {
+    use super::*;
+    use axum::extract::ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade};
+    use axum::extract::{Query, State};
+    use axum::http::{header, HeaderMap, StatusCode};
+    use axum::response::sse::{Event, KeepAlive, Sse};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::get;
+    use axum::Router;
+    use std::convert::Infallible;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    /// WebSocket close code for clients disconnected as too slow (RFC 6455 "try again later")
+    const CLOSE_SLOW_CONSUMER: u16 = 1013;
+
+    #[derive(Clone)]
+    struct FeedServer {
+        hub: Arc<FeedHub>,
+        authenticate: FeedAuthenticator,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct FeedQuery {
+        topics: Option<String>,
+        token: Option<String>,
+    }
+
+    /// Topic changes a WebSocket client may send
+    #[derive(Debug, Deserialize)]
+    #[serde(tag = "action", rename_all = "snake_case")]
+    enum FeedCommand {
+        Subscribe { topics: Vec<FeedTopic> },
+        Unsubscribe { topics: Vec<FeedTopic> },
+    }
+
+    impl FeedServer {
+        fn open(&self, query: &FeedQuery, headers: &HeaderMap) -> Result<FeedSubscription, Response> {
+            let bearer = headers
+                .get(header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "));
+            let token = query.token.as_deref().or(bearer);
+            let principal = (self.authenticate)(token).map_err(|e| (StatusCode::UNAUTHORIZED, e).into_response())?;
+            let topics = FeedTopic::parse_list(query.topics.as_deref().unwrap_or_default())
+                .map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
+            self.hub
+                .subscribe(principal, topics)
+                .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, e).into_response())
+        }
+    }
+
+    async fn websocket(
+        State(server): State<FeedServer>,
+        Query(query): Query<FeedQuery>,
+        headers: HeaderMap,
+        upgrade: WebSocketUpgrade,
+    ) -> Response {
+        match server.open(&query, &headers) {
+            Ok(subscription) => {
+                let heartbeat = Duration::from_secs(server.hub.config().heartbeat_secs.max(1));
+                upgrade.on_upgrade(move |socket| run_websocket(socket, subscription, heartbeat))
+            }
+            Err(response) => response,
+        }
+    }
+
+    async fn run_websocket(mut socket: WebSocket, mut subscription: FeedSubscription, heartbeat: Duration) {
+        let mut ping = tokio::time::interval(heartbeat);
+        ping.tick().await;
+
+        loop {
+            let outgoing = tokio::select! {
+                message = subscription.next() => match message {
+                    Some(message) => message,
+                    None => {
+                        let reason = if subscription.evicted() { "client too slow" } else { "feed closed" };
+                        let frame = CloseFrame { code: CLOSE_SLOW_CONSUMER, reason: reason.into() };
+                        let _ = socket.send(Message::Close(Some(frame))).await;
+                        return;
+                    }
+                },
+                incoming = socket.recv() => match incoming {
+                    Some(Ok(Message::Text(text))) => {
+                        let mut topics = subscription.topics().clone();
+                        match serde_json::from_str::<FeedCommand>(text.as_str()) {
+                            Ok(FeedCommand::Subscribe { topics: added }) => topics.extend(added),
+                            Ok(FeedCommand::Unsubscribe { topics: removed }) => topics.retain(|topic| !removed.contains(topic)),
+                            Err(e) => {
+                                let error = serde_json::json!({ "type": "error", "message": e.to_string() });
+                                if socket.send(Message::Text(error.to_string().into())).await.is_err() {
+                                    return;
+                                }
+                                continue;
+                            }
+                        }
+                        match subscription.set_topics(topics) {
+                            Some(message) => message,
+                            None => return,
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | None | Some(Err(_)) => return,
+                    Some(Ok(_)) => continue,
+                },
+                _ = ping.tick() => {
+                    if socket.send(Message::Ping(Vec::new().into())).await.is_err() {
+                        return;
+                    }
+                    continue;
+                }
+            };
+
+            let Ok(text) = serde_json::to_string(&outgoing) else { continue };
+            if socket.send(Message::Text(text.into())).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    async fn sse(State(server): State<FeedServer>, Query(query): Query<FeedQuery>, headers: HeaderMap) -> Response {
+        let subscription = match server.open(&query, &headers) {
+            Ok(subscription) => subscription,
+            Err(response) => return response,
+        };
+        let heartbeat = Duration::from_secs(server.hub.config().heartbeat_secs.max(1));
+        let stream = futures::stream::unfold(subscription, |mut subscription| async move {
+            let message = subscription.next().await?;
+            let event = match &message {
+                FeedMessage::Event(event) => Event::default()
+                    .event(event.topic.as_str())
+                    .id(event.sequence.to_string()),
+                FeedMessage::Lagged { .. } => Event::default().event("lagged"),
+                FeedMessage::Subscribed { .. } => Event::default().event("subscribed"),
+            };
+            let data = serde_json::to_string(&message).unwrap_or_default();
+            Some((Ok::<_, Infallible>(event.data(data)), subscription))
+        });
+        Sse::new(stream)
+            .keep_alive(KeepAlive::new().interval(heartbeat))
+            .into_response()
+    }
+
+    /// Serve `GET /feed/ws` and `GET /feed/sse` on `bind` until the returned
+    /// task is aborted; returns the bound address, so `127.0.0.1:0` picks a
+    /// free port
+    pub async fn serve_feed(bind: &str, hub: Arc<FeedHub>, authenticate: FeedAuthenticator) -> Result<(SocketAddr, JoinHandle<()>), String> {
+        let listener = TcpListener::bind(bind).await.map_err(|e| format!("Failed to bind live feed {}: {}", bind, e))?;
+        let address = listener.local_addr().map_err(|e| e.to_string())?;
+        let app = Router::new()
+            .route("/feed/ws", get(websocket))
+            .route("/feed/sse", get(sse))
+            .with_state(FeedServer { hub, authenticate });
+        let handle = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                log::warn!("Live feed server stopped: {}", e);
+            }
+        });
+        Ok((address, handle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn principal(tenant_id: Option<&str>) -> FeedPrincipal {
+        FeedPrincipal {
+            actor: "dashboard".to_string(),
+            tenant_id: tenant_id.map(str::to_string),
+        }
+    }
+
+    fn alert(tenant_id: &str) -> FeedEvent {
+        FeedEvent::new(FeedTopic::Alerts, "alert_created", tenant_id, "phantom-secop-core", serde_json::json!({}))
+    }
+
+    #[tokio::test]
+    async fn events_are_filtered_and_slow_clients_lag_then_disconnect() {
+        let hub = Arc::new(FeedHub::new(FeedConfig {
+            client_buffer: 2,
+            max_consecutive_drops: 3,
+            ..FeedConfig::default()
+        }));
+        let mut acme = hub.subscribe(principal(Some("acme")), FeedTopic::parse_list("alerts").unwrap()).unwrap();
+        let mut admin = hub.subscribe(principal(None), FeedTopic::parse_list("").unwrap()).unwrap();
+        assert!(FeedTopic::parse_list("alerts,weather").is_err());
+
+        assert_eq!(hub.publish(alert("acme")), 2);
+        assert_eq!(hub.publish(alert("globex")), 1);
+        let verdict = FeedEvent::new(FeedTopic::SandboxVerdicts, "verdict", "acme", "phantom-sandbox-core", serde_json::json!({}));
+        assert_eq!(hub.publish(verdict), 0);
+
+        match acme.next().await {
+            Some(FeedMessage::Event(event)) => assert_eq!((event.sequence, event.tenant_id.as_str()), (1, "acme")),
+            other => panic!("unexpected {:?}", other),
+        }
+        // admin's queue is full, so only it misses events
+        assert_eq!(hub.publish(alert("acme")), 1);
+        assert_eq!(hub.stats().clients[1].dropped, 2);
+        assert!(matches!(admin.next().await, Some(FeedMessage::Lagged { dropped: 2 })));
+        assert!(matches!(admin.next().await, Some(FeedMessage::Event(_))));
+
+        for _ in 0..5 {
+            hub.publish(alert("globex"));
+        }
+        assert!(admin.evicted());
+        assert_eq!(hub.client_count(), 1);
+        assert_eq!(hub.stats().slow_clients_disconnected, 1);
+
+        drop(acme);
+        assert_eq!(hub.client_count(), 0);
+    }
+}
@@ -0,0 +1,372 @@
+//! Metrics History
+//!
+//! Keeps the history of operational metrics (throughput, queue length,
+//! detection rate, MTTR, ...) so they can be charted over time instead of
+//! read as a single value that is overwritten in place. Every sample is
+//! rolled up as it is recorded into 1-minute, 1-hour and 1-day buckets, each
+//! resolution held in a fixed-size ring buffer, so memory stays bounded and
+//! the oldest buckets fall off first. Series are scoped (usually per tenant)
+//! and created on first use.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Resolution {
+    #[serde(rename = "1m")]
+    Minute,
+    #[serde(rename = "1h")]
+    Hour,
+    #[serde(rename = "1d")]
+    Day,
+}
+
+impl Resolution {
+    pub const ALL: [Resolution; 3] = [Resolution::Minute, Resolution::Hour, Resolution::Day];
+
+    pub fn seconds(&self) -> i64 {
+        match self {
+            Resolution::Minute => 60,
+            Resolution::Hour => 3_600,
+            Resolution::Day => 86_400,
+        }
+    }
+
+    /// Start of the bucket containing `at`
+    pub fn bucket_start(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let seconds = self.seconds();
+        let start = at.timestamp().div_euclid(seconds) * seconds;
+        DateTime::from_timestamp(start, 0).unwrap_or(at)
+    }
+
+    /// Finest resolution that keeps a chart of `range` to a few hundred points
+    pub fn for_range(range: Duration) -> Self {
+        if range <= Duration::hours(6) {
+            Resolution::Minute
+        } else if range <= Duration::days(14) {
+            Resolution::Hour
+        } else {
+            Resolution::Day
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Resolution::Minute => 0,
+            Resolution::Hour => 1,
+            Resolution::Day => 2,
+        }
+    }
+}
+
+impl FromStr for Resolution {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "1m" | "minute" => Ok(Resolution::Minute),
+            "1h" | "hour" => Ok(Resolution::Hour),
+            "1d" | "day" => Ok(Resolution::Day),
+            other => Err(format!("Unknown resolution '{}', expected 1m, 1h or 1d", other)),
+        }
+    }
+}
+
+/// Parse a look-back range such as `90m`, `24h` or `7d`
+pub fn parse_range(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value.len().saturating_sub(1);
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .ok()
+        .filter(|amount| *amount > 0)
+        .ok_or_else(|| format!("Invalid range '{}', expected e.g. 90m, 24h or 7d", value))?;
+    match unit {
+        "m" => Ok(Duration::minutes(amount)),
+        "h" => Ok(Duration::hours(amount)),
+        "d" => Ok(Duration::days(amount)),
+        _ => Err(format!("Invalid range '{}', expected e.g. 90m, 24h or 7d", value)),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsHistoryConfig {
+    /// 1-minute buckets kept per series (a day by default)
+    #[serde(default = "default_minute_buckets")]
+    pub minute_buckets: usize,
+    /// 1-hour buckets kept per series (30 days by default)
+    #[serde(default = "default_hour_buckets")]
+    pub hour_buckets: usize,
+    /// 1-day buckets kept per series (a year by default)
+    #[serde(default = "default_day_buckets")]
+    pub day_buckets: usize,
+    /// Series tracked at once; samples for new series are dropped beyond this
+    #[serde(default = "default_max_series")]
+    pub max_series: usize,
+}
+
+fn default_minute_buckets() -> usize {
+    1_440
+}
+
+fn default_hour_buckets() -> usize {
+    720
+}
+
+fn default_day_buckets() -> usize {
+    365
+}
+
+fn default_max_series() -> usize {
+    10_000
+}
+
+impl Default for MetricsHistoryConfig {
+    fn default() -> Self {
+        Self {
+            minute_buckets: default_minute_buckets(),
+            hour_buckets: default_hour_buckets(),
+            day_buckets: default_day_buckets(),
+            max_series: default_max_series(),
+        }
+    }
+}
+
+impl MetricsHistoryConfig {
+    fn capacity(&self, resolution: Resolution) -> usize {
+        match resolution {
+            Resolution::Minute => self.minute_buckets,
+            Resolution::Hour => self.hour_buckets,
+            Resolution::Day => self.day_buckets,
+        }
+    }
+}
+
+/// Aggregate of the samples recorded in one bucket
+///
+/// Counters are charted from `sum` (e.g. analyses completed per bucket),
+/// gauges from `mean`, `max` or `last` (e.g. queue length).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricPoint {
+    pub timestamp: DateTime<Utc>,
+    pub count: u64,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// Most recently recorded sample
+    pub last: f64,
+}
+
+impl MetricPoint {
+    fn new(timestamp: DateTime<Utc>, value: f64) -> Self {
+        Self { timestamp, count: 1, sum: value, min: value, max: value, mean: value, last: value }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.mean = self.sum / self.count as f64;
+        self.last = value;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricSeries {
+    pub metric: String,
+    pub scope: String,
+    pub resolution: Resolution,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Buckets with at least one sample, oldest first
+    pub points: Vec<MetricPoint>,
+}
+
+#[derive(Default)]
+struct Series {
+    rings: [VecDeque<MetricPoint>; 3],
+}
+
+impl Series {
+    fn add(&mut self, config: &MetricsHistoryConfig, value: f64, at: DateTime<Utc>) {
+        for resolution in Resolution::ALL {
+            let ring = &mut self.rings[resolution.index()];
+            let capacity = config.capacity(resolution);
+            let start = resolution.bucket_start(at);
+            match ring.back_mut() {
+                Some(bucket) if bucket.timestamp == start => bucket.add(value),
+                Some(bucket) if bucket.timestamp > start => match ring.binary_search_by_key(&start, |p| p.timestamp) {
+                    Ok(index) => ring[index].add(value),
+                    // Older than anything still retained
+                    Err(0) if ring.len() >= capacity => continue,
+                    Err(index) => ring.insert(index, MetricPoint::new(start, value)),
+                },
+                _ => ring.push_back(MetricPoint::new(start, value)),
+            }
+            while ring.len() > capacity {
+                ring.pop_front();
+            }
+        }
+    }
+}
+
+/// Bounded, in-memory time-series recorder
+pub struct MetricsHistory {
+    config: MetricsHistoryConfig,
+    series: Mutex<HashMap<(String, String), Series>>,
+}
+
+impl Default for MetricsHistory {
+    fn default() -> Self {
+        Self::new(MetricsHistoryConfig::default())
+    }
+}
+
+impl MetricsHistory {
+    pub fn new(config: MetricsHistoryConfig) -> Self {
+        Self { config, series: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn record(&self, scope: &str, metric: &str, value: f64) {
+        self.record_at(scope, metric, value, Utc::now());
+    }
+
+    /// Record a sample taken at `at`; late samples land in their own bucket
+    /// as long as it is still retained
+    pub fn record_at(&self, scope: &str, metric: &str, value: f64, at: DateTime<Utc>) {
+        if !value.is_finite() {
+            return;
+        }
+        let mut series = self.series.lock().unwrap_or_else(|e| e.into_inner());
+        let key = (scope.to_string(), metric.to_string());
+        if !series.contains_key(&key) && series.len() >= self.config.max_series {
+            log::warn!("Metrics history is tracking {} series; dropping sample for {}", series.len(), metric);
+            return;
+        }
+        series.entry(key).or_default().add(&self.config, value, at);
+    }
+
+    /// Buckets of `metric` at `resolution` between `from` and `to`
+    pub fn history(
+        &self,
+        scope: &str,
+        metric: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        resolution: Resolution,
+    ) -> Result<MetricSeries, String> {
+        if to < from {
+            return Err("History range ends before it starts".to_string());
+        }
+        let series = self.series.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = series
+            .get(&(scope.to_string(), metric.to_string()))
+            .ok_or_else(|| format!("No history recorded for metric {}", metric))?;
+        let first = resolution.bucket_start(from);
+        let points = entry.rings[resolution.index()]
+            .iter()
+            .filter(|p| p.timestamp >= first && p.timestamp <= to)
+            .cloned()
+            .collect();
+        Ok(MetricSeries { metric: metric.to_string(), scope: scope.to_string(), resolution, from, to, points })
+    }
+
+    /// History over the last `range`, at `resolution` or one suited to the range
+    pub fn recent(&self, scope: &str, metric: &str, range: Duration, resolution: Option<Resolution>) -> Result<MetricSeries, String> {
+        let to = Utc::now();
+        self.history(scope, metric, to - range, to, resolution.unwrap_or_else(|| Resolution::for_range(range)))
+    }
+
+    /// Metrics with recorded history in `scope`
+    pub fn metrics(&self, scope: &str) -> Vec<String> {
+        let series = self.series.lock().unwrap_or_else(|e| e.into_inner());
+        let mut metrics: Vec<String> = series.keys().filter(|(s, _)| s == scope).map(|(_, metric)| metric.clone()).collect();
+        metrics.sort();
+        metrics
+    }
+
+    /// Drop every series of `scope` and return how many there were
+    pub fn forget_scope(&self, scope: &str) -> usize {
+        let mut series = self.series.lock().unwrap_or_else(|e| e.into_inner());
+        let before = series.len();
+        series.retain(|(s, _), _| s != scope);
+        before - series.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 6, day, hour, minute, second).unwrap()
+    }
+
+    #[test]
+    fn samples_roll_up_into_every_resolution() {
+        let history = MetricsHistory::default();
+        history.record_at("acme", "queue_length", 4.0, at(1, 10, 0, 5));
+        history.record_at("acme", "queue_length", 8.0, at(1, 10, 0, 50));
+        history.record_at("acme", "queue_length", 2.0, at(1, 10, 1, 0));
+        history.record_at("acme", "queue_length", 6.0, at(1, 11, 30, 0));
+        history.record_at("acme", "queue_length", 1.0, at(2, 0, 0, 0));
+        // Late sample for a bucket that is still retained
+        history.record_at("acme", "queue_length", 10.0, at(1, 10, 0, 30));
+        history.record_at("globex", "queue_length", 99.0, at(1, 10, 0, 0));
+
+        let minutes = history.history("acme", "queue_length", at(1, 10, 0, 20), at(1, 12, 0, 0), Resolution::Minute).unwrap();
+        let timestamps: Vec<_> = minutes.points.iter().map(|p| p.timestamp).collect();
+        assert_eq!(timestamps, vec![at(1, 10, 0, 0), at(1, 10, 1, 0), at(1, 11, 30, 0)]);
+        let first = &minutes.points[0];
+        assert_eq!((first.count, first.sum, first.min, first.max, first.last), (3, 22.0, 4.0, 10.0, 10.0));
+
+        let hours = history.history("acme", "queue_length", at(1, 0, 0, 0), at(2, 23, 0, 0), Resolution::Hour).unwrap();
+        let sums: Vec<_> = hours.points.iter().map(|p| (p.timestamp, p.sum)).collect();
+        assert_eq!(sums, vec![(at(1, 10, 0, 0), 24.0), (at(1, 11, 0, 0), 6.0), (at(2, 0, 0, 0), 1.0)]);
+
+        let days = history.history("acme", "queue_length", at(1, 0, 0, 0), at(2, 0, 0, 0), Resolution::Day).unwrap();
+        assert_eq!(days.points.iter().map(|p| p.count).collect::<Vec<_>>(), vec![5, 1]);
+        assert_eq!(days.points[0].mean, 6.0);
+
+        assert!(history.history("acme", "mttr", at(1, 0, 0, 0), at(2, 0, 0, 0), Resolution::Day).is_err());
+        assert_eq!(history.metrics("globex"), vec!["queue_length"]);
+        assert_eq!(history.forget_scope("globex"), 1);
+        assert!(history.metrics("globex").is_empty());
+    }
+
+    #[test]
+    fn ring_buffers_drop_the_oldest_buckets() {
+        let history = MetricsHistory::new(MetricsHistoryConfig { minute_buckets: 3, max_series: 1, ..Default::default() });
+        for minute in 0..5 {
+            history.record_at("acme", "throughput", 1.0, at(1, 9, minute, 0));
+        }
+        history.record_at("acme", "throughput", 1.0, at(1, 8, 0, 0));
+        history.record_at("acme", "detection_rate", 0.5, at(1, 9, 0, 0));
+
+        let minutes = history.history("acme", "throughput", at(1, 0, 0, 0), at(1, 23, 0, 0), Resolution::Minute).unwrap();
+        assert_eq!(minutes.points.iter().map(|p| p.timestamp).collect::<Vec<_>>(), vec![at(1, 9, 2, 0), at(1, 9, 3, 0), at(1, 9, 4, 0)]);
+        let hours = history.history("acme", "throughput", at(1, 0, 0, 0), at(1, 23, 0, 0), Resolution::Hour).unwrap();
+        assert_eq!(hours.points.iter().map(|p| p.sum).collect::<Vec<_>>(), vec![1.0, 5.0]);
+        assert_eq!(history.metrics("acme"), vec!["throughput"]);
+    }
+
+    #[test]
+    fn ranges_and_resolutions_parse() {
+        assert_eq!(parse_range("90m").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_range("7d").unwrap(), Duration::days(7));
+        assert!(parse_range("0h").is_err());
+        assert!(parse_range("1w").is_err());
+        assert!(parse_range("").is_err());
+        assert_eq!("1h".parse::<Resolution>().unwrap(), Resolution::Hour);
+        assert!("5m".parse::<Resolution>().is_err());
+        assert_eq!(Resolution::for_range(Duration::hours(24)), Resolution::Hour);
+        assert_eq!(Resolution::for_range(Duration::days(90)), Resolution::Day);
+    }
+}
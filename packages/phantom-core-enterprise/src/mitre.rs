@@ -0,0 +1,11 @@
+//! MITRE ATT&CK Enterprise matrix
+//!
+//! The tactics, techniques and sub-techniques of the Enterprise matrix,
+//! embedded once for every core that maps evidence to ATT&CK. Each core
+//! parses the table into the catalog shape it needs.
+
+/// Tab-separated rows of `kind`, `id`, `name` and either the technique's
+/// tactic shortnames (comma-separated; sub-techniques leave it empty and
+/// inherit their parent's) or the tactic's shortname. Lines starting with
+/// `#` are comments.
+pub const ENTERPRISE_MATRIX_TSV: &str = include_str!("mitre_enterprise.tsv");
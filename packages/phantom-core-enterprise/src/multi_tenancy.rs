@@ -2,13 +2,19 @@
 //!
 //! Provides enterprise-grade multi-tenant capabilities with strict data isolation,
 //! tenant-specific configurations, and scalable resource management.
+//! [`TenantDirectory`] tracks the tenants a core partitions its data by and
+//! whether each may currently be served.
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
+use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use uuid::Uuid;
 
+/// Tenant that owns data created without a tenant-bound credential
+pub const DEFAULT_TENANT_ID: &str = "default";
+
 /// Enterprise tenant context with strict isolation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EnterpriseTenantContext {
@@ -675,3 +681,159 @@ impl MultiTenantSecurityModule for DefaultMultiTenantModule {
         Ok(summaries)
     }
 }
+
+/// A tenant known to a [`TenantDirectory`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantRecord {
+    pub tenant_id: String,
+    pub organization_name: String,
+    pub status: TenantStatus,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub suspension_reason: Option<String>,
+}
+
+/// What deleting a tenant removed, by record kind
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantPurgeReport {
+    pub tenant_id: String,
+    pub purged: BTreeMap<String, usize>,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// Tenant lifecycle: creation, suspension and deletion.
+///
+/// The default tenant always exists and cannot be deleted.
+pub struct TenantDirectory {
+    tenants: RwLock<HashMap<String, TenantRecord>>,
+}
+
+impl Default for TenantDirectory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TenantDirectory {
+    pub fn new() -> Self {
+        let now = Utc::now();
+        let default = TenantRecord {
+            tenant_id: DEFAULT_TENANT_ID.to_string(),
+            organization_name: "Default".to_string(),
+            status: TenantStatus::Active,
+            created_at: now,
+            updated_at: now,
+            suspension_reason: None,
+        };
+        Self {
+            tenants: RwLock::new(HashMap::from([(DEFAULT_TENANT_ID.to_string(), default)])),
+        }
+    }
+
+    pub fn create(&self, tenant_id: &str, organization_name: &str) -> Result<TenantRecord, TenantError> {
+        let valid = !tenant_id.is_empty()
+            && tenant_id.len() <= 64
+            && tenant_id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+        if !valid {
+            return Err(TenantError::ConfigurationError(format!(
+                "tenant id '{}' must be 1-64 letters, digits, '-' or '_'",
+                tenant_id
+            )));
+        }
+        let mut tenants = self.tenants.write();
+        if tenants.contains_key(tenant_id) {
+            return Err(TenantError::AlreadyExists(tenant_id.to_string()));
+        }
+        let now = Utc::now();
+        let record = TenantRecord {
+            tenant_id: tenant_id.to_string(),
+            organization_name: organization_name.to_string(),
+            status: TenantStatus::Active,
+            created_at: now,
+            updated_at: now,
+            suspension_reason: None,
+        };
+        tenants.insert(tenant_id.to_string(), record.clone());
+        Ok(record)
+    }
+
+    /// Refuse further calls for the tenant while keeping its data
+    pub fn suspend(&self, tenant_id: &str, reason: Option<String>) -> Result<TenantRecord, TenantError> {
+        self.set_status(tenant_id, TenantStatus::Suspended, reason)
+    }
+
+    pub fn resume(&self, tenant_id: &str) -> Result<TenantRecord, TenantError> {
+        self.set_status(tenant_id, TenantStatus::Active, None)
+    }
+
+    fn set_status(&self, tenant_id: &str, status: TenantStatus, reason: Option<String>) -> Result<TenantRecord, TenantError> {
+        let mut tenants = self.tenants.write();
+        let record = tenants
+            .get_mut(tenant_id)
+            .ok_or_else(|| TenantError::NotFound(tenant_id.to_string()))?;
+        record.status = status;
+        record.suspension_reason = reason;
+        record.updated_at = Utc::now();
+        Ok(record.clone())
+    }
+
+    /// Forget a tenant; its data must already have been purged
+    pub fn remove(&self, tenant_id: &str) -> Result<TenantRecord, TenantError> {
+        if tenant_id == DEFAULT_TENANT_ID {
+            return Err(TenantError::ConfigurationError("the default tenant cannot be deleted".to_string()));
+        }
+        self.tenants
+            .write()
+            .remove(tenant_id)
+            .ok_or_else(|| TenantError::NotFound(tenant_id.to_string()))
+    }
+
+    pub fn get(&self, tenant_id: &str) -> Option<TenantRecord> {
+        self.tenants.read().get(tenant_id).cloned()
+    }
+
+    pub fn list(&self) -> Vec<TenantRecord> {
+        let mut tenants: Vec<TenantRecord> = self.tenants.read().values().cloned().collect();
+        tenants.sort_by(|a, b| a.tenant_id.cmp(&b.tenant_id));
+        tenants
+    }
+
+    /// Check that calls may be served for `tenant_id`
+    pub fn ensure_active(&self, tenant_id: &str) -> Result<(), TenantError> {
+        match self.tenants.read().get(tenant_id) {
+            None => Err(TenantError::NotFound(tenant_id.to_string())),
+            Some(record) if record.status != TenantStatus::Active => {
+                Err(TenantError::AccessDenied(format!("tenant {} is {:?}", tenant_id, record.status).to_lowercase()))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tenant_lifecycle() {
+        let directory = TenantDirectory::new();
+        assert!(directory.ensure_active(DEFAULT_TENANT_ID).is_ok());
+        assert!(matches!(directory.ensure_active("acme"), Err(TenantError::NotFound(_))));
+        assert!(matches!(directory.create("acme corp", "Acme"), Err(TenantError::ConfigurationError(_))));
+
+        directory.create("acme", "Acme").unwrap();
+        assert!(matches!(directory.create("acme", "Acme"), Err(TenantError::AlreadyExists(_))));
+        assert!(directory.ensure_active("acme").is_ok());
+
+        let suspended = directory.suspend("acme", Some("unpaid".to_string())).unwrap();
+        assert_eq!(suspended.suspension_reason.as_deref(), Some("unpaid"));
+        assert!(matches!(directory.ensure_active("acme"), Err(TenantError::AccessDenied(_))));
+        directory.resume("acme").unwrap();
+        assert!(directory.ensure_active("acme").is_ok());
+
+        assert!(directory.remove(DEFAULT_TENANT_ID).is_err());
+        directory.remove("acme").unwrap();
+        assert_eq!(directory.list().len(), 1);
+    }
+}
@@ -0,0 +1,385 @@
+//! Cursor pagination for list APIs
+//!
+//! List endpoints take a `PageRequest`: an opaque cursor from the previous
+//! page, a page size, a sort field and direction, field filters and an
+//! optional projection. Records are filtered and sorted on their serialized
+//! form, so any field (dotted paths reach nested ones) can be used without
+//! per-list code.
+//!
+//! Cursors are keyset cursors: they hold the sort key and id of the last
+//! record returned, and the next page starts after that position. Records
+//! added or removed between requests therefore never shift a page. A
+//! cursor only fits the sort and filters it was issued for.
+//!
+//! Filters map a field to a value (equality; strings compare
+//! case-insensitively), a list of values (any of them), or an object of
+//! `gt`, `gte`, `lt`, `lte` and `contains` operators. Array fields match
+//! when any element does.
+//!
+//! Shared by the list APIs of the sandbox, hunting and secop cores, which
+//! map [`PageError`] onto their own error categories.
+
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use thiserror::Error;
+
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// Pagination errors
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum PageError {
+    /// The request, cursor or a filter is malformed
+    #[error("{0}")]
+    Invalid(String),
+
+    /// A record could not be serialized for filtering or projection
+    #[error("{0}")]
+    Serialization(String),
+}
+
+pub type PageResult<T> = Result<T, PageError>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortDirection {
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PageRequest {
+    /// `next_cursor` of the previous page
+    #[serde(default)]
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub page_size: Option<usize>,
+    #[serde(default)]
+    pub sort_by: Option<String>,
+    #[serde(default)]
+    pub direction: Option<SortDirection>,
+    #[serde(default)]
+    pub filters: BTreeMap<String, Value>,
+    /// Fields to return; all of them when empty
+    #[serde(default)]
+    pub fields: Vec<String>,
+}
+
+impl PageRequest {
+    /// Parse a request from JSON; none means the first page with defaults
+    pub fn from_json(json: Option<&str>) -> PageResult<Self> {
+        match json {
+            Some(json) if !json.trim().is_empty() => {
+                serde_json::from_str(json).map_err(|e| PageError::Invalid(format!("Invalid page request: {}", e)))
+            }
+            _ => Ok(Self::default()),
+        }
+    }
+}
+
+/// How a list is keyed and ordered by default
+#[derive(Debug, Clone, Copy)]
+pub struct ListSpec {
+    /// Unique field breaking ties between equal sort keys
+    pub id_field: &'static str,
+    pub sort_by: &'static str,
+    pub direction: SortDirection,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Absent on the last page
+    pub next_cursor: Option<String>,
+    /// Records matching the filters, over all pages
+    pub total: usize,
+    pub page_size: usize,
+    pub sort_by: String,
+    pub direction: SortDirection,
+}
+
+impl<T: Serialize> Page<T> {
+    /// The page with each item cut down to `fields`
+    pub fn project(self, fields: &[String]) -> PageResult<Page<Value>> {
+        let items = self
+            .items
+            .iter()
+            .map(|item| {
+                let value = serde_json::to_value(item).map_err(|e| PageError::Serialization(e.to_string()))?;
+                Ok(if fields.is_empty() { value } else { select_fields(&value, fields) })
+            })
+            .collect::<PageResult<Vec<Value>>>()?;
+        Ok(Page { items, next_cursor: self.next_cursor, total: self.total, page_size: self.page_size, sort_by: self.sort_by, direction: self.direction })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Cursor {
+    #[serde(rename = "k")]
+    key: Value,
+    id: Value,
+    /// Fingerprint of the sort and filters the cursor belongs to
+    #[serde(rename = "q")]
+    query: u64,
+}
+
+fn encode_cursor(cursor: &Cursor) -> String {
+    serde_json::to_vec(cursor).unwrap_or_default().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn decode_cursor(text: &str) -> PageResult<Cursor> {
+    let invalid = || PageError::Invalid("Invalid page cursor".to_string());
+    if !text.len().is_multiple_of(2) {
+        return Err(invalid());
+    }
+    let bytes = (0..text.len())
+        .step_by(2)
+        .map(|i| text.get(i..i + 2).and_then(|pair| u8::from_str_radix(pair, 16).ok()))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(invalid)?;
+    serde_json::from_slice(&bytes).map_err(|_| invalid())
+}
+
+fn fingerprint(sort_by: &str, direction: SortDirection, filters: &BTreeMap<String, Value>) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    (sort_by, direction == SortDirection::Asc, serde_json::to_string(filters).unwrap_or_default()).hash(&mut hasher);
+    hasher.finish()
+}
+
+fn field<'a>(value: &'a Value, path: &str) -> &'a Value {
+    path.split('.').try_fold(value, |value, part| value.get(part)).unwrap_or(&Value::Null)
+}
+
+fn select_fields(value: &Value, fields: &[String]) -> Value {
+    let mut selected = Map::new();
+    for path in fields {
+        let found = field(value, path);
+        // Rebuild the nesting of dotted paths
+        let mut parts = path.split('.').peekable();
+        let mut target = &mut selected;
+        while let Some(part) = parts.next() {
+            if parts.peek().is_none() {
+                target.insert(part.to_string(), found.clone());
+                break;
+            }
+            let entry = target.entry(part.to_string()).or_insert_with(|| Value::Object(Map::new()));
+            if !entry.is_object() {
+                *entry = Value::Object(Map::new());
+            }
+            target = entry.as_object_mut().expect("object entry");
+        }
+    }
+    Value::Object(selected)
+}
+
+fn type_rank(value: &Value) -> u8 {
+    match value {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        Value::Number(_) => 2,
+        Value::String(_) => 3,
+        Value::Array(_) => 4,
+        Value::Object(_) => 5,
+    }
+}
+
+/// Order two field values; RFC 3339 strings compare as instants
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a, b) {
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Number(a), Value::Number(b)) => a.as_f64().unwrap_or(0.0).total_cmp(&b.as_f64().unwrap_or(0.0)),
+        (Value::String(a), Value::String(b)) => match (DateTime::parse_from_rfc3339(a), DateTime::parse_from_rfc3339(b)) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            _ => a.cmp(b),
+        },
+        (Value::Array(_), Value::Array(_)) | (Value::Object(_), Value::Object(_)) => a.to_string().cmp(&b.to_string()),
+        _ => type_rank(a).cmp(&type_rank(b)),
+    }
+}
+
+/// Sort key of a field: timestamps become microseconds so sorting does not reparse them
+fn sort_key(value: &Value) -> Value {
+    match value {
+        Value::String(text) => match DateTime::parse_from_rfc3339(text) {
+            Ok(at) => Value::from(at.timestamp_micros()),
+            Err(_) => value.clone(),
+        },
+        _ => value.clone(),
+    }
+}
+
+fn scalar_matches(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::String(a), Value::String(b)) => a.eq_ignore_ascii_case(b),
+        (Value::Number(_), Value::Number(_)) => compare(actual, expected) == Ordering::Equal,
+        // Numbers and flags given as strings in query JSON
+        (Value::Number(_) | Value::Bool(_), Value::String(b)) => {
+            b.parse::<Value>().is_ok_and(|parsed| !parsed.is_string() && scalar_matches(actual, &parsed))
+        }
+        _ => actual == expected,
+    }
+}
+
+fn operator_matches(actual: &Value, operator: &str, operand: &Value) -> PageResult<bool> {
+    Ok(match operator {
+        "gt" => compare(actual, operand) == Ordering::Greater,
+        "gte" => compare(actual, operand) != Ordering::Less,
+        "lt" => compare(actual, operand) == Ordering::Less,
+        "lte" => compare(actual, operand) != Ordering::Greater,
+        "contains" => match (actual, operand) {
+            (Value::String(a), Value::String(b)) => a.to_lowercase().contains(&b.to_lowercase()),
+            _ => false,
+        },
+        other => return Err(PageError::Invalid(format!("Unknown filter operator {}", other))),
+    })
+}
+
+fn matches_filter(actual: &Value, expected: &Value) -> PageResult<bool> {
+    if let Value::Array(elements) = actual {
+        for element in elements {
+            if matches_filter(element, expected)? {
+                return Ok(true);
+            }
+        }
+        return Ok(false);
+    }
+    match expected {
+        Value::Array(options) => Ok(options.iter().any(|option| scalar_matches(actual, option))),
+        Value::Object(operators) => {
+            for (operator, operand) in operators {
+                if !operator_matches(actual, operator, operand)? {
+                    return Ok(false);
+                }
+            }
+            Ok(true)
+        }
+        expected => Ok(scalar_matches(actual, expected)),
+    }
+}
+
+/// Filter, sort and cut `items` down to the page `request` asks for
+pub fn paginate<T: Serialize>(items: Vec<T>, request: &PageRequest, spec: ListSpec) -> PageResult<Page<T>> {
+    let sort_by = request.sort_by.clone().unwrap_or_else(|| spec.sort_by.to_string());
+    let direction = request.direction.unwrap_or(spec.direction);
+    let page_size = request.page_size.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let query = fingerprint(&sort_by, direction, &request.filters);
+
+    let mut keyed = Vec::with_capacity(items.len());
+    for item in items {
+        let value = serde_json::to_value(&item).map_err(|e| PageError::Serialization(e.to_string()))?;
+        let mut keep = true;
+        for (path, expected) in &request.filters {
+            if !matches_filter(field(&value, path), expected)? {
+                keep = false;
+                break;
+            }
+        }
+        if keep {
+            keyed.push((sort_key(field(&value, &sort_by)), field(&value, spec.id_field).clone(), item));
+        }
+    }
+
+    let order = |key: &Value, id: &Value, other_key: &Value, other_id: &Value| {
+        let ordering = compare(key, other_key).then_with(|| compare(id, other_id));
+        match direction {
+            SortDirection::Asc => ordering,
+            SortDirection::Desc => ordering.reverse(),
+        }
+    };
+    keyed.sort_by(|a, b| order(&a.0, &a.1, &b.0, &b.1));
+    let total = keyed.len();
+
+    let start = match &request.cursor {
+        Some(text) => {
+            let cursor = decode_cursor(text)?;
+            if cursor.query != query {
+                return Err(PageError::Invalid("Page cursor belongs to a different sort or filter".to_string()));
+            }
+            keyed.partition_point(|(key, id, _)| order(key, id, &cursor.key, &cursor.id) != Ordering::Greater)
+        }
+        None => 0,
+    };
+
+    let mut page: Vec<(Value, Value, T)> = keyed.into_iter().skip(start).take(page_size + 1).collect();
+    let next_cursor = if page.len() > page_size {
+        page.truncate(page_size);
+        page.last().map(|(key, id, _)| encode_cursor(&Cursor { key: key.clone(), id: id.clone(), query }))
+    } else {
+        None
+    };
+
+    Ok(Page { items: page.into_iter().map(|(_, _, item)| item).collect(), next_cursor, total, page_size, sort_by, direction })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const SPEC: ListSpec = ListSpec { id_field: "id", sort_by: "created_at", direction: SortDirection::Desc };
+
+    fn records() -> Vec<Value> {
+        (0..25)
+            .map(|i| {
+                json!({
+                    "id": format!("r-{:02}", i),
+                    "created_at": format!("2024-05-01T00:{:02}:00{}", i / 2, if i % 2 == 0 { "Z" } else { ".5Z" }),
+                    "severity": if i % 2 == 0 { "Low" } else { "High" },
+                    "score": i,
+                    "tags": if i % 5 == 0 { json!(["phishing"]) } else { json!([]) },
+                    "meta": { "source": if i < 10 { "edr" } else { "email" } }
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn cursors_walk_every_record_once_in_order() {
+        let mut request = PageRequest { page_size: Some(10), ..Default::default() };
+        let mut seen = Vec::new();
+        loop {
+            let page = paginate(records(), &request, SPEC).unwrap();
+            assert_eq!(page.total, 25);
+            seen.extend(page.items.iter().map(|r| r["score"].as_i64().unwrap()));
+            match page.next_cursor {
+                Some(cursor) => request.cursor = Some(cursor),
+                None => break,
+            }
+        }
+        assert_eq!(seen, (0..25).rev().collect::<Vec<_>>());
+
+        // A record inserted ahead of the cursor does not shift the next page
+        let first = paginate(records(), &PageRequest { page_size: Some(5), ..Default::default() }, SPEC).unwrap();
+        let mut grown = records();
+        grown.push(json!({ "id": "r-99", "created_at": "2024-06-01T00:00:00Z", "score": 99 }));
+        let request = PageRequest { page_size: Some(5), cursor: first.next_cursor.clone(), ..Default::default() };
+        let second = paginate(grown, &request, SPEC).unwrap();
+        assert_eq!(second.items[0]["score"], 19);
+
+        let resorted = PageRequest { sort_by: Some("score".to_string()), ..request };
+        assert_eq!(paginate(records(), &resorted, SPEC).unwrap_err(), PageError::Invalid("Page cursor belongs to a different sort or filter".to_string()));
+        let garbage = PageRequest { cursor: Some("zz".to_string()), ..Default::default() };
+        assert!(paginate(records(), &garbage, SPEC).is_err());
+    }
+
+    #[test]
+    fn filters_sorting_and_projection() {
+        let request = PageRequest::from_json(Some(
+            r#"{"filters": {"severity": "high", "score": {"gte": 5, "lt": 21}, "meta.source": ["email", "siem"]},
+                "sort_by": "score", "direction": "asc", "fields": ["id", "meta.source"]}"#,
+        ))
+        .unwrap();
+        let page = paginate(records(), &request, SPEC).unwrap().project(&request.fields).unwrap();
+        assert_eq!(page.items.iter().map(|r| r["id"].as_str().unwrap()).collect::<Vec<_>>(), ["r-11", "r-13", "r-15", "r-17", "r-19"]);
+        assert_eq!(page.items[0], json!({ "id": "r-11", "meta": { "source": "email" } }));
+
+        let tagged = PageRequest { filters: BTreeMap::from([("tags".to_string(), json!("Phishing"))]), ..Default::default() };
+        assert_eq!(paginate(records(), &tagged, SPEC).unwrap().total, 5);
+        let bad = PageRequest { filters: BTreeMap::from([("score".to_string(), json!({ "near": 3 }))]), ..Default::default() };
+        assert!(paginate(records(), &bad, SPEC).is_err());
+    }
+}
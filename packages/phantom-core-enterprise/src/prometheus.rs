@@ -0,0 +1,433 @@
+//! Prometheus Exposition
+//!
+//! Renders core metrics in the Prometheus text exposition format (0.0.4) so
+//! ops teams can scrape them. Cores describe their counters, gauges and
+//! histograms through a [`PrometheusWriter`], which groups samples by family
+//! and escapes label values; [`CounterSet`] and [`HistogramSet`] keep the
+//! monotonic, labelled values between scrapes. Every sample carries a `core`
+//! label, plus `tenant` and `engine` where they apply, so dashboards can
+//! aggregate across cores. [`serve_metrics`] embeds a minimal `/metrics`
+//! HTTP endpoint for deployments without a Node-side exporter.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Default histogram buckets for durations in seconds
+pub const DURATION_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0];
+
+/// Label pairs of one sample, in a stable order
+pub type Labels = Vec<(String, String)>;
+
+/// Build labels from borrowed pairs
+pub fn labels(pairs: &[(&str, &str)]) -> Labels {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricType {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+impl MetricType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MetricType::Counter => "counter",
+            MetricType::Gauge => "gauge",
+            MetricType::Histogram => "histogram",
+        }
+    }
+}
+
+/// Cumulative histogram with fixed upper bounds
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    bounds: Vec<f64>,
+    /// Observations at or below each bound (non-cumulative)
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    pub fn new(bounds: &[f64]) -> Self {
+        let mut bounds: Vec<f64> = bounds.iter().copied().filter(|b| b.is_finite()).collect();
+        bounds.sort_by(|a, b| a.total_cmp(b));
+        bounds.dedup();
+        Self { counts: vec![0; bounds.len()], bounds, sum: 0.0, count: 0 }
+    }
+
+    pub fn observe(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        if let Some(index) = self.bounds.iter().position(|bound| value <= *bound) {
+            self.counts[index] += 1;
+        }
+        self.sum += value;
+        self.count += 1;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    /// `(upper bound, cumulative count)` per bucket, ending with `+Inf`
+    pub fn cumulative(&self) -> Vec<(f64, u64)> {
+        let mut running = 0;
+        let mut buckets: Vec<(f64, u64)> = self
+            .bounds
+            .iter()
+            .zip(&self.counts)
+            .map(|(bound, count)| {
+                running += count;
+                (*bound, running)
+            })
+            .collect();
+        buckets.push((f64::INFINITY, self.count));
+        buckets
+    }
+}
+
+/// Monotonic counters keyed by label set
+#[derive(Debug, Default)]
+pub struct CounterSet {
+    values: Mutex<BTreeMap<Labels, f64>>,
+}
+
+impl CounterSet {
+    pub fn inc(&self, labels: Labels, by: f64) {
+        if by.is_finite() && by >= 0.0 {
+            *self.values.lock().unwrap_or_else(|e| e.into_inner()).entry(labels).or_default() += by;
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<(Labels, f64)> {
+        self.values.lock().unwrap_or_else(|e| e.into_inner()).iter().map(|(l, v)| (l.clone(), *v)).collect()
+    }
+
+    /// Drop every series where `label` has `value`, e.g. a purged tenant
+    pub fn forget(&self, label: &str, value: &str) -> usize {
+        forget(&mut self.values.lock().unwrap_or_else(|e| e.into_inner()), label, value)
+    }
+}
+
+/// Histograms keyed by label set, sharing one set of bucket bounds
+#[derive(Debug)]
+pub struct HistogramSet {
+    bounds: Vec<f64>,
+    values: Mutex<BTreeMap<Labels, Histogram>>,
+}
+
+impl HistogramSet {
+    pub fn new(bounds: &[f64]) -> Self {
+        Self { bounds: bounds.to_vec(), values: Mutex::new(BTreeMap::new()) }
+    }
+
+    pub fn observe(&self, labels: Labels, value: f64) {
+        self.values
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(labels)
+            .or_insert_with(|| Histogram::new(&self.bounds))
+            .observe(value);
+    }
+
+    pub fn snapshot(&self) -> Vec<(Labels, Histogram)> {
+        self.values.lock().unwrap_or_else(|e| e.into_inner()).iter().map(|(l, h)| (l.clone(), h.clone())).collect()
+    }
+
+    pub fn forget(&self, label: &str, value: &str) -> usize {
+        forget(&mut self.values.lock().unwrap_or_else(|e| e.into_inner()), label, value)
+    }
+}
+
+impl Default for HistogramSet {
+    fn default() -> Self {
+        Self::new(DURATION_BUCKETS)
+    }
+}
+
+fn forget<V>(values: &mut BTreeMap<Labels, V>, label: &str, value: &str) -> usize {
+    let before = values.len();
+    values.retain(|labels, _| !labels.iter().any(|(k, v)| k == label && v == value));
+    before - values.len()
+}
+
+struct Family {
+    help: String,
+    kind: MetricType,
+    samples: Vec<String>,
+}
+
+/// Collects metric families and renders them in exposition order
+pub struct PrometheusWriter {
+    /// Labels added to every sample, e.g. `core="sandbox"`
+    common: Labels,
+    order: Vec<String>,
+    families: BTreeMap<String, Family>,
+}
+
+impl PrometheusWriter {
+    pub fn new(common: Labels) -> Self {
+        Self { common, order: Vec::new(), families: BTreeMap::new() }
+    }
+
+    /// Writer whose samples all carry `core="<core>"`
+    pub fn for_core(core: &str) -> Self {
+        Self::new(labels(&[("core", core)]))
+    }
+
+    pub fn counter(&mut self, name: &str, help: &str, labels: &[(&str, &str)], value: f64) {
+        let name = sanitize_name(name);
+        let line = format!("{}{} {}", name, self.render_labels(labels, None), format_value(value));
+        self.family(&name, help, MetricType::Counter).samples.push(line);
+    }
+
+    pub fn gauge(&mut self, name: &str, help: &str, labels: &[(&str, &str)], value: f64) {
+        let name = sanitize_name(name);
+        let line = format!("{}{} {}", name, self.render_labels(labels, None), format_value(value));
+        self.family(&name, help, MetricType::Gauge).samples.push(line);
+    }
+
+    pub fn histogram(&mut self, name: &str, help: &str, labels: &[(&str, &str)], histogram: &Histogram) {
+        let name = sanitize_name(name);
+        let mut lines = Vec::new();
+        for (bound, count) in histogram.cumulative() {
+            let le = if bound.is_infinite() { "+Inf".to_string() } else { format_value(bound) };
+            lines.push(format!("{}_bucket{} {}", name, self.render_labels(labels, Some(&le)), count));
+        }
+        lines.push(format!("{}_sum{} {}", name, self.render_labels(labels, None), format_value(histogram.sum())));
+        lines.push(format!("{}_count{} {}", name, self.render_labels(labels, None), histogram.count()));
+        self.family(&name, help, MetricType::Histogram).samples.extend(lines);
+    }
+
+    /// Every counter in `set` under one family
+    pub fn counter_set(&mut self, name: &str, help: &str, set: &CounterSet) {
+        for (labels, value) in set.snapshot() {
+            self.counter(name, help, &borrow(&labels), value);
+        }
+        self.declare(name, help, MetricType::Counter);
+    }
+
+    /// Every histogram in `set` under one family
+    pub fn histogram_set(&mut self, name: &str, help: &str, set: &HistogramSet) {
+        for (labels, histogram) in set.snapshot() {
+            self.histogram(name, help, &borrow(&labels), &histogram);
+        }
+        self.declare(name, help, MetricType::Histogram);
+    }
+
+    pub fn finish(self) -> String {
+        let mut out = String::new();
+        for name in &self.order {
+            let family = &self.families[name];
+            let _ = writeln!(out, "# HELP {} {}", name, escape_help(&family.help));
+            let _ = writeln!(out, "# TYPE {} {}", name, family.kind.as_str());
+            for sample in &family.samples {
+                out.push_str(sample);
+                out.push('\n');
+            }
+        }
+        out
+    }
+
+    /// Make sure the family is listed even when it has no samples yet
+    fn declare(&mut self, name: &str, help: &str, kind: MetricType) {
+        let name = sanitize_name(name);
+        self.family(&name, help, kind);
+    }
+
+    fn family(&mut self, name: &str, help: &str, kind: MetricType) -> &mut Family {
+        if !self.families.contains_key(name) {
+            self.order.push(name.to_string());
+        }
+        self.families
+            .entry(name.to_string())
+            .or_insert_with(|| Family { help: help.to_string(), kind, samples: Vec::new() })
+    }
+
+    fn render_labels(&self, labels: &[(&str, &str)], le: Option<&str>) -> String {
+        let mut pairs: Vec<(String, &str)> = Vec::new();
+        for (key, value) in self.common.iter().map(|(k, v)| (k.as_str(), v.as_str())).chain(labels.iter().copied()) {
+            let key = sanitize_name(key);
+            if !pairs.iter().any(|(k, _)| *k == key) {
+                pairs.push((key, value));
+            }
+        }
+        if let Some(le) = le {
+            pairs.push(("le".to_string(), le));
+        }
+        if pairs.is_empty() {
+            return String::new();
+        }
+        let rendered: Vec<String> = pairs.iter().map(|(k, v)| format!("{}=\"{}\"", k, escape_label(v))).collect();
+        format!("{{{}}}", rendered.join(","))
+    }
+}
+
+fn borrow(labels: &Labels) -> Vec<(&str, &str)> {
+    labels.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect()
+}
+
+/// Replace characters not allowed in metric and label names
+pub fn sanitize_name(name: &str) -> String {
+    let mut sanitized: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == ':' { c } else { '_' })
+        .collect();
+    if sanitized.is_empty() || sanitized.starts_with(|c: char| c.is_ascii_digit()) {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn escape_help(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Produces the exposition text for one scrape
+pub type RenderFn = Arc<dyn Fn() -> Pin<Box<dyn Future<Output = String> + Send>> + Send + Sync>;
+
+/// Serve `GET /metrics` on `bind` until the returned task is aborted
+///
+/// Each connection handles a single request. Returns the bound address, so
+/// `127.0.0.1:0` can be used to pick a free port.
+pub async fn serve_metrics(bind: &str, render: RenderFn) -> Result<(SocketAddr, JoinHandle<()>), String> {
+    let listener = TcpListener::bind(bind).await.map_err(|e| format!("Failed to bind metrics endpoint {}: {}", bind, e))?;
+    let address = listener.local_addr().map_err(|e| e.to_string())?;
+    let handle = tokio::spawn(async move {
+        loop {
+            let Ok((mut stream, _)) = listener.accept().await else {
+                continue;
+            };
+            let render = render.clone();
+            tokio::spawn(async move {
+                let mut buffer = vec![0u8; 8192];
+                let read = match tokio::time::timeout(std::time::Duration::from_secs(10), stream.read(&mut buffer)).await {
+                    Ok(Ok(read)) => read,
+                    _ => return,
+                };
+                let request = String::from_utf8_lossy(&buffer[..read]);
+                let mut parts = request.lines().next().unwrap_or_default().split_whitespace();
+                let (method, path) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+                let path = path.split('?').next().unwrap_or_default();
+                let (status, content_type, body) = match (method, path) {
+                    ("GET", "/metrics") => ("200 OK", CONTENT_TYPE, render().await),
+                    ("GET", _) => ("404 Not Found", "text/plain", "Not Found\n".to_string()),
+                    _ => ("405 Method Not Allowed", "text/plain", "Method Not Allowed\n".to_string()),
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    content_type,
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    });
+    Ok((address, handle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn families_are_grouped_and_labels_escaped() {
+        let counters = CounterSet::default();
+        counters.inc(labels(&[("tenant", "acme"), ("verdict", "malicious")]), 2.0);
+        counters.inc(labels(&[("tenant", "globex"), ("verdict", "clean")]), 1.0);
+        counters.inc(labels(&[("tenant", "acme"), ("verdict", "malicious")]), 1.0);
+        counters.inc(labels(&[("tenant", "acme")]), -5.0);
+
+        let durations = HistogramSet::new(&[1.0, 5.0]);
+        durations.observe(labels(&[("tenant", "acme"), ("engine", "win10")]), 0.5);
+        durations.observe(labels(&[("tenant", "acme"), ("engine", "win10")]), 3.0);
+        durations.observe(labels(&[("tenant", "acme"), ("engine", "win10")]), 9.0);
+
+        let mut writer = PrometheusWriter::for_core("sandbox");
+        writer.gauge("phantom_queue_length", "Jobs waiting", &[("tenant", "acme")], 3.0);
+        writer.counter_set("phantom_analyses_total", "Completed analyses", &counters);
+        writer.gauge("phantom_queue_length", "Jobs waiting", &[("tenant", "we\"ird\\")], 1.0);
+        writer.histogram_set("phantom_analysis_duration_seconds", "Analysis duration", &durations);
+        writer.counter_set("phantom_empty_total", "Nothing yet", &CounterSet::default());
+        let text = writer.finish();
+
+        let expected = "\
+# HELP phantom_queue_length Jobs waiting
+# TYPE phantom_queue_length gauge
+phantom_queue_length{core=\"sandbox\",tenant=\"acme\"} 3
+phantom_queue_length{core=\"sandbox\",tenant=\"we\\\"ird\\\\\"} 1
+# HELP phantom_analyses_total Completed analyses
+# TYPE phantom_analyses_total counter
+phantom_analyses_total{core=\"sandbox\",tenant=\"acme\",verdict=\"malicious\"} 3
+phantom_analyses_total{core=\"sandbox\",tenant=\"globex\",verdict=\"clean\"} 1
+# HELP phantom_analysis_duration_seconds Analysis duration
+# TYPE phantom_analysis_duration_seconds histogram
+phantom_analysis_duration_seconds_bucket{core=\"sandbox\",tenant=\"acme\",engine=\"win10\",le=\"1\"} 1
+phantom_analysis_duration_seconds_bucket{core=\"sandbox\",tenant=\"acme\",engine=\"win10\",le=\"5\"} 2
+phantom_analysis_duration_seconds_bucket{core=\"sandbox\",tenant=\"acme\",engine=\"win10\",le=\"+Inf\"} 3
+phantom_analysis_duration_seconds_sum{core=\"sandbox\",tenant=\"acme\",engine=\"win10\"} 12.5
+phantom_analysis_duration_seconds_count{core=\"sandbox\",tenant=\"acme\",engine=\"win10\"} 3
+# HELP phantom_empty_total Nothing yet
+# TYPE phantom_empty_total counter
+";
+        assert_eq!(text, expected);
+
+        assert_eq!(counters.forget("tenant", "acme"), 1);
+        assert_eq!(counters.snapshot().len(), 1);
+        assert_eq!(sanitize_name("9lives.total"), "_9lives_total");
+    }
+
+    #[tokio::test]
+    async fn endpoint_serves_metrics() {
+        let render: RenderFn = Arc::new(|| Box::pin(async { "up 1\n".to_string() }));
+        let (address, handle) = serve_metrics("127.0.0.1:0", render).await.unwrap();
+
+        let fetch = |path: &'static str| async move {
+            let mut stream = tokio::net::TcpStream::connect(address).await.unwrap();
+            stream.write_all(format!("GET {} HTTP/1.1\r\nHost: x\r\n\r\n", path).as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+        let response = fetch("/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(response.contains(CONTENT_TYPE));
+        assert!(response.ends_with("\r\n\r\nup 1\n"));
+        assert!(fetch("/other").await.starts_with("HTTP/1.1 404"));
+        handle.abort();
+    }
+}
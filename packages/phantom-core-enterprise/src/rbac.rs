@@ -0,0 +1,578 @@
+//! Role-based access control for the NAPI surfaces
+//!
+//! Embedding applications issue API keys or short-lived session tokens to a
+//! [`Principal`] holding one or more [`Role`]s, then pass the token with each
+//! mutating call. Cores check the token against the [`Permission`] the
+//! operation needs; every refusal is kept in a bounded denial log so it can
+//! be audited. Enforcement is off until explicitly enabled, which lets
+//! callers bootstrap their first admin credential.
+//!
+//! Only SHA-256 digests of tokens are stored; the plaintext is handed out
+//! once, when the credential is issued.
+
+use crate::compliance::AuditEvent;
+use chrono::{DateTime, Duration, Utc};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Number of denial events retained for auditing
+pub const DENIAL_LOG_CAPACITY: usize = 1000;
+
+/// Roles, from least to most privileged
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Viewer,
+    Analyst,
+    Responder,
+    Admin,
+}
+
+impl Role {
+    /// Permissions granted by this role
+    pub fn permissions(&self) -> &'static [Permission] {
+        use Permission::*;
+        match self {
+            Role::Viewer => &[Read],
+            Role::Analyst => &[Read, IncidentWrite, AnalysisSubmit],
+            Role::Responder => &[Read, IncidentWrite, AnalysisSubmit, AnalysisCancel, SampleExport, PlaybookExecute, RuleManage],
+            Role::Admin => &[Read, IncidentWrite, AnalysisSubmit, AnalysisCancel, SampleExport, PlaybookExecute, RuleManage, AuditRead, AccessManage],
+        }
+    }
+
+    pub fn allows(&self, permission: Permission) -> bool {
+        self.permissions().contains(&permission)
+    }
+}
+
+/// Operations that can be guarded by a permission check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Permission {
+    #[serde(rename = "read")]
+    Read,
+    #[serde(rename = "incident:write")]
+    IncidentWrite,
+    #[serde(rename = "analysis:submit")]
+    AnalysisSubmit,
+    #[serde(rename = "analysis:cancel")]
+    AnalysisCancel,
+    #[serde(rename = "sample:export")]
+    SampleExport,
+    #[serde(rename = "playbook:execute")]
+    PlaybookExecute,
+    #[serde(rename = "rule:manage")]
+    RuleManage,
+    #[serde(rename = "audit:read")]
+    AuditRead,
+    #[serde(rename = "access:manage")]
+    AccessManage,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::Read => "read",
+            Permission::IncidentWrite => "incident:write",
+            Permission::AnalysisSubmit => "analysis:submit",
+            Permission::AnalysisCancel => "analysis:cancel",
+            Permission::SampleExport => "sample:export",
+            Permission::PlaybookExecute => "playbook:execute",
+            Permission::RuleManage => "rule:manage",
+            Permission::AuditRead => "audit:read",
+            Permission::AccessManage => "access:manage",
+        }
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Permission {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(Permission::Read),
+            "incident:write" => Ok(Permission::IncidentWrite),
+            "analysis:submit" => Ok(Permission::AnalysisSubmit),
+            "analysis:cancel" => Ok(Permission::AnalysisCancel),
+            "sample:export" => Ok(Permission::SampleExport),
+            "playbook:execute" => Ok(Permission::PlaybookExecute),
+            "rule:manage" => Ok(Permission::RuleManage),
+            "audit:read" => Ok(Permission::AuditRead),
+            "access:manage" => Ok(Permission::AccessManage),
+            other => Err(format!("unknown permission '{}'", other)),
+        }
+    }
+}
+
+/// The identity a credential acts on behalf of
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Principal {
+    pub principal_id: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    pub roles: Vec<Role>,
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+}
+
+impl Principal {
+    pub fn new(principal_id: impl Into<String>, roles: Vec<Role>) -> Self {
+        Self {
+            principal_id: principal_id.into(),
+            display_name: None,
+            roles,
+            tenant_id: None,
+        }
+    }
+
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.roles.iter().any(|role| role.allows(permission))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialKind {
+    ApiKey,
+    Session,
+}
+
+/// Credential metadata; never contains the token itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CredentialInfo {
+    pub credential_id: String,
+    pub kind: CredentialKind,
+    pub principal: Principal,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked: bool,
+}
+
+/// A freshly issued credential; `token` is only ever returned here
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssuedCredential {
+    pub credential_id: String,
+    pub kind: CredentialKind,
+    pub token: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Why a call was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DenialReason {
+    MissingToken,
+    UnknownToken,
+    Expired,
+    Revoked,
+    InsufficientRole,
+}
+
+impl fmt::Display for DenialReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            DenialReason::MissingToken => "no credential supplied",
+            DenialReason::UnknownToken => "unknown credential",
+            DenialReason::Expired => "credential expired",
+            DenialReason::Revoked => "credential revoked",
+            DenialReason::InsufficientRole => "insufficient role",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Audit record for a refused call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessDenial {
+    pub event_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub reason: DenialReason,
+    pub permission: Permission,
+    pub resource: String,
+    pub credential_id: Option<String>,
+    pub principal_id: Option<String>,
+    #[serde(default)]
+    pub tenant_id: Option<String>,
+}
+
+impl AccessDenial {
+    /// Express the denial as a compliance audit event
+    pub fn to_audit_event(&self) -> AuditEvent {
+        let mut details = HashMap::new();
+        details.insert("reason".to_string(), serde_json::json!(self.reason));
+        if let Some(credential_id) = &self.credential_id {
+            details.insert("credential_id".to_string(), serde_json::json!(credential_id));
+        }
+        AuditEvent {
+            event_id: self.event_id.clone(),
+            timestamp: self.timestamp,
+            event_type: "access_denied".to_string(),
+            user_id: self.principal_id.clone().unwrap_or_else(|| "anonymous".to_string()),
+            resource: self.resource.clone(),
+            action: self.permission.to_string(),
+            result: "denied".to_string(),
+            details,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Error)]
+#[error("access denied: {reason} for {permission} on {resource}")]
+pub struct AccessDenied {
+    pub reason: DenialReason,
+    pub permission: Permission,
+    pub resource: String,
+    pub event_id: String,
+}
+
+struct CredentialRecord {
+    info: CredentialInfo,
+    token_hash: String,
+}
+
+/// Credential store and permission checker shared by a core's NAPI wrapper
+pub struct AccessControl {
+    enforced: AtomicBool,
+    credentials: RwLock<HashMap<String, CredentialRecord>>,
+    denials: Mutex<VecDeque<AccessDenial>>,
+}
+
+impl Default for AccessControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AccessControl {
+    /// Create an empty store with enforcement disabled
+    pub fn new() -> Self {
+        Self {
+            enforced: AtomicBool::new(false),
+            credentials: RwLock::new(HashMap::new()),
+            denials: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn set_enforced(&self, enforced: bool) {
+        self.enforced.store(enforced, Ordering::SeqCst);
+    }
+
+    pub fn is_enforced(&self) -> bool {
+        self.enforced.load(Ordering::SeqCst)
+    }
+
+    /// Issue a long-lived API key, optionally with an expiry
+    pub fn issue_api_key(&self, principal: Principal, ttl: Option<Duration>) -> IssuedCredential {
+        self.issue(CredentialKind::ApiKey, principal, ttl)
+    }
+
+    /// Start a session that expires after `ttl`
+    pub fn start_session(&self, principal: Principal, ttl: Duration) -> IssuedCredential {
+        self.issue(CredentialKind::Session, principal, Some(ttl))
+    }
+
+    fn issue(&self, kind: CredentialKind, principal: Principal, ttl: Option<Duration>) -> IssuedCredential {
+        let prefix = match kind {
+            CredentialKind::ApiKey => "psk",
+            CredentialKind::Session => "pss",
+        };
+        let token = format!("{}_{}{}", prefix, Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let now = Utc::now();
+        let info = CredentialInfo {
+            credential_id: Uuid::new_v4().to_string(),
+            kind,
+            principal,
+            created_at: now,
+            expires_at: ttl.map(|ttl| now + ttl),
+            last_used_at: None,
+            revoked: false,
+        };
+        let issued = IssuedCredential {
+            credential_id: info.credential_id.clone(),
+            kind,
+            token: token.clone(),
+            expires_at: info.expires_at,
+        };
+        self.credentials.write().insert(
+            info.credential_id.clone(),
+            CredentialRecord { info, token_hash: hash_token(&token) },
+        );
+        issued
+    }
+
+    /// Revoke a credential; returns false if it does not exist
+    pub fn revoke(&self, credential_id: &str) -> bool {
+        self.revoke_in(None, credential_id)
+    }
+
+    /// Revoke a credential belonging to `tenant_id`, or to any tenant for
+    /// `None`; credentials of other tenants are treated as missing
+    pub fn revoke_in(&self, tenant_id: Option<&str>, credential_id: &str) -> bool {
+        match self.credentials.write().get_mut(credential_id) {
+            Some(record) if in_scope(tenant_id, &record.info.principal) => {
+                record.info.revoked = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Drop expired sessions; returns how many were removed
+    pub fn purge_expired(&self) -> usize {
+        let now = Utc::now();
+        let mut credentials = self.credentials.write();
+        let before = credentials.len();
+        credentials.retain(|_, record| {
+            record.info.kind != CredentialKind::Session || record.info.expires_at.is_none_or(|at| at > now)
+        });
+        before - credentials.len()
+    }
+
+    pub fn list_credentials(&self) -> Vec<CredentialInfo> {
+        self.list_credentials_in(None)
+    }
+
+    /// Credentials of `tenant_id`, or of every tenant for `None`
+    pub fn list_credentials_in(&self, tenant_id: Option<&str>) -> Vec<CredentialInfo> {
+        let mut credentials: Vec<CredentialInfo> = self
+            .credentials
+            .read()
+            .values()
+            .filter(|record| in_scope(tenant_id, &record.info.principal))
+            .map(|record| record.info.clone())
+            .collect();
+        credentials.sort_by_key(|info| info.created_at);
+        credentials
+    }
+
+    /// Resolve a token to its principal without checking any permission
+    pub fn authenticate(&self, token: &str) -> Option<Principal> {
+        let token_hash = hash_token(token);
+        let now = Utc::now();
+        self.credentials
+            .read()
+            .values()
+            .find(|record| record.token_hash == token_hash)
+            .filter(|record| !record.info.revoked && record.info.expires_at.is_none_or(|at| at > now))
+            .map(|record| record.info.principal.clone())
+    }
+
+    /// Check that `token` may perform `permission` on `resource`.
+    ///
+    /// With enforcement disabled every call is allowed; the principal is
+    /// still returned when a valid token was supplied.
+    pub fn authorize(
+        &self,
+        token: Option<&str>,
+        permission: Permission,
+        resource: &str,
+    ) -> Result<Option<Principal>, AccessDenied> {
+        let enforced = self.is_enforced();
+        let Some(token) = token else {
+            return if enforced {
+                Err(self.deny(DenialReason::MissingToken, permission, resource, None))
+            } else {
+                Ok(None)
+            };
+        };
+
+        let token_hash = hash_token(token);
+        let now = Utc::now();
+        let outcome = {
+            let mut credentials = self.credentials.write();
+            match credentials.values_mut().find(|record| record.token_hash == token_hash) {
+                None => Err((DenialReason::UnknownToken, None)),
+                Some(record) => {
+                    let info = &mut record.info;
+                    if info.revoked {
+                        Err((DenialReason::Revoked, Some(info.clone())))
+                    } else if info.expires_at.is_some_and(|at| at <= now) {
+                        Err((DenialReason::Expired, Some(info.clone())))
+                    } else if !info.principal.has_permission(permission) {
+                        Err((DenialReason::InsufficientRole, Some(info.clone())))
+                    } else {
+                        info.last_used_at = Some(now);
+                        Ok(info.principal.clone())
+                    }
+                }
+            }
+        };
+
+        match outcome {
+            Ok(principal) => Ok(Some(principal)),
+            Err(_) if !enforced => Ok(None),
+            Err((reason, info)) => Err(self.deny(reason, permission, resource, info.as_ref())),
+        }
+    }
+
+    /// Most recent denial events, newest first
+    pub fn denials(&self, limit: usize) -> Vec<AccessDenial> {
+        self.denials_in(None, limit)
+    }
+
+    /// Most recent denials of `tenant_id`'s principals, or of everyone for
+    /// `None`; refusals without a known credential are platform-only
+    pub fn denials_in(&self, tenant_id: Option<&str>, limit: usize) -> Vec<AccessDenial> {
+        self.denials
+            .lock()
+            .iter()
+            .rev()
+            .filter(|denial| tenant_id.is_none() || denial.tenant_id.as_deref() == tenant_id)
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    fn deny(
+        &self,
+        reason: DenialReason,
+        permission: Permission,
+        resource: &str,
+        info: Option<&CredentialInfo>,
+    ) -> AccessDenied {
+        let denial = AccessDenial {
+            event_id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            reason,
+            permission,
+            resource: resource.to_string(),
+            credential_id: info.map(|info| info.credential_id.clone()),
+            principal_id: info.map(|info| info.principal.principal_id.clone()),
+            tenant_id: info.and_then(|info| info.principal.tenant_id.clone()),
+        };
+        log::warn!(
+            "access denied: {} for {} on {} (principal {})",
+            reason,
+            permission,
+            resource,
+            denial.principal_id.as_deref().unwrap_or("anonymous")
+        );
+
+        let error = AccessDenied {
+            reason,
+            permission,
+            resource: denial.resource.clone(),
+            event_id: denial.event_id.clone(),
+        };
+        let mut denials = self.denials.lock();
+        if denials.len() >= DENIAL_LOG_CAPACITY {
+            denials.pop_front();
+        }
+        denials.push_back(denial);
+        error
+    }
+}
+
+fn in_scope(tenant_id: Option<&str>, principal: &Principal) -> bool {
+    tenant_id.is_none() || principal.tenant_id.as_deref() == tenant_id
+}
+
+fn hash_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roles_gate_mutating_operations() {
+        let access = AccessControl::new();
+        access.set_enforced(true);
+        let viewer = access.issue_api_key(Principal::new("v", vec![Role::Viewer]), None);
+        let responder = access.issue_api_key(Principal::new("r", vec![Role::Responder]), None);
+
+        assert!(access.authorize(Some(&viewer.token), Permission::Read, "incidents").is_ok());
+        let denied = access
+            .authorize(Some(&viewer.token), Permission::IncidentWrite, "incidents")
+            .unwrap_err();
+        assert_eq!(denied.reason, DenialReason::InsufficientRole);
+
+        let principal = access
+            .authorize(Some(&responder.token), Permission::PlaybookExecute, "pb-1")
+            .unwrap()
+            .unwrap();
+        assert_eq!(principal.principal_id, "r");
+        assert!(access.authorize(Some(&responder.token), Permission::AccessManage, "access").is_err());
+        assert!(access.authorize(None, Permission::Read, "incidents").is_err());
+
+        let denials = access.denials(10);
+        assert_eq!(denials.len(), 3);
+        assert_eq!(denials[0].reason, DenialReason::MissingToken);
+        assert_eq!(denials[2].principal_id.as_deref(), Some("v"));
+        assert_eq!(denials[2].to_audit_event().action, "incident:write");
+    }
+
+    #[test]
+    fn revoked_and_expired_credentials_are_refused() {
+        let access = AccessControl::new();
+        let admin = access.issue_api_key(Principal::new("a", vec![Role::Admin]), None);
+        let session = access.start_session(Principal::new("s", vec![Role::Admin]), Duration::seconds(-1));
+
+        // Not enforced: anything goes, but nothing is logged either
+        assert!(access.authorize(None, Permission::RuleManage, "rules").unwrap().is_none());
+        assert!(access.authorize(Some(&session.token), Permission::RuleManage, "rules").unwrap().is_none());
+        assert!(access.denials(10).is_empty());
+
+        access.set_enforced(true);
+        let expired = access
+            .authorize(Some(&session.token), Permission::RuleManage, "rules")
+            .unwrap_err();
+        assert_eq!(expired.reason, DenialReason::Expired);
+        assert_eq!(access.purge_expired(), 1);
+
+        assert!(access.revoke(&admin.credential_id));
+        let revoked = access
+            .authorize(Some(&admin.token), Permission::RuleManage, "rules")
+            .unwrap_err();
+        assert_eq!(revoked.reason, DenialReason::Revoked);
+        assert!(access.authenticate(&admin.token).is_none());
+        assert_eq!(access.list_credentials().len(), 1);
+    }
+
+    #[test]
+    fn tenant_scopes_hide_other_tenants_credentials() {
+        let access = AccessControl::new();
+        access.set_enforced(true);
+        let mut acme = Principal::new("acme-viewer", vec![Role::Viewer]);
+        acme.tenant_id = Some("acme".to_string());
+        let acme = access.issue_api_key(acme, None);
+        let mut globex = Principal::new("globex-viewer", vec![Role::Viewer]);
+        globex.tenant_id = Some("globex".to_string());
+        let globex = access.issue_api_key(globex, None);
+        access.issue_api_key(Principal::new("platform", vec![Role::Admin]), None);
+
+        let listed = access.list_credentials_in(Some("acme"));
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].credential_id, acme.credential_id);
+        assert_eq!(access.list_credentials_in(None).len(), 3);
+
+        assert!(!access.revoke_in(Some("acme"), &globex.credential_id));
+        assert!(access.authenticate(&globex.token).is_some());
+        assert!(access.revoke_in(Some("globex"), &globex.credential_id));
+
+        assert!(access.authorize(Some(&acme.token), Permission::RuleManage, "rules").is_err());
+        assert!(access.authorize(None, Permission::RuleManage, "rules").is_err());
+        let denials = access.denials_in(Some("acme"), 10);
+        assert_eq!(denials.len(), 1);
+        assert_eq!(denials[0].principal_id.as_deref(), Some("acme-viewer"));
+        assert!(access.denials_in(Some("globex"), 10).is_empty());
+        assert_eq!(access.denials(10).len(), 2);
+    }
+}
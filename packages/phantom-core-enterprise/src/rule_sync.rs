@@ -0,0 +1,376 @@
+//! Detections-as-code repository sync
+//!
+//! Detection content (hunting rules, playbooks) is kept in Git. A
+//! [`RuleRepository`] names the remote, the branch to track and optionally a
+//! commit to pin it to. [`GitClient::checkout`] brings a local working copy
+//! to that revision and returns its commit SHA; [`collect_files`] lists the
+//! YAML and JSON files under a directory of the checkout for the core that
+//! loads them. Cores report what they loaded, replaced and retired, and
+//! every file that failed validation, in a [`SyncReport`].
+//!
+//! [`GitCli`] drives the `git` executable with prompts disabled, so remotes
+//! needing credentials must get them from the URL or a credential helper.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Largest content file loaded; bigger files are reported, not read
+pub const MAX_SOURCE_FILE_BYTES: u64 = 1024 * 1024;
+
+/// Deadline for each git command
+pub const GIT_COMMAND_TIMEOUT: Duration = Duration::from_secs(300);
+
+fn default_branch() -> String {
+    "main".to_string()
+}
+
+/// A Git repository of detection content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleRepository {
+    pub repository_id: String,
+    pub url: String,
+    #[serde(default = "default_branch")]
+    pub branch: String,
+    /// Full commit SHA to load instead of the branch head
+    #[serde(default)]
+    pub pinned_commit: Option<String>,
+    /// Directory of the repository holding the content; the root by default
+    #[serde(default)]
+    pub path: Option<String>,
+    /// Refuse a revision outright when any of its files fails validation
+    #[serde(default)]
+    pub strict: bool,
+    /// Local working copy; a directory under the system temp dir by default
+    #[serde(default)]
+    pub checkout_dir: Option<String>,
+}
+
+impl RuleRepository {
+    pub fn validate(&self) -> Result<(), String> {
+        let id = self.repository_id.trim();
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')) || id.starts_with('.') {
+            return Err("Repository id must be letters, digits, '-', '_' or '.'".to_string());
+        }
+        if self.url.trim().is_empty() || self.url.starts_with('-') {
+            return Err(format!("Repository {} needs a remote URL", id));
+        }
+        if self.branch.is_empty() || self.branch.starts_with('-') || self.branch.contains("..") || self.branch.chars().any(|c| c.is_whitespace() || c == ':') {
+            return Err(format!("Repository {} has an invalid branch name {}", id, self.branch));
+        }
+        if let Some(commit) = &self.pinned_commit {
+            if commit.len() != 40 || !commit.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("Repository {} must pin a full 40-character commit SHA", id));
+            }
+        }
+        if let Some(path) = &self.path {
+            if Path::new(path).is_absolute() || Path::new(path).components().any(|c| matches!(c, std::path::Component::ParentDir)) {
+                return Err(format!("Repository {} content path must stay inside the repository", id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Where the working copy lives; `scope` keeps the default checkouts of
+    /// different cores and tenants apart
+    pub fn working_dir(&self, scope: &str) -> PathBuf {
+        match &self.checkout_dir {
+            Some(dir) => PathBuf::from(dir),
+            None => {
+                let scope: String = scope.chars().map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_') { c } else { '_' }).collect();
+                std::env::temp_dir().join("phantom-rule-sync").join(scope).join(&self.repository_id)
+            }
+        }
+    }
+
+    /// Directory of the checkout holding `subdir` of the content
+    pub fn content_dir(&self, checkout: &Path, subdir: &str) -> PathBuf {
+        let mut dir = checkout.to_path_buf();
+        if let Some(path) = &self.path {
+            dir.push(path);
+        }
+        if !subdir.is_empty() {
+            dir.push(subdir);
+        }
+        dir
+    }
+}
+
+/// Brings a working copy to the revision a repository asks for
+#[async_trait]
+pub trait GitClient: Send + Sync {
+    /// Check out the pinned commit, or the head of the branch, in `dir` and
+    /// return the commit SHA now checked out
+    async fn checkout(&self, repository: &RuleRepository, dir: &Path) -> Result<String, String>;
+}
+
+/// [`GitClient`] running the `git` executable
+#[derive(Debug, Clone)]
+pub struct GitCli {
+    pub binary: String,
+}
+
+impl Default for GitCli {
+    fn default() -> Self {
+        Self { binary: "git".to_string() }
+    }
+}
+
+impl GitCli {
+    async fn git(&self, dir: &Path, args: &[&str]) -> Result<String, String> {
+        let mut command = tokio::process::Command::new(&self.binary);
+        command.arg("-C").arg(dir).args(args).env("GIT_TERMINAL_PROMPT", "0").kill_on_drop(true);
+        let output = tokio::time::timeout(GIT_COMMAND_TIMEOUT, command.output())
+            .await
+            .map_err(|_| format!("git {} timed out", args.first().unwrap_or(&"")))?
+            .map_err(|e| format!("Failed to run {}: {}", self.binary, e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "git {} failed: {}",
+                args.first().unwrap_or(&""),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+#[async_trait]
+impl GitClient for GitCli {
+    async fn checkout(&self, repository: &RuleRepository, dir: &Path) -> Result<String, String> {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        if dir.join(".git").exists() {
+            self.git(dir, &["remote", "set-url", "origin", &repository.url]).await?;
+        } else {
+            self.git(dir, &["init", "--quiet"]).await?;
+            self.git(dir, &["remote", "add", "origin", &repository.url]).await?;
+        }
+
+        let branch = format!("refs/heads/{}", repository.branch);
+        let target = match &repository.pinned_commit {
+            // Servers that refuse fetching a commit by id get the branch history instead
+            Some(commit) => {
+                if self.git(dir, &["fetch", "--quiet", "--no-tags", "--depth", "1", "origin", commit]).await.is_err() {
+                    self.git(dir, &["fetch", "--quiet", "--no-tags", "origin", &branch]).await?;
+                }
+                commit.clone()
+            }
+            None => {
+                self.git(dir, &["fetch", "--quiet", "--no-tags", "--depth", "1", "origin", &branch]).await?;
+                "FETCH_HEAD".to_string()
+            }
+        };
+        self.git(dir, &["checkout", "--quiet", "--force", "--detach", &target]).await?;
+        self.git(dir, &["clean", "--quiet", "-ffdx"]).await?;
+        self.git(dir, &["rev-parse", "HEAD"]).await
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceFormat {
+    Yaml,
+    Json,
+}
+
+/// A content file of the checkout
+#[derive(Debug, Clone)]
+pub struct SourceFile {
+    /// Path relative to the content directory, with `/` separators
+    pub path: String,
+    pub format: SourceFormat,
+    pub content: String,
+}
+
+/// A file, or one document of it, that could not be loaded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileError {
+    pub path: String,
+    /// Zero-based document of a multi-document YAML file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document: Option<usize>,
+    pub error: String,
+}
+
+impl FileError {
+    pub fn new(path: &str, document: Option<usize>, error: impl Into<String>) -> Self {
+        Self { path: path.to_string(), document, error: error.into() }
+    }
+}
+
+/// YAML and JSON files under `dir`, sorted by path. Hidden entries are
+/// skipped; unreadable or oversized files are returned as errors. A missing
+/// directory has no files.
+pub fn collect_files(dir: &Path) -> (Vec<SourceFile>, Vec<FileError>) {
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&current) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let Ok(file_type) = entry.file_type() else { continue };
+            if file_type.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let format = match path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase).as_deref() {
+                Some("yml" | "yaml") => SourceFormat::Yaml,
+                Some("json") => SourceFormat::Json,
+                _ => continue,
+            };
+            let relative = path
+                .strip_prefix(dir)
+                .unwrap_or(&path)
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            if entry.metadata().is_ok_and(|m| m.len() > MAX_SOURCE_FILE_BYTES) {
+                errors.push(FileError::new(&relative, None, format!("File is larger than {} bytes", MAX_SOURCE_FILE_BYTES)));
+                continue;
+            }
+            match std::fs::read_to_string(&path) {
+                Ok(content) => files.push(SourceFile { path: relative, format, content }),
+                Err(e) => errors.push(FileError::new(&relative, None, format!("Failed to read file: {}", e))),
+            }
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    errors.sort_by(|a, b| a.path.cmp(&b.path));
+    (files, errors)
+}
+
+/// An item loaded from the repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadedItem {
+    pub path: String,
+    pub id: String,
+    /// Whether it replaced an item already in the engine
+    pub replaced: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncOutcome {
+    /// Every file loaded
+    Applied,
+    /// Valid files loaded, the others reported
+    PartiallyApplied,
+    /// A strict repository had invalid files; nothing changed
+    Rejected,
+    /// The revision could not be checked out
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncReport {
+    pub repository_id: String,
+    pub branch: String,
+    pub commit_sha: Option<String>,
+    /// Commit active in the engine before the sync
+    pub previous_commit: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub outcome: SyncOutcome,
+    pub loaded: Vec<LoadedItem>,
+    /// Ids loaded by an earlier sync that the revision no longer contains
+    pub removed: Vec<String>,
+    pub errors: Vec<FileError>,
+    pub error: Option<String>,
+}
+
+impl SyncReport {
+    pub fn new(repository: &RuleRepository, previous_commit: Option<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            repository_id: repository.repository_id.clone(),
+            branch: repository.branch.clone(),
+            commit_sha: None,
+            previous_commit,
+            started_at: now,
+            finished_at: now,
+            outcome: SyncOutcome::Failed,
+            loaded: Vec::new(),
+            removed: Vec::new(),
+            errors: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Whether the revision may be applied given the errors found so far
+    pub fn may_apply(&self, repository: &RuleRepository) -> bool {
+        !repository.strict || self.errors.is_empty()
+    }
+
+    /// Set the outcome from the errors and stamp the finish time
+    pub fn finish(mut self, applied: bool) -> Self {
+        self.outcome = match (applied, self.errors.is_empty()) {
+            (false, _) if self.commit_sha.is_none() => SyncOutcome::Failed,
+            (false, _) => SyncOutcome::Rejected,
+            (true, true) => SyncOutcome::Applied,
+            (true, false) => SyncOutcome::PartiallyApplied,
+        };
+        self.finished_at = Utc::now();
+        self
+    }
+}
+
+/// A registered repository and the revision active in the engine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepositoryStatus {
+    pub repository: RuleRepository,
+    pub tenant_id: String,
+    /// Commit whose content the engine is running
+    pub active_commit: Option<String>,
+    pub synced_at: Option<DateTime<Utc>>,
+    /// Ids of the items loaded from the active commit
+    pub managed_ids: Vec<String>,
+    pub last_sync: Option<SyncReport>,
+}
+
+impl RepositoryStatus {
+    pub fn new(repository: RuleRepository, tenant_id: &str) -> Self {
+        Self { repository, tenant_id: tenant_id.to_string(), active_commit: None, synced_at: None, managed_ids: Vec::new(), last_sync: None }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collects_content_files_and_validates_repositories() {
+        let root = std::env::temp_dir().join(format!("rule-sync-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(root.join("rules/windows")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join("rules/windows/psexec.yml"), "title: x").unwrap();
+        std::fs::write(root.join("rules/beacon.JSON"), "{}").unwrap();
+        std::fs::write(root.join("rules/README.md"), "# rules").unwrap();
+        std::fs::write(root.join("rules/.draft.yml"), "title: y").unwrap();
+        std::fs::write(root.join(".git/config.yml"), "").unwrap();
+
+        let (files, errors) = collect_files(&root.join("rules"));
+        assert!(errors.is_empty());
+        let listed: Vec<(&str, SourceFormat)> = files.iter().map(|f| (f.path.as_str(), f.format)).collect();
+        assert_eq!(listed, [("beacon.JSON", SourceFormat::Json), ("windows/psexec.yml", SourceFormat::Yaml)]);
+        assert!(collect_files(&root.join("missing")).0.is_empty());
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let mut repository: RuleRepository =
+            serde_json::from_value(serde_json::json!({ "repository_id": "detections", "url": "https://git.example/d.git", "path": "content" })).unwrap();
+        assert!(repository.validate().is_ok());
+        assert_eq!(repository.content_dir(Path::new("/co"), "rules"), Path::new("/co/content/rules"));
+        assert!(repository.working_dir("hunting/../acme").ends_with("hunting____acme/detections"));
+        repository.pinned_commit = Some("abc123".to_string());
+        assert!(repository.validate().is_err());
+        repository.pinned_commit = None;
+        repository.path = Some("../etc".to_string());
+        assert!(repository.validate().is_err());
+    }
+}
@@ -0,0 +1,537 @@
+//! Connector secrets
+//!
+//! Credentials for connectors are kept in a `SecretStore`, sealed with
+//! AES-256-GCM under a master key. The key comes from a `KeyProvider`: an
+//! environment variable (64 hex digits) named by the core, a callback into a
+//! KMS, or a key the host hands over. It is loaded on first use and never
+//! leaves the store. The same providers supply the sandbox's sample
+//! key-encryption key.
+//!
+//! Configs refer to secrets as `secret://name`, and each core resolves the
+//! reference only when it connects, so the plaintext is never kept in a
+//! config. `resolve_under` confines a tenant's references to the secrets
+//! stored for that tenant. `redact` blanks credential values in anything
+//! serialized for audit, health or status output; references are kept, since
+//! they reveal nothing.
+//!
+//! Secrets are rotated one at a time with `rotate`, and the master key with
+//! `rotate_master_key`, which re-seals every secret under the new key. The
+//! sealed records can be exported and imported to persist them.
+//!
+//! Shared by the sandbox, hunting and secop cores, which map [`SecretError`]
+//! onto their own error categories.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use thiserror::Error;
+
+/// Prefix of a secret reference in a connector config
+pub const SECRET_SCHEME: &str = "secret://";
+const REDACTED: &str = "[redacted]";
+/// Config keys whose values are secrets wherever they appear
+const SENSITIVE_KEYS: [&str; 8] = ["password", "passwd", "secret", "token", "api_key", "apikey", "private_key", "client_secret"];
+
+/// Secret store errors
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum SecretError {
+    /// A secret name, key or sealed record is malformed
+    #[error("{0}")]
+    Invalid(String),
+
+    /// No secret of that name is stored
+    #[error("{0}")]
+    NotFound(String),
+
+    /// The key cannot be loaded, or a secret cannot be sealed or opened with it
+    #[error("{0}")]
+    Unavailable(String),
+}
+
+pub type SecretResult<T> = Result<T, SecretError>;
+
+/// Source of a 32-byte key: a secret store's master key or the sandbox's
+/// sample key-encryption key
+pub trait KeyProvider: Send + Sync {
+    /// Shown in status output
+    fn name(&self) -> &str;
+    fn key(&self) -> Result<Vec<u8>, String>;
+}
+
+/// Key from an environment variable, as hex digits
+pub struct EnvKey {
+    var: String,
+}
+
+impl EnvKey {
+    pub fn new(var: impl Into<String>) -> Self {
+        Self { var: var.into() }
+    }
+}
+
+impl KeyProvider for EnvKey {
+    fn name(&self) -> &str {
+        &self.var
+    }
+
+    fn key(&self) -> Result<Vec<u8>, String> {
+        let text = std::env::var(&self.var).map_err(|_| format!("{} is not set", self.var))?;
+        from_hex(text.trim()).ok_or_else(|| format!("{} is not hex", self.var))
+    }
+}
+
+/// Key fetched by a callback, e.g. a KMS decrypt of a wrapped data key
+pub struct KmsKey<F> {
+    name: String,
+    fetch: F,
+}
+
+impl<F> KmsKey<F>
+where
+    F: Fn() -> Result<Vec<u8>, String> + Send + Sync,
+{
+    pub fn new(name: impl Into<String>, fetch: F) -> Self {
+        Self { name: name.into(), fetch }
+    }
+}
+
+impl<F> KeyProvider for KmsKey<F>
+where
+    F: Fn() -> Result<Vec<u8>, String> + Send + Sync,
+{
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn key(&self) -> Result<Vec<u8>, String> {
+        (self.fetch)()
+    }
+}
+
+/// A key handed over directly, e.g. by the host after its own KMS call
+pub struct StaticKey(Vec<u8>);
+
+impl StaticKey {
+    pub fn new(key: Vec<u8>) -> Self {
+        Self(key)
+    }
+}
+
+impl KeyProvider for StaticKey {
+    fn name(&self) -> &str {
+        "static"
+    }
+
+    fn key(&self) -> Result<Vec<u8>, String> {
+        Ok(self.0.clone())
+    }
+}
+
+/// A secret as stored: ciphertext and nonce in hex, the name bound as associated data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SealedSecret {
+    pub name: String,
+    pub version: u32,
+    /// Master key generation the secret is sealed under
+    pub key_version: u32,
+    pub nonce: String,
+    pub ciphertext: String,
+    pub created_at: DateTime<Utc>,
+    pub rotated_at: DateTime<Utc>,
+}
+
+/// What listing a secret reveals
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretMetadata {
+    pub name: String,
+    pub reference: String,
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub rotated_at: DateTime<Utc>,
+}
+
+impl From<&SealedSecret> for SecretMetadata {
+    fn from(sealed: &SealedSecret) -> Self {
+        Self {
+            name: sealed.name.clone(),
+            reference: format!("{}{}", SECRET_SCHEME, sealed.name),
+            version: sealed.version,
+            created_at: sealed.created_at,
+            rotated_at: sealed.rotated_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretStoreStatus {
+    pub key_provider: String,
+    pub key_available: bool,
+    pub key_version: u32,
+    pub secrets: usize,
+    pub last_error: Option<String>,
+}
+
+struct Cipher {
+    cipher: Aes256Gcm,
+    version: u32,
+}
+
+pub struct SecretStore {
+    provider: RwLock<Arc<dyn KeyProvider>>,
+    /// Cipher for the current master key, built on first use
+    cipher: RwLock<Option<Arc<Cipher>>>,
+    key_version: RwLock<u32>,
+    secrets: RwLock<HashMap<String, SealedSecret>>,
+    last_error: RwLock<Option<String>>,
+}
+
+fn build_cipher(key: &[u8]) -> SecretResult<Aes256Gcm> {
+    Aes256Gcm::new_from_slice(key).map_err(|_| SecretError::Invalid(format!("Master key must be 32 bytes, got {}", key.len())))
+}
+
+fn validate_name(name: &str) -> SecretResult<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 200
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '/'));
+    if valid {
+        Ok(())
+    } else {
+        Err(SecretError::Invalid(format!("Invalid secret name {:?}; use letters, digits, '.', '_', '-' and '/'", name)))
+    }
+}
+
+/// Name a `secret://` reference points at
+pub fn reference_name(value: &str) -> Option<&str> {
+    value.strip_prefix(SECRET_SCHEME)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok()).collect()
+}
+
+impl SecretStore {
+    pub fn new(provider: Arc<dyn KeyProvider>) -> Self {
+        Self {
+            provider: RwLock::new(provider),
+            cipher: RwLock::new(None),
+            key_version: RwLock::new(1),
+            secrets: RwLock::new(HashMap::new()),
+            last_error: RwLock::new(None),
+        }
+    }
+
+    fn cipher(&self) -> SecretResult<Arc<Cipher>> {
+        if let Some(cipher) = self.cipher.read().unwrap_or_else(|e| e.into_inner()).clone() {
+            return Ok(cipher);
+        }
+        let provider = self.provider.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let loaded = provider
+            .key()
+            .map_err(|e| SecretError::Unavailable(format!("Master key unavailable from {}: {}", provider.name(), e)))
+            .and_then(|key| build_cipher(&key));
+        let cipher = match loaded {
+            Ok(cipher) => cipher,
+            Err(e) => {
+                *self.last_error.write().unwrap_or_else(|e| e.into_inner()) = Some(e.to_string());
+                return Err(e);
+            }
+        };
+        let cipher = Arc::new(Cipher { cipher, version: *self.key_version.read().unwrap_or_else(|e| e.into_inner()) });
+        *self.cipher.write().unwrap_or_else(|e| e.into_inner()) = Some(cipher.clone());
+        *self.last_error.write().unwrap_or_else(|e| e.into_inner()) = None;
+        Ok(cipher)
+    }
+
+    /// Whether secrets can be sealed and opened
+    pub fn is_available(&self) -> bool {
+        self.cipher().is_ok()
+    }
+
+    fn seal(cipher: &Cipher, name: &str, value: &str) -> SecretResult<(String, String)> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .cipher
+            .encrypt(&nonce, Payload { msg: value.as_bytes(), aad: name.as_bytes() })
+            .map_err(|_| SecretError::Unavailable(format!("Failed to seal secret {}", name)))?;
+        Ok((to_hex(&nonce), to_hex(&ciphertext)))
+    }
+
+    fn open(cipher: &Cipher, sealed: &SealedSecret) -> SecretResult<String> {
+        let corrupt = || SecretError::Unavailable(format!("Secret {} cannot be opened with the current master key", sealed.name));
+        if sealed.key_version != cipher.version {
+            return Err(corrupt());
+        }
+        let nonce = from_hex(&sealed.nonce).filter(|nonce| nonce.len() == 12).ok_or_else(corrupt)?;
+        let ciphertext = from_hex(&sealed.ciphertext).ok_or_else(corrupt)?;
+        let plain = cipher
+            .cipher
+            .decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: sealed.name.as_bytes() })
+            .map_err(|_| corrupt())?;
+        String::from_utf8(plain).map_err(|_| corrupt())
+    }
+
+    /// Store a secret, replacing any previous value
+    pub fn put(&self, name: &str, value: &str) -> SecretResult<SecretMetadata> {
+        validate_name(name)?;
+        let cipher = self.cipher()?;
+        let (nonce, ciphertext) = Self::seal(&cipher, name, value)?;
+        let now = Utc::now();
+        let mut secrets = self.secrets.write().unwrap_or_else(|e| e.into_inner());
+        let previous = secrets.get(name);
+        let sealed = SealedSecret {
+            name: name.to_string(),
+            version: previous.map_or(1, |p| p.version + 1),
+            key_version: cipher.version,
+            nonce,
+            ciphertext,
+            created_at: previous.map_or(now, |p| p.created_at),
+            rotated_at: now,
+        };
+        let metadata = SecretMetadata::from(&sealed);
+        secrets.insert(name.to_string(), sealed);
+        Ok(metadata)
+    }
+
+    /// Replace the value of an existing secret
+    pub fn rotate(&self, name: &str, value: &str) -> SecretResult<SecretMetadata> {
+        if !self.secrets.read().unwrap_or_else(|e| e.into_inner()).contains_key(name) {
+            return Err(SecretError::NotFound(format!("Secret {} not found", name)));
+        }
+        self.put(name, value)
+    }
+
+    pub fn remove(&self, name: &str) -> SecretResult<()> {
+        self.secrets
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| SecretError::NotFound(format!("Secret {} not found", name)))
+    }
+
+    pub fn get(&self, name: &str) -> SecretResult<String> {
+        let sealed = self
+            .secrets
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(name)
+            .cloned()
+            .ok_or_else(|| SecretError::NotFound(format!("Secret {} not found", name)))?;
+        Self::open(&*self.cipher()?, &sealed)
+    }
+
+    pub fn list(&self) -> Vec<SecretMetadata> {
+        let mut listed: Vec<SecretMetadata> = self.secrets.read().unwrap_or_else(|e| e.into_inner()).values().map(SecretMetadata::from).collect();
+        listed.sort_by(|a, b| a.name.cmp(&b.name));
+        listed
+    }
+
+    /// The value a config entry stands for: the secret for a reference, the entry otherwise
+    pub fn resolve(&self, value: &str) -> SecretResult<String> {
+        self.resolve_under("", value)
+    }
+
+    /// Like `resolve`, with the reference looked up as `<prefix><name>`, so a
+    /// tenant's configs only reach the secrets stored under its prefix
+    pub fn resolve_under(&self, prefix: &str, value: &str) -> SecretResult<String> {
+        let Some(name) = reference_name(value) else {
+            return Ok(value.to_string());
+        };
+        let failed = |e: SecretError| {
+            let message = format!("Failed to resolve {}: {}", value, e);
+            match e {
+                SecretError::Invalid(_) => SecretError::Invalid(message),
+                SecretError::NotFound(_) => SecretError::NotFound(message),
+                SecretError::Unavailable(_) => SecretError::Unavailable(message),
+            }
+        };
+        // `..` would be a valid name, but must not climb out of the prefix
+        if !prefix.is_empty() && name.split('/').any(|part| part.is_empty() || part == "..") {
+            return Err(failed(SecretError::Invalid(format!("Invalid secret name {:?}", name))));
+        }
+        self.get(&format!("{}{}", prefix, name)).map_err(failed)
+    }
+
+    /// Resolve every reference among the string values of a JSON config
+    pub fn resolve_json(&self, value: &mut Value) -> SecretResult<()> {
+        match value {
+            Value::String(text) if reference_name(text).is_some() => *text = self.resolve(text)?,
+            Value::Array(items) => items.iter_mut().try_for_each(|item| self.resolve_json(item))?,
+            Value::Object(map) => map.values_mut().try_for_each(|item| self.resolve_json(item))?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Re-seal every secret under the key from `provider` and use it from now on
+    pub fn rotate_master_key(&self, provider: Arc<dyn KeyProvider>) -> SecretResult<SecretStoreStatus> {
+        let old = self.cipher();
+        let key = provider
+            .key()
+            .map_err(|e| SecretError::Unavailable(format!("Master key unavailable from {}: {}", provider.name(), e)))?;
+        let version = *self.key_version.read().unwrap_or_else(|e| e.into_inner()) + 1;
+        let new = Cipher { cipher: build_cipher(&key)?, version };
+
+        let mut secrets = self.secrets.write().unwrap_or_else(|e| e.into_inner());
+        let mut resealed = HashMap::with_capacity(secrets.len());
+        if !secrets.is_empty() {
+            let old = old?;
+            for (name, sealed) in secrets.iter() {
+                let (nonce, ciphertext) = Self::seal(&new, name, &Self::open(&old, sealed)?)?;
+                resealed.insert(name.clone(), SealedSecret { key_version: version, nonce, ciphertext, ..sealed.clone() });
+            }
+        }
+        *secrets = resealed;
+        *self.provider.write().unwrap_or_else(|e| e.into_inner()) = provider;
+        *self.key_version.write().unwrap_or_else(|e| e.into_inner()) = version;
+        *self.cipher.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(new));
+        *self.last_error.write().unwrap_or_else(|e| e.into_inner()) = None;
+        drop(secrets);
+        Ok(self.status())
+    }
+
+    /// Sealed records, safe to persist as they are
+    pub fn export_sealed(&self) -> Vec<SealedSecret> {
+        let mut sealed: Vec<SealedSecret> = self.secrets.read().unwrap_or_else(|e| e.into_inner()).values().cloned().collect();
+        sealed.sort_by(|a, b| a.name.cmp(&b.name));
+        sealed
+    }
+
+    /// Load sealed records exported earlier; each must open under the current key
+    pub fn import_sealed(&self, records: Vec<SealedSecret>) -> SecretResult<usize> {
+        let cipher = self.cipher()?;
+        for record in &records {
+            validate_name(&record.name)?;
+            let sealed = SealedSecret { key_version: cipher.version, ..record.clone() };
+            Self::open(&cipher, &sealed)?;
+        }
+        let mut secrets = self.secrets.write().unwrap_or_else(|e| e.into_inner());
+        for record in &records {
+            secrets.insert(record.name.clone(), SealedSecret { key_version: cipher.version, ..record.clone() });
+        }
+        Ok(records.len())
+    }
+
+    pub fn status(&self) -> SecretStoreStatus {
+        let key_available = self.is_available();
+        SecretStoreStatus {
+            key_provider: self.provider.read().unwrap_or_else(|e| e.into_inner()).name().to_string(),
+            key_available,
+            key_version: *self.key_version.read().unwrap_or_else(|e| e.into_inner()),
+            secrets: self.secrets.read().unwrap_or_else(|e| e.into_inner()).len(),
+            last_error: self.last_error.read().unwrap_or_else(|e| e.into_inner()).clone(),
+        }
+    }
+}
+
+/// Blank secret values in serialized output: every value of a `credentials`
+/// map and every value under a sensitive key, except `secret://` references
+pub fn redact(value: &mut Value) {
+    fn blank(value: &mut Value) {
+        match value {
+            Value::String(text) if reference_name(text).is_some() || text.is_empty() => {}
+            Value::Null => {}
+            Value::Object(map) => map.values_mut().for_each(blank),
+            other => *other = json!(REDACTED),
+        }
+    }
+    match value {
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                let key = key.to_ascii_lowercase();
+                if key == "credentials" || SENSITIVE_KEYS.iter().any(|sensitive| key == *sensitive || key.ends_with(&format!("_{}", sensitive))) {
+                    blank(item);
+                } else {
+                    redact(item);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// `value` serialized with its secrets redacted
+pub fn redacted<T: Serialize>(value: &T) -> Value {
+    let mut value = serde_json::to_value(value).unwrap_or_default();
+    redact(&mut value);
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> Arc<dyn KeyProvider> {
+        Arc::new(StaticKey::new(vec![byte; 32]))
+    }
+
+    #[test]
+    fn secrets_rotate_reseal_and_round_trip() {
+        let store = SecretStore::new(key(7));
+        store.put("es/password", "hunter2").unwrap();
+        assert!(!serde_json::to_string(&store.export_sealed()).unwrap().contains("hunter2"));
+        assert_eq!(store.resolve("secret://es/password").unwrap(), "hunter2");
+        assert_eq!(store.resolve("plain").unwrap(), "plain");
+
+        store.rotate("es/password", "hunter3").unwrap();
+        store.rotate_master_key(key(9)).unwrap();
+        assert_eq!(store.get("es/password").unwrap(), "hunter3");
+        assert_eq!(store.list()[0].version, 2);
+        assert!(matches!(store.rotate("missing", "x"), Err(SecretError::NotFound(_))));
+
+        let exported = store.export_sealed();
+        assert!(SecretStore::new(key(7)).import_sealed(exported.clone()).is_err());
+        assert_eq!(SecretStore::new(key(9)).import_sealed(exported).unwrap(), 1);
+
+        let mut missing = json!({ "api_key": "secret://es-key" });
+        assert!(matches!(store.resolve_json(&mut missing), Err(SecretError::NotFound(_))));
+    }
+
+    #[test]
+    fn key_providers_report_missing_keys() {
+        let var = format!("PHANTOM_TEST_KEY_{}", uuid::Uuid::new_v4().simple());
+        assert!(EnvKey::new(var.clone()).key().unwrap_err().contains("is not set"));
+        let store = SecretStore::new(Arc::new(EnvKey::new(var.clone())));
+        assert!(!store.is_available());
+        assert_eq!(store.status().key_provider, var);
+        assert!(store.status().last_error.unwrap().contains("is not set"));
+
+        let short = SecretStore::new(Arc::new(KmsKey::new("kms:test", || Ok(vec![1u8; 8]))));
+        assert!(matches!(short.put("a", "b"), Err(SecretError::Invalid(_))));
+    }
+
+    #[test]
+    fn prefixed_references_stay_under_their_prefix() {
+        let store = SecretStore::new(key(7));
+        store.put("tenants/acme/jira", "acme-token").unwrap();
+        store.put("tenants/globex/jira", "globex-token").unwrap();
+        store.put("platform/jira", "platform-token").unwrap();
+
+        assert_eq!(store.resolve_under("tenants/acme/", "secret://jira").unwrap(), "acme-token");
+        assert!(store.resolve_under("tenants/acme/", "secret://../globex/jira").is_err());
+        assert!(store.resolve_under("tenants/acme/", "secret://platform/jira").is_err());
+        assert_eq!(store.resolve_under("tenants/acme/", "plain-token").unwrap(), "plain-token");
+    }
+
+    #[test]
+    fn redaction_keeps_references() {
+        let mut config = json!({ "base_url": "https://es", "password": "plain", "api_key": "secret://es-key", "providers": [{ "auth_token": "abc" }] });
+        redact(&mut config);
+        assert_eq!(config["password"], REDACTED);
+        assert_eq!(config["api_key"], "secret://es-key");
+        assert_eq!(config["providers"][0]["auth_token"], REDACTED);
+        assert_eq!(config["base_url"], "https://es");
+    }
+}
@@ -0,0 +1,371 @@
+//! Unified platform health model (phantom-status)
+//!
+//! Every core used to report health in its own JSON shape. Cores now describe
+//! themselves as a [`ComponentHealth`] with the dependencies they rely on, and
+//! register a [`HealthReporter`] with a [`StatusRegistry`]. The registry runs
+//! the checks, times them and rolls the results up into a [`PlatformHealth`]
+//! with an overall status and machine-readable degradation reasons.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Health of a component or dependency, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    Healthy,
+    /// No check could establish the state
+    Unknown,
+    Degraded,
+    Unhealthy,
+}
+
+impl HealthState {
+    /// The worse of two states
+    pub fn worst(self, other: HealthState) -> HealthState {
+        self.max(other)
+    }
+}
+
+/// Something a component relies on: a store, a worker pool, an upstream API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyHealth {
+    pub name: String,
+    pub status: HealthState,
+    /// A failing optional dependency degrades its component but never takes it down
+    #[serde(default = "default_required")]
+    pub required: bool,
+    #[serde(default)]
+    pub latency_ms: Option<u64>,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    /// Stable snake_case code explaining a non-healthy status
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+impl DependencyHealth {
+    pub fn healthy(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            status: HealthState::Healthy,
+            required: true,
+            latency_ms: None,
+            last_error: None,
+            reason: None,
+        }
+    }
+
+    pub fn degraded(name: &str, reason: &str, error: impl Into<String>) -> Self {
+        Self {
+            status: HealthState::Degraded,
+            reason: Some(reason.to_string()),
+            last_error: Some(error.into()),
+            ..Self::healthy(name)
+        }
+    }
+
+    pub fn unhealthy(name: &str, reason: &str, error: impl Into<String>) -> Self {
+        Self {
+            status: HealthState::Unhealthy,
+            reason: Some(reason.to_string()),
+            last_error: Some(error.into()),
+            ..Self::healthy(name)
+        }
+    }
+
+    pub fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency_ms = Some(latency.as_millis() as u64);
+        self
+    }
+
+    /// Status this dependency imposes on its component
+    pub fn impact(&self) -> HealthState {
+        if self.required || self.status == HealthState::Healthy {
+            self.status
+        } else {
+            HealthState::Degraded
+        }
+    }
+}
+
+/// Health of one core as reported to the platform
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentHealth {
+    pub component: String,
+    /// Status of the component itself, before its dependencies are considered
+    pub status: HealthState,
+    #[serde(default)]
+    pub version: Option<String>,
+    /// How long the health check took
+    #[serde(default)]
+    pub latency_ms: Option<u64>,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    pub checked_at: DateTime<Utc>,
+    #[serde(default)]
+    pub dependencies: Vec<DependencyHealth>,
+    /// Core-specific figures (queue length, rule counts…) kept out of the rollup
+    #[serde(default)]
+    pub details: HashMap<String, serde_json::Value>,
+}
+
+impl ComponentHealth {
+    pub fn new(component: &str) -> Self {
+        Self {
+            component: component.to_string(),
+            status: HealthState::Healthy,
+            version: None,
+            latency_ms: None,
+            last_error: None,
+            checked_at: Utc::now(),
+            dependencies: Vec::new(),
+            details: HashMap::new(),
+        }
+    }
+
+    pub fn with_version(mut self, version: &str) -> Self {
+        self.version = Some(version.to_string());
+        self
+    }
+
+    pub fn dependency(&mut self, dependency: DependencyHealth) {
+        self.dependencies.push(dependency);
+    }
+
+    pub fn detail(&mut self, key: &str, value: serde_json::Value) {
+        self.details.insert(key.to_string(), value);
+    }
+
+    /// Own status combined with the impact of every dependency
+    pub fn effective_status(&self) -> HealthState {
+        self.dependencies
+            .iter()
+            .fold(self.status, |status, dependency| status.worst(dependency.impact()))
+    }
+
+    fn reasons(&self) -> Vec<DegradationReason> {
+        let mut reasons = Vec::new();
+        if self.status != HealthState::Healthy {
+            reasons.push(DegradationReason {
+                component: self.component.clone(),
+                dependency: None,
+                status: self.status,
+                code: match self.status {
+                    HealthState::Unknown => "status_unknown",
+                    HealthState::Degraded => "component_degraded",
+                    _ => "component_unhealthy",
+                }
+                .to_string(),
+                message: self
+                    .last_error
+                    .clone()
+                    .unwrap_or_else(|| format!("{} reported {:?}", self.component, self.status)),
+            });
+        }
+        for dependency in self.dependencies.iter().filter(|d| d.status != HealthState::Healthy) {
+            reasons.push(DegradationReason {
+                component: self.component.clone(),
+                dependency: Some(dependency.name.clone()),
+                status: dependency.impact(),
+                code: dependency.reason.clone().unwrap_or_else(|| {
+                    match dependency.status {
+                        HealthState::Unknown => "dependency_unknown",
+                        HealthState::Degraded => "dependency_degraded",
+                        _ => "dependency_unhealthy",
+                    }
+                    .to_string()
+                }),
+                message: dependency
+                    .last_error
+                    .clone()
+                    .unwrap_or_else(|| format!("{} is {:?}", dependency.name, dependency.status)),
+            });
+        }
+        reasons
+    }
+}
+
+/// Why the platform is not fully healthy, one entry per failing component or dependency
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DegradationReason {
+    pub component: String,
+    pub dependency: Option<String>,
+    /// Status this reason contributes to the rollup
+    pub status: HealthState,
+    pub code: String,
+    pub message: String,
+}
+
+/// Rolled-up health of every registered component
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlatformHealth {
+    pub status: HealthState,
+    pub checked_at: DateTime<Utc>,
+    pub components: Vec<ComponentHealth>,
+    /// Worst reasons first
+    pub reasons: Vec<DegradationReason>,
+}
+
+/// Roll component reports up into a platform status
+///
+/// The platform takes the worst effective status of its components; a
+/// component whose state is unknown degrades the platform rather than
+/// failing it, and an empty platform is unknown.
+pub fn rollup_health(mut components: Vec<ComponentHealth>) -> PlatformHealth {
+    components.sort_by(|a, b| a.component.cmp(&b.component));
+
+    let status = if components.is_empty() {
+        HealthState::Unknown
+    } else {
+        components
+            .iter()
+            .map(|c| match c.effective_status() {
+                HealthState::Unknown => HealthState::Degraded,
+                state => state,
+            })
+            .fold(HealthState::Healthy, HealthState::worst)
+    };
+
+    let mut reasons: Vec<DegradationReason> = components.iter().flat_map(ComponentHealth::reasons).collect();
+    reasons.sort_by(|a, b| b.status.cmp(&a.status).then_with(|| a.component.cmp(&b.component)));
+
+    PlatformHealth {
+        status,
+        checked_at: Utc::now(),
+        components,
+        reasons,
+    }
+}
+
+/// Implemented by each core to describe its own health
+#[async_trait]
+pub trait HealthReporter: Send + Sync {
+    /// Name of the reporting core, e.g. "phantom-sandbox-core"
+    fn component(&self) -> &str;
+
+    async fn check_health(&self) -> ComponentHealth;
+}
+
+/// Cores registered for platform health checks
+#[derive(Clone)]
+pub struct StatusRegistry {
+    reporters: Vec<Arc<dyn HealthReporter>>,
+    timeout: Duration,
+}
+
+impl Default for StatusRegistry {
+    fn default() -> Self {
+        Self {
+            reporters: Vec::new(),
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl StatusRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Give up on a check after `timeout`, reporting the component unhealthy
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn register(&mut self, reporter: Arc<dyn HealthReporter>) {
+        self.reporters.push(reporter);
+    }
+
+    pub fn components(&self) -> Vec<String> {
+        self.reporters.iter().map(|r| r.component().to_string()).collect()
+    }
+
+    /// Run every registered check concurrently
+    pub async fn check_all(&self) -> Vec<ComponentHealth> {
+        let checks = self.reporters.iter().map(|reporter| async move {
+            let started = Instant::now();
+            match tokio::time::timeout(self.timeout, reporter.check_health()).await {
+                Ok(mut health) => {
+                    health.latency_ms.get_or_insert(started.elapsed().as_millis() as u64);
+                    health
+                }
+                Err(_) => ComponentHealth {
+                    status: HealthState::Unhealthy,
+                    latency_ms: Some(started.elapsed().as_millis() as u64),
+                    last_error: Some(format!("Health check timed out after {}ms", self.timeout.as_millis())),
+                    ..ComponentHealth::new(reporter.component())
+                },
+            }
+        });
+        futures::future::join_all(checks).await
+    }
+
+    /// Check every registered core and add reports gathered elsewhere
+    pub async fn platform_health(&self, external: Vec<ComponentHealth>) -> PlatformHealth {
+        let mut components = self.check_all().await;
+        let local: Vec<String> = components.iter().map(|c| c.component.clone()).collect();
+        components.extend(external.into_iter().filter(|c| !local.contains(&c.component)));
+        rollup_health(components)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedReporter(ComponentHealth);
+
+    #[async_trait]
+    impl HealthReporter for FixedReporter {
+        fn component(&self) -> &str {
+            &self.0.component
+        }
+
+        async fn check_health(&self) -> ComponentHealth {
+            self.0.clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rollup_takes_worst_status_with_reasons() {
+        let mut sandbox = ComponentHealth::new("phantom-sandbox-core");
+        sandbox.dependency(DependencyHealth::healthy("job_store"));
+        sandbox.dependency(DependencyHealth::unhealthy("webhooks", "delivery_failing", "HTTP 503").optional());
+
+        let mut hunting = ComponentHealth::new("phantom-hunting-core");
+        hunting.dependency(DependencyHealth::unhealthy("data_sources", "no_data_sources", "No data sources registered"));
+
+        let mut registry = StatusRegistry::new();
+        registry.register(Arc::new(FixedReporter(sandbox)));
+
+        let health = registry.platform_health(Vec::new()).await;
+        assert_eq!(health.status, HealthState::Degraded);
+        assert_eq!(health.reasons.len(), 1);
+        assert_eq!(health.reasons[0].code, "delivery_failing");
+        assert_eq!(health.reasons[0].status, HealthState::Degraded);
+        assert!(health.components[0].latency_ms.is_some());
+
+        let health = registry.platform_health(vec![hunting]).await;
+        assert_eq!(health.status, HealthState::Unhealthy);
+        assert_eq!(health.components.len(), 2);
+        assert_eq!(health.reasons[0].code, "no_data_sources");
+        assert_eq!(health.reasons[0].dependency.as_deref(), Some("data_sources"));
+
+        assert_eq!(rollup_health(Vec::new()).status, HealthState::Unknown);
+    }
+}
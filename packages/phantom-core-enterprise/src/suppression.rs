@@ -0,0 +1,362 @@
+//! IOC Suppression
+//!
+//! Per-tenant allowlists for domains, IPs, hashes and process paths that
+//! cores consult before they report an indicator, keep a hunt match or score
+//! a verdict, so known-good infrastructure ("this domain is our CDN") stops
+//! producing false positives. Entries match exactly, by CIDR range (IPs
+//! only), by glob (`*` and `?`, case-insensitive) or by regular expression;
+//! each counts its hits and may carry an expiry, after which it no longer
+//! suppresses anything.
+
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::RwLock;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuppressionKind {
+    Domain,
+    Ip,
+    Hash,
+    ProcessPath,
+}
+
+impl SuppressionKind {
+    /// Kind of an IOC type as cores and feeds spell it, e.g. `IP`, `sha256` or `FilePath`
+    pub fn from_ioc_type(ioc_type: &str) -> Option<Self> {
+        let ioc_type = ioc_type.to_ascii_lowercase().replace(['-', '_', ' '], "");
+        match ioc_type.as_str() {
+            "domain" | "hostname" | "fqdn" => Some(SuppressionKind::Domain),
+            "ip" | "ipv4" | "ipv6" | "ipaddress" | "ipsrc" | "ipdst" => Some(SuppressionKind::Ip),
+            "hash" | "filehash" | "md5" | "sha1" | "sha256" | "sha512" => Some(SuppressionKind::Hash),
+            "processpath" | "process" | "filepath" | "path" | "image" => Some(SuppressionKind::ProcessPath),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+    #[default]
+    Exact,
+    Cidr,
+    Glob,
+    Regex,
+}
+
+/// A suppression as submitted for creation or update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuppressionRule {
+    pub kind: SuppressionKind,
+    #[serde(default)]
+    pub mode: MatchMode,
+    pub pattern: String,
+    #[serde(default)]
+    pub reason: String,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuppressionEntry {
+    pub id: String,
+    pub kind: SuppressionKind,
+    pub mode: MatchMode,
+    pub pattern: String,
+    pub reason: String,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub hit_count: u64,
+    pub last_hit_at: Option<DateTime<Utc>>,
+}
+
+impl SuppressionEntry {
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+#[derive(Debug)]
+enum Matcher {
+    Exact(String),
+    Ip(IpAddr),
+    Cidr(IpAddr, u8),
+    Pattern(Regex),
+}
+
+impl Matcher {
+    fn compile(rule: &SuppressionRule) -> Result<Self, String> {
+        let pattern = rule.pattern.trim();
+        if pattern.is_empty() {
+            return Err("Suppression pattern is required".to_string());
+        }
+        match rule.mode {
+            MatchMode::Exact if rule.kind == SuppressionKind::Ip => pattern
+                .parse()
+                .map(Matcher::Ip)
+                .map_err(|_| format!("Invalid IP address: {}", pattern)),
+            MatchMode::Exact => Ok(Matcher::Exact(normalize(rule.kind, pattern))),
+            MatchMode::Cidr => {
+                if rule.kind != SuppressionKind::Ip {
+                    return Err("CIDR suppressions only apply to IPs".to_string());
+                }
+                let (network, prefix) = parse_cidr(pattern)?;
+                Ok(Matcher::Cidr(network, prefix))
+            }
+            MatchMode::Glob => Regex::new(&glob_to_regex(pattern))
+                .map(Matcher::Pattern)
+                .map_err(|e| format!("Invalid glob {}: {}", pattern, e)),
+            MatchMode::Regex => Regex::new(pattern)
+                .map(Matcher::Pattern)
+                .map_err(|e| format!("Invalid regex {}: {}", pattern, e)),
+        }
+    }
+
+    fn matches(&self, kind: SuppressionKind, value: &str) -> bool {
+        match self {
+            Matcher::Exact(expected) => normalize(kind, value) == *expected,
+            Matcher::Ip(expected) => value.trim().parse::<IpAddr>().is_ok_and(|ip| ip == *expected),
+            Matcher::Cidr(network, prefix) => value.trim().parse::<IpAddr>().is_ok_and(|ip| in_network(ip, *network, *prefix)),
+            Matcher::Pattern(regex) => regex.is_match(&normalize(kind, value)) || regex.is_match(value.trim()),
+        }
+    }
+}
+
+/// Domains, hashes and paths compare case-insensitively; domains ignore a trailing dot
+fn normalize(kind: SuppressionKind, value: &str) -> String {
+    let value = value.trim().to_lowercase();
+    match kind {
+        SuppressionKind::Domain => value.trim_end_matches('.').to_string(),
+        _ => value,
+    }
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("(?i)^");
+    for c in glob.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+fn parse_cidr(cidr: &str) -> Result<(IpAddr, u8), String> {
+    let invalid = || format!("Invalid CIDR range: {}", cidr);
+    let (address, prefix) = cidr.split_once('/').ok_or_else(invalid)?;
+    let address: IpAddr = address.parse().map_err(|_| invalid())?;
+    let prefix: u8 = prefix.parse().map_err(|_| invalid())?;
+    let max = if address.is_ipv4() { 32 } else { 128 };
+    if prefix > max {
+        return Err(invalid());
+    }
+    Ok((address, prefix))
+}
+
+fn in_network(ip: IpAddr, network: IpAddr, prefix: u8) -> bool {
+    match (ip, network) {
+        (IpAddr::V4(ip), IpAddr::V4(network)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(network)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+#[derive(Debug)]
+struct Compiled {
+    entry: SuppressionEntry,
+    matcher: Matcher,
+}
+
+/// Suppression entries per scope, usually a tenant
+#[derive(Debug, Default)]
+pub struct SuppressionList {
+    scopes: RwLock<HashMap<String, Vec<Compiled>>>,
+}
+
+impl SuppressionList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&self, scope: &str, rule: SuppressionRule, created_by: &str) -> Result<SuppressionEntry, String> {
+        let matcher = Matcher::compile(&rule)?;
+        let now = Utc::now();
+        let entry = SuppressionEntry {
+            id: format!("sup_{}", Uuid::new_v4().simple()),
+            kind: rule.kind,
+            mode: rule.mode,
+            pattern: rule.pattern.trim().to_string(),
+            reason: rule.reason,
+            created_by: created_by.to_string(),
+            created_at: now,
+            updated_at: now,
+            expires_at: rule.expires_at,
+            hit_count: 0,
+            last_hit_at: None,
+        };
+        self.scopes
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(scope.to_string())
+            .or_default()
+            .push(Compiled { entry: entry.clone(), matcher });
+        Ok(entry)
+    }
+
+    /// Replace the rule of `id`, keeping its hit counters
+    pub fn update(&self, scope: &str, id: &str, rule: SuppressionRule) -> Result<SuppressionEntry, String> {
+        let matcher = Matcher::compile(&rule)?;
+        let mut scopes = self.scopes.write().unwrap_or_else(|e| e.into_inner());
+        let compiled = scopes
+            .get_mut(scope)
+            .and_then(|entries| entries.iter_mut().find(|c| c.entry.id == id))
+            .ok_or_else(|| format!("Suppression {} not found", id))?;
+        compiled.matcher = matcher;
+        let entry = &mut compiled.entry;
+        entry.kind = rule.kind;
+        entry.mode = rule.mode;
+        entry.pattern = rule.pattern.trim().to_string();
+        entry.reason = rule.reason;
+        entry.expires_at = rule.expires_at;
+        entry.updated_at = Utc::now();
+        Ok(entry.clone())
+    }
+
+    pub fn remove(&self, scope: &str, id: &str) -> bool {
+        let mut scopes = self.scopes.write().unwrap_or_else(|e| e.into_inner());
+        let Some(entries) = scopes.get_mut(scope) else {
+            return false;
+        };
+        let before = entries.len();
+        entries.retain(|c| c.entry.id != id);
+        before != entries.len()
+    }
+
+    pub fn get(&self, scope: &str, id: &str) -> Option<SuppressionEntry> {
+        self.scopes
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(scope)
+            .and_then(|entries| entries.iter().find(|c| c.entry.id == id))
+            .map(|c| c.entry.clone())
+    }
+
+    /// Entries of `scope` in creation order, expired ones included
+    pub fn list(&self, scope: &str) -> Vec<SuppressionEntry> {
+        self.scopes
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(scope)
+            .map(|entries| entries.iter().map(|c| c.entry.clone()).collect())
+            .unwrap_or_default()
+    }
+
+    /// First unexpired entry suppressing `value`, counting the hit
+    pub fn check(&self, scope: &str, kind: SuppressionKind, value: &str) -> Option<SuppressionEntry> {
+        self.check_at(scope, kind, value, Utc::now())
+    }
+
+    pub fn check_at(&self, scope: &str, kind: SuppressionKind, value: &str, now: DateTime<Utc>) -> Option<SuppressionEntry> {
+        let mut scopes = self.scopes.write().unwrap_or_else(|e| e.into_inner());
+        let compiled = scopes
+            .get_mut(scope)?
+            .iter_mut()
+            .find(|c| c.entry.kind == kind && !c.entry.is_expired(now) && c.matcher.matches(kind, value))?;
+        compiled.entry.hit_count += 1;
+        compiled.entry.last_hit_at = Some(now);
+        Some(compiled.entry.clone())
+    }
+
+    /// Remove entries that expired before `now`
+    pub fn purge_expired(&self, now: DateTime<Utc>) -> usize {
+        let mut scopes = self.scopes.write().unwrap_or_else(|e| e.into_inner());
+        let mut purged = 0;
+        for entries in scopes.values_mut() {
+            let before = entries.len();
+            entries.retain(|c| !c.entry.is_expired(now));
+            purged += before - entries.len();
+        }
+        purged
+    }
+
+    pub fn forget_scope(&self, scope: &str) -> usize {
+        self.scopes.write().unwrap_or_else(|e| e.into_inner()).remove(scope).map_or(0, |entries| entries.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(kind: SuppressionKind, mode: MatchMode, pattern: &str) -> SuppressionRule {
+        SuppressionRule { kind, mode, pattern: pattern.to_string(), reason: "known good".to_string(), expires_at: None }
+    }
+
+    #[test]
+    fn match_modes() {
+        let list = SuppressionList::new();
+        list.add("acme", rule(SuppressionKind::Domain, MatchMode::Exact, "CDN.example.com"), "analyst").unwrap();
+        list.add("acme", rule(SuppressionKind::Domain, MatchMode::Glob, "*.akamaiedge.net"), "analyst").unwrap();
+        list.add("acme", rule(SuppressionKind::Ip, MatchMode::Cidr, "10.0.0.0/8"), "analyst").unwrap();
+        list.add("acme", rule(SuppressionKind::Ip, MatchMode::Cidr, "2001:db8::/32"), "analyst").unwrap();
+        list.add("acme", rule(SuppressionKind::ProcessPath, MatchMode::Regex, r"(?i)^c:\\program files\\corp\\.*\.exe$"), "analyst").unwrap();
+        list.add("acme", rule(SuppressionKind::Hash, MatchMode::Exact, "D41D8CD98F00B204E9800998ECF8427E"), "analyst").unwrap();
+
+        assert!(list.check("acme", SuppressionKind::Domain, "cdn.example.com.").is_some());
+        assert!(list.check("acme", SuppressionKind::Domain, "evil.example.com").is_none());
+        assert!(list.check("acme", SuppressionKind::Domain, "e123.a.AkamaiEdge.net").is_some());
+        assert!(list.check("acme", SuppressionKind::Ip, "10.20.30.40").is_some());
+        assert!(list.check("acme", SuppressionKind::Ip, "11.0.0.1").is_none());
+        assert!(list.check("acme", SuppressionKind::Ip, "2001:db8:1::5").is_some());
+        assert!(list.check("acme", SuppressionKind::ProcessPath, r"C:\Program Files\Corp\agent.exe").is_some());
+        assert!(list.check("acme", SuppressionKind::Hash, "d41d8cd98f00b204e9800998ecf8427e").is_some());
+        assert!(list.check("acme", SuppressionKind::Domain, "10.20.30.40").is_none(), "kinds do not cross");
+        assert!(list.check("globex", SuppressionKind::Ip, "10.20.30.40").is_none());
+
+        assert!(list.add("acme", rule(SuppressionKind::Domain, MatchMode::Cidr, "10.0.0.0/8"), "analyst").is_err());
+        assert!(list.add("acme", rule(SuppressionKind::Ip, MatchMode::Cidr, "10.0.0.0/33"), "analyst").is_err());
+        assert!(list.add("acme", rule(SuppressionKind::Domain, MatchMode::Regex, "("), "analyst").is_err());
+        assert_eq!(SuppressionKind::from_ioc_type("SHA-256"), Some(SuppressionKind::Hash));
+    }
+
+    #[test]
+    fn hits_expiry_and_crud() {
+        let list = SuppressionList::new();
+        let now = Utc::now();
+        let mut expiring = rule(SuppressionKind::Ip, MatchMode::Exact, "192.0.2.10");
+        expiring.expires_at = Some(now + chrono::Duration::hours(1));
+        let entry = list.add("acme", expiring.clone(), "analyst").unwrap();
+
+        list.check_at("acme", SuppressionKind::Ip, "192.0.2.10", now).unwrap();
+        let hit = list.check_at("acme", SuppressionKind::Ip, "192.0.2.10", now).unwrap();
+        assert_eq!(hit.hit_count, 2);
+        assert!(list.check_at("acme", SuppressionKind::Ip, "192.0.2.10", now + chrono::Duration::hours(2)).is_none());
+
+        expiring.pattern = "192.0.2.11".to_string();
+        let updated = list.update("acme", &entry.id, expiring).unwrap();
+        assert_eq!(updated.hit_count, 2);
+        assert!(list.check_at("acme", SuppressionKind::Ip, "192.0.2.10", now).is_none());
+        assert_eq!(list.get("acme", &entry.id).unwrap().pattern, "192.0.2.11");
+
+        assert_eq!(list.purge_expired(now + chrono::Duration::hours(2)), 1);
+        assert!(list.list("acme").is_empty());
+        assert!(!list.remove("acme", &entry.id));
+        assert!(list.update("acme", &entry.id, rule(SuppressionKind::Ip, MatchMode::Exact, "192.0.2.1")).is_err());
+    }
+}
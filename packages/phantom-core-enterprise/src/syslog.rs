@@ -0,0 +1,604 @@
+//! SIEM Syslog Forwarding
+//!
+//! Formats security events (alerts, sandbox verdicts, hunt matches) as CEF
+//! or LEEF and ships them to a syslog collector over UDP, TCP or TLS
+//! (RFC 5424 headers; octet-counted framing on streams). Event fields are
+//! renamed to the target format's keys through a default mapping that the
+//! configuration can override. Messages are queued while the collector is
+//! unreachable and delivered in order once it is back; when the queue is
+//! full the oldest messages are dropped and counted.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, UdpSocket};
+
+/// Longest wait between reconnection attempts while the collector is down
+const MAX_BACKOFF_SECS: i64 = 60;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyslogFormat {
+    #[default]
+    Cef,
+    Leef,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyslogProtocol {
+    Udp,
+    #[default]
+    Tcp,
+    /// TCP with TLS; requires the `syslog-tls` feature
+    Tls,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyslogConfig {
+    pub host: String,
+    pub port: u16,
+    #[serde(default)]
+    pub protocol: SyslogProtocol,
+    #[serde(default)]
+    pub format: SyslogFormat,
+    /// Syslog facility code; 13 is "log audit"
+    #[serde(default = "default_facility")]
+    pub facility: u8,
+    #[serde(default = "default_vendor")]
+    pub vendor: String,
+    #[serde(default = "default_product")]
+    pub product: String,
+    #[serde(default = "default_product_version")]
+    pub product_version: String,
+    /// Host name written in the syslog header; `-` when unset
+    #[serde(default)]
+    pub hostname: Option<String>,
+    /// Event field to CEF/LEEF key, overriding the default mapping; an
+    /// empty key drops the field
+    #[serde(default)]
+    pub field_mappings: BTreeMap<String, String>,
+    /// Events below this severity (0-10) are not forwarded
+    #[serde(default)]
+    pub min_severity: u8,
+    /// Messages held while the collector is unreachable
+    #[serde(default = "default_buffer_capacity")]
+    pub buffer_capacity: usize,
+    #[serde(default = "default_connect_timeout_ms")]
+    pub connect_timeout_ms: u64,
+    /// Name the collector's certificate is checked against; defaults to `host`
+    #[serde(default)]
+    pub tls_server_name: Option<String>,
+    /// PEM bundle of additional CAs trusted for the collector
+    #[serde(default)]
+    pub tls_ca_pem: Option<String>,
+}
+
+fn default_facility() -> u8 {
+    13
+}
+
+fn default_vendor() -> String {
+    "Phantom Spire".to_string()
+}
+
+fn default_product() -> String {
+    "phantom-spire".to_string()
+}
+
+fn default_product_version() -> String {
+    "1.0".to_string()
+}
+
+fn default_buffer_capacity() -> usize {
+    10_000
+}
+
+fn default_connect_timeout_ms() -> u64 {
+    5_000
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityEventKind {
+    Alert,
+    Verdict,
+    HuntMatch,
+}
+
+/// Event to forward. Field names are format independent (`source_ip`,
+/// `file_hash`, ...) and are mapped to CEF or LEEF keys when formatted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityEvent {
+    pub kind: SecurityEventKind,
+    /// CEF signature id / LEEF event id, e.g. the rule that fired
+    pub event_class_id: String,
+    pub name: String,
+    /// 0 (informational) to 10 (critical)
+    pub severity: u8,
+    pub timestamp: DateTime<Utc>,
+    pub fields: BTreeMap<String, String>,
+}
+
+impl SecurityEvent {
+    pub fn new(kind: SecurityEventKind, event_class_id: &str, name: &str, severity: u8) -> Self {
+        Self {
+            kind,
+            event_class_id: event_class_id.to_string(),
+            name: name.to_string(),
+            severity: severity.min(10),
+            timestamp: Utc::now(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    /// Add a field; empty values are skipped
+    pub fn field(mut self, name: &str, value: impl ToString) -> Self {
+        let value = value.to_string();
+        if !value.is_empty() {
+            self.fields.insert(name.to_string(), value);
+        }
+        self
+    }
+}
+
+fn default_key(format: SyslogFormat, field: &str) -> Option<&'static str> {
+    let key = match (format, field) {
+        (_, "source_ip") => "src",
+        (_, "destination_ip") => "dst",
+        (_, "source_port") => "srcPort",
+        (_, "destination_port") => "dstPort",
+        (_, "protocol") => "proto",
+        (SyslogFormat::Cef, "source_host") => "shost",
+        (SyslogFormat::Cef, "destination_host") => "dhost",
+        (SyslogFormat::Cef, "user") => "suser",
+        (SyslogFormat::Cef, "file_name") => "fname",
+        (SyslogFormat::Cef, "file_hash") => "fileHash",
+        (SyslogFormat::Cef, "message") => "msg",
+        (SyslogFormat::Cef, "category") => "cat",
+        (SyslogFormat::Cef, "outcome") => "outcome",
+        (SyslogFormat::Cef, "external_id") => "externalId",
+        (SyslogFormat::Leef, "source_host") => "srcHostName",
+        (SyslogFormat::Leef, "destination_host") => "dstHostName",
+        (SyslogFormat::Leef, "user") => "usrName",
+        (SyslogFormat::Leef, "file_name") => "fileName",
+        (SyslogFormat::Leef, "file_hash") => "fileHash",
+        (SyslogFormat::Leef, "message") => "msg",
+        (SyslogFormat::Leef, "category") => "cat",
+        (SyslogFormat::Leef, "external_id") => "externalId",
+        _ => return None,
+    };
+    Some(key)
+}
+
+fn mapped_fields<'a>(config: &SyslogConfig, event: &'a SecurityEvent) -> Vec<(String, &'a str)> {
+    event
+        .fields
+        .iter()
+        .filter_map(|(field, value)| {
+            let key = match config.field_mappings.get(field) {
+                Some(key) => key.clone(),
+                None => default_key(config.format, field).map_or_else(|| field.clone(), str::to_string),
+            };
+            let key: String = key.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '_').collect();
+            (!key.is_empty()).then_some((key, value.as_str()))
+        })
+        .collect()
+}
+
+fn escape_header(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+fn escape_cef_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('=', "\\=")
+        .replace('\r', "\\r")
+        .replace('\n', "\\n")
+}
+
+/// `CEF:0|Vendor|Product|Version|SignatureID|Name|Severity|Extension`
+pub fn format_cef(config: &SyslogConfig, event: &SecurityEvent) -> String {
+    let mut extension = vec![format!("rt={}", event.timestamp.timestamp_millis())];
+    extension.extend(
+        mapped_fields(config, event)
+            .into_iter()
+            .map(|(key, value)| format!("{}={}", key, escape_cef_value(value))),
+    );
+    format!(
+        "CEF:0|{}|{}|{}|{}|{}|{}|{}",
+        escape_header(&config.vendor),
+        escape_header(&config.product),
+        escape_header(&config.product_version),
+        escape_header(&event.event_class_id),
+        escape_header(&event.name),
+        event.severity,
+        extension.join(" ")
+    )
+}
+
+/// `LEEF:2.0|Vendor|Product|Version|EventID|^|` followed by `^`-delimited attributes
+pub fn format_leef(config: &SyslogConfig, event: &SecurityEvent) -> String {
+    let clean = |value: &str| value.replace(['^', '\t', '\r', '\n'], " ");
+    let mut attributes = vec![
+        format!("devTime={}", event.timestamp.format("%b %d %Y %H:%M:%S%.3f UTC")),
+        "devTimeFormat=MMM dd yyyy HH:mm:ss.SSS z".to_string(),
+        format!("sev={}", event.severity),
+        format!("name={}", clean(&event.name)),
+    ];
+    attributes.extend(
+        mapped_fields(config, event)
+            .into_iter()
+            .map(|(key, value)| format!("{}={}", key, clean(value))),
+    );
+    format!(
+        "LEEF:2.0|{}|{}|{}|{}|^|{}",
+        escape_header(&config.vendor),
+        escape_header(&config.product),
+        escape_header(&config.product_version),
+        escape_header(&event.event_class_id),
+        attributes.join("^")
+    )
+}
+
+/// Syslog severity for an event severity of 0-10
+fn syslog_severity(severity: u8) -> u8 {
+    match severity {
+        9.. => 2,
+        7..=8 => 3,
+        4..=6 => 4,
+        1..=3 => 5,
+        0 => 6,
+    }
+}
+
+/// RFC 5424 message carrying the formatted event
+pub fn syslog_message(config: &SyslogConfig, event: &SecurityEvent) -> String {
+    let body = match config.format {
+        SyslogFormat::Cef => format_cef(config, event),
+        SyslogFormat::Leef => format_leef(config, event),
+    };
+    format!(
+        "<{}>1 {} {} {} - - - {}",
+        u16::from(config.facility.min(23)) * 8 + u16::from(syslog_severity(event.severity)),
+        event.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+        config.hostname.as_deref().unwrap_or("-"),
+        config.product.replace(' ', "_"),
+        body
+    )
+}
+
+/// Delivery counters of a forwarder
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ForwarderMetrics {
+    pub forwarded: u64,
+    pub sent: u64,
+    /// Messages below `min_severity`
+    pub filtered: u64,
+    pub buffered: usize,
+    /// Messages discarded because the buffer was full
+    pub dropped: u64,
+    pub failed_attempts: u64,
+    pub connections: u64,
+    pub last_error: Option<String>,
+    pub last_sent_at: Option<DateTime<Utc>>,
+}
+
+enum Connection {
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+    #[cfg(feature = "syslog-tls")]
+    Tls(Box<tokio_rustls::client::TlsStream<TcpStream>>),
+}
+
+impl Connection {
+    async fn send(&mut self, message: &[u8]) -> std::io::Result<()> {
+        match self {
+            Self::Udp(socket) => socket.send(message).await.map(|_| ()),
+            Self::Tcp(stream) => write_framed(stream, message).await,
+            #[cfg(feature = "syslog-tls")]
+            Self::Tls(stream) => write_framed(stream.as_mut(), message).await,
+        }
+    }
+}
+
+/// RFC 6587 octet counting
+async fn write_framed<W: AsyncWriteExt + Unpin>(stream: &mut W, message: &[u8]) -> std::io::Result<()> {
+    stream.write_all(format!("{} ", message.len()).as_bytes()).await?;
+    stream.write_all(message).await?;
+    stream.flush().await
+}
+
+#[derive(Default)]
+struct Queue {
+    messages: VecDeque<String>,
+    /// No reconnection attempts from `forward` before this instant
+    retry_at: Option<DateTime<Utc>>,
+    failures_in_row: u32,
+}
+
+pub struct SyslogForwarder {
+    config: SyslogConfig,
+    queue: Mutex<Queue>,
+    connection: tokio::sync::Mutex<Option<Connection>>,
+    metrics: Mutex<ForwarderMetrics>,
+    #[cfg(feature = "syslog-tls")]
+    tls: Option<tokio_rustls::TlsConnector>,
+}
+
+impl SyslogForwarder {
+    pub fn new(config: SyslogConfig) -> Result<Self, String> {
+        if config.host.is_empty() || config.port == 0 {
+            return Err("Syslog collector host and port are required".to_string());
+        }
+        if config.buffer_capacity == 0 {
+            return Err("Syslog buffer capacity must be at least 1".to_string());
+        }
+        #[cfg(feature = "syslog-tls")]
+        let tls = match config.protocol {
+            SyslogProtocol::Tls => Some(tls_connector(config.tls_ca_pem.as_deref())?),
+            _ => None,
+        };
+        #[cfg(not(feature = "syslog-tls"))]
+        if config.protocol == SyslogProtocol::Tls {
+            return Err("TLS syslog forwarding requires the syslog-tls feature".to_string());
+        }
+        Ok(Self {
+            config,
+            queue: Mutex::new(Queue::default()),
+            connection: tokio::sync::Mutex::new(None),
+            metrics: Mutex::new(ForwarderMetrics::default()),
+            #[cfg(feature = "syslog-tls")]
+            tls,
+        })
+    }
+
+    pub fn config(&self) -> &SyslogConfig {
+        &self.config
+    }
+
+    pub fn metrics(&self) -> ForwarderMetrics {
+        let mut metrics = self.metrics.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        metrics.buffered = self.queue.lock().unwrap_or_else(|e| e.into_inner()).messages.len();
+        metrics
+    }
+
+    /// Queue `event` and deliver the queue in the background. Returns
+    /// without waiting for the collector, so callers on hot paths are not
+    /// slowed down by an unreachable SIEM.
+    pub fn forward(self: &Arc<Self>, event: &SecurityEvent) {
+        if !self.enqueue(event) {
+            return;
+        }
+        let retry_at = self.queue.lock().unwrap_or_else(|e| e.into_inner()).retry_at;
+        if retry_at.is_some_and(|at| Utc::now() < at) {
+            return;
+        }
+        let forwarder = Arc::clone(self);
+        tokio::spawn(async move {
+            forwarder.flush().await;
+        });
+    }
+
+    /// Queue `event` without sending; false when it is below `min_severity`
+    pub fn enqueue(&self, event: &SecurityEvent) -> bool {
+        let mut metrics = self.metrics.lock().unwrap_or_else(|e| e.into_inner());
+        if event.severity < self.config.min_severity {
+            metrics.filtered += 1;
+            return false;
+        }
+        metrics.forwarded += 1;
+        let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+        if queue.messages.len() >= self.config.buffer_capacity {
+            queue.messages.pop_front();
+            metrics.dropped += 1;
+        }
+        queue.messages.push_back(syslog_message(&self.config, event));
+        true
+    }
+
+    /// Deliver queued messages in order until the queue is empty or the
+    /// collector fails; returns how many were sent
+    pub async fn flush(&self) -> usize {
+        let mut connection = self.connection.lock().await;
+        let mut sent = 0;
+        loop {
+            let Some(message) = self.queue.lock().unwrap_or_else(|e| e.into_inner()).messages.front().cloned() else {
+                break;
+            };
+            if connection.is_none() {
+                match self.connect().await {
+                    Ok(conn) => {
+                        *connection = Some(conn);
+                        self.metrics.lock().unwrap_or_else(|e| e.into_inner()).connections += 1;
+                    }
+                    Err(e) => {
+                        self.record_failure(e);
+                        break;
+                    }
+                }
+            }
+            let Some(conn) = connection.as_mut() else { break };
+            if let Err(e) = conn.send(message.as_bytes()).await {
+                *connection = None;
+                self.record_failure(format!("Failed to send to {}:{}: {}", self.config.host, self.config.port, e));
+                break;
+            }
+            {
+                let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+                queue.messages.pop_front();
+                queue.retry_at = None;
+                queue.failures_in_row = 0;
+            }
+            let mut metrics = self.metrics.lock().unwrap_or_else(|e| e.into_inner());
+            metrics.sent += 1;
+            metrics.last_sent_at = Some(Utc::now());
+            sent += 1;
+        }
+        sent
+    }
+
+    fn record_failure(&self, error: String) {
+        log::warn!("Syslog forwarding: {}", error);
+        {
+            let mut queue = self.queue.lock().unwrap_or_else(|e| e.into_inner());
+            queue.failures_in_row = queue.failures_in_row.saturating_add(1);
+            let backoff = (1i64 << queue.failures_in_row.min(6)).min(MAX_BACKOFF_SECS);
+            queue.retry_at = Some(Utc::now() + chrono::Duration::seconds(backoff));
+        }
+        let mut metrics = self.metrics.lock().unwrap_or_else(|e| e.into_inner());
+        metrics.failed_attempts += 1;
+        metrics.last_error = Some(error);
+    }
+
+    async fn connect(&self) -> Result<Connection, String> {
+        let address = format!("{}:{}", self.config.host, self.config.port);
+        let timeout = Duration::from_millis(self.config.connect_timeout_ms);
+        let connect_error = |e: std::io::Error| format!("Failed to connect to {}: {}", address, e);
+        match self.config.protocol {
+            SyslogProtocol::Udp => {
+                let socket = UdpSocket::bind("0.0.0.0:0").await.map_err(connect_error)?;
+                socket.connect(&address).await.map_err(connect_error)?;
+                Ok(Connection::Udp(socket))
+            }
+            SyslogProtocol::Tcp => Ok(Connection::Tcp(self.connect_tcp(&address, timeout).await?)),
+            #[cfg(feature = "syslog-tls")]
+            SyslogProtocol::Tls => {
+                use tokio_rustls::rustls::pki_types::ServerName;
+
+                let tcp = self.connect_tcp(&address, timeout).await?;
+                let server_name = self.config.tls_server_name.clone().unwrap_or_else(|| self.config.host.clone());
+                let server_name = ServerName::try_from(server_name).map_err(|e| format!("Invalid TLS server name: {}", e))?;
+                let connector = self.tls.as_ref().ok_or("TLS connector not configured")?;
+                let stream = tokio::time::timeout(timeout, connector.connect(server_name, tcp))
+                    .await
+                    .map_err(|_| format!("TLS handshake with {} timed out", address))?
+                    .map_err(|e| format!("TLS handshake with {} failed: {}", address, e))?;
+                Ok(Connection::Tls(Box::new(stream)))
+            }
+            #[cfg(not(feature = "syslog-tls"))]
+            SyslogProtocol::Tls => Err("TLS syslog forwarding requires the syslog-tls feature".to_string()),
+        }
+    }
+
+    async fn connect_tcp(&self, address: &str, timeout: Duration) -> Result<TcpStream, String> {
+        tokio::time::timeout(timeout, TcpStream::connect(address))
+            .await
+            .map_err(|_| format!("Connection to {} timed out", address))?
+            .map_err(|e| format!("Failed to connect to {}: {}", address, e))
+    }
+}
+
+#[cfg(feature = "syslog-tls")]
+fn tls_connector(ca_pem: Option<&str>) -> Result<tokio_rustls::TlsConnector, String> {
+    use tokio_rustls::rustls::pki_types::{pem::PemObject, CertificateDer};
+    use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+
+    let mut roots = RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
+    if let Some(pem) = ca_pem {
+        for cert in CertificateDer::pem_slice_iter(pem.as_bytes()) {
+            let cert = cert.map_err(|e| format!("Invalid CA certificate: {}", e))?;
+            roots.add(cert).map_err(|e| format!("Invalid CA certificate: {}", e))?;
+        }
+    }
+    let config = ClientConfig::builder().with_root_certificates(roots).with_no_client_auth();
+    Ok(tokio_rustls::TlsConnector::from(Arc::new(config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    fn config(port: u16, format: SyslogFormat) -> SyslogConfig {
+        serde_json::from_value(serde_json::json!({
+            "host": "127.0.0.1", "port": port, "format": format, "product": "sandbox",
+            "field_mappings": { "tenant_id": "cs1", "analyst_note": "" }
+        }))
+        .unwrap()
+    }
+
+    fn verdict() -> SecurityEvent {
+        SecurityEvent::new(SecurityEventKind::Verdict, "sandbox-malicious", "Malicious | sample", 9)
+            .field("file_hash", "abc=123")
+            .field("source_ip", "10.0.0.5")
+            .field("tenant_id", "acme")
+            .field("analyst_note", "internal")
+    }
+
+    #[test]
+    fn formats_cef_and_leef_with_mapped_fields() {
+        let cef = format_cef(&config(514, SyslogFormat::Cef), &verdict());
+        assert!(cef.starts_with("CEF:0|Phantom Spire|sandbox|1.0|sandbox-malicious|Malicious \\| sample|9|rt="));
+        assert!(cef.contains(" fileHash=abc\\=123"));
+        assert!(cef.contains(" src=10.0.0.5"));
+        assert!(cef.contains(" cs1=acme"));
+        assert!(!cef.contains("internal"));
+
+        let leef = format_leef(&config(514, SyslogFormat::Leef), &verdict());
+        assert!(leef.starts_with("LEEF:2.0|Phantom Spire|sandbox|1.0|sandbox-malicious|^|devTime="));
+        assert!(leef.contains("^sev=9^"));
+        assert!(leef.contains("^fileHash=abc=123"));
+
+        let message = syslog_message(&config(514, SyslogFormat::Cef), &verdict());
+        assert!(message.starts_with("<106>1 "), "facility 13, severity crit: {}", message);
+    }
+
+    async fn read_frame(stream: &mut TcpStream) -> String {
+        let mut len = String::new();
+        loop {
+            let byte = stream.read_u8().await.unwrap();
+            if byte == b' ' {
+                break;
+            }
+            len.push(byte as char);
+        }
+        let mut message = vec![0; len.parse().unwrap()];
+        stream.read_exact(&mut message).await.unwrap();
+        String::from_utf8(message).unwrap()
+    }
+
+    #[tokio::test]
+    async fn buffers_while_collector_is_down_and_delivers_in_order() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let forwarder = SyslogForwarder::new(config(port, SyslogFormat::Cef)).unwrap();
+        for id in ["first", "second"] {
+            forwarder.enqueue(&SecurityEvent::new(SecurityEventKind::Alert, id, "alert", 5));
+        }
+        assert_eq!(forwarder.flush().await, 0);
+        let metrics = forwarder.metrics();
+        assert_eq!(metrics.buffered, 2);
+        assert_eq!(metrics.failed_attempts, 1);
+        assert!(metrics.last_error.is_some());
+
+        let listener = TcpListener::bind(("127.0.0.1", port)).await.unwrap();
+        assert_eq!(forwarder.flush().await, 2);
+        let (mut stream, _) = listener.accept().await.unwrap();
+        assert!(read_frame(&mut stream).await.contains("|first|alert|5|"));
+        assert!(read_frame(&mut stream).await.contains("|second|alert|5|"));
+        let metrics = forwarder.metrics();
+        assert_eq!((metrics.sent, metrics.buffered, metrics.connections), (2, 0, 1));
+    }
+
+    #[tokio::test]
+    async fn full_buffer_drops_oldest_and_low_severity_is_filtered() {
+        let mut config = config(9, SyslogFormat::Leef);
+        config.buffer_capacity = 2;
+        config.min_severity = 3;
+        let forwarder = SyslogForwarder::new(config).unwrap();
+        assert!(!forwarder.enqueue(&SecurityEvent::new(SecurityEventKind::HuntMatch, "noise", "noise", 1)));
+        for id in ["a", "b", "c"] {
+            forwarder.enqueue(&SecurityEvent::new(SecurityEventKind::HuntMatch, id, "match", 6));
+        }
+        let metrics = forwarder.metrics();
+        assert_eq!((metrics.filtered, metrics.forwarded, metrics.dropped, metrics.buffered), (1, 3, 1, 2));
+    }
+}
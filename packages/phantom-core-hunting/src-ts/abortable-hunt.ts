@@ -0,0 +1,68 @@
+import { randomUUID } from 'crypto';
+
+/**
+ * AbortSignal support for hunts
+ *
+ * The native core cancels hunts by id. This helper runs a hunt under an id it
+ * chooses and cancels it when the signal aborts; the hunt still resolves,
+ * with `completion` set to `Cancelled` and the matches found so far.
+ */
+
+/** The native methods the helper uses */
+export interface CancellableHuntingCore {
+  executeHuntWithOptions(ruleId: string, dataContext: string | undefined | null, options: string, authToken?: string | undefined | null): Promise<string>;
+  cancelHunt(huntId: string, authToken?: string | undefined | null): boolean;
+}
+
+export interface AbortableHuntOptions {
+  signal?: AbortSignal;
+  /** Deadline overriding the rule's `execution_timeout_secs` */
+  timeoutMs?: number;
+  incremental?: boolean;
+  dataContext?: Record<string, unknown>;
+  authToken?: string;
+}
+
+/** Hunt result as serialized by the core; see `HuntingResult` */
+export interface HuntResultJson {
+  hunt_id: string;
+  completion: 'Completed' | 'Cancelled' | 'TimedOut';
+  source_errors: string[];
+  [field: string]: unknown;
+}
+
+/** Retry delay while the hunt has not registered with the core yet */
+const CANCEL_RETRY_MS = 10;
+
+export async function executeHuntWithSignal(
+  core: CancellableHuntingCore,
+  ruleId: string,
+  options: AbortableHuntOptions = {},
+): Promise<HuntResultJson> {
+  const { signal, timeoutMs, incremental, dataContext, authToken } = options;
+  if (signal?.aborted) {
+    throw new Error(`Hunt of ${ruleId} aborted before it started`);
+  }
+
+  const huntId = `hunt_${randomUUID().replace(/-/g, '')}`;
+  let settled = false;
+  const cancel = (): void => {
+    if (!settled && !core.cancelHunt(huntId, authToken)) {
+      setTimeout(cancel, CANCEL_RETRY_MS);
+    }
+  };
+  signal?.addEventListener('abort', cancel, { once: true });
+
+  try {
+    const result = await core.executeHuntWithOptions(
+      ruleId,
+      dataContext === undefined ? undefined : JSON.stringify(dataContext),
+      JSON.stringify({ hunt_id: huntId, timeout_ms: timeoutMs, incremental: incremental ?? false }),
+      authToken,
+    );
+    return JSON.parse(result) as HuntResultJson;
+  } finally {
+    settled = true;
+    signal?.removeEventListener('abort', cancel);
+  }
+}
@@ -0,0 +1,148 @@
+//! Per-call authorization for the NAPI surface
+//!
+//! Mutating calls are checked with the shared
+//! `phantom_enterprise_standards::guard::AccessGuard`, whose docs describe
+//! how builds without `phantom-enterprise-standards` behave.
+
+pub use phantom_enterprise_standards::guard::AccessGuard;
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use napi_derive::napi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::guard::bind_principal_tenant;
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::HuntingCoreNapi;
+
+/// Guard for this build: RBAC with `phantom-enterprise-standards`, open without
+pub fn access_guard() -> AccessGuard {
+    if cfg!(feature = "phantom-enterprise-standards") {
+        AccessGuard::rbac()
+    } else {
+        AccessGuard::open()
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[napi]
+impl HuntingCoreNapi {
+    /// Turn permission enforcement on or off
+    #[napi]
+    pub fn set_access_enforced(&self, enforced: bool, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize_platform(auth_token, "access")?;
+        self.access.control().set_enforced(enforced);
+        self.audit.record(&actor, "set_access_enforced", "access", serde_json::json!({ "enforced": enforced }), Ok::<_, String>(()))
+            .map_err(napi::Error::from_reason)
+    }
+
+    /// Issue an API key for a principal; the token is only returned here
+    #[napi]
+    pub fn issue_api_key(&self, principal_data: String, ttl_secs: Option<i64>, auth_token: Option<String>) -> napi::Result<String> {
+        let caller_tenant = self.access.tenant(auth_token.as_deref());
+        let actor = self.authorize(auth_token, "access:manage", "access")?;
+        let mut principal = serde_json::from_str(&principal_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid principal: {}", e)))?;
+        bind_principal_tenant(caller_tenant, &mut principal).map_err(napi::Error::from_reason)?;
+        let issued = self.access.control().issue_api_key(principal, ttl_secs.map(chrono::Duration::seconds));
+        let params = serde_json::json!({ "principal": principal_data, "ttl_secs": ttl_secs });
+        let issued = self.audit.record(&actor, "issue_api_key", &issued.credential_id.clone(), params, Ok::<_, String>(issued))
+            .map_err(napi::Error::from_reason)?;
+        serde_json::to_string(&issued)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize credential: {}", e)))
+    }
+
+    /// Start a session that expires after `ttl_secs`
+    #[napi]
+    pub fn start_session(&self, principal_data: String, ttl_secs: i64, auth_token: Option<String>) -> napi::Result<String> {
+        let caller_tenant = self.access.tenant(auth_token.as_deref());
+        let actor = self.authorize(auth_token, "access:manage", "access")?;
+        let mut principal = serde_json::from_str(&principal_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid principal: {}", e)))?;
+        bind_principal_tenant(caller_tenant, &mut principal).map_err(napi::Error::from_reason)?;
+        let issued = self.access.control().start_session(principal, chrono::Duration::seconds(ttl_secs));
+        let params = serde_json::json!({ "principal": principal_data, "ttl_secs": ttl_secs });
+        let issued = self.audit.record(&actor, "start_session", &issued.credential_id.clone(), params, Ok::<_, String>(issued))
+            .map_err(napi::Error::from_reason)?;
+        serde_json::to_string(&issued)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize credential: {}", e)))
+    }
+
+    #[napi]
+    pub fn revoke_credential(&self, credential_id: String, auth_token: Option<String>) -> napi::Result<bool> {
+        let caller_tenant = self.access.tenant(auth_token.as_deref());
+        let actor = self.authorize(auth_token, "access:manage", "access")?;
+        let revoked = self.access.control().revoke_in(caller_tenant.as_deref(), &credential_id);
+        self.audit.record(&actor, "revoke_credential", &credential_id, serde_json::json!({}), Ok::<_, String>(revoked))
+            .map_err(napi::Error::from_reason)
+    }
+
+    #[napi]
+    pub fn list_credentials(&self, auth_token: Option<String>) -> napi::Result<String> {
+        let caller_tenant = self.access.tenant(auth_token.as_deref());
+        self.authorize(auth_token, "access:manage", "access")?;
+        serde_json::to_string(&self.access.control().list_credentials_in(caller_tenant.as_deref()))
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize credentials: {}", e)))
+    }
+
+    /// Most recent access denials, newest first
+    #[napi]
+    pub fn list_access_denials(&self, limit: Option<u32>, auth_token: Option<String>) -> napi::Result<String> {
+        let caller_tenant = self.access.tenant(auth_token.as_deref());
+        self.authorize(auth_token, "access:manage", "access")?;
+        let denials = self.access.control().denials_in(caller_tenant.as_deref(), limit.unwrap_or(100) as usize);
+        serde_json::to_string(&denials)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize denials: {}", e)))
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use crate::HuntingCoreNapi;
+    use phantom_enterprise_standards::rbac::{Principal, Role};
+
+    #[tokio::test]
+    async fn viewers_cannot_run_hunts_or_change_shared_settings() {
+        let napi = HuntingCoreNapi::new().unwrap();
+        let control = napi.access.control();
+        control.set_enforced(true);
+        let viewer = control.issue_api_key(Principal::new("viewer", vec![Role::Viewer]), None).token;
+        let mut tenant_admin = Principal::new("acme-admin", vec![Role::Admin]);
+        tenant_admin.tenant_id = Some("acme".to_string());
+        let tenant_admin = control.issue_api_key(tenant_admin, None).token;
+        let admin = control.issue_api_key(Principal::new("admin", vec![Role::Admin]), None).token;
+
+        assert!(napi.execute_hunt("apt_lateral_movement".to_string(), None, Some(viewer.clone())).await.is_err());
+        assert!(napi.open_hunt_session("triage".to_string(), None, Some(viewer.clone())).await.is_err());
+        assert!(napi.refresh_baselines(Some(viewer.clone())).await.is_err());
+        assert!(napi.stop_scheduler(Some(viewer)).await.is_err());
+
+        assert!(napi.stop_scheduler(Some(tenant_admin)).await.is_err());
+        assert!(!napi.stop_scheduler(Some(admin)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn tenant_admins_only_manage_their_own_credentials() {
+        let napi = HuntingCoreNapi::new().unwrap();
+        let control = napi.access.control();
+        control.set_enforced(true);
+        let mut tenant_admin = Principal::new("acme-admin", vec![Role::Admin]);
+        tenant_admin.tenant_id = Some("acme".to_string());
+        let tenant_admin = control.issue_api_key(tenant_admin, None).token;
+        let mut other = Principal::new("globex-viewer", vec![Role::Viewer]);
+        other.tenant_id = Some("globex".to_string());
+        let other = control.issue_api_key(other, None);
+        let admin = control.issue_api_key(Principal::new("admin", vec![Role::Admin]), None).token;
+        napi.create_tenant("globex".to_string(), "Globex".to_string(), Some(admin.clone())).unwrap();
+        assert!(napi.execute_hunt("apt_lateral_movement".to_string(), None, Some(other.token.clone())).await.is_err());
+
+        let count = |json: String| serde_json::from_str::<Vec<serde_json::Value>>(&json).unwrap().len();
+        assert_eq!(count(napi.list_credentials(Some(tenant_admin.clone())).unwrap()), 1);
+        assert_eq!(count(napi.list_access_denials(None, Some(tenant_admin.clone())).unwrap()), 0);
+        assert!(!napi.revoke_credential(other.credential_id.clone(), Some(tenant_admin.clone())).unwrap());
+        assert!(napi.set_access_enforced(false, Some(tenant_admin)).is_err());
+
+        assert_eq!(count(napi.list_credentials(Some(admin.clone())).unwrap()), 3);
+        assert_eq!(count(napi.list_access_denials(None, Some(admin.clone())).unwrap()), 1);
+        assert!(napi.revoke_credential(other.credential_id, Some(admin)).unwrap());
+    }
+}
@@ -0,0 +1,239 @@
+//! Asset Context
+//!
+//! With `phantom-enterprise-standards` enabled, each tenant keeps an asset
+//! inventory (hosts, users, services with criticality, owner, network zone
+//! and tags). A hunt match whose event names a host, or whose network
+//! context has an address, that resolves to an inventoried host gets that
+//! host as its `SystemContext`, and its risk score is weighted by the host's
+//! criticality. Without the feature matches keep the context they were built
+//! with.
+
+use crate::HuntingMatch;
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::{HuntingCore, HuntingCoreNapi, SystemContext};
+#[cfg(feature = "phantom-enterprise-standards")]
+use napi_derive::napi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::assets::{Asset, AssetImpact, AssetImportReport, AssetInventory, AssetKind, AssetRecord, Criticality};
+
+/// Event fields naming the host an event happened on, in order of preference
+#[cfg(feature = "phantom-enterprise-standards")]
+const HOST_FIELDS: &[&str] = &["hostname", "host", "host.name", "computer_name", "Computer", "device_name"];
+/// Risk scores are on a 0-10 scale
+#[cfg(feature = "phantom-enterprise-standards")]
+const MAX_RISK_SCORE: f64 = 10.0;
+
+#[derive(Default)]
+pub struct AssetState {
+    #[cfg(feature = "phantom-enterprise-standards")]
+    inventory: AssetInventory,
+}
+
+/// Risk multiplier of a host's criticality: 0.75 for low up to 1.5 for critical
+#[cfg(feature = "phantom-enterprise-standards")]
+fn risk_factor(criticality: Criticality) -> f64 {
+    0.5 + criticality.weight()
+}
+
+impl AssetState {
+    /// Give matches on inventoried hosts their asset's system context and criticality-weighted risk
+    pub(crate) fn contextualize_matches(&self, tenant_id: &str, matches: &mut [HuntingMatch]) {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        for hunting_match in matches.iter_mut() {
+            let Some(asset) = self.match_host(tenant_id, hunting_match) else {
+                continue;
+            };
+            let previous = hunting_match.context.system_context.take();
+            hunting_match.context.system_context = Some(SystemContext {
+                hostname: asset.name.clone(),
+                os_type: asset.os.clone().or_else(|| previous.as_ref().map(|p| p.os_type.clone())).unwrap_or_default(),
+                criticality_level: asset.criticality.as_str().to_string(),
+                network_zone: asset.network_zone.clone().unwrap_or_default(),
+                installed_software: previous.map(|p| p.installed_software).unwrap_or_default(),
+                security_controls: asset.tags.clone(),
+            });
+            hunting_match.risk_score = (hunting_match.risk_score * risk_factor(asset.criticality)).min(MAX_RISK_SCORE);
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        {
+            let _ = (tenant_id, matches);
+        }
+    }
+
+    /// Inventoried host a match's event names, by host field first, then by address
+    #[cfg(feature = "phantom-enterprise-standards")]
+    fn match_host(&self, tenant_id: &str, hunting_match: &HuntingMatch) -> Option<Asset> {
+        let named = HOST_FIELDS.iter().filter_map(|field| hunting_match.event_data.get(*field)?.as_str().map(str::to_string));
+        let addressed = hunting_match
+            .context
+            .network_context
+            .iter()
+            .flat_map(|network| [network.source_ip.clone(), network.destination_ip.clone()]);
+        named
+            .chain(addressed)
+            .filter_map(|reference| self.inventory.resolve(tenant_id, &reference))
+            .find(|asset| asset.kind == AssetKind::Host)
+    }
+
+    pub(crate) fn forget_tenant(&self, tenant_id: &str) -> usize {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            self.inventory.forget_scope(tenant_id)
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        {
+            let _ = tenant_id;
+            0
+        }
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+impl HuntingCore {
+    /// Create an asset, or update the one with the record's id or kind and name
+    pub fn put_asset(&self, tenant_id: &str, record: AssetRecord) -> Result<Asset, String> {
+        self.assets.inventory.put(tenant_id, record)
+    }
+
+    pub fn remove_asset(&self, tenant_id: &str, id: &str) -> bool {
+        self.assets.inventory.remove(tenant_id, id)
+    }
+
+    pub fn get_asset(&self, tenant_id: &str, id: &str) -> Option<Asset> {
+        self.assets.inventory.get(tenant_id, id)
+    }
+
+    pub fn list_assets(&self, tenant_id: &str) -> Vec<Asset> {
+        self.assets.inventory.list(tenant_id)
+    }
+
+    pub fn resolve_asset(&self, tenant_id: &str, reference: &str) -> Option<Asset> {
+        self.assets.inventory.resolve(tenant_id, reference)
+    }
+
+    pub fn import_assets_csv(&self, tenant_id: &str, data: &str) -> Result<AssetImportReport, String> {
+        self.assets.inventory.import_csv(tenant_id, data)
+    }
+
+    /// Affected assets, dependents and impact scores of `references`
+    pub fn asset_impact(&self, tenant_id: &str, references: &[String]) -> AssetImpact {
+        self.assets.inventory.impact(tenant_id, references)
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[napi]
+impl HuntingCoreNapi {
+    /// Create or update an asset, e.g. `{"kind": "host", "name": "db-01",
+    /// "criticality": "critical", "network_zone": "datacenter"}`
+    #[napi]
+    pub fn put_asset(&self, asset_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", "assets")?;
+        let record: AssetRecord = serde_json::from_str(&asset_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse asset: {}", e)))?;
+        let resource = record.id.clone().unwrap_or_else(|| record.name.clone());
+        let asset = self.inner.put_asset(&tenant_id, record);
+        let asset = self.audit.record(&actor, "put_asset", &resource, serde_json::json!({ "asset": asset_json }), asset)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to store asset: {}", e)))?;
+        serde_json::to_string(&asset)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize asset: {}", e)))
+    }
+
+    #[napi]
+    pub fn remove_asset(&self, id: String, auth_token: Option<String>) -> napi::Result<bool> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &id)?;
+        let removed = self.inner.remove_asset(&tenant_id, &id);
+        self.audit.record(&actor, "remove_asset", &id, serde_json::json!({ "removed": removed }), Ok::<_, String>(removed))
+            .map_err(napi::Error::from_reason)
+    }
+
+    #[napi]
+    pub fn get_asset(&self, id: String, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        self.inner
+            .get_asset(&tenant_id, &id)
+            .map(|asset| serde_json::to_string(&asset))
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize asset: {}", e)))
+    }
+
+    #[napi]
+    pub fn list_assets(&self, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        serde_json::to_string(&self.inner.list_assets(&tenant_id))
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize assets: {}", e)))
+    }
+
+    /// Asset a hostname, account, alias or address refers to
+    #[napi]
+    pub fn resolve_asset(&self, reference: String, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        self.inner
+            .resolve_asset(&tenant_id, &reference)
+            .map(|asset| serde_json::to_string(&asset))
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize asset: {}", e)))
+    }
+
+    /// Import assets from CSV (`kind,name,criticality,owner,network_zone,tags,...`)
+    #[napi]
+    pub fn import_assets_csv(&self, csv: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", "assets")?;
+        let report = self.inner.import_assets_csv(&tenant_id, &csv);
+        let report = self.audit.record(&actor, "import_assets_csv", "assets", serde_json::json!({ "bytes": csv.len() }), report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to import assets: {}", e)))?;
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize import report: {}", e)))
+    }
+
+    /// Impact and blast radius of an incident on `["host", ...]`
+    #[napi]
+    pub fn get_asset_impact(&self, references_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let references: Vec<String> = serde_json::from_str(&references_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse asset references: {}", e)))?;
+        serde_json::to_string(&self.inner.asset_impact(&tenant_id, &references))
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize asset impact: {}", e)))
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use super::*;
+    use crate::connectors::event_match;
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_match_on_inventoried_host_gets_asset_context() {
+        let core = HuntingCore::new().unwrap();
+        let report = core
+            .import_assets_csv("acme", "kind,name,criticality,zone,os,tags,addresses\nhost,DC-01,critical,tier0,Windows Server 2022,edr;lsa-protection,10.1.0.10\n")
+            .unwrap();
+        assert_eq!(report.created, 1);
+
+        let fields = |host: &str| HashMap::from([("hostname".to_string(), json!(host)), ("user".to_string(), json!("svc_backup"))]);
+        let mut matches = vec![
+            event_match("Sysmon", None, fields("dc-01.corp.example"), 0.8, 5.0),
+            event_match("Sysmon", None, fields("ws-77"), 0.8, 5.0),
+        ];
+        core.assets.contextualize_matches("acme", &mut matches);
+
+        let system = matches[0].context.system_context.as_ref().unwrap();
+        assert_eq!(system.hostname, "DC-01");
+        assert_eq!(system.criticality_level, "critical");
+        assert_eq!(system.network_zone, "tier0");
+        assert_eq!(system.security_controls, vec!["edr".to_string(), "lsa-protection".to_string()]);
+        assert!((matches[0].risk_score - 6.0).abs() < 1e-9);
+        assert!(matches[1].context.system_context.is_none());
+        assert!((matches[1].risk_score - 4.0).abs() < 1e-9);
+
+        let mut other_tenant = vec![event_match("Sysmon", None, fields("dc-01"), 0.8, 5.0)];
+        core.assets.contextualize_matches("globex", &mut other_tenant);
+        assert!(other_tenant[0].context.system_context.is_none());
+    }
+}
@@ -0,0 +1,477 @@
+//! Rule back-testing
+//!
+//! Before a new or edited rule goes live, analysts run it over archived
+//! data: the rule's connected sources over a historical window (the last 90
+//! days by default), or a replayed set of events. The window is queried one
+//! bucket at a time, so a busy source does not hit the per-query row cap on
+//! the first day of the window, and the report counts matches per bucket.
+//!
+//! The expected false-positive load comes from the best label available:
+//! the rule's disposition history when it already exists, otherwise the
+//! labeled incidents supplied with the run (matches attributed to none of
+//! them count as false positives), and otherwise the matches' own
+//! confidence. Each labeled incident is reported as caught or missed, with
+//! the delay between its start and the rule's first match. True-positive
+//! dispositions of the rule inside the window count as labeled incidents
+//! too, so an edit that stops matching a confirmed detection shows up as a
+//! missed incident.
+
+use chrono::{DateTime, Duration, Utc};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::cancellation::HuntCompletion;
+use crate::connectors::QueryWindow;
+use crate::correlation;
+use crate::error::{CoreError, CoreResult};
+use crate::ingestion::NormalizedEvent;
+use crate::shadow::{fingerprint, replay_events};
+use crate::tuning::MatchDisposition;
+use crate::{tenancy, HuntingCore, HuntingCoreNapi, HuntingMatch, HuntingRule};
+
+/// Window back-tested when the request names none
+pub const DEFAULT_BACKTEST_DAYS: i64 = 90;
+/// Buckets, and so connector queries per source, one back-test may run
+pub const MAX_BACKTEST_BUCKETS: usize = 800;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BucketSize {
+    Hour,
+    #[default]
+    Day,
+}
+
+impl BucketSize {
+    fn duration(self) -> Duration {
+        match self {
+            BucketSize::Hour => Duration::hours(1),
+            BucketSize::Day => Duration::days(1),
+        }
+    }
+}
+
+/// A past incident the rule is expected to detect
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabeledIncident {
+    pub incident_id: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// Event fields a match must carry to belong to the incident, such as
+    /// the host or account involved; strings compare case-insensitively
+    #[serde(default)]
+    pub indicators: HashMap<String, Value>,
+}
+
+impl LabeledIncident {
+    fn covers(&self, hunt_match: &HuntingMatch) -> bool {
+        hunt_match.timestamp >= self.start
+            && hunt_match.timestamp <= self.end
+            && self.indicators.iter().all(|(field, expected)| hunt_match.event_data.get(field).is_some_and(|actual| same_value(actual, expected)))
+    }
+}
+
+fn same_value(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::String(actual), Value::String(expected)) => actual.eq_ignore_ascii_case(expected),
+        (Value::String(text), other) | (other, Value::String(text)) if !other.is_object() && !other.is_array() => text == &other.to_string(),
+        _ => actual == expected,
+    }
+}
+
+/// What to back-test and over which data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestRequest {
+    /// New or edited rule; it is run as given and never stored
+    pub rule: HuntingRule,
+    /// Defaults to the last `DEFAULT_BACKTEST_DAYS` days
+    #[serde(default)]
+    pub window: Option<QueryWindow>,
+    #[serde(default)]
+    pub bucket: BucketSize,
+    #[serde(default)]
+    pub labeled_incidents: Vec<LabeledIncident>,
+    /// Id to run under, so the back-test can be cancelled with `cancel_hunt`
+    #[serde(default)]
+    pub backtest_id: Option<String>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+/// Matches the rule produced in one bucket of the window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestBucket {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub matches: usize,
+    pub estimated_false_positives: f64,
+}
+
+/// Where the false-positive estimate comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FalsePositiveBasis {
+    /// Analyst dispositions of the rule's past matches
+    Dispositions,
+    /// Matches attributed to no labeled incident
+    IncidentLabels,
+    /// One minus the matches' mean confidence; no labels were available
+    Confidence,
+}
+
+/// Whether the rule would have caught one labeled incident
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentAssessment {
+    pub incident_id: String,
+    pub title: Option<String>,
+    /// False for incidents outside the window, which are not assessed
+    pub in_window: bool,
+    pub caught: bool,
+    pub matches: usize,
+    pub first_match_at: Option<DateTime<Utc>>,
+    /// Seconds from the incident's start to the first match
+    pub detection_delay_secs: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestReport {
+    pub backtest_id: String,
+    pub tenant_id: String,
+    pub rule_id: String,
+    pub rule_version: String,
+    pub window: QueryWindow,
+    pub bucket: BucketSize,
+    /// Replayed events, or rows read from connected sources
+    pub events_processed: u64,
+    pub total_matches: usize,
+    /// Matches the tenant's suppressions would have dropped
+    pub suppressed_matches: u64,
+    pub buckets: Vec<BacktestBucket>,
+    pub matches_per_day: f64,
+    pub false_positive_rate: f64,
+    pub false_positive_basis: FalsePositiveBasis,
+    pub estimated_false_positives_per_day: f64,
+    pub peak_bucket_matches: usize,
+    pub incidents: Vec<IncidentAssessment>,
+    pub incidents_caught: usize,
+    /// Share of the in-window incidents caught; `None` without any
+    pub incident_recall: Option<f64>,
+    /// Source failures; the report misses their rows
+    #[serde(default)]
+    pub source_errors: Vec<String>,
+    pub completion: HuntCompletion,
+    pub generated_at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+pub struct BacktestState {
+    /// Latest report keyed by tenant and rule id
+    reports: RwLock<HashMap<(String, String), BacktestReport>>,
+}
+
+impl BacktestState {
+    pub(crate) async fn forget_tenant(&self, tenant_id: &str) -> usize {
+        let mut reports = self.reports.write().await;
+        let before = reports.len();
+        reports.retain(|(tenant, _), _| tenant != tenant_id);
+        before - reports.len()
+    }
+}
+
+/// Consecutive buckets of `size` covering `window`; the last one may be shorter
+fn bucket_windows(window: &QueryWindow, size: BucketSize) -> Vec<QueryWindow> {
+    let mut buckets = Vec::new();
+    let mut start = window.start;
+    while start < window.end {
+        let end = (start + size.duration()).min(window.end);
+        buckets.push(QueryWindow { start, end });
+        start = end;
+    }
+    buckets
+}
+
+impl HuntingCore {
+    /// Run a new or edited rule over a historical window and report its
+    /// match volume over time, expected false-positive load and the labeled
+    /// incidents it would have caught. Replays `events` when given; otherwise
+    /// queries the rule's connected sources one bucket at a time.
+    pub async fn backtest_rule(&self, tenant_id: &str, request: BacktestRequest, events: &[NormalizedEvent]) -> CoreResult<BacktestReport> {
+        let BacktestRequest { mut rule, window, bucket, labeled_incidents, backtest_id, timeout_ms } = request;
+        if let Some(existing) = self.rules.read().await.get(&rule.id) {
+            if !tenancy::rule_visible(existing, tenant_id) {
+                return Err(CoreError::validation(format!("Rule {} belongs to another tenant", rule.id)));
+            }
+        }
+        rule.tenant_id = Some(tenant_id.to_string());
+
+        let window = match window {
+            Some(window) if window.start >= window.end => {
+                return Err(CoreError::validation("Back-test window must start before it ends"));
+            }
+            Some(window) => window,
+            None => {
+                let end = Utc::now();
+                QueryWindow { start: end - Duration::days(DEFAULT_BACKTEST_DAYS), end }
+            }
+        };
+        let slices = bucket_windows(&window, bucket);
+        if slices.len() > MAX_BACKTEST_BUCKETS {
+            return Err(CoreError::validation(format!(
+                "Back-test window spans {} buckets, more than {}; use larger buckets or a shorter window",
+                slices.len(),
+                MAX_BACKTEST_BUCKETS
+            )));
+        }
+        if let Some(incident) = labeled_incidents.iter().find(|incident| incident.start > incident.end) {
+            return Err(CoreError::validation(format!("Incident {} ends before it starts", incident.incident_id)));
+        }
+        let connected = self.connected_sources(&rule).await;
+        if events.is_empty() && connected.is_empty() {
+            return Err(CoreError::validation(format!("Rule {} has no connected data sources; supply events to back-test", rule.id)));
+        }
+
+        let backtest_id = backtest_id.unwrap_or_else(|| format!("backtest_{}", Uuid::new_v4().simple()));
+        let timeout = timeout_ms.map(std::time::Duration::from_millis);
+        let running = self.running_hunts.start(&backtest_id, tenant_id, &rule.id, timeout)?;
+        let (mut matches, events_processed, source_errors) = if events.is_empty() {
+            let mut matches = Vec::new();
+            let mut seen = HashSet::new();
+            let (mut events_processed, mut source_errors) = (0, Vec::new());
+            for slice in &slices {
+                if running.abort().outcome().is_some() {
+                    break;
+                }
+                let hunt = self.hunt_connected_sources(&rule, connected.clone(), None, Some(slice), running.abort()).await;
+                events_processed += hunt.events_processed;
+                source_errors.extend(hunt.errors.into_iter().map(|e| format!("{} - {}: {}", slice.start.to_rfc3339(), slice.end.to_rfc3339(), e)));
+                // Rows on a bucket boundary are returned by both queries
+                matches.extend(hunt.matches.into_iter().filter(|m| seen.insert(fingerprint(m))));
+            }
+            let correlated = correlation::correlate_matches(&rule, &matches);
+            matches.extend(correlated);
+            (matches, events_processed, source_errors)
+        } else {
+            let replayed: Vec<NormalizedEvent> = events
+                .iter()
+                .filter(|event| event.timestamp >= window.start && event.timestamp <= window.end)
+                .cloned()
+                .collect();
+            (replay_events(&rule, &replayed), replayed.len() as u64, Vec::new())
+        };
+        let completion = running.abort().outcome().unwrap_or_default();
+        drop(running);
+        let suppressed_matches = self.suppression.suppress_matches(tenant_id, &mut matches);
+        matches.sort_by_key(|m| m.timestamp);
+
+        // Confirmed detections of the rule are incidents it should keep catching
+        let dispositions = self.tuning.rule_dispositions(tenant_id, &rule.id).await;
+        let mut incidents = labeled_incidents;
+        incidents.extend(dispositions.iter().filter(|record| record.disposition == MatchDisposition::TruePositive).map(|record| LabeledIncident {
+            incident_id: format!("disposition:{}", record.match_id),
+            title: record.note.clone(),
+            start: record.match_timestamp,
+            end: record.match_timestamp,
+            indicators: record.event_data.clone(),
+        }));
+
+        let attributed: Vec<bool> = matches.iter().map(|m| incidents.iter().any(|incident| incident.covers(m))).collect();
+        let (false_positive_rate, false_positive_basis) = if !dispositions.is_empty() {
+            let false_positives = dispositions.iter().filter(|r| r.disposition == MatchDisposition::FalsePositive).count();
+            (false_positives as f64 / dispositions.len() as f64, FalsePositiveBasis::Dispositions)
+        } else if !incidents.is_empty() {
+            let unattributed = attributed.iter().filter(|a| !**a).count();
+            (unattributed as f64 / matches.len().max(1) as f64, FalsePositiveBasis::IncidentLabels)
+        } else {
+            let confidence = matches.iter().map(|m| m.confidence_score.clamp(0.0, 1.0)).sum::<f64>() / matches.len().max(1) as f64;
+            (if matches.is_empty() { 0.0 } else { 1.0 - confidence }, FalsePositiveBasis::Confidence)
+        };
+
+        let mut buckets: Vec<BacktestBucket> = slices
+            .iter()
+            .map(|slice| BacktestBucket { start: slice.start, end: slice.end, matches: 0, estimated_false_positives: 0.0 })
+            .collect();
+        for (hunt_match, attributed) in matches.iter().zip(&attributed) {
+            let Some(bucket) = buckets.iter_mut().rev().find(|b| hunt_match.timestamp >= b.start) else { continue };
+            bucket.matches += 1;
+            bucket.estimated_false_positives += match false_positive_basis {
+                FalsePositiveBasis::IncidentLabels if *attributed => 0.0,
+                FalsePositiveBasis::IncidentLabels => 1.0,
+                _ => false_positive_rate,
+            };
+        }
+
+        let incidents: Vec<IncidentAssessment> = incidents
+            .iter()
+            .map(|incident| {
+                let in_window = incident.end >= window.start && incident.start <= window.end;
+                let covered: Vec<&HuntingMatch> = matches.iter().filter(|m| incident.covers(m)).collect();
+                let first_match_at = covered.first().map(|m| m.timestamp);
+                IncidentAssessment {
+                    incident_id: incident.incident_id.clone(),
+                    title: incident.title.clone(),
+                    in_window,
+                    caught: !covered.is_empty(),
+                    matches: covered.len(),
+                    first_match_at,
+                    detection_delay_secs: first_match_at.map(|at| (at - incident.start).num_seconds()),
+                }
+            })
+            .collect();
+        let assessed = incidents.iter().filter(|i| i.in_window).count();
+        let incidents_caught = incidents.iter().filter(|i| i.in_window && i.caught).count();
+
+        let days = (window.end - window.start).num_seconds() as f64 / 86_400.0;
+        let estimated_false_positives: f64 = buckets.iter().map(|b| b.estimated_false_positives).sum();
+        let report = BacktestReport {
+            backtest_id,
+            tenant_id: tenant_id.to_string(),
+            rule_id: rule.id.clone(),
+            rule_version: rule.metadata.version.clone(),
+            window,
+            bucket,
+            events_processed,
+            total_matches: matches.len(),
+            suppressed_matches,
+            peak_bucket_matches: buckets.iter().map(|b| b.matches).max().unwrap_or(0),
+            buckets,
+            matches_per_day: matches.len() as f64 / days,
+            false_positive_rate,
+            false_positive_basis,
+            estimated_false_positives_per_day: estimated_false_positives / days,
+            incidents,
+            incidents_caught,
+            incident_recall: (assessed > 0).then(|| incidents_caught as f64 / assessed as f64),
+            source_errors,
+            completion,
+            generated_at: Utc::now(),
+        };
+        self.backtest.reports.write().await.insert((tenant_id.to_string(), rule.id.clone()), report.clone());
+        log::info!(
+            "Back-tested rule {} over {} days: {} matches, {:.1} estimated false positives per day, {}/{} incidents caught",
+            rule.id, days.round(), report.total_matches, report.estimated_false_positives_per_day, incidents_caught, assessed
+        );
+        Ok(report)
+    }
+
+    /// Latest back-test report of a rule
+    pub async fn backtest_report(&self, tenant_id: &str, rule_id: &str) -> Option<BacktestReport> {
+        self.backtest.reports.read().await.get(&(tenant_id.to_string(), rule_id.to_string())).cloned()
+    }
+}
+
+#[napi]
+impl HuntingCoreNapi {
+    /// Back-test a rule (`{"rule": ..., "window": ..., "bucket": "day",
+    /// "labeled_incidents": [...]}`) over a JSON array of events, or over
+    /// its connected sources when no events are given
+    #[napi]
+    pub async fn backtest_rule(&self, request_json: String, events_json: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let request: BacktestRequest = serde_json::from_str(&request_json)
+            .map_err(|e| CoreError::from(e).context("Failed to parse back-test request"))?;
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", "backtest")?;
+        let records: Vec<Value> = events_json
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()
+            .map_err(|e| CoreError::from(e).context("Failed to parse events"))?
+            .unwrap_or_default();
+        let events = correlation::events_from_records(&records);
+
+        let rule_id = request.rule.id.clone();
+        let params = json!({ "window": request.window, "bucket": request.bucket, "incidents": request.labeled_incidents.len(), "events": records.len() });
+        let report = self.inner.backtest_rule(&tenant_id, request, &events).await;
+        let report = self.audit.record(&actor, "backtest_rule", &rule_id, params, report)
+            .map_err(|e| e.context("Failed to back-test rule"))?;
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize back-test report: {}", e)))
+    }
+
+    #[napi]
+    pub async fn get_backtest_report(&self, rule_id: String, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        self.inner.backtest_report(&tenant_id, &rule_id).await
+            .map(|report| serde_json::to_string(&report))
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize back-test report: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenancy::DEFAULT_TENANT;
+    use crate::DetectionCondition;
+
+    fn event(days_ago: i64, host: &str, failures: u64) -> NormalizedEvent {
+        let timestamp = Utc::now() - Duration::days(days_ago) - Duration::minutes(5);
+        NormalizedEvent {
+            timestamp,
+            received_at: timestamp,
+            source: "auth".to_string(),
+            fields: HashMap::from([("host".to_string(), json!(host)), ("failures".to_string(), json!(failures))]),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_backtest_reports_daily_load_and_incidents() {
+        let core = HuntingCore::new().unwrap();
+        let mut rule = core.list_rules(DEFAULT_TENANT).await.unwrap().remove(0);
+        rule.id = "brute_force_v2".to_string();
+        rule.detection_logic.correlation_rules.clear();
+        rule.detection_logic.conditions = vec![DetectionCondition {
+            condition_id: "failures".to_string(),
+            field: "failures".to_string(),
+            operator: "gt".to_string(),
+            value: json!(5),
+            weight: 1.0,
+            required: true,
+        }];
+
+        let events = vec![event(40, "db01", 9), event(40, "web02", 12), event(10, "web02", 3), event(2, "kiosk", 7), event(120, "db01", 50)];
+        let incident_start = Utc::now() - Duration::days(41);
+        let request = BacktestRequest {
+            rule,
+            window: None,
+            bucket: BucketSize::Day,
+            labeled_incidents: vec![
+                LabeledIncident {
+                    incident_id: "INC-1".to_string(),
+                    title: Some("db01 credential stuffing".to_string()),
+                    start: incident_start,
+                    end: incident_start + Duration::days(2),
+                    indicators: HashMap::from([("host".to_string(), json!("DB01"))]),
+                },
+                LabeledIncident {
+                    incident_id: "INC-2".to_string(),
+                    title: None,
+                    start: Utc::now() - Duration::days(11),
+                    end: Utc::now() - Duration::days(9),
+                    indicators: HashMap::from([("host".to_string(), json!("web02"))]),
+                },
+            ],
+            backtest_id: None,
+            timeout_ms: None,
+        };
+
+        let report = core.backtest_rule(DEFAULT_TENANT, request, &events).await.unwrap();
+        assert_eq!((report.events_processed, report.total_matches), (4, 3));
+        assert_eq!(report.buckets.len(), DEFAULT_BACKTEST_DAYS as usize);
+        assert_eq!(report.buckets.iter().map(|b| b.matches).sum::<usize>(), 3);
+        assert_eq!(report.false_positive_basis, FalsePositiveBasis::IncidentLabels);
+        // web02 and kiosk on days 40 and 2 belong to no incident
+        assert!((report.estimated_false_positives_per_day - 2.0 / 90.0).abs() < 1e-6);
+        let caught: Vec<(&str, bool)> = report.incidents.iter().map(|i| (i.incident_id.as_str(), i.caught)).collect();
+        assert_eq!(caught, [("INC-1", true), ("INC-2", false)]);
+        assert!(report.incidents[0].detection_delay_secs.unwrap() > 0);
+        assert_eq!(report.incident_recall, Some(0.5));
+        assert_eq!(core.backtest_report(DEFAULT_TENANT, "brute_force_v2").await.unwrap().backtest_id, report.backtest_id);
+        assert_eq!(core.backtest.forget_tenant(DEFAULT_TENANT).await, 1);
+    }
+}
@@ -0,0 +1,755 @@
+//! Behavioural baseline learning
+//!
+//! Historical logons, network flows and process launches are ingested in
+//! batches and folded into per-day statistics for every user, every system
+//! and the network as a whole. A refresh derives baselines from the days
+//! inside the training window — hourly activity histograms, typical values
+//! (source addresses, processes, destinations, protocols), and mean and
+//! standard deviation of daily volume for z-scoring — and writes them into
+//! the core's `BehavioralBaselines`. Training state and the derived
+//! baselines can be persisted to a JSON file and are refreshed in the
+//! background every `baseline_update_frequency` hours.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{watch, Mutex, RwLock};
+use tokio::task::JoinHandle;
+
+use crate::{
+    BandwidthPattern, BehavioralBaselines, HuntingCore, NetworkBaseline, ResourceUsageBaseline, SystemBaseline,
+    TrafficPattern, UserBaseline,
+};
+
+/// Values kept per attribute when listing what is typical for a subject
+const TYPICAL_VALUES: usize = 10;
+/// Hours carrying less than this share of a subject's activity are unusual
+const UNUSUAL_HOUR_SHARE: f64 = 0.02;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event_type", rename_all = "snake_case")]
+pub enum HistoricalEvent {
+    Logon {
+        timestamp: DateTime<Utc>,
+        user: String,
+        host: String,
+        #[serde(default)]
+        source_ip: Option<String>,
+    },
+    NetworkFlow {
+        timestamp: DateTime<Utc>,
+        host: String,
+        destination: String,
+        #[serde(default)]
+        port: Option<u16>,
+        protocol: String,
+        #[serde(default)]
+        bytes: u64,
+    },
+    ProcessLaunch {
+        timestamp: DateTime<Utc>,
+        user: String,
+        host: String,
+        process: String,
+        #[serde(default)]
+        parent_process: Option<String>,
+    },
+}
+
+impl HistoricalEvent {
+    pub fn timestamp(&self) -> DateTime<Utc> {
+        match self {
+            HistoricalEvent::Logon { timestamp, .. }
+            | HistoricalEvent::NetworkFlow { timestamp, .. }
+            | HistoricalEvent::ProcessLaunch { timestamp, .. } => *timestamp,
+        }
+    }
+}
+
+fn destination_label(destination: &str, port: Option<u16>) -> String {
+    match port {
+        Some(port) => format!("{}:{}", destination, port),
+        None => destination.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct DayStats {
+    hourly: [u64; 24],
+    hourly_bytes: [u64; 24],
+    attributes: HashMap<String, HashMap<String, u64>>,
+}
+
+impl DayStats {
+    fn total(&self) -> u64 {
+        self.hourly.iter().sum()
+    }
+
+    fn bytes(&self) -> u64 {
+        self.hourly_bytes.iter().sum()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SubjectHistory {
+    days: BTreeMap<NaiveDate, DayStats>,
+}
+
+impl SubjectHistory {
+    fn record(&mut self, at: DateTime<Utc>, bytes: u64, attributes: &[(&str, &str)]) {
+        let day = self.days.entry(at.date_naive()).or_default();
+        let hour = at.hour() as usize;
+        day.hourly[hour] += 1;
+        day.hourly_bytes[hour] += bytes;
+        for (key, value) in attributes {
+            *day.attributes
+                .entry(key.to_string())
+                .or_default()
+                .entry(value.to_string())
+                .or_insert(0) += 1;
+        }
+    }
+
+    fn prune(&mut self, oldest: NaiveDate) {
+        self.days = self.days.split_off(&oldest);
+    }
+
+    fn profile(&self, subject: &str) -> ActivityProfile {
+        let mut hourly = [0u64; 24];
+        let mut counts: HashMap<&str, HashMap<&str, u64>> = HashMap::new();
+        for day in self.days.values() {
+            for (hour, count) in day.hourly.iter().enumerate() {
+                hourly[hour] += count;
+            }
+            for (key, values) in &day.attributes {
+                let merged = counts.entry(key.as_str()).or_default();
+                for (value, count) in values {
+                    *merged.entry(value.as_str()).or_insert(0) += count;
+                }
+            }
+        }
+
+        let observations: u64 = hourly.iter().sum();
+        let typical = counts
+            .into_iter()
+            .map(|(key, values)| {
+                let mut values: Vec<(&str, u64)> = values.into_iter().collect();
+                values.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+                let values = values.into_iter().take(TYPICAL_VALUES).map(|(value, _)| value.to_string()).collect();
+                (key.to_string(), values)
+            })
+            .collect();
+
+        ActivityProfile {
+            subject: subject.to_string(),
+            observations,
+            days_observed: self.days.len(),
+            hourly_histogram: hourly
+                .iter()
+                .map(|count| if observations == 0 { 0.0 } else { *count as f64 / observations as f64 })
+                .collect(),
+            daily_volume: VolumeStats::from_samples(self.days.values().map(|day| day.total() as f64)),
+            typical,
+        }
+    }
+}
+
+/// Mean and standard deviation of a daily quantity
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VolumeStats {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub samples: usize,
+}
+
+impl VolumeStats {
+    fn from_samples(samples: impl Iterator<Item = f64>) -> Self {
+        let samples: Vec<f64> = samples.collect();
+        if samples.is_empty() {
+            return Self::default();
+        }
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance = samples.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+        Self {
+            mean,
+            std_dev: variance.sqrt(),
+            samples: samples.len(),
+        }
+    }
+
+    /// Standard deviations `value` lies from the mean; `None` without spread
+    pub fn z_score(&self, value: f64) -> Option<f64> {
+        (self.std_dev > f64::EPSILON).then(|| (value - self.mean) / self.std_dev)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityProfile {
+    pub subject: String,
+    pub observations: u64,
+    pub days_observed: usize,
+    /// Share of activity falling in each hour of the day (UTC)
+    pub hourly_histogram: Vec<f64>,
+    /// Events per day, for z-scoring a day's volume
+    pub daily_volume: VolumeStats,
+    /// Most frequent values per attribute (`source_ip`, `process`, `destination`, ...)
+    pub typical: HashMap<String, Vec<String>>,
+}
+
+impl ActivityProfile {
+    fn typical(&self, attribute: &str) -> Vec<String> {
+        self.typical.get(attribute).cloned().unwrap_or_default()
+    }
+
+    fn is_typical(&self, attribute: &str, value: &str) -> bool {
+        self.typical.get(attribute).is_some_and(|values| values.iter().any(|typical| typical == value))
+    }
+
+    fn hour_share(&self, hour: u32) -> f64 {
+        self.hourly_histogram.get(hour as usize).copied().unwrap_or(0.0)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkProfile {
+    pub activity: ActivityProfile,
+    /// Mean bytes transferred in each hour of the day
+    pub hourly_bytes: Vec<f64>,
+    /// Mean bytes per day, Monday first
+    pub weekday_bytes: Vec<f64>,
+    pub daily_bytes: VolumeStats,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LearnedBaselines {
+    pub trained_at: DateTime<Utc>,
+    pub window_start: Option<NaiveDate>,
+    pub window_end: Option<NaiveDate>,
+    pub events_ingested: u64,
+    pub users: HashMap<String, ActivityProfile>,
+    pub systems: HashMap<String, ActivityProfile>,
+    pub network: NetworkProfile,
+}
+
+/// How an event compares with the learned baselines
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineAssessment {
+    /// Share of the subject's activity usually seen in this hour
+    pub hour_share: f64,
+    pub unusual_hour: bool,
+    /// `attribute=value` pairs not among the subject's typical values
+    pub novel_attributes: Vec<String>,
+    /// False when the subject has no baseline yet
+    pub known_subject: bool,
+}
+
+impl LearnedBaselines {
+    pub fn assess(&self, event: &HistoricalEvent) -> BaselineAssessment {
+        let hour = event.timestamp().hour();
+        let (profile, checks): (Option<&ActivityProfile>, Vec<(&str, String)>) = match event {
+            HistoricalEvent::Logon { user, host, source_ip, .. } => {
+                let mut checks = vec![("host", host.clone())];
+                checks.extend(source_ip.clone().map(|ip| ("source_ip", ip)));
+                (self.users.get(user), checks)
+            }
+            HistoricalEvent::ProcessLaunch { user, process, .. } => {
+                (self.users.get(user), vec![("process", process.clone())])
+            }
+            HistoricalEvent::NetworkFlow { host, destination, port, protocol, .. } => (
+                self.systems.get(host),
+                vec![
+                    ("destination", destination_label(destination, *port)),
+                    ("protocol", protocol.clone()),
+                ],
+            ),
+        };
+
+        let Some(profile) = profile else {
+            return BaselineAssessment {
+                hour_share: 0.0,
+                unusual_hour: true,
+                novel_attributes: checks.into_iter().map(|(key, value)| format!("{}={}", key, value)).collect(),
+                known_subject: false,
+            };
+        };
+
+        let hour_share = profile.hour_share(hour);
+        BaselineAssessment {
+            hour_share,
+            unusual_hour: hour_share < UNUSUAL_HOUR_SHARE,
+            novel_attributes: checks
+                .into_iter()
+                .filter(|(key, value)| !profile.is_typical(key, value))
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect(),
+            known_subject: true,
+        }
+    }
+
+    /// Write the learned values into the core's baseline model, keeping
+    /// fields the training data says nothing about
+    pub fn apply_to(&self, baselines: &mut BehavioralBaselines) {
+        for (user_id, profile) in &self.users {
+            let baseline = baselines.user_baselines.entry(user_id.clone()).or_insert_with(|| UserBaseline {
+                user_id: user_id.clone(),
+                typical_login_times: vec![],
+                typical_locations: vec![],
+                typical_applications: vec![],
+                data_access_patterns: vec![],
+                risk_score: 0.0,
+            });
+            let mut hours: Vec<u32> = profile
+                .typical("logon_hour")
+                .iter()
+                .filter_map(|hour| hour.parse().ok())
+                .collect();
+            hours.sort_unstable();
+            baseline.typical_login_times = hours
+                .into_iter()
+                .map(|hour| format!("{:02}:00-{:02}:00", hour, (hour + 1) % 24))
+                .collect();
+            baseline.typical_locations = profile.typical("source_ip");
+            baseline.typical_applications = profile.typical("process");
+        }
+
+        for (system_id, profile) in &self.systems {
+            let baseline = baselines.system_baselines.entry(system_id.clone()).or_insert_with(|| SystemBaseline {
+                system_id: system_id.clone(),
+                normal_processes: vec![],
+                network_connections: vec![],
+                file_access_patterns: vec![],
+                resource_usage: ResourceUsageBaseline {
+                    avg_cpu_usage: 0.0,
+                    avg_memory_usage: 0.0,
+                    avg_disk_io: 0.0,
+                    avg_network_io: 0.0,
+                },
+            });
+            baseline.normal_processes = profile.typical("process");
+            baseline.network_connections = profile.typical("destination");
+        }
+
+        let network = &self.network;
+        let mut peak_hours: Vec<(usize, f64)> = network.hourly_bytes.iter().copied().enumerate().collect();
+        peak_hours.sort_by(|a, b| b.1.total_cmp(&a.1));
+        let daily = &network.daily_bytes;
+        baselines.network_baselines = NetworkBaseline {
+            normal_traffic_patterns: network
+                .activity
+                .typical("flow")
+                .iter()
+                .filter_map(|flow| {
+                    let mut parts = flow.splitn(3, '|');
+                    Some(TrafficPattern {
+                        source: parts.next()?.to_string(),
+                        destination: parts.next()?.to_string(),
+                        protocol: parts.next()?.to_string(),
+                        frequency: 0,
+                        time_patterns: vec![],
+                    })
+                })
+                .collect(),
+            typical_protocols: network.activity.typical("protocol"),
+            typical_destinations: network.activity.typical("destination"),
+            bandwidth_patterns: BandwidthPattern {
+                hourly_averages: network.hourly_bytes.clone(),
+                daily_averages: network.weekday_bytes.clone(),
+                peak_usage_times: peak_hours
+                    .into_iter()
+                    .filter(|(_, bytes)| *bytes > 0.0)
+                    .take(3)
+                    .map(|(hour, _)| format!("{:02}:00-{:02}:00", hour, (hour + 1) % 24))
+                    .collect(),
+                // Daily byte volumes at 2, 3 and 5 standard deviations
+                anomaly_thresholds: [2.0, 3.0, 5.0].iter().map(|k| daily.mean + k * daily.std_dev).collect(),
+            },
+        };
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestSummary {
+    pub accepted: usize,
+    /// Events older than the training window
+    pub skipped: usize,
+    pub events_ingested: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineTrainer {
+    users: HashMap<String, SubjectHistory>,
+    systems: HashMap<String, SubjectHistory>,
+    network: SubjectHistory,
+    events_ingested: u64,
+    /// Days of history baselines are computed over
+    pub window_days: i64,
+}
+
+impl Default for BaselineTrainer {
+    fn default() -> Self {
+        Self::new(30)
+    }
+}
+
+impl BaselineTrainer {
+    pub fn new(window_days: i64) -> Self {
+        Self {
+            users: HashMap::new(),
+            systems: HashMap::new(),
+            network: SubjectHistory::default(),
+            events_ingested: 0,
+            window_days: window_days.max(1),
+        }
+    }
+
+    fn oldest_day(&self, now: DateTime<Utc>) -> NaiveDate {
+        (now - Duration::days(self.window_days - 1)).date_naive()
+    }
+
+    pub fn ingest(&mut self, events: &[HistoricalEvent]) -> IngestSummary {
+        let oldest = self.oldest_day(Utc::now());
+        let mut accepted = 0;
+        for event in events {
+            if event.timestamp().date_naive() < oldest {
+                continue;
+            }
+            accepted += 1;
+            self.record(event);
+        }
+        self.events_ingested += accepted as u64;
+        IngestSummary {
+            accepted,
+            skipped: events.len() - accepted,
+            events_ingested: self.events_ingested,
+        }
+    }
+
+    fn record(&mut self, event: &HistoricalEvent) {
+        let at = event.timestamp();
+        match event {
+            HistoricalEvent::Logon { user, host, source_ip, .. } => {
+                let hour = format!("{:02}", at.hour());
+                let mut attributes = vec![("host", host.as_str()), ("logon_hour", hour.as_str())];
+                attributes.extend(source_ip.as_deref().map(|ip| ("source_ip", ip)));
+                self.users.entry(user.clone()).or_default().record(at, 0, &attributes);
+                self.systems.entry(host.clone()).or_default().record(at, 0, &[("user", user)]);
+            }
+            HistoricalEvent::ProcessLaunch { user, host, process, parent_process, .. } => {
+                self.users.entry(user.clone()).or_default().record(at, 0, &[("process", process)]);
+                let mut attributes = vec![("process", process.as_str())];
+                attributes.extend(parent_process.as_deref().map(|parent| ("parent_process", parent)));
+                self.systems.entry(host.clone()).or_default().record(at, 0, &attributes);
+            }
+            HistoricalEvent::NetworkFlow { host, destination, port, protocol, bytes, .. } => {
+                let target = destination_label(destination, *port);
+                self.systems
+                    .entry(host.clone())
+                    .or_default()
+                    .record(at, *bytes, &[("destination", &target), ("protocol", protocol)]);
+                let flow = format!("{}|{}|{}", host, target, protocol);
+                self.network.record(
+                    at,
+                    *bytes,
+                    &[("destination", &target), ("protocol", protocol), ("flow", &flow)],
+                );
+            }
+        }
+    }
+
+    /// Drop days that have left the window and derive baselines from the rest
+    pub fn train(&mut self, now: DateTime<Utc>) -> LearnedBaselines {
+        let oldest = self.oldest_day(now);
+        for history in self.users.values_mut().chain(self.systems.values_mut()) {
+            history.prune(oldest);
+        }
+        self.network.prune(oldest);
+        self.users.retain(|_, history| !history.days.is_empty());
+        self.systems.retain(|_, history| !history.days.is_empty());
+
+        let days = self.network.days.len().max(1) as f64;
+        let mut hourly_bytes = vec![0.0; 24];
+        let mut weekday_totals = [(0.0, 0u32); 7];
+        for (date, day) in &self.network.days {
+            for (hour, bytes) in day.hourly_bytes.iter().enumerate() {
+                hourly_bytes[hour] += *bytes as f64 / days;
+            }
+            let weekday = &mut weekday_totals[date.weekday().num_days_from_monday() as usize];
+            weekday.0 += day.bytes() as f64;
+            weekday.1 += 1;
+        }
+
+        let all_days = self
+            .users
+            .values()
+            .chain(self.systems.values())
+            .chain(std::iter::once(&self.network))
+            .flat_map(|history| history.days.keys());
+        LearnedBaselines {
+            trained_at: now,
+            window_start: all_days.clone().min().copied(),
+            window_end: all_days.max().copied(),
+            events_ingested: self.events_ingested,
+            users: self.users.iter().map(|(id, history)| (id.clone(), history.profile(id))).collect(),
+            systems: self.systems.iter().map(|(id, history)| (id.clone(), history.profile(id))).collect(),
+            network: NetworkProfile {
+                activity: self.network.profile("network"),
+                hourly_bytes,
+                weekday_bytes: weekday_totals
+                    .iter()
+                    .map(|(total, days)| if *days == 0 { 0.0 } else { total / *days as f64 })
+                    .collect(),
+                daily_bytes: VolumeStats::from_samples(self.network.days.values().map(|day| day.bytes() as f64)),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PersistedBaselines {
+    trainer: BaselineTrainer,
+    learned: Option<LearnedBaselines>,
+}
+
+fn save_state(path: &Path, state: &PersistedBaselines) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let content = serde_json::to_vec(state).map_err(|e| format!("Failed to encode baselines: {}", e))?;
+    let staging = path.with_extension("tmp");
+    std::fs::write(&staging, content).map_err(|e| format!("Failed to write {}: {}", staging.display(), e))?;
+    std::fs::rename(&staging, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
+
+fn load_state(path: &Path) -> Result<Option<PersistedBaselines>, String> {
+    match std::fs::read(path) {
+        Ok(content) => serde_json::from_slice(&content)
+            .map(Some)
+            .map_err(|e| format!("Invalid baseline file {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+struct RefreshRun {
+    stop: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+}
+
+/// Training state, the last derived baselines and the refresh task
+#[derive(Default)]
+pub struct BaselineLearner {
+    trainer: Mutex<BaselineTrainer>,
+    learned: RwLock<Option<LearnedBaselines>>,
+    store: RwLock<Option<PathBuf>>,
+    refresh: Mutex<Option<RefreshRun>>,
+}
+
+impl HuntingCore {
+    /// Fold a batch of historical events into the training state
+    pub async fn ingest_baseline_events(&self, events: &[HistoricalEvent]) -> IngestSummary {
+        self.baseline_learner.trainer.lock().await.ingest(events)
+    }
+
+    /// Persist training state and baselines to `path`, loading whatever an
+    /// earlier run left there. Returns whether existing state was loaded.
+    pub async fn set_baseline_store(&self, path: PathBuf) -> Result<bool, String> {
+        let loaded = load_state(&path)?;
+        let restored = loaded.is_some();
+        if let Some(state) = loaded {
+            *self.baseline_learner.trainer.lock().await = state.trainer;
+            if let Some(learned) = &state.learned {
+                learned.apply_to(&mut *self.baselines.write().await);
+            }
+            *self.baseline_learner.learned.write().await = state.learned;
+        }
+        *self.baseline_learner.store.write().await = Some(path);
+        Ok(restored)
+    }
+
+    /// Recompute baselines from the training window, apply and persist them
+    pub async fn refresh_baselines(&self) -> Result<LearnedBaselines, String> {
+        let mut trainer = self.baseline_learner.trainer.lock().await;
+        let learned = trainer.train(Utc::now());
+        learned.apply_to(&mut *self.baselines.write().await);
+        *self.baseline_learner.learned.write().await = Some(learned.clone());
+
+        if let Some(path) = self.baseline_learner.store.read().await.as_ref() {
+            save_state(path, &PersistedBaselines { trainer: trainer.clone(), learned: Some(learned.clone()) })?;
+        }
+        Ok(learned)
+    }
+
+    pub async fn get_learned_baselines(&self) -> Option<LearnedBaselines> {
+        self.baseline_learner.learned.read().await.clone()
+    }
+
+    pub async fn get_behavioral_baselines(&self) -> BehavioralBaselines {
+        self.baselines.read().await.clone()
+    }
+
+    /// Compare an event with the learned baselines
+    pub async fn assess_against_baseline(&self, event: &HistoricalEvent) -> Result<BaselineAssessment, String> {
+        self.baseline_learner
+            .learned
+            .read()
+            .await
+            .as_ref()
+            .map(|learned| learned.assess(event))
+            .ok_or_else(|| "Baselines have not been trained yet".to_string())
+    }
+
+    /// Refresh baselines every `baseline_update_frequency` hours in the background
+    pub async fn start_baseline_refresh(self: &Arc<Self>) -> Result<(), String> {
+        let mut run = self.baseline_learner.refresh.lock().await;
+        if run.as_ref().is_some_and(|r| !r.handle.is_finished()) {
+            return Err("Baseline refresh is already running".to_string());
+        }
+
+        let hours = self.baselines.read().await.baseline_update_frequency.max(1);
+        let period = std::time::Duration::from_secs(hours * 3600);
+        let (stop, mut stopped) = watch::channel(false);
+        let core = Arc::clone(self);
+
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {
+                        if let Err(e) = core.refresh_baselines().await {
+                            log::warn!("Baseline refresh failed: {}", e);
+                        }
+                    }
+                    changed = stopped.changed() => {
+                        if changed.is_err() || *stopped.borrow() {
+                            break;
+                        }
+                    }
+                }
+            }
+            log::info!("Baseline refresh stopped");
+        });
+
+        *run = Some(RefreshRun { stop, handle });
+        log::info!("Baseline refresh started (every {} hours)", hours);
+        Ok(())
+    }
+
+    pub async fn stop_baseline_refresh(&self) -> bool {
+        let Some(run) = self.baseline_learner.refresh.lock().await.take() else {
+            return false;
+        };
+        let _ = run.stop.send(true);
+        let _ = run.handle.await;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(days_ago: i64, hour: u32) -> DateTime<Utc> {
+        let day = (Utc::now() - Duration::days(days_ago)).date_naive();
+        Utc.from_utc_datetime(&day.and_hms_opt(hour, 15, 0).unwrap())
+    }
+
+    fn history() -> Vec<HistoricalEvent> {
+        let mut events = Vec::new();
+        for days_ago in 1..=10 {
+            events.push(HistoricalEvent::Logon {
+                timestamp: at(days_ago, 8),
+                user: "alice".to_string(),
+                host: "ws-01".to_string(),
+                source_ip: Some("10.0.0.5".to_string()),
+            });
+            events.push(HistoricalEvent::ProcessLaunch {
+                timestamp: at(days_ago, 9),
+                user: "alice".to_string(),
+                host: "ws-01".to_string(),
+                process: "outlook.exe".to_string(),
+                parent_process: Some("explorer.exe".to_string()),
+            });
+            events.push(HistoricalEvent::NetworkFlow {
+                timestamp: at(days_ago, 10),
+                host: "ws-01".to_string(),
+                destination: "10.0.0.1".to_string(),
+                port: Some(443),
+                protocol: "TCP".to_string(),
+                bytes: 1_000 * days_ago as u64,
+            });
+        }
+        events.push(HistoricalEvent::Logon {
+            timestamp: at(90, 8),
+            user: "alice".to_string(),
+            host: "ws-01".to_string(),
+            source_ip: None,
+        });
+        events
+    }
+
+    #[test]
+    fn test_training_builds_histograms_typical_values_and_volume_stats() {
+        let mut trainer = BaselineTrainer::new(30);
+        let summary = trainer.ingest(&history());
+        assert_eq!((summary.accepted, summary.skipped), (30, 1));
+
+        let learned = trainer.train(Utc::now());
+        let alice = &learned.users["alice"];
+        assert_eq!(alice.observations, 20);
+        assert!((alice.hourly_histogram[8] - 0.5).abs() < 1e-9);
+        assert_eq!(alice.typical["source_ip"], ["10.0.0.5"]);
+        assert_eq!(alice.daily_volume.mean, 2.0);
+
+        let bytes = &learned.network.daily_bytes;
+        assert!((bytes.mean - 5_500.0).abs() < 1e-9);
+        assert!(bytes.z_score(20_000.0).unwrap() > 3.0);
+        assert!((learned.network.hourly_bytes[10] - 5_500.0).abs() < 1e-9);
+
+        let usual = learned.assess(&HistoricalEvent::Logon {
+            timestamp: at(0, 8),
+            user: "alice".to_string(),
+            host: "ws-01".to_string(),
+            source_ip: Some("10.0.0.5".to_string()),
+        });
+        assert!(!usual.unusual_hour && usual.novel_attributes.is_empty());
+        let odd = learned.assess(&HistoricalEvent::Logon {
+            timestamp: at(0, 3),
+            user: "alice".to_string(),
+            host: "ws-01".to_string(),
+            source_ip: Some("203.0.113.9".to_string()),
+        });
+        assert!(odd.unusual_hour);
+        assert_eq!(odd.novel_attributes, ["source_ip=203.0.113.9"]);
+
+        let mut baselines = HuntingCore::initialize_baselines().unwrap();
+        learned.apply_to(&mut baselines);
+        assert_eq!(baselines.user_baselines["alice"].typical_login_times, ["08:00-09:00"]);
+        assert_eq!(baselines.system_baselines["ws-01"].normal_processes, ["outlook.exe"]);
+        assert_eq!(baselines.network_baselines.typical_destinations, ["10.0.0.1:443"]);
+        assert_eq!(baselines.network_baselines.normal_traffic_patterns[0].source, "ws-01");
+        // Untouched users keep their configured baseline
+        assert!(baselines.user_baselines.contains_key("admin_user"));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_persists_and_restores() {
+        let path = std::env::temp_dir().join(format!("phantom-baselines-{}.json", uuid::Uuid::new_v4()));
+
+        let core = HuntingCore::new().unwrap();
+        assert!(!core.set_baseline_store(path.clone()).await.unwrap());
+        assert!(core.assess_against_baseline(&history()[0]).await.is_err());
+        core.ingest_baseline_events(&history()).await;
+        core.refresh_baselines().await.unwrap();
+
+        let restored = HuntingCore::new().unwrap();
+        assert!(restored.set_baseline_store(path.clone()).await.unwrap());
+        assert_eq!(restored.get_learned_baselines().await.unwrap().events_ingested, 30);
+        assert!(restored.get_behavioral_baselines().await.user_baselines.contains_key("alice"));
+
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,321 @@
+//! Hunt Cancellation and Deadlines
+//!
+//! Every hunt runs under an abort handle registered by hunt id while it
+//! executes. Cancelling the hunt, or reaching its deadline (the rule's
+//! `execution_timeout_secs` unless the run overrides it), aborts the
+//! connector query in flight and skips the sources not yet queried. The hunt
+//! still completes with the rows received so far: its result is stored and
+//! returned with a `Cancelled` or `TimedOut` completion and a source error
+//! for every source that was cut short, whose checkpoint does not advance.
+//!
+//! Callers that need to cancel a hunt before it returns choose its id up
+//! front; the JS helper in `src-ts/abortable-hunt.ts` does this to tie a
+//! hunt to an `AbortSignal`.
+
+use chrono::{DateTime, Utc};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+use crate::error::{CoreError, CoreResult};
+use crate::{HuntingCore, HuntingCoreNapi, HuntingResult};
+
+/// How a hunt finished
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum HuntCompletion {
+    #[default]
+    Completed,
+    Cancelled,
+    TimedOut,
+}
+
+impl fmt::Display for HuntCompletion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            HuntCompletion::Completed => "completed",
+            HuntCompletion::Cancelled => "cancelled",
+            HuntCompletion::TimedOut => "timed out",
+        })
+    }
+}
+
+/// Options for one hunt run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HuntOptions {
+    /// Id to run the hunt under, so it can be cancelled before it returns
+    #[serde(default)]
+    pub hunt_id: Option<String>,
+    /// Deadline for this run, overriding the rule's `execution_timeout_secs`
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    /// Read connected sources from the rule's checkpoints
+    #[serde(default)]
+    pub incremental: bool,
+}
+
+/// Cooperative abort flag of one running hunt
+#[derive(Debug)]
+pub struct HuntAbort {
+    cancelled: AtomicBool,
+    notify: Notify,
+    deadline: Option<Instant>,
+}
+
+impl HuntAbort {
+    fn new(timeout: Option<std::time::Duration>) -> Self {
+        Self { cancelled: AtomicBool::new(false), notify: Notify::new(), deadline: timeout.map(|timeout| Instant::now() + timeout) }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    /// Why the hunt must stop, once it has been cancelled or is past its deadline
+    pub fn outcome(&self) -> Option<HuntCompletion> {
+        if self.cancelled.load(Ordering::SeqCst) {
+            Some(HuntCompletion::Cancelled)
+        } else if self.deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            Some(HuntCompletion::TimedOut)
+        } else {
+            None
+        }
+    }
+
+    /// Resolves when the hunt is cancelled or reaches its deadline
+    pub async fn aborted(&self) -> HuntCompletion {
+        loop {
+            let notified = self.notify.notified();
+            if let Some(outcome) = self.outcome() {
+                return outcome;
+            }
+            match self.deadline {
+                Some(deadline) => tokio::select! {
+                    _ = notified => {}
+                    _ = tokio::time::sleep_until(deadline) => return HuntCompletion::TimedOut,
+                },
+                None => notified.await,
+            }
+        }
+    }
+}
+
+/// A hunt currently executing
+#[derive(Debug, Clone, Serialize)]
+pub struct RunningHunt {
+    pub hunt_id: String,
+    pub tenant_id: String,
+    pub rule_id: String,
+    pub started_at: DateTime<Utc>,
+    pub deadline: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    abort: Arc<HuntAbort>,
+}
+
+#[derive(Debug, Default)]
+pub struct RunningHunts {
+    hunts: RwLock<HashMap<String, RunningHunt>>,
+}
+
+impl RunningHunts {
+    /// Register a hunt; it stays registered until the returned guard drops
+    pub(crate) fn start(self: &Arc<Self>, hunt_id: &str, tenant_id: &str, rule_id: &str, timeout: Option<std::time::Duration>) -> CoreResult<HuntGuard> {
+        let mut hunts = self.hunts.write().unwrap_or_else(|e| e.into_inner());
+        if hunts.contains_key(hunt_id) {
+            return Err(CoreError::validation(format!("Hunt {} is already running", hunt_id)));
+        }
+        let abort = Arc::new(HuntAbort::new(timeout));
+        let started_at = Utc::now();
+        hunts.insert(hunt_id.to_string(), RunningHunt {
+            hunt_id: hunt_id.to_string(),
+            tenant_id: tenant_id.to_string(),
+            rule_id: rule_id.to_string(),
+            started_at,
+            deadline: timeout.and_then(|timeout| chrono::Duration::from_std(timeout).ok()).map(|timeout| started_at + timeout),
+            abort: abort.clone(),
+        });
+        Ok(HuntGuard { hunts: self.clone(), hunt_id: hunt_id.to_string(), abort })
+    }
+
+    pub fn cancel(&self, tenant_id: &str, hunt_id: &str) -> bool {
+        let hunts = self.hunts.read().unwrap_or_else(|e| e.into_inner());
+        match hunts.get(hunt_id).filter(|hunt| hunt.tenant_id == tenant_id) {
+            Some(hunt) => {
+                hunt.abort.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn list(&self, tenant_id: &str) -> Vec<RunningHunt> {
+        let mut hunts: Vec<RunningHunt> = self.hunts.read().unwrap_or_else(|e| e.into_inner())
+            .values()
+            .filter(|hunt| hunt.tenant_id == tenant_id)
+            .cloned()
+            .collect();
+        hunts.sort_by_key(|hunt| hunt.started_at);
+        hunts
+    }
+
+    /// Cancel the tenant's running hunts; they deregister as they stop
+    pub fn forget_tenant(&self, tenant_id: &str) -> usize {
+        let hunts = self.hunts.read().unwrap_or_else(|e| e.into_inner());
+        hunts.values().filter(|hunt| hunt.tenant_id == tenant_id).inspect(|hunt| hunt.abort.cancel()).count()
+    }
+}
+
+/// Keeps a hunt registered as running
+pub(crate) struct HuntGuard {
+    hunts: Arc<RunningHunts>,
+    hunt_id: String,
+    abort: Arc<HuntAbort>,
+}
+
+impl HuntGuard {
+    pub(crate) fn abort(&self) -> &HuntAbort {
+        &self.abort
+    }
+}
+
+impl Drop for HuntGuard {
+    fn drop(&mut self) {
+        self.hunts.hunts.write().unwrap_or_else(|e| e.into_inner()).remove(&self.hunt_id);
+    }
+}
+
+impl HuntingCore {
+    /// Run `rule_id` under a caller-chosen id or deadline; a cancelled or
+    /// timed-out hunt returns the partial result it reached
+    pub async fn execute_hunt_with_options(&self, tenant_id: &str, rule_id: &str, data_context: Option<HashMap<String, serde_json::Value>>, options: HuntOptions) -> CoreResult<HuntingResult> {
+        self.run_hunt(tenant_id, rule_id, data_context, options).await
+    }
+
+    /// Ask a running hunt of `tenant_id` to stop; false when no such hunt is running
+    pub fn cancel_hunt(&self, tenant_id: &str, hunt_id: &str) -> bool {
+        self.running_hunts.cancel(tenant_id, hunt_id)
+    }
+
+    pub fn list_running_hunts(&self, tenant_id: &str) -> Vec<RunningHunt> {
+        self.running_hunts.list(tenant_id)
+    }
+}
+
+#[napi]
+impl HuntingCoreNapi {
+    /// Execute a hunt with options (`hunt_id`, `timeout_ms`, `incremental`) as JSON
+    #[napi]
+    pub async fn execute_hunt_with_options(&self, rule_id: String, data_context: Option<String>, options: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", &rule_id)?;
+        let options: HuntOptions = serde_json::from_str(&options)
+            .map_err(|e| CoreError::from(e).context("Failed to parse hunt options"))?;
+        let params = serde_json::json!({ "data_context": data_context, "options": options });
+        let context = match data_context {
+            Some(ctx) => Some(serde_json::from_str(&ctx).map_err(|e| CoreError::from(e).context("Failed to parse data context"))?),
+            None => None,
+        };
+
+        let result = self.inner.execute_hunt_with_options(&tenant_id, &rule_id, context, options).await;
+        let result = self.audit.record(&actor, "execute_hunt", &rule_id, params, result)
+            .map_err(|e| e.context("Failed to execute hunt"))?;
+        serde_json::to_string(&result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize result: {}", e)))
+    }
+
+    /// Cancel a running hunt; it returns the results gathered so far
+    #[napi]
+    pub fn cancel_hunt(&self, hunt_id: String, auth_token: Option<String>) -> napi::Result<bool> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:cancel", &hunt_id)?;
+        let cancelled = self.inner.cancel_hunt(&tenant_id, &hunt_id);
+        self.audit.record(&actor, "cancel_hunt", &hunt_id, serde_json::json!({}), Ok::<_, String>(cancelled))
+            .map_err(napi::Error::from_reason)
+    }
+
+    #[napi]
+    pub fn list_running_hunts(&self, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        serde_json::to_string(&self.inner.list_running_hunts(&tenant_id))
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize running hunts: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connectors::{flatten, ConnectorRow, HuntConnector, RowSink};
+    use crate::{DataSource, HuntingQuery};
+    use async_trait::async_trait;
+    use serde_json::json;
+
+    /// Sends one row, then hangs like a backend that stopped responding
+    struct StalledSource;
+
+    #[async_trait]
+    impl HuntConnector for StalledSource {
+        fn name(&self) -> &'static str {
+            "stalled"
+        }
+
+        async fn stream(&self, _source: &DataSource, _query: &HuntingQuery, _since: Option<DateTime<Utc>>, _limit: usize, sink: RowSink) -> Result<u64, String> {
+            let row = json!({ "ts": Utc::now().to_rfc3339(), "user": "alice" });
+            sink.send(ConnectorRow::new(flatten(&row), Some("ts"))).await.map_err(|e| e.to_string())?;
+            std::future::pending().await
+        }
+    }
+
+    async fn stalled_core() -> Arc<HuntingCore> {
+        let core = HuntingCore::new().unwrap();
+        core.register_data_source(crate::connectors::tests::source("siem", "https://siem.corp/events")).await.unwrap();
+        core.pin_connector("siem", Arc::new(StalledSource));
+        let mut rule = core.list_rules("acme").await.unwrap().remove(0);
+        rule.id = "logons".to_string();
+        rule.tenant_id = Some("acme".to_string());
+        rule.data_sources = vec![crate::connectors::tests::source("siem", "https://siem.corp/events")];
+        rule.execution_timeout_secs = Some(3600);
+        core.rules.write().await.insert(rule.id.clone(), rule);
+        Arc::new(core)
+    }
+
+    #[tokio::test]
+    async fn cancelled_hunts_return_partial_results() {
+        let core = stalled_core().await;
+        let options = HuntOptions { hunt_id: Some("hunt_abort".to_string()), ..HuntOptions::default() };
+        let hunt = tokio::spawn({
+            let core = core.clone();
+            async move { core.execute_hunt_with_options("acme", "logons", None, options).await }
+        });
+        while core.list_running_hunts("acme").is_empty() {
+            tokio::task::yield_now().await;
+        }
+        assert!(core.list_running_hunts("acme")[0].deadline.is_some());
+        assert!(!core.cancel_hunt("globex", "hunt_abort"));
+        assert!(core.cancel_hunt("acme", "hunt_abort"));
+
+        let result = hunt.await.unwrap().unwrap();
+        assert_eq!((result.hunt_id.as_str(), result.completion), ("hunt_abort", HuntCompletion::Cancelled));
+        assert_eq!(result.total_events_processed, 1);
+        assert!(result.source_errors[0].contains("cancelled"));
+        assert!(core.list_running_hunts("acme").is_empty());
+        assert_eq!(core.get_hunt_results("acme", None).await.unwrap()[0].completion, HuntCompletion::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn deadlines_time_hunts_out() {
+        let core = stalled_core().await;
+        let options = HuntOptions { timeout_ms: Some(50), ..HuntOptions::default() };
+        let result = core.execute_hunt_with_options("acme", "logons", None, options).await.unwrap();
+        assert_eq!(result.completion, HuntCompletion::TimedOut);
+        assert_eq!(result.total_events_processed, 1);
+        assert!(result.source_errors[0].contains("timed out"));
+
+        let taken = core.execute_hunt_with_options("acme", "logons", None, HuntOptions { hunt_id: Some(result.hunt_id.clone()), ..HuntOptions::default() }).await;
+        assert!(taken.is_err(), "a finished hunt's id is not reused");
+    }
+}
@@ -0,0 +1,408 @@
+//! Incremental Hunting
+//!
+//! An incremental hunt queries each connected data source only for rows
+//! newer than the rule's checkpoint on that source: the latest row timestamp
+//! an earlier run processed. Checkpoints are kept per tenant, rule and
+//! source, count the rows consumed so far as the source offset, and advance
+//! only when the source's query succeeded, so a failed source is read again
+//! from the same point on the next run. Rows at or before the checkpoint are
+//! skipped even if a backend returns them again; rows without a timestamp
+//! cannot be placed and are always processed. The first incremental run of
+//! a rule reads its whole time range. Checkpoints can be reset to a point
+//! in time, or dropped to start over, and persisted to a JSON file.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use crate::error::{CoreError, CoreResult};
+use crate::{tenancy, HuntingCore, HuntingCoreNapi};
+use napi_derive::napi;
+
+/// Progress of a rule through one data source
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HuntCheckpoint {
+    pub tenant_id: String,
+    pub rule_id: String,
+    pub source_id: String,
+    /// Timestamp of the newest row processed; the next run reads after it
+    pub last_timestamp: Option<DateTime<Utc>>,
+    /// Rows consumed from the source across all incremental runs
+    pub offset: u64,
+    /// Rows the last run consumed
+    pub last_run_rows: u64,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// How far a checkpoint trails the present
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointLag {
+    pub rule_id: String,
+    pub source_id: String,
+    pub last_timestamp: Option<DateTime<Utc>>,
+    /// Seconds between the checkpoint and now; `None` before the first timestamped row
+    pub lag_seconds: Option<i64>,
+    pub offset: u64,
+    pub updated_at: DateTime<Utc>,
+}
+
+fn checkpoint_key(tenant_id: &str, rule_id: &str, source_id: &str) -> String {
+    format!("{}:{}:{}", tenant_id, rule_id, source_id)
+}
+
+#[derive(Default)]
+pub struct CheckpointState {
+    checkpoints: RwLock<BTreeMap<String, HuntCheckpoint>>,
+    store: RwLock<Option<PathBuf>>,
+}
+
+impl CheckpointState {
+    pub(crate) fn get(&self, tenant_id: &str, rule_id: &str, source_id: &str) -> Option<HuntCheckpoint> {
+        self.checkpoints
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&checkpoint_key(tenant_id, rule_id, source_id))
+            .cloned()
+    }
+
+    /// Record that a run consumed `rows` rows up to `newest`
+    pub(crate) fn advance(&self, tenant_id: &str, rule_id: &str, source_id: &str, newest: Option<DateTime<Utc>>, rows: u64) {
+        let mut checkpoints = self.checkpoints.write().unwrap_or_else(|e| e.into_inner());
+        let checkpoint = checkpoints
+            .entry(checkpoint_key(tenant_id, rule_id, source_id))
+            .or_insert_with(|| HuntCheckpoint {
+                tenant_id: tenant_id.to_string(),
+                rule_id: rule_id.to_string(),
+                source_id: source_id.to_string(),
+                last_timestamp: None,
+                offset: 0,
+                last_run_rows: 0,
+                updated_at: Utc::now(),
+            });
+        checkpoint.last_timestamp = checkpoint.last_timestamp.max(newest);
+        checkpoint.offset += rows;
+        checkpoint.last_run_rows = rows;
+        checkpoint.updated_at = Utc::now();
+    }
+
+    fn matching(&self, tenant_id: &str, rule_id: Option<&str>) -> Vec<HuntCheckpoint> {
+        self.checkpoints
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .filter(|cp| cp.tenant_id == tenant_id && rule_id.is_none_or(|rule_id| cp.rule_id == rule_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Write checkpoints to the store, if one is set
+    pub(crate) fn persist(&self) -> Result<(), String> {
+        let Some(path) = self.store.read().unwrap_or_else(|e| e.into_inner()).clone() else {
+            return Ok(());
+        };
+        let checkpoints: Vec<HuntCheckpoint> = self.checkpoints.read().unwrap_or_else(|e| e.into_inner()).values().cloned().collect();
+        save_checkpoints(&path, &checkpoints)
+    }
+
+    pub(crate) fn forget_tenant(&self, tenant_id: &str) -> usize {
+        let removed = {
+            let mut checkpoints = self.checkpoints.write().unwrap_or_else(|e| e.into_inner());
+            let before = checkpoints.len();
+            checkpoints.retain(|_, cp| cp.tenant_id != tenant_id);
+            before - checkpoints.len()
+        };
+        if removed > 0 {
+            if let Err(e) = self.persist() {
+                log::warn!("Failed to persist hunt checkpoints: {}", e);
+            }
+        }
+        removed
+    }
+}
+
+fn save_checkpoints(path: &Path, checkpoints: &[HuntCheckpoint]) -> Result<(), String> {
+    let content = serde_json::to_vec_pretty(checkpoints).map_err(|e| format!("Failed to serialize checkpoints: {}", e))?;
+    let staging = path.with_extension("tmp");
+    std::fs::write(&staging, content).map_err(|e| format!("Failed to write {}: {}", staging.display(), e))?;
+    std::fs::rename(&staging, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
+
+fn load_checkpoints(path: &Path) -> Result<Option<Vec<HuntCheckpoint>>, String> {
+    match std::fs::read(path) {
+        Ok(content) => serde_json::from_slice(&content)
+            .map(Some)
+            .map_err(|e| format!("Invalid checkpoint file {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+impl HuntingCore {
+    /// Persist checkpoints to `path`, loading whatever an earlier run left
+    /// there. Returns whether existing checkpoints were loaded.
+    pub fn set_checkpoint_store(&self, path: PathBuf) -> Result<bool, String> {
+        let loaded = load_checkpoints(&path)?;
+        let restored = loaded.is_some();
+        if let Some(loaded) = loaded {
+            let mut checkpoints = self.checkpoints.checkpoints.write().unwrap_or_else(|e| e.into_inner());
+            for checkpoint in loaded {
+                checkpoints.insert(checkpoint_key(&checkpoint.tenant_id, &checkpoint.rule_id, &checkpoint.source_id), checkpoint);
+            }
+        }
+        *self.checkpoints.store.write().unwrap_or_else(|e| e.into_inner()) = Some(path);
+        Ok(restored)
+    }
+
+    /// The tenant's checkpoints, or only those of `rule_id`
+    pub fn list_checkpoints(&self, tenant_id: &str, rule_id: Option<&str>) -> Vec<HuntCheckpoint> {
+        self.checkpoints.matching(tenant_id, rule_id)
+    }
+
+    /// How far each of the tenant's checkpoints trails the present
+    pub fn checkpoint_lag(&self, tenant_id: &str, rule_id: Option<&str>) -> Vec<CheckpointLag> {
+        let now = Utc::now();
+        self.checkpoints
+            .matching(tenant_id, rule_id)
+            .into_iter()
+            .map(|cp| CheckpointLag {
+                lag_seconds: cp.last_timestamp.map(|at| (now - at).num_seconds().max(0)),
+                rule_id: cp.rule_id,
+                source_id: cp.source_id,
+                last_timestamp: cp.last_timestamp,
+                offset: cp.offset,
+                updated_at: cp.updated_at,
+            })
+            .collect()
+    }
+
+    /// Move the rule's checkpoint on `source_id`, or on each of its sources,
+    /// back or forward to `to`, or drop it when `to` is `None` so the next
+    /// incremental run reads the whole time range. Returns how many
+    /// checkpoints changed.
+    pub async fn reset_checkpoint(&self, tenant_id: &str, rule_id: &str, source_id: Option<&str>, to: Option<DateTime<Utc>>) -> CoreResult<usize> {
+        if to.is_some_and(|to| to > Utc::now()) {
+            return Err(CoreError::validation("Checkpoints cannot be reset to a future time"));
+        }
+        let rule_sources: Vec<String> = {
+            let rules = self.rules.read().await;
+            let rule = rules.get(rule_id).filter(|rule| tenancy::rule_visible(rule, tenant_id))
+                .ok_or_else(|| CoreError::not_found(format!("Rule {} not found", rule_id)))?;
+            rule.data_sources.iter().map(|source| source.source_id.clone()).collect()
+        };
+        let sources = match source_id {
+            Some(source_id) if rule_sources.iter().any(|s| s == source_id) => vec![source_id.to_string()],
+            Some(source_id) => return Err(CoreError::not_found(format!("Rule {} does not query data source {}", rule_id, source_id))),
+            None => rule_sources,
+        };
+
+        let changed = {
+            let mut checkpoints = self.checkpoints.checkpoints.write().unwrap_or_else(|e| e.into_inner());
+            let mut changed = 0;
+            for source_id in sources {
+                let key = checkpoint_key(tenant_id, rule_id, &source_id);
+                match to {
+                    Some(to) => {
+                        let checkpoint = checkpoints.entry(key).or_insert_with(|| HuntCheckpoint {
+                            tenant_id: tenant_id.to_string(),
+                            rule_id: rule_id.to_string(),
+                            source_id,
+                            last_timestamp: None,
+                            offset: 0,
+                            last_run_rows: 0,
+                            updated_at: Utc::now(),
+                        });
+                        checkpoint.last_timestamp = Some(to);
+                        checkpoint.updated_at = Utc::now();
+                        changed += 1;
+                    }
+                    None => changed += usize::from(checkpoints.remove(&key).is_some()),
+                }
+            }
+            changed
+        };
+        self.checkpoints.persist().map_err(|e| CoreError::backend(e).context("Failed to persist checkpoints"))?;
+        Ok(changed)
+    }
+}
+
+#[napi]
+impl HuntingCoreNapi {
+    /// Run a hunt over only the rows its data sources received since the
+    /// rule's last incremental run, advancing its checkpoints
+    #[napi]
+    pub async fn execute_incremental_hunt(&self, rule_id: String, data_context: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", &rule_id)?;
+        let params = serde_json::json!({ "data_context": data_context, "incremental": true });
+        let context = match data_context {
+            Some(ctx) => Some(serde_json::from_str(&ctx).map_err(|e| CoreError::from(e).context("Failed to parse data context"))?),
+            None => None,
+        };
+
+        let result = self.inner.execute_incremental_hunt(&tenant_id, &rule_id, context).await;
+        let result = self.audit.record(&actor, "execute_incremental_hunt", &rule_id, params, result)
+            .map_err(|e| e.context("Failed to execute incremental hunt"))?;
+        serde_json::to_string(&result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize result: {}", e)))
+    }
+
+    /// Persist hunt checkpoints to a file, restoring any already there
+    #[napi]
+    pub fn set_checkpoint_store(&self, path: String, auth_token: Option<String>) -> napi::Result<bool> {
+        let actor = self.authorize_platform(auth_token, "checkpoints")?;
+        let restored = self.inner.set_checkpoint_store(path.clone().into());
+        self.audit.record(&actor, "set_checkpoint_store", "checkpoints", serde_json::json!({ "path": path }), restored)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to set checkpoint store: {}", e)))
+    }
+
+    #[napi]
+    pub fn list_checkpoints(&self, rule_id: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        serde_json::to_string(&self.inner.list_checkpoints(&tenant_id, rule_id.as_deref()))
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize checkpoints: {}", e)))
+    }
+
+    /// Seconds each checkpoint trails the present
+    #[napi]
+    pub fn get_checkpoint_lag(&self, rule_id: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        serde_json::to_string(&self.inner.checkpoint_lag(&tenant_id, rule_id.as_deref()))
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize checkpoint lag: {}", e)))
+    }
+
+    /// Reset a rule's checkpoints to an RFC 3339 time, or drop them when no time is given
+    #[napi]
+    pub async fn reset_checkpoint(&self, rule_id: String, source_id: Option<String>, to_time: Option<String>, auth_token: Option<String>) -> napi::Result<u32> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &rule_id)?;
+        let to = to_time
+            .as_deref()
+            .map(DateTime::parse_from_rfc3339)
+            .transpose()
+            .map_err(|e| CoreError::validation(format!("Invalid checkpoint time: {}", e)))?
+            .map(|at| at.with_timezone(&Utc));
+        let params = serde_json::json!({ "source_id": source_id, "to_time": to_time });
+        let changed = self.inner.reset_checkpoint(&tenant_id, &rule_id, source_id.as_deref(), to).await;
+        let changed = self.audit.record(&actor, "reset_checkpoint", &rule_id, params, changed)
+            .map_err(|e| e.context("Failed to reset checkpoint"))?;
+        Ok(changed as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connectors::{flatten, ConnectorRow, HuntConnector, RowSink};
+    use crate::{DataSource, HuntingQuery};
+    use async_trait::async_trait;
+    use chrono::Duration;
+    use serde_json::{json, Value};
+    use std::sync::{Arc, Mutex};
+
+    /// Serves its rows newer than `since`, like a backend with a timestamp filter
+    #[derive(Default)]
+    struct GrowingSource {
+        rows: Mutex<Vec<Value>>,
+        windows: Mutex<Vec<Option<DateTime<Utc>>>>,
+        failing: Mutex<bool>,
+    }
+
+    #[async_trait]
+    impl HuntConnector for GrowingSource {
+        fn name(&self) -> &'static str {
+            "growing"
+        }
+
+        async fn stream(&self, _source: &DataSource, _query: &HuntingQuery, since: Option<DateTime<Utc>>, _limit: usize, sink: RowSink) -> Result<u64, String> {
+            self.windows.lock().unwrap().push(since);
+            let rows: Vec<ConnectorRow> = self.rows.lock().unwrap().iter().map(|row| ConnectorRow::new(flatten(row), Some("ts"))).collect();
+            let mut sent = 0;
+            for row in rows.into_iter().filter(|row| since.is_none_or(|since| row.timestamp.is_some_and(|at| at >= since))) {
+                sink.send(row).await.map_err(|e| e.to_string())?;
+                sent += 1;
+            }
+            match *self.failing.lock().unwrap() {
+                true => Err("connection reset".to_string()),
+                false => Ok(sent),
+            }
+        }
+    }
+
+    fn event(at: DateTime<Utc>, user: &str) -> Value {
+        json!({ "ts": at.to_rfc3339(), "user": user })
+    }
+
+    #[tokio::test]
+    async fn incremental_hunts_read_only_new_rows() {
+        let core = HuntingCore::new().unwrap();
+        let source = Arc::new(GrowingSource::default());
+        let t0 = Utc::now() - Duration::minutes(30);
+        source.rows.lock().unwrap().extend([event(t0, "alice"), event(t0 + Duration::minutes(5), "bob")]);
+        core.register_data_source(crate::connectors::tests::source("siem", "https://siem.corp/events")).await.unwrap();
+        core.pin_connector("siem", source.clone());
+
+        let mut rule = core.list_rules("acme").await.unwrap().remove(0);
+        rule.id = "logons".to_string();
+        rule.tenant_id = Some("acme".to_string());
+        rule.query.time_range = "last 24 hours".to_string();
+        rule.data_sources = vec![crate::connectors::tests::source("siem", "https://siem.corp/events")];
+        core.rules.write().await.insert(rule.id.clone(), rule);
+
+        let first = core.execute_incremental_hunt("acme", "logons", None).await.unwrap();
+        assert_eq!(first.total_events_processed, 2);
+        let checkpoint = core.list_checkpoints("acme", Some("logons")).remove(0);
+        assert_eq!((checkpoint.offset, checkpoint.last_run_rows), (2, 2));
+        assert_eq!(checkpoint.last_timestamp.unwrap().timestamp(), (t0 + Duration::minutes(5)).timestamp());
+
+        source.rows.lock().unwrap().push(event(t0 + Duration::minutes(10), "mallory"));
+        let second = core.execute_incremental_hunt("acme", "logons", None).await.unwrap();
+        assert_eq!(second.total_events_processed, 1, "the row at the checkpoint is not read again");
+        assert!(second.matches.iter().all(|m| m.event_data["user"] == "mallory"));
+        let windows = source.windows.lock().unwrap().clone();
+        assert_eq!(windows[1].unwrap().timestamp(), (t0 + Duration::minutes(5)).timestamp());
+
+        // A failing source keeps its checkpoint, so its rows are read again
+        *source.failing.lock().unwrap() = true;
+        source.rows.lock().unwrap().push(event(t0 + Duration::minutes(15), "eve"));
+        let failed = core.execute_incremental_hunt("acme", "logons", None).await.unwrap();
+        assert_eq!(failed.source_errors.len(), 1);
+        assert_eq!(core.list_checkpoints("acme", None)[0].offset, 3);
+        *source.failing.lock().unwrap() = false;
+        assert_eq!(core.execute_incremental_hunt("acme", "logons", None).await.unwrap().total_events_processed, 1);
+
+        let lag = core.checkpoint_lag("acme", None);
+        assert!((14 * 60..16 * 60).contains(&lag[0].lag_seconds.unwrap()));
+        assert!(core.checkpoint_lag("globex", None).is_empty());
+
+        // Full hunts neither read nor move checkpoints
+        assert_eq!(core.execute_hunt("acme", "logons", None).await.unwrap().total_events_processed, 4);
+        assert_eq!(core.list_checkpoints("acme", None)[0].offset, 4);
+
+        assert_eq!(core.reset_checkpoint("acme", "logons", Some("siem"), Some(t0 + Duration::minutes(7))).await.unwrap(), 1);
+        assert_eq!(core.execute_incremental_hunt("acme", "logons", None).await.unwrap().total_events_processed, 2);
+        assert!(core.reset_checkpoint("acme", "logons", Some("edr"), None).await.is_err());
+        assert!(core.reset_checkpoint("acme", "logons", None, Some(Utc::now() + Duration::hours(1))).await.is_err());
+        assert_eq!(core.reset_checkpoint("acme", "logons", None, None).await.unwrap(), 1);
+        assert_eq!(core.execute_incremental_hunt("acme", "logons", None).await.unwrap().total_events_processed, 4);
+    }
+
+    #[tokio::test]
+    async fn checkpoints_survive_a_restart() {
+        let path = std::env::temp_dir().join(format!("hunt-checkpoints-{}.json", uuid::Uuid::new_v4()));
+        let core = HuntingCore::new().unwrap();
+        assert!(!core.set_checkpoint_store(path.clone()).unwrap());
+        let at = Utc::now() - Duration::hours(2);
+        core.checkpoints.advance("acme", "logons", "siem", Some(at), 12);
+        core.checkpoints.persist().unwrap();
+
+        let restarted = HuntingCore::new().unwrap();
+        assert!(restarted.set_checkpoint_store(path.clone()).unwrap());
+        let checkpoint = restarted.list_checkpoints("acme", None).remove(0);
+        assert_eq!((checkpoint.last_timestamp, checkpoint.offset), (Some(at), 12));
+        assert_eq!(restarted.purge_tenant("acme").await["hunt_checkpoints"], 1);
+        assert!(restarted.list_checkpoints("acme", None).is_empty());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,289 @@
+//! Detection condition evaluation
+//!
+//! Evaluates a rule's `DetectionCondition` list against a flat event map. The
+//! same evaluator is used for every event shape the hunting core understands
+//! (log events, flow records), so rules stay source-agnostic.
+//!
+//! The exclusion operators `none_of`, `outside_cidr` and `outside_window`
+//! are what rule tuning adds to carve known-benign activity out of a rule.
+//! They only veto: an event an exclusion rejects never matches whatever
+//! `required` says, they do not count towards the confidence, and an event
+//! without the field passes them.
+
+use crate::DetectionCondition;
+use chrono::{DateTime, Datelike, TimeZone, Timelike, Utc};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// Operators that only exclude events
+pub const EXCLUSION_OPERATORS: [&str; 3] = ["none_of", "outside_cidr", "outside_window"];
+
+/// Evaluate `conditions` against `event`.
+///
+/// Returns the weighted confidence (0.0 - 1.0) when the event matches, or
+/// `None` when it does not. Every `required` condition must hold; when no
+/// condition is required, at least one must hold.
+pub fn evaluate_conditions(event: &HashMap<String, Value>, conditions: &[DetectionCondition]) -> Option<f64> {
+    if conditions.is_empty() {
+        return None;
+    }
+
+    let mut matched_weight = 0.0;
+    let mut total_weight = 0.0;
+    let mut any_matched = false;
+    let mut has_required = false;
+
+    for condition in conditions {
+        if EXCLUSION_OPERATORS.contains(&condition.operator.as_str()) {
+            match event.get(&condition.field) {
+                Some(actual) if !condition_holds(actual, &condition.operator, &condition.value) => return None,
+                _ => continue,
+            }
+        }
+        total_weight += condition.weight;
+        let matched = event
+            .get(&condition.field)
+            .map(|actual| condition_holds(actual, &condition.operator, &condition.value))
+            .unwrap_or_else(|| condition.operator == "not_exists");
+
+        if condition.required {
+            has_required = true;
+            if !matched {
+                return None;
+            }
+        }
+        if matched {
+            any_matched = true;
+            matched_weight += condition.weight;
+        }
+    }
+
+    if !has_required && !any_matched {
+        return None;
+    }
+
+    if total_weight > 0.0 {
+        Some((matched_weight / total_weight).clamp(0.0, 1.0))
+    } else {
+        Some(1.0)
+    }
+}
+
+/// Evaluate a single operator against an event value.
+pub fn condition_holds(actual: &Value, operator: &str, expected: &Value) -> bool {
+    match operator {
+        "equals" | "eq" | "==" => values_equal(actual, expected),
+        "not_equals" | "ne" | "!=" => !values_equal(actual, expected),
+        "contains" => contains(actual, expected),
+        "not_contains" => !contains(actual, expected),
+        "starts_with" => match (actual.as_str(), expected.as_str()) {
+            (Some(a), Some(e)) => a.to_lowercase().starts_with(&e.to_lowercase()),
+            _ => false,
+        },
+        "ends_with" => match (actual.as_str(), expected.as_str()) {
+            (Some(a), Some(e)) => a.to_lowercase().ends_with(&e.to_lowercase()),
+            _ => false,
+        },
+        "in" => expected.as_array().map(|values| values.iter().any(|v| values_equal(actual, v))).unwrap_or(false),
+        "not_in" => expected.as_array().map(|values| !values.iter().any(|v| values_equal(actual, v))).unwrap_or(false),
+        "gt" | ">" => compare(actual, expected).map(|o| o.is_gt()).unwrap_or(false),
+        "gte" | ">=" => compare(actual, expected).map(|o| o.is_ge()).unwrap_or(false),
+        "lt" | "<" => compare(actual, expected).map(|o| o.is_lt()).unwrap_or(false),
+        "lte" | "<=" => compare(actual, expected).map(|o| o.is_le()).unwrap_or(false),
+        "regex" | "matches" => match (value_as_string(actual), expected.as_str()) {
+            (Some(a), Some(pattern)) => Regex::new(pattern).map(|re| re.is_match(&a)).unwrap_or(false),
+            _ => false,
+        },
+        "not_regex" | "not_matches" => match (value_as_string(actual), expected.as_str()) {
+            (Some(a), Some(pattern)) => Regex::new(pattern).map(|re| !re.is_match(&a)).unwrap_or(false),
+            _ => false,
+        },
+        "exists" => !actual.is_null(),
+        "not_exists" => actual.is_null(),
+        "none_of" => match expected {
+            Value::Array(values) => !values.iter().any(|v| values_equal(actual, v)),
+            single => !values_equal(actual, single),
+        },
+        "outside_cidr" => {
+            let Some(ip) = value_as_string(actual).and_then(|a| a.trim().parse::<IpAddr>().ok()) else {
+                return true;
+            };
+            let ranges = match expected {
+                Value::Array(values) => values.iter().filter_map(Value::as_str).collect(),
+                single => single.as_str().into_iter().collect::<Vec<_>>(),
+            };
+            !ranges.into_iter().any(|range| in_cidr(ip, range))
+        }
+        "outside_window" => match value_as_timestamp(actual) {
+            Some(at) => !in_window(at, expected),
+            None => true,
+        },
+        _ => false,
+    }
+}
+
+/// Whether `ip` falls in `range` (`a.b.c.d/len`, or a bare address)
+pub fn in_cidr(ip: IpAddr, range: &str) -> bool {
+    let (network, prefix) = match range.split_once('/') {
+        Some((network, prefix)) => (network, prefix.trim().parse::<u32>().ok()),
+        None => (range, None),
+    };
+    match (ip, network.trim().parse::<IpAddr>()) {
+        (IpAddr::V4(ip), Ok(IpAddr::V4(network))) => {
+            let prefix = prefix.unwrap_or(32).min(32);
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(ip), Ok(IpAddr::V6(network))) => {
+            let prefix = prefix.unwrap_or(128).min(128);
+            let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Whether `at` falls in a `{"days": [1-7], "start_hour": h, "end_hour": h}`
+/// window (UTC, ISO weekdays with Monday = 1, end hour exclusive; a window
+/// whose end is before its start wraps past midnight)
+pub fn in_window(at: DateTime<Utc>, window: &Value) -> bool {
+    let hour = |key: &str, default: u64| window.get(key).and_then(Value::as_u64).unwrap_or(default) as u32;
+    let (start, end) = (hour("start_hour", 0), hour("end_hour", 24));
+    let day_matches = window
+        .get("days")
+        .and_then(Value::as_array)
+        .is_none_or(|days| days.iter().any(|day| day.as_u64() == Some(at.weekday().number_from_monday() as u64)));
+    let hour_matches = if start <= end { (start..end).contains(&at.hour()) } else { at.hour() >= start || at.hour() < end };
+    day_matches && hour_matches
+}
+
+/// RFC 3339 strings, or epoch seconds / milliseconds
+pub fn value_as_timestamp(value: &Value) -> Option<DateTime<Utc>> {
+    match value {
+        Value::String(s) => DateTime::parse_from_rfc3339(s.trim()).ok().map(|at| at.with_timezone(&Utc)),
+        Value::Number(n) => {
+            let epoch = n.as_i64()?;
+            if epoch.abs() >= 100_000_000_000 {
+                Utc.timestamp_millis_opt(epoch).single()
+            } else {
+                Utc.timestamp_opt(epoch, 0).single()
+            }
+        }
+        _ => None,
+    }
+}
+
+fn values_equal(actual: &Value, expected: &Value) -> bool {
+    if let (Some(a), Some(e)) = (value_as_f64(actual), value_as_f64(expected)) {
+        return a == e;
+    }
+    match (value_as_string(actual), value_as_string(expected)) {
+        (Some(a), Some(e)) => a.eq_ignore_ascii_case(&e),
+        _ => actual == expected,
+    }
+}
+
+fn contains(actual: &Value, expected: &Value) -> bool {
+    match actual {
+        Value::Array(items) => items.iter().any(|item| values_equal(item, expected)),
+        _ => match (value_as_string(actual), value_as_string(expected)) {
+            (Some(a), Some(e)) => a.to_lowercase().contains(&e.to_lowercase()),
+            _ => false,
+        },
+    }
+}
+
+fn compare(actual: &Value, expected: &Value) -> Option<std::cmp::Ordering> {
+    let a = value_as_f64(actual)?;
+    let e = value_as_f64(expected)?;
+    a.partial_cmp(&e)
+}
+
+fn value_as_f64(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    }
+}
+
+fn value_as_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn condition(field: &str, operator: &str, value: Value, required: bool) -> DetectionCondition {
+        DetectionCondition {
+            condition_id: field.to_string(),
+            field: field.to_string(),
+            operator: operator.to_string(),
+            value,
+            weight: 1.0,
+            required,
+        }
+    }
+
+    #[test]
+    fn test_required_conditions_must_all_hold() {
+        let event = HashMap::from([
+            ("bytes_out".to_string(), json!(20_000_000)),
+            ("destination_port".to_string(), json!(8443)),
+        ]);
+        let conditions = vec![
+            condition("bytes_out", "gt", json!(10_000_000), true),
+            condition("destination_port", "not_in", json!([80, 443, 53]), true),
+        ];
+        assert_eq!(evaluate_conditions(&event, &conditions), Some(1.0));
+
+        let conditions = vec![condition("destination_port", "in", json!([80, 443]), true)];
+        assert_eq!(evaluate_conditions(&event, &conditions), None);
+    }
+
+    #[test]
+    fn test_optional_conditions_score_by_weight() {
+        let event = HashMap::from([("protocol".to_string(), json!("TCP"))]);
+        let conditions = vec![
+            condition("protocol", "equals", json!("tcp"), false),
+            condition("missing", "equals", json!("x"), false),
+        ];
+        assert_eq!(evaluate_conditions(&event, &conditions), Some(0.5));
+    }
+
+    #[test]
+    fn test_exclusions_only_veto() {
+        let event = HashMap::from([
+            ("user".to_string(), json!("svc_backup")),
+            ("src_ip".to_string(), json!("10.20.30.41")),
+            ("timestamp".to_string(), json!("2026-10-14T10:15:00Z")),
+        ]);
+        let detection = condition("user", "exists", Value::Null, false);
+        let window = json!({ "days": [1, 2, 3, 4, 5], "start_hour": 9, "end_hour": 17 });
+
+        for exclusion in [
+            condition("user", "none_of", json!(["SVC_BACKUP"]), false),
+            condition("src_ip", "outside_cidr", json!(["10.20.30.0/24"]), false),
+            condition("timestamp", "outside_window", window, false),
+        ] {
+            assert_eq!(evaluate_conditions(&event, &[detection.clone(), exclusion]), None);
+        }
+        let passing = vec![
+            detection,
+            condition("user", "none_of", json!(["svc_scan"]), true),
+            condition("src_ip", "outside_cidr", json!("10.20.31.0/24"), true),
+            condition("hostname", "none_of", json!(["ws-001"]), true),
+            condition("timestamp", "outside_window", json!({ "start_hour": 22, "end_hour": 6 }), true),
+        ];
+        assert_eq!(evaluate_conditions(&event, &passing), Some(1.0));
+    }
+}
@@ -0,0 +1,542 @@
+//! Event Correlation
+//!
+//! Evaluates a rule's `CorrelationRule`s over an event stream. Each rule
+//! correlates "steps": a step named by a rule DSL stage label is satisfied
+//! by events matching that stage's conditions; any other step names an
+//! event type (`event_type`, `event.category`, ...) or, as `Name:Id`, an
+//! event id (`EventID`, `event.code`, ...). Events are grouped by the values
+//! of the rule's `correlation_fields`; events missing one are not
+//! correlated.
+//!
+//! * Threshold rules (`threshold` scoring, or a single step) fire once the
+//!   key has `minimum_occurrences` events within the window.
+//! * Sequence rules (`sequence` scoring) fire when the key's steps occur in
+//!   order, distinct events each, within the window of the first one. A step
+//!   with its own threshold takes part in the sequence when it fires.
+//! * Other rules fire when every step occurred and the key has
+//!   `minimum_occurrences` events within the window.
+//!
+//! Windows slide unless the rule's `TimeWindow` (id of the rule, or
+//! `<stage>.window` for a `<stage>.threshold` rule) is not `sliding`; a
+//! tumbling window counts from aligned bucket boundaries. A rule that fires
+//! starts over for that key, and emits a `HuntingMatch` carrying the
+//! contributing events.
+
+use chrono::{DateTime, Utc};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+
+use crate::connectors::event_match;
+use crate::dsl::{self, SEQUENCE_SCORING, STAGE_SEPARATOR, THRESHOLD_SCORING};
+use crate::error::{CoreError, CoreResult};
+use crate::ingestion::NormalizedEvent;
+use crate::{
+    conditions, tenancy, CorrelationRule, CorrelationType, DetectionCondition, EventCorrelation, HuntingCore,
+    HuntingCoreNapi, HuntingMatch, HuntingRule,
+};
+
+/// Keys tracked per correlation rule; the least recently active go first
+pub const MAX_TRACKED_KEYS: usize = 10_000;
+/// Unfinished sequences kept per key; the oldest go first
+pub const MAX_PARTIAL_SEQUENCES: usize = 32;
+
+const EVENT_TYPE_FIELDS: &[&str] = &["event_type", "event.type", "EventType", "event.category", "category"];
+const EVENT_ID_FIELDS: &[&str] = &["EventID", "event_id", "event.code", "EventCode"];
+
+/// An event that contributed to a correlated match
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContributingEvent {
+    /// Step of the correlation rule the event satisfied
+    pub step: String,
+    pub timestamp: DateTime<Utc>,
+    pub source: String,
+    pub fields: HashMap<String, Value>,
+    pub confidence: f64,
+}
+
+/// What satisfies a step
+enum StepMatcher {
+    Conditions(Vec<DetectionCondition>),
+    EventType(String),
+}
+
+impl StepMatcher {
+    fn new(conditions: &[DetectionCondition], step: &str) -> Self {
+        let staged: Vec<DetectionCondition> = conditions
+            .iter()
+            .filter(|c| !step.is_empty() && dsl::stage_of(&c.condition_id) == step)
+            .cloned()
+            .collect();
+        if staged.is_empty() {
+            StepMatcher::EventType(step.to_string())
+        } else {
+            StepMatcher::Conditions(staged)
+        }
+    }
+
+    fn confidence(&self, fields: &HashMap<String, Value>) -> Option<f64> {
+        match self {
+            StepMatcher::Conditions(conditions) => conditions::evaluate_conditions(fields, conditions),
+            StepMatcher::EventType(step) => {
+                let text = |name: &&str| match fields.get(*name)? {
+                    Value::String(s) => Some(s.clone()),
+                    Value::Number(n) => Some(n.to_string()),
+                    _ => None,
+                };
+                let typed = EVENT_TYPE_FIELDS.iter().filter_map(text).any(|t| t.eq_ignore_ascii_case(step));
+                let identified = step
+                    .rsplit_once(':')
+                    .is_some_and(|(_, id)| EVENT_ID_FIELDS.iter().filter_map(text).any(|t| t == id));
+                (typed || identified).then_some(1.0)
+            }
+        }
+    }
+}
+
+/// Window arithmetic of one correlation rule
+#[derive(Clone, Copy)]
+struct Window {
+    millis: i64,
+    sliding: bool,
+}
+
+impl Window {
+    fn of(rule: &CorrelationRule, rule_windows: &[crate::TimeWindow]) -> Self {
+        let stage_window = rule
+            .rule_id
+            .strip_suffix("threshold")
+            .filter(|prefix| prefix.ends_with(STAGE_SEPARATOR))
+            .map(|prefix| format!("{}window", prefix));
+        let sliding = rule_windows
+            .iter()
+            .find(|w| w.window_id == rule.rule_id || Some(&w.window_id) == stage_window.as_ref())
+            .is_none_or(|w| w.sliding);
+        Window { millis: rule.time_window.num_milliseconds().max(1), sliding }
+    }
+
+    /// Whether an event at `at` still shares a window with one at `since`
+    fn holds(&self, since: DateTime<Utc>, at: DateTime<Utc>) -> bool {
+        if self.sliding {
+            (at - since).num_milliseconds() <= self.millis
+        } else {
+            since.timestamp_millis().div_euclid(self.millis) == at.timestamp_millis().div_euclid(self.millis)
+        }
+    }
+}
+
+/// Progress of one key towards an ordered or unordered rule
+struct Partial {
+    started: DateTime<Utc>,
+    next: usize,
+    events: Vec<ContributingEvent>,
+}
+
+#[derive(Default)]
+struct KeyState {
+    /// Threshold and unordered rules: events in the current window
+    events: VecDeque<ContributingEvent>,
+    /// Sequence rules: unfinished sequences, oldest first
+    partials: VecDeque<Partial>,
+    last_seen: Option<DateTime<Utc>>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Threshold,
+    Sequence,
+    Unordered,
+}
+
+struct Tracker {
+    rule: CorrelationRule,
+    kind: Kind,
+    window: Window,
+    /// Steps fed by a threshold tracker rather than by single events
+    threshold_fed: Vec<bool>,
+    /// Threshold trackers: the (tracker, step) their firing feeds
+    feeds: Option<(usize, usize)>,
+    keys: HashMap<String, KeyState>,
+}
+
+impl Tracker {
+    fn key(&self, fields: &HashMap<String, Value>) -> Option<String> {
+        let mut parts = Vec::with_capacity(self.rule.correlation_fields.len());
+        for field in &self.rule.correlation_fields {
+            match fields.get(field)? {
+                Value::Null => return None,
+                Value::String(s) => parts.push(s.to_lowercase()),
+                other => parts.push(other.to_string()),
+            }
+        }
+        Some(parts.join("\u{1f}"))
+    }
+
+    /// Feed an occurrence of `step`; returns the events of the rule when it fires
+    fn deliver(&mut self, step: usize, occurrence: Vec<ContributingEvent>) -> Option<Vec<ContributingEvent>> {
+        let last = occurrence.last()?;
+        let at = last.timestamp;
+        let key = self.key(&last.fields)?;
+        self.evict(at);
+        let window = self.window;
+        let (kind, steps, minimum) = (self.kind, self.rule.events_to_correlate.len(), self.rule.minimum_occurrences.max(1) as usize);
+        let state = self.keys.entry(key).or_default();
+        state.last_seen = Some(at);
+
+        match kind {
+            Kind::Threshold | Kind::Unordered => {
+                while state.events.front().is_some_and(|e| !window.holds(e.timestamp, at)) {
+                    state.events.pop_front();
+                }
+                state.events.extend(occurrence);
+                let complete = kind == Kind::Threshold
+                    || self.rule.events_to_correlate.iter().all(|s| state.events.iter().any(|e| &e.step == s));
+                (complete && state.events.len() >= minimum).then(|| state.events.drain(..).collect())
+            }
+            Kind::Sequence => {
+                state.partials.retain(|p| window.holds(p.started, at));
+                if step > 0 {
+                    let position = state.partials.iter().position(|p| p.next == step)?;
+                    let partial = &mut state.partials[position];
+                    partial.events.extend(occurrence);
+                    partial.next += 1;
+                    if partial.next < steps {
+                        return None;
+                    }
+                    return state.partials.remove(position).map(|p| p.events);
+                }
+                let started = occurrence.first().map_or(at, |e| e.timestamp);
+                if steps <= 1 {
+                    return Some(occurrence);
+                }
+                if state.partials.len() >= MAX_PARTIAL_SEQUENCES {
+                    state.partials.pop_front();
+                }
+                state.partials.push_back(Partial { started, next: 1, events: occurrence });
+                None
+            }
+        }
+    }
+
+    /// Drop the least recently active keys beyond `MAX_TRACKED_KEYS`
+    fn evict(&mut self, at: DateTime<Utc>) {
+        if self.keys.len() < MAX_TRACKED_KEYS {
+            return;
+        }
+        let window = self.window;
+        self.keys.retain(|_, state| state.last_seen.is_some_and(|seen| window.holds(seen, at)));
+        if self.keys.len() >= MAX_TRACKED_KEYS {
+            let mut by_age: Vec<(DateTime<Utc>, String)> =
+                self.keys.iter().map(|(key, state)| (state.last_seen.unwrap_or(at), key.clone())).collect();
+            by_age.sort();
+            for (_, key) in by_age.into_iter().take(self.keys.len() + 1 - MAX_TRACKED_KEYS) {
+                self.keys.remove(&key);
+            }
+        }
+    }
+}
+
+/// Correlation state of one rule over one event stream
+pub struct RuleCorrelator {
+    rule_id: String,
+    risk_weight: f64,
+    steps: HashMap<String, StepMatcher>,
+    trackers: Vec<Tracker>,
+}
+
+impl RuleCorrelator {
+    /// `None` when the rule has no correlation rules
+    pub fn new(rule: &HuntingRule) -> Option<Self> {
+        let logic = &rule.detection_logic;
+        if logic.correlation_rules.is_empty() {
+            return None;
+        }
+        let mut trackers: Vec<Tracker> = logic
+            .correlation_rules
+            .iter()
+            .filter(|r| !r.events_to_correlate.is_empty())
+            .map(|r| {
+                let kind = if r.scoring_algorithm == SEQUENCE_SCORING {
+                    Kind::Sequence
+                } else if r.scoring_algorithm == THRESHOLD_SCORING || r.events_to_correlate.len() == 1 {
+                    Kind::Threshold
+                } else {
+                    Kind::Unordered
+                };
+                Tracker {
+                    rule: r.clone(),
+                    kind,
+                    window: Window::of(r, &logic.time_windows),
+                    threshold_fed: vec![false; r.events_to_correlate.len()],
+                    feeds: None,
+                    keys: HashMap::new(),
+                }
+            })
+            .collect();
+
+        for index in 0..trackers.len() {
+            if trackers[index].kind != Kind::Threshold {
+                continue;
+            }
+            let step = trackers[index].rule.events_to_correlate[0].clone();
+            let target = trackers.iter().enumerate().find_map(|(other, tracker)| {
+                let position = tracker.rule.events_to_correlate.iter().position(|s| *s == step)?;
+                (tracker.kind != Kind::Threshold).then_some((other, position))
+            });
+            if let Some((other, position)) = target {
+                trackers[other].threshold_fed[position] = true;
+                trackers[index].feeds = Some((other, position));
+            }
+        }
+
+        let steps = trackers
+            .iter()
+            .flat_map(|t| t.rule.events_to_correlate.iter())
+            .map(|step| (step.clone(), StepMatcher::new(&logic.conditions, step)))
+            .collect();
+        Some(Self { rule_id: rule.id.clone(), risk_weight: rule.severity.risk_weight(), steps, trackers })
+    }
+
+    /// Correlate one event; events should arrive in time order
+    pub fn observe(&mut self, source: &str, timestamp: DateTime<Utc>, fields: &HashMap<String, Value>) -> Vec<HuntingMatch> {
+        let satisfied: HashMap<&str, f64> = self
+            .steps
+            .iter()
+            .filter_map(|(step, matcher)| Some((step.as_str(), matcher.confidence(fields)?)))
+            .collect();
+        if satisfied.is_empty() {
+            return Vec::new();
+        }
+        let contribution = |step: &str, confidence: f64| ContributingEvent {
+            step: step.to_string(),
+            timestamp,
+            source: source.to_string(),
+            fields: fields.clone(),
+            confidence,
+        };
+
+        // (tracker, step, occurrence), latest steps first so one event cannot both start and advance a sequence
+        let mut deliveries: Vec<(usize, usize, Vec<ContributingEvent>)> = Vec::new();
+        for (index, tracker) in self.trackers.iter().enumerate() {
+            for (position, step) in tracker.rule.events_to_correlate.iter().enumerate() {
+                if let (false, Some(confidence)) = (tracker.threshold_fed[position], satisfied.get(step.as_str())) {
+                    deliveries.push((index, position, vec![contribution(step, *confidence)]));
+                }
+            }
+        }
+        deliveries.sort_by_key(|(index, position, _)| (self.trackers[*index].kind != Kind::Threshold, std::cmp::Reverse(*position)));
+
+        let mut fired = Vec::new();
+        let mut queue: VecDeque<_> = deliveries.into();
+        while let Some((index, position, occurrence)) = queue.pop_front() {
+            let Some(events) = self.trackers[index].deliver(position, occurrence) else {
+                continue;
+            };
+            match self.trackers[index].feeds {
+                Some((target, step)) => queue.push_back((target, step, events)),
+                None => fired.push(self.correlated_match(&self.trackers[index], events)),
+            }
+        }
+        fired
+    }
+
+    fn correlated_match(&self, tracker: &Tracker, events: Vec<ContributingEvent>) -> HuntingMatch {
+        let last = events.last().cloned();
+        let confidence = events.iter().map(|e| e.confidence).sum::<f64>() / events.len().max(1) as f64;
+        let same_source = events.windows(2).all(|pair| pair[0].source == pair[1].source);
+        let source = match &last {
+            Some(event) if same_source => event.source.clone(),
+            _ => "correlation".to_string(),
+        };
+        let fields = last.as_ref().map(|e| e.fields.clone()).unwrap_or_default();
+        let mut hunt_match = event_match(&source, last.map(|e| e.timestamp), fields, confidence, self.risk_weight);
+        hunt_match.correlations = vec![EventCorrelation {
+            correlation_id: format!("{}:{}", self.rule_id, tracker.rule.rule_id),
+            correlated_events: events.iter().map(|e| e.step.clone()).collect(),
+            correlation_type: match tracker.kind {
+                Kind::Sequence => CorrelationType::Sequential,
+                Kind::Threshold => CorrelationType::Temporal,
+                Kind::Unordered => CorrelationType::Behavioral,
+            },
+            correlation_strength: confidence,
+            time_window: tracker.rule.time_window,
+            shared_attributes: tracker.rule.correlation_fields.clone(),
+        }];
+        hunt_match.contributing_events = events;
+        hunt_match
+    }
+}
+
+/// Correlated matches of `rule` over `events`, in time order
+pub fn correlate<'a>(rule: &HuntingRule, events: impl IntoIterator<Item = (&'a str, DateTime<Utc>, &'a HashMap<String, Value>)>) -> Vec<HuntingMatch> {
+    let Some(mut correlator) = RuleCorrelator::new(rule) else {
+        return Vec::new();
+    };
+    let mut events: Vec<_> = events.into_iter().collect();
+    events.sort_by_key(|(_, at, _)| *at);
+    events.into_iter().flat_map(|(source, at, fields)| correlator.observe(source, at, fields)).collect()
+}
+
+/// Correlated matches of `rule` over the events of single-event matches
+pub fn correlate_matches(rule: &HuntingRule, matches: &[HuntingMatch]) -> Vec<HuntingMatch> {
+    correlate(rule, matches.iter().map(|m| (m.source.as_str(), m.timestamp, &m.event_data)))
+}
+
+/// Events from JSON objects with a `timestamp` (or `@timestamp`) and optional `source`
+pub(crate) fn events_from_records(records: &[Value]) -> Vec<NormalizedEvent> {
+    let received_at = Utc::now();
+    records
+        .iter()
+        .map(|record| {
+            let fields = crate::connectors::flatten(record);
+            let timestamp = ["timestamp", "@timestamp"]
+                .iter()
+                .find_map(|name| crate::ingestion::event_time(fields.get(*name)?))
+                .unwrap_or(received_at);
+            let source = fields.get("source").and_then(Value::as_str).unwrap_or("api").to_string();
+            NormalizedEvent { timestamp, received_at, source, fields }
+        })
+        .collect()
+}
+
+impl HuntingCore {
+    /// Correlate `events` with a rule's correlation rules
+    pub async fn correlate_events(&self, tenant_id: &str, rule_id: &str, events: &[NormalizedEvent]) -> CoreResult<Vec<HuntingMatch>> {
+        let rules = self.rules.read().await;
+        let rule = rules
+            .get(rule_id)
+            .filter(|rule| tenancy::rule_visible(rule, tenant_id))
+            .ok_or_else(|| CoreError::not_found(format!("Rule {} not found", rule_id)))?;
+        if rule.detection_logic.correlation_rules.is_empty() {
+            return Err(CoreError::validation(format!("Rule {} has no correlation rules", rule_id)));
+        }
+        Ok(correlate(rule, events.iter().map(|e| (e.source.as_str(), e.timestamp, &e.fields))))
+    }
+}
+
+#[napi]
+impl HuntingCoreNapi {
+    /// Correlate a JSON array of events (objects with a `timestamp`) with a rule's correlation rules
+    #[napi]
+    pub async fn correlate_events(&self, rule_id: String, events_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let records: Vec<Value> = serde_json::from_str(&events_json)
+            .map_err(|e| CoreError::from(e).context("Failed to parse events"))?;
+        let events = events_from_records(&records);
+        let matches = self.inner.correlate_events(&tenant_id, &rule_id, &events).await
+            .map_err(|e| e.context("Failed to correlate events"))?;
+        serde_json::to_string(&matches)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize matches: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use serde_json::json;
+
+    fn rule(source: &str) -> HuntingRule {
+        let core = HuntingCore::new().unwrap();
+        let mut rule = core.rules.blocking_read().get("apt_lateral_movement").cloned().unwrap();
+        rule.detection_logic = dsl::compile_source(source).unwrap().1;
+        rule
+    }
+
+    fn at(minute: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 16, 9, 0, 0).unwrap() + Duration::minutes(minute)
+    }
+
+    fn event(fields: serde_json::Value) -> HashMap<String, Value> {
+        crate::connectors::flatten(&fields)
+    }
+
+    #[test]
+    fn test_threshold_then_sequence_per_key() {
+        let rule = rule("logon.type == 3 AND count(logon by user) > 2 within 5m FOLLOWED BY process.name in (psexec.exe) WITHIN 10m");
+        let logon = |user: &str| event(json!({ "logon": { "type": 3 }, "user": user }));
+        let psexec = |user: &str| event(json!({ "process": { "name": "PsExec.exe" }, "user": user }));
+        let events = vec![
+            (0, psexec("alice")),
+            (0, logon("alice")),
+            (1, logon("bob")),
+            (2, logon("alice")),
+            (9, logon("alice")),
+            (10, logon("alice")),
+            (11, logon("alice")),
+            (12, psexec("bob")),
+            (13, psexec("alice")),
+            (14, psexec("alice")),
+        ];
+        let matches = correlate(&rule, events.iter().map(|(minute, fields)| ("sysmon", at(*minute), fields)));
+
+        assert_eq!(matches.len(), 1, "only alice's burst within 5m is followed by psexec");
+        let correlated = &matches[0];
+        let steps: Vec<&str> = correlated.contributing_events.iter().map(|e| e.step.as_str()).collect();
+        assert_eq!(steps, vec!["logon", "logon", "logon", "stage2"]);
+        assert_eq!(correlated.contributing_events[0].timestamp, at(9));
+        assert_eq!(correlated.timestamp, at(13));
+        assert!(matches!(correlated.correlations[0].correlation_type, CorrelationType::Sequential));
+        assert_eq!(correlated.correlations[0].shared_attributes, vec!["user".to_string()]);
+        assert_eq!(correlated.correlations[0].correlation_id, format!("{}:sequence", rule.id));
+    }
+
+    #[test]
+    fn test_tumbling_windows_and_event_type_steps() {
+        let mut rule = rule("a == 1");
+        rule.detection_logic.correlation_rules = vec![CorrelationRule {
+            rule_id: "burst".to_string(),
+            events_to_correlate: vec!["SecurityEvent:4625".to_string(), "account_lockout".to_string()],
+            time_window: Duration::minutes(10),
+            minimum_occurrences: 3,
+            correlation_fields: vec!["TargetUserName".to_string()],
+            scoring_algorithm: "weighted_sum".to_string(),
+        }];
+        let failure = event(json!({ "EventID": 4625, "TargetUserName": "Alice" }));
+        let lockout = event(json!({ "event_type": "ACCOUNT_LOCKOUT", "TargetUserName": "alice" }));
+        let stream = [(1, &failure), (2, &failure), (3, &lockout), (8, &failure), (12, &failure), (13, &lockout)];
+
+        let sliding = correlate(&rule, stream.iter().map(|(minute, fields)| ("dc", at(*minute), *fields)));
+        let windows: Vec<usize> = sliding.iter().map(|m| m.contributing_events.len()).collect();
+        assert_eq!(windows, vec![3, 3], "the second burst spans 09:08-09:13");
+        assert!(matches!(sliding[0].correlations[0].correlation_type, CorrelationType::Behavioral));
+
+        rule.detection_logic.time_windows = vec![crate::TimeWindow {
+            window_id: "burst".to_string(),
+            duration: Duration::minutes(10),
+            sliding: false,
+            aggregation_method: "count".to_string(),
+            threshold_type: crate::ThresholdType::Count,
+        }];
+        let tumbling = correlate(&rule, stream.iter().map(|(minute, fields)| ("dc", at(*minute), *fields)));
+        let windows: Vec<usize> = tumbling.iter().map(|m| m.contributing_events.len()).collect();
+        assert_eq!(windows, vec![3], "09:10 starts a new window that only has two events");
+    }
+
+    #[tokio::test]
+    async fn test_streamed_batches_share_correlation_state() {
+        let core = HuntingCore::new().unwrap();
+        core.set_rule_dsl("default", "apt_lateral_movement", "action == failed_login AND count(brute by user) >= 3 within 1m")
+            .await
+            .unwrap();
+        let config = serde_json::from_value(json!({ "name": "auth", "rule_ids": ["apt_lateral_movement"], "timestamp_field": "ts" })).unwrap();
+        core.configure_ingestion_source("default", config).await.unwrap();
+
+        let line = |second: u32, user: &str| format!("{{\"ts\": \"2026-10-16T09:00:{:02}Z\", \"action\": \"failed_login\", \"user\": \"{}\"}}\n", second, user);
+        for batch in [line(1, "alice") + &line(2, "bob"), line(3, "alice"), line(4, "alice")] {
+            core.ingest_event_batch("default", "auth", batch.as_bytes()).await.unwrap();
+        }
+        let mut matches = Vec::new();
+        for _ in 0..300 {
+            matches = core.continuous_matches("default", None);
+            if !matches.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(matches.len(), 1);
+        let users: Vec<&Value> = matches[0].hunt_match.contributing_events.iter().map(|e| &e.fields["user"]).collect();
+        assert_eq!(users, vec![&json!("alice"); 3]);
+    }
+}
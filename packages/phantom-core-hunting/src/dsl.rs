@@ -0,0 +1,1220 @@
+//! Behavioral Rule DSL
+//!
+//! A compact text form for detection logic, e.g.
+//!
+//! ```text
+//! logon.type == 3 AND count(logon by user) > 3 within 5m
+//!     FOLLOWED BY process.name in (psexec.exe) WITHIN 10m BY user
+//! ```
+//!
+//! A rule is one or more stages joined by `FOLLOWED BY`. A stage is a
+//! boolean expression (`AND`, `OR`, `NOT`, parentheses) over field
+//! predicates (`==`, `!=`, `>`, `>=`, `<`, `<=`, `in (...)`, `not in`,
+//! `contains`, `startswith`, `endswith`, `matches`, `exists`), optionally
+//! ANDed with one `count(<label> by <fields>) > N within <duration>`
+//! threshold. A sequence may end with `WITHIN <duration> BY <fields>`
+//! bounding the time between its first and last stage; without `BY` it is
+//! keyed by the first threshold's fields. Keywords are case-insensitive and
+//! durations are `s`, `m`, `h` or `d`.
+//!
+//! Stages compile into one `DetectionLogic`: each stage's predicates become
+//! conditions with ids `<label>.<n>` (the label is the threshold's, or
+//! `stage<n>`), a threshold becomes a `TimeWindow` and a `<label>.threshold`
+//! `CorrelationRule`, and a sequence becomes the `sequence` rule listing the
+//! stage labels in order. Since conditions are flat required/optional
+//! lists, a stage must be a conjunction of predicates or a disjunction of
+//! them; alternatives of one field (`a == x OR a == y`) fold into `in`.
+//! Expressions beyond that are reported with the span of the part that
+//! cannot be compiled. [`decompile`] turns logic of that shape back into a
+//! rule whose `Display` is the canonical source.
+
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::error::{CoreError, CoreResult};
+use crate::{
+    tenancy, CorrelationRule, DetectionCondition, DetectionLogic, HuntingCore, HuntingCoreNapi, HuntingRule, RuleType,
+    ThresholdType, TimeWindow,
+};
+
+/// Separates a condition id's stage label from its index
+pub const STAGE_SEPARATOR: char = '.';
+/// Id of the correlation rule holding a rule's stage sequence
+pub const SEQUENCE_RULE_ID: &str = "sequence";
+/// `scoring_algorithm` of count thresholds and of sequences
+pub const THRESHOLD_SCORING: &str = "threshold";
+pub const SEQUENCE_SCORING: &str = "sequence";
+/// Window of a sequence that does not give one
+pub const DEFAULT_SEQUENCE_WINDOW_MINUTES: i64 = 15;
+
+const KEYWORDS: &[&str] = &[
+    "and", "or", "not", "in", "followed", "by", "within", "count", "contains", "startswith", "endswith", "matches", "exists",
+];
+
+// AST
+
+/// Byte range of a construct in the rule source
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    fn to(self, other: Span) -> Span {
+        Span { start: self.start.min(other.start), end: self.end.max(other.end) }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, thiserror::Error)]
+#[error("{message} (at {}..{})", span.start, span.end)]
+pub struct DslError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl DslError {
+    fn new(message: impl Into<String>, span: Span) -> Self {
+        Self { message: message.into(), span }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operator {
+    Eq,
+    Ne,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    In,
+    NotIn,
+    Contains,
+    NotContains,
+    StartsWith,
+    EndsWith,
+    Matches,
+    NotMatches,
+    Exists,
+}
+
+impl Operator {
+    /// The `DetectionCondition` operator the evaluator knows it by
+    pub fn condition_operator(self) -> &'static str {
+        match self {
+            Operator::Eq => "equals",
+            Operator::Ne => "not_equals",
+            Operator::Gt => "gt",
+            Operator::Gte => "gte",
+            Operator::Lt => "lt",
+            Operator::Lte => "lte",
+            Operator::In => "in",
+            Operator::NotIn => "not_in",
+            Operator::Contains => "contains",
+            Operator::NotContains => "not_contains",
+            Operator::StartsWith => "starts_with",
+            Operator::EndsWith => "ends_with",
+            Operator::Matches => "regex",
+            Operator::NotMatches => "not_regex",
+            Operator::Exists => "exists",
+        }
+    }
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Operator::Eq => "==",
+            Operator::Ne => "!=",
+            Operator::Gt => ">",
+            Operator::Gte => ">=",
+            Operator::Lt => "<",
+            Operator::Lte => "<=",
+            Operator::In => "in",
+            Operator::NotIn => "not in",
+            Operator::Contains => "contains",
+            Operator::NotContains => "not contains",
+            Operator::StartsWith => "startswith",
+            Operator::EndsWith => "endswith",
+            Operator::Matches => "matches",
+            Operator::NotMatches => "not matches",
+            Operator::Exists => "exists",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Predicate {
+    pub field: String,
+    pub operator: Operator,
+    /// A list for `in` / `not in`, `null` for `exists`
+    pub value: Value,
+    #[serde(default)]
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Threshold {
+    /// Names the stage; `stage<n>` when absent
+    pub label: Option<String>,
+    pub by: Vec<String>,
+    /// `>` or `>=`
+    pub operator: Operator,
+    pub count: u32,
+    pub within: Duration,
+    #[serde(default)]
+    pub span: Span,
+}
+
+impl Threshold {
+    pub fn minimum_occurrences(&self) -> u32 {
+        match self.operator {
+            Operator::Gt => self.count.saturating_add(1),
+            _ => self.count,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Expr {
+    Predicate(Predicate),
+    Count(Threshold),
+    Not(Box<Expr>, Span),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+}
+
+impl Expr {
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Predicate(predicate) => predicate.span,
+            Expr::Count(threshold) => threshold.span,
+            Expr::Not(_, span) => *span,
+            Expr::And(items) | Expr::Or(items) => match (items.first(), items.last()) {
+                (Some(first), Some(last)) => first.span().to(last.span()),
+                _ => Span::default(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequenceClause {
+    pub within: Duration,
+    pub by: Vec<String>,
+    #[serde(default)]
+    pub span: Span,
+}
+
+/// A parsed rule: its stages in order and the window bounding their sequence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleExpr {
+    pub stages: Vec<Expr>,
+    pub sequence: Option<SequenceClause>,
+}
+
+// Tokenizer
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Word(String),
+    Str(String),
+    Sym(&'static str),
+    Open,
+    Close,
+    Comma,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    tok: Tok,
+    span: Span,
+}
+
+fn is_word_char(c: char) -> bool {
+    !c.is_whitespace() && !matches!(c, '(' | ')' | ',' | '"' | '\'' | '=' | '!' | '<' | '>')
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, DslError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let span = |end: usize| Span { start, end };
+        let single = match c {
+            '(' => Some(Tok::Open),
+            ')' => Some(Tok::Close),
+            ',' => Some(Tok::Comma),
+            _ => None,
+        };
+        if let Some(tok) = single {
+            chars.next();
+            tokens.push(Token { tok, span: span(start + 1) });
+            continue;
+        }
+        match c {
+            '"' | '\'' => {
+                chars.next();
+                let mut text = String::new();
+                let mut end = None;
+                while let Some((at, ch)) = chars.next() {
+                    match ch {
+                        '\\' if chars.peek().is_some_and(|&(_, next)| next == c || next == '\\') => {
+                            text.push(chars.next().map(|(_, next)| next).unwrap_or('\\'));
+                        }
+                        ch if ch == c => {
+                            end = Some(at + 1);
+                            break;
+                        }
+                        ch => text.push(ch),
+                    }
+                }
+                let end = end.ok_or_else(|| DslError::new("unterminated string", span(source.len())))?;
+                tokens.push(Token { tok: Tok::Str(text), span: span(end) });
+            }
+            '=' | '!' | '<' | '>' => {
+                chars.next();
+                let doubled = chars.peek().is_some_and(|&(_, next)| next == '=');
+                if doubled {
+                    chars.next();
+                }
+                let sym = match (c, doubled) {
+                    ('=', _) => "==",
+                    ('!', true) => "!=",
+                    ('<', false) => "<",
+                    ('<', true) => "<=",
+                    ('>', false) => ">",
+                    ('>', true) => ">=",
+                    _ => return Err(DslError::new("expected '!='", span(start + 1))),
+                };
+                tokens.push(Token { tok: Tok::Sym(sym), span: span(start + if doubled { 2 } else { 1 }) });
+            }
+            _ => {
+                let mut end = start;
+                while let Some(&(at, ch)) = chars.peek() {
+                    if !is_word_char(ch) {
+                        break;
+                    }
+                    end = at + ch.len_utf8();
+                    chars.next();
+                }
+                tokens.push(Token { tok: Tok::Word(source[start..end].to_string()), span: span(end) });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+// Parser
+
+/// Parse rule source, reporting the first syntax error
+pub fn parse(source: &str) -> Result<RuleExpr, DslError> {
+    let mut parser = Parser { tokens: tokenize(source)?, pos: 0, len: source.len() };
+    let rule = parser.parse_rule()?;
+    match parser.tokens.get(parser.pos) {
+        Some(token) => Err(DslError::new("unexpected input after the rule", token.span)),
+        None => Ok(rule),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    len: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn end_span(&self) -> Span {
+        Span { start: self.len, end: self.len }
+    }
+
+    /// Span of the next token, or of the end of input
+    fn here(&self) -> Span {
+        self.peek().map(|t| t.span).unwrap_or_else(|| self.end_span())
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token { tok: Tok::Word(word), .. }) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> Option<Span> {
+        if self.peek_keyword(keyword) {
+            self.next().map(|t| t.span)
+        } else {
+            None
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<Span, DslError> {
+        self.eat_keyword(keyword)
+            .ok_or_else(|| DslError::new(format!("expected '{}'", keyword.to_uppercase()), self.here()))
+    }
+
+    fn eat(&mut self, tok: &Tok) -> Option<Span> {
+        if self.peek().map(|t| &t.tok) == Some(tok) {
+            self.next().map(|t| t.span)
+        } else {
+            None
+        }
+    }
+
+    fn expect(&mut self, tok: &Tok, what: &str) -> Result<Span, DslError> {
+        self.eat(tok).ok_or_else(|| DslError::new(format!("expected {}", what), self.here()))
+    }
+
+    fn parse_rule(&mut self) -> Result<RuleExpr, DslError> {
+        let mut stages = vec![self.parse_or()?];
+        while let Some(start) = self.eat_keyword("followed") {
+            self.expect_keyword("by").map_err(|e| DslError::new("expected 'BY' after 'FOLLOWED'", start.to(e.span)))?;
+            stages.push(self.parse_or()?);
+        }
+        let sequence = match self.eat_keyword("within") {
+            Some(start) => {
+                let (within, mut span) = self.parse_duration()?;
+                let mut by = Vec::new();
+                if self.eat_keyword("by").is_some() {
+                    let (fields, fields_span) = self.parse_fields()?;
+                    by = fields;
+                    span = span.to(fields_span);
+                }
+                Some(SequenceClause { within, by, span: start.to(span) })
+            }
+            None => None,
+        };
+        Ok(RuleExpr { stages, sequence })
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, DslError> {
+        let mut items = vec![self.parse_and()?];
+        while self.eat_keyword("or").is_some() {
+            items.push(self.parse_and()?);
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { Expr::Or(items) })
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, DslError> {
+        let mut items = vec![self.parse_unary()?];
+        while self.eat_keyword("and").is_some() {
+            items.push(self.parse_unary()?);
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { Expr::And(items) })
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, DslError> {
+        if let Some(start) = self.eat_keyword("not") {
+            let inner = self.parse_unary()?;
+            let span = start.to(inner.span());
+            return Ok(Expr::Not(Box::new(inner), span));
+        }
+        if self.eat(&Tok::Open).is_some() {
+            let expr = self.parse_or()?;
+            self.expect(&Tok::Close, "')'")?;
+            return Ok(expr);
+        }
+        if self.peek_keyword("count") && self.tokens.get(self.pos + 1).is_some_and(|t| t.tok == Tok::Open) {
+            return self.parse_count().map(Expr::Count);
+        }
+        self.parse_predicate().map(Expr::Predicate)
+    }
+
+    fn parse_field(&mut self) -> Result<(String, Span), DslError> {
+        match self.next() {
+            Some(Token { tok: Tok::Word(word), span }) if !is_keyword(&word) => Ok((word, span)),
+            Some(token) => Err(DslError::new("expected a field name", token.span)),
+            None => Err(DslError::new("expected a field name", self.end_span())),
+        }
+    }
+
+    fn parse_fields(&mut self) -> Result<(Vec<String>, Span), DslError> {
+        let (first, mut span) = self.parse_field()?;
+        let mut fields = vec![first];
+        while self.eat(&Tok::Comma).is_some() {
+            let (field, field_span) = self.parse_field()?;
+            fields.push(field);
+            span = span.to(field_span);
+        }
+        Ok((fields, span))
+    }
+
+    fn parse_predicate(&mut self) -> Result<Predicate, DslError> {
+        let (field, start) = self.parse_field()?;
+        let op_span = self.here();
+        let negated = self.eat_keyword("not").is_some();
+        let operator = match self.next() {
+            Some(Token { tok: Tok::Sym(sym), .. }) if !negated => match sym {
+                "==" => Operator::Eq,
+                "!=" => Operator::Ne,
+                ">" => Operator::Gt,
+                ">=" => Operator::Gte,
+                "<" => Operator::Lt,
+                _ => Operator::Lte,
+            },
+            Some(Token { tok: Tok::Word(word), span }) => match (word.to_ascii_lowercase().as_str(), negated) {
+                ("in", false) => Operator::In,
+                ("in", true) => Operator::NotIn,
+                ("contains", false) => Operator::Contains,
+                ("contains", true) => Operator::NotContains,
+                ("matches", false) => Operator::Matches,
+                ("matches", true) => Operator::NotMatches,
+                ("startswith", false) => Operator::StartsWith,
+                ("endswith", false) => Operator::EndsWith,
+                ("exists", false) => Operator::Exists,
+                _ => return Err(DslError::new(format!("unknown operator '{}'", word), op_span.to(span))),
+            },
+            Some(token) => return Err(DslError::new("expected an operator", op_span.to(token.span))),
+            None => return Err(DslError::new("expected an operator", self.end_span())),
+        };
+
+        let (value, end) = match operator {
+            Operator::Exists => (Value::Null, self.tokens[self.pos - 1].span),
+            Operator::In | Operator::NotIn => self.parse_list()?,
+            _ => self.parse_value()?,
+        };
+        if let (Operator::Matches | Operator::NotMatches, Some(pattern)) = (operator, value.as_str()) {
+            if let Err(e) = regex::Regex::new(pattern) {
+                return Err(DslError::new(format!("invalid regular expression: {}", e), end));
+            }
+        }
+        Ok(Predicate { field, operator, value, span: start.to(end) })
+    }
+
+    fn parse_value(&mut self) -> Result<(Value, Span), DslError> {
+        match self.next() {
+            Some(Token { tok: Tok::Str(text), span }) => Ok((Value::String(text), span)),
+            Some(Token { tok: Tok::Word(word), span }) => Ok((scalar(&word), span)),
+            Some(token) => Err(DslError::new("expected a value", token.span)),
+            None => Err(DslError::new("expected a value", self.end_span())),
+        }
+    }
+
+    fn parse_list(&mut self) -> Result<(Value, Span), DslError> {
+        let start = self.expect(&Tok::Open, "'(' starting a list")?;
+        let mut values = Vec::new();
+        loop {
+            values.push(self.parse_value()?.0);
+            if self.eat(&Tok::Comma).is_none() {
+                break;
+            }
+        }
+        let end = self.expect(&Tok::Close, "')' closing the list")?;
+        Ok((Value::Array(values), start.to(end)))
+    }
+
+    fn parse_count(&mut self) -> Result<Threshold, DslError> {
+        let start = self.expect_keyword("count")?;
+        self.expect(&Tok::Open, "'('")?;
+        let label = match self.peek() {
+            Some(Token { tok: Tok::Word(word), span }) if !is_keyword(word) => {
+                if word.contains(STAGE_SEPARATOR) {
+                    return Err(DslError::new(format!("stage label cannot contain '{}'", STAGE_SEPARATOR), *span));
+                }
+                let label = word.clone();
+                self.pos += 1;
+                Some(label)
+            }
+            _ => None,
+        };
+        let by = match self.eat_keyword("by") {
+            Some(_) => self.parse_fields()?.0,
+            None => Vec::new(),
+        };
+        self.expect(&Tok::Close, "')'")?;
+
+        let operator = match self.next() {
+            Some(Token { tok: Tok::Sym(">"), .. }) => Operator::Gt,
+            Some(Token { tok: Tok::Sym(">="), .. }) => Operator::Gte,
+            Some(token) => return Err(DslError::new("count thresholds compare with '>' or '>='", token.span)),
+            None => return Err(DslError::new("expected '>' or '>='", self.end_span())),
+        };
+        let count = match self.next() {
+            Some(Token { tok: Tok::Word(word), span }) => word
+                .parse::<u32>()
+                .map_err(|_| DslError::new("expected a whole number of events", span))?,
+            Some(token) => return Err(DslError::new("expected a whole number of events", token.span)),
+            None => return Err(DslError::new("expected a whole number of events", self.end_span())),
+        };
+        self.expect_keyword("within")
+            .map_err(|e| DslError::new("count thresholds need a 'within' window", e.span))?;
+        let (within, end) = self.parse_duration()?;
+        Ok(Threshold { label, by, operator, count, within, span: start.to(end) })
+    }
+
+    fn parse_duration(&mut self) -> Result<(Duration, Span), DslError> {
+        match self.next() {
+            Some(Token { tok: Tok::Word(word), span }) => parse_duration(&word)
+                .map(|duration| (duration, span))
+                .ok_or_else(|| DslError::new(format!("invalid duration '{}', expected e.g. 30s, 5m, 1h or 2d", word), span)),
+            Some(token) => Err(DslError::new("expected a duration", token.span)),
+            None => Err(DslError::new("expected a duration", self.end_span())),
+        }
+    }
+}
+
+fn is_keyword(word: &str) -> bool {
+    KEYWORDS.iter().any(|keyword| word.eq_ignore_ascii_case(keyword))
+}
+
+/// Numbers and booleans as themselves, anything else as a string
+fn scalar(word: &str) -> Value {
+    if let Ok(n) = word.parse::<i64>() {
+        return json!(n);
+    }
+    let numeric = word.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '.');
+    match word.parse::<f64>() {
+        Ok(n) if numeric && n.is_finite() => return json!(n),
+        _ => {}
+    }
+    match word {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => Value::String(word.to_string()),
+    }
+}
+
+fn parse_duration(text: &str) -> Option<Duration> {
+    let unit = text.chars().last()?;
+    let amount: i64 = text[..text.len() - unit.len_utf8()].parse().ok().filter(|n| *n > 0)?;
+    match unit.to_ascii_lowercase() {
+        's' => Some(Duration::seconds(amount)),
+        'm' => Some(Duration::minutes(amount)),
+        'h' => Some(Duration::hours(amount)),
+        'd' => Some(Duration::days(amount)),
+        _ => None,
+    }
+}
+
+// Display
+
+fn format_duration(duration: &Duration) -> String {
+    let seconds = duration.num_seconds();
+    match seconds {
+        s if s % 86_400 == 0 => format!("{}d", s / 86_400),
+        s if s % 3_600 == 0 => format!("{}h", s / 3_600),
+        s if s % 60 == 0 => format!("{}m", s / 60),
+        s => format!("{}s", s),
+    }
+}
+
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => {
+            let bare = !text.is_empty()
+                && text.chars().all(is_word_char)
+                && !is_keyword(text)
+                && scalar(text) == Value::String(text.clone());
+            if bare {
+                text.clone()
+            } else {
+                format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+            }
+        }
+        Value::Array(values) => format!("({})", values.iter().map(format_value).collect::<Vec<_>>().join(", ")),
+        other => other.to_string(),
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.operator {
+            Operator::Exists => write!(f, "{} exists", self.field),
+            operator => write!(f, "{} {} {}", self.field, operator.symbol(), format_value(&self.value)),
+        }
+    }
+}
+
+impl fmt::Display for Threshold {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut inner = self.label.clone().unwrap_or_default();
+        if !self.by.is_empty() {
+            if !inner.is_empty() {
+                inner.push(' ');
+            }
+            inner.push_str(&format!("by {}", self.by.join(", ")));
+        }
+        write!(f, "count({}) {} {} within {}", inner, self.operator.symbol(), self.count, format_duration(&self.within))
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let grouped = |expr: &Expr, parent_and: bool| match expr {
+            Expr::Or(_) if parent_and => format!("({})", expr),
+            Expr::And(_) | Expr::Or(_) if !parent_and => format!("({})", expr),
+            _ => expr.to_string(),
+        };
+        match self {
+            Expr::Predicate(predicate) => predicate.fmt(f),
+            Expr::Count(threshold) => threshold.fmt(f),
+            Expr::Not(inner, _) => match inner.as_ref() {
+                Expr::And(_) | Expr::Or(_) => write!(f, "NOT ({})", inner),
+                _ => write!(f, "NOT {}", inner),
+            },
+            Expr::And(items) => write!(f, "{}", items.iter().map(|item| grouped(item, true)).collect::<Vec<_>>().join(" AND ")),
+            Expr::Or(items) => write!(
+                f,
+                "{}",
+                items
+                    .iter()
+                    .map(|item| if matches!(item, Expr::Or(_)) { grouped(item, false) } else { item.to_string() })
+                    .collect::<Vec<_>>()
+                    .join(" OR ")
+            ),
+        }
+    }
+}
+
+impl fmt::Display for RuleExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stages: Vec<String> = self.stages.iter().map(Expr::to_string).collect();
+        write!(f, "{}", stages.join(" FOLLOWED BY "))?;
+        if let Some(sequence) = &self.sequence {
+            write!(f, " WITHIN {}", format_duration(&sequence.within))?;
+            if !sequence.by.is_empty() {
+                write!(f, " BY {}", sequence.by.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Compilation
+
+/// A predicate reduced to a `DetectionCondition` operator
+struct Leaf {
+    field: String,
+    operator: &'static str,
+    value: Value,
+}
+
+enum Normal {
+    Leaf(Leaf),
+    And(Vec<Normal>, Span),
+    Or(Vec<Normal>, Span),
+}
+
+fn negated(operator: &'static str) -> Option<&'static str> {
+    Some(match operator {
+        "equals" => "not_equals",
+        "not_equals" => "equals",
+        "gt" => "lte",
+        "lte" => "gt",
+        "gte" => "lt",
+        "lt" => "gte",
+        "in" => "not_in",
+        "not_in" => "in",
+        "contains" => "not_contains",
+        "not_contains" => "contains",
+        "regex" => "not_regex",
+        "not_regex" => "regex",
+        "exists" => "not_exists",
+        "not_exists" => "exists",
+        _ => return None,
+    })
+}
+
+/// Push `NOT` down to the predicates and flatten nested groups
+fn normalize(expr: &Expr, negate: bool) -> Result<Normal, DslError> {
+    match expr {
+        Expr::Predicate(predicate) => {
+            let mut operator = predicate.operator.condition_operator();
+            if negate {
+                operator = negated(operator).ok_or_else(|| {
+                    DslError::new(format!("'{}' cannot be negated", predicate.operator.symbol()), predicate.span)
+                })?;
+            }
+            Ok(Normal::Leaf(Leaf { field: predicate.field.clone(), operator, value: predicate.value.clone() }))
+        }
+        Expr::Count(threshold) => Err(DslError::new(
+            "count(...) can only be ANDed with the rest of its stage",
+            threshold.span,
+        )),
+        Expr::Not(inner, _) => normalize(inner, !negate),
+        Expr::And(items) | Expr::Or(items) => {
+            let conjunction = matches!(expr, Expr::And(_)) != negate;
+            let mut flattened = Vec::new();
+            for item in items {
+                match (normalize(item, negate)?, conjunction) {
+                    (Normal::And(nested, _), true) | (Normal::Or(nested, _), false) => flattened.extend(nested),
+                    (normal, _) => flattened.push(normal),
+                }
+            }
+            Ok(if conjunction { Normal::And(flattened, expr.span()) } else { Normal::Or(flattened, expr.span()) })
+        }
+    }
+}
+
+/// Alternatives `a == x OR a in (y, z)` as one `a in (x, y, z)`
+fn fold_alternatives(items: Vec<Normal>, span: Span) -> Result<Leaf, DslError> {
+    let unsupported = || DslError::new("OR inside AND can only list alternatives of one field (a == x OR a == y)", span);
+    let mut field: Option<String> = None;
+    let mut values = Vec::new();
+    for item in items {
+        let Normal::Leaf(leaf) = item else {
+            return Err(unsupported());
+        };
+        if field.get_or_insert_with(|| leaf.field.clone()) != &leaf.field {
+            return Err(unsupported());
+        }
+        match (leaf.operator, leaf.value) {
+            ("equals", value) => values.push(value),
+            ("in", Value::Array(list)) => values.extend(list),
+            _ => return Err(unsupported()),
+        }
+    }
+    Ok(Leaf { field: field.unwrap_or_default(), operator: "in", value: Value::Array(values) })
+}
+
+/// Conditions of one stage as (leaf, required) pairs
+fn stage_leaves(filters: &[&Expr], span: Span) -> Result<Vec<(Leaf, bool)>, DslError> {
+    let normal = match filters {
+        [] => return Err(DslError::new("a stage needs at least one field condition", span)),
+        [single] => normalize(single, false)?,
+        many => {
+            let items = many.iter().map(|expr| normalize(expr, false)).collect::<Result<Vec<_>, _>>()?;
+            Normal::And(items, span)
+        }
+    };
+    match normal {
+        Normal::Leaf(leaf) => Ok(vec![(leaf, true)]),
+        Normal::Or(items, _) => items
+            .into_iter()
+            .map(|item| match item {
+                Normal::Leaf(leaf) => Ok((leaf, false)),
+                Normal::And(_, span) | Normal::Or(_, span) => {
+                    Err(DslError::new("AND inside OR cannot be compiled; split it into separate rules", span))
+                }
+            })
+            .collect(),
+        Normal::And(items, _) => items
+            .into_iter()
+            .map(|item| match item {
+                Normal::Leaf(leaf) => Ok((leaf, true)),
+                Normal::Or(alternatives, span) => fold_alternatives(alternatives, span).map(|leaf| (leaf, true)),
+                Normal::And(_, span) => Err(DslError::new("unexpected nested AND", span)),
+            })
+            .collect(),
+    }
+}
+
+fn default_label(index: usize) -> String {
+    format!("stage{}", index + 1)
+}
+
+/// Compile a parsed rule, reporting every part that cannot be compiled
+pub fn compile(rule: &RuleExpr) -> Result<DetectionLogic, Vec<DslError>> {
+    let mut errors = Vec::new();
+    let mut logic = DetectionLogic {
+        rule_type: RuleType::Signature,
+        conditions: Vec::new(),
+        correlation_rules: Vec::new(),
+        time_windows: Vec::new(),
+        statistical_models: Vec::new(),
+    };
+    let mut labels = Vec::new();
+    let mut seen = HashSet::new();
+    let mut first_keys: Option<Vec<String>> = None;
+
+    for (index, stage) in rule.stages.iter().enumerate() {
+        let conjuncts: Vec<&Expr> = match stage {
+            Expr::And(items) => items.iter().collect(),
+            single => vec![single],
+        };
+        let (counts, filters): (Vec<&Expr>, Vec<&Expr>) = conjuncts.into_iter().partition(|expr| matches!(expr, Expr::Count(_)));
+        for extra in counts.iter().skip(1) {
+            errors.push(DslError::new("a stage can have only one count(...)", extra.span()));
+        }
+        let threshold = counts.first().and_then(|expr| match expr {
+            Expr::Count(threshold) => Some(threshold),
+            _ => None,
+        });
+
+        let label = threshold.and_then(|t| t.label.clone()).unwrap_or_else(|| default_label(index));
+        if !seen.insert(label.clone()) {
+            errors.push(DslError::new(format!("stage label '{}' is used twice", label), threshold.map_or(stage.span(), |t| t.span)));
+        }
+
+        match stage_leaves(&filters, stage.span()) {
+            Ok(leaves) => {
+                for (n, (leaf, required)) in leaves.into_iter().enumerate() {
+                    logic.conditions.push(DetectionCondition {
+                        condition_id: format!("{}{}{}", label, STAGE_SEPARATOR, n + 1),
+                        field: leaf.field,
+                        operator: leaf.operator.to_string(),
+                        value: leaf.value,
+                        weight: 1.0,
+                        required,
+                    });
+                }
+            }
+            Err(error) => errors.push(error),
+        }
+
+        if let Some(threshold) = threshold {
+            logic.rule_type = RuleType::Behavioral;
+            if first_keys.is_none() && !threshold.by.is_empty() {
+                first_keys = Some(threshold.by.clone());
+            }
+            logic.time_windows.push(TimeWindow {
+                window_id: format!("{}{}window", label, STAGE_SEPARATOR),
+                duration: threshold.within,
+                sliding: true,
+                aggregation_method: "count".to_string(),
+                threshold_type: ThresholdType::Count,
+            });
+            logic.correlation_rules.push(CorrelationRule {
+                rule_id: format!("{}{}threshold", label, STAGE_SEPARATOR),
+                events_to_correlate: vec![label.clone()],
+                time_window: threshold.within,
+                minimum_occurrences: threshold.minimum_occurrences(),
+                correlation_fields: threshold.by.clone(),
+                scoring_algorithm: THRESHOLD_SCORING.to_string(),
+            });
+        }
+        labels.push(label);
+    }
+
+    match (&rule.sequence, labels.len()) {
+        (Some(sequence), 1) => errors.push(DslError::new("WITHIN after the rule needs stages joined by FOLLOWED BY", sequence.span)),
+        (sequence, stages) if stages > 1 => {
+            logic.rule_type = RuleType::Correlation;
+            logic.correlation_rules.push(CorrelationRule {
+                rule_id: SEQUENCE_RULE_ID.to_string(),
+                events_to_correlate: labels,
+                time_window: sequence.as_ref().map_or(Duration::minutes(DEFAULT_SEQUENCE_WINDOW_MINUTES), |s| s.within),
+                minimum_occurrences: stages as u32,
+                correlation_fields: sequence
+                    .as_ref()
+                    .map(|s| s.by.clone())
+                    .filter(|by| !by.is_empty())
+                    .or(first_keys)
+                    .unwrap_or_default(),
+                scoring_algorithm: SEQUENCE_SCORING.to_string(),
+            });
+        }
+        _ => {}
+    }
+
+    if errors.is_empty() {
+        Ok(logic)
+    } else {
+        errors.sort_by_key(|e| e.span.start);
+        Err(errors)
+    }
+}
+
+/// Parse and compile `source`
+pub fn compile_source(source: &str) -> Result<(RuleExpr, DetectionLogic), Vec<DslError>> {
+    let rule = parse(source).map_err(|e| vec![e])?;
+    let logic = compile(&rule)?;
+    Ok((rule, logic))
+}
+
+/// Every syntax or compile error in `source`; empty when it compiles
+pub fn validate(source: &str) -> Vec<DslError> {
+    compile_source(source).err().unwrap_or_default()
+}
+
+/// Stage label of a condition id (`""` for conditions not written in the DSL)
+pub fn stage_of(condition_id: &str) -> &str {
+    condition_id.split_once(STAGE_SEPARATOR).map_or("", |(stage, _)| stage)
+}
+
+/// The conditions of `stage`, e.g. for matching events to a sequence step
+pub fn stage_conditions<'a>(logic: &'a DetectionLogic, stage: &str) -> Vec<&'a DetectionCondition> {
+    logic.conditions.iter().filter(|c| stage_of(&c.condition_id) == stage).collect()
+}
+
+// Decompilation
+
+fn dsl_operator(operator: &str) -> Option<(Operator, bool)> {
+    Some(match operator {
+        "equals" | "eq" | "==" => (Operator::Eq, false),
+        "not_equals" | "ne" | "!=" => (Operator::Ne, false),
+        "gt" | ">" => (Operator::Gt, false),
+        "gte" | ">=" => (Operator::Gte, false),
+        "lt" | "<" => (Operator::Lt, false),
+        "lte" | "<=" => (Operator::Lte, false),
+        "in" => (Operator::In, false),
+        "not_in" => (Operator::NotIn, false),
+        "contains" => (Operator::Contains, false),
+        "not_contains" => (Operator::NotContains, false),
+        "starts_with" => (Operator::StartsWith, false),
+        "ends_with" => (Operator::EndsWith, false),
+        "regex" | "matches" => (Operator::Matches, false),
+        "not_regex" | "not_matches" => (Operator::NotMatches, false),
+        "exists" => (Operator::Exists, false),
+        "not_exists" => (Operator::Exists, true),
+        _ => return None,
+    })
+}
+
+/// Rule for detection logic of the shape [`compile`] produces
+pub fn decompile(logic: &DetectionLogic) -> Result<RuleExpr, String> {
+    let sequence = logic
+        .correlation_rules
+        .iter()
+        .find(|rule| rule.rule_id == SEQUENCE_RULE_ID && rule.scoring_algorithm == SEQUENCE_SCORING);
+    let mut labels: Vec<String> = match sequence {
+        Some(sequence) => sequence.events_to_correlate.clone(),
+        None => Vec::new(),
+    };
+    for condition in &logic.conditions {
+        let stage = stage_of(&condition.condition_id);
+        if !labels.iter().any(|label| label == stage) {
+            if sequence.is_some() {
+                return Err(format!("Condition {} is not in a stage of the sequence", condition.condition_id));
+            }
+            labels.push(stage.to_string());
+        }
+    }
+    if labels.len() > 1 && sequence.is_none() {
+        return Err("Conditions of several stages without a sequence".to_string());
+    }
+    if labels.is_empty() {
+        return Err("Detection logic has no conditions".to_string());
+    }
+    for rule in &logic.correlation_rules {
+        let threshold_of = rule.rule_id.strip_suffix("threshold").and_then(|id| id.strip_suffix(STAGE_SEPARATOR));
+        if !(sequence.is_some_and(|s| s.rule_id == rule.rule_id) || threshold_of.is_some_and(|label| labels.iter().any(|l| l == label))) {
+            return Err(format!("Correlation rule {} has no DSL form", rule.rule_id));
+        }
+    }
+
+    let mut stages = Vec::new();
+    for (index, label) in labels.iter().enumerate() {
+        let conditions = stage_conditions(logic, label);
+        let required = conditions.iter().filter(|c| c.required).count();
+        if required != 0 && required != conditions.len() {
+            return Err(format!("Stage '{}' mixes required and optional conditions", label));
+        }
+        let mut items = Vec::new();
+        for condition in &conditions {
+            let (operator, negate) = dsl_operator(&condition.operator)
+                .ok_or_else(|| format!("Operator '{}' of condition {} has no DSL form", condition.operator, condition.condition_id))?;
+            let value = if operator == Operator::Exists { Value::Null } else { condition.value.clone() };
+            let predicate = Expr::Predicate(Predicate { field: condition.field.clone(), operator, value, span: Span::default() });
+            items.push(if negate { Expr::Not(Box::new(predicate), Span::default()) } else { predicate });
+        }
+        let threshold_id = format!("{}{}threshold", label, STAGE_SEPARATOR);
+        if let Some(rule) = logic.correlation_rules.iter().find(|rule| rule.rule_id == threshold_id) {
+            let explicit = *label != default_label(index);
+            if required == 0 && conditions.len() > 1 {
+                items = vec![Expr::Or(items)];
+            }
+            items.push(Expr::Count(Threshold {
+                label: explicit.then(|| label.clone()),
+                by: rule.correlation_fields.clone(),
+                operator: if rule.minimum_occurrences > 0 { Operator::Gt } else { Operator::Gte },
+                count: rule.minimum_occurrences.saturating_sub(1),
+                within: rule.time_window,
+                span: Span::default(),
+            }));
+            stages.push(Expr::And(items));
+        } else if items.len() == 1 {
+            stages.push(items.remove(0));
+        } else if required == 0 {
+            stages.push(Expr::Or(items));
+        } else {
+            stages.push(Expr::And(items));
+        }
+    }
+
+    Ok(RuleExpr {
+        stages,
+        sequence: sequence.map(|rule| SequenceClause {
+            within: rule.time_window,
+            by: rule.correlation_fields.clone(),
+            span: Span::default(),
+        }),
+    })
+}
+
+impl HuntingCore {
+    /// Replace a rule's detection logic with the compiled `source`, keeping its statistical models
+    pub async fn set_rule_dsl(&self, tenant_id: &str, rule_id: &str, source: &str) -> CoreResult<HuntingRule> {
+        let (_, mut logic) = compile_source(source).map_err(|errors| {
+            CoreError::validation(errors.iter().map(DslError::to_string).collect::<Vec<_>>().join("; "))
+        })?;
+
+        let mut rules = self.rules.write().await;
+        let rule = rules
+            .get_mut(rule_id)
+            .ok_or_else(|| CoreError::not_found(format!("Rule {} not found", rule_id)))?;
+        if !tenancy::rule_writable(rule, tenant_id) {
+            return Err(CoreError::validation(format!("Rule {} belongs to another tenant", rule_id)));
+        }
+        logic.statistical_models = std::mem::take(&mut rule.detection_logic.statistical_models);
+        rule.detection_logic = logic;
+        rule.metadata.last_modified = Utc::now();
+        Ok(rule.clone())
+    }
+
+    /// A rule's detection logic as DSL source
+    pub async fn rule_dsl(&self, tenant_id: &str, rule_id: &str) -> CoreResult<String> {
+        let rules = self.rules.read().await;
+        let rule = rules
+            .get(rule_id)
+            .filter(|rule| tenancy::rule_visible(rule, tenant_id))
+            .ok_or_else(|| CoreError::not_found(format!("Rule {} not found", rule_id)))?;
+        decompile(&rule.detection_logic)
+            .map(|expr| expr.to_string())
+            .map_err(|e| CoreError::validation(e).context(format!("Rule {} cannot be written in the DSL", rule_id)))
+    }
+}
+
+#[napi_derive::napi]
+impl HuntingCoreNapi {
+    /// Syntax and compile errors of DSL `source` with their byte spans; `[]` when it compiles
+    #[napi]
+    pub fn validate_rule_dsl(&self, source: String) -> napi::Result<String> {
+        serde_json::to_string(&validate(&source))
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize DSL errors: {}", e)))
+    }
+
+    /// `{"canonical": ..., "detection_logic": ...}` for DSL `source`
+    #[napi]
+    pub fn compile_rule_dsl(&self, source: String) -> napi::Result<String> {
+        let (rule, logic) = compile_source(&source).map_err(|errors| {
+            CoreError::validation(errors.iter().map(DslError::to_string).collect::<Vec<_>>().join("; "))
+                .context("Failed to compile rule DSL")
+        })?;
+        serde_json::to_string(&json!({ "canonical": rule.to_string(), "detection_logic": logic }))
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize detection logic: {}", e)))
+    }
+
+    /// Replace a rule's detection logic with compiled DSL `source`
+    #[napi]
+    pub async fn set_rule_dsl(&self, rule_id: String, source: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &rule_id)?;
+        let rule = self.inner.set_rule_dsl(&tenant_id, &rule_id, &source).await;
+        let rule = self.audit.record(&actor, "set_rule_dsl", &rule_id, json!({ "source": source }), rule)
+            .map_err(|e| e.context("Failed to set rule DSL"))?;
+        serde_json::to_string(&rule)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize rule: {}", e)))
+    }
+
+    #[napi]
+    pub async fn get_rule_dsl(&self, rule_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        Ok(self.inner.rule_dsl(&tenant_id, &rule_id).await.map_err(|e| e.context("Failed to get rule DSL"))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conditions::evaluate_conditions;
+    use std::collections::HashMap;
+
+    const LATERAL: &str = "logon.type==3 AND count(logon by user) > 3 within 5m FOLLOWED BY process.name in (psexec.exe)";
+
+    #[test]
+    fn test_compiles_and_round_trips() {
+        let (rule, logic) = compile_source(LATERAL).unwrap();
+        assert!(matches!(logic.rule_type, RuleType::Correlation));
+        assert_eq!(
+            rule.to_string(),
+            "logon.type == 3 AND count(logon by user) > 3 within 5m FOLLOWED BY process.name in (psexec.exe)"
+        );
+
+        let ids: Vec<&str> = logic.conditions.iter().map(|c| c.condition_id.as_str()).collect();
+        assert_eq!(ids, vec!["logon.1", "stage2.1"]);
+        assert_eq!(logic.conditions[0].value, json!(3));
+        assert_eq!(logic.conditions[1].operator, "in");
+        assert_eq!(logic.time_windows[0].duration, Duration::minutes(5));
+
+        let threshold = &logic.correlation_rules[0];
+        assert_eq!((threshold.rule_id.as_str(), threshold.minimum_occurrences), ("logon.threshold", 4));
+        assert_eq!(threshold.correlation_fields, vec!["user".to_string()]);
+        let sequence = &logic.correlation_rules[1];
+        assert_eq!(sequence.events_to_correlate, vec!["logon".to_string(), "stage2".to_string()]);
+        assert_eq!(sequence.time_window, Duration::minutes(DEFAULT_SEQUENCE_WINDOW_MINUTES));
+        assert_eq!(sequence.correlation_fields, vec!["user".to_string()]);
+
+        let canonical = decompile(&logic).unwrap().to_string();
+        assert_eq!(canonical, format!("{} WITHIN 15m BY user", rule));
+        let (_, again) = compile_source(&canonical).unwrap();
+        assert_eq!(serde_json::to_value(&again).unwrap(), serde_json::to_value(&logic).unwrap());
+    }
+
+    #[test]
+    fn test_negation_and_alternatives_compile_to_flat_conditions() {
+        let source = "NOT user in (svc_backup, \"svc \\\"sql\\\"\") AND (image == psexec.exe OR image in (paexec.exe, 'remcom.exe')) \
+                      AND parent exists";
+        let (rule, logic) = compile_source(source).unwrap();
+        assert_eq!(parse(&rule.to_string()).unwrap().to_string(), rule.to_string());
+        let operators: Vec<&str> = logic.conditions.iter().map(|c| c.operator.as_str()).collect();
+        assert_eq!(operators, vec!["not_in", "in", "exists"]);
+        assert_eq!(logic.conditions[0].value, json!(["svc_backup", "svc \"sql\""]));
+        assert_eq!(logic.conditions[1].value, json!(["psexec.exe", "paexec.exe", "remcom.exe"]));
+
+        let event = |user: &str, image: &str| {
+            HashMap::from([
+                ("user".to_string(), json!(user)),
+                ("image".to_string(), json!(image)),
+                ("parent".to_string(), json!("services.exe")),
+            ])
+        };
+        assert!(evaluate_conditions(&event("mallory", "PsExec.exe"), &logic.conditions).is_some());
+        assert!(evaluate_conditions(&event("svc_backup", "psexec.exe"), &logic.conditions).is_none());
+        assert!(evaluate_conditions(&event("mallory", "cmd.exe"), &logic.conditions).is_none());
+
+        let any = compile_source("cmd contains -enc OR cmd contains \"-EncodedCommand\"").unwrap().1;
+        assert!(any.conditions.iter().all(|c| !c.required));
+        assert_eq!(decompile(&any).unwrap().to_string(), "cmd contains -enc OR cmd contains -EncodedCommand");
+    }
+
+    #[test]
+    fn test_errors_carry_spans() {
+        let source = "user == a AND (host == b OR ip == c)";
+        let errors = validate(source);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(&source[errors[0].span.start..errors[0].span.end], "host == b OR ip == c");
+
+        let source = "NOT host.name startswith 'ws-' AND count(a) > 1 within 1m AND count(b) > 2 within 1m";
+        let spans: Vec<&str> = validate(source).iter().map(|e| &source[e.span.start..e.span.end]).collect();
+        assert_eq!(spans, vec!["host.name startswith 'ws-'", "count(b) > 2 within 1m"]);
+
+        let source = "process.name in (a, b";
+        let error = parse(source).unwrap_err();
+        assert_eq!(error.message, "expected ')' closing the list");
+        assert_eq!(error.span, Span { start: source.len(), end: source.len() });
+
+        let source = "a == 1 AND count(x by user) > 3 within 5x";
+        assert_eq!(&source[parse(source).unwrap_err().span.start..], "5x");
+        assert_eq!(validate("a == 1 WITHIN 5m")[0].message, "WITHIN after the rule needs stages joined by FOLLOWED BY");
+    }
+
+    #[tokio::test]
+    async fn test_rule_logic_is_replaced_and_read_back() {
+        let core = HuntingCore::new().unwrap();
+        let rule = core.set_rule_dsl("default", "apt_lateral_movement", LATERAL).await.unwrap();
+        assert_eq!(rule.detection_logic.conditions.len(), 2);
+        assert_eq!(rule.detection_logic.statistical_models.len(), 1);
+        assert_eq!(
+            core.rule_dsl("default", "apt_lateral_movement").await.unwrap(),
+            "logon.type == 3 AND count(logon by user) > 3 within 5m FOLLOWED BY process.name in (psexec.exe) WITHIN 15m BY user"
+        );
+
+        let error = core.set_rule_dsl("default", "apt_lateral_movement", "a ==").await.unwrap_err();
+        assert_eq!(error.code(), "VALIDATION");
+        assert!(core.rule_dsl("default", "missing").await.is_err());
+    }
+}
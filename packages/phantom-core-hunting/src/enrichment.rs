@@ -0,0 +1,252 @@
+//! Observable Enrichment
+//!
+//! With `phantom-enterprise-standards` enabled, IPs, domains, URLs, hashes
+//! and email addresses found in a match's event data are run through the
+//! shared enrichment pipeline and each provider result is attached to the
+//! match as an [`Enrichment`]. Without it matches are left as they are.
+
+use crate::{HuntingCore, HuntingMatch};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::{Enrichment, EnrichmentType, HuntingCoreNapi};
+#[cfg(feature = "phantom-enterprise-standards")]
+use napi_derive::napi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::enrichment::{
+    EnrichmentCategory, EnrichmentPipeline, EnrichmentRecord, EnrichmentSetup, EnrichmentStats, Indicator,
+    IndicatorEnrichment, IndicatorKind, ReputationVerdict,
+};
+#[cfg(feature = "phantom-enterprise-standards")]
+use serde::Deserialize;
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::collections::HashMap;
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::net::IpAddr;
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::sync::{Arc, RwLock};
+
+/// Event fields whose dotted names may hold a domain rather than a file name
+#[cfg(feature = "phantom-enterprise-standards")]
+const DOMAIN_FIELD_HINTS: &[&str] = &["domain", "host", "query", "fqdn", "dns", "sni", "server_name"];
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[derive(Default)]
+pub struct EnrichmentState {
+    pipeline: RwLock<Arc<EnrichmentPipeline>>,
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+impl EnrichmentState {
+    pub fn pipeline(&self) -> Arc<EnrichmentPipeline> {
+        self.pipeline.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Observables in a match's event data, with the field each came from
+#[cfg(feature = "phantom-enterprise-standards")]
+pub fn match_observables(hunting_match: &HuntingMatch) -> Vec<(String, Indicator)> {
+    let mut observables = Vec::new();
+    for (field, value) in &hunting_match.event_data {
+        let values: Vec<&str> = match value {
+            serde_json::Value::String(s) => vec![s.as_str()],
+            serde_json::Value::Array(items) => items.iter().filter_map(|v| v.as_str()).collect(),
+            _ => continue,
+        };
+        for value in values {
+            let kind = IndicatorKind::infer(value);
+            let keep = match kind {
+                IndicatorKind::Ip => value.parse::<IpAddr>().is_ok_and(is_public),
+                // "powershell.exe" looks like a domain; only trust network fields
+                IndicatorKind::Domain => {
+                    let field = field.to_lowercase();
+                    DOMAIN_FIELD_HINTS.iter().any(|hint| field.contains(hint))
+                }
+                IndicatorKind::Url | IndicatorKind::Hash | IndicatorKind::Email => true,
+                IndicatorKind::Other => false,
+            };
+            if keep {
+                observables.push((field.clone(), Indicator::new(kind, value)));
+            }
+        }
+    }
+    observables.sort_by(|a, b| a.0.cmp(&b.0));
+    observables
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => !(v4.is_private() || v4.is_loopback() || v4.is_link_local() || v4.is_unspecified() || v4.is_broadcast()),
+        IpAddr::V6(v6) => !(v6.is_loopback() || v6.is_unspecified() || (v6.segments()[0] & 0xfe00) == 0xfc00 || (v6.segments()[0] & 0xffc0) == 0xfe80),
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+fn to_enrichment(field: &str, indicator: &Indicator, record: &EnrichmentRecord) -> Enrichment {
+    let enrichment_type = match record.category {
+        EnrichmentCategory::Reputation => EnrichmentType::ThreatIntelligence,
+        EnrichmentCategory::Geolocation | EnrichmentCategory::PassiveDns => EnrichmentType::NetworkInformation,
+        EnrichmentCategory::Registration | EnrichmentCategory::Other => EnrichmentType::ExternalAPI,
+    };
+    let confidence = match record.verdict {
+        Some(ReputationVerdict::Malicious) => 0.95,
+        Some(ReputationVerdict::Suspicious) => 0.7,
+        _ => 0.5,
+    };
+    let mut data = HashMap::new();
+    data.insert("field".to_string(), serde_json::json!(field));
+    data.insert("indicator".to_string(), serde_json::json!(indicator));
+    data.insert("verdict".to_string(), serde_json::json!(record.verdict));
+    data.insert("cached".to_string(), serde_json::json!(record.cached));
+    data.insert("result".to_string(), record.data.clone());
+    Enrichment {
+        enrichment_source: record.provider.clone(),
+        enrichment_type,
+        data,
+        confidence,
+        timestamp: record.retrieved_at,
+    }
+}
+
+impl HuntingCore {
+    /// Enrich the observables of every match in one batch, so an indicator
+    /// shared by many matches is looked up once
+    pub async fn enrich_match_observables(&self, matches: &mut [HuntingMatch]) {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            let observables: Vec<Vec<(String, Indicator)>> = matches.iter().map(match_observables).collect();
+            let indicators: Vec<Indicator> = observables.iter().flatten().map(|(_, i)| i.clone()).collect();
+            if indicators.is_empty() {
+                return;
+            }
+            let results: HashMap<Indicator, IndicatorEnrichment> = self
+                .enrichment
+                .pipeline()
+                .enrich_batch(&indicators)
+                .await
+                .into_iter()
+                .map(|enrichment| (enrichment.indicator.clone(), enrichment))
+                .collect();
+
+            for (hunting_match, observables) in matches.iter_mut().zip(observables) {
+                for (field, indicator) in observables {
+                    if let Some(enrichment) = results.get(&indicator) {
+                        hunting_match
+                            .enrichments
+                            .extend(enrichment.records.iter().map(|record| to_enrichment(&field, &indicator, record)));
+                    }
+                }
+            }
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        let _ = matches;
+    }
+
+    /// Replace the enrichment pipeline with one built from `setup`
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn configure_enrichment(&self, setup: EnrichmentSetup) -> Result<EnrichmentStats, String> {
+        let pipeline = Arc::new(EnrichmentPipeline::from_setup(setup)?);
+        let stats = pipeline.stats();
+        *self.enrichment.pipeline.write().unwrap_or_else(|e| e.into_inner()) = pipeline;
+        Ok(stats)
+    }
+
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn enrichment_pipeline(&self) -> Arc<EnrichmentPipeline> {
+        self.enrichment.pipeline()
+    }
+}
+
+/// Indicator submitted for ad-hoc enrichment
+#[cfg(feature = "phantom-enterprise-standards")]
+#[derive(Debug, Clone, Deserialize)]
+struct IocLookup {
+    ioc_type: String,
+    value: String,
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[napi]
+impl HuntingCoreNapi {
+    /// Configure enrichment providers (GeoIP, WHOIS, passive DNS, reputation lists) and cache limits
+    #[napi]
+    pub fn configure_enrichment(&self, config_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize(auth_token, "rule:manage", "enrichment")?;
+        let mut config: serde_json::Value = serde_json::from_str(&config_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse enrichment config: {}", e)))?;
+        let details = serde_json::json!({ "config": crate::secrets::redacted(&config) });
+        self.inner.secrets().resolve_json(&mut config)
+            .map_err(|e| crate::error::CoreError::from(e).context("Failed to resolve enrichment credentials"))?;
+        let setup: EnrichmentSetup = serde_json::from_value(config)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse enrichment config: {}", e)))?;
+
+        let stats = self.inner.configure_enrichment(setup);
+        let stats = self.audit.record(&actor, "configure_enrichment", "enrichment", details, stats)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to configure enrichment: {}", e)))?;
+
+        serde_json::to_string(&stats)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize enrichment stats: {}", e)))
+    }
+
+    /// Enrich `[{ioc_type, value}]` outside of a hunt
+    #[napi]
+    pub async fn enrich_iocs(&self, iocs_json: String) -> napi::Result<String> {
+        let lookups: Vec<IocLookup> = serde_json::from_str(&iocs_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse IOCs: {}", e)))?;
+        let indicators: Vec<Indicator> = lookups.iter().map(|l| Indicator::from_ioc(&l.ioc_type, &l.value)).collect();
+        let enrichments = self.inner.enrichment_pipeline().enrich_batch(&indicators).await;
+
+        serde_json::to_string(&enrichments)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize enrichments: {}", e)))
+    }
+
+    /// Get enrichment providers and cache hit/miss counters
+    #[napi]
+    pub fn get_enrichment_stats(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.enrichment_pipeline().stats())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize enrichment stats: {}", e)))
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use super::*;
+    use phantom_enterprise_standards::enrichment::{PassiveDnsRecord, ReputationListSetup};
+
+    #[tokio::test]
+    async fn match_observables_are_enriched() {
+        let core = HuntingCore::new().unwrap();
+        core.configure_enrichment(EnrichmentSetup {
+            reputation_lists: vec![ReputationListSetup {
+                name: "phishing-domains".to_string(),
+                verdict: ReputationVerdict::Suspicious,
+                path: None,
+                entries: vec!["login-update.example".to_string()],
+            }],
+            passive_dns: vec![PassiveDnsRecord {
+                rrname: "portal.login-update.example".to_string(),
+                rrtype: "A".to_string(),
+                rdata: "203.0.113.50".to_string(),
+                first_seen: chrono::Utc::now(),
+                last_seen: chrono::Utc::now(),
+                count: 4,
+            }],
+            ..Default::default()
+        })
+        .unwrap();
+
+        let mut hunting_match = core.generate_generic_match(0).await;
+        hunting_match.event_data = HashMap::from([
+            ("dns.query".to_string(), serde_json::json!("portal.login-update.example")),
+            ("process_name".to_string(), serde_json::json!("powershell.exe")),
+            ("src_ip".to_string(), serde_json::json!("10.0.0.5")),
+        ]);
+        let mut matches = vec![hunting_match];
+        core.enrich_match_observables(&mut matches).await;
+
+        let sources: Vec<&str> = matches[0].enrichments.iter().map(|e| e.enrichment_source.as_str()).collect();
+        assert!(sources.contains(&"reputation_lists"));
+        assert!(sources.contains(&"passive_dns"));
+        assert!(matches[0].enrichments.iter().all(|e| e.data["field"] == "dns.query"));
+    }
+}
@@ -0,0 +1,572 @@
+//! Hunt hypotheses
+//!
+//! A hypothesis is a testable statement about adversary activity ("a second
+//! stage is beaconing over DNS from finance hosts"), written by an analyst
+//! or imported from the threat hunting leads sandbox analyses produce. Each
+//! belongs to a tenant and moves through open, in progress, validated and
+//! refuted; linking a hunt that tested it puts an open hypothesis in
+//! progress, and it can only be concluded once at least one hunt is linked.
+//! Incidents raised from its findings are linked alongside.
+//!
+//! Hypotheses name the ATT&CK techniques or tactics they are about. The
+//! coverage report places them on the tactics of the embedded matrix, so a
+//! hunt lead can see which tactics the backlog (open and in progress
+//! hypotheses) addresses and which it leaves uncovered.
+
+use chrono::{DateTime, Utc};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::error::{CoreError, CoreResult};
+use crate::{killchain, HuntingCore, HuntingCoreNapi};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HypothesisStatus {
+    Open,
+    InProgress,
+    Validated,
+    Refuted,
+}
+
+impl HypothesisStatus {
+    /// Still waiting to be tested or concluded
+    pub fn in_backlog(&self) -> bool {
+        matches!(self, HypothesisStatus::Open | HypothesisStatus::InProgress)
+    }
+}
+
+/// What an analyst writes to create a hypothesis
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HypothesisDraft {
+    pub statement: String,
+    #[serde(default)]
+    pub rationale: String,
+    /// ATT&CK technique ids or names the hypothesis is about
+    #[serde(default)]
+    pub techniques: Vec<String>,
+    /// ATT&CK tactics, for hypotheses not tied to a technique
+    #[serde(default)]
+    pub tactics: Vec<String>,
+    #[serde(default)]
+    pub indicators_to_hunt: Vec<String>,
+    #[serde(default)]
+    pub data_sources: Vec<String>,
+    #[serde(default)]
+    pub query_templates: Vec<String>,
+    #[serde(default)]
+    pub expected_findings: Vec<String>,
+    #[serde(default = "default_priority")]
+    pub priority: String,
+    #[serde(default)]
+    pub owner: Option<String>,
+}
+
+fn default_priority() -> String {
+    "Medium".to_string()
+}
+
+/// A threat hunting lead as produced by sandbox analyses
+/// (`enterprise_insights.threat_hunting_leads`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HuntingLead {
+    pub hypothesis: String,
+    #[serde(default)]
+    pub indicators_to_hunt: Vec<String>,
+    #[serde(default)]
+    pub data_sources: Vec<String>,
+    #[serde(default)]
+    pub query_templates: Vec<String>,
+    #[serde(default)]
+    pub expected_findings: Vec<String>,
+    #[serde(default = "default_priority")]
+    pub priority: String,
+    #[serde(default)]
+    pub techniques: Vec<String>,
+}
+
+/// A hunt run to test a hypothesis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HuntLink {
+    pub hunt_id: String,
+    pub rule_id: String,
+    pub executed_at: DateTime<Utc>,
+    pub matches: usize,
+    pub linked_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusChange {
+    pub from: HypothesisStatus,
+    pub to: HypothesisStatus,
+    pub changed_by: String,
+    pub changed_at: DateTime<Utc>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hypothesis {
+    pub hypothesis_id: String,
+    pub tenant_id: String,
+    pub statement: String,
+    pub rationale: String,
+    pub techniques: Vec<String>,
+    pub tactics: Vec<String>,
+    pub indicators_to_hunt: Vec<String>,
+    pub data_sources: Vec<String>,
+    pub query_templates: Vec<String>,
+    pub expected_findings: Vec<String>,
+    pub priority: String,
+    pub owner: Option<String>,
+    pub status: HypothesisStatus,
+    /// Where the hypothesis came from, e.g. the sandbox analysis of a lead
+    pub source: Option<String>,
+    pub hunts: Vec<HuntLink>,
+    pub incidents: Vec<String>,
+    pub history: Vec<StatusChange>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Hypothesis {
+    /// Matrix positions of the tactics the hypothesis addresses
+    fn tactic_indices(&self) -> BTreeSet<usize> {
+        self.techniques.iter().chain(&self.tactics).flat_map(|reference| killchain::tactic_indices(reference)).collect()
+    }
+
+    fn set_status(&mut self, status: HypothesisStatus, actor: &str, note: Option<String>) {
+        let now = Utc::now();
+        self.history.push(StatusChange { from: self.status, to: status, changed_by: actor.to_string(), changed_at: now, note });
+        self.status = status;
+        self.updated_at = now;
+    }
+}
+
+/// Outcome of importing sandbox leads
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LeadImport {
+    pub created: Vec<Hypothesis>,
+    /// Leads whose statement matches a hypothesis still in the backlog
+    pub duplicates: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TacticCoverage {
+    pub tactic_id: String,
+    pub tactic: String,
+    /// Open and in-progress hypotheses
+    pub backlog: usize,
+    pub validated: usize,
+    pub refuted: usize,
+    pub hypothesis_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HypothesisCoverage {
+    pub tenant_id: String,
+    pub generated_at: DateTime<Utc>,
+    pub total: usize,
+    pub by_status: BTreeMap<HypothesisStatus, usize>,
+    /// Every tactic of the matrix, in kill-chain order
+    pub tactics: Vec<TacticCoverage>,
+    /// Tactics no backlog hypothesis addresses
+    pub uncovered_tactics: Vec<String>,
+    /// Backlog hypotheses naming no technique or tactic the matrix knows
+    pub unmapped_hypotheses: Vec<String>,
+    /// Validated share of concluded hypotheses
+    pub validation_rate: f64,
+}
+
+#[derive(Default)]
+pub struct HypothesisState {
+    /// Hypotheses of every tenant by id
+    records: RwLock<HashMap<String, Hypothesis>>,
+}
+
+impl HypothesisState {
+    pub(crate) async fn forget_tenant(&self, tenant_id: &str) -> usize {
+        let mut hypotheses = self.records.write().await;
+        let before = hypotheses.len();
+        hypotheses.retain(|_, hypothesis| hypothesis.tenant_id != tenant_id);
+        before - hypotheses.len()
+    }
+}
+
+fn normalize_statement(statement: &str) -> String {
+    statement.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+fn not_found(hypothesis_id: &str) -> CoreError {
+    CoreError::not_found(format!("Hypothesis {} not found", hypothesis_id))
+}
+
+impl HuntingCore {
+    pub async fn create_hypothesis(&self, tenant_id: &str, draft: HypothesisDraft, actor: &str) -> CoreResult<Hypothesis> {
+        self.insert_hypothesis(tenant_id, draft, None, actor).await
+    }
+
+    async fn insert_hypothesis(&self, tenant_id: &str, draft: HypothesisDraft, source: Option<String>, actor: &str) -> CoreResult<Hypothesis> {
+        if draft.statement.trim().is_empty() {
+            return Err(CoreError::validation("Hypothesis statement is required"));
+        }
+        let now = Utc::now();
+        let hypothesis = Hypothesis {
+            hypothesis_id: format!("hyp_{}", Uuid::new_v4().simple()),
+            tenant_id: tenant_id.to_string(),
+            statement: draft.statement.trim().to_string(),
+            rationale: draft.rationale,
+            techniques: draft.techniques,
+            tactics: draft.tactics,
+            indicators_to_hunt: draft.indicators_to_hunt,
+            data_sources: draft.data_sources,
+            query_templates: draft.query_templates,
+            expected_findings: draft.expected_findings,
+            priority: if draft.priority.trim().is_empty() { default_priority() } else { draft.priority },
+            owner: draft.owner,
+            status: HypothesisStatus::Open,
+            source,
+            hunts: Vec::new(),
+            incidents: Vec::new(),
+            history: Vec::new(),
+            created_by: actor.to_string(),
+            created_at: now,
+            updated_at: now,
+        };
+        self.hypotheses.records.write().await.insert(hypothesis.hypothesis_id.clone(), hypothesis.clone());
+        Ok(hypothesis)
+    }
+
+    /// Turn sandbox hunting leads into open hypotheses, skipping leads the
+    /// backlog already holds
+    pub async fn import_hunting_leads(&self, tenant_id: &str, leads: Vec<HuntingLead>, source: Option<String>, actor: &str) -> CoreResult<LeadImport> {
+        let mut seen: BTreeSet<String> = self
+            .list_hypotheses(tenant_id, None)
+            .await
+            .iter()
+            .filter(|h| h.status.in_backlog())
+            .map(|h| normalize_statement(&h.statement))
+            .collect();
+        let mut import = LeadImport::default();
+        for lead in leads {
+            if !seen.insert(normalize_statement(&lead.hypothesis)) {
+                import.duplicates.push(lead.hypothesis);
+                continue;
+            }
+            let draft = HypothesisDraft {
+                statement: lead.hypothesis,
+                techniques: lead.techniques,
+                indicators_to_hunt: lead.indicators_to_hunt,
+                data_sources: lead.data_sources,
+                query_templates: lead.query_templates,
+                expected_findings: lead.expected_findings,
+                priority: lead.priority,
+                ..HypothesisDraft::default()
+            };
+            import.created.push(self.insert_hypothesis(tenant_id, draft, source.clone(), actor).await?);
+        }
+        Ok(import)
+    }
+
+    pub async fn get_hypothesis(&self, tenant_id: &str, hypothesis_id: &str) -> Option<Hypothesis> {
+        self.hypotheses.records.read().await.get(hypothesis_id).filter(|h| h.tenant_id == tenant_id).cloned()
+    }
+
+    /// The tenant's hypotheses, newest first
+    pub async fn list_hypotheses(&self, tenant_id: &str, status: Option<HypothesisStatus>) -> Vec<Hypothesis> {
+        let mut hypotheses: Vec<Hypothesis> = self
+            .hypotheses
+            .records
+            .read()
+            .await
+            .values()
+            .filter(|h| h.tenant_id == tenant_id && status.is_none_or(|status| h.status == status))
+            .cloned()
+            .collect();
+        hypotheses.sort_by_key(|h| std::cmp::Reverse(h.created_at));
+        hypotheses
+    }
+
+    async fn update_hypothesis<T>(&self, tenant_id: &str, hypothesis_id: &str, update: impl FnOnce(&mut Hypothesis) -> CoreResult<T>) -> CoreResult<T> {
+        let mut hypotheses = self.hypotheses.records.write().await;
+        let hypothesis = hypotheses.get_mut(hypothesis_id).filter(|h| h.tenant_id == tenant_id).ok_or_else(|| not_found(hypothesis_id))?;
+        update(hypothesis)
+    }
+
+    /// Move a hypothesis to `status`; concluding requires a linked hunt
+    pub async fn set_hypothesis_status(&self, tenant_id: &str, hypothesis_id: &str, status: HypothesisStatus, note: Option<String>, actor: &str) -> CoreResult<Hypothesis> {
+        self.update_hypothesis(tenant_id, hypothesis_id, |hypothesis| {
+            if hypothesis.status == status {
+                return Ok(hypothesis.clone());
+            }
+            if !status.in_backlog() && hypothesis.hunts.is_empty() {
+                return Err(CoreError::validation(format!("Hypothesis {} has no linked hunt to conclude from", hypothesis_id)));
+            }
+            hypothesis.set_status(status, actor, note);
+            Ok(hypothesis.clone())
+        })
+        .await
+    }
+
+    /// Record that a hunt of the tenant tested the hypothesis
+    pub async fn link_hunt_to_hypothesis(&self, tenant_id: &str, hypothesis_id: &str, hunt_id: &str, actor: &str) -> CoreResult<Hypothesis> {
+        let hunt = self
+            .hunt_results
+            .read()
+            .await
+            .get(hunt_id)
+            .filter(|result| result.tenant_id == tenant_id)
+            .map(|result| HuntLink {
+                hunt_id: result.hunt_id.clone(),
+                rule_id: result.rule_id.clone(),
+                executed_at: result.execution_timestamp,
+                matches: result.matches.len(),
+                linked_at: Utc::now(),
+            })
+            .ok_or_else(|| CoreError::not_found(format!("Hunt {} not found", hunt_id)))?;
+        self.update_hypothesis(tenant_id, hypothesis_id, |hypothesis| {
+            if !hypothesis.hunts.iter().any(|link| link.hunt_id == hunt.hunt_id) {
+                hypothesis.hunts.push(hunt);
+                hypothesis.updated_at = Utc::now();
+            }
+            if hypothesis.status == HypothesisStatus::Open {
+                hypothesis.set_status(HypothesisStatus::InProgress, actor, Some(format!("Tested by hunt {}", hunt_id)));
+            }
+            Ok(hypothesis.clone())
+        })
+        .await
+    }
+
+    /// Record an incident raised from the hypothesis' findings
+    pub async fn link_incident_to_hypothesis(&self, tenant_id: &str, hypothesis_id: &str, incident_id: &str) -> CoreResult<Hypothesis> {
+        if incident_id.trim().is_empty() {
+            return Err(CoreError::validation("Incident id is required"));
+        }
+        self.update_hypothesis(tenant_id, hypothesis_id, |hypothesis| {
+            if !hypothesis.incidents.iter().any(|id| id == incident_id) {
+                hypothesis.incidents.push(incident_id.to_string());
+                hypothesis.updated_at = Utc::now();
+            }
+            Ok(hypothesis.clone())
+        })
+        .await
+    }
+
+    /// Which ATT&CK tactics the tenant's hypotheses address
+    pub async fn hypothesis_coverage(&self, tenant_id: &str) -> HypothesisCoverage {
+        let hypotheses = self.list_hypotheses(tenant_id, None).await;
+        let mut tactics: Vec<TacticCoverage> = killchain::tactics()
+            .map(|(tactic_id, name)| TacticCoverage {
+                tactic_id: tactic_id.to_string(),
+                tactic: name.to_string(),
+                backlog: 0,
+                validated: 0,
+                refuted: 0,
+                hypothesis_ids: Vec::new(),
+            })
+            .collect();
+        let mut by_status = BTreeMap::new();
+        let mut unmapped_hypotheses = Vec::new();
+        for hypothesis in &hypotheses {
+            *by_status.entry(hypothesis.status).or_insert(0) += 1;
+            let indices = hypothesis.tactic_indices();
+            if indices.is_empty() && hypothesis.status.in_backlog() {
+                unmapped_hypotheses.push(hypothesis.hypothesis_id.clone());
+            }
+            for index in indices {
+                if let Some(coverage) = tactics.get_mut(index) {
+                    match hypothesis.status {
+                        HypothesisStatus::Validated => coverage.validated += 1,
+                        HypothesisStatus::Refuted => coverage.refuted += 1,
+                        _ => coverage.backlog += 1,
+                    }
+                    coverage.hypothesis_ids.push(hypothesis.hypothesis_id.clone());
+                }
+            }
+        }
+        let validated = by_status.get(&HypothesisStatus::Validated).copied().unwrap_or(0);
+        let concluded = validated + by_status.get(&HypothesisStatus::Refuted).copied().unwrap_or(0);
+        HypothesisCoverage {
+            tenant_id: tenant_id.to_string(),
+            generated_at: Utc::now(),
+            total: hypotheses.len(),
+            by_status,
+            uncovered_tactics: tactics.iter().filter(|t| t.backlog == 0).map(|t| t.tactic.clone()).collect(),
+            tactics,
+            unmapped_hypotheses,
+            validation_rate: if concluded == 0 { 0.0 } else { validated as f64 / concluded as f64 },
+        }
+    }
+}
+
+#[napi]
+impl HuntingCoreNapi {
+    /// Create an open hypothesis from a JSON `HypothesisDraft`
+    #[napi]
+    pub async fn create_hypothesis(&self, draft_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let draft: HypothesisDraft = serde_json::from_str(&draft_json)
+            .map_err(|e| CoreError::from(e).context("Failed to parse hypothesis"))?;
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", "hypotheses")?;
+        let hypothesis = self.inner.create_hypothesis(&tenant_id, draft, &actor).await;
+        let hypothesis = self.audit.record(&actor, "create_hypothesis", "hypotheses", json!({ "draft": draft_json }), hypothesis)
+            .map_err(|e| e.context("Failed to create hypothesis"))?;
+        serde_json::to_string(&hypothesis)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize hypothesis: {}", e)))
+    }
+
+    /// Import a JSON array of sandbox threat hunting leads as hypotheses
+    #[napi]
+    pub async fn import_hunting_leads(&self, leads_json: String, source: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let leads: Vec<HuntingLead> = serde_json::from_str(&leads_json)
+            .map_err(|e| CoreError::from(e).context("Failed to parse hunting leads"))?;
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", "hypotheses")?;
+        let count = leads.len();
+        let import = self.inner.import_hunting_leads(&tenant_id, leads, source.clone(), &actor).await;
+        let import = self.audit.record(&actor, "import_hunting_leads", "hypotheses", json!({ "leads": count, "source": source }), import)
+            .map_err(|e| e.context("Failed to import hunting leads"))?;
+        serde_json::to_string(&import)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize lead import: {}", e)))
+    }
+
+    #[napi]
+    pub async fn get_hypothesis(&self, hypothesis_id: String, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        self.inner.get_hypothesis(&tenant_id, &hypothesis_id).await
+            .map(|hypothesis| serde_json::to_string(&hypothesis))
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize hypothesis: {}", e)))
+    }
+
+    /// Hypotheses of the caller's tenant, optionally of one status (`open`, `in_progress`, ...)
+    #[napi]
+    pub async fn list_hypotheses(&self, status: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let status: Option<HypothesisStatus> = status
+            .map(|status| serde_json::from_value(json!(status)))
+            .transpose()
+            .map_err(|e| CoreError::from(e).context("Invalid hypothesis status"))?;
+        serde_json::to_string(&self.inner.list_hypotheses(&tenant_id, status).await)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize hypotheses: {}", e)))
+    }
+
+    #[napi]
+    pub async fn set_hypothesis_status(&self, hypothesis_id: String, status: String, note: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", &hypothesis_id)?;
+        let parsed: HypothesisStatus = serde_json::from_value(json!(status))
+            .map_err(|e| CoreError::from(e).context("Invalid hypothesis status"))?;
+        let hypothesis = self.inner.set_hypothesis_status(&tenant_id, &hypothesis_id, parsed, note.clone(), &actor).await;
+        let hypothesis = self.audit.record(&actor, "set_hypothesis_status", &hypothesis_id, json!({ "status": status, "note": note }), hypothesis)
+            .map_err(|e| e.context("Failed to update hypothesis"))?;
+        serde_json::to_string(&hypothesis)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize hypothesis: {}", e)))
+    }
+
+    #[napi]
+    pub async fn link_hunt_to_hypothesis(&self, hypothesis_id: String, hunt_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", &hypothesis_id)?;
+        let hypothesis = self.inner.link_hunt_to_hypothesis(&tenant_id, &hypothesis_id, &hunt_id, &actor).await;
+        let hypothesis = self.audit.record(&actor, "link_hunt_to_hypothesis", &hypothesis_id, json!({ "hunt_id": hunt_id }), hypothesis)
+            .map_err(|e| e.context("Failed to link hunt"))?;
+        serde_json::to_string(&hypothesis)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize hypothesis: {}", e)))
+    }
+
+    #[napi]
+    pub async fn link_incident_to_hypothesis(&self, hypothesis_id: String, incident_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", &hypothesis_id)?;
+        let hypothesis = self.inner.link_incident_to_hypothesis(&tenant_id, &hypothesis_id, &incident_id).await;
+        let hypothesis = self.audit.record(&actor, "link_incident_to_hypothesis", &hypothesis_id, json!({ "incident_id": incident_id }), hypothesis)
+            .map_err(|e| e.context("Failed to link incident"))?;
+        serde_json::to_string(&hypothesis)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize hypothesis: {}", e)))
+    }
+
+    /// ATT&CK tactic coverage of the caller's hypotheses
+    #[napi]
+    pub async fn get_hypothesis_coverage(&self, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        serde_json::to_string(&self.inner.hypothesis_coverage(&tenant_id).await)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize hypothesis coverage: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "phantom-enterprise-standards")]
+    #[tokio::test]
+    async fn hypothesis_calls_pass_the_permission_check() {
+        use phantom_enterprise_standards::rbac::{Principal, Role};
+
+        let napi = HuntingCoreNapi::new().unwrap();
+        let draft = json!({ "statement": "Operators reuse the VPN account" }).to_string();
+        let created: serde_json::Value = serde_json::from_str(&napi.create_hypothesis(draft.clone(), None).await.unwrap()).unwrap();
+        let hypothesis_id = created["hypothesis_id"].as_str().unwrap().to_string();
+        napi.set_hypothesis_status(hypothesis_id.clone(), "in_progress".to_string(), None, None).await.unwrap();
+
+        let control = napi.access.control();
+        control.set_enforced(true);
+        let viewer = control.issue_api_key(Principal::new("viewer", vec![Role::Viewer]), None).token;
+        let analyst = control.issue_api_key(Principal::new("analyst", vec![Role::Analyst]), None).token;
+        assert!(napi.create_hypothesis(draft.clone(), Some(viewer.clone())).await.is_err());
+        assert!(napi.link_incident_to_hypothesis(hypothesis_id.clone(), "inc-1".to_string(), Some(viewer)).await.is_err());
+        napi.link_incident_to_hypothesis(hypothesis_id, "inc-1".to_string(), Some(analyst.clone())).await.unwrap();
+        napi.create_hypothesis(draft, Some(analyst)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn hypotheses_are_tested_concluded_and_covered() {
+        let core = HuntingCore::new().unwrap();
+        let leads: Vec<HuntingLead> = serde_json::from_value(json!([
+            { "hypothesis": "Additional samples from same campaign", "indicators_to_hunt": ["malware-c2.evil.com"],
+              "data_sources": ["DNS Logs"], "query_templates": [], "expected_findings": [], "priority": "High",
+              "techniques": ["T1071"] },
+            { "hypothesis": "additional  samples from same CAMPAIGN", "priority": "Low" }
+        ]))
+        .unwrap();
+        let import = core.import_hunting_leads("acme", leads, Some("analysis-1".to_string()), "alice").await.unwrap();
+        assert_eq!((import.created.len(), import.duplicates.len()), (1, 1));
+        let lead = &import.created[0];
+
+        let phishing = core
+            .create_hypothesis("acme", HypothesisDraft { statement: "Spearphishing reached finance".to_string(), techniques: vec!["T1566.001".to_string()], ..HypothesisDraft::default() }, "alice")
+            .await
+            .unwrap();
+        let vague = HypothesisDraft { statement: "Something is off".to_string(), ..HypothesisDraft::default() };
+        let vague = core.create_hypothesis("acme", vague, "alice").await.unwrap();
+
+        let concluded = core.set_hypothesis_status("acme", &phishing.hypothesis_id, HypothesisStatus::Validated, None, "alice").await;
+        assert!(concluded.is_err(), "no hunt tested it yet");
+
+        let rule_id = core.list_rules("default").await.unwrap()[0].id.clone();
+        let hunt = core.execute_hunt("acme", &rule_id, None).await.unwrap();
+        assert!(core.link_hunt_to_hypothesis("globex", &phishing.hypothesis_id, &hunt.hunt_id, "bob").await.is_err());
+        let linked = core.link_hunt_to_hypothesis("acme", &phishing.hypothesis_id, &hunt.hunt_id, "alice").await.unwrap();
+        assert_eq!(linked.status, HypothesisStatus::InProgress);
+        core.link_incident_to_hypothesis("acme", &phishing.hypothesis_id, "INC-7").await.unwrap();
+        let validated = core.set_hypothesis_status("acme", &phishing.hypothesis_id, HypothesisStatus::Validated, Some("Found the lure".to_string()), "alice").await.unwrap();
+        assert_eq!(validated.history.len(), 2);
+        assert_eq!(validated.incidents, vec!["INC-7".to_string()]);
+
+        let coverage = core.hypothesis_coverage("acme").await;
+        let tactic = |name: &str| coverage.tactics.iter().find(|t| t.tactic == name).unwrap();
+        assert_eq!(tactic("Command and Control").backlog, 1);
+        assert_eq!((tactic("Initial Access").backlog, tactic("Initial Access").validated), (0, 1));
+        assert!(coverage.uncovered_tactics.contains(&"Initial Access".to_string()));
+        assert!(!coverage.uncovered_tactics.contains(&"Command and Control".to_string()));
+        assert_eq!(coverage.unmapped_hypotheses, vec![vague.hypothesis_id]);
+        assert_eq!(coverage.validation_rate, 1.0);
+        assert!(core.get_hypothesis("globex", &lead.hypothesis_id).await.is_none());
+    }
+}
@@ -0,0 +1,241 @@
+//! Elasticsearch Indexing
+//!
+//! With `phantom-enterprise-standards` enabled and a cluster configured,
+//! every executed hunt is bulk indexed into monthly
+//! `<prefix>-hunts-YYYY.MM` indices as one document with its rule, match
+//! count and threat assessment, for Kibana dashboards. Documents are
+//! buffered and sent in the background. Hunts of every tenant go to the
+//! same cluster, so only platform administrators can configure it. Live
+//! HTTP needs the `es-indexing` feature.
+
+use crate::{HuntingCore, HuntingResult, HuntingRule};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::HuntingCoreNapi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use napi_derive::napi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::connectors::HttpTransport;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::elastic::{BulkIndexer, ElasticConfig, IndexerMetrics};
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+pub const HUNTS_STREAM: &str = "hunts";
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[derive(Default)]
+pub struct IndexingState {
+    indexer: RwLock<Option<Arc<BulkIndexer>>>,
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+impl IndexingState {
+    pub fn indexer(&self) -> Option<Arc<BulkIndexer>> {
+        self.indexer.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Field mappings of the hunts index template
+#[cfg(feature = "phantom-enterprise-standards")]
+pub fn hunt_mappings() -> serde_json::Value {
+    serde_json::json!({
+        "@timestamp": { "type": "date" },
+        "tenant_id": { "type": "keyword" },
+        "hunt_id": { "type": "keyword" },
+        "hunt_name": { "type": "keyword" },
+        "rule_id": { "type": "keyword" },
+        "category": { "type": "keyword" },
+        "severity": { "type": "keyword" },
+        "duration_ms": { "type": "long" },
+        "data_sources": { "type": "keyword" },
+        "events_processed": { "type": "long" },
+        "match_count": { "type": "integer" },
+        "max_risk_score": { "type": "float" },
+        "threat_score": { "type": "float" },
+        "threat_level": { "type": "keyword" },
+        "urgency_score": { "type": "float" },
+        "mitre_techniques": { "type": "keyword" }
+    })
+}
+
+/// Indexed document for an executed hunt
+#[cfg(feature = "phantom-enterprise-standards")]
+pub fn hunt_document(result: &HuntingResult, rule: &HuntingRule) -> serde_json::Value {
+    let assessment = &result.threat_assessment;
+    serde_json::json!({
+        "@timestamp": result.execution_timestamp,
+        "tenant_id": result.tenant_id,
+        "hunt_id": result.hunt_id,
+        "hunt_name": result.hunt_name,
+        "rule_id": result.rule_id,
+        "category": format!("{:?}", rule.category),
+        "severity": format!("{:?}", rule.severity),
+        "duration_ms": result.execution_duration.num_milliseconds(),
+        "data_sources": result.data_sources_queried,
+        "events_processed": result.total_events_processed,
+        "match_count": result.matches.len(),
+        "max_risk_score": result.matches.iter().map(|m| m.risk_score).fold(0.0, f64::max),
+        "threat_score": assessment.overall_threat_score,
+        "threat_level": format!("{:?}", assessment.threat_level),
+        "urgency_score": assessment.urgency_score,
+        "mitre_techniques": rule.mitre_techniques.iter().map(|t| &t.technique_id).collect::<Vec<_>>()
+    })
+}
+
+impl HuntingCore {
+    /// Queue an executed hunt for Elasticsearch
+    pub(crate) fn index_hunt_result(&self, result: &HuntingResult, rule: &HuntingRule) {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        if let Some(indexer) = self.indexing.indexer() {
+            let id = format!("{}:{}", result.tenant_id, result.hunt_id);
+            if indexer.enqueue(HUNTS_STREAM, &id, result.execution_timestamp, hunt_document(result, rule)) {
+                tokio::spawn(async move {
+                    indexer.flush().await;
+                });
+            }
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        let _ = (result, rule);
+    }
+
+    /// Start indexing into the cluster in `config`, or stop with `None`.
+    /// Hunts still buffered for a replaced cluster are discarded.
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub async fn configure_elasticsearch(&self, config: Option<ElasticConfig>) -> Result<Option<IndexerMetrics>, String> {
+        match config {
+            Some(config) => self.install_indexer(BulkIndexer::connect(config)?).await.map(Some),
+            None => {
+                *self.indexing.indexer.write().unwrap_or_else(|e| e.into_inner()) = None;
+                Ok(None)
+            }
+        }
+    }
+
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub async fn configure_elasticsearch_with_transport(&self, config: ElasticConfig, transport: Arc<dyn HttpTransport>) -> Result<IndexerMetrics, String> {
+        self.install_indexer(BulkIndexer::new(config, transport)?).await
+    }
+
+    #[cfg(feature = "phantom-enterprise-standards")]
+    async fn install_indexer(&self, indexer: BulkIndexer) -> Result<IndexerMetrics, String> {
+        indexer.register_stream(HUNTS_STREAM, hunt_mappings());
+        indexer.install_templates().await?;
+        let indexer = Arc::new(indexer);
+        indexer.spawn_flusher();
+        let metrics = indexer.metrics();
+        *self.indexing.indexer.write().unwrap_or_else(|e| e.into_inner()) = Some(indexer);
+        Ok(metrics)
+    }
+
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn elasticsearch_indexer(&self) -> Option<Arc<BulkIndexer>> {
+        self.indexing.indexer()
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[napi]
+impl HuntingCoreNapi {
+    /// Bulk index hunt results into Elasticsearch; installs the index
+    /// template first. Pass no config to stop indexing
+    #[napi]
+    pub async fn configure_elasticsearch_indexing(&self, config_json: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, "elasticsearch")?;
+        let mut config_value: Option<serde_json::Value> = config_json
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse Elasticsearch config: {}", e)))?;
+        let details = serde_json::json!({ "config": config_value.as_ref().map(crate::secrets::redacted) });
+        if let Some(value) = config_value.as_mut() {
+            self.inner.secrets().resolve_json(value).map_err(|e| crate::error::CoreError::from(e).context("Failed to resolve Elasticsearch credentials"))?;
+        }
+        let config: Option<ElasticConfig> = config_value
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse Elasticsearch config: {}", e)))?;
+
+        let metrics = self.inner.configure_elasticsearch(config).await;
+        let metrics = self.audit.record(&actor, "configure_elasticsearch_indexing", "elasticsearch", details, metrics)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to configure Elasticsearch indexing: {}", e)))?;
+
+        serde_json::to_string(&metrics)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize indexing metrics: {}", e)))
+    }
+
+    /// Indexing counters; `null` when not configured
+    #[napi]
+    pub fn get_elasticsearch_indexing_metrics(&self) -> napi::Result<String> {
+        let metrics = self.inner.elasticsearch_indexer().map(|indexer| indexer.metrics());
+        serde_json::to_string(&metrics)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize indexing metrics: {}", e)))
+    }
+
+    /// Send buffered hunt results now and report the outcome
+    #[napi]
+    pub async fn flush_elasticsearch_indexing(&self) -> napi::Result<String> {
+        let Some(indexer) = self.inner.elasticsearch_indexer() else {
+            return Err(napi::Error::from_reason("Elasticsearch indexing is not configured"));
+        };
+        let report = indexer.flush().await;
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize indexing report: {}", e)))
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use phantom_enterprise_standards::connectors::{ConnectorError, ConnectorRequest, ConnectorResponse};
+    use std::sync::Mutex;
+
+    /// Throttles the first bulk request, then accepts every document
+    #[derive(Default)]
+    struct FakeCluster {
+        requests: Mutex<Vec<ConnectorRequest>>,
+    }
+
+    #[async_trait]
+    impl HttpTransport for FakeCluster {
+        async fn send(&self, request: &ConnectorRequest) -> Result<ConnectorResponse, ConnectorError> {
+            let mut requests = self.requests.lock().unwrap();
+            requests.push(request.clone());
+            let bulk_requests = requests.iter().filter(|r| r.url.ends_with("/_bulk")).count();
+            let (status, body) = if request.url.ends_with("/_bulk") && bulk_requests == 1 {
+                (429, serde_json::json!({ "error": "es_rejected_execution_exception" }))
+            } else {
+                let lines = request.body.as_deref().unwrap_or_default().lines().count();
+                let items: Vec<_> = (0..lines / 2).map(|_| serde_json::json!({ "index": { "status": 201 } })).collect();
+                (200, serde_json::json!({ "errors": false, "items": items }))
+            };
+            Ok(ConnectorResponse { status, headers: Default::default(), body: body.to_string() })
+        }
+    }
+
+    #[tokio::test]
+    async fn hunt_results_are_indexed_after_throttling() {
+        let core = HuntingCore::new().unwrap();
+        let cluster = Arc::new(FakeCluster::default());
+        let config: ElasticConfig = serde_json::from_value(serde_json::json!({
+            "base_url": "http://es:9200", "index_prefix": "soc", "retry_backoff_ms": 1
+        }))
+        .unwrap();
+        core.configure_elasticsearch_with_transport(config, cluster.clone()).await.unwrap();
+
+        let rule = core.list_rules("acme").await.unwrap().remove(0);
+        let result = core.execute_hunt("acme", &rule.id, None).await.unwrap();
+        let report = core.elasticsearch_indexer().unwrap().flush().await;
+        assert_eq!(report.indexed, 1);
+        assert_eq!(report.retried, 1);
+
+        let requests = cluster.requests.lock().unwrap().clone();
+        assert_eq!(requests[0].url, "http://es:9200/_index_template/soc-hunts");
+        let bulk = requests.last().unwrap().body.clone().unwrap();
+        let index = format!("soc-hunts-{}", result.execution_timestamp.format("%Y.%m"));
+        assert!(bulk.contains(&format!(r#""_id":"acme:{}","_index":"{}""#, result.hunt_id, index)), "{}", bulk);
+        assert!(bulk.contains(&format!(r#""match_count":{}"#, result.matches.len())));
+    }
+}
@@ -0,0 +1,677 @@
+//! Model inference for match and analysis scoring
+//!
+//! `MLModel` entries describe models; this module runs them. A model is
+//! registered with a feature pipeline that turns a record — a `HuntingMatch`,
+//! or a sandbox `SandboxAnalysis` supplied as JSON — into a fixed-length input
+//! vector by resolving dotted field paths. ONNX models run on onnxruntime when
+//! the crate is built with the `onnx` feature (the shared library is located
+//! through `ORT_DYLIB_PATH`); `.json` files hold linear or logistic models
+//! that need no native runtime.
+//!
+//! Model files are watched for changes: before scoring, a model whose file
+//! modification time has moved is reloaded, checked at most once per reload
+//! interval. A failed reload keeps the previous model serving. Every model
+//! keeps inference counts and latency figures.
+
+use chrono::{DateTime, Utc};
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::{HuntingCore, HuntingMatch, MLModel, MLModelType};
+
+/// One model input, read from a field of the scored record
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureSpec {
+    pub name: String,
+    /// Dotted path into the record; a trailing `#len` counts the elements of
+    /// an array, object or string
+    pub path: String,
+    /// Used when the path is missing or not numeric
+    #[serde(default)]
+    pub default: f32,
+    /// Values are divided by this before reaching the model
+    #[serde(default)]
+    pub scale: Option<f32>,
+}
+
+impl FeatureSpec {
+    fn new(name: &str, path: &str, scale: Option<f32>) -> Self {
+        Self {
+            name: name.to_string(),
+            path: path.to_string(),
+            default: 0.0,
+            scale,
+        }
+    }
+
+    fn extract(&self, record: &Value) -> f32 {
+        let value = resolve(record, &self.path).unwrap_or(self.default);
+        match self.scale {
+            Some(scale) if scale != 0.0 => value / scale,
+            _ => value,
+        }
+    }
+}
+
+fn resolve(record: &Value, path: &str) -> Option<f32> {
+    let (path, count) = match path.strip_suffix("#len") {
+        Some(path) => (path.trim_end_matches('.'), true),
+        None => (path, false),
+    };
+
+    let mut current = record;
+    for segment in path.split('.').filter(|segment| !segment.is_empty()) {
+        current = match current {
+            Value::Object(map) => map.get(segment)?,
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+
+    if count {
+        return match current {
+            Value::Array(items) => Some(items.len() as f32),
+            Value::Object(map) => Some(map.len() as f32),
+            Value::String(text) => Some(text.chars().count() as f32),
+            Value::Null => Some(0.0),
+            _ => None,
+        };
+    }
+    match current {
+        Value::Number(number) => number.as_f64().map(|value| value as f32),
+        Value::Bool(flag) => Some(if *flag { 1.0 } else { 0.0 }),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Kind of record a model scores
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoringTarget {
+    /// Applied to every match produced by `execute_hunt`
+    HuntingMatch,
+    /// Scored on demand against analyses exported by the sandbox core
+    SandboxAnalysis,
+}
+
+impl ScoringTarget {
+    /// Feature pipeline used when a model spec does not define its own
+    pub fn default_features(&self) -> Vec<FeatureSpec> {
+        match self {
+            ScoringTarget::HuntingMatch => vec![
+                FeatureSpec::new("confidence", "confidence_score", None),
+                FeatureSpec::new("risk", "risk_score", Some(10.0)),
+                FeatureSpec::new("correlations", "correlations#len", None),
+                FeatureSpec::new("enrichments", "enrichments#len", None),
+                FeatureSpec::new("validations", "validation_results#len", None),
+                FeatureSpec::new("event_fields", "event_data#len", None),
+                FeatureSpec::new("event_frequency", "context.temporal_context.event_frequency", None),
+                FeatureSpec::new("user_risk_indicators", "context.user_context.risk_indicators#len", None),
+                FeatureSpec::new("attack_techniques", "context.threat_context.attack_techniques#len", None),
+                FeatureSpec::new("attribution_confidence", "context.threat_context.attribution_confidence", None),
+            ],
+            ScoringTarget::SandboxAnalysis => vec![
+                FeatureSpec::new("confidence", "confidence_score", None),
+                FeatureSpec::new("behavior_score", "behavioral_analysis.behavior_score", None),
+                FeatureSpec::new("network_score", "network_analysis.network_score", None),
+                FeatureSpec::new("entropy", "static_analysis.entropy_analysis.overall_entropy", Some(8.0)),
+                FeatureSpec::new("packing_probability", "static_analysis.entropy_analysis.packing_probability", None),
+                FeatureSpec::new("yara_matches", "static_analysis.yara_matches#len", None),
+                FeatureSpec::new("suspicious_behaviors", "behavioral_analysis.suspicious_behaviors#len", None),
+                FeatureSpec::new("persistence", "behavioral_analysis.persistence_mechanisms#len", None),
+                FeatureSpec::new("connections", "network_analysis.connections#len", None),
+                FeatureSpec::new("dns_queries", "network_analysis.dns_queries#len", None),
+                FeatureSpec::new("c2_indicators", "network_analysis.c2_indicators#len", None),
+                FeatureSpec::new("evasion_techniques", "evasion_techniques#len", None),
+                FeatureSpec::new("iocs", "iocs_extracted#len", None),
+                FeatureSpec::new("mitre_techniques", "mitre_techniques#len", None),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelSpec {
+    pub model_id: String,
+    pub model_type: MLModelType,
+    /// `.onnx` model, or `.json` linear model
+    pub path: PathBuf,
+    pub target: ScoringTarget,
+    /// Empty means the target's default pipeline
+    #[serde(default)]
+    pub features: Vec<FeatureSpec>,
+    /// Which element of the model output is the score
+    #[serde(default)]
+    pub output_index: usize,
+    /// Scores at or above this are flagged anomalous
+    #[serde(default = "default_threshold")]
+    pub threshold: f64,
+}
+
+fn default_threshold() -> f64 {
+    0.8
+}
+
+/// Something that maps a feature vector to model outputs
+pub trait InferenceBackend: Send + Sync {
+    fn run(&self, input: &[f32]) -> Result<Vec<f32>, String>;
+}
+
+/// Weights and bias stored as JSON; `logistic` squashes the output to 0..1
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinearModel {
+    pub weights: Vec<f32>,
+    #[serde(default)]
+    pub bias: f32,
+    #[serde(default)]
+    pub logistic: bool,
+}
+
+impl InferenceBackend for LinearModel {
+    fn run(&self, input: &[f32]) -> Result<Vec<f32>, String> {
+        if input.len() != self.weights.len() {
+            return Err(format!(
+                "Model expects {} features, pipeline produced {}",
+                self.weights.len(),
+                input.len()
+            ));
+        }
+        let sum = self.bias + self.weights.iter().zip(input).map(|(weight, value)| weight * value).sum::<f32>();
+        Ok(vec![if self.logistic { 1.0 / (1.0 + (-sum).exp()) } else { sum }])
+    }
+}
+
+#[cfg(feature = "onnx")]
+mod onnx {
+    use super::InferenceBackend;
+    use ort::session::Session;
+    use ort::value::Tensor;
+    use parking_lot::Mutex;
+    use std::path::Path;
+
+    /// onnxruntime session fed a single `[1, n]` float tensor
+    pub struct OnnxModel {
+        session: Mutex<Session>,
+    }
+
+    impl OnnxModel {
+        pub fn load(path: &Path) -> Result<Self, String> {
+            let session = Session::builder()
+                .and_then(|builder| builder.commit_from_file(path))
+                .map_err(|e| format!("Failed to load ONNX model {}: {}", path.display(), e))?;
+            Ok(Self { session: Mutex::new(session) })
+        }
+    }
+
+    impl InferenceBackend for OnnxModel {
+        fn run(&self, input: &[f32]) -> Result<Vec<f32>, String> {
+            let tensor = Tensor::from_array(([1usize, input.len()], input.to_vec()))
+                .map_err(|e| format!("Failed to build input tensor: {}", e))?;
+            let mut session = self.session.lock();
+            let outputs = session
+                .run(ort::inputs![tensor])
+                .map_err(|e| format!("ONNX inference failed: {}", e))?;
+            let (_, values) = outputs[0]
+                .try_extract_tensor::<f32>()
+                .map_err(|e| format!("Unexpected ONNX output: {}", e))?;
+            Ok(values.to_vec())
+        }
+    }
+}
+
+/// Load a model file, picking the backend from its extension
+pub fn load_backend(path: &Path) -> Result<Arc<dyn InferenceBackend>, String> {
+    if !path.is_file() {
+        return Err(format!("Model file not found: {}", path.display()));
+    }
+    match path.extension().and_then(|extension| extension.to_str()) {
+        #[cfg(feature = "onnx")]
+        Some("onnx") => Ok(Arc::new(onnx::OnnxModel::load(path)?)),
+        #[cfg(not(feature = "onnx"))]
+        Some("onnx") => Err("ONNX models require the `onnx` feature".to_string()),
+        Some("json") => {
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| format!("Failed to read model {}: {}", path.display(), e))?;
+            let model: LinearModel = serde_json::from_str(&content)
+                .map_err(|e| format!("Invalid linear model {}: {}", path.display(), e))?;
+            Ok(Arc::new(model))
+        }
+        _ => Err(format!("Unsupported model format: {}", path.display())),
+    }
+}
+
+fn modified_at(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+#[derive(Debug, Default)]
+struct LatencyStats {
+    inferences: u64,
+    failures: u64,
+    total: Duration,
+    max: Duration,
+    last: Duration,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelMetrics {
+    pub inferences: u64,
+    pub failures: u64,
+    pub mean_latency_ms: f64,
+    pub max_latency_ms: f64,
+    pub last_latency_ms: f64,
+    pub reloads: u32,
+    pub last_reload_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelInfo {
+    pub model_id: String,
+    pub model_type: MLModelType,
+    pub path: PathBuf,
+    pub target: ScoringTarget,
+    pub features: Vec<String>,
+    /// Bumped on every successful reload
+    pub version: u32,
+    pub loaded_at: DateTime<Utc>,
+    pub metrics: ModelMetrics,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelScore {
+    pub model_id: String,
+    pub model_version: u32,
+    pub score: f64,
+    pub anomalous: bool,
+    pub outputs: Vec<f32>,
+    pub latency_ms: f64,
+}
+
+struct LoadedModel {
+    spec: ModelSpec,
+    backend: Arc<dyn InferenceBackend>,
+    modified: Option<SystemTime>,
+    loaded_at: DateTime<Utc>,
+    version: u32,
+    reloads: u32,
+    last_reload_error: Option<String>,
+    last_checked: Instant,
+    stats: Mutex<LatencyStats>,
+}
+
+impl LoadedModel {
+    fn info(&self) -> ModelInfo {
+        let stats = self.stats.lock();
+        let millis = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        ModelInfo {
+            model_id: self.spec.model_id.clone(),
+            model_type: self.spec.model_type.clone(),
+            path: self.spec.path.clone(),
+            target: self.spec.target,
+            features: self.spec.features.iter().map(|feature| feature.name.clone()).collect(),
+            version: self.version,
+            loaded_at: self.loaded_at,
+            metrics: ModelMetrics {
+                inferences: stats.inferences,
+                failures: stats.failures,
+                mean_latency_ms: if stats.inferences == 0 { 0.0 } else { millis(stats.total) / stats.inferences as f64 },
+                max_latency_ms: millis(stats.max),
+                last_latency_ms: millis(stats.last),
+                reloads: self.reloads,
+                last_reload_error: self.last_reload_error.clone(),
+            },
+        }
+    }
+}
+
+/// Loaded models keyed by id
+pub struct ModelRegistry {
+    models: RwLock<HashMap<String, LoadedModel>>,
+    reload_interval: Duration,
+}
+
+impl Default for ModelRegistry {
+    fn default() -> Self {
+        Self::with_reload_interval(Duration::from_secs(5))
+    }
+}
+
+impl ModelRegistry {
+    pub fn with_reload_interval(reload_interval: Duration) -> Self {
+        Self {
+            models: RwLock::new(HashMap::new()),
+            reload_interval,
+        }
+    }
+
+    /// Load a model and make it available for scoring, replacing any model
+    /// registered under the same id
+    pub fn register(&self, mut spec: ModelSpec) -> Result<ModelInfo, String> {
+        if spec.model_id.trim().is_empty() {
+            return Err("Model id must not be empty".to_string());
+        }
+        if spec.features.is_empty() {
+            spec.features = spec.target.default_features();
+        }
+
+        let modified = modified_at(&spec.path);
+        let backend = load_backend(&spec.path)?;
+        let loaded = LoadedModel {
+            spec,
+            backend,
+            modified,
+            loaded_at: Utc::now(),
+            version: 1,
+            reloads: 0,
+            last_reload_error: None,
+            last_checked: Instant::now(),
+            stats: Mutex::new(LatencyStats::default()),
+        };
+        let info = loaded.info();
+        self.models.write().insert(info.model_id.clone(), loaded);
+        Ok(info)
+    }
+
+    pub fn unregister(&self, model_id: &str) -> bool {
+        self.models.write().remove(model_id).is_some()
+    }
+
+    pub fn list(&self) -> Vec<ModelInfo> {
+        let mut models: Vec<ModelInfo> = self.models.read().values().map(LoadedModel::info).collect();
+        models.sort_by(|a, b| a.model_id.cmp(&b.model_id));
+        models
+    }
+
+    pub fn model_ids_for(&self, target: ScoringTarget) -> Vec<String> {
+        self.models
+            .read()
+            .values()
+            .filter(|model| model.spec.target == target)
+            .map(|model| model.spec.model_id.clone())
+            .collect()
+    }
+
+    /// Reload the model if its file changed since it was loaded. Returns
+    /// whether a new version is now serving.
+    pub fn reload_if_changed(&self, model_id: &str) -> Result<bool, String> {
+        let (path, previous) = {
+            let mut models = self.models.write();
+            let model = models.get_mut(model_id).ok_or_else(|| format!("Model {} not registered", model_id))?;
+            model.last_checked = Instant::now();
+            (model.spec.path.clone(), model.modified)
+        };
+
+        let modified = modified_at(&path);
+        if modified.is_none() || modified == previous {
+            return Ok(false);
+        }
+
+        // Load outside the lock so scoring keeps running on the old version
+        let loaded = load_backend(&path);
+        let mut models = self.models.write();
+        let model = models.get_mut(model_id).ok_or_else(|| format!("Model {} not registered", model_id))?;
+        match loaded {
+            Ok(backend) => {
+                model.backend = backend;
+                model.modified = modified;
+                model.loaded_at = Utc::now();
+                model.version += 1;
+                model.reloads += 1;
+                model.last_reload_error = None;
+                log::info!("Reloaded model {} (version {})", model_id, model.version);
+                Ok(true)
+            }
+            Err(e) => {
+                // Remember the timestamp so a broken file is not retried on every score
+                model.modified = modified;
+                model.last_reload_error = Some(e.clone());
+                Err(e)
+            }
+        }
+    }
+
+    fn reload_due(&self, model_id: &str) -> bool {
+        self.models
+            .read()
+            .get(model_id)
+            .is_some_and(|model| model.last_checked.elapsed() >= self.reload_interval)
+    }
+
+    /// Score a record with one model
+    pub fn score(&self, model_id: &str, record: &Value) -> Result<ModelScore, String> {
+        if self.reload_due(model_id) {
+            if let Err(e) = self.reload_if_changed(model_id) {
+                log::warn!("Keeping previous version of model {}: {}", model_id, e);
+            }
+        }
+
+        let models = self.models.read();
+        let model = models.get(model_id).ok_or_else(|| format!("Model {} not registered", model_id))?;
+        let input: Vec<f32> = model.spec.features.iter().map(|feature| feature.extract(record)).collect();
+
+        let started = Instant::now();
+        let outcome = model.backend.run(&input).and_then(|outputs| {
+            let score = *outputs.get(model.spec.output_index).ok_or_else(|| {
+                format!("Model produced {} outputs, no index {}", outputs.len(), model.spec.output_index)
+            })?;
+            Ok((score as f64, outputs))
+        });
+        let latency = started.elapsed();
+
+        let mut stats = model.stats.lock();
+        stats.last = latency;
+        stats.max = stats.max.max(latency);
+        match outcome {
+            Ok((score, outputs)) => {
+                stats.inferences += 1;
+                stats.total += latency;
+                Ok(ModelScore {
+                    model_id: model_id.to_string(),
+                    model_version: model.version,
+                    score,
+                    anomalous: score >= model.spec.threshold,
+                    outputs,
+                    latency_ms: latency.as_secs_f64() * 1000.0,
+                })
+            }
+            Err(e) => {
+                stats.failures += 1;
+                Err(e)
+            }
+        }
+    }
+}
+
+impl HuntingCore {
+    /// Load a model and record it in the model catalogue
+    pub async fn register_ml_model(&self, spec: ModelSpec) -> Result<ModelInfo, String> {
+        let threshold = spec.threshold;
+        let info = self.inference.register(spec)?;
+
+        let mut catalogue = self.ml_models.write().await;
+        let accuracy = catalogue.get(&info.model_id).map(|model| model.accuracy).unwrap_or(0.0);
+        catalogue.insert(info.model_id.clone(), MLModel {
+            model_id: info.model_id.clone(),
+            model_type: info.model_type.clone(),
+            accuracy,
+            training_date: info.loaded_at,
+            feature_set: info.features.clone(),
+            enabled: true,
+            confidence_threshold: threshold,
+        });
+        Ok(info)
+    }
+
+    pub async fn unregister_ml_model(&self, model_id: &str) -> bool {
+        let removed = self.inference.unregister(model_id);
+        if removed {
+            self.ml_models.write().await.remove(model_id);
+        }
+        removed
+    }
+
+    pub fn list_loaded_models(&self) -> Vec<ModelInfo> {
+        self.inference.list()
+    }
+
+    /// Score an arbitrary record, e.g. a sandbox analysis, with one model
+    pub fn score_record(&self, model_id: &str, record: &Value) -> Result<ModelScore, String> {
+        self.inference.score(model_id, record)
+    }
+
+    /// Attach every enabled match model's score to each match
+    pub(crate) async fn apply_match_models(&self, matches: &mut [HuntingMatch]) {
+        let model_ids: Vec<String> = {
+            let catalogue = self.ml_models.read().await;
+            self.inference
+                .model_ids_for(ScoringTarget::HuntingMatch)
+                .into_iter()
+                .filter(|model_id| catalogue.get(model_id).is_none_or(|model| model.enabled))
+                .collect()
+        };
+        if model_ids.is_empty() {
+            return;
+        }
+
+        for hunt_match in matches.iter_mut() {
+            let record = match serde_json::to_value(&*hunt_match) {
+                Ok(record) => record,
+                Err(e) => {
+                    log::warn!("Failed to encode match {} for scoring: {}", hunt_match.match_id, e);
+                    continue;
+                }
+            };
+            for model_id in &model_ids {
+                match self.inference.score(model_id, &record) {
+                    Ok(score) => {
+                        hunt_match.ml_scores.insert(model_id.clone(), score.score);
+                    }
+                    Err(e) => log::warn!("Model {} failed on match {}: {}", model_id, hunt_match.match_id, e),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn write_model(path: &Path, weights: &[f32], bias: f32) {
+        let model = LinearModel { weights: weights.to_vec(), bias, logistic: false };
+        std::fs::write(path, serde_json::to_string(&model).unwrap()).unwrap();
+    }
+
+    fn spec(model_id: &str, path: &Path) -> ModelSpec {
+        ModelSpec {
+            model_id: model_id.to_string(),
+            model_type: MLModelType::BehavioralAnomaly,
+            path: path.to_path_buf(),
+            target: ScoringTarget::SandboxAnalysis,
+            features: vec![
+                FeatureSpec::new("score", "network_analysis.network_score", None),
+                FeatureSpec::new("dns", "network_analysis.dns_queries#len", Some(2.0)),
+            ],
+            output_index: 0,
+            threshold: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_feature_paths() {
+        let record = json!({ "a": { "b": [1, { "c": "2.5" }], "flag": true }, "name": "abc" });
+        assert_eq!(resolve(&record, "a.b.1.c"), Some(2.5));
+        assert_eq!(resolve(&record, "a.b#len"), Some(2.0));
+        assert_eq!(resolve(&record, "a.flag"), Some(1.0));
+        assert_eq!(resolve(&record, "name#len"), Some(3.0));
+        assert_eq!(resolve(&record, "a.missing"), None);
+        assert_eq!(FeatureSpec { default: -1.0, ..FeatureSpec::new("x", "nope", None) }.extract(&record), -1.0);
+    }
+
+    #[test]
+    fn test_scoring_hot_reload_and_metrics() {
+        let dir = std::env::temp_dir().join(format!("phantom-models-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("model.json");
+        write_model(&path, &[1.0, 0.5], 0.0);
+
+        let registry = ModelRegistry::with_reload_interval(Duration::ZERO);
+        let info = registry.register(spec("net", &path)).unwrap();
+        assert_eq!(info.features, ["score", "dns"]);
+        assert!(registry.register(spec("onnx", &dir.join("model.onnx"))).is_err());
+
+        let record = json!({ "network_analysis": { "network_score": 0.25, "dns_queries": [{}, {}] } });
+        let score = registry.score("net", &record).unwrap();
+        assert!((score.score - 0.75).abs() < 1e-6);
+        assert!(score.anomalous);
+        assert_eq!(score.model_version, 1);
+
+        // Rewrite with a later timestamp; the next score picks it up
+        std::thread::sleep(Duration::from_millis(20));
+        write_model(&path, &[0.0, 0.0], 0.1);
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(5)).unwrap();
+        let score = registry.score("net", &record).unwrap();
+        assert_eq!(score.model_version, 2);
+        assert!(!score.anomalous);
+
+        // A broken file keeps the previous version serving
+        std::fs::write(&path, "not json").unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10)).unwrap();
+        assert_eq!(registry.score("net", &record).unwrap().model_version, 2);
+
+        let metrics = &registry.list()[0].metrics;
+        assert_eq!((metrics.inferences, metrics.failures, metrics.reloads), (3, 0, 1));
+        assert!(metrics.last_reload_error.is_some());
+
+        write_model(&path, &[1.0], 0.0);
+        file.set_modified(SystemTime::now() + Duration::from_secs(15)).unwrap();
+        assert!(registry.score("net", &record).is_err());
+        assert_eq!(registry.list()[0].metrics.failures, 1);
+
+        assert!(registry.unregister("net"));
+        assert!(registry.score("net", &record).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_hunt_matches_carry_model_scores() {
+        let dir = std::env::temp_dir().join(format!("phantom-models-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("matches.json");
+        let features = ScoringTarget::HuntingMatch.default_features();
+        let model = LinearModel { weights: vec![0.1; features.len()], bias: 0.0, logistic: true };
+        std::fs::write(&path, serde_json::to_string(&model).unwrap()).unwrap();
+
+        let core = HuntingCore::new().unwrap();
+        core.register_ml_model(ModelSpec {
+            model_id: "match_scorer".to_string(),
+            model_type: MLModelType::ThreatClassifier,
+            path: path.clone(),
+            target: ScoringTarget::HuntingMatch,
+            features: vec![],
+            output_index: 0,
+            threshold: 0.8,
+        })
+        .await
+        .unwrap();
+        assert!(core.ml_models.read().await.contains_key("match_scorer"));
+
+        let rule_id = core.list_rules("default").await.unwrap()[0].id.clone();
+        let result = core.execute_hunt("default", &rule_id, None).await.unwrap();
+        assert!(result.matches.iter().all(|hunt_match| {
+            hunt_match.ml_scores.get("match_scorer").is_some_and(|score| *score > 0.0 && *score < 1.0)
+        }));
+
+        assert!(core.unregister_ml_model("match_scorer").await);
+        assert!(!core.ml_models.read().await.contains_key("match_scorer"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,943 @@
+//! Streaming event ingestion
+//!
+//! High-volume event streams feed continuous hunting instead of waiting for
+//! the next scheduled hunt. Each tenant configures named ingestion sources;
+//! records arrive as newline-delimited batches from JS (`Buffer`s) or as
+//! JSON lines over a TCP listener, are parsed per source (JSON, `key=value`
+//! or CEF), renamed into the field names rules use through the source's
+//! `field_map`, and queued in a bounded per-source queue.
+//!
+//! When a queue is full its overflow policy decides: `drop_oldest` (the
+//! default) evicts the oldest queued event, `drop_newest` discards the
+//! incoming one and `block` makes the producer wait up to `timeout_ms` for
+//! room before rejecting it. Blocking propagates to TCP senders, which stop
+//! being read while the queue is full.
+//!
+//! A task per source drains its queue in batches and evaluates every event
+//! against the detection conditions of the tenant's rules covering the
+//! source; matches are kept per tenant and read with `continuous_matches`,
+//! except for events the tenant's suppressions allowlist. Rules with
+//! correlation rules keep their correlation state per source across
+//! batches and match on correlated events instead (see `correlation`).
+//! Per-source metrics report throughput, drops, queue depth and lag.
+
+use chrono::{DateTime, TimeZone, Utc};
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::Instant;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::net::TcpListener;
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+
+use crate::connectors::{event_match, flatten};
+use crate::correlation::RuleCorrelator;
+use crate::suppression::SuppressionState;
+use crate::{conditions, tenancy, DataSourceType, HuntingCore, HuntingCoreNapi, HuntingMatch, HuntingRule};
+
+/// Longest record accepted; longer TCP lines close the connection
+pub const MAX_RECORD_BYTES: usize = 1 << 20;
+/// Continuous matches kept per tenant; the oldest are dropped first
+pub const MAX_CONTINUOUS_MATCHES: usize = 1_000;
+
+fn default_queue_capacity() -> usize {
+    10_000
+}
+
+fn default_batch_size() -> usize {
+    256
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventFormat {
+    /// One JSON object per line; nested objects become dotted field names
+    #[default]
+    Json,
+    /// `key=value` pairs separated by whitespace, values optionally quoted
+    KeyValue,
+    /// ArcSight Common Event Format, optionally behind a syslog header
+    Cef,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "policy", rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    #[default]
+    DropOldest,
+    DropNewest,
+    /// Wait up to `timeout_ms` for room, then reject the event
+    Block { timeout_ms: u64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestionSourceConfig {
+    pub name: String,
+    #[serde(default)]
+    pub format: EventFormat,
+    /// Rules whose data sources include this type evaluate the stream;
+    /// `None` selects every rule with detection conditions
+    #[serde(default)]
+    pub source_type: Option<DataSourceType>,
+    /// Evaluate only these rules instead of selecting by source type
+    #[serde(default)]
+    pub rule_ids: Vec<String>,
+    /// Raw field name to the field name rules use
+    #[serde(default)]
+    pub field_map: HashMap<String, String>,
+    /// Raw field holding the event time (RFC 3339 or epoch seconds/millis);
+    /// events without it are stamped on arrival
+    #[serde(default)]
+    pub timestamp_field: Option<String>,
+    #[serde(default = "default_queue_capacity")]
+    pub queue_capacity: usize,
+    #[serde(default)]
+    pub overflow: OverflowPolicy,
+    /// Events evaluated per batch
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+}
+
+impl IngestionSourceConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Ingestion source name is required".to_string());
+        }
+        if self.queue_capacity == 0 || self.batch_size == 0 {
+            return Err("Queue capacity and batch size must be positive".to_string());
+        }
+        Ok(())
+    }
+
+    fn covers(&self, rule: &HuntingRule, tenant_id: &str) -> bool {
+        if !tenancy::rule_visible(rule, tenant_id) || rule.detection_logic.conditions.is_empty() {
+            return false;
+        }
+        if !self.rule_ids.is_empty() {
+            return self.rule_ids.contains(&rule.id);
+        }
+        let Some(wanted) = &self.source_type else { return true };
+        let wanted = format!("{:?}", wanted);
+        rule.data_sources.iter().any(|source| format!("{:?}", source.source_type) == wanted)
+    }
+}
+
+/// An ingested record after parsing and field mapping
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizedEvent {
+    pub timestamp: DateTime<Utc>,
+    pub received_at: DateTime<Utc>,
+    pub source: String,
+    pub fields: HashMap<String, Value>,
+}
+
+fn parse_key_values(text: &str) -> HashMap<String, Value> {
+    let mut fields = HashMap::new();
+    let mut rest = text.trim_start();
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim();
+        rest = &rest[eq + 1..];
+        let value = if let Some(quoted) = rest.strip_prefix('"') {
+            let mut value = String::new();
+            let mut chars = quoted.char_indices();
+            let mut end = quoted.len();
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '\\' => value.extend(chars.next().map(|(_, escaped)| escaped)),
+                    '"' => {
+                        end = i + 1;
+                        break;
+                    }
+                    c => value.push(c),
+                }
+            }
+            rest = &quoted[end..];
+            value
+        } else {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let value = rest[..end].to_string();
+            rest = &rest[end..];
+            value
+        };
+        if !key.is_empty() && !key.contains(char::is_whitespace) {
+            fields.insert(key.to_string(), Value::String(value));
+        }
+        rest = rest.trim_start();
+    }
+    fields
+}
+
+fn cef_extension_key() -> &'static Regex {
+    static KEY: OnceLock<Regex> = OnceLock::new();
+    KEY.get_or_init(|| Regex::new(r"(?:^|\s)([A-Za-z0-9_.]+)=").expect("valid CEF key pattern"))
+}
+
+fn unescape_cef(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some(escaped) => out.push(escaped),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn parse_cef(text: &str) -> Result<HashMap<String, Value>, String> {
+    let start = text.find("CEF:").ok_or("Record is not CEF")?;
+    let body = &text[start + 4..];
+    let mut header = Vec::with_capacity(7);
+    let mut field_start = 0;
+    let mut escaped = false;
+    for (i, c) in body.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '|' => {
+                header.push(unescape_cef(&body[field_start..i]));
+                field_start = i + 1;
+                if header.len() == 7 {
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    if header.len() < 7 {
+        return Err("CEF header needs seven fields".to_string());
+    }
+    let names = ["cef_version", "device_vendor", "device_product", "device_version", "signature_id", "name", "severity"];
+    let mut fields: HashMap<String, Value> = names.iter().zip(header).map(|(name, value)| (name.to_string(), Value::String(value))).collect();
+
+    let extension = &body[field_start..];
+    let keys: Vec<_> = cef_extension_key().captures_iter(extension).filter_map(|c| c.get(1)).collect();
+    for (i, key) in keys.iter().enumerate() {
+        let value_end = keys.get(i + 1).map(|next| next.start()).unwrap_or(extension.len());
+        let value = extension[key.end() + 1..value_end].trim_end();
+        fields.insert(key.as_str().to_string(), Value::String(unescape_cef(value)));
+    }
+    Ok(fields)
+}
+
+pub(crate) fn event_time(value: &Value) -> Option<DateTime<Utc>> {
+    let epoch = |n: f64| {
+        let millis = if n.abs() < 1e11 { n * 1_000.0 } else { n };
+        Utc.timestamp_millis_opt(millis as i64).single()
+    };
+    match value {
+        Value::Number(n) => epoch(n.as_f64()?),
+        Value::String(s) => DateTime::parse_from_rfc3339(s).map(|at| at.with_timezone(&Utc)).ok().or_else(|| epoch(s.parse().ok()?)),
+        _ => None,
+    }
+}
+
+/// Parse one record of `config`'s format and map its fields
+pub fn parse_record(config: &IngestionSourceConfig, record: &str) -> Result<NormalizedEvent, String> {
+    let mut fields = match config.format {
+        EventFormat::Json => match serde_json::from_str::<Value>(record) {
+            Ok(value @ Value::Object(_)) => flatten(&value),
+            Ok(_) => return Err("JSON record is not an object".to_string()),
+            Err(e) => return Err(format!("Invalid JSON record: {}", e)),
+        },
+        EventFormat::KeyValue => parse_key_values(record),
+        EventFormat::Cef => parse_cef(record)?,
+    };
+    if fields.is_empty() {
+        return Err("Record has no fields".to_string());
+    }
+    let received_at = Utc::now();
+    let timestamp = config.timestamp_field.as_ref().and_then(|field| fields.get(field)).and_then(event_time).unwrap_or(received_at);
+    for (raw, mapped) in &config.field_map {
+        if let Some(value) = fields.remove(raw) {
+            fields.insert(mapped.clone(), value);
+        }
+    }
+    Ok(NormalizedEvent { timestamp, received_at, source: config.name.clone(), fields })
+}
+
+/// Outcome of offering one event to a queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Admission {
+    Accepted,
+    /// Queued, but the queue dropped an event to make room
+    DisplacedOldest,
+    Dropped,
+    Rejected,
+}
+
+/// Result of ingesting one batch of records
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IngestReceipt {
+    pub accepted: u64,
+    /// Events lost to the overflow policy, incoming or evicted
+    pub dropped: u64,
+    /// Events refused after waiting for room
+    pub rejected: u64,
+    pub parse_errors: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IngestionMetrics {
+    pub source: String,
+    pub tenant_id: String,
+    pub received: u64,
+    pub parse_errors: u64,
+    pub accepted: u64,
+    pub dropped: u64,
+    pub rejected: u64,
+    pub evaluated: u64,
+    pub matches: u64,
+    pub queue_depth: usize,
+    pub queue_capacity: usize,
+    /// Deepest the queue has been
+    pub high_watermark: usize,
+    /// Events evaluated per second over the last measuring window
+    pub events_per_second: f64,
+    /// Time the oldest queued event has been waiting
+    pub queue_lag_ms: i64,
+    /// How far the newest evaluated event time trails the clock
+    pub event_lag_ms: i64,
+    /// Address of the TCP listener feeding the source
+    pub listener: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContinuousMatch {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub source: String,
+    pub hunt_match: HuntingMatch,
+}
+
+struct Counters {
+    received: u64,
+    parse_errors: u64,
+    accepted: u64,
+    dropped: u64,
+    rejected: u64,
+    evaluated: u64,
+    matches: u64,
+    high_watermark: usize,
+    events_per_second: f64,
+    event_lag_ms: i64,
+    window_start: Instant,
+    window_events: u64,
+}
+
+impl Default for Counters {
+    fn default() -> Self {
+        Self {
+            received: 0,
+            parse_errors: 0,
+            accepted: 0,
+            dropped: 0,
+            rejected: 0,
+            evaluated: 0,
+            matches: 0,
+            high_watermark: 0,
+            events_per_second: 0.0,
+            event_lag_ms: 0,
+            window_start: Instant::now(),
+            window_events: 0,
+        }
+    }
+}
+
+/// Bounded queue of one source; the producer side applies the overflow policy
+struct SourceQueue {
+    tenant_id: String,
+    config: IngestionSourceConfig,
+    events: Mutex<VecDeque<NormalizedEvent>>,
+    /// Signals the evaluator that events were queued
+    ready: Notify,
+    /// Signals blocked producers that a batch was taken
+    space: Notify,
+    counters: Mutex<Counters>,
+}
+
+impl SourceQueue {
+    fn new(tenant_id: &str, config: IngestionSourceConfig) -> Self {
+        Self {
+            tenant_id: tenant_id.to_string(),
+            config,
+            events: Mutex::new(VecDeque::new()),
+            ready: Notify::new(),
+            space: Notify::new(),
+            counters: Mutex::new(Counters::default()),
+        }
+    }
+
+    fn counters(&self) -> std::sync::MutexGuard<'_, Counters> {
+        self.counters.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn events(&self) -> std::sync::MutexGuard<'_, VecDeque<NormalizedEvent>> {
+        self.events.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    async fn offer(&self, event: NormalizedEvent) -> Admission {
+        let deadline = match self.config.overflow {
+            OverflowPolicy::Block { timeout_ms } => Some(tokio::time::Instant::now() + std::time::Duration::from_millis(timeout_ms)),
+            _ => None,
+        };
+        let mut event = Some(event);
+        loop {
+            let space = self.space.notified();
+            tokio::pin!(space);
+            space.as_mut().enable();
+            let admission = {
+                let mut events = self.events();
+                let full = events.len() >= self.config.queue_capacity;
+                let admission = match (&self.config.overflow, full) {
+                    (_, false) => Some(Admission::Accepted),
+                    (OverflowPolicy::DropOldest, true) => {
+                        events.pop_front();
+                        Some(Admission::DisplacedOldest)
+                    }
+                    (OverflowPolicy::DropNewest, true) => return Admission::Dropped,
+                    (OverflowPolicy::Block { .. }, true) => None,
+                };
+                if admission.is_some() {
+                    events.extend(event.take());
+                    let depth = events.len();
+                    let mut counters = self.counters();
+                    counters.high_watermark = counters.high_watermark.max(depth);
+                }
+                admission
+            };
+            if let Some(admission) = admission {
+                self.ready.notify_one();
+                return admission;
+            }
+            let deadline = deadline.expect("only blocking queues wait");
+            if tokio::time::timeout_at(deadline, space).await.is_err() {
+                return Admission::Rejected;
+            }
+        }
+    }
+
+    /// Parse and queue newline-delimited records
+    async fn ingest(&self, data: &[u8]) -> IngestReceipt {
+        let mut receipt = IngestReceipt::default();
+        for line in data.split(|b| *b == b'\n') {
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            self.counters().received += 1;
+            let parsed = match std::str::from_utf8(line) {
+                Ok(_) if line.len() > MAX_RECORD_BYTES => Err(format!("Record exceeds {} bytes", MAX_RECORD_BYTES)),
+                Ok(text) => parse_record(&self.config, text),
+                Err(e) => Err(e.to_string()),
+            };
+            let event = match parsed {
+                Ok(event) => event,
+                Err(e) => {
+                    log::debug!("Dropping unparsable record on {}: {}", self.config.name, e);
+                    receipt.parse_errors += 1;
+                    self.counters().parse_errors += 1;
+                    continue;
+                }
+            };
+            let admission = self.offer(event).await;
+            let mut counters = self.counters();
+            match admission {
+                Admission::Accepted => {
+                    receipt.accepted += 1;
+                    counters.accepted += 1;
+                }
+                Admission::DisplacedOldest => {
+                    receipt.accepted += 1;
+                    receipt.dropped += 1;
+                    counters.accepted += 1;
+                    counters.dropped += 1;
+                }
+                Admission::Dropped => {
+                    receipt.dropped += 1;
+                    counters.dropped += 1;
+                }
+                Admission::Rejected => {
+                    receipt.rejected += 1;
+                    counters.rejected += 1;
+                }
+            }
+        }
+        receipt
+    }
+
+    fn take_batch(&self) -> Vec<NormalizedEvent> {
+        let mut events = self.events();
+        let count = self.config.batch_size.min(events.len());
+        events.drain(..count).collect()
+    }
+
+    fn metrics(&self, listener: Option<SocketAddr>) -> IngestionMetrics {
+        let (queue_depth, oldest) = {
+            let events = self.events();
+            (events.len(), events.front().map(|event| event.received_at))
+        };
+        let counters = self.counters();
+        IngestionMetrics {
+            source: self.config.name.clone(),
+            tenant_id: self.tenant_id.clone(),
+            received: counters.received,
+            parse_errors: counters.parse_errors,
+            accepted: counters.accepted,
+            dropped: counters.dropped,
+            rejected: counters.rejected,
+            evaluated: counters.evaluated,
+            matches: counters.matches,
+            queue_depth,
+            queue_capacity: self.config.queue_capacity,
+            high_watermark: counters.high_watermark,
+            events_per_second: counters.events_per_second,
+            queue_lag_ms: oldest.map(|at| (Utc::now() - at).num_milliseconds().max(0)).unwrap_or(0),
+            event_lag_ms: counters.event_lag_ms,
+            listener: listener.map(|addr| addr.to_string()),
+        }
+    }
+}
+
+type ContinuousMatches = Arc<RwLock<HashMap<String, VecDeque<ContinuousMatch>>>>;
+type Rules = Arc<tokio::sync::RwLock<HashMap<String, HuntingRule>>>;
+
+/// Drain `queue` forever, evaluating each batch against the covering rules
+async fn evaluate_source(queue: Arc<SourceQueue>, rules: Rules, matches: ContinuousMatches, suppression: Arc<SuppressionState>) {
+    // Correlation state per rule, rebuilt when the rule changes
+    let mut correlators: HashMap<String, (DateTime<Utc>, RuleCorrelator)> = HashMap::new();
+    loop {
+        let ready = queue.ready.notified();
+        tokio::pin!(ready);
+        ready.as_mut().enable();
+        let batch = queue.take_batch();
+        if batch.is_empty() {
+            ready.await;
+            continue;
+        }
+        queue.space.notify_waiters();
+
+        let covering: Vec<HuntingRule> = rules.read().await.values().filter(|rule| queue.config.covers(rule, &queue.tenant_id)).cloned().collect();
+        correlators.retain(|rule_id, _| covering.iter().any(|rule| &rule.id == rule_id));
+        for rule in &covering {
+            let current = correlators.get(&rule.id).is_some_and(|(modified, _)| *modified == rule.metadata.last_modified);
+            if !current {
+                match RuleCorrelator::new(rule) {
+                    Some(correlator) => correlators.insert(rule.id.clone(), (rule.metadata.last_modified, correlator)),
+                    None => correlators.remove(&rule.id),
+                };
+            }
+        }
+        let newest = batch.iter().map(|event| event.timestamp).max();
+        let mut found = Vec::new();
+        for event in &batch {
+            // Checked once per event, and only when a rule needs it
+            let mut suppressed = None;
+            let mut is_suppressed = || *suppressed.get_or_insert_with(|| suppression.suppresses_event(&queue.tenant_id, &event.fields));
+            for rule in &covering {
+                // Rules with correlation rules match on the correlated events instead of single ones
+                if let Some((_, correlator)) = correlators.get_mut(&rule.id) {
+                    if is_suppressed() {
+                        continue;
+                    }
+                    found.extend(correlator.observe(&event.source, event.timestamp, &event.fields).into_iter().map(|hunt_match| ContinuousMatch {
+                        rule_id: rule.id.clone(),
+                        rule_name: rule.name.clone(),
+                        source: event.source.clone(),
+                        hunt_match,
+                    }));
+                    continue;
+                }
+                if let Some(confidence) = conditions::evaluate_conditions(&event.fields, &rule.detection_logic.conditions) {
+                    if is_suppressed() {
+                        continue;
+                    }
+                    found.push(ContinuousMatch {
+                        rule_id: rule.id.clone(),
+                        rule_name: rule.name.clone(),
+                        source: event.source.clone(),
+                        hunt_match: event_match(&event.source, Some(event.timestamp), event.fields.clone(), confidence, rule.severity.risk_weight()),
+                    });
+                }
+            }
+        }
+
+        {
+            let mut counters = queue.counters();
+            counters.evaluated += batch.len() as u64;
+            counters.matches += found.len() as u64;
+            counters.event_lag_ms = newest.map(|at| (Utc::now() - at).num_milliseconds().max(0)).unwrap_or(0);
+            counters.window_events += batch.len() as u64;
+            let elapsed = counters.window_start.elapsed().as_secs_f64();
+            if elapsed >= 1.0 {
+                counters.events_per_second = counters.window_events as f64 / elapsed;
+                counters.window_start = Instant::now();
+                counters.window_events = 0;
+            }
+        }
+        if !found.is_empty() {
+            let mut matches = matches.write().unwrap_or_else(|e| e.into_inner());
+            let kept = matches.entry(queue.tenant_id.clone()).or_default();
+            kept.extend(found);
+            let excess = kept.len().saturating_sub(MAX_CONTINUOUS_MATCHES);
+            kept.drain(..excess);
+        }
+    }
+}
+
+/// Feed JSON lines from every connection to `queue`; a full blocking queue
+/// stops reading from the socket
+async fn listen(listener: TcpListener, queue: Arc<SourceQueue>) {
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                log::warn!("Ingestion listener for {} failed to accept: {}", queue.config.name, e);
+                continue;
+            }
+        };
+        let queue = queue.clone();
+        tokio::spawn(async move {
+            let mut reader = BufReader::new(stream);
+            let mut line = Vec::new();
+            loop {
+                line.clear();
+                match (&mut reader).take(MAX_RECORD_BYTES as u64 + 1).read_until(b'\n', &mut line).await {
+                    Ok(0) => break,
+                    Ok(_) if line.len() > MAX_RECORD_BYTES => {
+                        log::warn!("Closing ingestion connection from {}: record exceeds {} bytes", peer, MAX_RECORD_BYTES);
+                        queue.counters().parse_errors += 1;
+                        break;
+                    }
+                    Ok(_) => {
+                        queue.ingest(&line).await;
+                    }
+                    Err(e) => {
+                        log::debug!("Ingestion connection from {} closed: {}", peer, e);
+                        break;
+                    }
+                }
+            }
+        });
+    }
+}
+
+struct SourceEntry {
+    queue: Arc<SourceQueue>,
+    evaluator: JoinHandle<()>,
+    listener: Option<(SocketAddr, JoinHandle<()>)>,
+}
+
+impl Drop for SourceEntry {
+    fn drop(&mut self) {
+        self.evaluator.abort();
+        if let Some((_, listener)) = &self.listener {
+            listener.abort();
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct IngestionState {
+    /// Sources per (tenant, name)
+    sources: RwLock<HashMap<(String, String), SourceEntry>>,
+    matches: ContinuousMatches,
+}
+
+impl IngestionState {
+    fn queue(&self, tenant_id: &str, name: &str) -> Result<Arc<SourceQueue>, String> {
+        self.sources
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&(tenant_id.to_string(), name.to_string()))
+            .map(|entry| entry.queue.clone())
+            .ok_or_else(|| format!("Ingestion source {} not found", name))
+    }
+
+    /// Metrics of `tenant_id`'s sources, or of every tenant's, by tenant and source name
+    pub(crate) fn source_metrics(&self, tenant_id: Option<&str>) -> Vec<IngestionMetrics> {
+        let mut metrics: Vec<IngestionMetrics> = self
+            .sources
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|((tenant, _), _)| tenant_id.is_none_or(|wanted| tenant == wanted))
+            .map(|(_, entry)| entry.queue.metrics(entry.listener.as_ref().map(|(addr, _)| *addr)))
+            .collect();
+        metrics.sort_by(|a, b| (&a.tenant_id, &a.source).cmp(&(&b.tenant_id, &b.source)));
+        metrics
+    }
+
+    /// Drop the sources and continuous matches of `tenant_id`
+    pub(crate) fn forget_tenant(&self, tenant_id: &str) -> usize {
+        self.matches.write().unwrap_or_else(|e| e.into_inner()).remove(tenant_id);
+        let mut sources = self.sources.write().unwrap_or_else(|e| e.into_inner());
+        let before = sources.len();
+        sources.retain(|(tenant, _), _| tenant != tenant_id);
+        before - sources.len()
+    }
+}
+
+impl HuntingCore {
+    /// Create or replace an ingestion source and start evaluating it. Events
+    /// still queued on a replaced source move to the new queue; its TCP
+    /// listener is stopped.
+    pub async fn configure_ingestion_source(&self, tenant_id: &str, config: IngestionSourceConfig) -> Result<IngestionMetrics, String> {
+        config.validate()?;
+        let key = (tenant_id.to_string(), config.name.clone());
+        let queue = Arc::new(SourceQueue::new(tenant_id, config));
+        let mut sources = self.ingestion.sources.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(previous) = sources.remove(&key) {
+            let mut carried: VecDeque<_> = previous.queue.events().drain(..).collect();
+            let excess = carried.len().saturating_sub(queue.config.queue_capacity);
+            carried.drain(..excess);
+            *queue.events() = carried;
+        }
+        let evaluator = tokio::spawn(evaluate_source(queue.clone(), self.rules.clone(), self.ingestion.matches.clone(), self.suppression.clone()));
+        let metrics = queue.metrics(None);
+        sources.insert(key, SourceEntry { queue, evaluator, listener: None });
+        Ok(metrics)
+    }
+
+    pub fn remove_ingestion_source(&self, tenant_id: &str, name: &str) -> bool {
+        self.ingestion.sources.write().unwrap_or_else(|e| e.into_inner()).remove(&(tenant_id.to_string(), name.to_string())).is_some()
+    }
+
+    /// Parse and queue a batch of newline-delimited records
+    pub async fn ingest_event_batch(&self, tenant_id: &str, name: &str, data: &[u8]) -> Result<IngestReceipt, String> {
+        let queue = self.ingestion.queue(tenant_id, name)?;
+        Ok(queue.ingest(data).await)
+    }
+
+    /// Accept JSON lines for the source on `bind_addr`, replacing its
+    /// previous listener; returns the bound address
+    pub async fn start_ingestion_listener(&self, tenant_id: &str, name: &str, bind_addr: &str) -> Result<String, String> {
+        let queue = self.ingestion.queue(tenant_id, name)?;
+        let listener = TcpListener::bind(bind_addr).await.map_err(|e| format!("Failed to bind {}: {}", bind_addr, e))?;
+        let addr = listener.local_addr().map_err(|e| e.to_string())?;
+        let handle = tokio::spawn(listen(listener, queue.clone()));
+
+        let mut sources = self.ingestion.sources.write().unwrap_or_else(|e| e.into_inner());
+        match sources.get_mut(&(tenant_id.to_string(), name.to_string())) {
+            Some(entry) if Arc::ptr_eq(&entry.queue, &queue) => {
+                if let Some((_, previous)) = entry.listener.replace((addr, handle)) {
+                    previous.abort();
+                }
+                log::info!("Ingestion source {} of tenant {} listening on {}", name, tenant_id, addr);
+                Ok(addr.to_string())
+            }
+            _ => {
+                handle.abort();
+                Err(format!("Ingestion source {} was replaced while starting its listener", name))
+            }
+        }
+    }
+
+    pub fn ingestion_metrics(&self, tenant_id: &str) -> Vec<IngestionMetrics> {
+        self.ingestion.source_metrics(Some(tenant_id))
+    }
+
+    /// The tenant's continuous matches, newest first
+    pub fn continuous_matches(&self, tenant_id: &str, limit: Option<usize>) -> Vec<ContinuousMatch> {
+        self.ingestion
+            .matches
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(tenant_id)
+            .map(|kept| kept.iter().rev().take(limit.unwrap_or(100)).cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+#[napi]
+impl HuntingCoreNapi {
+    /// Create or replace a streaming ingestion source evaluated by continuous hunting
+    #[napi]
+    pub async fn configure_ingestion_source(&self, config_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let config: IngestionSourceConfig = serde_json::from_str(&config_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse ingestion source: {}", e)))?;
+        let name = config.name.clone();
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &name)?;
+
+        let metrics = self.inner.configure_ingestion_source(&tenant_id, config).await;
+        let metrics = self.audit.record(&actor, "configure_ingestion_source", &name, serde_json::json!({ "config": config_json }), metrics)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to configure ingestion source: {}", e)))?;
+
+        serde_json::to_string(&metrics)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize ingestion metrics: {}", e)))
+    }
+
+    #[napi]
+    pub async fn remove_ingestion_source(&self, name: String, auth_token: Option<String>) -> napi::Result<bool> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &name)?;
+        let removed = self.inner.remove_ingestion_source(&tenant_id, &name);
+        self.audit.record(&actor, "remove_ingestion_source", &name, serde_json::json!({}), Ok::<_, String>(removed))
+            .map_err(napi::Error::from_reason)
+    }
+
+    /// Queue a Buffer of newline-delimited records; resolves once every
+    /// record was queued, dropped or rejected per the source's overflow policy
+    #[napi]
+    pub async fn ingest_event_batch(&self, source: String, batch: Buffer, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let receipt = self.inner.ingest_event_batch(&tenant_id, &source, &batch).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to ingest events: {}", e)))?;
+        serde_json::to_string(&receipt)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize ingest receipt: {}", e)))
+    }
+
+    /// Open a TCP port feeding a tenant's source with JSON lines; binding
+    /// ports is reserved for platform administrators
+    #[napi]
+    pub async fn start_ingestion_listener(&self, source: String, bind_addr: String, tenant_id: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, &source)?;
+        let tenant_id = tenant_id.unwrap_or_else(tenancy::default_tenant);
+        let params = serde_json::json!({ "tenant_id": tenant_id, "bind_addr": bind_addr });
+
+        let bound = self.inner.start_ingestion_listener(&tenant_id, &source, &bind_addr).await;
+        self.audit.record(&actor, "start_ingestion_listener", &source, params, bound)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to start ingestion listener: {}", e)))
+    }
+
+    /// Throughput, drop and lag counters of the caller's ingestion sources
+    #[napi]
+    pub fn get_ingestion_metrics(&self, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        serde_json::to_string(&self.inner.ingestion_metrics(&tenant_id))
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize ingestion metrics: {}", e)))
+    }
+
+    #[napi]
+    pub fn get_continuous_matches(&self, limit: Option<u32>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        serde_json::to_string(&self.inner.continuous_matches(&tenant_id, limit.map(|l| l as usize)))
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize continuous matches: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+
+    fn config(name: &str, capacity: usize, overflow: OverflowPolicy) -> IngestionSourceConfig {
+        serde_json::from_value(serde_json::json!({ "name": name, "queue_capacity": capacity, "overflow": overflow })).unwrap()
+    }
+
+    async fn wait_for(core: &HuntingCore, tenant_id: &str, evaluated: u64) -> IngestionMetrics {
+        for _ in 0..300 {
+            let metrics = core.ingestion_metrics(tenant_id).remove(0);
+            if metrics.evaluated >= evaluated {
+                return metrics;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("events were not evaluated in time");
+    }
+
+    #[test]
+    fn records_parse_into_mapped_fields() {
+        let mut json = config("fw", 10, OverflowPolicy::default());
+        json.timestamp_field = Some("ts".to_string());
+        json.field_map = HashMap::from([("net.bytes".to_string(), "bytes_out".to_string())]);
+        let event = parse_record(&json, r#"{"ts": 1709287200, "net": {"bytes": 42}, "action": "allow"}"#).unwrap();
+        assert_eq!(event.timestamp.to_rfc3339(), "2024-03-01T10:00:00+00:00");
+        assert_eq!(event.fields["bytes_out"], 42);
+        assert!(!event.fields.contains_key("net.bytes"));
+        assert!(parse_record(&json, "[1, 2]").is_err());
+
+        let mut kv = json.clone();
+        kv.format = EventFormat::KeyValue;
+        let event = parse_record(&kv, r#"ts=2024-03-01T10:00:00Z user="svc \"backup\"" action=deny"#).unwrap();
+        assert_eq!(event.fields["user"], "svc \"backup\"");
+        assert_eq!(event.fields["action"], "deny");
+        assert_eq!(event.timestamp.to_rfc3339(), "2024-03-01T10:00:00+00:00");
+
+        let mut cef = json;
+        cef.format = EventFormat::Cef;
+        cef.field_map = HashMap::from([("dpt".to_string(), "destination_port".to_string())]);
+        let event = parse_record(&cef, r"<134>Mar  1 10:00:00 fw01 CEF:0|Acme|Firewall|2.1|100|Blocked \| outbound|7|src=10.0.0.5 dpt=4444 msg=path a\=b c").unwrap();
+        assert_eq!(event.fields["name"], "Blocked | outbound");
+        assert_eq!(event.fields["destination_port"], "4444");
+        assert_eq!(event.fields["msg"], "path a=b c");
+        assert!(parse_record(&cef, "CEF:0|too|short").is_err());
+    }
+
+    #[tokio::test]
+    async fn full_queues_apply_their_overflow_policy() {
+        let events = |n: u64| (0..n).map(|i| format!("{{\"seq\": {}}}", i)).collect::<Vec<_>>().join("\n");
+
+        let oldest = SourceQueue::new("default", config("a", 2, OverflowPolicy::DropOldest));
+        let receipt = oldest.ingest(format!("{}\nnot json", events(3)).as_bytes()).await;
+        assert_eq!((receipt.accepted, receipt.dropped, receipt.parse_errors), (3, 1, 1));
+        let kept: Vec<_> = oldest.take_batch().iter().map(|event| event.fields["seq"].clone()).collect();
+        assert_eq!(kept, [1, 2]);
+
+        let newest = SourceQueue::new("default", config("b", 2, OverflowPolicy::DropNewest));
+        let receipt = newest.ingest(events(3).as_bytes()).await;
+        assert_eq!((receipt.accepted, receipt.dropped), (2, 1));
+        assert_eq!(newest.take_batch()[1].fields["seq"], 1);
+
+        let blocking = Arc::new(SourceQueue::new("default", config("c", 1, OverflowPolicy::Block { timeout_ms: 50 })));
+        let receipt = blocking.ingest(events(2).as_bytes()).await;
+        assert_eq!((receipt.accepted, receipt.rejected), (1, 1));
+        let drainer = blocking.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            drainer.take_batch();
+            drainer.space.notify_waiters();
+        });
+        let receipt = blocking.ingest(events(1).as_bytes()).await;
+        assert_eq!(receipt.accepted, 1, "the producer waits for the consumer");
+        let metrics = blocking.metrics(None);
+        assert_eq!((metrics.received, metrics.rejected, metrics.high_watermark), (3, 1, 1));
+    }
+
+    #[tokio::test]
+    async fn streamed_events_are_evaluated_by_covering_rules() {
+        let core = HuntingCore::new().unwrap();
+        let mut flows = config("flows", 100, OverflowPolicy::Block { timeout_ms: 1_000 });
+        flows.source_type = Some(DataSourceType::NetFlow);
+        flows.field_map = HashMap::from([("bytes".to_string(), "bytes_out".to_string()), ("dport".to_string(), "destination_port".to_string())]);
+        core.configure_ingestion_source("acme", flows).await.unwrap();
+        assert!(core.ingest_event_batch("globex", "flows", b"{}").await.is_err());
+
+        let batch = b"{\"bytes\": 50000000, \"dport\": 4444, \"protocol\": \"TCP\", \"source_ip\": \"10.0.0.5\", \"destination_ip\": \"203.0.113.9\"}\n\
+                      {\"bytes\": 1200, \"dport\": 443, \"protocol\": \"TCP\"}\n";
+        let receipt = core.ingest_event_batch("acme", "flows", batch).await.unwrap();
+        assert_eq!(receipt.accepted, 2);
+        let metrics = wait_for(&core, "acme", 2).await;
+        assert_eq!((metrics.matches, metrics.queue_depth), (1, 0));
+
+        let matches = core.continuous_matches("acme", None);
+        assert_eq!(matches[0].rule_id, "data_exfiltration_detection");
+        let network = matches[0].hunt_match.context.network_context.as_ref().unwrap();
+        assert_eq!((network.destination_ip.as_str(), network.port), ("203.0.113.9", 4444));
+        assert!(core.continuous_matches("globex", None).is_empty());
+
+        let addr = core.start_ingestion_listener("acme", "flows", "127.0.0.1:0").await.unwrap();
+        let mut socket = tokio::net::TcpStream::connect(&addr).await.unwrap();
+        socket.write_all(b"{\"bytes\": 90000000, \"dport\": 8443, \"protocol\": \"TCP\"}\n").await.unwrap();
+        socket.shutdown().await.unwrap();
+        let metrics = wait_for(&core, "acme", 3).await;
+        assert_eq!(metrics.matches, 2);
+        assert_eq!(metrics.listener.as_deref(), Some(addr.as_str()));
+
+        assert!(core.remove_ingestion_source("acme", "flows"));
+        assert!(core.ingestion_metrics("acme").is_empty());
+    }
+}
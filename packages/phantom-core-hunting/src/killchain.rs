@@ -0,0 +1,675 @@
+//! Kill chain analysis
+//!
+//! ATT&CK technique evidence from hunt matches and sandbox analyses is kept
+//! for `retention`, tagged with the tenant it belongs to and the host it was
+//! seen on; analyses only read the caller's tenant. An analysis maps
+//! every technique to an ATT&CK tactic — the tactics, in matrix order, are
+//! the kill-chain phases — measures dwell time from the first to the latest
+//! evidence, lists the phases skipped between the earliest and the furthest
+//! phase reached, and predicts likely next techniques from the transition
+//! weights in `attack_adjacency.tsv`. The matrix is the one shared through
+//! `phantom_enterprise_standards::mitre`.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::OnceLock;
+use tokio::sync::RwLock;
+
+use crate::{AttackProgression, HuntingCore, HuntingCoreNapi, HuntingMatch, HuntingRule};
+use napi_derive::napi;
+use phantom_enterprise_standards::mitre::ENTERPRISE_MATRIX_TSV as ENTERPRISE_TSV;
+
+const ADJACENCY_TSV: &str = include_str!("attack_adjacency.tsv");
+
+/// Evidence older than this is dropped
+const DEFAULT_RETENTION_DAYS: i64 = 90;
+/// Upper bound on stored evidence; the oldest is dropped first
+const MAX_OBSERVATIONS: usize = 100_000;
+/// Next techniques reported per analysis
+const MAX_PREDICTIONS: usize = 5;
+/// Likelihood factor for a predicted technique that only belongs to phases
+/// the intrusion has already moved past
+const BACKTRACK_FACTOR: f64 = 0.5;
+/// Event fields naming the host a match was seen on
+const HOST_FIELDS: &[&str] = &["hostname", "host", "computer_name", "Computer"];
+
+#[derive(Debug)]
+struct Tactic {
+    tactic_id: String,
+    shortname: String,
+    name: String,
+}
+
+#[derive(Debug)]
+struct Technique {
+    technique_id: String,
+    name: String,
+    /// Indices into `AttackMatrix::tactics`, primary tactic first
+    tactics: Vec<usize>,
+}
+
+/// ATT&CK tactics, techniques and technique transitions
+#[derive(Debug)]
+struct AttackMatrix {
+    tactics: Vec<Tactic>,
+    techniques: HashMap<String, Technique>,
+    adjacency: HashMap<String, Vec<(String, f64)>>,
+}
+
+fn matrix() -> &'static AttackMatrix {
+    static MATRIX: OnceLock<AttackMatrix> = OnceLock::new();
+    MATRIX.get_or_init(|| AttackMatrix::parse(ENTERPRISE_TSV, ADJACENCY_TSV).expect("embedded ATT&CK dataset is valid"))
+}
+
+/// Canonical `T1234` / `T1234.001` form of a technique reference
+fn normalize_technique_id(reference: &str) -> Option<String> {
+    let reference = reference.trim();
+    let reference = reference.strip_prefix("attack.").unwrap_or(reference);
+    let (base, sub) = match reference.split_once(['.', '/']) {
+        Some((base, sub)) => (base, Some(sub)),
+        None => (reference, None),
+    };
+    let digits = base.strip_prefix(['T', 't'])?;
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    match sub {
+        None => Some(format!("T{}", digits)),
+        Some(sub) if sub.len() == 3 && sub.bytes().all(|b| b.is_ascii_digit()) => Some(format!("T{}.{}", digits, sub)),
+        Some(_) => None,
+    }
+}
+
+/// `T1055.001` -> `T1055`
+fn parent_id(technique_id: &str) -> &str {
+    technique_id.split('.').next().unwrap_or(technique_id)
+}
+
+impl AttackMatrix {
+    fn parse(matrix_tsv: &str, adjacency_tsv: &str) -> Result<Self, String> {
+        let mut matrix = AttackMatrix { tactics: Vec::new(), techniques: HashMap::new(), adjacency: HashMap::new() };
+        for (line_no, line) in data_rows(matrix_tsv) {
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 4 {
+                return Err(format!("mitre_enterprise.tsv line {}: expected 4 columns", line_no));
+            }
+            let (kind, id, name, extra) = (fields[0], fields[1], fields[2], fields[3]);
+            let tactics = match kind {
+                "tactic" => {
+                    matrix.tactics.push(Tactic { tactic_id: id.to_string(), shortname: extra.to_string(), name: name.to_string() });
+                    continue;
+                }
+                "technique" => extra
+                    .split(',')
+                    .map(|shortname| {
+                        matrix
+                            .tactics
+                            .iter()
+                            .position(|t| t.shortname == shortname)
+                            .ok_or_else(|| format!("mitre_enterprise.tsv line {}: unknown tactic {}", line_no, shortname))
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+                "sub-technique" => matrix
+                    .techniques
+                    .get(parent_id(id))
+                    .map(|parent| parent.tactics.clone())
+                    .ok_or_else(|| format!("mitre_enterprise.tsv line {}: {} has no parent technique", line_no, id))?,
+                other => return Err(format!("mitre_enterprise.tsv line {}: unknown row kind {}", line_no, other)),
+            };
+            matrix
+                .techniques
+                .insert(id.to_string(), Technique { technique_id: id.to_string(), name: name.to_string(), tactics });
+        }
+
+        for (line_no, line) in data_rows(adjacency_tsv) {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [from, to, weight] = fields[..] else {
+                return Err(format!("attack_adjacency.tsv line {}: expected 3 columns", line_no));
+            };
+            if let Some(unknown) = [from, to].into_iter().find(|id| !matrix.techniques.contains_key(*id)) {
+                return Err(format!("attack_adjacency.tsv line {}: unknown technique {}", line_no, unknown));
+            }
+            let weight: f64 = weight
+                .parse()
+                .ok()
+                .filter(|w| (0.0..=1.0).contains(w))
+                .ok_or_else(|| format!("attack_adjacency.tsv line {}: weight must be between 0 and 1", line_no))?;
+            matrix.adjacency.entry(from.to_string()).or_default().push((to.to_string(), weight));
+        }
+        Ok(matrix)
+    }
+
+    /// Look up a technique by id (any spelling `normalize_technique_id`
+    /// accepts) or by name
+    fn technique(&self, reference: &str) -> Option<&Technique> {
+        match normalize_technique_id(reference) {
+            Some(id) => self.techniques.get(&id),
+            None => self.techniques.values().find(|t| t.name.eq_ignore_ascii_case(reference.trim())),
+        }
+    }
+
+    /// Phase index of a tactic id, shortname or display name
+    fn tactic_index(&self, reference: &str) -> Option<usize> {
+        let reference = reference.trim();
+        let shortname = reference.to_ascii_lowercase().replace(['_', ' '], "-");
+        self.tactics.iter().position(|t| {
+            t.tactic_id.eq_ignore_ascii_case(reference) || t.shortname == shortname || t.name.eq_ignore_ascii_case(reference)
+        })
+    }
+}
+
+/// ATT&CK tactics in matrix order as `(tactic_id, name)`
+pub(crate) fn tactics() -> impl Iterator<Item = (&'static str, &'static str)> {
+    matrix().tactics.iter().map(|t| (t.tactic_id.as_str(), t.name.as_str()))
+}
+
+/// Matrix positions of a tactic reference, or of every tactic a technique
+/// reference belongs to; empty when neither resolves
+pub(crate) fn tactic_indices(reference: &str) -> Vec<usize> {
+    let matrix = matrix();
+    match matrix.tactic_index(reference) {
+        Some(index) => vec![index],
+        None => matrix.technique(reference).map(|t| t.tactics.clone()).unwrap_or_default(),
+    }
+}
+
+/// Non-comment lines with their 1-based line numbers
+fn data_rows(source: &str) -> impl Iterator<Item = (usize, &str)> {
+    source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(index, line)| (index + 1, line))
+}
+
+/// Where a technique observation came from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EvidenceSource {
+    HuntMatch { hunt_id: String, rule_id: String, match_id: String },
+    SandboxAnalysis { analysis_id: String, sample_id: String },
+}
+
+/// A technique seen at a point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TechniqueObservation {
+    /// Technique id or name as reported
+    pub technique: String,
+    /// Tactic the reporter attributed the technique to, used to pick a
+    /// phase for techniques that belong to several
+    #[serde(default)]
+    pub tactic: Option<String>,
+    pub observed_at: DateTime<Utc>,
+    #[serde(default)]
+    pub host: Option<String>,
+    pub source: EvidenceSource,
+    #[serde(default = "crate::tenancy::default_tenant")]
+    pub tenant_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseEvidence {
+    pub tactic_id: String,
+    pub phase: String,
+    pub techniques: Vec<String>,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub evidence_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TechniquePrediction {
+    pub technique_id: String,
+    pub technique_name: String,
+    pub phase: String,
+    pub likelihood: f64,
+    /// Observed techniques that lead to this one
+    pub preceded_by: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KillChainAnalysis {
+    pub hosts: Vec<String>,
+    /// Phases with evidence, in kill-chain order
+    pub phases: Vec<PhaseEvidence>,
+    /// Phase of the most recent evidence
+    pub current_phase: Option<String>,
+    /// Latest phase in the kill chain with evidence
+    pub furthest_phase: Option<String>,
+    /// Phases without evidence between the earliest and the furthest phase
+    pub missing_phases: Vec<String>,
+    pub potential_next_phases: Vec<String>,
+    pub predicted_techniques: Vec<TechniquePrediction>,
+    pub first_evidence: Option<DateTime<Utc>>,
+    pub latest_evidence: Option<DateTime<Utc>>,
+    pub dwell_time_seconds: i64,
+    /// Phases reached per day of dwell time
+    pub progression_speed: f64,
+    pub evidence_count: usize,
+    /// Techniques that could not be placed in the matrix
+    pub unmapped_techniques: Vec<String>,
+}
+
+impl KillChainAnalysis {
+    pub fn to_attack_progression(&self) -> AttackProgression {
+        let current = self.current_phase.clone();
+        AttackProgression {
+            current_phase: current.clone().unwrap_or_else(|| "Unknown".to_string()),
+            completed_phases: self
+                .phases
+                .iter()
+                .map(|p| p.phase.clone())
+                .filter(|phase| Some(phase) != current.as_ref())
+                .collect(),
+            potential_next_phases: self.potential_next_phases.clone(),
+            dwell_time: Duration::seconds(self.dwell_time_seconds),
+            progression_speed: self.progression_speed,
+            missing_phases: self.missing_phases.clone(),
+            predicted_techniques: self.predicted_techniques.iter().map(|p| p.technique_id.clone()).collect(),
+        }
+    }
+}
+
+/// Place `observations` on the kill chain
+pub fn analyze(observations: &[TechniqueObservation]) -> KillChainAnalysis {
+    let matrix = matrix();
+    let hosts: BTreeSet<String> = observations.iter().filter_map(|o| o.host.clone()).collect();
+
+    // (phase index, technique id if known, observation)
+    let mut placed = Vec::new();
+    let mut unmapped = BTreeSet::new();
+    for observation in observations {
+        let technique = matrix.technique(&observation.technique);
+        let hinted = observation.tactic.as_deref().and_then(|t| matrix.tactic_index(t));
+        let phase = match (technique, hinted) {
+            (Some(technique), Some(hint)) if technique.tactics.contains(&hint) => Some(hint),
+            (Some(technique), _) => technique.tactics.first().copied(),
+            (None, hint) => hint,
+        };
+        match phase {
+            Some(phase) => placed.push((phase, technique, observation)),
+            None => {
+                unmapped.insert(observation.technique.clone());
+            }
+        }
+    }
+
+    let mut phases: BTreeMap<usize, PhaseEvidence> = BTreeMap::new();
+    for (phase, technique, observation) in &placed {
+        let tactic = &matrix.tactics[*phase];
+        let evidence = phases.entry(*phase).or_insert_with(|| PhaseEvidence {
+            tactic_id: tactic.tactic_id.clone(),
+            phase: tactic.name.clone(),
+            techniques: Vec::new(),
+            first_seen: observation.observed_at,
+            last_seen: observation.observed_at,
+            evidence_count: 0,
+        });
+        let label = technique.map(|t| t.technique_id.clone()).unwrap_or_else(|| observation.technique.clone());
+        if !evidence.techniques.contains(&label) {
+            evidence.techniques.push(label);
+        }
+        evidence.first_seen = evidence.first_seen.min(observation.observed_at);
+        evidence.last_seen = evidence.last_seen.max(observation.observed_at);
+        evidence.evidence_count += 1;
+    }
+
+    let first_evidence = placed.iter().map(|(_, _, o)| o.observed_at).min();
+    let latest_evidence = placed.iter().map(|(_, _, o)| o.observed_at).max();
+    let current = placed.iter().map(|(phase, _, o)| (o.observed_at, *phase)).max().map(|(_, phase)| phase);
+    let earliest = phases.keys().next().copied();
+    let furthest = phases.keys().next_back().copied();
+
+    let missing_phases = match (earliest, furthest) {
+        (Some(earliest), Some(furthest)) => (earliest..furthest)
+            .filter(|index| !phases.contains_key(index))
+            .map(|index| matrix.tactics[index].name.clone())
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let dwell_time_seconds = match (first_evidence, latest_evidence) {
+        (Some(first), Some(latest)) => (latest - first).num_seconds(),
+        _ => 0,
+    };
+    let progression_speed = if dwell_time_seconds > 0 && phases.len() > 1 {
+        (phases.len() - 1) as f64 / (dwell_time_seconds as f64 / 86_400.0)
+    } else {
+        0.0
+    };
+
+    let observed: HashSet<&str> =
+        placed.iter().filter_map(|(_, technique, _)| technique.map(|t| parent_id(&t.technique_id))).collect();
+    let predicted_techniques = predict(matrix, &observed, current.unwrap_or_default());
+
+    let mut potential_next_phases = Vec::new();
+    for prediction in &predicted_techniques {
+        let already_seen = phases.values().any(|p| p.phase == prediction.phase);
+        if !already_seen && !potential_next_phases.contains(&prediction.phase) {
+            potential_next_phases.push(prediction.phase.clone());
+        }
+    }
+    if potential_next_phases.is_empty() {
+        if let Some(furthest) = furthest {
+            potential_next_phases.extend(matrix.tactics.get(furthest + 1).map(|t| t.name.clone()));
+        }
+    }
+
+    KillChainAnalysis {
+        hosts: hosts.into_iter().collect(),
+        current_phase: current.map(|index| matrix.tactics[index].name.clone()),
+        furthest_phase: furthest.map(|index| matrix.tactics[index].name.clone()),
+        phases: phases.into_values().collect(),
+        missing_phases,
+        potential_next_phases,
+        predicted_techniques,
+        first_evidence,
+        latest_evidence,
+        dwell_time_seconds,
+        progression_speed,
+        evidence_count: placed.len(),
+        unmapped_techniques: unmapped.into_iter().collect(),
+    }
+}
+
+/// Score techniques reachable from the observed ones. Several observed
+/// techniques leading to the same one combine as independent chances.
+fn predict(matrix: &AttackMatrix, observed: &HashSet<&str>, current_phase: usize) -> Vec<TechniquePrediction> {
+    let mut candidates: HashMap<&str, (f64, Vec<String>)> = HashMap::new();
+    for from in observed {
+        for (to, weight) in matrix.adjacency.get(*from).into_iter().flatten() {
+            if observed.contains(to.as_str()) {
+                continue;
+            }
+            let (miss, preceded_by) = candidates.entry(to.as_str()).or_insert((1.0, Vec::new()));
+            *miss *= 1.0 - weight;
+            preceded_by.push(from.to_string());
+        }
+    }
+
+    let mut predictions: Vec<TechniquePrediction> = candidates
+        .into_iter()
+        .filter_map(|(id, (miss, mut preceded_by))| {
+            let technique = matrix.techniques.get(id)?;
+            let phase = technique.tactics.iter().copied().find(|&t| t >= current_phase);
+            let likelihood = match phase {
+                Some(_) => 1.0 - miss,
+                None => (1.0 - miss) * BACKTRACK_FACTOR,
+            };
+            let phase = phase.or_else(|| technique.tactics.first().copied())?;
+            preceded_by.sort();
+            Some(TechniquePrediction {
+                technique_id: technique.technique_id.clone(),
+                technique_name: technique.name.clone(),
+                phase: matrix.tactics[phase].name.clone(),
+                likelihood,
+                preceded_by,
+            })
+        })
+        .collect();
+    predictions.sort_by(|a, b| b.likelihood.total_cmp(&a.likelihood).then_with(|| a.technique_id.cmp(&b.technique_id)));
+    predictions.truncate(MAX_PREDICTIONS);
+    predictions
+}
+
+/// Technique evidence kept across hunts
+pub struct KillChainTracker {
+    observations: RwLock<Vec<TechniqueObservation>>,
+    retention: Duration,
+}
+
+impl Default for KillChainTracker {
+    fn default() -> Self {
+        Self { observations: RwLock::new(Vec::new()), retention: Duration::days(DEFAULT_RETENTION_DAYS) }
+    }
+}
+
+impl KillChainTracker {
+    async fn record(&self, observations: impl IntoIterator<Item = TechniqueObservation>) -> usize {
+        let cutoff = Utc::now() - self.retention;
+        let mut stored = self.observations.write().await;
+        let before = stored.len();
+        stored.extend(observations.into_iter().filter(|o| o.observed_at >= cutoff));
+        let recorded = stored.len() - before;
+
+        stored.retain(|o| o.observed_at >= cutoff);
+        if stored.len() > MAX_OBSERVATIONS {
+            stored.sort_by_key(|o| o.observed_at);
+            let excess = stored.len() - MAX_OBSERVATIONS;
+            stored.drain(..excess);
+        }
+        recorded
+    }
+
+    /// Drop all evidence of `tenant_id`
+    pub(crate) async fn forget_tenant(&self, tenant_id: &str) -> usize {
+        let mut stored = self.observations.write().await;
+        let before = stored.len();
+        stored.retain(|o| o.tenant_id != tenant_id);
+        before - stored.len()
+    }
+
+    /// The tenant's evidence seen on any of `hosts` (everything when empty) since `since`
+    async fn select(&self, tenant_id: &str, hosts: &HashSet<String>, since: Option<DateTime<Utc>>) -> Vec<TechniqueObservation> {
+        self.observations
+            .read()
+            .await
+            .iter()
+            .filter(|o| o.tenant_id == tenant_id)
+            .filter(|o| hosts.is_empty() || o.host.as_ref().is_some_and(|h| hosts.contains(h)))
+            .filter(|o| since.is_none_or(|since| o.observed_at >= since))
+            .cloned()
+            .collect()
+    }
+}
+
+/// The parts of a sandbox core `SandboxAnalysis` the analyzer reads
+#[derive(Debug, Clone, Deserialize)]
+pub struct SandboxAnalysisTechniques {
+    pub analysis_id: String,
+    pub sample_info: SandboxSampleRef,
+    pub analysis_metadata: SandboxAnalysisWindow,
+    pub mitre_techniques: Vec<SandboxTechnique>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SandboxSampleRef {
+    pub sample_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SandboxAnalysisWindow {
+    pub analysis_start: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SandboxTechnique {
+    pub technique_id: String,
+    #[serde(default)]
+    pub tactic: Option<String>,
+}
+
+fn match_host(hunting_match: &HuntingMatch) -> Option<String> {
+    if let Some(system) = &hunting_match.context.system_context {
+        return Some(system.hostname.clone());
+    }
+    HOST_FIELDS
+        .iter()
+        .find_map(|field| hunting_match.event_data.get(*field).and_then(|v| v.as_str()))
+        .map(str::to_string)
+}
+
+/// Techniques evidenced by a match: the rule's mappings plus whatever the
+/// match's threat context names
+fn match_observations(tenant_id: &str, hunt_id: &str, rule: &HuntingRule, hunting_match: &HuntingMatch) -> Vec<TechniqueObservation> {
+    let host = match_host(hunting_match);
+    let observation = |technique: &str, tactic: Option<&str>| TechniqueObservation {
+        technique: technique.to_string(),
+        tactic: tactic.map(str::to_string),
+        observed_at: hunting_match.timestamp,
+        host: host.clone(),
+        source: EvidenceSource::HuntMatch {
+            hunt_id: hunt_id.to_string(),
+            rule_id: rule.id.clone(),
+            match_id: hunting_match.match_id.clone(),
+        },
+        tenant_id: tenant_id.to_string(),
+    };
+
+    let mut seen = HashSet::new();
+    let mut observations = Vec::new();
+    for mapping in &rule.mitre_techniques {
+        if seen.insert(mapping.technique_id.clone()) {
+            observations.push(observation(&mapping.technique_id, Some(&mapping.tactic)));
+        }
+    }
+    let context_techniques = hunting_match.context.threat_context.iter().flat_map(|c| &c.attack_techniques);
+    for technique in context_techniques {
+        if seen.insert(technique.clone()) {
+            observations.push(observation(technique, None));
+        }
+    }
+    observations
+}
+
+impl HuntingCore {
+    /// Store technique evidence from a hunt's matches
+    pub async fn record_hunt_techniques(&self, tenant_id: &str, hunt_id: &str, rule: &HuntingRule, matches: &[HuntingMatch]) -> usize {
+        let observations = matches.iter().flat_map(|m| match_observations(tenant_id, hunt_id, rule, m));
+        self.kill_chain.record(observations).await
+    }
+
+    /// Store the techniques a sandbox analysis mapped, as seen on `host` when
+    /// the sample came from one. Evidence without a host only shows up in
+    /// analyses that are not scoped to hosts.
+    pub async fn ingest_sandbox_techniques(&self, tenant_id: &str, analysis: &SandboxAnalysisTechniques, host: Option<String>) -> usize {
+        let observations = analysis.mitre_techniques.iter().map(|technique| TechniqueObservation {
+            technique: technique.technique_id.clone(),
+            tactic: technique.tactic.clone(),
+            observed_at: analysis.analysis_metadata.analysis_start,
+            host: host.clone(),
+            source: EvidenceSource::SandboxAnalysis {
+                analysis_id: analysis.analysis_id.clone(),
+                sample_id: analysis.sample_info.sample_id.clone(),
+            },
+            tenant_id: tenant_id.to_string(),
+        });
+        self.kill_chain.record(observations).await
+    }
+
+    /// Kill chain across stored evidence, optionally for one host and from
+    /// `since` on
+    pub async fn analyze_kill_chain(&self, tenant_id: &str, host: Option<&str>, since: Option<DateTime<Utc>>) -> KillChainAnalysis {
+        let hosts = host.map(|h| HashSet::from([h.to_string()])).unwrap_or_default();
+        analyze(&self.kill_chain.select(tenant_id, &hosts, since).await)
+    }
+
+    /// Progression across the tenant's evidence for the hosts `matches` were seen on
+    pub(crate) async fn attack_progression(&self, tenant_id: &str, matches: &[HuntingMatch]) -> AttackProgression {
+        let hosts: HashSet<String> = matches.iter().filter_map(match_host).collect();
+        analyze(&self.kill_chain.select(tenant_id, &hosts, None).await).to_attack_progression()
+    }
+}
+
+#[napi]
+impl HuntingCoreNapi {
+    /// Record the ATT&CK techniques of a sandbox analysis (the sandbox core's
+    /// analysis JSON) as kill-chain evidence, optionally tied to the host the
+    /// sample was collected from. Returns the number of techniques recorded.
+    #[napi]
+    pub async fn ingest_sandbox_techniques(&self, analysis_json: String, host: Option<String>, auth_token: Option<String>) -> napi::Result<u32> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let analysis: SandboxAnalysisTechniques = serde_json::from_str(&analysis_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse sandbox analysis: {}", e)))?;
+        Ok(self.inner.ingest_sandbox_techniques(&tenant_id, &analysis, host).await as u32)
+    }
+
+    /// Analyze attack progression across hunts and sandbox analyses
+    #[napi]
+    pub async fn analyze_kill_chain(&self, host: Option<String>, since: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let since = since
+            .map(|s| DateTime::parse_from_rfc3339(&s).map(|t| t.with_timezone(&Utc)))
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Invalid since timestamp: {}", e)))?;
+        let analysis = self.inner.analyze_kill_chain(&tenant_id, host.as_deref(), since).await;
+
+        serde_json::to_string(&analysis)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize kill chain analysis: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observation(technique: &str, tactic: Option<&str>, hours_ago: i64) -> TechniqueObservation {
+        TechniqueObservation {
+            technique: technique.to_string(),
+            tactic: tactic.map(str::to_string),
+            observed_at: Utc::now() - Duration::hours(hours_ago),
+            host: Some("ws-042".to_string()),
+            source: EvidenceSource::SandboxAnalysis { analysis_id: "a1".to_string(), sample_id: "s1".to_string() },
+            tenant_id: "default".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_embedded_datasets_parse() {
+        let matrix = matrix();
+        assert_eq!(matrix.tactics.len(), 14);
+        assert_eq!(matrix.technique("attack.t1021/001").map(|t| t.technique_id.as_str()), Some("T1021.001"));
+        assert!(matrix.adjacency["T1566"].iter().any(|(to, _)| to == "T1204"));
+    }
+
+    #[test]
+    fn test_progression_phases_dwell_and_predictions() {
+        let analysis = analyze(&[
+            observation("T1566.001", None, 72),
+            observation("T1204.002", None, 71),
+            observation("T1003.001", None, 24),
+            observation("T1078", Some("Privilege Escalation"), 2),
+            observation("not-a-technique", None, 1),
+        ]);
+
+        let phases: Vec<&str> = analysis.phases.iter().map(|p| p.phase.as_str()).collect();
+        assert_eq!(phases, ["Initial Access", "Execution", "Privilege Escalation", "Credential Access"]);
+        assert_eq!(analysis.current_phase.as_deref(), Some("Privilege Escalation"));
+        assert_eq!(analysis.furthest_phase.as_deref(), Some("Credential Access"));
+        assert_eq!(analysis.missing_phases, ["Persistence", "Defense Evasion"]);
+        assert_eq!(analysis.dwell_time_seconds, 70 * 3600);
+        assert!((analysis.progression_speed - 3.0 / (70.0 / 24.0)).abs() < 1e-9);
+        assert_eq!(analysis.unmapped_techniques, ["not-a-technique"]);
+
+        // Credential dumping and valid accounts both lead to remote services
+        let top = &analysis.predicted_techniques[0];
+        assert_eq!(top.technique_id, "T1021");
+        assert_eq!(top.preceded_by, ["T1003", "T1078"]);
+        assert!((top.likelihood - (1.0 - 0.3 * 0.4)).abs() < 1e-9);
+        assert!(analysis.predicted_techniques.iter().all(|p| p.technique_id != "T1204"));
+        assert_eq!(analysis.potential_next_phases[0], "Lateral Movement");
+    }
+
+    #[tokio::test]
+    async fn test_hunts_and_sandbox_evidence_accumulate() {
+        let core = HuntingCore::new().unwrap();
+        let analysis: SandboxAnalysisTechniques = serde_json::from_value(serde_json::json!({
+            "analysis_id": "analysis-1",
+            "sample_info": { "sample_id": "sample-1" },
+            "analysis_metadata": { "analysis_start": (Utc::now() - Duration::days(3)).to_rfc3339() },
+            "mitre_techniques": [{ "technique_id": "T1059.001", "tactic": "Execution", "confidence": 0.9 }],
+        }))
+        .unwrap();
+        assert_eq!(core.ingest_sandbox_techniques("default", &analysis, None).await, 1);
+
+        let rule_id = core.list_rules("default").await.unwrap()[0].id.clone();
+        let result = core.execute_hunt("default", &rule_id, None).await.unwrap();
+        assert_ne!(result.threat_assessment.attack_progression.current_phase, "Unknown");
+
+        let overall = core.analyze_kill_chain("default", None, None).await;
+        assert!(overall.phases.iter().any(|p| p.phase == "Execution"));
+        assert!(core.analyze_kill_chain("acme", None, None).await.phases.is_empty());
+        assert!(overall.dwell_time_seconds >= 3 * 86_400 - 60);
+    }
+}
@@ -13,6 +13,57 @@ use tokio::sync::RwLock;
 use std::sync::Arc;
 use regex::Regex;
 
+use crate::error::{CoreError, CoreResult};
+
+pub mod access;
+pub mod assets;
+pub mod audit;
+pub mod backtest;
+pub mod baseline;
+pub mod cancellation;
+pub mod checkpoints;
+pub mod conditions;
+pub mod connectors;
+pub mod correlation;
+pub mod dsl;
+pub mod enrichment;
+pub mod error;
+pub mod evtx;
+pub mod hypotheses;
+pub mod indexing;
+pub mod inference;
+pub mod ingestion;
+pub mod killchain;
+pub mod live_feed;
+pub mod metrics_history;
+pub mod netflow;
+pub mod objects;
+pub mod prometheus;
+#[cfg(feature = "phantom-enterprise-standards")]
+pub mod references;
+pub mod rule_tests;
+#[cfg(feature = "phantom-enterprise-standards")]
+pub mod rule_sync;
+pub mod scheduler;
+pub mod secrets;
+pub mod sessions;
+pub mod shadow;
+pub mod sigma;
+#[cfg(feature = "phantom-enterprise-standards")]
+pub mod status;
+pub mod suppression;
+pub mod syslog;
+pub mod sysmon;
+pub mod tenancy;
+pub mod timeline;
+pub mod tuning;
+
+// List pagination is shared with the other cores
+pub use phantom_enterprise_standards::pagination;
+
+use netflow::{FlowDecoder, FlowRecord};
+use scheduler::HuntScheduler;
+
 // Enterprise Threat Hunting Configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HuntingConfiguration {
@@ -265,6 +316,9 @@ pub struct EnterpriseHuntingFeatures {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HuntingRule {
     pub id: String,
+    /// Owning tenant; `None` for built-in rules shared by every tenant
+    #[serde(default)]
+    pub tenant_id: Option<String>,
     pub name: String,
     pub description: String,
     pub category: HuntingCategory,
@@ -278,6 +332,12 @@ pub struct HuntingRule {
     pub response_actions: Vec<ResponseAction>,
     pub metadata: HuntingRuleMetadata,
     pub performance_metrics: RulePerformanceMetrics,
+    /// Deadline after which a run of the rule stops with what it has found
+    #[serde(default)]
+    pub execution_timeout_secs: Option<u64>,
+    /// Events the rule should and should not match, checked by `test_rule`
+    #[serde(default)]
+    pub test_fixtures: Vec<rule_tests::RuleFixture>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -350,6 +410,7 @@ pub enum DataSourceType {
     ThreatIntelligence,
     Sandbox,
     SIEM,
+    NetFlow, // NetFlow v5/v9 and IPFIX flow exports
     Custom(String),
 }
 
@@ -378,6 +439,8 @@ pub enum DataFormat {
     LEEF, // Log Event Extended Format
     Syslog,
     WindowsEventXML,
+    NetFlow,
+    IPFIX,
     Custom(String),
 }
 
@@ -504,10 +567,25 @@ pub enum HuntingSeverity {
     Informational,
 }
 
+impl HuntingSeverity {
+    /// Risk score of a fully confident match
+    pub(crate) fn risk_weight(&self) -> f64 {
+        match self {
+            HuntingSeverity::Critical => 9.0,
+            HuntingSeverity::High => 7.5,
+            HuntingSeverity::Medium => 5.0,
+            HuntingSeverity::Low => 3.0,
+            HuntingSeverity::Informational => 1.0,
+        }
+    }
+}
+
 // Comprehensive Hunting Results
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HuntingResult {
     pub hunt_id: String,
+    #[serde(default = "tenancy::default_tenant")]
+    pub tenant_id: String,
     pub rule_id: String,
     pub hunt_name: String,
     pub execution_timestamp: DateTime<Utc>,
@@ -520,6 +598,15 @@ pub struct HuntingResult {
     pub recommendations: Vec<HuntingRecommendation>,
     pub false_positive_analysis: FalsePositiveAnalysis,
     pub hunt_metadata: HuntMetadata,
+    /// Connected data sources whose query failed; their rows are missing from `matches`
+    #[serde(default)]
+    pub source_errors: Vec<String>,
+    /// Matches dropped because an allowlist entry covers them
+    #[serde(default)]
+    pub suppressed_matches: u64,
+    /// Whether the hunt ran to the end or was cut short with partial results
+    #[serde(default)]
+    pub completion: cancellation::HuntCompletion,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -534,6 +621,11 @@ pub struct HuntingMatch {
     pub correlations: Vec<EventCorrelation>,
     pub enrichments: Vec<Enrichment>,
     pub validation_results: Vec<ValidationResult>,
+    /// Scores from loaded match models, keyed by model id
+    #[serde(default)]
+    pub ml_scores: HashMap<String, f64>,    /// Events a correlation rule matched on, oldest first
+    #[serde(default)]
+    pub contributing_events: Vec<correlation::ContributingEvent>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -781,6 +873,10 @@ pub struct AttackProgression {
     pub potential_next_phases: Vec<String>,
     pub dwell_time: Duration,
     pub progression_speed: f64,
+    #[serde(default)]
+    pub missing_phases: Vec<String>,
+    #[serde(default)]
+    pub predicted_techniques: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -836,11 +932,20 @@ pub struct FalsePositiveAnalysis {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TuningRecommendation {
+    /// Id to accept the recommendation with through `apply_tuning`
+    #[serde(default)]
+    pub recommendation_id: String,
     pub parameter: String,
     pub current_value: serde_json::Value,
     pub recommended_value: serde_json::Value,
     pub expected_improvement: f64,
     pub rationale: String,
+    /// False-positive pattern the recommendation was derived from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<tuning::FalsePositivePattern>,
+    /// Ready-to-apply change; advisory recommendations have none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch: Option<tuning::RulePatch>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -877,7 +982,39 @@ pub struct HuntingCore {
     hunt_results: Arc<RwLock<HashMap<String, HuntingResult>>>,
     ml_models: Arc<RwLock<HashMap<String, MLModel>>>,
     data_sources: Arc<RwLock<HashMap<String, DataSource>>>,
-    performance_metrics: Arc<RwLock<HuntingPerformanceMetrics>>,
+    /// Hunt metrics per tenant
+    performance_metrics: Arc<RwLock<HashMap<String, HuntingPerformanceMetrics>>>,
+    flow_records: netflow::FlowStore,
+    /// NetFlow v9/IPFIX template caches per tenant
+    flow_decoders: Arc<RwLock<HashMap<String, FlowDecoder>>>,
+    scheduler: Arc<HuntScheduler>,
+    inference: Arc<inference::ModelRegistry>,
+    baseline_learner: Arc<baseline::BaselineLearner>,
+    #[cfg(feature = "phantom-enterprise-standards")]
+    enrichment: Arc<enrichment::EnrichmentState>,
+    kill_chain: Arc<killchain::KillChainTracker>,
+    #[cfg(feature = "phantom-enterprise-standards")]
+    syslog: Arc<syslog::SyslogState>,
+    #[cfg(feature = "phantom-enterprise-standards")]
+    live_feed: Arc<live_feed::LiveFeedState>,
+    #[cfg(feature = "phantom-enterprise-standards")]
+    indexing: Arc<indexing::IndexingState>,
+    sessions: Arc<sessions::SessionState>,
+    connectors: Arc<connectors::ConnectorState>,
+    ingestion: Arc<ingestion::IngestionState>,
+    metrics_history: Arc<metrics_history::MetricsHistoryState>,
+    prometheus: Arc<prometheus::PrometheusState>,
+    suppression: Arc<suppression::SuppressionState>,
+    tuning: Arc<tuning::TuningState>,
+    secrets: Arc<secrets::SecretStore>,
+    assets: Arc<assets::AssetState>,
+    checkpoints: Arc<checkpoints::CheckpointState>,
+    running_hunts: Arc<cancellation::RunningHunts>,
+    shadow: Arc<shadow::ShadowState>,
+    hypotheses: Arc<hypotheses::HypothesisState>,
+    backtest: Arc<backtest::BacktestState>,
+    #[cfg(feature = "phantom-enterprise-standards")]
+    rule_sync: Arc<rule_sync::RuleSyncState>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -895,13 +1032,32 @@ pub struct HuntingPerformanceMetrics {
     pub last_reset: DateTime<Utc>,
 }
 
+impl HuntingPerformanceMetrics {
+    fn new() -> Self {
+        Self {
+            total_hunts_executed: 0,
+            successful_hunts: 0,
+            failed_hunts: 0,
+            average_execution_time: 0.0,
+            events_processed_per_second: 0.0,
+            detection_rate: 0.0,
+            false_positive_rate: 0.0,
+            analyst_efficiency: 0.0,
+            cost_per_detection: 0.0,
+            uptime_percentage: 100.0,
+            last_reset: Utc::now(),
+        }
+    }
+}
+
 impl HuntingCore {
-    pub fn new() -> Result<Self, String> {
+    pub fn new() -> CoreResult<Self> {
         let config = Self::default_config();
         let rules = Self::initialize_default_rules()?;
         let baselines = Self::initialize_baselines()?;
         let ml_models = Self::initialize_ml_models()?;
         let data_sources = Self::initialize_data_sources()?;
+        let flow_records: netflow::FlowStore = Arc::new(RwLock::new(HashMap::new()));
 
         Ok(Self {
             config,
@@ -910,19 +1066,37 @@ impl HuntingCore {
             hunt_results: Arc::new(RwLock::new(HashMap::new())),
             ml_models: Arc::new(RwLock::new(ml_models)),
             data_sources: Arc::new(RwLock::new(data_sources)),
-            performance_metrics: Arc::new(RwLock::new(HuntingPerformanceMetrics {
-                total_hunts_executed: 0,
-                successful_hunts: 0,
-                failed_hunts: 0,
-                average_execution_time: 0.0,
-                events_processed_per_second: 0.0,
-                detection_rate: 0.0,
-                false_positive_rate: 0.0,
-                analyst_efficiency: 0.0,
-                cost_per_detection: 0.0,
-                uptime_percentage: 100.0,
-                last_reset: Utc::now(),
-            })),
+            performance_metrics: Arc::new(RwLock::new(HashMap::new())),
+            flow_records: flow_records.clone(),
+            flow_decoders: Arc::new(RwLock::new(HashMap::new())),
+            scheduler: Arc::new(HuntScheduler::default()),
+            inference: Arc::new(inference::ModelRegistry::default()),
+            baseline_learner: Arc::new(baseline::BaselineLearner::default()),
+            #[cfg(feature = "phantom-enterprise-standards")]
+            enrichment: Arc::new(enrichment::EnrichmentState::default()),
+            kill_chain: Arc::new(killchain::KillChainTracker::default()),
+            #[cfg(feature = "phantom-enterprise-standards")]
+            syslog: Arc::new(syslog::SyslogState::default()),
+            #[cfg(feature = "phantom-enterprise-standards")]
+            live_feed: Arc::new(live_feed::LiveFeedState::default()),
+            #[cfg(feature = "phantom-enterprise-standards")]
+            indexing: Arc::new(indexing::IndexingState::default()),
+            sessions: Arc::new(sessions::SessionState::new(flow_records)),
+            connectors: Arc::new(connectors::ConnectorState::default()),
+            ingestion: Arc::new(ingestion::IngestionState::default()),
+            metrics_history: Arc::new(metrics_history::MetricsHistoryState::default()),
+            prometheus: Arc::new(prometheus::PrometheusState::default()),
+            suppression: Arc::new(suppression::SuppressionState::default()),
+            tuning: Arc::new(tuning::TuningState::default()),
+            secrets: Arc::new(secrets::SecretStore::new(Arc::new(secrets::EnvKey::new(secrets::MASTER_KEY_ENV)))),
+            assets: Arc::new(assets::AssetState::default()),
+            checkpoints: Arc::new(checkpoints::CheckpointState::default()),
+            running_hunts: Arc::new(cancellation::RunningHunts::default()),
+            shadow: Arc::new(shadow::ShadowState::default()),
+            hypotheses: Arc::new(hypotheses::HypothesisState::default()),
+            backtest: Arc::new(backtest::BacktestState::default()),
+            #[cfg(feature = "phantom-enterprise-standards")]
+            rule_sync: Arc::new(rule_sync::RuleSyncState::default()),
         })
     }
 
@@ -937,6 +1111,7 @@ impl HuntingCore {
                 "proxy_logs".to_string(),
                 "active_directory".to_string(),
                 "cloud_audit_logs".to_string(),
+                "netflow".to_string(),
             ],
             ml_models: vec![],
             behavioral_baselines: BehavioralBaselines {
@@ -980,11 +1155,12 @@ impl HuntingCore {
         }
     }
 
-    fn initialize_default_rules() -> Result<HashMap<String, HuntingRule>, String> {
+    fn initialize_default_rules() -> std::result::Result<HashMap<String, HuntingRule>, String> {
         let mut rules = HashMap::new();
 
         rules.insert("apt_lateral_movement".to_string(), HuntingRule {
             id: "apt_lateral_movement".to_string(),
+            tenant_id: None,
             name: "APT Lateral Movement Detection".to_string(),
             description: "Detects potential APT lateral movement using administrative tools".to_string(),
             category: HuntingCategory::LateralMovement,
@@ -1177,11 +1353,14 @@ impl HuntingCore {
                 effectiveness_score: 0.0,
                 last_updated: Utc::now(),
             },
+            execution_timeout_secs: None,
+            test_fixtures: Vec::new(),
         });
 
         // Add more sophisticated hunting rules...
         rules.insert("data_exfiltration_detection".to_string(), HuntingRule {
             id: "data_exfiltration_detection".to_string(),
+            tenant_id: None,
             name: "Data Exfiltration via Network".to_string(),
             description: "Detects potential data exfiltration through unusual network patterns".to_string(),
             category: HuntingCategory::Exfiltration,
@@ -1197,7 +1376,28 @@ impl HuntingCore {
                 filters: vec![],
                 aggregations: vec![],
             },
-            data_sources: vec![],
+            data_sources: vec![
+                DataSource {
+                    source_id: "netflow".to_string(),
+                    source_name: "NetFlow/IPFIX Flows".to_string(),
+                    source_type: DataSourceType::NetFlow,
+                    connection_details: ConnectionDetails {
+                        endpoint: "udp://0.0.0.0:2055".to_string(),
+                        authentication: AuthenticationConfig {
+                            auth_type: "none".to_string(),
+                            credentials: HashMap::new(),
+                            token_refresh: None,
+                        },
+                        connection_pooling: false,
+                        timeout_seconds: 30,
+                        retry_attempts: 0,
+                    },
+                    data_format: DataFormat::IPFIX,
+                    update_frequency: Duration::seconds(60),
+                    retention_period: Duration::days(30),
+                    reliability_score: 0.9,
+                },
+            ],
             mitre_techniques: vec![
                 MITREMapping {
                     technique_id: "T1041".to_string(),
@@ -1210,7 +1410,32 @@ impl HuntingCore {
             ],
             detection_logic: DetectionLogic {
                 rule_type: RuleType::Statistical,
-                conditions: vec![],
+                conditions: vec![
+                    DetectionCondition {
+                        condition_id: "large_outbound_transfer".to_string(),
+                        field: "bytes_out".to_string(),
+                        operator: "gt".to_string(),
+                        value: serde_json::json!(10000000),
+                        weight: 0.6,
+                        required: true,
+                    },
+                    DetectionCondition {
+                        condition_id: "non_standard_port".to_string(),
+                        field: "destination_port".to_string(),
+                        operator: "not_in".to_string(),
+                        value: serde_json::json!([80, 443, 53]),
+                        weight: 0.3,
+                        required: true,
+                    },
+                    DetectionCondition {
+                        condition_id: "tcp_transport".to_string(),
+                        field: "protocol".to_string(),
+                        operator: "equals".to_string(),
+                        value: serde_json::json!("TCP"),
+                        weight: 0.1,
+                        required: false,
+                    },
+                ],
                 correlation_rules: vec![],
                 time_windows: vec![],
                 statistical_models: vec![
@@ -1264,6 +1489,26 @@ impl HuntingCore {
                 effectiveness_score: 0.0,
                 last_updated: Utc::now(),
             },
+            execution_timeout_secs: None,
+            test_fixtures: serde_json::from_value(serde_json::json!([
+                {
+                    "name": "large upload to non-standard port",
+                    "expectation": "should_match",
+                    "events": [
+                        { "bytes_out": 25000000, "destination_port": 8443, "protocol": "TCP" },
+                        { "bytes_out": 150000000, "destination_port": 22 }
+                    ]
+                },
+                {
+                    "name": "web and small transfers",
+                    "expectation": "should_not_match",
+                    "events": [
+                        { "bytes_out": 25000000, "destination_port": 443, "protocol": "TCP" },
+                        { "bytes_out": 4096, "destination_port": 8443, "protocol": "TCP" }
+                    ]
+                }
+            ]))
+            .map_err(|e| format!("Invalid built-in rule fixtures: {}", e))?,
         });
 
         Ok(rules)
@@ -1380,39 +1625,90 @@ impl HuntingCore {
             reliability_score: 0.98,
         });
 
+        sources.insert("netflow".to_string(), DataSource {
+            source_id: "netflow".to_string(),
+            source_name: "NetFlow/IPFIX Flows".to_string(),
+            source_type: DataSourceType::NetFlow,
+            connection_details: ConnectionDetails {
+                endpoint: "udp://0.0.0.0:2055".to_string(),
+                authentication: AuthenticationConfig {
+                    auth_type: "none".to_string(),
+                    credentials: HashMap::new(),
+                    token_refresh: None,
+                },
+                connection_pooling: false,
+                timeout_seconds: 30,
+                retry_attempts: 0,
+            },
+            data_format: DataFormat::IPFIX,
+            update_frequency: Duration::seconds(60),
+            retention_period: Duration::days(30),
+            reliability_score: 0.9,
+        });
+
         Ok(sources)
     }
 
-    pub async fn execute_hunt(&self, rule_id: &str, data_context: Option<HashMap<String, serde_json::Value>>) -> Result<HuntingResult, String> {
+    /// Run `rule_id` for `tenant_id`, which sees its own rules and the built-in ones
+    pub async fn execute_hunt(&self, tenant_id: &str, rule_id: &str, data_context: Option<HashMap<String, serde_json::Value>>) -> CoreResult<HuntingResult> {
+        self.run_hunt(tenant_id, rule_id, data_context, cancellation::HuntOptions::default()).await
+    }
+
+    /// Run `rule_id` over only the rows its connected sources received since
+    /// the rule's last incremental run, then advance its checkpoints
+    pub async fn execute_incremental_hunt(&self, tenant_id: &str, rule_id: &str, data_context: Option<HashMap<String, serde_json::Value>>) -> CoreResult<HuntingResult> {
+        let options = cancellation::HuntOptions { incremental: true, ..cancellation::HuntOptions::default() };
+        self.run_hunt(tenant_id, rule_id, data_context, options).await
+    }
+
+    async fn run_hunt(&self, tenant_id: &str, rule_id: &str, data_context: Option<HashMap<String, serde_json::Value>>, options: cancellation::HuntOptions) -> CoreResult<HuntingResult> {
         let start_time = std::time::Instant::now();
-        let hunt_id = Uuid::new_v4().to_string();
+        let hunt_id = match options.hunt_id {
+            Some(hunt_id) if self.hunt_results.read().await.contains_key(&hunt_id) => {
+                return Err(CoreError::validation(format!("Hunt {} already exists", hunt_id)));
+            }
+            Some(hunt_id) => hunt_id,
+            None => format!("hunt_{}", Uuid::new_v4().simple()),
+        };
 
         // Get rule
         let rule = {
             let rules = self.rules.read().await;
-            rules.get(rule_id).cloned()
-                .ok_or_else(|| format!("Rule {} not found", rule_id))?
+            rules.get(rule_id).filter(|rule| tenancy::rule_visible(rule, tenant_id)).cloned()
+                .ok_or_else(|| CoreError::not_found(format!("Rule {} not found", rule_id)))?
         };
 
+        let timeout = options.timeout_ms.map(std::time::Duration::from_millis)
+            .or(rule.execution_timeout_secs.map(std::time::Duration::from_secs));
+        let running = self.running_hunts.start(&hunt_id, tenant_id, rule_id, timeout)?;
+
         // Execute the hunt logic
-        let execution_result = self.execute_hunting_logic(&rule, data_context.clone()).await?;
+        let checkpoint_tenant = options.incremental.then_some(tenant_id);
+        let mut execution_result = self.execute_hunting_logic(tenant_id, &rule, data_context.clone(), checkpoint_tenant, running.abort()).await?;
+        let completion = running.abort().outcome().unwrap_or_default();
+        let suppressed_matches = self.suppression.suppress_matches(tenant_id, &mut execution_result.matches);
+        self.assets.contextualize_matches(tenant_id, &mut execution_result.matches);
         
         // Enrich results with context
-        let enriched_matches = self.enrich_hunting_matches(execution_result.matches, &rule).await?;
+        let mut enriched_matches = self.enrich_hunting_matches(execution_result.matches, &rule).await?;
+        self.apply_match_models(&mut enriched_matches).await;
         
+        self.record_hunt_techniques(tenant_id, &hunt_id, &rule, &enriched_matches).await;
+
         // Perform threat assessment
-        let threat_assessment = self.assess_threats(&enriched_matches, &rule).await;
+        let threat_assessment = self.assess_threats(tenant_id, &enriched_matches, &rule).await;
         
         // Generate recommendations
         let recommendations = self.generate_recommendations(&enriched_matches, &threat_assessment, &rule).await;
         
         // Analyze false positives
-        let false_positive_analysis = self.analyze_false_positives(&enriched_matches, &rule).await;
+        let false_positive_analysis = self.analyze_false_positives(tenant_id, &enriched_matches, &rule).await;
         
         let execution_duration = Duration::milliseconds(start_time.elapsed().as_millis() as i64);
 
         let hunt_result = HuntingResult {
-            hunt_id,
+            hunt_id: hunt_id.clone(),
+            tenant_id: tenant_id.to_string(),
             rule_id: rule_id.to_string(),
             hunt_name: rule.name.clone(),
             execution_timestamp: Utc::now(),
@@ -1442,6 +1738,9 @@ impl HuntingCore {
                     detection_coverage: 0.87,
                 },
             },
+            source_errors: execution_result.source_errors,
+            suppressed_matches,
+            completion,
         };
 
         // Store the result
@@ -1452,40 +1751,119 @@ impl HuntingCore {
 
         // Update performance metrics
         self.update_performance_metrics(&hunt_result).await;
+        self.forward_hunt_matches(&hunt_result, &rule);
+        self.publish_hunt_matches(&hunt_result, &rule);
+        self.index_hunt_result(&hunt_result, &rule);
 
         Ok(hunt_result)
     }
 
-    async fn execute_hunting_logic(&self, rule: &HuntingRule, _data_context: Option<HashMap<String, serde_json::Value>>) -> Result<HuntingExecutionResult, String> {
-        // Simulate comprehensive hunting logic
-        let mut matches = Vec::new();
-        let events_processed = 10000;
-
-        // Generate realistic hunting matches based on rule
-        match rule.category {
-            HuntingCategory::LateralMovement => {
-                for i in 0..5 {
-                    matches.push(self.generate_lateral_movement_match(i).await);
-                }
-            },
-            HuntingCategory::Exfiltration => {
-                for i in 0..3 {
-                    matches.push(self.generate_exfiltration_match(i).await);
+    /// Flow sources are read from `tenant_id`'s flows. `checkpoint_tenant` is
+    /// set for incremental runs, whose connected sources are read from that
+    /// tenant's checkpoints; `abort` stops the connector queries early
+    async fn execute_hunting_logic(&self, tenant_id: &str, rule: &HuntingRule, _data_context: Option<HashMap<String, serde_json::Value>>, checkpoint_tenant: Option<&str>, abort: &cancellation::HuntAbort) -> Result<HuntingExecutionResult, String> {
+        // Rules whose sources have a connector query them; the rest use simulated events
+        let connected = self.connected_sources(rule).await;
+        let (mut matches, mut events_processed, source_errors) = if !connected.is_empty() {
+            let hunt = self.hunt_connected_sources(rule, connected, checkpoint_tenant, None, abort).await;
+            (hunt.matches, hunt.events_processed, hunt.errors)
+        } else {
+            let mut matches = Vec::new();
+            match rule.category {
+                HuntingCategory::LateralMovement => {
+                    for i in 0..5 {
+                        matches.push(self.generate_lateral_movement_match(i).await);
+                    }
+                },
+                HuntingCategory::Exfiltration => {
+                    for i in 0..3 {
+                        matches.push(self.generate_exfiltration_match(i).await);
+                    }
+                },
+                _ => {
+                    for i in 0..2 {
+                        matches.push(self.generate_generic_match(i).await);
+                    }
                 }
-            },
-            _ => {
-                for i in 0..2 {
-                    matches.push(self.generate_generic_match(i).await);
+            }
+            (matches, 10000, Vec::new())
+        };
+
+        // Rules that target flow sources are evaluated against ingested flows alongside log events
+        if rule.data_sources.iter().any(|ds| matches!(ds.source_type, DataSourceType::NetFlow)) {
+            let store = self.flow_records.read().await;
+            let flows = store.get(tenant_id).map_or(&[][..], Vec::as_slice);
+            events_processed += flows.len() as u64;
+            for flow in flows {
+                let event_data = flow.to_event_data();
+                if let Some(confidence) = conditions::evaluate_conditions(&event_data, &rule.detection_logic.conditions) {
+                    matches.push(self.generate_flow_match(flow, event_data, confidence, rule));
                 }
             }
         }
 
+        // Correlation rules run over the events the hunt matched
+        let correlated = correlation::correlate_matches(rule, &matches);
+        matches.extend(correlated);
+
         Ok(HuntingExecutionResult {
             matches,
             events_processed,
+            source_errors,
         })
     }
 
+    fn generate_flow_match(&self, flow: &FlowRecord, event_data: HashMap<String, serde_json::Value>, confidence: f64, rule: &HuntingRule) -> HuntingMatch {
+        let severity_score = rule.severity.risk_weight();
+
+        HuntingMatch {
+            match_id: Uuid::new_v4().to_string(),
+            timestamp: flow.flow_end,
+            source: format!("Flow Export ({})", flow.exporter),
+            event_data,
+            confidence_score: confidence,
+            risk_score: severity_score * confidence,
+            context: MatchContext {
+                user_context: None,
+                system_context: None,
+                network_context: Some(NetworkContext {
+                    source_ip: flow.source_ip.to_string(),
+                    destination_ip: flow.destination_ip.to_string(),
+                    protocol: flow.protocol_name(),
+                    port: flow.destination_port,
+                    geographic_info: GeographicInfo {
+                        country: "Unknown".to_string(),
+                        region: "Unknown".to_string(),
+                        city: "Unknown".to_string(),
+                        isp: "Unknown".to_string(),
+                        is_tor_exit_node: false,
+                        is_datacenter: false,
+                    },
+                    reputation_info: ReputationInfo {
+                        reputation_score: 50.0,
+                        threat_categories: vec![],
+                        first_seen: Some(flow.flow_start),
+                        last_seen: Some(flow.flow_end),
+                        confidence: 0.5,
+                    },
+                }),
+                temporal_context: TemporalContext {
+                    event_frequency: 0.0,
+                    time_since_last_occurrence: Utc::now() - flow.flow_end,
+                    seasonal_patterns: vec![],
+                    day_of_week_pattern: flow.flow_start.format("%A").to_string(),
+                    hour_of_day_pattern: flow.flow_start.format("%H:00").to_string(),
+                },
+                threat_context: None,
+            },
+            correlations: vec![],
+            enrichments: vec![],
+            validation_results: vec![],
+            ml_scores: HashMap::new(),
+            contributing_events: Vec::new(),
+        }
+    }
+
     async fn generate_lateral_movement_match(&self, index: usize) -> HuntingMatch {
         let mut event_data = HashMap::new();
         event_data.insert("EventID".to_string(), serde_json::json!("4624"));
@@ -1593,6 +1971,8 @@ impl HuntingCore {
                     recommendations: vec!["Verify with user".to_string(), "Check for compromise".to_string()],
                 },
             ],
+            ml_scores: HashMap::new(),
+            contributing_events: Vec::new(),
         }
     }
 
@@ -1660,6 +2040,8 @@ impl HuntingCore {
             correlations: vec![],
             enrichments: vec![],
             validation_results: vec![],
+            ml_scores: HashMap::new(),
+            contributing_events: Vec::new(),
         }
     }
 
@@ -1690,15 +2072,17 @@ impl HuntingCore {
             correlations: vec![],
             enrichments: vec![],
             validation_results: vec![],
+            ml_scores: HashMap::new(),
+            contributing_events: Vec::new(),
         }
     }
 
-    async fn enrich_hunting_matches(&self, matches: Vec<HuntingMatch>, _rule: &HuntingRule) -> Result<Vec<HuntingMatch>, String> {
-        // In a real implementation, this would perform comprehensive enrichment
+    async fn enrich_hunting_matches(&self, mut matches: Vec<HuntingMatch>, _rule: &HuntingRule) -> Result<Vec<HuntingMatch>, String> {
+        self.enrich_match_observables(&mut matches).await;
         Ok(matches)
     }
 
-    async fn assess_threats(&self, matches: &[HuntingMatch], _rule: &HuntingRule) -> ThreatAssessment {
+    async fn assess_threats(&self, tenant_id: &str, matches: &[HuntingMatch], _rule: &HuntingRule) -> ThreatAssessment {
         let overall_threat_score = matches.iter().map(|m| m.risk_score).sum::<f64>() / matches.len() as f64;
         
         let threat_level = match overall_threat_score {
@@ -1721,13 +2105,7 @@ impl HuntingCore {
                     evidence: vec!["Lateral movement patterns".to_string()],
                 },
             ],
-            attack_progression: AttackProgression {
-                current_phase: "Lateral Movement".to_string(),
-                completed_phases: vec!["Initial Access".to_string(), "Execution".to_string()],
-                potential_next_phases: vec!["Privilege Escalation".to_string(), "Exfiltration".to_string()],
-                dwell_time: Duration::hours(72),
-                progression_speed: 0.7,
-            },
+            attack_progression: self.attack_progression(tenant_id, matches).await,
             impact_assessment: ImpactAssessment {
                 business_impact: 8.5,
                 operational_impact: 7.0,
@@ -1771,7 +2149,12 @@ impl HuntingCore {
         ]
     }
 
-    async fn analyze_false_positives(&self, _matches: &[HuntingMatch], _rule: &HuntingRule) -> FalsePositiveAnalysis {
+    async fn analyze_false_positives(&self, tenant_id: &str, _matches: &[HuntingMatch], rule: &HuntingRule) -> FalsePositiveAnalysis {
+        // Analyst dispositions beat the generic estimate once there are any
+        let dispositions = self.tuning.rule_dispositions(tenant_id, &rule.id).await;
+        if !dispositions.is_empty() {
+            return tuning::false_positive_analysis(rule, &dispositions);
+        }
         FalsePositiveAnalysis {
             false_positive_rate: 0.15,
             common_false_positive_patterns: vec![
@@ -1784,11 +2167,14 @@ impl HuntingCore {
             ],
             tuning_recommendations: vec![
                 TuningRecommendation {
+                    recommendation_id: String::new(),
                     parameter: "confidence_threshold".to_string(),
                     current_value: serde_json::json!(0.8),
                     recommended_value: serde_json::json!(0.85),
                     expected_improvement: 0.1,
                     rationale: "Reduce false positives while maintaining detection capability".to_string(),
+                    pattern: None,
+                    patch: None,
                 },
             ],
         }
@@ -1825,7 +2211,8 @@ impl HuntingCore {
     }
 
     async fn update_performance_metrics(&self, hunt_result: &HuntingResult) {
-        let mut metrics = self.performance_metrics.write().await;
+        let mut tenants = self.performance_metrics.write().await;
+        let metrics = tenants.entry(hunt_result.tenant_id.clone()).or_insert_with(HuntingPerformanceMetrics::new);
         metrics.total_hunts_executed += 1;
         
         if hunt_result.matches.is_empty() {
@@ -1837,23 +2224,28 @@ impl HuntingCore {
         
         metrics.events_processed_per_second = hunt_result.total_events_processed as f64 / (execution_time / 1000.0);
         metrics.detection_rate = (hunt_result.matches.len() as f64 / hunt_result.total_events_processed as f64) * 100.0;
+        drop(tenants);
+        self.record_hunt_metrics(hunt_result);
+        self.observe_hunt(hunt_result).await;
     }
 
-    pub async fn get_performance_metrics(&self) -> Result<HuntingPerformanceMetrics, String> {
+    pub async fn get_performance_metrics(&self, tenant_id: &str) -> CoreResult<HuntingPerformanceMetrics> {
         let metrics = self.performance_metrics.read().await;
-        Ok(metrics.clone())
+        Ok(metrics.get(tenant_id).cloned().unwrap_or_else(HuntingPerformanceMetrics::new))
     }
 
-    pub async fn list_rules(&self) -> Result<Vec<HuntingRule>, String> {
+    /// The tenant's own rules together with the built-in ones
+    pub async fn list_rules(&self, tenant_id: &str) -> CoreResult<Vec<HuntingRule>> {
         let rules = self.rules.read().await;
-        Ok(rules.values().cloned().collect())
+        Ok(rules.values().filter(|rule| tenancy::rule_visible(rule, tenant_id)).cloned().collect())
     }
 
-    pub async fn get_hunt_results(&self, limit: Option<usize>) -> Result<Vec<HuntingResult>, String> {
+    pub async fn get_hunt_results(&self, tenant_id: &str, limit: Option<usize>) -> CoreResult<Vec<HuntingResult>> {
         let results = self.hunt_results.read().await;
         let limit = limit.unwrap_or(10);
         
         Ok(results.values()
+            .filter(|result| result.tenant_id == tenant_id)
             .cloned()
             .collect::<Vec<_>>()
             .into_iter()
@@ -1861,17 +2253,37 @@ impl HuntingCore {
             .take(limit)
             .collect())
     }
+
+    /// One page of the rules visible to the tenant, ordered by name by default
+    pub async fn rules_page(&self, tenant_id: &str, request: &pagination::PageRequest) -> CoreResult<pagination::Page<HuntingRule>> {
+        let rules = self.list_rules(tenant_id).await?;
+        Ok(pagination::paginate(rules, request, pagination::ListSpec { id_field: "id", sort_by: "name", direction: pagination::SortDirection::Asc })?)
+    }
+
+    /// One page of the tenant's hunt results, newest first by default
+    pub async fn hunt_results_page(&self, tenant_id: &str, request: &pagination::PageRequest) -> CoreResult<pagination::Page<HuntingResult>> {
+        let results: Vec<HuntingResult> = self.hunt_results.read().await
+            .values()
+            .filter(|result| result.tenant_id == tenant_id)
+            .cloned()
+            .collect();
+        Ok(pagination::paginate(results, request, pagination::ListSpec { id_field: "hunt_id", sort_by: "execution_timestamp", direction: pagination::SortDirection::Desc })?)
+    }
 }
 
 struct HuntingExecutionResult {
     matches: Vec<HuntingMatch>,
     events_processed: u64,
+    source_errors: Vec<String>,
 }
 
 // Enterprise NAPI Bindings for Phantom Hunting Core
 #[napi]
 pub struct HuntingCoreNapi {
     inner: Arc<HuntingCore>,
+    access: access::AccessGuard,
+    audit: audit::AuditTrail,
+    tenants: tenancy::TenantGuard,
 }
 
 #[napi]
@@ -1879,22 +2291,31 @@ impl HuntingCoreNapi {
     #[napi(constructor)]
     pub fn new() -> Result<Self> {
         let core = HuntingCore::new()
-            .map_err(|e| napi::Error::from_reason(format!("Failed to create Hunting Core: {}", e)))?;
-        Ok(HuntingCoreNapi { inner: Arc::new(core) })
+            .map_err(|e| e.context("Failed to create Hunting Core"))?;
+        Ok(HuntingCoreNapi {
+            inner: Arc::new(core),
+            access: access::access_guard(),
+            audit: audit::audit_trail(),
+            tenants: tenancy::TenantGuard::default(),
+        })
     }
 
     /// Execute comprehensive threat hunting with ML-powered analysis
     #[napi]
-    pub async fn execute_hunt(&self, rule_id: String, data_context: Option<String>) -> Result<String> {
+    pub async fn execute_hunt(&self, rule_id: String, data_context: Option<String>, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", &rule_id)?;
+        let params = serde_json::json!({ "data_context": data_context });
         let context = if let Some(ctx) = data_context {
             Some(serde_json::from_str(&ctx)
-                .map_err(|e| napi::Error::from_reason(format!("Failed to parse data context: {}", e)))?)
+                .map_err(|e| CoreError::from(e).context("Failed to parse data context"))?)
         } else {
             None
         };
 
-        let result = self.inner.execute_hunt(&rule_id, context).await
-            .map_err(|e| napi::Error::from_reason(format!("Failed to execute hunt: {}", e)))?;
+        let result = self.inner.execute_hunt(&tenant_id, &rule_id, context).await;
+        let result = self.audit.record(&actor, "execute_hunt", &rule_id, params, result)
+            .map_err(|e| e.context("Failed to execute hunt"))?;
 
         serde_json::to_string(&result)
             .map_err(|e| napi::Error::from_reason(format!("Failed to serialize result: {}", e)))
@@ -1902,9 +2323,10 @@ impl HuntingCoreNapi {
 
     /// Get comprehensive hunting performance metrics
     #[napi]
-    pub async fn get_performance_metrics(&self) -> Result<String> {
-        let metrics = self.inner.get_performance_metrics().await
-            .map_err(|e| napi::Error::from_reason(format!("Failed to get performance metrics: {}", e)))?;
+    pub async fn get_performance_metrics(&self, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let metrics = self.inner.get_performance_metrics(&tenant_id).await
+            .map_err(|e| e.context("Failed to get performance metrics"))?;
 
         serde_json::to_string(&metrics)
             .map_err(|e| napi::Error::from_reason(format!("Failed to serialize metrics: {}", e)))
@@ -1912,32 +2334,313 @@ impl HuntingCoreNapi {
 
     /// List all available hunting rules
     #[napi]
-    pub async fn list_rules(&self) -> Result<String> {
-        let rules = self.inner.list_rules().await
-            .map_err(|e| napi::Error::from_reason(format!("Failed to list rules: {}", e)))?;
+    pub async fn list_rules(&self, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let rules = self.inner.list_rules(&tenant_id).await
+            .map_err(|e| e.context("Failed to list rules"))?;
 
-        serde_json::to_string(&rules)
+        serde_json::to_string(&secrets::redacted(&rules))
             .map_err(|e| napi::Error::from_reason(format!("Failed to serialize rules: {}", e)))
     }
 
     /// Get recent hunting results with analytics
     #[napi]
-    pub async fn get_hunt_results(&self, limit: Option<u32>) -> Result<String> {
-        let results = self.inner.get_hunt_results(limit.map(|l| l as usize)).await
-            .map_err(|e| napi::Error::from_reason(format!("Failed to get hunt results: {}", e)))?;
+    pub async fn get_hunt_results(&self, limit: Option<u32>, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let results = self.inner.get_hunt_results(&tenant_id, limit.map(|l| l as usize)).await
+            .map_err(|e| e.context("Failed to get hunt results"))?;
 
         serde_json::to_string(&results)
             .map_err(|e| napi::Error::from_reason(format!("Failed to serialize results: {}", e)))
     }
 
+    /// Page through hunting rules with an opaque cursor, sort and filters
+    #[napi]
+    pub async fn list_rules_page(&self, page_json: Option<String>, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let request = pagination::PageRequest::from_json(page_json.as_deref()).map_err(CoreError::from)?;
+        let page = self.inner.rules_page(&tenant_id, &request).await
+            .map_err(|e| e.context("Failed to list rules"))?
+            .project(&request.fields).map_err(CoreError::from)?;
+
+        serde_json::to_string(&secrets::redacted(&page))
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize rules page: {}", e)))
+    }
+
+    /// Page through hunt results; use `fields` to leave out bulky match data
+    #[napi]
+    pub async fn get_hunt_results_page(&self, page_json: Option<String>, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let request = pagination::PageRequest::from_json(page_json.as_deref()).map_err(CoreError::from)?;
+        let page = self.inner.hunt_results_page(&tenant_id, &request).await
+            .map_err(|e| e.context("Failed to get hunt results"))?
+            .project(&request.fields).map_err(CoreError::from)?;
+
+        serde_json::to_string(&page)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize results page: {}", e)))
+    }
+
+    /// Ingest a raw NetFlow v5/v9 or IPFIX export packet
+    #[napi]
+    pub async fn ingest_flow_packet(&self, packet: Buffer, exporter: String, auth_token: Option<String>) -> Result<u32> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", "flows")?;
+        let result = self.inner.ingest_flow_packet(&tenant_id, &packet, &exporter).await;
+        let count = self.audit.record(&actor, "ingest_flow_packet", &exporter, serde_json::json!({ "bytes": packet.len() }), result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to ingest flow packet: {}", e)))?;
+        Ok(count as u32)
+    }
+
+    /// Ingest decoded flow records supplied as a JSON array
+    #[napi]
+    pub async fn ingest_flow_records(&self, records_json: String, auth_token: Option<String>) -> Result<u32> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", "flows")?;
+        let records: Vec<FlowRecord> = serde_json::from_str(&records_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse flow records: {}", e)))?;
+
+        let params = serde_json::json!({ "records": records.len() });
+        let result = self.inner.ingest_flow_records(&tenant_id, records).await;
+        let count = self.audit.record(&actor, "ingest_flow_records", "flows", params, result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to ingest flow records: {}", e)))?;
+        Ok(count as u32)
+    }
+
+    /// Run built-in flow analytics (long-lived low-volume, port scans, border asymmetry)
+    #[napi]
+    pub async fn run_flow_analytics(&self, config_json: Option<String>, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let config = if let Some(cfg) = config_json {
+            Some(serde_json::from_str(&cfg)
+                .map_err(|e| napi::Error::from_reason(format!("Failed to parse flow analytics config: {}", e)))?)
+        } else {
+            None
+        };
+
+        let findings = self.inner.run_flow_analytics(&tenant_id, config).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to run flow analytics: {}", e)))?;
+
+        serde_json::to_string(&findings)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize flow findings: {}", e)))
+    }
+
+    /// Attach a cron or interval schedule to a rule
+    #[napi]
+    pub async fn schedule_hunt(&self, schedule_json: String, auth_token: Option<String>) -> Result<String> {
+        let schedule: scheduler::HuntSchedule = serde_json::from_str(&schedule_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse hunt schedule: {}", e)))?;
+        let rule_id = schedule.rule_id.clone();
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &rule_id)?;
+
+        let status = self.inner.schedule_hunt(&tenant_id, schedule).await;
+        let status = self.audit.record(&actor, "schedule_hunt", &rule_id, serde_json::json!({ "schedule": schedule_json }), status)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to schedule hunt: {}", e)))?;
+
+        serde_json::to_string(&status)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize schedule status: {}", e)))
+    }
+
+    /// Remove a rule's schedule
+    #[napi]
+    pub async fn unschedule_hunt(&self, rule_id: String, auth_token: Option<String>) -> Result<bool> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &rule_id)?;
+        let removed = self.inner.unschedule_hunt(&tenant_id, &rule_id).await;
+        self.audit.record(&actor, "unschedule_hunt", &rule_id, serde_json::json!({}), Ok::<_, String>(removed))
+            .map_err(napi::Error::from_reason)
+    }
+
+    /// List scheduled hunts with their next-run times
+    #[napi]
+    pub async fn get_scheduled_hunts(&self, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let statuses = self.inner.list_scheduled_hunts(&tenant_id).await;
+
+        serde_json::to_string(&statuses)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize scheduled hunts: {}", e)))
+    }
+
+    /// Start the background hunt scheduler
+    #[napi]
+    pub async fn start_scheduler(&self, config_json: Option<String>, auth_token: Option<String>) -> Result<()> {
+        let actor = self.authorize_platform(auth_token, "scheduler")?;
+        let params = serde_json::json!({ "config": config_json });
+        let config = match config_json {
+            Some(cfg) => serde_json::from_str(&cfg)
+                .map_err(|e| napi::Error::from_reason(format!("Failed to parse scheduler config: {}", e)))?,
+            None => scheduler::SchedulerConfig::default(),
+        };
+
+        let result = self.inner.start_scheduler(config).await;
+        self.audit.record(&actor, "start_scheduler", "scheduler", params, result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to start scheduler: {}", e)))
+    }
+
+    /// Stop the background hunt scheduler; returns false if it was not running
+    #[napi]
+    pub async fn stop_scheduler(&self, auth_token: Option<String>) -> Result<bool> {
+        let actor = self.authorize_platform(auth_token, "scheduler")?;
+        let stopped = self.inner.stop_scheduler().await;
+        self.audit.record(&actor, "stop_scheduler", "scheduler", serde_json::json!({}), Ok::<_, String>(stopped))
+            .map_err(napi::Error::from_reason)
+    }
+
+    /// Load an ONNX or linear model with its feature pipeline for scoring
+    #[napi]
+    pub async fn register_ml_model(&self, spec_json: String, auth_token: Option<String>) -> Result<String> {
+        let spec: inference::ModelSpec = serde_json::from_str(&spec_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse model spec: {}", e)))?;
+        let model_id = spec.model_id.clone();
+        let actor = self.authorize(auth_token, "rule:manage", &model_id)?;
+
+        let info = self.inner.register_ml_model(spec).await;
+        let info = self.audit.record(&actor, "register_ml_model", &model_id, serde_json::json!({ "spec": spec_json }), info)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to register model: {}", e)))?;
+
+        serde_json::to_string(&info)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize model info: {}", e)))
+    }
+
+    /// Unload a model; returns false if it was not registered
+    #[napi]
+    pub async fn unregister_ml_model(&self, model_id: String, auth_token: Option<String>) -> Result<bool> {
+        let actor = self.authorize(auth_token, "rule:manage", &model_id)?;
+        let removed = self.inner.unregister_ml_model(&model_id).await;
+        self.audit.record(&actor, "unregister_ml_model", &model_id, serde_json::json!({}), Ok::<_, String>(removed))
+            .map_err(napi::Error::from_reason)
+    }
+
+    /// List loaded models with versions and latency metrics
+    #[napi]
+    pub fn list_loaded_models(&self) -> Result<String> {
+        serde_json::to_string(&self.inner.list_loaded_models())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize models: {}", e)))
+    }
+
+    /// Score a JSON record (e.g. a sandbox analysis) with a loaded model
+    #[napi]
+    pub fn score_with_model(&self, model_id: String, record_json: String) -> Result<String> {
+        let record: serde_json::Value = serde_json::from_str(&record_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse record: {}", e)))?;
+        let score = self.inner.score_record(&model_id, &record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to score record: {}", e)))?;
+
+        serde_json::to_string(&score)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize score: {}", e)))
+    }
+
+    /// Ingest a batch of historical logons, flows and process launches for baseline training
+    #[napi]
+    pub async fn ingest_baseline_events(&self, events_json: String, auth_token: Option<String>) -> Result<String> {
+        let actor = self.authorize_platform(auth_token, "baselines")?;
+        let events: Vec<baseline::HistoricalEvent> = serde_json::from_str(&events_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse baseline events: {}", e)))?;
+
+        let summary = self.inner.ingest_baseline_events(&events).await;
+        let summary = self.audit.record(&actor, "ingest_baseline_events", "baselines", serde_json::json!({ "events": events.len() }), Ok::<_, String>(summary))
+            .map_err(napi::Error::from_reason)?;
+
+        serde_json::to_string(&summary)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize ingest summary: {}", e)))
+    }
+
+    /// Recompute behavioural baselines from the training window
+    #[napi]
+    pub async fn refresh_baselines(&self, auth_token: Option<String>) -> Result<String> {
+        let actor = self.authorize_platform(auth_token, "baselines")?;
+        let learned = self.inner.refresh_baselines().await;
+        let learned = self.audit.record(&actor, "refresh_baselines", "baselines", serde_json::json!({}), learned)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to refresh baselines: {}", e)))?;
+
+        serde_json::to_string(&learned)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize baselines: {}", e)))
+    }
+
+    /// Persist baseline training state to a file, restoring any state already there
+    #[napi]
+    pub async fn set_baseline_store(&self, path: String, auth_token: Option<String>) -> Result<bool> {
+        let actor = self.authorize_platform(auth_token, "baselines")?;
+        let restored = self.inner.set_baseline_store(path.clone().into()).await;
+        self.audit.record(&actor, "set_baseline_store", "baselines", serde_json::json!({ "path": path }), restored)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to set baseline store: {}", e)))
+    }
+
+    /// Refresh baselines every `baseline_update_frequency` hours
+    #[napi]
+    pub async fn start_baseline_refresh(&self, auth_token: Option<String>) -> Result<()> {
+        let actor = self.authorize_platform(auth_token, "baselines")?;
+        let result = self.inner.start_baseline_refresh().await;
+        self.audit.record(&actor, "start_baseline_refresh", "baselines", serde_json::json!({}), result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to start baseline refresh: {}", e)))
+    }
+
+    /// Stop periodic baseline refresh; returns false if it was not running
+    #[napi]
+    pub async fn stop_baseline_refresh(&self, auth_token: Option<String>) -> Result<bool> {
+        let actor = self.authorize_platform(auth_token, "baselines")?;
+        let stopped = self.inner.stop_baseline_refresh().await;
+        self.audit.record(&actor, "stop_baseline_refresh", "baselines", serde_json::json!({}), Ok::<_, String>(stopped))
+            .map_err(napi::Error::from_reason)
+    }
+
+    /// Get the learned baseline profiles, if baselines have been trained
+    #[napi]
+    pub async fn get_learned_baselines(&self) -> Result<Option<String>> {
+        self.inner.get_learned_baselines().await
+            .map(|learned| serde_json::to_string(&learned))
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize baselines: {}", e)))
+    }
+
+    /// Get the behavioural baselines used by hunts
+    #[napi]
+    pub async fn get_behavioral_baselines(&self) -> Result<String> {
+        serde_json::to_string(&self.inner.get_behavioral_baselines().await)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize baselines: {}", e)))
+    }
+
+    /// Compare a single event with the learned baselines
+    #[napi]
+    pub async fn assess_baseline_event(&self, event_json: String) -> Result<String> {
+        let event: baseline::HistoricalEvent = serde_json::from_str(&event_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse event: {}", e)))?;
+        let assessment = self.inner.assess_against_baseline(&event).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to assess event: {}", e)))?;
+
+        serde_json::to_string(&assessment)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize assessment: {}", e)))
+    }
+
+    /// Import a (multi-document) Sigma YAML pack, translating rules to KQL, SQL or SPL
+    #[napi]
+    pub async fn import_sigma_rules(&self, rules_yaml: String, backend: Option<String>, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", "sigma")?;
+        let params = serde_json::json!({ "rules": rules_yaml, "backend": backend });
+        let backend = match backend {
+            Some(name) => sigma::SigmaBackend::parse(&name)
+                .map_err(|e| napi::Error::from_reason(format!("Failed to import Sigma rules: {}", e)))?,
+            None => sigma::SigmaBackend::KQL,
+        };
+
+        let report = self.inner.import_sigma_rules(&tenant_id, &rules_yaml, backend).await;
+        let report = self.audit.record(&actor, "import_sigma_rules", "sigma", params, report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to import Sigma rules: {}", e)))?;
+
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize Sigma import report: {}", e)))
+    }
+
     /// Get enterprise health status with comprehensive metrics
     #[napi]
-    pub async fn get_health_status(&self) -> Result<String> {
-        let performance_metrics = self.inner.get_performance_metrics().await
-            .map_err(|e| napi::Error::from_reason(format!("Failed to get performance metrics: {}", e)))?;
+    pub async fn get_health_status(&self, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let performance_metrics = self.inner.get_performance_metrics(&tenant_id).await
+            .map_err(|e| e.context("Failed to get performance metrics"))?;
         
-        let rules = self.inner.list_rules().await
-            .map_err(|e| napi::Error::from_reason(format!("Failed to list rules: {}", e)))?;
+        let rules = self.inner.list_rules(&tenant_id).await
+            .map_err(|e| e.context("Failed to list rules"))?;
 
         let data_sources = self.inner.data_sources.read().await;
         let ml_models = self.inner.ml_models.read().await;
@@ -1993,7 +2696,9 @@ impl HuntingCoreNapi {
                     "source_name": ds.source_name,
                     "source_type": format!("{:?}", ds.source_type),
                     "reliability_score": ds.reliability_score,
-                    "update_frequency_seconds": ds.update_frequency.num_seconds()
+                    "update_frequency_seconds": ds.update_frequency.num_seconds(),
+                    "auth_type": ds.connection_details.authentication.auth_type,
+                    "credentials": ds.connection_details.authentication.credentials
                 })
             }).collect::<Vec<_>>(),
             "ml_models": ml_models.values().map(|model| {
@@ -2010,14 +2715,31 @@ impl HuntingCoreNapi {
                 "enabled_data_sources": self.inner.config.enabled_data_sources,
                 "alert_thresholds": self.inner.config.alert_thresholds,
                 "enterprise_features": self.inner.config.enterprise_features
-            }
+            },
+            "secrets": self.inner.secrets().status()
         });
+        let mut status = status;
+        secrets::redact(&mut status);
 
         serde_json::to_string(&status)
             .map_err(|e| napi::Error::from_reason(format!("Failed to serialize health status: {}", e)))
     }
 }
 
+impl HuntingCoreNapi {
+    fn authorize(&self, auth_token: Option<String>, permission: &str, resource: &str) -> Result<String> {
+        self.access.check(auth_token.as_deref(), permission, resource)
+            .map_err(napi::Error::from_reason)
+    }
+
+    /// Tenant the call acts for, refused when the tenant is not active
+    fn tenant(&self, auth_token: Option<&str>) -> Result<String> {
+        let tenant_id = self.access.tenant(auth_token).unwrap_or_else(tenancy::default_tenant);
+        self.tenants.check(&tenant_id).map_err(napi::Error::from_reason)?;
+        Ok(tenant_id)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,193 @@
+//! Live Feed
+//!
+//! With `phantom-enterprise-standards` enabled, the matches of every executed
+//! hunt are published to the `hunt_matches` topic of the core's live feed
+//! hub, one event per match up to [`MAX_MATCH_EVENTS`] per hunt. With
+//! `live-feed`, `start_live_feed` serves the hub to dashboards over WebSocket
+//! and SSE. The host can relay events of other cores through
+//! `publish_feed_event`, so one server can carry incidents, alerts and
+//! sandbox verdicts as well. Clients only receive events of their own
+//! tenant. Without the feature nothing is published.
+
+use crate::{HuntingCore, HuntingResult, HuntingRule};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::{HuntingCoreNapi, HuntingMatch};
+#[cfg(feature = "phantom-enterprise-standards")]
+use napi_derive::napi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::live_feed::{FeedEvent, FeedHub, FeedTopic};
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "live-feed")]
+use crate::tenancy;
+#[cfg(feature = "live-feed")]
+use phantom_enterprise_standards::live_feed::{feed_principal, serve_feed, FeedAuthenticator, FeedConfig};
+#[cfg(feature = "live-feed")]
+use std::net::SocketAddr;
+#[cfg(feature = "live-feed")]
+use std::sync::Mutex;
+
+/// Matches of one hunt published individually; every event carries the
+/// hunt's total so dashboards can tell when matches were left out
+pub const MAX_MATCH_EVENTS: usize = 100;
+
+#[cfg(feature = "phantom-enterprise-standards")]
+const SOURCE: &str = "phantom-hunting-core";
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[derive(Default)]
+pub struct LiveFeedState {
+    hub: RwLock<Arc<FeedHub>>,
+    #[cfg(feature = "live-feed")]
+    server: Mutex<Option<tokio::task::JoinHandle<()>>>,
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+impl LiveFeedState {
+    pub fn hub(&self) -> Arc<FeedHub> {
+        self.hub.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Feed event for one match of a hunt
+#[cfg(feature = "phantom-enterprise-standards")]
+pub fn match_feed_event(result: &HuntingResult, rule: &HuntingRule, hunt_match: &HuntingMatch) -> FeedEvent {
+    let context = &hunt_match.context;
+    let payload = serde_json::json!({
+        "hunt_id": result.hunt_id,
+        "rule_id": rule.id,
+        "rule_name": rule.name,
+        "severity": rule.severity,
+        "source": hunt_match.source,
+        "timestamp": hunt_match.timestamp,
+        "host": context.system_context.as_ref().map(|system| system.hostname.as_str()),
+        "user": context.user_context.as_ref().map(|user| user.user_id.as_str()),
+        "source_ip": context.network_context.as_ref().map(|network| network.source_ip.as_str()),
+        "destination_ip": context.network_context.as_ref().map(|network| network.destination_ip.as_str()),
+        "confidence_score": hunt_match.confidence_score,
+        "risk_score": hunt_match.risk_score,
+        "mitre_techniques": rule.mitre_techniques.iter().map(|t| t.technique_id.as_str()).collect::<Vec<_>>(),
+        "total_matches": result.matches.len(),
+    });
+    FeedEvent::new(FeedTopic::HuntMatches, "hunt_match", &result.tenant_id, SOURCE, payload).entity(&hunt_match.match_id)
+}
+
+impl HuntingCore {
+    /// Push the matches of a finished hunt to live feed subscribers
+    pub(crate) fn publish_hunt_matches(&self, result: &HuntingResult, rule: &HuntingRule) {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            let hub = self.live_feed.hub();
+            for hunt_match in result.matches.iter().take(MAX_MATCH_EVENTS) {
+                hub.publish(match_feed_event(result, rule, hunt_match));
+            }
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        let _ = (result, rule);
+    }
+
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn live_feed_hub(&self) -> Arc<FeedHub> {
+        self.live_feed.hub()
+    }
+
+    /// Serve the live feed on `bind`, or stop it with `None`. A running
+    /// server is replaced and its clients are disconnected.
+    #[cfg(feature = "live-feed")]
+    pub async fn start_live_feed(&self, bind: Option<&str>, config: FeedConfig, authenticate: FeedAuthenticator) -> Result<Option<SocketAddr>, String> {
+        if let Some(running) = self.live_feed.server.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            running.abort();
+        }
+        let hub = Arc::new(FeedHub::new(config));
+        let previous = std::mem::replace(&mut *self.live_feed.hub.write().unwrap_or_else(|e| e.into_inner()), hub.clone());
+        previous.close_all();
+
+        let Some(bind) = bind else { return Ok(None) };
+        let (address, handle) = serve_feed(bind, hub, authenticate).await?;
+        *self.live_feed.server.lock().unwrap_or_else(|e| e.into_inner()) = Some(handle);
+        Ok(Some(address))
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[napi]
+impl HuntingCoreNapi {
+    /// Relay an event (JSON `FeedEvent`) from another core to this core's
+    /// live feed subscribers; returns how many clients accepted it
+    #[napi]
+    pub fn publish_feed_event(&self, event_json: String, auth_token: Option<String>) -> napi::Result<u32> {
+        self.authorize_platform(auth_token, "live-feed")?;
+        let event: FeedEvent = serde_json::from_str(&event_json)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid feed event: {}", e)))?;
+        Ok(self.inner.live_feed_hub().publish(event) as u32)
+    }
+
+    /// Connected live feed clients with their queue depth and drop counts
+    #[napi]
+    pub fn get_live_feed_stats(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize_platform(auth_token, "live-feed")?;
+        serde_json::to_string(&self.inner.live_feed_hub().stats())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize live feed stats: {}", e)))
+    }
+}
+
+#[cfg(feature = "live-feed")]
+#[napi]
+impl HuntingCoreNapi {
+    /// Serve the live feed (`/feed/ws` and `/feed/sse`) on `bind`, e.g.
+    /// `0.0.0.0:9465`, replacing any running server; returns the bound
+    /// address. Pass no address to stop
+    #[napi]
+    pub async fn start_live_feed(&self, bind: Option<String>, config_json: Option<String>, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        let actor = self.authorize_platform(auth_token, "live-feed")?;
+        let config: FeedConfig = match &config_json {
+            Some(json) => serde_json::from_str(json)
+                .map_err(|e| napi::Error::from_reason(format!("Invalid live feed config: {}", e)))?,
+            None => FeedConfig::default(),
+        };
+
+        let access = self.access.clone();
+        let tenants = self.tenants.clone();
+        let authenticate: FeedAuthenticator = Arc::new(move |token| {
+            let principal = feed_principal(access.control(), token, tenancy::DEFAULT_TENANT)?;
+            if let Some(tenant_id) = &principal.tenant_id {
+                tenants.check(tenant_id)?;
+            }
+            Ok(principal)
+        });
+
+        let details = serde_json::json!({ "bind": bind, "config": config_json });
+        let started = self.inner.start_live_feed(bind.as_deref(), config, authenticate).await;
+        let address = self.audit.record(&actor, "start_live_feed", "live-feed", details, started)
+            .map_err(napi::Error::from_reason)?;
+        Ok(address.map(|address| address.to_string()))
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use super::*;
+    use phantom_enterprise_standards::live_feed::{FeedMessage, FeedPrincipal};
+
+    #[tokio::test]
+    async fn hunt_matches_are_published_to_subscribers() {
+        let core = HuntingCore::new().unwrap();
+        let principal = FeedPrincipal { actor: "dashboard".to_string(), tenant_id: Some("acme".to_string()) };
+        let mut subscription = core.live_feed_hub().subscribe(principal, FeedTopic::parse_list("hunt_matches").unwrap()).unwrap();
+
+        let rule = core.list_rules("acme").await.unwrap().remove(0);
+        let result = core.execute_hunt("acme", &rule.id, None).await.unwrap();
+        assert!(!result.matches.is_empty());
+
+        match subscription.next().await {
+            Some(FeedMessage::Event(event)) => {
+                assert_eq!(event.entity_id.as_deref(), Some(result.matches[0].match_id.as_str()));
+                assert_eq!(event.payload["hunt_id"], result.hunt_id);
+                assert_eq!(event.payload["total_matches"], result.matches.len());
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
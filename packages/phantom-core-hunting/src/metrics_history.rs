@@ -0,0 +1,121 @@
+//! Metrics History
+//!
+//! With `phantom-enterprise-standards` enabled, every executed hunt records
+//! its detection rate, events processed and duration per tenant into
+//! 1-minute, 1-hour and 1-day rollups, so they can be charted over time
+//! alongside the point-in-time `get_performance_metrics`. Without the feature
+//! nothing is recorded.
+
+use crate::{HuntingCore, HuntingResult};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::HuntingCoreNapi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use napi_derive::napi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::metrics_history::{parse_range, MetricSeries, MetricsHistory, Resolution};
+
+/// Hunts executed; chart the bucket `sum`
+pub const HUNTS_EXECUTED: &str = "hunts_executed";
+pub const EVENTS_PROCESSED: &str = "events_processed";
+/// Matches per 100 events scanned, for hunts that scanned any
+pub const DETECTION_RATE: &str = "detection_rate";
+pub const HUNT_DURATION_MS: &str = "hunt_duration_ms";
+
+#[derive(Default)]
+pub struct MetricsHistoryState {
+    #[cfg(feature = "phantom-enterprise-standards")]
+    history: MetricsHistory,
+}
+
+impl MetricsHistoryState {
+    pub(crate) fn record(&self, tenant_id: &str, metric: &str, value: f64) {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        self.history.record(tenant_id, metric, value);
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        let _ = (tenant_id, metric, value);
+    }
+
+    pub(crate) fn forget_tenant(&self, tenant_id: &str) -> usize {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            self.history.forget_scope(tenant_id)
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        {
+            let _ = tenant_id;
+            0
+        }
+    }
+}
+
+impl HuntingCore {
+    pub(crate) fn record_hunt_metrics(&self, hunt_result: &HuntingResult) {
+        let tenant_id = &hunt_result.tenant_id;
+        self.metrics_history.record(tenant_id, HUNTS_EXECUTED, 1.0);
+        self.metrics_history.record(tenant_id, EVENTS_PROCESSED, hunt_result.total_events_processed as f64);
+        self.metrics_history.record(tenant_id, HUNT_DURATION_MS, hunt_result.execution_duration.num_milliseconds() as f64);
+        if hunt_result.total_events_processed > 0 {
+            let rate = hunt_result.matches.len() as f64 / hunt_result.total_events_processed as f64 * 100.0;
+            self.metrics_history.record(tenant_id, DETECTION_RATE, rate);
+        }
+    }
+
+    /// History of `metric` over the last `range` for charting
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn metrics_history(&self, tenant_id: &str, metric: &str, range: chrono::Duration, resolution: Option<Resolution>) -> Result<MetricSeries, String> {
+        self.metrics_history.history.recent(tenant_id, metric, range, resolution)
+    }
+
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn recorded_metrics(&self, tenant_id: &str) -> Vec<String> {
+        self.metrics_history.history.metrics(tenant_id)
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[napi]
+impl HuntingCoreNapi {
+    /// Bucketed history of a metric, e.g. `("detection_rate", "7d", "1h")`;
+    /// the resolution is picked from the range when omitted
+    #[napi]
+    pub fn get_metrics_history(&self, metric: String, range: String, resolution: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let range = parse_range(&range).map_err(napi::Error::from_reason)?;
+        let resolution = resolution.as_deref().map(str::parse).transpose().map_err(napi::Error::from_reason)?;
+        let series = self.inner.metrics_history(&tenant_id, &metric, range, resolution)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to get metrics history: {}", e)))?;
+        serde_json::to_string(&series)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize metrics history: {}", e)))
+    }
+
+    /// Metrics with recorded history for the caller's tenant
+    #[napi]
+    pub fn list_history_metrics(&self, auth_token: Option<String>) -> napi::Result<Vec<String>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        Ok(self.inner.recorded_metrics(&tenant_id))
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn executed_hunts_are_recorded_per_tenant() {
+        let core = HuntingCore::new().unwrap();
+        let rule = core.list_rules("acme").await.unwrap().remove(0);
+        let first = core.execute_hunt("acme", &rule.id, None).await.unwrap();
+        let second = core.execute_hunt("acme", &rule.id, None).await.unwrap();
+
+        let hunts = core.metrics_history("acme", HUNTS_EXECUTED, chrono::Duration::days(1), Some(Resolution::Day)).unwrap();
+        assert_eq!(hunts.points.iter().map(|p| p.sum).sum::<f64>(), 2.0);
+        let events = core.metrics_history("acme", EVENTS_PROCESSED, chrono::Duration::hours(1), None).unwrap();
+        let total: f64 = events.points.iter().map(|p| p.sum).sum();
+        assert_eq!(total, (first.total_events_processed + second.total_events_processed) as f64);
+
+        assert!(core.metrics_history("globex", HUNTS_EXECUTED, chrono::Duration::hours(1), None).is_err());
+        core.purge_tenant("acme").await;
+        assert!(core.recorded_metrics("acme").is_empty());
+    }
+}
@@ -0,0 +1,959 @@
+//! NetFlow / IPFIX flow hunting
+//!
+//! Decodes NetFlow v5, NetFlow v9 and IPFIX (v10) export packets into a
+//! canonical `FlowRecord`, exposes flows to the rule engine as flat event maps
+//! and provides flow-specific built-in analytics:
+//!
+//! * long-lived, low-volume conversations (beaconing / covert channels)
+//! * vertical and horizontal port scanning
+//! * border-crossing byte asymmetry (internal host pushing data outbound)
+//!
+//! Flows and exporter templates are kept per tenant: hunts, sessions and
+//! analytics only see the flows the caller's tenant ingested.
+
+use crate::{HuntingCore, HuntingSeverity};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Flow records per tenant
+pub type FlowStore = Arc<RwLock<HashMap<String, Vec<FlowRecord>>>>;
+
+// Canonical Flow Schema
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowRecord {
+    #[serde(default = "new_flow_id")]
+    pub flow_id: String,
+    #[serde(default)]
+    pub exporter: String,
+    #[serde(default = "default_flow_format")]
+    pub format: FlowFormat,
+    pub source_ip: IpAddr,
+    pub destination_ip: IpAddr,
+    #[serde(default)]
+    pub source_port: u16,
+    #[serde(default)]
+    pub destination_port: u16,
+    pub protocol: u8,
+    pub bytes: u64,
+    pub packets: u64,
+    #[serde(default)]
+    pub tcp_flags: u8,
+    pub flow_start: DateTime<Utc>,
+    pub flow_end: DateTime<Utc>,
+    #[serde(default)]
+    pub input_interface: Option<u32>,
+    #[serde(default)]
+    pub output_interface: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlowFormat {
+    NetFlowV5,
+    NetFlowV9,
+    IPFIX,
+    JSON,
+}
+
+fn new_flow_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+fn default_flow_format() -> FlowFormat {
+    FlowFormat::JSON
+}
+
+const TCP_FLAG_NAMES: [(u8, &str); 8] = [
+    (0x01, "FIN"),
+    (0x02, "SYN"),
+    (0x04, "RST"),
+    (0x08, "PSH"),
+    (0x10, "ACK"),
+    (0x20, "URG"),
+    (0x40, "ECE"),
+    (0x80, "CWR"),
+];
+
+impl FlowRecord {
+    pub fn duration(&self) -> Duration {
+        (self.flow_end - self.flow_start).max(Duration::zero())
+    }
+
+    pub fn protocol_name(&self) -> String {
+        match self.protocol {
+            1 => "ICMP".to_string(),
+            6 => "TCP".to_string(),
+            17 => "UDP".to_string(),
+            47 => "GRE".to_string(),
+            50 => "ESP".to_string(),
+            58 => "ICMPv6".to_string(),
+            132 => "SCTP".to_string(),
+            other => other.to_string(),
+        }
+    }
+
+    pub fn tcp_flag_names(&self) -> Vec<String> {
+        TCP_FLAG_NAMES
+            .iter()
+            .filter(|(bit, _)| self.tcp_flags & bit != 0)
+            .map(|(_, name)| name.to_string())
+            .collect()
+    }
+
+    /// Flatten the flow into the event shape used by hunting rules. Field names
+    /// follow the network event conventions already used by the built-in rules
+    /// so existing conditions apply to flows without rewriting.
+    pub fn to_event_data(&self) -> HashMap<String, serde_json::Value> {
+        let duration_ms = self.duration().num_milliseconds();
+        HashMap::from([
+            ("flow_id".to_string(), serde_json::json!(self.flow_id)),
+            ("event_type".to_string(), serde_json::json!("flow")),
+            ("flow_format".to_string(), serde_json::json!(format!("{:?}", self.format))),
+            ("exporter".to_string(), serde_json::json!(self.exporter)),
+            ("source_ip".to_string(), serde_json::json!(self.source_ip.to_string())),
+            ("destination_ip".to_string(), serde_json::json!(self.destination_ip.to_string())),
+            ("source_port".to_string(), serde_json::json!(self.source_port)),
+            ("destination_port".to_string(), serde_json::json!(self.destination_port)),
+            ("protocol".to_string(), serde_json::json!(self.protocol_name())),
+            ("protocol_number".to_string(), serde_json::json!(self.protocol)),
+            ("bytes".to_string(), serde_json::json!(self.bytes)),
+            ("bytes_out".to_string(), serde_json::json!(self.bytes)),
+            ("packets".to_string(), serde_json::json!(self.packets)),
+            ("duration_ms".to_string(), serde_json::json!(duration_ms)),
+            ("tcp_flags".to_string(), serde_json::json!(self.tcp_flags)),
+            ("tcp_flag_names".to_string(), serde_json::json!(self.tcp_flag_names())),
+            ("flow_start".to_string(), serde_json::json!(self.flow_start.to_rfc3339())),
+            ("flow_end".to_string(), serde_json::json!(self.flow_end.to_rfc3339())),
+        ])
+    }
+}
+
+// Export Packet Decoding
+#[derive(Debug, Clone, Copy)]
+struct TemplateField {
+    element_id: u16,
+    length: u16,
+    enterprise: bool,
+}
+
+/// Stateful decoder for NetFlow v5/v9 and IPFIX export packets.
+///
+/// v9 and IPFIX data sets are only decodable once the exporter has sent the
+/// matching template, so templates are cached per exporter, observation
+/// domain and template id.
+#[derive(Debug, Default)]
+pub struct FlowDecoder {
+    templates: HashMap<(String, u32, u16), Vec<TemplateField>>,
+}
+
+impl FlowDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn template_count(&self) -> usize {
+        self.templates.len()
+    }
+
+    /// Decode a single export packet. Data sets that reference an unknown
+    /// template are skipped rather than failing the whole packet.
+    pub fn decode(&mut self, packet: &[u8], exporter: &str) -> Result<Vec<FlowRecord>, String> {
+        let version = read_u16(packet, 0).ok_or("Flow packet too short")?;
+        match version {
+            5 => decode_v5(packet, exporter),
+            9 => self.decode_v9(packet, exporter),
+            10 => self.decode_ipfix(packet, exporter),
+            other => Err(format!("Unsupported flow export version: {}", other)),
+        }
+    }
+
+    fn decode_v9(&mut self, packet: &[u8], exporter: &str) -> Result<Vec<FlowRecord>, String> {
+        if packet.len() < 20 {
+            return Err("NetFlow v9 header truncated".to_string());
+        }
+        let sys_uptime = read_u32(packet, 4).unwrap_or(0) as i64;
+        let unix_secs = read_u32(packet, 8).unwrap_or(0) as i64;
+        let source_id = read_u32(packet, 16).unwrap_or(0);
+        let header = ExportHeader {
+            boot_time_ms: unix_secs * 1000 - sys_uptime,
+            export_time: Utc.timestamp_opt(unix_secs, 0).single().unwrap_or_else(Utc::now),
+        };
+
+        let mut flows = Vec::new();
+        for (set_id, body) in flow_sets(packet, 20)? {
+            match set_id {
+                0 => self.read_templates(body, exporter, source_id, false)?,
+                1 => {} // options templates carry exporter metadata, not flows
+                id if id >= 256 => {
+                    if let Some(template) = self.templates.get(&(exporter.to_string(), source_id, id)) {
+                        flows.extend(decode_data_set(body, template, &header, exporter, FlowFormat::NetFlowV9));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(flows)
+    }
+
+    fn decode_ipfix(&mut self, packet: &[u8], exporter: &str) -> Result<Vec<FlowRecord>, String> {
+        if packet.len() < 16 {
+            return Err("IPFIX header truncated".to_string());
+        }
+        let message_length = read_u16(packet, 2).unwrap_or(0) as usize;
+        if message_length < 16 || message_length > packet.len() {
+            return Err("IPFIX message length does not match packet".to_string());
+        }
+        let packet = &packet[..message_length];
+        let export_secs = read_u32(packet, 4).unwrap_or(0) as i64;
+        let domain_id = read_u32(packet, 12).unwrap_or(0);
+        let header = ExportHeader {
+            boot_time_ms: export_secs * 1000,
+            export_time: Utc.timestamp_opt(export_secs, 0).single().unwrap_or_else(Utc::now),
+        };
+
+        let mut flows = Vec::new();
+        for (set_id, body) in flow_sets(packet, 16)? {
+            match set_id {
+                2 => self.read_templates(body, exporter, domain_id, true)?,
+                3 => {}
+                id if id >= 256 => {
+                    if let Some(template) = self.templates.get(&(exporter.to_string(), domain_id, id)) {
+                        flows.extend(decode_data_set(body, template, &header, exporter, FlowFormat::IPFIX));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(flows)
+    }
+
+    fn read_templates(&mut self, body: &[u8], exporter: &str, domain: u32, ipfix: bool) -> Result<(), String> {
+        let mut offset = 0;
+        while offset + 4 <= body.len() {
+            let template_id = read_u16(body, offset).unwrap_or(0);
+            let field_count = read_u16(body, offset + 2).unwrap_or(0) as usize;
+            offset += 4;
+
+            let key = (exporter.to_string(), domain, template_id);
+            if field_count == 0 {
+                // IPFIX template withdrawal
+                self.templates.remove(&key);
+                continue;
+            }
+
+            let mut fields = Vec::with_capacity(field_count);
+            for _ in 0..field_count {
+                let raw_id = read_u16(body, offset).ok_or("Template record truncated")?;
+                let length = read_u16(body, offset + 2).ok_or("Template record truncated")?;
+                offset += 4;
+                let enterprise = ipfix && raw_id & 0x8000 != 0;
+                if enterprise {
+                    offset += 4;
+                }
+                fields.push(TemplateField {
+                    element_id: raw_id & 0x7fff,
+                    length,
+                    enterprise,
+                });
+            }
+            if offset > body.len() {
+                return Err("Template record truncated".to_string());
+            }
+            self.templates.insert(key, fields);
+        }
+        Ok(())
+    }
+}
+
+struct ExportHeader {
+    /// Exporter boot time in unix milliseconds, used to resolve sysUpTime
+    /// relative timestamps.
+    boot_time_ms: i64,
+    export_time: DateTime<Utc>,
+}
+
+fn decode_v5(packet: &[u8], exporter: &str) -> Result<Vec<FlowRecord>, String> {
+    const HEADER_LEN: usize = 24;
+    const RECORD_LEN: usize = 48;
+
+    if packet.len() < HEADER_LEN {
+        return Err("NetFlow v5 header truncated".to_string());
+    }
+    let count = read_u16(packet, 2).unwrap_or(0) as usize;
+    if packet.len() < HEADER_LEN + count * RECORD_LEN {
+        return Err(format!("NetFlow v5 packet declares {} records but is truncated", count));
+    }
+    let sys_uptime = read_u32(packet, 4).unwrap_or(0) as i64;
+    let unix_secs = read_u32(packet, 8).unwrap_or(0) as i64;
+    let unix_nsecs = read_u32(packet, 12).unwrap_or(0) as i64;
+    let boot_time_ms = unix_secs * 1000 + unix_nsecs / 1_000_000 - sys_uptime;
+
+    let mut flows = Vec::with_capacity(count);
+    for index in 0..count {
+        let r = &packet[HEADER_LEN + index * RECORD_LEN..HEADER_LEN + (index + 1) * RECORD_LEN];
+        let first = read_u32(r, 24).unwrap_or(0) as i64;
+        let last = read_u32(r, 28).unwrap_or(0) as i64;
+        flows.push(FlowRecord {
+            flow_id: new_flow_id(),
+            exporter: exporter.to_string(),
+            format: FlowFormat::NetFlowV5,
+            source_ip: IpAddr::V4(Ipv4Addr::new(r[0], r[1], r[2], r[3])),
+            destination_ip: IpAddr::V4(Ipv4Addr::new(r[4], r[5], r[6], r[7])),
+            source_port: read_u16(r, 32).unwrap_or(0),
+            destination_port: read_u16(r, 34).unwrap_or(0),
+            protocol: r[38],
+            bytes: read_u32(r, 20).unwrap_or(0) as u64,
+            packets: read_u32(r, 16).unwrap_or(0) as u64,
+            tcp_flags: r[37],
+            flow_start: millis_to_datetime(boot_time_ms + first),
+            flow_end: millis_to_datetime(boot_time_ms + last),
+            input_interface: Some(read_u16(r, 12).unwrap_or(0) as u32),
+            output_interface: Some(read_u16(r, 14).unwrap_or(0) as u32),
+        });
+    }
+    Ok(flows)
+}
+
+fn flow_sets(packet: &[u8], header_len: usize) -> Result<Vec<(u16, &[u8])>, String> {
+    let mut sets = Vec::new();
+    let mut offset = header_len;
+    while offset + 4 <= packet.len() {
+        let set_id = read_u16(packet, offset).unwrap_or(0);
+        let length = read_u16(packet, offset + 2).unwrap_or(0) as usize;
+        if length < 4 || offset + length > packet.len() {
+            return Err(format!("Malformed flow set {} at offset {}", set_id, offset));
+        }
+        sets.push((set_id, &packet[offset + 4..offset + length]));
+        offset += length;
+    }
+    Ok(sets)
+}
+
+fn decode_data_set(
+    body: &[u8],
+    template: &[TemplateField],
+    header: &ExportHeader,
+    exporter: &str,
+    format: FlowFormat,
+) -> Vec<FlowRecord> {
+    let mut flows = Vec::new();
+    let mut offset = 0;
+    // Anything shorter than the smallest possible record is set padding.
+    let min_record_len: usize = template
+        .iter()
+        .map(|f| if f.length == u16::MAX { 1 } else { f.length as usize })
+        .sum();
+
+    while min_record_len > 0 && offset + min_record_len <= body.len() {
+        let mut builder = FlowBuilder::default();
+        let mut truncated = false;
+        for field in template {
+            let length = if field.length == u16::MAX {
+                // IPFIX variable-length encoding
+                let Some(&short) = body.get(offset) else { truncated = true; break };
+                offset += 1;
+                if short == 255 {
+                    let Some(long) = read_u16(body, offset) else { truncated = true; break };
+                    offset += 2;
+                    long as usize
+                } else {
+                    short as usize
+                }
+            } else {
+                field.length as usize
+            };
+            let Some(value) = body.get(offset..offset + length) else { truncated = true; break };
+            offset += length;
+            if !field.enterprise {
+                builder.apply(field.element_id, value);
+            }
+        }
+        if truncated {
+            break;
+        }
+        if let Some(flow) = builder.build(header, exporter, format) {
+            flows.push(flow);
+        }
+    }
+    flows
+}
+
+#[derive(Default)]
+struct FlowBuilder {
+    source_ip: Option<IpAddr>,
+    destination_ip: Option<IpAddr>,
+    source_port: u16,
+    destination_port: u16,
+    protocol: u8,
+    bytes: Option<u64>,
+    packets: Option<u64>,
+    tcp_flags: u8,
+    start_uptime_ms: Option<i64>,
+    end_uptime_ms: Option<i64>,
+    start_ms: Option<i64>,
+    end_ms: Option<i64>,
+    input_interface: Option<u32>,
+    output_interface: Option<u32>,
+}
+
+impl FlowBuilder {
+    fn apply(&mut self, element_id: u16, value: &[u8]) {
+        let number = read_uint(value);
+        match element_id {
+            1 => self.bytes = Some(number),                          // octetDeltaCount / IN_BYTES
+            2 => self.packets = Some(number),                        // packetDeltaCount / IN_PKTS
+            85 => self.bytes = self.bytes.or(Some(number)),          // octetTotalCount
+            86 => self.packets = self.packets.or(Some(number)),      // packetTotalCount
+            4 => self.protocol = number as u8,                       // protocolIdentifier
+            6 => self.tcp_flags = number as u8,                      // tcpControlBits
+            7 => self.source_port = number as u16,                   // sourceTransportPort
+            11 => self.destination_port = number as u16,             // destinationTransportPort
+            8 if value.len() == 4 => self.source_ip = Some(IpAddr::V4(Ipv4Addr::new(value[0], value[1], value[2], value[3]))),
+            12 if value.len() == 4 => self.destination_ip = Some(IpAddr::V4(Ipv4Addr::new(value[0], value[1], value[2], value[3]))),
+            27 => self.source_ip = self.source_ip.or(read_ipv6(value)),
+            28 => self.destination_ip = self.destination_ip.or(read_ipv6(value)),
+            10 => self.input_interface = Some(number as u32),        // ingressInterface
+            14 => self.output_interface = Some(number as u32),       // egressInterface
+            21 => self.end_uptime_ms = Some(number as i64),          // flowEndSysUpTime / LAST_SWITCHED
+            22 => self.start_uptime_ms = Some(number as i64),        // flowStartSysUpTime / FIRST_SWITCHED
+            150 => self.start_ms = Some(number as i64 * 1000),       // flowStartSeconds
+            151 => self.end_ms = Some(number as i64 * 1000),         // flowEndSeconds
+            152 => self.start_ms = Some(number as i64),              // flowStartMilliseconds
+            153 => self.end_ms = Some(number as i64),                // flowEndMilliseconds
+            _ => {}
+        }
+    }
+
+    fn build(self, header: &ExportHeader, exporter: &str, format: FlowFormat) -> Option<FlowRecord> {
+        let source_ip = self.source_ip?;
+        let destination_ip = self.destination_ip?;
+
+        let flow_end = self
+            .end_ms
+            .or(self.end_uptime_ms.map(|ms| header.boot_time_ms + ms))
+            .map(millis_to_datetime)
+            .unwrap_or(header.export_time);
+        let flow_start = self
+            .start_ms
+            .or(self.start_uptime_ms.map(|ms| header.boot_time_ms + ms))
+            .map(millis_to_datetime)
+            .unwrap_or(flow_end);
+
+        Some(FlowRecord {
+            flow_id: new_flow_id(),
+            exporter: exporter.to_string(),
+            format,
+            source_ip,
+            destination_ip,
+            source_port: self.source_port,
+            destination_port: self.destination_port,
+            protocol: self.protocol,
+            bytes: self.bytes.unwrap_or(0),
+            packets: self.packets.unwrap_or(0),
+            tcp_flags: self.tcp_flags,
+            flow_start,
+            flow_end,
+            input_interface: self.input_interface,
+            output_interface: self.output_interface,
+        })
+    }
+}
+
+fn read_u16(buf: &[u8], offset: usize) -> Option<u16> {
+    buf.get(offset..offset + 2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> Option<u32> {
+    buf.get(offset..offset + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_uint(value: &[u8]) -> u64 {
+    value.iter().take(8).fold(0u64, |acc, b| (acc << 8) | *b as u64)
+}
+
+fn read_ipv6(value: &[u8]) -> Option<IpAddr> {
+    let octets: [u8; 16] = value.try_into().ok()?;
+    Some(IpAddr::V6(Ipv6Addr::from(octets)))
+}
+
+fn millis_to_datetime(ms: i64) -> DateTime<Utc> {
+    Utc.timestamp_millis_opt(ms).single().unwrap_or_else(Utc::now)
+}
+
+// Flow Analytics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowAnalyticsConfig {
+    /// CIDR ranges treated as inside the organisation's border.
+    pub internal_networks: Vec<String>,
+    /// Minimum conversation span for the long-lived low-volume analytic.
+    pub long_lived_min_duration_seconds: i64,
+    /// Conversations averaging above this rate are not "low volume".
+    pub long_lived_max_bytes_per_second: f64,
+    /// Window used to bucket flows for scan detection.
+    pub scan_window_seconds: i64,
+    /// Distinct destination ports on one host before a vertical scan fires.
+    pub vertical_scan_port_threshold: usize,
+    /// Distinct hosts on one port before a horizontal sweep fires.
+    pub horizontal_scan_host_threshold: usize,
+    /// Scan flows are small; flows averaging more packets are ignored.
+    pub scan_max_packets_per_flow: f64,
+    /// Minimum outbound bytes before asymmetry is considered.
+    pub asymmetry_min_outbound_bytes: u64,
+    /// Outbound / inbound byte ratio that flags a border-crossing pair.
+    pub asymmetry_ratio_threshold: f64,
+}
+
+impl Default for FlowAnalyticsConfig {
+    fn default() -> Self {
+        Self {
+            internal_networks: vec![
+                "10.0.0.0/8".to_string(),
+                "172.16.0.0/12".to_string(),
+                "192.168.0.0/16".to_string(),
+                "fc00::/7".to_string(),
+            ],
+            long_lived_min_duration_seconds: 3600,
+            long_lived_max_bytes_per_second: 100.0,
+            scan_window_seconds: 300,
+            vertical_scan_port_threshold: 25,
+            horizontal_scan_host_threshold: 25,
+            scan_max_packets_per_flow: 3.0,
+            asymmetry_min_outbound_bytes: 10_000_000,
+            asymmetry_ratio_threshold: 10.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowFinding {
+    pub finding_id: String,
+    pub finding_type: FlowFindingType,
+    pub severity: HuntingSeverity,
+    pub source_ip: String,
+    pub destination_ip: Option<String>,
+    pub description: String,
+    pub mitre_technique: String,
+    pub flow_count: usize,
+    pub total_bytes: u64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub evidence: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlowFindingType {
+    LongLivedLowVolume,
+    VerticalPortScan,
+    HorizontalPortScan,
+    BorderAsymmetry,
+}
+
+/// Run every built-in flow analytic over `flows`.
+pub fn analyze_flows(flows: &[FlowRecord], config: &FlowAnalyticsConfig) -> Vec<FlowFinding> {
+    let mut findings = detect_long_lived_low_volume(flows, config);
+    findings.extend(detect_port_scans(flows, config));
+    findings.extend(detect_border_asymmetry(flows, config));
+    findings
+}
+
+/// Conversations that stay up for a long time while moving very little data
+/// are characteristic of C2 keep-alives and low-and-slow tunnels.
+pub fn detect_long_lived_low_volume(flows: &[FlowRecord], config: &FlowAnalyticsConfig) -> Vec<FlowFinding> {
+    let mut conversations: HashMap<(IpAddr, IpAddr, u16, u8), Vec<&FlowRecord>> = HashMap::new();
+    for flow in flows {
+        conversations
+            .entry((flow.source_ip, flow.destination_ip, flow.destination_port, flow.protocol))
+            .or_default()
+            .push(flow);
+    }
+
+    let mut findings = Vec::new();
+    for ((src, dst, port, protocol), group) in conversations {
+        let (first_seen, last_seen) = time_bounds(&group);
+        let span_seconds = (last_seen - first_seen).num_seconds();
+        if span_seconds < config.long_lived_min_duration_seconds {
+            continue;
+        }
+        let total_bytes: u64 = group.iter().map(|f| f.bytes).sum();
+        let bytes_per_second = total_bytes as f64 / span_seconds.max(1) as f64;
+        if bytes_per_second > config.long_lived_max_bytes_per_second {
+            continue;
+        }
+
+        findings.push(FlowFinding {
+            finding_id: Uuid::new_v4().to_string(),
+            finding_type: FlowFindingType::LongLivedLowVolume,
+            severity: HuntingSeverity::Medium,
+            source_ip: src.to_string(),
+            destination_ip: Some(dst.to_string()),
+            description: format!(
+                "{} held a {}s conversation with {}:{} averaging {:.1} bytes/s",
+                src, span_seconds, dst, port, bytes_per_second
+            ),
+            mitre_technique: "T1071".to_string(),
+            flow_count: group.len(),
+            total_bytes,
+            first_seen,
+            last_seen,
+            evidence: HashMap::from([
+                ("destination_port".to_string(), serde_json::json!(port)),
+                ("protocol_number".to_string(), serde_json::json!(protocol)),
+                ("span_seconds".to_string(), serde_json::json!(span_seconds)),
+                ("bytes_per_second".to_string(), serde_json::json!(bytes_per_second)),
+            ]),
+        });
+    }
+    findings
+}
+
+/// Vertical scans (many ports on one host) and horizontal sweeps (one port
+/// across many hosts) from a single source inside a scan window.
+pub fn detect_port_scans(flows: &[FlowRecord], config: &FlowAnalyticsConfig) -> Vec<FlowFinding> {
+    let window_ms = config.scan_window_seconds.max(1) * 1000;
+    let mut buckets: HashMap<(IpAddr, i64), Vec<&FlowRecord>> = HashMap::new();
+    for flow in flows {
+        let packets_per_flow = flow.packets.max(1) as f64;
+        if packets_per_flow > config.scan_max_packets_per_flow {
+            continue;
+        }
+        let bucket = flow.flow_start.timestamp_millis().div_euclid(window_ms);
+        buckets.entry((flow.source_ip, bucket)).or_default().push(flow);
+    }
+
+    let mut findings = Vec::new();
+    for ((src, _), group) in buckets {
+        let mut ports_by_host: HashMap<IpAddr, HashSet<u16>> = HashMap::new();
+        let mut hosts_by_port: HashMap<u16, HashSet<IpAddr>> = HashMap::new();
+        for flow in &group {
+            ports_by_host.entry(flow.destination_ip).or_default().insert(flow.destination_port);
+            hosts_by_port.entry(flow.destination_port).or_default().insert(flow.destination_ip);
+        }
+
+        for (dst, ports) in ports_by_host {
+            if ports.len() < config.vertical_scan_port_threshold {
+                continue;
+            }
+            let scan_flows: Vec<&FlowRecord> = group.iter().copied().filter(|f| f.destination_ip == dst).collect();
+            let (first_seen, last_seen) = time_bounds(&scan_flows);
+            findings.push(FlowFinding {
+                finding_id: Uuid::new_v4().to_string(),
+                finding_type: FlowFindingType::VerticalPortScan,
+                severity: HuntingSeverity::High,
+                source_ip: src.to_string(),
+                destination_ip: Some(dst.to_string()),
+                description: format!("{} probed {} distinct ports on {}", src, ports.len(), dst),
+                mitre_technique: "T1046".to_string(),
+                flow_count: scan_flows.len(),
+                total_bytes: scan_flows.iter().map(|f| f.bytes).sum(),
+                first_seen,
+                last_seen,
+                evidence: HashMap::from([("distinct_ports".to_string(), serde_json::json!(ports.len()))]),
+            });
+        }
+
+        for (port, hosts) in hosts_by_port {
+            if hosts.len() < config.horizontal_scan_host_threshold {
+                continue;
+            }
+            let scan_flows: Vec<&FlowRecord> = group.iter().copied().filter(|f| f.destination_port == port).collect();
+            let (first_seen, last_seen) = time_bounds(&scan_flows);
+            findings.push(FlowFinding {
+                finding_id: Uuid::new_v4().to_string(),
+                finding_type: FlowFindingType::HorizontalPortScan,
+                severity: HuntingSeverity::High,
+                source_ip: src.to_string(),
+                destination_ip: None,
+                description: format!("{} swept port {} across {} hosts", src, port, hosts.len()),
+                mitre_technique: "T1046".to_string(),
+                flow_count: scan_flows.len(),
+                total_bytes: scan_flows.iter().map(|f| f.bytes).sum(),
+                first_seen,
+                last_seen,
+                evidence: HashMap::from([
+                    ("destination_port".to_string(), serde_json::json!(port)),
+                    ("distinct_hosts".to_string(), serde_json::json!(hosts.len())),
+                ]),
+            });
+        }
+    }
+    findings
+}
+
+/// Internal hosts sending far more data across the border than they receive
+/// back from the same external peer.
+pub fn detect_border_asymmetry(flows: &[FlowRecord], config: &FlowAnalyticsConfig) -> Vec<FlowFinding> {
+    let networks: Vec<IpNetwork> = config.internal_networks.iter().filter_map(|c| IpNetwork::parse(c)).collect();
+    let is_internal = |ip: &IpAddr| networks.iter().any(|n| n.contains(ip));
+
+    #[derive(Default)]
+    struct PairStats<'a> {
+        outbound: u64,
+        inbound: u64,
+        flows: Vec<&'a FlowRecord>,
+    }
+
+    let mut pairs: HashMap<(IpAddr, IpAddr), PairStats> = HashMap::new();
+    for flow in flows {
+        let src_internal = is_internal(&flow.source_ip);
+        let dst_internal = is_internal(&flow.destination_ip);
+        if src_internal == dst_internal {
+            continue;
+        }
+        if src_internal {
+            let stats = pairs.entry((flow.source_ip, flow.destination_ip)).or_default();
+            stats.outbound += flow.bytes;
+            stats.flows.push(flow);
+        } else {
+            let stats = pairs.entry((flow.destination_ip, flow.source_ip)).or_default();
+            stats.inbound += flow.bytes;
+            stats.flows.push(flow);
+        }
+    }
+
+    let mut findings = Vec::new();
+    for ((internal, external), stats) in pairs {
+        if stats.outbound < config.asymmetry_min_outbound_bytes {
+            continue;
+        }
+        let ratio = stats.outbound as f64 / stats.inbound.max(1) as f64;
+        if ratio < config.asymmetry_ratio_threshold {
+            continue;
+        }
+        let (first_seen, last_seen) = time_bounds(&stats.flows);
+        findings.push(FlowFinding {
+            finding_id: Uuid::new_v4().to_string(),
+            finding_type: FlowFindingType::BorderAsymmetry,
+            severity: if ratio >= config.asymmetry_ratio_threshold * 10.0 {
+                HuntingSeverity::Critical
+            } else {
+                HuntingSeverity::High
+            },
+            source_ip: internal.to_string(),
+            destination_ip: Some(external.to_string()),
+            description: format!(
+                "{} sent {} bytes to external host {} and received {} ({:.1}x asymmetry)",
+                internal, stats.outbound, external, stats.inbound, ratio
+            ),
+            mitre_technique: "T1048".to_string(),
+            flow_count: stats.flows.len(),
+            total_bytes: stats.outbound + stats.inbound,
+            first_seen,
+            last_seen,
+            evidence: HashMap::from([
+                ("bytes_outbound".to_string(), serde_json::json!(stats.outbound)),
+                ("bytes_inbound".to_string(), serde_json::json!(stats.inbound)),
+                ("asymmetry_ratio".to_string(), serde_json::json!(ratio)),
+            ]),
+        });
+    }
+    findings
+}
+
+fn time_bounds(flows: &[&FlowRecord]) -> (DateTime<Utc>, DateTime<Utc>) {
+    let first = flows.iter().map(|f| f.flow_start).min().unwrap_or_else(Utc::now);
+    let last = flows.iter().map(|f| f.flow_end).max().unwrap_or(first);
+    (first, last)
+}
+
+struct IpNetwork {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl IpNetwork {
+    fn parse(cidr: &str) -> Option<Self> {
+        let (addr, prefix) = cidr.split_once('/').unwrap_or((cidr, ""));
+        let network: IpAddr = addr.trim().parse().ok()?;
+        let max_prefix = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = if prefix.is_empty() { max_prefix } else { prefix.trim().parse().ok()? };
+        (prefix <= max_prefix).then_some(Self { network, prefix })
+    }
+
+    fn contains(&self, ip: &IpAddr) -> bool {
+        match (self.network, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(*ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(*ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+// Hunting Core Integration
+
+/// Oldest flows of a tenant are dropped once its flow store reaches this size.
+const MAX_RETAINED_FLOWS: usize = 500_000;
+
+impl HuntingCore {
+    /// Decode a raw NetFlow v5/v9 or IPFIX export packet and store the flows
+    /// for `tenant_id`; templates are only applied to the same tenant's exports.
+    pub async fn ingest_flow_packet(&self, tenant_id: &str, packet: &[u8], exporter: &str) -> Result<usize, String> {
+        let flows = {
+            let mut decoders = self.flow_decoders.write().await;
+            decoders.entry(tenant_id.to_string()).or_default().decode(packet, exporter)?
+        };
+        self.ingest_flow_records(tenant_id, flows).await
+    }
+
+    /// Store already-decoded flow records (e.g. from an external collector).
+    pub async fn ingest_flow_records(&self, tenant_id: &str, records: Vec<FlowRecord>) -> Result<usize, String> {
+        let count = records.len();
+        let mut store = self.flow_records.write().await;
+        let flows = store.entry(tenant_id.to_string()).or_default();
+        flows.extend(records);
+        if flows.len() > MAX_RETAINED_FLOWS {
+            let excess = flows.len() - MAX_RETAINED_FLOWS;
+            flows.drain(..excess);
+        }
+        Ok(count)
+    }
+
+    pub async fn run_flow_analytics(&self, tenant_id: &str, config: Option<FlowAnalyticsConfig>) -> Result<Vec<FlowFinding>, String> {
+        let config = config.unwrap_or_default();
+        let store = self.flow_records.read().await;
+        Ok(analyze_flows(store.get(tenant_id).map_or(&[], Vec::as_slice), &config))
+    }
+
+    pub async fn get_flow_count(&self, tenant_id: &str) -> usize {
+        self.flow_records.read().await.get(tenant_id).map_or(0, Vec::len)
+    }
+
+    /// Drop the tenant's flows and exporter templates; returns how many flows were removed
+    pub(crate) async fn forget_tenant_flows(&self, tenant_id: &str) -> usize {
+        self.flow_decoders.write().await.remove(tenant_id);
+        self.flow_records.write().await.remove(tenant_id).map_or(0, |flows| flows.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flow(src: &str, dst: &str, dst_port: u16, bytes: u64, packets: u64, start: i64, end: i64) -> FlowRecord {
+        FlowRecord {
+            flow_id: new_flow_id(),
+            exporter: "test".to_string(),
+            format: FlowFormat::JSON,
+            source_ip: src.parse().unwrap(),
+            destination_ip: dst.parse().unwrap(),
+            source_port: 40000,
+            destination_port: dst_port,
+            protocol: 6,
+            bytes,
+            packets,
+            tcp_flags: 0x02,
+            flow_start: Utc.timestamp_opt(start, 0).unwrap(),
+            flow_end: Utc.timestamp_opt(end, 0).unwrap(),
+            input_interface: None,
+            output_interface: None,
+        }
+    }
+
+    #[test]
+    fn test_decode_netflow_v5() {
+        let mut packet = vec![0u8; 24 + 48];
+        packet[0..2].copy_from_slice(&5u16.to_be_bytes());
+        packet[2..4].copy_from_slice(&1u16.to_be_bytes());
+        packet[4..8].copy_from_slice(&10_000u32.to_be_bytes());
+        packet[8..12].copy_from_slice(&1_700_000_000u32.to_be_bytes());
+        let r = &mut packet[24..];
+        r[0..4].copy_from_slice(&[10, 0, 0, 5]);
+        r[4..8].copy_from_slice(&[203, 0, 113, 9]);
+        r[16..20].copy_from_slice(&12u32.to_be_bytes());
+        r[20..24].copy_from_slice(&4096u32.to_be_bytes());
+        r[24..28].copy_from_slice(&2_000u32.to_be_bytes());
+        r[28..32].copy_from_slice(&9_000u32.to_be_bytes());
+        r[32..34].copy_from_slice(&51515u16.to_be_bytes());
+        r[34..36].copy_from_slice(&443u16.to_be_bytes());
+        r[37] = 0x12;
+        r[38] = 6;
+
+        let flows = FlowDecoder::new().decode(&packet, "router-1").unwrap();
+        assert_eq!(flows.len(), 1);
+        let f = &flows[0];
+        assert_eq!(f.source_ip.to_string(), "10.0.0.5");
+        assert_eq!(f.destination_port, 443);
+        assert_eq!(f.bytes, 4096);
+        assert_eq!(f.duration().num_milliseconds(), 7_000);
+        assert_eq!(f.tcp_flag_names(), vec!["SYN", "ACK"]);
+    }
+
+    #[test]
+    fn test_decode_ipfix_with_template() {
+        let fields: [(u16, u16); 6] = [(8, 4), (12, 4), (7, 2), (11, 2), (4, 1), (1, 8)];
+        let mut template_set = Vec::new();
+        template_set.extend_from_slice(&256u16.to_be_bytes());
+        template_set.extend_from_slice(&(fields.len() as u16).to_be_bytes());
+        for (id, len) in fields {
+            template_set.extend_from_slice(&id.to_be_bytes());
+            template_set.extend_from_slice(&len.to_be_bytes());
+        }
+        let mut record = vec![192, 168, 1, 10, 198, 51, 100, 7];
+        record.extend_from_slice(&53000u16.to_be_bytes());
+        record.extend_from_slice(&22u16.to_be_bytes());
+        record.push(6);
+        record.extend_from_slice(&1500u64.to_be_bytes());
+
+        let mut packet = vec![0u8; 16];
+        packet[0..2].copy_from_slice(&10u16.to_be_bytes());
+        packet[4..8].copy_from_slice(&1_700_000_000u32.to_be_bytes());
+        for (set_id, body) in [(2u16, &template_set), (256u16, &record)] {
+            packet.extend_from_slice(&set_id.to_be_bytes());
+            packet.extend_from_slice(&((body.len() + 4) as u16).to_be_bytes());
+            packet.extend_from_slice(body);
+        }
+        let length = packet.len() as u16;
+        packet[2..4].copy_from_slice(&length.to_be_bytes());
+
+        let mut decoder = FlowDecoder::new();
+        let flows = decoder.decode(&packet, "collector").unwrap();
+        assert_eq!(decoder.template_count(), 1);
+        assert_eq!(flows.len(), 1);
+        assert_eq!(flows[0].destination_ip.to_string(), "198.51.100.7");
+        assert_eq!(flows[0].destination_port, 22);
+        assert_eq!(flows[0].bytes, 1500);
+    }
+
+    #[test]
+    fn test_flow_analytics() {
+        let config = FlowAnalyticsConfig::default();
+        let mut flows = Vec::new();
+        for port in 1..=30 {
+            flows.push(flow("10.0.0.66", "10.0.0.1", port, 60, 1, 1_000, 1_001));
+        }
+        flows.push(flow("10.0.0.7", "203.0.113.50", 4444, 2_000, 40, 0, 7_200));
+        flows.push(flow("10.0.0.8", "198.51.100.20", 8443, 50_000_000, 40_000, 0, 600));
+        flows.push(flow("198.51.100.20", "10.0.0.8", 51000, 100_000, 800, 0, 600));
+
+        let findings = analyze_flows(&flows, &config);
+        let has = |t: FlowFindingType, src: &str| findings.iter().any(|f| f.finding_type == t && f.source_ip == src);
+        assert!(has(FlowFindingType::VerticalPortScan, "10.0.0.66"));
+        assert!(has(FlowFindingType::LongLivedLowVolume, "10.0.0.7"));
+        assert!(has(FlowFindingType::BorderAsymmetry, "10.0.0.8"));
+        assert!(!has(FlowFindingType::HorizontalPortScan, "10.0.0.66"));
+    }
+
+    #[tokio::test]
+    async fn test_flows_are_kept_per_tenant() {
+        let core = HuntingCore::new().unwrap();
+        let scan: Vec<FlowRecord> = (1..=30).map(|port| flow("10.0.0.66", "10.0.0.1", port, 60, 1, 1_000, 1_001)).collect();
+        assert_eq!(core.ingest_flow_records("acme", scan).await.unwrap(), 30);
+
+        assert!(!core.run_flow_analytics("acme", None).await.unwrap().is_empty());
+        assert!(core.run_flow_analytics("globex", None).await.unwrap().is_empty());
+        assert_eq!(core.get_flow_count("globex").await, 0);
+
+        let purged = core.purge_tenant("acme").await;
+        assert_eq!(purged["flow_records"], 30);
+        assert_eq!(core.get_flow_count("acme").await, 0);
+    }
+}
@@ -0,0 +1,130 @@
+//! Typed NAPI Objects
+//!
+//! Plain `#[napi(object)]` views of hunt results, returned by the `*_typed`
+//! method variants so TypeScript callers get generated types and skip the
+//! JSON string round-trip. The views carry the fields callers act on; the
+//! string methods still return the full result. Timestamps are RFC 3339
+//! strings and durations are milliseconds.
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use std::collections::HashMap;
+
+use crate::{HuntingCoreNapi, HuntingMatch, HuntingResult};
+
+/// One event a hunt matched
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct HuntMatch {
+    pub match_id: String,
+    pub timestamp: String,
+    pub source: String,
+    pub confidence_score: f64,
+    pub risk_score: f64,
+    pub event_data: HashMap<String, serde_json::Value>,
+    pub ml_scores: HashMap<String, f64>,
+}
+
+impl From<&HuntingMatch> for HuntMatch {
+    fn from(hunt_match: &HuntingMatch) -> Self {
+        Self {
+            match_id: hunt_match.match_id.clone(),
+            timestamp: hunt_match.timestamp.to_rfc3339(),
+            source: hunt_match.source.clone(),
+            confidence_score: hunt_match.confidence_score,
+            risk_score: hunt_match.risk_score,
+            event_data: hunt_match.event_data.clone(),
+            ml_scores: hunt_match.ml_scores.clone(),
+        }
+    }
+}
+
+/// Outcome of one hunt execution
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct HuntResult {
+    pub hunt_id: String,
+    pub tenant_id: String,
+    pub rule_id: String,
+    pub hunt_name: String,
+    pub executed_at: String,
+    pub duration_ms: i64,
+    pub data_sources_queried: Vec<String>,
+    pub total_events_processed: i64,
+    pub matches: Vec<HuntMatch>,
+    pub threat_score: f64,
+    pub threat_level: String,
+    pub recommended_actions: Vec<String>,
+    pub source_errors: Vec<String>,
+    pub suppressed_matches: i64,
+}
+
+impl From<&HuntingResult> for HuntResult {
+    fn from(result: &HuntingResult) -> Self {
+        Self {
+            hunt_id: result.hunt_id.clone(),
+            tenant_id: result.tenant_id.clone(),
+            rule_id: result.rule_id.clone(),
+            hunt_name: result.hunt_name.clone(),
+            executed_at: result.execution_timestamp.to_rfc3339(),
+            duration_ms: result.execution_duration.num_milliseconds(),
+            data_sources_queried: result.data_sources_queried.clone(),
+            total_events_processed: i64::try_from(result.total_events_processed).unwrap_or(i64::MAX),
+            matches: result.matches.iter().map(HuntMatch::from).collect(),
+            threat_score: result.threat_assessment.overall_threat_score,
+            threat_level: format!("{:?}", result.threat_assessment.threat_level),
+            recommended_actions: result.threat_assessment.recommended_actions.clone(),
+            source_errors: result.source_errors.clone(),
+            suppressed_matches: i64::try_from(result.suppressed_matches).unwrap_or(i64::MAX),
+        }
+    }
+}
+
+#[napi]
+impl HuntingCoreNapi {
+    /// Execute a hunt, returning a typed result
+    #[napi]
+    pub async fn execute_hunt_typed(&self, rule_id: String, data_context: Option<HashMap<String, serde_json::Value>>, auth_token: Option<String>) -> Result<HuntResult> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", &rule_id)?;
+        let params = serde_json::json!({ "data_context": data_context });
+
+        let result = self.inner.execute_hunt(&tenant_id, &rule_id, data_context).await;
+        let result = self.audit.record(&actor, "execute_hunt", &rule_id, params, result)
+            .map_err(|e| e.context("Failed to execute hunt"))?;
+        Ok(HuntResult::from(&result))
+    }
+
+    /// Get recent hunting results as typed objects
+    #[napi]
+    pub async fn get_hunt_results_typed(&self, limit: Option<u32>, auth_token: Option<String>) -> Result<Vec<HuntResult>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let results = self.inner.get_hunt_results(&tenant_id, limit.map(|l| l as usize)).await
+            .map_err(|e| e.context("Failed to get hunt results"))?;
+        Ok(results.iter().map(HuntResult::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HuntingCore;
+
+    #[tokio::test]
+    async fn hunt_result_carries_the_executed_hunt() {
+        let core = HuntingCore::new().unwrap();
+        let rule_id = core.list_rules("acme").await.unwrap()[0].id.clone();
+        let executed = core.execute_hunt("acme", &rule_id, None).await.unwrap();
+
+        let typed = HuntResult::from(&executed);
+        assert_eq!((typed.hunt_id.as_str(), typed.rule_id.as_str()), (executed.hunt_id.as_str(), rule_id.as_str()));
+        assert_eq!(typed.executed_at, executed.execution_timestamp.to_rfc3339());
+        assert_eq!(typed.duration_ms, executed.execution_duration.num_milliseconds());
+        assert_eq!(typed.total_events_processed as u64, executed.total_events_processed);
+        assert_eq!(typed.matches.len(), executed.matches.len());
+        for (typed_match, hunt_match) in typed.matches.iter().zip(&executed.matches) {
+            assert_eq!(typed_match.match_id, hunt_match.match_id);
+            assert_eq!(typed_match.event_data, hunt_match.event_data);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Prometheus Metrics
+//!
+//! With `phantom-enterprise-standards` enabled, executed hunts, their
+//! matches and durations, per-tenant detection rates and ingestion queue
+//! health are exposed in the Prometheus text format, either through
+//! `render_prometheus_metrics` or an embedded `/metrics` endpoint. Samples
+//! carry `core="hunting"`, the `tenant` and, for hunts, the rule's query
+//! language as `engine`; ingestion samples add the `source`. Without the
+//! feature nothing is collected.
+
+use crate::{HuntingCore, HuntingResult, QueryLanguage};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::HuntingCoreNapi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use napi_derive::napi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::prometheus::{labels, serve_metrics, CounterSet, HistogramSet, PrometheusWriter, RenderFn};
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+pub struct PrometheusState {
+    #[cfg(feature = "phantom-enterprise-standards")]
+    hunts: CounterSet,
+    #[cfg(feature = "phantom-enterprise-standards")]
+    matches: CounterSet,
+    #[cfg(feature = "phantom-enterprise-standards")]
+    events: CounterSet,
+    #[cfg(feature = "phantom-enterprise-standards")]
+    durations: HistogramSet,
+    #[cfg(feature = "phantom-enterprise-standards")]
+    endpoint: Mutex<Option<tokio::task::JoinHandle<()>>>,
+}
+
+impl PrometheusState {
+    pub(crate) fn forget_tenant(&self, tenant_id: &str) -> usize {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            [&self.hunts, &self.matches, &self.events].iter().map(|set| set.forget("tenant", tenant_id)).sum::<usize>()
+                + self.durations.forget("tenant", tenant_id)
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        {
+            let _ = tenant_id;
+            0
+        }
+    }
+}
+
+/// `engine` label of a rule's query language, e.g. `kql` or `sigma`
+pub fn engine_label(language: &QueryLanguage) -> String {
+    match language {
+        QueryLanguage::Custom(name) => name.to_lowercase(),
+        other => format!("{:?}", other).to_lowercase(),
+    }
+}
+
+impl HuntingCore {
+    pub(crate) async fn observe_hunt(&self, hunt_result: &HuntingResult) {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            let engine = self
+                .rules
+                .read()
+                .await
+                .get(&hunt_result.rule_id)
+                .map(|rule| engine_label(&rule.query.query_language))
+                .unwrap_or_else(|| "unknown".to_string());
+            let labels = labels(&[("tenant", &hunt_result.tenant_id), ("engine", &engine)]);
+            self.prometheus.hunts.inc(labels.clone(), 1.0);
+            self.prometheus.matches.inc(labels.clone(), hunt_result.matches.len() as f64);
+            self.prometheus.events.inc(labels.clone(), hunt_result.total_events_processed as f64);
+            self.prometheus.durations.observe(labels, hunt_result.execution_duration.num_milliseconds() as f64 / 1000.0);
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        let _ = hunt_result;
+    }
+
+    /// All tenants' hunting metrics in the Prometheus text format
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub async fn render_prometheus_metrics(&self) -> String {
+        let mut writer = PrometheusWriter::for_core("hunting");
+        writer.counter_set("phantom_hunting_hunts_total", "Hunts executed", &self.prometheus.hunts);
+        writer.counter_set("phantom_hunting_matches_total", "Matches found by hunts", &self.prometheus.matches);
+        writer.counter_set("phantom_hunting_events_processed_total", "Events scanned by hunts", &self.prometheus.events);
+        writer.histogram_set("phantom_hunting_hunt_duration_seconds", "Hunt execution duration", &self.prometheus.durations);
+
+        let mut tenants: Vec<(String, f64)> = self
+            .performance_metrics
+            .read()
+            .await
+            .iter()
+            .map(|(tenant, metrics)| (tenant.clone(), metrics.detection_rate))
+            .collect();
+        tenants.sort_by(|a, b| a.0.cmp(&b.0));
+        for (tenant, rate) in &tenants {
+            writer.gauge("phantom_hunting_detection_rate", "Matches per 100 events in the latest hunt", &[("tenant", tenant)], *rate);
+        }
+
+        for source in self.ingestion.source_metrics(None) {
+            let labels = [("tenant", source.tenant_id.as_str()), ("source", source.source.as_str())];
+            writer.gauge("phantom_hunting_ingestion_queue_depth", "Events waiting in the ingestion queue", &labels, source.queue_depth as f64);
+            writer.gauge("phantom_hunting_ingestion_queue_capacity", "Ingestion queue capacity", &labels, source.queue_capacity as f64);
+            for (outcome, count) in [
+                ("accepted", source.accepted),
+                ("dropped", source.dropped),
+                ("rejected", source.rejected),
+                ("parse_error", source.parse_errors),
+            ] {
+                let labels = [labels[0], labels[1], ("outcome", outcome)];
+                writer.counter("phantom_hunting_ingested_events_total", "Ingested records by outcome", &labels, count as f64);
+            }
+            writer.counter("phantom_hunting_continuous_matches_total", "Matches from continuous evaluation", &labels, source.matches as f64);
+        }
+        writer.finish()
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[napi]
+impl HuntingCoreNapi {
+    /// Platform-wide metrics in the Prometheus text exposition format
+    #[napi]
+    pub async fn render_prometheus_metrics(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize_platform(auth_token, "metrics")?;
+        Ok(self.inner.render_prometheus_metrics().await)
+    }
+
+    /// Serve `GET /metrics` on `bind`, e.g. `0.0.0.0:9464`, replacing any
+    /// running endpoint; returns the bound address. Pass no address to stop
+    #[napi]
+    pub async fn start_metrics_endpoint(&self, bind: Option<String>, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        let actor = self.authorize_platform(auth_token, "metrics")?;
+        if let Some(running) = self.inner.prometheus.endpoint.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            running.abort();
+        }
+        let details = serde_json::json!({ "bind": bind });
+        let Some(bind) = bind else {
+            return self.audit.record(&actor, "start_metrics_endpoint", "metrics", details, Ok(None));
+        };
+        let core = self.inner.clone();
+        let render: RenderFn = Arc::new(move || {
+            let core = core.clone();
+            Box::pin(async move { core.render_prometheus_metrics().await })
+        });
+        let served = serve_metrics(&bind, render).await;
+        let (address, handle) = self.audit.record(&actor, "start_metrics_endpoint", "metrics", details, served)
+            .map_err(napi::Error::from_reason)?;
+        *self.inner.prometheus.endpoint.lock().unwrap_or_else(|e| e.into_inner()) = Some(handle);
+        Ok(Some(address.to_string()))
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn hunts_are_exposed_with_core_tenant_and_engine_labels() {
+        let core = HuntingCore::new().unwrap();
+        let rule = core.list_rules("acme").await.unwrap().remove(0);
+        let result = core.execute_hunt("acme", &rule.id, None).await.unwrap();
+
+        let text = core.render_prometheus_metrics().await;
+        let labels = format!("{{core=\"hunting\",tenant=\"acme\",engine=\"{}\"}}", engine_label(&rule.query.query_language));
+        assert!(text.contains(&format!("phantom_hunting_hunts_total{} 1\n", labels)), "{}", text);
+        assert!(text.contains(&format!("phantom_hunting_matches_total{} {}\n", labels, result.matches.len())));
+        assert!(text.contains("# TYPE phantom_hunting_hunt_duration_seconds histogram\n"));
+        assert!(text.contains("phantom_hunting_detection_rate{core=\"hunting\",tenant=\"acme\"}"));
+
+        core.purge_tenant("acme").await;
+        assert!(!core.render_prometheus_metrics().await.contains("tenant=\"acme\""));
+    }
+}
@@ -0,0 +1,78 @@
+//! Cross-core reference resolution for hunts
+//!
+//! Lets an enterprise `ReferenceResolver` turn `hunt_…` ids (or legacy bare
+//! UUIDs) into summaries owned by the hunting core. Summaries carry the
+//! owning tenant; the NAPI lookup only resolves hunts of the caller's tenant.
+
+use async_trait::async_trait;
+use napi_derive::napi;
+use phantom_enterprise_standards::{EntityId, EntityKind, EntityResolver, EntitySummary};
+use std::collections::HashMap;
+
+use crate::{HuntingCore, HuntingCoreNapi};
+
+#[async_trait]
+impl EntityResolver for HuntingCore {
+    fn owner(&self) -> &str {
+        "phantom-hunting-core"
+    }
+
+    fn kinds(&self) -> Vec<EntityKind> {
+        vec![EntityKind::Hunt]
+    }
+
+    async fn resolve_entity(&self, id: &EntityId) -> Option<EntitySummary> {
+        self.resolve_reference(None, id).await
+    }
+}
+
+impl HuntingCore {
+    /// Resolve `id` among the hunts of `tenant_id`, or of every tenant
+    pub async fn resolve_reference(&self, tenant_id: Option<&str>, id: &EntityId) -> Option<EntitySummary> {
+        if id.kind().is_some_and(|kind| kind != EntityKind::Hunt) {
+            return None;
+        }
+
+        let results = self.hunt_results.read().await;
+        let result = results
+            .values()
+            .filter(|result| tenant_id.is_none_or(|t| t == result.tenant_id))
+            .find(|result| id.matches(&result.hunt_id))?;
+
+        let mut attributes = HashMap::new();
+        attributes.insert("tenant_id".to_string(), serde_json::json!(result.tenant_id));
+        attributes.insert("rule_id".to_string(), serde_json::json!(result.rule_id));
+        attributes.insert("match_count".to_string(), serde_json::json!(result.matches.len()));
+        attributes.insert(
+            "threat_score".to_string(),
+            serde_json::json!(result.threat_assessment.overall_threat_score),
+        );
+
+        Some(EntitySummary {
+            id: result.hunt_id.clone(),
+            kind: EntityKind::Hunt,
+            owner: self.owner().to_string(),
+            title: result.hunt_name.clone(),
+            status: Some(format!("{:?}", result.threat_assessment.threat_level)),
+            created_at: Some(result.execution_timestamp),
+            attributes,
+        })
+    }
+}
+
+#[napi]
+impl HuntingCoreNapi {
+    /// Resolve a hunt reference to its summary
+    #[napi]
+    pub async fn resolve_reference(&self, id: String, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let entity_id = EntityId::parse(&id)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to resolve reference: {}", e)))?;
+        match self.inner.resolve_reference(Some(&tenant_id), &entity_id).await {
+            Some(summary) => serde_json::to_string(&summary)
+                .map(Some)
+                .map_err(|e| napi::Error::from_reason(format!("Failed to serialize summary: {}", e))),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,396 @@
+//! Detections-as-code
+//!
+//! A tenant registers Git repositories of hunting rules. Syncing one checks
+//! out its pinned commit or branch head and loads every YAML and JSON file
+//! under `rules/` (below the repository's content path). A document with a
+//! `logsource` is a Sigma rule and is translated to KQL; any other document
+//! is a `HuntingRule`. JSON files may hold one rule or an array of them.
+//!
+//! Before anything is loaded each rule is validated: it needs an id unique
+//! across the revision and a name, may not replace another tenant's rule,
+//! and its test fixtures must be well-formed and pass. Every failure is
+//! reported against its file and document. A strict repository loads
+//! nothing when any file fails; otherwise the valid rules are loaded. Rules
+//! an earlier sync loaded that the revision no longer contains are removed
+//! — only when the revision loaded cleanly, since a rule whose file failed
+//! to parse cannot be told apart from a deleted one. The commit whose rules
+//! are active is recorded per repository.
+
+use chrono::Utc;
+use napi_derive::napi;
+use phantom_enterprise_standards::rule_sync::{
+    collect_files, FileError, GitCli, GitClient, LoadedItem, RepositoryStatus, RuleRepository, SourceFile, SourceFormat, SyncReport,
+};
+use serde::Deserialize;
+use serde_json::json;
+use serde_yaml::Value as Yaml;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+use crate::error::{CoreError, CoreResult};
+use crate::rule_tests::{test_rule_fixtures, validate_fixtures};
+use crate::sigma::{SigmaBackend, SigmaRule};
+use crate::{tenancy, HuntingCore, HuntingCoreNapi, HuntingRule};
+
+/// Directory of the repository content holding hunting rules
+pub const RULES_DIR: &str = "rules";
+
+pub struct RuleSyncState {
+    git: RwLock<Arc<dyn GitClient>>,
+    /// Registered repositories keyed by tenant and repository id. Held for
+    /// the whole of a sync, so syncs never share a working copy.
+    repositories: tokio::sync::Mutex<HashMap<(String, String), RepositoryStatus>>,
+}
+
+impl Default for RuleSyncState {
+    fn default() -> Self {
+        Self { git: RwLock::new(Arc::new(GitCli::default())), repositories: tokio::sync::Mutex::new(HashMap::new()) }
+    }
+}
+
+impl RuleSyncState {
+    fn git(&self) -> Arc<dyn GitClient> {
+        self.git.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub(crate) async fn forget_tenant(&self, tenant_id: &str) -> usize {
+        let mut repositories = self.repositories.lock().await;
+        let before = repositories.len();
+        repositories.retain(|(tenant, _), _| tenant != tenant_id);
+        before - repositories.len()
+    }
+}
+
+fn rule_from_yaml(doc: Yaml) -> Result<HuntingRule, String> {
+    if doc.get("logsource").is_some() {
+        SigmaRule::from_yaml(&doc)?.to_hunting_rule(SigmaBackend::KQL)
+    } else {
+        serde_yaml::from_value(doc).map_err(|e| format!("Invalid hunting rule: {}", e))
+    }
+}
+
+fn rule_from_json(item: serde_json::Value) -> Result<HuntingRule, String> {
+    if item.get("logsource").is_some() {
+        rule_from_yaml(serde_yaml::to_value(item).map_err(|e| format!("Invalid Sigma rule: {}", e))?)
+    } else {
+        serde_json::from_value(item).map_err(|e| format!("Invalid hunting rule: {}", e))
+    }
+}
+
+/// Rules of one content file, each with its document index or the reason it
+/// could not be read
+fn parse_rule_file(file: &SourceFile) -> Vec<(Option<usize>, Result<HuntingRule, String>)> {
+    match file.format {
+        SourceFormat::Json => match serde_json::from_str::<serde_json::Value>(&file.content) {
+            Ok(serde_json::Value::Array(items)) => {
+                items.into_iter().enumerate().map(|(index, item)| (Some(index), rule_from_json(item))).collect()
+            }
+            Ok(item) => vec![(None, rule_from_json(item))],
+            Err(e) => vec![(None, Err(format!("Invalid JSON: {}", e)))],
+        },
+        SourceFormat::Yaml => {
+            let mut rules = Vec::new();
+            for (index, document) in serde_yaml::Deserializer::from_str(&file.content).enumerate() {
+                match Yaml::deserialize(document) {
+                    Ok(Yaml::Null) => continue,
+                    Ok(doc) => rules.push((Some(index), rule_from_yaml(doc))),
+                    Err(e) => {
+                        rules.push((Some(index), Err(format!("Invalid YAML: {}", e))));
+                        break;
+                    }
+                }
+            }
+            rules
+        }
+    }
+}
+
+/// Checks a rule must pass before it is loaded from a repository
+fn validate_synced_rule(rule: &HuntingRule) -> Result<(), String> {
+    if rule.id.trim().is_empty() {
+        return Err("Rule has no id".to_string());
+    }
+    if rule.name.trim().is_empty() {
+        return Err(format!("Rule {} has no name", rule.id));
+    }
+    validate_fixtures(&rule.test_fixtures).map_err(|e| format!("Rule {}: {}", rule.id, e))?;
+    if !rule.test_fixtures.is_empty() {
+        let report = test_rule_fixtures(rule);
+        if !report.passed {
+            let failed: Vec<&str> = report.results.iter().filter(|result| !result.passed).map(|result| result.name.as_str()).collect();
+            return Err(format!("Rule {} failed test fixtures: {}", rule.id, failed.join(", ")));
+        }
+    }
+    Ok(())
+}
+
+impl HuntingCore {
+    /// Replace the Git client syncs check out repositories with
+    pub fn set_git_client(&self, git: Arc<dyn GitClient>) {
+        *self.rule_sync.git.write().unwrap_or_else(|e| e.into_inner()) = git;
+    }
+
+    /// Register a rule repository, or update its settings keeping the active commit
+    pub async fn register_rule_repository(&self, tenant_id: &str, repository: RuleRepository) -> CoreResult<RepositoryStatus> {
+        repository.validate().map_err(CoreError::validation)?;
+        let mut repositories = self.rule_sync.repositories.lock().await;
+        let status = repositories
+            .entry((tenant_id.to_string(), repository.repository_id.clone()))
+            .or_insert_with(|| RepositoryStatus::new(repository.clone(), tenant_id));
+        status.repository = repository;
+        Ok(status.clone())
+    }
+
+    /// Stop tracking a repository; rules it loaded stay in place
+    pub async fn remove_rule_repository(&self, tenant_id: &str, repository_id: &str) -> bool {
+        self.rule_sync.repositories.lock().await.remove(&(tenant_id.to_string(), repository_id.to_string())).is_some()
+    }
+
+    pub async fn list_rule_repositories(&self, tenant_id: &str) -> Vec<RepositoryStatus> {
+        let mut statuses: Vec<RepositoryStatus> =
+            self.rule_sync.repositories.lock().await.values().filter(|status| status.tenant_id == tenant_id).cloned().collect();
+        statuses.sort_by(|a, b| a.repository.repository_id.cmp(&b.repository.repository_id));
+        statuses
+    }
+
+    /// Check out a repository and load its rules. Checkout failures and
+    /// invalid files are reported, not returned as errors.
+    pub async fn sync_rule_repository(&self, tenant_id: &str, repository_id: &str) -> CoreResult<SyncReport> {
+        let mut repositories = self.rule_sync.repositories.lock().await;
+        let status = repositories
+            .get_mut(&(tenant_id.to_string(), repository_id.to_string()))
+            .ok_or_else(|| CoreError::not_found(format!("Rule repository {} not found", repository_id)))?;
+        let repository = status.repository.clone();
+        let mut report = SyncReport::new(&repository, status.active_commit.clone());
+
+        let checkout = repository.working_dir(&format!("hunting-{}", tenant_id));
+        match self.rule_sync.git().checkout(&repository, &checkout).await {
+            Ok(commit) => report.commit_sha = Some(commit),
+            Err(e) => {
+                log::warn!("Failed to check out rule repository {} for tenant {}: {}", repository_id, tenant_id, e);
+                report.error = Some(e);
+                let report = report.finish(false);
+                status.last_sync = Some(report.clone());
+                return Ok(report);
+            }
+        }
+
+        let (files, errors) = collect_files(&repository.content_dir(&checkout, RULES_DIR));
+        report.errors = errors;
+        let mut parsed: Vec<(String, HuntingRule)> = Vec::new();
+        let mut defined_in: HashMap<String, String> = HashMap::new();
+        for file in &files {
+            for (document, rule) in parse_rule_file(file) {
+                let rule = rule.and_then(|rule| validate_synced_rule(&rule).map(|_| rule)).and_then(|rule| {
+                    match defined_in.get(&rule.id) {
+                        Some(first) => Err(format!("Rule {} is already defined in {}", rule.id, first)),
+                        None => Ok(rule),
+                    }
+                });
+                match rule {
+                    Ok(rule) => {
+                        defined_in.insert(rule.id.clone(), file.path.clone());
+                        parsed.push((file.path.clone(), rule));
+                    }
+                    Err(error) => report.errors.push(FileError::new(&file.path, document, error)),
+                }
+            }
+        }
+
+        let mut rules = self.rules.write().await;
+        parsed.retain(|(path, rule)| match rules.get(&rule.id) {
+            Some(existing) if !tenancy::rule_writable(existing, tenant_id) => {
+                report.errors.push(FileError::new(path, None, format!("Rule {} belongs to another tenant", rule.id)));
+                false
+            }
+            _ => true,
+        });
+        if !report.may_apply(&repository) {
+            let report = report.finish(false);
+            status.last_sync = Some(report.clone());
+            return Ok(report);
+        }
+
+        let mut managed: HashSet<String> = HashSet::new();
+        for (path, mut rule) in parsed {
+            rule.tenant_id = Some(tenant_id.to_string());
+            report.loaded.push(LoadedItem { path, id: rule.id.clone(), replaced: rules.contains_key(&rule.id) });
+            managed.insert(rule.id.clone());
+            rules.insert(rule.id.clone(), rule);
+        }
+        for rule_id in std::mem::take(&mut status.managed_ids) {
+            if managed.contains(&rule_id) {
+                continue;
+            }
+            if !report.errors.is_empty() {
+                managed.insert(rule_id);
+            } else if rules.get(&rule_id).is_some_and(|rule| tenancy::rule_writable(rule, tenant_id)) {
+                rules.remove(&rule_id);
+                report.removed.push(rule_id);
+            }
+        }
+        drop(rules);
+
+        let report = report.finish(true);
+        log::info!(
+            "Synced rule repository {} at {} for tenant {}: {} loaded, {} removed, {} errors",
+            repository_id,
+            report.commit_sha.as_deref().unwrap_or_default(),
+            tenant_id,
+            report.loaded.len(),
+            report.removed.len(),
+            report.errors.len()
+        );
+        status.managed_ids = managed.into_iter().collect();
+        status.managed_ids.sort();
+        status.active_commit = report.commit_sha.clone();
+        status.synced_at = Some(Utc::now());
+        status.last_sync = Some(report.clone());
+        Ok(report)
+    }
+}
+
+#[napi]
+impl HuntingCoreNapi {
+    /// Register a Git repository of rules (JSON `RuleRepository`)
+    #[napi]
+    pub async fn register_rule_repository(&self, repository_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let repository: RuleRepository = serde_json::from_str(&repository_json)
+            .map_err(|e| CoreError::from(e).context("Failed to parse rule repository"))?;
+        let repository_id = repository.repository_id.clone();
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &repository_id)?;
+        let params = json!({ "url": repository.url, "branch": repository.branch, "pinned_commit": repository.pinned_commit });
+        let status = self.inner.register_rule_repository(&tenant_id, repository).await;
+        let status = self.audit.record(&actor, "register_rule_repository", &repository_id, params, status)
+            .map_err(|e| e.context("Failed to register rule repository"))?;
+        serde_json::to_string(&status)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize rule repository: {}", e)))
+    }
+
+    /// Stop syncing a repository; the rules it loaded are kept
+    #[napi]
+    pub async fn remove_rule_repository(&self, repository_id: String, auth_token: Option<String>) -> napi::Result<bool> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &repository_id)?;
+        let removed = self.inner.remove_rule_repository(&tenant_id, &repository_id).await;
+        self.audit.record(&actor, "remove_rule_repository", &repository_id, json!({}), Ok::<_, String>(removed))
+            .map_err(napi::Error::from_reason)
+    }
+
+    /// Registered repositories with the commit active in the engine and the last sync report
+    #[napi]
+    pub async fn list_rule_repositories(&self, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let statuses = self.inner.list_rule_repositories(&tenant_id).await;
+        serde_json::to_string(&statuses)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize rule repositories: {}", e)))
+    }
+
+    /// Pull a repository and load its rules; the report lists every file that failed validation
+    #[napi]
+    pub async fn sync_rule_repository(&self, repository_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &repository_id)?;
+        let report = self.inner.sync_rule_repository(&tenant_id, &repository_id).await;
+        let params = json!({
+            "commit_sha": report.as_ref().ok().and_then(|report| report.commit_sha.clone()),
+            "outcome": report.as_ref().ok().map(|report| report.outcome),
+        });
+        let report = self.audit.record(&actor, "sync_rule_repository", &repository_id, params, report)
+            .map_err(|e| e.context("Failed to sync rule repository"))?;
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize sync report: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use phantom_enterprise_standards::rule_sync::SyncOutcome;
+    use std::path::Path;
+    use std::sync::Mutex;
+
+    /// Commit id and the files it contains
+    type Revision = (String, Vec<(&'static str, String)>);
+
+    /// Serves revisions from memory: each checkout writes the next one
+    struct FakeGit {
+        revisions: Mutex<Vec<Revision>>,
+    }
+
+    #[async_trait]
+    impl GitClient for FakeGit {
+        async fn checkout(&self, _repository: &RuleRepository, dir: &Path) -> Result<String, String> {
+            let (commit, files) = self.revisions.lock().unwrap().remove(0);
+            let _ = std::fs::remove_dir_all(dir);
+            for (path, content) in files {
+                let path = dir.join(path);
+                std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+                std::fs::write(path, content).unwrap();
+            }
+            Ok(commit)
+        }
+    }
+
+    const SIGMA: &str = r#"
+title: Encoded PowerShell
+id: 5f3c1d2e-0000-4000-8000-0000000000aa
+logsource:
+  product: windows
+  category: process_creation
+detection:
+  selection:
+    CommandLine|contains: ' -enc '
+  condition: selection
+level: high
+"#;
+
+    #[tokio::test]
+    async fn test_sync_loads_valid_rules_and_records_the_commit() {
+        let core = HuntingCore::new().unwrap();
+        let tenant = tenancy::DEFAULT_TENANT;
+        let mut native = core.rules.read().await.get("data_exfiltration_detection").cloned().unwrap();
+        native.id = "git_exfiltration".to_string();
+        native.tenant_id = None;
+        let native = serde_json::to_string(&native).unwrap();
+
+        let git = FakeGit {
+            revisions: Mutex::new(vec![
+                ("a".repeat(40), vec![
+                    ("rules/windows/powershell.yml", SIGMA.to_string()),
+                    ("rules/exfil.json", native.clone()),
+                    ("rules/broken.yml", "title: [unclosed".to_string()),
+                ]),
+                ("b".repeat(40), vec![("rules/windows/powershell.yml", SIGMA.to_string())]),
+            ]),
+        };
+        core.set_git_client(Arc::new(git));
+        let checkout = std::env::temp_dir().join(format!("hunting-rule-sync-{}", uuid::Uuid::new_v4()));
+        let repository: RuleRepository = serde_json::from_value(json!({
+            "repository_id": "detections", "url": "https://git.example/detections.git", "checkout_dir": checkout,
+        }))
+        .unwrap();
+        core.register_rule_repository(tenant, repository).await.unwrap();
+
+        let report = core.sync_rule_repository(tenant, "detections").await.unwrap();
+        assert_eq!(report.outcome, SyncOutcome::PartiallyApplied);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].path, "broken.yml");
+        let mut loaded: Vec<&str> = report.loaded.iter().map(|item| item.id.as_str()).collect();
+        loaded.sort();
+        assert_eq!(loaded, ["git_exfiltration", "sigma_5f3c1d2e-0000-4000-8000-0000000000aa"]);
+        assert!(core.rules.read().await.contains_key("git_exfiltration"));
+
+        let report = core.sync_rule_repository(tenant, "detections").await.unwrap();
+        assert_eq!(report.outcome, SyncOutcome::Applied);
+        assert_eq!(report.previous_commit.as_deref(), Some("a".repeat(40).as_str()));
+        assert_eq!(report.removed, ["git_exfiltration"]);
+        assert!(!core.rules.read().await.contains_key("git_exfiltration"));
+        let status = &core.list_rule_repositories(tenant).await[0];
+        assert_eq!(status.active_commit.as_deref(), Some("b".repeat(40).as_str()));
+        assert_eq!(status.managed_ids, ["sigma_5f3c1d2e-0000-4000-8000-0000000000aa"]);
+        std::fs::remove_dir_all(&checkout).unwrap();
+    }
+}
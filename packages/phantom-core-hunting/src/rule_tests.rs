@@ -0,0 +1,382 @@
+//! Rule unit tests
+//!
+//! A rule can carry fixtures: small sets of events it should match and sets
+//! it should not. Testing a rule replays each fixture through its detection
+//! logic the way continuous hunting evaluates events — per-event conditions,
+//! or the correlator when the rule has correlation rules — and reports per
+//! fixture whether the expectation held. A failure comes with a diff: for
+//! per-event rules, every event whose outcome differs from the expectation
+//! together with how each condition evaluated on it; for correlation rules,
+//! the expected against the actual match count. Suppressions are not
+//! applied, so a test covers the rule's own logic only.
+
+use chrono::{DateTime, Utc};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+
+use crate::conditions::{self, EXCLUSION_OPERATORS};
+use crate::correlation::{self, RuleCorrelator};
+use crate::error::{CoreError, CoreResult};
+use crate::{shadow, tenancy, DetectionCondition, HuntingCore, HuntingCoreNapi, HuntingRule};
+
+/// Event diffs listed per failed fixture
+pub const MAX_EVENT_DIFFS: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FixtureExpectation {
+    ShouldMatch,
+    ShouldNotMatch,
+}
+
+/// A named set of events and what the rule should make of them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleFixture {
+    pub name: String,
+    pub expectation: FixtureExpectation,
+    /// JSON event records with an optional `timestamp` and `source`, as for event replay
+    pub events: Vec<Value>,
+    /// Exact number of matches a should-match fixture produces. Unset, every
+    /// event must match — or, for correlation rules, at least one match must result.
+    #[serde(default)]
+    pub expected_matches: Option<usize>,
+}
+
+/// How one condition evaluated on a fixture event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConditionOutcome {
+    pub condition_id: String,
+    pub field: String,
+    pub operator: String,
+    pub expected: Value,
+    /// Value of the field in the event; `None` when absent
+    pub actual: Option<Value>,
+    pub held: bool,
+    pub required: bool,
+}
+
+/// A fixture event whose outcome differs from the fixture's expectation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventDiff {
+    /// Position of the event in the fixture
+    pub index: usize,
+    pub expected_match: bool,
+    pub matched: bool,
+    pub conditions: Vec<ConditionOutcome>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixtureResult {
+    pub name: String,
+    pub expectation: FixtureExpectation,
+    pub passed: bool,
+    /// The expectation in words, e.g. `each of 3 events matches`
+    pub expected: String,
+    pub events: usize,
+    pub matches: usize,
+    pub event_diffs: Vec<EventDiff>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleTestReport {
+    pub rule_id: String,
+    pub rule_version: String,
+    pub passed: bool,
+    pub fixtures_passed: usize,
+    pub fixtures_failed: usize,
+    pub results: Vec<FixtureResult>,
+    pub tested_at: DateTime<Utc>,
+}
+
+/// Aggregate of every rule with fixtures, for CI gates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleTestSummary {
+    /// True when every fixture of every tested rule passed
+    pub passed: bool,
+    pub rules_tested: usize,
+    pub rules_passed: usize,
+    pub rules_failed: usize,
+    pub fixtures_run: usize,
+    pub fixtures_passed: usize,
+    /// Visible rules that carry no fixtures and were not tested
+    pub rules_without_fixtures: Vec<String>,
+    /// Failed fixtures as `rule_id/fixture name`
+    pub failures: Vec<String>,
+    pub reports: Vec<RuleTestReport>,
+    pub tested_at: DateTime<Utc>,
+}
+
+pub fn validate_fixtures(fixtures: &[RuleFixture]) -> CoreResult<()> {
+    let mut names = HashSet::new();
+    for fixture in fixtures {
+        let name = fixture.name.trim();
+        if name.is_empty() {
+            return Err(CoreError::validation("Fixture name is required"));
+        }
+        if !names.insert(name) {
+            return Err(CoreError::validation(format!("Duplicate fixture name: {}", name)));
+        }
+        if fixture.events.is_empty() {
+            return Err(CoreError::validation(format!("Fixture {} has no events", name)));
+        }
+        if let Some(index) = fixture.events.iter().position(|event| !event.is_object()) {
+            return Err(CoreError::validation(format!("Event {} of fixture {} is not a JSON object", index, name)));
+        }
+        match (fixture.expectation, fixture.expected_matches) {
+            (FixtureExpectation::ShouldNotMatch, Some(_)) => {
+                return Err(CoreError::validation(format!("Fixture {} should not match; it cannot expect a match count", name)));
+            }
+            (FixtureExpectation::ShouldMatch, Some(0)) => {
+                return Err(CoreError::validation(format!("Fixture {} expects no matches; make it should_not_match", name)));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn condition_outcomes(event: &HashMap<String, Value>, rule_conditions: &[DetectionCondition]) -> Vec<ConditionOutcome> {
+    rule_conditions
+        .iter()
+        .map(|condition| {
+            let actual = event.get(&condition.field).cloned();
+            let held = match &actual {
+                Some(actual) => conditions::condition_holds(actual, &condition.operator, &condition.value),
+                // Exclusions pass events without the field
+                None => condition.operator == "not_exists" || EXCLUSION_OPERATORS.contains(&condition.operator.as_str()),
+            };
+            ConditionOutcome {
+                condition_id: condition.condition_id.clone(),
+                field: condition.field.clone(),
+                operator: condition.operator.clone(),
+                expected: condition.value.clone(),
+                actual,
+                held,
+                required: condition.required,
+            }
+        })
+        .collect()
+}
+
+/// Replay one fixture through `rule`
+pub fn run_fixture(rule: &HuntingRule, fixture: &RuleFixture) -> FixtureResult {
+    let events = correlation::events_from_records(&fixture.events);
+    let matches = shadow::replay_events(rule, &events).len();
+    let correlated = RuleCorrelator::new(rule).is_some();
+    let should_match = fixture.expectation == FixtureExpectation::ShouldMatch;
+    let outcomes: Vec<bool> = events
+        .iter()
+        .map(|event| conditions::evaluate_conditions(&event.fields, &rule.detection_logic.conditions).is_some())
+        .collect();
+
+    let (expected, passed) = match (fixture.expectation, fixture.expected_matches) {
+        (FixtureExpectation::ShouldNotMatch, _) => ("no matches".to_string(), matches == 0),
+        (FixtureExpectation::ShouldMatch, Some(count)) => (format!("exactly {} matches", count), matches == count),
+        (FixtureExpectation::ShouldMatch, None) if correlated => ("at least one correlated match".to_string(), matches > 0),
+        (FixtureExpectation::ShouldMatch, None) => {
+            (format!("each of {} events matches", events.len()), outcomes.iter().all(|matched| *matched))
+        }
+    };
+
+    // Correlated matches span events, so only per-event rules get event diffs
+    let event_diffs = if passed || correlated {
+        Vec::new()
+    } else {
+        outcomes
+            .iter()
+            .enumerate()
+            .filter(|(_, matched)| **matched != should_match)
+            .take(MAX_EVENT_DIFFS)
+            .map(|(index, matched)| EventDiff {
+                index,
+                expected_match: should_match,
+                matched: *matched,
+                conditions: condition_outcomes(&events[index].fields, &rule.detection_logic.conditions),
+            })
+            .collect()
+    };
+
+    FixtureResult {
+        name: fixture.name.clone(),
+        expectation: fixture.expectation,
+        passed,
+        expected,
+        events: events.len(),
+        matches,
+        event_diffs,
+    }
+}
+
+/// Run every fixture `rule` carries
+pub fn test_rule_fixtures(rule: &HuntingRule) -> RuleTestReport {
+    let results: Vec<FixtureResult> = rule.test_fixtures.iter().map(|fixture| run_fixture(rule, fixture)).collect();
+    let fixtures_passed = results.iter().filter(|result| result.passed).count();
+    RuleTestReport {
+        rule_id: rule.id.clone(),
+        rule_version: rule.metadata.version.clone(),
+        passed: fixtures_passed == results.len(),
+        fixtures_passed,
+        fixtures_failed: results.len() - fixtures_passed,
+        results,
+        tested_at: Utc::now(),
+    }
+}
+
+impl HuntingCore {
+    /// Replace the fixtures of a rule the tenant may write
+    pub async fn set_rule_fixtures(&self, tenant_id: &str, rule_id: &str, fixtures: Vec<RuleFixture>) -> CoreResult<HuntingRule> {
+        validate_fixtures(&fixtures)?;
+        let mut rules = self.rules.write().await;
+        let rule = rules
+            .get_mut(rule_id)
+            .filter(|rule| tenancy::rule_writable(rule, tenant_id))
+            .ok_or_else(|| CoreError::not_found(format!("Rule {} not found", rule_id)))?;
+        rule.test_fixtures = fixtures;
+        Ok(rule.clone())
+    }
+
+    /// Run a rule's fixtures against its detection logic
+    pub async fn test_rule(&self, tenant_id: &str, rule_id: &str) -> CoreResult<RuleTestReport> {
+        let rule = self
+            .rules
+            .read()
+            .await
+            .get(rule_id)
+            .filter(|rule| tenancy::rule_visible(rule, tenant_id))
+            .cloned()
+            .ok_or_else(|| CoreError::not_found(format!("Rule {} not found", rule_id)))?;
+        if rule.test_fixtures.is_empty() {
+            return Err(CoreError::validation(format!("Rule {} has no test fixtures", rule_id)));
+        }
+        Ok(test_rule_fixtures(&rule))
+    }
+
+    /// Run the fixtures of every rule visible to the tenant
+    pub async fn test_all_rules(&self, tenant_id: &str) -> RuleTestSummary {
+        let mut rules: Vec<HuntingRule> = self
+            .rules
+            .read()
+            .await
+            .values()
+            .filter(|rule| tenancy::rule_visible(rule, tenant_id))
+            .cloned()
+            .collect();
+        rules.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let (tested, untested): (Vec<HuntingRule>, Vec<HuntingRule>) = rules.into_iter().partition(|rule| !rule.test_fixtures.is_empty());
+        let reports: Vec<RuleTestReport> = tested.iter().map(test_rule_fixtures).collect();
+        let failures: Vec<String> = reports
+            .iter()
+            .flat_map(|report| {
+                report.results.iter().filter(|result| !result.passed).map(move |result| format!("{}/{}", report.rule_id, result.name))
+            })
+            .collect();
+        let rules_passed = reports.iter().filter(|report| report.passed).count();
+        let summary = RuleTestSummary {
+            passed: failures.is_empty(),
+            rules_tested: reports.len(),
+            rules_passed,
+            rules_failed: reports.len() - rules_passed,
+            fixtures_run: reports.iter().map(|report| report.results.len()).sum(),
+            fixtures_passed: reports.iter().map(|report| report.fixtures_passed).sum(),
+            rules_without_fixtures: untested.into_iter().map(|rule| rule.id).collect(),
+            failures,
+            reports,
+            tested_at: Utc::now(),
+        };
+        log::info!(
+            "Rule tests for tenant {}: {}/{} fixtures passed across {} rules",
+            tenant_id, summary.fixtures_passed, summary.fixtures_run, summary.rules_tested
+        );
+        summary
+    }
+}
+
+#[napi]
+impl HuntingCoreNapi {
+    /// Replace a rule's test fixtures with a JSON array of `RuleFixture`
+    #[napi]
+    pub async fn set_rule_fixtures(&self, rule_id: String, fixtures_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let fixtures: Vec<RuleFixture> = serde_json::from_str(&fixtures_json)
+            .map_err(|e| CoreError::from(e).context("Failed to parse rule fixtures"))?;
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &rule_id)?;
+        let names: Vec<&str> = fixtures.iter().map(|fixture| fixture.name.as_str()).collect();
+        let params = json!({ "fixtures": names });
+        let rule = self.inner.set_rule_fixtures(&tenant_id, &rule_id, fixtures).await;
+        let rule = self.audit.record(&actor, "set_rule_fixtures", &rule_id, params, rule)
+            .map_err(|e| e.context("Failed to set rule fixtures"))?;
+        serde_json::to_string(&rule)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize rule: {}", e)))
+    }
+
+    /// Run one rule's fixtures; the report lists a diff for each failure
+    #[napi]
+    pub async fn test_rule(&self, rule_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let report = self.inner.test_rule(&tenant_id, &rule_id).await
+            .map_err(|e| e.context("Failed to test rule"))?;
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize rule test report: {}", e)))
+    }
+
+    /// Run the fixtures of every visible rule; `passed` is false when any fixture failed
+    #[napi]
+    pub async fn test_all_rules(&self, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let summary = self.inner.test_all_rules(&tenant_id).await;
+        serde_json::to_string(&summary)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize rule test summary: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fixtures_report_diffs_for_failures() {
+        let core = HuntingCore::new().unwrap();
+        let summary = core.test_all_rules(tenancy::DEFAULT_TENANT).await;
+        assert!(summary.passed, "built-in fixtures failed: {:?}", summary.failures);
+        assert!(summary.reports.iter().any(|report| report.rule_id == "data_exfiltration_detection"));
+
+        let fixtures: Vec<RuleFixture> = serde_json::from_value(json!([
+            { "name": "big upload", "expectation": "should_match",
+              "events": [{ "bytes_out": 50000000, "destination_port": 4444 }, { "bytes_out": 20000000, "destination_port": 443 }] },
+            { "name": "web traffic", "expectation": "should_not_match",
+              "events": [{ "bytes_out": 50000000, "destination_port": 443 }] }
+        ]))
+        .unwrap();
+        core.set_rule_fixtures(tenancy::DEFAULT_TENANT, "data_exfiltration_detection", fixtures).await.unwrap();
+
+        let report = core.test_rule(tenancy::DEFAULT_TENANT, "data_exfiltration_detection").await.unwrap();
+        assert!(!report.passed);
+        assert_eq!((report.fixtures_passed, report.fixtures_failed), (1, 1));
+        let failed = &report.results[0];
+        assert_eq!((failed.events, failed.matches), (2, 1));
+        assert_eq!(failed.event_diffs.len(), 1);
+        let diff = &failed.event_diffs[0];
+        assert_eq!((diff.index, diff.expected_match, diff.matched), (1, true, false));
+        let port = diff.conditions.iter().find(|c| c.condition_id == "non_standard_port").unwrap();
+        assert_eq!((port.actual.clone(), port.held), (Some(json!(443)), false));
+
+        let summary = core.test_all_rules(tenancy::DEFAULT_TENANT).await;
+        assert_eq!(summary.failures, vec!["data_exfiltration_detection/big upload".to_string()]);
+
+        let duplicate = vec![fixture("a"), fixture("a")];
+        assert!(core.set_rule_fixtures(tenancy::DEFAULT_TENANT, "data_exfiltration_detection", duplicate).await.is_err());
+        assert!(core.set_rule_fixtures("other", "data_exfiltration_detection", Vec::new()).await.is_err());
+    }
+
+    fn fixture(name: &str) -> RuleFixture {
+        RuleFixture {
+            name: name.to_string(),
+            expectation: FixtureExpectation::ShouldNotMatch,
+            events: vec![json!({})],
+            expected_matches: None,
+        }
+    }
+}
@@ -0,0 +1,406 @@
+//! Scheduled and continuous hunt execution
+//!
+//! Rules can be attached to a cron expression or a fixed interval. A tokio
+//! background task wakes up on a short tick, runs every hunt whose next-run
+//! time has passed and reschedules it with optional random jitter so rules
+//! sharing a schedule do not all fire on the same instant. A semaphore caps
+//! how many scheduled hunts may execute at once; due hunts that cannot get a
+//! slot simply wait for the next tick. Schedules belong to the tenant that
+//! created them and run the hunt on that tenant's behalf.
+
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use tokio::sync::{watch, Mutex, RwLock, Semaphore};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::{tenancy, HuntingCore};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HuntTrigger {
+    /// Standard 5-field cron (`min hour dom month dow`) or 6/7-field with seconds
+    Cron { expression: String },
+    Interval { every_secs: u64 },
+}
+
+impl HuntTrigger {
+    fn validate(&self) -> Result<(), String> {
+        match self {
+            HuntTrigger::Cron { expression } => parse_cron(expression).map(|_| ()),
+            HuntTrigger::Interval { every_secs: 0 } => Err("Interval must be at least one second".to_string()),
+            HuntTrigger::Interval { .. } => Ok(()),
+        }
+    }
+
+    /// First run strictly after `after`, before jitter
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            HuntTrigger::Cron { expression } => parse_cron(expression).ok()?.after(&after).next(),
+            HuntTrigger::Interval { every_secs } => {
+                Some(after + chrono::Duration::seconds((*every_secs).min(i64::MAX as u64) as i64))
+            }
+        }
+    }
+}
+
+fn parse_cron(expression: &str) -> Result<Schedule, String> {
+    let fields = expression.split_whitespace().count();
+    let normalized = if fields == 5 {
+        format!("0 {}", expression.trim())
+    } else {
+        expression.trim().to_string()
+    };
+    Schedule::from_str(&normalized).map_err(|e| format!("Invalid cron expression '{}': {}", expression, e))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HuntSchedule {
+    pub rule_id: String,
+    pub trigger: HuntTrigger,
+    /// Upper bound of the random delay added to each run
+    #[serde(default)]
+    pub jitter_secs: u64,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Run once as soon as the scheduler picks the schedule up
+    #[serde(default)]
+    pub run_immediately: bool,
+    #[serde(default)]
+    pub data_context: Option<HashMap<String, serde_json::Value>>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledHuntStatus {
+    pub rule_id: String,
+    #[serde(default = "crate::tenancy::default_tenant")]
+    pub tenant_id: String,
+    pub trigger: HuntTrigger,
+    pub enabled: bool,
+    pub next_run: Option<DateTime<Utc>>,
+    pub last_run: Option<DateTime<Utc>>,
+    pub last_hunt_id: Option<String>,
+    pub last_error: Option<String>,
+    pub run_count: u64,
+    pub failure_count: u64,
+    pub running: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchedulerConfig {
+    /// How often the scheduler checks for due hunts
+    pub tick_ms: u64,
+    pub max_concurrent_hunts: usize,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            tick_ms: 1_000,
+            max_concurrent_hunts: 4,
+        }
+    }
+}
+
+struct ScheduleEntry {
+    schedule: HuntSchedule,
+    status: ScheduledHuntStatus,
+}
+
+struct SchedulerRun {
+    stop: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+}
+
+/// Schedules attached to a hunting core and the background task running them
+#[derive(Default)]
+pub struct HuntScheduler {
+    entries: Arc<RwLock<HashMap<String, ScheduleEntry>>>,
+    run: Mutex<Option<SchedulerRun>>,
+}
+
+/// Key of a tenant's schedule for `rule_id`
+fn schedule_key(tenant_id: &str, rule_id: &str) -> String {
+    format!("{}:{}", tenant_id, rule_id)
+}
+
+fn with_jitter(at: DateTime<Utc>, jitter_secs: u64) -> DateTime<Utc> {
+    if jitter_secs == 0 {
+        return at;
+    }
+    let jitter_ms = (Uuid::new_v4().as_u128() % (jitter_secs as u128 * 1_000 + 1)) as i64;
+    at + chrono::Duration::milliseconds(jitter_ms)
+}
+
+fn first_run(schedule: &HuntSchedule, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if !schedule.enabled {
+        return None;
+    }
+    if schedule.run_immediately {
+        return Some(now);
+    }
+    schedule
+        .trigger
+        .next_after(now)
+        .map(|at| with_jitter(at, schedule.jitter_secs))
+}
+
+impl HuntingCore {
+    /// Attach (or replace) the tenant's schedule for a rule
+    pub async fn schedule_hunt(&self, tenant_id: &str, schedule: HuntSchedule) -> Result<ScheduledHuntStatus, String> {
+        schedule.trigger.validate()?;
+        let visible = self
+            .rules
+            .read()
+            .await
+            .get(&schedule.rule_id)
+            .is_some_and(|rule| tenancy::rule_visible(rule, tenant_id));
+        if !visible {
+            return Err(format!("Rule {} not found", schedule.rule_id));
+        }
+
+        let key = schedule_key(tenant_id, &schedule.rule_id);
+        let mut entries = self.scheduler.entries.write().await;
+        let previous = entries.remove(&key);
+        let status = ScheduledHuntStatus {
+            rule_id: schedule.rule_id.clone(),
+            tenant_id: tenant_id.to_string(),
+            trigger: schedule.trigger.clone(),
+            enabled: schedule.enabled,
+            next_run: first_run(&schedule, Utc::now()),
+            last_run: previous.as_ref().and_then(|p| p.status.last_run),
+            last_hunt_id: previous.as_ref().and_then(|p| p.status.last_hunt_id.clone()),
+            last_error: None,
+            run_count: previous.as_ref().map(|p| p.status.run_count).unwrap_or(0),
+            failure_count: previous.as_ref().map(|p| p.status.failure_count).unwrap_or(0),
+            running: previous.as_ref().is_some_and(|p| p.status.running),
+        };
+        entries.insert(
+            key,
+            ScheduleEntry {
+                schedule,
+                status: status.clone(),
+            },
+        );
+        Ok(status)
+    }
+
+    pub async fn unschedule_hunt(&self, tenant_id: &str, rule_id: &str) -> bool {
+        self.scheduler.entries.write().await.remove(&schedule_key(tenant_id, rule_id)).is_some()
+    }
+
+    /// Remove every schedule of `tenant_id`
+    pub(crate) async fn unschedule_tenant(&self, tenant_id: &str) -> usize {
+        let mut entries = self.scheduler.entries.write().await;
+        let before = entries.len();
+        entries.retain(|_, entry| entry.status.tenant_id != tenant_id);
+        before - entries.len()
+    }
+
+    /// The tenant's schedules with their next-run times, soonest first
+    pub async fn list_scheduled_hunts(&self, tenant_id: &str) -> Vec<ScheduledHuntStatus> {
+        let mut statuses: Vec<ScheduledHuntStatus> = self
+            .scheduler
+            .entries
+            .read()
+            .await
+            .values()
+            .filter(|entry| entry.status.tenant_id == tenant_id)
+            .map(|entry| entry.status.clone())
+            .collect();
+        statuses.sort_by(|a, b| match (a.next_run, b.next_run) {
+            (Some(x), Some(y)) => x.cmp(&y),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => a.rule_id.cmp(&b.rule_id),
+        });
+        statuses
+    }
+
+    /// Schedules of every tenant, for platform-level health reporting
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub(crate) async fn all_scheduled_hunts(&self) -> Vec<ScheduledHuntStatus> {
+        self.scheduler.entries.read().await.values().map(|entry| entry.status.clone()).collect()
+    }
+
+    pub async fn is_scheduler_running(&self) -> bool {
+        self.scheduler
+            .run
+            .lock()
+            .await
+            .as_ref()
+            .is_some_and(|run| !run.handle.is_finished())
+    }
+
+    /// Start the background scheduler; fails if it is already running
+    pub async fn start_scheduler(self: &Arc<Self>, config: SchedulerConfig) -> Result<(), String> {
+        let mut run = self.scheduler.run.lock().await;
+        if run.as_ref().is_some_and(|r| !r.handle.is_finished()) {
+            return Err("Hunt scheduler is already running".to_string());
+        }
+
+        let (stop, mut stopped) = watch::channel(false);
+        let core = Arc::clone(self);
+        let limiter = Arc::new(Semaphore::new(config.max_concurrent_hunts.max(1)));
+        let tick = std::time::Duration::from_millis(config.tick_ms.max(10));
+
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tick);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => core.dispatch_due_hunts(&limiter).await,
+                    changed = stopped.changed() => {
+                        if changed.is_err() || *stopped.borrow() {
+                            break;
+                        }
+                    }
+                }
+            }
+            log::info!("Hunt scheduler stopped");
+        });
+
+        *run = Some(SchedulerRun { stop, handle });
+        log::info!("Hunt scheduler started (tick {:?}, max {} concurrent hunts)", tick, config.max_concurrent_hunts);
+        Ok(())
+    }
+
+    /// Stop the scheduler; hunts already running are allowed to finish
+    pub async fn stop_scheduler(&self) -> bool {
+        let Some(run) = self.scheduler.run.lock().await.take() else {
+            return false;
+        };
+        let _ = run.stop.send(true);
+        let _ = run.handle.await;
+        true
+    }
+
+    async fn dispatch_due_hunts(self: &Arc<Self>, limiter: &Arc<Semaphore>) {
+        let now = Utc::now();
+        let mut entries = self.scheduler.entries.write().await;
+
+        let mut due: Vec<&mut ScheduleEntry> = entries
+            .values_mut()
+            .filter(|entry| !entry.status.running && entry.status.next_run.is_some_and(|at| at <= now))
+            .collect();
+        due.sort_by_key(|entry| entry.status.next_run);
+
+        for entry in due {
+            let Ok(permit) = limiter.clone().try_acquire_owned() else {
+                break;
+            };
+            entry.status.running = true;
+            entry.status.last_run = Some(now);
+            entry.status.next_run = entry
+                .schedule
+                .trigger
+                .next_after(now)
+                .map(|at| with_jitter(at, entry.schedule.jitter_secs));
+
+            let core = Arc::clone(self);
+            let rule_id = entry.schedule.rule_id.clone();
+            let tenant_id = entry.status.tenant_id.clone();
+            let context = entry.schedule.data_context.clone();
+            tokio::spawn(async move {
+                let outcome = core.execute_hunt(&tenant_id, &rule_id, context).await.map_err(|e| e.to_string());
+                drop(permit);
+
+                let mut entries = core.scheduler.entries.write().await;
+                let Some(entry) = entries.get_mut(&schedule_key(&tenant_id, &rule_id)) else { return };
+                entry.status.running = false;
+                entry.status.run_count += 1;
+                match outcome {
+                    Ok(result) => {
+                        entry.status.last_hunt_id = Some(result.hunt_id);
+                        entry.status.last_error = None;
+                    }
+                    Err(e) => {
+                        log::warn!("Scheduled hunt for rule {} failed: {}", rule_id, e);
+                        entry.status.failure_count += 1;
+                        entry.status.last_error = Some(e);
+                    }
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Timelike};
+
+    #[test]
+    fn test_cron_and_interval_next_runs() {
+        let at = Utc.with_ymd_and_hms(2024, 3, 1, 10, 7, 30).unwrap();
+
+        let cron = HuntTrigger::Cron { expression: "*/15 * * * *".to_string() };
+        let next = cron.next_after(at).unwrap();
+        assert_eq!((next.hour(), next.minute(), next.second()), (10, 15, 0));
+
+        let interval = HuntTrigger::Interval { every_secs: 90 };
+        assert_eq!(interval.next_after(at).unwrap(), at + chrono::Duration::seconds(90));
+
+        assert!(HuntTrigger::Cron { expression: "not a cron".to_string() }.validate().is_err());
+        assert!(HuntTrigger::Interval { every_secs: 0 }.validate().is_err());
+
+        let jittered = with_jitter(at, 30);
+        assert!(jittered >= at && jittered <= at + chrono::Duration::seconds(30));
+    }
+
+    #[tokio::test]
+    async fn test_scheduler_runs_due_hunts_and_stops() {
+        let core = Arc::new(HuntingCore::new().unwrap());
+        let rule_id = core.list_rules("default").await.unwrap()[0].id.clone();
+
+        assert!(core
+            .schedule_hunt("default", HuntSchedule {
+                rule_id: "missing".to_string(),
+                trigger: HuntTrigger::Interval { every_secs: 60 },
+                jitter_secs: 0,
+                enabled: true,
+                run_immediately: true,
+                data_context: None,
+            })
+            .await
+            .is_err());
+
+        core.schedule_hunt("default", HuntSchedule {
+            rule_id: rule_id.clone(),
+            trigger: HuntTrigger::Interval { every_secs: 3600 },
+            jitter_secs: 5,
+            enabled: true,
+            run_immediately: true,
+            data_context: None,
+        })
+        .await
+        .unwrap();
+
+        core.start_scheduler(SchedulerConfig { tick_ms: 10, max_concurrent_hunts: 1 }).await.unwrap();
+        assert!(core.start_scheduler(SchedulerConfig::default()).await.is_err());
+
+        let mut status = core.list_scheduled_hunts("default").await.remove(0);
+        for _ in 0..200 {
+            if status.run_count > 0 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            status = core.list_scheduled_hunts("default").await.remove(0);
+        }
+        assert_eq!(status.run_count, 1);
+        assert!(status.last_hunt_id.is_some());
+        assert!(status.next_run.unwrap() > Utc::now() + chrono::Duration::minutes(59));
+
+        assert!(core.stop_scheduler().await);
+        assert!(!core.is_scheduler_running().await);
+        assert!(!core.stop_scheduler().await);
+    }
+}
@@ -0,0 +1,199 @@
+//! Connector secrets
+//!
+//! Credentials for data sources and other connectors are kept in the shared
+//! `SecretStore` from phantom-enterprise-standards, sealed under a master key
+//! from the `PHANTOM_HUNTING_MASTER_KEY` environment variable (64 hex digits)
+//! unless the host rotates in one of its own.
+//!
+//! Connector configs refer to secrets as `secret://name`. Data source
+//! credentials keep the reference in the catalog and are resolved only when a
+//! hunt connects; plaintext credentials passed to `register_data_source` are
+//! moved into the store as `datasource/<source id>/<key>` when a master key is
+//! available. Elasticsearch indexing and enrichment configs are resolved when
+//! they are applied. `redact` blanks credential values in anything serialized
+//! for audit, health or status output; references are kept, since they reveal
+//! nothing.
+//!
+//! Secrets are rotated one at a time with `rotate_secret`, and the master key
+//! with `rotate_master_key`, which re-seals every secret under the new key.
+//! The sealed records can be exported and imported to persist them.
+
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::error::{CoreError, CoreResult};
+use crate::{DataSource, HuntingCore, HuntingCoreNapi};
+
+pub use phantom_enterprise_standards::secrets::{
+    redact, redacted, reference_name, EnvKey, KeyProvider, KmsKey, SealedSecret, SecretError, SecretMetadata, SecretStore,
+    SecretStoreStatus, StaticKey, SECRET_SCHEME,
+};
+
+/// Environment variable holding the default master key, as 64 hex digits
+pub const MASTER_KEY_ENV: &str = "PHANTOM_HUNTING_MASTER_KEY";
+
+impl HuntingCore {
+    pub fn secrets(&self) -> &SecretStore {
+        &self.secrets
+    }
+
+    /// Move plaintext credentials of a source into the store, leaving references.
+    /// Without a master key they stay as given.
+    pub(crate) fn seal_credentials(&self, source: &mut DataSource) -> CoreResult<()> {
+        let plaintext = source
+            .connection_details
+            .authentication
+            .credentials
+            .values()
+            .any(|value| !value.is_empty() && reference_name(value).is_none());
+        if !plaintext {
+            return Ok(());
+        }
+        if !self.secrets.is_available() {
+            log::warn!("No master key; credentials of data source {} are kept in plaintext", source.source_id);
+            return Ok(());
+        }
+        for (key, value) in source.connection_details.authentication.credentials.iter_mut() {
+            if value.is_empty() || reference_name(value).is_some() {
+                continue;
+            }
+            let name = format!("datasource/{}/{}", source.source_id, key);
+            self.secrets.put(&name, value)?;
+            *value = format!("{}{}", SECRET_SCHEME, name);
+        }
+        Ok(())
+    }
+
+    /// Copy of `source` with its credential references resolved, for connecting
+    pub(crate) fn resolve_credentials(&self, source: &DataSource) -> CoreResult<DataSource> {
+        let mut resolved = source.clone();
+        for value in resolved.connection_details.authentication.credentials.values_mut() {
+            *value = self.secrets.resolve(value)?;
+        }
+        Ok(resolved)
+    }
+}
+
+#[napi]
+impl HuntingCoreNapi {
+    /// Store a connector secret; configs refer to it as `secret://<name>`
+    #[napi]
+    pub fn put_secret(&self, name: String, value: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, &name)?;
+        let metadata = self.inner.secrets().put(&name, &value).map_err(CoreError::from);
+        let metadata = self.audit.record(&actor, "put_secret", &name, json!({}), metadata)
+            .map_err(|e| e.context("Failed to store secret"))?;
+        serde_json::to_string(&metadata)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize secret metadata: {}", e)))
+    }
+
+    /// Replace the value of an existing secret
+    #[napi]
+    pub fn rotate_secret(&self, name: String, value: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, &name)?;
+        let metadata = self.inner.secrets().rotate(&name, &value).map_err(CoreError::from);
+        let metadata = self.audit.record(&actor, "rotate_secret", &name, json!({}), metadata)
+            .map_err(|e| e.context("Failed to rotate secret"))?;
+        serde_json::to_string(&metadata)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize secret metadata: {}", e)))
+    }
+
+    #[napi]
+    pub fn remove_secret(&self, name: String, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize_platform(auth_token, &name)?;
+        let outcome = self.inner.secrets().remove(&name).map_err(CoreError::from);
+        self.audit.record(&actor, "remove_secret", &name, json!({}), outcome)
+            .map_err(|e| e.context("Failed to remove secret"))?;
+        Ok(())
+    }
+
+    /// Secret names and versions; values are never returned
+    #[napi]
+    pub fn list_secrets(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize_platform(auth_token, "secrets")?;
+        serde_json::to_string(&self.inner.secrets().list())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize secrets: {}", e)))
+    }
+
+    /// Re-seal every secret under a new 32-byte master key, e.g. one the host
+    /// unwrapped through its KMS
+    #[napi]
+    pub fn rotate_master_key(&self, master_key: Buffer, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, "secrets")?;
+        let status = self.inner.secrets().rotate_master_key(Arc::new(StaticKey::new(master_key.to_vec()))).map_err(CoreError::from);
+        let status = self.audit.record(&actor, "rotate_master_key", "secrets", json!({}), status)
+            .map_err(|e| e.context("Failed to rotate master key"))?;
+        serde_json::to_string(&status)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize secret store status: {}", e)))
+    }
+
+    /// Sealed secrets for the host to persist
+    #[napi]
+    pub fn export_sealed_secrets(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize_platform(auth_token, "secrets")?;
+        serde_json::to_string(&self.inner.secrets().export_sealed())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize sealed secrets: {}", e)))
+    }
+
+    #[napi]
+    pub fn import_sealed_secrets(&self, sealed_json: String, auth_token: Option<String>) -> napi::Result<u32> {
+        let actor = self.authorize_platform(auth_token, "secrets")?;
+        let records: Vec<SealedSecret> = serde_json::from_str(&sealed_json)
+            .map_err(|e| CoreError::from(e).context("Failed to parse sealed secrets"))?;
+        let imported = self.inner.secrets().import_sealed(records).map_err(CoreError::from);
+        let imported = self.audit.record(&actor, "import_sealed_secrets", "secrets", json!({}), imported)
+            .map_err(|e| e.context("Failed to import sealed secrets"))?;
+        Ok(imported as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> Arc<dyn KeyProvider> {
+        Arc::new(StaticKey::new(vec![byte; 32]))
+    }
+
+    #[tokio::test]
+    async fn test_sealed_credentials_resolve_rotate_and_redact() {
+        let core = HuntingCore::new().unwrap();
+        core.secrets().rotate_master_key(key(7)).unwrap();
+
+        let mut es = core.data_sources.read().await.values().next().unwrap().clone();
+        es.source_id = "es".to_string();
+        es.connection_details.endpoint = "elasticsearch+https://es.internal:9200/logs-*".to_string();
+        let credentials = &mut es.connection_details.authentication.credentials;
+        credentials.insert("username".to_string(), "hunter".to_string());
+        credentials.insert("password".to_string(), "hunter2".to_string());
+        core.register_data_source(es).await.unwrap();
+
+        let stored = core.data_sources.read().await["es"].clone();
+        let credentials = &stored.connection_details.authentication.credentials;
+        assert_eq!(credentials["password"], "secret://datasource/es/password");
+        assert!(!serde_json::to_string(&core.secrets().export_sealed()).unwrap().contains("hunter2"));
+        assert_eq!(core.resolve_credentials(&stored).unwrap().connection_details.authentication.credentials["password"], "hunter2");
+
+        core.secrets().rotate("datasource/es/password", "hunter3").unwrap();
+        core.secrets().rotate_master_key(key(9)).unwrap();
+        assert_eq!(core.secrets().get("datasource/es/password").unwrap(), "hunter3");
+        assert_eq!(core.secrets().list()[0].version, 2);
+
+        let exported = core.secrets().export_sealed();
+        let other = SecretStore::new(key(7));
+        assert!(other.import_sealed(exported.clone()).is_err());
+        assert_eq!(SecretStore::new(key(9)).import_sealed(exported).unwrap(), 2);
+
+        let mut config = json!({ "base_url": "https://es", "password": "plain", "api_key": "secret://es-key", "providers": [{ "auth_token": "abc" }] });
+        redact(&mut config);
+        assert_eq!(config["password"], "[redacted]");
+        assert_eq!(config["api_key"], "secret://es-key");
+        assert_eq!(config["providers"][0]["auth_token"], "[redacted]");
+        assert_eq!(config["base_url"], "https://es");
+
+        let mut missing = json!({ "api_key": "secret://es-key" });
+        assert_eq!(CoreError::from(core.secrets().resolve_json(&mut missing).unwrap_err()).code(), "NOT_FOUND");
+    }
+}
@@ -0,0 +1,1076 @@
+//! Interactive hunt sessions
+//!
+//! A session lets an analyst pivot during a hunt instead of running one-shot
+//! rules: ad-hoc queries run against the connected data sources, each query
+//! may refine an earlier one, interesting matches are pinned and annotated,
+//! and the session is finally converted into a persistent tenant rule or an
+//! incident draft for the SecOp core. Every step is kept in the session
+//! history.
+//!
+//! Built-in sources are `netflow` (ingested flow records) and per-tenant event
+//! buffers filled through `ingest_session_events`; further sources implement
+//! `SessionDataSource`.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::error::{CoreError, CoreResult};
+use crate::netflow::FlowStore;
+use crate::pagination::{paginate, ListSpec, Page, PageRequest, SortDirection};
+use crate::{
+    conditions, DataSourceType, DetectionCondition, DetectionLogic, HuntingCategory, HuntingCore, HuntingCoreNapi,
+    HuntingQuery, HuntingRule, HuntingRuleMetadata, HuntingSeverity, QueryLanguage, ResourceUsage, RuleType,
+    RulePerformanceMetrics,
+};
+
+/// Name of the built-in source over ingested flow records
+pub const NETFLOW_SOURCE: &str = "netflow";
+/// Events kept per tenant and buffered source; the oldest are dropped first
+pub const MAX_BUFFERED_EVENTS: usize = 10_000;
+/// Matches a query keeps when it does not set a limit
+pub const DEFAULT_QUERY_LIMIT: usize = 500;
+
+/// One event a session query is evaluated against
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceEvent {
+    #[serde(default = "Utc::now")]
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub fields: HashMap<String, Value>,
+}
+
+/// A data source sessions can query
+#[async_trait]
+pub trait SessionDataSource: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Events of `tenant_id` at or after `since`
+    async fn events(&self, tenant_id: &str, since: Option<DateTime<Utc>>) -> Result<Vec<SourceEvent>, String>;
+}
+
+/// Flow records the tenant ingested
+struct FlowSource {
+    records: FlowStore,
+}
+
+#[async_trait]
+impl SessionDataSource for FlowSource {
+    fn name(&self) -> &str {
+        NETFLOW_SOURCE
+    }
+
+    async fn events(&self, tenant_id: &str, since: Option<DateTime<Utc>>) -> Result<Vec<SourceEvent>, String> {
+        let records = self.records.read().await;
+        Ok(records
+            .get(tenant_id)
+            .into_iter()
+            .flatten()
+            .filter(|flow| since.is_none_or(|since| flow.flow_end >= since))
+            .map(|flow| SourceEvent { timestamp: flow.flow_start, fields: flow.to_event_data() })
+            .collect())
+    }
+}
+
+/// One clause of an ad-hoc query, evaluated like a rule's detection condition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryClause {
+    pub field: String,
+    pub operator: String,
+    pub value: Value,
+    /// Optional clauses only raise the confidence of events matching the rest
+    #[serde(default)]
+    pub optional: bool,
+}
+
+impl QueryClause {
+    fn to_condition(&self, index: usize) -> DetectionCondition {
+        DetectionCondition {
+            condition_id: format!("clause_{}", index + 1),
+            field: self.field.clone(),
+            operator: self.operator.clone(),
+            value: self.value.clone(),
+            weight: 1.0,
+            required: !self.optional,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionQuery {
+    /// Sources to search; all connected sources when empty
+    #[serde(default)]
+    pub sources: Vec<String>,
+    #[serde(default)]
+    pub clauses: Vec<QueryClause>,
+    /// Earlier query of the session whose clauses and sources this one narrows
+    #[serde(default)]
+    pub refine: Option<String>,
+    #[serde(default)]
+    pub since: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// A query as it ran, with the clauses inherited through refinement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryRun {
+    pub query_id: String,
+    pub refined_from: Option<String>,
+    pub sources: Vec<String>,
+    pub clauses: Vec<QueryClause>,
+    pub since: Option<DateTime<Utc>>,
+    pub events_scanned: u64,
+    pub match_count: usize,
+    /// Matches beyond the query limit
+    pub truncated: bool,
+    pub ran_by: String,
+    pub ran_at: DateTime<Utc>,
+    pub duration_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Annotation {
+    pub author: String,
+    pub note: String,
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionMatch {
+    pub match_id: String,
+    pub query_id: String,
+    pub source: String,
+    pub event: SourceEvent,
+    pub confidence: f64,
+    pub pinned: bool,
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionStatus {
+    Open,
+    Closed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SessionAction {
+    Opened,
+    QueryRun { query_id: String, match_count: usize },
+    Pinned { match_id: String },
+    Unpinned { match_id: String },
+    Annotated { match_id: String },
+    ConvertedToRule { rule_id: String },
+    ConvertedToIncident { incident_id: String },
+    Closed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionHistoryEntry {
+    pub actor: String,
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub action: SessionAction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HuntSession {
+    pub session_id: String,
+    pub tenant_id: String,
+    pub name: String,
+    pub hypothesis: Option<String>,
+    pub analyst: String,
+    pub status: SessionStatus,
+    pub queries: Vec<QueryRun>,
+    /// Pinned matches of every query and the unpinned ones of the latest
+    pub matches: Vec<SessionMatch>,
+    pub history: Vec<SessionHistoryEntry>,
+    pub rule_ids: Vec<String>,
+    pub incident_ids: Vec<String>,
+    pub opened_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+impl HuntSession {
+    fn record(&mut self, actor: &str, action: SessionAction) {
+        let now = Utc::now();
+        self.history.push(SessionHistoryEntry { actor: actor.to_string(), at: now, action });
+        self.updated_at = now;
+    }
+
+    fn ensure_open(&self) -> Result<(), String> {
+        match self.status {
+            SessionStatus::Open => Ok(()),
+            SessionStatus::Closed => Err(format!("Hunt session {} is closed", self.session_id)),
+        }
+    }
+
+    fn query(&self, query_id: &str) -> Result<&QueryRun, String> {
+        self.queries
+            .iter()
+            .find(|run| run.query_id == query_id)
+            .ok_or_else(|| format!("Query {} not found in hunt session {}", query_id, self.session_id))
+    }
+
+    fn match_mut(&mut self, match_id: &str) -> Result<&mut SessionMatch, String> {
+        let session_id = self.session_id.clone();
+        self.matches
+            .iter_mut()
+            .find(|m| m.match_id == match_id)
+            .ok_or_else(|| format!("Match {} not found in hunt session {}", match_id, session_id))
+    }
+
+    fn pinned(&self) -> impl Iterator<Item = &SessionMatch> {
+        self.matches.iter().filter(|m| m.pinned)
+    }
+}
+
+/// Settings of the rule a session is converted into
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleConversion {
+    /// Query whose clauses become the rule conditions; the latest by default
+    #[serde(default)]
+    pub query_id: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default = "default_rule_severity")]
+    pub severity: HuntingSeverity,
+    #[serde(default = "default_rule_category")]
+    pub category: HuntingCategory,
+}
+
+fn default_rule_severity() -> HuntingSeverity {
+    HuntingSeverity::Medium
+}
+
+fn default_rule_category() -> HuntingCategory {
+    HuntingCategory::CustomCategory("hunt_session".to_string())
+}
+
+/// Settings of the incident a session is converted into
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IncidentConversion {
+    #[serde(default)]
+    pub title: Option<String>,
+    /// SecOp severity: `Critical`, `High`, `Medium` or `Low`
+    #[serde(default)]
+    pub severity: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+}
+
+/// Incident in the shape the SecOp core accepts in `create_incident`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentDraft {
+    pub incident_id: String,
+    pub title: String,
+    pub description: String,
+    pub severity: String,
+    pub status: String,
+    pub category: String,
+    pub priority: u32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub assigned_to: String,
+    pub reporter: String,
+    pub affected_systems: Vec<String>,
+    pub indicators: Vec<DraftIndicator>,
+    pub timeline: Vec<DraftEvent>,
+    pub mitigation_actions: Vec<String>,
+    pub estimated_impact: f64,
+    pub containment_status: String,
+    pub evidence: Vec<String>,
+    pub tags: Vec<String>,
+    pub tenant_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DraftIndicator {
+    pub indicator_id: String,
+    pub indicator_type: String,
+    pub value: String,
+    pub confidence: f64,
+    pub severity: String,
+    pub source: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub context: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DraftEvent {
+    pub event_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub event_type: String,
+    pub description: String,
+    pub source: String,
+    pub severity: String,
+    pub data: HashMap<String, String>,
+}
+
+/// Event fields lifted into incident indicators, with their indicator type
+const INDICATOR_FIELDS: &[(&str, &str)] = &[
+    ("source_ip", "ip"),
+    ("destination_ip", "ip"),
+    ("src_ip", "ip"),
+    ("dst_ip", "ip"),
+    ("domain", "domain"),
+    ("query_name", "domain"),
+    ("url", "url"),
+    ("sha256", "hash"),
+    ("file_hash", "hash"),
+];
+
+/// Event fields naming the affected host
+const HOST_FIELDS: &[&str] = &["hostname", "host", "computer_name"];
+
+pub struct SessionState {
+    sessions: RwLock<HashMap<String, HuntSession>>,
+    sources: RwLock<HashMap<String, Arc<dyn SessionDataSource>>>,
+    /// Ingested events per (tenant, source)
+    buffers: RwLock<HashMap<(String, String), VecDeque<SourceEvent>>>,
+}
+
+impl SessionState {
+    pub(crate) fn new(flow_records: FlowStore) -> Self {
+        let flows: Arc<dyn SessionDataSource> = Arc::new(FlowSource { records: flow_records });
+        Self {
+            sessions: RwLock::new(HashMap::new()),
+            sources: RwLock::new(HashMap::from([(NETFLOW_SOURCE.to_string(), flows)])),
+            buffers: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Names of the sources `tenant_id` can query
+    async fn source_names(&self, tenant_id: &str) -> Vec<String> {
+        let mut names: Vec<String> = self.sources.read().await.keys().cloned().collect();
+        names.extend(self.buffers.read().await.keys().filter(|(tenant, _)| tenant == tenant_id).map(|(_, source)| source.clone()));
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    async fn events(&self, tenant_id: &str, source: &str, since: Option<DateTime<Utc>>) -> Result<Vec<SourceEvent>, String> {
+        let connected = self.sources.read().await.get(source).cloned();
+        if let Some(connected) = connected {
+            return connected.events(tenant_id, since).await;
+        }
+        let buffers = self.buffers.read().await;
+        let buffer = buffers
+            .get(&(tenant_id.to_string(), source.to_string()))
+            .ok_or_else(|| format!("Data source {} not found", source))?;
+        Ok(buffer.iter().filter(|event| since.is_none_or(|since| event.timestamp >= since)).cloned().collect())
+    }
+
+    /// Drop the sessions and buffered events of `tenant_id`
+    pub(crate) async fn forget_tenant(&self, tenant_id: &str) -> usize {
+        self.buffers.write().await.retain(|(tenant, _), _| tenant != tenant_id);
+        let mut sessions = self.sessions.write().await;
+        let before = sessions.len();
+        sessions.retain(|_, session| session.tenant_id != tenant_id);
+        before - sessions.len()
+    }
+}
+
+fn render_clauses(clauses: &[QueryClause]) -> String {
+    clauses
+        .iter()
+        .map(|clause| {
+            let rendered = format!("{} {} {}", clause.field, clause.operator, clause.value);
+            if clause.optional { format!("[{}]", rendered) } else { rendered }
+        })
+        .collect::<Vec<_>>()
+        .join(" AND ")
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn priority_for(severity: &str) -> u32 {
+    match severity {
+        "Critical" => 1,
+        "High" => 2,
+        "Medium" => 3,
+        _ => 4,
+    }
+}
+
+impl HuntingCore {
+    /// Connect another data source to every session, replacing one of the same name
+    pub async fn register_session_source(&self, source: Arc<dyn SessionDataSource>) {
+        self.sessions.sources.write().await.insert(source.name().to_string(), source);
+    }
+
+    /// Sources `tenant_id` can query in sessions
+    pub async fn session_sources(&self, tenant_id: &str) -> Vec<String> {
+        self.sessions.source_names(tenant_id).await
+    }
+
+    /// Buffer events of `tenant_id` under `source` for session queries;
+    /// returns the number of events buffered for the source
+    pub async fn ingest_session_events(&self, tenant_id: &str, source: &str, events: Vec<SourceEvent>) -> Result<usize, String> {
+        if source.is_empty() {
+            return Err("Data source name is required".to_string());
+        }
+        if self.sessions.sources.read().await.contains_key(source) {
+            return Err(format!("Data source {} is connected and cannot be ingested into", source));
+        }
+        let mut buffers = self.sessions.buffers.write().await;
+        let buffer = buffers.entry((tenant_id.to_string(), source.to_string())).or_default();
+        buffer.extend(events);
+        let excess = buffer.len().saturating_sub(MAX_BUFFERED_EVENTS);
+        buffer.drain(..excess);
+        Ok(buffer.len())
+    }
+
+    pub async fn open_hunt_session(&self, tenant_id: &str, analyst: &str, name: &str, hypothesis: Option<String>) -> Result<HuntSession, String> {
+        if name.trim().is_empty() {
+            return Err("Hunt session name is required".to_string());
+        }
+        let now = Utc::now();
+        let mut session = HuntSession {
+            session_id: format!("session_{}", Uuid::new_v4().simple()),
+            tenant_id: tenant_id.to_string(),
+            name: name.to_string(),
+            hypothesis,
+            analyst: analyst.to_string(),
+            status: SessionStatus::Open,
+            queries: Vec::new(),
+            matches: Vec::new(),
+            history: Vec::new(),
+            rule_ids: Vec::new(),
+            incident_ids: Vec::new(),
+            opened_at: now,
+            updated_at: now,
+            closed_at: None,
+        };
+        session.record(analyst, SessionAction::Opened);
+        self.sessions.sessions.write().await.insert(session.session_id.clone(), session.clone());
+        Ok(session)
+    }
+
+    pub async fn get_hunt_session(&self, tenant_id: &str, session_id: &str) -> Result<HuntSession, String> {
+        self.sessions.sessions.read().await
+            .get(session_id)
+            .filter(|session| session.tenant_id == tenant_id)
+            .cloned()
+            .ok_or_else(|| format!("Hunt session {} not found", session_id))
+    }
+
+    /// Sessions of `tenant_id`, most recently updated first
+    pub async fn list_hunt_sessions(&self, tenant_id: &str, include_closed: bool) -> Vec<HuntSession> {
+        let mut sessions: Vec<HuntSession> = self.sessions.sessions.read().await
+            .values()
+            .filter(|session| session.tenant_id == tenant_id)
+            .filter(|session| include_closed || session.status == SessionStatus::Open)
+            .cloned()
+            .collect();
+        sessions.sort_by_key(|session| std::cmp::Reverse(session.updated_at));
+        sessions
+    }
+
+    /// One page of the tenant's sessions, open and closed, most recently
+    /// updated first by default; filter on `status` to keep only open ones
+    pub async fn hunt_sessions_page(&self, tenant_id: &str, request: &PageRequest) -> CoreResult<Page<HuntSession>> {
+        let sessions = self.list_hunt_sessions(tenant_id, true).await;
+        Ok(paginate(sessions, request, ListSpec { id_field: "session_id", sort_by: "updated_at", direction: SortDirection::Desc })?)
+    }
+
+    /// Run an ad-hoc query in a session. Its matches replace the unpinned
+    /// matches of earlier queries; pinned matches are kept.
+    pub async fn run_session_query(&self, tenant_id: &str, session_id: &str, actor: &str, query: SessionQuery) -> Result<(QueryRun, Vec<SessionMatch>), String> {
+        let start = std::time::Instant::now();
+        let session = self.get_hunt_session(tenant_id, session_id).await?;
+        session.ensure_open()?;
+
+        let (mut clauses, mut sources) = match &query.refine {
+            Some(parent) => {
+                let parent = session.query(parent)?;
+                (parent.clauses.clone(), parent.sources.clone())
+            }
+            None => (Vec::new(), Vec::new()),
+        };
+        clauses.extend(query.clauses);
+        if clauses.is_empty() {
+            return Err("A session query needs at least one clause".to_string());
+        }
+        if !query.sources.is_empty() {
+            sources = query.sources;
+        }
+        if sources.is_empty() {
+            sources = self.sessions.source_names(tenant_id).await;
+        }
+
+        let detection: Vec<DetectionCondition> = clauses.iter().enumerate().map(|(i, c)| c.to_condition(i)).collect();
+        let query_id = format!("q{}", session.queries.len() + 1);
+        let limit = query.limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+        let mut events_scanned = 0u64;
+        let mut matches = Vec::new();
+        let mut truncated = false;
+        for source in &sources {
+            for event in self.sessions.events(tenant_id, source, query.since).await? {
+                events_scanned += 1;
+                let Some(confidence) = conditions::evaluate_conditions(&event.fields, &detection) else {
+                    continue;
+                };
+                if matches.len() == limit {
+                    truncated = true;
+                    continue;
+                }
+                matches.push(SessionMatch {
+                    match_id: format!("match_{}", Uuid::new_v4().simple()),
+                    query_id: query_id.clone(),
+                    source: source.clone(),
+                    event,
+                    confidence,
+                    pinned: false,
+                    annotations: Vec::new(),
+                });
+            }
+        }
+
+        let run = QueryRun {
+            query_id: query_id.clone(),
+            refined_from: query.refine,
+            sources,
+            clauses,
+            since: query.since,
+            events_scanned,
+            match_count: matches.len(),
+            truncated,
+            ran_by: actor.to_string(),
+            ran_at: Utc::now(),
+            duration_ms: start.elapsed().as_millis() as i64,
+        };
+
+        self.update_session(tenant_id, session_id, |session| {
+            session.ensure_open()?;
+            if session.queries.iter().any(|existing| existing.query_id == query_id) {
+                return Err(format!("Hunt session {} changed while the query ran", session_id));
+            }
+            session.queries.push(run.clone());
+            session.matches.retain(|m| m.pinned);
+            session.matches.extend(matches.iter().cloned());
+            session.record(actor, SessionAction::QueryRun { query_id: query_id.clone(), match_count: matches.len() });
+            Ok(())
+        })
+        .await?;
+        Ok((run, matches))
+    }
+
+    pub async fn pin_session_match(&self, tenant_id: &str, session_id: &str, actor: &str, match_id: &str, pinned: bool) -> Result<SessionMatch, String> {
+        self.update_session(tenant_id, session_id, |session| {
+            session.ensure_open()?;
+            let found = session.match_mut(match_id)?;
+            let changed = found.pinned != pinned;
+            found.pinned = pinned;
+            let found = found.clone();
+            if changed {
+                let match_id = match_id.to_string();
+                session.record(actor, if pinned { SessionAction::Pinned { match_id } } else { SessionAction::Unpinned { match_id } });
+            }
+            Ok(found)
+        })
+        .await
+    }
+
+    /// Annotate a match; annotating pins it so the note is not lost to the next query
+    pub async fn annotate_session_match(&self, tenant_id: &str, session_id: &str, actor: &str, match_id: &str, note: &str, tags: Vec<String>) -> Result<SessionMatch, String> {
+        if note.trim().is_empty() {
+            return Err("Annotation note is required".to_string());
+        }
+        self.update_session(tenant_id, session_id, |session| {
+            session.ensure_open()?;
+            let found = session.match_mut(match_id)?;
+            found.pinned = true;
+            found.annotations.push(Annotation { author: actor.to_string(), note: note.to_string(), tags, created_at: Utc::now() });
+            let found = found.clone();
+            session.record(actor, SessionAction::Annotated { match_id: match_id.to_string() });
+            Ok(found)
+        })
+        .await
+    }
+
+    /// Store the clauses of a session query as a rule owned by `tenant_id`
+    pub async fn convert_session_to_rule(&self, tenant_id: &str, session_id: &str, actor: &str, conversion: RuleConversion) -> Result<HuntingRule, String> {
+        let session = self.get_hunt_session(tenant_id, session_id).await?;
+        let run = match &conversion.query_id {
+            Some(query_id) => session.query(query_id)?,
+            None => session.queries.last().ok_or_else(|| format!("Hunt session {} has no queries", session_id))?,
+        };
+
+        let data_sources = if run.sources.iter().any(|source| source == NETFLOW_SOURCE) {
+            self.data_sources.read().await.values().filter(|ds| matches!(ds.source_type, DataSourceType::NetFlow)).cloned().collect()
+        } else {
+            Vec::new()
+        };
+        let mut tags = vec!["hunt-session".to_string()];
+        tags.extend(session.pinned().flat_map(|m| m.annotations.iter().flat_map(|a| a.tags.iter().cloned())));
+        tags.sort();
+        tags.dedup();
+
+        let now = Utc::now();
+        let rule = HuntingRule {
+            id: format!("session_rule_{}", Uuid::new_v4().simple()),
+            tenant_id: Some(tenant_id.to_string()),
+            name: conversion.name.unwrap_or_else(|| session.name.clone()),
+            description: conversion.description.or_else(|| session.hypothesis.clone()).unwrap_or_default(),
+            category: conversion.category,
+            severity: conversion.severity,
+            query: HuntingQuery {
+                query_language: QueryLanguage::Custom("hunt_session".to_string()),
+                primary_query: render_clauses(&run.clauses),
+                secondary_queries: Vec::new(),
+                correlation_queries: Vec::new(),
+                time_range: "last 24 hours".to_string(),
+                filters: Vec::new(),
+                aggregations: Vec::new(),
+            },
+            data_sources,
+            mitre_techniques: Vec::new(),
+            detection_logic: DetectionLogic {
+                rule_type: RuleType::Signature,
+                conditions: run.clauses.iter().enumerate().map(|(i, c)| c.to_condition(i)).collect(),
+                correlation_rules: Vec::new(),
+                time_windows: Vec::new(),
+                statistical_models: Vec::new(),
+            },
+            false_positive_mitigation: Vec::new(),
+            validation_rules: Vec::new(),
+            response_actions: Vec::new(),
+            metadata: HuntingRuleMetadata {
+                author: actor.to_string(),
+                creation_date: now,
+                last_modified: now,
+                version: "1.0".to_string(),
+                tags,
+                references: vec![format!("hunt_session:{}#{}", session_id, run.query_id)],
+                attack_phases: Vec::new(),
+                target_platforms: Vec::new(),
+                data_source_requirements: run.sources.clone(),
+            },
+            performance_metrics: RulePerformanceMetrics {
+                total_executions: 0,
+                total_matches: 0,
+                false_positives: 0,
+                true_positives: 0,
+                average_execution_time: 0.0,
+                resource_usage: ResourceUsage {
+                    cpu_usage: 0.0,
+                    memory_usage: 0,
+                    network_usage: 0,
+                    storage_usage: 0,
+                },
+                effectiveness_score: 0.0,
+                last_updated: now,
+            },
+            execution_timeout_secs: None,
+            test_fixtures: Vec::new(),
+        };
+
+        self.rules.write().await.insert(rule.id.clone(), rule.clone());
+        let rule_id = rule.id.clone();
+        self.update_session(tenant_id, session_id, |session| {
+            session.rule_ids.push(rule_id.clone());
+            session.record(actor, SessionAction::ConvertedToRule { rule_id });
+            Ok(())
+        })
+        .await?;
+        Ok(rule)
+    }
+
+    /// Build an incident from the pinned matches of a session, for the SecOp
+    /// core's `create_incident`
+    pub async fn convert_session_to_incident(&self, tenant_id: &str, session_id: &str, actor: &str, conversion: IncidentConversion) -> Result<IncidentDraft, String> {
+        let session = self.get_hunt_session(tenant_id, session_id).await?;
+        let pinned: Vec<&SessionMatch> = session.pinned().collect();
+        if pinned.is_empty() {
+            return Err(format!("Hunt session {} has no pinned matches", session_id));
+        }
+
+        let severity = conversion.severity.unwrap_or_else(|| "Medium".to_string());
+        let now = Utc::now();
+        let mut indicators: BTreeMap<(String, String), DraftIndicator> = BTreeMap::new();
+        let mut affected_systems = Vec::new();
+        let mut timeline = Vec::new();
+        let mut tags = vec!["hunt-session".to_string()];
+
+        for found in &pinned {
+            for (field, indicator_type) in INDICATOR_FIELDS {
+                let Some(value) = found.event.fields.get(*field).map(value_text) else {
+                    continue;
+                };
+                let indicator = indicators.entry((indicator_type.to_string(), value.clone())).or_insert_with(|| DraftIndicator {
+                    indicator_id: format!("ioc_{}", Uuid::new_v4().simple()),
+                    indicator_type: indicator_type.to_string(),
+                    value,
+                    confidence: found.confidence,
+                    severity: severity.clone(),
+                    source: format!("hunting:{}", found.source),
+                    first_seen: found.event.timestamp,
+                    last_seen: found.event.timestamp,
+                    context: format!("Pinned in hunt session {}", session.name),
+                });
+                indicator.confidence = indicator.confidence.max(found.confidence);
+                indicator.first_seen = indicator.first_seen.min(found.event.timestamp);
+                indicator.last_seen = indicator.last_seen.max(found.event.timestamp);
+            }
+            affected_systems.extend(HOST_FIELDS.iter().filter_map(|field| found.event.fields.get(*field)).map(value_text));
+
+            let mut data: HashMap<String, String> = found.event.fields.iter().map(|(k, v)| (k.clone(), value_text(v))).collect();
+            data.insert("match_id".to_string(), found.match_id.clone());
+            data.insert("query_id".to_string(), found.query_id.clone());
+            data.insert("confidence".to_string(), format!("{:.2}", found.confidence));
+            if !found.annotations.is_empty() {
+                let notes: Vec<String> = found.annotations.iter().map(|a| format!("{}: {}", a.author, a.note)).collect();
+                data.insert("annotations".to_string(), notes.join("\n"));
+            }
+            tags.extend(found.annotations.iter().flat_map(|a| a.tags.iter().cloned()));
+            timeline.push(DraftEvent {
+                event_id: found.match_id.clone(),
+                timestamp: found.event.timestamp,
+                event_type: "hunt_match".to_string(),
+                description: found.annotations.last().map(|a| a.note.clone())
+                    .unwrap_or_else(|| format!("Match of {} in {}", found.query_id, found.source)),
+                source: format!("hunting:{}", found.source),
+                severity: severity.clone(),
+                data,
+            });
+        }
+        timeline.sort_by_key(|event| event.timestamp);
+        affected_systems.sort();
+        affected_systems.dedup();
+        tags.sort();
+        tags.dedup();
+
+        let incident = IncidentDraft {
+            incident_id: format!("INC-HUNT-{}", Uuid::new_v4().simple().to_string()[..12].to_uppercase()),
+            title: conversion.title.unwrap_or_else(|| format!("Hunt: {}", session.name)),
+            description: session.hypothesis.clone().unwrap_or_else(|| format!("Raised from hunt session {}", session.name)),
+            priority: priority_for(&severity),
+            severity,
+            status: "Open".to_string(),
+            category: conversion.category.unwrap_or_else(|| "Threat Hunt".to_string()),
+            created_at: now,
+            updated_at: now,
+            assigned_to: session.analyst.clone(),
+            reporter: actor.to_string(),
+            affected_systems,
+            indicators: indicators.into_values().collect(),
+            timeline,
+            mitigation_actions: Vec::new(),
+            estimated_impact: pinned.iter().map(|m| m.confidence).fold(0.0, f64::max) * 10.0,
+            containment_status: "None".to_string(),
+            evidence: std::iter::once(format!("hunt_session:{}", session_id))
+                .chain(pinned.iter().map(|m| format!("hunt_match:{}", m.match_id)))
+                .collect(),
+            tags,
+            tenant_id: tenant_id.to_string(),
+        };
+
+        let incident_id = incident.incident_id.clone();
+        self.update_session(tenant_id, session_id, |session| {
+            session.incident_ids.push(incident_id.clone());
+            session.record(actor, SessionAction::ConvertedToIncident { incident_id });
+            Ok(())
+        })
+        .await?;
+        Ok(incident)
+    }
+
+    pub async fn close_hunt_session(&self, tenant_id: &str, session_id: &str, actor: &str) -> Result<HuntSession, String> {
+        self.update_session(tenant_id, session_id, |session| {
+            session.ensure_open()?;
+            session.status = SessionStatus::Closed;
+            session.closed_at = Some(Utc::now());
+            session.record(actor, SessionAction::Closed);
+            Ok(session.clone())
+        })
+        .await
+    }
+
+    async fn update_session<T>(&self, tenant_id: &str, session_id: &str, update: impl FnOnce(&mut HuntSession) -> Result<T, String>) -> Result<T, String> {
+        let mut sessions = self.sessions.sessions.write().await;
+        let session = sessions
+            .get_mut(session_id)
+            .filter(|session| session.tenant_id == tenant_id)
+            .ok_or_else(|| format!("Hunt session {} not found", session_id))?;
+        update(session)
+    }
+}
+
+#[derive(Serialize)]
+struct QueryOutcome {
+    run: QueryRun,
+    matches: Vec<SessionMatch>,
+}
+
+#[napi]
+impl HuntingCoreNapi {
+    /// Open an interactive hunt session for the calling analyst
+    #[napi]
+    pub async fn open_hunt_session(&self, name: String, hypothesis: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", "hunt_sessions")?;
+        let params = serde_json::json!({ "name": name, "hypothesis": hypothesis });
+
+        let session = self.inner.open_hunt_session(&tenant_id, &actor, &name, hypothesis).await;
+        let session = self.audit.record(&actor, "open_hunt_session", &name, params, session)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to open hunt session: {}", e)))?;
+
+        serde_json::to_string(&session)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize hunt session: {}", e)))
+    }
+
+    /// Get a hunt session with its queries, matches and history
+    #[napi]
+    pub async fn get_hunt_session(&self, session_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let session = self.inner.get_hunt_session(&tenant_id, &session_id).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to get hunt session: {}", e)))?;
+
+        serde_json::to_string(&session)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize hunt session: {}", e)))
+    }
+
+    #[napi]
+    pub async fn list_hunt_sessions(&self, include_closed: Option<bool>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let sessions = self.inner.list_hunt_sessions(&tenant_id, include_closed.unwrap_or(false)).await;
+        serde_json::to_string(&sessions)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize hunt sessions: {}", e)))
+    }
+
+    #[napi]
+    pub async fn list_hunt_sessions_page(&self, page_json: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let request = PageRequest::from_json(page_json.as_deref()).map_err(CoreError::from)?;
+        let page = self.inner.hunt_sessions_page(&tenant_id, &request).await?.project(&request.fields).map_err(CoreError::from)?;
+        serde_json::to_string(&page)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize hunt sessions page: {}", e)))
+    }
+
+    /// Data sources session queries can search
+    #[napi]
+    pub async fn get_session_sources(&self, auth_token: Option<String>) -> napi::Result<Vec<String>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        Ok(self.inner.session_sources(&tenant_id).await)
+    }
+
+    /// Buffer a JSON array of events under `source` for session queries
+    #[napi]
+    pub async fn ingest_session_events(&self, source: String, events_json: String, auth_token: Option<String>) -> napi::Result<u32> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let events: Vec<SourceEvent> = serde_json::from_str(&events_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse session events: {}", e)))?;
+        let buffered = self.inner.ingest_session_events(&tenant_id, &source, events).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to ingest session events: {}", e)))?;
+        Ok(buffered as u32)
+    }
+
+    /// Run an ad-hoc query in a session; returns the run and its matches
+    #[napi]
+    pub async fn run_session_query(&self, session_id: String, query_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", &session_id)?;
+        let query: SessionQuery = serde_json::from_str(&query_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse session query: {}", e)))?;
+        let params = serde_json::to_value(&query).unwrap_or_default();
+
+        let outcome = self.inner.run_session_query(&tenant_id, &session_id, &actor, query).await;
+        let (run, matches) = self.audit.record(&actor, "run_session_query", &session_id, params, outcome)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to run session query: {}", e)))?;
+
+        serde_json::to_string(&QueryOutcome { run, matches })
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize query result: {}", e)))
+    }
+
+    /// Pin a match so later queries keep it, or unpin it
+    #[napi]
+    pub async fn pin_session_match(&self, session_id: String, match_id: String, pinned: Option<bool>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", &session_id)?;
+        let pinned = pinned.unwrap_or(true);
+        let params = serde_json::json!({ "session_id": session_id, "pinned": pinned });
+
+        let found = self.inner.pin_session_match(&tenant_id, &session_id, &actor, &match_id, pinned).await;
+        let found = self.audit.record(&actor, "pin_session_match", &match_id, params, found)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to pin session match: {}", e)))?;
+
+        serde_json::to_string(&found)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize session match: {}", e)))
+    }
+
+    #[napi]
+    pub async fn annotate_session_match(&self, session_id: String, match_id: String, note: String, tags: Option<Vec<String>>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", &session_id)?;
+        let tags = tags.unwrap_or_default();
+        let params = serde_json::json!({ "session_id": session_id, "note": note, "tags": tags });
+
+        let found = self.inner.annotate_session_match(&tenant_id, &session_id, &actor, &match_id, &note, tags).await;
+        let found = self.audit.record(&actor, "annotate_session_match", &match_id, params, found)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to annotate session match: {}", e)))?;
+
+        serde_json::to_string(&found)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize session match: {}", e)))
+    }
+
+    /// Store a session query as a persistent hunting rule of the tenant
+    #[napi]
+    pub async fn convert_session_to_rule(&self, session_id: String, conversion_json: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &session_id)?;
+        let conversion: RuleConversion = serde_json::from_str(conversion_json.as_deref().unwrap_or("{}"))
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse rule conversion: {}", e)))?;
+        let params = serde_json::to_value(&conversion).unwrap_or_default();
+
+        let rule = self.inner.convert_session_to_rule(&tenant_id, &session_id, &actor, conversion).await;
+        let rule = self.audit.record(&actor, "convert_session_to_rule", &session_id, params, rule)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to convert hunt session to rule: {}", e)))?;
+
+        serde_json::to_string(&rule)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize rule: {}", e)))
+    }
+
+    /// Build an incident from the session's pinned matches, to pass to the
+    /// SecOp core's `createIncident`
+    #[napi]
+    pub async fn convert_session_to_incident(&self, session_id: String, conversion_json: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", &session_id)?;
+        let conversion: IncidentConversion = serde_json::from_str(conversion_json.as_deref().unwrap_or("{}"))
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse incident conversion: {}", e)))?;
+        let params = serde_json::to_value(&conversion).unwrap_or_default();
+
+        let incident = self.inner.convert_session_to_incident(&tenant_id, &session_id, &actor, conversion).await;
+        let incident = self.audit.record(&actor, "convert_session_to_incident", &session_id, params, incident)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to convert hunt session to incident: {}", e)))?;
+
+        serde_json::to_string(&incident)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize incident: {}", e)))
+    }
+
+    #[napi]
+    pub async fn close_hunt_session(&self, session_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", &session_id)?;
+
+        let session = self.inner.close_hunt_session(&tenant_id, &session_id, &actor).await;
+        let session = self.audit.record(&actor, "close_hunt_session", &session_id, serde_json::json!({}), session)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to close hunt session: {}", e)))?;
+
+        serde_json::to_string(&session)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize hunt session: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(host: &str, process: &str, dst: &str) -> SourceEvent {
+        SourceEvent {
+            timestamp: Utc::now(),
+            fields: HashMap::from([
+                ("hostname".to_string(), Value::from(host)),
+                ("process_name".to_string(), Value::from(process)),
+                ("destination_ip".to_string(), Value::from(dst)),
+            ]),
+        }
+    }
+
+    fn clause(field: &str, operator: &str, value: &str) -> QueryClause {
+        QueryClause { field: field.to_string(), operator: operator.to_string(), value: Value::from(value), optional: false }
+    }
+
+    #[tokio::test]
+    async fn refined_queries_keep_pinned_matches_and_become_rules() {
+        let core = HuntingCore::new().unwrap();
+        let events = vec![
+            event("ws-01", "powershell.exe", "203.0.113.7"),
+            event("ws-02", "powershell.exe", "10.0.0.5"),
+            event("ws-03", "explorer.exe", "203.0.113.7"),
+        ];
+        core.ingest_session_events("acme", "edr", events).await.unwrap();
+        assert!(core.session_sources("acme").await.contains(&"edr".to_string()));
+        assert!(!core.session_sources("globex").await.contains(&"edr".to_string()));
+
+        let session = core.open_hunt_session("acme", "alice", "PowerShell egress", Some("PS beacons out".to_string())).await.unwrap();
+        let id = &session.session_id;
+        let (broad, matches) = core
+            .run_session_query("acme", id, "alice", SessionQuery { clauses: vec![clause("process_name", "equals", "powershell.exe")], ..Default::default() })
+            .await
+            .unwrap();
+        assert_eq!(broad.match_count, 2);
+        let pinned = matches.iter().find(|m| m.event.fields["hostname"] == "ws-02").unwrap();
+        core.annotate_session_match("acme", id, "alice", &pinned.match_id, "internal, benign?", vec!["triage".to_string()]).await.unwrap();
+
+        let refined = SessionQuery {
+            refine: Some(broad.query_id.clone()),
+            clauses: vec![clause("destination_ip", "equals", "203.0.113.7")],
+            ..Default::default()
+        };
+        let (narrow, _) = core.run_session_query("acme", id, "alice", refined).await.unwrap();
+        assert_eq!(narrow.clauses.len(), 2);
+        assert_eq!(narrow.sources, broad.sources);
+        assert_eq!(narrow.match_count, 1);
+
+        let session = core.get_hunt_session("acme", id).await.unwrap();
+        assert_eq!(session.matches.len(), 2);
+        assert!(session.matches.iter().any(|m| m.pinned && m.query_id == broad.query_id));
+        assert!(core.get_hunt_session("globex", id).await.is_err());
+
+        let rule = core.convert_session_to_rule("acme", id, "alice", serde_json::from_str("{}").unwrap()).await.unwrap();
+        assert_eq!(rule.tenant_id.as_deref(), Some("acme"));
+        assert_eq!(rule.detection_logic.conditions.len(), 2);
+        assert!(rule.metadata.tags.contains(&"triage".to_string()));
+        assert!(core.list_rules("acme").await.unwrap().iter().any(|r| r.id == rule.id));
+        assert!(!core.list_rules("globex").await.unwrap().iter().any(|r| r.id == rule.id));
+
+        let closed = core.close_hunt_session("acme", id, "alice").await.unwrap();
+        let kinds: Vec<String> = closed.history.iter().map(|h| serde_json::to_value(&h.action).unwrap()["kind"].as_str().unwrap().to_string()).collect();
+        assert_eq!(kinds, ["opened", "query_run", "annotated", "query_run", "converted_to_rule", "closed"]);
+        assert!(core.run_session_query("acme", id, "alice", SessionQuery { clauses: vec![clause("a", "equals", "b")], ..Default::default() }).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn pinned_matches_become_an_incident_draft() {
+        let core = HuntingCore::new().unwrap();
+        core.ingest_session_events("acme", "edr", vec![event("ws-01", "rundll32.exe", "198.51.100.9")]).await.unwrap();
+        let session = core.open_hunt_session("acme", "alice", "LOLBins", None).await.unwrap();
+        let id = &session.session_id;
+        let (_, matches) = core
+            .run_session_query("acme", id, "alice", SessionQuery { clauses: vec![clause("process_name", "contains", "rundll32")], ..Default::default() })
+            .await
+            .unwrap();
+        assert!(core.convert_session_to_incident("acme", id, "bob", IncidentConversion::default()).await.is_err());
+
+        core.pin_session_match("acme", id, "alice", &matches[0].match_id, true).await.unwrap();
+        let conversion = IncidentConversion { severity: Some("High".to_string()), ..Default::default() };
+        let incident = core.convert_session_to_incident("acme", id, "bob", conversion).await.unwrap();
+        assert_eq!(incident.priority, 2);
+        assert_eq!(incident.affected_systems, vec!["ws-01".to_string()]);
+        assert_eq!(incident.indicators.len(), 1);
+        assert_eq!(incident.indicators[0].value, "198.51.100.9");
+        assert_eq!(incident.timeline[0].data["match_id"], matches[0].match_id);
+        assert!(incident.evidence.contains(&format!("hunt_session:{}", id)));
+        assert_eq!(core.get_hunt_session("acme", id).await.unwrap().incident_ids, vec![incident.incident_id]);
+    }
+}
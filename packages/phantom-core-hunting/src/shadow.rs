@@ -0,0 +1,520 @@
+//! Shadow rule versions
+//!
+//! An edited rule does not replace the active one straight away. It is
+//! proposed as a shadow version, which the tenant runs alongside the active
+//! version on the same data: either a replayed set of events, or the rule's
+//! connected sources over one fixed time window. Matches of the two runs are
+//! compared by event, and the comparison reports what the edit would newly
+//! match and what would stop matching. A shadow version is promoted to
+//! active only after a comparison against the version currently active, so
+//! analysts always see the delta before it takes effect.
+
+use chrono::{DateTime, Duration, Utc};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::cancellation::{HuntAbort, HuntCompletion};
+use crate::connectors::{event_match, parse_time_range, QueryWindow};
+use crate::correlation::{self, RuleCorrelator};
+use crate::error::{CoreError, CoreResult};
+use crate::ingestion::NormalizedEvent;
+use crate::{conditions, tenancy, HuntingCore, HuntingCoreNapi, HuntingMatch, HuntingRule};
+
+/// Window compared when neither version has a parseable time range
+const DEFAULT_COMPARISON_RANGE_HOURS: i64 = 24;
+/// Delta matches listed per direction; counts always cover all of them
+pub const MAX_DELTA_MATCHES: usize = 500;
+
+/// A match that only one of the two versions produced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeltaMatch {
+    pub source: String,
+    pub timestamp: DateTime<Utc>,
+    pub event_data: HashMap<String, Value>,
+    pub confidence_score: f64,
+    pub risk_score: f64,
+    /// Correlation rules that produced the match, for correlated matches
+    #[serde(default)]
+    pub correlation_ids: Vec<String>,
+}
+
+/// How the candidate's results differ from the active version's on the same data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleVersionDiff {
+    pub comparison_id: String,
+    pub tenant_id: String,
+    pub rule_id: String,
+    pub active_version: String,
+    pub candidate_version: String,
+    pub window: QueryWindow,
+    /// Replayed events, or rows read by the active version from connected sources
+    pub events_processed: u64,
+    pub active_matches: usize,
+    pub candidate_matches: usize,
+    pub unchanged_matches: usize,
+    pub newly_matched_count: usize,
+    pub no_longer_matched_count: usize,
+    pub newly_matched: Vec<DeltaMatch>,
+    pub no_longer_matched: Vec<DeltaMatch>,
+    /// Source failures of either run; the delta misses their rows
+    #[serde(default)]
+    pub source_errors: Vec<String>,
+    pub completion: HuntCompletion,
+    pub compared_at: DateTime<Utc>,
+}
+
+/// An edited rule waiting to be promoted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShadowRule {
+    pub tenant_id: String,
+    pub rule_id: String,
+    pub candidate: HuntingRule,
+    /// Version of the active rule when the candidate was proposed
+    pub base_version: String,
+    pub proposed_by: String,
+    pub proposed_at: DateTime<Utc>,
+    pub last_comparison: Option<RuleVersionDiff>,
+}
+
+#[derive(Default)]
+pub struct ShadowState {
+    /// Shadow versions keyed by tenant and rule id
+    rules: RwLock<HashMap<(String, String), ShadowRule>>,
+}
+
+impl ShadowState {
+    pub(crate) async fn forget_tenant(&self, tenant_id: &str) -> usize {
+        let mut rules = self.rules.write().await;
+        let before = rules.len();
+        rules.retain(|(tenant, _), _| tenant != tenant_id);
+        before - rules.len()
+    }
+}
+
+/// `1.0` -> `1.1`, `3` -> `4`; versions without a numeric tail get `.1` appended
+pub fn next_version(version: &str) -> String {
+    let (head, tail) = match version.rfind('.') {
+        Some(dot) => (&version[..=dot], &version[dot + 1..]),
+        None => ("", version),
+    };
+    match tail.parse::<u64>() {
+        Ok(number) => format!("{}{}", head, number + 1),
+        Err(_) => format!("{}.1", version),
+    }
+}
+
+/// Identity of a match across runs: its source, time, event and correlation
+pub(crate) fn fingerprint(hunt_match: &HuntingMatch) -> String {
+    let fields: BTreeMap<&String, &Value> = hunt_match.event_data.iter().collect();
+    let correlations: Vec<&str> = hunt_match.correlations.iter().map(|c| c.correlation_id.as_str()).collect();
+    format!(
+        "{}|{}|{}|{}",
+        hunt_match.source,
+        hunt_match.timestamp.timestamp_millis(),
+        serde_json::to_string(&fields).unwrap_or_default(),
+        correlations.join(",")
+    )
+}
+
+fn delta(hunt_match: HuntingMatch) -> DeltaMatch {
+    DeltaMatch {
+        correlation_ids: hunt_match.correlations.iter().map(|c| c.correlation_id.clone()).collect(),
+        source: hunt_match.source,
+        timestamp: hunt_match.timestamp,
+        event_data: hunt_match.event_data,
+        confidence_score: hunt_match.confidence_score,
+        risk_score: hunt_match.risk_score,
+    }
+}
+
+/// Matches of `rule` over replayed events, the way continuous hunting evaluates them
+pub fn replay_events(rule: &HuntingRule, events: &[NormalizedEvent]) -> Vec<HuntingMatch> {
+    if RuleCorrelator::new(rule).is_some() {
+        return correlation::correlate(rule, events.iter().map(|e| (e.source.as_str(), e.timestamp, &e.fields)));
+    }
+    events
+        .iter()
+        .filter_map(|event| {
+            let confidence = conditions::evaluate_conditions(&event.fields, &rule.detection_logic.conditions)?;
+            Some(event_match(&event.source, Some(event.timestamp), event.fields.clone(), confidence, rule.severity.risk_weight()))
+        })
+        .collect()
+}
+
+/// Matches of one version over the comparison data
+struct VersionRun {
+    matches: Vec<HuntingMatch>,
+    events_processed: u64,
+    errors: Vec<String>,
+}
+
+impl HuntingCore {
+    /// Propose `candidate` as the next version of an existing rule the tenant may replace
+    pub async fn propose_rule_version(&self, tenant_id: &str, mut candidate: HuntingRule, proposed_by: &str) -> CoreResult<ShadowRule> {
+        let active = {
+            let rules = self.rules.read().await;
+            rules
+                .get(&candidate.id)
+                .filter(|rule| tenancy::rule_visible(rule, tenant_id))
+                .cloned()
+                .ok_or_else(|| CoreError::not_found(format!("Rule {} not found", candidate.id)))?
+        };
+        if !tenancy::rule_writable(&active, tenant_id) {
+            return Err(CoreError::validation(format!("Rule {} belongs to another tenant", active.id)));
+        }
+        candidate.tenant_id = active.tenant_id.clone();
+        if candidate.metadata.version == active.metadata.version {
+            candidate.metadata.version = next_version(&active.metadata.version);
+        }
+        candidate.metadata.last_modified = Utc::now();
+        candidate.performance_metrics = active.performance_metrics.clone();
+        if candidate.test_fixtures.is_empty() {
+            candidate.test_fixtures = active.test_fixtures.clone();
+        }
+
+        let shadow = ShadowRule {
+            tenant_id: tenant_id.to_string(),
+            rule_id: active.id.clone(),
+            candidate,
+            base_version: active.metadata.version.clone(),
+            proposed_by: proposed_by.to_string(),
+            proposed_at: Utc::now(),
+            last_comparison: None,
+        };
+        self.shadow.rules.write().await.insert((tenant_id.to_string(), active.id), shadow.clone());
+        Ok(shadow)
+    }
+
+    pub async fn shadow_rule(&self, tenant_id: &str, rule_id: &str) -> Option<ShadowRule> {
+        self.shadow.rules.read().await.get(&(tenant_id.to_string(), rule_id.to_string())).cloned()
+    }
+
+    /// Drop a rule's shadow version; false when it had none
+    pub async fn discard_rule_version(&self, tenant_id: &str, rule_id: &str) -> bool {
+        self.shadow.rules.write().await.remove(&(tenant_id.to_string(), rule_id.to_string())).is_some()
+    }
+
+    /// Run the active and shadow versions of a rule on the same data and
+    /// report the delta. Replays `events` when given; otherwise both versions
+    /// query the rule's connected sources over `window`, which defaults to the
+    /// longer of the two versions' time ranges ending now.
+    pub async fn compare_rule_versions(&self, tenant_id: &str, rule_id: &str, window: Option<QueryWindow>, events: &[NormalizedEvent]) -> CoreResult<RuleVersionDiff> {
+        let key = (tenant_id.to_string(), rule_id.to_string());
+        let candidate = self
+            .shadow
+            .rules
+            .read()
+            .await
+            .get(&key)
+            .map(|shadow| shadow.candidate.clone())
+            .ok_or_else(|| CoreError::not_found(format!("Rule {} has no shadow version", rule_id)))?;
+        let active = self
+            .rules
+            .read()
+            .await
+            .get(rule_id)
+            .filter(|rule| tenancy::rule_visible(rule, tenant_id))
+            .cloned()
+            .ok_or_else(|| CoreError::not_found(format!("Rule {} not found", rule_id)))?;
+
+        let window = match window {
+            Some(window) if window.start >= window.end => {
+                return Err(CoreError::validation("Comparison window must start before it ends"));
+            }
+            Some(window) => window,
+            None => {
+                let range = [&active, &candidate]
+                    .iter()
+                    .filter_map(|rule| parse_time_range(&rule.query.time_range))
+                    .max()
+                    .unwrap_or_else(|| Duration::hours(DEFAULT_COMPARISON_RANGE_HOURS));
+                let end = Utc::now();
+                QueryWindow { start: end - range, end }
+            }
+        };
+        let replayed: Vec<NormalizedEvent> = events
+            .iter()
+            .filter(|event| event.timestamp >= window.start && event.timestamp <= window.end)
+            .cloned()
+            .collect();
+        if events.is_empty() && self.connected_sources(&active).await.is_empty() && self.connected_sources(&candidate).await.is_empty() {
+            return Err(CoreError::validation(format!("Rule {} has no connected data sources; supply events to compare", rule_id)));
+        }
+
+        let comparison_id = format!("shadow_{}", Uuid::new_v4().simple());
+        let timeout = active.execution_timeout_secs.max(candidate.execution_timeout_secs).map(std::time::Duration::from_secs);
+        let running = self.running_hunts.start(&comparison_id, tenant_id, rule_id, timeout)?;
+        let baseline = self.run_version(tenant_id, &active, &window, &replayed, !events.is_empty(), running.abort()).await;
+        let shadow = self.run_version(tenant_id, &candidate, &window, &replayed, !events.is_empty(), running.abort()).await;
+        let completion = running.abort().outcome().unwrap_or_default();
+        drop(running);
+
+        let active_matches = baseline.matches.len();
+        let candidate_matches = shadow.matches.len();
+        let mut before: BTreeMap<String, HuntingMatch> = baseline.matches.into_iter().map(|m| (fingerprint(&m), m)).collect();
+        let mut newly_matched = Vec::new();
+        let mut unchanged_matches = 0;
+        for hunt_match in shadow.matches {
+            match before.remove(&fingerprint(&hunt_match)) {
+                Some(_) => unchanged_matches += 1,
+                None => newly_matched.push(delta(hunt_match)),
+            }
+        }
+        let mut no_longer_matched: Vec<DeltaMatch> = before.into_values().map(delta).collect();
+        newly_matched.sort_by_key(|m| m.timestamp);
+        no_longer_matched.sort_by_key(|m| m.timestamp);
+        let (newly_matched_count, no_longer_matched_count) = (newly_matched.len(), no_longer_matched.len());
+        newly_matched.truncate(MAX_DELTA_MATCHES);
+        no_longer_matched.truncate(MAX_DELTA_MATCHES);
+
+        let mut source_errors: Vec<String> = baseline.errors.into_iter().map(|e| format!("active: {}", e)).collect();
+        source_errors.extend(shadow.errors.into_iter().map(|e| format!("shadow: {}", e)));
+        let diff = RuleVersionDiff {
+            comparison_id,
+            tenant_id: tenant_id.to_string(),
+            rule_id: rule_id.to_string(),
+            active_version: active.metadata.version.clone(),
+            candidate_version: candidate.metadata.version.clone(),
+            window,
+            events_processed: baseline.events_processed,
+            active_matches,
+            candidate_matches,
+            unchanged_matches,
+            newly_matched_count,
+            no_longer_matched_count,
+            newly_matched,
+            no_longer_matched,
+            source_errors,
+            completion,
+            compared_at: Utc::now(),
+        };
+
+        // The candidate may have been replaced or discarded while the runs were in flight
+        if let Some(shadow) = self.shadow.rules.write().await.get_mut(&key) {
+            if shadow.candidate.metadata.version == diff.candidate_version {
+                shadow.last_comparison = Some(diff.clone());
+            }
+        }
+        log::info!(
+            "Compared rule {} {} against {}: +{} -{} ={}",
+            rule_id, diff.candidate_version, diff.active_version, diff.newly_matched_count, diff.no_longer_matched_count, diff.unchanged_matches
+        );
+        Ok(diff)
+    }
+
+    async fn run_version(&self, tenant_id: &str, rule: &HuntingRule, window: &QueryWindow, events: &[NormalizedEvent], replay: bool, abort: &HuntAbort) -> VersionRun {
+        let (mut matches, events_processed, errors) = if replay {
+            (replay_events(rule, events), events.len() as u64, Vec::new())
+        } else {
+            let connected = self.connected_sources(rule).await;
+            let mut hunt = self.hunt_connected_sources(rule, connected, None, Some(window), abort).await;
+            let correlated = correlation::correlate_matches(rule, &hunt.matches);
+            hunt.matches.extend(correlated);
+            (hunt.matches, hunt.events_processed, hunt.errors)
+        };
+        self.suppression.suppress_matches(tenant_id, &mut matches);
+        VersionRun { matches, events_processed, errors }
+    }
+
+    /// Make the shadow version the active rule. Requires a complete
+    /// comparison against the version that is active now.
+    pub async fn promote_rule_version(&self, tenant_id: &str, rule_id: &str) -> CoreResult<HuntingRule> {
+        let key = (tenant_id.to_string(), rule_id.to_string());
+        let mut shadows = self.shadow.rules.write().await;
+        let shadow = shadows
+            .get(&key)
+            .ok_or_else(|| CoreError::not_found(format!("Rule {} has no shadow version", rule_id)))?;
+        let mut rules = self.rules.write().await;
+        let active = rules
+            .get_mut(rule_id)
+            .filter(|rule| tenancy::rule_writable(rule, tenant_id))
+            .ok_or_else(|| CoreError::not_found(format!("Rule {} not found", rule_id)))?;
+        match &shadow.last_comparison {
+            None => return Err(CoreError::validation(format!("Compare rule {} with its shadow version before promoting it", rule_id))),
+            Some(diff) if diff.active_version != active.metadata.version => {
+                return Err(CoreError::validation(format!(
+                    "Rule {} changed to version {} since the last comparison; compare again",
+                    rule_id, active.metadata.version
+                )));
+            }
+            Some(diff) if diff.completion != HuntCompletion::Completed => {
+                return Err(CoreError::validation(format!("Last comparison of rule {} was {}; compare again", rule_id, diff.completion)));
+            }
+            Some(_) => {}
+        }
+
+        let mut promoted = shadow.candidate.clone();
+        promoted.performance_metrics = active.performance_metrics.clone();
+        promoted.metadata.last_modified = Utc::now();
+        *active = promoted.clone();
+        shadows.remove(&key);
+        log::info!("Promoted rule {} to version {}", rule_id, promoted.metadata.version);
+        Ok(promoted)
+    }
+}
+
+#[napi]
+impl HuntingCoreNapi {
+    /// Propose an edited rule (full rule JSON, same id) as its shadow version
+    #[napi]
+    pub async fn propose_rule_version(&self, rule_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let candidate: HuntingRule = serde_json::from_str(&rule_json)
+            .map_err(|e| CoreError::from(e).context("Failed to parse rule"))?;
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let rule_id = candidate.id.clone();
+        let actor = self.authorize(auth_token, "rule:manage", &rule_id)?;
+        let shadow = self.inner.propose_rule_version(&tenant_id, candidate, &actor).await;
+        let shadow = self.audit.record(&actor, "propose_rule_version", &rule_id, json!({ "rule": rule_json }), shadow)
+            .map_err(|e| e.context("Failed to propose rule version"))?;
+        serde_json::to_string(&shadow)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize shadow rule: {}", e)))
+    }
+
+    #[napi]
+    pub async fn get_shadow_rule(&self, rule_id: String, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        self.inner.shadow_rule(&tenant_id, &rule_id).await
+            .map(|shadow| serde_json::to_string(&shadow))
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize shadow rule: {}", e)))
+    }
+
+    /// Compare a rule's active and shadow versions over a JSON array of
+    /// events, or over `{"start": ..., "end": ...}` of its connected sources
+    #[napi]
+    pub async fn compare_rule_versions(&self, rule_id: String, window_json: Option<String>, events_json: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", &rule_id)?;
+        let window: Option<QueryWindow> = window_json
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()
+            .map_err(|e| CoreError::from(e).context("Failed to parse comparison window"))?;
+        let records: Vec<Value> = events_json
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()
+            .map_err(|e| CoreError::from(e).context("Failed to parse events"))?
+            .unwrap_or_default();
+        let events = correlation::events_from_records(&records);
+
+        let diff = self.inner.compare_rule_versions(&tenant_id, &rule_id, window, &events).await;
+        let diff = self.audit.record(&actor, "compare_rule_versions", &rule_id, json!({ "window": window, "events": records.len() }), diff)
+            .map_err(|e| e.context("Failed to compare rule versions"))?;
+        serde_json::to_string(&diff)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize rule version diff: {}", e)))
+    }
+
+    /// Replace the active rule with its compared shadow version
+    #[napi]
+    pub async fn promote_rule_version(&self, rule_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &rule_id)?;
+        let rule = self.inner.promote_rule_version(&tenant_id, &rule_id).await;
+        let rule = self.audit.record(&actor, "promote_rule_version", &rule_id, json!({}), rule)
+            .map_err(|e| e.context("Failed to promote rule version"))?;
+        serde_json::to_string(&rule)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize rule: {}", e)))
+    }
+
+    #[napi]
+    pub async fn discard_rule_version(&self, rule_id: String, auth_token: Option<String>) -> napi::Result<bool> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &rule_id)?;
+        let discarded = self.inner.discard_rule_version(&tenant_id, &rule_id).await;
+        self.audit.record(&actor, "discard_rule_version", &rule_id, json!({}), Ok::<_, String>(discarded))
+            .map_err(napi::Error::from_reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenancy::DEFAULT_TENANT;
+    use crate::DetectionCondition;
+
+    fn event(minutes_ago: i64, user: &str, failures: u64) -> NormalizedEvent {
+        let timestamp = Utc::now() - Duration::minutes(minutes_ago);
+        NormalizedEvent {
+            timestamp,
+            received_at: timestamp,
+            source: "auth".to_string(),
+            fields: HashMap::from([("user".to_string(), json!(user)), ("failures".to_string(), json!(failures))]),
+        }
+    }
+
+    fn condition(field: &str, operator: &str, value: Value) -> DetectionCondition {
+        DetectionCondition {
+            condition_id: format!("{}-{}", field, operator),
+            field: field.to_string(),
+            operator: operator.to_string(),
+            value,
+            weight: 1.0,
+            required: true,
+        }
+    }
+
+    #[test]
+    fn test_next_version() {
+        assert_eq!(next_version("1.0"), "1.1");
+        assert_eq!(next_version("2.9"), "2.10");
+        assert_eq!(next_version("7"), "8");
+        assert_eq!(next_version("beta"), "beta.1");
+    }
+
+    #[tokio::test]
+    async fn test_shadow_version_reports_delta_before_promotion() {
+        let core = HuntingCore::new().unwrap();
+        // A default rule whose range covers every event below
+        let mut rule = core.rules.read().await["apt_lateral_movement"].clone();
+        rule.detection_logic.correlation_rules.clear();
+        rule.detection_logic.conditions = vec![condition("failures", "gt", json!(5))];
+        let rule_id = rule.id.clone();
+        core.rules.write().await.insert(rule_id.clone(), rule.clone());
+
+        let events = vec![event(30, "alice", 3), event(20, "bob", 8), event(10, "svc_scan", 40), event(5, "carol", 6)];
+        assert_eq!(core.compare_rule_versions(DEFAULT_TENANT, &rule_id, None, &events).await.unwrap_err().code(), "NOT_FOUND");
+
+        // Lower the threshold but exclude the scanner account
+        let mut candidate = rule.clone();
+        candidate.detection_logic.conditions = vec![condition("failures", "gt", json!(2)), condition("user", "none_of", json!(["svc_scan"]))];
+        let shadow = core.propose_rule_version(DEFAULT_TENANT, candidate, "analyst").await.unwrap();
+        assert_eq!(shadow.candidate.metadata.version, next_version(&rule.metadata.version));
+        assert!(core.promote_rule_version(DEFAULT_TENANT, &rule_id).await.unwrap_err().to_string().contains("Compare"));
+        // Hunts keep running the active version
+        assert_eq!(core.rules.read().await[&rule_id].metadata.version, rule.metadata.version);
+
+        let diff = core.compare_rule_versions(DEFAULT_TENANT, &rule_id, None, &events).await.unwrap();
+        assert_eq!((diff.active_matches, diff.candidate_matches, diff.unchanged_matches), (3, 3, 2));
+        assert_eq!(diff.newly_matched.iter().map(|m| m.event_data["user"].clone()).collect::<Vec<_>>(), [json!("alice")]);
+        assert_eq!(diff.no_longer_matched.iter().map(|m| m.event_data["user"].clone()).collect::<Vec<_>>(), [json!("svc_scan")]);
+        assert_eq!(diff.events_processed, 4);
+        assert_eq!(diff.completion, HuntCompletion::Completed);
+
+        let promoted = core.promote_rule_version(DEFAULT_TENANT, &rule_id).await.unwrap();
+        assert_eq!(promoted.metadata.version, diff.candidate_version);
+        assert_eq!(core.rules.read().await[&rule_id].detection_logic.conditions.len(), 2);
+        assert!(core.shadow_rule(DEFAULT_TENANT, &rule_id).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_promotion_requires_comparison_against_current_version() {
+        let core = HuntingCore::new().unwrap();
+        let rule = core.list_rules(DEFAULT_TENANT).await.unwrap().remove(0);
+        let rule_id = rule.id.clone();
+        assert_eq!(core.propose_rule_version("acme", rule.clone(), "analyst").await.unwrap_err().code(), "VALIDATION");
+
+        core.propose_rule_version(DEFAULT_TENANT, rule.clone(), "analyst").await.unwrap();
+        core.compare_rule_versions(DEFAULT_TENANT, &rule_id, None, &[event(1, "alice", 9)]).await.unwrap();
+        // The active rule is edited directly after the comparison
+        core.rules.write().await.get_mut(&rule_id).unwrap().metadata.version = "9.0".to_string();
+        assert!(core.promote_rule_version(DEFAULT_TENANT, &rule_id).await.unwrap_err().to_string().contains("compare again"));
+
+        assert!(core.discard_rule_version(DEFAULT_TENANT, &rule_id).await);
+        assert!(!core.discard_rule_version(DEFAULT_TENANT, &rule_id).await);
+    }
+}
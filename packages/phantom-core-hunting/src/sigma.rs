@@ -0,0 +1,1331 @@
+//! Sigma rule ingestion and backend translation
+//!
+//! Parses YAML Sigma rules (single rules or multi-document packs), converts
+//! them into `HuntingRule`s and renders their detection logic as KQL, SQL or
+//! SPL. The translated queries are exact; the `DetectionCondition` list built
+//! for the local evaluator is a flattened approximation, since the evaluator
+//! only understands required/optional conditions rather than a boolean tree.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_yaml::Value as Yaml;
+use std::collections::HashMap;
+
+use crate::{
+    DetectionCondition, DetectionLogic, HuntingCategory, HuntingCore, HuntingQuery, HuntingRule,
+    HuntingRuleMetadata, HuntingSeverity, MITREMapping, QueryLanguage, ResourceUsage,
+    RulePerformanceMetrics, RuleType, tenancy,
+};
+
+/// Field used for keyword searches that are not bound to a field
+pub const RAW_EVENT_FIELD: &str = "_raw";
+
+// Backends
+
+/// Query languages a Sigma rule can be translated to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SigmaBackend {
+    KQL,
+    SQL,
+    SPL,
+}
+
+impl SigmaBackend {
+    pub const ALL: [SigmaBackend; 3] = [SigmaBackend::KQL, SigmaBackend::SQL, SigmaBackend::SPL];
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "kql" | "kusto" => Ok(SigmaBackend::KQL),
+            "sql" => Ok(SigmaBackend::SQL),
+            "spl" | "splunk" => Ok(SigmaBackend::SPL),
+            other => Err(format!("Unsupported Sigma backend '{}'", other)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SigmaBackend::KQL => "kql",
+            SigmaBackend::SQL => "sql",
+            SigmaBackend::SPL => "spl",
+        }
+    }
+
+    pub fn query_language(&self) -> QueryLanguage {
+        match self {
+            SigmaBackend::KQL => QueryLanguage::KQL,
+            SigmaBackend::SQL => QueryLanguage::SQL,
+            SigmaBackend::SPL => QueryLanguage::SPL,
+        }
+    }
+
+    fn and(&self) -> &'static str {
+        match self {
+            SigmaBackend::KQL => " and ",
+            _ => " AND ",
+        }
+    }
+
+    fn or(&self) -> &'static str {
+        match self {
+            SigmaBackend::KQL => " or ",
+            _ => " OR ",
+        }
+    }
+
+    fn not(&self, inner: &str) -> String {
+        match self {
+            SigmaBackend::KQL => format!("not({})", inner),
+            _ => format!("NOT ({})", inner),
+        }
+    }
+}
+
+// Parsed rule
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SigmaLogSource {
+    pub product: Option<String>,
+    pub category: Option<String>,
+    pub service: Option<String>,
+}
+
+impl SigmaLogSource {
+    /// Table / sourcetype name the translated queries run against
+    pub fn table_name(&self) -> String {
+        let parts: Vec<String> = [&self.product, &self.category, &self.service]
+            .into_iter()
+            .flatten()
+            .map(|part| {
+                part.to_ascii_lowercase()
+                    .chars()
+                    .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                    .collect()
+            })
+            .collect();
+        if parts.is_empty() {
+            "events".to_string()
+        } else {
+            parts.join("_")
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Modifier {
+    Equals,
+    Contains,
+    StartsWith,
+    EndsWith,
+    Regex,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+}
+
+#[derive(Debug, Clone)]
+struct FieldMatcher {
+    field: String,
+    modifier: Modifier,
+    values: Vec<Value>,
+    all: bool,
+    cased: bool,
+}
+
+#[derive(Debug, Clone)]
+enum SearchGroup {
+    /// Every matcher must hold
+    Fields(Vec<FieldMatcher>),
+    /// Any keyword may appear anywhere in the raw event
+    Keywords(Vec<Value>),
+}
+
+/// Named detection item; any of its groups may match
+#[derive(Debug, Clone)]
+struct Selection {
+    name: String,
+    groups: Vec<SearchGroup>,
+}
+
+#[derive(Debug, Clone)]
+enum ConditionExpr {
+    Selection(String),
+    And(Vec<ConditionExpr>),
+    Or(Vec<ConditionExpr>),
+    Not(Box<ConditionExpr>),
+}
+
+/// A Sigma rule parsed from YAML
+#[derive(Debug, Clone)]
+pub struct SigmaRule {
+    pub title: String,
+    pub id: Option<String>,
+    pub status: Option<String>,
+    pub description: Option<String>,
+    pub author: Option<String>,
+    pub references: Vec<String>,
+    pub tags: Vec<String>,
+    pub level: Option<String>,
+    pub logsource: SigmaLogSource,
+    pub falsepositives: Vec<String>,
+    selections: Vec<Selection>,
+    condition: ConditionExpr,
+}
+
+impl SigmaRule {
+    /// Parse a single-document Sigma rule
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let doc: Yaml = serde_yaml::from_str(source).map_err(|e| format!("Invalid YAML: {}", e))?;
+        Self::from_yaml(&doc)
+    }
+
+    pub fn from_yaml(doc: &Yaml) -> Result<Self, String> {
+        let map = doc.as_mapping().ok_or("Sigma rule must be a YAML mapping")?;
+        if map.contains_key("action") {
+            return Err("Sigma rule collections with 'action' are not supported".to_string());
+        }
+
+        let title = yaml_str(doc.get("title")).ok_or("Sigma rule is missing 'title'")?;
+        let detection = doc
+            .get("detection")
+            .and_then(Yaml::as_mapping)
+            .ok_or("Sigma rule is missing 'detection'")?;
+
+        let mut selections = Vec::new();
+        let mut condition_source = None;
+        for (key, value) in detection {
+            let name = key.as_str().ok_or("Detection keys must be strings")?;
+            match name {
+                "condition" => condition_source = Some(value.clone()),
+                "timeframe" => return Err("Aggregations with 'timeframe' are not supported".to_string()),
+                _ => selections.push(parse_selection(name, value)?),
+            }
+        }
+        if selections.is_empty() {
+            return Err("Sigma detection has no search identifiers".to_string());
+        }
+
+        let condition = match condition_source {
+            Some(Yaml::String(text)) => parse_condition(&text, &selections)?,
+            Some(Yaml::Sequence(items)) => {
+                let mut exprs = Vec::new();
+                for item in items {
+                    let text = item.as_str().ok_or("Sigma conditions must be strings")?;
+                    exprs.push(parse_condition(text, &selections)?);
+                }
+                ConditionExpr::Or(exprs)
+            }
+            Some(_) => return Err("Sigma condition must be a string or list".to_string()),
+            None => return Err("Sigma detection is missing 'condition'".to_string()),
+        };
+
+        let logsource = doc
+            .get("logsource")
+            .map(|ls| SigmaLogSource {
+                product: yaml_str(ls.get("product")),
+                category: yaml_str(ls.get("category")),
+                service: yaml_str(ls.get("service")),
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            title,
+            id: yaml_str(doc.get("id")),
+            status: yaml_str(doc.get("status")),
+            description: yaml_str(doc.get("description")),
+            author: yaml_str(doc.get("author")),
+            references: yaml_str_list(doc.get("references")),
+            tags: yaml_str_list(doc.get("tags")),
+            level: yaml_str(doc.get("level")),
+            logsource,
+            falsepositives: yaml_str_list(doc.get("falsepositives")),
+            selections,
+            condition,
+        })
+    }
+
+    /// Hunting rule id derived from the Sigma id, or the title when absent
+    pub fn rule_id(&self) -> String {
+        let base = self.id.clone().unwrap_or_else(|| {
+            self.title
+                .to_ascii_lowercase()
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect()
+        });
+        format!("sigma_{}", base)
+    }
+
+    /// Render the rule as a query in `backend`
+    pub fn translate(&self, backend: SigmaBackend) -> Result<String, String> {
+        let expr = self.render_expr(&self.condition, backend)?;
+        let table = self.logsource.table_name();
+        Ok(match backend {
+            SigmaBackend::KQL => format!("{} | where {}", table, expr),
+            SigmaBackend::SQL => format!("SELECT * FROM {} WHERE {}", table, expr),
+            SigmaBackend::SPL => format!("search sourcetype=\"{}\" | where {}", table, expr),
+        })
+    }
+
+    /// Convert into a hunting rule whose primary query is in `backend`
+    pub fn to_hunting_rule(&self, backend: SigmaBackend) -> Result<HuntingRule, String> {
+        let primary_query = self.translate(backend)?;
+        let mut conditions = Vec::new();
+        self.collect_conditions(&self.condition, false, true, &mut conditions);
+
+        let tactics: Vec<String> = self
+            .tags
+            .iter()
+            .filter_map(|tag| tag.strip_prefix("attack."))
+            .filter(|name| tactic_category(name).is_some())
+            .map(|name| name.replace('-', "_"))
+            .collect();
+        let category = tactics
+            .first()
+            .and_then(|name| tactic_category(name))
+            .unwrap_or_else(|| HuntingCategory::CustomCategory("sigma".to_string()));
+        let attack_phases: Vec<String> = tactics.iter().map(|t| tactic_display_name(t)).collect();
+
+        let confidence = match self.status.as_deref() {
+            Some("stable") => 0.9,
+            Some("test") => 0.75,
+            Some("experimental") => 0.6,
+            _ => 0.5,
+        };
+        let mitre_techniques = self.mitre_mappings(attack_phases.first().cloned().unwrap_or_default(), confidence);
+
+        let severity = match self.level.as_deref().map(str::to_ascii_lowercase).as_deref() {
+            Some("critical") => HuntingSeverity::Critical,
+            Some("high") => HuntingSeverity::High,
+            Some("low") => HuntingSeverity::Low,
+            Some("informational") => HuntingSeverity::Informational,
+            _ => HuntingSeverity::Medium,
+        };
+
+        let now = Utc::now();
+        Ok(HuntingRule {
+            id: self.rule_id(),
+            tenant_id: None,
+            name: self.title.clone(),
+            description: self.description.clone().unwrap_or_default(),
+            category,
+            severity,
+            query: HuntingQuery {
+                query_language: backend.query_language(),
+                primary_query,
+                secondary_queries: Vec::new(),
+                correlation_queries: Vec::new(),
+                time_range: "last 24 hours".to_string(),
+                filters: Vec::new(),
+                aggregations: Vec::new(),
+            },
+            data_sources: Vec::new(),
+            mitre_techniques,
+            detection_logic: DetectionLogic {
+                rule_type: RuleType::Signature,
+                conditions,
+                correlation_rules: Vec::new(),
+                time_windows: Vec::new(),
+                statistical_models: Vec::new(),
+            },
+            false_positive_mitigation: self.falsepositives.clone(),
+            validation_rules: Vec::new(),
+            response_actions: Vec::new(),
+            metadata: HuntingRuleMetadata {
+                author: self.author.clone().unwrap_or_else(|| "Sigma".to_string()),
+                creation_date: now,
+                last_modified: now,
+                version: "1.0".to_string(),
+                tags: self.tags.clone(),
+                references: self.references.clone(),
+                attack_phases,
+                target_platforms: self.logsource.product.iter().cloned().collect(),
+                data_source_requirements: [&self.logsource.category, &self.logsource.service]
+                    .into_iter()
+                    .flatten()
+                    .cloned()
+                    .collect(),
+            },
+            performance_metrics: RulePerformanceMetrics {
+                total_executions: 0,
+                total_matches: 0,
+                false_positives: 0,
+                true_positives: 0,
+                average_execution_time: 0.0,
+                resource_usage: ResourceUsage {
+                    cpu_usage: 0.0,
+                    memory_usage: 0,
+                    network_usage: 0,
+                    storage_usage: 0,
+                },
+                effectiveness_score: 0.0,
+                last_updated: now,
+            },
+            execution_timeout_secs: None,
+            test_fixtures: Vec::new(),
+        })
+    }
+
+    fn mitre_mappings(&self, tactic: String, confidence: f64) -> Vec<MITREMapping> {
+        let mut mappings: Vec<MITREMapping> = Vec::new();
+        for tag in &self.tags {
+            let Some(technique) = tag.strip_prefix("attack.") else { continue };
+            let technique = technique.to_ascii_uppercase();
+            if !technique.starts_with('T') || !technique[1..].chars().next().is_some_and(|c| c.is_ascii_digit()) {
+                continue;
+            }
+            let base = technique.split('.').next().unwrap_or(&technique).to_string();
+            let index = match mappings.iter().position(|m| m.technique_id == base) {
+                Some(index) => index,
+                None => {
+                    mappings.push(MITREMapping {
+                        technique_id: base.clone(),
+                        technique_name: base.clone(),
+                        tactic: tactic.clone(),
+                        sub_techniques: Vec::new(),
+                        confidence,
+                        detection_coverage: 0.0,
+                    });
+                    mappings.len() - 1
+                }
+            };
+            if technique != base {
+                mappings[index].sub_techniques.push(technique);
+            }
+        }
+        mappings
+    }
+
+    fn selection(&self, name: &str) -> Option<&Selection> {
+        self.selections.iter().find(|s| s.name == name)
+    }
+
+    // Query rendering
+
+    fn render_expr(&self, expr: &ConditionExpr, backend: SigmaBackend) -> Result<String, String> {
+        Ok(match expr {
+            ConditionExpr::Selection(name) => {
+                let selection = self
+                    .selection(name)
+                    .ok_or_else(|| format!("Unknown search identifier '{}'", name))?;
+                let groups: Vec<String> = selection
+                    .groups
+                    .iter()
+                    .map(|group| render_group(group, backend))
+                    .collect();
+                join_clauses(groups, backend.or())
+            }
+            ConditionExpr::And(items) => join_clauses(
+                items.iter().map(|e| self.render_expr(e, backend)).collect::<Result<_, _>>()?,
+                backend.and(),
+            ),
+            ConditionExpr::Or(items) => join_clauses(
+                items.iter().map(|e| self.render_expr(e, backend)).collect::<Result<_, _>>()?,
+                backend.or(),
+            ),
+            ConditionExpr::Not(inner) => backend.not(&self.render_expr(inner, backend)?),
+        })
+    }
+
+    // Evaluator conditions
+
+    fn collect_conditions(
+        &self,
+        expr: &ConditionExpr,
+        negated: bool,
+        required: bool,
+        out: &mut Vec<DetectionCondition>,
+    ) {
+        match expr {
+            ConditionExpr::Selection(name) => {
+                let Some(selection) = self.selection(name) else { return };
+                // A negated disjunction of groups is a conjunction, so only a
+                // single group can keep `required` when not negated.
+                let required = required && (selection.groups.len() == 1 || negated);
+                for group in &selection.groups {
+                    match group {
+                        SearchGroup::Fields(matchers) => {
+                            let required = required && (!negated || matchers.len() == 1);
+                            for matcher in matchers {
+                                matcher_conditions(&selection.name, matcher, negated, required, out);
+                            }
+                        }
+                        SearchGroup::Keywords(values) => {
+                            let matcher = FieldMatcher {
+                                field: RAW_EVENT_FIELD.to_string(),
+                                modifier: Modifier::Contains,
+                                values: values.clone(),
+                                all: false,
+                                cased: false,
+                            };
+                            matcher_conditions(&selection.name, &matcher, negated, required, out);
+                        }
+                    }
+                }
+            }
+            ConditionExpr::And(items) => {
+                let required = required && !negated;
+                for item in items {
+                    self.collect_conditions(item, negated, required, out);
+                }
+            }
+            ConditionExpr::Or(items) => {
+                let required = required && (negated || items.len() == 1);
+                for item in items {
+                    self.collect_conditions(item, negated, required, out);
+                }
+            }
+            ConditionExpr::Not(inner) => self.collect_conditions(inner, !negated, required, out),
+        }
+    }
+}
+
+fn tactic_category(name: &str) -> Option<HuntingCategory> {
+    Some(match name.replace('-', "_").as_str() {
+        "reconnaissance" => HuntingCategory::Reconnaissance,
+        "initial_access" => HuntingCategory::InitialAccess,
+        "execution" => HuntingCategory::Execution,
+        "persistence" => HuntingCategory::Persistence,
+        "privilege_escalation" => HuntingCategory::PrivilegeEscalation,
+        "defense_evasion" => HuntingCategory::DefenseEvasion,
+        "credential_access" => HuntingCategory::CredentialAccess,
+        "discovery" => HuntingCategory::Discovery,
+        "lateral_movement" => HuntingCategory::LateralMovement,
+        "collection" => HuntingCategory::Collection,
+        "exfiltration" => HuntingCategory::Exfiltration,
+        "command_and_control" => HuntingCategory::CommandAndControl,
+        "impact" => HuntingCategory::Impact,
+        _ => return None,
+    })
+}
+
+/// "command_and_control" -> "Command and Control"
+fn tactic_display_name(name: &str) -> String {
+    name.split('_')
+        .map(|word| match word {
+            "and" => word.to_string(),
+            _ => {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_ascii_uppercase().to_string() + chars.as_str())
+                    .unwrap_or_default()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn join_clauses(clauses: Vec<String>, separator: &str) -> String {
+    if clauses.len() == 1 {
+        return clauses.into_iter().next().unwrap_or_default();
+    }
+    format!("({})", clauses.join(separator))
+}
+
+fn render_group(group: &SearchGroup, backend: SigmaBackend) -> String {
+    match group {
+        SearchGroup::Fields(matchers) => join_clauses(
+            matchers.iter().map(|m| render_matcher(m, backend)).collect(),
+            backend.and(),
+        ),
+        SearchGroup::Keywords(values) => join_clauses(
+            values
+                .iter()
+                .map(|value| match backend {
+                    SigmaBackend::KQL => format!("* has {}", kql_string(&scalar_text(value))),
+                    _ => render_value(RAW_EVENT_FIELD, Modifier::Contains, false, value, backend),
+                })
+                .collect(),
+            backend.or(),
+        ),
+    }
+}
+
+fn render_matcher(matcher: &FieldMatcher, backend: SigmaBackend) -> String {
+    let clauses = matcher
+        .values
+        .iter()
+        .map(|value| render_value(&matcher.field, matcher.modifier, matcher.cased, value, backend))
+        .collect();
+    join_clauses(clauses, if matcher.all { backend.and() } else { backend.or() })
+}
+
+fn render_value(field: &str, modifier: Modifier, cased: bool, value: &Value, backend: SigmaBackend) -> String {
+    let field = quote_field(field, backend);
+
+    if value.is_null() {
+        return match backend {
+            SigmaBackend::KQL => format!("isempty({})", field),
+            SigmaBackend::SQL => format!("{} IS NULL", field),
+            SigmaBackend::SPL => format!("isnull({})", field),
+        };
+    }
+
+    if let Some(op) = comparison_operator(modifier) {
+        return format!("{} {} {}", field, op, scalar_text(value));
+    }
+
+    if !value.is_string() && modifier == Modifier::Equals {
+        let eq = if backend == SigmaBackend::KQL { "==" } else { "=" };
+        return format!("{} {} {}", field, eq, scalar_text(value));
+    }
+
+    let text = scalar_text(value);
+    if modifier == Modifier::Regex {
+        return match backend {
+            SigmaBackend::KQL => format!("{} matches regex {}", field, kql_string(&text)),
+            SigmaBackend::SQL => format!("{} REGEXP {}", field, sql_string(&text)),
+            SigmaBackend::SPL => format!("match({}, {})", field, spl_string(&text)),
+        };
+    }
+
+    let pieces = wildcard_pieces(&text, modifier);
+    match backend {
+        SigmaBackend::KQL => render_kql_pattern(&field, &pieces, cased),
+        SigmaBackend::SQL => {
+            let (target, pattern) = like_parts(&field, &pieces, cased, "LOWER");
+            match pattern {
+                Like::Exact(literal) => format!("{} = {}", target, sql_string(&literal)),
+                Like::Pattern(pattern) => format!("{} LIKE {}", target, sql_string(&pattern)),
+            }
+        }
+        SigmaBackend::SPL => {
+            let (target, pattern) = like_parts(&field, &pieces, cased, "lower");
+            match pattern {
+                Like::Exact(literal) => format!("{}={}", target, spl_string(&literal)),
+                Like::Pattern(pattern) => format!("like({}, {})", target, spl_string(&pattern)),
+            }
+        }
+    }
+}
+
+fn render_kql_pattern(field: &str, pieces: &[Piece], cased: bool) -> String {
+    let suffix = if cased { "_cs" } else { "" };
+    match pieces {
+        [Piece::Literal(lit)] => format!("{} {} {}", field, if cased { "==" } else { "=~" }, kql_string(lit)),
+        [Piece::AnyMany, Piece::Literal(lit), Piece::AnyMany] => {
+            format!("{} contains{} {}", field, suffix, kql_string(lit))
+        }
+        [Piece::Literal(lit), Piece::AnyMany] => format!("{} startswith{} {}", field, suffix, kql_string(lit)),
+        [Piece::AnyMany, Piece::Literal(lit)] => format!("{} endswith{} {}", field, suffix, kql_string(lit)),
+        _ => format!("{} matches regex {}", field, kql_string(&pieces_regex(pieces, cased))),
+    }
+}
+
+enum Like {
+    Exact(String),
+    Pattern(String),
+}
+
+fn like_parts(field: &str, pieces: &[Piece], cased: bool, lower_fn: &str) -> (String, Like) {
+    let target = if cased { field.to_string() } else { format!("{}({})", lower_fn, field) };
+    let fold = |s: &str| if cased { s.to_string() } else { s.to_lowercase() };
+
+    if let [Piece::Literal(lit)] = pieces {
+        return (target, Like::Exact(fold(lit)));
+    }
+    let mut pattern = String::new();
+    for piece in pieces {
+        match piece {
+            Piece::Literal(lit) => {
+                for c in fold(lit).chars() {
+                    if matches!(c, '%' | '_' | '\\') {
+                        pattern.push('\\');
+                    }
+                    pattern.push(c);
+                }
+            }
+            Piece::AnyMany => pattern.push('%'),
+            Piece::AnyOne => pattern.push('_'),
+        }
+    }
+    (target, Like::Pattern(pattern))
+}
+
+fn quote_field(field: &str, backend: SigmaBackend) -> String {
+    if field.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return field.to_string();
+    }
+    match backend {
+        SigmaBackend::KQL => format!("['{}']", field.replace('\'', "\\'")),
+        SigmaBackend::SQL => format!("\"{}\"", field.replace('"', "\"\"")),
+        SigmaBackend::SPL => format!("'{}'", field.replace('\'', "\\'")),
+    }
+}
+
+fn kql_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn sql_string(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn spl_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn comparison_operator(modifier: Modifier) -> Option<&'static str> {
+    match modifier {
+        Modifier::Gt => Some(">"),
+        Modifier::Gte => Some(">="),
+        Modifier::Lt => Some("<"),
+        Modifier::Lte => Some("<="),
+        _ => None,
+    }
+}
+
+// Wildcards
+
+#[derive(Debug, Clone, PartialEq)]
+enum Piece {
+    Literal(String),
+    AnyMany,
+    AnyOne,
+}
+
+/// Split a Sigma value on its `*`/`?` wildcards, applying the modifier's
+/// implicit leading/trailing wildcard. `\*`, `\?` and `\\` are literals.
+fn wildcard_pieces(value: &str, modifier: Modifier) -> Vec<Piece> {
+    let mut pieces = Vec::new();
+    let mut literal = String::new();
+    let flush = |literal: &mut String, pieces: &mut Vec<Piece>| {
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(std::mem::take(literal)));
+        }
+    };
+
+    if matches!(modifier, Modifier::Contains | Modifier::EndsWith) {
+        pieces.push(Piece::AnyMany);
+    }
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if matches!(chars.peek(), Some('*' | '?' | '\\')) => {
+                literal.push(chars.next().unwrap_or('\\'));
+            }
+            '*' => {
+                flush(&mut literal, &mut pieces);
+                if pieces.last() != Some(&Piece::AnyMany) {
+                    pieces.push(Piece::AnyMany);
+                }
+            }
+            '?' => {
+                flush(&mut literal, &mut pieces);
+                pieces.push(Piece::AnyOne);
+            }
+            _ => literal.push(c),
+        }
+    }
+    flush(&mut literal, &mut pieces);
+    if matches!(modifier, Modifier::Contains | Modifier::StartsWith) && pieces.last() != Some(&Piece::AnyMany) {
+        pieces.push(Piece::AnyMany);
+    }
+    pieces
+}
+
+/// Anchored regex equivalent of `pieces`
+fn pieces_regex(pieces: &[Piece], cased: bool) -> String {
+    let mut pattern = String::from(if cased { "^" } else { "(?i)^" });
+    for piece in pieces {
+        match piece {
+            Piece::Literal(lit) => pattern.push_str(&regex::escape(lit)),
+            Piece::AnyMany => pattern.push_str(".*"),
+            Piece::AnyOne => pattern.push('.'),
+        }
+    }
+    pattern.push('$');
+    pattern
+}
+
+fn matcher_conditions(
+    selection: &str,
+    matcher: &FieldMatcher,
+    negated: bool,
+    required: bool,
+    out: &mut Vec<DetectionCondition>,
+) {
+    let mut push = |operator: &str, value: Value| {
+        out.push(DetectionCondition {
+            condition_id: format!("{}.{}.{}", selection, matcher.field, out.len()),
+            field: matcher.field.clone(),
+            operator: operator.to_string(),
+            value,
+            weight: 1.0,
+            required,
+        });
+    };
+
+    let values: Vec<&Value> = matcher.values.iter().filter(|v| !v.is_null()).collect();
+    if values.is_empty() {
+        push(if negated { "exists" } else { "not_exists" }, Value::Null);
+        return;
+    }
+
+    if let Some(op) = comparison_operator(matcher.modifier) {
+        let numbers: Vec<f64> = values.iter().filter_map(|v| v.as_f64()).collect();
+        // "any of" a lower bound is the loosest bound, and vice versa
+        let bound = match (matcher.all, matcher.modifier) {
+            (false, Modifier::Gt | Modifier::Gte) | (true, Modifier::Lt | Modifier::Lte) => {
+                numbers.iter().copied().fold(f64::INFINITY, f64::min)
+            }
+            _ => numbers.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        };
+        let op = if negated {
+            match op {
+                ">" => "<=",
+                ">=" => "<",
+                "<" => ">=",
+                _ => ">",
+            }
+        } else {
+            op
+        };
+        push(op, serde_json::json!(bound));
+        return;
+    }
+
+    let groups: Vec<Vec<&Value>> = if matcher.all {
+        values.iter().map(|v| vec![*v]).collect()
+    } else {
+        vec![values]
+    };
+    for group in groups {
+        let exact = matcher.modifier == Modifier::Equals
+            && !matcher.cased
+            && group.iter().all(|v| {
+                !v.is_string() || wildcard_pieces(&scalar_text(v), Modifier::Equals).iter().all(|p| matches!(p, Piece::Literal(_)))
+            });
+        if exact {
+            let literals: Vec<Value> = group
+                .iter()
+                .map(|v| match v.as_str() {
+                    Some(text) => Value::String(unescape_literal(text)),
+                    None => (*v).clone(),
+                })
+                .collect();
+            match (literals.len(), negated) {
+                (1, false) => push("equals", literals[0].clone()),
+                (1, true) => push("not_equals", literals[0].clone()),
+                (_, false) => push("in", Value::Array(literals)),
+                (_, true) => push("not_in", Value::Array(literals)),
+            }
+            continue;
+        }
+
+        let alternatives: Vec<String> = group
+            .iter()
+            .map(|v| {
+                let text = scalar_text(v);
+                if matcher.modifier == Modifier::Regex {
+                    text
+                } else {
+                    pieces_regex(&wildcard_pieces(&text, matcher.modifier), matcher.cased)
+                }
+            })
+            .collect();
+        let pattern = if alternatives.len() == 1 {
+            alternatives[0].clone()
+        } else {
+            alternatives.iter().map(|a| format!("(?:{})", a)).collect::<Vec<_>>().join("|")
+        };
+        push(if negated { "not_regex" } else { "regex" }, Value::String(pattern));
+    }
+}
+
+fn unescape_literal(text: &str) -> String {
+    wildcard_pieces(text, Modifier::Equals)
+        .into_iter()
+        .map(|p| match p {
+            Piece::Literal(lit) => lit,
+            _ => String::new(),
+        })
+        .collect()
+}
+
+// YAML parsing
+
+fn yaml_str(value: Option<&Yaml>) -> Option<String> {
+    match value? {
+        Yaml::String(s) => Some(s.clone()),
+        Yaml::Number(n) => Some(n.to_string()),
+        Yaml::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn yaml_str_list(value: Option<&Yaml>) -> Vec<String> {
+    match value {
+        Some(Yaml::Sequence(items)) => items.iter().filter_map(|item| yaml_str(Some(item))).collect(),
+        Some(other) => yaml_str(Some(other)).into_iter().collect(),
+        None => Vec::new(),
+    }
+}
+
+fn yaml_scalar(value: &Yaml) -> Result<Value, String> {
+    Ok(match value {
+        Yaml::Null => Value::Null,
+        Yaml::Bool(b) => Value::Bool(*b),
+        Yaml::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Value::from(i)
+            } else if let Some(u) = n.as_u64() {
+                Value::from(u)
+            } else {
+                serde_json::json!(n.as_f64().unwrap_or_default())
+            }
+        }
+        Yaml::String(s) => Value::String(s.clone()),
+        _ => return Err("Detection values must be scalars".to_string()),
+    })
+}
+
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn parse_selection(name: &str, value: &Yaml) -> Result<Selection, String> {
+    let groups = match value {
+        Yaml::Mapping(map) => vec![parse_field_group(map)?],
+        Yaml::Sequence(items) if items.iter().all(Yaml::is_mapping) => items
+            .iter()
+            .filter_map(Yaml::as_mapping)
+            .map(parse_field_group)
+            .collect::<Result<_, _>>()?,
+        Yaml::Sequence(items) => {
+            vec![SearchGroup::Keywords(items.iter().map(yaml_scalar).collect::<Result<_, _>>()?)]
+        }
+        scalar => vec![SearchGroup::Keywords(vec![yaml_scalar(scalar)?])],
+    };
+    Ok(Selection {
+        name: name.to_string(),
+        groups,
+    })
+}
+
+fn parse_field_group(map: &serde_yaml::Mapping) -> Result<SearchGroup, String> {
+    let mut matchers = Vec::new();
+    for (key, value) in map {
+        let key = key.as_str().ok_or("Detection field names must be strings")?;
+        let mut parts = key.split('|');
+        let field = parts.next().unwrap_or_default().to_string();
+
+        let mut matcher = FieldMatcher {
+            field,
+            modifier: Modifier::Equals,
+            values: Vec::new(),
+            all: false,
+            cased: false,
+        };
+        for modifier in parts {
+            match modifier {
+                "contains" => matcher.modifier = Modifier::Contains,
+                "startswith" => matcher.modifier = Modifier::StartsWith,
+                "endswith" => matcher.modifier = Modifier::EndsWith,
+                "re" => matcher.modifier = Modifier::Regex,
+                "gt" => matcher.modifier = Modifier::Gt,
+                "gte" => matcher.modifier = Modifier::Gte,
+                "lt" => matcher.modifier = Modifier::Lt,
+                "lte" => matcher.modifier = Modifier::Lte,
+                "all" => matcher.all = true,
+                "cased" => matcher.cased = true,
+                other => return Err(format!("Unsupported Sigma modifier '{}' on '{}'", other, key)),
+            }
+        }
+
+        matcher.values = match value {
+            Yaml::Sequence(items) => items.iter().map(yaml_scalar).collect::<Result<_, _>>()?,
+            scalar => vec![yaml_scalar(scalar)?],
+        };
+        if matcher.values.is_empty() {
+            return Err(format!("Field '{}' has no values", key));
+        }
+        if comparison_operator(matcher.modifier).is_some() && !matcher.values.iter().all(Value::is_number) {
+            return Err(format!("Numeric modifier on '{}' requires numeric values", key));
+        }
+        if matcher.modifier == Modifier::Regex {
+            for value in &matcher.values {
+                regex::Regex::new(&scalar_text(value))
+                    .map_err(|e| format!("Invalid regex on '{}': {}", key, e))?;
+            }
+        }
+        matchers.push(matcher);
+    }
+    Ok(SearchGroup::Fields(matchers))
+}
+
+// Condition parsing
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    And,
+    Or,
+    Not,
+    OneOf,
+    AllOf,
+    Open,
+    Close,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut words = Vec::new();
+    let mut current = String::new();
+    for c in text.chars() {
+        match c {
+            '(' | ')' => {
+                if !current.is_empty() {
+                    words.push(std::mem::take(&mut current));
+                }
+                words.push(c.to_string());
+            }
+            c if c.is_whitespace() => {
+                if !current.is_empty() {
+                    words.push(std::mem::take(&mut current));
+                }
+            }
+            '|' => return Err("Sigma aggregation expressions are not supported".to_string()),
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    let mut iter = words.into_iter().peekable();
+    while let Some(word) = iter.next() {
+        let token = match word.as_str() {
+            "(" => Token::Open,
+            ")" => Token::Close,
+            "and" => Token::And,
+            "or" => Token::Or,
+            "not" => Token::Not,
+            "1" | "any" | "all" if iter.peek().map(String::as_str) == Some("of") => {
+                iter.next();
+                if word == "all" {
+                    Token::AllOf
+                } else {
+                    Token::OneOf
+                }
+            }
+            _ => Token::Ident(word),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+struct ConditionParser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    selections: &'a [Selection],
+}
+
+fn parse_condition(text: &str, selections: &[Selection]) -> Result<ConditionExpr, String> {
+    let mut parser = ConditionParser {
+        tokens: tokenize(text)?,
+        pos: 0,
+        selections,
+    };
+    let expr = parser.parse_or()?;
+    if parser.pos != parser.tokens.len() {
+        return Err(format!("Unexpected token in condition '{}'", text));
+    }
+    Ok(expr)
+}
+
+impl ConditionParser<'_> {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.tokens.get(self.pos) == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<ConditionExpr, String> {
+        let mut items = Vec::new();
+        loop {
+            match self.parse_and()? {
+                ConditionExpr::Or(nested) => items.extend(nested),
+                expr => items.push(expr),
+            }
+            if !self.eat(&Token::Or) {
+                break;
+            }
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { ConditionExpr::Or(items) })
+    }
+
+    fn parse_and(&mut self) -> Result<ConditionExpr, String> {
+        let mut items = Vec::new();
+        loop {
+            match self.parse_unary()? {
+                ConditionExpr::And(nested) => items.extend(nested),
+                expr => items.push(expr),
+            }
+            if !self.eat(&Token::And) {
+                break;
+            }
+        }
+        Ok(if items.len() == 1 { items.remove(0) } else { ConditionExpr::And(items) })
+    }
+
+    fn parse_unary(&mut self) -> Result<ConditionExpr, String> {
+        match self.next() {
+            Some(Token::Not) => Ok(ConditionExpr::Not(Box::new(self.parse_unary()?))),
+            Some(Token::Open) => {
+                let expr = self.parse_or()?;
+                if !self.eat(&Token::Close) {
+                    return Err("Unbalanced parentheses in condition".to_string());
+                }
+                Ok(expr)
+            }
+            Some(Token::OneOf) => Ok(ConditionExpr::Or(self.expand_pattern()?)),
+            Some(Token::AllOf) => Ok(ConditionExpr::And(self.expand_pattern()?)),
+            Some(Token::Ident(name)) => {
+                if self.selections.iter().any(|s| s.name == name) {
+                    Ok(ConditionExpr::Selection(name))
+                } else {
+                    Err(format!("Unknown search identifier '{}'", name))
+                }
+            }
+            Some(token) => Err(format!("Unexpected {:?} in condition", token)),
+            None => Err("Condition ended unexpectedly".to_string()),
+        }
+    }
+
+    fn expand_pattern(&mut self) -> Result<Vec<ConditionExpr>, String> {
+        let Some(Token::Ident(pattern)) = self.next() else {
+            return Err("Expected a search identifier pattern after 'of'".to_string());
+        };
+        let matches: Vec<ConditionExpr> = self
+            .selections
+            .iter()
+            .filter(|s| {
+                if pattern == "them" {
+                    !s.name.starts_with('_')
+                } else if let Some(prefix) = pattern.strip_suffix('*') {
+                    s.name.starts_with(prefix)
+                } else {
+                    s.name == pattern
+                }
+            })
+            .map(|s| ConditionExpr::Selection(s.name.clone()))
+            .collect();
+        if matches.is_empty() {
+            return Err(format!("No search identifiers match '{}'", pattern));
+        }
+        Ok(matches)
+    }
+}
+
+// Bulk import
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SigmaImportedRule {
+    pub rule_id: String,
+    pub title: String,
+    pub replaced: bool,
+    /// Translations keyed by backend name ("kql", "sql", "spl")
+    pub queries: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SigmaImportFailure {
+    pub document: usize,
+    pub title: Option<String>,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SigmaImportReport {
+    pub imported: Vec<SigmaImportedRule>,
+    pub failed: Vec<SigmaImportFailure>,
+}
+
+impl HuntingCore {
+    /// Import every rule in a (multi-document) Sigma YAML pack.
+    ///
+    /// Rules that fail to parse or translate are reported and skipped; the
+    /// rest are stored for `tenant_id` with their primary query in `backend`.
+    /// A rule id held by another tenant is reported rather than replaced.
+    pub async fn import_sigma_rules(&self, tenant_id: &str, source: &str, backend: SigmaBackend) -> Result<SigmaImportReport, String> {
+        let mut report = SigmaImportReport::default();
+        let mut converted = Vec::new();
+
+        for (index, document) in serde_yaml::Deserializer::from_str(source).enumerate() {
+            let doc = match Yaml::deserialize(document) {
+                Ok(Yaml::Null) => continue,
+                Ok(doc) => doc,
+                Err(e) => {
+                    report.failed.push(SigmaImportFailure {
+                        document: index,
+                        title: None,
+                        error: format!("Invalid YAML: {}", e),
+                    });
+                    break;
+                }
+            };
+
+            let title = yaml_str(doc.get("title"));
+            let result = SigmaRule::from_yaml(&doc).and_then(|rule| {
+                let mut queries = HashMap::new();
+                for other in SigmaBackend::ALL {
+                    queries.insert(other.name().to_string(), rule.translate(other)?);
+                }
+                Ok((rule.to_hunting_rule(backend)?, queries))
+            });
+            match result {
+                Ok(entry) => converted.push((index, entry)),
+                Err(error) => report.failed.push(SigmaImportFailure {
+                    document: index,
+                    title,
+                    error,
+                }),
+            }
+        }
+
+        let mut rules = self.rules.write().await;
+        for (index, (mut rule, queries)) in converted {
+            let existing = rules.get(&rule.id);
+            if existing.is_some_and(|existing| !tenancy::rule_writable(existing, tenant_id)) {
+                report.failed.push(SigmaImportFailure {
+                    document: index,
+                    title: Some(rule.name.clone()),
+                    error: format!("Rule {} belongs to another tenant", rule.id),
+                });
+                continue;
+            }
+            rule.tenant_id = Some(tenant_id.to_string());
+            report.imported.push(SigmaImportedRule {
+                rule_id: rule.id.clone(),
+                title: rule.name.clone(),
+                replaced: existing.is_some(),
+                queries,
+            });
+            rules.insert(rule.id.clone(), rule);
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conditions::evaluate_conditions;
+    use serde_json::json;
+
+    const RULE: &str = r#"
+title: Suspicious Encoded PowerShell
+id: 5f3c1d2e-0000-4000-8000-000000000001
+status: test
+description: Detects encoded PowerShell command lines
+author: Phantom Tests
+tags:
+  - attack.execution
+  - attack.t1059.001
+logsource:
+  product: windows
+  category: process_creation
+detection:
+  selection_img:
+    Image|endswith: '\powershell.exe'
+  selection_cli:
+    CommandLine|contains:
+      - ' -enc '
+      - ' -EncodedCommand '
+  filter:
+    User: 'SYSTEM'
+  condition: all of selection_* and not filter
+level: high
+"#;
+
+    #[test]
+    fn test_translates_to_each_backend() {
+        let rule = SigmaRule::parse(RULE).unwrap();
+        assert_eq!(rule.rule_id(), "sigma_5f3c1d2e-0000-4000-8000-000000000001");
+
+        assert_eq!(
+            rule.translate(SigmaBackend::KQL).unwrap(),
+            "windows_process_creation | where (Image endswith \"\\\\powershell.exe\" and \
+             (CommandLine contains \" -enc \" or CommandLine contains \" -EncodedCommand \") and \
+             not(User =~ \"SYSTEM\"))"
+        );
+        assert_eq!(
+            rule.translate(SigmaBackend::SQL).unwrap(),
+            "SELECT * FROM windows_process_creation WHERE (LOWER(Image) LIKE '%\\\\powershell.exe' AND \
+             (LOWER(CommandLine) LIKE '% -enc %' OR LOWER(CommandLine) LIKE '% -encodedcommand %') AND \
+             NOT (LOWER(User) = 'system'))"
+        );
+        assert!(rule
+            .translate(SigmaBackend::SPL)
+            .unwrap()
+            .starts_with("search sourcetype=\"windows_process_creation\" | where (like(lower(Image), "));
+
+        let hunting = rule.to_hunting_rule(SigmaBackend::KQL).unwrap();
+        assert!(matches!(hunting.category, HuntingCategory::Execution));
+        assert!(matches!(hunting.severity, HuntingSeverity::High));
+        assert_eq!(hunting.mitre_techniques[0].technique_id, "T1059");
+        assert_eq!(hunting.mitre_techniques[0].sub_techniques, vec!["T1059.001"]);
+    }
+
+    #[test]
+    fn test_detection_conditions_match_events() {
+        let rule = SigmaRule::parse(RULE).unwrap().to_hunting_rule(SigmaBackend::SQL).unwrap();
+        let conditions = &rule.detection_logic.conditions;
+
+        let event = HashMap::from([
+            ("Image".to_string(), json!("C:\\Windows\\System32\\WindowsPowerShell\\v1.0\\powershell.exe")),
+            ("CommandLine".to_string(), json!("powershell.exe -enc SQBFAFgA")),
+            ("User".to_string(), json!("alice")),
+        ]);
+        assert!(evaluate_conditions(&event, conditions).is_some());
+
+        let mut system = event.clone();
+        system.insert("User".to_string(), json!("system"));
+        assert!(evaluate_conditions(&system, conditions).is_none());
+
+        let mut benign = event;
+        benign.insert("CommandLine".to_string(), json!("powershell.exe -File update.ps1"));
+        assert!(evaluate_conditions(&benign, conditions).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_import_reports_failures_and_stores_rules() {
+        let core = HuntingCore::new().unwrap();
+        let pack = format!(
+            "{}\n---\ntitle: Broken\ndetection:\n  selection:\n    Field|base64offset: x\n  condition: selection\n",
+            RULE
+        );
+        let report = core.import_sigma_rules("default", &pack, SigmaBackend::SPL).await.unwrap();
+        assert_eq!(report.imported.len(), 1);
+        assert_eq!(report.imported[0].queries.len(), 3);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].title.as_deref(), Some("Broken"));
+
+        let rules = core.list_rules("default").await.unwrap();
+        let stored = rules.iter().find(|r| r.id == report.imported[0].rule_id).unwrap();
+        assert!(matches!(stored.query.query_language, QueryLanguage::SPL));
+
+        let again = core.import_sigma_rules("default", RULE, SigmaBackend::KQL).await.unwrap();
+        assert!(again.imported[0].replaced);
+        let other = core.import_sigma_rules("acme", RULE, SigmaBackend::KQL).await.unwrap();
+        assert!(other.imported.is_empty());
+        assert_eq!(other.failed.len(), 1);
+    }
+}
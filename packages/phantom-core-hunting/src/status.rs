@@ -0,0 +1,129 @@
+//! Hunting health in the unified platform status model
+//!
+//! Reports the rule catalog, data sources and their connectors, and the hunt
+//! scheduler as dependencies of the hunting core, and exposes
+//! `get_platform_health` to roll them up with the reports of other cores.
+
+use async_trait::async_trait;
+use napi_derive::napi;
+use phantom_enterprise_standards::{ComponentHealth, DependencyHealth, HealthReporter, StatusRegistry};
+use std::sync::Arc;
+
+use crate::{HuntingCore, HuntingCoreNapi};
+
+const COMPONENT: &str = "phantom-hunting-core";
+
+#[async_trait]
+impl HealthReporter for HuntingCore {
+    fn component(&self) -> &str {
+        COMPONENT
+    }
+
+    async fn check_health(&self) -> ComponentHealth {
+        self.component_health().await
+    }
+}
+
+impl HuntingCore {
+    /// Health of the hunting core and the dependencies hunts rely on
+    pub async fn component_health(&self) -> ComponentHealth {
+        let mut health = ComponentHealth::new(COMPONENT).with_version(env!("CARGO_PKG_VERSION"));
+
+        let rules = self.rules.read().await.len();
+        health.dependency(if rules == 0 {
+            DependencyHealth::degraded("rules", "no_rules", "No hunting rules are loaded")
+        } else {
+            DependencyHealth::healthy("rules")
+        });
+
+        let sources: Vec<_> = self.data_sources.read().await.values().cloned().collect();
+        let unserved: Vec<&str> = sources
+            .iter()
+            .filter(|source| self.connector_for(source).is_none())
+            .map(|source| source.source_id.as_str())
+            .collect();
+        health.dependency(if sources.is_empty() {
+            DependencyHealth::degraded("data_sources", "no_data_sources", "No data sources are registered")
+        } else if unserved.len() == sources.len() {
+            DependencyHealth::unhealthy(
+                "data_sources",
+                "connector_unavailable",
+                "No registered data source has a connector in this build",
+            )
+        } else if !unserved.is_empty() {
+            DependencyHealth::degraded(
+                "data_sources",
+                "connector_unavailable",
+                format!("No connector for data sources: {}", unserved.join(", ")),
+            )
+        } else {
+            DependencyHealth::healthy("data_sources")
+        });
+
+        let schedules = self.all_scheduled_hunts().await;
+        let enabled = schedules.iter().filter(|schedule| schedule.enabled).count();
+        if enabled > 0 {
+            let failing: Vec<_> = schedules
+                .iter()
+                .filter(|schedule| schedule.enabled && schedule.last_error.is_some())
+                .collect();
+            health.dependency(if !self.is_scheduler_running().await {
+                DependencyHealth::degraded(
+                    "scheduler",
+                    "scheduler_stopped",
+                    format!("{} enabled schedules but the scheduler is not running", enabled),
+                )
+            } else if let Some(schedule) = failing.first() {
+                DependencyHealth::degraded(
+                    "scheduler",
+                    "scheduled_hunt_failing",
+                    format!(
+                        "{} scheduled hunts failing, {}: {}",
+                        failing.len(),
+                        schedule.rule_id,
+                        schedule.last_error.as_deref().unwrap_or_default()
+                    ),
+                )
+                .optional()
+            } else {
+                DependencyHealth::healthy("scheduler")
+            });
+        }
+
+        health.detail("rules", serde_json::json!(rules));
+        health.detail("data_sources", serde_json::json!(sources.len()));
+        health.detail("scheduled_hunts", serde_json::json!(enabled));
+        health.detail("ml_models", serde_json::json!(self.ml_models.read().await.len()));
+        health
+    }
+}
+
+#[napi]
+impl HuntingCoreNapi {
+    /// Health of this core in the unified component model
+    #[napi]
+    pub async fn get_component_health(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.tenant(auth_token.as_deref())?;
+        serde_json::to_string(&self.inner.component_health().await)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize component health: {}", e)))
+    }
+
+    /// Platform health rolled up from this core and the component reports of its peers
+    ///
+    /// `peer_health_json` is a JSON array of `get_component_health` results
+    /// from the other cores loaded in the process.
+    #[napi]
+    pub async fn get_platform_health(&self, peer_health_json: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        self.tenant(auth_token.as_deref())?;
+        let peers: Vec<ComponentHealth> = match peer_health_json {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| napi::Error::from_reason(format!("Invalid peer health: {}", e)))?,
+            None => Vec::new(),
+        };
+
+        let mut registry = StatusRegistry::new();
+        registry.register(self.inner.clone() as Arc<dyn HealthReporter>);
+        serde_json::to_string(&registry.platform_health(peers).await)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize platform health: {}", e)))
+    }
+}
@@ -0,0 +1,234 @@
+//! Match Suppression
+//!
+//! With `phantom-enterprise-standards` enabled, each tenant keeps allowlists
+//! of known-good domains, IPs, hashes and process paths. A hunt match is
+//! dropped when its network context or an indicator field of its event
+//! (`*_ip`, `*domain*`, `*hash*`, `*process*`, `image`, ...) is allowlisted,
+//! both for executed hunts, which report how many matches were suppressed,
+//! and for continuous hunting over ingested events. Without the feature
+//! nothing is suppressed.
+
+use crate::HuntingMatch;
+use serde_json::Value;
+use std::collections::HashMap;
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::{HuntingCore, HuntingCoreNapi};
+#[cfg(feature = "phantom-enterprise-standards")]
+use napi_derive::napi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::suppression::{SuppressionEntry, SuppressionKind, SuppressionList, SuppressionRule};
+
+#[derive(Default)]
+pub struct SuppressionState {
+    #[cfg(feature = "phantom-enterprise-standards")]
+    list: SuppressionList,
+}
+
+impl SuppressionState {
+    /// Drop the matches an allowlist entry covers, returning how many were dropped
+    pub(crate) fn suppress_matches(&self, tenant_id: &str, matches: &mut Vec<HuntingMatch>) -> u64 {
+        let before = matches.len();
+        matches.retain(|hunt_match| !self.suppresses_match(tenant_id, hunt_match));
+        (before - matches.len()) as u64
+    }
+
+    pub(crate) fn suppresses_match(&self, tenant_id: &str, hunt_match: &HuntingMatch) -> bool {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        if let Some(network) = &hunt_match.context.network_context {
+            if [&network.source_ip, &network.destination_ip]
+                .into_iter()
+                .any(|ip| self.list.check(tenant_id, SuppressionKind::Ip, ip).is_some())
+            {
+                return true;
+            }
+        }
+        self.suppresses_event(tenant_id, &hunt_match.event_data)
+    }
+
+    /// Whether an indicator field of the event is allowlisted
+    pub(crate) fn suppresses_event(&self, tenant_id: &str, fields: &HashMap<String, Value>) -> bool {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            fields.iter().any(|(field, value)| {
+                let (Some(kind), Some(value)) = (field_kind(field), value.as_str()) else {
+                    return false;
+                };
+                self.list.check(tenant_id, kind, value).is_some()
+            })
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        {
+            let _ = (tenant_id, fields);
+            false
+        }
+    }
+
+    pub(crate) fn forget_tenant(&self, tenant_id: &str) -> usize {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            self.list.forget_scope(tenant_id)
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        {
+            let _ = tenant_id;
+            0
+        }
+    }
+}
+
+/// Kind of indicator an event field holds, judged by its name
+#[cfg(feature = "phantom-enterprise-standards")]
+fn field_kind(field: &str) -> Option<SuppressionKind> {
+    let field = snake_case(field);
+    if field == "ip" || field.ends_with("_ip") || field.ends_with(".ip") || field.ends_with("_address") || field.ends_with("addr") {
+        Some(SuppressionKind::Ip)
+    } else if field.contains("domain") || field.contains("fqdn") || field == "dns_query" || field == "query_name" {
+        Some(SuppressionKind::Domain)
+    } else if ["hash", "md5", "sha1", "sha256"].iter().any(|hash| field.contains(hash)) {
+        Some(SuppressionKind::Hash)
+    } else if field.contains("process") || field == "image" || field.ends_with("_image") || field.ends_with("_path") {
+        Some(SuppressionKind::ProcessPath)
+    } else {
+        None
+    }
+}
+
+/// `SourceIP` and `sourceIp` as `source_ip`
+#[cfg(feature = "phantom-enterprise-standards")]
+fn snake_case(field: &str) -> String {
+    let mut snake = String::with_capacity(field.len() + 4);
+    let mut previous_lower = false;
+    for c in field.chars() {
+        if c.is_uppercase() && previous_lower {
+            snake.push('_');
+        }
+        previous_lower = c.is_lowercase() || c.is_ascii_digit();
+        snake.extend(c.to_lowercase());
+    }
+    snake
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+impl HuntingCore {
+    pub fn add_suppression(&self, tenant_id: &str, rule: SuppressionRule, created_by: &str) -> Result<SuppressionEntry, String> {
+        self.suppression.list.add(tenant_id, rule, created_by)
+    }
+
+    pub fn update_suppression(&self, tenant_id: &str, id: &str, rule: SuppressionRule) -> Result<SuppressionEntry, String> {
+        self.suppression.list.update(tenant_id, id, rule)
+    }
+
+    pub fn remove_suppression(&self, tenant_id: &str, id: &str) -> bool {
+        self.suppression.list.remove(tenant_id, id)
+    }
+
+    pub fn get_suppression(&self, tenant_id: &str, id: &str) -> Option<SuppressionEntry> {
+        self.suppression.list.get(tenant_id, id)
+    }
+
+    /// The tenant's entries with their hit counters, expired ones included
+    pub fn list_suppressions(&self, tenant_id: &str) -> Vec<SuppressionEntry> {
+        self.suppression.list.list(tenant_id)
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[napi]
+impl HuntingCoreNapi {
+    /// Allowlist a domain, IP, hash or process path, e.g.
+    /// `{"kind": "ip", "mode": "cidr", "pattern": "10.20.0.0/16"}`
+    #[napi]
+    pub fn add_suppression(&self, rule_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", "suppressions")?;
+        let rule: SuppressionRule = serde_json::from_str(&rule_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse suppression: {}", e)))?;
+        let entry = self.inner.add_suppression(&tenant_id, rule, &actor);
+        let entry = self.audit.record(&actor, "add_suppression", "suppressions", serde_json::json!({ "rule": rule_json }), entry)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to add suppression: {}", e)))?;
+        serde_json::to_string(&entry)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize suppression: {}", e)))
+    }
+
+    /// Replace a suppression's rule; its hit counters are kept
+    #[napi]
+    pub fn update_suppression(&self, id: String, rule_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &id)?;
+        let rule: SuppressionRule = serde_json::from_str(&rule_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse suppression: {}", e)))?;
+        let entry = self.inner.update_suppression(&tenant_id, &id, rule);
+        let entry = self.audit.record(&actor, "update_suppression", &id, serde_json::json!({ "rule": rule_json }), entry)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to update suppression: {}", e)))?;
+        serde_json::to_string(&entry)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize suppression: {}", e)))
+    }
+
+    #[napi]
+    pub fn remove_suppression(&self, id: String, auth_token: Option<String>) -> napi::Result<bool> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &id)?;
+        let removed = self.inner.remove_suppression(&tenant_id, &id);
+        self.audit.record(&actor, "remove_suppression", &id, serde_json::json!({ "removed": removed }), Ok::<_, String>(removed))
+            .map_err(napi::Error::from_reason)
+    }
+
+    #[napi]
+    pub fn get_suppression(&self, id: String, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        self.inner
+            .get_suppression(&tenant_id, &id)
+            .map(|entry| serde_json::to_string(&entry))
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize suppression: {}", e)))
+    }
+
+    #[napi]
+    pub fn list_suppressions(&self, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        serde_json::to_string(&self.inner.list_suppressions(&tenant_id))
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize suppressions: {}", e)))
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use super::*;
+    use crate::HuntingCategory;
+    use phantom_enterprise_standards::suppression::MatchMode;
+
+    #[tokio::test]
+    async fn allowlisted_matches_are_dropped_from_hunts() {
+        let core = HuntingCore::new().unwrap();
+        let rule = core
+            .list_rules("acme")
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|rule| matches!(rule.category, HuntingCategory::LateralMovement))
+            .unwrap();
+        let baseline = core.execute_hunt("acme", &rule.id, None).await.unwrap();
+        assert_eq!((baseline.matches.len(), baseline.suppressed_matches), (5, 0));
+
+        // Simulated logons come from 192.168.1.100-104
+        let cidr = SuppressionRule {
+            kind: SuppressionKind::Ip,
+            mode: MatchMode::Cidr,
+            pattern: "192.168.1.100/31".to_string(),
+            reason: "jump hosts".to_string(),
+            expires_at: None,
+        };
+        let entry = core.add_suppression("acme", cidr, "analyst").unwrap();
+        let result = core.execute_hunt("acme", &rule.id, None).await.unwrap();
+        assert_eq!((result.matches.len(), result.suppressed_matches), (3, 2));
+        assert_eq!(core.get_suppression("acme", &entry.id).unwrap().hit_count, 2);
+        assert_eq!(core.execute_hunt("globex", &rule.id, None).await.unwrap().suppressed_matches, 0);
+
+        assert_eq!(field_kind("SourceIP"), Some(SuppressionKind::Ip));
+        assert_eq!(field_kind("ParentProcessName"), Some(SuppressionKind::ProcessPath));
+        assert_eq!(field_kind("WorkstationName"), None);
+        core.purge_tenant("acme").await;
+        assert!(core.list_suppressions("acme").is_empty());
+    }
+}
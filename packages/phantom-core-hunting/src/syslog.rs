@@ -0,0 +1,158 @@
+//! SIEM Forwarding
+//!
+//! With `phantom-enterprise-standards` enabled and a collector configured,
+//! every match of an executed hunt is forwarded as a CEF or LEEF syslog
+//! event carrying the rule, the host, user and network context of the match
+//! and its scores. Delivery happens in the background and is buffered while
+//! the collector is down. Events of every tenant go to the same collector,
+//! so only platform administrators can configure it. Without the feature
+//! nothing is forwarded.
+
+use crate::{HuntingCore, HuntingResult, HuntingRule};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::{HuntingCoreNapi, HuntingMatch, HuntingSeverity};
+#[cfg(feature = "phantom-enterprise-standards")]
+use napi_derive::napi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::syslog::{ForwarderMetrics, SecurityEvent, SecurityEventKind, SyslogConfig, SyslogForwarder};
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[derive(Default)]
+pub struct SyslogState {
+    forwarder: RwLock<Option<Arc<SyslogForwarder>>>,
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+impl SyslogState {
+    pub fn forwarder(&self) -> Option<Arc<SyslogForwarder>> {
+        self.forwarder.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// SIEM event for one match of a hunt
+#[cfg(feature = "phantom-enterprise-standards")]
+pub fn match_event(result: &HuntingResult, rule: &HuntingRule, hunt_match: &HuntingMatch) -> SecurityEvent {
+    let severity = match rule.severity {
+        HuntingSeverity::Critical => 10,
+        HuntingSeverity::High => 8,
+        HuntingSeverity::Medium => 5,
+        HuntingSeverity::Low => 3,
+        HuntingSeverity::Informational => 1,
+    };
+    let context = &hunt_match.context;
+    let network = context.network_context.as_ref();
+    SecurityEvent::new(SecurityEventKind::HuntMatch, &rule.id, &rule.name, severity)
+        .field("external_id", &hunt_match.match_id)
+        .field("hunt_id", &result.hunt_id)
+        .field("category", format!("{:?}", rule.category))
+        .field("source", &hunt_match.source)
+        .field("source_host", context.system_context.as_ref().map(|system| system.hostname.as_str()).unwrap_or_default())
+        .field("user", context.user_context.as_ref().map(|user| user.user_id.as_str()).unwrap_or_default())
+        .field("source_ip", network.map(|n| n.source_ip.as_str()).unwrap_or_default())
+        .field("destination_ip", network.map(|n| n.destination_ip.as_str()).unwrap_or_default())
+        .field("destination_port", network.map(|n| n.port.to_string()).unwrap_or_default())
+        .field("protocol", network.map(|n| n.protocol.as_str()).unwrap_or_default())
+        .field("confidence", format!("{:.2}", hunt_match.confidence_score))
+        .field("risk_score", format!("{:.2}", hunt_match.risk_score))
+        .field(
+            "mitre_techniques",
+            rule.mitre_techniques.iter().map(|t| t.technique_id.as_str()).collect::<Vec<_>>().join(","),
+        )
+        .field("tenant_id", &result.tenant_id)
+}
+
+impl HuntingCore {
+    /// Forward every match of a finished hunt to the SIEM
+    pub(crate) fn forward_hunt_matches(&self, result: &HuntingResult, rule: &HuntingRule) {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        if let Some(forwarder) = self.syslog.forwarder() {
+            for hunt_match in &result.matches {
+                forwarder.forward(&match_event(result, rule, hunt_match));
+            }
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        let _ = (result, rule);
+    }
+
+    /// Start forwarding to the collector in `config`, or stop with `None`.
+    /// Messages still buffered for a replaced collector are discarded.
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn configure_syslog(&self, config: Option<SyslogConfig>) -> Result<Option<ForwarderMetrics>, String> {
+        let forwarder = config.map(SyslogForwarder::new).transpose()?.map(Arc::new);
+        let metrics = forwarder.as_ref().map(|forwarder| forwarder.metrics());
+        *self.syslog.forwarder.write().unwrap_or_else(|e| e.into_inner()) = forwarder;
+        Ok(metrics)
+    }
+
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn syslog_forwarder(&self) -> Option<Arc<SyslogForwarder>> {
+        self.syslog.forwarder()
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[napi]
+impl HuntingCoreNapi {
+    /// Forward hunt matches to a syslog collector (UDP/TCP/TLS, CEF or
+    /// LEEF); pass no config to stop forwarding
+    #[napi]
+    pub fn configure_syslog_forwarding(&self, config_json: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, "syslog")?;
+        let config: Option<SyslogConfig> = config_json
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse syslog config: {}", e)))?;
+
+        let metrics = self.inner.configure_syslog(config);
+        let metrics = self.audit.record(&actor, "configure_syslog_forwarding", "syslog", serde_json::json!({ "config": config_json }), metrics)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to configure syslog forwarding: {}", e)))?;
+
+        serde_json::to_string(&metrics)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize forwarding metrics: {}", e)))
+    }
+
+    /// Delivery counters of the syslog forwarder; `null` when not configured
+    #[napi]
+    pub fn get_syslog_forwarding_metrics(&self) -> napi::Result<String> {
+        let metrics = self.inner.syslog_forwarder().map(|forwarder| forwarder.metrics());
+        serde_json::to_string(&metrics)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize forwarding metrics: {}", e)))
+    }
+
+    /// Retry delivery of buffered events now
+    #[napi]
+    pub async fn flush_syslog_forwarding(&self) -> napi::Result<String> {
+        let Some(forwarder) = self.inner.syslog_forwarder() else {
+            return Err(napi::Error::from_reason("Syslog forwarding is not configured"));
+        };
+        forwarder.flush().await;
+        serde_json::to_string(&forwarder.metrics())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize forwarding metrics: {}", e)))
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn every_hunt_match_is_queued_for_the_siem() {
+        let core = HuntingCore::new().unwrap();
+        let config = serde_json::json!({ "host": "127.0.0.1", "port": 9, "format": "leef", "buffer_capacity": 100 });
+        core.configure_syslog(Some(serde_json::from_value(config).unwrap())).unwrap();
+
+        let rule = core.list_rules("acme").await.unwrap().remove(0);
+        let result = core.execute_hunt("acme", &rule.id, None).await.unwrap();
+        assert!(!result.matches.is_empty());
+        assert_eq!(core.syslog_forwarder().unwrap().metrics().forwarded, result.matches.len() as u64);
+
+        let event = match_event(&result, &rule, &result.matches[0]);
+        assert_eq!(event.event_class_id, rule.id);
+        assert_eq!(event.fields["hunt_id"], result.hunt_id);
+        assert_eq!(event.fields["tenant_id"], "acme");
+    }
+}
@@ -0,0 +1,654 @@
+//! Sysmon configuration coverage
+//!
+//! Parses a Sysmon XML configuration and works out how much of every Sysmon
+//! event type it logs, which ATT&CK data components that covers, and which
+//! of the tenant's hunting rules lose data to it.
+//!
+//! An event type is logged in full when the configuration does not mention
+//! it or only has filter-less `exclude` rules, partially when `include` or
+//! `exclude` filters select events, and not at all when its `include` rules
+//! are empty. A rule needs an event type when its
+//! `metadata.data_source_requirements` name one (`Process Events`,
+//! `dns_query`, `image_load`, ...) on Windows, or when a Sysmon rule's
+//! conditions, filters or query select its `EventID`. Every event type a
+//! rule needs that is not logged in full is reported as a blind spot.
+
+use napi_derive::napi;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::OnceLock;
+
+use crate::error::{CoreError, CoreResult};
+use crate::{DataSourceType, HuntingCore, HuntingCoreNapi, HuntingRule};
+
+/// A Sysmon event type as it appears under `EventFiltering`
+#[derive(Debug, Clone, Copy)]
+pub struct SysmonEventType {
+    pub element: &'static str,
+    pub event_ids: &'static [u32],
+    pub description: &'static str,
+    /// ATT&CK data components (`Data Source: Data Component`) it provides
+    pub data_components: &'static [&'static str],
+    /// Phrases in a rule's data source requirements that need it
+    requirement_phrases: &'static [&'static str],
+}
+
+/// Filterable Sysmon event types. Longer requirement phrases come first so
+/// `file create time` is not read as `file create`.
+pub const EVENT_TYPES: [SysmonEventType; 22] = [
+    SysmonEventType {
+        element: "ProcessCreate",
+        event_ids: &[1],
+        description: "Process creation",
+        data_components: &["Process: Process Creation", "Command: Command Execution"],
+        requirement_phrases: &["process creation", "process create", "process events", "process execution"],
+    },
+    SysmonEventType {
+        element: "FileCreateTime",
+        event_ids: &[2],
+        description: "File creation time changed",
+        data_components: &["File: File Metadata"],
+        requirement_phrases: &["file create time", "file change", "file metadata", "timestomp"],
+    },
+    SysmonEventType {
+        element: "NetworkConnect",
+        event_ids: &[3],
+        description: "Network connection",
+        data_components: &["Network Traffic: Network Connection Creation"],
+        requirement_phrases: &["network connection", "network connect", "network events"],
+    },
+    SysmonEventType {
+        element: "ProcessTerminate",
+        event_ids: &[5],
+        description: "Process terminated",
+        data_components: &["Process: Process Termination"],
+        requirement_phrases: &["process termination", "process terminate"],
+    },
+    SysmonEventType {
+        element: "DriverLoad",
+        event_ids: &[6],
+        description: "Driver loaded",
+        data_components: &["Driver: Driver Load"],
+        requirement_phrases: &["driver load"],
+    },
+    SysmonEventType {
+        element: "ImageLoad",
+        event_ids: &[7],
+        description: "Image loaded",
+        data_components: &["Module: Module Load"],
+        requirement_phrases: &["image load", "module load"],
+    },
+    SysmonEventType {
+        element: "CreateRemoteThread",
+        event_ids: &[8],
+        description: "Remote thread created",
+        data_components: &["Process: OS API Execution"],
+        requirement_phrases: &["create remote thread", "remote thread"],
+    },
+    SysmonEventType {
+        element: "RawAccessRead",
+        event_ids: &[9],
+        description: "Raw disk access",
+        data_components: &["Drive: Drive Access"],
+        requirement_phrases: &["raw access"],
+    },
+    SysmonEventType {
+        element: "ProcessAccess",
+        event_ids: &[10],
+        description: "Process accessed",
+        data_components: &["Process: Process Access"],
+        requirement_phrases: &["process access"],
+    },
+    SysmonEventType {
+        element: "FileCreateStreamHash",
+        event_ids: &[15],
+        description: "Alternate data stream created",
+        data_components: &["File: File Creation"],
+        requirement_phrases: &["create stream hash", "alternate data stream"],
+    },
+    SysmonEventType {
+        element: "FileCreate",
+        event_ids: &[11],
+        description: "File created",
+        data_components: &["File: File Creation"],
+        requirement_phrases: &["file event", "file creation", "file create"],
+    },
+    SysmonEventType {
+        element: "RegistryEvent",
+        event_ids: &[12, 13, 14],
+        description: "Registry key and value changes",
+        data_components: &["Windows Registry: Windows Registry Key Creation", "Windows Registry: Windows Registry Key Modification", "Windows Registry: Windows Registry Key Deletion"],
+        requirement_phrases: &["registry"],
+    },
+    SysmonEventType {
+        element: "PipeEvent",
+        event_ids: &[17, 18],
+        description: "Named pipe created or connected",
+        data_components: &["Named Pipe: Named Pipe Metadata"],
+        requirement_phrases: &["pipe"],
+    },
+    SysmonEventType {
+        element: "WmiEvent",
+        event_ids: &[19, 20, 21],
+        description: "WMI event filter, consumer and binding",
+        data_components: &["WMI: WMI Creation"],
+        requirement_phrases: &["wmi event", "wmi subscription"],
+    },
+    SysmonEventType {
+        element: "DnsQuery",
+        event_ids: &[22],
+        description: "DNS query",
+        data_components: &["Network Traffic: Network Traffic Content"],
+        requirement_phrases: &["dns"],
+    },
+    SysmonEventType {
+        element: "FileDelete",
+        event_ids: &[23],
+        description: "File deleted and archived",
+        data_components: &["File: File Deletion"],
+        requirement_phrases: &["file delete", "file deletion"],
+    },
+    SysmonEventType {
+        element: "ClipboardChange",
+        event_ids: &[24],
+        description: "Clipboard changed",
+        data_components: &[],
+        requirement_phrases: &["clipboard"],
+    },
+    SysmonEventType {
+        element: "ProcessTampering",
+        event_ids: &[25],
+        description: "Process image tampering",
+        data_components: &["Process: Process Modification"],
+        requirement_phrases: &["process tampering"],
+    },
+    SysmonEventType {
+        element: "FileDeleteDetected",
+        event_ids: &[26],
+        description: "File deletion logged",
+        data_components: &["File: File Deletion"],
+        requirement_phrases: &[],
+    },
+    SysmonEventType {
+        element: "FileBlockExecutable",
+        event_ids: &[27],
+        description: "Executable file creation blocked",
+        data_components: &["File: File Creation"],
+        requirement_phrases: &["file block executable"],
+    },
+    SysmonEventType {
+        element: "FileBlockShredding",
+        event_ids: &[28],
+        description: "File shredding blocked",
+        data_components: &["File: File Deletion"],
+        requirement_phrases: &["file block shredding"],
+    },
+    SysmonEventType {
+        element: "FileExecutableDetected",
+        event_ids: &[29],
+        description: "Executable file creation detected",
+        data_components: &["File: File Creation"],
+        requirement_phrases: &["file executable detected"],
+    },
+];
+
+/// Event type logging `event_id`
+pub fn event_type_for_id(event_id: u32) -> Option<&'static SysmonEventType> {
+    EVENT_TYPES.iter().find(|event_type| event_type.event_ids.contains(&event_id))
+}
+
+fn event_type_for_element(element: &str) -> Option<&'static SysmonEventType> {
+    EVENT_TYPES.iter().find(|event_type| event_type.element.eq_ignore_ascii_case(element))
+}
+
+/// How much of an event type a configuration logs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoverageLevel {
+    Disabled,
+    Partial,
+    Full,
+}
+
+/// One field filter of an `include` or `exclude` rule
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SysmonFilter {
+    pub field: String,
+    /// `is`, `contains`, `end with`, `image`, ...
+    pub condition: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SysmonEventCoverage {
+    pub element: String,
+    pub event_ids: Vec<u32>,
+    pub description: String,
+    pub data_components: Vec<String>,
+    pub level: CoverageLevel,
+    /// Whether the configuration mentions the event type at all
+    pub configured: bool,
+    pub include_filters: Vec<SysmonFilter>,
+    pub exclude_filters: Vec<SysmonFilter>,
+}
+
+impl SysmonEventCoverage {
+    /// Why the event type is not logged in full
+    fn limitation(&self) -> String {
+        match self.level {
+            CoverageLevel::Disabled => "which the configuration excludes".to_string(),
+            CoverageLevel::Partial if !self.include_filters.is_empty() => format!(
+                "which the configuration only logs for events matching {} include filter{}",
+                self.include_filters.len(),
+                if self.include_filters.len() == 1 { "" } else { "s" }
+            ),
+            CoverageLevel::Partial => format!(
+                "which the configuration drops for events matching {} exclude filter{}",
+                self.exclude_filters.len(),
+                if self.exclude_filters.len() == 1 { "" } else { "s" }
+            ),
+            CoverageLevel::Full => "which the configuration logs in full".to_string(),
+        }
+    }
+}
+
+/// A parsed Sysmon configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SysmonConfig {
+    pub schema_version: Option<String>,
+    pub event_types: Vec<SysmonEventCoverage>,
+    /// `EventFiltering` elements that are not Sysmon event types
+    pub unknown_elements: Vec<String>,
+}
+
+#[derive(Default)]
+struct Rules {
+    configured: bool,
+    include: Option<Vec<SysmonFilter>>,
+    exclude: Option<Vec<SysmonFilter>>,
+}
+
+/// Field filters of an event type element, including those grouped in `Rule`
+fn collect_filters(node: roxmltree::Node, out: &mut Vec<SysmonFilter>) {
+    for child in node.children().filter(|child| child.is_element()) {
+        if child.tag_name().name() == "Rule" {
+            collect_filters(child, out);
+        } else {
+            out.push(SysmonFilter {
+                field: child.tag_name().name().to_string(),
+                condition: child.attribute("condition").unwrap_or("is").to_string(),
+                value: child.text().unwrap_or_default().trim().to_string(),
+            });
+        }
+    }
+}
+
+/// Parse a Sysmon XML configuration
+pub fn parse_config(xml: &str) -> CoreResult<SysmonConfig> {
+    let document = roxmltree::Document::parse(xml).map_err(|e| CoreError::validation(format!("Invalid Sysmon configuration: {}", e)))?;
+    let root = document.root_element();
+    if root.tag_name().name() != "Sysmon" {
+        return Err(CoreError::validation(format!("Expected a <Sysmon> configuration, found <{}>", root.tag_name().name())));
+    }
+
+    let mut rules: BTreeMap<&'static str, Rules> = BTreeMap::new();
+    let mut unknown_elements = BTreeSet::new();
+    let filtering = root.children().filter(|child| child.is_element() && child.tag_name().name() == "EventFiltering");
+    for section in filtering {
+        for node in section.children().filter(|child| child.is_element()) {
+            let elements: Vec<roxmltree::Node> = if node.tag_name().name() == "RuleGroup" {
+                node.children().filter(|child| child.is_element()).collect()
+            } else {
+                vec![node]
+            };
+            for element in elements {
+                let Some(event_type) = event_type_for_element(element.tag_name().name()) else {
+                    unknown_elements.insert(element.tag_name().name().to_string());
+                    continue;
+                };
+                let entry = rules.entry(event_type.element).or_default();
+                entry.configured = true;
+                let mut filters = Vec::new();
+                collect_filters(element, &mut filters);
+                let target = match element.attribute("onmatch").map(str::to_ascii_lowercase).as_deref() {
+                    Some("include") => &mut entry.include,
+                    Some("exclude") => &mut entry.exclude,
+                    other => {
+                        return Err(CoreError::validation(format!(
+                            "<{}> needs onmatch=\"include\" or \"exclude\", found {:?}",
+                            event_type.element,
+                            other.unwrap_or_default()
+                        )))
+                    }
+                };
+                target.get_or_insert_with(Vec::new).extend(filters);
+            }
+        }
+    }
+
+    let event_types = EVENT_TYPES
+        .iter()
+        .map(|event_type| {
+            let rules = rules.remove(event_type.element).unwrap_or_default();
+            // Only events an include rule matches are logged once one exists
+            let level = match (&rules.include, &rules.exclude) {
+                (Some(include), _) if include.is_empty() => CoverageLevel::Disabled,
+                (Some(_), _) => CoverageLevel::Partial,
+                (None, Some(exclude)) if !exclude.is_empty() => CoverageLevel::Partial,
+                (None, _) => CoverageLevel::Full,
+            };
+            SysmonEventCoverage {
+                element: event_type.element.to_string(),
+                event_ids: event_type.event_ids.to_vec(),
+                description: event_type.description.to_string(),
+                data_components: event_type.data_components.iter().map(|c| c.to_string()).collect(),
+                level,
+                configured: rules.configured,
+                include_filters: rules.include.unwrap_or_default(),
+                exclude_filters: rules.exclude.unwrap_or_default(),
+            }
+        })
+        .collect();
+
+    Ok(SysmonConfig {
+        schema_version: root.attribute("schemaversion").map(str::to_string),
+        event_types,
+        unknown_elements: unknown_elements.into_iter().collect(),
+    })
+}
+
+fn event_id_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"(?i)\bEventI[Dd]\s*(?:==?|\bin\b)\s*\(?\s*([0-9][0-9,\s]*)").expect("valid pattern"))
+}
+
+fn mentions_sysmon(rule: &HuntingRule) -> bool {
+    let text = |s: &str| s.to_ascii_lowercase().contains("sysmon");
+    rule.data_sources.iter().any(|source| matches!(source.source_type, DataSourceType::Sysmon))
+        || rule.metadata.data_source_requirements.iter().any(|r| text(r))
+        || rule.metadata.tags.iter().any(|t| text(t))
+        || text(&rule.query.primary_query)
+}
+
+fn runs_on_windows(rule: &HuntingRule) -> bool {
+    rule.metadata.target_platforms.is_empty() || rule.metadata.target_platforms.iter().any(|p| p.to_ascii_lowercase().contains("windows"))
+}
+
+/// Sysmon event types (by element) a rule needs, in `EVENT_TYPES` order
+pub fn rule_requirements(rule: &HuntingRule) -> Vec<&'static str> {
+    let mut needed = BTreeSet::new();
+    if runs_on_windows(rule) {
+        for requirement in &rule.metadata.data_source_requirements {
+            let normalized = requirement
+                .to_ascii_lowercase()
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            let found = EVENT_TYPES
+                .iter()
+                .position(|event_type| event_type.requirement_phrases.iter().any(|phrase| normalized.contains(phrase)));
+            needed.extend(found);
+        }
+    }
+
+    if mentions_sysmon(rule) {
+        let mut ids = Vec::new();
+        let mut push_value = |value: &Value| match value {
+            Value::Array(items) => ids.extend(items.iter().filter_map(|item| item.as_u64().or_else(|| item.as_str()?.trim().parse().ok()))),
+            other => ids.extend(other.as_u64().or_else(|| other.as_str()?.trim().parse().ok())),
+        };
+        for condition in &rule.detection_logic.conditions {
+            if condition.field.eq_ignore_ascii_case("EventID") && !condition.operator.starts_with("not") {
+                push_value(&condition.value);
+            }
+        }
+        for filter in &rule.query.filters {
+            if filter.field.eq_ignore_ascii_case("EventID") && !filter.negation {
+                filter.values.iter().for_each(|value| push_value(&Value::String(value.clone())));
+            }
+        }
+        for query in std::iter::once(&rule.query.primary_query).chain(&rule.query.secondary_queries) {
+            for captures in event_id_pattern().captures_iter(query) {
+                ids.extend(captures[1].split(',').filter_map(|id| id.trim().parse::<u64>().ok()));
+            }
+        }
+        let found = ids
+            .into_iter()
+            .filter_map(|id| u32::try_from(id).ok())
+            .filter_map(event_type_for_id)
+            .filter_map(|event_type| EVENT_TYPES.iter().position(|known| known.element == event_type.element));
+        needed.extend(found);
+    }
+    needed.into_iter().map(|index| EVENT_TYPES[index].element).collect()
+}
+
+/// Best coverage of one ATT&CK data component across the event types providing it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataComponentCoverage {
+    pub data_component: String,
+    pub level: CoverageLevel,
+    pub elements: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleRequirement {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub event_types: Vec<String>,
+}
+
+/// An event type rules need that the configuration does not log in full
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageGap {
+    pub element: String,
+    pub event_ids: Vec<u32>,
+    pub level: CoverageLevel,
+    pub rule_ids: Vec<String>,
+    /// ATT&CK techniques of those rules
+    pub techniques: Vec<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SysmonCoverageReport {
+    pub schema_version: Option<String>,
+    pub event_types: Vec<SysmonEventCoverage>,
+    pub data_components: Vec<DataComponentCoverage>,
+    pub rule_requirements: Vec<RuleRequirement>,
+    /// Disabled event types first, then partially logged ones
+    pub blind_spots: Vec<CoverageGap>,
+    pub unknown_elements: Vec<String>,
+    pub rules_analyzed: usize,
+    pub rules_fully_covered: usize,
+}
+
+/// Coverage of `config` and the blind spots it leaves `rules`
+pub fn coverage_report(config: SysmonConfig, rules: &[HuntingRule]) -> SysmonCoverageReport {
+    let mut components: BTreeMap<String, DataComponentCoverage> = BTreeMap::new();
+    for coverage in &config.event_types {
+        for component in &coverage.data_components {
+            let entry = components.entry(component.clone()).or_insert_with(|| DataComponentCoverage {
+                data_component: component.clone(),
+                level: CoverageLevel::Disabled,
+                elements: Vec::new(),
+            });
+            entry.level = entry.level.max(coverage.level);
+            entry.elements.push(coverage.element.clone());
+        }
+    }
+
+    let mut sorted: Vec<&HuntingRule> = rules.iter().collect();
+    sorted.sort_by(|a, b| a.id.cmp(&b.id));
+    let mut requirements = Vec::new();
+    let mut needed_by: BTreeMap<&str, Vec<&HuntingRule>> = BTreeMap::new();
+    for rule in sorted {
+        let needed = rule_requirements(rule);
+        if needed.is_empty() {
+            continue;
+        }
+        for element in &needed {
+            needed_by.entry(element).or_default().push(rule);
+        }
+        requirements.push(RuleRequirement {
+            rule_id: rule.id.clone(),
+            rule_name: rule.name.clone(),
+            event_types: needed.iter().map(|element| element.to_string()).collect(),
+        });
+    }
+
+    let mut blind_spots = Vec::new();
+    let mut rules_with_gaps = BTreeSet::new();
+    for coverage in config.event_types.iter().filter(|coverage| coverage.level != CoverageLevel::Full) {
+        let Some(rules) = needed_by.get(coverage.element.as_str()) else {
+            continue;
+        };
+        let rule_ids: Vec<String> = rules.iter().map(|rule| rule.id.clone()).collect();
+        rules_with_gaps.extend(rule_ids.iter().cloned());
+        let techniques: BTreeSet<String> = rules.iter().flat_map(|rule| rule.mitre_techniques.iter().map(|t| t.technique_id.clone())).collect();
+        let ids = coverage.event_ids.iter().map(u32::to_string).collect::<Vec<_>>().join("/");
+        let message = format!(
+            "{} {} need{} EventID {} ({}) logging, {}",
+            if rule_ids.len() == 1 { "Rule" } else { "Rules" },
+            rule_ids.join(", "),
+            if rule_ids.len() == 1 { "s" } else { "" },
+            ids,
+            coverage.element,
+            coverage.limitation()
+        );
+        blind_spots.push(CoverageGap {
+            element: coverage.element.clone(),
+            event_ids: coverage.event_ids.clone(),
+            level: coverage.level,
+            rule_ids,
+            techniques: techniques.into_iter().collect(),
+            message,
+        });
+    }
+    blind_spots.sort_by_key(|gap| gap.level);
+
+    SysmonCoverageReport {
+        schema_version: config.schema_version,
+        event_types: config.event_types,
+        data_components: components.into_values().collect(),
+        rules_analyzed: rules.len(),
+        rules_fully_covered: rules.len() - rules_with_gaps.len(),
+        rule_requirements: requirements,
+        blind_spots,
+        unknown_elements: config.unknown_elements,
+    }
+}
+
+impl HuntingCore {
+    /// Coverage report of a Sysmon configuration against the tenant's rules
+    pub async fn analyze_sysmon_coverage(&self, tenant_id: &str, config_xml: &str) -> CoreResult<SysmonCoverageReport> {
+        let config = parse_config(config_xml)?;
+        let rules = self.list_rules(tenant_id).await?;
+        Ok(coverage_report(config, &rules))
+    }
+}
+
+#[napi]
+impl HuntingCoreNapi {
+    /// Event type and ATT&CK data component coverage of a Sysmon XML
+    /// configuration, with the blind spots it leaves the tenant's rules
+    #[napi]
+    pub async fn analyze_sysmon_coverage(&self, config_xml: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let report = self.inner.analyze_sysmon_coverage(&tenant_id, &config_xml).await
+            .map_err(|e| e.context("Failed to analyze Sysmon coverage"))?;
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize coverage report: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DetectionCondition, MITREMapping};
+
+    const CONFIG: &str = r#"<Sysmon schemaversion="4.90">
+  <HashAlgorithms>sha256</HashAlgorithms>
+  <EventFiltering>
+    <RuleGroup name="" groupRelation="or">
+      <ProcessCreate onmatch="exclude">
+        <Image condition="is">C:\Windows\System32\svchost.exe</Image>
+      </ProcessCreate>
+    </RuleGroup>
+    <RuleGroup name="" groupRelation="or">
+      <NetworkConnect onmatch="include">
+        <Rule groupRelation="and">
+          <Image condition="end with">powershell.exe</Image>
+          <DestinationPort condition="is">443</DestinationPort>
+        </Rule>
+      </NetworkConnect>
+    </RuleGroup>
+    <DnsQuery onmatch="include" />
+    <ImageLoad onmatch="exclude" />
+    <SomethingNew onmatch="include" />
+  </EventFiltering>
+</Sysmon>"#;
+
+    #[tokio::test]
+    async fn sysmon_config_gaps_are_reported_per_rule() {
+        let config = parse_config(CONFIG).unwrap();
+        assert_eq!(config.schema_version.as_deref(), Some("4.90"));
+        assert_eq!(config.unknown_elements, ["SomethingNew"]);
+        let level = |element: &str| config.event_types.iter().find(|c| c.element == element).unwrap().level;
+        assert_eq!(level("ProcessCreate"), CoverageLevel::Partial);
+        assert_eq!(level("NetworkConnect"), CoverageLevel::Partial);
+        assert_eq!(level("DnsQuery"), CoverageLevel::Disabled);
+        assert_eq!((level("ImageLoad"), level("RegistryEvent")), (CoverageLevel::Full, CoverageLevel::Full));
+        let network = config.event_types.iter().find(|c| c.element == "NetworkConnect").unwrap();
+        assert_eq!(network.include_filters.len(), 2);
+        assert_eq!(network.include_filters[0].condition, "end with");
+        assert!(parse_config("<Sysmon><EventFiltering><DnsQuery/></EventFiltering></Sysmon>").is_err());
+        assert_eq!(parse_config("<Config/>").unwrap_err().code(), "VALIDATION");
+
+        let core = HuntingCore::new().unwrap();
+        let mut rule = core.list_rules("acme").await.unwrap().remove(0);
+        rule.id = "dns_tunnel".to_string();
+        rule.tenant_id = Some("acme".to_string());
+        rule.metadata.target_platforms = vec!["Windows".to_string()];
+        rule.metadata.data_source_requirements = vec!["Sysmon".to_string()];
+        rule.query.primary_query = "Sysmon | where EventID == 22 | summarize count() by QueryName".to_string();
+        rule.query.secondary_queries.clear();
+        rule.mitre_techniques = vec![MITREMapping {
+            technique_id: "T1071.004".to_string(),
+            technique_name: "DNS".to_string(),
+            tactic: "Command and Control".to_string(),
+            sub_techniques: vec![],
+            confidence: 0.8,
+            detection_coverage: 0.6,
+        }];
+        rule.detection_logic.conditions = vec![DetectionCondition {
+            condition_id: "image_load".to_string(),
+            field: "EventID".to_string(),
+            operator: "equals".to_string(),
+            value: serde_json::json!("7"),
+            weight: 1.0,
+            required: false,
+        }];
+        assert_eq!(rule_requirements(&rule), ["ImageLoad", "DnsQuery"]);
+        core.rules.write().await.insert(rule.id.clone(), rule.clone());
+        rule.id = "dns_other_tenant".to_string();
+        rule.tenant_id = Some("globex".to_string());
+        core.rules.write().await.insert(rule.id.clone(), rule);
+
+        let report = core.analyze_sysmon_coverage("acme", CONFIG).await.unwrap();
+        assert_eq!(report.rules_analyzed, 3);
+        let dns = report.blind_spots.first().unwrap();
+        assert_eq!((dns.element.as_str(), dns.level), ("DnsQuery", CoverageLevel::Disabled));
+        assert_eq!(dns.rule_ids, ["dns_tunnel"]);
+        assert_eq!(dns.techniques, ["T1071.004"]);
+        assert_eq!(dns.message, "Rule dns_tunnel needs EventID 22 (DnsQuery) logging, which the configuration excludes");
+        let process = report.blind_spots.iter().find(|gap| gap.element == "ProcessCreate").unwrap();
+        assert!(process.rule_ids.contains(&"apt_lateral_movement".to_string()));
+        assert!(process.message.ends_with("which the configuration drops for events matching 1 exclude filter"));
+        assert!(report.blind_spots.iter().all(|gap| gap.element != "ImageLoad"));
+        let dns_component = report.data_components.iter().find(|c| c.data_component == "Network Traffic: Network Traffic Content").unwrap();
+        assert_eq!(dns_component.level, CoverageLevel::Disabled);
+    }
+}
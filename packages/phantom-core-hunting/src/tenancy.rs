@@ -0,0 +1,227 @@
+//! Tenant partitioning
+//!
+//! Hunt results, schedules, metrics and kill-chain evidence belong to the
+//! tenant of the credential that produced them, and reads through
+//! `HuntingCoreNapi` only see the caller's tenant. Rules imported by a tenant
+//! are private to it; the built-in rules are shared by every tenant and can
+//! only be replaced by the default tenant. Calls without a tenant-bound
+//! credential act for the default tenant. With `phantom-enterprise-standards`
+//! enabled tenants have a lifecycle (create, suspend, resume, delete with
+//! data purge) and calls for unknown or suspended tenants are refused.
+
+use std::collections::BTreeMap;
+
+use crate::{HuntingCore, HuntingRule};
+
+use crate::HuntingCoreNapi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use napi_derive::napi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::multi_tenancy::TenantDirectory;
+#[cfg(feature = "phantom-enterprise-standards")]
+use serde_json::json;
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::sync::Arc;
+
+/// Tenant that owns records created without a tenant-bound credential
+pub const DEFAULT_TENANT: &str = "default";
+
+pub fn default_tenant() -> String {
+    DEFAULT_TENANT.to_string()
+}
+
+/// Whether `tenant_id` may list and run `rule`
+pub fn rule_visible(rule: &HuntingRule, tenant_id: &str) -> bool {
+    rule.tenant_id.as_deref().is_none_or(|owner| owner == tenant_id)
+}
+
+/// Whether `tenant_id` may replace `rule`
+pub fn rule_writable(rule: &HuntingRule, tenant_id: &str) -> bool {
+    rule.tenant_id.as_deref().unwrap_or(DEFAULT_TENANT) == tenant_id
+}
+
+#[derive(Clone, Default)]
+pub struct TenantGuard {
+    #[cfg(feature = "phantom-enterprise-standards")]
+    directory: Arc<TenantDirectory>,
+}
+
+impl TenantGuard {
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn directory(&self) -> &TenantDirectory {
+        &self.directory
+    }
+
+    /// Check that calls may be served for `tenant_id`
+    pub fn check(&self, tenant_id: &str) -> Result<(), String> {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            self.directory.ensure_active(tenant_id).map_err(|e| e.to_string())
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        {
+            let _ = tenant_id;
+            Ok(())
+        }
+    }
+}
+
+impl HuntingCore {
+    /// Remove the rules, hunt results, schedules, metrics, kill-chain
+    /// evidence, hunt sessions, match dispositions, hunt checkpoints, shadow
+    /// rule versions, back-test reports, hypotheses and rule repositories of
+    /// `tenant_id`, cancelling its running hunts; returns how many records of
+    /// each kind were removed
+    pub async fn purge_tenant(&self, tenant_id: &str) -> BTreeMap<String, usize> {
+        let rules = {
+            let mut rules = self.rules.write().await;
+            let before = rules.len();
+            rules.retain(|_, rule| rule.tenant_id.as_deref() != Some(tenant_id));
+            before - rules.len()
+        };
+        let hunt_results = {
+            let mut results = self.hunt_results.write().await;
+            let before = results.len();
+            results.retain(|_, result| result.tenant_id != tenant_id);
+            before - results.len()
+        };
+        let metrics = usize::from(self.performance_metrics.write().await.remove(tenant_id).is_some());
+
+        #[allow(unused_mut)]
+        let mut purged = BTreeMap::from([
+            ("rules".to_string(), rules),
+            ("hunt_results".to_string(), hunt_results),
+            ("schedules".to_string(), self.unschedule_tenant(tenant_id).await),
+            ("performance_metrics".to_string(), metrics),
+            ("kill_chain_observations".to_string(), self.kill_chain.forget_tenant(tenant_id).await),
+            ("hunt_sessions".to_string(), self.sessions.forget_tenant(tenant_id).await),
+            ("ingestion_sources".to_string(), self.ingestion.forget_tenant(tenant_id)),
+            ("metric_series".to_string(), self.metrics_history.forget_tenant(tenant_id)),
+            ("prometheus_series".to_string(), self.prometheus.forget_tenant(tenant_id)),
+            ("suppressions".to_string(), self.suppression.forget_tenant(tenant_id)),
+            ("assets".to_string(), self.assets.forget_tenant(tenant_id)),
+            ("match_dispositions".to_string(), self.tuning.forget_tenant(tenant_id).await),
+            ("hunt_checkpoints".to_string(), self.checkpoints.forget_tenant(tenant_id)),
+            ("running_hunts".to_string(), self.running_hunts.forget_tenant(tenant_id)),
+            ("shadow_rules".to_string(), self.shadow.forget_tenant(tenant_id).await),
+            ("backtest_reports".to_string(), self.backtest.forget_tenant(tenant_id).await),
+            ("hypotheses".to_string(), self.hypotheses.forget_tenant(tenant_id).await),
+            ("flow_records".to_string(), self.forget_tenant_flows(tenant_id).await),
+        ]);
+        #[cfg(feature = "phantom-enterprise-standards")]
+        purged.insert("rule_repositories".to_string(), self.rule_sync.forget_tenant(tenant_id).await);
+        purged
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[napi]
+impl HuntingCoreNapi {
+    #[napi]
+    pub fn create_tenant(&self, tenant_id: String, organization_name: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, &tenant_id)?;
+        let record = self.tenants.directory().create(&tenant_id, &organization_name).map_err(|e| e.to_string());
+        let record = self.audit.record(&actor, "create_tenant", &tenant_id, json!({ "organization_name": organization_name }), record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to create tenant: {}", e)))?;
+        serde_json::to_string(&record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize tenant: {}", e)))
+    }
+
+    /// Refuse every call for the tenant until it is resumed; its data is kept
+    #[napi]
+    pub fn suspend_tenant(&self, tenant_id: String, reason: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, &tenant_id)?;
+        let record = self.tenants.directory().suspend(&tenant_id, reason.clone()).map_err(|e| e.to_string());
+        let record = self.audit.record(&actor, "suspend_tenant", &tenant_id, json!({ "reason": reason }), record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to suspend tenant: {}", e)))?;
+        serde_json::to_string(&record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize tenant: {}", e)))
+    }
+
+    #[napi]
+    pub fn resume_tenant(&self, tenant_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, &tenant_id)?;
+        let record = self.tenants.directory().resume(&tenant_id).map_err(|e| e.to_string());
+        let record = self.audit.record(&actor, "resume_tenant", &tenant_id, json!({}), record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to resume tenant: {}", e)))?;
+        serde_json::to_string(&record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize tenant: {}", e)))
+    }
+
+    /// Suspend the tenant, purge its rules, hunts and evidence and forget it
+    #[napi]
+    pub async fn delete_tenant(&self, tenant_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        use phantom_enterprise_standards::multi_tenancy::TenantPurgeReport;
+
+        let actor = self.authorize_platform(auth_token, &tenant_id)?;
+        let report = async {
+            if tenant_id == DEFAULT_TENANT {
+                return Err("the default tenant cannot be deleted".to_string());
+            }
+            let directory = self.tenants.directory();
+            directory.suspend(&tenant_id, Some("deleting".to_string())).map_err(|e| e.to_string())?;
+            let purged = self.inner.purge_tenant(&tenant_id).await;
+            directory.remove(&tenant_id).map_err(|e| e.to_string())?;
+            Ok(TenantPurgeReport { tenant_id: tenant_id.clone(), purged, deleted_at: chrono::Utc::now() })
+        }
+        .await;
+        let report = self.audit.record(&actor, "delete_tenant", &tenant_id, json!({}), report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to delete tenant: {}", e)))?;
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize purge report: {}", e)))
+    }
+
+    #[napi]
+    pub fn list_tenants(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize_platform(auth_token, "tenants")?;
+        serde_json::to_string(&self.tenants.directory().list())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize tenants: {}", e)))
+    }
+}
+
+impl HuntingCoreNapi {
+    /// Tenant lifecycle and settings that span tenants are reserved for
+    /// platform administrators, i.e. credentials with `access:manage` that
+    /// are not bound to a tenant
+    pub(crate) fn authorize_platform(&self, auth_token: Option<String>, resource: &str) -> napi::Result<String> {
+        if self.access.tenant(auth_token.as_deref()).is_some() {
+            return Err(napi::Error::from_reason("Tenant-bound credentials cannot manage platform settings"));
+        }
+        self.authorize(auth_token, "access:manage", resource)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn tenants_share_built_in_rules_but_not_results() {
+        let core = HuntingCore::new().unwrap();
+        let built_in = core.list_rules("default").await.unwrap();
+        assert_eq!(core.list_rules("acme").await.unwrap().len(), built_in.len());
+
+        let result = core.execute_hunt("acme", &built_in[0].id, None).await.unwrap();
+        assert_eq!(result.tenant_id, "acme");
+        assert_eq!(core.get_hunt_results("acme", None).await.unwrap().len(), 1);
+        assert!(core.get_hunt_results("globex", None).await.unwrap().is_empty());
+        assert_eq!(core.get_performance_metrics("acme").await.unwrap().total_hunts_executed, 1);
+        assert_eq!(core.get_performance_metrics("globex").await.unwrap().total_hunts_executed, 0);
+        assert!(!rule_writable(&built_in[0], "acme"));
+    }
+
+    #[tokio::test]
+    async fn purge_removes_only_the_tenant() {
+        let core = HuntingCore::new().unwrap();
+        let rule_id = core.list_rules("default").await.unwrap()[0].id.clone();
+        core.execute_hunt("acme", &rule_id, None).await.unwrap();
+        core.execute_hunt("globex", &rule_id, None).await.unwrap();
+
+        let purged = core.purge_tenant("acme").await;
+        assert_eq!(purged["hunt_results"], 1);
+        assert_eq!(purged["rules"], 0, "built-in rules are shared");
+        assert!(core.get_hunt_results("acme", None).await.unwrap().is_empty());
+        assert_eq!(core.get_hunt_results("globex", None).await.unwrap().len(), 1);
+        assert!(core.list_rules("globex").await.unwrap().iter().any(|rule| rule.id == rule_id));
+    }
+}
@@ -0,0 +1,132 @@
+//! Timeline events for incident reconstruction
+//!
+//! Turns a hunt's execution and its matches into timestamped events, in the
+//! shape the SecOp core accepts as external events when building an incident
+//! timeline.
+
+use chrono::{DateTime, Utc};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{HuntingCore, HuntingCoreNapi, HuntingResult};
+
+/// Value of `source` on every event produced here
+pub const TIMELINE_SOURCE: &str = "hunting";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineEvent {
+    pub timestamp: DateTime<Utc>,
+    pub source: String,
+    /// Match id, or the hunt id for the execution event
+    pub source_ref: String,
+    pub kind: String,
+    pub summary: String,
+    pub severity: Option<String>,
+    pub attributes: HashMap<String, String>,
+}
+
+/// Severity label for a 0–10 match risk score
+fn risk_severity(risk_score: f64) -> &'static str {
+    if risk_score >= 9.0 {
+        "critical"
+    } else if risk_score >= 7.0 {
+        "high"
+    } else if risk_score >= 4.0 {
+        "medium"
+    } else {
+        "low"
+    }
+}
+
+/// The hunt execution followed by each of its matches, oldest first
+pub fn hunt_timeline(result: &HuntingResult) -> Vec<TimelineEvent> {
+    let hunt_attributes = HashMap::from([
+        ("hunt_id".to_string(), result.hunt_id.clone()),
+        ("rule_id".to_string(), result.rule_id.clone()),
+    ]);
+
+    let mut events = vec![TimelineEvent {
+        timestamp: result.execution_timestamp,
+        source: TIMELINE_SOURCE.to_string(),
+        source_ref: result.hunt_id.clone(),
+        kind: "hunt_executed".to_string(),
+        summary: format!("{} executed with {} matches", result.hunt_name, result.matches.len()),
+        severity: None,
+        attributes: hunt_attributes.clone(),
+    }];
+
+    events.extend(result.matches.iter().map(|hunt_match| {
+        let mut attributes = hunt_attributes.clone();
+        attributes.insert("match_source".to_string(), hunt_match.source.clone());
+        attributes.insert("confidence".to_string(), format!("{:.2}", hunt_match.confidence_score));
+        attributes.insert("risk_score".to_string(), format!("{:.1}", hunt_match.risk_score));
+        TimelineEvent {
+            timestamp: hunt_match.timestamp,
+            source: TIMELINE_SOURCE.to_string(),
+            source_ref: hunt_match.match_id.clone(),
+            kind: "hunt_match".to_string(),
+            summary: format!("{} matched in {}", result.hunt_name, hunt_match.source),
+            severity: Some(risk_severity(hunt_match.risk_score).to_string()),
+            attributes,
+        }
+    }));
+
+    events.sort_by_key(|event| event.timestamp);
+    events
+}
+
+impl HuntingCore {
+    /// Timeline events for a stored hunt result of `tenant_id`
+    pub async fn get_timeline_events(&self, tenant_id: &str, hunt_id: &str) -> Result<Vec<TimelineEvent>, String> {
+        let results = self.hunt_results.read().await;
+        let result = results
+            .get(hunt_id)
+            .filter(|result| result.tenant_id == tenant_id)
+            .ok_or_else(|| format!("Hunt {} not found", hunt_id))?;
+        Ok(hunt_timeline(result))
+    }
+}
+
+#[napi]
+impl HuntingCoreNapi {
+    /// Get a hunt's execution and matches as incident timeline events
+    #[napi]
+    pub async fn get_timeline_events(&self, hunt_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let events = self.inner.get_timeline_events(&tenant_id, &hunt_id).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to get timeline events: {}", e)))?;
+
+        serde_json::to_string(&events)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize timeline events: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_risk_severity_bands() {
+        assert_eq!(risk_severity(9.5), "critical");
+        assert_eq!(risk_severity(7.5), "high");
+        assert_eq!(risk_severity(5.0), "medium");
+        assert_eq!(risk_severity(1.0), "low");
+    }
+
+    #[tokio::test]
+    async fn test_hunt_events_cover_execution_and_matches() {
+        let core = HuntingCore::new().unwrap();
+        assert!(core.get_timeline_events("default", "hunt_missing").await.is_err());
+
+        let rule_id = core.list_rules("default").await.unwrap()[0].id.clone();
+        let result = core.execute_hunt("default", &rule_id, None).await.unwrap();
+
+        assert!(core.get_timeline_events("acme", &result.hunt_id).await.is_err());
+        let events = core.get_timeline_events("default", &result.hunt_id).await.unwrap();
+        assert_eq!(events.len(), result.matches.len() + 1);
+        assert!(events.windows(2).all(|pair| pair[0].timestamp <= pair[1].timestamp));
+        assert!(events.iter().all(|event| event.source == TIMELINE_SOURCE));
+        assert_eq!(events.iter().filter(|event| event.kind == "hunt_executed").count(), 1);
+    }
+}
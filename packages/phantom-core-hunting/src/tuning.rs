@@ -0,0 +1,564 @@
+//! Rule tuning from disposition history
+//!
+//! Analysts mark hunt matches as true or false positives, and each
+//! disposition updates the rule's `RulePerformanceMetrics`. The analyzer
+//! looks for attributes a rule's false positives share while its true
+//! positives do not: the same account, the same subnet, business hours, or
+//! one recurring hour of the day (a scheduled job). Each pattern becomes a
+//! `TuningRecommendation` whose patch adds an exclusion condition (see
+//! `conditions`) to the rule, and `apply_tuning` applies that patch. Hunt
+//! results of a rule with dispositions report the real false-positive rate
+//! and these recommendations in their `false_positive_analysis`.
+
+use chrono::{DateTime, Timelike, Utc};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::net::IpAddr;
+use tokio::sync::RwLock;
+
+use crate::conditions::{self, EXCLUSION_OPERATORS};
+use crate::error::{CoreError, CoreResult};
+use crate::{tenancy, DetectionCondition, FalsePositiveAnalysis, HuntingCore, HuntingCoreNapi, HuntingRule, TuningRecommendation};
+
+/// False positives needed before patterns are looked for
+pub const MIN_FALSE_POSITIVES: usize = 3;
+/// Share of the false positives a pattern has to cover
+pub const MIN_PATTERN_SHARE: f64 = 0.6;
+/// Business hours cover most working activity, so they have to cover nearly all false positives
+pub const BUSINESS_HOURS_SHARE: f64 = 0.9;
+/// Largest share of true positives an exclusion may also drop
+pub const MAX_TRUE_POSITIVE_SHARE: f64 = 0.2;
+/// Values one exclusion lists at most
+const MAX_PATTERN_VALUES: usize = 3;
+
+/// Fields naming the account an event was performed by or against
+const ACCOUNT_FIELD_HINTS: [&str; 4] = ["user", "account", "principal", "logon"];
+/// Event fields carrying the event time, in order of preference
+const TIMESTAMP_FIELDS: [&str; 5] = ["timestamp", "@timestamp", "event_time", "time", "TimeCreated"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchDisposition {
+    TruePositive,
+    FalsePositive,
+}
+
+/// An analyst's verdict on one hunt match, with the event it was about
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DispositionRecord {
+    pub tenant_id: String,
+    pub rule_id: String,
+    pub hunt_id: String,
+    pub match_id: String,
+    pub disposition: MatchDisposition,
+    pub analyst: String,
+    pub note: Option<String>,
+    pub match_timestamp: DateTime<Utc>,
+    pub event_data: HashMap<String, Value>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PatternKind {
+    Account,
+    Subnet,
+    BusinessHours,
+    RecurringHour,
+}
+
+/// Attribute values most of a rule's false positives share
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FalsePositivePattern {
+    pub kind: PatternKind,
+    pub field: String,
+    /// Accounts, CIDR ranges, or the hour window
+    pub values: Vec<String>,
+    /// Share of the rule's false positives showing the pattern
+    pub false_positive_share: f64,
+    /// True positives the exclusion would also have dropped
+    pub true_positives_affected: usize,
+}
+
+/// Change `apply_tuning` makes to a rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RulePatch {
+    /// Added to `detection_logic.conditions`
+    pub add_conditions: Vec<DetectionCondition>,
+    /// Added to `false_positive_mitigation`
+    pub false_positive_mitigation: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleTuningReport {
+    pub tenant_id: String,
+    pub rule_id: String,
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub false_positive_rate: f64,
+    pub recommendations: Vec<TuningRecommendation>,
+    pub generated_at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+pub struct TuningState {
+    /// match id -> latest disposition
+    dispositions: RwLock<HashMap<String, DispositionRecord>>,
+}
+
+impl TuningState {
+    pub(crate) async fn rule_dispositions(&self, tenant_id: &str, rule_id: &str) -> Vec<DispositionRecord> {
+        let mut records: Vec<DispositionRecord> = self
+            .dispositions
+            .read()
+            .await
+            .values()
+            .filter(|record| record.tenant_id == tenant_id && record.rule_id == rule_id)
+            .cloned()
+            .collect();
+        records.sort_by(|a, b| a.recorded_at.cmp(&b.recorded_at).then_with(|| a.match_id.cmp(&b.match_id)));
+        records
+    }
+
+    pub(crate) async fn forget_tenant(&self, tenant_id: &str) -> usize {
+        let mut dispositions = self.dispositions.write().await;
+        let before = dispositions.len();
+        dispositions.retain(|_, record| record.tenant_id != tenant_id);
+        before - dispositions.len()
+    }
+}
+
+/// Stable id so a recommendation can be accepted after it is recomputed
+fn recommendation_id(rule_id: &str, field: &str, operator: &str, value: &Value) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in [rule_id, field, operator, &value.to_string()].join("\u{1f}").bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("tun_{:016x}", hash)
+}
+
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// `/24` for IPv4 and `/64` for IPv6
+fn network_of(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            format!("{}.{}.{}.0/24", a, b, c)
+        }
+        IpAddr::V6(ip) => {
+            let network = std::net::Ipv6Addr::from(u128::from(ip) & (u128::MAX << 64));
+            format!("{}/64", network)
+        }
+    }
+}
+
+/// Fewest of the most frequent keys (each seen at least twice) covering
+/// `MIN_PATTERN_SHARE` of `total`, with the number of records they cover
+fn dominant(keys: &[String], total: usize) -> Option<(Vec<String>, usize)> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for key in keys {
+        *counts.entry(key.as_str()).or_default() += 1;
+    }
+    let mut ranked: Vec<(&str, usize)> = counts.into_iter().filter(|(_, count)| *count >= 2).collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+    let mut chosen = Vec::new();
+    let mut covered = 0;
+    for (key, count) in ranked.into_iter().take(MAX_PATTERN_VALUES) {
+        chosen.push(key.to_string());
+        covered += count;
+        if covered as f64 >= MIN_PATTERN_SHARE * total as f64 {
+            return Some((chosen, covered));
+        }
+    }
+    None
+}
+
+struct Candidate {
+    pattern: FalsePositivePattern,
+    operator: &'static str,
+    value: Value,
+    rationale: String,
+    mitigation: String,
+}
+
+/// Recommendations for `rule` from its disposition history, best first
+pub fn analyze_dispositions(rule: &HuntingRule, records: &[DispositionRecord]) -> Vec<TuningRecommendation> {
+    let false_positives: Vec<&DispositionRecord> = records.iter().filter(|r| r.disposition == MatchDisposition::FalsePositive).collect();
+    let true_positives: Vec<&DispositionRecord> = records.iter().filter(|r| r.disposition == MatchDisposition::TruePositive).collect();
+    if false_positives.len() < MIN_FALSE_POSITIVES {
+        return Vec::new();
+    }
+    let fp_total = false_positives.len();
+    let tp_allowed = (MAX_TRUE_POSITIVE_SHARE * true_positives.len() as f64).floor() as usize;
+
+    // Excluding on a field the rule detects on would blind it
+    let detection_fields: BTreeSet<String> = rule
+        .detection_logic
+        .conditions
+        .iter()
+        .filter(|c| !EXCLUSION_OPERATORS.contains(&c.operator.as_str()))
+        .map(|c| c.field.to_lowercase())
+        .collect();
+    let fields: BTreeSet<&String> = false_positives.iter().flat_map(|r| r.event_data.keys()).collect();
+
+    let mut candidates = Vec::new();
+    for field in fields {
+        if detection_fields.contains(&field.to_lowercase()) || TIMESTAMP_FIELDS.contains(&field.as_str()) {
+            continue;
+        }
+        let values: Vec<String> = false_positives.iter().filter_map(|r| r.event_data.get(field).and_then(scalar)).collect();
+        if values.is_empty() {
+            continue;
+        }
+        let ips: Vec<IpAddr> = values.iter().filter_map(|v| v.parse().ok()).collect();
+
+        if ips.len() == values.len() {
+            let networks: Vec<String> = ips.into_iter().map(network_of).collect();
+            let Some((ranges, covered)) = dominant(&networks, fp_total) else { continue };
+            let affected = true_positives
+                .iter()
+                .filter_map(|r| r.event_data.get(field).and_then(scalar)?.parse::<IpAddr>().ok())
+                .filter(|ip| ranges.iter().any(|range| conditions::in_cidr(*ip, range)))
+                .count();
+            candidates.push(Candidate {
+                rationale: format!("{} of {} false positives had {} in {}, seen in {} true positives", covered, fp_total, field, ranges.join(", "), affected),
+                mitigation: format!("Exclude {} in {}", field, ranges.join(", ")),
+                operator: "outside_cidr",
+                value: json!(ranges),
+                pattern: FalsePositivePattern { kind: PatternKind::Subnet, field: field.clone(), values: ranges, false_positive_share: covered as f64 / fp_total as f64, true_positives_affected: affected },
+            });
+        } else if ACCOUNT_FIELD_HINTS.iter().any(|hint| field.to_lowercase().contains(hint)) {
+            let lowered: Vec<String> = values.iter().map(|v| v.to_lowercase()).collect();
+            let Some((accounts, covered)) = dominant(&lowered, fp_total) else { continue };
+            let accounts: Vec<String> = accounts
+                .iter()
+                .map(|account| values.iter().find(|v| v.to_lowercase() == *account).cloned().unwrap_or_else(|| account.clone()))
+                .collect();
+            let affected = true_positives
+                .iter()
+                .filter_map(|r| r.event_data.get(field).and_then(scalar))
+                .filter(|v| accounts.iter().any(|account| account.eq_ignore_ascii_case(v)))
+                .count();
+            candidates.push(Candidate {
+                rationale: format!("{} of {} false positives involved {} {}, seen in {} true positives", covered, fp_total, field, accounts.join(", "), affected),
+                mitigation: format!("Exclude {} {}", field, accounts.join(", ")),
+                operator: "none_of",
+                value: json!(accounts),
+                pattern: FalsePositivePattern { kind: PatternKind::Account, field: field.clone(), values: accounts, false_positive_share: covered as f64 / fp_total as f64, true_positives_affected: affected },
+            });
+        }
+    }
+
+    // Time windows can only be excluded on a timestamp the events carry
+    let time_field = TIMESTAMP_FIELDS
+        .iter()
+        .find(|field| false_positives.iter().all(|r| r.event_data.get(**field).and_then(conditions::value_as_timestamp).is_some()));
+    if let Some(field) = time_field {
+        let times = |records: &[&DispositionRecord]| -> Vec<DateTime<Utc>> {
+            records.iter().filter_map(|r| r.event_data.get(*field).and_then(conditions::value_as_timestamp)).collect()
+        };
+        let (fp_times, tp_times) = (times(&false_positives), times(&true_positives));
+        let hours: Vec<String> = fp_times.iter().map(|at| format!("{:02}", at.hour())).collect();
+        let recurring = dominant(&hours, fp_total).filter(|(hours, _)| hours.len() == 1);
+
+        let (kind, window, covered, label) = match recurring {
+            Some((hour, covered)) => {
+                let start: u32 = hour[0].parse().unwrap_or(0);
+                (PatternKind::RecurringHour, json!({ "start_hour": start, "end_hour": (start + 1) % 24 }), covered, format!("{:02}:00-{:02}:00 UTC", start, (start + 1) % 24))
+            }
+            None => {
+                let window = json!({ "days": [1, 2, 3, 4, 5], "start_hour": 9, "end_hour": 17 });
+                let covered = fp_times.iter().filter(|at| conditions::in_window(**at, &window)).count();
+                (PatternKind::BusinessHours, window, covered, "weekdays 09:00-17:00 UTC".to_string())
+            }
+        };
+        let required_share = if kind == PatternKind::BusinessHours { BUSINESS_HOURS_SHARE } else { MIN_PATTERN_SHARE };
+        if covered as f64 >= required_share * fp_total as f64 {
+            let affected = tp_times.iter().filter(|at| conditions::in_window(**at, &window)).count();
+            candidates.push(Candidate {
+                rationale: format!("{} of {} false positives occurred {}, as did {} true positives", covered, fp_total, label, affected),
+                mitigation: format!("Exclude events {}", label),
+                operator: "outside_window",
+                value: window,
+                pattern: FalsePositivePattern { kind, field: field.to_string(), values: vec![label], false_positive_share: covered as f64 / fp_total as f64, true_positives_affected: affected },
+            });
+        }
+    }
+
+    let applied: BTreeSet<&str> = rule.detection_logic.conditions.iter().map(|c| c.condition_id.as_str()).collect();
+    let mut recommendations: Vec<TuningRecommendation> = candidates
+        .into_iter()
+        .filter(|candidate| candidate.pattern.true_positives_affected <= tp_allowed)
+        .filter_map(|candidate| {
+            let id = recommendation_id(&rule.id, &candidate.pattern.field, candidate.operator, &candidate.value);
+            if applied.contains(id.as_str()) {
+                return None;
+            }
+            let condition = DetectionCondition {
+                condition_id: id.clone(),
+                field: candidate.pattern.field.clone(),
+                operator: candidate.operator.to_string(),
+                value: candidate.value.clone(),
+                weight: 0.0,
+                required: true,
+            };
+            Some(TuningRecommendation {
+                recommendation_id: id,
+                parameter: format!("detection_logic.conditions.{}", candidate.pattern.field),
+                current_value: Value::Null,
+                recommended_value: json!({ "operator": candidate.operator, "value": candidate.value }),
+                expected_improvement: candidate.pattern.false_positive_share,
+                rationale: candidate.rationale,
+                patch: Some(RulePatch { add_conditions: vec![condition], false_positive_mitigation: candidate.mitigation }),
+                pattern: Some(candidate.pattern),
+            })
+        })
+        .collect();
+    recommendations.sort_by(|a, b| b.expected_improvement.total_cmp(&a.expected_improvement).then_with(|| a.recommendation_id.cmp(&b.recommendation_id)));
+    recommendations
+}
+
+/// False-positive section of a hunt result built from disposition history
+pub(crate) fn false_positive_analysis(rule: &HuntingRule, records: &[DispositionRecord]) -> FalsePositiveAnalysis {
+    let false_positives = records.iter().filter(|r| r.disposition == MatchDisposition::FalsePositive).count();
+    let recommendations = analyze_dispositions(rule, records);
+    FalsePositiveAnalysis {
+        false_positive_rate: false_positives as f64 / records.len().max(1) as f64,
+        common_false_positive_patterns: recommendations.iter().map(|r| r.rationale.clone()).collect(),
+        mitigation_suggestions: recommendations.iter().filter_map(|r| r.patch.as_ref().map(|p| p.false_positive_mitigation.clone())).collect(),
+        tuning_recommendations: recommendations,
+    }
+}
+
+impl HuntingCore {
+    /// Record an analyst's verdict on a match of one of the tenant's hunts;
+    /// a later verdict on the same match replaces the earlier one
+    pub async fn record_match_disposition(
+        &self,
+        tenant_id: &str,
+        hunt_id: &str,
+        match_id: &str,
+        disposition: MatchDisposition,
+        analyst: &str,
+        note: Option<String>,
+    ) -> CoreResult<DispositionRecord> {
+        let (rule_id, hunting_match) = {
+            let results = self.hunt_results.read().await;
+            let result = results
+                .get(hunt_id)
+                .filter(|result| result.tenant_id == tenant_id)
+                .ok_or_else(|| CoreError::not_found(format!("Hunt {} not found", hunt_id)))?;
+            let hunting_match = result
+                .matches
+                .iter()
+                .find(|m| m.match_id == match_id)
+                .cloned()
+                .ok_or_else(|| CoreError::not_found(format!("Match {} not found in hunt {}", match_id, hunt_id)))?;
+            (result.rule_id.clone(), hunting_match)
+        };
+        let record = DispositionRecord {
+            tenant_id: tenant_id.to_string(),
+            rule_id: rule_id.clone(),
+            hunt_id: hunt_id.to_string(),
+            match_id: match_id.to_string(),
+            disposition,
+            analyst: analyst.to_string(),
+            note,
+            match_timestamp: hunting_match.timestamp,
+            event_data: hunting_match.event_data,
+            recorded_at: Utc::now(),
+        };
+        let previous = self.tuning.dispositions.write().await.insert(match_id.to_string(), record.clone());
+
+        if let Some(rule) = self.rules.write().await.get_mut(&rule_id) {
+            let metrics = &mut rule.performance_metrics;
+            match previous.map(|p| p.disposition) {
+                Some(MatchDisposition::TruePositive) => metrics.true_positives = metrics.true_positives.saturating_sub(1),
+                Some(MatchDisposition::FalsePositive) => metrics.false_positives = metrics.false_positives.saturating_sub(1),
+                None => {}
+            }
+            match disposition {
+                MatchDisposition::TruePositive => metrics.true_positives += 1,
+                MatchDisposition::FalsePositive => metrics.false_positives += 1,
+            }
+            let judged = metrics.true_positives + metrics.false_positives;
+            if judged > 0 {
+                metrics.effectiveness_score = metrics.true_positives as f64 / judged as f64;
+            }
+            metrics.last_updated = Utc::now();
+        }
+        Ok(record)
+    }
+
+    /// Tuning recommendations for a rule the tenant can see
+    pub async fn suggest_rule_tuning(&self, tenant_id: &str, rule_id: &str) -> CoreResult<RuleTuningReport> {
+        let rule = self
+            .rules
+            .read()
+            .await
+            .get(rule_id)
+            .filter(|rule| tenancy::rule_visible(rule, tenant_id))
+            .cloned()
+            .ok_or_else(|| CoreError::not_found(format!("Rule {} not found", rule_id)))?;
+        let records = self.tuning.rule_dispositions(tenant_id, rule_id).await;
+        let false_positives = records.iter().filter(|r| r.disposition == MatchDisposition::FalsePositive).count();
+        Ok(RuleTuningReport {
+            tenant_id: tenant_id.to_string(),
+            rule_id: rule_id.to_string(),
+            true_positives: records.len() - false_positives,
+            false_positives,
+            false_positive_rate: false_positives as f64 / records.len().max(1) as f64,
+            recommendations: analyze_dispositions(&rule, &records),
+            generated_at: Utc::now(),
+        })
+    }
+
+    /// Apply a recommendation's patch to the rule and return the tuned rule
+    pub async fn apply_tuning(&self, tenant_id: &str, rule_id: &str, recommendation_id: &str) -> CoreResult<HuntingRule> {
+        let report = self.suggest_rule_tuning(tenant_id, rule_id).await?;
+        let patch = report
+            .recommendations
+            .into_iter()
+            .find(|r| r.recommendation_id == recommendation_id)
+            .and_then(|r| r.patch)
+            .ok_or_else(|| CoreError::not_found(format!("Recommendation {} is not pending for rule {}", recommendation_id, rule_id)))?;
+
+        let mut rules = self.rules.write().await;
+        let rule = rules
+            .get_mut(rule_id)
+            .ok_or_else(|| CoreError::not_found(format!("Rule {} not found", rule_id)))?;
+        if !tenancy::rule_writable(rule, tenant_id) {
+            return Err(CoreError::validation(format!("Rule {} belongs to another tenant", rule_id)));
+        }
+        rule.detection_logic.conditions.extend(patch.add_conditions);
+        rule.false_positive_mitigation.push(patch.false_positive_mitigation);
+        rule.metadata.last_modified = Utc::now();
+        log::info!("Applied tuning {} to rule {}", recommendation_id, rule_id);
+        Ok(rule.clone())
+    }
+}
+
+#[napi]
+impl HuntingCoreNapi {
+    /// Mark a hunt match `true_positive` or `false_positive`
+    #[napi]
+    pub async fn record_match_disposition(&self, hunt_id: String, match_id: String, disposition: String, note: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", &match_id)?;
+        let verdict: MatchDisposition = serde_json::from_value(Value::String(disposition.clone()))
+            .map_err(|e| CoreError::from(e).context("Failed to parse disposition"))?;
+        let params = json!({ "hunt_id": hunt_id, "disposition": disposition, "note": note });
+
+        let record = self.inner.record_match_disposition(&tenant_id, &hunt_id, &match_id, verdict, &actor, note).await;
+        let record = self.audit.record(&actor, "record_match_disposition", &match_id, params, record)
+            .map_err(|e| e.context("Failed to record match disposition"))?;
+        serde_json::to_string(&record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize disposition: {}", e)))
+    }
+
+    /// False-positive patterns and tuning recommendations for a rule
+    #[napi]
+    pub async fn suggest_rule_tuning(&self, rule_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let report = self.inner.suggest_rule_tuning(&tenant_id, &rule_id).await
+            .map_err(|e| e.context("Failed to suggest rule tuning"))?;
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize tuning report: {}", e)))
+    }
+
+    /// Accept a tuning recommendation, patching the rule
+    #[napi]
+    pub async fn apply_tuning(&self, rule_id: String, recommendation_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &rule_id)?;
+        let rule = self.inner.apply_tuning(&tenant_id, &rule_id, &recommendation_id).await;
+        let rule = self.audit.record(&actor, "apply_tuning", &rule_id, json!({ "recommendation_id": recommendation_id }), rule)
+            .map_err(|e| e.context("Failed to apply tuning"))?;
+        serde_json::to_string(&rule)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize rule: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tenancy::DEFAULT_TENANT;
+
+    const RULE: &str = "apt_lateral_movement";
+
+    /// Run the rule and give its matches the event fields of `rows`; returns (hunt id, match id) per row
+    async fn hunt_with_events(core: &HuntingCore, rows: &[(&str, &str, &str)]) -> Vec<(String, String)> {
+        let mut matches = Vec::new();
+        for chunk in rows.chunks(5) {
+            let result = core.execute_hunt(DEFAULT_TENANT, RULE, None).await.unwrap();
+            let mut results = core.hunt_results.write().await;
+            for (m, (user, ip, at)) in results.get_mut(&result.hunt_id).unwrap().matches.iter_mut().zip(chunk) {
+                m.event_data = HashMap::from([
+                    ("TargetUser".to_string(), json!(user)),
+                    ("SourceIP".to_string(), json!(ip)),
+                    ("timestamp".to_string(), json!(at)),
+                ]);
+                matches.push((result.hunt_id.clone(), m.match_id.clone()));
+            }
+        }
+        matches
+    }
+
+    #[tokio::test]
+    async fn false_positives_become_applicable_exclusions() {
+        let core = HuntingCore::new().unwrap();
+        let rows = [
+            ("svc_backup", "10.20.30.11", "2026-10-12T02:05:00Z"),
+            ("svc_backup", "10.20.30.12", "2026-10-13T02:10:00Z"),
+            ("SVC_BACKUP", "10.20.30.13", "2026-10-14T02:15:00Z"),
+            ("svc_backup", "10.20.30.14", "2026-10-15T02:20:00Z"),
+            ("jdoe", "172.16.5.9", "2026-10-15T14:00:00Z"),
+            ("mallory", "198.51.100.7", "2026-10-15T23:30:00Z"),
+            ("eve", "203.0.113.50", "2026-10-16T11:45:00Z"),
+        ];
+        let matches = hunt_with_events(&core, &rows).await;
+        for (i, (hunt_id, match_id)) in matches.iter().enumerate() {
+            let disposition = if i < 4 { MatchDisposition::FalsePositive } else { MatchDisposition::TruePositive };
+            core.record_match_disposition(DEFAULT_TENANT, hunt_id, match_id, disposition, "analyst", None).await.unwrap();
+        }
+        // Changing a verdict replaces it rather than counting twice
+        let (hunt_id, match_id) = &matches[4];
+        core.record_match_disposition(DEFAULT_TENANT, hunt_id, match_id, MatchDisposition::FalsePositive, "lead", Some("change window".into())).await.unwrap();
+        let missing = core.record_match_disposition("acme", hunt_id, match_id, MatchDisposition::FalsePositive, "analyst", None).await.unwrap_err();
+        assert_eq!(missing.code(), "NOT_FOUND");
+
+        let report = core.suggest_rule_tuning(DEFAULT_TENANT, RULE).await.unwrap();
+        assert_eq!((report.false_positives, report.true_positives), (5, 2));
+        let kinds: Vec<PatternKind> = report.recommendations.iter().filter_map(|r| r.pattern.as_ref().map(|p| p.kind)).collect();
+        assert!(kinds.contains(&PatternKind::Account) && kinds.contains(&PatternKind::Subnet) && kinds.contains(&PatternKind::RecurringHour), "{:?}", kinds);
+        let account = report.recommendations.iter().find(|r| r.pattern.as_ref().is_some_and(|p| p.kind == PatternKind::Account)).unwrap();
+        assert_eq!(account.pattern.as_ref().unwrap().values, ["svc_backup"]);
+        assert_eq!(account.expected_improvement, 0.8);
+
+        let rule = core.apply_tuning(DEFAULT_TENANT, RULE, &account.recommendation_id).await.unwrap();
+        let added = rule.detection_logic.conditions.last().unwrap();
+        assert_eq!((added.operator.as_str(), added.field.as_str()), ("none_of", "TargetUser"));
+        assert_eq!(rule.performance_metrics.false_positives, 5);
+        assert_eq!(core.apply_tuning(DEFAULT_TENANT, RULE, &account.recommendation_id).await.unwrap_err().code(), "NOT_FOUND");
+        let pending = core.suggest_rule_tuning(DEFAULT_TENANT, RULE).await.unwrap();
+        assert_eq!(pending.recommendations.len(), report.recommendations.len() - 1);
+
+        let event = HashMap::from([("TargetUser".to_string(), json!("svc_backup"))]);
+        assert_eq!(conditions::evaluate_conditions(&event, &rule.detection_logic.conditions), None);
+
+        let hunt = core.execute_hunt(DEFAULT_TENANT, RULE, None).await.unwrap();
+        assert!((hunt.false_positive_analysis.false_positive_rate - 5.0 / 7.0).abs() < 1e-9);
+        assert_eq!(hunt.false_positive_analysis.tuning_recommendations.len(), pending.recommendations.len());
+    }
+}
@@ -0,0 +1,54 @@
+//! Per-call authorization for the NAPI surface
+//!
+//! With `phantom-enterprise-standards` enabled, mutating calls are checked
+//! against the shared RBAC layer using the token passed with each call.
+//! Without it every call is allowed.
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::rbac::{AccessControl, Permission};
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::sync::Arc;
+
+/// Actor recorded when no credential was supplied
+pub const ANONYMOUS: &str = "anonymous";
+
+#[derive(Clone, Default)]
+pub struct AccessGuard {
+    #[cfg(feature = "phantom-enterprise-standards")]
+    control: Arc<AccessControl>,
+}
+
+impl AccessGuard {
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn control(&self) -> &AccessControl {
+        &self.control
+    }
+
+    /// Check that `token` grants `permission` (e.g. `"incident:write"`) on
+    /// `resource` and return the acting principal
+    pub fn check(&self, token: Option<&str>, permission: &str, resource: &str) -> Result<String, String> {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            let permission: Permission = permission.parse()?;
+            self.control
+                .authorize(token, permission, resource)
+                .map(|principal| principal.map_or_else(|| ANONYMOUS.to_string(), |p| p.principal_id))
+                .map_err(|e| e.to_string())
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        {
+            let _ = (token, permission, resource);
+            Ok(ANONYMOUS.to_string())
+        }
+    }
+
+    /// Principal behind `token`, for operations that are recorded but not gated
+    pub fn actor(&self, token: Option<&str>) -> String {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        if let Some(principal) = token.and_then(|token| self.control.authenticate(token)) {
+            return principal.principal_id;
+        }
+        let _ = token;
+        ANONYMOUS.to_string()
+    }
+}
@@ -0,0 +1,258 @@
+//! Asset Inventory
+//!
+//! With `phantom-enterprise-standards` enabled, incident response keeps one
+//! asset inventory per process, scoped by tenant (`default` when an incident
+//! names none). `create_incident_response` resolves an incident's affected
+//! systems against it to assess business impact and derive the priority, and
+//! `implement_containment` resolves its target systems to estimate the blast
+//! radius: the assets that depend on the contained ones. Without the feature
+//! nothing resolves and both report only the names they were given.
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::assets::{Asset, AssetInventory, Criticality};
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::sync::OnceLock;
+
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+use crate::access::AccessGuard;
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+use napi_derive::napi;
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+use phantom_enterprise_standards::assets::AssetRecord;
+
+/// Scope of incidents that do not name a tenant
+pub const DEFAULT_SCOPE: &str = "default";
+
+/// Inventory shared by every incident response entry point of the process
+#[cfg(feature = "phantom-enterprise-standards")]
+pub fn inventory() -> &'static AssetInventory {
+    static INVENTORY: OnceLock<AssetInventory> = OnceLock::new();
+    INVENTORY.get_or_init(AssetInventory::new)
+}
+
+/// An inventoried asset as incident output shows it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetSummary {
+    pub asset_id: String,
+    pub name: String,
+    pub kind: String,
+    pub criticality: String,
+    pub owner: Option<String>,
+    pub network_zone: Option<String>,
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+impl From<&Asset> for AssetSummary {
+    fn from(asset: &Asset) -> Self {
+        Self {
+            asset_id: asset.id.clone(),
+            name: asset.name.clone(),
+            kind: serde_json::to_value(asset.kind).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default(),
+            criticality: asset.criticality.as_str().to_string(),
+            owner: asset.owner.clone(),
+            network_zone: asset.network_zone.clone(),
+        }
+    }
+}
+
+/// Business impact of an incident on a set of systems
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImpactAssessment {
+    pub affected_assets: Vec<AssetSummary>,
+    /// Assets reachable through dependencies; the containment blast radius
+    pub dependent_assets: Vec<AssetSummary>,
+    /// System names the inventory does not know
+    pub unresolved_systems: Vec<String>,
+    pub highest_criticality: Option<String>,
+    pub owners: Vec<String>,
+    pub network_zones: Vec<String>,
+    pub impact_score: f64,
+    pub blast_radius_score: f64,
+}
+
+impl ImpactAssessment {
+    /// `minimal`, `moderate`, `significant` or `severe` for a 0-1 score
+    pub fn level(score: f64) -> &'static str {
+        match score {
+            s if s >= 0.9 => "severe",
+            s if s >= 0.6 => "significant",
+            s if s >= 0.3 => "moderate",
+            _ => "minimal",
+        }
+    }
+
+    /// Incident priority the most critical affected asset calls for
+    pub fn priority(&self) -> Option<&'static str> {
+        match self.affected_assets.iter().map(|asset| asset.criticality.as_str()).max_by_key(|c| criticality_rank(c))? {
+            "critical" => Some("critical"),
+            "high" => Some("high"),
+            "medium" => Some("medium"),
+            _ => Some("low"),
+        }
+    }
+}
+
+fn criticality_rank(criticality: &str) -> u8 {
+    match criticality {
+        "critical" => 3,
+        "high" => 2,
+        "medium" => 1,
+        _ => 0,
+    }
+}
+
+/// Resolve `systems` in the tenant's inventory and assess what an incident on them reaches
+pub fn assess(scope: &str, systems: &[String]) -> ImpactAssessment {
+    #[cfg(feature = "phantom-enterprise-standards")]
+    {
+        let impact = inventory().impact(scope, systems);
+        ImpactAssessment {
+            affected_assets: impact.affected.iter().map(AssetSummary::from).collect(),
+            dependent_assets: impact.dependents.iter().map(AssetSummary::from).collect(),
+            unresolved_systems: impact.unresolved,
+            highest_criticality: impact.highest_criticality.map(|c: Criticality| c.as_str().to_string()),
+            owners: impact.owners,
+            network_zones: impact.network_zones,
+            impact_score: impact.impact_score,
+            blast_radius_score: impact.blast_radius_score,
+        }
+    }
+    #[cfg(not(feature = "phantom-enterprise-standards"))]
+    {
+        let _ = scope;
+        ImpactAssessment { unresolved_systems: systems.to_vec(), ..Default::default() }
+    }
+}
+
+/// Tenant an incident or containment request names, or the default scope
+pub fn scope_of(input: &serde_json::Value) -> String {
+    input.get("tenant_id").and_then(|v| v.as_str()).filter(|t| !t.is_empty()).unwrap_or(DEFAULT_SCOPE).to_string()
+}
+
+// NAPI Bindings
+
+/// NAPI access to the process-wide asset inventory
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+#[napi]
+pub struct AssetInventoryNapi {
+    access: AccessGuard,
+}
+
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+#[napi]
+impl AssetInventoryNapi {
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        AssetInventoryNapi { access: AccessGuard::default() }
+    }
+
+    /// Create or update an asset, e.g. `{"kind": "service", "name": "payments",
+    /// "criticality": "high", "depends_on": ["db-01"]}`
+    #[napi]
+    pub fn put_asset(&self, asset_data: String, tenant_id: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let record: AssetRecord = serde_json::from_str(&asset_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid asset: {}", e)))?;
+        self.authorize(auth_token, "incident:write", &record.name)?;
+        let asset = inventory().put(&Self::scope(tenant_id), record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to store asset: {}", e)))?;
+        serde_json::to_string(&asset)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub fn remove_asset(&self, asset_id: String, tenant_id: Option<String>, auth_token: Option<String>) -> napi::Result<bool> {
+        self.authorize(auth_token, "incident:write", &asset_id)?;
+        Ok(inventory().remove(&Self::scope(tenant_id), &asset_id))
+    }
+
+    #[napi]
+    pub fn get_asset(&self, asset_id: String, tenant_id: Option<String>) -> napi::Result<Option<String>> {
+        inventory()
+            .get(&Self::scope(tenant_id), &asset_id)
+            .map(|asset| serde_json::to_string(&asset))
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub fn list_assets(&self, tenant_id: Option<String>) -> napi::Result<String> {
+        serde_json::to_string(&inventory().list(&Self::scope(tenant_id)))
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Asset a hostname, account, alias or address refers to
+    #[napi]
+    pub fn resolve_asset(&self, reference: String, tenant_id: Option<String>) -> napi::Result<Option<String>> {
+        inventory()
+            .resolve(&Self::scope(tenant_id), &reference)
+            .map(|asset| serde_json::to_string(&asset))
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Import assets from CSV (`kind,name,criticality,owner,network_zone,depends_on,...`)
+    #[napi]
+    pub fn import_assets_csv(&self, csv_data: String, tenant_id: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token, "incident:write", "assets")?;
+        let report = inventory().import_csv(&Self::scope(tenant_id), &csv_data)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to import assets: {}", e)))?;
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Business impact and blast radius of an incident on `["system", ...]`
+    #[napi]
+    pub fn assess_asset_impact(&self, systems_data: String, tenant_id: Option<String>) -> napi::Result<String> {
+        let systems: Vec<String> = serde_json::from_str(&systems_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid systems: {}", e)))?;
+        serde_json::to_string(&assess(&Self::scope(tenant_id), &systems))
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+}
+
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+impl Default for AssetInventoryNapi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+impl AssetInventoryNapi {
+    fn scope(tenant_id: Option<String>) -> String {
+        tenant_id.filter(|t| !t.is_empty()).unwrap_or_else(|| DEFAULT_SCOPE.to_string())
+    }
+
+    fn authorize(&self, auth_token: Option<String>, permission: &str, resource: &str) -> napi::Result<String> {
+        self.access.check(auth_token.as_deref(), permission, resource)
+            .map_err(napi::Error::from_reason)
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_impact_resolves_inventory_and_blast_radius() {
+        let scope = format!("tenant-{}", uuid::Uuid::new_v4());
+        let csv = "kind,name,criticality,owner,depends_on\n\
+                   host,erp-db,critical,finance-it,\n\
+                   service,erp,high,finance,erp-db\n";
+        assert_eq!(inventory().import_csv(&scope, csv).unwrap().created, 2);
+
+        let impact = assess(&scope, &["ERP-DB".to_string(), "printer-3".to_string()]);
+        assert_eq!(impact.affected_assets[0].name, "erp-db");
+        assert_eq!(impact.dependent_assets[0].name, "erp");
+        assert_eq!(impact.unresolved_systems, vec!["printer-3".to_string()]);
+        assert_eq!(impact.priority(), Some("critical"));
+        assert_eq!(ImpactAssessment::level(impact.impact_score), "severe");
+        assert_eq!(impact.owners, vec!["finance".to_string(), "finance-it".to_string()]);
+
+        let unknown = assess(DEFAULT_SCOPE, &["nowhere".to_string()]);
+        assert_eq!(unknown.priority(), None);
+        assert_eq!(ImpactAssessment::level(unknown.blast_radius_score), "minimal");
+    }
+}
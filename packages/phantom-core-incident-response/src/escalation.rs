@@ -0,0 +1,880 @@
+//! On-call Schedules and Escalation Policies
+//!
+//! Works out who is on call from rotating schedules (with temporary
+//! overrides) and walks escalation policies tier by tier: notify the first
+//! tier, wait, then escalate to the next until someone acknowledges.
+//! Incident severity changes start the chain bound to the new severity, and
+//! every page is recorded as a `CommunicationRecord`.
+//!
+//! The engine is clock-driven: `advance` fires every tier whose wait has
+//! elapsed, so it can run from a ticker or be stepped explicitly.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+#[cfg(feature = "napi")]
+use crate::access::AccessGuard;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+
+use crate::CommunicationRecord;
+
+// Schedules
+
+/// Participants take turns in fixed-length shifts starting at `starts_at`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rotation {
+    pub participants: Vec<String>,
+    pub shift_hours: u32,
+    pub starts_at: DateTime<Utc>,
+}
+
+/// Temporarily hands a schedule to someone outside the rotation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleOverride {
+    pub user: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnCallSchedule {
+    pub schedule_id: String,
+    pub name: String,
+    pub rotation: Rotation,
+    #[serde(default)]
+    pub overrides: Vec<ScheduleOverride>,
+}
+
+impl OnCallSchedule {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.rotation.participants.is_empty() {
+            return Err(format!("Schedule {} has no participants", self.schedule_id));
+        }
+        if self.rotation.shift_hours == 0 {
+            return Err(format!("Schedule {} has a zero-length shift", self.schedule_id));
+        }
+        for entry in &self.overrides {
+            entry.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Who holds the schedule at `at`; the most recently added matching
+    /// override wins over the rotation, and nobody is on call before the
+    /// rotation starts
+    pub fn on_call_at(&self, at: DateTime<Utc>) -> Option<String> {
+        if let Some(entry) = self.overrides.iter().rev().find(|o| o.starts_at <= at && at < o.ends_at) {
+            return Some(entry.user.clone());
+        }
+        if at < self.rotation.starts_at {
+            return None;
+        }
+        let shift = Duration::hours(self.rotation.shift_hours as i64);
+        let elapsed = at - self.rotation.starts_at;
+        let index = (elapsed.num_seconds() / shift.num_seconds()) as usize % self.rotation.participants.len();
+        Some(self.rotation.participants[index].clone())
+    }
+}
+
+impl ScheduleOverride {
+    fn validate(&self) -> Result<(), String> {
+        if self.ends_at <= self.starts_at {
+            return Err(format!("Override for {} ends before it starts", self.user));
+        }
+        Ok(())
+    }
+}
+
+// Policies
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EscalationTarget {
+    /// Whoever is on call for the schedule when the tier fires
+    Schedule { schedule_id: String },
+    User { user: String },
+}
+
+/// One step of a policy: page `targets`, then wait before escalating
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EscalationTier {
+    pub targets: Vec<EscalationTarget>,
+    pub wait_minutes: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EscalationPolicy {
+    pub policy_id: String,
+    pub name: String,
+    pub tiers: Vec<EscalationTier>,
+    /// How many more times to restart from the first tier once the last
+    /// tier goes unacknowledged
+    #[serde(default)]
+    pub repeat: u32,
+}
+
+impl EscalationPolicy {
+    pub fn validate(&self, schedules: &HashMap<String, OnCallSchedule>) -> Result<(), String> {
+        if self.tiers.is_empty() {
+            return Err(format!("Policy {} has no tiers", self.policy_id));
+        }
+        for (index, tier) in self.tiers.iter().enumerate() {
+            if tier.targets.is_empty() {
+                return Err(format!("Tier {} of policy {} has no targets", index + 1, self.policy_id));
+            }
+            for target in &tier.targets {
+                if let EscalationTarget::Schedule { schedule_id } = target {
+                    if !schedules.contains_key(schedule_id) {
+                        return Err(format!("Policy {} references unknown schedule {}", self.policy_id, schedule_id));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Escalations
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EscalationStatus {
+    Active,
+    Acknowledged,
+    Resolved,
+    /// Every tier (and repeat) fired without an acknowledgement
+    Exhausted,
+    /// Replaced by the chain for a higher severity
+    Superseded,
+}
+
+/// A running escalation chain for one incident
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Escalation {
+    pub escalation_id: String,
+    pub incident_id: String,
+    pub policy_id: String,
+    pub severity: String,
+    pub status: EscalationStatus,
+    /// Zero-based index of the tier paged most recently
+    pub current_tier: usize,
+    pub cycle: u32,
+    pub started_at: DateTime<Utc>,
+    pub next_escalation_at: Option<DateTime<Utc>>,
+    pub acknowledged_by: Option<String>,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub notifications: Vec<CommunicationRecord>,
+}
+
+/// Delivers pages to people (email, SMS, chat, ...)
+#[async_trait]
+pub trait EscalationNotifier: Send + Sync {
+    async fn notify(&self, record: &CommunicationRecord) -> Result<(), String>;
+}
+
+fn severity_rank(severity: &str) -> u8 {
+    match severity {
+        "low" => 1,
+        "medium" => 2,
+        "high" => 3,
+        "critical" => 4,
+        _ => 0,
+    }
+}
+
+#[derive(Default)]
+struct EscalationState {
+    schedules: HashMap<String, OnCallSchedule>,
+    policies: HashMap<String, EscalationPolicy>,
+    /// Severity (lowercase) to the policy paged for it
+    severity_policies: HashMap<String, String>,
+    escalations: HashMap<String, Escalation>,
+}
+
+impl EscalationState {
+    fn active_for(&self, incident_id: &str) -> Option<&Escalation> {
+        self.escalations
+            .values()
+            .find(|e| e.incident_id == incident_id && e.status == EscalationStatus::Active)
+    }
+
+    /// Page the escalation's current tier and schedule the next step
+    fn fire_tier(&mut self, escalation_id: &str, at: DateTime<Utc>) -> Option<CommunicationRecord> {
+        let escalation = self.escalations.get(escalation_id)?;
+        let policy = self.policies.get(&escalation.policy_id)?;
+        let tier = &policy.tiers[escalation.current_tier];
+
+        let mut recipients: Vec<String> = Vec::new();
+        for target in &tier.targets {
+            let user = match target {
+                EscalationTarget::Schedule { schedule_id } => self.schedules.get(schedule_id).and_then(|s| s.on_call_at(at)),
+                EscalationTarget::User { user } => Some(user.clone()),
+            };
+            if let Some(user) = user.filter(|u| !recipients.contains(u)) {
+                recipients.push(user);
+            }
+        }
+
+        let level = escalation.current_tier + 1;
+        let record = CommunicationRecord {
+            communication_id: Uuid::new_v4().to_string(),
+            incident_id: escalation.incident_id.clone(),
+            communication_type: "escalation_page".to_string(),
+            message: format!(
+                "Incident {} ({}) escalated to tier {} of {}",
+                escalation.incident_id, escalation.severity, level, policy.name
+            ),
+            delivery_status: if recipients.is_empty() { "no_recipients" } else { "pending" }.to_string(),
+            recipients,
+            sent_at: at,
+            escalation_level: format!("tier{}", level),
+        };
+        let next = at + Duration::minutes(tier.wait_minutes as i64);
+
+        let escalation = self.escalations.get_mut(escalation_id)?;
+        escalation.next_escalation_at = Some(next);
+        escalation.notifications.push(record.clone());
+        Some(record)
+    }
+
+    /// Move past the current tier once its wait has elapsed, looping back for
+    /// repeats and stopping when the policy is used up
+    fn step(&mut self, escalation_id: &str) -> bool {
+        let Some(escalation) = self.escalations.get(escalation_id) else {
+            return false;
+        };
+        let Some(policy) = self.policies.get(&escalation.policy_id) else {
+            return false;
+        };
+        let (tiers, repeat) = (policy.tiers.len(), policy.repeat);
+
+        let Some(escalation) = self.escalations.get_mut(escalation_id) else {
+            return false;
+        };
+        if escalation.current_tier + 1 < tiers {
+            escalation.current_tier += 1;
+            true
+        } else if escalation.cycle < repeat {
+            escalation.cycle += 1;
+            escalation.current_tier = 0;
+            true
+        } else {
+            escalation.status = EscalationStatus::Exhausted;
+            escalation.closed_at = escalation.next_escalation_at;
+            escalation.next_escalation_at = None;
+            false
+        }
+    }
+
+    fn start(&mut self, incident_id: &str, policy_id: &str, severity: &str, at: DateTime<Utc>) -> (String, Option<CommunicationRecord>) {
+        let escalation_id = Uuid::new_v4().to_string();
+        self.escalations.insert(
+            escalation_id.clone(),
+            Escalation {
+                escalation_id: escalation_id.clone(),
+                incident_id: incident_id.to_string(),
+                policy_id: policy_id.to_string(),
+                severity: severity.to_string(),
+                status: EscalationStatus::Active,
+                current_tier: 0,
+                cycle: 0,
+                started_at: at,
+                next_escalation_at: None,
+                acknowledged_by: None,
+                closed_at: None,
+                notifications: Vec::new(),
+            },
+        );
+        let record = self.fire_tier(&escalation_id, at);
+        (escalation_id, record)
+    }
+
+    fn close(&mut self, escalation_id: &str, status: EscalationStatus, at: DateTime<Utc>) -> Result<Escalation, String> {
+        let escalation = self
+            .escalations
+            .get_mut(escalation_id)
+            .ok_or_else(|| format!("Escalation {} not found", escalation_id))?;
+        if escalation.status != EscalationStatus::Active {
+            return Err(format!("Escalation {} is no longer active", escalation_id));
+        }
+        escalation.status = status;
+        escalation.next_escalation_at = None;
+        escalation.closed_at = Some(at);
+        Ok(escalation.clone())
+    }
+}
+
+/// On-call and escalation engine
+pub struct EscalationEngine {
+    state: Arc<RwLock<EscalationState>>,
+    notifier: Arc<RwLock<Option<Arc<dyn EscalationNotifier>>>>,
+}
+
+impl Default for EscalationEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EscalationEngine {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(RwLock::new(EscalationState::default())),
+            notifier: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Deliver pages through `notifier`; without one pages stay `pending`
+    pub async fn set_notifier(&self, notifier: Arc<dyn EscalationNotifier>) {
+        *self.notifier.write().await = Some(notifier);
+    }
+
+    pub async fn register_schedule(&self, schedule: OnCallSchedule) -> Result<(), String> {
+        schedule.validate()?;
+        self.state.write().await.schedules.insert(schedule.schedule_id.clone(), schedule);
+        Ok(())
+    }
+
+    pub async fn add_override(&self, schedule_id: &str, entry: ScheduleOverride) -> Result<(), String> {
+        entry.validate()?;
+        let mut state = self.state.write().await;
+        let schedule = state
+            .schedules
+            .get_mut(schedule_id)
+            .ok_or_else(|| format!("Schedule {} not found", schedule_id))?;
+        schedule.overrides.push(entry);
+        Ok(())
+    }
+
+    pub async fn list_schedules(&self) -> Vec<OnCallSchedule> {
+        let mut schedules: Vec<_> = self.state.read().await.schedules.values().cloned().collect();
+        schedules.sort_by(|a, b| a.schedule_id.cmp(&b.schedule_id));
+        schedules
+    }
+
+    pub async fn on_call(&self, schedule_id: &str, at: DateTime<Utc>) -> Result<Option<String>, String> {
+        let state = self.state.read().await;
+        let schedule = state
+            .schedules
+            .get(schedule_id)
+            .ok_or_else(|| format!("Schedule {} not found", schedule_id))?;
+        Ok(schedule.on_call_at(at))
+    }
+
+    pub async fn register_policy(&self, policy: EscalationPolicy) -> Result<(), String> {
+        let mut state = self.state.write().await;
+        policy.validate(&state.schedules)?;
+        state.policies.insert(policy.policy_id.clone(), policy);
+        Ok(())
+    }
+
+    pub async fn list_policies(&self) -> Vec<EscalationPolicy> {
+        let mut policies: Vec<_> = self.state.read().await.policies.values().cloned().collect();
+        policies.sort_by(|a, b| a.policy_id.cmp(&b.policy_id));
+        policies
+    }
+
+    /// Page `policy_id` whenever an incident reaches `severity`
+    pub async fn bind_severity(&self, severity: &str, policy_id: &str) -> Result<(), String> {
+        let mut state = self.state.write().await;
+        if !state.policies.contains_key(policy_id) {
+            return Err(format!("Policy {} not found", policy_id));
+        }
+        state.severity_policies.insert(severity.to_lowercase(), policy_id.to_string());
+        Ok(())
+    }
+
+    /// Start a policy for an incident by hand, paging its first tier now
+    pub async fn start_escalation(&self, incident_id: &str, policy_id: &str, severity: &str, at: DateTime<Utc>) -> Result<Escalation, String> {
+        let (escalation_id, record) = {
+            let mut state = self.state.write().await;
+            if !state.policies.contains_key(policy_id) {
+                return Err(format!("Policy {} not found", policy_id));
+            }
+            if let Some(active) = state.active_for(incident_id) {
+                return Err(format!("Incident {} already has active escalation {}", incident_id, active.escalation_id));
+            }
+            state.start(incident_id, policy_id, &severity.to_lowercase(), at)
+        };
+        self.deliver(record.into_iter().collect()).await;
+        self.get_escalation(&escalation_id).await.ok_or_else(|| format!("Escalation {} not found", escalation_id))
+    }
+
+    /// Hook for incident severity changes
+    ///
+    /// Starts the chain bound to `severity` when the incident has none
+    /// running, and replaces a running chain when severity rises to one bound
+    /// to a different policy. Downgrades never re-page. Returns the chain that
+    /// was started, if any.
+    pub async fn on_severity_change(&self, incident_id: &str, severity: &str, at: DateTime<Utc>) -> Option<Escalation> {
+        let severity = severity.to_lowercase();
+        let (escalation_id, record) = {
+            let mut state = self.state.write().await;
+            let policy_id = state.severity_policies.get(&severity).cloned();
+            let active = state.active_for(incident_id).map(|e| (e.escalation_id.clone(), e.policy_id.clone(), e.severity.clone()));
+
+            match (policy_id, active) {
+                (Some(policy_id), None) => state.start(incident_id, &policy_id, &severity, at),
+                (Some(policy_id), Some((active_id, active_policy, active_severity)))
+                    if policy_id != active_policy && severity_rank(&severity) > severity_rank(&active_severity) =>
+                {
+                    let _ = state.close(&active_id, EscalationStatus::Superseded, at);
+                    state.start(incident_id, &policy_id, &severity, at)
+                }
+                (_, Some((active_id, _, active_severity))) => {
+                    if severity_rank(&severity) > severity_rank(&active_severity) {
+                        if let Some(active) = state.escalations.get_mut(&active_id) {
+                            active.severity = severity;
+                        }
+                    }
+                    return None;
+                }
+                (None, None) => return None,
+            }
+        };
+        self.deliver(record.into_iter().collect()).await;
+        self.get_escalation(&escalation_id).await
+    }
+
+    /// Stop escalating; nobody further is paged
+    pub async fn acknowledge(&self, escalation_id: &str, user: &str, at: DateTime<Utc>) -> Result<Escalation, String> {
+        let mut state = self.state.write().await;
+        state.close(escalation_id, EscalationStatus::Acknowledged, at)?;
+        let escalation = state.escalations.get_mut(escalation_id).ok_or_else(|| format!("Escalation {} not found", escalation_id))?;
+        escalation.acknowledged_by = Some(user.to_string());
+        Ok(escalation.clone())
+    }
+
+    pub async fn resolve(&self, escalation_id: &str, at: DateTime<Utc>) -> Result<Escalation, String> {
+        self.state.write().await.close(escalation_id, EscalationStatus::Resolved, at)
+    }
+
+    /// Fire every tier whose predecessor's wait has elapsed by `now` and
+    /// return the pages sent
+    pub async fn advance(&self, now: DateTime<Utc>) -> Vec<CommunicationRecord> {
+        let records = {
+            let mut state = self.state.write().await;
+            let mut due: Vec<(DateTime<Utc>, String)> = state
+                .escalations
+                .values()
+                .filter(|e| e.status == EscalationStatus::Active)
+                .filter_map(|e| e.next_escalation_at.filter(|at| *at <= now).map(|at| (at, e.escalation_id.clone())))
+                .collect();
+            due.sort();
+
+            let mut records = Vec::new();
+            for (_, escalation_id) in due {
+                // A late tick catches up tier by tier, each page stamped with
+                // the time it was due
+                while let Some(at) = state.escalations.get(&escalation_id).and_then(|e| e.next_escalation_at).filter(|at| *at <= now) {
+                    if !state.step(&escalation_id) {
+                        break;
+                    }
+                    records.extend(state.fire_tier(&escalation_id, at));
+                }
+            }
+            records
+        };
+        self.deliver(records).await
+    }
+
+    /// Hand pages to the notifier and record the outcome on the escalation
+    async fn deliver(&self, records: Vec<CommunicationRecord>) -> Vec<CommunicationRecord> {
+        let notifier = self.notifier.read().await.clone();
+        let mut delivered = Vec::with_capacity(records.len());
+        for mut record in records {
+            if let (Some(notifier), "pending") = (&notifier, record.delivery_status.as_str()) {
+                record.delivery_status = match notifier.notify(&record).await {
+                    Ok(()) => "delivered".to_string(),
+                    Err(error) => format!("failed: {}", error),
+                };
+                let mut state = self.state.write().await;
+                if let Some(stored) = state
+                    .escalations
+                    .values_mut()
+                    .flat_map(|e| e.notifications.iter_mut())
+                    .find(|n| n.communication_id == record.communication_id)
+                {
+                    stored.delivery_status = record.delivery_status.clone();
+                }
+            }
+            delivered.push(record);
+        }
+        delivered
+    }
+
+    pub async fn get_escalation(&self, escalation_id: &str) -> Option<Escalation> {
+        self.state.read().await.escalations.get(escalation_id).cloned()
+    }
+
+    pub async fn list_escalations(&self, incident_id: Option<&str>) -> Vec<Escalation> {
+        let mut escalations: Vec<_> = self
+            .state
+            .read()
+            .await
+            .escalations
+            .values()
+            .filter(|e| incident_id.is_none_or(|id| e.incident_id == id))
+            .cloned()
+            .collect();
+        escalations.sort_by_key(|e| e.started_at);
+        escalations
+    }
+}
+
+// NAPI Bindings
+
+/// NAPI wrapper around the escalation engine
+#[cfg(feature = "napi")]
+#[napi]
+pub struct EscalationEngineNapi {
+    inner: Arc<EscalationEngine>,
+    access: AccessGuard,
+    ticker: std::sync::Mutex<Option<tokio::task::JoinHandle<()>>>,
+}
+
+#[cfg(feature = "napi")]
+impl Default for EscalationEngineNapi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "napi")]
+impl Drop for EscalationEngineNapi {
+    fn drop(&mut self) {
+        if let Some(ticker) = self.ticker.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            ticker.abort();
+        }
+    }
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl EscalationEngineNapi {
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        EscalationEngineNapi {
+            inner: Arc::new(EscalationEngine::new()),
+            access: AccessGuard::default(),
+            ticker: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// Register or replace an on-call schedule
+    #[napi]
+    pub async fn register_on_call_schedule(&self, schedule_data: String, auth_token: Option<String>) -> napi::Result<()> {
+        self.authorize(auth_token, "incident:write", "escalation")?;
+        let schedule: OnCallSchedule = serde_json::from_str(&schedule_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid schedule: {}", e)))?;
+        self.inner.register_schedule(schedule).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to register schedule: {}", e)))
+    }
+
+    /// Add a temporary override to a schedule
+    #[napi]
+    pub async fn add_schedule_override(&self, schedule_id: String, override_data: String, auth_token: Option<String>) -> napi::Result<()> {
+        self.authorize(auth_token, "incident:write", &schedule_id)?;
+        let entry: ScheduleOverride = serde_json::from_str(&override_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid override: {}", e)))?;
+        self.inner.add_override(&schedule_id, entry).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to add override: {}", e)))
+    }
+
+    #[napi]
+    pub async fn list_on_call_schedules(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.list_schedules().await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Who is on call for a schedule, now or at an RFC 3339 time
+    #[napi]
+    pub async fn who_is_on_call(&self, schedule_id: String, at: Option<String>) -> napi::Result<Option<String>> {
+        let at = parse_time(at)?;
+        self.inner.on_call(&schedule_id, at).await.map_err(napi::Error::from_reason)
+    }
+
+    /// Register or replace an escalation policy
+    #[napi]
+    pub async fn register_escalation_policy(&self, policy_data: String, auth_token: Option<String>) -> napi::Result<()> {
+        self.authorize(auth_token, "incident:write", "escalation")?;
+        let policy: EscalationPolicy = serde_json::from_str(&policy_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid escalation policy: {}", e)))?;
+        self.inner.register_policy(policy).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to register escalation policy: {}", e)))
+    }
+
+    #[napi]
+    pub async fn list_escalation_policies(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.list_policies().await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Page `policy_id` whenever an incident reaches `severity`
+    #[napi]
+    pub async fn bind_severity_policy(&self, severity: String, policy_id: String, auth_token: Option<String>) -> napi::Result<()> {
+        self.authorize(auth_token, "incident:write", "escalation")?;
+        self.inner.bind_severity(&severity, &policy_id).await.map_err(napi::Error::from_reason)
+    }
+
+    /// Report an incident's new severity; returns the escalation started, if any
+    #[napi]
+    pub async fn incident_severity_changed(&self, incident_id: String, severity: String, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        self.authorize(auth_token, "incident:write", &incident_id)?;
+        match self.inner.on_severity_change(&incident_id, &severity, Utc::now()).await {
+            Some(escalation) => serde_json::to_string(&escalation)
+                .map(Some)
+                .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e))),
+            None => Ok(None),
+        }
+    }
+
+    /// Start an escalation policy for an incident by hand
+    #[napi]
+    pub async fn start_escalation(&self, incident_id: String, policy_id: String, severity: String, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token, "incident:write", &incident_id)?;
+        let escalation = self.inner.start_escalation(&incident_id, &policy_id, &severity, Utc::now()).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to start escalation: {}", e)))?;
+        serde_json::to_string(&escalation)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Acknowledge an escalation as the caller (or `user` when given)
+    #[napi]
+    pub async fn acknowledge_escalation(&self, escalation_id: String, user: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize(auth_token, "incident:write", &escalation_id)?;
+        let escalation = self.inner.acknowledge(&escalation_id, user.as_deref().unwrap_or(&actor), Utc::now()).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to acknowledge escalation: {}", e)))?;
+        serde_json::to_string(&escalation)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn resolve_escalation(&self, escalation_id: String, auth_token: Option<String>) -> napi::Result<()> {
+        self.authorize(auth_token, "incident:write", &escalation_id)?;
+        self.inner.resolve(&escalation_id, Utc::now()).await
+            .map(|_| ())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to resolve escalation: {}", e)))
+    }
+
+    /// Fire any tiers that are due now and return the pages sent
+    #[napi]
+    pub async fn advance_escalations(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.advance(Utc::now()).await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Advance escalations in the background every `interval_secs`
+    #[napi]
+    pub async fn start_escalation_ticker(&self, interval_secs: u32, auth_token: Option<String>) -> napi::Result<()> {
+        self.authorize(auth_token, "incident:write", "escalation")?;
+        let inner = self.inner.clone();
+        let period = std::time::Duration::from_secs(interval_secs.max(1) as u64);
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                inner.advance(Utc::now()).await;
+            }
+        });
+        if let Some(previous) = self.ticker.lock().unwrap_or_else(|e| e.into_inner()).replace(handle) {
+            previous.abort();
+        }
+        Ok(())
+    }
+
+    #[napi]
+    pub async fn get_escalation(&self, escalation_id: String) -> napi::Result<Option<String>> {
+        match self.inner.get_escalation(&escalation_id).await {
+            Some(escalation) => serde_json::to_string(&escalation)
+                .map(Some)
+                .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e))),
+            None => Ok(None),
+        }
+    }
+
+    /// List escalations, optionally for a single incident
+    #[napi]
+    pub async fn list_escalations(&self, incident_id: Option<String>) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.list_escalations(incident_id.as_deref()).await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+}
+
+#[cfg(feature = "napi")]
+impl EscalationEngineNapi {
+    fn authorize(&self, auth_token: Option<String>, permission: &str, resource: &str) -> napi::Result<String> {
+        self.access.check(auth_token.as_deref(), permission, resource)
+            .map_err(napi::Error::from_reason)
+    }
+}
+
+#[cfg(feature = "napi")]
+fn parse_time(at: Option<String>) -> napi::Result<DateTime<Utc>> {
+    match at {
+        Some(at) => DateTime::parse_from_rfc3339(&at)
+            .map(|t| t.with_timezone(&Utc))
+            .map_err(|e| napi::Error::from_reason(format!("Invalid timestamp {}: {}", at, e))),
+        None => Ok(Utc::now()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::sync::Mutex;
+
+    struct RecordingNotifier {
+        pages: Mutex<Vec<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl EscalationNotifier for RecordingNotifier {
+        async fn notify(&self, record: &CommunicationRecord) -> Result<(), String> {
+            self.pages.lock().unwrap().push(record.recipients.clone());
+            if record.recipients.iter().any(|r| r == "pager-down") {
+                return Err("gateway timeout".to_string());
+            }
+            Ok(())
+        }
+    }
+
+    fn t(hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 3, 3, hour, minute, 0).unwrap()
+    }
+
+    fn schedule(schedule_id: &str, participants: &[&str]) -> OnCallSchedule {
+        OnCallSchedule {
+            schedule_id: schedule_id.to_string(),
+            name: schedule_id.to_string(),
+            rotation: Rotation {
+                participants: participants.iter().map(|p| p.to_string()).collect(),
+                shift_hours: 8,
+                starts_at: t(0, 0),
+            },
+            overrides: vec![],
+        }
+    }
+
+    fn on_schedule(schedule_id: &str) -> EscalationTarget {
+        EscalationTarget::Schedule { schedule_id: schedule_id.to_string() }
+    }
+
+    #[test]
+    fn test_rotation_and_overrides() {
+        let mut soc = schedule("soc", &["alice", "bob", "carol"]);
+        assert_eq!(soc.on_call_at(t(0, 0) - Duration::minutes(1)), None);
+        assert_eq!(soc.on_call_at(t(7, 59)).as_deref(), Some("alice"));
+        assert_eq!(soc.on_call_at(t(8, 0)).as_deref(), Some("bob"));
+        assert_eq!(soc.on_call_at(t(23, 0)).as_deref(), Some("carol"));
+        assert_eq!(soc.on_call_at(t(0, 0) + Duration::hours(24)).as_deref(), Some("alice"));
+
+        soc.overrides.push(ScheduleOverride { user: "dave".to_string(), starts_at: t(9, 0), ends_at: t(12, 0) });
+        soc.overrides.push(ScheduleOverride { user: "erin".to_string(), starts_at: t(10, 0), ends_at: t(11, 0) });
+        assert_eq!(soc.on_call_at(t(9, 30)).as_deref(), Some("dave"));
+        assert_eq!(soc.on_call_at(t(10, 30)).as_deref(), Some("erin"));
+        assert_eq!(soc.on_call_at(t(12, 0)).as_deref(), Some("bob"));
+
+        soc.rotation.shift_hours = 0;
+        assert!(soc.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tiers_escalate_until_acknowledged() {
+        let engine = EscalationEngine::new();
+        let notifier = Arc::new(RecordingNotifier { pages: Mutex::new(vec![]) });
+        engine.set_notifier(notifier.clone()).await;
+        engine.register_schedule(schedule("tier1", &["alice", "bob"])).await.unwrap();
+        engine.register_schedule(schedule("tier2", &["lead"])).await.unwrap();
+
+        let unknown = EscalationPolicy {
+            policy_id: "bad".to_string(),
+            name: "Bad".to_string(),
+            tiers: vec![EscalationTier { targets: vec![on_schedule("nope")], wait_minutes: 5 }],
+            repeat: 0,
+        };
+        assert!(engine.register_policy(unknown).await.is_err());
+
+        engine
+            .register_policy(EscalationPolicy {
+                policy_id: "standard".to_string(),
+                name: "Standard".to_string(),
+                tiers: vec![
+                    EscalationTier { targets: vec![on_schedule("tier1")], wait_minutes: 15 },
+                    EscalationTier {
+                        targets: vec![on_schedule("tier2"), EscalationTarget::User { user: "pager-down".to_string() }],
+                        wait_minutes: 30,
+                    },
+                ],
+                repeat: 0,
+            })
+            .await
+            .unwrap();
+        engine.bind_severity("High", "standard").await.unwrap();
+
+        assert!(engine.on_severity_change("INC-1", "low", t(9, 0)).await.is_none());
+        let escalation = engine.on_severity_change("INC-1", "high", t(9, 0)).await.unwrap();
+        assert_eq!(escalation.notifications[0].recipients, vec!["bob"]);
+        assert_eq!(escalation.notifications[0].escalation_level, "tier1");
+        assert_eq!(escalation.notifications[0].delivery_status, "delivered");
+        assert!(engine.on_severity_change("INC-1", "high", t(9, 1)).await.is_none());
+
+        assert!(engine.advance(t(9, 14)).await.is_empty());
+        let pages = engine.advance(t(9, 20)).await;
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].recipients, vec!["lead", "pager-down"]);
+        assert_eq!(pages[0].sent_at, t(9, 15));
+        assert_eq!(pages[0].delivery_status, "failed: gateway timeout");
+
+        let acknowledged = engine.acknowledge(&escalation.escalation_id, "lead", t(9, 25)).await.unwrap();
+        assert_eq!(acknowledged.status, EscalationStatus::Acknowledged);
+        assert_eq!(acknowledged.notifications[1].delivery_status, "failed: gateway timeout");
+        assert!(engine.advance(t(12, 0)).await.is_empty());
+        assert!(engine.acknowledge(&escalation.escalation_id, "lead", t(9, 26)).await.is_err());
+        assert_eq!(notifier.pages.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_repeat_exhaustion_and_severity_upgrade() {
+        let engine = EscalationEngine::new();
+        engine.register_schedule(schedule("soc", &["alice"])).await.unwrap();
+        for (policy_id, repeat) in [("standard", 1), ("major", 0)] {
+            engine
+                .register_policy(EscalationPolicy {
+                    policy_id: policy_id.to_string(),
+                    name: policy_id.to_string(),
+                    tiers: vec![EscalationTier { targets: vec![on_schedule("soc")], wait_minutes: 10 }],
+                    repeat,
+                })
+                .await
+                .unwrap();
+        }
+        engine.bind_severity("medium", "standard").await.unwrap();
+        engine.bind_severity("critical", "major").await.unwrap();
+
+        // A late tick catches up on both cycles and then stops
+        let first = engine.on_severity_change("INC-2", "medium", t(1, 0)).await.unwrap();
+        assert_eq!(first.notifications[0].delivery_status, "pending");
+        let pages = engine.advance(t(2, 0)).await;
+        assert_eq!(pages.len(), 1);
+        let exhausted = engine.get_escalation(&first.escalation_id).await.unwrap();
+        assert_eq!(exhausted.status, EscalationStatus::Exhausted);
+        assert_eq!(exhausted.cycle, 1);
+        assert_eq!(exhausted.closed_at, Some(t(1, 20)));
+
+        let standard = engine.on_severity_change("INC-3", "medium", t(3, 0)).await.unwrap();
+        let major = engine.on_severity_change("INC-3", "critical", t(3, 5)).await.unwrap();
+        assert_eq!(major.policy_id, "major");
+        assert_eq!(engine.get_escalation(&standard.escalation_id).await.unwrap().status, EscalationStatus::Superseded);
+        assert!(engine.on_severity_change("INC-3", "medium", t(3, 6)).await.is_none());
+        assert_eq!(engine.list_escalations(Some("INC-3")).await.len(), 2);
+        assert!(engine.start_escalation("INC-3", "standard", "medium", t(3, 7)).await.is_err());
+    }
+}
@@ -0,0 +1,735 @@
+//! Evidence Retention and Legal Hold
+//!
+//! Every evidence item has a retention class: 30, 90 or 365 days from
+//! acquisition, or indefinite. Purge runs destroy items whose retention has
+//! lapsed; the record and any chunks no other item shares are removed and a
+//! certificate of destruction is written to the store's audit log.
+//!
+//! Legal holds cover a single item or every item of an incident, including
+//! evidence collected after the hold was placed. Held evidence can be neither
+//! deleted nor purged, whatever its retention class; once its last hold is
+//! released the item is eligible again. Holds, retention changes, purges and
+//! destructions are appended to `audit.jsonl` under the store root, which is
+//! never rewritten.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use uuid::Uuid;
+
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+
+#[cfg(feature = "napi")]
+use crate::evidence_store::EvidenceStoreNapi;
+use crate::evidence_store::{CustodyEntry, EvidenceBlob, EvidenceHashes, EvidenceStore};
+
+// Retention Classes
+
+/// How long evidence is kept after acquisition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RetentionClass {
+    #[serde(rename = "30_days")]
+    Days30,
+    #[serde(rename = "90_days")]
+    Days90,
+    #[serde(rename = "365_days")]
+    Days365,
+    #[default]
+    #[serde(rename = "indefinite")]
+    Indefinite,
+}
+
+impl RetentionClass {
+    /// Retention period, `None` for evidence kept indefinitely
+    pub fn period(self) -> Option<Duration> {
+        match self {
+            RetentionClass::Days30 => Some(Duration::days(30)),
+            RetentionClass::Days90 => Some(Duration::days(90)),
+            RetentionClass::Days365 => Some(Duration::days(365)),
+            RetentionClass::Indefinite => None,
+        }
+    }
+}
+
+impl fmt::Display for RetentionClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            RetentionClass::Days30 => "30_days",
+            RetentionClass::Days90 => "90_days",
+            RetentionClass::Days365 => "365_days",
+            RetentionClass::Indefinite => "indefinite",
+        })
+    }
+}
+
+impl FromStr for RetentionClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "30_days" | "30d" | "30" => Ok(RetentionClass::Days30),
+            "90_days" | "90d" | "90" => Ok(RetentionClass::Days90),
+            "365_days" | "365d" | "365" => Ok(RetentionClass::Days365),
+            "indefinite" => Ok(RetentionClass::Indefinite),
+            other => Err(format!("Unknown retention class: {}", other)),
+        }
+    }
+}
+
+impl EvidenceBlob {
+    /// When the item's retention lapses, `None` if it never does
+    pub fn retention_expires_at(&self) -> Option<DateTime<Utc>> {
+        self.retention_class.period().map(|period| self.stored_at + period)
+    }
+}
+
+// Legal Holds
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "scope", rename_all = "snake_case")]
+pub enum HoldScope {
+    /// Every item of the incident, including ones collected later
+    Incident { incident_id: String },
+    Evidence { evidence_id: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LegalHold {
+    pub hold_id: String,
+    #[serde(flatten)]
+    pub scope: HoldScope,
+    pub reason: String,
+    pub placed_by: String,
+    pub placed_at: DateTime<Utc>,
+    pub released_by: Option<String>,
+    pub released_at: Option<DateTime<Utc>>,
+}
+
+impl LegalHold {
+    pub fn is_active(&self) -> bool {
+        self.released_at.is_none()
+    }
+
+    /// Whether the hold applies to an item of `incident_id` (with `evidence_id`, once known)
+    pub fn covers(&self, incident_id: &str, evidence_id: Option<&str>) -> bool {
+        match &self.scope {
+            HoldScope::Incident { incident_id: held } => held == incident_id,
+            HoldScope::Evidence { evidence_id: held } => evidence_id == Some(held.as_str()),
+        }
+    }
+}
+
+fn holds_path(root: &Path) -> PathBuf {
+    root.join("holds.json")
+}
+
+pub(crate) fn load_holds(root: &Path) -> Result<Vec<LegalHold>, String> {
+    let path = holds_path(root);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let bytes = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_slice(&bytes).map_err(|e| format!("Corrupt legal hold register {}: {}", path.display(), e))
+}
+
+fn save_holds(root: &Path, holds: &[LegalHold]) -> Result<(), String> {
+    let path = holds_path(root);
+    let bytes = serde_json::to_vec_pretty(holds).map_err(|e| e.to_string())?;
+    let staging = path.with_extension(format!("tmp-{}", Uuid::new_v4().simple()));
+    fs::write(&staging, bytes).map_err(|e| format!("Failed to write legal hold register: {}", e))?;
+    fs::rename(&staging, &path).map_err(|e| format!("Failed to commit legal hold register: {}", e))
+}
+
+// Destruction and Audit Records
+
+/// Proof that an evidence item was destroyed, and what it was
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DestructionCertificate {
+    pub certificate_id: String,
+    pub evidence_id: String,
+    pub incident_id: String,
+    pub file_name: String,
+    pub evidence_type: String,
+    pub size_bytes: u64,
+    pub hashes: EvidenceHashes,
+    pub retention_class: RetentionClass,
+    pub stored_at: DateTime<Utc>,
+    /// Set when the item was purged because its retention lapsed
+    pub retention_expired_at: Option<DateTime<Utc>>,
+    pub destroyed_at: DateTime<Utc>,
+    pub destroyed_by: String,
+    pub reason: String,
+    pub chunks_destroyed: usize,
+    /// Chunks kept because other evidence shares their content
+    pub chunks_retained: usize,
+    /// Custody history up to and including the destruction
+    pub chain_of_custody: Vec<CustodyEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceAuditEntry {
+    pub entry_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub action: String,
+    pub actor: String,
+    #[serde(default)]
+    pub evidence_id: Option<String>,
+    #[serde(default)]
+    pub incident_id: Option<String>,
+    pub details: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<DestructionCertificate>,
+}
+
+impl EvidenceAuditEntry {
+    fn new(action: &str, actor: &str, details: String) -> Self {
+        Self {
+            entry_id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            action: action.to_string(),
+            actor: actor.to_string(),
+            evidence_id: None,
+            incident_id: None,
+            details,
+            certificate: None,
+        }
+    }
+
+    fn for_item(mut self, evidence_id: Option<&str>, incident_id: Option<&str>) -> Self {
+        self.evidence_id = evidence_id.map(str::to_string);
+        self.incident_id = incident_id.map(str::to_string);
+        self
+    }
+}
+
+/// Append-only JSON-lines audit log of retention and hold activity
+pub struct EvidenceAuditLog {
+    path: PathBuf,
+    writer: std::sync::Mutex<()>,
+}
+
+impl EvidenceAuditLog {
+    pub(crate) fn new(root: &Path) -> Self {
+        Self { path: root.join("audit.jsonl"), writer: std::sync::Mutex::new(()) }
+    }
+
+    pub fn append(&self, entry: &EvidenceAuditEntry) -> Result<(), String> {
+        let mut line = serde_json::to_vec(entry).map_err(|e| e.to_string())?;
+        line.push(b'\n');
+        let _guard = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("Failed to open evidence audit log: {}", e))?;
+        file.write_all(&line).and_then(|_| file.sync_data())
+            .map_err(|e| format!("Failed to write evidence audit log: {}", e))
+    }
+
+    pub fn entries(&self) -> Result<Vec<EvidenceAuditEntry>, String> {
+        if !self.path.exists() {
+            return Ok(Vec::new());
+        }
+        let text = fs::read_to_string(&self.path).map_err(|e| format!("Failed to read evidence audit log: {}", e))?;
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .enumerate()
+            .map(|(n, line)| serde_json::from_str(line).map_err(|e| format!("Corrupt evidence audit entry {}: {}", n + 1, e)))
+            .collect()
+    }
+}
+
+/// Outcome of one retention purge run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurgeReport {
+    pub run_at: DateTime<Utc>,
+    pub destroyed: Vec<DestructionCertificate>,
+    /// Expired items kept because a legal hold covers them
+    pub held: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+// Store Operations
+
+impl EvidenceStore {
+    /// Place a legal hold; evidence holds need an existing item
+    pub async fn place_legal_hold(&self, scope: HoldScope, reason: &str, actor: &str) -> Result<LegalHold, String> {
+        if reason.trim().is_empty() {
+            return Err("A legal hold needs a reason".to_string());
+        }
+        let (evidence_id, incident_id) = match &scope {
+            HoldScope::Evidence { evidence_id } => {
+                let record = self.get(evidence_id).await.ok_or_else(|| format!("Evidence {} not found", evidence_id))?;
+                (Some(evidence_id.clone()), record.incident_id)
+            }
+            HoldScope::Incident { incident_id } => (None, incident_id.clone()),
+        };
+        let hold = LegalHold {
+            hold_id: Uuid::new_v4().to_string(),
+            scope,
+            reason: reason.to_string(),
+            placed_by: actor.to_string(),
+            placed_at: Utc::now(),
+            released_by: None,
+            released_at: None,
+        };
+
+        {
+            let mut holds = self.holds.write().await;
+            holds.push(hold.clone());
+            if let Err(e) = save_holds(&self.config.root, &holds) {
+                holds.pop();
+                return Err(e);
+            }
+        }
+        self.audit.append(
+            &EvidenceAuditEntry::new("LegalHoldPlaced", actor, format!("Hold {}: {}", hold.hold_id, hold.reason))
+                .for_item(evidence_id.as_deref(), Some(&incident_id)),
+        )?;
+        self.refresh_hold_flags(actor).await?;
+        Ok(hold)
+    }
+
+    pub async fn release_legal_hold(&self, hold_id: &str, actor: &str) -> Result<LegalHold, String> {
+        let released = {
+            let mut holds = self.holds.write().await;
+            let hold = holds
+                .iter_mut()
+                .find(|hold| hold.hold_id == hold_id && hold.is_active())
+                .ok_or_else(|| format!("Active legal hold {} not found", hold_id))?;
+            hold.released_by = Some(actor.to_string());
+            hold.released_at = Some(Utc::now());
+            let released = hold.clone();
+            if let Err(e) = save_holds(&self.config.root, &holds) {
+                if let Some(hold) = holds.iter_mut().find(|hold| hold.hold_id == hold_id) {
+                    hold.released_by = None;
+                    hold.released_at = None;
+                }
+                return Err(e);
+            }
+            released
+        };
+        let (evidence_id, incident_id) = match &released.scope {
+            HoldScope::Evidence { evidence_id } => (Some(evidence_id.as_str()), None),
+            HoldScope::Incident { incident_id } => (None, Some(incident_id.as_str())),
+        };
+        self.audit.append(
+            &EvidenceAuditEntry::new("LegalHoldReleased", actor, format!("Hold {} released", hold_id))
+                .for_item(evidence_id, incident_id),
+        )?;
+        self.refresh_hold_flags(actor).await?;
+        Ok(released)
+    }
+
+    pub async fn list_legal_holds(&self, include_released: bool) -> Vec<LegalHold> {
+        self.holds
+            .read()
+            .await
+            .iter()
+            .filter(|hold| include_released || hold.is_active())
+            .cloned()
+            .collect()
+    }
+
+    /// Bring every item's `legal_hold` flag in line with the active holds,
+    /// noting each change in its chain of custody
+    async fn refresh_hold_flags(&self, actor: &str) -> Result<(), String> {
+        let holds = self.holds.read().await;
+        let mut records = self.records.write().await;
+        for record in records.values_mut() {
+            let held = holds
+                .iter()
+                .any(|hold| hold.is_active() && hold.covers(&record.incident_id, Some(&record.evidence_id)));
+            if held == record.legal_hold {
+                continue;
+            }
+            record.legal_hold = held;
+            let (action, details) = if held {
+                ("LegalHoldPlaced", "Deletion and retention purge suspended")
+            } else {
+                ("LegalHoldReleased", "No active legal hold remains")
+            };
+            record.chain_of_custody.push(CustodyEntry {
+                timestamp: Utc::now(),
+                action: action.to_string(),
+                actor: actor.to_string(),
+                details: details.to_string(),
+            });
+            self.persist(record)?;
+        }
+        Ok(())
+    }
+
+    /// Change an item's retention class
+    pub async fn set_retention_class(&self, evidence_id: &str, class: RetentionClass, actor: &str) -> Result<EvidenceBlob, String> {
+        let record = {
+            let mut records = self.records.write().await;
+            let record = records.get_mut(evidence_id).ok_or_else(|| format!("Evidence {} not found", evidence_id))?;
+            let previous = record.retention_class;
+            record.retention_class = class;
+            record.chain_of_custody.push(CustodyEntry {
+                timestamp: Utc::now(),
+                action: "RetentionChanged".to_string(),
+                actor: actor.to_string(),
+                details: format!("{} -> {}", previous, class),
+            });
+            self.persist(record)?;
+            record.clone()
+        };
+        self.audit.append(
+            &EvidenceAuditEntry::new("RetentionChanged", actor, format!("Retention set to {}", class))
+                .for_item(Some(evidence_id), Some(&record.incident_id)),
+        )?;
+        Ok(record)
+    }
+
+    /// Destroy an item ahead of its retention; refused while it is held
+    pub async fn delete(&self, evidence_id: &str, reason: &str, actor: &str) -> Result<DestructionCertificate, String> {
+        if reason.trim().is_empty() {
+            return Err("Deleting evidence needs a reason".to_string());
+        }
+        let holds = self.holds.read().await;
+        let mut records = self.records.write().await;
+        let record = records.get(evidence_id).ok_or_else(|| format!("Evidence {} not found", evidence_id))?;
+        if is_held(record, &holds) {
+            return Err(format!("Evidence {} is under legal hold", evidence_id));
+        }
+        self.destroy(&mut records, evidence_id, reason, actor, None)
+    }
+
+    /// Destroy every unheld item whose retention lapsed by `now`
+    pub async fn purge_expired(&self, actor: &str, now: DateTime<Utc>) -> PurgeReport {
+        let holds = self.holds.read().await;
+        let mut records = self.records.write().await;
+        let mut expired: Vec<(String, DateTime<Utc>)> = records
+            .values()
+            .filter_map(|record| record.retention_expires_at().filter(|at| *at <= now).map(|at| (record.evidence_id.clone(), at)))
+            .collect();
+        expired.sort_by_key(|(_, at)| *at);
+
+        let mut report = PurgeReport { run_at: now, destroyed: Vec::new(), held: Vec::new(), errors: Vec::new() };
+        for (evidence_id, expired_at) in expired {
+            if records.get(&evidence_id).is_some_and(|record| is_held(record, &holds)) {
+                report.held.push(evidence_id);
+                continue;
+            }
+            match self.destroy(&mut records, &evidence_id, "Retention period expired", actor, Some(expired_at)) {
+                Ok(certificate) => report.destroyed.push(certificate),
+                Err(e) => report.errors.push(format!("{}: {}", evidence_id, e)),
+            }
+        }
+
+        let summary = format!(
+            "{} destroyed, {} held, {} failed",
+            report.destroyed.len(),
+            report.held.len(),
+            report.errors.len()
+        );
+        if let Err(e) = self.audit.append(&EvidenceAuditEntry::new("RetentionPurge", actor, summary)) {
+            report.errors.push(e);
+        }
+        report
+    }
+
+    /// Remove a record and its unshared chunks, then certify the destruction
+    fn destroy(
+        &self,
+        records: &mut HashMap<String, EvidenceBlob>,
+        evidence_id: &str,
+        reason: &str,
+        actor: &str,
+        retention_expired_at: Option<DateTime<Utc>>,
+    ) -> Result<DestructionCertificate, String> {
+        fs::remove_file(self.record_path(evidence_id)).map_err(|e| format!("Failed to remove evidence record {}: {}", evidence_id, e))?;
+        let mut record = records.remove(evidence_id).ok_or_else(|| format!("Evidence {} not found", evidence_id))?;
+
+        let shared: HashSet<(&str, bool)> = records
+            .values()
+            .flat_map(|other| other.chunks.iter().map(move |sha256| (sha256.as_str(), other.compressed)))
+            .collect();
+        let mut seen = HashSet::new();
+        let (mut chunks_destroyed, mut chunks_retained) = (0, 0);
+        for sha256 in record.chunks.iter().filter(|sha256| seen.insert(sha256.as_str())) {
+            if shared.contains(&(sha256.as_str(), record.compressed)) {
+                chunks_retained += 1;
+                continue;
+            }
+            match fs::remove_file(self.chunk_path(sha256, record.compressed)) {
+                Ok(()) => chunks_destroyed += 1,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => log::warn!("Evidence {}: failed to remove chunk {}: {}", evidence_id, sha256, e),
+            }
+        }
+
+        let destroyed_at = Utc::now();
+        record.chain_of_custody.push(CustodyEntry {
+            timestamp: destroyed_at,
+            action: "Destroyed".to_string(),
+            actor: actor.to_string(),
+            details: reason.to_string(),
+        });
+        let certificate = DestructionCertificate {
+            certificate_id: Uuid::new_v4().to_string(),
+            evidence_id: record.evidence_id,
+            incident_id: record.incident_id,
+            file_name: record.file_name,
+            evidence_type: record.evidence_type,
+            size_bytes: record.size_bytes,
+            hashes: record.hashes,
+            retention_class: record.retention_class,
+            stored_at: record.stored_at,
+            retention_expired_at,
+            destroyed_at,
+            destroyed_by: actor.to_string(),
+            reason: reason.to_string(),
+            chunks_destroyed,
+            chunks_retained,
+            chain_of_custody: record.chain_of_custody,
+        };
+
+        let mut entry = EvidenceAuditEntry::new("EvidenceDestroyed", actor, format!("Certificate {}: {}", certificate.certificate_id, reason))
+            .for_item(Some(&certificate.evidence_id), Some(&certificate.incident_id));
+        entry.certificate = Some(certificate.clone());
+        self.audit.append(&entry).map_err(|e| {
+            log::error!("Evidence {} destroyed without a recorded certificate: {}", evidence_id, e);
+            format!("Evidence {} destroyed but its certificate was not recorded: {}", evidence_id, e)
+        })?;
+        Ok(certificate)
+    }
+
+    /// Certificates of destruction, optionally for one incident
+    pub fn destruction_certificates(&self, incident_id: Option<&str>) -> Result<Vec<DestructionCertificate>, String> {
+        Ok(self
+            .audit
+            .entries()?
+            .into_iter()
+            .filter_map(|entry| entry.certificate)
+            .filter(|certificate| incident_id.is_none_or(|id| certificate.incident_id == id))
+            .collect())
+    }
+
+    /// Audit log entries, optionally those about one item
+    pub fn audit_entries(&self, evidence_id: Option<&str>) -> Result<Vec<EvidenceAuditEntry>, String> {
+        Ok(self
+            .audit
+            .entries()?
+            .into_iter()
+            .filter(|entry| evidence_id.is_none_or(|id| entry.evidence_id.as_deref() == Some(id)))
+            .collect())
+    }
+}
+
+fn is_held(record: &EvidenceBlob, holds: &[LegalHold]) -> bool {
+    record.legal_hold
+        || holds
+            .iter()
+            .any(|hold| hold.is_active() && hold.covers(&record.incident_id, Some(&record.evidence_id)))
+}
+
+// NAPI Bindings
+
+#[cfg(feature = "napi")]
+#[napi]
+impl EvidenceStoreNapi {
+    /// Hold every item of an incident, including evidence collected later
+    #[napi]
+    pub async fn place_incident_legal_hold(&self, incident_id: String, reason: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize(auth_token, "incident:write", &incident_id)?;
+        let hold = self.inner.place_legal_hold(HoldScope::Incident { incident_id }, &reason, &actor).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to place legal hold: {}", e)))?;
+        serde_json::to_string(&hold)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn place_evidence_legal_hold(&self, evidence_id: String, reason: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize(auth_token, "incident:write", &evidence_id)?;
+        let hold = self.inner.place_legal_hold(HoldScope::Evidence { evidence_id }, &reason, &actor).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to place legal hold: {}", e)))?;
+        serde_json::to_string(&hold)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn release_legal_hold(&self, hold_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize(auth_token, "incident:write", &hold_id)?;
+        let hold = self.inner.release_legal_hold(&hold_id, &actor).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to release legal hold: {}", e)))?;
+        serde_json::to_string(&hold)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn list_legal_holds(&self, include_released: Option<bool>) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.list_legal_holds(include_released.unwrap_or(false)).await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Set an item's retention class (`30_days`, `90_days`, `365_days` or `indefinite`)
+    #[napi]
+    pub async fn set_evidence_retention(&self, evidence_id: String, retention_class: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize(auth_token, "incident:write", &evidence_id)?;
+        let class: RetentionClass = retention_class.parse().map_err(napi::Error::from_reason)?;
+        let record = self.inner.set_retention_class(&evidence_id, class, &actor).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to set retention: {}", e)))?;
+        serde_json::to_string(&record)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Destroy an unheld item and return its certificate of destruction
+    #[napi]
+    pub async fn delete_evidence(&self, evidence_id: String, reason: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize(auth_token, "incident:write", &evidence_id)?;
+        let certificate = self.inner.delete(&evidence_id, &reason, &actor).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to delete evidence: {}", e)))?;
+        serde_json::to_string(&certificate)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Destroy evidence whose retention has lapsed now
+    #[napi]
+    pub async fn purge_expired_evidence(&self, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize(auth_token, "incident:write", "evidence")?;
+        serde_json::to_string(&self.inner.purge_expired(&actor, Utc::now()).await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Purge expired evidence in the background every `interval_secs`
+    #[napi]
+    pub async fn start_evidence_purge_job(&self, interval_secs: u32, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize(auth_token, "incident:write", "evidence")?;
+        let inner = self.inner.clone();
+        let period = std::time::Duration::from_secs(interval_secs.max(1) as u64);
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                let report = inner.purge_expired(&actor, Utc::now()).await;
+                for error in &report.errors {
+                    log::warn!("Evidence purge: {}", error);
+                }
+            }
+        });
+        if let Some(previous) = self.purge_job.lock().unwrap_or_else(|e| e.into_inner()).replace(handle) {
+            previous.abort();
+        }
+        Ok(())
+    }
+
+    #[napi]
+    pub fn stop_evidence_purge_job(&self, auth_token: Option<String>) -> napi::Result<bool> {
+        self.authorize(auth_token, "incident:write", "evidence")?;
+        let job = self.purge_job.lock().unwrap_or_else(|e| e.into_inner()).take();
+        Ok(job.map(|job| job.abort()).is_some())
+    }
+
+    #[napi]
+    pub async fn list_destruction_certificates(&self, incident_id: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token, "audit:read", "evidence")?;
+        let certificates = self.inner.destruction_certificates(incident_id.as_deref())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to read certificates: {}", e)))?;
+        serde_json::to_string(&certificates)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn get_evidence_audit_log(&self, evidence_id: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token, "audit:read", "evidence")?;
+        let entries = self.inner.audit_entries(evidence_id.as_deref())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to read audit log: {}", e)))?;
+        serde_json::to_string(&entries)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+}
+
+#[cfg(feature = "napi")]
+impl EvidenceStoreNapi {
+    fn authorize(&self, auth_token: Option<String>, permission: &str, resource: &str) -> napi::Result<String> {
+        self.access.check(auth_token.as_deref(), permission, resource)
+            .map_err(napi::Error::from_reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evidence_store::EvidenceStoreConfig;
+
+    fn temp_root() -> PathBuf {
+        std::env::temp_dir().join(format!("phantom-retention-{}", Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn test_holds_block_deletion_and_purge() {
+        let root = temp_root();
+        let mut config = EvidenceStoreConfig::new(&root);
+        config.default_retention = RetentionClass::Days30;
+        let store = EvidenceStore::open(config.clone()).unwrap();
+
+        let disk = store.ingest_bytes("INC-1", "disk.img", "DiskImage", b"disk image", "responder").await.unwrap();
+        let hold = store
+            .place_legal_hold(HoldScope::Incident { incident_id: "INC-1".to_string() }, "Litigation", "counsel")
+            .await
+            .unwrap();
+        let later = store.ingest_bytes("INC-1", "mem.raw", "MemoryDump", b"memory", "responder").await.unwrap();
+        assert!(later.legal_hold);
+        assert!(store.delete(&disk.evidence_id, "cleanup", "analyst").await.unwrap_err().contains("legal hold"));
+
+        let expired = Utc::now() + Duration::days(31);
+        let report = store.purge_expired("retention", expired).await;
+        assert!(report.destroyed.is_empty());
+        assert_eq!(report.held.len(), 2);
+
+        // The hold register survives a restart
+        let store = EvidenceStore::open(config).unwrap();
+        assert_eq!(store.list_legal_holds(false).await.len(), 1);
+        store.release_legal_hold(&hold.hold_id, "counsel").await.unwrap();
+        let custody = store.get(&disk.evidence_id).await.unwrap().chain_of_custody;
+        let actions: Vec<&str> = custody.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(actions, vec!["Acquired", "LegalHoldPlaced", "LegalHoldReleased"]);
+
+        let report = store.purge_expired("retention", expired).await;
+        assert_eq!(report.destroyed.len(), 2);
+        assert!(report.held.is_empty());
+        assert!(store.list(Some("INC-1")).await.is_empty());
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn test_destruction_keeps_shared_chunks_and_certifies() {
+        let root = temp_root();
+        let store = EvidenceStore::open(EvidenceStoreConfig::new(&root)).unwrap();
+        let original = store.ingest_bytes("INC-2", "a.log", "LogFile", b"shared content", "responder").await.unwrap();
+        let copy = store.ingest_bytes("INC-3", "b.log", "LogFile", b"shared content", "responder").await.unwrap();
+        store.set_retention_class(&original.evidence_id, "90_days".parse().unwrap(), "analyst").await.unwrap();
+
+        // Indefinite evidence is never purged
+        let report = store.purge_expired("retention", Utc::now() + Duration::days(91)).await;
+        assert_eq!(report.destroyed.len(), 1);
+        let certificate = &report.destroyed[0];
+        assert_eq!(certificate.evidence_id, original.evidence_id);
+        assert_eq!(certificate.hashes, original.hashes);
+        assert_eq!((certificate.chunks_destroyed, certificate.chunks_retained), (0, 1));
+        assert_eq!(certificate.chain_of_custody.last().unwrap().action, "Destroyed");
+        assert_eq!(store.retrieve(&copy.evidence_id, "analyst").await.unwrap(), b"shared content");
+
+        let certificate = store.delete(&copy.evidence_id, "Duplicate of case file", "analyst").await.unwrap();
+        assert_eq!(certificate.chunks_destroyed, 1);
+        assert!(!store.chunk_path(&copy.chunks[0], false).exists());
+
+        let certificates = store.destruction_certificates(None).unwrap();
+        assert_eq!(certificates.len(), 2);
+        assert_eq!(store.destruction_certificates(Some("INC-3")).unwrap()[0].reason, "Duplicate of case file");
+        let actions: Vec<String> = store.audit_entries(None).unwrap().into_iter().map(|e| e.action).collect();
+        assert_eq!(actions, vec!["RetentionChanged", "EvidenceDestroyed", "RetentionPurge", "EvidenceDestroyed"]);
+
+        let _ = fs::remove_dir_all(root);
+    }
+}
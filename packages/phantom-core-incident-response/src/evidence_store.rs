@@ -0,0 +1,670 @@
+//! Evidence Blob Store
+//!
+//! Content-addressed on-disk storage for evidence files. Ingested content is
+//! hashed (MD5, SHA-1, SHA-256), split into fixed-size chunks and each chunk
+//! is stored under its own SHA-256, optionally gzip-compressed, so identical
+//! content is stored once. Every retrieval re-verifies chunk and file hashes,
+//! and every operation on an evidence item is appended to its chain of custody.
+//! Items carry a retention class and may be placed under legal hold; see
+//! [`crate::evidence_retention`] for holds, purges and destruction records.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+#[cfg(feature = "napi")]
+use napi::bindgen_prelude::Buffer;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+
+#[cfg(feature = "napi")]
+use crate::access::AccessGuard;
+use crate::evidence_retention::{load_holds, EvidenceAuditLog, LegalHold, RetentionClass};
+use crate::IncidentEvidence;
+
+/// Default chunk size for stored content
+const DEFAULT_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+// Store Configuration
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceStoreConfig {
+    pub root: PathBuf,
+    /// Gzip chunks on disk; requires the `compression` feature
+    #[serde(default)]
+    pub compress: bool,
+    #[serde(default = "default_chunk_size")]
+    pub chunk_size: usize,
+    /// Retention class given to newly ingested evidence
+    #[serde(default)]
+    pub default_retention: RetentionClass,
+}
+
+fn default_chunk_size() -> usize {
+    DEFAULT_CHUNK_SIZE
+}
+
+impl EvidenceStoreConfig {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            compress: false,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            default_retention: RetentionClass::default(),
+        }
+    }
+}
+
+// Evidence Records
+
+/// One entry in an evidence item's chain of custody
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustodyEntry {
+    pub timestamp: DateTime<Utc>,
+    pub action: String,
+    pub actor: String,
+    pub details: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvidenceHashes {
+    pub md5: String,
+    pub sha1: String,
+    pub sha256: String,
+}
+
+/// Stored evidence file and its custody history
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvidenceBlob {
+    pub evidence_id: String,
+    pub incident_id: String,
+    pub file_name: String,
+    pub evidence_type: String,
+    /// Original path for file ingestion, empty for buffers
+    pub source_path: String,
+    pub size_bytes: u64,
+    pub hashes: EvidenceHashes,
+    /// SHA-256 of each chunk, in order
+    pub chunks: Vec<String>,
+    pub compressed: bool,
+    pub stored_at: DateTime<Utc>,
+    pub chain_of_custody: Vec<CustodyEntry>,
+    #[serde(default)]
+    pub retention_class: RetentionClass,
+    /// Set while any active legal hold covers the item or its incident
+    #[serde(default)]
+    pub legal_hold: bool,
+}
+
+impl EvidenceBlob {
+    /// Metadata view used by the incident response records
+    pub fn to_incident_evidence(&self) -> IncidentEvidence {
+        IncidentEvidence {
+            evidence_id: self.evidence_id.clone(),
+            incident_id: self.incident_id.clone(),
+            evidence_type: self.evidence_type.clone(),
+            source_system: self.source_path.clone(),
+            collected_at: self.stored_at,
+            collected_by: self.chain_of_custody.first().map(|e| e.actor.clone()).unwrap_or_default(),
+            file_hash: self.hashes.sha256.clone(),
+            chain_of_custody: self
+                .chain_of_custody
+                .iter()
+                .map(|e| format!("{} {} by {}", e.timestamp.to_rfc3339(), e.action, e.actor))
+                .collect(),
+            preservation_method: if self.compressed {
+                "content_addressed_store+gzip".to_string()
+            } else {
+                "content_addressed_store".to_string()
+            },
+        }
+    }
+}
+
+/// Outcome of re-hashing stored evidence
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    pub evidence_id: String,
+    pub verified: bool,
+    pub expected: EvidenceHashes,
+    pub actual: Option<EvidenceHashes>,
+    /// Chunks that were missing or did not match their address
+    pub corrupt_chunks: Vec<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Incremental MD5/SHA-1/SHA-256 over streamed content
+struct MultiHasher {
+    md5: md5::Context,
+    sha1: Sha1,
+    sha256: Sha256,
+}
+
+impl MultiHasher {
+    fn new() -> Self {
+        Self {
+            md5: md5::Context::new(),
+            sha1: Sha1::new(),
+            sha256: Sha256::new(),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.md5.consume(data);
+        self.sha1.update(data);
+        self.sha256.update(data);
+    }
+
+    fn finish(self) -> EvidenceHashes {
+        EvidenceHashes {
+            md5: format!("{:x}", self.md5.compute()),
+            sha1: hex::encode(self.sha1.finalize()),
+            sha256: hex::encode(self.sha256.finalize()),
+        }
+    }
+}
+
+#[cfg(feature = "compression")]
+fn compress(data: &[u8]) -> Result<Vec<u8>, String> {
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).map_err(|e| e.to_string())?;
+    encoder.finish().map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "compression"))]
+fn compress(_data: &[u8]) -> Result<Vec<u8>, String> {
+    Err("Compression requires the `compression` feature".to_string())
+}
+
+#[cfg(feature = "compression")]
+fn decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut decoded = Vec::new();
+    flate2::read::GzDecoder::new(data).read_to_end(&mut decoded).map_err(|e| e.to_string())?;
+    Ok(decoded)
+}
+
+#[cfg(not(feature = "compression"))]
+fn decompress(_data: &[u8]) -> Result<Vec<u8>, String> {
+    Err("Compressed evidence requires the `compression` feature".to_string())
+}
+
+/// Content written to the chunk store, not yet registered as evidence
+struct StagedContent {
+    size_bytes: u64,
+    hashes: EvidenceHashes,
+    chunks: Vec<String>,
+}
+
+// Evidence Store
+
+pub struct EvidenceStore {
+    pub(crate) config: EvidenceStoreConfig,
+    pub(crate) records: Arc<RwLock<HashMap<String, EvidenceBlob>>>,
+    pub(crate) holds: RwLock<Vec<LegalHold>>,
+    pub(crate) audit: EvidenceAuditLog,
+}
+
+impl EvidenceStore {
+    /// Open (or create) a store under `config.root`, loading existing records
+    pub fn open(config: EvidenceStoreConfig) -> Result<Self, String> {
+        if config.chunk_size == 0 {
+            return Err("Chunk size must be greater than zero".to_string());
+        }
+        if config.compress && cfg!(not(feature = "compression")) {
+            return Err("Compression requires the `compression` feature".to_string());
+        }
+        for dir in ["chunks", "records"] {
+            let path = config.root.join(dir);
+            fs::create_dir_all(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        }
+
+        let mut records = HashMap::new();
+        let entries = fs::read_dir(config.root.join("records")).map_err(|e| e.to_string())?;
+        for entry in entries.flatten() {
+            let bytes = fs::read(entry.path()).map_err(|e| e.to_string())?;
+            let record: EvidenceBlob = serde_json::from_slice(&bytes)
+                .map_err(|e| format!("Corrupt evidence record {}: {}", entry.path().display(), e))?;
+            records.insert(record.evidence_id.clone(), record);
+        }
+
+        let holds = load_holds(&config.root)?;
+        let audit = EvidenceAuditLog::new(&config.root);
+        Ok(Self {
+            config,
+            records: Arc::new(RwLock::new(records)),
+            holds: RwLock::new(holds),
+            audit,
+        })
+    }
+
+    pub(crate) fn chunk_path(&self, sha256: &str, compressed: bool) -> PathBuf {
+        let name = if compressed { format!("{}.gz", sha256) } else { sha256.to_string() };
+        self.config.root.join("chunks").join(&sha256[..2]).join(name)
+    }
+
+    pub(crate) fn record_path(&self, evidence_id: &str) -> PathBuf {
+        self.config.root.join("records").join(format!("{}.json", evidence_id))
+    }
+
+    pub(crate) fn persist(&self, record: &EvidenceBlob) -> Result<(), String> {
+        let bytes = serde_json::to_vec_pretty(record).map_err(|e| e.to_string())?;
+        fs::write(self.record_path(&record.evidence_id), bytes)
+            .map_err(|e| format!("Failed to write evidence record {}: {}", record.evidence_id, e))
+    }
+
+    /// Store one chunk under its SHA-256 unless it is already present
+    fn put_chunk(&self, chunk: &[u8]) -> Result<String, String> {
+        let sha256 = hex::encode(Sha256::digest(chunk));
+        let path = self.chunk_path(&sha256, self.config.compress);
+        if path.exists() {
+            return Ok(sha256);
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let bytes = if self.config.compress { compress(chunk)? } else { chunk.to_vec() };
+        // Write then rename so a crash never leaves a truncated chunk at its address
+        let staging = path.with_extension(format!("tmp-{}", Uuid::new_v4().simple()));
+        fs::write(&staging, bytes).map_err(|e| format!("Failed to write chunk {}: {}", sha256, e))?;
+        fs::rename(&staging, &path).map_err(|e| format!("Failed to commit chunk {}: {}", sha256, e))?;
+        Ok(sha256)
+    }
+
+    fn read_chunk(&self, sha256: &str, compressed: bool) -> Result<Vec<u8>, String> {
+        let bytes = fs::read(self.chunk_path(sha256, compressed)).map_err(|e| format!("Missing chunk {}: {}", sha256, e))?;
+        let chunk = if compressed { decompress(&bytes)? } else { bytes };
+        if hex::encode(Sha256::digest(&chunk)) != sha256 {
+            return Err(format!("Chunk {} does not match its address", sha256));
+        }
+        Ok(chunk)
+    }
+
+    async fn register(
+        &self,
+        incident_id: &str,
+        file_name: &str,
+        evidence_type: &str,
+        source_path: String,
+        collected_by: &str,
+        staged: StagedContent,
+    ) -> Result<EvidenceBlob, String> {
+        let StagedContent { size_bytes, hashes, chunks } = staged;
+        let stored_at = Utc::now();
+        let legal_hold = self.holds.read().await.iter().any(|hold| hold.is_active() && hold.covers(incident_id, None));
+        let record = EvidenceBlob {
+            evidence_id: Uuid::new_v4().to_string(),
+            incident_id: incident_id.to_string(),
+            file_name: file_name.to_string(),
+            evidence_type: evidence_type.to_string(),
+            source_path,
+            size_bytes,
+            chain_of_custody: vec![CustodyEntry {
+                timestamp: stored_at,
+                action: "Acquired".to_string(),
+                actor: collected_by.to_string(),
+                details: format!("Stored {} bytes in {} chunk(s), sha256 {}", size_bytes, chunks.len(), hashes.sha256),
+            }],
+            hashes,
+            chunks,
+            compressed: self.config.compress,
+            stored_at,
+            retention_class: self.config.default_retention,
+            legal_hold,
+        };
+        self.persist(&record)?;
+        self.records.write().await.insert(record.evidence_id.clone(), record.clone());
+        Ok(record)
+    }
+
+    /// Ingest an in-memory evidence buffer
+    pub async fn ingest_bytes(
+        &self,
+        incident_id: &str,
+        file_name: &str,
+        evidence_type: &str,
+        data: &[u8],
+        collected_by: &str,
+    ) -> Result<EvidenceBlob, String> {
+        let mut hasher = MultiHasher::new();
+        hasher.update(data);
+        let chunks = data
+            .chunks(self.config.chunk_size)
+            .map(|chunk| self.put_chunk(chunk))
+            .collect::<Result<Vec<_>, _>>()?;
+        let staged = StagedContent {
+            size_bytes: data.len() as u64,
+            hashes: hasher.finish(),
+            chunks,
+        };
+        self.register(incident_id, file_name, evidence_type, String::new(), collected_by, staged).await
+    }
+
+    /// Ingest a file from disk, streaming it chunk by chunk
+    pub async fn ingest_path(
+        &self,
+        incident_id: &str,
+        path: &Path,
+        evidence_type: &str,
+        collected_by: &str,
+    ) -> Result<EvidenceBlob, String> {
+        let mut file = fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        let mut hasher = MultiHasher::new();
+        let mut chunks = Vec::new();
+        let mut size_bytes = 0u64;
+        let mut buffer = vec![0u8; self.config.chunk_size];
+
+        loop {
+            let mut filled = 0;
+            while filled < buffer.len() {
+                let read = file
+                    .read(&mut buffer[filled..])
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                if read == 0 {
+                    break;
+                }
+                filled += read;
+            }
+            if filled == 0 {
+                break;
+            }
+            hasher.update(&buffer[..filled]);
+            chunks.push(self.put_chunk(&buffer[..filled])?);
+            size_bytes += filled as u64;
+            if filled < buffer.len() {
+                break;
+            }
+        }
+
+        let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let staged = StagedContent {
+            size_bytes,
+            hashes: hasher.finish(),
+            chunks,
+        };
+        self.register(incident_id, &file_name, evidence_type, path.display().to_string(), collected_by, staged)
+            .await
+    }
+
+    /// Append a custody entry and persist the record
+    pub async fn append_custody(&self, evidence_id: &str, action: &str, actor: &str, details: String) -> Result<EvidenceBlob, String> {
+        let mut records = self.records.write().await;
+        let record = records.get_mut(evidence_id).ok_or_else(|| format!("Evidence {} not found", evidence_id))?;
+        record.chain_of_custody.push(CustodyEntry {
+            timestamp: Utc::now(),
+            action: action.to_string(),
+            actor: actor.to_string(),
+            details,
+        });
+        self.persist(record)?;
+        Ok(record.clone())
+    }
+
+    /// Reassemble the content, checking every chunk against its address
+    fn assemble(&self, record: &EvidenceBlob) -> (Vec<u8>, Vec<String>) {
+        let mut content = Vec::with_capacity(record.size_bytes as usize);
+        let mut corrupt = Vec::new();
+        for sha256 in &record.chunks {
+            match self.read_chunk(sha256, record.compressed) {
+                Ok(chunk) => content.extend_from_slice(&chunk),
+                Err(e) => {
+                    log::warn!("Evidence {}: {}", record.evidence_id, e);
+                    corrupt.push(sha256.clone());
+                }
+            }
+        }
+        (content, corrupt)
+    }
+
+    /// Re-hash stored content and record the check in the chain of custody
+    pub async fn verify(&self, evidence_id: &str, actor: &str) -> Result<IntegrityReport, String> {
+        let record = self.get(evidence_id).await.ok_or_else(|| format!("Evidence {} not found", evidence_id))?;
+        let (content, corrupt_chunks) = self.assemble(&record);
+        let actual = corrupt_chunks.is_empty().then(|| {
+            let mut hasher = MultiHasher::new();
+            hasher.update(&content);
+            hasher.finish()
+        });
+        let verified = actual.as_ref() == Some(&record.hashes);
+
+        let (action, details) = if verified {
+            ("IntegrityVerified", format!("sha256 {} verified", record.hashes.sha256))
+        } else {
+            ("IntegrityCheckFailed", format!("{} corrupt chunk(s); hashes differ from acquisition", corrupt_chunks.len()))
+        };
+        self.append_custody(evidence_id, action, actor, details).await?;
+
+        Ok(IntegrityReport {
+            evidence_id: evidence_id.to_string(),
+            verified,
+            expected: record.hashes,
+            actual,
+            corrupt_chunks,
+            checked_at: Utc::now(),
+        })
+    }
+
+    /// Return verified content; tampered or incomplete evidence is refused
+    pub async fn retrieve(&self, evidence_id: &str, accessed_by: &str) -> Result<Vec<u8>, String> {
+        let record = self.get(evidence_id).await.ok_or_else(|| format!("Evidence {} not found", evidence_id))?;
+        let (content, corrupt_chunks) = self.assemble(&record);
+        let sha256 = hex::encode(Sha256::digest(&content));
+
+        if !corrupt_chunks.is_empty() || sha256 != record.hashes.sha256 {
+            self.append_custody(
+                evidence_id,
+                "IntegrityCheckFailed",
+                accessed_by,
+                format!("Retrieval refused: expected sha256 {}, found {}", record.hashes.sha256, sha256),
+            )
+            .await?;
+            return Err(format!("Evidence {} failed integrity verification", evidence_id));
+        }
+
+        self.append_custody(evidence_id, "Retrieved", accessed_by, format!("sha256 {} verified on retrieval", sha256))
+            .await?;
+        Ok(content)
+    }
+
+    pub async fn get(&self, evidence_id: &str) -> Option<EvidenceBlob> {
+        self.records.read().await.get(evidence_id).cloned()
+    }
+
+    pub async fn list(&self, incident_id: Option<&str>) -> Vec<EvidenceBlob> {
+        let mut records: Vec<EvidenceBlob> = self
+            .records
+            .read()
+            .await
+            .values()
+            .filter(|r| incident_id.is_none_or(|id| r.incident_id == id))
+            .cloned()
+            .collect();
+        records.sort_by_key(|r| r.stored_at);
+        records
+    }
+}
+
+// NAPI Bindings
+
+/// NAPI wrapper around the evidence blob store
+#[cfg(feature = "napi")]
+#[napi]
+pub struct EvidenceStoreNapi {
+    pub(crate) inner: Arc<EvidenceStore>,
+    pub(crate) access: AccessGuard,
+    pub(crate) purge_job: std::sync::Mutex<Option<tokio::task::JoinHandle<()>>>,
+}
+
+#[cfg(feature = "napi")]
+impl Drop for EvidenceStoreNapi {
+    fn drop(&mut self) {
+        if let Some(job) = self.purge_job.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            job.abort();
+        }
+    }
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl EvidenceStoreNapi {
+    #[napi(constructor)]
+    pub fn new(root_dir: String, compress: Option<bool>) -> napi::Result<Self> {
+        let mut config = EvidenceStoreConfig::new(root_dir);
+        config.compress = compress.unwrap_or(false);
+        let store = EvidenceStore::open(config)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to open evidence store: {}", e)))?;
+        Ok(EvidenceStoreNapi {
+            inner: Arc::new(store),
+            access: AccessGuard::default(),
+            purge_job: std::sync::Mutex::new(None),
+        })
+    }
+
+    /// Store an evidence buffer and return its record
+    #[napi]
+    pub async fn ingest_evidence_buffer(
+        &self,
+        incident_id: String,
+        file_name: String,
+        evidence_type: String,
+        data: Buffer,
+        collected_by: String,
+    ) -> napi::Result<String> {
+        let record = self.inner.ingest_bytes(&incident_id, &file_name, &evidence_type, &data, &collected_by).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to ingest evidence: {}", e)))?;
+        serde_json::to_string(&record)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Store an evidence file from disk and return its record
+    #[napi]
+    pub async fn ingest_evidence_file(
+        &self,
+        incident_id: String,
+        path: String,
+        evidence_type: String,
+        collected_by: String,
+    ) -> napi::Result<String> {
+        let record = self.inner.ingest_path(&incident_id, Path::new(&path), &evidence_type, &collected_by).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to ingest evidence: {}", e)))?;
+        serde_json::to_string(&record)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Return verified evidence content
+    #[napi]
+    pub async fn retrieve_evidence(&self, evidence_id: String, accessed_by: String) -> napi::Result<Buffer> {
+        self.inner.retrieve(&evidence_id, &accessed_by).await
+            .map(Buffer::from)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to retrieve evidence: {}", e)))
+    }
+
+    #[napi]
+    pub async fn verify_evidence(&self, evidence_id: String, actor: String) -> napi::Result<String> {
+        let report = self.inner.verify(&evidence_id, &actor).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to verify evidence: {}", e)))?;
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn get_evidence(&self, evidence_id: String) -> napi::Result<Option<String>> {
+        match self.inner.get(&evidence_id).await {
+            Some(record) => serde_json::to_string(&record)
+                .map(Some)
+                .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e))),
+            None => Ok(None),
+        }
+    }
+
+    #[napi]
+    pub async fn list_evidence(&self, incident_id: Option<String>) -> napi::Result<String> {
+        let records = self.inner.list(incident_id.as_deref()).await;
+        serde_json::to_string(&records)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_root() -> PathBuf {
+        std::env::temp_dir().join(format!("phantom-evidence-{}", Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn test_ingest_chunks_hashes_and_custody() {
+        let root = temp_root();
+        let mut config = EvidenceStoreConfig::new(&root);
+        config.chunk_size = 8;
+        let store = EvidenceStore::open(config.clone()).unwrap();
+
+        let data = b"memory dump contents spanning chunks";
+        let record = store.ingest_bytes("INC-1", "mem.raw", "MemoryDump", data, "responder").await.unwrap();
+        assert_eq!(record.chunks.len(), 5);
+        assert_eq!(record.hashes.sha256, hex::encode(Sha256::digest(data)));
+        assert_eq!(record.hashes.md5, format!("{:x}", md5::compute(data)));
+
+        let path = root.join("mem-copy.raw");
+        fs::write(&path, data).unwrap();
+        let from_file = store.ingest_path("INC-1", &path, "MemoryDump", "responder").await.unwrap();
+        assert_eq!(from_file.hashes, record.hashes);
+        assert_eq!(from_file.chunks, record.chunks);
+
+        assert_eq!(store.retrieve(&record.evidence_id, "analyst").await.unwrap(), data);
+        let reopened = EvidenceStore::open(config).unwrap();
+        let custody = reopened.get(&record.evidence_id).await.unwrap().chain_of_custody;
+        let actions: Vec<&str> = custody.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(actions, vec!["Acquired", "Retrieved"]);
+        assert_eq!(reopened.list(Some("INC-1")).await.len(), 2);
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn test_tampered_chunk_fails_verification() {
+        let root = temp_root();
+        let store = EvidenceStore::open(EvidenceStoreConfig::new(&root)).unwrap();
+        let record = store.ingest_bytes("INC-2", "log.txt", "LogFile", b"original log line", "responder").await.unwrap();
+
+        fs::write(store.chunk_path(&record.chunks[0], false), b"tampered log line").unwrap();
+
+        assert!(store.retrieve(&record.evidence_id, "analyst").await.is_err());
+        let report = store.verify(&record.evidence_id, "auditor").await.unwrap();
+        assert!(!report.verified);
+        assert_eq!(report.corrupt_chunks, record.chunks);
+
+        let custody = store.get(&record.evidence_id).await.unwrap().chain_of_custody;
+        assert_eq!(custody.len(), 3);
+        assert!(custody[1..].iter().all(|e| e.action == "IntegrityCheckFailed"));
+
+        let _ = fs::remove_dir_all(root);
+    }
+
+    #[cfg(feature = "compression")]
+    #[tokio::test]
+    async fn test_compressed_round_trip() {
+        let root = temp_root();
+        let mut config = EvidenceStoreConfig::new(&root);
+        config.compress = true;
+        let store = EvidenceStore::open(config).unwrap();
+
+        let data = vec![b'A'; 64 * 1024];
+        let record = store.ingest_bytes("INC-3", "pcap", "NetworkCapture", &data, "responder").await.unwrap();
+        assert!(fs::metadata(store.chunk_path(&record.chunks[0], true)).unwrap().len() < 1024);
+        assert_eq!(store.retrieve(&record.evidence_id, "analyst").await.unwrap(), data);
+
+        let _ = fs::remove_dir_all(root);
+    }
+}
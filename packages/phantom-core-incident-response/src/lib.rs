@@ -25,6 +25,16 @@ use indexmap::IndexMap;
 // use regex::Regex;
 use time::OffsetDateTime;
 
+pub mod access;
+pub mod assets;
+pub mod escalation;
+pub mod evidence_retention;
+pub mod evidence_store;
+pub mod live_response;
+pub mod notifications;
+pub mod playbook_executor;
+pub mod playbook_steps;
+
 /// Incident classification and metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IncidentRecord {
@@ -163,6 +173,16 @@ pub fn create_incident_response(incident_data: String, response_config: String)
     let incident_type = input.get("incident_type").and_then(|v| v.as_str())
         .unwrap_or("security_breach");
 
+    let affected_systems = input.get("affected_systems").and_then(|v| v.as_array())
+        .map(|arr| arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect())
+        .unwrap_or_else(|| vec![
+            "web_application_server".to_string(),
+            "database_server".to_string(),
+            "user_workstations".to_string(),
+        ]);
+    let business_impact = assets::assess(&assets::scope_of(&input), &affected_systems);
+    let priority = business_impact.priority().unwrap_or("high");
+
     let incident_id = Uuid::new_v4().to_string();
     let now = Utc::now();
 
@@ -172,18 +192,14 @@ pub fn create_incident_response(incident_data: String, response_config: String)
         description: input.get("description").and_then(|v| v.as_str())
             .unwrap_or("Potential security breach requiring immediate investigation").to_string(),
         severity: severity.to_string(),
-        priority: "high".to_string(),
+        priority: priority.to_string(),
         status: "active".to_string(),
         incident_type: incident_type.to_string(),
         created_at: now,
         updated_at: now,
         assigned_to: input.get("assigned_to").and_then(|v| v.as_str())
             .unwrap_or("incident_response_team").to_string(),
-        affected_systems: vec![
-            "web_application_server".to_string(),
-            "database_server".to_string(),
-            "user_workstations".to_string(),
-        ],
+        affected_systems,
         indicators: vec![
             "unusual_network_traffic".to_string(),
             "failed_authentication_attempts".to_string(),
@@ -220,6 +236,14 @@ pub fn create_incident_response(incident_data: String, response_config: String)
             "playbook_triggered": true,
             "stakeholders_notified": true,
             "evidence_collection": "initiated"
+        },
+        "business_impact": {
+            "impact_level": assets::ImpactAssessment::level(business_impact.impact_score),
+            "impact_score": business_impact.impact_score,
+            "highest_criticality": business_impact.highest_criticality,
+            "affected_assets": business_impact.affected_assets,
+            "owners": business_impact.owners,
+            "unresolved_systems": business_impact.unresolved_systems
         }
     });
 
@@ -390,6 +414,13 @@ pub fn implement_containment(containment_config: String, containment_strategy: S
             "workstation_exec_suite".to_string(),
         ]);
 
+    let blast_radius = assets::assess(&assets::scope_of(&input), &target_systems);
+    let impact_assessment = if blast_radius.affected_assets.is_empty() {
+        "minimal_business_disruption".to_string()
+    } else {
+        format!("{}_business_disruption", assets::ImpactAssessment::level(blast_radius.blast_radius_score))
+    };
+
     let action_id = Uuid::new_v4().to_string();
     let now = Utc::now();
 
@@ -424,10 +455,17 @@ pub fn implement_containment(containment_config: String, containment_strategy: S
         },
         "containment_metadata": {
             "containment_level": "immediate",
-            "impact_assessment": "minimal_business_disruption",
+            "impact_assessment": impact_assessment,
             "monitoring_enabled": true,
             "escalation_prevented": true,
-            "recovery_planned": true
+            "recovery_planned": true,
+            "blast_radius": {
+                "blast_radius_score": blast_radius.blast_radius_score,
+                "dependent_assets": blast_radius.dependent_assets,
+                "network_zones": blast_radius.network_zones,
+                "owners": blast_radius.owners,
+                "unresolved_systems": blast_radius.unresolved_systems
+            }
         }
     });
 
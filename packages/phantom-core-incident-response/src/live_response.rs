@@ -0,0 +1,750 @@
+//! Live Response
+//!
+//! Collects artifacts from endpoints during an incident. Commands (collect a
+//! file, list processes, dump autoruns, grab a memory region) are queued per
+//! endpoint and run one at a time, in order. Endpoints served by an
+//! `AgentConnector` (an EDR API, a remote shell) are driven by a worker task;
+//! other endpoints run an agent that pulls its commands with `poll` and
+//! returns the output with `complete`.
+//!
+//! Every command has a timeout, counted from dispatch, and can be cancelled
+//! while queued or in flight. Output is ingested into the evidence store for
+//! the command's incident, hashed there, checked against the hash the agent
+//! reported, and tagged with the endpoint and command in its chain of custody.
+//! The `collect_artifacts` playbook action queues a command and waits for its
+//! evidence, backing `CollectArtifacts` response actions.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{watch, Notify, RwLock};
+use uuid::Uuid;
+
+#[cfg(feature = "napi")]
+use crate::access::AccessGuard;
+#[cfg(feature = "napi")]
+use napi::bindgen_prelude::Buffer;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+
+use crate::evidence_store::{EvidenceHashes, EvidenceStore};
+use crate::playbook_executor::ActionHandler;
+
+/// Largest memory region one command may grab
+pub const MAX_MEMORY_REGION: u64 = 512 * 1024 * 1024;
+/// Connector used for endpoints without one of their own
+pub const DEFAULT_CONNECTOR: &str = "*";
+const DEFAULT_TIMEOUT_SECS: u64 = 120;
+const MEMORY_TIMEOUT_SECS: u64 = 600;
+
+// Command Protocol
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveResponseCommand {
+    CollectFile { path: String },
+    ListProcesses,
+    DumpAutoruns,
+    /// `length` bytes at `address` in the memory of process `pid`
+    GrabMemory { pid: u32, address: u64, length: u64 },
+}
+
+impl LiveResponseCommand {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            LiveResponseCommand::CollectFile { path } if path.trim().is_empty() => Err("File path is required".to_string()),
+            LiveResponseCommand::GrabMemory { length, .. } if *length == 0 || *length > MAX_MEMORY_REGION => {
+                Err(format!("Memory region must be 1 to {} bytes", MAX_MEMORY_REGION))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LiveResponseCommand::CollectFile { .. } => "collect_file",
+            LiveResponseCommand::ListProcesses => "list_processes",
+            LiveResponseCommand::DumpAutoruns => "dump_autoruns",
+            LiveResponseCommand::GrabMemory { .. } => "grab_memory",
+        }
+    }
+
+    pub fn evidence_type(&self) -> &'static str {
+        match self {
+            LiveResponseCommand::CollectFile { .. } => "File",
+            LiveResponseCommand::ListProcesses => "ProcessList",
+            LiveResponseCommand::DumpAutoruns => "Autoruns",
+            LiveResponseCommand::GrabMemory { .. } => "MemoryDump",
+        }
+    }
+
+    pub fn default_timeout_secs(&self) -> u64 {
+        match self {
+            LiveResponseCommand::GrabMemory { .. } => MEMORY_TIMEOUT_SECS,
+            _ => DEFAULT_TIMEOUT_SECS,
+        }
+    }
+
+    /// Evidence file name when the agent does not name the artifact
+    fn artifact_name(&self, endpoint_id: &str) -> String {
+        match self {
+            LiveResponseCommand::CollectFile { path } => {
+                path.rsplit(['/', '\\']).find(|part| !part.is_empty()).unwrap_or(path).to_string()
+            }
+            LiveResponseCommand::ListProcesses => format!("processes-{}.json", endpoint_id),
+            LiveResponseCommand::DumpAutoruns => format!("autoruns-{}.json", endpoint_id),
+            LiveResponseCommand::GrabMemory { pid, address, length } => {
+                format!("memory-{}-{}-{:x}-{:x}.bin", endpoint_id, pid, address, length)
+            }
+        }
+    }
+}
+
+/// What an agent returns for a command
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AgentOutput {
+    /// Overrides the generated evidence file name
+    #[serde(default)]
+    pub artifact_name: Option<String>,
+    #[serde(skip)]
+    pub data: Vec<u8>,
+    /// SHA-256 the agent computed on the endpoint; checked on ingestion
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// Structured summary (process count, autorun entries, ...)
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+}
+
+/// Runs live-response commands on endpoints
+#[async_trait]
+pub trait AgentConnector: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Run `command` on `endpoint_id`. The future is dropped when the command
+    /// times out or is cancelled.
+    async fn execute(&self, endpoint_id: &str, command: &LiveResponseCommand) -> Result<AgentOutput, String>;
+}
+
+// Command State
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandStatus {
+    Queued,
+    Dispatched,
+    Completed,
+    Failed,
+    TimedOut,
+    Cancelled,
+}
+
+impl CommandStatus {
+    pub fn is_terminal(&self) -> bool {
+        !matches!(self, CommandStatus::Queued | CommandStatus::Dispatched)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandRecord {
+    pub command_id: String,
+    pub endpoint_id: String,
+    pub incident_id: String,
+    pub command: LiveResponseCommand,
+    pub status: CommandStatus,
+    pub requested_by: String,
+    pub timeout_secs: u64,
+    pub queued_at: DateTime<Utc>,
+    pub dispatched_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Connector that ran the command; `None` for pulling agents
+    pub connector: Option<String>,
+    pub error: Option<String>,
+    pub evidence_id: Option<String>,
+    pub hashes: Option<EvidenceHashes>,
+    pub size_bytes: Option<u64>,
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+}
+
+impl CommandRecord {
+    fn deadline(&self) -> Option<DateTime<Utc>> {
+        self.dispatched_at.map(|at| at + Duration::seconds(self.timeout_secs as i64))
+    }
+
+    fn finish(&mut self, status: CommandStatus, error: Option<String>) {
+        self.status = status;
+        self.error = error;
+        self.completed_at = Some(Utc::now());
+    }
+}
+
+// Live Response Service
+
+pub struct LiveResponse {
+    store: Arc<EvidenceStore>,
+    commands: RwLock<HashMap<String, CommandRecord>>,
+    /// Queued command ids per endpoint, oldest first
+    queues: RwLock<HashMap<String, VecDeque<String>>>,
+    connectors: RwLock<HashMap<String, Arc<dyn AgentConnector>>>,
+    /// Cancellation signals of commands a connector is running
+    cancels: Mutex<HashMap<String, watch::Sender<bool>>>,
+    /// Endpoints with a running worker
+    workers: Mutex<HashSet<String>>,
+    changed: Notify,
+}
+
+impl LiveResponse {
+    pub fn new(store: Arc<EvidenceStore>) -> Self {
+        Self {
+            store,
+            commands: RwLock::new(HashMap::new()),
+            queues: RwLock::new(HashMap::new()),
+            connectors: RwLock::new(HashMap::new()),
+            cancels: Mutex::new(HashMap::new()),
+            workers: Mutex::new(HashSet::new()),
+            changed: Notify::new(),
+        }
+    }
+
+    /// Serve `endpoint_id` (or every endpoint, with `DEFAULT_CONNECTOR`) through `connector`
+    pub async fn register_connector(self: &Arc<Self>, endpoint_id: &str, connector: Arc<dyn AgentConnector>) {
+        self.connectors.write().await.insert(endpoint_id.to_string(), connector);
+        let endpoints: Vec<String> = self.queues.read().await.keys().cloned().collect();
+        for endpoint in endpoints {
+            self.ensure_worker(&endpoint).await;
+        }
+    }
+
+    async fn connector_for(&self, endpoint_id: &str) -> Option<Arc<dyn AgentConnector>> {
+        let connectors = self.connectors.read().await;
+        connectors.get(endpoint_id).or_else(|| connectors.get(DEFAULT_CONNECTOR)).cloned()
+    }
+
+    /// Queue a command for an endpoint; runs once the commands ahead of it are done
+    pub async fn queue(
+        self: &Arc<Self>,
+        endpoint_id: &str,
+        incident_id: &str,
+        command: LiveResponseCommand,
+        timeout_secs: Option<u64>,
+        requested_by: &str,
+    ) -> Result<CommandRecord, String> {
+        if endpoint_id.trim().is_empty() || incident_id.trim().is_empty() {
+            return Err("Endpoint and incident are required".to_string());
+        }
+        command.validate()?;
+        let record = CommandRecord {
+            command_id: Uuid::new_v4().to_string(),
+            endpoint_id: endpoint_id.to_string(),
+            incident_id: incident_id.to_string(),
+            timeout_secs: timeout_secs.filter(|secs| *secs > 0).unwrap_or_else(|| command.default_timeout_secs()),
+            command,
+            status: CommandStatus::Queued,
+            requested_by: requested_by.to_string(),
+            queued_at: Utc::now(),
+            dispatched_at: None,
+            completed_at: None,
+            connector: None,
+            error: None,
+            evidence_id: None,
+            hashes: None,
+            size_bytes: None,
+            metadata: serde_json::Value::Null,
+        };
+        self.commands.write().await.insert(record.command_id.clone(), record.clone());
+        self.queues.write().await.entry(endpoint_id.to_string()).or_default().push_back(record.command_id.clone());
+        self.ensure_worker(endpoint_id).await;
+        Ok(record)
+    }
+
+    /// Pop the endpoint's next queued command and mark it dispatched
+    async fn dispatch_next(&self, endpoint_id: &str, connector: Option<&str>) -> Option<CommandRecord> {
+        let command_id = self.queues.write().await.get_mut(endpoint_id)?.pop_front()?;
+        let mut commands = self.commands.write().await;
+        let record = commands.get_mut(&command_id)?;
+        record.status = CommandStatus::Dispatched;
+        record.dispatched_at = Some(Utc::now());
+        record.connector = connector.map(str::to_string);
+        Some(record.clone())
+    }
+
+    /// Start a worker for an endpoint served by a connector, unless one runs
+    async fn ensure_worker(self: &Arc<Self>, endpoint_id: &str) {
+        let Some(connector) = self.connector_for(endpoint_id).await else {
+            return;
+        };
+        if !self.workers.lock().unwrap_or_else(|e| e.into_inner()).insert(endpoint_id.to_string()) {
+            return;
+        }
+        let this = self.clone();
+        let endpoint = endpoint_id.to_string();
+        tokio::spawn(async move {
+            loop {
+                while let Some(record) = this.dispatch_next(&endpoint, Some(connector.name())).await {
+                    this.run(connector.clone(), record).await;
+                }
+                this.workers.lock().unwrap_or_else(|e| e.into_inner()).remove(&endpoint);
+                // A command queued after the last pop but before the worker deregistered
+                let pending = this.queues.read().await.get(&endpoint).is_some_and(|queue| !queue.is_empty());
+                if !pending || !this.workers.lock().unwrap_or_else(|e| e.into_inner()).insert(endpoint.clone()) {
+                    break;
+                }
+            }
+        });
+    }
+
+    async fn run(&self, connector: Arc<dyn AgentConnector>, record: CommandRecord) {
+        let (cancel_tx, mut cancel_rx) = watch::channel(false);
+        self.cancels.lock().unwrap_or_else(|e| e.into_inner()).insert(record.command_id.clone(), cancel_tx);
+        let timeout = std::time::Duration::from_secs(record.timeout_secs);
+        let outcome = tokio::select! {
+            result = tokio::time::timeout(timeout, connector.execute(&record.endpoint_id, &record.command)) => match result {
+                Ok(Ok(output)) => Ok(output),
+                Ok(Err(e)) => Err((CommandStatus::Failed, e)),
+                Err(_) => Err((CommandStatus::TimedOut, format!("No result within {}s", record.timeout_secs))),
+            },
+            _ = cancel_rx.wait_for(|cancelled| *cancelled) => Err((CommandStatus::Cancelled, "Cancelled".to_string())),
+        };
+        self.cancels.lock().unwrap_or_else(|e| e.into_inner()).remove(&record.command_id);
+        let result = match outcome {
+            Ok(output) => self.complete(&record.command_id, output).await.map(|_| ()),
+            Err((status, error)) => self.close(&record.command_id, status, error).await,
+        };
+        if let Err(e) = result {
+            log::warn!("Live response command {}: {}", record.command_id, e);
+        }
+    }
+
+    /// End a command without output
+    async fn close(&self, command_id: &str, status: CommandStatus, error: String) -> Result<(), String> {
+        let mut commands = self.commands.write().await;
+        let record = commands.get_mut(command_id).ok_or_else(|| format!("Command {} not found", command_id))?;
+        if !record.status.is_terminal() {
+            record.finish(status, Some(error));
+        }
+        drop(commands);
+        self.changed.notify_waiters();
+        Ok(())
+    }
+
+    /// Time out dispatched commands whose agents missed their deadline
+    pub async fn expire_overdue(&self, now: DateTime<Utc>) -> usize {
+        let mut expired = 0;
+        for record in self.commands.write().await.values_mut() {
+            if record.status == CommandStatus::Dispatched && record.deadline().is_some_and(|deadline| deadline <= now) {
+                record.finish(CommandStatus::TimedOut, Some(format!("No result within {}s", record.timeout_secs)));
+                expired += 1;
+            }
+        }
+        if expired > 0 {
+            self.changed.notify_waiters();
+        }
+        expired
+    }
+
+    /// Commands for a pulling agent, up to `max`, marked dispatched.
+    /// Endpoints served by a connector get nothing.
+    pub async fn poll(&self, endpoint_id: &str, max: usize) -> Vec<CommandRecord> {
+        self.expire_overdue(Utc::now()).await;
+        if self.connector_for(endpoint_id).await.is_some() {
+            return Vec::new();
+        }
+        let mut dispatched = Vec::new();
+        while dispatched.len() < max {
+            match self.dispatch_next(endpoint_id, None).await {
+                Some(record) => dispatched.push(record),
+                None => break,
+            }
+        }
+        dispatched
+    }
+
+    /// Ingest a dispatched command's output as evidence
+    pub async fn complete(&self, command_id: &str, output: AgentOutput) -> Result<CommandRecord, String> {
+        self.expire_overdue(Utc::now()).await;
+        let record = self.get(command_id).await.ok_or_else(|| format!("Command {} not found", command_id))?;
+        if record.status != CommandStatus::Dispatched {
+            return Err(format!("Command {} is {:?}, not awaiting a result", command_id, record.status));
+        }
+
+        let actual = hex::encode(sha2::Sha256::digest(&output.data));
+        if let Some(reported) = output.sha256.as_deref().filter(|reported| !reported.eq_ignore_ascii_case(&actual)) {
+            let error = format!("Artifact sha256 {} does not match the {} the agent reported", actual, reported);
+            self.close(command_id, CommandStatus::Failed, error.clone()).await?;
+            return Err(error);
+        }
+
+        let file_name = output.artifact_name.clone().unwrap_or_else(|| record.command.artifact_name(&record.endpoint_id));
+        let collector = record.connector.clone().map_or_else(|| format!("agent:{}", record.endpoint_id), |name| format!("connector:{}", name));
+        let blob = self
+            .store
+            .ingest_bytes(&record.incident_id, &file_name, record.command.evidence_type(), &output.data, &collector)
+            .await?;
+        self.store
+            .append_custody(
+                &blob.evidence_id,
+                "LiveResponseCollected",
+                &record.requested_by,
+                format!("{} on endpoint {} (command {})", record.command.kind(), record.endpoint_id, record.command_id),
+            )
+            .await?;
+
+        let mut commands = self.commands.write().await;
+        let stored = commands.get_mut(command_id).ok_or_else(|| format!("Command {} not found", command_id))?;
+        // Cancelled or timed out while the output was being stored; the evidence stays
+        if stored.status != CommandStatus::Dispatched {
+            return Err(format!("Command {} was {:?} before its result was stored", command_id, stored.status));
+        }
+        stored.evidence_id = Some(blob.evidence_id.clone());
+        stored.size_bytes = Some(blob.size_bytes);
+        stored.hashes = Some(blob.hashes);
+        stored.metadata = output.metadata;
+        stored.finish(CommandStatus::Completed, None);
+        let completed = stored.clone();
+        drop(commands);
+        self.changed.notify_waiters();
+        Ok(completed)
+    }
+
+    /// Report that a pulling agent could not run a command
+    pub async fn fail(&self, command_id: &str, error: &str) -> Result<CommandRecord, String> {
+        let record = self.get(command_id).await.ok_or_else(|| format!("Command {} not found", command_id))?;
+        if record.status != CommandStatus::Dispatched {
+            return Err(format!("Command {} is {:?}, not awaiting a result", command_id, record.status));
+        }
+        self.close(command_id, CommandStatus::Failed, error.to_string()).await?;
+        self.get(command_id).await.ok_or_else(|| format!("Command {} not found", command_id))
+    }
+
+    /// Cancel a queued or in-flight command
+    pub async fn cancel(&self, command_id: &str) -> Result<CommandRecord, String> {
+        let record = self.get(command_id).await.ok_or_else(|| format!("Command {} not found", command_id))?;
+        match record.status {
+            CommandStatus::Queued => {
+                if let Some(queue) = self.queues.write().await.get_mut(&record.endpoint_id) {
+                    queue.retain(|id| id != command_id);
+                }
+                self.close(command_id, CommandStatus::Cancelled, "Cancelled".to_string()).await?;
+            }
+            CommandStatus::Dispatched => {
+                let signal = self.cancels.lock().unwrap_or_else(|e| e.into_inner()).get(command_id).cloned();
+                match signal {
+                    // The worker drops the connector call and records the cancellation
+                    Some(signal) => {
+                        let _ = signal.send(true);
+                        let changed = self.changed.notified();
+                        if !self.get(command_id).await.is_some_and(|r| r.status.is_terminal()) {
+                            let _ = tokio::time::timeout(std::time::Duration::from_secs(5), changed).await;
+                        }
+                    }
+                    None => self.close(command_id, CommandStatus::Cancelled, "Cancelled".to_string()).await?,
+                }
+            }
+            status => return Err(format!("Command {} is already {:?}", command_id, status)),
+        }
+        self.get(command_id).await.ok_or_else(|| format!("Command {} not found", command_id))
+    }
+
+    /// Wait until a command reaches a terminal state
+    pub async fn wait(&self, command_id: &str) -> Result<CommandRecord, String> {
+        loop {
+            let changed = self.changed.notified();
+            self.expire_overdue(Utc::now()).await;
+            let record = self.get(command_id).await.ok_or_else(|| format!("Command {} not found", command_id))?;
+            if record.status.is_terminal() {
+                return Ok(record);
+            }
+            // Pulling agents are only timed out when something looks, so look again by the deadline
+            let wake = record.deadline().map(|deadline| (deadline - Utc::now()).to_std().unwrap_or_default() + std::time::Duration::from_millis(10));
+            match wake {
+                Some(wake) => {
+                    let _ = tokio::time::timeout(wake, changed).await;
+                }
+                None => changed.await,
+            }
+        }
+    }
+
+    pub async fn get(&self, command_id: &str) -> Option<CommandRecord> {
+        self.commands.read().await.get(command_id).cloned()
+    }
+
+    pub async fn list(&self, endpoint_id: Option<&str>, incident_id: Option<&str>) -> Vec<CommandRecord> {
+        self.expire_overdue(Utc::now()).await;
+        let mut records: Vec<CommandRecord> = self
+            .commands
+            .read()
+            .await
+            .values()
+            .filter(|r| endpoint_id.is_none_or(|id| r.endpoint_id == id))
+            .filter(|r| incident_id.is_none_or(|id| r.incident_id == id))
+            .cloned()
+            .collect();
+        records.sort_by_key(|r| r.queued_at);
+        records
+    }
+}
+
+// Playbook Action
+
+/// `collect_artifacts` playbook action. Parameters: `endpoint_id`,
+/// `incident_id` (or the `incident_id` variable), and either `command` (a
+/// `LiveResponseCommand` as JSON), `path` for a file, or `artifact` set to
+/// `list_processes` / `dump_autoruns`; optional `timeout_secs`. Outputs
+/// `command_id`, `evidence_id` and `sha256`.
+pub struct CollectArtifactsAction {
+    live_response: Arc<LiveResponse>,
+}
+
+impl CollectArtifactsAction {
+    pub fn new(live_response: Arc<LiveResponse>) -> Self {
+        Self { live_response }
+    }
+}
+
+#[async_trait]
+impl ActionHandler for CollectArtifactsAction {
+    async fn execute(&self, parameters: &HashMap<String, String>, variables: &HashMap<String, String>) -> Result<HashMap<String, String>, String> {
+        let endpoint_id = parameters.get("endpoint_id").ok_or("endpoint_id is required")?;
+        let incident_id = parameters.get("incident_id").or_else(|| variables.get("incident_id")).ok_or("incident_id is required")?;
+        let command = match (parameters.get("command"), parameters.get("path"), parameters.get("artifact").map(String::as_str)) {
+            (Some(json), _, _) => serde_json::from_str(json).map_err(|e| format!("Invalid command: {}", e))?,
+            (None, Some(path), _) => LiveResponseCommand::CollectFile { path: path.clone() },
+            (None, None, Some("list_processes")) => LiveResponseCommand::ListProcesses,
+            (None, None, Some("dump_autoruns")) => LiveResponseCommand::DumpAutoruns,
+            _ => return Err("Set command, path or artifact".to_string()),
+        };
+        let timeout_secs = parameters.get("timeout_secs").and_then(|secs| secs.parse().ok());
+
+        let record = self.live_response.queue(endpoint_id, incident_id, command, timeout_secs, "playbook").await?;
+        // The step timeout drops this wait; the command itself must not outlive it
+        struct CancelOnDrop(Arc<LiveResponse>, Option<String>);
+        impl Drop for CancelOnDrop {
+            fn drop(&mut self) {
+                if let Some(command_id) = self.1.take() {
+                    let live_response = self.0.clone();
+                    tokio::spawn(async move {
+                        let _ = live_response.cancel(&command_id).await;
+                    });
+                }
+            }
+        }
+        let mut guard = CancelOnDrop(self.live_response.clone(), Some(record.command_id.clone()));
+        let done = self.live_response.wait(&record.command_id).await;
+        guard.1 = None;
+        let done = done?;
+        if done.status != CommandStatus::Completed {
+            return Err(format!("{} on {} {:?}: {}", done.command.kind(), done.endpoint_id, done.status, done.error.unwrap_or_default()));
+        }
+        Ok(HashMap::from([
+            ("command_id".to_string(), done.command_id),
+            ("evidence_id".to_string(), done.evidence_id.unwrap_or_default()),
+            ("sha256".to_string(), done.hashes.map(|h| h.sha256).unwrap_or_default()),
+        ]))
+    }
+}
+
+// NAPI Bindings
+
+/// NAPI wrapper around live response; output goes to the evidence store at `evidence_root`
+#[cfg(feature = "napi")]
+#[napi]
+pub struct LiveResponseNapi {
+    inner: Arc<LiveResponse>,
+    access: AccessGuard,
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl LiveResponseNapi {
+    #[napi(constructor)]
+    pub fn new(evidence_root: String) -> napi::Result<Self> {
+        let store = EvidenceStore::open(crate::evidence_store::EvidenceStoreConfig::new(evidence_root))
+            .map_err(|e| napi::Error::from_reason(format!("Failed to open evidence store: {}", e)))?;
+        Ok(LiveResponseNapi { inner: Arc::new(LiveResponse::new(Arc::new(store))), access: AccessGuard::default() })
+    }
+
+    /// Queue a command (`{"type": "collect_file", "path": ...}`) for an endpoint
+    #[napi]
+    pub async fn queue_live_response_command(
+        &self,
+        endpoint_id: String,
+        incident_id: String,
+        command_data: String,
+        timeout_secs: Option<u32>,
+        auth_token: Option<String>,
+    ) -> napi::Result<String> {
+        let actor = self.authorize(auth_token, "incident:write", &incident_id)?;
+        let command: LiveResponseCommand = serde_json::from_str(&command_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid command: {}", e)))?;
+        let record = self.inner.queue(&endpoint_id, &incident_id, command, timeout_secs.map(u64::from), &actor).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to queue command: {}", e)))?;
+        serde_json::to_string(&record)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Commands for a pulling agent to run, marked dispatched
+    #[napi]
+    pub async fn poll_live_response_commands(&self, endpoint_id: String, max: Option<u32>, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token, "incident:write", &endpoint_id)?;
+        let records = self.inner.poll(&endpoint_id, max.unwrap_or(1) as usize).await;
+        serde_json::to_string(&records)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Return a command's output; `result_data` is an `AgentOutput` without the data
+    #[napi]
+    pub async fn complete_live_response_command(
+        &self,
+        command_id: String,
+        data: Buffer,
+        result_data: Option<String>,
+        auth_token: Option<String>,
+    ) -> napi::Result<String> {
+        self.authorize(auth_token, "incident:write", &command_id)?;
+        let mut output: AgentOutput = match result_data {
+            Some(data) => serde_json::from_str(&data).map_err(|e| napi::Error::from_reason(format!("Invalid result: {}", e)))?,
+            None => AgentOutput::default(),
+        };
+        output.data = data.to_vec();
+        let record = self.inner.complete(&command_id, output).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to complete command: {}", e)))?;
+        serde_json::to_string(&record)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn fail_live_response_command(&self, command_id: String, error: String, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token, "incident:write", &command_id)?;
+        let record = self.inner.fail(&command_id, &error).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to fail command: {}", e)))?;
+        serde_json::to_string(&record)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn cancel_live_response_command(&self, command_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token, "incident:write", &command_id)?;
+        let record = self.inner.cancel(&command_id).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to cancel command: {}", e)))?;
+        serde_json::to_string(&record)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn get_live_response_command(&self, command_id: String) -> napi::Result<Option<String>> {
+        match self.inner.get(&command_id).await {
+            Some(record) => serde_json::to_string(&record)
+                .map(Some)
+                .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e))),
+            None => Ok(None),
+        }
+    }
+
+    #[napi]
+    pub async fn list_live_response_commands(&self, endpoint_id: Option<String>, incident_id: Option<String>) -> napi::Result<String> {
+        let records = self.inner.list(endpoint_id.as_deref(), incident_id.as_deref()).await;
+        serde_json::to_string(&records)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+}
+
+#[cfg(feature = "napi")]
+impl LiveResponseNapi {
+    fn authorize(&self, auth_token: Option<String>, permission: &str, resource: &str) -> napi::Result<String> {
+        self.access.check(auth_token.as_deref(), permission, resource)
+            .map_err(napi::Error::from_reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evidence_store::EvidenceStoreConfig;
+
+    fn temp_root() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("phantom-live-response-{}", Uuid::new_v4()))
+    }
+
+    struct HangingConnector;
+
+    #[async_trait]
+    impl AgentConnector for HangingConnector {
+        fn name(&self) -> &str {
+            "hanging"
+        }
+
+        async fn execute(&self, _endpoint_id: &str, command: &LiveResponseCommand) -> Result<AgentOutput, String> {
+            if *command == LiveResponseCommand::ListProcesses {
+                return Ok(AgentOutput { data: b"[{\"pid\":4}]".to_vec(), ..Default::default() });
+            }
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pulled_command_ingested_as_evidence() {
+        let root = temp_root();
+        let store = Arc::new(EvidenceStore::open(EvidenceStoreConfig::new(&root)).unwrap());
+        let live = Arc::new(LiveResponse::new(store.clone()));
+
+        let file = LiveResponseCommand::CollectFile { path: "C:\\Windows\\Temp\\drop.exe".to_string() };
+        let queued = live.queue("host-1", "INC-1", file, None, "responder").await.unwrap();
+        let memory = LiveResponseCommand::GrabMemory { pid: 4, address: 0x1000, length: 0 };
+        assert!(live.queue("host-1", "INC-1", memory, None, "responder").await.is_err());
+
+        let polled = live.poll("host-1", 5).await;
+        assert_eq!(polled.len(), 1);
+        assert_eq!(polled[0].status, CommandStatus::Dispatched);
+
+        let data = b"MZ payload";
+        let wrong = AgentOutput { data: data.to_vec(), sha256: Some("00".repeat(32)), ..Default::default() };
+        assert!(live.complete(&queued.command_id, wrong).await.is_err());
+        assert_eq!(live.get(&queued.command_id).await.unwrap().status, CommandStatus::Failed);
+
+        let queued = live.queue("host-1", "INC-1", LiveResponseCommand::DumpAutoruns, None, "responder").await.unwrap();
+        live.poll("host-1", 1).await;
+        let output = AgentOutput { data: data.to_vec(), sha256: Some(hex::encode(sha2::Sha256::digest(data))), ..Default::default() };
+        let done = live.complete(&queued.command_id, output).await.unwrap();
+        assert_eq!(done.status, CommandStatus::Completed);
+        let blob = store.get(done.evidence_id.as_deref().unwrap()).await.unwrap();
+        assert_eq!(blob.incident_id, "INC-1");
+        assert_eq!(blob.evidence_type, "Autoruns");
+        assert_eq!(blob.chain_of_custody.last().unwrap().action, "LiveResponseCollected");
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+
+    #[tokio::test]
+    async fn test_connector_timeout_cancel_and_playbook_action() {
+        let root = temp_root();
+        let store = Arc::new(EvidenceStore::open(EvidenceStoreConfig::new(&root)).unwrap());
+        let live = Arc::new(LiveResponse::new(store));
+        live.register_connector(DEFAULT_CONNECTOR, Arc::new(HangingConnector)).await;
+
+        let slow = LiveResponseCommand::CollectFile { path: "/etc/shadow".to_string() };
+        let timed = live.queue("host-2", "INC-2", slow.clone(), Some(1), "responder").await.unwrap();
+        let cancelled = live.queue("host-2", "INC-2", slow, None, "responder").await.unwrap();
+        assert_eq!(live.wait(&timed.command_id).await.unwrap().status, CommandStatus::TimedOut);
+        while live.get(&cancelled.command_id).await.unwrap().status != CommandStatus::Dispatched {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(live.cancel(&cancelled.command_id).await.unwrap().status, CommandStatus::Cancelled);
+
+        let action = CollectArtifactsAction::new(live.clone());
+        let parameters = HashMap::from([
+            ("endpoint_id".to_string(), "host-2".to_string()),
+            ("artifact".to_string(), "list_processes".to_string()),
+        ]);
+        let variables = HashMap::from([("incident_id".to_string(), "INC-2".to_string())]);
+        let outputs = action.execute(&parameters, &variables).await.unwrap();
+        assert_eq!(outputs["sha256"].len(), 64);
+        assert_eq!(live.list(Some("host-2"), None).await.len(), 3);
+
+        let _ = std::fs::remove_dir_all(root);
+    }
+}
@@ -0,0 +1,1198 @@
+//! Incident Communications
+//!
+//! Sends incident updates to people over notification channels: SMTP email,
+//! Slack and Microsoft Teams incoming webhooks, and generic JSON webhooks.
+//! Messages come from templates keyed by event type and (optionally)
+//! severity, and go to the members of the template's and the channel's
+//! recipient groups. Every send is a `CommunicationRecord` tracked as a
+//! delivery; failed deliveries are retried with exponential backoff from
+//! `retry_due`, which, like the escalation engine, is clock-driven.
+//!
+//! Templates use handlebars syntax: `{{incident.title}}`, `{{#if ...}}`,
+//! `{{#unless ...}}`, `{{#each ...}}` with `{{this}}` and `{{@index}}`,
+//! `{{else}}` and `{{! comments }}`. Output is plain text, so `{{x}}` and
+//! `{{{x}}}` render the same; channel payloads are escaped when encoded.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+#[cfg(feature = "napi")]
+use crate::access::AccessGuard;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+
+use crate::escalation::EscalationNotifier;
+use crate::{CommunicationRecord, IncidentRecord};
+
+const SEND_TIMEOUT_SECS: u64 = 30;
+
+// Channels and recipients
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChannelKind {
+    Email {
+        smtp_host: String,
+        #[serde(default = "default_smtp_port")]
+        smtp_port: u16,
+        from: String,
+        #[serde(default)]
+        username: Option<String>,
+        #[serde(default)]
+        password: Option<String>,
+    },
+    Slack { webhook_url: String },
+    Teams { webhook_url: String },
+    /// JSON POST of the message, signed with HMAC-SHA256 when `secret` is set
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        #[serde(default)]
+        secret: Option<String>,
+    },
+}
+
+fn default_smtp_port() -> u16 {
+    25
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationChannel {
+    pub channel_id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: ChannelKind,
+    /// Groups addressed on every message sent through this channel
+    #[serde(default)]
+    pub recipient_groups: Vec<String>,
+}
+
+impl NotificationChannel {
+    pub fn validate(&self) -> Result<(), String> {
+        let url = match &self.kind {
+            ChannelKind::Email { smtp_host, from, username, password, .. } => {
+                if smtp_host.trim().is_empty() {
+                    return Err(format!("Channel {} has no SMTP host", self.channel_id));
+                }
+                validate_address(from)?;
+                if username.is_some() != password.is_some() {
+                    return Err(format!("Channel {} needs both an SMTP username and password", self.channel_id));
+                }
+                return Ok(());
+            }
+            ChannelKind::Slack { webhook_url } | ChannelKind::Teams { webhook_url } => webhook_url,
+            ChannelKind::Webhook { url, .. } => url,
+        };
+        let parsed = url::Url::parse(url).map_err(|e| format!("Channel {} has an invalid URL: {}", self.channel_id, e))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(format!("Channel {} URL must be http or https", self.channel_id));
+        }
+        Ok(())
+    }
+
+    pub fn kind_name(&self) -> &'static str {
+        match self.kind {
+            ChannelKind::Email { .. } => "email",
+            ChannelKind::Slack { .. } => "slack",
+            ChannelKind::Teams { .. } => "teams",
+            ChannelKind::Webhook { .. } => "webhook",
+        }
+    }
+
+    /// Copy with credentials and webhook secrets masked, for listing
+    pub fn redacted(&self) -> Self {
+        let mut channel = self.clone();
+        match &mut channel.kind {
+            ChannelKind::Email { password, .. } => *password = password.as_ref().map(|_| "***".to_string()),
+            ChannelKind::Slack { webhook_url } | ChannelKind::Teams { webhook_url } => *webhook_url = redact_url(webhook_url),
+            ChannelKind::Webhook { secret, headers, .. } => {
+                *secret = secret.as_ref().map(|_| "***".to_string());
+                headers.values_mut().for_each(|value| *value = "***".to_string());
+            }
+        }
+        channel
+    }
+}
+
+/// Slack and Teams webhook URLs are bearer credentials; keep only the host
+fn redact_url(url: &str) -> String {
+    url::Url::parse(url)
+        .map(|parsed| format!("{}://{}/***", parsed.scheme(), parsed.host_str().unwrap_or_default()))
+        .unwrap_or_else(|_| "***".to_string())
+}
+
+fn validate_address(address: &str) -> Result<(), String> {
+    let valid = address.split_once('@').is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'))
+        && !address.chars().any(|c| c.is_whitespace() || c.is_control() || matches!(c, '<' | '>' | ',' | ';'));
+    if valid { Ok(()) } else { Err(format!("Invalid email address {}", address)) }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecipientGroup {
+    pub group_id: String,
+    pub name: String,
+    /// Email addresses, Slack/Teams handles or webhook-defined identifiers
+    pub members: Vec<String>,
+}
+
+// Templates
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageTemplate {
+    pub template_id: String,
+    /// e.g. `status_update`, `containment`, `resolved`
+    pub event_type: String,
+    /// Severity this template is for; `None` applies to any severity
+    #[serde(default)]
+    pub severity: Option<String>,
+    pub subject: String,
+    pub body: String,
+    #[serde(default)]
+    pub recipient_groups: Vec<String>,
+}
+
+impl MessageTemplate {
+    pub fn validate(&self) -> Result<(), String> {
+        for source in [&self.subject, &self.body] {
+            Template::compile(source).map_err(|e| format!("Template {}: {}", self.template_id, e))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Token {
+    Text(String),
+    Tag(String),
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Text(String),
+    Var(String),
+    If { path: String, then: Vec<Node>, otherwise: Vec<Node> },
+    Each { path: String, body: Vec<Node>, otherwise: Vec<Node> },
+}
+
+/// A compiled handlebars-syntax template
+#[derive(Debug, Clone)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    pub fn compile(source: &str) -> Result<Self, String> {
+        let mut tokens = tokenize(source)?.into_iter();
+        match parse_block(&mut tokens)? {
+            (nodes, None) => Ok(Self { nodes }),
+            (_, Some(tag)) => Err(format!("Unexpected {{{{{}}}}}", tag)),
+        }
+    }
+
+    pub fn render(&self, context: &Value) -> String {
+        let mut out = String::new();
+        render_nodes(&self.nodes, &mut vec![(context.clone(), None)], &mut out);
+        out
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            tokens.push(Token::Text(rest[..start].to_string()));
+        }
+        let (open, close) = if rest[start..].starts_with("{{{") { (3, "}}}") } else { (2, "}}") };
+        let inner = &rest[start + open..];
+        let end = inner.find(close).ok_or_else(|| "Unclosed {{ tag".to_string())?;
+        tokens.push(Token::Tag(inner[..end].trim().to_string()));
+        rest = &inner[end + close.len()..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest.to_string()));
+    }
+    Ok(tokens)
+}
+
+/// Parse nodes up to an `{{else}}` or closing tag, which is returned
+fn parse_block(tokens: &mut impl Iterator<Item = Token>) -> Result<(Vec<Node>, Option<String>), String> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.next() {
+        let tag = match token {
+            Token::Text(text) => {
+                nodes.push(Node::Text(text));
+                continue;
+            }
+            Token::Tag(tag) => tag,
+        };
+        if tag.starts_with('!') {
+            continue;
+        }
+        if tag == "else" || tag.starts_with('/') {
+            return Ok((nodes, Some(tag)));
+        }
+        let Some(block) = tag.strip_prefix('#') else {
+            nodes.push(Node::Var(tag));
+            continue;
+        };
+        let (helper, path) = block
+            .split_once(char::is_whitespace)
+            .map(|(helper, path)| (helper, path.trim().to_string()))
+            .ok_or_else(|| format!("Block #{} needs an argument", block))?;
+        let (body, end) = parse_block(tokens)?;
+        let (otherwise, end) = match end.as_deref() {
+            Some("else") => parse_block(tokens)?,
+            _ => (Vec::new(), end),
+        };
+        if end.as_deref() != Some(format!("/{}", helper).as_str()) {
+            return Err(format!("Block #{} is not closed", helper));
+        }
+        nodes.push(match helper {
+            "if" => Node::If { path, then: body, otherwise },
+            "unless" => Node::If { path, then: otherwise, otherwise: body },
+            "each" => Node::Each { path, body, otherwise },
+            other => return Err(format!("Unknown block helper #{}", other)),
+        });
+    }
+    Ok((nodes, None))
+}
+
+type Scope = (Value, Option<usize>);
+
+fn render_nodes(nodes: &[Node], scopes: &mut Vec<Scope>, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var(path) => out.push_str(&display(&lookup(scopes, path))),
+            Node::If { path, then, otherwise } => {
+                let branch = if truthy(&lookup(scopes, path)) { then } else { otherwise };
+                render_nodes(branch, scopes, out);
+            }
+            Node::Each { path, body, otherwise } => {
+                let items = match lookup(scopes, path) {
+                    Value::Array(items) => items,
+                    Value::Object(map) => map.into_iter().map(|(_, value)| value).collect(),
+                    _ => Vec::new(),
+                };
+                if items.is_empty() {
+                    render_nodes(otherwise, scopes, out);
+                }
+                for (index, item) in items.into_iter().enumerate() {
+                    scopes.push((item, Some(index)));
+                    render_nodes(body, scopes, out);
+                    scopes.pop();
+                }
+            }
+        }
+    }
+}
+
+/// Resolve a dotted path against the innermost scope that has it
+fn lookup(scopes: &[Scope], path: &str) -> Value {
+    let Some((current, index)) = scopes.last() else {
+        return Value::Null;
+    };
+    match path {
+        "this" | "." => return current.clone(),
+        "@index" => return index.map_or(Value::Null, Value::from),
+        _ => {}
+    }
+    let path = path.strip_prefix("this.").unwrap_or(path);
+    scopes
+        .iter()
+        .rev()
+        .find_map(|(scope, _)| {
+            path.split('.').try_fold(scope, |value, key| match value {
+                Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+                _ => value.get(key),
+            })
+        })
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(flag) => *flag,
+        Value::Number(number) => number.as_f64().is_some_and(|n| n != 0.0),
+        Value::String(text) => !text.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items.iter().map(display).collect::<Vec<_>>().join(", "),
+        other => other.to_string(),
+    }
+}
+
+// Delivery
+
+/// A rendered message ready for a channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboundMessage {
+    pub communication_id: String,
+    pub incident_id: String,
+    pub event_type: String,
+    pub severity: String,
+    pub subject: String,
+    pub body: String,
+    pub recipients: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    /// Failed, with another attempt scheduled at `next_attempt_at`
+    Retrying,
+    Failed,
+}
+
+impl DeliveryStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Delivered => "delivered",
+            Self::Retrying => "retrying",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Delivery {
+    pub record: CommunicationRecord,
+    pub channel_id: String,
+    pub template_id: Option<String>,
+    pub subject: String,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub delivered_at: Option<DateTime<Utc>>,
+}
+
+impl Delivery {
+    fn message(&self, severity: &str) -> OutboundMessage {
+        OutboundMessage {
+            communication_id: self.record.communication_id.clone(),
+            incident_id: self.record.incident_id.clone(),
+            event_type: self.record.communication_type.clone(),
+            severity: severity.to_string(),
+            subject: self.subject.clone(),
+            body: self.record.message.clone(),
+            recipients: self.record.recipients.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Attempts per delivery, including the first
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubles on each further retry
+    pub backoff_secs: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_attempts: 4, backoff_secs: 30 }
+    }
+}
+
+/// Puts a message on the wire for a channel
+#[async_trait]
+pub trait ChannelSender: Send + Sync {
+    async fn send(&self, channel: &NotificationChannel, message: &OutboundMessage) -> Result<(), String>;
+}
+
+/// Delivers over SMTP and, with the `reqwest` feature, HTTP webhooks
+#[derive(Default)]
+pub struct LiveChannelSender;
+
+#[async_trait]
+impl ChannelSender for LiveChannelSender {
+    async fn send(&self, channel: &NotificationChannel, message: &OutboundMessage) -> Result<(), String> {
+        let timeout = std::time::Duration::from_secs(SEND_TIMEOUT_SECS);
+        match &channel.kind {
+            ChannelKind::Email { smtp_host, smtp_port, from, username, password } => {
+                let credentials = username.as_deref().zip(password.as_deref());
+                tokio::time::timeout(timeout, send_email(smtp_host, *smtp_port, from, credentials, message))
+                    .await
+                    .map_err(|_| format!("SMTP delivery to {} timed out", smtp_host))?
+            }
+            _ => {
+                post_webhook(webhook_request(channel, message)?, timeout).await
+            }
+        }
+    }
+}
+
+/// An HTTP POST to a Slack, Teams or generic webhook
+#[derive(Debug, Clone)]
+pub struct WebhookRequest {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+pub fn webhook_request(channel: &NotificationChannel, message: &OutboundMessage) -> Result<WebhookRequest, String> {
+    let mut headers = vec![("Content-Type".to_string(), "application/json".to_string())];
+    let (url, payload) = match &channel.kind {
+        ChannelKind::Email { .. } => return Err("Email channels are not webhooks".to_string()),
+        ChannelKind::Slack { webhook_url } => (
+            webhook_url,
+            serde_json::json!({ "text": format!("*{}*\n{}", message.subject, message.body) }),
+        ),
+        ChannelKind::Teams { webhook_url } => (
+            webhook_url,
+            serde_json::json!({
+                "@type": "MessageCard",
+                "@context": "https://schema.org/extensions",
+                "summary": message.subject,
+                "title": message.subject,
+                "themeColor": theme_color(&message.severity),
+                "text": message.body.replace('\n', "  \n"),
+            }),
+        ),
+        ChannelKind::Webhook { url, headers: extra, secret } => {
+            let payload = serde_json::to_value(message).map_err(|e| e.to_string())?;
+            headers.extend(extra.iter().map(|(name, value)| (name.clone(), value.clone())));
+            if let Some(secret) = secret {
+                let body = serde_json::to_vec(&payload).map_err(|e| e.to_string())?;
+                headers.push(("X-Phantom-Signature".to_string(), format!("sha256={}", sign(secret, &body))));
+                return Ok(WebhookRequest { url: url.clone(), headers, body });
+            }
+            (url, payload)
+        }
+    };
+    let body = serde_json::to_vec(&payload).map_err(|e| e.to_string())?;
+    Ok(WebhookRequest { url: url.clone(), headers, body })
+}
+
+fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn theme_color(severity: &str) -> &'static str {
+    match severity.to_ascii_lowercase().as_str() {
+        "critical" => "8B0000",
+        "high" => "D9534F",
+        "medium" => "F0AD4E",
+        _ => "5BC0DE",
+    }
+}
+
+#[cfg(feature = "reqwest")]
+async fn post_webhook(request: WebhookRequest, timeout: std::time::Duration) -> Result<(), String> {
+    let client = reqwest::Client::builder().timeout(timeout).build().map_err(|e| e.to_string())?;
+    let mut outgoing = client.post(&request.url).body(request.body);
+    for (name, value) in &request.headers {
+        outgoing = outgoing.header(name.as_str(), value.as_str());
+    }
+    let response = outgoing.send().await.map_err(|e| e.to_string())?;
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("HTTP {} from webhook", response.status().as_u16()))
+    }
+}
+
+#[cfg(not(feature = "reqwest"))]
+async fn post_webhook(_request: WebhookRequest, _timeout: std::time::Duration) -> Result<(), String> {
+    Err("Webhook channels require the `reqwest` feature".to_string())
+}
+
+/// Send `message` through a plaintext SMTP relay
+pub async fn send_email(host: &str, port: u16, from: &str, credentials: Option<(&str, &str)>, message: &OutboundMessage) -> Result<(), String> {
+    use base64::Engine as _;
+
+    if message.recipients.is_empty() {
+        return Err("Email has no recipients".to_string());
+    }
+    for recipient in &message.recipients {
+        validate_address(recipient)?;
+    }
+    let stream = tokio::net::TcpStream::connect((host, port)).await.map_err(|e| format!("Cannot reach {}:{}: {}", host, port, e))?;
+    let (read, mut write) = stream.into_split();
+    let mut reader = BufReader::new(read);
+
+    smtp_expect(&mut reader, 220).await?;
+    smtp_command(&mut reader, &mut write, "EHLO phantom-spire", 250).await?;
+    if let Some((username, password)) = credentials {
+        let token = base64::engine::general_purpose::STANDARD.encode(format!("\0{}\0{}", username, password));
+        smtp_command(&mut reader, &mut write, &format!("AUTH PLAIN {}", token), 235).await?;
+    }
+    smtp_command(&mut reader, &mut write, &format!("MAIL FROM:<{}>", from), 250).await?;
+    for recipient in &message.recipients {
+        smtp_command(&mut reader, &mut write, &format!("RCPT TO:<{}>", recipient), 250).await?;
+    }
+    smtp_command(&mut reader, &mut write, "DATA", 354).await?;
+    write.write_all(email_content(from, message).as_bytes()).await.map_err(|e| e.to_string())?;
+    smtp_command(&mut reader, &mut write, ".", 250).await?;
+    // The message is accepted at this point; a failed QUIT does not matter
+    let _ = write.write_all(b"QUIT\r\n").await;
+    Ok(())
+}
+
+/// RFC 5322 message with CRLF line endings and dot-stuffed body
+fn email_content(from: &str, message: &OutboundMessage) -> String {
+    let single_line = |text: &str| text.replace(['\r', '\n'], " ");
+    let mut content = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMessage-ID: <{}@phantom-spire>\r\nX-Phantom-Incident: {}\r\nMIME-Version: 1.0\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: 8bit\r\n\r\n",
+        from,
+        message.recipients.join(", "),
+        single_line(&message.subject),
+        Utc::now().to_rfc2822(),
+        message.communication_id,
+        single_line(&message.incident_id),
+    );
+    for line in message.body.lines() {
+        if line.starts_with('.') {
+            content.push('.');
+        }
+        content.push_str(line);
+        content.push_str("\r\n");
+    }
+    content
+}
+
+async fn smtp_command(
+    reader: &mut BufReader<tokio::net::tcp::OwnedReadHalf>,
+    write: &mut tokio::net::tcp::OwnedWriteHalf,
+    command: &str,
+    expected: u16,
+) -> Result<(), String> {
+    write.write_all(format!("{}\r\n", command).as_bytes()).await.map_err(|e| e.to_string())?;
+    // Only the verb is reported, so AUTH credentials stay out of errors
+    let verb = command.split_whitespace().next().unwrap_or(command);
+    smtp_expect(reader, expected).await.map_err(|e| format!("{} rejected: {}", verb, e))
+}
+
+/// Read a (possibly multi-line) reply and check its code
+async fn smtp_expect(reader: &mut BufReader<tokio::net::tcp::OwnedReadHalf>, expected: u16) -> Result<(), String> {
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await.map_err(|e| e.to_string())? == 0 {
+            return Err("SMTP server closed the connection".to_string());
+        }
+        let code: u16 = line.get(..3).and_then(|code| code.parse().ok()).ok_or_else(|| format!("Malformed SMTP reply {:?}", line.trim_end()))?;
+        if line.as_bytes().get(3) == Some(&b'-') {
+            continue;
+        }
+        return if code == expected { Ok(()) } else { Err(line.trim_end().to_string()) };
+    }
+}
+
+// Notification center
+
+#[derive(Default)]
+struct NotificationState {
+    channels: HashMap<String, NotificationChannel>,
+    groups: HashMap<String, RecipientGroup>,
+    templates: HashMap<String, MessageTemplate>,
+    incidents: HashMap<String, IncidentRecord>,
+    deliveries: HashMap<String, Delivery>,
+    retry: RetryPolicy,
+}
+
+impl NotificationState {
+    /// A template by id, else the best match for an event type: one for the
+    /// incident's severity before one for any severity
+    fn template(&self, template: &str, severity: &str) -> Option<&MessageTemplate> {
+        self.templates.get(template).or_else(|| {
+            self.templates
+                .values()
+                .filter(|t| t.event_type == template)
+                .filter(|t| t.severity.as_deref().is_none_or(|s| s.eq_ignore_ascii_case(severity)))
+                .max_by_key(|t| (t.severity.is_some(), t.template_id.clone()))
+        })
+    }
+
+    /// Members of the groups, de-duplicated in order
+    fn recipients<'a>(&self, groups: impl Iterator<Item = &'a String>) -> Result<Vec<String>, String> {
+        let mut recipients: Vec<String> = Vec::new();
+        for group_id in groups {
+            let group = self.groups.get(group_id).ok_or_else(|| format!("Recipient group {} not found", group_id))?;
+            for member in &group.members {
+                if !recipients.contains(member) {
+                    recipients.push(member.clone());
+                }
+            }
+        }
+        Ok(recipients)
+    }
+
+    fn severity_of(&self, incident_id: &str) -> String {
+        self.incidents.get(incident_id).map(|incident| incident.severity.clone()).unwrap_or_default()
+    }
+}
+
+/// Templated incident notifications with delivery tracking
+pub struct NotificationCenter {
+    state: Arc<RwLock<NotificationState>>,
+    sender: Arc<RwLock<Arc<dyn ChannelSender>>>,
+}
+
+impl Default for NotificationCenter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NotificationCenter {
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(RwLock::new(NotificationState::default())),
+            sender: Arc::new(RwLock::new(Arc::new(LiveChannelSender))),
+        }
+    }
+
+    /// Deliver through `sender` instead of SMTP and HTTP
+    pub async fn set_sender(&self, sender: Arc<dyn ChannelSender>) {
+        *self.sender.write().await = sender;
+    }
+
+    pub async fn register_channel(&self, channel: NotificationChannel) -> Result<(), String> {
+        channel.validate()?;
+        self.state.write().await.channels.insert(channel.channel_id.clone(), channel);
+        Ok(())
+    }
+
+    pub async fn list_channels(&self) -> Vec<NotificationChannel> {
+        let mut channels: Vec<_> = self.state.read().await.channels.values().map(NotificationChannel::redacted).collect();
+        channels.sort_by(|a, b| a.channel_id.cmp(&b.channel_id));
+        channels
+    }
+
+    pub async fn register_group(&self, group: RecipientGroup) -> Result<(), String> {
+        if group.members.is_empty() {
+            return Err(format!("Recipient group {} has no members", group.group_id));
+        }
+        self.state.write().await.groups.insert(group.group_id.clone(), group);
+        Ok(())
+    }
+
+    pub async fn register_template(&self, template: MessageTemplate) -> Result<(), String> {
+        template.validate()?;
+        self.state.write().await.templates.insert(template.template_id.clone(), template);
+        Ok(())
+    }
+
+    pub async fn list_templates(&self) -> Vec<MessageTemplate> {
+        let mut templates: Vec<_> = self.state.read().await.templates.values().cloned().collect();
+        templates.sort_by(|a, b| a.template_id.cmp(&b.template_id));
+        templates
+    }
+
+    pub async fn set_retry_policy(&self, policy: RetryPolicy) -> Result<(), String> {
+        if policy.max_attempts == 0 {
+            return Err("max_attempts must be at least 1".to_string());
+        }
+        self.state.write().await.retry = policy;
+        Ok(())
+    }
+
+    /// Record the latest state of an incident for use in messages
+    pub async fn track_incident(&self, incident: IncidentRecord) {
+        self.state.write().await.incidents.insert(incident.incident_id.clone(), incident);
+    }
+
+    /// Render `template` (a template id, or an event type resolved against
+    /// the incident's severity) for an incident and send it through `channel_id`
+    pub async fn send_incident_update(&self, incident_id: &str, template: &str, channel_id: &str, extra: Option<Value>, now: DateTime<Utc>) -> Result<Delivery, String> {
+        let delivery = {
+            let state = self.state.read().await;
+            let incident = state.incidents.get(incident_id).ok_or_else(|| format!("Incident {} not found", incident_id))?;
+            let template = state
+                .template(template, &incident.severity)
+                .ok_or_else(|| format!("No template {} for {} incidents", template, incident.severity))?;
+            let channel = state.channels.get(channel_id).ok_or_else(|| format!("Channel {} not found", channel_id))?;
+
+            let mut context = match extra {
+                Some(Value::Object(map)) => map,
+                Some(_) => return Err("Message context must be a JSON object".to_string()),
+                None => serde_json::Map::new(),
+            };
+            context.insert("incident".to_string(), serde_json::to_value(incident).map_err(|e| e.to_string())?);
+            context.insert("event_type".to_string(), Value::from(template.event_type.clone()));
+            context.insert("channel".to_string(), Value::from(channel.name.clone()));
+            context.insert("now".to_string(), Value::from(now.to_rfc3339()));
+            let context = Value::Object(context);
+
+            let recipients = state.recipients(template.recipient_groups.iter().chain(&channel.recipient_groups))?;
+            if recipients.is_empty() && matches!(channel.kind, ChannelKind::Email { .. }) {
+                return Err(format!("Template {} and channel {} name no recipients", template.template_id, channel_id));
+            }
+            Delivery {
+                record: CommunicationRecord {
+                    communication_id: Uuid::new_v4().to_string(),
+                    incident_id: incident_id.to_string(),
+                    communication_type: template.event_type.clone(),
+                    recipients,
+                    message: Template::compile(&template.body)?.render(&context),
+                    sent_at: now,
+                    delivery_status: DeliveryStatus::Pending.as_str().to_string(),
+                    escalation_level: "none".to_string(),
+                },
+                channel_id: channel_id.to_string(),
+                template_id: Some(template.template_id.clone()),
+                subject: Template::compile(&template.subject)?.render(&context).replace(['\r', '\n'], " "),
+                status: DeliveryStatus::Pending,
+                attempts: 0,
+                last_error: None,
+                next_attempt_at: None,
+                delivered_at: None,
+            }
+        };
+        Ok(self.dispatch(delivery, now).await)
+    }
+
+    /// Send an existing record (e.g. an escalation page) through a channel
+    pub async fn send_record(&self, record: CommunicationRecord, channel_id: &str, now: DateTime<Utc>) -> Result<Delivery, String> {
+        if !self.state.read().await.channels.contains_key(channel_id) {
+            return Err(format!("Channel {} not found", channel_id));
+        }
+        let subject = format!("Incident {}: {}", record.incident_id, record.communication_type.replace('_', " "));
+        let delivery = Delivery {
+            record: CommunicationRecord { delivery_status: DeliveryStatus::Pending.as_str().to_string(), ..record },
+            channel_id: channel_id.to_string(),
+            template_id: None,
+            subject,
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            last_error: None,
+            next_attempt_at: None,
+            delivered_at: None,
+        };
+        Ok(self.dispatch(delivery, now).await)
+    }
+
+    async fn dispatch(&self, delivery: Delivery, now: DateTime<Utc>) -> Delivery {
+        let id = delivery.record.communication_id.clone();
+        self.state.write().await.deliveries.insert(id.clone(), delivery);
+        self.attempt(&id, now).await.expect("delivery was just stored")
+    }
+
+    /// Make one delivery attempt and schedule a retry if it fails
+    async fn attempt(&self, communication_id: &str, now: DateTime<Utc>) -> Option<Delivery> {
+        let (channel, message) = {
+            let state = self.state.read().await;
+            let delivery = state.deliveries.get(communication_id)?;
+            let severity = state.severity_of(&delivery.record.incident_id);
+            (state.channels.get(&delivery.channel_id).cloned(), delivery.message(&severity))
+        };
+        let outcome = match &channel {
+            Some(channel) => self.sender.read().await.clone().send(channel, &message).await,
+            None => Err("Channel was removed".to_string()),
+        };
+
+        let mut state = self.state.write().await;
+        let retry = state.retry.clone();
+        let delivery = state.deliveries.get_mut(communication_id)?;
+        delivery.attempts += 1;
+        match outcome {
+            Ok(()) => {
+                delivery.status = DeliveryStatus::Delivered;
+                delivery.delivered_at = Some(now);
+                delivery.next_attempt_at = None;
+            }
+            Err(error) => {
+                log::warn!("Delivery {} failed on attempt {}: {}", communication_id, delivery.attempts, error);
+                delivery.last_error = Some(error);
+                if delivery.attempts < retry.max_attempts && channel.is_some() {
+                    let backoff = i64::from(retry.backoff_secs) << (delivery.attempts - 1).min(16);
+                    delivery.status = DeliveryStatus::Retrying;
+                    delivery.next_attempt_at = Some(now + Duration::seconds(backoff));
+                } else {
+                    delivery.status = DeliveryStatus::Failed;
+                    delivery.next_attempt_at = None;
+                }
+            }
+        }
+        delivery.record.delivery_status = delivery.status.as_str().to_string();
+        Some(delivery.clone())
+    }
+
+    /// Retry every delivery whose backoff has elapsed
+    pub async fn retry_due(&self, now: DateTime<Utc>) -> Vec<Delivery> {
+        let due: Vec<String> = self
+            .state
+            .read()
+            .await
+            .deliveries
+            .values()
+            .filter(|d| d.status == DeliveryStatus::Retrying && d.next_attempt_at.is_some_and(|at| at <= now))
+            .map(|d| d.record.communication_id.clone())
+            .collect();
+        let mut retried = Vec::with_capacity(due.len());
+        for id in due {
+            retried.extend(self.attempt(&id, now).await);
+        }
+        retried
+    }
+
+    pub async fn get_delivery(&self, communication_id: &str) -> Option<Delivery> {
+        self.state.read().await.deliveries.get(communication_id).cloned()
+    }
+
+    pub async fn list_deliveries(&self, incident_id: Option<&str>) -> Vec<Delivery> {
+        let mut deliveries: Vec<_> = self
+            .state
+            .read()
+            .await
+            .deliveries
+            .values()
+            .filter(|d| incident_id.is_none_or(|id| d.record.incident_id == id))
+            .cloned()
+            .collect();
+        deliveries.sort_by_key(|d| d.record.sent_at);
+        deliveries
+    }
+}
+
+/// Routes escalation pages through a notification channel
+pub struct ChannelEscalationNotifier {
+    pub center: Arc<NotificationCenter>,
+    pub channel_id: String,
+}
+
+#[async_trait]
+impl EscalationNotifier for ChannelEscalationNotifier {
+    async fn notify(&self, record: &CommunicationRecord) -> Result<(), String> {
+        let delivery = self.center.send_record(record.clone(), &self.channel_id, Utc::now()).await?;
+        match delivery.status {
+            DeliveryStatus::Delivered => Ok(()),
+            _ => Err(delivery.last_error.unwrap_or_else(|| "not delivered".to_string())),
+        }
+    }
+}
+
+// NAPI Bindings
+
+/// NAPI wrapper around the notification center
+#[cfg(feature = "napi")]
+#[napi]
+pub struct NotificationCenterNapi {
+    inner: Arc<NotificationCenter>,
+    access: AccessGuard,
+}
+
+#[cfg(feature = "napi")]
+impl Default for NotificationCenterNapi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl NotificationCenterNapi {
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        NotificationCenterNapi {
+            inner: Arc::new(NotificationCenter::new()),
+            access: AccessGuard::default(),
+        }
+    }
+
+    /// Register or replace an email, Slack, Teams or webhook channel
+    #[napi]
+    pub async fn register_notification_channel(&self, channel_data: String, auth_token: Option<String>) -> napi::Result<()> {
+        self.authorize(auth_token, "incident:write", "notifications")?;
+        let channel: NotificationChannel = serde_json::from_str(&channel_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid channel: {}", e)))?;
+        self.inner.register_channel(channel).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to register channel: {}", e)))
+    }
+
+    /// Channels with credentials masked
+    #[napi]
+    pub async fn list_notification_channels(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.list_channels().await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn register_recipient_group(&self, group_data: String, auth_token: Option<String>) -> napi::Result<()> {
+        self.authorize(auth_token, "incident:write", "notifications")?;
+        let group: RecipientGroup = serde_json::from_str(&group_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid recipient group: {}", e)))?;
+        self.inner.register_group(group).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to register recipient group: {}", e)))
+    }
+
+    /// Register or replace a message template
+    #[napi]
+    pub async fn register_message_template(&self, template_data: String, auth_token: Option<String>) -> napi::Result<()> {
+        self.authorize(auth_token, "incident:write", "notifications")?;
+        let template: MessageTemplate = serde_json::from_str(&template_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid template: {}", e)))?;
+        self.inner.register_template(template).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to register template: {}", e)))
+    }
+
+    #[napi]
+    pub async fn list_message_templates(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.list_templates().await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn set_delivery_retry_policy(&self, policy_data: String, auth_token: Option<String>) -> napi::Result<()> {
+        self.authorize(auth_token, "incident:write", "notifications")?;
+        let policy: RetryPolicy = serde_json::from_str(&policy_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid retry policy: {}", e)))?;
+        self.inner.set_retry_policy(policy).await.map_err(napi::Error::from_reason)
+    }
+
+    /// Record an incident's latest state for use in messages
+    #[napi]
+    pub async fn track_incident(&self, incident_data: String, auth_token: Option<String>) -> napi::Result<()> {
+        let incident: IncidentRecord = serde_json::from_str(&incident_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid incident: {}", e)))?;
+        self.authorize(auth_token, "incident:write", &incident.incident_id)?;
+        self.inner.track_incident(incident).await;
+        Ok(())
+    }
+
+    /// Send an incident update; `template` is a template id or an event type,
+    /// and `context_data` adds variables to the template context
+    #[napi]
+    pub async fn send_incident_update(&self, incident_id: String, template: String, channel: String, context_data: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token, "incident:write", &incident_id)?;
+        let extra = context_data
+            .map(|data| serde_json::from_str(&data))
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Invalid message context: {}", e)))?;
+        let delivery = self.inner.send_incident_update(&incident_id, &template, &channel, extra, Utc::now()).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to send incident update: {}", e)))?;
+        serde_json::to_string(&delivery)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Retry failed deliveries whose backoff has elapsed
+    #[napi]
+    pub async fn retry_due_deliveries(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.retry_due(Utc::now()).await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn get_delivery(&self, communication_id: String) -> napi::Result<Option<String>> {
+        match self.inner.get_delivery(&communication_id).await {
+            Some(delivery) => serde_json::to_string(&delivery)
+                .map(Some)
+                .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e))),
+            None => Ok(None),
+        }
+    }
+
+    /// List deliveries, optionally for a single incident
+    #[napi]
+    pub async fn list_deliveries(&self, incident_id: Option<String>) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.list_deliveries(incident_id.as_deref()).await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+}
+
+#[cfg(feature = "napi")]
+impl NotificationCenterNapi {
+    fn authorize(&self, auth_token: Option<String>, permission: &str, resource: &str) -> napi::Result<String> {
+        self.access.check(auth_token.as_deref(), permission, resource)
+            .map_err(napi::Error::from_reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::sync::Mutex;
+
+    /// Records messages and fails the first `failures` sends
+    struct FlakySender {
+        sent: Mutex<Vec<OutboundMessage>>,
+        failures: Mutex<u32>,
+    }
+
+    #[async_trait]
+    impl ChannelSender for FlakySender {
+        async fn send(&self, _channel: &NotificationChannel, message: &OutboundMessage) -> Result<(), String> {
+            let mut failures = self.failures.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err("503 from webhook".to_string());
+            }
+            self.sent.lock().unwrap().push(message.clone());
+            Ok(())
+        }
+    }
+
+    fn t(minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 3, 3, 9, minute, 0).unwrap()
+    }
+
+    fn incident(severity: &str) -> IncidentRecord {
+        IncidentRecord {
+            incident_id: "INC-7".to_string(),
+            title: "Ransomware on file server".to_string(),
+            description: String::new(),
+            severity: severity.to_string(),
+            priority: "P1".to_string(),
+            status: "containment".to_string(),
+            incident_type: "ransomware".to_string(),
+            created_at: t(0),
+            updated_at: t(0),
+            assigned_to: "alice".to_string(),
+            affected_systems: vec!["fs-01".to_string(), "fs-02".to_string()],
+            indicators: vec![],
+            timeline: vec![],
+            response_actions: vec![],
+        }
+    }
+
+    fn template(template_id: &str, severity: Option<&str>, body: &str) -> MessageTemplate {
+        MessageTemplate {
+            template_id: template_id.to_string(),
+            event_type: "status_update".to_string(),
+            severity: severity.map(str::to_string),
+            subject: "[{{incident.severity}}] {{incident.title}}".to_string(),
+            body: body.to_string(),
+            recipient_groups: vec!["soc".to_string()],
+        }
+    }
+
+    #[tokio::test]
+    async fn updates_render_by_severity_and_retry_with_backoff() {
+        let center = NotificationCenter::new();
+        let sender = Arc::new(FlakySender { sent: Mutex::new(vec![]), failures: Mutex::new(1) });
+        center.set_sender(sender.clone()).await;
+        center.register_group(RecipientGroup { group_id: "soc".to_string(), name: "SOC".to_string(), members: vec!["#soc".to_string(), "#ir".to_string()] }).await.unwrap();
+        center.register_group(RecipientGroup { group_id: "execs".to_string(), name: "Execs".to_string(), members: vec!["#ir".to_string(), "#ciso".to_string()] }).await.unwrap();
+        center.register_channel(NotificationChannel {
+            channel_id: "slack".to_string(),
+            name: "IR Slack".to_string(),
+            kind: ChannelKind::Slack { webhook_url: "https://hooks.slack.com/services/T0/B0/secret".to_string() },
+            recipient_groups: vec!["execs".to_string()],
+        }).await.unwrap();
+        assert!(center.register_template(template("broken", None, "{{#if incident.title}}open")).await.is_err());
+        center.register_template(template("any", None, "Status: {{incident.status}}")).await.unwrap();
+        center.register_template(template(
+            "critical",
+            Some("critical"),
+            "{{incident.title}} is {{incident.status}}.\n{{#each incident.affected_systems}}{{@index}}. {{this}}\n{{/each}}{{#if eta}}ETA {{eta}}{{else}}No ETA{{/if}}",
+        )).await.unwrap();
+
+        center.track_incident(incident("critical")).await;
+        let delivery = center
+            .send_incident_update("INC-7", "status_update", "slack", Some(serde_json::json!({ "eta": "2h" })), t(0))
+            .await
+            .unwrap();
+        assert_eq!(delivery.template_id.as_deref(), Some("critical"));
+        assert_eq!(delivery.subject, "[critical] Ransomware on file server");
+        assert_eq!(delivery.record.message, "Ransomware on file server is containment.\n0. fs-01\n1. fs-02\nETA 2h");
+        assert_eq!(delivery.record.recipients, vec!["#soc", "#ir", "#ciso"]);
+        assert_eq!((delivery.status, delivery.attempts), (DeliveryStatus::Retrying, 1));
+        assert_eq!(delivery.next_attempt_at, Some(t(0) + Duration::seconds(30)));
+
+        assert!(center.retry_due(t(0)).await.is_empty());
+        let retried = center.retry_due(t(1)).await;
+        assert_eq!((retried[0].status, retried[0].attempts, retried[0].record.delivery_status.as_str()), (DeliveryStatus::Delivered, 2, "delivered"));
+        assert_eq!(sender.sent.lock().unwrap()[0].severity, "critical");
+
+        center.track_incident(incident("low")).await;
+        center.set_retry_policy(RetryPolicy { max_attempts: 1, backoff_secs: 30 }).await.unwrap();
+        *sender.failures.lock().unwrap() = 1;
+        let delivery = center.send_incident_update("INC-7", "status_update", "slack", None, t(2)).await.unwrap();
+        assert_eq!((delivery.template_id.as_deref(), delivery.record.message.as_str()), (Some("any"), "Status: containment"));
+        assert_eq!((delivery.status, delivery.last_error.as_deref()), (DeliveryStatus::Failed, Some("503 from webhook")));
+        assert_eq!(center.list_deliveries(Some("INC-7")).await.len(), 2);
+        assert!(center.send_incident_update("INC-7", "status_update", "email", None, t(3)).await.is_err());
+
+        let request = webhook_request(&center.state.read().await.channels["slack"], &sender.sent.lock().unwrap()[0]).unwrap();
+        assert!(request.url.ends_with("/secret"));
+        let payload: Value = serde_json::from_slice(&request.body).unwrap();
+        assert!(payload["text"].as_str().unwrap().starts_with("*[critical] Ransomware on file server*\n"));
+        assert_eq!(center.list_channels().await[0].kind_name(), "slack");
+        assert!(!serde_json::to_string(&center.list_channels().await).unwrap().contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn email_goes_through_an_smtp_dialogue() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let (read, mut write) = stream.into_split();
+            let mut lines = BufReader::new(read).lines();
+            let mut transcript = Vec::new();
+            write.write_all(b"220 relay ESMTP\r\n").await.unwrap();
+            let mut in_data = false;
+            while let Some(line) = lines.next_line().await.unwrap() {
+                let reply: &[u8] = if in_data {
+                    if line == "." {
+                        in_data = false;
+                        b"250 queued\r\n"
+                    } else {
+                        b""
+                    }
+                } else if line.starts_with("EHLO") {
+                    b"250-relay\r\n250 AUTH PLAIN\r\n"
+                } else if line.starts_with("AUTH") {
+                    b"235 ok\r\n"
+                } else if line == "DATA" {
+                    in_data = true;
+                    b"354 go ahead\r\n"
+                } else if line == "QUIT" {
+                    transcript.push(line);
+                    break;
+                } else {
+                    b"250 ok\r\n"
+                };
+                transcript.push(line);
+                write.write_all(reply).await.unwrap();
+            }
+            transcript
+        });
+
+        let message = OutboundMessage {
+            communication_id: "c-1".to_string(),
+            incident_id: "INC-7".to_string(),
+            event_type: "status_update".to_string(),
+            severity: "high".to_string(),
+            subject: "Update\r\nBcc: attacker@evil.test".to_string(),
+            body: "Contained.\n.hidden line".to_string(),
+            recipients: vec!["soc@example.com".to_string(), "ciso@example.com".to_string()],
+        };
+        send_email("127.0.0.1", port, "ir@example.com", Some(("ir", "hunter2")), &message).await.unwrap();
+        let transcript = server.await.unwrap();
+        assert_eq!(transcript[1], "AUTH PLAIN AGlyAGh1bnRlcjI=");
+        assert_eq!(&transcript[2..5], ["MAIL FROM:<ir@example.com>", "RCPT TO:<soc@example.com>", "RCPT TO:<ciso@example.com>"]);
+        assert!(transcript.contains(&"Subject: Update  Bcc: attacker@evil.test".to_string()));
+        assert!(transcript.contains(&"..hidden line".to_string()));
+        assert_eq!(transcript.last().map(String::as_str), Some("QUIT"));
+
+        let invalid = OutboundMessage { recipients: vec!["not an address".to_string()], ..message };
+        assert!(send_email("127.0.0.1", port, "ir@example.com", None, &invalid).await.is_err());
+    }
+}
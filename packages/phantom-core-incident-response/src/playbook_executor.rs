@@ -0,0 +1,1167 @@
+//! Playbook Executor
+//!
+//! Asynchronous engine that walks a playbook definition step by step:
+//! evaluates step conditions, runs actions with per-step timeouts and
+//! retries, follows on_success/on_failure edges, and tracks the state of
+//! every step. Executions can be paused, resumed and cancelled while running.
+//! Branch steps pick the next step from the execution variables, and approval
+//! steps hold the execution until an approver accepts or rejects them. Action
+//! parameters may reference variables as `{{name}}`; actions registered with a
+//! `StepDescriptor` have their parameters checked against its schema when the
+//! playbook is registered and again, resolved, before each run.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration as StdDuration;
+use tokio::sync::{oneshot, watch, RwLock};
+use uuid::Uuid;
+
+use crate::playbook_steps::{self, StepBackends, StepDescriptor};
+
+#[cfg(feature = "napi")]
+use crate::access::AccessGuard;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+
+/// Default per-step timeout when neither the step nor the playbook sets one
+const DEFAULT_STEP_TIMEOUT_SECS: u64 = 300;
+
+// Playbook Definition
+
+/// Executable playbook: a graph of steps joined by success/failure edges
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaybookDefinition {
+    pub playbook_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub steps: Vec<StepDefinition>,
+    /// First step to run; defaults to the first step in `steps`
+    #[serde(default)]
+    pub entry_step: Option<String>,
+    #[serde(default)]
+    pub default_timeout_secs: Option<u64>,
+    /// Upper bound on step transitions, guards against cyclic edges
+    #[serde(default)]
+    pub max_transitions: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepDefinition {
+    pub step_id: String,
+    pub name: String,
+    pub action: StepAction,
+    /// Step is skipped when the condition does not hold
+    #[serde(default)]
+    pub condition: Option<StepCondition>,
+    /// Next step after success or skip; falls through to the next step in order when unset
+    #[serde(default)]
+    pub on_success: Option<String>,
+    /// Next step after failure; the execution fails when unset
+    #[serde(default)]
+    pub on_failure: Option<String>,
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub retries: u32,
+    #[serde(default)]
+    pub retry_backoff_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StepAction {
+    /// Invoke a registered action handler, e.g. "isolate_host"
+    Action {
+        name: String,
+        #[serde(default)]
+        parameters: HashMap<String, String>,
+    },
+    SetVariable { name: String, value: String },
+    Delay { millis: u64 },
+    Fail { message: String },
+    /// Continue at the first case whose condition holds, else at `default`,
+    /// else along `on_success`
+    Branch {
+        cases: Vec<BranchCase>,
+        #[serde(default)]
+        default: Option<String>,
+    },
+    /// Hold the execution until one of `approvers`, or anyone when empty,
+    /// decides; a rejection fails the step
+    Approval {
+        prompt: String,
+        #[serde(default)]
+        approvers: Vec<String>,
+    },
+    /// Marks the end of the playbook
+    End,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BranchCase {
+    pub condition: StepCondition,
+    pub next: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepCondition {
+    pub variable: String,
+    pub operator: ConditionOperator,
+    #[serde(default)]
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConditionOperator {
+    Equals,
+    NotEquals,
+    Contains,
+    Exists,
+    NotExists,
+}
+
+impl StepCondition {
+    pub fn evaluate(&self, variables: &HashMap<String, String>) -> bool {
+        let actual = variables.get(&self.variable);
+        let expected = self.value.as_deref().unwrap_or_default();
+        match self.operator {
+            ConditionOperator::Equals => actual.is_some_and(|v| v == expected),
+            ConditionOperator::NotEquals => actual.is_none_or(|v| v != expected),
+            ConditionOperator::Contains => actual.is_some_and(|v| v.contains(expected)),
+            ConditionOperator::Exists => actual.is_some(),
+            ConditionOperator::NotExists => actual.is_none(),
+        }
+    }
+}
+
+impl PlaybookDefinition {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.playbook_id.trim().is_empty() {
+            return Err("Playbook id is required".to_string());
+        }
+        if self.steps.is_empty() {
+            return Err(format!("Playbook {} has no steps", self.playbook_id));
+        }
+
+        let mut ids = HashSet::new();
+        for step in &self.steps {
+            if !ids.insert(step.step_id.as_str()) {
+                return Err(format!("Duplicate step id {}", step.step_id));
+            }
+        }
+
+        let branches = self.steps.iter().flat_map(|s| match &s.action {
+            StepAction::Branch { cases, default } => cases.iter().map(|case| &case.next).chain(default.iter()).collect(),
+            _ => Vec::new(),
+        });
+        let edges = self.steps.iter().flat_map(|s| s.on_success.iter().chain(s.on_failure.iter()));
+        for target in edges.chain(branches).chain(self.entry_step.iter()) {
+            if !ids.contains(target.as_str()) {
+                return Err(format!("Unknown step {} referenced in playbook {}", target, self.playbook_id));
+            }
+        }
+        Ok(())
+    }
+
+    fn step_index(&self, step_id: &str) -> Option<usize> {
+        self.steps.iter().position(|s| s.step_id == step_id)
+    }
+
+    fn entry_index(&self) -> usize {
+        self.entry_step.as_deref().and_then(|id| self.step_index(id)).unwrap_or(0)
+    }
+
+    fn step_timeout(&self, step: &StepDefinition) -> StdDuration {
+        let secs = step.timeout_secs.or(self.default_timeout_secs).unwrap_or(DEFAULT_STEP_TIMEOUT_SECS);
+        StdDuration::from_secs(secs)
+    }
+}
+
+// Action Handlers
+
+/// Performs a named response action (containment, notification, ...)
+#[async_trait]
+pub trait ActionHandler: Send + Sync {
+    /// Returns outputs merged into the execution variables as `<step_id>.<key>`
+    async fn execute(
+        &self,
+        parameters: &HashMap<String, String>,
+        variables: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, String>;
+}
+
+// Approvals
+
+/// Approval step waiting for a decision
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalRequest {
+    pub execution_id: String,
+    pub playbook_id: String,
+    pub incident_id: String,
+    pub step_id: String,
+    pub prompt: String,
+    pub approvers: Vec<String>,
+    pub requested_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApprovalDecision {
+    pub approver: String,
+    pub approved: bool,
+    #[serde(default)]
+    pub comment: String,
+}
+
+struct PendingApproval {
+    request: ApprovalRequest,
+    decision: oneshot::Sender<ApprovalDecision>,
+}
+
+// Execution State
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionStatus {
+    Running,
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl ExecutionStatus {
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, ExecutionStatus::Completed | ExecutionStatus::Failed | ExecutionStatus::Cancelled)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    Skipped,
+    TimedOut,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepState {
+    pub step_id: String,
+    pub status: StepStatus,
+    pub attempts: u32,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+    pub output: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineExecution {
+    pub execution_id: String,
+    pub playbook_id: String,
+    pub incident_id: String,
+    pub status: ExecutionStatus,
+    pub current_step: Option<String>,
+    pub steps: Vec<StepState>,
+    /// Step ids in the order they were visited
+    pub path: Vec<String>,
+    pub variables: HashMap<String, String>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ControlSignal {
+    Run,
+    Pause,
+    Cancel,
+}
+
+enum StepOutcome {
+    Succeeded(HashMap<String, String>),
+    Failed(StepStatus, String),
+    Cancelled,
+}
+
+type Executions = Arc<RwLock<HashMap<String, EngineExecution>>>;
+type Handlers = Arc<RwLock<HashMap<String, Arc<dyn ActionHandler>>>>;
+type Descriptors = Arc<RwLock<HashMap<String, StepDescriptor>>>;
+/// Pending approvals by execution id; an execution runs one step at a time
+type Approvals = Arc<Mutex<HashMap<String, PendingApproval>>>;
+
+/// What a running execution needs to perform its steps
+#[derive(Clone)]
+struct StepRuntime {
+    handlers: Handlers,
+    descriptors: Descriptors,
+    approvals: Approvals,
+}
+
+/// Playbook execution engine
+pub struct PlaybookEngine {
+    playbooks: Arc<RwLock<HashMap<String, PlaybookDefinition>>>,
+    executions: Executions,
+    controls: Arc<RwLock<HashMap<String, watch::Sender<ControlSignal>>>>,
+    handlers: Handlers,
+    descriptors: Descriptors,
+    approvals: Approvals,
+}
+
+impl Default for PlaybookEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PlaybookEngine {
+    pub fn new() -> Self {
+        Self {
+            playbooks: Arc::new(RwLock::new(HashMap::new())),
+            executions: Arc::new(RwLock::new(HashMap::new())),
+            controls: Arc::new(RwLock::new(HashMap::new())),
+            handlers: Arc::new(RwLock::new(HashMap::new())),
+            descriptors: Arc::new(RwLock::new(HashMap::new())),
+            approvals: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Engine with the built-in step library registered against `backends`
+    pub fn with_builtin_steps(backends: &StepBackends) -> Self {
+        let mut handlers = HashMap::new();
+        let mut descriptors = HashMap::new();
+        for (descriptor, handler) in playbook_steps::builtin_steps(backends) {
+            handlers.insert(descriptor.name.clone(), handler);
+            descriptors.insert(descriptor.name.clone(), descriptor);
+        }
+        Self {
+            handlers: Arc::new(RwLock::new(handlers)),
+            descriptors: Arc::new(RwLock::new(descriptors)),
+            ..Self::new()
+        }
+    }
+
+    pub async fn register_action(&self, name: &str, handler: Arc<dyn ActionHandler>) {
+        self.handlers.write().await.insert(name.to_string(), handler);
+        self.descriptors.write().await.remove(name);
+    }
+
+    /// Register an action whose parameters are validated against `descriptor`
+    pub async fn register_step(&self, descriptor: StepDescriptor, handler: Arc<dyn ActionHandler>) -> Result<(), String> {
+        descriptor.check_schema()?;
+        self.handlers.write().await.insert(descriptor.name.clone(), handler);
+        self.descriptors.write().await.insert(descriptor.name.clone(), descriptor);
+        Ok(())
+    }
+
+    /// Descriptors of the actions registered with a parameter schema
+    pub async fn list_steps(&self) -> Vec<StepDescriptor> {
+        let mut steps: Vec<_> = self.descriptors.read().await.values().cloned().collect();
+        steps.sort_by(|a, b| a.name.cmp(&b.name));
+        steps
+    }
+
+    pub async fn register_playbook(&self, playbook: PlaybookDefinition) -> Result<(), String> {
+        playbook.validate()?;
+        {
+            let descriptors = self.descriptors.read().await;
+            for step in &playbook.steps {
+                if let StepAction::Action { name, parameters } = &step.action {
+                    if let Some(descriptor) = descriptors.get(name) {
+                        descriptor.validate(parameters, true).map_err(|e| format!("Step {}: {}", step.step_id, e))?;
+                    }
+                }
+            }
+        }
+        self.playbooks.write().await.insert(playbook.playbook_id.clone(), playbook);
+        Ok(())
+    }
+
+    pub async fn get_playbook(&self, playbook_id: &str) -> Option<PlaybookDefinition> {
+        self.playbooks.read().await.get(playbook_id).cloned()
+    }
+
+    pub async fn list_playbooks(&self) -> Vec<PlaybookDefinition> {
+        let mut playbooks: Vec<_> = self.playbooks.read().await.values().cloned().collect();
+        playbooks.sort_by(|a, b| a.playbook_id.cmp(&b.playbook_id));
+        playbooks
+    }
+
+    /// Start a playbook for an incident and return the execution id
+    ///
+    /// The execution runs on the tokio runtime; poll `get_execution` for progress.
+    pub async fn start_execution(
+        &self,
+        playbook_id: &str,
+        incident_id: &str,
+        variables: HashMap<String, String>,
+    ) -> Result<String, String> {
+        let playbook = self
+            .get_playbook(playbook_id)
+            .await
+            .ok_or_else(|| format!("Playbook {} not found", playbook_id))?;
+
+        let execution_id = Uuid::new_v4().to_string();
+        let execution = EngineExecution {
+            execution_id: execution_id.clone(),
+            playbook_id: playbook.playbook_id.clone(),
+            incident_id: incident_id.to_string(),
+            status: ExecutionStatus::Running,
+            current_step: None,
+            steps: playbook
+                .steps
+                .iter()
+                .map(|s| StepState {
+                    step_id: s.step_id.clone(),
+                    status: StepStatus::Pending,
+                    attempts: 0,
+                    started_at: None,
+                    completed_at: None,
+                    error: None,
+                    output: HashMap::new(),
+                })
+                .collect(),
+            path: Vec::new(),
+            variables,
+            started_at: Utc::now(),
+            completed_at: None,
+            error: None,
+        };
+
+        let (control_tx, control_rx) = watch::channel(ControlSignal::Run);
+        self.executions.write().await.insert(execution_id.clone(), execution);
+        self.controls.write().await.insert(execution_id.clone(), control_tx);
+
+        let executions = self.executions.clone();
+        let runtime = StepRuntime {
+            handlers: self.handlers.clone(),
+            descriptors: self.descriptors.clone(),
+            approvals: self.approvals.clone(),
+        };
+        let controls = self.controls.clone();
+        let id = execution_id.clone();
+        tokio::spawn(async move {
+            drive(playbook, id.clone(), executions, runtime, control_rx).await;
+            controls.write().await.remove(&id);
+        });
+
+        Ok(execution_id)
+    }
+
+    pub async fn get_execution(&self, execution_id: &str) -> Option<EngineExecution> {
+        self.executions.read().await.get(execution_id).cloned()
+    }
+
+    pub async fn list_executions(&self, incident_id: Option<&str>) -> Vec<EngineExecution> {
+        let mut executions: Vec<_> = self
+            .executions
+            .read()
+            .await
+            .values()
+            .filter(|e| incident_id.is_none_or(|id| e.incident_id == id))
+            .cloned()
+            .collect();
+        executions.sort_by_key(|e| e.started_at);
+        executions
+    }
+
+    /// Pause after the current step finishes
+    pub async fn pause_execution(&self, execution_id: &str) -> Result<(), String> {
+        self.signal(execution_id, ControlSignal::Pause).await
+    }
+
+    pub async fn resume_execution(&self, execution_id: &str) -> Result<(), String> {
+        self.signal(execution_id, ControlSignal::Run).await
+    }
+
+    /// Cancel immediately, aborting the step in flight
+    pub async fn cancel_execution(&self, execution_id: &str) -> Result<(), String> {
+        self.signal(execution_id, ControlSignal::Cancel).await
+    }
+
+    /// Approval steps waiting for a decision, oldest first
+    pub fn pending_approvals(&self, incident_id: Option<&str>) -> Vec<ApprovalRequest> {
+        let mut pending: Vec<_> = self
+            .approvals
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .map(|pending| pending.request.clone())
+            .filter(|request| incident_id.is_none_or(|id| request.incident_id == id))
+            .collect();
+        pending.sort_by_key(|request| request.requested_at);
+        pending
+    }
+
+    /// Accept or reject the approval step `execution_id` is waiting on
+    pub fn decide_approval(&self, execution_id: &str, decision: ApprovalDecision) -> Result<ApprovalRequest, String> {
+        let mut approvals = self.approvals.lock().unwrap_or_else(|e| e.into_inner());
+        let pending = approvals
+            .get(execution_id)
+            .ok_or_else(|| format!("Execution {} is not waiting for approval", execution_id))?;
+        let approvers = &pending.request.approvers;
+        if !approvers.is_empty() && !approvers.contains(&decision.approver) {
+            return Err(format!("{} cannot approve step {}", decision.approver, pending.request.step_id));
+        }
+        let Some(pending) = approvals.remove(execution_id) else {
+            return Err(format!("Execution {} is not waiting for approval", execution_id));
+        };
+        pending
+            .decision
+            .send(decision)
+            .map_err(|_| format!("Execution {} is no longer waiting for approval", execution_id))?;
+        Ok(pending.request)
+    }
+
+    async fn signal(&self, execution_id: &str, signal: ControlSignal) -> Result<(), String> {
+        let controls = self.controls.read().await;
+        let sender = controls
+            .get(execution_id)
+            .ok_or_else(|| format!("Execution {} is not running", execution_id))?;
+        sender
+            .send(signal)
+            .map_err(|_| format!("Execution {} is not running", execution_id))
+    }
+}
+
+// Execution Loop
+
+async fn update<F: FnOnce(&mut EngineExecution)>(executions: &Executions, execution_id: &str, f: F) {
+    if let Some(execution) = executions.write().await.get_mut(execution_id) {
+        f(execution);
+    }
+}
+
+async fn finish(executions: &Executions, execution_id: &str, status: ExecutionStatus, error: Option<String>) {
+    update(executions, execution_id, |e| {
+        e.status = status;
+        e.error = error;
+        e.current_step = None;
+        e.completed_at = Some(Utc::now());
+        if status == ExecutionStatus::Cancelled {
+            for step in e.steps.iter_mut().filter(|s| s.status == StepStatus::Running) {
+                step.status = StepStatus::Cancelled;
+                step.completed_at = Some(Utc::now());
+            }
+        }
+    })
+    .await;
+}
+
+/// Blocks while paused; returns false once the execution is cancelled
+async fn wait_while_paused(
+    executions: &Executions,
+    execution_id: &str,
+    control: &mut watch::Receiver<ControlSignal>,
+) -> bool {
+    loop {
+        let signal = *control.borrow_and_update();
+        match signal {
+            ControlSignal::Run => {
+                update(executions, execution_id, |e| e.status = ExecutionStatus::Running).await;
+                return true;
+            }
+            ControlSignal::Cancel => return false,
+            ControlSignal::Pause => {
+                update(executions, execution_id, |e| e.status = ExecutionStatus::Paused).await;
+                if control.changed().await.is_err() {
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+async fn run_action(
+    playbook: &PlaybookDefinition,
+    step: &StepDefinition,
+    execution: &EngineExecution,
+    runtime: &StepRuntime,
+) -> Result<HashMap<String, String>, String> {
+    let variables = &execution.variables;
+    match &step.action {
+        StepAction::Action { name, parameters } => {
+            let handler = runtime
+                .handlers
+                .read()
+                .await
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("No handler registered for action {}", name))?;
+            let parameters = playbook_steps::resolve_parameters(parameters, variables)?;
+            if let Some(descriptor) = runtime.descriptors.read().await.get(name) {
+                descriptor.validate(&parameters, false)?;
+            }
+            handler.execute(&parameters, variables).await
+        }
+        StepAction::Branch { cases, default } => {
+            let next = cases.iter().find(|case| case.condition.evaluate(variables)).map(|case| &case.next).or(default.as_ref());
+            Ok(next.map(|next| HashMap::from([("next".to_string(), next.clone())])).unwrap_or_default())
+        }
+        StepAction::Approval { prompt, approvers } => {
+            let (decision_tx, decision_rx) = oneshot::channel();
+            let request = ApprovalRequest {
+                execution_id: execution.execution_id.clone(),
+                playbook_id: playbook.playbook_id.clone(),
+                incident_id: execution.incident_id.clone(),
+                step_id: step.step_id.clone(),
+                prompt: prompt.clone(),
+                approvers: approvers.clone(),
+                requested_at: Utc::now(),
+            };
+            runtime
+                .approvals
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(execution.execution_id.clone(), PendingApproval { request, decision: decision_tx });
+            // Timeouts and cancellation drop this wait; the request goes with it
+            struct WithdrawOnDrop<'a>(&'a Approvals, &'a str);
+            impl Drop for WithdrawOnDrop<'_> {
+                fn drop(&mut self) {
+                    self.0.lock().unwrap_or_else(|e| e.into_inner()).remove(self.1);
+                }
+            }
+            let _withdraw = WithdrawOnDrop(&runtime.approvals, &execution.execution_id);
+            let decision = decision_rx.await.map_err(|_| "Approval request was withdrawn".to_string())?;
+            if !decision.approved {
+                return Err(format!("Rejected by {}: {}", decision.approver, decision.comment));
+            }
+            Ok(HashMap::from([
+                ("approved_by".to_string(), decision.approver),
+                ("comment".to_string(), decision.comment),
+            ]))
+        }
+        StepAction::SetVariable { name, value } => Ok(HashMap::from([(name.clone(), value.clone())])),
+        StepAction::Delay { millis } => {
+            tokio::time::sleep(StdDuration::from_millis(*millis)).await;
+            Ok(HashMap::new())
+        }
+        StepAction::Fail { message } => Err(message.clone()),
+        StepAction::End => Ok(HashMap::new()),
+    }
+}
+
+/// Runs one step with retries, racing each attempt against its timeout and cancellation
+async fn run_step(
+    playbook: &PlaybookDefinition,
+    step: &StepDefinition,
+    index: usize,
+    execution_id: &str,
+    executions: &Executions,
+    runtime: &StepRuntime,
+    control: &mut watch::Receiver<ControlSignal>,
+) -> StepOutcome {
+    let timeout = playbook.step_timeout(step);
+    let mut last_failure = (StepStatus::Failed, String::new());
+
+    for attempt in 0..=step.retries {
+        if attempt > 0 && step.retry_backoff_ms > 0 {
+            tokio::time::sleep(StdDuration::from_millis(step.retry_backoff_ms * attempt as u64)).await;
+        }
+
+        let snapshot = {
+            let mut executions_guard = executions.write().await;
+            let Some(execution) = executions_guard.get_mut(execution_id) else {
+                return StepOutcome::Cancelled;
+            };
+            let state = &mut execution.steps[index];
+            state.status = StepStatus::Running;
+            state.attempts = attempt + 1;
+            state.started_at.get_or_insert_with(Utc::now);
+            execution.clone()
+        };
+
+        let cancelled = async {
+            loop {
+                if *control.borrow_and_update() == ControlSignal::Cancel || control.changed().await.is_err() {
+                    return;
+                }
+            }
+        };
+
+        tokio::select! {
+            result = tokio::time::timeout(timeout, run_action(playbook, step, &snapshot, runtime)) => match result {
+                Ok(Ok(output)) => return StepOutcome::Succeeded(output),
+                Ok(Err(error)) => last_failure = (StepStatus::Failed, error),
+                Err(_) => last_failure = (StepStatus::TimedOut, format!("Step {} timed out after {:?}", step.step_id, timeout)),
+            },
+            _ = cancelled => return StepOutcome::Cancelled,
+        }
+    }
+
+    StepOutcome::Failed(last_failure.0, last_failure.1)
+}
+
+async fn drive(
+    playbook: PlaybookDefinition,
+    execution_id: String,
+    executions: Executions,
+    runtime: StepRuntime,
+    mut control: watch::Receiver<ControlSignal>,
+) {
+    let max_transitions = playbook.max_transitions.unwrap_or(playbook.steps.len() as u32 * 10);
+    let mut transitions = 0u32;
+    let mut next = Some(playbook.entry_index());
+
+    while let Some(index) = next {
+        if !wait_while_paused(&executions, &execution_id, &mut control).await {
+            finish(&executions, &execution_id, ExecutionStatus::Cancelled, None).await;
+            return;
+        }
+
+        transitions += 1;
+        if transitions > max_transitions {
+            let error = format!("Exceeded {} step transitions", max_transitions);
+            finish(&executions, &execution_id, ExecutionStatus::Failed, Some(error)).await;
+            return;
+        }
+
+        let step = &playbook.steps[index];
+        let fallthrough = (index + 1 < playbook.steps.len()).then_some(index + 1);
+        let success_edge = match &step.on_success {
+            Some(target) => playbook.step_index(target),
+            None if matches!(step.action, StepAction::End) => None,
+            None => fallthrough,
+        };
+
+        let variables = {
+            let mut executions_guard = executions.write().await;
+            let Some(execution) = executions_guard.get_mut(&execution_id) else {
+                return;
+            };
+            execution.current_step = Some(step.step_id.clone());
+            execution.path.push(step.step_id.clone());
+            execution.variables.clone()
+        };
+
+        if let Some(condition) = &step.condition {
+            if !condition.evaluate(&variables) {
+                update(&executions, &execution_id, |e| {
+                    let state = &mut e.steps[index];
+                    state.status = StepStatus::Skipped;
+                    state.completed_at = Some(Utc::now());
+                })
+                .await;
+                next = success_edge;
+                continue;
+            }
+        }
+
+        match run_step(&playbook, step, index, &execution_id, &executions, &runtime, &mut control).await {
+            StepOutcome::Succeeded(output) => {
+                let branch = match (&step.action, output.get("next")) {
+                    (StepAction::Branch { .. }, Some(target)) => playbook.step_index(target),
+                    _ => None,
+                };
+                update(&executions, &execution_id, |e| {
+                    for (key, value) in &output {
+                        if matches!(step.action, StepAction::SetVariable { .. }) {
+                            e.variables.insert(key.clone(), value.clone());
+                        }
+                        e.variables.insert(format!("{}.{}", step.step_id, key), value.clone());
+                    }
+                    e.variables.insert(format!("{}.status", step.step_id), "succeeded".to_string());
+                    let state = &mut e.steps[index];
+                    state.status = StepStatus::Succeeded;
+                    state.error = None;
+                    state.output = output;
+                    state.completed_at = Some(Utc::now());
+                })
+                .await;
+                next = branch.or(success_edge);
+            }
+            StepOutcome::Failed(status, error) => {
+                update(&executions, &execution_id, |e| {
+                    e.variables.insert(format!("{}.status", step.step_id), "failed".to_string());
+                    let state = &mut e.steps[index];
+                    state.status = status;
+                    state.error = Some(error.clone());
+                    state.completed_at = Some(Utc::now());
+                })
+                .await;
+                match step.on_failure.as_deref().and_then(|target| playbook.step_index(target)) {
+                    Some(target) => next = Some(target),
+                    None => {
+                        let error = format!("Step {} failed: {}", step.step_id, error);
+                        finish(&executions, &execution_id, ExecutionStatus::Failed, Some(error)).await;
+                        return;
+                    }
+                }
+            }
+            StepOutcome::Cancelled => {
+                finish(&executions, &execution_id, ExecutionStatus::Cancelled, None).await;
+                return;
+            }
+        }
+    }
+
+    finish(&executions, &execution_id, ExecutionStatus::Completed, None).await;
+}
+
+// NAPI Bindings
+
+/// NAPI wrapper around the playbook engine
+#[cfg(feature = "napi")]
+#[napi]
+pub struct PlaybookEngineNapi {
+    inner: Arc<PlaybookEngine>,
+    tasks: Arc<playbook_steps::TaskBoard>,
+    access: AccessGuard,
+}
+
+#[cfg(feature = "napi")]
+impl Default for PlaybookEngineNapi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl PlaybookEngineNapi {
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        let tasks = Arc::new(playbook_steps::TaskBoard::default());
+        #[allow(unused_mut)]
+        let mut backends = StepBackends::local(tasks.clone());
+        #[cfg(feature = "reqwest")]
+        match playbook_steps::ReqwestStepClient::new(StdDuration::from_secs(DEFAULT_STEP_TIMEOUT_SECS)) {
+            Ok(client) => backends.http = Some(Arc::new(client)),
+            Err(e) => log::warn!("HTTP playbook steps unavailable: {}", e),
+        }
+        PlaybookEngineNapi { inner: Arc::new(PlaybookEngine::with_builtin_steps(&backends)), tasks, access: AccessGuard::default() }
+    }
+
+    /// Register or replace a playbook definition
+    #[napi]
+    pub async fn register_playbook(&self, playbook_data: String, auth_token: Option<String>) -> napi::Result<()> {
+        self.authorize(auth_token, "playbook:execute", "playbooks")?;
+        let playbook: PlaybookDefinition = serde_json::from_str(&playbook_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid playbook definition: {}", e)))?;
+        self.inner.register_playbook(playbook).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to register playbook: {}", e)))
+    }
+
+    /// List registered playbook definitions
+    #[napi]
+    pub async fn list_playbooks(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.list_playbooks().await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// List the registered steps with their parameter schemas
+    #[napi]
+    pub async fn list_playbook_steps(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.list_steps().await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Tasks opened by `create_task` steps, optionally for a single incident
+    #[napi]
+    pub fn list_playbook_tasks(&self, incident_id: Option<String>) -> napi::Result<String> {
+        serde_json::to_string(&self.tasks.tasks(incident_id.as_deref()))
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Start a playbook for an incident and return the execution id
+    #[napi]
+    pub async fn start_playbook(&self, playbook_id: String, incident_id: String, variables: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token, "playbook:execute", &playbook_id)?;
+        let variables: HashMap<String, String> = match variables {
+            Some(data) => serde_json::from_str(&data)
+                .map_err(|e| napi::Error::from_reason(format!("Invalid variables: {}", e)))?,
+            None => HashMap::new(),
+        };
+        self.inner.start_execution(&playbook_id, &incident_id, variables).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to start playbook: {}", e)))
+    }
+
+    /// Get execution state including per-step status
+    #[napi]
+    pub async fn get_playbook_execution(&self, execution_id: String) -> napi::Result<Option<String>> {
+        match self.inner.get_execution(&execution_id).await {
+            Some(execution) => serde_json::to_string(&execution)
+                .map(Some)
+                .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e))),
+            None => Ok(None),
+        }
+    }
+
+    /// List executions, optionally for a single incident
+    #[napi]
+    pub async fn list_playbook_executions(&self, incident_id: Option<String>) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.list_executions(incident_id.as_deref()).await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Pause an execution after its current step
+    #[napi]
+    pub async fn pause_playbook(&self, execution_id: String, auth_token: Option<String>) -> napi::Result<()> {
+        self.authorize(auth_token, "playbook:execute", &execution_id)?;
+        self.inner.pause_execution(&execution_id).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to pause playbook: {}", e)))
+    }
+
+    /// Resume a paused execution
+    #[napi]
+    pub async fn resume_playbook(&self, execution_id: String, auth_token: Option<String>) -> napi::Result<()> {
+        self.authorize(auth_token, "playbook:execute", &execution_id)?;
+        self.inner.resume_execution(&execution_id).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to resume playbook: {}", e)))
+    }
+
+    /// Approval steps waiting for a decision, optionally for a single incident
+    #[napi]
+    pub fn list_pending_approvals(&self, incident_id: Option<String>) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.pending_approvals(incident_id.as_deref()))
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Approve or reject the approval step an execution is waiting on
+    #[napi]
+    pub fn decide_playbook_approval(&self, execution_id: String, approved: bool, comment: Option<String>, auth_token: Option<String>) -> napi::Result<()> {
+        let approver = self.authorize(auth_token, "playbook:execute", &execution_id)?;
+        let decision = ApprovalDecision { approver, approved, comment: comment.unwrap_or_default() };
+        self.inner.decide_approval(&execution_id, decision)
+            .map(|_| ())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to decide approval: {}", e)))
+    }
+
+    /// Cancel an execution, aborting the step in flight
+    #[napi]
+    pub async fn cancel_playbook(&self, execution_id: String, auth_token: Option<String>) -> napi::Result<()> {
+        self.authorize(auth_token, "playbook:execute", &execution_id)?;
+        self.inner.cancel_execution(&execution_id).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to cancel playbook: {}", e)))
+    }
+}
+
+#[cfg(feature = "napi")]
+impl PlaybookEngineNapi {
+    fn authorize(&self, auth_token: Option<String>, permission: &str, resource: &str) -> napi::Result<String> {
+        self.access.check(auth_token.as_deref(), permission, resource)
+            .map_err(napi::Error::from_reason)
+    }
+}
+
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+#[napi]
+impl PlaybookEngineNapi {
+    /// Turn permission enforcement on or off
+    #[napi]
+    pub fn set_access_enforced(&self, enforced: bool, auth_token: Option<String>) -> napi::Result<()> {
+        self.authorize(auth_token, "access:manage", "access")?;
+        self.access.control().set_enforced(enforced);
+        Ok(())
+    }
+
+    /// Issue an API key for a principal; the token is only returned here
+    #[napi]
+    pub fn issue_api_key(&self, principal_data: String, ttl_secs: Option<i64>, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token, "access:manage", "access")?;
+        let principal = serde_json::from_str(&principal_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid principal: {}", e)))?;
+        let issued = self.access.control().issue_api_key(principal, ttl_secs.map(chrono::Duration::seconds));
+        serde_json::to_string(&issued)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Start a session that expires after `ttl_secs`
+    #[napi]
+    pub fn start_session(&self, principal_data: String, ttl_secs: i64, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token, "access:manage", "access")?;
+        let principal = serde_json::from_str(&principal_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid principal: {}", e)))?;
+        let issued = self.access.control().start_session(principal, chrono::Duration::seconds(ttl_secs));
+        serde_json::to_string(&issued)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub fn revoke_credential(&self, credential_id: String, auth_token: Option<String>) -> napi::Result<bool> {
+        self.authorize(auth_token, "access:manage", "access")?;
+        Ok(self.access.control().revoke(&credential_id))
+    }
+
+    #[napi]
+    pub fn list_credentials(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token, "access:manage", "access")?;
+        serde_json::to_string(&self.access.control().list_credentials())
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Most recent access denials, newest first
+    #[napi]
+    pub fn list_access_denials(&self, limit: Option<u32>, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token, "access:manage", "access")?;
+        let denials = self.access.control().denials(limit.unwrap_or(100) as usize);
+        serde_json::to_string(&denials)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    struct FlakyHandler {
+        calls: AtomicU32,
+        fail_first: u32,
+    }
+
+    #[async_trait]
+    impl ActionHandler for FlakyHandler {
+        async fn execute(
+            &self,
+            parameters: &HashMap<String, String>,
+            _variables: &HashMap<String, String>,
+        ) -> Result<HashMap<String, String>, String> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            if call < self.fail_first {
+                return Err("host unreachable".to_string());
+            }
+            let host = parameters.get("host").cloned().unwrap_or_default();
+            Ok(HashMap::from([("isolated".to_string(), host)]))
+        }
+    }
+
+    fn step(step_id: &str, action: StepAction) -> StepDefinition {
+        StepDefinition {
+            step_id: step_id.to_string(),
+            name: step_id.to_string(),
+            action,
+            condition: None,
+            on_success: None,
+            on_failure: None,
+            timeout_secs: None,
+            retries: 0,
+            retry_backoff_ms: 0,
+        }
+    }
+
+    async fn wait_for(engine: &PlaybookEngine, execution_id: &str, status: ExecutionStatus) -> EngineExecution {
+        for _ in 0..500 {
+            let execution = engine.get_execution(execution_id).await.unwrap();
+            if execution.status == status {
+                return execution;
+            }
+            tokio::time::sleep(StdDuration::from_millis(5)).await;
+        }
+        panic!("execution never reached {:?}", status);
+    }
+
+    #[tokio::test]
+    async fn test_retries_conditions_and_failure_branch() {
+        let engine = PlaybookEngine::new();
+        engine
+            .register_action("isolate_host", Arc::new(FlakyHandler { calls: AtomicU32::new(0), fail_first: 1 }))
+            .await;
+
+        let mut isolate = step(
+            "isolate",
+            StepAction::Action {
+                name: "isolate_host".to_string(),
+                parameters: HashMap::from([("host".to_string(), "ws-042".to_string())]),
+            },
+        );
+        isolate.retries = 1;
+
+        let mut notify_legal = step("notify_legal", StepAction::SetVariable { name: "legal".to_string(), value: "yes".to_string() });
+        notify_legal.condition = Some(StepCondition {
+            variable: "severity".to_string(),
+            operator: ConditionOperator::Equals,
+            value: Some("critical".to_string()),
+        });
+
+        let mut reimage = step("reimage", StepAction::Fail { message: "no golden image".to_string() });
+        reimage.on_failure = Some("escalate".to_string());
+        let close = step("close", StepAction::End);
+        let escalate = step("escalate", StepAction::SetVariable { name: "escalated".to_string(), value: "true".to_string() });
+
+        let playbook = PlaybookDefinition {
+            playbook_id: "malware-containment".to_string(),
+            name: "Malware containment".to_string(),
+            description: String::new(),
+            steps: vec![isolate, notify_legal, reimage, close, escalate],
+            entry_step: None,
+            default_timeout_secs: Some(5),
+            max_transitions: None,
+        };
+        engine.register_playbook(playbook).await.unwrap();
+
+        let id = engine
+            .start_execution("malware-containment", "INC-1", HashMap::from([("severity".to_string(), "high".to_string())]))
+            .await
+            .unwrap();
+        let execution = wait_for(&engine, &id, ExecutionStatus::Completed).await;
+
+        assert_eq!(execution.path, vec!["isolate", "notify_legal", "reimage", "escalate"]);
+        assert_eq!(execution.steps[0].status, StepStatus::Succeeded);
+        assert_eq!(execution.steps[0].attempts, 2);
+        assert_eq!(execution.steps[1].status, StepStatus::Skipped);
+        assert_eq!(execution.steps[2].status, StepStatus::Failed);
+        assert_eq!(execution.steps[3].status, StepStatus::Pending);
+        assert_eq!(execution.variables["isolate.isolated"], "ws-042");
+        assert_eq!(execution.variables["escalated"], "true");
+    }
+
+    #[tokio::test]
+    async fn test_pause_resume_cancel_and_timeout() {
+        let engine = PlaybookEngine::new();
+        let mut slow = step("slow", StepAction::Delay { millis: 10_000 });
+        slow.timeout_secs = Some(60);
+        let playbook = PlaybookDefinition {
+            playbook_id: "long".to_string(),
+            name: "Long running".to_string(),
+            description: String::new(),
+            steps: vec![step("wait", StepAction::Delay { millis: 50 }), slow],
+            entry_step: None,
+            default_timeout_secs: None,
+            max_transitions: None,
+        };
+        engine.register_playbook(playbook).await.unwrap();
+
+        let id = engine.start_execution("long", "INC-2", HashMap::new()).await.unwrap();
+        while engine.get_execution(&id).await.unwrap().steps[0].status != StepStatus::Running {
+            tokio::time::sleep(StdDuration::from_millis(1)).await;
+        }
+        engine.pause_execution(&id).await.unwrap();
+        let paused = wait_for(&engine, &id, ExecutionStatus::Paused).await;
+        assert_eq!(paused.steps[0].status, StepStatus::Succeeded);
+        assert_eq!(paused.steps[1].status, StepStatus::Pending);
+
+        engine.resume_execution(&id).await.unwrap();
+        wait_for(&engine, &id, ExecutionStatus::Running).await;
+        engine.cancel_execution(&id).await.unwrap();
+        let cancelled = wait_for(&engine, &id, ExecutionStatus::Cancelled).await;
+        assert_eq!(cancelled.steps[1].status, StepStatus::Cancelled);
+        assert!(engine.cancel_execution(&id).await.is_err());
+
+        let mut hung = step("hung", StepAction::Delay { millis: 5_000 });
+        hung.timeout_secs = Some(0);
+        engine
+            .register_playbook(PlaybookDefinition {
+                playbook_id: "hung".to_string(),
+                name: "Hung".to_string(),
+                description: String::new(),
+                steps: vec![hung],
+                entry_step: None,
+                default_timeout_secs: None,
+                max_transitions: None,
+            })
+            .await
+            .unwrap();
+        let id = engine.start_execution("hung", "INC-3", HashMap::new()).await.unwrap();
+        let failed = wait_for(&engine, &id, ExecutionStatus::Failed).await;
+        assert_eq!(failed.steps[0].status, StepStatus::TimedOut);
+    }
+}
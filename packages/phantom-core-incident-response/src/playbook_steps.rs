@@ -0,0 +1,814 @@
+//! Playbook Step Library
+//!
+//! Built-in actions that make playbooks runnable without custom glue:
+//! `http_request`, `send_webhook`, `enrich_ioc`, `submit_to_sandbox`,
+//! `create_task` and `wait`. Each is registered with a `StepDescriptor`
+//! whose parameter schema is a JSON Schema subset (`type`, `required`,
+//! `properties`, `additionalProperties`, `anyOf`, and per property `type`,
+//! `enum`, `minimum`, `maximum`, `minLength`, `maxLength`, `pattern` and
+//! `format` of `uri` or `date-time`). Parameters are strings, so typed
+//! properties must parse as their type.
+//!
+//! Steps that reach other systems do so through `StepBackends`; a step
+//! whose backend is missing is not registered. Conditional branches and
+//! approval gates change the flow of an execution and are step kinds of the
+//! engine itself (`StepAction::Branch`, `StepAction::Approval`).
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration as StdDuration;
+use uuid::Uuid;
+
+use crate::playbook_executor::ActionHandler;
+
+pub const SIGNATURE_HEADER: &str = "X-Phantom-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Phantom-Timestamp";
+pub const EVENT_HEADER: &str = "X-Phantom-Event";
+/// Response bodies kept in step outputs are cut to this many bytes
+pub const MAX_RESPONSE_OUTPUT: usize = 64 * 1024;
+/// Longest a `wait` step may sleep
+pub const MAX_WAIT_SECS: i64 = 86_400;
+
+// Step Descriptors
+
+/// Name, purpose and parameter schema of a registered action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepDescriptor {
+    pub name: String,
+    pub description: String,
+    /// JSON Schema for the step's parameters
+    pub parameters: Value,
+    /// Output keys, available to later steps as `<step_id>.<key>`
+    #[serde(default)]
+    pub outputs: Vec<String>,
+}
+
+impl StepDescriptor {
+    /// Check the schema uses only keywords the validator understands
+    pub fn check_schema(&self) -> Result<(), String> {
+        if self.parameters.get("type").and_then(Value::as_str) != Some("object") {
+            return Err(format!("Parameter schema of {} must be of type object", self.name));
+        }
+        let properties = self.parameters.get("properties").and_then(Value::as_object).into_iter().flatten();
+        for (name, property) in properties {
+            match property.get("type").and_then(Value::as_str) {
+                None | Some("string" | "integer" | "number" | "boolean" | "object" | "array") => {}
+                Some(other) => return Err(format!("Parameter {} of {} has unsupported type {}", name, self.name, other)),
+            }
+            if let Some(pattern) = property.get("pattern").and_then(Value::as_str) {
+                Regex::new(pattern).map_err(|e| format!("Parameter {} of {} has an invalid pattern: {}", name, self.name, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate parameters against the schema. With `allow_templates`,
+    /// values that reference variables are only checked for presence.
+    pub fn validate(&self, parameters: &HashMap<String, String>, allow_templates: bool) -> Result<(), String> {
+        validate_parameters(&self.parameters, parameters, allow_templates)
+    }
+}
+
+pub fn validate_parameters(schema: &Value, parameters: &HashMap<String, String>, allow_templates: bool) -> Result<(), String> {
+    for required in schema.get("required").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str) {
+        if !parameters.contains_key(required) {
+            return Err(format!("Parameter {} is required", required));
+        }
+    }
+    if let Some(alternatives) = schema.get("anyOf").and_then(Value::as_array) {
+        let errors: Vec<String> = alternatives
+            .iter()
+            .filter_map(|alternative| validate_parameters(alternative, parameters, allow_templates).err())
+            .collect();
+        if errors.len() == alternatives.len() && !errors.is_empty() {
+            return Err(errors.join(", or "));
+        }
+    }
+
+    let properties = schema.get("properties").and_then(Value::as_object);
+    let closed = schema.get("additionalProperties") == Some(&Value::Bool(false));
+    for (name, value) in parameters {
+        match properties.and_then(|properties| properties.get(name)) {
+            Some(_) if allow_templates && TEMPLATE.get_or_init(template_regex).is_match(value) => {}
+            Some(property) => check_property(name, property, value)?,
+            None if closed => return Err(format!("Unknown parameter {}", name)),
+            None => {}
+        }
+    }
+    Ok(())
+}
+
+fn check_property(name: &str, property: &Value, value: &str) -> Result<(), String> {
+    let number = match property.get("type").and_then(Value::as_str) {
+        Some("integer") => Some(value.parse::<i64>().map_err(|_| format!("Parameter {} must be an integer", name))? as f64),
+        Some("number") => Some(value.parse::<f64>().map_err(|_| format!("Parameter {} must be a number", name))?),
+        Some("boolean") if value != "true" && value != "false" => return Err(format!("Parameter {} must be true or false", name)),
+        Some(kind @ ("object" | "array")) => {
+            let parsed: Value = serde_json::from_str(value).map_err(|e| format!("Parameter {} must be JSON: {}", name, e))?;
+            if (kind == "object") != parsed.is_object() || (kind == "array") != parsed.is_array() {
+                return Err(format!("Parameter {} must be a JSON {}", name, kind));
+            }
+            None
+        }
+        _ => None,
+    };
+
+    if let Some(allowed) = property.get("enum").and_then(Value::as_array) {
+        let listed = allowed.iter().any(|option| match option {
+            Value::String(option) => option == value,
+            other => serde_json::from_str::<Value>(value).is_ok_and(|parsed| parsed == *other),
+        });
+        if !listed {
+            return Err(format!("Parameter {} must be one of {}", name, Value::Array(allowed.clone())));
+        }
+    }
+    if let Some(number) = number {
+        if property.get("minimum").and_then(Value::as_f64).is_some_and(|min| number < min) {
+            return Err(format!("Parameter {} must be at least {}", name, property["minimum"]));
+        }
+        if property.get("maximum").and_then(Value::as_f64).is_some_and(|max| number > max) {
+            return Err(format!("Parameter {} must be at most {}", name, property["maximum"]));
+        }
+    }
+    let length = value.chars().count() as u64;
+    if property.get("minLength").and_then(Value::as_u64).is_some_and(|min| length < min) {
+        return Err(format!("Parameter {} must be at least {} characters", name, property["minLength"]));
+    }
+    if property.get("maxLength").and_then(Value::as_u64).is_some_and(|max| length > max) {
+        return Err(format!("Parameter {} must be at most {} characters", name, property["maxLength"]));
+    }
+    if let Some(pattern) = property.get("pattern").and_then(Value::as_str) {
+        let pattern = Regex::new(pattern).map_err(|e| format!("Invalid pattern for {}: {}", name, e))?;
+        if !pattern.is_match(value) {
+            return Err(format!("Parameter {} does not match {}", name, pattern));
+        }
+    }
+    match property.get("format").and_then(Value::as_str) {
+        Some("uri") => {
+            let url = url::Url::parse(value).map_err(|e| format!("Parameter {} must be a URL: {}", name, e))?;
+            if !matches!(url.scheme(), "http" | "https") {
+                return Err(format!("Parameter {} must be an http or https URL", name));
+            }
+        }
+        Some("date-time") => {
+            DateTime::parse_from_rfc3339(value).map_err(|e| format!("Parameter {} must be an RFC 3339 time: {}", name, e))?;
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+// Variable Templates
+
+static TEMPLATE: OnceLock<Regex> = OnceLock::new();
+
+fn template_regex() -> Regex {
+    Regex::new(r"\{\{\s*([A-Za-z0-9_.\-]+)\s*\}\}").expect("template pattern is valid")
+}
+
+/// Replace `{{name}}` in parameter values with execution variables
+pub fn resolve_parameters(parameters: &HashMap<String, String>, variables: &HashMap<String, String>) -> Result<HashMap<String, String>, String> {
+    let template = TEMPLATE.get_or_init(template_regex);
+    parameters
+        .iter()
+        .map(|(name, value)| {
+            let mut resolved = String::with_capacity(value.len());
+            let mut last = 0;
+            for captures in template.captures_iter(value) {
+                let (Some(whole), Some(variable)) = (captures.get(0), captures.get(1)) else {
+                    continue;
+                };
+                let substitute = variables
+                    .get(variable.as_str())
+                    .ok_or_else(|| format!("Parameter {} references unknown variable {}", name, variable.as_str()))?;
+                resolved.push_str(&value[last..whole.start()]);
+                resolved.push_str(substitute);
+                last = whole.end();
+            }
+            resolved.push_str(&value[last..]);
+            Ok((name.clone(), resolved))
+        })
+        .collect()
+}
+
+// Backends
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepHttpRequest {
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepHttpResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub body: String,
+}
+
+/// Sends the HTTP requests of `http_request` and `send_webhook` steps
+#[async_trait]
+pub trait StepHttpClient: Send + Sync {
+    async fn send(&self, request: &StepHttpRequest) -> Result<StepHttpResponse, String>;
+}
+
+#[cfg(feature = "reqwest")]
+pub struct ReqwestStepClient {
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "reqwest")]
+impl ReqwestStepClient {
+    pub fn new(timeout: StdDuration) -> Result<Self, String> {
+        let client = reqwest::Client::builder().timeout(timeout).build().map_err(|e| e.to_string())?;
+        Ok(Self { client })
+    }
+}
+
+#[cfg(feature = "reqwest")]
+#[async_trait]
+impl StepHttpClient for ReqwestStepClient {
+    async fn send(&self, request: &StepHttpRequest) -> Result<StepHttpResponse, String> {
+        let method = reqwest::Method::from_bytes(request.method.as_bytes()).map_err(|e| e.to_string())?;
+        let mut builder = self.client.request(method, &request.url);
+        for (name, value) in &request.headers {
+            builder = builder.header(name, value);
+        }
+        if let Some(body) = &request.body {
+            builder = builder.body(body.clone());
+        }
+        let response = builder.send().await.map_err(|e| e.to_string())?;
+        let status = response.status().as_u16();
+        let body = response.text().await.map_err(|e| e.to_string())?;
+        Ok(StepHttpResponse { status, body })
+    }
+}
+
+/// Looks an indicator up in threat intelligence; outputs become step outputs
+#[async_trait]
+pub trait IocEnricher: Send + Sync {
+    async fn enrich(&self, indicator: &str, indicator_type: &str) -> Result<HashMap<String, String>, String>;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SandboxSubmission {
+    /// File path or URL of the sample
+    pub sample: String,
+    pub file_name: Option<String>,
+    pub priority: String,
+    pub incident_id: Option<String>,
+}
+
+/// Queues samples for detonation and returns the analysis id
+#[async_trait]
+pub trait SandboxSubmitter: Send + Sync {
+    async fn submit(&self, submission: &SandboxSubmission) -> Result<String, String>;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaybookTask {
+    pub task_id: String,
+    pub incident_id: String,
+    pub title: String,
+    pub description: String,
+    pub assignee: Option<String>,
+    pub priority: String,
+    pub due_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Receives the tasks `create_task` steps open
+#[async_trait]
+pub trait TaskSink: Send + Sync {
+    async fn create_task(&self, task: PlaybookTask) -> Result<String, String>;
+}
+
+/// Task sink that keeps tasks in memory
+#[derive(Default)]
+pub struct TaskBoard {
+    tasks: Mutex<Vec<PlaybookTask>>,
+}
+
+impl TaskBoard {
+    pub fn tasks(&self, incident_id: Option<&str>) -> Vec<PlaybookTask> {
+        self.tasks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|task| incident_id.is_none_or(|id| task.incident_id == id))
+            .cloned()
+            .collect()
+    }
+}
+
+#[async_trait]
+impl TaskSink for TaskBoard {
+    async fn create_task(&self, task: PlaybookTask) -> Result<String, String> {
+        let task_id = task.task_id.clone();
+        self.tasks.lock().unwrap_or_else(|e| e.into_inner()).push(task);
+        Ok(task_id)
+    }
+}
+
+/// Systems the built-in steps act on
+#[derive(Clone)]
+pub struct StepBackends {
+    pub http: Option<Arc<dyn StepHttpClient>>,
+    pub enricher: Option<Arc<dyn IocEnricher>>,
+    pub sandbox: Option<Arc<dyn SandboxSubmitter>>,
+    pub tasks: Arc<dyn TaskSink>,
+}
+
+impl StepBackends {
+    /// No external systems; tasks go to `tasks`
+    pub fn local(tasks: Arc<dyn TaskSink>) -> Self {
+        Self { http: None, enricher: None, sandbox: None, tasks }
+    }
+}
+
+// Built-in Steps
+
+/// `http_request`: outputs `status` and `body`; fails on a non-2xx status
+struct HttpRequestStep {
+    client: Arc<dyn StepHttpClient>,
+}
+
+#[async_trait]
+impl ActionHandler for HttpRequestStep {
+    async fn execute(&self, parameters: &HashMap<String, String>, _variables: &HashMap<String, String>) -> Result<HashMap<String, String>, String> {
+        let headers: BTreeMap<String, String> = match parameters.get("headers") {
+            Some(headers) => serde_json::from_str(headers).map_err(|e| format!("Headers must map names to strings: {}", e))?,
+            None => BTreeMap::new(),
+        };
+        let request = StepHttpRequest {
+            method: parameters.get("method").map(|m| m.to_uppercase()).unwrap_or_else(|| "GET".to_string()),
+            url: parameters["url"].clone(),
+            headers,
+            body: parameters.get("body").cloned(),
+        };
+        let response = self.client.send(&request).await?;
+        if !(200..300).contains(&response.status) {
+            return Err(format!("{} {} returned {}", request.method, request.url, response.status));
+        }
+        Ok(HashMap::from([
+            ("status".to_string(), response.status.to_string()),
+            ("body".to_string(), truncate(response.body, MAX_RESPONSE_OUTPUT)),
+        ]))
+    }
+}
+
+fn truncate(mut text: String, max: usize) -> String {
+    if text.len() > max {
+        let mut end = max;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+    }
+    text
+}
+
+/// `send_webhook`: POSTs `{event, sent_at, payload}`, signed when a secret is set
+struct SendWebhookStep {
+    client: Arc<dyn StepHttpClient>,
+}
+
+/// `sha256=<hex>` HMAC over `"{timestamp}.{body}"`, as sandbox webhooks sign
+pub fn sign_webhook(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
+
+#[async_trait]
+impl ActionHandler for SendWebhookStep {
+    async fn execute(&self, parameters: &HashMap<String, String>, _variables: &HashMap<String, String>) -> Result<HashMap<String, String>, String> {
+        let event = parameters.get("event").map(String::as_str).unwrap_or("playbook.notification");
+        let payload: Value = match parameters.get("payload") {
+            Some(payload) => serde_json::from_str(payload).map_err(|e| format!("Invalid payload: {}", e))?,
+            None => json!({}),
+        };
+        let sent_at = Utc::now();
+        let body = json!({ "event": event, "sent_at": sent_at, "payload": payload }).to_string();
+        let mut headers = BTreeMap::from([
+            ("Content-Type".to_string(), "application/json".to_string()),
+            (EVENT_HEADER.to_string(), event.to_string()),
+            (TIMESTAMP_HEADER.to_string(), sent_at.timestamp().to_string()),
+        ]);
+        if let Some(secret) = parameters.get("secret") {
+            headers.insert(SIGNATURE_HEADER.to_string(), sign_webhook(secret, sent_at.timestamp(), &body));
+        }
+        let request = StepHttpRequest { method: "POST".to_string(), url: parameters["url"].clone(), headers, body: Some(body) };
+        let response = self.client.send(&request).await?;
+        if !(200..300).contains(&response.status) {
+            return Err(format!("Webhook {} returned {}", request.url, response.status));
+        }
+        Ok(HashMap::from([("status".to_string(), response.status.to_string())]))
+    }
+}
+
+/// `enrich_ioc`: outputs whatever the enricher reports
+struct EnrichIocStep {
+    enricher: Arc<dyn IocEnricher>,
+}
+
+#[async_trait]
+impl ActionHandler for EnrichIocStep {
+    async fn execute(&self, parameters: &HashMap<String, String>, _variables: &HashMap<String, String>) -> Result<HashMap<String, String>, String> {
+        self.enricher.enrich(&parameters["indicator"], &parameters["indicator_type"]).await
+    }
+}
+
+/// `submit_to_sandbox`: outputs `analysis_id`
+struct SubmitToSandboxStep {
+    sandbox: Arc<dyn SandboxSubmitter>,
+}
+
+#[async_trait]
+impl ActionHandler for SubmitToSandboxStep {
+    async fn execute(&self, parameters: &HashMap<String, String>, variables: &HashMap<String, String>) -> Result<HashMap<String, String>, String> {
+        let submission = SandboxSubmission {
+            sample: parameters["sample"].clone(),
+            file_name: parameters.get("file_name").cloned(),
+            priority: parameters.get("priority").cloned().unwrap_or_else(|| "normal".to_string()),
+            incident_id: parameters.get("incident_id").or_else(|| variables.get("incident_id")).cloned(),
+        };
+        let analysis_id = self.sandbox.submit(&submission).await?;
+        Ok(HashMap::from([("analysis_id".to_string(), analysis_id)]))
+    }
+}
+
+/// `create_task`: outputs `task_id`
+struct CreateTaskStep {
+    tasks: Arc<dyn TaskSink>,
+}
+
+#[async_trait]
+impl ActionHandler for CreateTaskStep {
+    async fn execute(&self, parameters: &HashMap<String, String>, variables: &HashMap<String, String>) -> Result<HashMap<String, String>, String> {
+        let incident_id = parameters.get("incident_id").or_else(|| variables.get("incident_id")).ok_or("incident_id is required")?;
+        let now = Utc::now();
+        let due_in_minutes: Option<i64> = parameters.get("due_in_minutes").and_then(|minutes| minutes.parse().ok());
+        let task = PlaybookTask {
+            task_id: Uuid::new_v4().to_string(),
+            incident_id: incident_id.clone(),
+            title: parameters["title"].clone(),
+            description: parameters.get("description").cloned().unwrap_or_default(),
+            assignee: parameters.get("assignee").cloned(),
+            priority: parameters.get("priority").cloned().unwrap_or_else(|| "medium".to_string()),
+            due_at: due_in_minutes.map(|minutes| now + Duration::minutes(minutes)),
+            created_at: now,
+        };
+        let task_id = self.tasks.create_task(task).await?;
+        Ok(HashMap::from([("task_id".to_string(), task_id)]))
+    }
+}
+
+/// `wait`: sleeps for `seconds` or until `until`; outputs `waited_secs`
+struct WaitStep;
+
+#[async_trait]
+impl ActionHandler for WaitStep {
+    async fn execute(&self, parameters: &HashMap<String, String>, _variables: &HashMap<String, String>) -> Result<HashMap<String, String>, String> {
+        let seconds = match (parameters.get("seconds"), parameters.get("until")) {
+            (Some(seconds), _) => seconds.parse::<i64>().map_err(|e| format!("Invalid seconds: {}", e))?,
+            (None, Some(until)) => {
+                let until = DateTime::parse_from_rfc3339(until).map_err(|e| format!("Invalid until: {}", e))?;
+                (until.with_timezone(&Utc) - Utc::now()).num_seconds().max(0)
+            }
+            (None, None) => return Err("Set seconds or until".to_string()),
+        };
+        if seconds > MAX_WAIT_SECS {
+            return Err(format!("Cannot wait longer than {} seconds", MAX_WAIT_SECS));
+        }
+        tokio::time::sleep(StdDuration::from_secs(seconds as u64)).await;
+        Ok(HashMap::from([("waited_secs".to_string(), seconds.to_string())]))
+    }
+}
+
+fn descriptor(name: &str, description: &str, parameters: Value, outputs: &[&str]) -> StepDescriptor {
+    StepDescriptor {
+        name: name.to_string(),
+        description: description.to_string(),
+        parameters,
+        outputs: outputs.iter().map(|output| output.to_string()).collect(),
+    }
+}
+
+/// The built-in steps that `backends` can serve
+pub fn builtin_steps(backends: &StepBackends) -> Vec<(StepDescriptor, Arc<dyn ActionHandler>)> {
+    let priority = json!({ "type": "string", "enum": ["low", "medium", "high", "critical"] });
+    let mut steps: Vec<(StepDescriptor, Arc<dyn ActionHandler>)> = vec![
+        (
+            descriptor(
+                "create_task",
+                "Open a task on the incident",
+                json!({
+                    "type": "object",
+                    "required": ["title"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "title": { "type": "string", "minLength": 1, "maxLength": 200 },
+                        "description": { "type": "string" },
+                        "assignee": { "type": "string" },
+                        "priority": priority,
+                        "due_in_minutes": { "type": "integer", "minimum": 1 },
+                        "incident_id": { "type": "string" }
+                    }
+                }),
+                &["task_id"],
+            ),
+            Arc::new(CreateTaskStep { tasks: backends.tasks.clone() }),
+        ),
+        (
+            descriptor(
+                "wait",
+                "Pause the playbook for a number of seconds or until a time",
+                json!({
+                    "type": "object",
+                    "additionalProperties": false,
+                    "anyOf": [{ "required": ["seconds"] }, { "required": ["until"] }],
+                    "properties": {
+                        "seconds": { "type": "integer", "minimum": 0, "maximum": MAX_WAIT_SECS },
+                        "until": { "type": "string", "format": "date-time" }
+                    }
+                }),
+                &["waited_secs"],
+            ),
+            Arc::new(WaitStep),
+        ),
+    ];
+
+    if let Some(client) = &backends.http {
+        steps.push((
+            descriptor(
+                "http_request",
+                "Call an HTTP API; non-2xx responses fail the step",
+                json!({
+                    "type": "object",
+                    "required": ["url"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "url": { "type": "string", "format": "uri" },
+                        "method": { "type": "string", "enum": ["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD"] },
+                        "headers": { "type": "object" },
+                        "body": { "type": "string" }
+                    }
+                }),
+                &["status", "body"],
+            ),
+            Arc::new(HttpRequestStep { client: client.clone() }),
+        ));
+        steps.push((
+            descriptor(
+                "send_webhook",
+                "POST a JSON event, HMAC-signed when a secret is set",
+                json!({
+                    "type": "object",
+                    "required": ["url"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "url": { "type": "string", "format": "uri" },
+                        "event": { "type": "string", "minLength": 1 },
+                        "payload": { "type": "object" },
+                        "secret": { "type": "string", "minLength": 16 }
+                    }
+                }),
+                &["status"],
+            ),
+            Arc::new(SendWebhookStep { client: client.clone() }),
+        ));
+    }
+    if let Some(enricher) = &backends.enricher {
+        steps.push((
+            descriptor(
+                "enrich_ioc",
+                "Look an indicator up in threat intelligence",
+                json!({
+                    "type": "object",
+                    "required": ["indicator", "indicator_type"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "indicator": { "type": "string", "minLength": 1 },
+                        "indicator_type": { "type": "string", "enum": ["ip", "domain", "url", "hash", "email"] }
+                    }
+                }),
+                &[],
+            ),
+            Arc::new(EnrichIocStep { enricher: enricher.clone() }),
+        ));
+    }
+    if let Some(sandbox) = &backends.sandbox {
+        steps.push((
+            descriptor(
+                "submit_to_sandbox",
+                "Queue a file or URL for sandbox detonation",
+                json!({
+                    "type": "object",
+                    "required": ["sample"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "sample": { "type": "string", "minLength": 1 },
+                        "file_name": { "type": "string" },
+                        "priority": { "type": "string", "enum": ["low", "normal", "high", "critical"] },
+                        "incident_id": { "type": "string" }
+                    }
+                }),
+                &["analysis_id"],
+            ),
+            Arc::new(SubmitToSandboxStep { sandbox: sandbox.clone() }),
+        ));
+    }
+    steps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playbook_executor::{
+        ApprovalDecision, BranchCase, ConditionOperator, EngineExecution, ExecutionStatus, PlaybookDefinition, PlaybookEngine, StepAction,
+        StepCondition, StepDefinition, StepStatus,
+    };
+
+    #[derive(Default)]
+    struct RecordingClient {
+        requests: Mutex<Vec<StepHttpRequest>>,
+    }
+
+    #[async_trait]
+    impl StepHttpClient for RecordingClient {
+        async fn send(&self, request: &StepHttpRequest) -> Result<StepHttpResponse, String> {
+            self.requests.lock().unwrap().push(request.clone());
+            Ok(StepHttpResponse { status: 200, body: r#"{"ok":true}"#.to_string() })
+        }
+    }
+
+    struct StaticEnricher;
+
+    #[async_trait]
+    impl IocEnricher for StaticEnricher {
+        async fn enrich(&self, indicator: &str, _indicator_type: &str) -> Result<HashMap<String, String>, String> {
+            let verdict = if indicator == "203.0.113.9" { "malicious" } else { "clean" };
+            Ok(HashMap::from([("verdict".to_string(), verdict.to_string())]))
+        }
+    }
+
+    fn step(step_id: &str, action: StepAction) -> StepDefinition {
+        StepDefinition {
+            step_id: step_id.to_string(),
+            name: step_id.to_string(),
+            action,
+            condition: None,
+            on_success: None,
+            on_failure: None,
+            timeout_secs: None,
+            retries: 0,
+            retry_backoff_ms: 0,
+        }
+    }
+
+    fn action(name: &str, parameters: &[(&str, &str)]) -> StepAction {
+        StepAction::Action {
+            name: name.to_string(),
+            parameters: parameters.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+        }
+    }
+
+    async fn wait_until<F: Fn(&EngineExecution) -> bool>(engine: &PlaybookEngine, execution_id: &str, done: F) -> EngineExecution {
+        for _ in 0..500 {
+            let execution = engine.get_execution(execution_id).await.unwrap();
+            if done(&execution) {
+                return execution;
+            }
+            tokio::time::sleep(StdDuration::from_millis(5)).await;
+        }
+        panic!("execution never reached the expected state");
+    }
+
+    #[test]
+    fn test_schema_validation_and_templates() {
+        let steps = builtin_steps(&StepBackends::local(Arc::new(TaskBoard::default())));
+        let names: Vec<&str> = steps.iter().map(|(d, _)| d.name.as_str()).collect();
+        assert_eq!(names, ["create_task", "wait"]);
+        assert!(steps.iter().all(|(d, _)| d.check_schema().is_ok()));
+
+        let params = |pairs: &[(&str, &str)]| -> HashMap<String, String> { pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect() };
+        let wait = &steps[1].0;
+        assert!(wait.validate(&params(&[("seconds", "30")]), false).is_ok());
+        assert!(wait.validate(&params(&[("until", "2026-03-01T10:00:00Z")]), false).is_ok());
+        assert!(wait.validate(&params(&[]), false).is_err());
+        assert!(wait.validate(&params(&[("seconds", "soon")]), false).unwrap_err().contains("integer"));
+        assert!(wait.validate(&params(&[("seconds", "90000")]), false).unwrap_err().contains("at most"));
+        assert!(wait.validate(&params(&[("seconds", "{{delay}}")]), true).is_ok());
+        assert!(wait.validate(&params(&[("seconds", "{{delay}}")]), false).is_err());
+
+        let task = &steps[0].0;
+        assert!(task.validate(&params(&[("title", "Reset password"), ("priority", "urgent")]), false).unwrap_err().contains("one of"));
+        assert!(task.validate(&params(&[("title", "Reset password"), ("owner", "ana")]), false).unwrap_err().contains("Unknown parameter"));
+
+        let variables = params(&[("host", "ws-042"), ("scan.verdict", "malicious")]);
+        let resolved = resolve_parameters(&params(&[("title", "Isolate {{ host }} ({{scan.verdict}})")]), &variables).unwrap();
+        assert_eq!(resolved["title"], "Isolate ws-042 (malicious)");
+        assert!(resolve_parameters(&params(&[("title", "{{missing}}")]), &variables).unwrap_err().contains("missing"));
+    }
+
+    #[tokio::test]
+    async fn test_builtin_steps_branch_and_approval() {
+        let client = Arc::new(RecordingClient::default());
+        let board = Arc::new(TaskBoard::default());
+        let backends = StepBackends { http: Some(client.clone()), enricher: Some(Arc::new(StaticEnricher)), sandbox: None, tasks: board.clone() };
+        let engine = PlaybookEngine::with_builtin_steps(&backends);
+        assert_eq!(engine.list_steps().await.len(), 5);
+
+        let mut triage = step(
+            "triage",
+            StepAction::Branch {
+                cases: vec![BranchCase {
+                    condition: StepCondition {
+                        variable: "enrich.verdict".to_string(),
+                        operator: ConditionOperator::Equals,
+                        value: Some("malicious".to_string()),
+                    },
+                    next: "approve".to_string(),
+                }],
+                default: Some("close".to_string()),
+            },
+        );
+        triage.on_success = Some("close".to_string());
+        let mut approve = step("approve", StepAction::Approval { prompt: "Block the address at the edge?".to_string(), approvers: vec!["lead".to_string()] });
+        approve.on_failure = Some("close".to_string());
+        let playbook = PlaybookDefinition {
+            playbook_id: "ip-triage".to_string(),
+            name: "IP triage".to_string(),
+            description: String::new(),
+            steps: vec![
+                step("enrich", action("enrich_ioc", &[("indicator", "{{ip}}"), ("indicator_type", "ip")])),
+                triage,
+                approve,
+                step("task", action("create_task", &[("title", "Block {{ip}} at the edge"), ("priority", "high"), ("incident_id", "{{incident}}")])),
+                step("notify", action("send_webhook", &[("url", "https://hooks.example/soc"), ("payload", r#"{"ip":"{{ip}}"}"#), ("secret", "0123456789abcdef")])),
+                step("close", StepAction::End),
+            ],
+            entry_step: None,
+            default_timeout_secs: Some(5),
+            max_transitions: None,
+        };
+
+        let mut invalid = playbook.clone();
+        invalid.steps[0] = step("enrich", action("enrich_ioc", &[("indicator", "{{ip}}"), ("indicator_type", "asn")]));
+        assert!(engine.register_playbook(invalid).await.unwrap_err().contains("enrich"));
+        engine.register_playbook(playbook).await.unwrap();
+
+        let variables = |ip: &str| HashMap::from([("ip".to_string(), ip.to_string()), ("incident".to_string(), "INC-7".to_string())]);
+        let clean = engine.start_execution("ip-triage", "INC-7", variables("198.51.100.4")).await.unwrap();
+        let clean = wait_until(&engine, &clean, |e| e.status == ExecutionStatus::Completed).await;
+        assert_eq!(clean.path, ["enrich", "triage", "close"]);
+
+        let id = engine.start_execution("ip-triage", "INC-7", variables("203.0.113.9")).await.unwrap();
+        while engine.pending_approvals(Some("INC-7")).is_empty() {
+            tokio::time::sleep(StdDuration::from_millis(1)).await;
+        }
+        let analyst = ApprovalDecision { approver: "analyst".to_string(), approved: true, comment: String::new() };
+        assert!(engine.decide_approval(&id, analyst).is_err());
+        let lead = ApprovalDecision { approver: "lead".to_string(), approved: true, comment: "go".to_string() };
+        assert_eq!(engine.decide_approval(&id, lead).unwrap().step_id, "approve");
+
+        let execution = wait_until(&engine, &id, |e| e.status.is_terminal()).await;
+        assert_eq!(execution.status, ExecutionStatus::Completed, "{:?}", execution.error);
+        assert_eq!(execution.path, ["enrich", "triage", "approve", "task", "notify", "close"]);
+        assert_eq!(execution.variables["approve.approved_by"], "lead");
+        let tasks = board.tasks(Some("INC-7"));
+        assert_eq!((tasks[0].title.as_str(), tasks[0].priority.as_str()), ("Block 203.0.113.9 at the edge", "high"));
+        assert_eq!(execution.variables["task.task_id"], tasks[0].task_id);
+
+        let webhook = client.requests.lock().unwrap()[0].clone();
+        let body = webhook.body.unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&body).unwrap()["payload"]["ip"], "203.0.113.9");
+        let timestamp: i64 = webhook.headers[TIMESTAMP_HEADER].parse().unwrap();
+        assert_eq!(webhook.headers[SIGNATURE_HEADER], sign_webhook("0123456789abcdef", timestamp, &body));
+
+        // A rejection fails the approval step and follows its failure edge
+        let id = engine.start_execution("ip-triage", "INC-8", variables("203.0.113.9")).await.unwrap();
+        while engine.pending_approvals(Some("INC-8")).is_empty() {
+            tokio::time::sleep(StdDuration::from_millis(1)).await;
+        }
+        engine.decide_approval(&id, ApprovalDecision { approver: "lead".to_string(), approved: false, comment: "false positive".to_string() }).unwrap();
+        let rejected = wait_until(&engine, &id, |e| e.status.is_terminal()).await;
+        assert_eq!(rejected.path, ["enrich", "triage", "approve", "close"]);
+        assert_eq!(rejected.steps[2].status, StepStatus::Failed);
+        assert!(engine.pending_approvals(None).is_empty());
+    }
+}
@@ -0,0 +1,599 @@
+//! Threat actor knowledge base and attribution
+//!
+//! Actor profiles record aliases, ATT&CK techniques, malware families,
+//! infrastructure patterns and targeted sectors; campaigns tie a period of
+//! activity, its techniques and exact indicators to an actor. The knowledge
+//! base is shared by all tenants.
+//!
+//! Attribution compares a completed analysis with every profile and ranks
+//! the actors as hypotheses, each listing the evidence behind it. Evidence
+//! items combine as independent signals (noisy-OR), so no single weak match
+//! dominates: technique overlap is weighted by how distinctive each
+//! technique is across the profiles, while a malware family, an
+//! infrastructure pattern or a campaign indicator hit is strong on its own.
+//! While the knowledge base holds profiles, new analyses carry their
+//! hypotheses in `threat_intelligence`.
+
+use chrono::{DateTime, Utc};
+use napi_derive::napi;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::net::IpAddr;
+
+use crate::campaign_graph::{ioc_node, IocNodeKind};
+use crate::mitre::normalize_technique_id;
+use crate::error::{CoreError, CoreResult};
+use crate::{SandboxAnalysis, SandboxCore, SandboxCoreNapi};
+
+/// Weight of a perfect technique overlap
+const TECHNIQUE_WEIGHT: f64 = 0.6;
+const MALWARE_FAMILY_WEIGHT: f64 = 0.5;
+const INFRASTRUCTURE_WEIGHT: f64 = 0.4;
+const CAMPAIGN_INDICATOR_WEIGHT: f64 = 0.5;
+const SECTOR_WEIGHT: f64 = 0.1;
+
+/// Infrastructure an actor is known to use. Domains match exactly or by
+/// glob (`*.example.net`), IPs exactly or by CIDR, hashes exactly
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InfrastructurePattern {
+    pub kind: IocNodeKind,
+    pub pattern: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActorProfile {
+    pub actor_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub description: String,
+    /// ATT&CK technique ids, e.g. `T1566.001`
+    #[serde(default)]
+    pub techniques: Vec<String>,
+    #[serde(default)]
+    pub malware_families: Vec<String>,
+    #[serde(default)]
+    pub infrastructure: Vec<InfrastructurePattern>,
+    #[serde(default)]
+    pub sectors: Vec<String>,
+    #[serde(default)]
+    pub regions: Vec<String>,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Campaign {
+    pub campaign_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub actor_id: Option<String>,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub techniques: Vec<String>,
+    /// Domains, IPs and hashes observed in the campaign
+    #[serde(default)]
+    pub indicators: Vec<String>,
+    #[serde(default)]
+    pub first_seen: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_seen: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttributionQuery {
+    /// Hypotheses scoring below this are dropped
+    #[serde(default = "default_min_score")]
+    pub min_score: f64,
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+    /// Sector of the targeted organisation, matched against actor sectors
+    #[serde(default)]
+    pub sector: Option<String>,
+}
+
+fn default_min_score() -> f64 {
+    0.2
+}
+
+fn default_limit() -> usize {
+    5
+}
+
+impl Default for AttributionQuery {
+    fn default() -> Self {
+        Self { min_score: default_min_score(), limit: default_limit(), sector: None }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvidenceKind {
+    Technique,
+    MalwareFamily,
+    Infrastructure,
+    CampaignIndicator,
+    Sector,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttributionEvidence {
+    pub kind: EvidenceKind,
+    /// What matched, e.g. `T1071.001` or `cdn.badhost.net ~ *.badhost.net`
+    pub detail: String,
+    pub weight: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttributionHypothesis {
+    pub actor_id: String,
+    pub actor_name: String,
+    pub score: f64,
+    /// Campaigns of the actor whose indicators or techniques were observed
+    pub campaigns: Vec<String>,
+    pub evidence: Vec<AttributionEvidence>,
+}
+
+/// Actor profiles and campaigns
+#[derive(Debug, Default)]
+pub struct ActorKnowledgeBase {
+    actors: BTreeMap<String, ActorProfile>,
+    campaigns: BTreeMap<String, Campaign>,
+}
+
+fn normalize_techniques(techniques: &[String]) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = techniques
+        .iter()
+        .map(|t| normalize_technique_id(t).ok_or_else(|| format!("Invalid ATT&CK technique id '{}'", t)))
+        .collect::<Result<_, _>>()?;
+    normalized.sort();
+    normalized.dedup();
+    Ok(normalized)
+}
+
+/// Parent technique of a sub-technique, the id itself otherwise
+fn base_technique(technique: &str) -> &str {
+    technique.split('.').next().unwrap_or(technique)
+}
+
+fn cidr_contains(cidr: &str, ip: IpAddr) -> Option<bool> {
+    let (network, prefix) = cidr.split_once('/')?;
+    let network: IpAddr = network.trim().parse().ok()?;
+    let prefix: u32 = prefix.trim().parse().ok()?;
+    let (network, ip, bits) = match (network, ip) {
+        (IpAddr::V4(network), IpAddr::V4(ip)) => (u32::from(network) as u128, u32::from(ip) as u128, 32),
+        (IpAddr::V6(network), IpAddr::V6(ip)) => (u128::from(network), u128::from(ip), 128),
+        _ => return Some(false),
+    };
+    if prefix > bits {
+        return None;
+    }
+    let shift = bits - prefix;
+    Some(shift == bits || (network >> shift) == (ip >> shift))
+}
+
+fn glob(pattern: &str) -> Option<Regex> {
+    let escaped = regex::escape(&pattern.to_ascii_lowercase()).replace(r"\*", ".*").replace(r"\?", ".");
+    Regex::new(&format!("^{}$", escaped)).ok()
+}
+
+impl InfrastructurePattern {
+    fn validate(&self) -> Result<(), String> {
+        match self.kind {
+            IocNodeKind::Ip if self.pattern.contains('/') => cidr_contains(&self.pattern, IpAddr::from([0, 0, 0, 0]))
+                .map(|_| ())
+                .ok_or_else(|| format!("Invalid CIDR '{}'", self.pattern)),
+            IocNodeKind::Ip => self.pattern.trim().parse::<IpAddr>().map(|_| ()).map_err(|_| format!("Invalid IP '{}'", self.pattern)),
+            _ if self.pattern.trim().is_empty() => Err("Infrastructure pattern must not be empty".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    fn matches(&self, kind: IocNodeKind, value: &str) -> bool {
+        if kind != self.kind {
+            return false;
+        }
+        match kind {
+            IocNodeKind::Ip if self.pattern.contains('/') => {
+                value.parse().ok().and_then(|ip| cidr_contains(&self.pattern, ip)).unwrap_or(false)
+            }
+            IocNodeKind::Domain if self.pattern.contains(['*', '?']) => glob(&self.pattern).is_some_and(|re| re.is_match(value)),
+            _ => self.pattern.trim().eq_ignore_ascii_case(value),
+        }
+    }
+}
+
+impl ActorKnowledgeBase {
+    pub fn save_actor(&mut self, mut actor: ActorProfile) -> Result<ActorProfile, String> {
+        if actor.actor_id.trim().is_empty() || actor.name.trim().is_empty() {
+            return Err("Actor id and name are required".to_string());
+        }
+        actor.techniques = normalize_techniques(&actor.techniques)?;
+        actor.infrastructure.iter().try_for_each(InfrastructurePattern::validate)?;
+        actor.updated_at = Utc::now();
+        self.actors.insert(actor.actor_id.clone(), actor.clone());
+        Ok(actor)
+    }
+
+    /// Remove an actor; its campaigns stay but lose their attribution
+    pub fn remove_actor(&mut self, actor_id: &str) -> bool {
+        for campaign in self.campaigns.values_mut().filter(|c| c.actor_id.as_deref() == Some(actor_id)) {
+            campaign.actor_id = None;
+        }
+        self.actors.remove(actor_id).is_some()
+    }
+
+    /// Actor by id, name or alias, ignoring case
+    pub fn find_actor(&self, reference: &str) -> Option<&ActorProfile> {
+        let reference = reference.trim();
+        self.actors.get(reference).or_else(|| {
+            self.actors.values().find(|actor| {
+                actor.name.eq_ignore_ascii_case(reference) || actor.aliases.iter().any(|alias| alias.eq_ignore_ascii_case(reference))
+            })
+        })
+    }
+
+    pub fn actors(&self) -> Vec<ActorProfile> {
+        self.actors.values().cloned().collect()
+    }
+
+    pub fn save_campaign(&mut self, mut campaign: Campaign) -> Result<Campaign, String> {
+        if campaign.campaign_id.trim().is_empty() || campaign.name.trim().is_empty() {
+            return Err("Campaign id and name are required".to_string());
+        }
+        if let Some(actor_id) = &campaign.actor_id {
+            if !self.actors.contains_key(actor_id) {
+                return Err(format!("Actor {} not found", actor_id));
+            }
+        }
+        campaign.techniques = normalize_techniques(&campaign.techniques)?;
+        campaign.indicators = campaign.indicators.iter().map(|i| i.trim().to_ascii_lowercase()).filter(|i| !i.is_empty()).collect();
+        self.campaigns.insert(campaign.campaign_id.clone(), campaign.clone());
+        Ok(campaign)
+    }
+
+    pub fn remove_campaign(&mut self, campaign_id: &str) -> bool {
+        self.campaigns.remove(campaign_id).is_some()
+    }
+
+    pub fn campaigns(&self, actor_id: Option<&str>) -> Vec<Campaign> {
+        self.campaigns
+            .values()
+            .filter(|campaign| actor_id.is_none() || campaign.actor_id.as_deref() == actor_id)
+            .cloned()
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actors.is_empty()
+    }
+
+    /// Rank the actors whose profile the analysis matches
+    pub fn attribute(&self, analysis: &SandboxAnalysis, query: &AttributionQuery) -> Vec<AttributionHypothesis> {
+        let observed_techniques: BTreeSet<String> = analysis
+            .mitre_techniques
+            .iter()
+            .filter_map(|t| normalize_technique_id(&t.technique_id))
+            .collect();
+        let sample = &analysis.sample_info;
+        let mut indicators: BTreeSet<(IocNodeKind, String)> = analysis
+            .iocs_extracted
+            .iter()
+            .filter_map(|ioc| ioc_node(&ioc.ioc_type, &ioc.value))
+            .collect();
+        for hash in [&sample.file_hash_sha256, &sample.file_hash_sha1, &sample.file_hash_md5] {
+            indicators.extend(ioc_node("hash", hash));
+        }
+        let families: Vec<String> = analysis.malware_classification.family.iter().chain(&analysis.threat_intelligence.malware_families).cloned().collect();
+
+        // A technique few actors use says more than one they all share
+        let distinctiveness = |technique: &str| {
+            let users = self.actors.values().filter(|a| a.techniques.iter().any(|t| base_technique(t) == base_technique(technique))).count();
+            (1.0 + self.actors.len() as f64 / (1 + users) as f64).ln()
+        };
+        let observed_weight: f64 = observed_techniques.iter().map(|t| distinctiveness(t)).sum();
+
+        let mut hypotheses: Vec<AttributionHypothesis> = self
+            .actors
+            .values()
+            .filter_map(|actor| {
+                let mut evidence = Vec::new();
+                let mut campaigns = BTreeSet::new();
+
+                if observed_weight > 0.0 {
+                    let matched: Vec<&String> = observed_techniques
+                        .iter()
+                        .filter(|t| actor.techniques.iter().any(|known| known == *t || base_technique(known) == base_technique(t)))
+                        .collect();
+                    let overlap: f64 = matched.iter().map(|t| distinctiveness(t)).sum::<f64>() / observed_weight;
+                    if !matched.is_empty() {
+                        let detail = matched.iter().map(|t| t.as_str()).collect::<Vec<_>>().join(", ");
+                        evidence.push(AttributionEvidence { kind: EvidenceKind::Technique, detail, weight: TECHNIQUE_WEIGHT * overlap });
+                    }
+                }
+                for family in families.iter().filter(|f| actor.malware_families.iter().any(|known| known.eq_ignore_ascii_case(f))) {
+                    evidence.push(AttributionEvidence { kind: EvidenceKind::MalwareFamily, detail: family.clone(), weight: MALWARE_FAMILY_WEIGHT });
+                }
+                for (kind, value) in &indicators {
+                    if let Some(pattern) = actor.infrastructure.iter().find(|p| p.matches(*kind, value)) {
+                        let detail = if pattern.pattern.eq_ignore_ascii_case(value) { value.clone() } else { format!("{} ~ {}", value, pattern.pattern) };
+                        evidence.push(AttributionEvidence { kind: EvidenceKind::Infrastructure, detail, weight: INFRASTRUCTURE_WEIGHT });
+                    }
+                }
+                for campaign in self.campaigns.values().filter(|c| c.actor_id.as_deref() == Some(actor.actor_id.as_str())) {
+                    for (_, value) in indicators.iter().filter(|(_, value)| campaign.indicators.contains(value)) {
+                        evidence.push(AttributionEvidence {
+                            kind: EvidenceKind::CampaignIndicator,
+                            detail: format!("{} ({})", value, campaign.name),
+                            weight: CAMPAIGN_INDICATOR_WEIGHT,
+                        });
+                        campaigns.insert(campaign.name.clone());
+                    }
+                    if !campaign.techniques.is_empty() && campaign.techniques.iter().all(|t| observed_techniques.contains(t)) {
+                        campaigns.insert(campaign.name.clone());
+                    }
+                }
+                if let Some(sector) = &query.sector {
+                    if actor.sectors.iter().any(|s| s.eq_ignore_ascii_case(sector)) && !evidence.is_empty() {
+                        evidence.push(AttributionEvidence { kind: EvidenceKind::Sector, detail: sector.clone(), weight: SECTOR_WEIGHT });
+                    }
+                }
+
+                let score = 1.0 - evidence.iter().map(|e| 1.0 - e.weight.clamp(0.0, 1.0)).product::<f64>();
+                (score >= query.min_score && score > 0.0).then(|| AttributionHypothesis {
+                    actor_id: actor.actor_id.clone(),
+                    actor_name: actor.name.clone(),
+                    score: (score * 1000.0).round() / 1000.0,
+                    campaigns: campaigns.into_iter().collect(),
+                    evidence,
+                })
+            })
+            .collect();
+        hypotheses.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.actor_id.cmp(&b.actor_id)));
+        hypotheses.truncate(query.limit);
+        hypotheses
+    }
+}
+
+impl SandboxCore {
+    /// Replace the placeholder actor attribution of a new analysis with the
+    /// knowledge base's hypotheses
+    pub(crate) async fn apply_attribution(&self, analysis: &mut SandboxAnalysis) {
+        let actors = self.actors.read().await;
+        if actors.is_empty() {
+            return;
+        }
+        let hypotheses = actors.attribute(analysis, &AttributionQuery::default());
+        let intelligence = &mut analysis.threat_intelligence;
+        intelligence.threat_actors = hypotheses.iter().map(|h| h.actor_name.clone()).collect();
+        intelligence.campaigns = hypotheses.iter().flat_map(|h| h.campaigns.iter().cloned()).collect();
+        intelligence.attribution_confidence = hypotheses.first().map_or(0.0, |h| h.score);
+        intelligence.attribution = hypotheses;
+    }
+
+    pub async fn save_actor_profile(&self, actor: ActorProfile) -> Result<ActorProfile, String> {
+        self.actors.write().await.save_actor(actor)
+    }
+
+    pub async fn remove_actor_profile(&self, actor_id: &str) -> bool {
+        self.actors.write().await.remove_actor(actor_id)
+    }
+
+    pub async fn find_actor_profile(&self, reference: &str) -> Option<ActorProfile> {
+        self.actors.read().await.find_actor(reference).cloned()
+    }
+
+    pub async fn list_actor_profiles(&self) -> Vec<ActorProfile> {
+        self.actors.read().await.actors()
+    }
+
+    pub async fn save_campaign(&self, campaign: Campaign) -> Result<Campaign, String> {
+        self.actors.write().await.save_campaign(campaign)
+    }
+
+    pub async fn remove_campaign(&self, campaign_id: &str) -> bool {
+        self.actors.write().await.remove_campaign(campaign_id)
+    }
+
+    pub async fn list_campaigns(&self, actor_id: Option<&str>) -> Vec<Campaign> {
+        self.actors.read().await.campaigns(actor_id)
+    }
+
+    /// Attribution hypotheses for one of the tenant's analyses
+    pub async fn attribute_analysis(&self, tenant_id: &str, sample_id: &str, query: &AttributionQuery) -> CoreResult<Vec<AttributionHypothesis>> {
+        let analysis = self
+            .get_analysis(tenant_id, sample_id)
+            .await?
+            .ok_or_else(|| CoreError::not_found(format!("Analysis for sample {} not found", sample_id)))?;
+        Ok(self.actors.read().await.attribute(&analysis, query))
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// Create or replace a threat actor profile
+    #[napi]
+    pub async fn save_actor_profile(&self, actor_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor: ActorProfile = serde_json::from_str(&actor_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse actor profile: {}", e)))?;
+        let actor_id = actor.actor_id.clone();
+        let operator = self.authorize(auth_token, "rule:manage", &actor_id)?;
+        let saved = self.inner.save_actor_profile(actor).await;
+        let saved = self.audit.record(&operator, "save_actor_profile", &actor_id, serde_json::json!({ "actor": actor_json }), saved)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to save actor profile: {}", e)))?;
+        serde_json::to_string(&saved)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize actor profile: {}", e)))
+    }
+
+    #[napi]
+    pub async fn remove_actor_profile(&self, actor_id: String, auth_token: Option<String>) -> napi::Result<bool> {
+        let operator = self.authorize(auth_token, "rule:manage", &actor_id)?;
+        let removed = self.inner.remove_actor_profile(&actor_id).await;
+        self.audit.record(&operator, "remove_actor_profile", &actor_id, serde_json::json!({ "removed": removed }), Ok::<_, String>(removed))
+            .map_err(napi::Error::from_reason)
+    }
+
+    /// Look an actor up by id, name or alias
+    #[napi]
+    pub async fn get_actor_profile(&self, reference: String) -> napi::Result<Option<String>> {
+        self.inner
+            .find_actor_profile(&reference)
+            .await
+            .map(|actor| serde_json::to_string(&actor))
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize actor profile: {}", e)))
+    }
+
+    #[napi]
+    pub async fn list_actor_profiles(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.list_actor_profiles().await)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize actor profiles: {}", e)))
+    }
+
+    #[napi]
+    pub async fn save_campaign(&self, campaign_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let campaign: Campaign = serde_json::from_str(&campaign_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse campaign: {}", e)))?;
+        let campaign_id = campaign.campaign_id.clone();
+        let operator = self.authorize(auth_token, "rule:manage", &campaign_id)?;
+        let saved = self.inner.save_campaign(campaign).await;
+        let saved = self.audit.record(&operator, "save_campaign", &campaign_id, serde_json::json!({ "campaign": campaign_json }), saved)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to save campaign: {}", e)))?;
+        serde_json::to_string(&saved)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize campaign: {}", e)))
+    }
+
+    #[napi]
+    pub async fn remove_campaign(&self, campaign_id: String, auth_token: Option<String>) -> napi::Result<bool> {
+        let operator = self.authorize(auth_token, "rule:manage", &campaign_id)?;
+        let removed = self.inner.remove_campaign(&campaign_id).await;
+        self.audit.record(&operator, "remove_campaign", &campaign_id, serde_json::json!({ "removed": removed }), Ok::<_, String>(removed))
+            .map_err(napi::Error::from_reason)
+    }
+
+    #[napi]
+    pub async fn list_campaigns(&self, actor_id: Option<String>) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.list_campaigns(actor_id.as_deref()).await)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize campaigns: {}", e)))
+    }
+
+    /// Ranked attribution hypotheses with their evidence, e.g.
+    /// `{"min_score": 0.1, "sector": "Financial Services"}`
+    #[napi]
+    pub async fn attribute_analysis(&self, sample_id: String, query_json: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let query: AttributionQuery = match query_json {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| napi::Error::from_reason(format!("Failed to parse attribution query: {}", e)))?,
+            None => AttributionQuery::default(),
+        };
+        let hypotheses = self.inner.attribute_analysis(&tenant_id, &sample_id, &query).await
+            .map_err(|e| e.context("Failed to attribute analysis"))?;
+        serde_json::to_string(&hypotheses)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize attribution: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AnalysisPriority, ExtractedIOC, MITRETechnique};
+
+    fn actor(id: &str, techniques: &[&str], families: &[&str], infrastructure: &[(IocNodeKind, &str)]) -> ActorProfile {
+        ActorProfile {
+            actor_id: id.to_string(),
+            name: id.to_uppercase(),
+            aliases: vec![format!("{} group", id)],
+            description: String::new(),
+            techniques: techniques.iter().map(|t| t.to_string()).collect(),
+            malware_families: families.iter().map(|f| f.to_string()).collect(),
+            infrastructure: infrastructure
+                .iter()
+                .map(|(kind, pattern)| InfrastructurePattern { kind: *kind, pattern: pattern.to_string(), description: String::new() })
+                .collect(),
+            sectors: vec!["Energy".to_string()],
+            regions: Vec::new(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn ioc(ioc_type: &str, value: &str) -> ExtractedIOC {
+        ExtractedIOC {
+            ioc_type: ioc_type.to_string(),
+            value: value.to_string(),
+            category: "Network".to_string(),
+            confidence: 0.9,
+            context: "test".to_string(),
+            first_seen: Utc::now(),
+            threat_intelligence: None,
+            enrichments: Vec::new(),
+        }
+    }
+
+    fn technique(id: &str) -> MITRETechnique {
+        MITRETechnique {
+            technique_id: id.to_string(),
+            technique_name: String::new(),
+            tactic: String::new(),
+            confidence: 0.8,
+            evidence: Vec::new(),
+            sub_techniques: Vec::new(),
+            detection_methods: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn analyses_are_attributed_by_weighted_evidence() {
+        let core = SandboxCore::new().unwrap();
+        core.save_actor_profile(actor("ember", &["T1566.001", "T1071.001", "T1547"], &["EmberLoader"], &[(IocNodeKind::Domain, "*.ember-cdn.net"), (IocNodeKind::Ip, "198.51.100.0/24")]))
+            .await
+            .unwrap();
+        core.save_actor_profile(actor("slate", &["T1566", "T1059.001"], &[], &[])).await.unwrap();
+        core.save_actor_profile(actor("quartz", &["T1190"], &[], &[])).await.unwrap();
+        core.save_campaign(Campaign {
+            campaign_id: "cmp-1".to_string(),
+            name: "Winter Grid".to_string(),
+            actor_id: Some("ember".to_string()),
+            description: String::new(),
+            techniques: vec!["T1547".to_string()],
+            indicators: vec!["203.0.113.77".to_string()],
+            first_seen: None,
+            last_seen: None,
+        })
+        .await
+        .unwrap();
+        assert!(core.save_actor_profile(actor("bad", &["T99"], &[], &[])).await.is_err());
+        assert_eq!(core.find_actor_profile("Ember Group").await.unwrap().actor_id, "ember");
+
+        let sample_id = core.submit_sample("acme", b"MZ implant", "implant.exe".to_string(), AnalysisPriority::Normal, vec![], false).await.unwrap();
+        core.process_queue().await.unwrap();
+        let mut analysis = core.get_analysis("acme", &sample_id).await.unwrap().unwrap();
+        analysis.mitre_techniques = vec![technique("T1566.002"), technique("T1071.001"), technique("T1547.001")];
+        analysis.iocs_extracted = vec![ioc("Domain", "update.ember-cdn.net"), ioc("IP", "203.0.113.77"), ioc("IP", "192.0.2.1")];
+        analysis.malware_classification.family = Some("emberloader".to_string());
+        analysis.threat_intelligence.malware_families.clear();
+
+        let query = AttributionQuery { min_score: 0.0, limit: 5, sector: Some("energy".to_string()) };
+        let hypotheses = core.actors.read().await.attribute(&analysis, &query);
+        let ranked: Vec<&str> = hypotheses.iter().map(|h| h.actor_id.as_str()).collect();
+        assert_eq!(ranked, ["ember", "slate"]);
+        let ember = &hypotheses[0];
+        assert!(ember.score > 0.9 && hypotheses[1].score < 0.3, "{:?}", hypotheses);
+        assert_eq!(ember.campaigns, ["Winter Grid"]);
+        let kinds: BTreeSet<String> = ember.evidence.iter().map(|e| format!("{:?}", e.kind)).collect();
+        assert_eq!(kinds.len(), 5, "{:?}", ember.evidence);
+        assert!(ember.evidence.iter().any(|e| e.detail == "update.ember-cdn.net ~ *.ember-cdn.net"));
+
+        // New analyses pick up the hypotheses
+        let sample_id = core.submit_sample("acme", b"MZ second", "second.exe".to_string(), AnalysisPriority::Normal, vec![], false).await.unwrap();
+        core.process_queue().await.unwrap();
+        let intelligence = core.get_analysis("acme", &sample_id).await.unwrap().unwrap().threat_intelligence;
+        assert_eq!(intelligence.threat_actors, intelligence.attribution.iter().map(|h| h.actor_name.clone()).collect::<Vec<_>>());
+        assert!(!intelligence.threat_actors.contains(&"Unknown Actor".to_string()));
+    }
+}
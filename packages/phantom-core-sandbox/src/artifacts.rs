@@ -0,0 +1,428 @@
+//! Detonation artifact store
+//!
+//! Files a detonation driver exports from the guest (dropped files, memory
+//! dumps, packet captures) and the browser screenshot of a URL detonation are
+//! retained per analysis, so they can still be listed and downloaded after the
+//! guest was reverted. Content is held in memory or, with a `storage_dir`,
+//! written to disk. The retention policy expires artifacts by age and then
+//! evicts the oldest until the total size fits `max_bytes`. The Docker
+//! backend only reports which paths changed, so it contributes no content.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::dedup::sha256_hex;
+use crate::detonation::DetonationReport;
+use crate::email::decode_base64;
+use crate::url_detonation::UrlDetonation;
+use crate::{AnalysisJob, SandboxCore, SandboxCoreNapi};
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactCategory {
+    DroppedFile,
+    MemoryDump,
+    Pcap,
+    Screenshot,
+}
+
+impl ArtifactCategory {
+    /// Category of an exported file, judged by its extension and directory
+    pub fn from_path(path: &str) -> Self {
+        let path = path.replace('\\', "/").to_lowercase();
+        let extension = Path::new(&path).extension().and_then(|ext| ext.to_str()).unwrap_or_default();
+        let in_dir = |names: &[&str]| path.split('/').rev().skip(1).any(|dir| names.contains(&dir));
+        match extension {
+            "pcap" | "pcapng" | "cap" => ArtifactCategory::Pcap,
+            "dmp" | "mdmp" | "vmem" | "lime" => ArtifactCategory::MemoryDump,
+            _ if in_dir(&["memory", "memdump", "dumps"]) => ArtifactCategory::MemoryDump,
+            _ if in_dir(&["screenshots"]) => ArtifactCategory::Screenshot,
+            _ => ArtifactCategory::DroppedFile,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredArtifact {
+    pub artifact_id: String,
+    pub analysis_id: String,
+    pub sample_id: String,
+    pub tenant_id: String,
+    pub category: ArtifactCategory,
+    /// Path relative to the guest export directory, or `screenshot.png`
+    pub name: String,
+    pub size_bytes: u64,
+    pub sha256: String,
+    /// Detonation backend or browser driver that produced the artifact
+    pub source: String,
+    pub stored_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactRetentionPolicy {
+    pub max_age_secs: Option<u64>,
+    /// Upper bound for the content of all retained artifacts
+    pub max_bytes: Option<u64>,
+    /// Larger artifacts stay listed in the detonation report but are not retained
+    pub max_artifact_bytes: Option<u64>,
+    /// Directory artifact content is written to; `None` keeps it in memory
+    #[serde(default)]
+    pub storage_dir: Option<PathBuf>,
+}
+
+impl Default for ArtifactRetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_age_secs: Some(30 * 24 * 3600),
+            max_bytes: Some(1 << 30),
+            max_artifact_bytes: Some(256 << 20),
+            storage_dir: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ArtifactRetentionReport {
+    pub retained_artifacts: u64,
+    pub retained_bytes: u64,
+    pub evicted_expired: u64,
+    pub evicted_max_bytes: u64,
+    pub evicted_bytes: u64,
+}
+
+#[derive(Debug)]
+enum ArtifactContent {
+    Memory(Vec<u8>),
+    Disk(PathBuf),
+}
+
+/// Retained artifacts and where their content lives
+#[derive(Debug, Default)]
+pub struct ArtifactStore {
+    policy: ArtifactRetentionPolicy,
+    artifacts: HashMap<String, (StoredArtifact, ArtifactContent)>,
+}
+
+impl ArtifactStore {
+    fn insert(&mut self, artifact: StoredArtifact, data: Vec<u8>) -> Result<(), String> {
+        let content = match &self.policy.storage_dir {
+            Some(dir) => {
+                let path = dir.join(&artifact.artifact_id);
+                fs::write(&path, &data).map_err(|e| format!("Failed to write artifact {}: {}", path.display(), e))?;
+                ArtifactContent::Disk(path)
+            }
+            None => ArtifactContent::Memory(data),
+        };
+        self.artifacts.insert(artifact.artifact_id.clone(), (artifact, content));
+        Ok(())
+    }
+
+    fn remove(&mut self, artifact_id: &str) -> Option<StoredArtifact> {
+        let (artifact, content) = self.artifacts.remove(artifact_id)?;
+        if let ArtifactContent::Disk(path) = content {
+            if let Err(e) = fs::remove_file(&path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    log::warn!("Failed to remove artifact {}: {}", path.display(), e);
+                }
+            }
+        }
+        Some(artifact)
+    }
+
+    /// Expire old artifacts, then evict the oldest until `max_bytes` holds
+    fn enforce(&mut self, now: DateTime<Utc>) -> ArtifactRetentionReport {
+        let mut report = ArtifactRetentionReport::default();
+        let mut by_age: Vec<(DateTime<Utc>, String, u64)> = self
+            .artifacts
+            .values()
+            .map(|(artifact, _)| (artifact.stored_at, artifact.artifact_id.clone(), artifact.size_bytes))
+            .collect();
+        by_age.sort();
+
+        let cutoff = self
+            .policy
+            .max_age_secs
+            .map(|max_age| now - chrono::Duration::seconds(max_age.min(i64::MAX as u64) as i64));
+        let mut bytes: u64 = by_age.iter().map(|(_, _, size)| size).sum();
+        for (stored_at, artifact_id, size) in by_age {
+            if cutoff.is_some_and(|cutoff| stored_at < cutoff) {
+                report.evicted_expired += 1;
+            } else if self.policy.max_bytes.is_some_and(|max| bytes > max) {
+                report.evicted_max_bytes += 1;
+            } else {
+                break;
+            }
+            self.remove(&artifact_id);
+            bytes -= size;
+            report.evicted_bytes += size;
+        }
+
+        report.retained_artifacts = self.artifacts.len() as u64;
+        report.retained_bytes = bytes;
+        report
+    }
+}
+
+impl SandboxCore {
+    /// Move exported detonation content and the URL screenshot into the
+    /// artifact store; returns warnings for artifacts that were not retained
+    pub(crate) async fn retain_artifacts(
+        &self,
+        job: &AnalysisJob,
+        analysis_id: &str,
+        detonation: Option<&mut DetonationReport>,
+        url_detonation: Option<&UrlDetonation>,
+    ) -> Vec<String> {
+        let now = Utc::now();
+        let artifact = |category, name: &str, source: &str, data: &[u8]| StoredArtifact {
+            artifact_id: format!("art_{}", Uuid::new_v4().simple()),
+            analysis_id: analysis_id.to_string(),
+            sample_id: job.sample_id.clone(),
+            tenant_id: job.tenant_id.clone(),
+            category,
+            name: name.to_string(),
+            size_bytes: data.len() as u64,
+            sha256: sha256_hex(data),
+            source: source.to_string(),
+            stored_at: now,
+        };
+
+        let mut warnings = Vec::new();
+        let mut store = self.artifacts.write().await;
+        let mut retain = |stored: StoredArtifact, data: Vec<u8>| {
+            if store.policy.max_artifact_bytes.is_some_and(|max| stored.size_bytes > max) {
+                warnings.push(format!("Artifact {} ({} bytes) exceeds the retention size limit and was not kept", stored.name, stored.size_bytes));
+                return None;
+            }
+            let artifact_id = stored.artifact_id.clone();
+            match store.insert(stored, data) {
+                Ok(()) => Some(artifact_id),
+                Err(e) => {
+                    warnings.push(e);
+                    None
+                }
+            }
+        };
+        if let Some(report) = detonation {
+            for exported in &mut report.artifacts {
+                let Some(data) = exported.content.take() else { continue };
+                let stored = artifact(ArtifactCategory::from_path(&exported.path), &exported.path, &report.backend, &data);
+                exported.artifact_id = retain(stored, data);
+            }
+        }
+        if let Some(report) = url_detonation {
+            if let Some(screenshot) = &report.screenshot {
+                let data = decode_base64(screenshot.png.as_bytes());
+                retain(artifact(ArtifactCategory::Screenshot, "screenshot.png", &report.driver, &data), data);
+            }
+        }
+        store.enforce(now);
+        warnings
+    }
+
+    /// Retained artifacts of an analysis, sorted by category and name
+    pub async fn list_artifacts(&self, tenant_id: &str, analysis_id: &str) -> Vec<StoredArtifact> {
+        let mut artifacts: Vec<StoredArtifact> = self
+            .artifacts
+            .read()
+            .await
+            .artifacts
+            .values()
+            .map(|(artifact, _)| artifact)
+            .filter(|artifact| artifact.tenant_id == tenant_id && artifact.analysis_id == analysis_id)
+            .cloned()
+            .collect();
+        artifacts.sort_by(|a, b| (a.category as u8, &a.name).cmp(&(b.category as u8, &b.name)));
+        artifacts
+    }
+
+    pub async fn get_artifact(&self, tenant_id: &str, artifact_id: &str) -> Result<Vec<u8>, String> {
+        let store = self.artifacts.read().await;
+        match store.artifacts.get(artifact_id) {
+            Some((artifact, ArtifactContent::Memory(data))) if artifact.tenant_id == tenant_id => Ok(data.clone()),
+            Some((artifact, ArtifactContent::Disk(path))) if artifact.tenant_id == tenant_id => {
+                tokio::fs::read(path).await.map_err(|e| format!("Failed to read artifact {}: {}", artifact_id, e))
+            }
+            _ => Err(format!("Artifact {} not found", artifact_id)),
+        }
+    }
+
+    /// Write an artifact to `path` without loading disk-backed content into memory
+    pub async fn save_artifact(&self, tenant_id: &str, artifact_id: &str, path: &Path) -> Result<StoredArtifact, String> {
+        let store = self.artifacts.read().await;
+        let saved = match store.artifacts.get(artifact_id) {
+            Some((artifact, ArtifactContent::Memory(data))) if artifact.tenant_id == tenant_id => {
+                tokio::fs::write(path, data).await.map(|_| artifact)
+            }
+            Some((artifact, ArtifactContent::Disk(source))) if artifact.tenant_id == tenant_id => {
+                tokio::fs::copy(source, path).await.map(|_| artifact)
+            }
+            _ => return Err(format!("Artifact {} not found", artifact_id)),
+        };
+        saved
+            .cloned()
+            .map_err(|e| format!("Failed to save artifact {} to {}: {}", artifact_id, path.display(), e))
+    }
+
+    pub async fn artifact_retention_policy(&self) -> ArtifactRetentionPolicy {
+        self.artifacts.read().await.policy.clone()
+    }
+
+    /// Replace the artifact retention policy and apply it immediately; the
+    /// storage directory only applies to artifacts retained from now on
+    pub async fn set_artifact_retention_policy(&self, policy: ArtifactRetentionPolicy) -> Result<ArtifactRetentionReport, String> {
+        if let Some(dir) = &policy.storage_dir {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create artifact directory {}: {}", dir.display(), e))?;
+        }
+        let mut store = self.artifacts.write().await;
+        store.policy = policy;
+        Ok(store.enforce(Utc::now()))
+    }
+
+    pub(crate) async fn forget_artifacts(&self, tenant_id: &str) -> usize {
+        let mut store = self.artifacts.write().await;
+        let owned: Vec<String> = store
+            .artifacts
+            .values()
+            .filter(|(artifact, _)| artifact.tenant_id == tenant_id)
+            .map(|(artifact, _)| artifact.artifact_id.clone())
+            .collect();
+        owned.iter().filter(|artifact_id| store.remove(artifact_id).is_some()).count()
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// Dropped files, memory dumps, pcaps and screenshots retained for an analysis
+    #[napi]
+    pub async fn list_artifacts(&self, analysis_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        serde_json::to_string(&self.inner.list_artifacts(&tenant_id, &analysis_id).await)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize artifacts: {}", e)))
+    }
+
+    /// Download an artifact's content
+    #[napi]
+    pub async fn get_artifact(&self, artifact_id: String, auth_token: Option<String>) -> napi::Result<Buffer> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "sample:export", &artifact_id)?;
+        let content = self.inner.get_artifact(&tenant_id, &artifact_id).await;
+        self.audit.record(&actor, "get_artifact", &artifact_id, serde_json::json!({}), content)
+            .map(Buffer::from)
+            .map_err(napi::Error::from_reason)
+    }
+
+    /// Write an artifact to a file on the host, returning its metadata
+    #[napi]
+    pub async fn save_artifact(&self, artifact_id: String, path: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "sample:export", &artifact_id)?;
+        let saved = self.inner.save_artifact(&tenant_id, &artifact_id, Path::new(&path)).await;
+        let artifact = self.audit.record(&actor, "save_artifact", &artifact_id, serde_json::json!({ "path": path }), saved)
+            .map_err(napi::Error::from_reason)?;
+        serde_json::to_string(&artifact)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize artifact: {}", e)))
+    }
+
+    /// Configure artifact retention and apply it immediately
+    #[napi]
+    pub async fn set_artifact_retention_policy(&self, policy_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, "artifact_retention")?;
+        let policy: ArtifactRetentionPolicy = serde_json::from_str(&policy_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse artifact retention policy: {}", e)))?;
+
+        let report = self.inner.set_artifact_retention_policy(policy).await;
+        let report = self.audit.record(&actor, "set_artifact_retention_policy", "artifacts", serde_json::json!({ "policy": policy_json }), report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to set artifact retention policy: {}", e)))?;
+
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize artifact retention report: {}", e)))
+    }
+
+    #[napi]
+    pub async fn get_artifact_retention_policy(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.artifact_retention_policy().await)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize artifact retention policy: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detonation::{ArtifactKind, DetonationArtifact};
+    use crate::AnalysisPriority;
+
+    fn exported(path: &str, data: &[u8]) -> DetonationArtifact {
+        DetonationArtifact {
+            kind: ArtifactKind::File,
+            path: path.to_string(),
+            size_bytes: data.len() as u64,
+            sha256: Some(sha256_hex(data)),
+            artifact_id: None,
+            content: Some(data.to_vec()),
+        }
+    }
+
+    #[tokio::test]
+    async fn exported_files_are_retained_per_analysis() {
+        let core = SandboxCore::new().unwrap();
+        let dir = std::env::temp_dir().join(format!("phantom-artifacts-{}", Uuid::new_v4()));
+        let policy = ArtifactRetentionPolicy { max_artifact_bytes: Some(16), storage_dir: Some(dir.clone()), ..Default::default() };
+        core.set_artifact_retention_policy(policy).await.unwrap();
+
+        let sample_id = core.submit_sample("acme", b"MZ dropper", "dropper.exe".to_string(), AnalysisPriority::Normal, vec![], false).await.unwrap();
+        let job = core.get_analysis_status("acme", &sample_id).await.unwrap().unwrap();
+        let now = Utc::now();
+        let mut report = DetonationReport {
+            backend: "libvirt".to_string(),
+            environment_id: "win10-x64".to_string(),
+            guest_id: None,
+            started_at: now,
+            finished_at: now,
+            vm_startup_ms: 0,
+            guest_path: None,
+            execution: None,
+            artifacts: vec![
+                exported("dropped/payload.dll", b"MZ payload"),
+                exported("memory/lsass.bin", b"MDMP"),
+                exported("capture.pcapng", b"\x0a\x0d\x0d\x0a"),
+                exported("dropped/huge.iso", &[0u8; 64]),
+            ],
+            errors: Vec::new(),
+            countermeasures: None,
+        };
+
+        let warnings = core.retain_artifacts(&job, "anl_1", Some(&mut report), None).await;
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+        assert!(report.artifacts.iter().all(|artifact| artifact.content.is_none()));
+        assert!(report.artifacts[3].artifact_id.is_none());
+
+        let listed = core.list_artifacts("acme", "anl_1").await;
+        let categories: Vec<ArtifactCategory> = listed.iter().map(|artifact| artifact.category).collect();
+        assert_eq!(categories, [ArtifactCategory::DroppedFile, ArtifactCategory::MemoryDump, ArtifactCategory::Pcap]);
+        assert_eq!(report.artifacts[0].artifact_id.as_ref(), Some(&listed[0].artifact_id));
+        assert_eq!(core.get_artifact("acme", &listed[1].artifact_id).await.unwrap(), b"MDMP");
+        assert!(core.get_artifact("globex", &listed[1].artifact_id).await.is_err());
+        assert!(core.list_artifacts("globex", "anl_1").await.is_empty());
+
+        let target = dir.join("payload.dll");
+        let saved = core.save_artifact("acme", &listed[0].artifact_id, &target).await.unwrap();
+        assert_eq!(std::fs::read(&target).unwrap(), b"MZ payload");
+        assert_eq!(saved.sha256, sha256_hex(b"MZ payload"));
+
+        let policy = ArtifactRetentionPolicy { max_bytes: Some(12), storage_dir: Some(dir.clone()), ..Default::default() };
+        let retention = core.set_artifact_retention_policy(policy).await.unwrap();
+        assert!(retention.evicted_max_bytes >= 1 && retention.retained_bytes <= 12);
+
+        core.purge_tenant("acme").await.unwrap();
+        assert!(core.list_artifacts("acme", "anl_1").await.is_empty());
+        assert!(!dir.join(&listed[1].artifact_id).exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
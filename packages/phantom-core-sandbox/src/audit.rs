@@ -0,0 +1,76 @@
+//! Audit trail for mutating NAPI operations
+//!
+//! Every mutating call made through `SandboxCoreNapi` is recorded with the shared
+//! `phantom_enterprise_standards::guard::AuditTrail`, whose docs describe
+//! how builds without `phantom-enterprise-standards` behave.
+//!
+//! Queue processing ticks are not recorded; the operations they act on are.
+
+pub use phantom_enterprise_standards::guard::AuditTrail;
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use napi_derive::napi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::audit_log::AuditQuery;
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::SandboxCoreNapi;
+
+/// Name recorded as the `source` of every entry
+pub const AUDIT_SOURCE: &str = "sandbox";
+
+/// Trail for this build: recording with `phantom-enterprise-standards`, disabled without
+pub fn audit_trail() -> AuditTrail {
+    if cfg!(feature = "phantom-enterprise-standards") {
+        AuditTrail::new(AUDIT_SOURCE)
+    } else {
+        AuditTrail::disabled(AUDIT_SOURCE)
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[napi]
+impl SandboxCoreNapi {
+    /// Persist the audit log to a JSON-lines file, resuming any chain already in it
+    #[napi]
+    pub fn open_audit_log(&self, path: String, auth_token: Option<String>) -> napi::Result<()> {
+        self.authorize(auth_token, "access:manage", &path)?;
+        self.audit.open(&path)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to open audit log: {}", e)))
+    }
+
+    /// Walk the hash chain and report the first broken entry, if any
+    #[napi]
+    pub fn verify_audit_log(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token, "audit:read", "audit")?;
+        serde_json::to_string(&self.audit.log().verify())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize audit entries: {}", e)))
+    }
+
+    /// List audit entries matching a JSON `AuditQuery`
+    #[napi]
+    pub fn list_audit_entries(&self, query: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token, "audit:read", "audit")?;
+        let query = parse_query(query)?;
+        serde_json::to_string(&self.audit.log().entries(&query))
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize audit entries: {}", e)))
+    }
+
+    /// Export audit entries as JSON lines
+    #[napi]
+    pub fn export_audit_log(&self, query: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token, "audit:read", "audit")?;
+        let query = parse_query(query)?;
+        self.audit.log().export_jsonl(&query)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to export audit log: {}", e)))
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+fn parse_query(query: Option<String>) -> napi::Result<AuditQuery> {
+    match query {
+        Some(data) => serde_json::from_str(&data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid audit query: {}", e))),
+        None => Ok(AuditQuery::default()),
+    }
+}
@@ -0,0 +1,693 @@
+//! Campaign infrastructure graph
+//!
+//! Completed analyses are merged into one undirected IOC graph: samples,
+//! domains, IP addresses, payload hashes and mutexes are nodes, and an edge
+//! records that an analysis observed the two together (a sample contacting an
+//! IP, a domain resolving to an IP, a sample dropping a file). Samples that
+//! share infrastructure end up in the same connected component, which is
+//! reported as a campaign cluster. The graph also answers how two samples
+//! are linked (shortest path) and which infrastructure holds campaigns
+//! together (betweenness centrality).
+//!
+//! Private, loopback and link-local addresses are left out: every sample
+//! talks to the sandbox's resolver and gateway, which would otherwise merge
+//! unrelated samples into one cluster.
+
+use chrono::{DateTime, Utc};
+use napi_derive::napi;
+use petgraph::graph::{NodeIndex, UnGraph};
+use petgraph::unionfind::UnionFind;
+use petgraph::visit::EdgeRef;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::net::IpAddr;
+
+use crate::{SandboxAnalysis, SandboxCore, SandboxCoreNapi};
+
+fn default_min_cluster_samples() -> usize {
+    2
+}
+
+fn default_top_central() -> usize {
+    10
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IocNodeKind {
+    Sample,
+    Domain,
+    Ip,
+    Hash,
+    Mutex,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObservationKind {
+    /// The sample opened a connection to the IP
+    Contacted,
+    /// The sample looked the domain up or it was flagged in its traffic
+    Queried,
+    /// A DNS answer seen during the analysis mapped the domain to the IP
+    Resolved,
+    /// The sample sent an HTTP request to the host
+    Requested,
+    Dropped,
+    CreatedMutex,
+    /// Listed among the analysis' extracted IOCs
+    Extracted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IocNode {
+    pub kind: IocNodeKind,
+    /// Lowercase SHA-256 for samples and hashes, normalized value otherwise
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Observation {
+    pub kinds: BTreeSet<ObservationKind>,
+    pub analyses: BTreeSet<String>,
+    pub first_seen: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SampleMeta {
+    file_name: String,
+    sample_ids: BTreeSet<String>,
+    analysis_ids: BTreeSet<String>,
+    families: BTreeSet<String>,
+}
+
+/// Which analyses the graph covers and which queries to run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CampaignGraphQuery {
+    /// Restrict the graph to these analyses; all completed analyses when empty
+    #[serde(default)]
+    pub analysis_ids: Vec<String>,
+    /// Clusters with fewer samples are not reported
+    #[serde(default = "default_min_cluster_samples")]
+    pub min_cluster_samples: usize,
+    #[serde(default = "default_top_central")]
+    pub top_central: usize,
+    /// Sample (SHA-256, sample id or analysis id) to find a path from
+    #[serde(default)]
+    pub path_from: Option<String>,
+    #[serde(default)]
+    pub path_to: Option<String>,
+    /// Leave nodes and edges out of the response, keeping only query results
+    #[serde(default)]
+    pub summary_only: bool,
+}
+
+impl Default for CampaignGraphQuery {
+    fn default() -> Self {
+        Self {
+            analysis_ids: Vec::new(),
+            min_cluster_samples: default_min_cluster_samples(),
+            top_central: default_top_central(),
+            path_from: None,
+            path_to: None,
+            summary_only: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphNode {
+    pub id: usize,
+    pub kind: IocNodeKind,
+    pub value: String,
+    /// File name for samples
+    pub label: Option<String>,
+    pub degree: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub source: usize,
+    pub target: usize,
+    #[serde(flatten)]
+    pub observation: Observation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterSample {
+    pub sha256: String,
+    pub file_name: String,
+    pub analysis_ids: Vec<String>,
+}
+
+/// Samples connected through shared infrastructure
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CampaignCluster {
+    /// `campaign-<n>`, numbered by size, largest first
+    pub cluster_id: String,
+    pub samples: Vec<ClusterSample>,
+    pub malware_families: Vec<String>,
+    /// Infrastructure nodes of the cluster per kind
+    pub infrastructure: BTreeMap<String, Vec<String>>,
+    /// Infrastructure observed by two or more of the cluster's samples
+    pub shared_infrastructure: Vec<IocNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CentralNode {
+    #[serde(flatten)]
+    pub node: IocNode,
+    /// Normalized betweenness centrality, 0.0 - 1.0
+    pub betweenness: f64,
+    pub degree: usize,
+    /// Samples observed with the node directly
+    pub samples: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathStep {
+    #[serde(flatten)]
+    pub node: IocNode,
+    /// How this node was observed with the previous step
+    pub via: Vec<ObservationKind>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CampaignGraphView {
+    pub analyses: usize,
+    pub node_count: usize,
+    pub edge_count: usize,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+    pub clusters: Vec<CampaignCluster>,
+    pub central_nodes: Vec<CentralNode>,
+    /// Shortest path between `path_from` and `path_to`; empty when unconnected
+    pub path: Option<Vec<PathStep>>,
+}
+
+fn public_ip(value: &str) -> Option<IpAddr> {
+    let ip: IpAddr = value.trim().trim_matches(['[', ']']).parse().ok()?;
+    let internal = match ip {
+        IpAddr::V4(v4) => v4.is_private() || v4.is_loopback() || v4.is_link_local() || v4.is_unspecified() || v4.is_broadcast() || v4.is_multicast(),
+        IpAddr::V6(v6) => v6.is_loopback() || v6.is_unspecified() || v6.is_multicast() || (v6.segments()[0] & 0xfe00) == 0xfc00 || (v6.segments()[0] & 0xffc0) == 0xfe80,
+    };
+    (!internal).then_some(ip)
+}
+
+/// Node for a host name or address; internal addresses yield `None`
+fn host_node(host: &str) -> Option<(IocNodeKind, String)> {
+    let host = host.trim().trim_end_matches('.').to_ascii_lowercase();
+    if host.is_empty() {
+        return None;
+    }
+    if host.parse::<IpAddr>().is_ok() || host.starts_with('[') {
+        return public_ip(&host).map(|ip| (IocNodeKind::Ip, ip.to_string()));
+    }
+    host.contains('.').then_some((IocNodeKind::Domain, host))
+}
+
+pub(crate) fn ioc_node(ioc_type: &str, value: &str) -> Option<(IocNodeKind, String)> {
+    match ioc_type.to_ascii_lowercase().as_str() {
+        "ip" | "ipv4" | "ipv6" | "ip-dst" | "domain" | "hostname" => host_node(value),
+        "url" | "uri" => host_node(url::Url::parse(value).ok()?.host_str()?),
+        "md5" | "sha1" | "sha256" | "hash" => Some((IocNodeKind::Hash, value.trim().to_ascii_lowercase())).filter(|(_, v)| !v.is_empty()),
+        "mutex" => Some((IocNodeKind::Mutex, value.trim().to_string())).filter(|(_, v)| !v.is_empty()),
+        _ => None,
+    }
+}
+
+/// IOC graph over a set of analyses
+pub struct CampaignGraph {
+    graph: UnGraph<IocNode, Observation>,
+    index: HashMap<(IocNodeKind, String), NodeIndex>,
+    samples: HashMap<NodeIndex, SampleMeta>,
+    analyses: usize,
+}
+
+impl CampaignGraph {
+    pub fn build<'a>(analyses: impl IntoIterator<Item = &'a SandboxAnalysis>) -> Self {
+        let mut graph = Self { graph: UnGraph::new_undirected(), index: HashMap::new(), samples: HashMap::new(), analyses: 0 };
+        for analysis in analyses {
+            graph.add_analysis(analysis);
+        }
+        graph
+    }
+
+    fn node(&mut self, kind: IocNodeKind, value: String) -> NodeIndex {
+        if let Some(index) = self.index.get(&(kind, value.clone())) {
+            return *index;
+        }
+        let index = self.graph.add_node(IocNode { kind, value: value.clone() });
+        self.index.insert((kind, value), index);
+        index
+    }
+
+    fn observe(&mut self, a: NodeIndex, b: NodeIndex, kind: ObservationKind, analysis_id: &str, at: DateTime<Utc>) {
+        if a == b {
+            return;
+        }
+        let edge = match self.graph.find_edge(a, b) {
+            Some(edge) => edge,
+            None => self.graph.add_edge(a, b, Observation::default()),
+        };
+        let observation = &mut self.graph[edge];
+        observation.kinds.insert(kind);
+        observation.analyses.insert(analysis_id.to_string());
+        observation.first_seen = Some(observation.first_seen.map_or(at, |seen| seen.min(at)));
+    }
+
+    fn add_analysis(&mut self, analysis: &SandboxAnalysis) {
+        self.analyses += 1;
+        let info = &analysis.sample_info;
+        let sample_hash = info.file_hash_sha256.trim().to_ascii_lowercase();
+        let sample_key = if sample_hash.is_empty() { info.sample_id.clone() } else { sample_hash };
+        let sample = self.node(IocNodeKind::Sample, sample_key);
+        let meta = self.samples.entry(sample).or_default();
+        meta.file_name = info.file_name.clone();
+        meta.sample_ids.insert(info.sample_id.clone());
+        meta.analysis_ids.insert(analysis.analysis_id.clone());
+        if let Some(family) = analysis.malware_classification.family.as_ref().filter(|family| !family.is_empty()) {
+            meta.families.insert(family.clone());
+        }
+        let own_hashes: BTreeSet<String> = [&info.file_hash_md5, &info.file_hash_sha1, &info.file_hash_sha256].iter().map(|h| h.to_ascii_lowercase()).collect();
+
+        let id = analysis.analysis_id.as_str();
+        let started = info.submission_time;
+        let network = &analysis.network_analysis;
+        let mut linked: Vec<(IocNodeKind, String, ObservationKind, DateTime<Utc>)> = Vec::new();
+        for connection in &network.connections {
+            if let Some(ip) = public_ip(&connection.remote_address) {
+                linked.push((IocNodeKind::Ip, ip.to_string(), ObservationKind::Contacted, connection.first_seen));
+            }
+        }
+        for query in &network.dns_queries {
+            let Some((kind, domain)) = host_node(&query.domain) else { continue };
+            linked.push((kind, domain.clone(), ObservationKind::Queried, query.timestamp));
+            if kind != IocNodeKind::Domain {
+                continue;
+            }
+            let domain_node = self.node(IocNodeKind::Domain, domain);
+            for answer in &query.response {
+                if let Some(ip) = public_ip(answer) {
+                    let ip_node = self.node(IocNodeKind::Ip, ip.to_string());
+                    self.observe(domain_node, ip_node, ObservationKind::Resolved, id, query.timestamp);
+                }
+            }
+        }
+        for request in &network.http_requests {
+            let host = url::Url::parse(&request.url).ok().and_then(|url| url.host_str().map(str::to_string));
+            if let Some((kind, value)) = host.as_deref().and_then(host_node) {
+                linked.push((kind, value, ObservationKind::Requested, request.timestamp));
+            }
+        }
+        for domain in &network.suspicious_domains {
+            if let Some((kind, value)) = host_node(domain) {
+                linked.push((kind, value, ObservationKind::Queried, started));
+            }
+        }
+        for dropped in &analysis.file_system_analysis.dropped_files {
+            let hash = dropped.file_hash.trim().to_ascii_lowercase();
+            if !hash.is_empty() && !own_hashes.contains(&hash) {
+                linked.push((IocNodeKind::Hash, hash, ObservationKind::Dropped, dropped.timestamp));
+            }
+        }
+        for ioc in &analysis.iocs_extracted {
+            let Some((kind, value)) = ioc_node(&ioc.ioc_type, &ioc.value) else { continue };
+            if kind == IocNodeKind::Hash && own_hashes.contains(&value) {
+                continue;
+            }
+            let observed = if kind == IocNodeKind::Mutex { ObservationKind::CreatedMutex } else { ObservationKind::Extracted };
+            linked.push((kind, value, observed, ioc.first_seen));
+        }
+
+        for (kind, value, observed, at) in linked {
+            let node = self.node(kind, value);
+            self.observe(sample, node, observed, id, at);
+        }
+    }
+
+    fn resolve_sample(&self, reference: &str) -> Option<NodeIndex> {
+        let reference = reference.trim();
+        self.index.get(&(IocNodeKind::Sample, reference.to_ascii_lowercase())).copied().or_else(|| {
+            self.samples
+                .iter()
+                .find(|(_, meta)| meta.sample_ids.contains(reference) || meta.analysis_ids.contains(reference))
+                .map(|(index, _)| *index)
+        })
+    }
+
+    fn sample_neighbours(&self, node: NodeIndex) -> usize {
+        self.graph.neighbors(node).filter(|n| self.graph[*n].kind == IocNodeKind::Sample).count()
+    }
+
+    /// Connected components holding at least `min_samples` samples, largest first
+    pub fn clusters(&self, min_samples: usize) -> Vec<CampaignCluster> {
+        let mut components = UnionFind::<usize>::new(self.graph.node_count());
+        for edge in self.graph.edge_references() {
+            components.union(edge.source().index(), edge.target().index());
+        }
+        let mut grouped: BTreeMap<usize, Vec<NodeIndex>> = BTreeMap::new();
+        for node in self.graph.node_indices() {
+            grouped.entry(components.find(node.index())).or_default().push(node);
+        }
+
+        let mut clusters: Vec<CampaignCluster> = grouped
+            .into_values()
+            .filter_map(|nodes| {
+                let mut samples: Vec<ClusterSample> = nodes
+                    .iter()
+                    .filter_map(|node| {
+                        let meta = self.samples.get(node)?;
+                        Some(ClusterSample {
+                            sha256: self.graph[*node].value.clone(),
+                            file_name: meta.file_name.clone(),
+                            analysis_ids: meta.analysis_ids.iter().cloned().collect(),
+                        })
+                    })
+                    .collect();
+                if samples.len() < min_samples.max(1) {
+                    return None;
+                }
+                samples.sort_by(|a, b| a.sha256.cmp(&b.sha256));
+                let malware_families: BTreeSet<String> = nodes.iter().filter_map(|node| self.samples.get(node)).flat_map(|meta| meta.families.iter().cloned()).collect();
+                let mut infrastructure: BTreeMap<String, Vec<String>> = BTreeMap::new();
+                let mut shared_infrastructure = Vec::new();
+                for node in nodes.iter().filter(|node| self.graph[**node].kind != IocNodeKind::Sample) {
+                    let ioc = &self.graph[*node];
+                    let kind = serde_json::to_value(ioc.kind).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default();
+                    infrastructure.entry(kind).or_default().push(ioc.value.clone());
+                    if self.sample_neighbours(*node) >= 2 {
+                        shared_infrastructure.push(ioc.clone());
+                    }
+                }
+                infrastructure.values_mut().for_each(|values| values.sort());
+                shared_infrastructure.sort_by(|a, b| (a.kind, &a.value).cmp(&(b.kind, &b.value)));
+                Some(CampaignCluster {
+                    cluster_id: String::new(),
+                    samples,
+                    malware_families: malware_families.into_iter().collect(),
+                    infrastructure,
+                    shared_infrastructure,
+                })
+            })
+            .collect();
+        clusters.sort_by(|a, b| b.samples.len().cmp(&a.samples.len()).then_with(|| a.samples[0].sha256.cmp(&b.samples[0].sha256)));
+        for (i, cluster) in clusters.iter_mut().enumerate() {
+            cluster.cluster_id = format!("campaign-{}", i + 1);
+        }
+        clusters
+    }
+
+    /// Fewest-hop chain of observations linking two samples
+    pub fn shortest_path(&self, from: &str, to: &str) -> Result<Vec<PathStep>, String> {
+        let start = self.resolve_sample(from).ok_or_else(|| format!("Sample {} not found", from))?;
+        let goal = self.resolve_sample(to).ok_or_else(|| format!("Sample {} not found", to))?;
+        let Some((_, nodes)) = petgraph::algo::astar(&self.graph, start, |node| node == goal, |_| 1u32, |_| 0) else {
+            return Ok(Vec::new());
+        };
+        Ok(nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| PathStep {
+                node: self.graph[*node].clone(),
+                via: match i.checked_sub(1).and_then(|prev| self.graph.find_edge(nodes[prev], *node)) {
+                    Some(edge) => self.graph[edge].kinds.iter().copied().collect(),
+                    None => Vec::new(),
+                },
+            })
+            .collect())
+    }
+
+    /// Brandes' betweenness centrality on the unweighted graph, normalized
+    fn betweenness(&self) -> Vec<f64> {
+        let n = self.graph.node_count();
+        let mut centrality = vec![0.0; n];
+        for source in self.graph.node_indices() {
+            let mut stack = Vec::with_capacity(n);
+            let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); n];
+            let mut paths = vec![0.0f64; n];
+            let mut distance = vec![-1i64; n];
+            paths[source.index()] = 1.0;
+            distance[source.index()] = 0;
+            let mut queue = VecDeque::from([source]);
+            while let Some(v) = queue.pop_front() {
+                stack.push(v.index());
+                for w in self.graph.neighbors(v) {
+                    if distance[w.index()] < 0 {
+                        distance[w.index()] = distance[v.index()] + 1;
+                        queue.push_back(w);
+                    }
+                    if distance[w.index()] == distance[v.index()] + 1 {
+                        paths[w.index()] += paths[v.index()];
+                        predecessors[w.index()].push(v.index());
+                    }
+                }
+            }
+            let mut dependency = vec![0.0; n];
+            while let Some(w) = stack.pop() {
+                for &v in &predecessors[w] {
+                    dependency[v] += paths[v] / paths[w] * (1.0 + dependency[w]);
+                }
+                if w != source.index() {
+                    centrality[w] += dependency[w];
+                }
+            }
+        }
+        // Each pair was counted from both ends of the undirected graph
+        let pairs = if n > 2 { ((n - 1) * (n - 2)) as f64 } else { 1.0 };
+        centrality.iter().map(|c| c / pairs).collect()
+    }
+
+    /// Infrastructure nodes bridging the most shortest paths
+    pub fn central_nodes(&self, top: usize) -> Vec<CentralNode> {
+        let betweenness = self.betweenness();
+        let mut central: Vec<CentralNode> = self
+            .graph
+            .node_indices()
+            .filter(|node| self.graph[*node].kind != IocNodeKind::Sample)
+            .map(|node| CentralNode {
+                node: self.graph[node].clone(),
+                betweenness: betweenness[node.index()],
+                degree: self.graph.neighbors(node).count(),
+                samples: self.sample_neighbours(node),
+            })
+            .filter(|node| node.betweenness > 0.0 || node.samples > 1)
+            .collect();
+        central.sort_by(|a, b| {
+            b.betweenness
+                .total_cmp(&a.betweenness)
+                .then_with(|| b.samples.cmp(&a.samples))
+                .then_with(|| a.node.value.cmp(&b.node.value))
+        });
+        central.truncate(top);
+        central
+    }
+
+    /// Infrastructure of `kind` observed by two or more samples
+    pub fn shared(&self, kind: IocNodeKind) -> Vec<String> {
+        let mut shared: Vec<String> = self
+            .graph
+            .node_indices()
+            .filter(|node| self.graph[*node].kind == kind && self.sample_neighbours(*node) >= 2)
+            .map(|node| self.graph[node].value.clone())
+            .collect();
+        shared.sort();
+        shared
+    }
+
+    pub fn view(&self, query: &CampaignGraphQuery) -> Result<CampaignGraphView, String> {
+        let path = match (&query.path_from, &query.path_to) {
+            (Some(from), Some(to)) => Some(self.shortest_path(from, to)?),
+            (None, None) => None,
+            _ => return Err("Both path_from and path_to are required for a path query".to_string()),
+        };
+        let (nodes, edges) = if query.summary_only {
+            (Vec::new(), Vec::new())
+        } else {
+            let nodes = self
+                .graph
+                .node_indices()
+                .map(|node| GraphNode {
+                    id: node.index(),
+                    kind: self.graph[node].kind,
+                    value: self.graph[node].value.clone(),
+                    label: self.samples.get(&node).map(|meta| meta.file_name.clone()),
+                    degree: self.graph.neighbors(node).count(),
+                })
+                .collect();
+            let edges = self
+                .graph
+                .edge_references()
+                .map(|edge| GraphEdge { source: edge.source().index(), target: edge.target().index(), observation: edge.weight().clone() })
+                .collect();
+            (nodes, edges)
+        };
+        Ok(CampaignGraphView {
+            analyses: self.analyses,
+            node_count: self.graph.node_count(),
+            edge_count: self.graph.edge_count(),
+            nodes,
+            edges,
+            clusters: self.clusters(query.min_cluster_samples),
+            central_nodes: self.central_nodes(query.top_central),
+            path,
+        })
+    }
+}
+
+impl SandboxCore {
+    /// IOC graph over the tenant's completed analyses
+    pub async fn campaign_graph(&self, tenant_id: &str, query: &CampaignGraphQuery) -> Result<CampaignGraphView, String> {
+        let completed = self.completed_analyses.read().await;
+        let analyses = completed
+            .values()
+            .filter(|analysis| analysis.tenant_id == tenant_id)
+            .filter(|analysis| query.analysis_ids.is_empty() || query.analysis_ids.contains(&analysis.analysis_id));
+        CampaignGraph::build(analyses).view(query)
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// Campaign clusters, central infrastructure and, with `path_from` and
+    /// `path_to`, the link between two samples
+    #[napi]
+    pub async fn get_campaign_graph(&self, query_json: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let query: CampaignGraphQuery = match query_json {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| napi::Error::from_reason(format!("Failed to parse campaign graph query: {}", e)))?,
+            None => CampaignGraphQuery::default(),
+        };
+        let view = self.inner.campaign_graph(&tenant_id, &query).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to build campaign graph: {}", e)))?;
+        serde_json::to_string(&view)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize campaign graph: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AnalysisPriority, DNSQuery, DroppedFile, ExtractedIOC, NetworkConnection};
+
+    fn connection(remote: &str) -> NetworkConnection {
+        NetworkConnection {
+            connection_id: remote.to_string(),
+            protocol: "TCP".to_string(),
+            local_address: "10.0.2.15".to_string(),
+            local_port: 49152,
+            remote_address: remote.to_string(),
+            remote_port: 443,
+            state: "ESTABLISHED".to_string(),
+            bytes_sent: 1,
+            bytes_received: 1,
+            packets_sent: 1,
+            packets_received: 1,
+            first_seen: Utc::now(),
+            last_seen: Utc::now(),
+            process_name: "sample.exe".to_string(),
+            process_id: 1,
+            geo_location: None,
+            reputation_score: 0.0,
+        }
+    }
+
+    fn analysis(template: &SandboxAnalysis, id: &str, sha256: &str, family: &str, remotes: &[&str], domains: &[(&str, &str)], mutex: Option<&str>) -> SandboxAnalysis {
+        let mut analysis = template.clone();
+        analysis.analysis_id = id.to_string();
+        analysis.sample_info.sample_id = id.to_string();
+        analysis.sample_info.file_hash_sha256 = sha256.to_string();
+        analysis.sample_info.file_name = format!("{}.exe", id);
+        analysis.malware_classification.family = Some(family.to_string());
+        analysis.network_analysis.connections = remotes.iter().map(|remote| connection(remote)).collect();
+        analysis.network_analysis.dns_queries = domains
+            .iter()
+            .map(|(domain, answer)| DNSQuery {
+                query_id: domain.to_string(),
+                domain: domain.to_string(),
+                query_type: "A".to_string(),
+                response: vec![answer.to_string()],
+                response_code: "NOERROR".to_string(),
+                timestamp: Utc::now(),
+                process_name: "sample.exe".to_string(),
+                process_id: 1,
+                is_suspicious: true,
+                threat_category: None,
+            })
+            .collect();
+        analysis.network_analysis.http_requests.clear();
+        analysis.network_analysis.suspicious_domains.clear();
+        analysis.file_system_analysis.dropped_files.clear();
+        analysis.iocs_extracted = mutex
+            .map(|name| ExtractedIOC {
+                ioc_type: "mutex".to_string(),
+                value: name.to_string(),
+                category: "host".to_string(),
+                confidence: 0.9,
+                context: String::new(),
+                first_seen: Utc::now(),
+                threat_intelligence: None,
+                enrichments: vec![],
+            })
+            .into_iter()
+            .collect();
+        analysis
+    }
+
+    #[tokio::test]
+    async fn shared_infrastructure_forms_campaigns() {
+        let core = SandboxCore::new().unwrap();
+        let sample_id = core.submit_sample("acme", b"MZ campaign", "c.exe".to_string(), AnalysisPriority::Normal, vec![], false).await.unwrap();
+        core.process_queue().await.unwrap();
+        let template = core.get_analysis("acme", &sample_id).await.unwrap().unwrap();
+        let view = core.campaign_graph("acme", &CampaignGraphQuery::default()).await.unwrap();
+        assert_eq!((view.analyses, view.nodes[0].kind), (1, IocNodeKind::Sample));
+        assert_eq!(core.campaign_graph("globex", &CampaignGraphQuery::default()).await.unwrap().node_count, 0);
+
+        let mut first = analysis(&template, "a1", "AAAA", "Emotet", &["198.51.100.7", "10.0.2.3"], &[("c2.evil.example", "198.51.100.7")], None);
+        first.file_system_analysis.dropped_files.push(DroppedFile {
+            file_path: "C:\\Temp\\stage2.dll".to_string(),
+            file_hash: "DDDD".to_string(),
+            file_size: 1,
+            file_type: "PE32".to_string(),
+            dropped_by: "a1.exe".to_string(),
+            timestamp: Utc::now(),
+            is_executable: true,
+            is_packed: false,
+            entropy: 7.1,
+            strings: vec![],
+        });
+        let second = analysis(&template, "a2", "bbbb", "Emotet", &[], &[("cdn.evil.example", "198.51.100.7")], Some("Global\\emo_mtx"));
+        let third = analysis(&template, "a3", "cccc", "Qakbot", &["203.0.113.50"], &[], Some("Global\\emo_mtx"));
+        let unrelated = analysis(&template, "a4", "eeee", "AgentTesla", &["192.0.2.99", "10.0.2.3"], &[], None);
+
+        let graph = CampaignGraph::build([&first, &second, &third, &unrelated]);
+        let clusters = graph.clusters(2);
+        assert_eq!(clusters.len(), 1, "the sandbox gateway 10.0.2.3 must not link samples");
+        let campaign = &clusters[0];
+        assert_eq!(campaign.cluster_id, "campaign-1");
+        assert_eq!(campaign.samples.iter().map(|s| s.sha256.as_str()).collect::<Vec<_>>(), ["aaaa", "bbbb", "cccc"]);
+        assert_eq!(campaign.malware_families, ["Emotet", "Qakbot"]);
+        assert_eq!(campaign.infrastructure["hash"], ["dddd"]);
+        assert!(campaign.shared_infrastructure.iter().any(|node| node.kind == IocNodeKind::Mutex));
+        assert_eq!(graph.clusters(1).len(), 2);
+
+        let path = graph.shortest_path("a1", "cccc").unwrap();
+        let hops: Vec<&str> = path.iter().map(|step| step.node.value.as_str()).collect();
+        assert_eq!(hops, ["aaaa", "198.51.100.7", "cdn.evil.example", "bbbb", "Global\\emo_mtx", "cccc"]);
+        assert_eq!(path[2].via, [ObservationKind::Resolved]);
+        assert!(graph.shortest_path("aaaa", "eeee").unwrap().is_empty());
+        assert!(graph.shortest_path("aaaa", "ffff").is_err());
+
+        let central = graph.central_nodes(3);
+        let values: Vec<&str> = central.iter().map(|node| node.node.value.as_str()).collect();
+        assert_eq!(values, ["cdn.evil.example", "198.51.100.7", "Global\\emo_mtx"]);
+        assert!(central[0].betweenness > central[1].betweenness && central[1].betweenness > central[2].betweenness);
+        assert_eq!(graph.shared(IocNodeKind::Ip), Vec::<String>::new());
+        assert_eq!(graph.shared(IocNodeKind::Mutex), ["Global\\emo_mtx"]);
+
+        let view = graph.view(&CampaignGraphQuery { summary_only: true, path_from: Some("a1".to_string()), ..Default::default() });
+        assert!(view.is_err());
+    }
+}
@@ -0,0 +1,857 @@
+//! Registry and file change risk heuristics
+//!
+//! Detonation records every registry write, file write and command line the
+//! sample produced, but a raw change list does not say which of them matter.
+//! This library scores changes against known-bad locations: autostart and
+//! other persistence points, keys that switch off security tooling, hosts
+//! file edits, shadow-copy deletion, living-off-the-land binaries dropped in
+//! world-writable directories, and so on. Each heuristic carries an ATT&CK
+//! technique and a 0–10 score on the same scale as `threat_score`.
+//!
+//! The same heuristics assess change records from outside the sandbox, such
+//! as autoruns and file listings gathered by live response, through
+//! `assess_changes`.
+//!
+//! Paths are matched case-insensitively after separators are normalized to
+//! `\` and registry hive names to their short forms (`HKLM`, `HKCU`, ...),
+//! so one pattern covers `/etc/cron.d` and `HKEY_LOCAL_MACHINE\...` spellings.
+
+use chrono::{DateTime, Utc};
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+use crate::detonation::{ArtifactKind, DetonationReport};
+use crate::{
+    AutorunEntry, FileChange, FileSystemAnalysis, ProcessAnalysis, RegistryAnalysis, RegistryChange, SandboxCore,
+    SecurityModification, SuspiciousFile, SuspiciousRegistryKey, SystemChanges,
+};
+
+/// Added to the top score for each further heuristic a change trips
+const ADDITIONAL_HIT_BONUS: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeTarget {
+    Registry,
+    File,
+    /// A process command line
+    Command,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeuristicCategory {
+    Persistence,
+    SecurityTampering,
+    Impact,
+    Masquerading,
+    CredentialAccess,
+    Staging,
+}
+
+/// One known-bad change pattern
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeHeuristic {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub target: ChangeTarget,
+    pub category: HeuristicCategory,
+    /// Regex over the normalized key path, file path or command line
+    pub path_pattern: &'static str,
+    /// Regex over the registry value name; any value when unset
+    pub value_name_pattern: Option<&'static str>,
+    /// Regex over the new value data; any data when unset
+    pub data_pattern: Option<&'static str>,
+    /// Operations it applies to, e.g. `SET` or `DELETE`; any when empty
+    pub operations: &'static [&'static str],
+    pub mitre_technique: &'static str,
+    pub score: f64,
+    pub description: &'static str,
+}
+
+const WRITE: &[&str] = &["SET", "CREATE", "MODIFY", "WRITE"];
+const CREATE: &[&str] = &["CREATE", "WRITE", "MODIFY"];
+const DELETE: &[&str] = &["DELETE"];
+
+pub const BUILTIN_HEURISTICS: &[ChangeHeuristic] = &[
+    // Registry persistence
+    ChangeHeuristic {
+        id: "registry_run_key",
+        name: "Run key autostart",
+        target: ChangeTarget::Registry,
+        category: HeuristicCategory::Persistence,
+        path_pattern: r"\\software\\(wow6432node\\)?microsoft\\windows\\currentversion\\(run|runonce|runonceex|runservices|runservicesonce|policies\\explorer\\run)$",
+        value_name_pattern: None,
+        data_pattern: None,
+        operations: WRITE,
+        mitre_technique: "T1547.001",
+        score: 7.0,
+        description: "Program registered to start at logon",
+    },
+    ChangeHeuristic {
+        id: "winlogon_helper",
+        name: "Winlogon helper",
+        target: ChangeTarget::Registry,
+        category: HeuristicCategory::Persistence,
+        path_pattern: r"\\microsoft\\windows nt\\currentversion\\winlogon$",
+        value_name_pattern: Some(r"^(shell|userinit|notify|taskman)$"),
+        data_pattern: None,
+        operations: WRITE,
+        mitre_technique: "T1547.004",
+        score: 8.0,
+        description: "Winlogon shell or userinit changed to launch a program",
+    },
+    ChangeHeuristic {
+        id: "service_image_path",
+        name: "Service binary registered",
+        target: ChangeTarget::Registry,
+        category: HeuristicCategory::Persistence,
+        path_pattern: r"\\system\\(currentcontrolset|controlset\d+)\\services\\[^\\]+(\\parameters)?$",
+        value_name_pattern: Some(r"^(imagepath|servicedll)$"),
+        data_pattern: None,
+        operations: WRITE,
+        mitre_technique: "T1543.003",
+        score: 6.5,
+        description: "Service created or pointed at a new binary",
+    },
+    ChangeHeuristic {
+        id: "ifeo_debugger",
+        name: "Image File Execution Options hijack",
+        target: ChangeTarget::Registry,
+        category: HeuristicCategory::Persistence,
+        path_pattern: r"\\image file execution options\\[^\\]+$",
+        value_name_pattern: Some(r"^(debugger|globalflag)$"),
+        data_pattern: None,
+        operations: WRITE,
+        mitre_technique: "T1546.012",
+        score: 8.5,
+        description: "Debugger set to run whenever the target program starts",
+    },
+    ChangeHeuristic {
+        id: "appinit_dlls",
+        name: "AppInit DLLs",
+        target: ChangeTarget::Registry,
+        category: HeuristicCategory::Persistence,
+        path_pattern: r"\\microsoft\\windows nt\\currentversion\\windows$",
+        value_name_pattern: Some(r"^(appinit_dlls|loadappinit_dlls)$"),
+        data_pattern: None,
+        operations: WRITE,
+        mitre_technique: "T1546.010",
+        score: 8.0,
+        description: "DLL loaded into every process that loads user32.dll",
+    },
+    ChangeHeuristic {
+        id: "com_hijack",
+        name: "COM object hijack",
+        target: ChangeTarget::Registry,
+        category: HeuristicCategory::Persistence,
+        path_pattern: r"^hkcu\\software\\classes\\clsid\\\{[^}]+\}\\(inprocserver32|localserver32)$",
+        value_name_pattern: None,
+        data_pattern: None,
+        operations: WRITE,
+        mitre_technique: "T1546.015",
+        score: 6.0,
+        description: "Per-user COM registration shadowing a system class",
+    },
+    ChangeHeuristic {
+        id: "startup_folder_redirect",
+        name: "Startup folder redirected",
+        target: ChangeTarget::Registry,
+        category: HeuristicCategory::Persistence,
+        path_pattern: r"\\explorer\\(user )?shell folders$",
+        value_name_pattern: Some(r"^(common )?startup$"),
+        data_pattern: None,
+        operations: WRITE,
+        mitre_technique: "T1547.001",
+        score: 7.0,
+        description: "Startup folder location changed",
+    },
+    ChangeHeuristic {
+        id: "lsa_packages",
+        name: "LSA package registered",
+        target: ChangeTarget::Registry,
+        category: HeuristicCategory::Persistence,
+        path_pattern: r"\\system\\(currentcontrolset|controlset\d+)\\control\\lsa(\\osconfig)?$",
+        value_name_pattern: Some(r"^(security packages|authentication packages|notification packages)$"),
+        data_pattern: None,
+        operations: WRITE,
+        mitre_technique: "T1547.005",
+        score: 8.5,
+        description: "DLL loaded by LSASS at boot",
+    },
+    // Security tool tampering
+    ChangeHeuristic {
+        id: "defender_disabled",
+        name: "Microsoft Defender disabled",
+        target: ChangeTarget::Registry,
+        category: HeuristicCategory::SecurityTampering,
+        path_pattern: r"\\(policies\\)?microsoft\\windows defender(\\real-time protection|\\spynet|\\features)?$",
+        value_name_pattern: Some(
+            r"^(disableantispyware|disableantivirus|disablerealtimemonitoring|disablebehaviormonitoring|disableioavprotection|disableonaccessprotection|tamperprotection|spynetreporting|submitsamplesconsent)$",
+        ),
+        data_pattern: None,
+        operations: WRITE,
+        mitre_technique: "T1562.001",
+        score: 9.0,
+        description: "Defender protection switched off",
+    },
+    ChangeHeuristic {
+        id: "defender_exclusion",
+        name: "Microsoft Defender exclusion added",
+        target: ChangeTarget::Registry,
+        category: HeuristicCategory::SecurityTampering,
+        path_pattern: r"\\windows defender\\exclusions\\(paths|extensions|processes|ipaddresses)$",
+        value_name_pattern: None,
+        data_pattern: None,
+        operations: WRITE,
+        mitre_technique: "T1562.001",
+        score: 8.5,
+        description: "Path, extension or process excluded from scanning",
+    },
+    ChangeHeuristic {
+        id: "security_service_disabled",
+        name: "Security service disabled",
+        target: ChangeTarget::Registry,
+        category: HeuristicCategory::SecurityTampering,
+        path_pattern: r"\\system\\(currentcontrolset|controlset\d+)\\services\\(windefend|sense|wdnissvc|wdboot|wdfilter|securityhealthservice|wscsvc|mpssvc|eventlog|sysmon\d*|sysmondrv)$",
+        value_name_pattern: Some(r"^start$"),
+        data_pattern: Some(r"^(4|0x0*4|dword:0*4)$"),
+        operations: WRITE,
+        mitre_technique: "T1562.001",
+        score: 9.0,
+        description: "Security or logging service set to disabled",
+    },
+    ChangeHeuristic {
+        id: "firewall_disabled",
+        name: "Windows Firewall disabled",
+        target: ChangeTarget::Registry,
+        category: HeuristicCategory::SecurityTampering,
+        path_pattern: r"\\services\\sharedaccess\\parameters\\firewallpolicy\\(standardprofile|domainprofile|publicprofile)$",
+        value_name_pattern: Some(r"^enablefirewall$"),
+        data_pattern: Some(r"^(0|0x0+|dword:0+)$"),
+        operations: WRITE,
+        mitre_technique: "T1562.004",
+        score: 8.0,
+        description: "Firewall profile turned off",
+    },
+    ChangeHeuristic {
+        id: "uac_disabled",
+        name: "UAC weakened",
+        target: ChangeTarget::Registry,
+        category: HeuristicCategory::SecurityTampering,
+        path_pattern: r"\\microsoft\\windows\\currentversion\\policies\\system$",
+        value_name_pattern: Some(r"^(enablelua|consentpromptbehavioradmin|promptonsecuredesktop)$"),
+        data_pattern: Some(r"^(0|0x0+|dword:0+)$"),
+        operations: WRITE,
+        mitre_technique: "T1548.002",
+        score: 7.5,
+        description: "User Account Control prompts switched off",
+    },
+    ChangeHeuristic {
+        id: "event_channel_disabled",
+        name: "Event log channel disabled",
+        target: ChangeTarget::Registry,
+        category: HeuristicCategory::SecurityTampering,
+        path_pattern: r"\\microsoft\\windows\\currentversion\\winevt\\channels\\[^\\]+$",
+        value_name_pattern: Some(r"^enabled$"),
+        data_pattern: Some(r"^(0|0x0+|dword:0+)$"),
+        operations: WRITE,
+        mitre_technique: "T1562.002",
+        score: 7.5,
+        description: "Event log channel stopped recording",
+    },
+    ChangeHeuristic {
+        id: "wdigest_cleartext",
+        name: "WDigest cleartext credentials",
+        target: ChangeTarget::Registry,
+        category: HeuristicCategory::CredentialAccess,
+        path_pattern: r"\\control\\securityproviders\\wdigest$",
+        value_name_pattern: Some(r"^uselogoncredential$"),
+        data_pattern: Some(r"^(1|0x0*1|dword:0*1)$"),
+        operations: WRITE,
+        mitre_technique: "T1003.001",
+        score: 8.0,
+        description: "LSASS told to keep cleartext passwords in memory",
+    },
+    ChangeHeuristic {
+        id: "vss_service_disabled",
+        name: "Volume Shadow Copy service disabled",
+        target: ChangeTarget::Registry,
+        category: HeuristicCategory::Impact,
+        path_pattern: r"\\system\\(currentcontrolset|controlset\d+)\\services\\(vss|swprv)$",
+        value_name_pattern: Some(r"^start$"),
+        data_pattern: Some(r"^(4|0x0*4|dword:0*4)$"),
+        operations: WRITE,
+        mitre_technique: "T1490",
+        score: 8.0,
+        description: "Shadow copies can no longer be created",
+    },
+    // File system
+    ChangeHeuristic {
+        id: "startup_folder_drop",
+        name: "Startup folder drop",
+        target: ChangeTarget::File,
+        category: HeuristicCategory::Persistence,
+        path_pattern: r"\\start menu\\programs\\startup\\[^\\]+$",
+        value_name_pattern: None,
+        data_pattern: None,
+        operations: CREATE,
+        mitre_technique: "T1547.001",
+        score: 7.5,
+        description: "File placed in a Startup folder runs at logon",
+    },
+    ChangeHeuristic {
+        id: "scheduled_task_file",
+        name: "Scheduled task created",
+        target: ChangeTarget::File,
+        category: HeuristicCategory::Persistence,
+        path_pattern: r"\\windows\\system32\\tasks\\",
+        value_name_pattern: None,
+        data_pattern: None,
+        operations: CREATE,
+        mitre_technique: "T1053.005",
+        score: 7.0,
+        description: "Task definition written to the task store",
+    },
+    ChangeHeuristic {
+        id: "hosts_file_modified",
+        name: "Hosts file modified",
+        target: ChangeTarget::File,
+        category: HeuristicCategory::Impact,
+        path_pattern: r"(\\system32\\drivers\\etc\\hosts|^\\etc\\hosts)$",
+        value_name_pattern: None,
+        data_pattern: None,
+        operations: CREATE,
+        mitre_technique: "T1565.001",
+        score: 8.0,
+        description: "Name resolution redirected through the hosts file",
+    },
+    ChangeHeuristic {
+        id: "lolbin_in_writable_path",
+        name: "System binary dropped in writable directory",
+        target: ChangeTarget::File,
+        category: HeuristicCategory::Masquerading,
+        path_pattern: r"(\\users\\public\\|\\programdata\\|\\windows\\temp\\|\\appdata\\local\\temp\\|\\appdata\\roaming\\)([^\\]+\\)*(certutil|mshta|rundll32|regsvr32|powershell|pwsh|cmd|wscript|cscript|bitsadmin|msbuild|installutil|regasm|regsvcs|wmic|svchost|lsass|csrss|explorer)\.exe$",
+        value_name_pattern: None,
+        data_pattern: None,
+        operations: CREATE,
+        mitre_technique: "T1036.005",
+        score: 8.0,
+        description: "Copy of a trusted system binary outside System32",
+    },
+    ChangeHeuristic {
+        id: "executable_in_writable_path",
+        name: "Executable dropped in writable directory",
+        target: ChangeTarget::File,
+        category: HeuristicCategory::Staging,
+        path_pattern: r"(\\users\\public\\|\\programdata\\|\\windows\\temp\\|\\appdata\\local\\temp\\|\\appdata\\roaming\\|^\\tmp\\|^\\var\\tmp\\|^\\dev\\shm\\)([^\\]+\\)*[^\\]+\.(exe|dll|scr|sys|ps1|vbs|vbe|js|jse|hta|bat|cmd|lnk|elf|so|sh)$",
+        value_name_pattern: None,
+        data_pattern: None,
+        operations: CREATE,
+        mitre_technique: "T1105",
+        score: 4.5,
+        description: "Payload staged where any user can write",
+    },
+    ChangeHeuristic {
+        id: "defender_files_deleted",
+        name: "Security product files deleted",
+        target: ChangeTarget::File,
+        category: HeuristicCategory::SecurityTampering,
+        path_pattern: r"\\(windows defender|windows defender advanced threat protection|microsoft\\windows defender)\\",
+        value_name_pattern: None,
+        data_pattern: None,
+        operations: DELETE,
+        mitre_technique: "T1562.001",
+        score: 8.5,
+        description: "Files of the endpoint protection removed",
+    },
+    ChangeHeuristic {
+        id: "shadow_copy_storage_deleted",
+        name: "Shadow copy storage deleted",
+        target: ChangeTarget::File,
+        category: HeuristicCategory::Impact,
+        path_pattern: r"\\system volume information\\\{[0-9a-f-]+\}",
+        value_name_pattern: None,
+        data_pattern: None,
+        operations: DELETE,
+        mitre_technique: "T1490",
+        score: 8.0,
+        description: "Shadow copy differential area removed",
+    },
+    ChangeHeuristic {
+        id: "unix_cron_persistence",
+        name: "Cron job written",
+        target: ChangeTarget::File,
+        category: HeuristicCategory::Persistence,
+        path_pattern: r"^\\(etc\\cron(tab|\.d|\.hourly|\.daily|\.weekly|\.monthly)|var\\spool\\cron)",
+        value_name_pattern: None,
+        data_pattern: None,
+        operations: CREATE,
+        mitre_technique: "T1053.003",
+        score: 7.0,
+        description: "Command scheduled through cron",
+    },
+    ChangeHeuristic {
+        id: "unix_service_persistence",
+        name: "Systemd or init service written",
+        target: ChangeTarget::File,
+        category: HeuristicCategory::Persistence,
+        path_pattern: r"(^\\etc\\systemd\\system\\[^\\]+\.(service|timer)$|^\\etc\\init\.d\\|^\\etc\\rc\.local$|\\\.config\\systemd\\user\\[^\\]+\.service$)",
+        value_name_pattern: None,
+        data_pattern: None,
+        operations: CREATE,
+        mitre_technique: "T1543.002",
+        score: 7.0,
+        description: "Service unit or init script started at boot",
+    },
+    ChangeHeuristic {
+        id: "unix_shell_profile",
+        name: "Shell profile modified",
+        target: ChangeTarget::File,
+        category: HeuristicCategory::Persistence,
+        path_pattern: r"(\\\.(bashrc|bash_profile|profile|zshrc|zprofile)$|^\\etc\\(profile|bash\.bashrc)$|^\\etc\\profile\.d\\)",
+        value_name_pattern: None,
+        data_pattern: None,
+        operations: CREATE,
+        mitre_technique: "T1546.004",
+        score: 6.0,
+        description: "Commands run whenever a shell starts",
+    },
+    ChangeHeuristic {
+        id: "ld_preload",
+        name: "Dynamic linker preload",
+        target: ChangeTarget::File,
+        category: HeuristicCategory::Persistence,
+        path_pattern: r"^\\etc\\ld\.so\.preload$",
+        value_name_pattern: None,
+        data_pattern: None,
+        operations: CREATE,
+        mitre_technique: "T1574.006",
+        score: 8.5,
+        description: "Library injected into every dynamically linked process",
+    },
+    ChangeHeuristic {
+        id: "ssh_authorized_keys",
+        name: "SSH authorized key added",
+        target: ChangeTarget::File,
+        category: HeuristicCategory::Persistence,
+        path_pattern: r"\\\.ssh\\authorized_keys2?$",
+        value_name_pattern: None,
+        data_pattern: None,
+        operations: CREATE,
+        mitre_technique: "T1098.004",
+        score: 7.5,
+        description: "Key grants remote login to the account",
+    },
+    // Command lines
+    ChangeHeuristic {
+        id: "shadow_copy_deletion",
+        name: "Shadow copies deleted",
+        target: ChangeTarget::Command,
+        category: HeuristicCategory::Impact,
+        path_pattern: r"(vssadmin(\.exe)?\s+(delete\s+shadows|resize\s+shadowstorage)|wmic(\.exe)?\s+shadowcopy\s+delete|win32_shadowcopy.*delete|wbadmin(\.exe)?\s+delete\s+(catalog|systemstatebackup|backup)|bcdedit(\.exe)?.*(recoveryenabled\s+no|bootstatuspolicy\s+ignoreallfailures))",
+        value_name_pattern: None,
+        data_pattern: None,
+        operations: &[],
+        mitre_technique: "T1490",
+        score: 9.5,
+        description: "Backups and shadow copies removed before encryption",
+    },
+    ChangeHeuristic {
+        id: "defender_tamper_command",
+        name: "Defender disabled from the command line",
+        target: ChangeTarget::Command,
+        category: HeuristicCategory::SecurityTampering,
+        path_pattern: r"(set-mppreference.*-(disablerealtimemonitoring|disableioavprotection|disablebehaviormonitoring)\s+(\$true|1)|add-mppreference.*-exclusion(path|process|extension)|sc(\.exe)?\s+(stop|delete|config)\s+(windefend|sense|wdnissvc))",
+        value_name_pattern: None,
+        data_pattern: None,
+        operations: &[],
+        mitre_technique: "T1562.001",
+        score: 9.0,
+        description: "Endpoint protection switched off or given an exclusion",
+    },
+    ChangeHeuristic {
+        id: "event_log_cleared",
+        name: "Event logs cleared",
+        target: ChangeTarget::Command,
+        category: HeuristicCategory::SecurityTampering,
+        path_pattern: r"(wevtutil(\.exe)?\s+(cl|clear-log)\s|clear-eventlog|remove-eventlog)",
+        value_name_pattern: None,
+        data_pattern: None,
+        operations: &[],
+        mitre_technique: "T1070.001",
+        score: 8.5,
+        description: "Windows event logs wiped",
+    },
+    ChangeHeuristic {
+        id: "firewall_disable_command",
+        name: "Firewall disabled from the command line",
+        target: ChangeTarget::Command,
+        category: HeuristicCategory::SecurityTampering,
+        path_pattern: r"(netsh(\.exe)?\s+(advfirewall\s+set\s+\w+\s+state\s+off|firewall\s+set\s+opmode\s+(mode=)?disable)|set-netfirewallprofile.*-enabled\s+(false|0))",
+        value_name_pattern: None,
+        data_pattern: None,
+        operations: &[],
+        mitre_technique: "T1562.004",
+        score: 8.0,
+        description: "Host firewall turned off",
+    },
+];
+
+struct CompiledHeuristic {
+    heuristic: &'static ChangeHeuristic,
+    path: Regex,
+    value_name: Option<Regex>,
+    data: Option<Regex>,
+}
+
+fn compile(pattern: &str) -> Regex {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .expect("built-in change heuristic patterns are valid")
+}
+
+fn library() -> &'static [CompiledHeuristic] {
+    static LIBRARY: OnceLock<Vec<CompiledHeuristic>> = OnceLock::new();
+    LIBRARY.get_or_init(|| {
+        BUILTIN_HEURISTICS
+            .iter()
+            .map(|heuristic| CompiledHeuristic {
+                heuristic,
+                path: compile(heuristic.path_pattern),
+                value_name: heuristic.value_name_pattern.map(compile),
+                data: heuristic.data_pattern.map(compile),
+            })
+            .collect()
+    })
+}
+
+/// A change observed on a host, from detonation or from live response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObservedChange {
+    pub target: ChangeTarget,
+    /// Registry key, file path, or the full command line
+    pub path: String,
+    #[serde(default)]
+    pub value_name: Option<String>,
+    /// Data written to a registry value
+    #[serde(default)]
+    pub data: Option<String>,
+    /// e.g. `SET`, `CREATE`, `MODIFY`, `DELETE`; unset for command lines
+    #[serde(default)]
+    pub operation: Option<String>,
+    #[serde(default)]
+    pub process_name: Option<String>,
+    #[serde(default)]
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeuristicHit {
+    pub heuristic_id: String,
+    pub name: String,
+    pub category: HeuristicCategory,
+    pub mitre_technique: String,
+    pub score: f64,
+    pub description: String,
+}
+
+impl HeuristicHit {
+    fn reason(&self) -> String {
+        format!("{} ({}): {}", self.name, self.mitre_technique, self.description)
+    }
+}
+
+/// A change with the heuristics it tripped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeAssessment {
+    pub change: ObservedChange,
+    pub hits: Vec<HeuristicHit>,
+    /// Top hit score plus a bonus for each further hit, capped at 10
+    pub score: f64,
+}
+
+/// Registry keys get short hive names and no trailing separator; registry
+/// and file paths get `\` separators; command lines are only trimmed
+pub fn normalize_path(target: ChangeTarget, path: &str) -> String {
+    if target == ChangeTarget::Command {
+        return path.trim().to_string();
+    }
+    let path = path.trim().replace('/', "\\");
+    if target == ChangeTarget::File {
+        return path;
+    }
+    let path = path.trim_end_matches('\\');
+    const HIVES: &[(&str, &str)] = &[
+        ("hkey_local_machine", "HKLM"),
+        ("\\registry\\machine", "HKLM"),
+        ("hkey_current_user", "HKCU"),
+        ("hkey_users", "HKU"),
+        ("\\registry\\user", "HKU"),
+        ("hkey_classes_root", "HKCR"),
+        ("hkey_current_config", "HKCC"),
+    ];
+    let lower = path.to_ascii_lowercase();
+    for (long, short) in HIVES {
+        if lower.starts_with(long) {
+            return format!("{}{}", short, &path[long.len()..]);
+        }
+    }
+    path.to_string()
+}
+
+/// Heuristics `change` trips, highest score first
+pub fn evaluate(change: &ObservedChange) -> Vec<HeuristicHit> {
+    let path = normalize_path(change.target, &change.path);
+    let operation = change.operation.as_deref().unwrap_or_default().trim().to_ascii_uppercase();
+    let mut hits: Vec<HeuristicHit> = library()
+        .iter()
+        .filter(|compiled| {
+            let heuristic = compiled.heuristic;
+            heuristic.target == change.target
+                && (heuristic.operations.is_empty() || heuristic.operations.contains(&operation.as_str()))
+                && compiled.path.is_match(&path)
+                && compiled
+                    .value_name
+                    .as_ref()
+                    .is_none_or(|re| change.value_name.as_deref().is_some_and(|name| re.is_match(name.trim())))
+                && compiled.data.as_ref().is_none_or(|re| change.data.as_deref().is_some_and(|data| re.is_match(data.trim())))
+        })
+        .map(|compiled| HeuristicHit {
+            heuristic_id: compiled.heuristic.id.to_string(),
+            name: compiled.heuristic.name.to_string(),
+            category: compiled.heuristic.category,
+            mitre_technique: compiled.heuristic.mitre_technique.to_string(),
+            score: compiled.heuristic.score,
+            description: compiled.heuristic.description.to_string(),
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits
+}
+
+fn combined_score(hits: &[HeuristicHit]) -> f64 {
+    match hits.first() {
+        Some(top) => (top.score + ADDITIONAL_HIT_BONUS * (hits.len() - 1) as f64).min(10.0),
+        None => 0.0,
+    }
+}
+
+/// Assess changes gathered anywhere; only changes that trip a heuristic are returned
+pub fn assess_changes(changes: &[ObservedChange]) -> Vec<ChangeAssessment> {
+    changes
+        .iter()
+        .filter_map(|change| {
+            let hits = evaluate(change);
+            (!hits.is_empty()).then(|| ChangeAssessment { score: combined_score(&hits), change: change.clone(), hits })
+        })
+        .collect()
+}
+
+fn registry_change(change: &RegistryChange) -> ObservedChange {
+    ObservedChange {
+        target: ChangeTarget::Registry,
+        path: change.key_path.clone(),
+        value_name: Some(change.value_name.clone()),
+        data: change.new_value.clone(),
+        operation: Some(change.operation.clone()),
+        process_name: Some(change.process_name.clone()),
+        timestamp: Some(change.timestamp),
+    }
+}
+
+fn file_change(change: &FileChange) -> ObservedChange {
+    ObservedChange {
+        target: ChangeTarget::File,
+        path: change.file_path.clone(),
+        value_name: None,
+        data: None,
+        operation: Some(change.operation.clone()),
+        process_name: Some(change.process_name.clone()),
+        timestamp: Some(change.timestamp),
+    }
+}
+
+fn file_extension(path: &str) -> String {
+    path.rsplit(['\\', '/'])
+        .next()
+        .and_then(|name| name.rsplit_once('.'))
+        .map(|(_, extension)| extension.to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+fn mitre_techniques(hits: &[HeuristicHit]) -> Vec<String> {
+    let mut techniques: Vec<String> = hits.iter().map(|hit| hit.mitre_technique.clone()).collect();
+    techniques.sort();
+    techniques.dedup();
+    techniques
+}
+
+fn security_modification(assessment: &ChangeAssessment, target: &str, timestamp: DateTime<Utc>) -> Option<SecurityModification> {
+    let hit = assessment
+        .hits
+        .iter()
+        .find(|hit| matches!(hit.category, HeuristicCategory::SecurityTampering | HeuristicCategory::Impact | HeuristicCategory::CredentialAccess))?;
+    Some(SecurityModification {
+        modification_type: hit.heuristic_id.clone(),
+        target: target.to_string(),
+        description: hit.reason(),
+        impact: if assessment.score >= 8.0 { "High" } else { "Medium" }.to_string(),
+        timestamp,
+    })
+}
+
+/// Flag the registry writes, file changes and command lines of an analysis
+/// that trip a heuristic. Detonation artifacts count as file changes, so a
+/// real guest run is covered as well as the behavioural monitor's records.
+pub fn apply(
+    system_changes: &SystemChanges,
+    detonation: Option<&DetonationReport>,
+    process_analysis: &ProcessAnalysis,
+    file_system: &mut FileSystemAnalysis,
+    registry: &mut RegistryAnalysis,
+) {
+    for change in &system_changes.registry_changes {
+        let Some(assessment) = assess_changes(&[registry_change(change)]).pop() else { continue };
+        let reasons: Vec<String> = assessment.hits.iter().map(HeuristicHit::reason).collect();
+        if assessment.hits.iter().any(|hit| hit.category == HeuristicCategory::Persistence) {
+            registry.autorun_entries.push(AutorunEntry {
+                name: change.value_name.clone(),
+                path: change.key_path.clone(),
+                command: change.new_value.clone().unwrap_or_default(),
+                location: assessment.hits[0].name.clone(),
+                is_suspicious: true,
+                timestamp: change.timestamp,
+            });
+        }
+        registry.security_modifications.extend(security_modification(&assessment, &change.key_path, change.timestamp));
+        registry.suspicious_keys.push(SuspiciousRegistryKey {
+            key_path: change.key_path.clone(),
+            value_name: change.value_name.clone(),
+            value_data: change.new_value.clone().unwrap_or_default(),
+            suspicion_reasons: reasons,
+            threat_score: assessment.score,
+            created_by: change.process_name.clone(),
+            timestamp: change.timestamp,
+            mitre_techniques: mitre_techniques(&assessment.hits),
+        });
+    }
+
+    let monitored = system_changes
+        .files_created
+        .iter()
+        .chain(&system_changes.files_modified)
+        .chain(&system_changes.files_deleted)
+        .map(|change| (file_change(change), change.file_hash.clone(), change.file_size));
+    let guest = detonation.into_iter().flat_map(|report| {
+        report.artifacts.iter().filter_map(move |artifact| {
+            let operation = match artifact.kind {
+                ArtifactKind::FileCreated => "CREATE",
+                ArtifactKind::FileModified => "MODIFY",
+                ArtifactKind::FileDeleted => "DELETE",
+                ArtifactKind::File => return None,
+            };
+            let change = ObservedChange {
+                target: ChangeTarget::File,
+                path: artifact.path.clone(),
+                value_name: None,
+                data: None,
+                operation: Some(operation.to_string()),
+                process_name: None,
+                timestamp: Some(report.finished_at),
+            };
+            Some((change, artifact.sha256.clone(), artifact.size_bytes))
+        })
+    });
+    for (change, hash, size) in monitored.chain(guest) {
+        if file_system.suspicious_files.iter().any(|file| file.file_path.eq_ignore_ascii_case(&change.path)) {
+            continue;
+        }
+        let Some(assessment) = assess_changes(std::slice::from_ref(&change)).pop() else { continue };
+        if assessment.hits.iter().any(|hit| hit.heuristic_id == "hosts_file_modified") && !file_system.modified_system_files.contains(&change.path) {
+            file_system.modified_system_files.push(change.path.clone());
+        }
+        file_system.suspicious_files.push(SuspiciousFile {
+            file_type: file_extension(&change.path),
+            file_path: change.path.clone(),
+            file_hash: hash.unwrap_or_default(),
+            file_size: size,
+            suspicion_reasons: assessment.hits.iter().map(HeuristicHit::reason).collect(),
+            threat_score: assessment.score,
+            created_by: change.process_name.clone().unwrap_or_default(),
+            timestamp: change.timestamp.unwrap_or_else(Utc::now),
+            mitre_techniques: mitre_techniques(&assessment.hits),
+        });
+    }
+
+    for process in &process_analysis.process_tree.processes {
+        let change = ObservedChange {
+            target: ChangeTarget::Command,
+            path: process.command_line.clone(),
+            value_name: None,
+            data: None,
+            operation: None,
+            process_name: Some(process.process_name.clone()),
+            timestamp: Some(process.creation_time),
+        };
+        if let Some(assessment) = assess_changes(&[change]).pop() {
+            registry.security_modifications.extend(security_modification(&assessment, &process.command_line, process.creation_time));
+        }
+    }
+}
+
+impl SandboxCore {
+    /// The built-in change heuristics
+    pub fn change_heuristics(&self) -> &'static [ChangeHeuristic] {
+        BUILTIN_HEURISTICS
+    }
+
+    /// Score change records gathered outside the sandbox, e.g. by live response
+    pub fn assess_system_changes(&self, changes: &[ObservedChange]) -> Vec<ChangeAssessment> {
+        assess_changes(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(target: ChangeTarget, path: &str, value_name: Option<&str>, data: Option<&str>, operation: &str) -> ObservedChange {
+        ObservedChange {
+            target,
+            path: path.to_string(),
+            value_name: value_name.map(str::to_string),
+            data: data.map(str::to_string),
+            operation: Some(operation.to_string()),
+            process_name: None,
+            timestamp: None,
+        }
+    }
+
+    #[test]
+    fn heuristics_flag_known_bad_changes_only() {
+        let ids = |change: ObservedChange| evaluate(&change).into_iter().map(|hit| hit.heuristic_id).collect::<Vec<_>>();
+
+        let run = change(ChangeTarget::Registry, r"HKEY_CURRENT_USER\Software\Microsoft\Windows\CurrentVersion\Run\", Some("Updater"), Some("C:\\x.exe"), "set");
+        assert_eq!(ids(run), vec!["registry_run_key"]);
+        let defender = r"HKLM\SYSTEM\CurrentControlSet\Services\WinDefend";
+        assert_eq!(ids(change(ChangeTarget::Registry, defender, Some("Start"), Some("4"), "SET")), vec!["security_service_disabled"]);
+        assert!(ids(change(ChangeTarget::Registry, defender, Some("Start"), Some("2"), "SET")).is_empty());
+
+        let dropped = change(ChangeTarget::File, r"C:\Users\Public\Downloads\certutil.exe", None, None, "CREATE");
+        assert_eq!(ids(dropped), vec!["lolbin_in_writable_path", "executable_in_writable_path"]);
+        assert_eq!(ids(change(ChangeTarget::File, "/etc/cron.d/update", None, None, "create")), vec!["unix_cron_persistence"]);
+        assert!(ids(change(ChangeTarget::File, r"C:\Windows\System32\drivers\etc\hosts", None, None, "DELETE")).is_empty());
+
+        let mut vssadmin = change(ChangeTarget::Command, "cmd /c vssadmin.exe Delete Shadows /All /Quiet", None, None, "");
+        vssadmin.operation = None;
+        let assessed = assess_changes(&[vssadmin, change(ChangeTarget::Command, "notepad.exe readme.txt", None, None, "")]);
+        assert_eq!(assessed.len(), 1);
+        assert_eq!(assessed[0].hits[0].mitre_technique, "T1490");
+
+        let combined = assess_changes(&[change(ChangeTarget::File, r"C:\ProgramData\svchost.exe", None, None, "CREATE")]);
+        assert_eq!(combined[0].score, 8.5);
+    }
+}
@@ -0,0 +1,547 @@
+//! ClamAV baseline verdicts
+//!
+//! With a clamd daemon configured, every file sample is streamed to it with
+//! `INSTREAM` as it is submitted, and `clamav` is listed among the analysis
+//! engines. Signature names are recorded in the static analysis, and a
+//! detection feeds the verdict like any other engine: `PUA.` and
+//! `Heuristics.` signatures make a sample suspicious, anything else
+//! malicious. A clean scan never lowers a verdict. Samples submitted while
+//! the engine was off, or recovered from the job store, are scanned when
+//! their analysis runs.
+//!
+//! clamd is reached over TCP (`tcp://host:3310`) or a Unix socket
+//! (`unix:///run/clamav/clamd.ctl`). Its availability and the version and
+//! age of its signature database are reported in the sandbox health.
+
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::engine_plugins::{EngineBehavior, EngineFindings, EngineResult};
+use crate::{AnalysisEngine, AnalysisJob, BehaviorSeverity, EngineType, SandboxCore, SandboxCoreNapi, SandboxVerdict};
+
+/// Analysis engine id of the ClamAV integration
+pub const CLAMAV_ENGINE: &str = "clamav";
+
+/// Bytes per `INSTREAM` chunk
+const CHUNK_BYTES: usize = 64 * 1024;
+/// Largest reply read from clamd
+const MAX_REPLY_BYTES: u64 = 64 * 1024;
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+/// clamd's default `StreamMaxLength`
+fn default_max_stream_bytes() -> u64 {
+    25 * 1024 * 1024
+}
+
+fn default_max_signature_age_hours() -> u32 {
+    48
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClamAvConfig {
+    /// `tcp://host:port` or `unix:///path/to/clamd.sock`
+    pub address: String,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Larger samples are not sent; clamd's `StreamMaxLength` must allow this
+    #[serde(default = "default_max_stream_bytes")]
+    pub max_stream_bytes: u64,
+    /// Older signature databases degrade health
+    #[serde(default = "default_max_signature_age_hours")]
+    pub max_signature_age_hours: u32,
+}
+
+impl ClamAvConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        ClamdAddress::parse(&self.address)?;
+        if self.timeout_secs == 0 {
+            return Err("timeout_secs must be positive".to_string());
+        }
+        if self.max_stream_bytes == 0 {
+            return Err("max_stream_bytes must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClamdAddress {
+    Tcp(String),
+    Unix(std::path::PathBuf),
+}
+
+impl ClamdAddress {
+    pub fn parse(address: &str) -> Result<Self, String> {
+        let address = address.trim();
+        if let Some(host) = address.strip_prefix("tcp://") {
+            let (name, port) = host.rsplit_once(':').ok_or_else(|| format!("{} has no port", address))?;
+            if name.is_empty() || port.parse::<u16>().is_err() {
+                return Err(format!("Invalid clamd address {}", address));
+            }
+            return Ok(Self::Tcp(host.to_string()));
+        }
+        match address.strip_prefix("unix://") {
+            Some(path) if path.starts_with('/') => Ok(Self::Unix(path.into())),
+            _ => Err(format!("clamd address must be tcp://host:port or unix:///path, got {}", address)),
+        }
+    }
+}
+
+/// Transport to clamd; replies are returned without the trailing NUL
+#[async_trait]
+pub trait ClamdClient: Send + Sync {
+    /// `VERSION`, e.g. `ClamAV 1.3.1/27421/Tue Oct 15 08:21:46 2024`
+    async fn version(&self) -> Result<String, String>;
+
+    /// `INSTREAM`, e.g. `stream: Win.Test.EICAR_HDB-1 FOUND`
+    async fn scan(&self, data: &[u8]) -> Result<String, String>;
+}
+
+/// clamd over its socket, one connection per command
+pub struct ClamdSocket {
+    address: ClamdAddress,
+    timeout: Duration,
+}
+
+impl ClamdSocket {
+    pub fn new(config: &ClamAvConfig) -> Result<Self, String> {
+        Ok(Self { address: ClamdAddress::parse(&config.address)?, timeout: Duration::from_secs(config.timeout_secs) })
+    }
+
+    async fn command(&self, command: &[u8], data: Option<&[u8]>) -> Result<String, String> {
+        let reply = async {
+            match &self.address {
+                ClamdAddress::Tcp(host) => {
+                    let stream = tokio::net::TcpStream::connect(host).await.map_err(|e| format!("connect {}: {}", host, e))?;
+                    exchange(stream, command, data).await
+                }
+                #[cfg(unix)]
+                ClamdAddress::Unix(path) => {
+                    let stream = tokio::net::UnixStream::connect(path).await.map_err(|e| format!("connect {}: {}", path.display(), e))?;
+                    exchange(stream, command, data).await
+                }
+                #[cfg(not(unix))]
+                ClamdAddress::Unix(_) => Err("Unix sockets are not supported on this platform".to_string()),
+            }
+        };
+        tokio::time::timeout(self.timeout, reply)
+            .await
+            .unwrap_or_else(|_| Err(format!("clamd did not answer within {}s", self.timeout.as_secs())))
+    }
+}
+
+async fn exchange<S: AsyncRead + AsyncWrite + Unpin>(mut stream: S, command: &[u8], data: Option<&[u8]>) -> Result<String, String> {
+    let io = |e: std::io::Error| format!("clamd: {}", e);
+    stream.write_all(command).await.map_err(io)?;
+    if let Some(data) = data {
+        for chunk in data.chunks(CHUNK_BYTES) {
+            stream.write_all(&(chunk.len() as u32).to_be_bytes()).await.map_err(io)?;
+            stream.write_all(chunk).await.map_err(io)?;
+        }
+        stream.write_all(&0u32.to_be_bytes()).await.map_err(io)?;
+    }
+    stream.flush().await.map_err(io)?;
+    let mut reply = Vec::new();
+    (&mut stream).take(MAX_REPLY_BYTES).read_to_end(&mut reply).await.map_err(io)?;
+    Ok(String::from_utf8_lossy(&reply).trim_end_matches(['\0', '\n', '\r']).to_string())
+}
+
+#[async_trait]
+impl ClamdClient for ClamdSocket {
+    async fn version(&self) -> Result<String, String> {
+        self.command(b"zVERSION\0", None).await
+    }
+
+    async fn scan(&self, data: &[u8]) -> Result<String, String> {
+        self.command(b"zINSTREAM\0", Some(data)).await
+    }
+}
+
+/// Signature names in an `INSTREAM` reply; `Err` for a clamd error
+pub fn parse_scan_reply(reply: &str) -> Result<Vec<String>, String> {
+    let mut signatures = Vec::new();
+    for line in reply.split(['\0', '\n']).map(str::trim).filter(|line| !line.is_empty()) {
+        let result = line.strip_prefix("stream:").map_or(line, str::trim);
+        if result == "OK" {
+            continue;
+        }
+        match result.strip_suffix(" FOUND") {
+            Some(signature) => signatures.push(signature.trim().to_string()),
+            None => return Err(line.to_string()),
+        }
+    }
+    Ok(signatures)
+}
+
+/// Engine and signature database reported by `VERSION`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClamdVersion {
+    pub engine: String,
+    pub signature_version: Option<u64>,
+    pub signature_date: Option<DateTime<Utc>>,
+}
+
+impl ClamdVersion {
+    pub fn parse(reply: &str) -> Self {
+        let mut parts = reply.trim().splitn(3, '/');
+        let engine = parts.next().unwrap_or_default().trim().to_string();
+        let signature_version = parts.next().and_then(|v| v.trim().parse().ok());
+        // ctime format, with the day padded by a space
+        let signature_date = parts.next().and_then(|date| {
+            let date = date.split_whitespace().collect::<Vec<_>>().join(" ");
+            NaiveDateTime::parse_from_str(&date, "%a %b %d %H:%M:%S %Y").ok().map(|at| at.and_utc())
+        });
+        Self { engine, signature_version, signature_date }
+    }
+
+    pub fn signature_age_hours(&self, now: DateTime<Utc>) -> Option<f64> {
+        self.signature_date.map(|date| (now - date).num_minutes().max(0) as f64 / 60.0)
+    }
+}
+
+/// One scan of a sample, kept in its static analysis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AntivirusScan {
+    pub engine: String,
+    pub engine_version: Option<String>,
+    pub signature_version: Option<u64>,
+    pub scanned_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    /// Signature names; empty for a clean scan
+    pub detections: Vec<String>,
+    pub error: Option<String>,
+}
+
+fn is_low_confidence(signature: &str) -> bool {
+    signature.starts_with("PUA.") || signature.starts_with("Heuristics.")
+}
+
+impl AntivirusScan {
+    /// Verdict and confidence of the detections; `None` when nothing was found
+    pub fn verdict(&self) -> Option<(SandboxVerdict, f64)> {
+        if self.detections.is_empty() {
+            None
+        } else if self.detections.iter().all(|signature| is_low_confidence(signature)) {
+            Some((SandboxVerdict::Suspicious, 0.7))
+        } else {
+            Some((SandboxVerdict::Malicious, 0.9))
+        }
+    }
+
+    /// Family named by a `Platform.Category.Family-id` signature
+    pub fn family(&self) -> Option<String> {
+        self.detections.iter().filter(|signature| !is_low_confidence(signature)).find_map(|signature| {
+            let mut parts = signature.splitn(3, '.');
+            let (_, category, name) = (parts.next()?, parts.next()?, parts.next()?);
+            let family = name.split(['-', '.']).next().unwrap_or_default();
+            (category != "Test" && !family.is_empty()).then(|| family.to_string())
+        })
+    }
+
+    /// The scan as an engine run for `merge_engine_findings`
+    pub fn engine_run(&self) -> (EngineResult, EngineFindings) {
+        let verdict = self.verdict();
+        let findings = EngineFindings {
+            verdict: verdict.as_ref().map(|(verdict, _)| verdict.clone()),
+            confidence: verdict.as_ref().map(|(_, confidence)| *confidence),
+            family: self.family(),
+            behaviors: self
+                .detections
+                .iter()
+                .map(|signature| EngineBehavior {
+                    description: format!("ClamAV signature {} matched", signature),
+                    severity: if is_low_confidence(signature) { BehaviorSeverity::Medium } else { BehaviorSeverity::High },
+                    confidence: if is_low_confidence(signature) { 0.7 } else { 0.9 },
+                    evidence: vec![signature.clone()],
+                    mitre_technique: None,
+                })
+                .collect(),
+            ..EngineFindings::default()
+        };
+        let result = EngineResult {
+            engine: self.engine.clone(),
+            version: self.engine_version.clone().unwrap_or_else(|| "unknown".to_string()),
+            duration_ms: self.duration_ms,
+            verdict: findings.verdict.clone(),
+            confidence: findings.confidence,
+            family: findings.family.clone(),
+            behaviors: findings.behaviors.len(),
+            iocs: 0,
+            mitre_techniques: 0,
+            error: self.error.clone(),
+        };
+        (result, findings)
+    }
+}
+
+/// clamd reachability and signature freshness
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClamAvStatus {
+    pub address: String,
+    pub enabled: bool,
+    pub reachable: bool,
+    pub latency_ms: u64,
+    pub version: Option<ClamdVersion>,
+    pub signature_age_hours: Option<f64>,
+    pub max_signature_age_hours: u32,
+    pub error: Option<String>,
+}
+
+impl ClamAvStatus {
+    pub fn signatures_stale(&self) -> bool {
+        self.signature_age_hours.is_some_and(|age| age > f64::from(self.max_signature_age_hours))
+    }
+}
+
+struct Daemon {
+    config: ClamAvConfig,
+    client: Arc<dyn ClamdClient>,
+}
+
+#[derive(Default)]
+pub struct ClamAvState {
+    daemon: RwLock<Option<Arc<Daemon>>>,
+    version: Mutex<Option<ClamdVersion>>,
+    /// Scans taken at submission, by sample id, with the owning tenant
+    scans: Mutex<HashMap<String, (String, AntivirusScan)>>,
+}
+
+impl ClamAvState {
+    fn daemon(&self) -> Option<Arc<Daemon>> {
+        self.daemon.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn version(&self) -> Option<ClamdVersion> {
+        self.version.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    async fn probe(&self, daemon: &Daemon) -> Result<ClamdVersion, String> {
+        let version = ClamdVersion::parse(&daemon.client.version().await?);
+        *self.version.lock().unwrap_or_else(|e| e.into_inner()) = Some(version.clone());
+        Ok(version)
+    }
+
+    async fn scan(&self, daemon: &Daemon, data: &[u8]) -> AntivirusScan {
+        let started = Instant::now();
+        let version = self.version();
+        let mut scan = AntivirusScan {
+            engine: CLAMAV_ENGINE.to_string(),
+            engine_version: version.as_ref().map(|v| v.engine.clone()),
+            signature_version: version.and_then(|v| v.signature_version),
+            scanned_at: Utc::now(),
+            duration_ms: 0,
+            detections: Vec::new(),
+            error: None,
+        };
+        if data.len() as u64 > daemon.config.max_stream_bytes {
+            scan.error = Some(format!("Sample of {} bytes exceeds the {} byte stream limit", data.len(), daemon.config.max_stream_bytes));
+            return scan;
+        }
+        match daemon.client.scan(data).await.and_then(|reply| parse_scan_reply(&reply)) {
+            Ok(detections) => scan.detections = detections,
+            Err(error) => scan.error = Some(error),
+        }
+        scan.duration_ms = started.elapsed().as_millis() as u64;
+        scan
+    }
+
+    pub(crate) fn forget_tenant(&self, tenant_id: &str) -> usize {
+        let mut scans = self.scans.lock().unwrap_or_else(|e| e.into_inner());
+        let before = scans.len();
+        scans.retain(|_, (owner, _)| owner != tenant_id);
+        before - scans.len()
+    }
+}
+
+impl SandboxCore {
+    /// Scan samples with the clamd at `config.address`, or stop with `None`
+    pub async fn configure_clamav(&self, config: Option<ClamAvConfig>) -> Result<Option<ClamAvStatus>, String> {
+        match config {
+            Some(config) => {
+                config.validate()?;
+                let client = Arc::new(ClamdSocket::new(&config)?);
+                self.configure_clamav_with_client(config, client).await.map(Some)
+            }
+            None => {
+                *self.clamav.daemon.write().unwrap_or_else(|e| e.into_inner()) = None;
+                *self.clamav.version.lock().unwrap_or_else(|e| e.into_inner()) = None;
+                self.analysis_engines.write().await.remove(CLAMAV_ENGINE);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Scan through `client`; an unreachable daemon is installed anyway and
+    /// reported in the returned status
+    pub async fn configure_clamav_with_client(&self, config: ClamAvConfig, client: Arc<dyn ClamdClient>) -> Result<ClamAvStatus, String> {
+        config.validate()?;
+        let daemon = Arc::new(Daemon { config, client });
+        *self.clamav.version.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        *self.clamav.daemon.write().unwrap_or_else(|e| e.into_inner()) = Some(daemon.clone());
+
+        let status = self.probe_clamav(&daemon).await;
+        let mut engines = self.analysis_engines.write().await;
+        let priority = engines.values().map(|engine| engine.priority).max().unwrap_or(0) + 1;
+        let previous = engines.get(CLAMAV_ENGINE);
+        let engine = AnalysisEngine {
+            engine_id: CLAMAV_ENGINE.to_string(),
+            engine_type: EngineType::Antivirus,
+            version: status.version.as_ref().map_or_else(|| "unknown".to_string(), |v| v.engine.clone()),
+            capabilities: vec!["Signature Detection".to_string(), "Archive Scanning".to_string()],
+            priority: previous.map_or(priority, |engine| engine.priority),
+            enabled: previous.is_none_or(|engine| engine.enabled),
+        };
+        engines.insert(CLAMAV_ENGINE.to_string(), engine);
+        Ok(status)
+    }
+
+    async fn probe_clamav(&self, daemon: &Daemon) -> ClamAvStatus {
+        let started = Instant::now();
+        let probed = self.clamav.probe(daemon).await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+        let enabled = self.analysis_engines.read().await.get(CLAMAV_ENGINE).is_none_or(|engine| engine.enabled);
+        let (version, error) = match probed {
+            Ok(version) => (Some(version), None),
+            Err(error) => (None, Some(error)),
+        };
+        ClamAvStatus {
+            address: daemon.config.address.clone(),
+            enabled,
+            reachable: error.is_none(),
+            latency_ms,
+            signature_age_hours: version.as_ref().and_then(|v| v.signature_age_hours(Utc::now())),
+            version,
+            max_signature_age_hours: daemon.config.max_signature_age_hours,
+            error,
+        }
+    }
+
+    /// Probe clamd now; `None` when ClamAV is not configured
+    pub async fn clamav_status(&self) -> Option<ClamAvStatus> {
+        let daemon = self.clamav.daemon()?;
+        Some(self.probe_clamav(&daemon).await)
+    }
+
+    async fn clamav_daemon(&self) -> Option<Arc<Daemon>> {
+        let daemon = self.clamav.daemon()?;
+        let enabled = self.analysis_engines.read().await.get(CLAMAV_ENGINE).is_some_and(|engine| engine.enabled);
+        enabled.then_some(daemon)
+    }
+
+    /// Scan a newly submitted file and keep the result for its analysis
+    pub(crate) async fn scan_on_submission(&self, tenant_id: &str, sample_id: &str, data: &[u8]) {
+        let Some(daemon) = self.clamav_daemon().await else {
+            return;
+        };
+        let scan = self.clamav.scan(&daemon, data).await;
+        if let Some(error) = &scan.error {
+            log::warn!("ClamAV scan of {} failed: {}", sample_id, error);
+        }
+        self.clamav.scans.lock().unwrap_or_else(|e| e.into_inner()).insert(sample_id.to_string(), (tenant_id.to_string(), scan));
+    }
+
+    /// The submission scan of the job's sample, scanning now when there is none
+    pub(crate) async fn antivirus_scan(&self, job: &AnalysisJob) -> Option<AntivirusScan> {
+        if job.analysis_config.target_url.is_some() {
+            return None;
+        }
+        let daemon = self.clamav_daemon().await?;
+        let taken = self.clamav.scans.lock().unwrap_or_else(|e| e.into_inner()).remove(&job.sample_id);
+        if let Some((_, scan)) = taken {
+            return Some(scan);
+        }
+        let data = self.sample_data.read().await.get(&job.sample_id).cloned()?;
+        Some(self.clamav.scan(&daemon, &data).await)
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// Scan samples with clamd, e.g. `{"address": "tcp://clamd:3310"}`;
+    /// pass no config to stop. Returns the daemon status
+    #[napi]
+    pub async fn configure_clamav(&self, config_json: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize(auth_token, "rule:manage", "clamav")?;
+        let config: Option<ClamAvConfig> = config_json
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse ClamAV config: {}", e)))?;
+
+        let status = self.inner.configure_clamav(config).await;
+        let status = self.audit.record(&actor, "configure_clamav", "clamav", serde_json::json!({ "config": config_json }), status)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to configure ClamAV: {}", e)))?;
+
+        serde_json::to_string(&status)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize ClamAV status: {}", e)))
+    }
+
+    /// clamd reachability and signature age; `null` when not configured
+    #[napi]
+    pub async fn get_clamav_status(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.clamav_status().await)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize ClamAV status: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AnalysisPriority;
+
+    struct FakeClamd;
+
+    #[async_trait]
+    impl ClamdClient for FakeClamd {
+        async fn version(&self) -> Result<String, String> {
+            Ok("ClamAV 1.3.1/27421/Tue Oct  1 08:21:46 2024".to_string())
+        }
+
+        async fn scan(&self, data: &[u8]) -> Result<String, String> {
+            Ok(if data.windows(6).any(|w| w == b"EMOTET") { "stream: Win.Trojan.Emotet-9953347-0 FOUND" } else { "stream: OK" }.to_string())
+        }
+    }
+
+    #[test]
+    fn test_parses_clamd_replies() {
+        assert_eq!(parse_scan_reply("stream: OK"), Ok(vec![]));
+        assert_eq!(parse_scan_reply("stream: PUA.Win.Packer.Upx-1 FOUND\nstream: Win.Test.EICAR_HDB-1 FOUND"), Ok(vec!["PUA.Win.Packer.Upx-1".to_string(), "Win.Test.EICAR_HDB-1".to_string()]));
+        assert!(parse_scan_reply("INSTREAM size limit exceeded. ERROR").is_err());
+        assert_eq!(ClamdAddress::parse("unix:///run/clamav/clamd.ctl"), Ok(ClamdAddress::Unix("/run/clamav/clamd.ctl".into())));
+        assert!(ClamdAddress::parse("clamd:3310").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_clamav_detections_reach_static_analysis_and_verdict() {
+        let core = SandboxCore::new().unwrap();
+        let config: ClamAvConfig = serde_json::from_value(serde_json::json!({ "address": "tcp://127.0.0.1:3310" })).unwrap();
+        let status = core.configure_clamav_with_client(config, Arc::new(FakeClamd)).await.unwrap();
+        let version = status.version.clone().unwrap();
+        assert_eq!((version.engine.as_str(), version.signature_version), ("ClamAV 1.3.1", Some(27421)));
+        assert!(status.signatures_stale());
+
+        let sample_id = core.submit_sample("acme", b"MZ\x90\x00 EMOTET loader", "invoice.exe".to_string(), AnalysisPriority::Normal, vec![], false).await.unwrap();
+        core.process_queue().await.unwrap();
+        let analysis = core.get_analysis("acme", &sample_id).await.unwrap().unwrap();
+
+        let scan = analysis.static_analysis.antivirus.as_ref().unwrap();
+        assert_eq!(scan.detections, ["Win.Trojan.Emotet-9953347-0"]);
+        assert!(matches!(analysis.verdict, SandboxVerdict::Malicious));
+        assert_eq!(analysis.malware_classification.family.as_deref(), Some("Emotet"));
+        let result = analysis.engine_results.iter().find(|r| r.engine == CLAMAV_ENGINE).unwrap();
+        assert_eq!((result.behaviors, result.error.is_none()), (1, true));
+
+        core.configure_clamav(None).await.unwrap();
+        assert!(core.clamav_status().await.is_none());
+        assert!(!core.analysis_engines.read().await.contains_key(CLAMAV_ENGINE));
+    }
+}
@@ -0,0 +1,1069 @@
+//! Distributed analysis across worker nodes
+//!
+//! One SandboxCore acts as coordinator: with a coordinator enabled,
+//! `process_queue` hands queued jobs (with their sample bytes) to a shared
+//! cluster queue instead of analysing them locally, and folds finished
+//! analyses back into the coordinator's results. Any number of SandboxCore
+//! instances run as workers: they register, pull jobs up to their
+//! concurrency limit, heartbeat with per-job progress and post the completed
+//! analysis. A worker that misses heartbeats for longer than the timeout is
+//! declared dead and its jobs go back on the queue, up to a maximum number of
+//! attempts per job.
+//!
+//! Workers reach the coordinator through a `ClusterClient`: in-process for
+//! cores sharing a runtime (coordinators register under their cluster name),
+//! or NATS request/reply behind the `nats` feature.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::{watch, Mutex as AsyncMutex, RwLock};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::{AnalysisJob, AnalysisPriority, JobStatus, SandboxAnalysis, SandboxCore};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClusterConfig {
+    /// Name workers use to find the coordinator; also the NATS subject suffix
+    pub cluster_name: String,
+    /// Workers silent for longer than this are declared dead
+    pub heartbeat_timeout_secs: u64,
+    /// Assignments per job before it is failed
+    pub max_attempts: u32,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            cluster_name: "default".to_string(),
+            heartbeat_timeout_secs: 30,
+            max_attempts: 3,
+        }
+    }
+}
+
+impl ClusterConfig {
+    pub fn subject(&self) -> String {
+        format!("phantom.sandbox.{}", self.cluster_name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerRegistration {
+    pub worker_id: String,
+    pub hostname: String,
+    pub max_concurrent_jobs: usize,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkerState {
+    Active,
+    Dead,
+    Left,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerStatus {
+    pub worker_id: String,
+    pub hostname: String,
+    pub capabilities: Vec<String>,
+    pub max_concurrent_jobs: usize,
+    pub state: WorkerState,
+    pub registered_at: DateTime<Utc>,
+    pub last_heartbeat: DateTime<Utc>,
+    pub active_jobs: Vec<String>,
+    /// Progress last reported per active job, 0–100
+    pub progress: HashMap<String, f64>,
+    pub completed_jobs: u64,
+    pub failed_jobs: u64,
+}
+
+/// A job as shipped to a worker, carrying the sample it analyses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterJob {
+    pub job: AnalysisJob,
+    pub sample: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClusterRequest {
+    Register(WorkerRegistration),
+    Heartbeat {
+        worker_id: String,
+        #[serde(default)]
+        progress: HashMap<String, f64>,
+    },
+    Pull {
+        worker_id: String,
+    },
+    Complete {
+        worker_id: String,
+        job_id: String,
+        analysis: Box<SandboxAnalysis>,
+    },
+    Fail {
+        worker_id: String,
+        job_id: String,
+        error: String,
+    },
+    Leave {
+        worker_id: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClusterResponse {
+    Ack,
+    Job { job: Option<Box<ClusterJob>> },
+    /// The worker is unknown or was declared dead and must register again
+    Rejected { reason: String },
+}
+
+/// Finished cluster work waiting to be folded into the coordinator's results
+#[derive(Debug, Clone)]
+pub enum ClusterOutcome {
+    Completed {
+        job_id: String,
+        worker_id: String,
+        analysis: Box<SandboxAnalysis>,
+    },
+    Failed {
+        job_id: String,
+        error: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Assignment {
+    Pending,
+    Assigned { worker_id: String },
+}
+
+struct TrackedJob {
+    job: ClusterJob,
+    assignment: Assignment,
+    attempts: u32,
+    enqueued_at: DateTime<Utc>,
+}
+
+fn priority_rank(priority: &AnalysisPriority) -> u8 {
+    match priority {
+        AnalysisPriority::Emergency => 5,
+        AnalysisPriority::Critical => 4,
+        AnalysisPriority::High => 3,
+        AnalysisPriority::Normal => 2,
+        AnalysisPriority::Low => 1,
+    }
+}
+
+#[derive(Default)]
+struct CoordinatorState {
+    workers: HashMap<String, WorkerStatus>,
+    jobs: HashMap<String, TrackedJob>,
+    outcomes: Vec<ClusterOutcome>,
+    completed: u64,
+    failed: u64,
+    reassigned: u64,
+}
+
+impl CoordinatorState {
+    fn release(&mut self, worker_id: &str, job_id: &str) {
+        if let Some(worker) = self.workers.get_mut(worker_id) {
+            worker.active_jobs.retain(|id| id != job_id);
+            worker.progress.remove(job_id);
+        }
+    }
+
+    /// Put a job back on the queue, or fail it once attempts run out
+    fn requeue(&mut self, job_id: &str, max_attempts: u32, reason: &str) {
+        let Some(tracked) = self.jobs.get_mut(job_id) else { return };
+        if tracked.attempts >= max_attempts {
+            self.jobs.remove(job_id);
+            self.failed += 1;
+            self.outcomes.push(ClusterOutcome::Failed {
+                job_id: job_id.to_string(),
+                error: format!("{} (gave up after {} attempts)", reason, max_attempts),
+            });
+        } else {
+            tracked.assignment = Assignment::Pending;
+            self.reassigned += 1;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoordinatorStatus {
+    pub cluster_name: String,
+    pub workers: Vec<WorkerStatus>,
+    pub pending_jobs: usize,
+    pub assigned_jobs: usize,
+    pub completed_jobs: u64,
+    pub failed_jobs: u64,
+    pub reassigned_jobs: u64,
+}
+
+/// Shared job queue and worker registry
+pub struct ClusterCoordinator {
+    config: ClusterConfig,
+    state: Mutex<CoordinatorState>,
+}
+
+impl ClusterCoordinator {
+    pub fn new(config: ClusterConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(CoordinatorState::default()),
+        }
+    }
+
+    pub fn config(&self) -> &ClusterConfig {
+        &self.config
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CoordinatorState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn enqueue(&self, job: ClusterJob) {
+        let job_id = job.job.job_id.clone();
+        self.lock().jobs.insert(
+            job_id,
+            TrackedJob {
+                job,
+                assignment: Assignment::Pending,
+                attempts: 0,
+                enqueued_at: Utc::now(),
+            },
+        );
+    }
+
+    pub fn handle(&self, request: ClusterRequest) -> ClusterResponse {
+        let now = Utc::now();
+        let mut state = self.lock();
+        match request {
+            ClusterRequest::Register(registration) => {
+                let previous = state.workers.remove(&registration.worker_id);
+                // A worker re-registering has lost whatever it was running
+                if let Some(previous) = &previous {
+                    for job_id in &previous.active_jobs {
+                        state.requeue(job_id, self.config.max_attempts, "Worker restarted");
+                    }
+                }
+                log::info!("Worker {} registered from {}", registration.worker_id, registration.hostname);
+                state.workers.insert(
+                    registration.worker_id.clone(),
+                    WorkerStatus {
+                        worker_id: registration.worker_id,
+                        hostname: registration.hostname,
+                        capabilities: registration.capabilities,
+                        max_concurrent_jobs: registration.max_concurrent_jobs.max(1),
+                        state: WorkerState::Active,
+                        registered_at: now,
+                        last_heartbeat: now,
+                        active_jobs: Vec::new(),
+                        progress: HashMap::new(),
+                        completed_jobs: previous.as_ref().map_or(0, |p| p.completed_jobs),
+                        failed_jobs: previous.as_ref().map_or(0, |p| p.failed_jobs),
+                    },
+                );
+                ClusterResponse::Ack
+            }
+            ClusterRequest::Heartbeat { worker_id, progress } => {
+                let Some(worker) = state.workers.get_mut(&worker_id).filter(|w| w.state == WorkerState::Active) else {
+                    return rejected(&worker_id);
+                };
+                worker.last_heartbeat = now;
+                for (job_id, value) in progress {
+                    if worker.active_jobs.contains(&job_id) {
+                        worker.progress.insert(job_id, value.clamp(0.0, 100.0));
+                    }
+                }
+                ClusterResponse::Ack
+            }
+            ClusterRequest::Pull { worker_id } => {
+                let Some(worker) = state.workers.get(&worker_id).filter(|w| w.state == WorkerState::Active) else {
+                    return rejected(&worker_id);
+                };
+                if worker.active_jobs.len() >= worker.max_concurrent_jobs {
+                    return ClusterResponse::Job { job: None };
+                }
+
+                let next = state
+                    .jobs
+                    .iter()
+                    .filter(|(_, tracked)| tracked.assignment == Assignment::Pending)
+                    .max_by(|(_, a), (_, b)| {
+                        priority_rank(&a.job.job.priority)
+                            .cmp(&priority_rank(&b.job.job.priority))
+                            .then(b.enqueued_at.cmp(&a.enqueued_at))
+                    })
+                    .map(|(job_id, _)| job_id.clone());
+                let Some(job_id) = next else {
+                    return ClusterResponse::Job { job: None };
+                };
+
+                let tracked = state.jobs.get_mut(&job_id).expect("job selected above");
+                tracked.assignment = Assignment::Assigned { worker_id: worker_id.clone() };
+                tracked.attempts += 1;
+                let job = tracked.job.clone();
+                if let Some(worker) = state.workers.get_mut(&worker_id) {
+                    worker.last_heartbeat = now;
+                    worker.active_jobs.push(job_id.clone());
+                    worker.progress.insert(job_id, 0.0);
+                }
+                ClusterResponse::Job { job: Some(Box::new(job)) }
+            }
+            ClusterRequest::Complete { worker_id, job_id, analysis } => {
+                // Late results from a worker presumed dead are still accepted
+                // as long as nobody else finished the job first
+                if state.jobs.remove(&job_id).is_none() {
+                    state.release(&worker_id, &job_id);
+                    return ClusterResponse::Rejected { reason: format!("Job {} is no longer outstanding", job_id) };
+                }
+                state.release(&worker_id, &job_id);
+                if let Some(worker) = state.workers.get_mut(&worker_id) {
+                    worker.completed_jobs += 1;
+                }
+                state.completed += 1;
+                state.outcomes.push(ClusterOutcome::Completed { job_id, worker_id, analysis });
+                ClusterResponse::Ack
+            }
+            ClusterRequest::Fail { worker_id, job_id, error } => {
+                state.release(&worker_id, &job_id);
+                if let Some(worker) = state.workers.get_mut(&worker_id) {
+                    worker.failed_jobs += 1;
+                }
+                state.requeue(&job_id, self.config.max_attempts, &error);
+                ClusterResponse::Ack
+            }
+            ClusterRequest::Leave { worker_id } => {
+                let active = match state.workers.get_mut(&worker_id) {
+                    Some(worker) => {
+                        worker.state = WorkerState::Left;
+                        worker.progress.clear();
+                        std::mem::take(&mut worker.active_jobs)
+                    }
+                    None => return rejected(&worker_id),
+                };
+                for job_id in active {
+                    state.requeue(&job_id, self.config.max_attempts, "Worker left the cluster");
+                }
+                ClusterResponse::Ack
+            }
+        }
+    }
+
+    /// Declare silent workers dead and requeue their jobs. Returns the ids of
+    /// the workers newly declared dead.
+    pub fn reap(&self, now: DateTime<Utc>) -> Vec<String> {
+        let timeout = Duration::seconds(self.config.heartbeat_timeout_secs.min(i64::MAX as u64) as i64);
+        let mut state = self.lock();
+        let mut dead = Vec::new();
+        let mut orphaned = Vec::new();
+        for worker in state.workers.values_mut() {
+            if worker.state == WorkerState::Active && now - worker.last_heartbeat > timeout {
+                worker.state = WorkerState::Dead;
+                worker.progress.clear();
+                orphaned.append(&mut worker.active_jobs);
+                dead.push(worker.worker_id.clone());
+            }
+        }
+        for worker_id in &dead {
+            log::warn!("Worker {} missed heartbeats, reassigning its jobs", worker_id);
+        }
+        for job_id in orphaned {
+            state.requeue(&job_id, self.config.max_attempts, "Worker stopped responding");
+        }
+        dead
+    }
+
+    pub fn drain_outcomes(&self) -> Vec<ClusterOutcome> {
+        std::mem::take(&mut self.lock().outcomes)
+    }
+
+    pub fn status(&self) -> CoordinatorStatus {
+        let state = self.lock();
+        let mut workers: Vec<WorkerStatus> = state.workers.values().cloned().collect();
+        workers.sort_by(|a, b| a.worker_id.cmp(&b.worker_id));
+        let pending_jobs = state.jobs.values().filter(|job| job.assignment == Assignment::Pending).count();
+        CoordinatorStatus {
+            cluster_name: self.config.cluster_name.clone(),
+            workers,
+            pending_jobs,
+            assigned_jobs: state.jobs.len() - pending_jobs,
+            completed_jobs: state.completed,
+            failed_jobs: state.failed,
+            reassigned_jobs: state.reassigned,
+        }
+    }
+}
+
+fn rejected(worker_id: &str) -> ClusterResponse {
+    ClusterResponse::Rejected { reason: format!("Worker {} is not registered", worker_id) }
+}
+
+fn local_coordinators() -> &'static Mutex<HashMap<String, Arc<ClusterCoordinator>>> {
+    static COORDINATORS: OnceLock<Mutex<HashMap<String, Arc<ClusterCoordinator>>>> = OnceLock::new();
+    COORDINATORS.get_or_init(Default::default)
+}
+
+/// How a worker talks to its coordinator
+#[async_trait]
+pub trait ClusterClient: Send + Sync {
+    async fn call(&self, request: ClusterRequest) -> Result<ClusterResponse, String>;
+}
+
+/// Direct calls into a coordinator in the same process
+pub struct LocalClusterClient {
+    coordinator: Arc<ClusterCoordinator>,
+}
+
+impl LocalClusterClient {
+    pub fn new(coordinator: Arc<ClusterCoordinator>) -> Self {
+        Self { coordinator }
+    }
+
+    /// Client for a coordinator started in this process under `cluster_name`
+    pub fn find(cluster_name: &str) -> Result<Self, String> {
+        local_coordinators()
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .get(cluster_name)
+            .cloned()
+            .map(Self::new)
+            .ok_or_else(|| format!("No coordinator for cluster {} in this process", cluster_name))
+    }
+}
+
+#[async_trait]
+impl ClusterClient for LocalClusterClient {
+    async fn call(&self, request: ClusterRequest) -> Result<ClusterResponse, String> {
+        Ok(self.coordinator.handle(request))
+    }
+}
+
+#[cfg(feature = "nats")]
+pub mod nats {
+    //! NATS request/reply transport; the coordinator answers on the
+    //! cluster subject and workers send requests to it.
+
+    use super::{ClusterClient, ClusterCoordinator, ClusterRequest, ClusterResponse};
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use std::sync::Arc;
+    use tokio::sync::watch;
+    use tokio::task::JoinHandle;
+
+    pub struct NatsClusterClient {
+        client: async_nats::Client,
+        subject: String,
+    }
+
+    impl NatsClusterClient {
+        pub async fn connect(url: &str, subject: &str) -> Result<Self, String> {
+            let client = async_nats::connect(url)
+                .await
+                .map_err(|e| format!("Failed to connect to NATS at {}: {}", url, e))?;
+            Ok(Self { client, subject: subject.to_string() })
+        }
+    }
+
+    #[async_trait]
+    impl ClusterClient for NatsClusterClient {
+        async fn call(&self, request: ClusterRequest) -> Result<ClusterResponse, String> {
+            let payload = serde_json::to_vec(&request).map_err(|e| format!("Failed to encode request: {}", e))?;
+            let reply = self
+                .client
+                .request(self.subject.clone(), payload.into())
+                .await
+                .map_err(|e| format!("Coordinator request failed: {}", e))?;
+            serde_json::from_slice(&reply.payload).map_err(|e| format!("Invalid coordinator reply: {}", e))
+        }
+    }
+
+    /// Answer worker requests on the coordinator's subject until stopped
+    pub async fn serve(
+        coordinator: Arc<ClusterCoordinator>,
+        url: &str,
+        mut stopped: watch::Receiver<bool>,
+    ) -> Result<JoinHandle<()>, String> {
+        let client = async_nats::connect(url)
+            .await
+            .map_err(|e| format!("Failed to connect to NATS at {}: {}", url, e))?;
+        let subject = coordinator.config().subject();
+        let mut requests = client
+            .subscribe(subject.clone())
+            .await
+            .map_err(|e| format!("Failed to subscribe to {}: {}", subject, e))?;
+
+        Ok(tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    message = requests.next() => {
+                        let Some(message) = message else { break };
+                        let Some(reply) = message.reply else { continue };
+                        let response = match serde_json::from_slice::<ClusterRequest>(&message.payload) {
+                            Ok(request) => coordinator.handle(request),
+                            Err(e) => ClusterResponse::Rejected { reason: format!("Invalid request: {}", e) },
+                        };
+                        match serde_json::to_vec(&response) {
+                            Ok(payload) => {
+                                if let Err(e) = client.publish(reply, payload.into()).await {
+                                    log::warn!("Failed to reply to worker: {}", e);
+                                }
+                            }
+                            Err(e) => log::warn!("Failed to encode coordinator reply: {}", e),
+                        }
+                    }
+                    changed = stopped.changed() => {
+                        if changed.is_err() || *stopped.borrow() {
+                            break;
+                        }
+                    }
+                }
+            }
+            log::info!("Cluster coordinator stopped serving {}", subject);
+        }))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkerConfig {
+    /// Generated when not set
+    pub worker_id: Option<String>,
+    pub max_concurrent_jobs: usize,
+    pub heartbeat_interval_ms: u64,
+    /// How often an idle worker asks for work
+    pub poll_interval_ms: u64,
+    pub capabilities: Vec<String>,
+}
+
+impl Default for WorkerConfig {
+    fn default() -> Self {
+        Self {
+            worker_id: None,
+            max_concurrent_jobs: 2,
+            heartbeat_interval_ms: 5_000,
+            poll_interval_ms: 1_000,
+            capabilities: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LocalWorkerStatus {
+    pub worker_id: String,
+    pub active_jobs: Vec<String>,
+    pub completed_jobs: u64,
+    pub failed_jobs: u64,
+    pub last_heartbeat: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+struct WorkerRun {
+    stop: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+    status: Arc<RwLock<LocalWorkerStatus>>,
+    client: Arc<dyn ClusterClient>,
+}
+
+struct CoordinatorRun {
+    coordinator: Arc<ClusterCoordinator>,
+    #[cfg(feature = "nats")]
+    server: Option<(watch::Sender<bool>, JoinHandle<()>)>,
+}
+
+/// Cluster roles this core currently plays
+#[derive(Default)]
+pub struct ClusterState {
+    coordinator: RwLock<Option<CoordinatorRun>>,
+    worker: AsyncMutex<Option<WorkerRun>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterStatus {
+    pub distributed_analysis: bool,
+    pub coordinator: Option<CoordinatorStatus>,
+    pub worker: Option<LocalWorkerStatus>,
+}
+
+async fn connect_client(cluster_name: &str, nats_url: Option<&str>) -> Result<Arc<dyn ClusterClient>, String> {
+    if let Some(url) = nats_url {
+        #[cfg(feature = "nats")]
+        {
+            let subject = ClusterConfig { cluster_name: cluster_name.to_string(), ..Default::default() }.subject();
+            return Ok(Arc::new(nats::NatsClusterClient::connect(url, &subject).await?));
+        }
+        #[cfg(not(feature = "nats"))]
+        return Err(format!("Joining a cluster over NATS ({}) requires the `nats` feature", url));
+    }
+    Ok(Arc::new(LocalClusterClient::find(cluster_name)?))
+}
+
+impl SandboxCore {
+    /// Become the coordinator for a cluster; queued jobs are distributed to
+    /// workers from the next `process_queue` on. With `nats_url` the
+    /// coordinator also serves remote workers over NATS.
+    pub async fn start_cluster_coordinator(
+        &self,
+        config: ClusterConfig,
+        nats_url: Option<String>,
+    ) -> Result<CoordinatorStatus, String> {
+        if !self.config.enterprise_features.distributed_analysis {
+            return Err("Distributed analysis is disabled in the sandbox configuration".to_string());
+        }
+        let mut current = self.cluster.coordinator.write().await;
+        if current.is_some() {
+            return Err("Cluster coordinator is already running".to_string());
+        }
+
+        let coordinator = Arc::new(ClusterCoordinator::new(config));
+        let cluster_name = coordinator.config().cluster_name.clone();
+        {
+            let mut registry = local_coordinators().lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if registry.contains_key(&cluster_name) {
+                return Err(format!("Cluster {} already has a coordinator in this process", cluster_name));
+            }
+            registry.insert(cluster_name.clone(), Arc::clone(&coordinator));
+        }
+
+        #[cfg(feature = "nats")]
+        let server = match nats_url {
+            Some(url) => {
+                let (stop, stopped) = watch::channel(false);
+                match nats::serve(Arc::clone(&coordinator), &url, stopped).await {
+                    Ok(handle) => Some((stop, handle)),
+                    Err(e) => {
+                        local_coordinators().lock().unwrap_or_else(|p| p.into_inner()).remove(&cluster_name);
+                        return Err(e);
+                    }
+                }
+            }
+            None => None,
+        };
+        #[cfg(not(feature = "nats"))]
+        if nats_url.is_some() {
+            local_coordinators().lock().unwrap_or_else(|p| p.into_inner()).remove(&cluster_name);
+            return Err("Serving workers over NATS requires the `nats` feature".to_string());
+        }
+
+        let status = coordinator.status();
+        *current = Some(CoordinatorRun {
+            coordinator,
+            #[cfg(feature = "nats")]
+            server,
+        });
+        log::info!("Cluster coordinator started for {}", cluster_name);
+        Ok(status)
+    }
+
+    /// Stop coordinating; jobs still out with workers are returned to the local queue
+    pub async fn stop_cluster_coordinator(&self) -> Result<bool, String> {
+        let Some(run) = self.cluster.coordinator.write().await.take() else {
+            return Ok(false);
+        };
+        local_coordinators()
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .remove(&run.coordinator.config().cluster_name);
+        #[cfg(feature = "nats")]
+        if let Some((stop, handle)) = run.server {
+            let _ = stop.send(true);
+            let _ = handle.await;
+        }
+
+        // Fold in anything that already finished, then take back the rest
+        self.collect_cluster_outcomes(&run.coordinator).await?;
+        let mut queue = self.analysis_queue.write().await;
+        let running: Vec<String> = queue
+            .iter()
+            .filter(|job| matches!(job.status, JobStatus::Running))
+            .map(|job| job.job_id.clone())
+            .collect();
+        for job_id in &running {
+            if let Some(job) = queue.requeue(job_id) {
+                self.persist_job(job).map_err(|e| e.reason)?;
+            }
+        }
+        Ok(true)
+    }
+
+    pub(crate) async fn cluster_coordinator(&self) -> Option<Arc<ClusterCoordinator>> {
+        self.cluster
+            .coordinator
+            .read()
+            .await
+            .as_ref()
+            .map(|run| Arc::clone(&run.coordinator))
+    }
+
+    /// `process_queue` on a coordinator: hand out queued jobs, reap dead
+    /// workers and store finished analyses
+    pub(crate) async fn process_cluster_queue(&self, coordinator: &ClusterCoordinator) -> Result<(), String> {
+        {
+            let samples = self.sample_data.read().await;
+            let mut queue = self.analysis_queue.write().await;
+            while let Some(job_id) = queue.pop(Utc::now()) {
+                let Some(job) = queue.get_mut(&job_id) else { continue };
+                let loaded = match samples.get(&job.sample_id) {
+                    Some(data) => Ok(Some(data.as_ref().clone())),
+                    None => self.job_store.load_sample(&job.sample_id),
+                };
+                let sample = match loaded {
+                    Ok(Some(data)) => data,
+                    Err(e) => {
+                        queue.restore(&job_id);
+                        return Err(e);
+                    }
+                    Ok(None) => {
+                        job.status = JobStatus::Failed;
+                        job.error_message = Some("Sample data is missing".to_string());
+                        self.persist_job(job).map_err(|e| e.reason)?;
+                        continue;
+                    }
+                };
+                job.status = JobStatus::Running;
+                job.analysis_start = Some(Utc::now());
+                self.persist_job(job).map_err(|e| e.reason)?;
+                coordinator.enqueue(ClusterJob { job: job.clone(), sample });
+            }
+        }
+
+        coordinator.reap(Utc::now());
+        self.collect_cluster_outcomes(coordinator).await
+    }
+
+    async fn collect_cluster_outcomes(&self, coordinator: &ClusterCoordinator) -> Result<(), String> {
+        let outcomes = coordinator.drain_outcomes();
+        if outcomes.is_empty() {
+            return Ok(());
+        }
+
+        let mut queue = self.analysis_queue.write().await;
+        for outcome in outcomes {
+            let job_id = match &outcome {
+                ClusterOutcome::Completed { job_id, .. } | ClusterOutcome::Failed { job_id, .. } => job_id.clone(),
+            };
+            let Some(job) = queue.iter_mut().find(|job| job.job_id == job_id) else {
+                log::warn!("Cluster result for unknown job {}", job_id);
+                continue;
+            };
+            match outcome {
+                ClusterOutcome::Completed { worker_id, analysis, .. } => {
+                    log::info!("Job {} completed on worker {}", job_id, worker_id);
+                    self.store_completed_analysis(job, *analysis).await.map_err(|e| e.reason)?;
+                    self.performance_metrics.write().await.successful_analyses += 1;
+                }
+                ClusterOutcome::Failed { error, .. } => {
+                    job.status = JobStatus::Failed;
+                    job.analysis_end = Some(Utc::now());
+                    job.error_message = Some(error);
+                    self.persist_job(job).map_err(|e| e.reason)?;
+                    self.performance_metrics.write().await.failed_analyses += 1;
+                }
+            }
+        }
+        let queue_length = queue.len() as u32;
+        drop(queue);
+        self.submit_url_downloads().await;
+        self.performance_metrics.write().await.queue_length = queue_length;
+        self.enforce_retention().await;
+        Ok(())
+    }
+
+    /// Run as a worker for the coordinator reached through `client`
+    pub async fn start_cluster_worker(
+        self: &Arc<Self>,
+        client: Arc<dyn ClusterClient>,
+        config: WorkerConfig,
+    ) -> Result<String, String> {
+        let mut current = self.cluster.worker.lock().await;
+        if current.as_ref().is_some_and(|run| !run.handle.is_finished()) {
+            return Err("Cluster worker is already running".to_string());
+        }
+
+        let registration = WorkerRegistration {
+            worker_id: config.worker_id.clone().unwrap_or_else(|| format!("wkr_{}", Uuid::new_v4().simple())),
+            hostname: std::env::var("HOSTNAME").unwrap_or_else(|_| "unknown".to_string()),
+            max_concurrent_jobs: config.max_concurrent_jobs.max(1),
+            capabilities: config.capabilities.clone(),
+        };
+        match client.call(ClusterRequest::Register(registration.clone())).await? {
+            ClusterResponse::Ack => {}
+            ClusterResponse::Rejected { reason } => return Err(reason),
+            ClusterResponse::Job { .. } => return Err("Unexpected reply to registration".to_string()),
+        }
+
+        let worker_id = registration.worker_id.clone();
+        let status = Arc::new(RwLock::new(LocalWorkerStatus {
+            worker_id: worker_id.clone(),
+            last_heartbeat: Some(Utc::now()),
+            ..Default::default()
+        }));
+        let progress: Arc<RwLock<HashMap<String, f64>>> = Arc::default();
+        let (stop, mut stopped) = watch::channel(false);
+
+        let core = Arc::clone(self);
+        let task_client = Arc::clone(&client);
+        let task_status = Arc::clone(&status);
+        let handle = tokio::spawn(async move {
+            let mut poll = tokio::time::interval(std::time::Duration::from_millis(config.poll_interval_ms.max(10)));
+            poll.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            let heartbeat_every = std::time::Duration::from_millis(config.heartbeat_interval_ms.max(10));
+            let mut last_heartbeat = tokio::time::Instant::now();
+
+            loop {
+                tokio::select! {
+                    _ = poll.tick() => {
+                        if last_heartbeat.elapsed() >= heartbeat_every {
+                            last_heartbeat = tokio::time::Instant::now();
+                            let report = progress.read().await.clone();
+                            let reply = task_client.call(ClusterRequest::Heartbeat { worker_id: registration.worker_id.clone(), progress: report }).await;
+                            match reply {
+                                Ok(ClusterResponse::Rejected { .. }) => {
+                                    log::warn!("Coordinator dropped worker {}, registering again", registration.worker_id);
+                                    let _ = task_client.call(ClusterRequest::Register(registration.clone())).await;
+                                }
+                                Ok(_) => task_status.write().await.last_heartbeat = Some(Utc::now()),
+                                Err(e) => task_status.write().await.last_error = Some(e),
+                            }
+                        }
+
+                        while progress.read().await.len() < registration.max_concurrent_jobs {
+                            let job = match task_client.call(ClusterRequest::Pull { worker_id: registration.worker_id.clone() }).await {
+                                Ok(ClusterResponse::Job { job: Some(job) }) => job,
+                                Ok(_) => break,
+                                Err(e) => {
+                                    task_status.write().await.last_error = Some(e);
+                                    break;
+                                }
+                            };
+                            let job_id = job.job.job_id.clone();
+                            progress.write().await.insert(job_id.clone(), 0.0);
+                            task_status.write().await.active_jobs.push(job_id.clone());
+                            tokio::spawn(run_cluster_job(
+                                Arc::clone(&core),
+                                Arc::clone(&task_client),
+                                registration.worker_id.clone(),
+                                *job,
+                                Arc::clone(&progress),
+                                Arc::clone(&task_status),
+                            ));
+                        }
+                    }
+                    changed = stopped.changed() => {
+                        if changed.is_err() || *stopped.borrow() {
+                            break;
+                        }
+                    }
+                }
+            }
+            log::info!("Cluster worker {} stopped", registration.worker_id);
+        });
+
+        *current = Some(WorkerRun { stop, handle, status, client });
+        log::info!("Cluster worker {} started", worker_id);
+        Ok(worker_id)
+    }
+
+    /// Join a cluster as a worker, over NATS when `nats_url` is given and
+    /// otherwise through the coordinator running in this process
+    pub async fn join_cluster(
+        self: &Arc<Self>,
+        cluster_name: &str,
+        nats_url: Option<&str>,
+        config: WorkerConfig,
+    ) -> Result<String, String> {
+        let client = connect_client(cluster_name, nats_url).await?;
+        self.start_cluster_worker(client, config).await
+    }
+
+    /// Leave the cluster; the coordinator requeues jobs still in flight here
+    pub async fn stop_cluster_worker(&self) -> bool {
+        let Some(run) = self.cluster.worker.lock().await.take() else {
+            return false;
+        };
+        let _ = run.stop.send(true);
+        let _ = run.handle.await;
+        let worker_id = run.status.read().await.worker_id.clone();
+        if let Err(e) = run.client.call(ClusterRequest::Leave { worker_id }).await {
+            log::warn!("Failed to leave cluster cleanly: {}", e);
+        }
+        true
+    }
+
+    pub async fn get_cluster_status(&self) -> ClusterStatus {
+        let coordinator = self.cluster_coordinator().await.map(|coordinator| coordinator.status());
+        let worker = match self.cluster.worker.lock().await.as_ref() {
+            Some(run) => Some(run.status.read().await.clone()),
+            None => None,
+        };
+        ClusterStatus {
+            distributed_analysis: self.config.enterprise_features.distributed_analysis,
+            coordinator,
+            worker,
+        }
+    }
+}
+
+async fn run_cluster_job(
+    core: Arc<SandboxCore>,
+    client: Arc<dyn ClusterClient>,
+    worker_id: String,
+    cluster_job: ClusterJob,
+    progress: Arc<RwLock<HashMap<String, f64>>>,
+    status: Arc<RwLock<LocalWorkerStatus>>,
+) {
+    let ClusterJob { mut job, sample } = cluster_job;
+    let job_id = job.job_id.clone();
+    let sample_id = job.sample_id.clone();
+    job.status = JobStatus::Running;
+    job.analysis_start = Some(Utc::now());
+
+    // The static pipeline reads sample bytes from the core
+    core.sample_data.write().await.insert(sample_id.clone(), Arc::new(sample));
+    progress.write().await.insert(job_id.clone(), 10.0);
+    let outcome = core.perform_analysis(&job).await;
+    core.sample_data.write().await.remove(&sample_id);
+    progress.write().await.insert(job_id.clone(), 90.0);
+
+    let (request, succeeded) = match outcome {
+        Ok(analysis) => (
+            ClusterRequest::Complete { worker_id, job_id: job_id.clone(), analysis: Box::new(analysis) },
+            true,
+        ),
+        Err(e) => (ClusterRequest::Fail { worker_id, job_id: job_id.clone(), error: e.reason }, false),
+    };
+    let delivered = client.call(request).await;
+
+    progress.write().await.remove(&job_id);
+    let mut status = status.write().await;
+    status.active_jobs.retain(|id| id != &job_id);
+    match delivered {
+        Ok(ClusterResponse::Ack) if succeeded => status.completed_jobs += 1,
+        Ok(ClusterResponse::Ack) => status.failed_jobs += 1,
+        Ok(ClusterResponse::Rejected { reason }) => status.last_error = Some(reason),
+        Ok(ClusterResponse::Job { .. }) => {}
+        Err(e) => status.last_error = Some(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn core() -> Arc<SandboxCore> {
+        Arc::new(SandboxCore::new().unwrap())
+    }
+
+    fn worker(worker_id: &str, max_concurrent_jobs: usize) -> ClusterRequest {
+        ClusterRequest::Register(WorkerRegistration {
+            worker_id: worker_id.to_string(),
+            hostname: "test".to_string(),
+            max_concurrent_jobs,
+            capabilities: vec![],
+        })
+    }
+
+    fn pulled(response: ClusterResponse) -> Option<ClusterJob> {
+        match response {
+            ClusterResponse::Job { job } => job.map(|job| *job),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dead_worker_jobs_are_reassigned_then_failed() {
+        let coordinator_core = core();
+        coordinator_core
+            .submit_sample("default", b"MZ cluster", "a.exe".to_string(), AnalysisPriority::Low, vec![], false)
+            .await
+            .unwrap();
+        let coordinator = ClusterCoordinator::new(ClusterConfig {
+            heartbeat_timeout_secs: 0,
+            max_attempts: 2,
+            ..Default::default()
+        });
+        let job = coordinator_core.analysis_queue.read().await.iter().next().unwrap().clone();
+        coordinator.enqueue(ClusterJob { job, sample: b"MZ cluster".to_vec() });
+
+        coordinator.handle(worker("w1", 1));
+        assert!(pulled(coordinator.handle(ClusterRequest::Pull { worker_id: "w1".to_string() })).is_some());
+        // At capacity
+        assert!(pulled(coordinator.handle(ClusterRequest::Pull { worker_id: "w1".to_string() })).is_none());
+
+        let later = Utc::now() + Duration::seconds(5);
+        assert_eq!(coordinator.reap(later), ["w1"]);
+        assert_eq!(coordinator.status().pending_jobs, 1);
+        assert!(matches!(
+            coordinator.handle(ClusterRequest::Heartbeat { worker_id: "w1".to_string(), progress: HashMap::new() }),
+            ClusterResponse::Rejected { .. }
+        ));
+
+        coordinator.handle(worker("w2", 1));
+        assert!(pulled(coordinator.handle(ClusterRequest::Pull { worker_id: "w2".to_string() })).is_some());
+        coordinator.reap(later);
+
+        let status = coordinator.status();
+        assert_eq!((status.pending_jobs, status.failed_jobs, status.reassigned_jobs), (0, 1, 1));
+        assert!(matches!(coordinator.drain_outcomes().as_slice(), [ClusterOutcome::Failed { .. }]));
+    }
+
+    #[tokio::test]
+    async fn test_workers_analyse_jobs_for_the_coordinator() {
+        let coordinator_core = core();
+        let config = ClusterConfig { cluster_name: format!("test-{}", Uuid::new_v4()), ..Default::default() };
+        coordinator_core.start_cluster_coordinator(config.clone(), None).await.unwrap();
+        assert!(coordinator_core.start_cluster_coordinator(config.clone(), None).await.is_err());
+
+        let mut sample_ids = Vec::new();
+        for (name, priority) in [("low.exe", AnalysisPriority::Low), ("urgent.exe", AnalysisPriority::Emergency)] {
+            sample_ids.push(
+                coordinator_core
+                    .submit_sample("default", name.as_bytes(), name.to_string(), priority, vec![], false)
+                    .await
+                    .unwrap(),
+            );
+        }
+        coordinator_core.process_queue().await.unwrap();
+        assert_eq!(coordinator_core.get_cluster_status().await.coordinator.unwrap().pending_jobs, 2);
+
+        let worker_core = core();
+        let worker_config = WorkerConfig { poll_interval_ms: 10, heartbeat_interval_ms: 10, ..Default::default() };
+        let worker_id = worker_core.join_cluster(&config.cluster_name, None, worker_config).await.unwrap();
+
+        let mut finished = false;
+        for _ in 0..200 {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            coordinator_core.process_queue().await.unwrap();
+            if coordinator_core.completed_analyses.read().await.len() == 2 {
+                finished = true;
+                break;
+            }
+        }
+        assert!(finished, "worker did not finish the distributed jobs");
+        for sample_id in &sample_ids {
+            assert!(coordinator_core.get_analysis("default", sample_id).await.unwrap().is_some());
+        }
+
+        let status = coordinator_core.get_cluster_status().await.coordinator.unwrap();
+        assert_eq!(status.completed_jobs, 2);
+        assert_eq!(status.workers[0].worker_id, worker_id);
+        assert_eq!(worker_core.get_cluster_status().await.worker.unwrap().completed_jobs, 2);
+
+        assert!(worker_core.stop_cluster_worker().await);
+        assert_eq!(coordinator_core.get_cluster_status().await.coordinator.unwrap().workers[0].state, WorkerState::Left);
+        assert!(coordinator_core.stop_cluster_coordinator().await.unwrap());
+        assert!(LocalClusterClient::find(&config.cluster_name).is_err());
+    }
+}
@@ -0,0 +1,366 @@
+//! Anti-evasion countermeasure profiles
+//!
+//! Evasive samples probe for analysis environments before doing anything
+//! interesting: hypervisor CPUID bits, well-known virtual NIC vendors,
+//! default hostnames, an idle desktop, or long sleeps that outlast the
+//! analysis window. A `CountermeasureProfile` lists the tricks used to defeat
+//! those checks. Profiles are attached to a `VMEnvironment`, resolved into a
+//! concrete `CountermeasurePlan` per job (random MAC and hostname, clock
+//! factor) and applied by the detonation driver. What each driver actually
+//! managed to apply is kept on the detonation report so analysts can tell
+//! which tricks were active when reading a verdict.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::SandboxCore;
+
+/// Locally administered, unicast prefix used when a profile names no vendor OUI
+const DEFAULT_MAC_PREFIX: [u8; 3] = [0x02, 0x1a, 0x4b];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CountermeasureKind {
+    HideHypervisor,
+    RandomizeMac,
+    RandomizeHostname,
+    HumanInteraction,
+    ClockAcceleration,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HumanInteraction {
+    #[serde(default = "default_mouse_moves")]
+    pub mouse_moves_per_minute: u32,
+    /// Click through installer and document dialogs
+    #[serde(default = "default_true")]
+    pub click_dialogs: bool,
+    /// Scroll opened documents past the first page
+    #[serde(default = "default_true")]
+    pub scroll_documents: bool,
+}
+
+fn default_mouse_moves() -> u32 {
+    30
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Countermeasure {
+    /// Clear the CPUID hypervisor bit and hide the KVM signature
+    HideHypervisor,
+    RandomizeMac {
+        /// Vendor prefix such as `3c:52:82`; a locally administered prefix otherwise
+        #[serde(default)]
+        oui: Option<String>,
+    },
+    RandomizeHostname {
+        #[serde(default = "default_hostname_prefix")]
+        prefix: String,
+    },
+    HumanInteraction(HumanInteraction),
+    /// Run the guest clock faster so long sleeps expire inside the analysis window
+    ClockAcceleration { factor: f64 },
+}
+
+fn default_hostname_prefix() -> String {
+    "DESKTOP-".to_string()
+}
+
+impl Countermeasure {
+    pub fn kind(&self) -> CountermeasureKind {
+        match self {
+            Countermeasure::HideHypervisor => CountermeasureKind::HideHypervisor,
+            Countermeasure::RandomizeMac { .. } => CountermeasureKind::RandomizeMac,
+            Countermeasure::RandomizeHostname { .. } => CountermeasureKind::RandomizeHostname,
+            Countermeasure::HumanInteraction(_) => CountermeasureKind::HumanInteraction,
+            Countermeasure::ClockAcceleration { .. } => CountermeasureKind::ClockAcceleration,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CountermeasureProfile {
+    pub profile_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub countermeasures: Vec<Countermeasure>,
+}
+
+/// Concrete settings for one detonation, drawn from a profile
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CountermeasurePlan {
+    pub profile_id: String,
+    pub profile_name: String,
+    pub hide_hypervisor: bool,
+    pub mac_address: Option<String>,
+    pub hostname: Option<String>,
+    pub human_interaction: Option<HumanInteraction>,
+    pub clock_factor: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CountermeasureStatus {
+    /// The driver configured it and confirmed the setting
+    Applied,
+    /// Handed to the in-guest monitor, which applies it before the sample runs
+    Delegated,
+    /// The backend has no way to apply it
+    Unsupported,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppliedCountermeasure {
+    pub kind: CountermeasureKind,
+    pub status: CountermeasureStatus,
+    /// Value used (MAC, hostname, factor) or why it could not be applied
+    pub detail: String,
+}
+
+impl AppliedCountermeasure {
+    pub fn new(kind: CountermeasureKind, status: CountermeasureStatus, detail: impl Into<String>) -> Self {
+        Self { kind, status, detail: detail.into() }
+    }
+}
+
+/// Countermeasures active during a detonation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CountermeasureRecord {
+    pub profile_id: String,
+    pub profile_name: String,
+    pub countermeasures: Vec<AppliedCountermeasure>,
+}
+
+impl CountermeasureRecord {
+    pub fn active(&self) -> impl Iterator<Item = &AppliedCountermeasure> {
+        self.countermeasures
+            .iter()
+            .filter(|c| matches!(c.status, CountermeasureStatus::Applied | CountermeasureStatus::Delegated))
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &AppliedCountermeasure> {
+        self.countermeasures.iter().filter(|c| c.status == CountermeasureStatus::Failed)
+    }
+}
+
+fn parse_oui(oui: &str) -> Option<[u8; 3]> {
+    let bytes: Vec<u8> = oui
+        .split([':', '-'])
+        .map(|part| u8::from_str_radix(part, 16).ok())
+        .collect::<Option<_>>()?;
+    bytes.try_into().ok()
+}
+
+impl CountermeasureProfile {
+    /// Hide the hypervisor and randomize identifiers, without touching timing
+    pub fn stealth() -> Self {
+        Self {
+            profile_id: "stealth".to_string(),
+            name: "Stealth".to_string(),
+            description: "Hide the hypervisor and present fresh hardware identifiers".to_string(),
+            countermeasures: vec![
+                Countermeasure::HideHypervisor,
+                Countermeasure::RandomizeMac { oui: Some("3c:52:82".to_string()) },
+                Countermeasure::RandomizeHostname { prefix: default_hostname_prefix() },
+            ],
+        }
+    }
+
+    /// Everything in `stealth`, plus a simulated user and a 10x clock for sleep skipping
+    pub fn full() -> Self {
+        let mut countermeasures = Self::stealth().countermeasures;
+        countermeasures.push(Countermeasure::HumanInteraction(HumanInteraction {
+            mouse_moves_per_minute: default_mouse_moves(),
+            click_dialogs: true,
+            scroll_documents: true,
+        }));
+        countermeasures.push(Countermeasure::ClockAcceleration { factor: 10.0 });
+        Self {
+            profile_id: "full".to_string(),
+            name: "Full anti-evasion".to_string(),
+            description: "Stealth identifiers, simulated user activity and accelerated clock".to_string(),
+            countermeasures,
+        }
+    }
+
+    pub fn builtin() -> Vec<Self> {
+        vec![Self::stealth(), Self::full()]
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.profile_id.trim().is_empty() || self.name.trim().is_empty() {
+            return Err("Countermeasure profile id and name are required".to_string());
+        }
+        let mut seen = Vec::new();
+        for countermeasure in &self.countermeasures {
+            if seen.contains(&countermeasure.kind()) {
+                return Err(format!("Countermeasure {:?} is listed more than once", countermeasure.kind()));
+            }
+            seen.push(countermeasure.kind());
+            match countermeasure {
+                Countermeasure::RandomizeMac { oui: Some(oui) } if parse_oui(oui).is_none() => {
+                    return Err(format!("Invalid MAC vendor prefix {}", oui));
+                }
+                Countermeasure::RandomizeHostname { prefix } if prefix.len() > 8 || !prefix.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') => {
+                    return Err(format!("Hostname prefix {} must be at most 8 letters, digits or dashes", prefix));
+                }
+                Countermeasure::ClockAcceleration { factor } if !(1.0..=100.0).contains(factor) => {
+                    return Err(format!("Clock acceleration factor {} must be between 1 and 100", factor));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Draw fresh identifiers for one detonation
+    pub fn resolve(&self) -> CountermeasurePlan {
+        let random = Uuid::new_v4();
+        let random = random.as_bytes();
+        let mut plan = CountermeasurePlan {
+            profile_id: self.profile_id.clone(),
+            profile_name: self.name.clone(),
+            ..Default::default()
+        };
+        for countermeasure in &self.countermeasures {
+            match countermeasure {
+                Countermeasure::HideHypervisor => plan.hide_hypervisor = true,
+                Countermeasure::RandomizeMac { oui } => {
+                    let prefix = oui.as_deref().and_then(parse_oui).unwrap_or(DEFAULT_MAC_PREFIX);
+                    let octets = [prefix[0], prefix[1], prefix[2], random[0], random[1], random[2]];
+                    plan.mac_address = Some(octets.iter().map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(":"));
+                }
+                Countermeasure::RandomizeHostname { prefix } => {
+                    // NetBIOS names stop at 15 characters
+                    let suffix: String = random[3..]
+                        .iter()
+                        .map(|b| char::from(b"ABCDEFGHJKLMNPQRSTUVWXYZ0123456789"[*b as usize % 34]))
+                        .take(15usize.saturating_sub(prefix.len()).min(7))
+                        .collect();
+                    plan.hostname = Some(format!("{}{}", prefix, suffix));
+                }
+                Countermeasure::HumanInteraction(interaction) => plan.human_interaction = Some(interaction.clone()),
+                Countermeasure::ClockAcceleration { factor } => plan.clock_factor = Some(*factor),
+            }
+        }
+        plan
+    }
+}
+
+impl CountermeasurePlan {
+    /// Countermeasures the plan asks for, with the value each one uses
+    pub fn requested(&self) -> Vec<(CountermeasureKind, String)> {
+        let mut requested = Vec::new();
+        if self.hide_hypervisor {
+            requested.push((CountermeasureKind::HideHypervisor, "cpuid hypervisor bit cleared".to_string()));
+        }
+        if let Some(mac) = &self.mac_address {
+            requested.push((CountermeasureKind::RandomizeMac, mac.clone()));
+        }
+        if let Some(hostname) = &self.hostname {
+            requested.push((CountermeasureKind::RandomizeHostname, hostname.clone()));
+        }
+        if let Some(interaction) = &self.human_interaction {
+            requested.push((
+                CountermeasureKind::HumanInteraction,
+                format!("{} mouse moves/min", interaction.mouse_moves_per_minute),
+            ));
+        }
+        if let Some(factor) = self.clock_factor {
+            requested.push((CountermeasureKind::ClockAcceleration, format!("x{}", factor)));
+        }
+        requested
+    }
+
+    /// Mark every requested countermeasure as unsupported by a backend
+    pub fn unsupported(&self, backend: &str) -> Vec<AppliedCountermeasure> {
+        self.requested()
+            .into_iter()
+            .map(|(kind, _)| AppliedCountermeasure::new(kind, CountermeasureStatus::Unsupported, format!("not supported by {}", backend)))
+            .collect()
+    }
+}
+
+impl SandboxCore {
+    pub async fn register_countermeasure_profile(&self, profile: CountermeasureProfile) -> Result<(), String> {
+        profile.validate()?;
+        self.countermeasure_profiles.write().await.insert(profile.profile_id.clone(), profile);
+        Ok(())
+    }
+
+    pub async fn list_countermeasure_profiles(&self) -> Vec<CountermeasureProfile> {
+        let mut profiles: Vec<CountermeasureProfile> = self.countermeasure_profiles.read().await.values().cloned().collect();
+        profiles.sort_by(|a, b| a.profile_id.cmp(&b.profile_id));
+        profiles
+    }
+
+    /// Attach a profile to an environment, or detach with `None`
+    pub async fn attach_countermeasure_profile(&self, environment_id: &str, profile_id: Option<&str>) -> Result<(), String> {
+        if let Some(profile_id) = profile_id {
+            if !self.countermeasure_profiles.read().await.contains_key(profile_id) {
+                return Err(format!("Unknown countermeasure profile {}", profile_id));
+            }
+        }
+        let mut environments = self.vm_environments.write().await;
+        let environment = environments
+            .get_mut(environment_id)
+            .ok_or_else(|| format!("VM environment {} not found", environment_id))?;
+        environment.countermeasure_profile = profile_id.map(str::to_string);
+        Ok(())
+    }
+
+    /// Resolve the environment's profile into a plan for one detonation
+    pub(crate) async fn countermeasure_plan(&self, profile_id: Option<&str>) -> Option<CountermeasurePlan> {
+        let profiles = self.countermeasure_profiles.read().await;
+        profiles.get(profile_id?).map(CountermeasureProfile::resolve)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_full_profile_resolves_fresh_identifiers() {
+        let profile = CountermeasureProfile::full();
+        profile.validate().unwrap();
+
+        let first = profile.resolve();
+        let second = profile.resolve();
+        assert!(first.hide_hypervisor);
+        assert_eq!(first.clock_factor, Some(10.0));
+        let mac = first.mac_address.clone().unwrap();
+        assert!(mac.starts_with("3c:52:82:"));
+        assert_eq!(mac.len(), 17);
+        let hostname = first.hostname.clone().unwrap();
+        assert!(hostname.starts_with("DESKTOP-") && hostname.len() <= 15);
+        assert_ne!(first.mac_address, second.mac_address);
+        assert_eq!(first.requested().len(), 5);
+    }
+
+    #[test]
+    fn test_profile_validation() {
+        let mut profile = CountermeasureProfile::stealth();
+        profile.countermeasures.push(Countermeasure::HideHypervisor);
+        assert!(profile.validate().unwrap_err().contains("more than once"));
+
+        let profile = CountermeasureProfile {
+            profile_id: "fast".to_string(),
+            name: "Fast clock".to_string(),
+            description: String::new(),
+            countermeasures: vec![Countermeasure::ClockAcceleration { factor: 500.0 }],
+        };
+        assert!(profile.validate().is_err());
+
+        let parsed: Countermeasure = serde_json::from_str(r#"{"kind":"randomize_mac","oui":"zz:00:00"}"#).unwrap();
+        let profile = CountermeasureProfile { countermeasures: vec![parsed], ..profile };
+        assert!(profile.validate().unwrap_err().contains("MAC vendor prefix"));
+    }
+}
@@ -0,0 +1,302 @@
+//! Sample deduplication by SHA-256
+//!
+//! Every submission is recorded against the sample's SHA-256. When the same
+//! bytes arrive again the core hands back the sample that already has (or is
+//! about to have) an analysis instead of queueing a second full run, unless
+//! the caller explicitly asks to re-analyze. The index is kept per tenant,
+//! so identical bytes from two tenants are analysed separately.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use crate::job_store::JobStore;
+use crate::{AnalysisJob, AnalysisPriority, JobStatus, SandboxCore};
+
+/// What happened to a submission
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmissionDisposition {
+    /// First time these bytes were seen; a new job was queued
+    Queued,
+    /// Re-analysis was forced even though the hash was known
+    Reanalyzed,
+    /// Attached to a job for the same hash that has not finished yet
+    AttachedToInFlight,
+    /// Answered by an analysis that already completed
+    ExistingAnalysis,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SampleSubmission {
+    pub sample_id: String,
+    pub file_name: String,
+    pub submitted_at: DateTime<Utc>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub disposition: SubmissionDisposition,
+}
+
+/// Everything submitted under one SHA-256
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HashRecord {
+    pub sha256: String,
+    #[serde(default = "crate::tenancy::default_tenant")]
+    pub tenant_id: String,
+    /// Sample that new duplicate submissions resolve to
+    pub current_sample_id: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub submissions: Vec<SampleSubmission>,
+}
+
+/// Prior submissions of a hash together with their analyses
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriorSubmissions {
+    pub sha256: String,
+    pub current_sample_id: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    /// Analysis ids of completed runs, keyed by sample id
+    pub analyses: HashMap<String, String>,
+    /// Job still working on this hash, if any
+    pub in_flight_job_id: Option<String>,
+    pub submissions: Vec<SampleSubmission>,
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// Key of a tenant's record for `sha256` in the hash index
+pub(crate) fn hash_key(tenant_id: &str, sha256: &str) -> String {
+    format!("{}:{}", tenant_id, sha256)
+}
+
+fn is_pending(status: &JobStatus) -> bool {
+    matches!(
+        status,
+        JobStatus::Queued | JobStatus::PreProcessing | JobStatus::Running | JobStatus::PostProcessing
+    )
+}
+
+fn priority_rank(priority: &AnalysisPriority) -> u8 {
+    match priority {
+        AnalysisPriority::Emergency => 5,
+        AnalysisPriority::Critical => 4,
+        AnalysisPriority::High => 3,
+        AnalysisPriority::Normal => 2,
+        AnalysisPriority::Low => 1,
+    }
+}
+
+/// Rebuild the hash index from persisted jobs and their sample bytes
+pub(crate) fn rebuild_hash_index(
+    store: &dyn JobStore,
+    jobs: &[AnalysisJob],
+) -> Result<HashMap<String, HashRecord>, String> {
+    let mut index: HashMap<String, HashRecord> = HashMap::new();
+    let mut ordered: Vec<&AnalysisJob> = jobs.iter().collect();
+    ordered.sort_by_key(|job| job.submission_time);
+
+    for job in ordered {
+        let Some(data) = store.load_sample(&job.sample_id)? else { continue };
+        let sha256 = sha256_hex(&data);
+        let key = hash_key(&job.tenant_id, &sha256);
+        let submission = SampleSubmission {
+            sample_id: job.sample_id.clone(),
+            file_name: String::new(),
+            submitted_at: job.submission_time,
+            tags: Vec::new(),
+            disposition: if index.contains_key(&key) {
+                SubmissionDisposition::Reanalyzed
+            } else {
+                SubmissionDisposition::Queued
+            },
+        };
+        let record = index.entry(key).or_insert_with(|| HashRecord {
+            sha256,
+            tenant_id: job.tenant_id.clone(),
+            current_sample_id: job.sample_id.clone(),
+            first_seen: job.submission_time,
+            last_seen: job.submission_time,
+            submissions: Vec::new(),
+        });
+        record.current_sample_id = job.sample_id.clone();
+        record.last_seen = job.submission_time;
+        record.submissions.push(submission);
+    }
+    Ok(index)
+}
+
+impl SandboxCore {
+    /// Resolve a duplicate submission to an existing sample, if one can answer it.
+    ///
+    /// A pending job for the hash is raised to `priority` when that is higher.
+    pub(crate) async fn attach_duplicate(
+        &self,
+        tenant_id: &str,
+        sha256: &str,
+        file_name: &str,
+        priority: &AnalysisPriority,
+        tags: &[String],
+    ) -> Option<String> {
+        let sample_id = {
+            let index = self.hash_index.read().await;
+            index.get(&hash_key(tenant_id, sha256))?.current_sample_id.clone()
+        };
+
+        let disposition = if self.completed_analyses.read().await.contains_key(&sample_id) {
+            SubmissionDisposition::ExistingAnalysis
+        } else {
+            let mut queue = self.analysis_queue.write().await;
+            let job = queue
+                .iter()
+                .find(|job| job.sample_id == sample_id && is_pending(&job.status))?;
+            if matches!(job.status, JobStatus::Queued) && priority_rank(priority) > priority_rank(&job.priority) {
+                let job_id = job.job_id.clone();
+                if let Some(job) = queue.reprioritize(&job_id, priority.clone()) {
+                    if let Err(e) = self.job_store.save_job(job) {
+                        log::warn!("Failed to persist priority change for job {}: {}", job.job_id, e);
+                    }
+                }
+            }
+            SubmissionDisposition::AttachedToInFlight
+        };
+
+        self.record_submission(tenant_id, sha256, &sample_id, file_name, tags, disposition).await;
+        Some(sample_id)
+    }
+
+    pub(crate) async fn record_submission(
+        &self,
+        tenant_id: &str,
+        sha256: &str,
+        sample_id: &str,
+        file_name: &str,
+        tags: &[String],
+        disposition: SubmissionDisposition,
+    ) {
+        let now = Utc::now();
+        let mut index = self.hash_index.write().await;
+        let record = index.entry(hash_key(tenant_id, sha256)).or_insert_with(|| HashRecord {
+            sha256: sha256.to_string(),
+            tenant_id: tenant_id.to_string(),
+            current_sample_id: sample_id.to_string(),
+            first_seen: now,
+            last_seen: now,
+            submissions: Vec::new(),
+        });
+        if matches!(disposition, SubmissionDisposition::Queued | SubmissionDisposition::Reanalyzed) {
+            record.current_sample_id = sample_id.to_string();
+        }
+        record.last_seen = now;
+        record.submissions.push(SampleSubmission {
+            sample_id: sample_id.to_string(),
+            file_name: file_name.to_string(),
+            submitted_at: now,
+            tags: tags.to_vec(),
+            disposition,
+        });
+    }
+
+    /// SHA-256 and submission record of a sample as it was submitted
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub(crate) async fn submitted_sample(&self, tenant_id: &str, sample_id: &str) -> Option<(String, SampleSubmission)> {
+        let index = self.hash_index.read().await;
+        index.values().filter(|record| record.tenant_id == tenant_id).find_map(|record| {
+            record
+                .submissions
+                .iter()
+                .find(|submission| submission.sample_id == sample_id)
+                .map(|submission| (record.sha256.clone(), submission.clone()))
+        })
+    }
+
+    /// Look up every prior submission of a SHA-256
+    pub async fn find_submissions_by_hash(&self, tenant_id: &str, sha256: &str) -> Option<PriorSubmissions> {
+        let sha256 = sha256.trim().to_ascii_lowercase();
+        let record = self.hash_index.read().await.get(&hash_key(tenant_id, &sha256)).cloned()?;
+
+        let analyses = {
+            let completed = self.completed_analyses.read().await;
+            record
+                .submissions
+                .iter()
+                .filter_map(|s| completed.get(&s.sample_id).map(|a| (s.sample_id.clone(), a.analysis_id.clone())))
+                .collect()
+        };
+        let in_flight_job_id = self
+            .analysis_queue
+            .read()
+            .await
+            .iter()
+            .find(|job| job.sample_id == record.current_sample_id && is_pending(&job.status))
+            .map(|job| job.job_id.clone());
+
+        Some(PriorSubmissions {
+            sha256: record.sha256,
+            current_sample_id: record.current_sample_id,
+            first_seen: record.first_seen,
+            last_seen: record.last_seen,
+            analyses,
+            in_flight_job_id,
+            submissions: record.submissions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_duplicate_submissions_reuse_existing_sample() {
+        let core = SandboxCore::new().unwrap();
+        let data = b"MZ duplicate payload";
+
+        let first = core
+            .submit_sample("default", data, "a.exe".to_string(), AnalysisPriority::Low, vec![], false)
+            .await
+            .unwrap();
+        let attached = core
+            .submit_sample("default", data, "b.exe".to_string(), AnalysisPriority::High, vec![], false)
+            .await
+            .unwrap();
+        assert_eq!(attached, first);
+        assert_eq!(core.get_queue_status("default").await.unwrap().len(), 1);
+        assert!(matches!(
+            core.get_queue_status("default").await.unwrap()[0].priority,
+            AnalysisPriority::High
+        ));
+
+        core.process_queue().await.unwrap();
+        let existing = core
+            .submit_sample("default", data, "c.exe".to_string(), AnalysisPriority::Normal, vec![], false)
+            .await
+            .unwrap();
+        assert_eq!(existing, first);
+
+        let forced = core
+            .submit_sample("default", data, "d.exe".to_string(), AnalysisPriority::Normal, vec![], true)
+            .await
+            .unwrap();
+        assert_ne!(forced, first);
+
+        let prior = core.find_submissions_by_hash("default", &sha256_hex(data)).await.unwrap();
+        assert_eq!(prior.current_sample_id, forced);
+        assert!(prior.analyses.contains_key(&first));
+        assert!(prior.in_flight_job_id.is_some());
+        let dispositions: Vec<_> = prior.submissions.iter().map(|s| s.disposition).collect();
+        assert_eq!(
+            dispositions,
+            vec![
+                SubmissionDisposition::Queued,
+                SubmissionDisposition::AttachedToInFlight,
+                SubmissionDisposition::ExistingAnalysis,
+                SubmissionDisposition::Reanalyzed,
+            ]
+        );
+    }
+}
@@ -0,0 +1,979 @@
+//! Detonation drivers
+//!
+//! A `DetonationDriver` runs a sample inside a real guest: bring the guest up,
+//! roll it back to its clean snapshot, copy the sample in, execute it, collect
+//! the artifacts it left behind and revert the guest. Each `VMEnvironment`
+//! selects its backend through `DriverConfig`; environments left on
+//! `Simulated` keep the built-in simulated analysis.
+//!
+//! The libvirt backend drives a QEMU domain through `virsh` and the QEMU guest
+//! agent, exchanging the sample and artifacts through a host directory shared
+//! into the guest. The Docker backend uses a throwaway container per job with
+//! networking disabled. Both shell out through a `CommandRunner` so tests can
+//! script the hypervisor's responses.
+//!
+//! Environments with a countermeasure profile get it applied right after the
+//! snapshot is restored; whatever the backend could not apply is reported as
+//! unsupported or failed on the detonation report.
+//!
+//! Interactive submissions hold the guest between execution and the final
+//! artifact collection until the analyst's session ends (see `interactive`).
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::countermeasures::{AppliedCountermeasure, CountermeasureKind, CountermeasurePlan, CountermeasureRecord, CountermeasureStatus};
+use crate::dedup::sha256_hex;
+use crate::interactive::{merge_artifacts, InteractiveSession, SessionEnd};
+use crate::{AnalysisJob, SandboxCore, VMEnvironment};
+
+/// Upper bound for hypervisor management commands (not sample execution)
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(120);
+/// How often the guest agent is polled for the sample's exit status
+const EXEC_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Environment variable overriding the `virsh` executable
+pub const VIRSH_PATH_ENV: &str = "PHANTOM_SANDBOX_VIRSH";
+/// Environment variable overriding the `docker` executable
+pub const DOCKER_PATH_ENV: &str = "PHANTOM_SANDBOX_DOCKER";
+
+// Configuration
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "backend", rename_all = "snake_case")]
+pub enum DriverConfig {
+    #[default]
+    Simulated,
+    Libvirt(LibvirtConfig),
+    Docker(DockerConfig),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibvirtConfig {
+    #[serde(default = "default_libvirt_uri")]
+    pub connection_uri: String,
+    /// Libvirt domain name; defaults to the environment id
+    #[serde(default)]
+    pub domain: Option<String>,
+    /// Host side of the directory shared into the guest (virtiofs or 9p)
+    pub host_share_dir: PathBuf,
+    /// The same directory as the guest sees it, e.g. `Z:\` or `/mnt/phantom`
+    pub guest_share_dir: String,
+    /// Executables are chosen by the host owner, never by a driver config
+    /// submitted over NAPI: set it here or through `PHANTOM_SANDBOX_VIRSH`
+    #[serde(skip_deserializing, default = "default_virsh")]
+    pub virsh_path: String,
+}
+
+fn default_libvirt_uri() -> String {
+    "qemu:///system".to_string()
+}
+
+fn default_virsh() -> String {
+    std::env::var(VIRSH_PATH_ENV).unwrap_or_else(|_| "virsh".to_string())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DockerConfig {
+    pub image: String,
+    /// Set by the host owner, like `LibvirtConfig::virsh_path`, or through
+    /// `PHANTOM_SANDBOX_DOCKER`
+    #[serde(skip_deserializing, default = "default_docker")]
+    pub docker_path: String,
+    /// Container network; "none" keeps the sample offline
+    #[serde(default = "default_docker_network")]
+    pub network: String,
+    #[serde(default = "default_docker_workdir")]
+    pub workdir: String,
+    /// Run the sample as this user instead of the image default
+    #[serde(default)]
+    pub user: Option<String>,
+    /// libfaketime inside the image, used for clock acceleration
+    #[serde(default)]
+    pub faketime_library: Option<String>,
+}
+
+fn default_docker() -> String {
+    std::env::var(DOCKER_PATH_ENV).unwrap_or_else(|_| "docker".to_string())
+}
+
+fn default_docker_network() -> String {
+    "none".to_string()
+}
+
+fn default_docker_workdir() -> String {
+    "/sandbox".to_string()
+}
+
+impl DriverConfig {
+    /// Build the driver for this backend; `None` for simulated environments
+    pub fn build(&self, runner: Arc<dyn CommandRunner>) -> Option<Arc<dyn DetonationDriver>> {
+        match self {
+            DriverConfig::Simulated => None,
+            DriverConfig::Libvirt(config) => Some(Arc::new(LibvirtDriver::new(config.clone(), runner))),
+            DriverConfig::Docker(config) => Some(Arc::new(DockerDriver::new(config.clone(), runner))),
+        }
+    }
+}
+
+// Command execution
+
+#[derive(Debug, Clone, Default)]
+pub struct CommandOutput {
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub timed_out: bool,
+}
+
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0) && !self.timed_out
+    }
+}
+
+/// Runs hypervisor CLI commands
+#[async_trait]
+pub trait CommandRunner: Send + Sync {
+    async fn run(&self, program: &str, args: &[String], timeout: Duration) -> Result<CommandOutput, String>;
+}
+
+/// Runs commands as local processes, killing them on timeout
+pub struct SystemCommandRunner;
+
+#[async_trait]
+impl CommandRunner for SystemCommandRunner {
+    async fn run(&self, program: &str, args: &[String], timeout: Duration) -> Result<CommandOutput, String> {
+        let child = tokio::process::Command::new(program)
+            .args(args)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+        match tokio::time::timeout(timeout, child.wait_with_output()).await {
+            Ok(output) => {
+                let output = output.map_err(|e| format!("Failed to run {}: {}", program, e))?;
+                Ok(CommandOutput {
+                    exit_code: output.status.code(),
+                    stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+                    stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+                    timed_out: false,
+                })
+            }
+            Err(_) => Ok(CommandOutput { timed_out: true, ..Default::default() }),
+        }
+    }
+}
+
+async fn run_checked(
+    runner: &dyn CommandRunner,
+    program: &str,
+    args: Vec<String>,
+) -> Result<String, String> {
+    let output = runner.run(program, &args, CONTROL_TIMEOUT).await?;
+    if output.timed_out {
+        return Err(format!("{} {} timed out", program, args.first().map(String::as_str).unwrap_or_default()));
+    }
+    if !output.success() {
+        return Err(format!(
+            "{} {} failed: {}",
+            program,
+            args.first().map(String::as_str).unwrap_or_default(),
+            output.stderr.trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+// Driver interface
+
+/// A running guest owned by one detonation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetonationHandle {
+    pub job_id: String,
+    pub environment_id: String,
+    /// Libvirt domain name or container id
+    pub guest_id: String,
+    pub snapshot_id: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecutionOutcome {
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactKind {
+    /// File the in-guest monitor exported (memory dump, pcap, trace, dropped file)
+    File,
+    FileCreated,
+    FileModified,
+    FileDeleted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetonationArtifact {
+    pub kind: ArtifactKind,
+    pub path: String,
+    pub size_bytes: u64,
+    pub sha256: Option<String>,
+    /// Id in the artifact store once the content was retained
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artifact_id: Option<String>,
+    /// Exported content, handed over to the artifact store
+    #[serde(skip)]
+    pub content: Option<Vec<u8>>,
+}
+
+#[async_trait]
+pub trait DetonationDriver: Send + Sync {
+    fn backend(&self) -> &'static str;
+
+    /// Bring up (or attach to) the guest for `environment`
+    async fn start_vm(&self, environment: &VMEnvironment, job_id: &str, countermeasures: &CountermeasurePlan) -> Result<DetonationHandle, String>;
+
+    /// Roll the guest back to its clean snapshot
+    async fn restore_snapshot(&self, handle: &DetonationHandle) -> Result<(), String>;
+
+    /// Apply anti-evasion countermeasures to the restored guest
+    async fn apply_countermeasures(&self, _handle: &DetonationHandle, plan: &CountermeasurePlan) -> Vec<AppliedCountermeasure> {
+        plan.unsupported(self.backend())
+    }
+
+    /// Place the sample in the guest and return its guest path
+    async fn copy_sample(&self, handle: &DetonationHandle, file_name: &str, data: &[u8]) -> Result<String, String>;
+
+    async fn execute_sample(&self, handle: &DetonationHandle, guest_path: &str, timeout: Duration) -> Result<ExecutionOutcome, String>;
+
+    async fn collect_artifacts(&self, handle: &DetonationHandle) -> Result<Vec<DetonationArtifact>, String>;
+
+    /// Discard every change the sample made and release the guest
+    async fn revert(&self, handle: &DetonationHandle) -> Result<(), String>;
+}
+
+/// Outcome of one real detonation, attached to the analysis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetonationReport {
+    pub backend: String,
+    pub environment_id: String,
+    pub guest_id: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub vm_startup_ms: u64,
+    pub guest_path: Option<String>,
+    pub execution: Option<ExecutionOutcome>,
+    pub artifacts: Vec<DetonationArtifact>,
+    pub errors: Vec<String>,
+    /// Anti-evasion countermeasures active while the sample ran
+    #[serde(default)]
+    pub countermeasures: Option<CountermeasureRecord>,
+}
+
+/// Keep only characters that are safe in a guest file name
+fn guest_file_name(file_name: &str) -> String {
+    let name: String = Path::new(file_name)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect();
+    if name.trim_matches('.').is_empty() {
+        "sample.bin".to_string()
+    } else {
+        name
+    }
+}
+
+/// Run the full lifecycle, always reverting the guest once it was started.
+/// With an interactive session the guest is held for the analyst between
+/// execution and the final artifact collection.
+#[allow(clippy::too_many_arguments)]
+pub async fn detonate(
+    driver: &dyn DetonationDriver,
+    environment: &VMEnvironment,
+    job_id: &str,
+    file_name: &str,
+    data: &[u8],
+    timeout: Duration,
+    countermeasures: Option<&CountermeasurePlan>,
+    interactive: Option<&InteractiveSession>,
+) -> DetonationReport {
+    let started_at = Utc::now();
+    let mut report = DetonationReport {
+        backend: driver.backend().to_string(),
+        environment_id: environment.id.clone(),
+        guest_id: None,
+        started_at,
+        finished_at: started_at,
+        vm_startup_ms: 0,
+        guest_path: None,
+        execution: None,
+        artifacts: Vec::new(),
+        errors: Vec::new(),
+        countermeasures: None,
+    };
+
+    let no_countermeasures = CountermeasurePlan::default();
+    let plan = countermeasures.unwrap_or(&no_countermeasures);
+    let startup = std::time::Instant::now();
+    let handle = match driver.start_vm(environment, job_id, plan).await {
+        Ok(handle) => handle,
+        Err(e) => {
+            report.errors.push(format!("start_vm: {}", e));
+            report.finished_at = Utc::now();
+            return report;
+        }
+    };
+    report.guest_id = Some(handle.guest_id.clone());
+
+    let run = async {
+        driver.restore_snapshot(&handle).await.map_err(|e| format!("restore_snapshot: {}", e))?;
+        if let Some(plan) = countermeasures {
+            report.countermeasures = Some(CountermeasureRecord {
+                profile_id: plan.profile_id.clone(),
+                profile_name: plan.profile_name.clone(),
+                countermeasures: driver.apply_countermeasures(&handle, plan).await,
+            });
+        }
+        report.vm_startup_ms = startup.elapsed().as_millis() as u64;
+        let guest_path = driver
+            .copy_sample(&handle, &guest_file_name(file_name), data)
+            .await
+            .map_err(|e| format!("copy_sample: {}", e))?;
+        report.guest_path = Some(guest_path.clone());
+        let execution = driver
+            .execute_sample(&handle, &guest_path, timeout)
+            .await
+            .map_err(|e| format!("execute_sample: {}", e))?;
+        report.execution = Some(execution);
+        if let Some(session) = interactive {
+            if session.hold(driver, &handle, &mut report.artifacts).await == SessionEnd::Terminated {
+                return Ok(());
+            }
+        }
+        let collected = driver.collect_artifacts(&handle).await.map_err(|e| format!("collect_artifacts: {}", e))?;
+        merge_artifacts(&mut report.artifacts, collected);
+        Ok::<(), String>(())
+    };
+    if let Err(e) = run.await {
+        report.errors.push(e);
+    }
+    if let Err(e) = driver.revert(&handle).await {
+        report.errors.push(format!("revert: {}", e));
+    }
+    report.finished_at = Utc::now();
+    report
+}
+
+// Libvirt / QEMU
+
+pub struct LibvirtDriver {
+    config: LibvirtConfig,
+    runner: Arc<dyn CommandRunner>,
+}
+
+impl LibvirtDriver {
+    pub fn new(config: LibvirtConfig, runner: Arc<dyn CommandRunner>) -> Self {
+        Self { config, runner }
+    }
+
+    async fn virsh(&self, args: &[&str]) -> Result<String, String> {
+        let mut full = vec!["-c".to_string(), self.config.connection_uri.clone()];
+        full.extend(args.iter().map(|a| a.to_string()));
+        run_checked(self.runner.as_ref(), &self.config.virsh_path, full).await
+    }
+
+    async fn agent(&self, domain: &str, command: Value) -> Result<Value, String> {
+        let stdout = self.virsh(&["qemu-agent-command", domain, &command.to_string()]).await?;
+        let response: Value = serde_json::from_str(stdout.trim()).map_err(|e| format!("Invalid guest agent response: {}", e))?;
+        response.get("return").cloned().ok_or_else(|| format!("Guest agent error: {}", response))
+    }
+
+    fn job_dir(&self, handle: &DetonationHandle) -> PathBuf {
+        self.config.host_share_dir.join(&handle.job_id)
+    }
+
+    fn guest_join(&self, parts: &[&str]) -> String {
+        let separator = if self.config.guest_share_dir.contains('\\') { '\\' } else { '/' };
+        let mut path = self.config.guest_share_dir.trim_end_matches(['/', '\\']).to_string();
+        for part in parts {
+            path.push(separator);
+            path.push_str(part);
+        }
+        path
+    }
+}
+
+#[async_trait]
+impl DetonationDriver for LibvirtDriver {
+    fn backend(&self) -> &'static str {
+        "libvirt"
+    }
+
+    async fn start_vm(&self, environment: &VMEnvironment, job_id: &str, _countermeasures: &CountermeasurePlan) -> Result<DetonationHandle, String> {
+        let domain = self.config.domain.clone().unwrap_or_else(|| environment.id.clone());
+        let state = self.virsh(&["domstate", &domain]).await?;
+        if state.trim() != "running" {
+            self.virsh(&["start", &domain]).await?;
+        }
+        Ok(DetonationHandle {
+            job_id: job_id.to_string(),
+            environment_id: environment.id.clone(),
+            guest_id: domain,
+            snapshot_id: environment.snapshot_id.clone(),
+        })
+    }
+
+    async fn restore_snapshot(&self, handle: &DetonationHandle) -> Result<(), String> {
+        self.virsh(&["snapshot-revert", &handle.guest_id, &handle.snapshot_id, "--running", "--force"]).await?;
+        Ok(())
+    }
+
+    /// CPUID hiding is part of the domain definition and can only be checked;
+    /// everything else is handed to the in-guest monitor through the share
+    async fn apply_countermeasures(&self, handle: &DetonationHandle, plan: &CountermeasurePlan) -> Vec<AppliedCountermeasure> {
+        let mut applied = Vec::new();
+        let mut delegated = Vec::new();
+        for (kind, detail) in plan.requested() {
+            if kind != CountermeasureKind::HideHypervisor {
+                delegated.push((kind, detail));
+                continue;
+            }
+            let checked = match self.virsh(&["dumpxml", &handle.guest_id]).await {
+                Ok(xml) if hides_hypervisor(&xml) => AppliedCountermeasure::new(kind, CountermeasureStatus::Applied, detail),
+                Ok(_) => AppliedCountermeasure::new(
+                    kind,
+                    CountermeasureStatus::Failed,
+                    "domain does not set <kvm><hidden state='on'/> and disable the hypervisor cpu feature",
+                ),
+                Err(e) => AppliedCountermeasure::new(kind, CountermeasureStatus::Failed, e),
+            };
+            applied.push(checked);
+        }
+        if delegated.is_empty() {
+            return applied;
+        }
+
+        let job_dir = self.job_dir(handle);
+        let written = async {
+            tokio::fs::create_dir_all(&job_dir).await?;
+            tokio::fs::write(job_dir.join("countermeasures.json"), serde_json::to_vec(plan).unwrap_or_default()).await
+        };
+        let (status, error) = match written.await {
+            Ok(()) => (CountermeasureStatus::Delegated, None),
+            Err(e) => (CountermeasureStatus::Failed, Some(format!("Failed to stage countermeasures: {}", e))),
+        };
+        applied.extend(
+            delegated
+                .into_iter()
+                .map(|(kind, detail)| AppliedCountermeasure::new(kind, status, error.clone().unwrap_or(detail))),
+        );
+        applied
+    }
+
+    async fn copy_sample(&self, handle: &DetonationHandle, file_name: &str, data: &[u8]) -> Result<String, String> {
+        let job_dir = self.job_dir(handle);
+        tokio::fs::create_dir_all(job_dir.join("artifacts"))
+            .await
+            .map_err(|e| format!("Failed to create {}: {}", job_dir.display(), e))?;
+        tokio::fs::write(job_dir.join(file_name), data)
+            .await
+            .map_err(|e| format!("Failed to stage sample: {}", e))?;
+        Ok(self.guest_join(&[&handle.job_id, file_name]))
+    }
+
+    async fn execute_sample(&self, handle: &DetonationHandle, guest_path: &str, timeout: Duration) -> Result<ExecutionOutcome, String> {
+        let started = self
+            .agent(&handle.guest_id, json!({ "execute": "guest-exec", "arguments": { "path": guest_path, "capture-output": false } }))
+            .await?;
+        let pid = started.get("pid").and_then(Value::as_i64).ok_or("Guest agent did not return a pid")?;
+
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let status = self
+                .agent(&handle.guest_id, json!({ "execute": "guest-exec-status", "arguments": { "pid": pid } }))
+                .await?;
+            if status.get("exited").and_then(Value::as_bool).unwrap_or(false) {
+                return Ok(ExecutionOutcome {
+                    exit_code: status.get("exitcode").and_then(Value::as_i64).map(|c| c as i32),
+                    ..Default::default()
+                });
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Ok(ExecutionOutcome { timed_out: true, ..Default::default() });
+            }
+            tokio::time::sleep(EXEC_POLL_INTERVAL.min(timeout)).await;
+        }
+    }
+
+    async fn collect_artifacts(&self, handle: &DetonationHandle) -> Result<Vec<DetonationArtifact>, String> {
+        let root = self.job_dir(handle).join("artifacts");
+        let mut artifacts = Vec::new();
+        let mut pending = vec![root.clone()];
+        while let Some(dir) = pending.pop() {
+            let mut entries = match tokio::fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(format!("Failed to read {}: {}", dir.display(), e)),
+            };
+            while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+                let path = entry.path();
+                if entry.file_type().await.map_err(|e| e.to_string())?.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+                let data = tokio::fs::read(&path).await.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+                artifacts.push(DetonationArtifact {
+                    kind: ArtifactKind::File,
+                    path: path.strip_prefix(&root).unwrap_or(&path).to_string_lossy().into_owned(),
+                    size_bytes: data.len() as u64,
+                    sha256: Some(sha256_hex(&data)),
+                    artifact_id: None,
+                    content: Some(data),
+                });
+            }
+        }
+        artifacts.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(artifacts)
+    }
+
+    async fn revert(&self, handle: &DetonationHandle) -> Result<(), String> {
+        let reverted = self.virsh(&["snapshot-revert", &handle.guest_id, &handle.snapshot_id, "--force"]).await;
+        // Stopping an already stopped domain fails harmlessly
+        let _ = self.virsh(&["destroy", &handle.guest_id]).await;
+        let job_dir = self.job_dir(handle);
+        if let Err(e) = tokio::fs::remove_dir_all(&job_dir).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::warn!("Failed to clean up {}: {}", job_dir.display(), e);
+            }
+        }
+        reverted.map(|_| ())
+    }
+}
+
+/// True when the domain sets `<features><kvm><hidden state='on'/>` and
+/// `<cpu><feature policy='disable' name='hypervisor'/>`
+fn hides_hypervisor(domain_xml: &str) -> bool {
+    let Ok(document) = roxmltree::Document::parse(domain_xml) else {
+        return false;
+    };
+    fn child<'a, 'input>(parent: roxmltree::Node<'a, 'input>, name: &str) -> Option<roxmltree::Node<'a, 'input>> {
+        parent.children().find(|n| n.has_tag_name(name))
+    }
+    let domain = document.root_element();
+    let kvm_hidden = child(domain, "features")
+        .and_then(|features| child(features, "kvm"))
+        .and_then(|kvm| child(kvm, "hidden"))
+        .is_some_and(|hidden| hidden.attribute("state") == Some("on"));
+    let cpuid_disabled = child(domain, "cpu").is_some_and(|cpu| {
+        cpu.children()
+            .filter(|n| n.has_tag_name("feature"))
+            .any(|feature| feature.attribute("name") == Some("hypervisor") && feature.attribute("policy") == Some("disable"))
+    });
+    kvm_hidden && cpuid_disabled
+}
+
+// Docker
+
+pub struct DockerDriver {
+    config: DockerConfig,
+    runner: Arc<dyn CommandRunner>,
+}
+
+impl DockerDriver {
+    pub fn new(config: DockerConfig, runner: Arc<dyn CommandRunner>) -> Self {
+        Self { config, runner }
+    }
+
+    async fn docker(&self, args: Vec<String>) -> Result<String, String> {
+        run_checked(self.runner.as_ref(), &self.config.docker_path, args).await
+    }
+
+    fn has_network(&self) -> bool {
+        self.config.network != "none"
+    }
+}
+
+fn strings(args: &[&str]) -> Vec<String> {
+    args.iter().map(|a| a.to_string()).collect()
+}
+
+#[async_trait]
+impl DetonationDriver for DockerDriver {
+    fn backend(&self) -> &'static str {
+        "docker"
+    }
+
+    async fn start_vm(&self, environment: &VMEnvironment, job_id: &str, countermeasures: &CountermeasurePlan) -> Result<DetonationHandle, String> {
+        let limits = &environment.resource_limits;
+        let mut args = strings(&["run", "--detach", "--network", &self.config.network]);
+        args.extend([
+            "--name".to_string(),
+            format!("phantom-{}", job_id),
+            "--memory".to_string(),
+            format!("{}m", limits.memory_mb),
+            "--cpus".to_string(),
+            limits.cpu_cores.max(1).to_string(),
+            "--workdir".to_string(),
+            self.config.workdir.clone(),
+        ]);
+        if let Some(hostname) = &countermeasures.hostname {
+            args.extend(["--hostname".to_string(), hostname.clone()]);
+        }
+        // Docker rejects a MAC address on containers without a network
+        if let (Some(mac), true) = (&countermeasures.mac_address, self.has_network()) {
+            args.extend(["--mac-address".to_string(), mac.clone()]);
+        }
+        if let (Some(factor), Some(library)) = (countermeasures.clock_factor, &self.config.faketime_library) {
+            args.extend([
+                "--env".to_string(),
+                format!("LD_PRELOAD={}", library),
+                "--env".to_string(),
+                format!("FAKETIME=+0 x{}", factor),
+            ]);
+        }
+        args.extend([
+            "--entrypoint".to_string(),
+            "sleep".to_string(),
+            self.config.image.clone(),
+            "infinity".to_string(),
+        ]);
+        let container_id = self.docker(args).await?.trim().to_string();
+        if container_id.is_empty() {
+            return Err("docker run returned no container id".to_string());
+        }
+        Ok(DetonationHandle {
+            job_id: job_id.to_string(),
+            environment_id: environment.id.clone(),
+            guest_id: container_id,
+            snapshot_id: environment.snapshot_id.clone(),
+        })
+    }
+
+    /// Every container starts from the pristine image, so there is nothing to roll back
+    async fn restore_snapshot(&self, _handle: &DetonationHandle) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Identifiers and the clock are fixed when the container starts; report what `start_vm` set
+    async fn apply_countermeasures(&self, _handle: &DetonationHandle, plan: &CountermeasurePlan) -> Vec<AppliedCountermeasure> {
+        plan.requested()
+            .into_iter()
+            .map(|(kind, detail)| {
+                let unsupported = |reason: &str| AppliedCountermeasure::new(kind, CountermeasureStatus::Unsupported, reason);
+                match kind {
+                    CountermeasureKind::HideHypervisor => unsupported("containers run on the host CPU"),
+                    CountermeasureKind::HumanInteraction => unsupported("containers have no desktop session"),
+                    CountermeasureKind::RandomizeMac if !self.has_network() => unsupported("container networking is disabled"),
+                    CountermeasureKind::ClockAcceleration if self.config.faketime_library.is_none() => {
+                        unsupported("no faketime_library configured for the image")
+                    }
+                    _ => AppliedCountermeasure::new(kind, CountermeasureStatus::Applied, detail),
+                }
+            })
+            .collect()
+    }
+
+    async fn copy_sample(&self, handle: &DetonationHandle, file_name: &str, data: &[u8]) -> Result<String, String> {
+        let staged = std::env::temp_dir().join(format!("phantom-{}-{}", handle.job_id, file_name));
+        tokio::fs::write(&staged, data).await.map_err(|e| format!("Failed to stage sample: {}", e))?;
+        let guest_path = format!("{}/{}", self.config.workdir.trim_end_matches('/'), file_name);
+        let copied = self
+            .docker(vec!["cp".to_string(), staged.to_string_lossy().into_owned(), format!("{}:{}", handle.guest_id, guest_path)])
+            .await;
+        let _ = tokio::fs::remove_file(&staged).await;
+        copied?;
+        self.docker(strings(&["exec", &handle.guest_id, "chmod", "+x", &guest_path])).await?;
+        Ok(guest_path)
+    }
+
+    async fn execute_sample(&self, handle: &DetonationHandle, guest_path: &str, timeout: Duration) -> Result<ExecutionOutcome, String> {
+        let mut args = strings(&["exec"]);
+        if let Some(user) = &self.config.user {
+            args.extend(["--user".to_string(), user.clone()]);
+        }
+        args.extend([handle.guest_id.clone(), guest_path.to_string()]);
+        let output = self.runner.run(&self.config.docker_path, &args, timeout).await?;
+        Ok(ExecutionOutcome {
+            exit_code: output.exit_code,
+            timed_out: output.timed_out,
+            stdout: output.stdout,
+            stderr: output.stderr,
+        })
+    }
+
+    async fn collect_artifacts(&self, handle: &DetonationHandle) -> Result<Vec<DetonationArtifact>, String> {
+        let diff = self.docker(strings(&["diff", &handle.guest_id])).await?;
+        Ok(diff
+            .lines()
+            .filter_map(|line| {
+                let (change, path) = line.split_once(' ')?;
+                let kind = match change {
+                    "A" => ArtifactKind::FileCreated,
+                    "C" => ArtifactKind::FileModified,
+                    "D" => ArtifactKind::FileDeleted,
+                    _ => return None,
+                };
+                Some(DetonationArtifact {
+                    kind,
+                    path: path.trim().to_string(),
+                    size_bytes: 0,
+                    sha256: None,
+                    artifact_id: None,
+                    content: None,
+                })
+            })
+            .collect())
+    }
+
+    async fn revert(&self, handle: &DetonationHandle) -> Result<(), String> {
+        self.docker(strings(&["rm", "--force", &handle.guest_id])).await.map(|_| ())
+    }
+}
+
+// SandboxCore integration
+
+impl SandboxCore {
+    /// Select the detonation backend for an environment
+    pub async fn configure_vm_driver(&self, environment_id: &str, driver: DriverConfig) -> Result<(), String> {
+        let mut environments = self.vm_environments.write().await;
+        let environment = environments
+            .get_mut(environment_id)
+            .ok_or_else(|| format!("VM environment {} not found", environment_id))?;
+        environment.driver = driver;
+        self.detonation_drivers.write().await.remove(environment_id);
+        Ok(())
+    }
+
+    /// Use a specific driver instance for an environment, overriding its config
+    pub async fn set_detonation_driver(&self, environment_id: &str, driver: Arc<dyn DetonationDriver>) {
+        self.detonation_drivers.write().await.insert(environment_id.to_string(), driver);
+    }
+
+    async fn detonation_driver(&self, environment: &VMEnvironment) -> Option<Arc<dyn DetonationDriver>> {
+        if let Some(driver) = self.detonation_drivers.read().await.get(&environment.id) {
+            return Some(driver.clone());
+        }
+        environment.driver.build(Arc::new(SystemCommandRunner))
+    }
+
+    /// Detonate the job's sample on a real guest; `None` when the environment is simulated
+    pub(crate) async fn run_detonation(&self, job: &AnalysisJob, file_name: &str) -> Option<DetonationReport> {
+        let environment = self.vm_environments.read().await.get(&job.vm_environment).cloned()?;
+        let driver = self.detonation_driver(&environment).await?;
+        let data = self.sample_data.read().await.get(&job.sample_id).cloned().unwrap_or_default();
+        let timeout = Duration::from_secs(job.analysis_config.analysis_time.max(1));
+        let countermeasures = self.countermeasure_plan(environment.countermeasure_profile.as_deref()).await;
+        let interactive = job.analysis_config.interactive_session.as_deref().and_then(|session_id| self.interactive.get(session_id));
+        Some(detonate(driver.as_ref(), &environment, &job.job_id, file_name, &data, timeout, countermeasures.as_ref(), interactive.as_deref()).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::sync::Mutex;
+
+    /// Replays canned outputs and records every command line
+    #[derive(Default)]
+    struct ScriptedRunner {
+        responses: Mutex<VecDeque<CommandOutput>>,
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl ScriptedRunner {
+        fn respond(self, stdout: &str) -> Self {
+            self.responses.lock().unwrap().push_back(CommandOutput {
+                exit_code: Some(0),
+                stdout: stdout.to_string(),
+                ..Default::default()
+            });
+            self
+        }
+
+        fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl CommandRunner for ScriptedRunner {
+        async fn run(&self, program: &str, args: &[String], _timeout: Duration) -> Result<CommandOutput, String> {
+            self.calls.lock().unwrap().push(format!("{} {}", program, args.join(" ")));
+            Ok(self.responses.lock().unwrap().pop_front().unwrap_or(CommandOutput {
+                exit_code: Some(1),
+                stderr: "unexpected command".to_string(),
+                ..Default::default()
+            }))
+        }
+    }
+
+    fn environment() -> VMEnvironment {
+        SandboxCore::initialize_vm_environments().unwrap().remove("win10-x64").unwrap()
+    }
+
+    #[test]
+    fn test_driver_configs_cannot_choose_executables() {
+        let docker: DriverConfig = serde_json::from_value(serde_json::json!({
+            "backend": "docker", "image": "phantom/detonate:ubuntu22", "docker_path": "/tmp/payload",
+        }))
+        .unwrap();
+        let DriverConfig::Docker(docker) = docker else { panic!("expected a docker config") };
+        assert_ne!(docker.docker_path, "/tmp/payload");
+
+        let libvirt: DriverConfig = serde_json::from_value(serde_json::json!({
+            "backend": "libvirt", "host_share_dir": "/srv/share", "guest_share_dir": "Z:\\", "virsh_path": "/tmp/payload",
+        }))
+        .unwrap();
+        let DriverConfig::Libvirt(libvirt) = libvirt else { panic!("expected a libvirt config") };
+        assert_ne!(libvirt.virsh_path, "/tmp/payload");
+    }
+
+    #[tokio::test]
+    async fn test_docker_lifecycle_collects_changes_and_removes_container() {
+        let runner = Arc::new(
+            ScriptedRunner::default()
+                .respond("abc123\n")
+                .respond("")
+                .respond("")
+                .respond("hello")
+                .respond("A /tmp/dropped.sh\nC /etc/crontab\nD /var/log/auth.log\n")
+                .respond(""),
+        );
+        let driver = DockerDriver::new(
+            DockerConfig {
+                image: "phantom/detonate:ubuntu22".to_string(),
+                docker_path: "docker".to_string(),
+                network: "none".to_string(),
+                workdir: "/sandbox".to_string(),
+                user: None,
+                faketime_library: None,
+            },
+            runner.clone(),
+        );
+
+        let report = detonate(&driver, &environment(), "job-1", "../evil payload.sh", b"#!/bin/sh", Duration::from_secs(5), None, None).await;
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!(report.guest_id.as_deref(), Some("abc123"));
+        assert_eq!(report.guest_path.as_deref(), Some("/sandbox/evil_payload.sh"));
+        assert_eq!(report.execution.unwrap().stdout, "hello");
+        let kinds: Vec<ArtifactKind> = report.artifacts.iter().map(|a| a.kind).collect();
+        assert_eq!(kinds, vec![ArtifactKind::FileCreated, ArtifactKind::FileModified, ArtifactKind::FileDeleted]);
+
+        let calls = runner.calls();
+        assert!(calls[0].starts_with("docker run --detach --network none --name phantom-job-1 --memory 4096m"));
+        assert_eq!(calls.last().unwrap(), "docker rm --force abc123");
+    }
+
+    #[tokio::test]
+    async fn test_libvirt_reverts_even_when_execution_fails() {
+        let share = std::env::temp_dir().join(format!("phantom-libvirt-{}", uuid::Uuid::new_v4()));
+        let runner = Arc::new(
+            ScriptedRunner::default()
+                .respond("shut off\n")
+                .respond("")
+                .respond("Domain snapshot reverted\n")
+                .respond(r#"{"error":{"class":"GenericError","desc":"agent not available"}}"#)
+                .respond("")
+                .respond(""),
+        );
+        let driver = LibvirtDriver::new(
+            LibvirtConfig {
+                connection_uri: default_libvirt_uri(),
+                domain: None,
+                host_share_dir: share.clone(),
+                guest_share_dir: "Z:\\".to_string(),
+                virsh_path: default_virsh(),
+            },
+            runner.clone(),
+        );
+
+        let report = detonate(&driver, &environment(), "job-2", "sample.exe", b"MZ", Duration::from_secs(5), None, None).await;
+        assert_eq!(report.guest_path.as_deref(), Some("Z:\\job-2\\sample.exe"));
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].starts_with("execute_sample: Guest agent error"));
+
+        let calls = runner.calls();
+        assert_eq!(calls[1], "virsh -c qemu:///system start win10-x64");
+        assert_eq!(calls[4], "virsh -c qemu:///system snapshot-revert win10-x64 clean_win10_snapshot_001 --force");
+        assert_eq!(calls[5], "virsh -c qemu:///system destroy win10-x64");
+        assert!(!share.join("job-2").exists());
+    }
+
+    #[tokio::test]
+    async fn test_docker_records_which_countermeasures_were_active() {
+        let runner = Arc::new(ScriptedRunner::default().respond("abc123\n").respond("").respond("").respond("").respond("").respond(""));
+        let driver = DockerDriver::new(
+            DockerConfig {
+                image: "phantom/detonate:ubuntu22".to_string(),
+                docker_path: "docker".to_string(),
+                network: "none".to_string(),
+                workdir: "/sandbox".to_string(),
+                user: None,
+                faketime_library: Some("/usr/lib/faketime/libfaketime.so.1".to_string()),
+            },
+            runner.clone(),
+        );
+        let plan = crate::countermeasures::CountermeasureProfile::full().resolve();
+
+        let report = detonate(&driver, &environment(), "job-3", "a.sh", b"#!/bin/sh", Duration::from_secs(5), Some(&plan), None).await;
+        let record = report.countermeasures.unwrap();
+        assert_eq!(record.profile_id, "full");
+        let status = |kind: CountermeasureKind| record.countermeasures.iter().find(|c| c.kind == kind).unwrap().status;
+        assert_eq!(status(CountermeasureKind::RandomizeHostname), CountermeasureStatus::Applied);
+        assert_eq!(status(CountermeasureKind::ClockAcceleration), CountermeasureStatus::Applied);
+        assert_eq!(status(CountermeasureKind::RandomizeMac), CountermeasureStatus::Unsupported);
+        assert_eq!(status(CountermeasureKind::HideHypervisor), CountermeasureStatus::Unsupported);
+
+        let run = &runner.calls()[0];
+        assert!(run.contains(&format!("--hostname {}", plan.hostname.unwrap())));
+        assert!(run.contains("--env FAKETIME=+0 x10"));
+        assert!(!run.contains("--mac-address"));
+    }
+
+    #[tokio::test]
+    async fn test_libvirt_checks_cpuid_hiding_and_delegates_the_rest() {
+        let share = std::env::temp_dir().join(format!("phantom-libvirt-{}", uuid::Uuid::new_v4()));
+        let runner = Arc::new(
+            ScriptedRunner::default()
+                .respond("running\n")
+                .respond("<domain><features><kvm><hidden state=\"on\"/></kvm></features><cpu><feature policy='disable' name='hypervisor'/></cpu></domain>"),
+        );
+        let driver = LibvirtDriver::new(
+            LibvirtConfig {
+                connection_uri: default_libvirt_uri(),
+                domain: None,
+                host_share_dir: share.clone(),
+                guest_share_dir: "Z:\\".to_string(),
+                virsh_path: default_virsh(),
+            },
+            runner,
+        );
+        let plan = crate::countermeasures::CountermeasureProfile::stealth().resolve();
+        let handle = driver.start_vm(&environment(), "job-4", &plan).await.unwrap();
+
+        let applied = driver.apply_countermeasures(&handle, &plan).await;
+        assert_eq!(applied[0].kind, CountermeasureKind::HideHypervisor);
+        assert_eq!(applied[0].status, CountermeasureStatus::Applied);
+        assert!(applied[1..].iter().all(|c| c.status == CountermeasureStatus::Delegated));
+        let staged: CountermeasurePlan = serde_json::from_slice(&std::fs::read(share.join("job-4").join("countermeasures.json")).unwrap()).unwrap();
+        assert_eq!(staged, plan);
+        let _ = std::fs::remove_dir_all(&share);
+
+        let required = "<domain><features><kvm><hidden state='on'/></kvm></features><cpu><feature policy='require' name='hypervisor'/></cpu></domain>";
+        assert!(!hides_hypervisor(required));
+    }
+}
@@ -0,0 +1,330 @@
+//! Analysis diffing
+//!
+//! Answers "what changed between variant A and B": two completed analyses
+//! are compared section by section (behaviors, API calls, registry and file
+//! changes, network infrastructure, strings and MITRE techniques). Each
+//! section lists what B added and dropped relative to A, items present in
+//! both whose detail differs (a file's hash, a registry value, a domain's
+//! resolution), and the Jaccard similarity of the two sides. The overall
+//! similarity is the weighted mean over the sections observed in at least
+//! one of the analyses.
+
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::error::{CoreError, CoreResult};
+use crate::{SandboxAnalysis, SandboxCore, SandboxCoreNapi};
+
+/// Section weights of the overall similarity
+const WEIGHTS: [(&str, f64); 7] = [
+    ("behaviors", 0.20),
+    ("api_calls", 0.15),
+    ("registry", 0.10),
+    ("files", 0.15),
+    ("network", 0.15),
+    ("strings", 0.10),
+    ("mitre_techniques", 0.15),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangedItem {
+    pub key: String,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiffSection {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<ChangedItem>,
+    pub unchanged: usize,
+    /// Jaccard similarity of the two sides; 1.0 when both are empty
+    pub similarity: f64,
+}
+
+impl DiffSection {
+    /// Diff two sides keyed by item, each with a detail compared for changes
+    fn compare(before: &BTreeMap<String, String>, after: &BTreeMap<String, String>) -> Self {
+        let mut section = DiffSection::default();
+        for (key, old) in before {
+            match after.get(key) {
+                Some(new) if new != old => section.changed.push(ChangedItem { key: key.clone(), before: old.clone(), after: new.clone() }),
+                Some(_) => section.unchanged += 1,
+                None => section.removed.push(key.clone()),
+            }
+        }
+        section.added = after.keys().filter(|key| !before.contains_key(*key)).cloned().collect();
+        let shared = section.changed.len() + section.unchanged;
+        let union = shared + section.added.len() + section.removed.len();
+        section.similarity = if union == 0 { 1.0 } else { shared as f64 / union as f64 };
+        section
+    }
+
+    fn is_empty(&self) -> bool {
+        self.unchanged == 0 && self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisDiff {
+    pub sample_a: String,
+    pub sample_b: String,
+    /// Verdict, threat level, family, category and confidence where they differ
+    pub overview: Vec<ChangedItem>,
+    pub behaviors: DiffSection,
+    pub api_calls: DiffSection,
+    pub registry: DiffSection,
+    pub files: DiffSection,
+    pub network: DiffSection,
+    pub strings: DiffSection,
+    pub mitre_techniques: DiffSection,
+    pub similarity: f64,
+}
+
+impl AnalysisDiff {
+    fn sections(&self) -> [(&'static str, &DiffSection); 7] {
+        [
+            ("behaviors", &self.behaviors),
+            ("api_calls", &self.api_calls),
+            ("registry", &self.registry),
+            ("files", &self.files),
+            ("network", &self.network),
+            ("strings", &self.strings),
+            ("mitre_techniques", &self.mitre_techniques),
+        ]
+    }
+}
+
+fn behaviors(analysis: &SandboxAnalysis) -> BTreeMap<String, String> {
+    analysis
+        .behavioral_analysis
+        .suspicious_behaviors
+        .iter()
+        .map(|behavior| {
+            let key = match &behavior.mitre_technique {
+                Some(technique) => format!("{} [{}]", behavior.description, technique),
+                None => behavior.description.clone(),
+            };
+            (key, format!("{:?}", behavior.severity))
+        })
+        .collect()
+}
+
+fn api_calls(analysis: &SandboxAnalysis) -> BTreeMap<String, String> {
+    let calls = &analysis.behavioral_analysis.api_calls;
+    let mut apis: BTreeMap<String, String> = calls.call_frequency.keys().map(|api| (api.clone(), String::new())).collect();
+    apis.extend(calls.call_timeline.iter().map(|event| (event.api_name.clone(), String::new())));
+    // Suspicious calls carry the DLL, so a call moving to another module shows as changed
+    for call in &calls.suspicious_calls {
+        apis.insert(call.api_name.clone(), call.dll_name.to_ascii_lowercase());
+    }
+    apis
+}
+
+fn registry(analysis: &SandboxAnalysis) -> BTreeMap<String, String> {
+    let mut keys: BTreeMap<String, String> = analysis
+        .behavioral_analysis
+        .system_changes
+        .registry_changes
+        .iter()
+        .map(|change| {
+            let detail = change.new_value.clone().unwrap_or_else(|| change.operation.clone());
+            (format!("{}\\{}", change.key_path, change.value_name), detail)
+        })
+        .collect();
+    for key in &analysis.registry_analysis.suspicious_keys {
+        keys.entry(format!("{}\\{}", key.key_path, key.value_name)).or_insert_with(|| key.value_data.clone());
+    }
+    keys
+}
+
+fn files(analysis: &SandboxAnalysis) -> BTreeMap<String, String> {
+    let changes = &analysis.behavioral_analysis.system_changes;
+    let mut files = BTreeMap::new();
+    for change in changes.files_created.iter().chain(&changes.files_modified).chain(&changes.files_deleted) {
+        let detail = change.file_hash.clone().unwrap_or_else(|| change.operation.clone());
+        files.insert(change.file_path.clone(), detail);
+    }
+    for dropped in &analysis.file_system_analysis.dropped_files {
+        files.insert(dropped.file_path.clone(), dropped.file_hash.clone());
+    }
+    files
+}
+
+fn network(analysis: &SandboxAnalysis) -> BTreeMap<String, String> {
+    let network = &analysis.network_analysis;
+    let mut infrastructure = BTreeMap::new();
+    for connection in &network.connections {
+        let key = format!("{}://{}:{}", connection.protocol.to_ascii_lowercase(), connection.remote_address, connection.remote_port);
+        infrastructure.insert(key, String::new());
+    }
+    for query in &network.dns_queries {
+        let mut answers = query.response.clone();
+        answers.sort();
+        infrastructure.insert(format!("dns:{}", query.domain.to_ascii_lowercase()), answers.join(","));
+    }
+    for request in &network.http_requests {
+        infrastructure.insert(format!("{} {}", request.method.to_ascii_uppercase(), request.url), String::new());
+    }
+    infrastructure
+}
+
+fn strings(analysis: &SandboxAnalysis) -> BTreeMap<String, String> {
+    let strings = &analysis.static_analysis.strings_analysis;
+    let mut values: BTreeMap<String, String> =
+        strings.suspicious_strings.iter().map(|s| (s.string_value.clone(), s.category.clone())).collect();
+    for (kind, items) in [("url", &strings.urls), ("ip", &strings.ip_addresses), ("path", &strings.file_paths), ("registry", &strings.registry_keys)] {
+        for item in items {
+            values.entry(item.clone()).or_insert_with(|| kind.to_string());
+        }
+    }
+    values
+}
+
+/// Confidence is bucketed to a tenth so run-to-run noise does not count as a change
+fn mitre_techniques(analysis: &SandboxAnalysis) -> BTreeMap<String, String> {
+    analysis
+        .mitre_techniques
+        .iter()
+        .map(|technique| (technique.technique_id.clone(), format!("confidence {:.1}", technique.confidence)))
+        .collect()
+}
+
+fn overview(a: &SandboxAnalysis, b: &SandboxAnalysis) -> Vec<ChangedItem> {
+    let fields = |analysis: &SandboxAnalysis| {
+        [
+            ("verdict", format!("{:?}", analysis.verdict)),
+            ("threat_level", format!("{:?}", analysis.threat_level)),
+            ("family", analysis.malware_classification.family.clone().unwrap_or_default()),
+            ("category", format!("{:?}", analysis.malware_classification.category)),
+            ("confidence", format!("{:.2}", analysis.confidence_score)),
+        ]
+    };
+    fields(a)
+        .into_iter()
+        .zip(fields(b))
+        .filter(|((_, before), (_, after))| before != after)
+        .map(|((key, before), (_, after))| ChangedItem { key: key.to_string(), before, after })
+        .collect()
+}
+
+/// Structured diff of analysis `b` against analysis `a`
+pub fn diff(a: &SandboxAnalysis, b: &SandboxAnalysis) -> AnalysisDiff {
+    let section = |extract: fn(&SandboxAnalysis) -> BTreeMap<String, String>| DiffSection::compare(&extract(a), &extract(b));
+    let mut diff = AnalysisDiff {
+        sample_a: a.sample_info.sample_id.clone(),
+        sample_b: b.sample_info.sample_id.clone(),
+        overview: overview(a, b),
+        behaviors: section(behaviors),
+        api_calls: section(api_calls),
+        registry: section(registry),
+        files: section(files),
+        network: section(network),
+        strings: section(strings),
+        mitre_techniques: section(mitre_techniques),
+        similarity: 1.0,
+    };
+
+    let weights: BTreeMap<&str, f64> = WEIGHTS.into_iter().collect();
+    let (weighted, total) = diff
+        .sections()
+        .iter()
+        .filter(|(_, section)| !section.is_empty())
+        .fold((0.0, 0.0), |(weighted, total), (name, section)| {
+            let weight = weights[name];
+            (weighted + weight * section.similarity, total + weight)
+        });
+    if total > 0.0 {
+        diff.similarity = weighted / total;
+    }
+    diff
+}
+
+impl SandboxCore {
+    /// Diff the completed analyses of two of the tenant's samples
+    pub async fn diff_analyses(&self, tenant_id: &str, sample_id_a: &str, sample_id_b: &str) -> CoreResult<AnalysisDiff> {
+        let mut analyses = Vec::with_capacity(2);
+        for sample_id in [sample_id_a, sample_id_b] {
+            let analysis = self
+                .get_analysis(tenant_id, sample_id)
+                .await?
+                .ok_or_else(|| CoreError::not_found(format!("Analysis for sample {} not found", sample_id)))?;
+            analyses.push(analysis);
+        }
+        Ok(diff(&analyses[0], &analyses[1]))
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// What changed from the analysis of `sample_id_a` to that of `sample_id_b`
+    #[napi]
+    pub async fn diff_analyses(&self, sample_id_a: String, sample_id_b: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let diff = self.inner.diff_analyses(&tenant_id, &sample_id_a, &sample_id_b).await
+            .map_err(|e| e.context("Failed to diff analyses"))?;
+        serde_json::to_string(&diff)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize analysis diff: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AnalysisPriority, DNSQuery, DroppedFile};
+    use chrono::Utc;
+
+    fn dns(domain: &str, answer: &str) -> DNSQuery {
+        serde_json::from_value(serde_json::json!({
+            "query_id": domain, "domain": domain, "query_type": "A", "response": [answer], "response_code": "NOERROR",
+            "timestamp": Utc::now(), "process_name": "implant.exe", "process_id": 4242, "is_suspicious": true, "threat_category": null
+        }))
+        .unwrap()
+    }
+
+    fn dropped(path: &str, hash: &str) -> DroppedFile {
+        serde_json::from_value(serde_json::json!({
+            "file_path": path, "file_hash": hash, "file_size": 1024, "file_type": "PE32", "dropped_by": "implant.exe", "entropy": 6.1, "strings": [],
+            "timestamp": Utc::now(), "is_executable": true, "is_packed": false
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn variants_diff_by_section() {
+        let core = SandboxCore::new().unwrap();
+        let mut samples = Vec::new();
+        for (bytes, name) in [(&b"MZ variant a"[..], "a.exe"), (&b"MZ variant b"[..], "b.exe")] {
+            samples.push(core.submit_sample("acme", bytes, name.to_string(), AnalysisPriority::Normal, vec![], false).await.unwrap());
+        }
+        core.process_queue().await.unwrap();
+
+        let same = core.diff_analyses("acme", &samples[0], &samples[0]).await.unwrap();
+        assert_eq!(same.similarity, 1.0);
+        assert!(same.overview.is_empty());
+        let missing = core.diff_analyses("globex", &samples[0], &samples[1]).await.unwrap_err();
+        assert_eq!(missing.code(), "NOT_FOUND");
+
+        let mut a = core.get_analysis("acme", &samples[0]).await.unwrap().unwrap();
+        let mut b = a.clone();
+        b.sample_info.sample_id = samples[1].clone();
+        a.network_analysis.dns_queries = vec![dns("c2.example.net", "203.0.113.5"), dns("cdn.example.org", "198.51.100.1")];
+        b.network_analysis.dns_queries = vec![dns("c2.example.net", "203.0.113.9"), dns("update.example.io", "192.0.2.44")];
+        a.file_system_analysis.dropped_files = vec![dropped("C:\\Users\\Public\\svc.exe", "aaaa")];
+        b.file_system_analysis.dropped_files = vec![dropped("C:\\Users\\Public\\svc.exe", "bbbb")];
+        b.mitre_techniques.clear();
+        b.malware_classification.family = Some("EmberLoader".to_string());
+
+        let diff = diff(&a, &b);
+        assert_eq!(diff.network.added, ["dns:update.example.io"]);
+        assert_eq!(diff.network.removed, ["dns:cdn.example.org"]);
+        assert_eq!(diff.network.changed[0].after, "203.0.113.9");
+        assert_eq!(diff.files.changed[0].key, "C:\\Users\\Public\\svc.exe");
+        assert_eq!(diff.mitre_techniques.removed.len(), a.mitre_techniques.len());
+        assert_eq!(diff.behaviors.similarity, 1.0);
+        assert!(diff.overview.iter().any(|item| item.key == "family" && item.after == "EmberLoader"));
+        assert!(diff.similarity < 1.0 && diff.similarity > 0.0, "{}", diff.similarity);
+    }
+}
@@ -0,0 +1,617 @@
+//! DNS tunneling and DGA detection
+//!
+//! Two detectors read the DNS queries a sample made. The tunneling detector
+//! groups queries by registrable domain and looks for what carrying data in
+//! DNS leaves behind: long, high-entropy subdomains, many distinct
+//! subdomains under one parent, a high query rate, and TXT or NULL lookups,
+//! which have room for data in their answers. Each signal adds its weight
+//! to the domain's score.
+//!
+//! The DGA detector scores the registrable label of every queried domain
+//! with a logistic model over its length, character entropy, consonant
+//! ratio, longest consonant run, digit share and likelihood under a
+//! character bigram model. The bigram model is trained on first use from an
+//! embedded corpus of common words and popular domain labels, so names that
+//! read like words score low and keyboard mash or hex scores high. A burst
+//! of NXDOMAIN answers for such names, typical of a DGA cycling through
+//! candidates until one resolves, raises their score.
+//!
+//! Findings above the configured thresholds become `C2Indicator`s carrying
+//! the measurements that triggered them, and their queries are marked
+//! suspicious.
+
+use chrono::{DateTime, Utc};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::{OnceLock, RwLock};
+
+use crate::{C2Indicator, DNSQuery, IndicatorEvidence, NetworkAnalysis, SandboxCore, SandboxCoreNapi};
+
+/// Suffixes under which the registrable domain has three labels
+const TWO_LABEL_SUFFIXES: &[&str] = &[
+    "co.uk", "org.uk", "ac.uk", "gov.uk", "com.au", "net.au", "org.au", "co.jp", "co.nz", "com.br", "com.cn", "com.mx", "co.in",
+    "co.za", "com.tr", "com.sg",
+];
+
+/// Record types with room for arbitrary data in the answer
+const DATA_RECORD_TYPES: &[&str] = &["TXT", "NULL", "10"];
+
+const TUNNEL_ENTROPY_BITS: f64 = 3.5;
+const TUNNEL_MIN_SUBDOMAIN_LEN: f64 = 16.0;
+const TUNNEL_LONG_LABEL: usize = 40;
+const TUNNEL_UNIQUE_SHARE: f64 = 0.7;
+const TUNNEL_QUERIES_PER_MINUTE: f64 = 30.0;
+const TUNNEL_DATA_RECORD_SHARE: f64 = 0.5;
+const NXDOMAIN_BURST: usize = 3;
+const NXDOMAIN_BONUS: f64 = 0.1;
+
+/// Benign text the bigram model is trained on
+const BENIGN_CORPUS: &str = "google youtube facebook amazon wikipedia twitter instagram linkedin microsoft apple netflix yahoo reddit \
+bing office live windows update github gitlab stackoverflow cloudflare akamai adobe dropbox salesforce oracle zoom slack spotify paypal \
+ebay walmart target bestbuy weather news sports health bank banking credit card finance insurance travel hotel booking airline mail \
+email login account secure support service services online store shop shopping market digital media cloud data center network systems \
+solutions technology software hardware security analytics content delivery static assets images video music games gaming play stream \
+tv radio school university college education library government city county state national international global world local community \
+center group company corporation business enterprise partners consulting marketing advertising blog forum wiki docs help download \
+downloads mobile app apps api cdn edge portal dashboard admin manager management customer client server host hosting domain website web \
+site page home house garden kitchen food recipe restaurant coffee pizza burger fashion clothing shoes beauty fitness medical hospital \
+clinic pharmacy doctor dental law legal attorney real estate property energy power electric solar water environment nature animal pets \
+dog cat horse farm agriculture science research institute foundation charity church family children kids baby parents wedding photo \
+photography design creative studio agency events tickets movies theater books reading writing journal magazine press report review \
+reviews compare deals coupons discount microsoftonline windowsupdate googleapis gstatic doubleclick googleusercontent amazonaws \
+azureedge azurewebsites outlook hotmail skype teams sharepoint onedrive icloud itunes appstore mozilla firefox chrome safari opera \
+brave duckduckgo baidu yandex naver alibaba aliexpress taobao tencent weibo sina mailru wordpress blogger tumblr pinterest quora medium \
+imgur twitch discord telegram whatsapp signal viber wechat tiktok snapchat vimeo dailymotion soundcloud bandcamp shopify squarespace \
+wix godaddy namecheap hostgator bluehost digitalocean linode heroku vercel netlify fastly jsdelivr unpkg npmjs pypi rubygems docker \
+kubernetes redhat ubuntu debian fedora centos apache nginx python java javascript golang rust";
+
+/// Characters of a hostname label; index 0 marks the start and end of a label
+const ALPHABET: &str = "abcdefghijklmnopqrstuvwxyz0123456789-";
+const SYMBOLS: usize = ALPHABET.len() + 1;
+
+// Logistic weights of the DGA model, each applied to the feature's distance
+// from a typical benign label
+const DGA_BIAS: f64 = -3.5;
+const DGA_BIGRAM_WEIGHT: f64 = -1.6;
+const DGA_BIGRAM_BASELINE: f64 = -2.6;
+const DGA_ENTROPY_WEIGHT: f64 = 0.9;
+const DGA_ENTROPY_BASELINE: f64 = 3.0;
+const DGA_CONSONANT_WEIGHT: f64 = 2.0;
+const DGA_CONSONANT_BASELINE: f64 = 0.6;
+const DGA_CONSONANT_RUN_WEIGHT: f64 = 0.35;
+const DGA_CONSONANT_RUN_BASELINE: usize = 3;
+const DGA_DIGIT_WEIGHT: f64 = 3.0;
+const DGA_LENGTH_WEIGHT: f64 = 0.06;
+const DGA_LENGTH_BASELINE: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DnsAnalyticsConfig {
+    /// Tunneling score, 0–1, at which a domain is reported
+    #[serde(default = "default_threshold")]
+    pub tunnel_threshold: f64,
+    /// DGA probability, 0–1, at which a domain is reported
+    #[serde(default = "default_threshold")]
+    pub dga_threshold: f64,
+    /// Queries under one domain before its volume and rate count
+    #[serde(default = "default_min_tunnel_queries")]
+    pub min_tunnel_queries: usize,
+    /// Shorter labels carry too little to judge
+    #[serde(default = "default_min_dga_label_len")]
+    pub min_dga_label_len: usize,
+    #[serde(default = "default_max_dga_indicators")]
+    pub max_dga_indicators: usize,
+}
+
+fn default_threshold() -> f64 {
+    0.5
+}
+
+fn default_min_tunnel_queries() -> usize {
+    10
+}
+
+fn default_min_dga_label_len() -> usize {
+    7
+}
+
+fn default_max_dga_indicators() -> usize {
+    50
+}
+
+impl Default for DnsAnalyticsConfig {
+    fn default() -> Self {
+        Self {
+            tunnel_threshold: default_threshold(),
+            dga_threshold: default_threshold(),
+            min_tunnel_queries: default_min_tunnel_queries(),
+            min_dga_label_len: default_min_dga_label_len(),
+            max_dga_indicators: default_max_dga_indicators(),
+        }
+    }
+}
+
+impl DnsAnalyticsConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in [("tunnel_threshold", self.tunnel_threshold), ("dga_threshold", self.dga_threshold)] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(format!("{} must be between 0 and 1", name));
+            }
+        }
+        if self.min_tunnel_queries == 0 {
+            return Err("min_tunnel_queries must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct DnsAnalyticsState {
+    config: RwLock<DnsAnalyticsConfig>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DnsThreatKind {
+    Tunneling,
+    Dga,
+}
+
+impl DnsThreatKind {
+    fn category(&self) -> &'static str {
+        match self {
+            DnsThreatKind::Tunneling => "DNS tunneling",
+            DnsThreatKind::Dga => "DGA",
+        }
+    }
+}
+
+/// A domain one of the detectors reported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsDetection {
+    pub kind: DnsThreatKind,
+    /// Registrable domain for tunneling, the queried name for DGA
+    pub domain: String,
+    /// 0–1
+    pub score: f64,
+    pub query_count: usize,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub processes: Vec<String>,
+    pub evidence: Vec<IndicatorEvidence>,
+}
+
+impl DnsDetection {
+    fn covers(&self, query: &DNSQuery) -> bool {
+        match self.kind {
+            DnsThreatKind::Tunneling => registrable_domain(&normalize(&query.domain)).is_some_and(|(base, _)| base == self.domain),
+            DnsThreatKind::Dga => normalize(&query.domain) == self.domain,
+        }
+    }
+
+    pub fn to_indicator(&self) -> C2Indicator {
+        let span = (self.last_seen - self.first_seen).num_seconds().max(0);
+        let (indicator_type, description) = match self.kind {
+            DnsThreatKind::Tunneling => ("DnsTunnel", format!("Data carried in DNS queries under {}", self.domain)),
+            DnsThreatKind::Dga => ("DgaDomain", format!("{} looks algorithmically generated", self.domain)),
+        };
+        C2Indicator {
+            indicator_type: indicator_type.to_string(),
+            value: self.domain.clone(),
+            confidence: self.score,
+            description,
+            first_seen: self.first_seen,
+            communication_pattern: format!("{} DNS queries over {}s", self.query_count, span),
+            encryption_used: false,
+            protocol: "DNS".to_string(),
+            evidence: self.evidence.clone(),
+        }
+    }
+}
+
+fn normalize(domain: &str) -> String {
+    domain.trim().trim_end_matches('.').to_ascii_lowercase()
+}
+
+/// Registrable domain of `domain` and the subdomain in front of it
+fn registrable_domain(domain: &str) -> Option<(String, String)> {
+    let labels: Vec<&str> = domain.split('.').collect();
+    if labels.len() < 2 || labels.iter().any(|label| label.is_empty()) {
+        return None;
+    }
+    let suffix = labels[labels.len() - 2..].join(".");
+    let keep = if TWO_LABEL_SUFFIXES.contains(&suffix.as_str()) { 3 } else { 2 };
+    if labels.len() < keep {
+        return None;
+    }
+    let split = labels.len() - keep;
+    Some((labels[split..].join("."), labels[..split].join(".")))
+}
+
+/// Shannon entropy in bits per character
+pub fn shannon_entropy(text: &str) -> f64 {
+    let mut counts: BTreeMap<char, usize> = BTreeMap::new();
+    for c in text.chars() {
+        *counts.entry(c).or_default() += 1;
+    }
+    let total = text.chars().count() as f64;
+    counts.values().map(|&count| count as f64 / total).map(|p| -p * p.log2()).sum()
+}
+
+fn evidence(signal: &str, observed: f64, threshold: f64, detail: String) -> IndicatorEvidence {
+    IndicatorEvidence { signal: signal.to_string(), observed, threshold, detail }
+}
+
+fn span(queries: &[&DNSQuery]) -> (DateTime<Utc>, DateTime<Utc>) {
+    let first = queries.iter().map(|query| query.timestamp).min().unwrap_or_else(Utc::now);
+    let last = queries.iter().map(|query| query.timestamp).max().unwrap_or(first);
+    (first, last)
+}
+
+fn processes(queries: &[&DNSQuery]) -> Vec<String> {
+    queries.iter().map(|query| query.process_name.clone()).collect::<BTreeSet<_>>().into_iter().collect()
+}
+
+/// Score every registrable domain of the stream for tunneling
+pub fn detect_tunneling(config: &DnsAnalyticsConfig, queries: &[DNSQuery]) -> Vec<DnsDetection> {
+    let mut streams: BTreeMap<String, Vec<(&DNSQuery, String)>> = BTreeMap::new();
+    for query in queries {
+        if let Some((base, subdomain)) = registrable_domain(&normalize(&query.domain)) {
+            streams.entry(base).or_default().push((query, subdomain));
+        }
+    }
+
+    let mut detections = Vec::new();
+    for (base, stream) in streams {
+        let queries: Vec<&DNSQuery> = stream.iter().map(|(query, _)| *query).collect();
+        let total = queries.len();
+        let subdomains: HashSet<&str> = stream.iter().map(|(_, subdomain)| subdomain.as_str()).filter(|s| !s.is_empty()).collect();
+        if subdomains.is_empty() {
+            continue;
+        }
+        let payloads: Vec<String> = subdomains.iter().map(|subdomain| subdomain.replace('.', "")).collect();
+        let mean_entropy = payloads.iter().map(|payload| shannon_entropy(payload)).sum::<f64>() / payloads.len() as f64;
+        let mean_length = payloads.iter().map(String::len).sum::<usize>() as f64 / payloads.len() as f64;
+        let longest_label = subdomains.iter().flat_map(|subdomain| subdomain.split('.')).map(str::len).max().unwrap_or(0);
+        let unique_share = subdomains.len() as f64 / total as f64;
+        let (first_seen, last_seen) = span(&queries);
+        let minutes = ((last_seen - first_seen).num_seconds() as f64 / 60.0).max(1.0);
+        let rate = total as f64 / minutes;
+        let data_records = queries
+            .iter()
+            .filter(|query| DATA_RECORD_TYPES.iter().any(|kind| query.query_type.eq_ignore_ascii_case(kind)))
+            .count();
+        let data_share = data_records as f64 / total as f64;
+
+        let mut score = 0.0;
+        let mut found = Vec::new();
+        if mean_entropy >= TUNNEL_ENTROPY_BITS && mean_length >= TUNNEL_MIN_SUBDOMAIN_LEN {
+            score += 0.35;
+            found.push(evidence(
+                "subdomain_entropy",
+                mean_entropy,
+                TUNNEL_ENTROPY_BITS,
+                format!("Subdomains average {:.2} bits/char over {:.0} characters", mean_entropy, mean_length),
+            ));
+        }
+        if longest_label >= TUNNEL_LONG_LABEL {
+            score += 0.15;
+            found.push(evidence("long_label", longest_label as f64, TUNNEL_LONG_LABEL as f64, format!("A subdomain label is {} characters long", longest_label)));
+        }
+        if subdomains.len() >= config.min_tunnel_queries && unique_share >= TUNNEL_UNIQUE_SHARE {
+            score += 0.2;
+            found.push(evidence(
+                "unique_subdomains",
+                subdomains.len() as f64,
+                config.min_tunnel_queries as f64,
+                format!("{} distinct subdomains in {} queries", subdomains.len(), total),
+            ));
+        }
+        if total >= config.min_tunnel_queries && rate >= TUNNEL_QUERIES_PER_MINUTE {
+            score += 0.15;
+            found.push(evidence("query_rate", rate, TUNNEL_QUERIES_PER_MINUTE, format!("{:.1} queries per minute", rate)));
+        }
+        if total >= 5 && data_share >= TUNNEL_DATA_RECORD_SHARE {
+            score += 0.25;
+            found.push(evidence(
+                "data_record_types",
+                data_share,
+                TUNNEL_DATA_RECORD_SHARE,
+                format!("{} of {} queries ask for TXT or NULL records", data_records, total),
+            ));
+        }
+
+        let score = f64::min(score, 1.0);
+        if score >= config.tunnel_threshold && !found.is_empty() {
+            detections.push(DnsDetection {
+                kind: DnsThreatKind::Tunneling,
+                domain: base,
+                score,
+                query_count: total,
+                first_seen,
+                last_seen,
+                processes: processes(&queries),
+                evidence: found,
+            });
+        }
+    }
+    detections
+}
+
+fn symbol(c: char) -> usize {
+    ALPHABET.find(c).map_or(0, |index| index + 1)
+}
+
+/// Log-probabilities of each character following another, add-one smoothed
+fn bigram_model() -> &'static Vec<[f64; SYMBOLS]> {
+    static MODEL: OnceLock<Vec<[f64; SYMBOLS]>> = OnceLock::new();
+    MODEL.get_or_init(|| {
+        let mut counts = vec![[1.0f64; SYMBOLS]; SYMBOLS];
+        for word in BENIGN_CORPUS.split_whitespace() {
+            let symbols: Vec<usize> = std::iter::once(0).chain(word.chars().map(symbol)).chain(std::iter::once(0)).collect();
+            for pair in symbols.windows(2) {
+                counts[pair[0]][pair[1]] += 1.0;
+            }
+        }
+        counts
+            .into_iter()
+            .map(|row| {
+                let total: f64 = row.iter().sum();
+                row.map(|count| (count / total).ln())
+            })
+            .collect()
+    })
+}
+
+/// Mean log-probability per transition of `label` under the bigram model
+fn bigram_likelihood(label: &str) -> f64 {
+    let model = bigram_model();
+    let symbols: Vec<usize> = std::iter::once(0).chain(label.chars().map(symbol)).chain(std::iter::once(0)).collect();
+    let transitions = symbols.windows(2).map(|pair| model[pair[0]][pair[1]]);
+    transitions.sum::<f64>() / (symbols.len() - 1) as f64
+}
+
+/// Features of a hostname label the DGA model reads
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DgaFeatures {
+    pub length: usize,
+    pub entropy: f64,
+    pub consonant_ratio: f64,
+    pub longest_consonant_run: usize,
+    pub digit_ratio: f64,
+    pub bigram_likelihood: f64,
+}
+
+impl DgaFeatures {
+    pub fn of(label: &str) -> Self {
+        let letters = label.chars().filter(char::is_ascii_alphabetic).count();
+        let is_consonant = |c: char| c.is_ascii_alphabetic() && !"aeiou".contains(c);
+        let consonants = label.chars().filter(|&c| is_consonant(c)).count();
+        let longest_consonant_run = label
+            .chars()
+            .fold((0, 0), |(run, longest), c| {
+                let run = if is_consonant(c) { run + 1 } else { 0 };
+                (run, usize::max(longest, run))
+            })
+            .1;
+        Self {
+            length: label.len(),
+            entropy: shannon_entropy(label),
+            consonant_ratio: consonants as f64 / letters.max(1) as f64,
+            longest_consonant_run,
+            digit_ratio: label.chars().filter(char::is_ascii_digit).count() as f64 / label.len().max(1) as f64,
+            bigram_likelihood: bigram_likelihood(label),
+        }
+    }
+
+    /// Probability that the label was generated rather than chosen
+    pub fn probability(&self) -> f64 {
+        let z = DGA_BIAS
+            + DGA_BIGRAM_WEIGHT * (self.bigram_likelihood - DGA_BIGRAM_BASELINE)
+            + DGA_ENTROPY_WEIGHT * (self.entropy - DGA_ENTROPY_BASELINE)
+            + DGA_CONSONANT_WEIGHT * (self.consonant_ratio - DGA_CONSONANT_BASELINE)
+            + DGA_CONSONANT_RUN_WEIGHT * self.longest_consonant_run.saturating_sub(DGA_CONSONANT_RUN_BASELINE) as f64
+            + DGA_DIGIT_WEIGHT * self.digit_ratio
+            + DGA_LENGTH_WEIGHT * self.length.saturating_sub(DGA_LENGTH_BASELINE) as f64;
+        1.0 / (1.0 + (-z).exp())
+    }
+}
+
+/// Score the registrable label of every queried name for DGA
+pub fn detect_dga(config: &DnsAnalyticsConfig, queries: &[DNSQuery], skip: &HashSet<String>) -> Vec<DnsDetection> {
+    let mut names: BTreeMap<String, Vec<&DNSQuery>> = BTreeMap::new();
+    for query in queries {
+        names.entry(normalize(&query.domain)).or_default().push(query);
+    }
+
+    let mut candidates = Vec::new();
+    for (domain, queries) in names {
+        let Some((base, _)) = registrable_domain(&domain) else { continue };
+        let label = base.split('.').next().unwrap_or_default();
+        if skip.contains(&base) || label.len() < config.min_dga_label_len || label.starts_with("xn--") || base.ends_with(".arpa") || base.ends_with(".local") {
+            continue;
+        }
+        let features = DgaFeatures::of(label);
+        let probability = features.probability();
+        if probability < config.dga_threshold {
+            continue;
+        }
+        let nxdomain = queries.iter().any(|query| query.response_code.eq_ignore_ascii_case("NXDOMAIN"));
+        let (first_seen, last_seen) = span(&queries);
+        let detail = format!(
+            "Label {} has bigram likelihood {:.2}, entropy {:.2}, consonant ratio {:.2}, consonant run {}, digit ratio {:.2}",
+            label, features.bigram_likelihood, features.entropy, features.consonant_ratio, features.longest_consonant_run, features.digit_ratio
+        );
+        candidates.push((
+            nxdomain,
+            DnsDetection {
+                kind: DnsThreatKind::Dga,
+                domain,
+                score: probability,
+                query_count: queries.len(),
+                first_seen,
+                last_seen,
+                processes: processes(&queries),
+                evidence: vec![evidence("dga_model", probability, config.dga_threshold, detail)],
+            },
+        ));
+    }
+
+    let unresolved = candidates.iter().filter(|(nxdomain, _)| *nxdomain).count();
+    let mut detections: Vec<DnsDetection> = candidates
+        .into_iter()
+        .map(|(nxdomain, mut detection)| {
+            if nxdomain && unresolved >= NXDOMAIN_BURST {
+                detection.score = f64::min(detection.score + NXDOMAIN_BONUS, 1.0);
+                detection.evidence.push(evidence(
+                    "nxdomain_burst",
+                    unresolved as f64,
+                    NXDOMAIN_BURST as f64,
+                    format!("{} generated-looking names did not resolve", unresolved),
+                ));
+            }
+            detection
+        })
+        .collect();
+    detections.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.domain.cmp(&b.domain)));
+    detections.truncate(config.max_dga_indicators);
+    detections
+}
+
+/// Tunneling findings, then DGA findings for domains not already reported as tunnels
+pub fn detect(config: &DnsAnalyticsConfig, queries: &[DNSQuery]) -> Vec<DnsDetection> {
+    let mut detections = detect_tunneling(config, queries);
+    let tunnels: HashSet<String> = detections.iter().map(|detection| detection.domain.clone()).collect();
+    detections.extend(detect_dga(config, queries, &tunnels));
+    detections
+}
+
+/// Add the findings on an analysis's DNS queries to its C2 indicators,
+/// suspicious domains and network score
+pub fn apply(config: &DnsAnalyticsConfig, network: &mut NetworkAnalysis) {
+    let detections = detect(config, &network.dns_queries);
+    for detection in &detections {
+        for query in network.dns_queries.iter_mut().filter(|query| detection.covers(query)) {
+            query.is_suspicious = true;
+            query.threat_category.get_or_insert_with(|| detection.kind.category().to_string());
+        }
+        if !network.suspicious_domains.contains(&detection.domain) {
+            network.suspicious_domains.push(detection.domain.clone());
+        }
+        let indicator = detection.to_indicator();
+        if !network.c2_indicators.iter().any(|c2| c2.indicator_type == indicator.indicator_type && c2.value == indicator.value) {
+            network.c2_indicators.push(indicator);
+        }
+        network.network_score = f64::max(network.network_score, detection.score * 10.0);
+    }
+}
+
+impl SandboxCore {
+    pub fn configure_dns_analytics(&self, config: DnsAnalyticsConfig) -> Result<(), String> {
+        config.validate()?;
+        *self.dns_analytics.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+        Ok(())
+    }
+
+    pub fn dns_analytics_config(&self) -> DnsAnalyticsConfig {
+        self.dns_analytics.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Run both detectors over DNS queries gathered outside the sandbox
+    pub fn analyze_dns_queries(&self, queries: &[DNSQuery]) -> Vec<DnsDetection> {
+        detect(&self.dns_analytics_config(), queries)
+    }
+
+    pub(crate) fn apply_dns_analytics(&self, network: &mut NetworkAnalysis) {
+        apply(&self.dns_analytics_config(), network);
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// Set the thresholds of the DNS tunneling and DGA detectors
+    #[napi]
+    pub fn configure_dns_analytics(&self, config_json: String, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize_platform(auth_token, "dns_analytics")?;
+        let config: DnsAnalyticsConfig = serde_json::from_str(&config_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse DNS analytics config: {}", e)))?;
+
+        let result = self.inner.configure_dns_analytics(config);
+        self.audit.record(&actor, "configure_dns_analytics", "dns_analytics", serde_json::json!({ "config": config_json }), result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to configure DNS analytics: {}", e)))
+    }
+
+    #[napi]
+    pub fn get_dns_analytics_config(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.dns_analytics_config())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize DNS analytics config: {}", e)))
+    }
+
+    /// Run the tunneling and DGA detectors over a JSON array of `DNSQuery`
+    /// records, e.g. resolver logs; only reported domains are returned
+    #[napi]
+    pub fn analyze_dns_queries(&self, queries_json: String) -> napi::Result<String> {
+        let queries: Vec<DNSQuery> = serde_json::from_str(&queries_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse DNS queries: {}", e)))?;
+        serde_json::to_string(&self.inner.analyze_dns_queries(&queries))
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize DNS detections: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(domain: &str, query_type: &str, response_code: &str, seconds: i64) -> DNSQuery {
+        DNSQuery {
+            query_id: format!("q-{}", domain),
+            domain: domain.to_string(),
+            query_type: query_type.to_string(),
+            response: Vec::new(),
+            response_code: response_code.to_string(),
+            timestamp: DateTime::<Utc>::from_timestamp(1_700_000_000 + seconds, 0).unwrap(),
+            process_name: "sample.exe".to_string(),
+            process_id: 1234,
+            is_suspicious: false,
+            threat_category: None,
+        }
+    }
+
+    #[test]
+    fn tunnels_and_generated_domains_become_c2_indicators() {
+        let mut queries: Vec<DNSQuery> = (0..40)
+            .map(|i| {
+                let chunk = format!("{:x}{:x}", pseudo_random(i), pseudo_random(i + 1000));
+                query(&format!("{}.{}.t.exfil-relay.com", &chunk[..48], &chunk[48..]), "TXT", "NOERROR", i as i64)
+            })
+            .collect();
+        for domain in ["www.google.com", "login.microsoftonline.com", "cdn.cloudflare.com", "weather.co.uk"] {
+            queries.push(query(domain, "A", "NOERROR", 50));
+        }
+        for domain in ["xjwqkzptlmv.net", "qwhfjdklzmxn.org", "ydxqtrnvbplw.info"] {
+            queries.push(query(domain, "A", "NXDOMAIN", 60));
+        }
+
+        let mut network = NetworkAnalysis { dns_queries: queries, ..Default::default() };
+        apply(&DnsAnalyticsConfig::default(), &mut network);
+
+        let tunnel = network.c2_indicators.iter().find(|c2| c2.indicator_type == "DnsTunnel").unwrap();
+        assert_eq!(tunnel.value, "exfil-relay.com");
+        let signals: Vec<&str> = tunnel.evidence.iter().map(|e| e.signal.as_str()).collect();
+        assert_eq!(signals, ["subdomain_entropy", "long_label", "unique_subdomains", "query_rate", "data_record_types"]);
+
+        let mut generated: Vec<&str> = network.c2_indicators.iter().filter(|c2| c2.indicator_type == "DgaDomain").map(|c2| c2.value.as_str()).collect();
+        generated.sort();
+        assert_eq!(generated, ["qwhfjdklzmxn.org", "xjwqkzptlmv.net", "ydxqtrnvbplw.info"]);
+        assert!(network.c2_indicators.iter().filter(|c2| c2.indicator_type == "DgaDomain").all(|c2| c2.evidence.len() == 2));
+        assert!(network.dns_queries.iter().filter(|q| q.domain.ends_with("google.com") || q.domain.ends_with(".co.uk")).all(|q| !q.is_suspicious));
+        assert!(network.network_score >= 9.0);
+    }
+
+    /// Deterministic stand-in for encoded exfiltrated data, always 32 hex digits
+    fn pseudo_random(i: u64) -> u128 {
+        let mut x = (i as u128 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15_F39C_C060_5CED_C835);
+        x ^= x >> 61;
+        x.wrapping_mul(0xBF58_476D_1CE4_E5B9_94D0_49BB_1331_11EB) | (1 << 127)
+    }
+}
@@ -0,0 +1,889 @@
+//! Email parsing
+//!
+//! Parses RFC 5322 messages (`.eml`) and Outlook messages (`.msg`, an OLE2
+//! compound file) into one shape: headers, sender authentication results
+//! (SPF, DKIM, DMARC), bodies, the URLs they link to, and attachments with
+//! their content. Only what phishing triage needs is decoded; unknown parts
+//! are kept as attachments rather than dropped.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+use std::sync::OnceLock;
+
+/// Largest message accepted for parsing
+pub const MAX_EMAIL_BYTES: usize = 50 * 1024 * 1024;
+/// MIME nesting below this depth is not descended into
+const MAX_MIME_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailFormat {
+    Eml,
+    Msg,
+}
+
+/// Outcome of one sender authentication mechanism
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthResult {
+    Pass,
+    Fail,
+    SoftFail,
+    Neutral,
+    TempError,
+    PermError,
+    #[default]
+    None,
+}
+
+impl AuthResult {
+    fn parse(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "pass" => Self::Pass,
+            "fail" | "hardfail" => Self::Fail,
+            "softfail" => Self::SoftFail,
+            "neutral" | "policy" => Self::Neutral,
+            "temperror" => Self::TempError,
+            "permerror" => Self::PermError,
+            _ => Self::None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuthenticationResults {
+    pub spf: AuthResult,
+    pub dkim: AuthResult,
+    pub dmarc: AuthResult,
+    /// `Authentication-Results` and `Received-SPF` header values as received
+    pub raw: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailAttachment {
+    pub filename: String,
+    pub content_type: String,
+    pub size: u64,
+    pub sha256: String,
+    /// Decoded content; not serialized
+    #[serde(skip)]
+    pub data: Vec<u8>,
+}
+
+impl EmailAttachment {
+    fn new(filename: String, content_type: String, data: Vec<u8>) -> Self {
+        Self {
+            filename,
+            content_type,
+            size: data.len() as u64,
+            sha256: format!("{:x}", Sha256::digest(&data)),
+            data,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParsedEmail {
+    pub format: EmailFormat,
+    pub message_id: Option<String>,
+    pub subject: String,
+    pub from: Option<String>,
+    pub reply_to: Option<String>,
+    pub return_path: Option<String>,
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub date: Option<String>,
+    /// Every header in order, unfolded and decoded
+    pub headers: Vec<(String, String)>,
+    pub received: Vec<String>,
+    pub authentication: AuthenticationResults,
+    pub text_body: Option<String>,
+    pub html_body: Option<String>,
+    pub urls: Vec<String>,
+    pub attachments: Vec<EmailAttachment>,
+}
+
+impl ParsedEmail {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+}
+
+/// Bare address of a header value such as `"Jane" <jane@example.com>`
+pub fn address_of(value: &str) -> Option<String> {
+    let address = match (value.rfind('<'), value.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &value[start + 1..end],
+        _ => value,
+    };
+    let address = address.trim().trim_matches('"').to_ascii_lowercase();
+    address.contains('@').then_some(address)
+}
+
+/// Domain of an address or header value
+pub fn domain_of(value: &str) -> Option<String> {
+    address_of(value).and_then(|address| address.rsplit_once('@').map(|(_, domain)| domain.to_string()))
+}
+
+/// Display name of a header value, without quotes
+pub fn display_name_of(value: &str) -> Option<String> {
+    let start = value.rfind('<')?;
+    let name = value[..start].trim().trim_matches('"').trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Parse an `.eml` or `.msg` message, detected from its content
+pub fn parse_email(data: &[u8]) -> Result<ParsedEmail, String> {
+    if data.len() > MAX_EMAIL_BYTES {
+        return Err(format!("Email exceeds {} bytes", MAX_EMAIL_BYTES));
+    }
+    if data.starts_with(&cfb::MAGIC) {
+        parse_msg(data)
+    } else {
+        parse_eml(data)
+    }
+}
+
+pub fn parse_eml(data: &[u8]) -> Result<ParsedEmail, String> {
+    let (header_block, body) = split_head(data);
+    let headers = parse_headers(&String::from_utf8_lossy(header_block));
+    if headers.is_empty() {
+        return Err("Message has no headers".to_string());
+    }
+    let mut email = from_headers(EmailFormat::Eml, headers.clone());
+    walk_part(&headers, body, 0, &mut email);
+    finish(&mut email);
+    Ok(email)
+}
+
+pub fn parse_msg(data: &[u8]) -> Result<ParsedEmail, String> {
+    let file = cfb::CompoundFile::parse(data)?;
+    let root = file.root();
+    let property = |storage: usize, id: u16| -> Option<String> { msg_string(&file, storage, id) };
+
+    // Transport headers carry the authentication results the MAPI properties lack
+    let headers = property(root, 0x007D).map(|raw| parse_headers(&raw)).unwrap_or_default();
+    let mut email = from_headers(EmailFormat::Msg, headers);
+    if let Some(subject) = property(root, 0x0037) {
+        email.subject = subject;
+    }
+    if email.from.is_none() {
+        let address = property(root, 0x5D01).or_else(|| property(root, 0x0C1F));
+        email.from = match (property(root, 0x0C1A), address) {
+            (Some(name), Some(address)) => Some(format!("\"{}\" <{}>", name, address)),
+            (None, Some(address)) => Some(address),
+            _ => None,
+        };
+    }
+    if email.message_id.is_none() {
+        email.message_id = property(root, 0x1035);
+    }
+    email.text_body = property(root, 0x1000);
+    email.html_body = property(root, 0x1013)
+        .or_else(|| msg_binary(&file, root, 0x1013).map(|html| String::from_utf8_lossy(&html).into_owned()));
+
+    for storage in file.children(root).into_iter().filter(|&i| file.entry(i).name.starts_with("__attach_version1.0_")) {
+        let Some(data) = msg_binary(&file, storage, 0x3701) else {
+            continue;
+        };
+        let filename = property(storage, 0x3707)
+            .or_else(|| property(storage, 0x3704))
+            .unwrap_or_else(|| "attachment.bin".to_string());
+        let content_type = property(storage, 0x370E).unwrap_or_else(|| "application/octet-stream".to_string());
+        email.attachments.push(EmailAttachment::new(filename, content_type, data));
+    }
+    finish(&mut email);
+    Ok(email)
+}
+
+fn msg_stream(file: &cfb::CompoundFile, storage: usize, id: u16, kind: &str) -> Option<Vec<u8>> {
+    let name = format!("__substg1.0_{:04X}{}", id, kind);
+    let entry = file.children(storage).into_iter().find(|&i| file.entry(i).name.eq_ignore_ascii_case(&name))?;
+    file.stream(entry).ok()
+}
+
+fn msg_string(file: &cfb::CompoundFile, storage: usize, id: u16) -> Option<String> {
+    if let Some(raw) = msg_stream(file, storage, id, "001F") {
+        let units: Vec<u16> = raw.chunks_exact(2).map(|pair| u16::from_le_bytes([pair[0], pair[1]])).collect();
+        return Some(String::from_utf16_lossy(&units).trim_end_matches('\0').to_string());
+    }
+    msg_stream(file, storage, id, "001E").map(|raw| String::from_utf8_lossy(&raw).trim_end_matches('\0').to_string())
+}
+
+fn msg_binary(file: &cfb::CompoundFile, storage: usize, id: u16) -> Option<Vec<u8>> {
+    msg_stream(file, storage, id, "0102")
+}
+
+fn split_head(data: &[u8]) -> (&[u8], &[u8]) {
+    for (i, window) in data.windows(2).enumerate() {
+        if window == b"\n\n" {
+            return (&data[..i], &data[i + 2..]);
+        }
+        if data[i..].starts_with(b"\r\n\r\n") {
+            return (&data[..i], &data[i + 4..]);
+        }
+    }
+    (data, &[])
+}
+
+/// Unfold and split a header block; values are RFC 2047 decoded
+pub fn parse_headers(block: &str) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in block.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(line.trim());
+            }
+        } else if let Some((name, value)) = line.split_once(':') {
+            if !name.is_empty() && !name.contains(' ') {
+                headers.push((name.to_string(), value.trim().to_string()));
+            }
+        }
+    }
+    headers.into_iter().map(|(name, value)| (name, decode_words(&value))).collect()
+}
+
+fn from_headers(format: EmailFormat, headers: Vec<(String, String)>) -> ParsedEmail {
+    let get = |name: &str| headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.clone());
+    let all = |name: &str| -> Vec<String> {
+        headers.iter().filter(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.clone()).collect()
+    };
+    let recipients = |name: &str| -> Vec<String> {
+        all(name).iter().flat_map(|value| value.split(',')).filter_map(address_of).collect()
+    };
+
+    let mut authentication = AuthenticationResults::default();
+    for value in all("Authentication-Results").iter().chain(&all("ARC-Authentication-Results")) {
+        authentication.raw.push(value.clone());
+        for (method, slot) in [("spf", &mut authentication.spf), ("dkim", &mut authentication.dkim), ("dmarc", &mut authentication.dmarc)] {
+            if *slot == AuthResult::None {
+                if let Some(result) = method_result(value, method) {
+                    *slot = result;
+                }
+            }
+        }
+    }
+    if authentication.spf == AuthResult::None {
+        if let Some(value) = get("Received-SPF") {
+            authentication.spf = AuthResult::parse(value.split_whitespace().next().unwrap_or_default());
+            authentication.raw.push(value);
+        }
+    }
+
+    ParsedEmail {
+        format,
+        message_id: get("Message-ID").map(|id| id.trim_matches(['<', '>']).to_string()),
+        subject: get("Subject").unwrap_or_default(),
+        from: get("From"),
+        reply_to: get("Reply-To"),
+        return_path: get("Return-Path"),
+        to: recipients("To"),
+        cc: recipients("Cc"),
+        date: get("Date"),
+        received: all("Received"),
+        authentication,
+        headers,
+        text_body: None,
+        html_body: None,
+        urls: Vec::new(),
+        attachments: Vec::new(),
+    }
+}
+
+/// Result of `method` in an `Authentication-Results` value, e.g. `dkim=pass`
+fn method_result(value: &str, method: &str) -> Option<AuthResult> {
+    value.split(';').find_map(|clause| {
+        let (name, rest) = clause.trim().split_once('=')?;
+        name.trim().eq_ignore_ascii_case(method).then(|| AuthResult::parse(rest.split_whitespace().next().unwrap_or_default()))
+    })
+}
+
+/// Main value and parameters of a structured header such as Content-Type
+fn header_params(value: &str) -> (String, Vec<(String, String)>) {
+    let mut parts = value.split(';');
+    let main = parts.next().unwrap_or_default().trim().to_ascii_lowercase();
+    let params = parts
+        .filter_map(|part| {
+            let (key, value) = part.split_once('=')?;
+            Some((key.trim().trim_end_matches('*').to_ascii_lowercase(), value.trim().trim_matches('"').to_string()))
+        })
+        .collect();
+    (main, params)
+}
+
+fn param<'a>(params: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    params.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+}
+
+fn walk_part(headers: &[(String, String)], body: &[u8], depth: usize, email: &mut ParsedEmail) {
+    let get = |name: &str| headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str());
+    let (content_type, type_params) = header_params(get("Content-Type").unwrap_or("text/plain"));
+    let (disposition, disposition_params) = header_params(get("Content-Disposition").unwrap_or_default());
+
+    if content_type.starts_with("multipart/") && depth < MAX_MIME_DEPTH {
+        if let Some(boundary) = param(&type_params, "boundary") {
+            for part in split_multipart(body, boundary) {
+                let (head, content) = split_head(part);
+                walk_part(&parse_headers(&String::from_utf8_lossy(head)), content, depth + 1, email);
+            }
+            return;
+        }
+    }
+
+    let data = match get("Content-Transfer-Encoding").map(|e| e.trim().to_ascii_lowercase()).as_deref() {
+        Some("base64") => decode_base64(body),
+        Some("quoted-printable") => decode_quoted_printable(body),
+        _ => body.to_vec(),
+    };
+    let filename = param(&disposition_params, "filename")
+        .or_else(|| param(&type_params, "name"))
+        .map(|name| decode_extended(&decode_words(name)));
+    let inline_text = matches!(content_type.as_str(), "text/plain" | "text/html") && disposition != "attachment" && filename.is_none();
+
+    if inline_text {
+        let text = String::from_utf8_lossy(&data).into_owned();
+        let slot = if content_type == "text/html" { &mut email.html_body } else { &mut email.text_body };
+        match slot {
+            Some(existing) => {
+                existing.push('\n');
+                existing.push_str(&text);
+            }
+            None => *slot = Some(text),
+        }
+    } else if !data.is_empty() {
+        let extension = if content_type == "message/rfc822" { "eml" } else { "bin" };
+        let filename = filename.unwrap_or_else(|| format!("part{}.{}", email.attachments.len() + 1, extension));
+        email.attachments.push(EmailAttachment::new(filename, content_type, data));
+    }
+}
+
+fn split_multipart<'a>(body: &'a [u8], boundary: &str) -> Vec<&'a [u8]> {
+    let delimiter = format!("--{}", boundary);
+    let text = body;
+    let mut parts = Vec::new();
+    let mut starts = Vec::new();
+    let mut line_start = 0;
+    for i in 0..=text.len() {
+        if i == text.len() || text[i] == b'\n' {
+            let line = &text[line_start..i];
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+            if line.starts_with(delimiter.as_bytes()) {
+                let closing = line[delimiter.len()..].starts_with(b"--");
+                starts.push((line_start, (i + 1).min(text.len()), closing));
+            }
+            line_start = i + 1;
+        }
+    }
+    for pair in starts.windows(2) {
+        let (_, content_start, closing) = pair[0];
+        if closing {
+            break;
+        }
+        let end = pair[1].0;
+        let mut part = &text[content_start..end.max(content_start)];
+        part = part.strip_suffix(b"\n").unwrap_or(part);
+        part = part.strip_suffix(b"\r").unwrap_or(part);
+        parts.push(part);
+    }
+    parts
+}
+
+/// Lenient base64: whitespace and invalid characters are skipped
+pub fn decode_base64(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for &byte in input {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            b'=' => break,
+            _ => continue,
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    out
+}
+
+pub fn decode_quoted_printable(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        if input[i] == b'=' {
+            if input[i + 1..].starts_with(b"\r\n") {
+                i += 3;
+                continue;
+            }
+            if input[i + 1..].starts_with(b"\n") {
+                i += 2;
+                continue;
+            }
+            if let Some(byte) = input.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(input[i]);
+        i += 1;
+    }
+    out
+}
+
+/// Decode RFC 2047 encoded words (`=?utf-8?B?...?=`)
+pub fn decode_words(value: &str) -> String {
+    static WORD: OnceLock<Regex> = OnceLock::new();
+    static ADJACENT: OnceLock<Regex> = OnceLock::new();
+    let word = WORD.get_or_init(|| Regex::new(r"=\?([^?]+)\?([bBqQ])\?([^?]*)\?=").unwrap());
+    let adjacent = ADJACENT.get_or_init(|| Regex::new(r"(=\?[^?]+\?[bBqQ]\?[^?]*\?=)\s+(=\?)").unwrap());
+    if !value.contains("=?") {
+        return value.to_string();
+    }
+    // Whitespace between adjacent encoded words is not part of the text
+    let mut value = value.to_string();
+    while adjacent.is_match(&value) {
+        value = adjacent.replace_all(&value, "$1$2").into_owned();
+    }
+    word.replace_all(&value, |caps: &regex::Captures| {
+        let bytes = if caps[2].eq_ignore_ascii_case("b") {
+            decode_base64(caps[3].as_bytes())
+        } else {
+            decode_quoted_printable(caps[3].replace('_', " ").as_bytes())
+        };
+        if caps[1].eq_ignore_ascii_case("iso-8859-1") || caps[1].eq_ignore_ascii_case("latin1") {
+            bytes.iter().map(|&b| b as char).collect()
+        } else {
+            String::from_utf8_lossy(&bytes).into_owned()
+        }
+    })
+    .into_owned()
+}
+
+/// Decode an RFC 2231 value such as `utf-8''invoice%20.pdf`
+fn decode_extended(value: &str) -> String {
+    let Some((_, encoded)) = value.split_once("''") else {
+        return value.to_string();
+    };
+    let mut out = Vec::new();
+    let bytes = encoded.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(byte) = bytes.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// URLs linked from text, deduplicated in order of appearance
+pub fn extract_urls(text: &str) -> Vec<String> {
+    static URL: OnceLock<Regex> = OnceLock::new();
+    let url = URL.get_or_init(|| Regex::new(r#"(?i)\b(?:https?|ftp)://[^\s"'<>()\[\]{}]+"#).unwrap());
+    let mut seen = BTreeSet::new();
+    url.find_iter(text)
+        .map(|m| m.as_str().replace("&amp;", "&").trim_end_matches(['.', ',', ';', ':', '!', '?']).to_string())
+        .filter(|u| seen.insert(u.clone()))
+        .collect()
+}
+
+/// Host of a URL, lowercased and without port or credentials
+pub fn url_host(url: &str) -> Option<String> {
+    let rest = url.split_once("://")?.1;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+    let host = if host.starts_with('[') { host.split(']').next()?.trim_start_matches('[') } else { host.split(':').next()? };
+    (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+fn finish(email: &mut ParsedEmail) {
+    let text = [email.text_body.as_deref(), email.html_body.as_deref()].into_iter().flatten().collect::<Vec<_>>().join("\n");
+    email.urls = extract_urls(&text);
+}
+
+/// Minimal reader for OLE2 compound files, enough to read Outlook messages
+mod cfb {
+    pub const MAGIC: [u8; 8] = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+    const END_OF_CHAIN: u32 = 0xFFFF_FFFE;
+    const NO_STREAM: u32 = 0xFFFF_FFFF;
+    const MAX_SECTORS: usize = 1 << 20;
+
+    pub struct Entry {
+        pub name: String,
+        kind: u8,
+        left: u32,
+        right: u32,
+        child: u32,
+        start: u32,
+        size: u64,
+    }
+
+    pub struct CompoundFile<'a> {
+        data: &'a [u8],
+        sector_size: usize,
+        mini_cutoff: u64,
+        fat: Vec<u32>,
+        mini_fat: Vec<u32>,
+        mini_stream: Vec<u8>,
+        entries: Vec<Entry>,
+    }
+
+    fn u16_at(data: &[u8], offset: usize) -> Result<u16, String> {
+        data.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]])).ok_or_else(|| "Truncated compound file".to_string())
+    }
+
+    fn u32_at(data: &[u8], offset: usize) -> Result<u32, String> {
+        data.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).ok_or_else(|| "Truncated compound file".to_string())
+    }
+
+    impl<'a> CompoundFile<'a> {
+        pub fn parse(data: &'a [u8]) -> Result<Self, String> {
+            if !data.starts_with(&MAGIC) || data.len() < 512 {
+                return Err("Not a compound file".to_string());
+            }
+            let shift = u16_at(data, 0x1E)?;
+            if !(7..=16).contains(&shift) {
+                return Err(format!("Unsupported sector shift {}", shift));
+            }
+            let mut file = Self {
+                data,
+                sector_size: 1 << shift,
+                mini_cutoff: u32_at(data, 0x38)? as u64,
+                fat: Vec::new(),
+                mini_fat: Vec::new(),
+                mini_stream: Vec::new(),
+                entries: Vec::new(),
+            };
+
+            // The FAT sectors are listed in the header, then in the DIFAT chain
+            let mut fat_sectors: Vec<u32> = (0..109).map(|i| u32_at(data, 0x4C + i * 4)).collect::<Result<_, _>>()?;
+            let mut difat = u32_at(data, 0x44)?;
+            let per_difat = file.sector_size / 4 - 1;
+            let mut guard = 0;
+            while difat < END_OF_CHAIN && guard < MAX_SECTORS {
+                let sector = file.sector(difat)?;
+                fat_sectors.extend((0..per_difat).map(|i| u32_at(sector, i * 4)).collect::<Result<Vec<_>, _>>()?);
+                difat = u32_at(sector, per_difat * 4)?;
+                guard += 1;
+            }
+            for id in fat_sectors.into_iter().filter(|&id| id < END_OF_CHAIN - 3) {
+                let sector = file.sector(id)?;
+                file.fat.extend((0..file.sector_size / 4).map(|i| u32_at(sector, i * 4)).collect::<Result<Vec<_>, _>>()?);
+            }
+
+            let directory = file.chain(u32_at(data, 0x30)?)?;
+            for raw in directory.chunks_exact(128) {
+                let name_len = (u16_at(raw, 0x40)? as usize).min(64);
+                let units: Vec<u16> = raw[..name_len].chunks_exact(2).map(|b| u16::from_le_bytes([b[0], b[1]])).collect();
+                file.entries.push(Entry {
+                    name: String::from_utf16_lossy(&units).trim_end_matches('\0').to_string(),
+                    kind: raw[0x42],
+                    left: u32_at(raw, 0x44)?,
+                    right: u32_at(raw, 0x48)?,
+                    child: u32_at(raw, 0x4C)?,
+                    start: u32_at(raw, 0x74)?,
+                    size: u32_at(raw, 0x78)? as u64,
+                });
+            }
+            let root = file.entries.first().ok_or_else(|| "Compound file has no root entry".to_string())?;
+            let (root_start, root_size) = (root.start, root.size);
+
+            let mini_fat = file.chain(u32_at(data, 0x3C)?)?;
+            file.mini_fat = mini_fat.chunks_exact(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+            let mut mini_stream = file.chain(root_start)?;
+            mini_stream.truncate(root_size as usize);
+            file.mini_stream = mini_stream;
+            Ok(file)
+        }
+
+        fn sector(&self, id: u32) -> Result<&'a [u8], String> {
+            let offset = (id as usize + 1) * self.sector_size;
+            self.data.get(offset..offset + self.sector_size).ok_or_else(|| format!("Sector {} is out of range", id))
+        }
+
+        fn chain(&self, start: u32) -> Result<Vec<u8>, String> {
+            let mut out = Vec::new();
+            let mut id = start;
+            let mut guard = 0;
+            while id < END_OF_CHAIN - 3 {
+                out.extend_from_slice(self.sector(id)?);
+                id = *self.fat.get(id as usize).ok_or_else(|| format!("Sector {} is not in the FAT", id))?;
+                guard += 1;
+                if guard > MAX_SECTORS {
+                    return Err("Sector chain loops".to_string());
+                }
+            }
+            Ok(out)
+        }
+
+        fn mini_chain(&self, start: u32, size: u64) -> Result<Vec<u8>, String> {
+            let mut out = Vec::new();
+            let mut id = start;
+            let mut guard = 0;
+            while id < END_OF_CHAIN - 3 && (out.len() as u64) < size {
+                let offset = id as usize * 64;
+                out.extend_from_slice(self.mini_stream.get(offset..offset + 64).ok_or_else(|| format!("Mini sector {} is out of range", id))?);
+                id = *self.mini_fat.get(id as usize).ok_or_else(|| format!("Mini sector {} is not in the mini FAT", id))?;
+                guard += 1;
+                if guard > MAX_SECTORS {
+                    return Err("Mini sector chain loops".to_string());
+                }
+            }
+            Ok(out)
+        }
+
+        pub fn root(&self) -> usize {
+            0
+        }
+
+        pub fn entry(&self, index: usize) -> &Entry {
+            &self.entries[index]
+        }
+
+        /// Entries directly inside a storage
+        pub fn children(&self, storage: usize) -> Vec<usize> {
+            let mut found = Vec::new();
+            let mut stack = vec![self.entries[storage].child];
+            while let Some(id) = stack.pop() {
+                if id == NO_STREAM || id as usize >= self.entries.len() || found.contains(&(id as usize)) {
+                    continue;
+                }
+                let entry = &self.entries[id as usize];
+                found.push(id as usize);
+                stack.push(entry.left);
+                stack.push(entry.right);
+            }
+            found
+        }
+
+        pub fn stream(&self, index: usize) -> Result<Vec<u8>, String> {
+            let entry = &self.entries[index];
+            if entry.kind != 2 {
+                return Err(format!("{} is not a stream", entry.name));
+            }
+            let mut data = if entry.size < self.mini_cutoff {
+                self.mini_chain(entry.start, entry.size)?
+            } else {
+                self.chain(entry.start)?
+            };
+            data.truncate(entry.size as usize);
+            Ok(data)
+        }
+    }
+
+    /// Named storage and its streams
+    #[cfg(test)]
+    pub type Storage<'a> = (&'a str, &'a [(&'a str, &'a [u8])]);
+
+    /// Compound file writer for tests; every stream goes in regular sectors
+    #[cfg(test)]
+    pub fn build(storages: &[Storage]) -> Vec<u8> {
+        const SECTOR: usize = 512;
+        struct Node {
+            name: String,
+            kind: u8,
+            child: u32,
+            right: u32,
+            start: u32,
+            size: u32,
+        }
+        let mut nodes = vec![Node { name: "Root Entry".to_string(), kind: 5, child: NO_STREAM, right: NO_STREAM, start: END_OF_CHAIN, size: 0 }];
+        let mut payload: Vec<u8> = Vec::new();
+        let mut fat_chains: Vec<(u32, u32)> = Vec::new();
+        let mut add_stream = |payload: &mut Vec<u8>, data: &[u8]| -> u32 {
+            let start = (payload.len() / SECTOR) as u32;
+            payload.extend_from_slice(data);
+            payload.resize(payload.len().div_ceil(SECTOR).max(start as usize + 1) * SECTOR, 0);
+            let count = (payload.len() / SECTOR) as u32 - start;
+            fat_chains.push((start, count));
+            start
+        };
+        let link = |nodes: &mut Vec<Node>, parent: usize, node: Node| {
+            let index = nodes.len() as u32;
+            nodes.push(node);
+            let previous = nodes[parent].child;
+            nodes[index as usize].right = previous;
+            nodes[parent].child = index;
+        };
+        for (storage, streams) in storages {
+            let parent = if storage.is_empty() {
+                0
+            } else {
+                link(&mut nodes, 0, Node { name: storage.to_string(), kind: 1, child: NO_STREAM, right: NO_STREAM, start: 0, size: 0 });
+                nodes.len() - 1
+            };
+            for (name, data) in *streams {
+                let start = add_stream(&mut payload, data);
+                link(&mut nodes, parent, Node { name: name.to_string(), kind: 2, child: NO_STREAM, right: NO_STREAM, start, size: data.len() as u32 });
+            }
+        }
+
+        // Layout: FAT sector, payload sectors, directory sectors
+        let payload_sectors = payload.len() / SECTOR;
+        let dir_start = payload_sectors as u32 + 1;
+        let dir_sectors = (nodes.len() * 128).div_ceil(SECTOR);
+        let mut fat = vec![0xFFFF_FFFDu32];
+        let mut next = vec![END_OF_CHAIN; payload_sectors];
+        for (start, count) in fat_chains {
+            for i in 0..count - 1 {
+                next[(start + i) as usize] = start + i + 2;
+            }
+        }
+        fat.extend(next);
+        for i in 0..dir_sectors as u32 {
+            fat.push(if i + 1 == dir_sectors as u32 { END_OF_CHAIN } else { dir_start + i + 1 });
+        }
+        assert!(fat.len() <= SECTOR / 4);
+        fat.resize(SECTOR / 4, NO_STREAM);
+
+        let mut header = vec![0u8; SECTOR];
+        header[..8].copy_from_slice(&MAGIC);
+        header[0x1A..0x1C].copy_from_slice(&0x3Eu16.to_le_bytes());
+        header[0x1C..0x1E].copy_from_slice(&3u16.to_le_bytes());
+        header[0x1E..0x20].copy_from_slice(&9u16.to_le_bytes());
+        header[0x20..0x22].copy_from_slice(&6u16.to_le_bytes());
+        header[0x2C..0x30].copy_from_slice(&1u32.to_le_bytes());
+        header[0x30..0x34].copy_from_slice(&dir_start.to_le_bytes());
+        header[0x3C..0x40].copy_from_slice(&END_OF_CHAIN.to_le_bytes());
+        header[0x44..0x48].copy_from_slice(&END_OF_CHAIN.to_le_bytes());
+        for i in 0..109 {
+            header[0x4C + i * 4..0x50 + i * 4].copy_from_slice(&if i == 0 { 0u32 } else { NO_STREAM }.to_le_bytes());
+        }
+
+        let mut directory = vec![0u8; dir_sectors * SECTOR];
+        for (i, node) in nodes.iter().enumerate() {
+            let raw = &mut directory[i * 128..(i + 1) * 128];
+            let units: Vec<u16> = node.name.encode_utf16().chain(std::iter::once(0)).collect();
+            for (j, unit) in units.iter().enumerate() {
+                raw[j * 2..j * 2 + 2].copy_from_slice(&unit.to_le_bytes());
+            }
+            raw[0x40..0x42].copy_from_slice(&((units.len() * 2) as u16).to_le_bytes());
+            raw[0x42] = node.kind;
+            raw[0x44..0x48].copy_from_slice(&NO_STREAM.to_le_bytes());
+            raw[0x48..0x4C].copy_from_slice(&node.right.to_le_bytes());
+            raw[0x4C..0x50].copy_from_slice(&node.child.to_le_bytes());
+            // Payload sectors follow the FAT sector
+            let start = if node.kind == 2 { node.start + 1 } else { node.start };
+            raw[0x74..0x78].copy_from_slice(&start.to_le_bytes());
+            raw[0x78..0x7C].copy_from_slice(&node.size.to_le_bytes());
+        }
+
+        let mut file = header;
+        file.extend(fat.iter().flat_map(|n| n.to_le_bytes()));
+        file.extend(payload);
+        file.extend(directory);
+        file
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PHISH: &str = "Return-Path: <bounce@mailer.example.net>\r\n\
+Received: from mailer.example.net (mailer.example.net [203.0.113.9])\r\n\
+\tby mx.corp.example with ESMTP id 42\r\n\
+Authentication-Results: mx.corp.example; spf=softfail smtp.mailfrom=mailer.example.net;\r\n\
+\x20dkim=none; dmarc=fail header.from=bank.example\r\n\
+From: \"Bank Security\" <security@bank.example>\r\n\
+Reply-To: help@bank-verify.example\r\n\
+To: alice@corp.example, \"Bob\" <bob@corp.example>\r\n\
+Subject: =?utf-8?B?VXJnZW50OiB2ZXJpZnk=?= your account\r\n\
+Message-ID: <abc123@mailer.example.net>\r\n\
+MIME-Version: 1.0\r\n\
+Content-Type: multipart/mixed; boundary=\"outer\"\r\n\
+\r\n\
+--outer\r\n\
+Content-Type: multipart/alternative; boundary=\"inner\"\r\n\
+\r\n\
+--inner\r\n\
+Content-Type: text/plain; charset=utf-8\r\n\
+Content-Transfer-Encoding: quoted-printable\r\n\
+\r\n\
+Please verify at http://198.51.100.7/login.php?id=1=\r\n\
+\x20today.\r\n\
+--inner\r\n\
+Content-Type: text/html\r\n\
+\r\n\
+<a href=\"https://bank-verify.example/reset?a=1&amp;b=2\">Verify</a>\r\n\
+--inner--\r\n\
+--outer\r\n\
+Content-Type: application/octet-stream; name=\"invoice.pdf.exe\"\r\n\
+Content-Disposition: attachment; filename=\"invoice.pdf.exe\"\r\n\
+Content-Transfer-Encoding: base64\r\n\
+\r\n\
+TVqQAAMAAAAEAAAA\r\n\
+--outer--\r\n";
+
+    #[test]
+    fn eml_headers_auth_urls_and_attachments() {
+        let email = parse_email(PHISH.as_bytes()).unwrap();
+        assert_eq!(email.format, EmailFormat::Eml);
+        assert_eq!(email.subject, "Urgent: verify your account");
+        assert_eq!(email.message_id.as_deref(), Some("abc123@mailer.example.net"));
+        assert_eq!(email.to, vec!["alice@corp.example", "bob@corp.example"]);
+        assert_eq!(domain_of(email.from.as_deref().unwrap()).as_deref(), Some("bank.example"));
+        assert_eq!(display_name_of(email.from.as_deref().unwrap()).as_deref(), Some("Bank Security"));
+        assert_eq!(email.received.len(), 1);
+        assert_eq!(email.authentication.spf, AuthResult::SoftFail);
+        assert_eq!(email.authentication.dkim, AuthResult::None);
+        assert_eq!(email.authentication.dmarc, AuthResult::Fail);
+
+        assert!(email.text_body.as_deref().unwrap().contains("login.php?id=1 today"));
+        assert_eq!(email.urls, vec!["http://198.51.100.7/login.php?id=1", "https://bank-verify.example/reset?a=1&b=2"]);
+        assert_eq!(url_host(&email.urls[0]).as_deref(), Some("198.51.100.7"));
+
+        assert_eq!(email.attachments.len(), 1);
+        assert_eq!(email.attachments[0].filename, "invoice.pdf.exe");
+        assert!(email.attachments[0].data.starts_with(b"MZ"));
+    }
+
+    #[test]
+    fn msg_properties_and_attachments() {
+        let utf16 = |s: &str| s.encode_utf16().flat_map(u16::to_le_bytes).collect::<Vec<u8>>();
+        let subject = utf16("Quarterly report");
+        let body = utf16("See https://files.example/report");
+        let sender = utf16("ceo@corp-example.com");
+        let headers = utf16("Authentication-Results: mx; spf=pass; dkim=fail\r\nFrom: ceo@corp-example.com\r\n");
+        let name = utf16("report.docm");
+        let file = cfb::build(&[
+            ("", &[
+                ("__substg1.0_0037001F", &subject[..]),
+                ("__substg1.0_1000001F", &body[..]),
+                ("__substg1.0_0C1F001F", &sender[..]),
+                ("__substg1.0_007D001F", &headers[..]),
+            ]),
+            ("__attach_version1.0_#00000000", &[
+                ("__substg1.0_3707001F", &name[..]),
+                ("__substg1.0_37010102", b"PK\x03\x04 macro document"),
+            ]),
+        ]);
+
+        let email = parse_email(&file).unwrap();
+        assert_eq!(email.format, EmailFormat::Msg);
+        assert_eq!(email.subject, "Quarterly report");
+        assert_eq!(email.from.as_deref(), Some("ceo@corp-example.com"));
+        assert_eq!(email.authentication.spf, AuthResult::Pass);
+        assert_eq!(email.authentication.dkim, AuthResult::Fail);
+        assert_eq!(email.urls, vec!["https://files.example/report"]);
+        assert_eq!(email.attachments.len(), 1);
+        assert_eq!(email.attachments[0].filename, "report.docm");
+        assert_eq!(email.attachments[0].data, b"PK\x03\x04 macro document");
+    }
+}
@@ -0,0 +1,591 @@
+//! Third-party analysis engines
+//!
+//! An `AnalysisEnginePlugin` looks at the raw sample and reports partial
+//! results: a verdict, a family, behaviors, IOCs and ATT&CK techniques.
+//! Plugins are registered from Rust with `register_engine_plugin`, or from
+//! Node through `registerEnginePlugin` with an async callback. Each one is
+//! listed among the analysis engines as `CustomRules` and can be disabled
+//! there.
+//!
+//! Every enabled plugin whose file types include the sample's runs
+//! concurrently with the others under its own timeout. Findings are merged
+//! into the analysis with the engine named in their evidence or context: a
+//! more severe verdict raises the analysis verdict (never for allowlisted
+//! samples), a family replaces the generic placeholder, and IOCs pass
+//! through the same suppression and enrichment as built-in ones. Each run
+//! is recorded in `SandboxAnalysis::engine_results`; a failing plugin adds
+//! an error there and to the analysis metadata without failing the analysis.
+
+use async_trait::async_trait;
+use chrono::Utc;
+use napi::bindgen_prelude::{Buffer, FnArgs, Promise};
+use napi::threadsafe_function::ThreadsafeFunction;
+use napi::Status;
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::{CoreError, CoreResult};
+use crate::{
+    AnalysisEngine, AnalysisJob, BehaviorSeverity, EngineType, ExtractedIOC, MITRETechnique, SampleInfo, SandboxAnalysis,
+    SandboxCore, SandboxCoreNapi, SandboxVerdict, SuspiciousBehavior,
+};
+
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// What a plugin sees of the sample
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginSample {
+    pub sample_id: String,
+    pub tenant_id: String,
+    pub file_name: String,
+    /// Detected from the bytes: `PE`, `ELF`, `ZIP`, `PDF`, `URL` or `Unknown`
+    pub file_type: String,
+    pub mime_type: String,
+    pub sha256: String,
+    pub size: u64,
+    pub tags: Vec<String>,
+    #[serde(skip)]
+    pub data: Arc<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineBehavior {
+    pub description: String,
+    #[serde(default = "default_severity")]
+    pub severity: BehaviorSeverity,
+    #[serde(default = "default_confidence")]
+    pub confidence: f64,
+    #[serde(default)]
+    pub evidence: Vec<String>,
+    #[serde(default)]
+    pub mitre_technique: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineIoc {
+    pub ioc_type: String,
+    pub value: String,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default = "default_confidence")]
+    pub confidence: f64,
+    #[serde(default)]
+    pub context: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineTechnique {
+    pub technique_id: String,
+    #[serde(default)]
+    pub technique_name: String,
+    #[serde(default)]
+    pub tactic: String,
+    #[serde(default = "default_confidence")]
+    pub confidence: f64,
+    #[serde(default)]
+    pub evidence: Vec<String>,
+}
+
+fn default_severity() -> BehaviorSeverity {
+    BehaviorSeverity::Medium
+}
+
+fn default_confidence() -> f64 {
+    0.5
+}
+
+/// Partial results of one engine; everything is optional
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EngineFindings {
+    #[serde(default)]
+    pub verdict: Option<SandboxVerdict>,
+    #[serde(default)]
+    pub confidence: Option<f64>,
+    #[serde(default)]
+    pub family: Option<String>,
+    #[serde(default)]
+    pub behaviors: Vec<EngineBehavior>,
+    #[serde(default)]
+    pub iocs: Vec<EngineIoc>,
+    #[serde(default)]
+    pub mitre_techniques: Vec<EngineTechnique>,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+/// A plugin's contribution to one analysis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineResult {
+    pub engine: String,
+    pub version: String,
+    pub duration_ms: u64,
+    pub verdict: Option<SandboxVerdict>,
+    pub confidence: Option<f64>,
+    pub family: Option<String>,
+    pub behaviors: usize,
+    pub iocs: usize,
+    pub mitre_techniques: usize,
+    pub error: Option<String>,
+}
+
+/// A custom analysis engine
+#[async_trait]
+pub trait AnalysisEnginePlugin: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn version(&self) -> &str {
+        "1.0.0"
+    }
+
+    /// File types the engine handles (as in `PluginSample::file_type`);
+    /// empty for all of them
+    fn supported_file_types(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(DEFAULT_TIMEOUT_SECS)
+    }
+
+    async fn analyze(&self, sample: &PluginSample) -> Result<EngineFindings, String>;
+}
+
+#[derive(Default)]
+pub struct EnginePluginState {
+    plugins: std::sync::RwLock<BTreeMap<String, Arc<dyn AnalysisEnginePlugin>>>,
+}
+
+fn handles(plugin: &dyn AnalysisEnginePlugin, file_type: &str) -> bool {
+    let types = plugin.supported_file_types();
+    types.is_empty() || types.iter().any(|t| t == "*" || t.eq_ignore_ascii_case(file_type))
+}
+
+fn verdict_rank(verdict: &SandboxVerdict) -> u8 {
+    match verdict {
+        SandboxVerdict::Clean => 0,
+        SandboxVerdict::Likely_Clean => 1,
+        SandboxVerdict::Unknown => 2,
+        SandboxVerdict::Suspicious => 3,
+        SandboxVerdict::Malicious => 4,
+    }
+}
+
+impl SandboxCore {
+    /// Add or replace an engine; it is listed as a `CustomRules` engine
+    pub async fn register_engine_plugin(&self, plugin: Arc<dyn AnalysisEnginePlugin>) -> CoreResult<()> {
+        let name = plugin.name().to_string();
+        if name.trim().is_empty() {
+            return Err(CoreError::validation("Engine plugin name is required"));
+        }
+        let mut engines = self.analysis_engines.write().await;
+        if engines.get(&name).is_some_and(|engine| !matches!(engine.engine_type, EngineType::CustomRules)) {
+            return Err(CoreError::validation(format!("{} is a built-in engine", name)));
+        }
+        let priority = engines.values().map(|engine| engine.priority).max().unwrap_or(0) + 1;
+        let engine = AnalysisEngine {
+            engine_id: name.clone(),
+            engine_type: EngineType::CustomRules,
+            version: plugin.version().to_string(),
+            capabilities: plugin.supported_file_types(),
+            priority: engines.get(&name).map_or(priority, |engine| engine.priority),
+            enabled: true,
+        };
+        engines.insert(name.clone(), engine);
+        self.engine_plugins.plugins.write().unwrap_or_else(|e| e.into_inner()).insert(name, plugin);
+        Ok(())
+    }
+
+    pub async fn unregister_engine_plugin(&self, name: &str) -> bool {
+        let removed = self.engine_plugins.plugins.write().unwrap_or_else(|e| e.into_inner()).remove(name).is_some();
+        if removed {
+            self.analysis_engines.write().await.remove(name);
+        }
+        removed
+    }
+
+    /// Turn a plugin engine on or off without unregistering it
+    pub async fn set_engine_plugin_enabled(&self, name: &str, enabled: bool) -> CoreResult<()> {
+        let mut engines = self.analysis_engines.write().await;
+        match engines.get_mut(name) {
+            Some(engine) if matches!(engine.engine_type, EngineType::CustomRules) => {
+                engine.enabled = enabled;
+                Ok(())
+            }
+            _ => Err(CoreError::not_found(format!("Engine plugin {} not found", name))),
+        }
+    }
+
+    pub async fn list_engine_plugins(&self) -> Vec<AnalysisEngine> {
+        let engines = self.analysis_engines.read().await;
+        let mut plugins: Vec<AnalysisEngine> =
+            engines.values().filter(|engine| matches!(engine.engine_type, EngineType::CustomRules)).cloned().collect();
+        plugins.sort_by_key(|engine| engine.priority);
+        plugins
+    }
+
+    /// Run the enabled plugins that handle the job's sample
+    pub(crate) async fn run_engine_plugins(&self, job: &AnalysisJob, sample_info: &SampleInfo) -> Vec<(EngineResult, EngineFindings)> {
+        let enabled: Vec<String> = {
+            let engines = self.analysis_engines.read().await;
+            engines
+                .values()
+                .filter(|engine| engine.enabled && matches!(engine.engine_type, EngineType::CustomRules))
+                .map(|engine| engine.engine_id.clone())
+                .collect()
+        };
+        let plugins: Vec<Arc<dyn AnalysisEnginePlugin>> = {
+            let plugins = self.engine_plugins.plugins.read().unwrap_or_else(|e| e.into_inner());
+            enabled.iter().filter_map(|name| plugins.get(name).cloned()).collect()
+        };
+        if plugins.is_empty() {
+            return Vec::new();
+        }
+
+        let data = self.sample_data.read().await.get(&job.sample_id).cloned().unwrap_or_default();
+        let file_type = match job.analysis_config.target_url {
+            Some(_) => "URL".to_string(),
+            None => self.detect_file_type(&data),
+        };
+        let sample = PluginSample {
+            sample_id: job.sample_id.clone(),
+            tenant_id: job.tenant_id.clone(),
+            file_name: sample_info.file_name.clone(),
+            mime_type: self.detect_mime_type(&data),
+            file_type,
+            sha256: sample_info.file_hash_sha256.clone(),
+            size: data.len() as u64,
+            tags: sample_info.tags.clone(),
+            data,
+        };
+
+        let runs = plugins.into_iter().filter(|plugin| handles(plugin.as_ref(), &sample.file_type)).map(|plugin| {
+            let sample = &sample;
+            async move {
+                let started = std::time::Instant::now();
+                let outcome = match tokio::time::timeout(plugin.timeout(), plugin.analyze(sample)).await {
+                    Ok(outcome) => outcome,
+                    Err(_) => Err(format!("timed out after {}s", plugin.timeout().as_secs())),
+                };
+                let mut result = EngineResult {
+                    engine: plugin.name().to_string(),
+                    version: plugin.version().to_string(),
+                    duration_ms: started.elapsed().as_millis() as u64,
+                    verdict: None,
+                    confidence: None,
+                    family: None,
+                    behaviors: 0,
+                    iocs: 0,
+                    mitre_techniques: 0,
+                    error: None,
+                };
+                match outcome {
+                    Ok(findings) => {
+                        result.verdict = findings.verdict.clone();
+                        result.confidence = findings.confidence.map(|c| c.clamp(0.0, 1.0));
+                        result.family = findings.family.clone();
+                        result.behaviors = findings.behaviors.len();
+                        result.iocs = findings.iocs.len();
+                        result.mitre_techniques = findings.mitre_techniques.len();
+                        (result, findings)
+                    }
+                    Err(error) => {
+                        result.error = Some(error);
+                        (result, EngineFindings::default())
+                    }
+                }
+            }
+        });
+        futures::future::join_all(runs).await
+    }
+
+    /// Fold plugin findings into `analysis`; `allowlisted` samples keep their verdict
+    pub(crate) async fn merge_engine_findings(&self, analysis: &mut SandboxAnalysis, runs: Vec<(EngineResult, EngineFindings)>, allowlisted: bool) {
+        let now = Utc::now();
+        for (result, findings) in runs {
+            let engine = result.engine.clone();
+            analysis.analysis_metadata.analysis_engines_used.push(engine.clone());
+            analysis.performance_metrics.analysis_engines_time.insert(engine.clone(), result.duration_ms);
+            if let Some(error) = &result.error {
+                analysis.analysis_metadata.errors.push(format!("{} engine: {}", engine, error));
+            }
+            analysis.analysis_metadata.warnings.extend(findings.warnings.iter().map(|w| format!("{} engine: {}", engine, w)));
+
+            if let Some(verdict) = findings.verdict.filter(|_| !allowlisted) {
+                let confidence = result.confidence.unwrap_or(analysis.confidence_score);
+                let current = verdict_rank(&analysis.verdict);
+                if verdict_rank(&verdict) > current {
+                    analysis.verdict = verdict;
+                    analysis.confidence_score = confidence;
+                } else if verdict_rank(&verdict) == current {
+                    analysis.confidence_score = analysis.confidence_score.max(confidence);
+                }
+                analysis.threat_level = self.determine_threat_level(&analysis.verdict, analysis.confidence_score);
+            }
+            if let Some(family) = findings.family {
+                let placeholder = analysis.malware_classification.family.as_deref().is_none_or(|f| f == crate::fuzzy::GENERIC_FAMILY);
+                if placeholder {
+                    analysis.malware_classification.family = Some(family.clone());
+                }
+                if !analysis.threat_intelligence.malware_families.contains(&family) {
+                    analysis.threat_intelligence.malware_families.push(family);
+                }
+            }
+
+            let attribution = format!("engine:{}", engine);
+            for (index, behavior) in findings.behaviors.into_iter().enumerate() {
+                let mut evidence = behavior.evidence;
+                evidence.push(attribution.clone());
+                analysis.behavioral_analysis.suspicious_behaviors.push(SuspiciousBehavior {
+                    behavior_id: format!("{}-{}", engine, index + 1),
+                    description: behavior.description,
+                    severity: behavior.severity,
+                    confidence: behavior.confidence.clamp(0.0, 1.0),
+                    evidence,
+                    mitre_technique: behavior.mitre_technique,
+                    first_observed: now,
+                    frequency: 1,
+                });
+            }
+            for technique in findings.mitre_techniques {
+                match analysis.mitre_techniques.iter_mut().find(|t| t.technique_id == technique.technique_id) {
+                    Some(existing) => {
+                        existing.confidence = existing.confidence.max(technique.confidence);
+                        existing.evidence.extend(technique.evidence);
+                        existing.evidence.push(attribution.clone());
+                    }
+                    None => {
+                        let mut evidence = technique.evidence;
+                        evidence.push(attribution.clone());
+                        analysis.mitre_techniques.push(MITRETechnique {
+                            technique_id: technique.technique_id,
+                            technique_name: technique.technique_name,
+                            tactic: technique.tactic,
+                            confidence: technique.confidence.clamp(0.0, 1.0),
+                            evidence,
+                            sub_techniques: Vec::new(),
+                            detection_methods: vec![format!("{} engine", engine)],
+                        });
+                    }
+                }
+            }
+
+            let mut iocs: Vec<ExtractedIOC> = findings
+                .iocs
+                .into_iter()
+                .filter(|ioc| !analysis.iocs_extracted.iter().any(|known| known.ioc_type == ioc.ioc_type && known.value == ioc.value))
+                .map(|ioc| ExtractedIOC {
+                    ioc_type: ioc.ioc_type,
+                    value: ioc.value,
+                    category: ioc.category.unwrap_or_else(|| "Engine".to_string()),
+                    confidence: ioc.confidence.clamp(0.0, 1.0),
+                    context: match ioc.context {
+                        Some(context) => format!("{} ({})", context, attribution),
+                        None => attribution.clone(),
+                    },
+                    first_seen: now,
+                    threat_intelligence: None,
+                    enrichments: Vec::new(),
+                })
+                .collect();
+            let suppressed = self.suppression.suppress_iocs(&analysis.tenant_id, &mut iocs);
+            if suppressed > 0 {
+                analysis.analysis_metadata.warnings.push(format!("{} {} engine IOCs suppressed by allowlist", suppressed, engine));
+            }
+            self.enrich_iocs(&mut iocs).await;
+            analysis.iocs_extracted.extend(iocs);
+            analysis.engine_results.push(result);
+        }
+    }
+}
+
+/// Node callback of a JS engine: `(sampleJson, bytes) => Promise<findingsJson>`
+pub type JsAnalyzeCallback = ThreadsafeFunction<FnArgs<(String, Buffer)>, Promise<String>, FnArgs<(String, Buffer)>, Status, false, true>;
+
+/// How a JS engine is described to `registerEnginePlugin`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsEngineDefinition {
+    pub name: String,
+    #[serde(default = "default_version")]
+    pub version: String,
+    #[serde(default)]
+    pub file_types: Vec<String>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_version() -> String {
+    "1.0.0".to_string()
+}
+
+fn default_timeout_secs() -> u64 {
+    DEFAULT_TIMEOUT_SECS
+}
+
+/// Engine backed by a Node callback
+pub struct JsEnginePlugin {
+    definition: JsEngineDefinition,
+    callback: JsAnalyzeCallback,
+}
+
+#[async_trait]
+impl AnalysisEnginePlugin for JsEnginePlugin {
+    fn name(&self) -> &str {
+        &self.definition.name
+    }
+
+    fn version(&self) -> &str {
+        &self.definition.version
+    }
+
+    fn supported_file_types(&self) -> Vec<String> {
+        self.definition.file_types.clone()
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_secs(self.definition.timeout_secs.max(1))
+    }
+
+    async fn analyze(&self, sample: &PluginSample) -> Result<EngineFindings, String> {
+        let metadata = serde_json::to_string(sample).map_err(|e| e.to_string())?;
+        let bytes = Buffer::from(sample.data.as_ref().clone());
+        let promise = self.callback.call_async((metadata, bytes).into()).await.map_err(|e| e.to_string())?;
+        let findings = promise.await.map_err(|e| e.to_string())?;
+        serde_json::from_str(&findings).map_err(|e| format!("Invalid findings: {}", e))
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// Register a JS analysis engine. `definition_json` is
+    /// `{"name", "version", "file_types": ["PE"], "timeout_secs": 60}`; `analyze`
+    /// receives the sample's metadata as JSON and its bytes, and resolves to
+    /// findings JSON (`verdict`, `confidence`, `family`, `behaviors`, `iocs`,
+    /// `mitre_techniques`, `warnings`)
+    #[napi]
+    pub async fn register_engine_plugin(&self, definition_json: String, analyze: JsAnalyzeCallback, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize(auth_token, "rule:manage", "engine_plugins")?;
+        let definition: JsEngineDefinition = serde_json::from_str(&definition_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse engine definition: {}", e)))?;
+        let params = serde_json::json!({ "definition": definition });
+        let name = definition.name.clone();
+
+        let result = self.inner.register_engine_plugin(Arc::new(JsEnginePlugin { definition, callback: analyze })).await;
+        Ok(self.audit.record(&actor, "register_engine_plugin", &name, params, result)
+            .map_err(|e| e.context("Failed to register engine plugin"))?)
+    }
+
+    #[napi]
+    pub async fn unregister_engine_plugin(&self, name: String, auth_token: Option<String>) -> napi::Result<bool> {
+        let actor = self.authorize(auth_token, "rule:manage", &name)?;
+        let removed = self.inner.unregister_engine_plugin(&name).await;
+        self.audit.record(&actor, "unregister_engine_plugin", &name, serde_json::json!({}), Ok::<_, String>(removed))
+            .map_err(napi::Error::from_reason)
+    }
+
+    #[napi]
+    pub async fn set_engine_plugin_enabled(&self, name: String, enabled: bool, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize(auth_token, "rule:manage", &name)?;
+        let result = self.inner.set_engine_plugin_enabled(&name, enabled).await;
+        Ok(self.audit.record(&actor, "set_engine_plugin_enabled", &name, serde_json::json!({ "enabled": enabled }), result)
+            .map_err(|e| e.context("Failed to update engine plugin"))?)
+    }
+
+    #[napi]
+    pub async fn list_engine_plugins(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.list_engine_plugins().await)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize engine plugins: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AnalysisPriority;
+
+    struct MacroScanner;
+
+    #[async_trait]
+    impl AnalysisEnginePlugin for MacroScanner {
+        fn name(&self) -> &str {
+            "macro_scanner"
+        }
+
+        fn supported_file_types(&self) -> Vec<String> {
+            vec!["PE".to_string()]
+        }
+
+        async fn analyze(&self, sample: &PluginSample) -> Result<EngineFindings, String> {
+            if !sample.data.starts_with(b"MZ") {
+                return Err("not a PE".to_string());
+            }
+            Ok(serde_json::from_value(serde_json::json!({
+                "verdict": "Malicious",
+                "confidence": 0.99,
+                "family": "Emotet",
+                "behaviors": [{ "description": "Auto-open macro spawns PowerShell", "severity": "High", "mitre_technique": "T1059.001" }],
+                "iocs": [{ "ioc_type": "Domain", "value": "drop.evil.test", "context": "macro download URL" }],
+                "mitre_techniques": [{ "technique_id": "T1059.001", "technique_name": "PowerShell", "tactic": "Execution", "confidence": 0.9 }]
+            }))
+            .unwrap())
+        }
+    }
+
+    struct Stalled;
+
+    #[async_trait]
+    impl AnalysisEnginePlugin for Stalled {
+        fn name(&self) -> &str {
+            "stalled"
+        }
+
+        fn timeout(&self) -> Duration {
+            Duration::from_millis(20)
+        }
+
+        async fn analyze(&self, _sample: &PluginSample) -> Result<EngineFindings, String> {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(EngineFindings::default())
+        }
+    }
+
+    #[tokio::test]
+    async fn plugin_findings_are_merged_with_attribution() {
+        let core = SandboxCore::new().unwrap();
+        core.register_engine_plugin(Arc::new(MacroScanner)).await.unwrap();
+        core.register_engine_plugin(Arc::new(Stalled)).await.unwrap();
+        assert_eq!(core.list_engine_plugins().await.iter().map(|e| e.engine_id.as_str()).collect::<Vec<_>>(), ["macro_scanner", "stalled"]);
+
+        let sample_id = core.submit_sample("acme", b"MZ\x90\x00 dropper", "invoice.exe".to_string(), AnalysisPriority::Normal, vec![], false).await.unwrap();
+        core.process_queue().await.unwrap();
+        let analysis = core.get_analysis("acme", &sample_id).await.unwrap().unwrap();
+
+        assert_eq!(analysis.engine_results.len(), 2);
+        let scanner = analysis.engine_results.iter().find(|r| r.engine == "macro_scanner").unwrap();
+        assert_eq!((scanner.behaviors, scanner.iocs, scanner.mitre_techniques, scanner.error.is_none()), (1, 1, 1, true));
+        let stalled = analysis.engine_results.iter().find(|r| r.engine == "stalled").unwrap();
+        assert!(stalled.error.as_deref().unwrap().contains("timed out"));
+        assert!(analysis.analysis_metadata.errors.iter().any(|e| e.starts_with("stalled engine:")));
+
+        assert!(matches!(analysis.verdict, SandboxVerdict::Malicious));
+        assert_eq!(analysis.confidence_score, 0.99);
+        assert_eq!(analysis.malware_classification.family.as_deref(), Some("Emotet"));
+        let ioc = analysis.iocs_extracted.iter().find(|i| i.value == "drop.evil.test").unwrap();
+        assert_eq!(ioc.context, "macro download URL (engine:macro_scanner)");
+        assert!(analysis.behavioral_analysis.suspicious_behaviors.iter().any(|b| b.behavior_id == "macro_scanner-1"));
+        assert!(analysis.mitre_techniques.iter().any(|t| t.technique_id == "T1059.001" && t.evidence.contains(&"engine:macro_scanner".to_string())));
+        assert!(analysis.analysis_metadata.analysis_engines_used.contains(&"macro_scanner".to_string()));
+
+        // Disabled engines and unsupported file types are skipped
+        core.set_engine_plugin_enabled("stalled", false).await.unwrap();
+        assert!(core.set_engine_plugin_enabled("phantom_static", false).await.is_err());
+        let pdf = core.submit_sample("acme", b"%PDF-1.7 invoice", "invoice.pdf".to_string(), AnalysisPriority::Normal, vec![], false).await.unwrap();
+        core.process_queue().await.unwrap();
+        assert!(core.get_analysis("acme", &pdf).await.unwrap().unwrap().engine_results.is_empty());
+
+        assert!(core.unregister_engine_plugin("macro_scanner").await);
+        assert_eq!(core.list_engine_plugins().await.len(), 1);
+    }
+}
@@ -0,0 +1,193 @@
+//! IOC Enrichment
+//!
+//! With `phantom-enterprise-standards` enabled, IOCs extracted by an analysis
+//! are run through the shared enrichment pipeline: provider results are
+//! attached to each IOC and summarised into its `threat_intelligence`.
+//! Without it IOCs are left as extracted.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{ExtractedIOC, SandboxCore};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use napi_derive::napi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::enrichment::{
+    EnrichmentPipeline, EnrichmentRecord, EnrichmentSetup, EnrichmentStats, Indicator, IndicatorEnrichment,
+};
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::collections::HashMap;
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::SandboxCoreNapi;
+
+/// One provider's findings for an IOC
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IOCEnrichment {
+    pub provider: String,
+    pub category: String,
+    pub data: serde_json::Value,
+    pub verdict: Option<String>,
+    pub retrieved_at: DateTime<Utc>,
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+impl From<&EnrichmentRecord> for IOCEnrichment {
+    fn from(record: &EnrichmentRecord) -> Self {
+        let label = |value: serde_json::Value| value.as_str().map(str::to_string);
+        Self {
+            provider: record.provider.clone(),
+            category: serde_json::to_value(record.category).ok().and_then(label).unwrap_or_default(),
+            data: record.data.clone(),
+            verdict: record.verdict.and_then(|v| serde_json::to_value(v).ok()).and_then(label),
+            retrieved_at: record.retrieved_at,
+        }
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[derive(Default)]
+pub struct EnrichmentState {
+    pipeline: RwLock<Arc<EnrichmentPipeline>>,
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+impl EnrichmentState {
+    pub fn pipeline(&self) -> Arc<EnrichmentPipeline> {
+        self.pipeline.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+impl SandboxCore {
+    /// Attach provider results to each IOC, replacing its
+    /// `threat_intelligence` with a summary when any provider knew it
+    pub async fn enrich_iocs(&self, iocs: &mut [ExtractedIOC]) {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            let indicators: Vec<Indicator> = iocs.iter().map(|ioc| Indicator::from_ioc(&ioc.ioc_type, &ioc.value)).collect();
+            let results: HashMap<Indicator, IndicatorEnrichment> = self
+                .enrichment
+                .pipeline()
+                .enrich_batch(&indicators)
+                .await
+                .into_iter()
+                .map(|enrichment| (enrichment.indicator.clone(), enrichment))
+                .collect();
+
+            for (ioc, indicator) in iocs.iter_mut().zip(&indicators) {
+                let Some(enrichment) = results.get(indicator) else { continue };
+                for (provider, error) in &enrichment.errors {
+                    log::debug!("Enrichment of {} by {} failed: {}", ioc.value, provider, error);
+                }
+                ioc.enrichments = enrichment.records.iter().map(IOCEnrichment::from).collect();
+                if let Some(summary) = enrichment.summary() {
+                    ioc.threat_intelligence = Some(summary);
+                }
+            }
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        let _ = iocs;
+    }
+
+    /// Replace the enrichment pipeline with one built from `setup`
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn configure_enrichment(&self, setup: EnrichmentSetup) -> Result<EnrichmentStats, String> {
+        let pipeline = Arc::new(EnrichmentPipeline::from_setup(setup)?);
+        let stats = pipeline.stats();
+        *self.enrichment.pipeline.write().unwrap_or_else(|e| e.into_inner()) = pipeline;
+        Ok(stats)
+    }
+
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn enrichment_pipeline(&self) -> Arc<EnrichmentPipeline> {
+        self.enrichment.pipeline()
+    }
+}
+
+/// IOC submitted for ad-hoc enrichment
+#[cfg(feature = "phantom-enterprise-standards")]
+#[derive(Debug, Clone, Deserialize)]
+struct IOCLookup {
+    ioc_type: String,
+    value: String,
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[napi]
+impl SandboxCoreNapi {
+    /// Configure enrichment providers (GeoIP, WHOIS, passive DNS, reputation lists) and cache limits
+    #[napi]
+    pub fn configure_enrichment(&self, config_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize(auth_token, "rule:manage", "enrichment")?;
+        let setup: EnrichmentSetup = serde_json::from_str(&config_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse enrichment config: {}", e)))?;
+
+        let stats = self.inner.configure_enrichment(setup);
+        let stats = self.audit.record(&actor, "configure_enrichment", "enrichment", serde_json::json!({ "config": config_json }), stats)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to configure enrichment: {}", e)))?;
+
+        serde_json::to_string(&stats)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize enrichment stats: {}", e)))
+    }
+
+    /// Enrich `[{ioc_type, value}]` outside of an analysis
+    #[napi]
+    pub async fn enrich_iocs(&self, iocs_json: String) -> napi::Result<String> {
+        let lookups: Vec<IOCLookup> = serde_json::from_str(&iocs_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse IOCs: {}", e)))?;
+        let indicators: Vec<Indicator> = lookups.iter().map(|l| Indicator::from_ioc(&l.ioc_type, &l.value)).collect();
+        let enrichments = self.inner.enrichment_pipeline().enrich_batch(&indicators).await;
+
+        serde_json::to_string(&enrichments)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize enrichments: {}", e)))
+    }
+
+    /// Get enrichment providers and cache hit/miss counters
+    #[napi]
+    pub fn get_enrichment_stats(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.enrichment_pipeline().stats())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize enrichment stats: {}", e)))
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use super::*;
+    use phantom_enterprise_standards::enrichment::ReputationListSetup;
+
+    #[tokio::test]
+    async fn extracted_iocs_carry_enrichment() {
+        let core = SandboxCore::new().unwrap();
+        core.configure_enrichment(EnrichmentSetup {
+            reputation_lists: vec![ReputationListSetup {
+                name: "c2-blocklist".to_string(),
+                verdict: phantom_enterprise_standards::enrichment::ReputationVerdict::Malicious,
+                path: None,
+                entries: vec!["203.0.113.0/24".to_string()],
+            }],
+            ..Default::default()
+        })
+        .unwrap();
+
+        let ioc = |value: &str| ExtractedIOC {
+            ioc_type: "IP".to_string(),
+            value: value.to_string(),
+            category: "Network".to_string(),
+            confidence: 0.9,
+            context: "C2".to_string(),
+            first_seen: Utc::now(),
+            threat_intelligence: None,
+            enrichments: Vec::new(),
+        };
+        let mut iocs = vec![ioc("203.0.113.9"), ioc("198.51.100.1")];
+        core.enrich_iocs(&mut iocs).await;
+
+        assert_eq!(iocs[0].enrichments[0].verdict.as_deref(), Some("malicious"));
+        assert_eq!(iocs[0].threat_intelligence.as_deref(), Some("malicious (c2-blocklist)"));
+        assert!(iocs[1].enrichments.is_empty());
+        assert!(iocs[1].threat_intelligence.is_none());
+    }
+}
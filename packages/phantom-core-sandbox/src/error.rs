@@ -0,0 +1,206 @@
+//! Structured Errors
+//!
+//! Fallible `SandboxCore` APIs return [`CoreError`], whose category tells a
+//! caller what to do about a failure: fix the request (`Validation`), stop
+//! asking for something that is not there (`NotFound`), back off (`Quota`,
+//! `Timeout`) or retry later (`Backend`). The error that caused it is kept
+//! as the `source`. Over NAPI the category's code prefixes the message, e.g.
+//! `[NOT_FOUND] Failed to get timeline events: No completed analysis for
+//! sample smp_…`, and validation errors carry the `InvalidArg` status.
+//!
+//! Modules that still report plain `String` errors convert as `Backend`.
+
+use std::error::Error as StdError;
+use std::fmt::Display;
+
+pub type CoreResult<T> = std::result::Result<T, CoreError>;
+
+type Source = Box<dyn StdError + Send + Sync + 'static>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CoreError {
+    #[error("{message}")]
+    NotFound { message: String, #[source] source: Option<Source> },
+    #[error("{message}")]
+    Validation { message: String, #[source] source: Option<Source> },
+    #[error("{message}")]
+    Quota { message: String, #[source] source: Option<Source> },
+    #[error("{message}")]
+    Backend { message: String, #[source] source: Option<Source> },
+    #[error("{message}")]
+    Timeout { message: String, #[source] source: Option<Source> },
+}
+
+impl CoreError {
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound { message: message.into(), source: None }
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::Validation { message: message.into(), source: None }
+    }
+
+    pub fn quota(message: impl Into<String>) -> Self {
+        Self::Quota { message: message.into(), source: None }
+    }
+
+    pub fn backend(message: impl Into<String>) -> Self {
+        Self::Backend { message: message.into(), source: None }
+    }
+
+    pub fn timeout(message: impl Into<String>) -> Self {
+        Self::Timeout { message: message.into(), source: None }
+    }
+
+    /// Attach the error that caused this one
+    pub fn with_source(mut self, error: impl StdError + Send + Sync + 'static) -> Self {
+        *self.parts().1 = Some(Box::new(error));
+        self
+    }
+
+    /// Prefix the message with what was being attempted
+    pub fn context(mut self, context: impl Display) -> Self {
+        let message = self.parts().0;
+        *message = format!("{}: {}", context, message);
+        self
+    }
+
+    /// Stable code of the category, e.g. `NOT_FOUND`
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound { .. } => "NOT_FOUND",
+            Self::Validation { .. } => "VALIDATION",
+            Self::Quota { .. } => "QUOTA_EXCEEDED",
+            Self::Backend { .. } => "BACKEND",
+            Self::Timeout { .. } => "TIMEOUT",
+        }
+    }
+
+    /// Whether the same call may succeed later
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Quota { .. } | Self::Backend { .. } | Self::Timeout { .. })
+    }
+
+    /// The message followed by each error in the source chain
+    pub fn chain(&self) -> String {
+        let mut chain = self.to_string();
+        let mut source = self.source();
+        while let Some(error) = source {
+            chain.push_str(": ");
+            chain.push_str(&error.to_string());
+            source = error.source();
+        }
+        chain
+    }
+
+    fn parts(&mut self) -> (&mut String, &mut Option<Source>) {
+        match self {
+            Self::NotFound { message, source }
+            | Self::Validation { message, source }
+            | Self::Quota { message, source }
+            | Self::Backend { message, source }
+            | Self::Timeout { message, source } => (message, source),
+        }
+    }
+}
+
+impl From<String> for CoreError {
+    fn from(message: String) -> Self {
+        Self::backend(message)
+    }
+}
+
+impl From<&str> for CoreError {
+    fn from(message: &str) -> Self {
+        Self::backend(message)
+    }
+}
+
+impl From<std::io::Error> for CoreError {
+    fn from(error: std::io::Error) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::NotFound => Self::not_found("File not found"),
+            std::io::ErrorKind::TimedOut => Self::timeout("I/O timed out"),
+            std::io::ErrorKind::InvalidInput | std::io::ErrorKind::InvalidData => Self::validation("Invalid data"),
+            _ => Self::backend("I/O failure"),
+        }
+        .with_source(error)
+    }
+}
+
+impl From<serde_json::Error> for CoreError {
+    fn from(error: serde_json::Error) -> Self {
+        match error.classify() {
+            serde_json::error::Category::Io => Self::backend("JSON I/O failure"),
+            _ => Self::validation("Invalid JSON"),
+        }
+        .with_source(error)
+    }
+}
+
+impl From<phantom_enterprise_standards::pagination::PageError> for CoreError {
+    fn from(error: phantom_enterprise_standards::pagination::PageError) -> Self {
+        use phantom_enterprise_standards::pagination::PageError;
+        match error {
+            PageError::Invalid(message) => Self::validation(message),
+            PageError::Serialization(message) => Self::backend(message),
+        }
+    }
+}
+
+impl From<phantom_enterprise_standards::secrets::SecretError> for CoreError {
+    fn from(error: phantom_enterprise_standards::secrets::SecretError) -> Self {
+        use phantom_enterprise_standards::secrets::SecretError;
+        match error {
+            SecretError::Invalid(message) => Self::validation(message),
+            SecretError::NotFound(message) => Self::not_found(message),
+            SecretError::Unavailable(message) => Self::backend(message),
+        }
+    }
+}
+
+impl From<tokio::time::error::Elapsed> for CoreError {
+    fn from(error: tokio::time::error::Elapsed) -> Self {
+        Self::timeout("Operation timed out").with_source(error)
+    }
+}
+
+impl<S: AsRef<str>> From<napi::Error<S>> for CoreError {
+    fn from(error: napi::Error<S>) -> Self {
+        Self::backend(error.reason)
+    }
+}
+
+impl From<CoreError> for napi::Error {
+    fn from(error: CoreError) -> Self {
+        let status = match &error {
+            CoreError::Validation { .. } => napi::Status::InvalidArg,
+            _ => napi::Status::GenericFailure,
+        };
+        napi::Error::new(status, format!("[{}] {}", error.code(), error.chain()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn errors_keep_their_category_and_source() {
+        let core = crate::SandboxCore::new().unwrap();
+        let missing = core.get_timeline_events("acme", "smp_missing").await.unwrap_err();
+        assert_eq!(missing.code(), "NOT_FOUND");
+        assert!(!missing.is_retryable());
+
+        let parse = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let invalid = CoreError::from(parse).context("Failed to parse batch config");
+        assert_eq!(invalid.code(), "VALIDATION");
+        assert!(invalid.source().is_some());
+        assert!(invalid.chain().starts_with("Failed to parse batch config: Invalid JSON: EOF"));
+
+        let napi_error = napi::Error::from(invalid);
+        assert_eq!(napi_error.status, napi::Status::InvalidArg);
+        assert!(napi_error.reason.starts_with("[VALIDATION] Failed to parse batch config"));
+        assert_eq!(napi::Error::from(missing.context("Failed to get timeline events")).status, napi::Status::GenericFailure);
+    }
+}
@@ -0,0 +1,442 @@
+//! Streaming NDJSON export of completed analyses
+//!
+//! `export_analyses` assembles its whole result in memory, which does not
+//! scale to large archives. The streaming export visits analyses one at a
+//! time — the in-memory cache first, then the analyses the retention policy
+//! spilled to disk — applies the filter before anything is serialized, and
+//! emits one JSON object per line. An export is either written to a file or
+//! read through a cursor the caller pulls chunks from, so a slow consumer
+//! holds the export back instead of it buffering up in memory. Both report
+//! progress under an export id.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+use crate::{SandboxAnalysis, SandboxCore, SandboxVerdict, ThreatLevel};
+
+/// Lines returned by a cursor read when the caller does not ask for a size
+pub const DEFAULT_CHUNK_LINES: usize = 100;
+
+/// Cursors not read from for this long are dropped
+const CURSOR_IDLE_MINUTES: i64 = 15;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// Complete analysis objects
+    #[default]
+    Full,
+    /// Verdict, classification and counts only
+    Summary,
+}
+
+/// Which analyses an export includes; checked before an analysis is serialized
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportFilter {
+    /// Only analyses started within this many hours; all when unset
+    pub time_range_hours: Option<u64>,
+    /// Include `Clean` and `Likely_Clean` verdicts
+    pub include_benign: bool,
+    /// Restrict to these verdicts, e.g. `["Malicious", "Suspicious"]`
+    pub verdicts: Vec<String>,
+    /// Lowest threat level to include, e.g. `"High"`
+    pub min_threat_level: Option<String>,
+    pub min_confidence: Option<f64>,
+    /// Case-insensitive malware family name
+    pub malware_family: Option<String>,
+    pub format: ExportFormat,
+}
+
+impl ExportFilter {
+    fn cutoff(&self) -> Option<DateTime<Utc>> {
+        self.time_range_hours.map(|hours| Utc::now() - Duration::hours(hours as i64))
+    }
+
+    fn matches(&self, analysis: &SandboxAnalysis, cutoff: Option<DateTime<Utc>>) -> bool {
+        if cutoff.is_some_and(|cutoff| analysis.analysis_metadata.analysis_start < cutoff) {
+            return false;
+        }
+        if !self.include_benign && matches!(analysis.verdict, SandboxVerdict::Clean | SandboxVerdict::Likely_Clean) {
+            return false;
+        }
+        let verdict = format!("{:?}", analysis.verdict);
+        if !self.verdicts.is_empty() && !self.verdicts.iter().any(|v| v.eq_ignore_ascii_case(&verdict)) {
+            return false;
+        }
+        if let Some(min) = &self.min_threat_level {
+            if threat_rank(&analysis.threat_level) < threat_rank_named(min) {
+                return false;
+            }
+        }
+        if self.min_confidence.is_some_and(|min| analysis.confidence_score < min) {
+            return false;
+        }
+        match &self.malware_family {
+            Some(family) => analysis
+                .malware_classification
+                .family
+                .as_deref()
+                .is_some_and(|f| f.eq_ignore_ascii_case(family)),
+            None => true,
+        }
+    }
+
+    fn render(&self, analysis: &SandboxAnalysis) -> Result<String, String> {
+        let line = match self.format {
+            ExportFormat::Full => serde_json::to_string(analysis),
+            ExportFormat::Summary => serde_json::to_string(&summary_record(analysis)),
+        };
+        line.map_err(|e| format!("Failed to serialize analysis {}: {}", analysis.sample_info.sample_id, e))
+    }
+}
+
+fn threat_rank(level: &ThreatLevel) -> u8 {
+    match level {
+        ThreatLevel::None => 0,
+        ThreatLevel::Low => 1,
+        ThreatLevel::Medium => 2,
+        ThreatLevel::High => 3,
+        ThreatLevel::Critical => 4,
+    }
+}
+
+fn threat_rank_named(name: &str) -> u8 {
+    match name.to_ascii_lowercase().as_str() {
+        "low" => 1,
+        "medium" => 2,
+        "high" => 3,
+        "critical" => 4,
+        _ => 0,
+    }
+}
+
+/// The per-analysis record of summary exports
+pub fn summary_record(analysis: &SandboxAnalysis) -> serde_json::Value {
+    serde_json::json!({
+        "sample_id": analysis.sample_info.sample_id,
+        "file_name": analysis.sample_info.file_name,
+        "file_hash_sha256": analysis.sample_info.file_hash_sha256,
+        "verdict": format!("{:?}", analysis.verdict),
+        "threat_level": format!("{:?}", analysis.threat_level),
+        "confidence_score": analysis.confidence_score,
+        "analysis_duration": analysis.analysis_metadata.analysis_duration,
+        "malware_family": analysis.malware_classification.family,
+        "iocs_count": analysis.iocs_extracted.len(),
+        "mitre_techniques": analysis.mitre_techniques.iter().map(|t| &t.technique_id).collect::<Vec<_>>()
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportProgress {
+    pub export_id: String,
+    /// File being written; `None` for cursor exports
+    pub destination: Option<String>,
+    /// Analyses the export will look at, in memory and spilled
+    pub candidates: usize,
+    pub scanned: usize,
+    pub exported: usize,
+    pub bytes_written: u64,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// One read from an export cursor
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportChunk {
+    /// Newline-terminated JSON lines, empty once the export is exhausted
+    pub ndjson: String,
+    pub done: bool,
+    pub progress: ExportProgress,
+}
+
+/// Analyses still to be visited by an export
+struct ExportCursor {
+    tenant_id: String,
+    filter: ExportFilter,
+    cutoff: Option<DateTime<Utc>>,
+    cached: VecDeque<String>,
+    /// Sample ids taken from the cache, so spill files written for them
+    /// during the export are not exported twice
+    cached_ids: HashSet<String>,
+    spilled: VecDeque<PathBuf>,
+    progress: ExportProgress,
+    last_read: DateTime<Utc>,
+}
+
+#[derive(Default)]
+pub struct ExportRegistry {
+    cursors: tokio::sync::Mutex<HashMap<String, ExportCursor>>,
+    /// Progress of file exports still being written
+    writing: Mutex<HashMap<String, (String, ExportProgress)>>,
+}
+
+impl SandboxCore {
+    async fn open_cursor(&self, tenant_id: &str, filter: ExportFilter, destination: Option<String>) -> ExportCursor {
+        let cached: VecDeque<String> = self.completed_analyses.read().await.keys().cloned().collect();
+        let spilled: VecDeque<PathBuf> = self.spill_files().await.into();
+        let now = Utc::now();
+        ExportCursor {
+            tenant_id: tenant_id.to_string(),
+            cutoff: filter.cutoff(),
+            filter,
+            cached_ids: cached.iter().cloned().collect(),
+            progress: ExportProgress {
+                export_id: Uuid::new_v4().to_string(),
+                destination,
+                candidates: cached.len() + spilled.len(),
+                scanned: 0,
+                exported: 0,
+                bytes_written: 0,
+                started_at: now,
+                finished_at: None,
+            },
+            cached,
+            spilled,
+            last_read: now,
+        }
+    }
+
+    /// Next line of the export, or `None` once every candidate was visited
+    async fn next_line(&self, cursor: &mut ExportCursor) -> Result<Option<String>, String> {
+        while let Some(sample_id) = cursor.cached.pop_front() {
+            cursor.progress.scanned += 1;
+            let cached = self.completed_analyses.read().await.get(&sample_id).map(|analysis| cursor.accept(analysis));
+            let line = match cached {
+                Some(line) => line,
+                // Evicted since the export started
+                None => self.load_spilled(&sample_id).await.and_then(|analysis| cursor.accept(&analysis)),
+            };
+            if let Some(line) = line {
+                return line.map(Some);
+            }
+        }
+        while let Some(path) = cursor.spilled.pop_front() {
+            cursor.progress.scanned += 1;
+            let Some(analysis) = read_spilled(&path) else { continue };
+            if cursor.cached_ids.contains(&analysis.sample_info.sample_id) {
+                continue;
+            }
+            if let Some(line) = cursor.accept(&analysis) {
+                return line.map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Start a cursor export for `tenant_id`; read it with `next_export_chunk`
+    pub async fn open_export(&self, tenant_id: &str, filter: ExportFilter) -> ExportProgress {
+        let cursor = self.open_cursor(tenant_id, filter, None).await;
+        let progress = cursor.progress.clone();
+        let mut cursors = self.exports.cursors.lock().await;
+        let idle_cutoff = Utc::now() - Duration::minutes(CURSOR_IDLE_MINUTES);
+        cursors.retain(|_, cursor| cursor.last_read >= idle_cutoff);
+        cursors.insert(progress.export_id.clone(), cursor);
+        progress
+    }
+
+    /// Up to `max_lines` further lines of a cursor export. The cursor is
+    /// dropped once the chunk marked `done` was returned.
+    pub async fn next_export_chunk(&self, tenant_id: &str, export_id: &str, max_lines: usize) -> Result<ExportChunk, String> {
+        let mut cursors = self.exports.cursors.lock().await;
+        let cursor = cursors
+            .get_mut(export_id)
+            .filter(|cursor| cursor.tenant_id == tenant_id)
+            .ok_or_else(|| format!("Export {} not found", export_id))?;
+        cursor.last_read = Utc::now();
+
+        let mut ndjson = String::new();
+        let mut done = false;
+        for _ in 0..max_lines.max(1) {
+            match self.next_line(cursor).await? {
+                Some(line) => {
+                    ndjson.push_str(&line);
+                    ndjson.push('\n');
+                }
+                None => {
+                    done = true;
+                    break;
+                }
+            }
+        }
+        cursor.progress.bytes_written += ndjson.len() as u64;
+        if done {
+            cursor.progress.finished_at = Some(Utc::now());
+        }
+        let progress = cursor.progress.clone();
+        if done {
+            cursors.remove(export_id);
+        }
+        Ok(ExportChunk { ndjson, done, progress })
+    }
+
+    /// Abandon a cursor export
+    pub async fn close_export(&self, tenant_id: &str, export_id: &str) -> Result<ExportProgress, String> {
+        let mut cursors = self.exports.cursors.lock().await;
+        let owned = cursors.get(export_id).is_some_and(|cursor| cursor.tenant_id == tenant_id);
+        let cursor = owned
+            .then(|| cursors.remove(export_id))
+            .flatten()
+            .ok_or_else(|| format!("Export {} not found", export_id))?;
+        let mut progress = cursor.progress;
+        progress.finished_at = Some(Utc::now());
+        Ok(progress)
+    }
+
+    /// Progress of an open cursor export or of a file export being written
+    pub async fn export_progress(&self, tenant_id: &str, export_id: &str) -> Option<ExportProgress> {
+        if let Some(cursor) = self.exports.cursors.lock().await.get(export_id) {
+            return (cursor.tenant_id == tenant_id).then(|| cursor.progress.clone());
+        }
+        let writing = self.exports.writing.lock().unwrap_or_else(|e| e.into_inner());
+        writing
+            .get(export_id)
+            .filter(|(owner, _)| owner == tenant_id)
+            .map(|(_, progress)| progress.clone())
+    }
+
+    /// Write the analyses of `tenant_id` matching `filter` to `path` as NDJSON.
+    /// The file is written under a temporary name and renamed into place once
+    /// complete, so a failed export never leaves a truncated file at `path`.
+    pub async fn export_to_file(&self, tenant_id: &str, path: &Path, filter: ExportFilter) -> Result<ExportProgress, String> {
+        let mut cursor = self.open_cursor(tenant_id, filter, Some(path.display().to_string())).await;
+        let export_id = cursor.progress.export_id.clone();
+        let partial = path.with_extension("ndjson.partial");
+        let result = self.write_export(&mut cursor, &partial).await.and_then(|()| {
+            fs::rename(&partial, path).map_err(|e| format!("Failed to move export into {}: {}", path.display(), e))
+        });
+        self.exports.writing.lock().unwrap_or_else(|e| e.into_inner()).remove(&export_id);
+        if let Err(e) = result {
+            let _ = fs::remove_file(&partial);
+            return Err(e);
+        }
+        cursor.progress.finished_at = Some(Utc::now());
+        Ok(cursor.progress)
+    }
+
+    async fn write_export(&self, cursor: &mut ExportCursor, partial: &Path) -> Result<(), String> {
+        let file = File::create(partial).map_err(|e| format!("Failed to create {}: {}", partial.display(), e))?;
+        let mut writer = BufWriter::new(file);
+        while let Some(line) = self.next_line(cursor).await? {
+            writer
+                .write_all(line.as_bytes())
+                .and_then(|()| writer.write_all(b"\n"))
+                .map_err(|e| format!("Failed to write {}: {}", partial.display(), e))?;
+            cursor.progress.bytes_written += line.len() as u64 + 1;
+            self.exports
+                .writing
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(cursor.progress.export_id.clone(), (cursor.tenant_id.clone(), cursor.progress.clone()));
+        }
+        writer.flush().map_err(|e| format!("Failed to write {}: {}", partial.display(), e))
+    }
+}
+
+impl ExportCursor {
+    /// Render `analysis` when it belongs to the export
+    fn accept(&mut self, analysis: &SandboxAnalysis) -> Option<Result<String, String>> {
+        if analysis.tenant_id != self.tenant_id || !self.filter.matches(analysis, self.cutoff) {
+            return None;
+        }
+        self.progress.exported += 1;
+        Some(self.filter.render(analysis))
+    }
+}
+
+fn read_spilled(path: &Path) -> Option<SandboxAnalysis> {
+    let bytes = fs::read(path).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AnalysisPriority;
+
+    async fn core_with_analyses(count: usize) -> SandboxCore {
+        let core = SandboxCore::new().unwrap();
+        for i in 0..count {
+            let data = format!("MZ export sample {}", i);
+            core.submit_sample("default", data.as_bytes(), format!("s{}.exe", i), AnalysisPriority::Normal, vec![], false)
+                .await
+                .unwrap();
+        }
+        core.submit_sample("acme", b"MZ other tenant", "o.exe".to_string(), AnalysisPriority::Normal, vec![], false)
+            .await
+            .unwrap();
+        for _ in 0..=count {
+            core.process_queue().await.unwrap();
+        }
+        core
+    }
+
+    fn everything() -> ExportFilter {
+        ExportFilter { include_benign: true, format: ExportFormat::Summary, ..Default::default() }
+    }
+
+    #[tokio::test]
+    async fn cursor_yields_one_line_per_analysis_in_chunks() {
+        let core = core_with_analyses(5).await;
+        let export = core.open_export("default", everything()).await;
+        assert_eq!(export.candidates, 6);
+
+        let mut lines = Vec::new();
+        loop {
+            let chunk = core.next_export_chunk("default", &export.export_id, 2).await.unwrap();
+            assert!(chunk.ndjson.lines().count() <= 2);
+            lines.extend(chunk.ndjson.lines().map(str::to_string));
+            if chunk.done {
+                assert_eq!(chunk.progress.exported, 5);
+                assert_eq!(chunk.progress.scanned, 6);
+                break;
+            }
+        }
+        assert_eq!(lines.len(), 5, "the other tenant's analysis is not exported");
+        for line in &lines {
+            let record: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert!(record["sample_id"].is_string());
+        }
+        assert!(core.next_export_chunk("default", &export.export_id, 2).await.is_err(), "cursor is dropped when done");
+
+        let other = core.open_export("acme", everything()).await;
+        assert!(core.next_export_chunk("default", &other.export_id, 2).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn file_export_includes_spilled_analyses() {
+        let dir = std::env::temp_dir().join(format!("sandbox-export-{}", Uuid::new_v4()));
+        let core = core_with_analyses(4).await;
+        core.set_retention_policy(crate::retention::RetentionPolicy {
+            max_entries: Some(1),
+            spill_dir: Some(dir.join("spill")),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+        let path = dir.join("analyses.ndjson");
+        let progress = core.export_to_file("default", &path, ExportFilter { include_benign: true, ..Default::default() }).await.unwrap();
+        assert_eq!(progress.exported, 4);
+        let written = fs::read_to_string(&path).unwrap();
+        assert_eq!(written.lines().count(), 4);
+        assert_eq!(progress.bytes_written, written.len() as u64);
+        for line in written.lines() {
+            let analysis: SandboxAnalysis = serde_json::from_str(line).unwrap();
+            assert_eq!(analysis.tenant_id, "default");
+        }
+        assert!(!path.with_extension("ndjson.partial").exists());
+
+        let none = ExportFilter { include_benign: true, verdicts: vec!["NoSuchVerdict".to_string()], ..Default::default() };
+        let progress = core.export_to_file("default", &path, none).await.unwrap();
+        assert_eq!(progress.exported, 0);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,192 @@
+//! Field selection for analysis payloads
+//!
+//! A completed `SandboxAnalysis` serializes to hundreds of kilobytes, most
+//! of which a dashboard never reads. Callers of `get_analysis` can name the
+//! parts they want as a comma-separated list of dotted paths
+//! (`verdict,network_analysis.dns_queries`) and presets (`summary`,
+//! `network`, `behavior`, `static`, `full`); everything else is pruned from
+//! the serialized document before it crosses NAPI. A path through an array
+//! selects from each of its elements, so `iocs_extracted.value` returns the
+//! list of IOC values. The first segment of every path must be a field of
+//! the analysis; deeper segments that are absent are simply left out.
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use crate::error::{CoreError, CoreResult};
+use crate::SandboxCore;
+
+/// Presets and the paths they expand to; `full` selects everything
+pub const PRESETS: &[(&str, &[&str])] = &[
+    (
+        "summary",
+        &[
+            "analysis_id",
+            "tenant_id",
+            "sample_info.sample_id",
+            "sample_info.file_name",
+            "sample_info.file_hash_sha256",
+            "sample_info.file_size",
+            "sample_info.file_type",
+            "sample_info.tags",
+            "analysis_metadata.analysis_start",
+            "analysis_metadata.analysis_duration",
+            "verdict",
+            "confidence_score",
+            "threat_level",
+            "malware_classification.family",
+            "malware_classification.category",
+            "mitre_techniques.technique_id",
+        ],
+    ),
+    ("network", &["analysis_id", "verdict", "network_analysis", "decoy_interactions", "iocs_extracted"]),
+    (
+        "behavior",
+        &[
+            "analysis_id",
+            "verdict",
+            "behavioral_analysis",
+            "process_analysis",
+            "file_system_analysis",
+            "registry_analysis",
+            "evasion_techniques",
+            "mitre_techniques",
+        ],
+    ),
+    ("static", &["analysis_id", "verdict", "sample_info", "static_analysis"]),
+];
+
+#[derive(Debug, Default)]
+enum Selection {
+    #[default]
+    All,
+    Fields(BTreeMap<String, Selection>),
+}
+
+impl Selection {
+    fn insert(&mut self, path: &[&str]) {
+        let Some((first, rest)) = path.split_first() else {
+            *self = Selection::All;
+            return;
+        };
+        let fields = match self {
+            Selection::Fields(fields) => fields,
+            // Already selected whole
+            Selection::All => return,
+        };
+        let child = fields.entry(first.to_string()).or_insert_with(|| Selection::Fields(BTreeMap::new()));
+        child.insert(rest);
+    }
+
+    fn prune(&self, value: &Value) -> Value {
+        match (self, value) {
+            (Selection::All, _) => value.clone(),
+            (Selection::Fields(fields), Value::Object(object)) => Value::Object(
+                fields
+                    .iter()
+                    .filter_map(|(name, selection)| object.get(name).map(|child| (name.clone(), selection.prune(child))))
+                    .collect::<Map<String, Value>>(),
+            ),
+            (Selection::Fields(_), Value::Array(items)) => Value::Array(items.iter().map(|item| self.prune(item)).collect()),
+            // The path goes deeper than the data does
+            (Selection::Fields(_), scalar) => scalar.clone(),
+        }
+    }
+}
+
+/// Parsed field selection; the default selects everything
+#[derive(Debug, Default)]
+pub struct FieldSelection {
+    selection: Selection,
+}
+
+impl FieldSelection {
+    /// Parse a comma-separated list of dotted paths and preset names;
+    /// none or an empty list selects everything
+    pub fn parse(spec: Option<&str>) -> CoreResult<Self> {
+        let entries: Vec<&str> =
+            spec.unwrap_or_default().split(',').map(str::trim).filter(|entry| !entry.is_empty()).collect();
+        if entries.is_empty() || entries.contains(&"full") {
+            return Ok(Self::default());
+        }
+        let mut selection = Selection::Fields(BTreeMap::new());
+        for entry in entries {
+            let paths = match PRESETS.iter().find(|(name, _)| *name == entry) {
+                Some((_, paths)) => paths.to_vec(),
+                None => vec![entry],
+            };
+            for path in paths {
+                let parts: Vec<&str> = path.split('.').collect();
+                if parts.iter().any(|part| part.is_empty()) {
+                    return Err(CoreError::validation(format!("Invalid field path: {}", path)));
+                }
+                selection.insert(&parts);
+            }
+        }
+        Ok(Self { selection })
+    }
+
+    pub fn is_full(&self) -> bool {
+        matches!(self.selection, Selection::All)
+    }
+
+    /// Serialize `record` and prune it to the selection
+    pub fn apply<T: Serialize>(&self, record: &T) -> CoreResult<Value> {
+        let value = serde_json::to_value(record).map_err(|e| CoreError::backend(e.to_string()))?;
+        if let (Selection::Fields(fields), Value::Object(object)) = (&self.selection, &value) {
+            if let Some(unknown) = fields.keys().find(|name| !object.contains_key(*name)) {
+                return Err(CoreError::validation(format!("Unknown analysis field: {}", unknown)));
+            }
+        }
+        Ok(self.selection.prune(&value))
+    }
+}
+
+impl SandboxCore {
+    /// The tenant's analysis of `sample_id` cut down to `selection`
+    pub async fn get_analysis_fields(&self, tenant_id: &str, sample_id: &str, selection: &FieldSelection) -> CoreResult<Option<Value>> {
+        match self.get_analysis(tenant_id, sample_id).await? {
+            Some(analysis) => selection.apply(&analysis).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn selection_prunes_nested_paths_and_arrays() {
+        let analysis = json!({
+            "analysis_id": "a1",
+            "verdict": "Malicious",
+            "sample_info": { "sample_id": "s1", "file_name": "x.exe", "file_size": 10 },
+            "network_analysis": { "dns_queries": ["evil.example"], "http_requests": [{ "url": "http://x", "body": "..." }] },
+            "iocs_extracted": [{ "ioc_type": "domain", "value": "evil.example", "context": "dns" }],
+            "detonation": null
+        });
+
+        let selection = FieldSelection::parse(Some("verdict, sample_info.file_name,iocs_extracted.value,detonation.driver")).unwrap();
+        assert_eq!(
+            selection.apply(&analysis).unwrap(),
+            json!({
+                "verdict": "Malicious",
+                "sample_info": { "file_name": "x.exe" },
+                "iocs_extracted": [{ "value": "evil.example" }],
+                "detonation": null
+            })
+        );
+
+        // A whole subtree wins over a narrower path into it
+        let selection = FieldSelection::parse(Some("network_analysis.dns_queries,network_analysis")).unwrap();
+        assert_eq!(selection.apply(&analysis).unwrap()["network_analysis"], analysis["network_analysis"]);
+
+        assert!(FieldSelection::parse(Some("summary,full")).unwrap().is_full());
+        assert!(FieldSelection::parse(None).unwrap().is_full());
+        assert!(FieldSelection::parse(Some("sample_info..file_name")).is_err());
+        assert!(FieldSelection::parse(Some("verdikt")).unwrap().apply(&analysis).is_err());
+    }
+}
@@ -0,0 +1,794 @@
+//! Fuzzy hashing and sample families
+//!
+//! Every submission gets an ssdeep (context-triggered piecewise) digest and a
+//! TLSH digest next to its cryptographic hashes. Unlike SHA-256 these stay
+//! close when a build is repacked or lightly patched, so
+//! `find_similar_samples` can rank a tenant's other samples by how related
+//! they are. Clustering links every pair scoring at or above the family
+//! threshold and names each connected group after the family most of its
+//! labelled members already carry, or `cluster-<sha256 prefix>` of its
+//! earliest member when none is labelled. That name replaces the placeholder
+//! "Generic" family on the members' analyses, and new analyses inherit the
+//! family of their closest clustered neighbour. Clustering runs on demand or
+//! periodically in the background.
+
+use chrono::{DateTime, Utc};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+use tokio::sync::{watch, Mutex as AsyncMutex};
+use tokio::task::JoinHandle;
+
+use crate::error::{CoreError, CoreResult};
+use crate::{SampleInfo, SandboxCore, SandboxCoreNapi};
+
+/// Family the classifier assigns before clustering knows better
+pub(crate) const GENERIC_FAMILY: &str = "Generic";
+/// Prefix of the names given to clusters without a labelled member
+const CLUSTER_PREFIX: &str = "cluster-";
+/// Default minimum score for `find_similar_samples`
+pub const DEFAULT_SIMILARITY_THRESHOLD: u32 = 50;
+/// Default minimum score for two samples to share a family
+pub const DEFAULT_FAMILY_THRESHOLD: u32 = 70;
+/// TLSH distance treated as unrelated (score 0)
+const TLSH_UNRELATED_DISTANCE: u32 = 200;
+
+const ROLLING_WINDOW: usize = 7;
+const MIN_BLOCKSIZE: u32 = 3;
+const SPAMSUM_LENGTH: usize = 64;
+const HASH_PRIME: u32 = 0x0100_0193;
+const HASH_INIT: u32 = 0x2802_1967;
+const B64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[derive(Default)]
+struct RollingHash {
+    window: [u8; ROLLING_WINDOW],
+    h1: u32,
+    h2: u32,
+    h3: u32,
+    n: usize,
+}
+
+impl RollingHash {
+    fn update(&mut self, c: u8) -> u32 {
+        self.h2 = self.h2.wrapping_sub(self.h1).wrapping_add(ROLLING_WINDOW as u32 * c as u32);
+        self.h1 = self.h1.wrapping_add(c as u32).wrapping_sub(self.window[self.n % ROLLING_WINDOW] as u32);
+        self.window[self.n % ROLLING_WINDOW] = c;
+        self.n += 1;
+        self.h3 = (self.h3 << 5) ^ c as u32;
+        self.h1.wrapping_add(self.h2).wrapping_add(self.h3)
+    }
+}
+
+fn sum_hash(c: u8, h: u32) -> u32 {
+    h.wrapping_mul(HASH_PRIME) ^ c as u32
+}
+
+/// ssdeep digest of `data` as `blocksize:hash:hash`; `None` for empty input
+pub fn ssdeep(data: &[u8]) -> Option<String> {
+    if data.is_empty() {
+        return None;
+    }
+    let mut block_size = MIN_BLOCKSIZE;
+    while (block_size as usize) * SPAMSUM_LENGTH < data.len() {
+        block_size *= 2;
+    }
+    loop {
+        let mut roll = RollingHash::default();
+        let (mut h1, mut h2) = (HASH_INIT, HASH_INIT);
+        let (mut sig1, mut sig2) = (String::new(), String::new());
+        for &c in data {
+            h1 = sum_hash(c, h1);
+            h2 = sum_hash(c, h2);
+            let rolled = roll.update(c);
+            if rolled % block_size == block_size - 1 && sig1.len() < SPAMSUM_LENGTH - 1 {
+                sig1.push(B64[(h1 % 64) as usize] as char);
+                h1 = HASH_INIT;
+            }
+            if rolled % (block_size * 2) == block_size * 2 - 1 && sig2.len() < SPAMSUM_LENGTH / 2 - 1 {
+                sig2.push(B64[(h2 % 64) as usize] as char);
+                h2 = HASH_INIT;
+            }
+        }
+        if h1 != HASH_INIT {
+            sig1.push(B64[(h1 % 64) as usize] as char);
+        }
+        if h2 != HASH_INIT {
+            sig2.push(B64[(h2 % 64) as usize] as char);
+        }
+        // Too few pieces to compare meaningfully; retry with smaller blocks
+        if block_size > MIN_BLOCKSIZE && sig1.len() < SPAMSUM_LENGTH / 2 {
+            block_size /= 2;
+            continue;
+        }
+        return Some(format!("{}:{}:{}", block_size, sig1, sig2));
+    }
+}
+
+/// Runs longer than three characters carry little information and are cut down
+fn eliminate_sequences(signature: &str) -> Vec<u8> {
+    let mut out: Vec<u8> = Vec::with_capacity(signature.len());
+    for &c in signature.as_bytes() {
+        if out.len() >= 3 && out[out.len() - 3..].iter().all(|&prev| prev == c) {
+            continue;
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Edit distance with insertions and deletions costing 1 and substitutions 2
+fn edit_distance(a: &[u8], b: &[u8]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, &ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let substitute = previous[j] + if ca == cb { 0 } else { 2 };
+            current[j + 1] = substitute.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+fn score_signatures(a: &[u8], b: &[u8], block_size: u32) -> u32 {
+    // Unrelated inputs rarely share a full rolling window of pieces
+    if a.len() < ROLLING_WINDOW || b.len() < ROLLING_WINDOW || !a.windows(ROLLING_WINDOW).any(|w| b.windows(ROLLING_WINDOW).any(|v| v == w)) {
+        return 0;
+    }
+    let distance = edit_distance(a, b) * SPAMSUM_LENGTH / (a.len() + b.len());
+    let distance = (distance * 100 / SPAMSUM_LENGTH) as u32;
+    if distance >= 100 {
+        return 0;
+    }
+    let score = 100 - distance;
+    // Short signatures at small block sizes match by chance too easily
+    let uncapped_from = (99 + ROLLING_WINDOW as u32) / ROLLING_WINDOW as u32 * MIN_BLOCKSIZE;
+    if block_size >= uncapped_from {
+        return score;
+    }
+    score.min(block_size / MIN_BLOCKSIZE * a.len().min(b.len()) as u32)
+}
+
+fn parse_ssdeep(digest: &str) -> Option<(u32, &str, &str)> {
+    let mut parts = digest.splitn(3, ':');
+    let block_size = parts.next()?.parse().ok()?;
+    Some((block_size, parts.next()?, parts.next()?))
+}
+
+/// ssdeep match score from 0 (unrelated) to 100 (identical); `None` for malformed digests
+pub fn ssdeep_compare(a: &str, b: &str) -> Option<u32> {
+    let (bs_a, a1, a2) = parse_ssdeep(a)?;
+    let (bs_b, b1, b2) = parse_ssdeep(b)?;
+    if bs_a != bs_b && bs_a != bs_b * 2 && bs_b != bs_a * 2 {
+        return Some(0);
+    }
+    let (a1, a2, b1, b2) = (eliminate_sequences(a1), eliminate_sequences(a2), eliminate_sequences(b1), eliminate_sequences(b2));
+    if bs_a == bs_b && a1 == b1 && !a1.is_empty() {
+        return Some(100);
+    }
+    Some(if bs_a == bs_b {
+        score_signatures(&a1, &b1, bs_a).max(score_signatures(&a2, &b2, bs_a * 2))
+    } else if bs_a == bs_b * 2 {
+        score_signatures(&a1, &b2, bs_a)
+    } else {
+        score_signatures(&a2, &b1, bs_b)
+    })
+}
+
+/// Pearson permutation used by TLSH
+const V_TABLE: [u8; 256] = [
+    1, 87, 49, 12, 176, 178, 102, 166, 121, 193, 6, 84, 249, 230, 44, 163, 14, 197, 213, 181, 161, 85, 218, 80, 64, 239, 24, 226, 236, 142, 38, 200,
+    110, 177, 104, 103, 141, 253, 255, 50, 77, 101, 81, 18, 45, 96, 31, 222, 25, 107, 190, 70, 86, 237, 240, 34, 72, 242, 20, 214, 244, 227, 149, 235,
+    97, 234, 57, 22, 60, 250, 82, 175, 208, 5, 127, 199, 111, 62, 135, 248, 174, 169, 211, 58, 66, 154, 106, 195, 245, 171, 17, 187, 182, 179, 0, 243,
+    132, 56, 148, 75, 128, 133, 158, 100, 130, 126, 91, 13, 153, 246, 216, 219, 119, 68, 223, 78, 83, 88, 201, 99, 122, 11, 92, 32, 136, 114, 52, 10,
+    138, 30, 48, 183, 156, 35, 61, 26, 143, 74, 251, 94, 129, 162, 63, 152, 170, 7, 115, 167, 241, 206, 3, 150, 55, 59, 151, 220, 90, 53, 23, 131,
+    125, 173, 15, 238, 79, 95, 89, 16, 105, 137, 225, 224, 217, 160, 37, 123, 118, 73, 2, 157, 46, 116, 9, 145, 134, 228, 207, 212, 202, 215, 69, 229,
+    27, 188, 67, 124, 168, 252, 42, 4, 29, 108, 21, 247, 19, 205, 39, 203, 233, 40, 186, 147, 198, 192, 155, 33, 164, 191, 98, 204, 165, 180, 117, 76,
+    140, 36, 210, 172, 41, 54, 159, 8, 185, 232, 113, 196, 231, 47, 146, 120, 51, 65, 28, 144, 254, 221, 93, 189, 194, 139, 112, 43, 71, 109, 184, 209,
+];
+
+const TLSH_MIN_LENGTH: usize = 50;
+const TLSH_BUCKETS: usize = 128;
+const TLSH_CODE_SIZE: usize = TLSH_BUCKETS / 4;
+
+fn pearson(salt: u8, a: u8, b: u8, c: u8) -> u8 {
+    let h = V_TABLE[salt as usize];
+    let h = V_TABLE[(h ^ a) as usize];
+    let h = V_TABLE[(h ^ b) as usize];
+    V_TABLE[(h ^ c) as usize]
+}
+
+/// Log-scale bucket of the input length
+fn length_capture(len: usize) -> u8 {
+    let len = len as f64;
+    let value = if len <= 656.0 {
+        len.ln() / 1.5f64.ln()
+    } else if len <= 3199.0 {
+        len.ln() / 1.3f64.ln() - 8.72777
+    } else {
+        len.ln() / 1.1f64.ln() - 62.5472
+    };
+    (value.floor() as u32 & 0xff) as u8
+}
+
+fn swap_nibbles(byte: u8) -> u8 {
+    byte.rotate_left(4)
+}
+
+/// TLSH digest of `data` (`T1` + 70 hex digits); `None` below 50 bytes or
+/// when the input has too little variation to fill half the buckets
+pub fn tlsh(data: &[u8]) -> Option<String> {
+    if data.len() < TLSH_MIN_LENGTH {
+        return None;
+    }
+    let mut buckets = [0u32; 256];
+    let mut checksum = 0u8;
+    for window in data.windows(5) {
+        let (a4, a3, a2, a1, a0) = (window[0], window[1], window[2], window[3], window[4]);
+        checksum = pearson(0, a0, a1, checksum);
+        for (salt, x, y) in [(2, a1, a2), (3, a1, a3), (5, a2, a3), (7, a2, a4), (11, a1, a4), (13, a3, a4)] {
+            buckets[pearson(salt, a0, x, y) as usize] += 1;
+        }
+    }
+
+    let counted = &buckets[..TLSH_BUCKETS];
+    if counted.iter().filter(|&&count| count > 0).count() <= TLSH_BUCKETS / 2 {
+        return None;
+    }
+    let mut sorted = counted.to_vec();
+    sorted.sort_unstable();
+    let quarter = TLSH_BUCKETS / 4;
+    let (q1, q2, q3) = (sorted[quarter - 1], sorted[2 * quarter - 1], sorted[3 * quarter - 1]);
+    if q3 == 0 {
+        return None;
+    }
+
+    let mut code = [0u8; TLSH_CODE_SIZE];
+    for (i, group) in counted.chunks(4).enumerate() {
+        let mut byte = 0u8;
+        for (j, &count) in group.iter().enumerate() {
+            let quartile = if count > q3 { 3 } else if count > q2 { 2 } else if count > q1 { 1 } else { 0 };
+            byte |= quartile << (j * 2);
+        }
+        code[TLSH_CODE_SIZE - 1 - i] = byte;
+    }
+    let q1_ratio = ((q1 as f64 * 100.0 / q3 as f64) as u32 % 16) as u8;
+    let q2_ratio = ((q2 as f64 * 100.0 / q3 as f64) as u32 % 16) as u8;
+
+    let mut digest = format!("T1{:02X}{:02X}{:X}{:X}", swap_nibbles(checksum), swap_nibbles(length_capture(data.len())), q1_ratio, q2_ratio);
+    for byte in code {
+        digest.push_str(&format!("{:02X}", byte));
+    }
+    Some(digest)
+}
+
+fn parse_tlsh(digest: &str) -> Option<Vec<u8>> {
+    let hex = digest.strip_prefix("T1").unwrap_or(digest);
+    if hex.len() != 2 * (3 + TLSH_CODE_SIZE) {
+        return None;
+    }
+    (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()).collect()
+}
+
+fn mod_diff(x: u32, y: u32, range: u32) -> u32 {
+    let (low, high) = if x < y { (x, y) } else { (y, x) };
+    (high - low).min(low + range - high)
+}
+
+/// TLSH distance; 0 for identical digests, growing without bound as inputs
+/// diverge (values past ~100 are effectively unrelated). `None` for
+/// malformed digests.
+pub fn tlsh_distance(a: &str, b: &str) -> Option<u32> {
+    let (a, b) = (parse_tlsh(a)?, parse_tlsh(b)?);
+    let mut distance = u32::from(a[0] != b[0]);
+    distance += match mod_diff(swap_nibbles(a[1]) as u32, swap_nibbles(b[1]) as u32, 256) {
+        0 => 0,
+        1 => 1,
+        diff => diff * 12,
+    };
+    for (x, y) in [(a[2] >> 4, b[2] >> 4), (a[2] & 0xf, b[2] & 0xf)] {
+        distance += match mod_diff(x as u32, y as u32, 16) {
+            diff @ 0..=1 => diff,
+            diff => (diff - 1) * 12,
+        };
+    }
+    for (x, y) in a[3..].iter().zip(&b[3..]) {
+        for shift in [0, 2, 4, 6] {
+            distance += match ((x >> shift) & 3).abs_diff((y >> shift) & 3) {
+                3 => 6,
+                diff => diff as u32,
+            };
+        }
+    }
+    Some(distance)
+}
+
+/// Fuzzy digests recorded for a submitted sample
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SampleDigests {
+    pub sample_id: String,
+    pub tenant_id: String,
+    pub file_name: String,
+    pub sha256: String,
+    pub ssdeep: Option<String>,
+    pub tlsh: Option<String>,
+    pub submitted_at: DateTime<Utc>,
+}
+
+/// How close two samples are; `score` is the better of the two measures
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarSample {
+    pub sample_id: String,
+    pub file_name: String,
+    pub sha256: String,
+    pub ssdeep_score: Option<u32>,
+    pub tlsh_distance: Option<u32>,
+    /// 0 (unrelated) to 100 (identical)
+    pub score: u32,
+    /// Family clustering last placed the sample in
+    pub family: Option<String>,
+}
+
+/// Samples clustering grouped together
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SampleFamily {
+    pub family: String,
+    /// Earliest submitted member
+    pub representative: String,
+    pub members: Vec<String>,
+    /// Whether the name came from a family already on a member's analysis
+    pub labelled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusteringReport {
+    pub tenant_id: String,
+    pub clustered_at: DateTime<Utc>,
+    pub threshold: u32,
+    pub samples_considered: usize,
+    pub families: Vec<SampleFamily>,
+    /// Analyses whose family was replaced
+    pub relabelled: usize,
+}
+
+struct ClusteringRun {
+    stop: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+}
+
+/// Digests of submitted samples and the families clustering derived from them
+#[derive(Default)]
+pub struct FuzzyState {
+    digests: RwLock<HashMap<String, SampleDigests>>,
+    /// sample id -> family name
+    families: RwLock<HashMap<String, String>>,
+    reports: RwLock<HashMap<String, ClusteringReport>>,
+    run: AsyncMutex<Option<ClusteringRun>>,
+}
+
+impl FuzzyState {
+    pub(crate) fn record(&self, digests: SampleDigests) {
+        self.digests.write().unwrap_or_else(|p| p.into_inner()).insert(digests.sample_id.clone(), digests);
+    }
+
+    pub(crate) fn digests_of(&self, sample_id: &str) -> Option<SampleDigests> {
+        self.digests.read().unwrap_or_else(|p| p.into_inner()).get(sample_id).cloned()
+    }
+
+    fn family_of(&self, sample_id: &str) -> Option<String> {
+        self.families.read().unwrap_or_else(|p| p.into_inner()).get(sample_id).cloned()
+    }
+
+    /// Family of the closest clustered sample scoring at least `threshold`
+    pub(crate) fn nearest_family(&self, tenant_id: &str, sample_id: &str, threshold: u32) -> Option<String> {
+        if let Some(family) = self.family_of(sample_id) {
+            return Some(family);
+        }
+        let own = self.digests_of(sample_id)?;
+        let families = self.families.read().unwrap_or_else(|p| p.into_inner());
+        let digests = self.digests.read().unwrap_or_else(|p| p.into_inner());
+        digests
+            .values()
+            .filter(|other| other.tenant_id == tenant_id && other.sample_id != sample_id)
+            .filter_map(|other| Some((compare(&own, other).2, families.get(&other.sample_id)?)))
+            .filter(|(score, _)| *score >= threshold)
+            .max_by_key(|(score, _)| *score)
+            .map(|(_, family)| family.clone())
+    }
+
+    pub(crate) fn forget_tenant(&self, tenant_id: &str) -> usize {
+        let mut digests = self.digests.write().unwrap_or_else(|p| p.into_inner());
+        let owned: Vec<String> = digests.values().filter(|d| d.tenant_id == tenant_id).map(|d| d.sample_id.clone()).collect();
+        let mut families = self.families.write().unwrap_or_else(|p| p.into_inner());
+        for sample_id in &owned {
+            digests.remove(sample_id);
+            families.remove(sample_id);
+        }
+        self.reports.write().unwrap_or_else(|p| p.into_inner()).remove(tenant_id);
+        owned.len()
+    }
+}
+
+/// ssdeep score, TLSH distance and the combined 0-100 score
+fn compare(a: &SampleDigests, b: &SampleDigests) -> (Option<u32>, Option<u32>, u32) {
+    let ssdeep_score = a.ssdeep.as_deref().zip(b.ssdeep.as_deref()).and_then(|(x, y)| ssdeep_compare(x, y));
+    let distance = a.tlsh.as_deref().zip(b.tlsh.as_deref()).and_then(|(x, y)| tlsh_distance(x, y));
+    let tlsh_score = distance.map(|d| 100 - d.min(TLSH_UNRELATED_DISTANCE) * 100 / TLSH_UNRELATED_DISTANCE);
+    let score = if a.sha256 == b.sha256 { 100 } else { ssdeep_score.unwrap_or(0).max(tlsh_score.unwrap_or(0)) };
+    (ssdeep_score, distance, score)
+}
+
+fn is_placeholder(family: &str) -> bool {
+    family == GENERIC_FAMILY || family.starts_with(CLUSTER_PREFIX)
+}
+
+fn find(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+impl SandboxCore {
+    /// Remember a submission's digests for similarity search
+    pub(crate) fn record_fuzzy_digests(&self, tenant_id: &str, sample_info: &SampleInfo) {
+        self.fuzzy.record(SampleDigests {
+            sample_id: sample_info.sample_id.clone(),
+            tenant_id: tenant_id.to_string(),
+            file_name: sample_info.file_name.clone(),
+            sha256: sample_info.file_hash_sha256.clone(),
+            ssdeep: sample_info.ssdeep.clone(),
+            tlsh: sample_info.tlsh.clone(),
+            submitted_at: sample_info.submission_time,
+        });
+    }
+
+    /// Copy the recorded digests onto the sample info of an analysis
+    pub(crate) fn attach_fuzzy_digests(&self, sample_info: &mut SampleInfo) {
+        if let Some(digests) = self.fuzzy.digests_of(&sample_info.sample_id) {
+            sample_info.ssdeep = digests.ssdeep;
+            sample_info.tlsh = digests.tlsh;
+        }
+    }
+
+    /// The tenant's digests, including samples only known from completed analyses
+    async fn tenant_digests(&self, tenant_id: &str) -> Vec<SampleDigests> {
+        let mut digests: BTreeMap<String, SampleDigests> = self
+            .fuzzy
+            .digests
+            .read()
+            .unwrap_or_else(|p| p.into_inner())
+            .values()
+            .filter(|d| d.tenant_id == tenant_id)
+            .map(|d| (d.sample_id.clone(), d.clone()))
+            .collect();
+        for (sample_id, analysis) in self.completed_analyses.read().await.iter() {
+            let info = &analysis.sample_info;
+            if analysis.tenant_id != tenant_id || digests.contains_key(sample_id) || (info.ssdeep.is_none() && info.tlsh.is_none()) {
+                continue;
+            }
+            digests.insert(sample_id.clone(), SampleDigests {
+                sample_id: sample_id.clone(),
+                tenant_id: tenant_id.to_string(),
+                file_name: info.file_name.clone(),
+                sha256: info.file_hash_sha256.clone(),
+                ssdeep: info.ssdeep.clone(),
+                tlsh: info.tlsh.clone(),
+                submitted_at: info.submission_time,
+            });
+        }
+        let mut digests: Vec<SampleDigests> = digests.into_values().collect();
+        digests.sort_by(|a, b| a.submitted_at.cmp(&b.submitted_at).then_with(|| a.sample_id.cmp(&b.sample_id)));
+        digests
+    }
+
+    /// The tenant's other samples scoring at least `threshold` against `sample_id`, best first
+    pub async fn find_similar_samples(&self, tenant_id: &str, sample_id: &str, threshold: u32) -> CoreResult<Vec<SimilarSample>> {
+        let digests = self.tenant_digests(tenant_id).await;
+        let own = digests
+            .iter()
+            .find(|d| d.sample_id == sample_id)
+            .ok_or_else(|| CoreError::not_found(format!("Sample {} not found", sample_id)))?;
+        if own.ssdeep.is_none() && own.tlsh.is_none() {
+            return Err(CoreError::validation(format!("Sample {} is too small to fuzzy hash", sample_id)));
+        }
+        let mut similar: Vec<SimilarSample> = digests
+            .iter()
+            .filter(|other| other.sample_id != sample_id)
+            .filter_map(|other| {
+                let (ssdeep_score, tlsh_distance, score) = compare(own, other);
+                (score >= threshold).then(|| SimilarSample {
+                    sample_id: other.sample_id.clone(),
+                    file_name: other.file_name.clone(),
+                    sha256: other.sha256.clone(),
+                    ssdeep_score,
+                    tlsh_distance,
+                    score,
+                    family: self.fuzzy.family_of(&other.sample_id),
+                })
+            })
+            .collect();
+        similar.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.sample_id.cmp(&b.sample_id)));
+        Ok(similar)
+    }
+
+    /// Group the tenant's samples into families and relabel their analyses
+    pub async fn cluster_samples(&self, tenant_id: &str, threshold: u32) -> CoreResult<ClusteringReport> {
+        let digests = self.tenant_digests(tenant_id).await;
+        let mut parent: Vec<usize> = (0..digests.len()).collect();
+        for i in 0..digests.len() {
+            for j in i + 1..digests.len() {
+                if compare(&digests[i], &digests[j]).2 >= threshold {
+                    let (root_i, root_j) = (find(&mut parent, i), find(&mut parent, j));
+                    // Keep the earlier sample as the root so it represents the group
+                    parent[root_j.max(root_i)] = root_i.min(root_j);
+                }
+            }
+        }
+        let mut groups: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for i in 0..digests.len() {
+            let root = find(&mut parent, i);
+            groups.entry(root).or_default().push(i);
+        }
+
+        let mut analyses = self.completed_analyses.write().await;
+        let mut families = Vec::new();
+        let mut assignments = HashMap::new();
+        for (root, members) in groups.into_iter().filter(|(_, members)| members.len() > 1) {
+            let mut votes: BTreeMap<String, usize> = BTreeMap::new();
+            for &i in &members {
+                let label = analyses.get(&digests[i].sample_id).and_then(|a| a.malware_classification.family.clone());
+                if let Some(label) = label.filter(|label| !label.is_empty() && !is_placeholder(label)) {
+                    *votes.entry(label).or_default() += 1;
+                }
+            }
+            // Most votes wins; ties go to the alphabetically first name
+            let labelled = votes.iter().max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0))).map(|(name, _)| name.clone());
+            let family = labelled.clone().unwrap_or_else(|| {
+                let sha256 = &digests[root].sha256;
+                format!("{}{}", CLUSTER_PREFIX, &sha256[..sha256.len().min(12)])
+            });
+            for &i in &members {
+                assignments.insert(digests[i].sample_id.clone(), family.clone());
+            }
+            families.push(SampleFamily {
+                family,
+                representative: digests[root].sample_id.clone(),
+                members: members.iter().map(|&i| digests[i].sample_id.clone()).collect(),
+                labelled: labelled.is_some(),
+            });
+        }
+
+        let mut relabelled = Vec::new();
+        for (sample_id, family) in &assignments {
+            let Some(analysis) = analyses.get_mut(sample_id) else { continue };
+            let classification = &mut analysis.malware_classification;
+            if classification.family.as_deref().is_some_and(|current| is_placeholder(current) && current != family) {
+                classification.family = Some(family.clone());
+                relabelled.push(analysis.clone());
+            }
+        }
+        drop(analyses);
+        for analysis in &relabelled {
+            self.persist_analysis(&analysis.sample_info.sample_id, analysis)?;
+        }
+
+        {
+            let mut known = self.fuzzy.families.write().unwrap_or_else(|p| p.into_inner());
+            known.retain(|sample_id, _| !digests.iter().any(|d| &d.sample_id == sample_id));
+            known.extend(assignments);
+        }
+        let report = ClusteringReport {
+            tenant_id: tenant_id.to_string(),
+            clustered_at: Utc::now(),
+            threshold,
+            samples_considered: digests.len(),
+            families,
+            relabelled: relabelled.len(),
+        };
+        self.fuzzy.reports.write().unwrap_or_else(|p| p.into_inner()).insert(tenant_id.to_string(), report.clone());
+        Ok(report)
+    }
+
+    /// Families from the tenant's last clustering run
+    pub fn get_sample_families(&self, tenant_id: &str) -> Option<ClusteringReport> {
+        self.fuzzy.reports.read().unwrap_or_else(|p| p.into_inner()).get(tenant_id).cloned()
+    }
+
+    /// Re-cluster every tenant's samples every `interval_secs`
+    pub async fn start_sample_clustering(self: &Arc<Self>, interval_secs: u64, threshold: u32) -> CoreResult<()> {
+        let mut current = self.fuzzy.run.lock().await;
+        if current.as_ref().is_some_and(|run| !run.handle.is_finished()) {
+            return Err(CoreError::validation("Sample clustering is already running"));
+        }
+        let (stop, mut stopped) = watch::channel(false);
+        let core = Arc::clone(self);
+        let handle = tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(std::time::Duration::from_secs(interval_secs.max(1)));
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                tokio::select! {
+                    _ = ticks.tick() => {
+                        let mut tenants: Vec<String> = core.fuzzy.digests.read().unwrap_or_else(|p| p.into_inner()).values().map(|d| d.tenant_id.clone()).collect();
+                        tenants.sort();
+                        tenants.dedup();
+                        for tenant_id in tenants {
+                            if let Err(e) = core.cluster_samples(&tenant_id, threshold).await {
+                                log::warn!("Sample clustering failed for tenant {}: {}", tenant_id, e);
+                            }
+                        }
+                    }
+                    changed = stopped.changed() => {
+                        if changed.is_err() || *stopped.borrow() {
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+        *current = Some(ClusteringRun { stop, handle });
+        log::info!("Sample clustering started every {}s at threshold {}", interval_secs.max(1), threshold);
+        Ok(())
+    }
+
+    /// Stop periodic clustering; returns false if it was not running
+    pub async fn stop_sample_clustering(&self) -> bool {
+        let Some(run) = self.fuzzy.run.lock().await.take() else {
+            return false;
+        };
+        let _ = run.stop.send(true);
+        let _ = run.handle.await;
+        true
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// Samples of the caller's tenant similar to `sample_id`, scored 0-100 (default threshold 50)
+    #[napi]
+    pub async fn find_similar_samples(&self, sample_id: String, threshold: Option<u32>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let similar = self.inner.find_similar_samples(&tenant_id, &sample_id, threshold.unwrap_or(DEFAULT_SIMILARITY_THRESHOLD)).await
+            .map_err(|e| e.context("Failed to find similar samples"))?;
+        serde_json::to_string(&similar)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize similar samples: {}", e)))
+    }
+
+    /// Cluster the caller's samples into families now (default threshold 70)
+    #[napi]
+    pub async fn cluster_samples(&self, threshold: Option<u32>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", "sample_clustering")?;
+        let threshold = threshold.unwrap_or(DEFAULT_FAMILY_THRESHOLD);
+        let report = self.inner.cluster_samples(&tenant_id, threshold).await;
+        let report = self.audit.record(&actor, "cluster_samples", &tenant_id, serde_json::json!({ "threshold": threshold }), report)
+            .map_err(|e| e.context("Failed to cluster samples"))?;
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize clustering report: {}", e)))
+    }
+
+    /// Families from the caller's last clustering run, or null before the first
+    #[napi]
+    pub fn get_sample_families(&self, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        self.inner
+            .get_sample_families(&tenant_id)
+            .map(|report| serde_json::to_string(&report))
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize clustering report: {}", e)))
+    }
+
+    /// Re-cluster all tenants' samples every `interval_secs` (default hourly)
+    #[napi]
+    pub async fn start_sample_clustering(&self, interval_secs: Option<u32>, threshold: Option<u32>, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize_platform(auth_token, "sample_clustering")?;
+        let interval_secs = interval_secs.unwrap_or(3600);
+        let threshold = threshold.unwrap_or(DEFAULT_FAMILY_THRESHOLD);
+        let started = self.inner.start_sample_clustering(interval_secs as u64, threshold).await;
+        self.audit.record(&actor, "start_sample_clustering", "fuzzy", serde_json::json!({ "interval_secs": interval_secs, "threshold": threshold }), started)
+            .map_err(|e| e.context("Failed to start sample clustering"))?;
+        Ok(())
+    }
+
+    #[napi]
+    pub async fn stop_sample_clustering(&self, auth_token: Option<String>) -> napi::Result<bool> {
+        let actor = self.authorize_platform(auth_token, "sample_clustering")?;
+        let stopped = self.inner.stop_sample_clustering().await;
+        self.audit.record(&actor, "stop_sample_clustering", "fuzzy", serde_json::json!({}), Ok::<_, String>(stopped))
+            .map_err(napi::Error::from_reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AnalysisPriority;
+
+    /// Deterministic pseudo-random bytes standing in for a packed payload
+    fn payload(seed: u32, len: usize) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(2_654_435_761).max(1);
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect()
+    }
+
+    fn patched(data: &[u8], offset: usize) -> Vec<u8> {
+        let mut data = data.to_vec();
+        data[offset..offset + 16].copy_from_slice(b"patched-c2-host!");
+        data
+    }
+
+    #[test]
+    fn digests_track_small_edits() {
+        assert_eq!(V_TABLE.iter().map(|&v| v as usize).sum::<usize>(), (0..256).sum::<usize>());
+        let base = payload(7, 8192);
+        let variant = patched(&base, 4000);
+        let unrelated = payload(99, 8192);
+
+        let (a, b, c) = (ssdeep(&base).unwrap(), ssdeep(&variant).unwrap(), ssdeep(&unrelated).unwrap());
+        assert_eq!(ssdeep_compare(&a, &a), Some(100));
+        assert!(ssdeep_compare(&a, &b).unwrap() >= 70, "{} vs {}", a, b);
+        assert_eq!(ssdeep_compare(&a, &c), Some(0));
+
+        let (a, b, c) = (tlsh(&base).unwrap(), tlsh(&variant).unwrap(), tlsh(&unrelated).unwrap());
+        assert_eq!(a.len(), 72);
+        assert_eq!(tlsh_distance(&a, &a), Some(0));
+        assert!(tlsh_distance(&a, &b).unwrap() < tlsh_distance(&a, &c).unwrap());
+        assert!(tlsh(&[0u8; 40]).is_none());
+        assert!(tlsh(&[0u8; 4096]).is_none());
+    }
+
+    #[tokio::test]
+    async fn variants_cluster_into_a_family() {
+        let core = Arc::new(SandboxCore::new().unwrap());
+        let base = payload(7, 8192);
+        let mut samples = Vec::new();
+        for (bytes, name) in [(base.clone(), "loader.exe"), (patched(&base, 1000), "loader_v2.exe"), (payload(99, 8192), "other.exe")] {
+            samples.push(core.submit_sample("acme", &bytes, name.to_string(), AnalysisPriority::Normal, vec![], false).await.unwrap());
+            core.process_queue().await.unwrap();
+        }
+        let analysis = core.get_analysis("acme", &samples[0]).await.unwrap().unwrap();
+        assert!(analysis.sample_info.ssdeep.is_some() && analysis.sample_info.tlsh.is_some());
+
+        let similar = core.find_similar_samples("acme", &samples[0], DEFAULT_SIMILARITY_THRESHOLD).await.unwrap();
+        assert_eq!(similar.iter().map(|s| s.sample_id.as_str()).collect::<Vec<_>>(), [samples[1].as_str()]);
+        assert_eq!(core.find_similar_samples("globex", &samples[0], 0).await.unwrap_err().code(), "NOT_FOUND");
+
+        let report = core.cluster_samples("acme", DEFAULT_FAMILY_THRESHOLD).await.unwrap();
+        assert_eq!(report.families.len(), 1);
+        assert_eq!(report.families[0].representative, samples[0]);
+        assert_eq!(report.relabelled, 2);
+        let family = report.families[0].family.clone();
+        assert!(family.starts_with(CLUSTER_PREFIX));
+        let relabelled = core.get_analysis("acme", &samples[1]).await.unwrap().unwrap();
+        assert_eq!(relabelled.malware_classification.family.as_deref(), Some(family.as_str()));
+        let other = core.get_analysis("acme", &samples[2]).await.unwrap().unwrap();
+        assert_eq!(other.malware_classification.family.as_deref(), Some(GENERIC_FAMILY));
+
+        // A known family on one member names the whole cluster
+        core.completed_analyses.write().await.get_mut(&samples[1]).unwrap().malware_classification.family = Some("EmberLoader".to_string());
+        let report = core.cluster_samples("acme", DEFAULT_FAMILY_THRESHOLD).await.unwrap();
+        assert!(report.families[0].labelled);
+        assert_eq!(core.get_analysis("acme", &samples[0]).await.unwrap().unwrap().malware_classification.family.as_deref(), Some("EmberLoader"));
+
+        // New variants inherit the family when analysed
+        let third = core.submit_sample("acme", &patched(&base, 6000), "loader_v3.exe".to_string(), AnalysisPriority::Normal, vec![], false).await.unwrap();
+        core.process_queue().await.unwrap();
+        let inherited = core.get_analysis("acme", &third).await.unwrap().unwrap();
+        assert_eq!(inherited.malware_classification.family.as_deref(), Some("EmberLoader"));
+
+        core.start_sample_clustering(3600, DEFAULT_FAMILY_THRESHOLD).await.unwrap();
+        assert!(core.start_sample_clustering(3600, DEFAULT_FAMILY_THRESHOLD).await.is_err());
+        assert!(core.stop_sample_clustering().await);
+        assert!(!core.stop_sample_clustering().await);
+    }
+}
@@ -0,0 +1,212 @@
+//! Elasticsearch Indexing
+//!
+//! With `phantom-enterprise-standards` enabled and a cluster configured, the
+//! summary of every completed analysis is bulk indexed into monthly
+//! `<prefix>-sandbox-YYYY.MM` indices for Kibana dashboards. Documents are
+//! buffered and sent in the background, so analysis throughput does not
+//! depend on the cluster. Summaries of every tenant go to the same cluster,
+//! so only platform administrators can configure it. Live HTTP needs the
+//! `es-indexing` feature.
+
+use crate::{SandboxAnalysis, SandboxCore};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::SandboxCoreNapi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use napi_derive::napi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::connectors::HttpTransport;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::elastic::{BulkIndexer, ElasticConfig, IndexerMetrics};
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+pub const SANDBOX_STREAM: &str = "sandbox";
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[derive(Default)]
+pub struct IndexingState {
+    indexer: RwLock<Option<Arc<BulkIndexer>>>,
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+impl IndexingState {
+    pub fn indexer(&self) -> Option<Arc<BulkIndexer>> {
+        self.indexer.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Field mappings of the sandbox index template
+#[cfg(feature = "phantom-enterprise-standards")]
+pub fn sandbox_mappings() -> serde_json::Value {
+    serde_json::json!({
+        "@timestamp": { "type": "date" },
+        "tenant_id": { "type": "keyword" },
+        "sample_id": { "type": "keyword" },
+        "file_name": { "type": "keyword" },
+        "file_hash_sha256": { "type": "keyword" },
+        "verdict": { "type": "keyword" },
+        "threat_level": { "type": "keyword" },
+        "confidence_score": { "type": "float" },
+        "analysis_duration": { "type": "long" },
+        "malware_family": { "type": "keyword" },
+        "iocs_count": { "type": "integer" },
+        "mitre_techniques": { "type": "keyword" }
+    })
+}
+
+/// Indexed document for a completed analysis
+#[cfg(feature = "phantom-enterprise-standards")]
+pub fn analysis_document(analysis: &SandboxAnalysis) -> serde_json::Value {
+    let mut document = crate::export::summary_record(analysis);
+    document["@timestamp"] = serde_json::json!(analysis.analysis_metadata.analysis_end);
+    document["tenant_id"] = serde_json::json!(analysis.tenant_id);
+    document
+}
+
+impl SandboxCore {
+    /// Queue the summary of a completed analysis for Elasticsearch
+    pub(crate) fn index_analysis_summary(&self, analysis: &SandboxAnalysis) {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        if let Some(indexer) = self.indexing.indexer() {
+            let id = format!("{}:{}", analysis.tenant_id, analysis.sample_info.sample_id);
+            if indexer.enqueue(SANDBOX_STREAM, &id, analysis.analysis_metadata.analysis_end, analysis_document(analysis)) {
+                tokio::spawn(async move {
+                    indexer.flush().await;
+                });
+            }
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        let _ = analysis;
+    }
+
+    /// Start indexing into the cluster in `config`, or stop with `None`.
+    /// Summaries still buffered for a replaced cluster are discarded.
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub async fn configure_elasticsearch(&self, config: Option<ElasticConfig>) -> Result<Option<IndexerMetrics>, String> {
+        match config {
+            Some(config) => self.install_indexer(BulkIndexer::connect(config)?).await.map(Some),
+            None => {
+                *self.indexing.indexer.write().unwrap_or_else(|e| e.into_inner()) = None;
+                Ok(None)
+            }
+        }
+    }
+
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub async fn configure_elasticsearch_with_transport(&self, config: ElasticConfig, transport: Arc<dyn HttpTransport>) -> Result<IndexerMetrics, String> {
+        self.install_indexer(BulkIndexer::new(config, transport)?).await
+    }
+
+    #[cfg(feature = "phantom-enterprise-standards")]
+    async fn install_indexer(&self, indexer: BulkIndexer) -> Result<IndexerMetrics, String> {
+        indexer.register_stream(SANDBOX_STREAM, sandbox_mappings());
+        indexer.install_templates().await?;
+        let indexer = Arc::new(indexer);
+        indexer.spawn_flusher();
+        let metrics = indexer.metrics();
+        *self.indexing.indexer.write().unwrap_or_else(|e| e.into_inner()) = Some(indexer);
+        Ok(metrics)
+    }
+
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn elasticsearch_indexer(&self) -> Option<Arc<BulkIndexer>> {
+        self.indexing.indexer()
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[napi]
+impl SandboxCoreNapi {
+    /// Bulk index analysis summaries into Elasticsearch; installs the index
+    /// template first. Pass no config to stop indexing
+    #[napi]
+    pub async fn configure_elasticsearch_indexing(&self, config_json: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, "elasticsearch")?;
+        let config: Option<ElasticConfig> = config_json
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse Elasticsearch config: {}", e)))?;
+        let details = serde_json::json!({ "config": config.as_ref().map(ElasticConfig::redacted) });
+
+        let metrics = self.inner.configure_elasticsearch(config).await;
+        let metrics = self.audit.record(&actor, "configure_elasticsearch_indexing", "elasticsearch", details, metrics)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to configure Elasticsearch indexing: {}", e)))?;
+
+        serde_json::to_string(&metrics)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize indexing metrics: {}", e)))
+    }
+
+    /// Indexing counters; `null` when not configured
+    #[napi]
+    pub fn get_elasticsearch_indexing_metrics(&self) -> napi::Result<String> {
+        let metrics = self.inner.elasticsearch_indexer().map(|indexer| indexer.metrics());
+        serde_json::to_string(&metrics)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize indexing metrics: {}", e)))
+    }
+
+    /// Send buffered summaries now and report the outcome
+    #[napi]
+    pub async fn flush_elasticsearch_indexing(&self) -> napi::Result<String> {
+        let Some(indexer) = self.inner.elasticsearch_indexer() else {
+            return Err(napi::Error::from_reason("Elasticsearch indexing is not configured"));
+        };
+        let report = indexer.flush().await;
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize indexing report: {}", e)))
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use super::*;
+    use crate::AnalysisPriority;
+    use async_trait::async_trait;
+    use phantom_enterprise_standards::connectors::{ConnectorError, ConnectorRequest, ConnectorResponse};
+    use std::sync::Mutex;
+
+    /// Accepts every document and keeps the request bodies
+    #[derive(Default)]
+    struct FakeCluster {
+        requests: Mutex<Vec<ConnectorRequest>>,
+    }
+
+    #[async_trait]
+    impl HttpTransport for FakeCluster {
+        async fn send(&self, request: &ConnectorRequest) -> Result<ConnectorResponse, ConnectorError> {
+            self.requests.lock().unwrap().push(request.clone());
+            let lines = request.body.as_deref().unwrap_or_default().lines().count();
+            let items: Vec<_> = (0..lines / 2).map(|_| serde_json::json!({ "index": { "status": 201 } })).collect();
+            Ok(ConnectorResponse {
+                status: 200,
+                headers: Default::default(),
+                body: serde_json::json!({ "errors": false, "items": items }).to_string(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn completed_analyses_are_indexed_monthly() {
+        let core = SandboxCore::new().unwrap();
+        let cluster = Arc::new(FakeCluster::default());
+        let config: ElasticConfig = serde_json::from_value(serde_json::json!({ "base_url": "http://es:9200" })).unwrap();
+        core.configure_elasticsearch_with_transport(config, cluster.clone()).await.unwrap();
+
+        let sample_id = core.submit_sample("acme", b"MZ dropper", "dropper.exe".to_string(), AnalysisPriority::High, vec![], false).await.unwrap();
+        core.process_queue().await.unwrap();
+        let analysis = core.get_analysis("acme", &sample_id).await.unwrap().unwrap();
+
+        let indexer = core.elasticsearch_indexer().unwrap();
+        assert_eq!(indexer.flush().await.indexed, 1);
+
+        let requests = cluster.requests.lock().unwrap().clone();
+        assert_eq!(requests[0].url, "http://es:9200/_index_template/phantom-sandbox");
+        let bulk = requests[1].body.as_deref().unwrap();
+        let index = format!("phantom-sandbox-{}", analysis.analysis_metadata.analysis_end.format("%Y.%m"));
+        assert!(bulk.contains(&format!(r#""_id":"acme:{}","_index":"{}""#, sample_id, index)), "{}", bulk);
+        assert!(bulk.contains(r#""tenant_id":"acme""#));
+        assert_eq!(indexer.metrics().indexed, 1);
+    }
+}
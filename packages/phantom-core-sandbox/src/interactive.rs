@@ -0,0 +1,584 @@
+//! Interactive analysis sessions
+//!
+//! A sample submitted in interactive mode (`submit_interactive_sample`) gets
+//! a session and a session token. Once the sample has run, its detonation
+//! does not collect artifacts and revert the guest right away: it holds the
+//! guest for the session window so an analyst can work in the VM. With the
+//! token the analyst can extend the window, have the guest's artifacts
+//! collected again, resume the automatic pipeline (final collection and
+//! revert) or terminate the guest without a final collection. An expired
+//! window resumes automatically; cancelling the analysis terminates.
+//!
+//! Only the token's SHA-256 is kept. When the analysis is stored the session
+//! record — window, extensions, collection rounds and how it ended — moves
+//! into its `AnalysisMetadata` and the token stops working. Environments
+//! without a detonation driver have no guest to hold; their sessions end as
+//! `no_guest`.
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::dedup::sha256_hex;
+use crate::detonation::{DetonationArtifact, DetonationDriver, DetonationHandle};
+use crate::error::{CoreError, CoreResult};
+use crate::{parse_priority, AnalysisPriority, SandboxCore, SandboxCoreNapi};
+
+/// Window an interactive guest is held for before it resumes automatically
+pub const DEFAULT_SESSION_WINDOW_SECS: u64 = 15 * 60;
+/// Longest a guest can be held, extensions included
+pub const MAX_SESSION_WINDOW_SECS: u64 = 2 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionState {
+    /// Waiting for the sample to run
+    Pending,
+    /// Guest held for the analyst
+    Active,
+    Closed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionEnd {
+    /// Handed back to the automatic pipeline by the analyst
+    Resumed,
+    /// Guest reverted without a final artifact collection
+    Terminated,
+    /// Window ran out; the automatic pipeline took over
+    Expired,
+    /// The analysis finished without holding a guest
+    NoGuest,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClockExtension {
+    pub actor: String,
+    pub seconds: u64,
+    pub requested_at: DateTime<Utc>,
+}
+
+/// Artifact collection an analyst triggered while the guest was held
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionRound {
+    pub collection_id: String,
+    pub actor: String,
+    pub requested_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Artifacts not seen in earlier rounds
+    pub new_artifacts: usize,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractiveSessionRecord {
+    pub session_id: String,
+    pub sample_id: String,
+    pub tenant_id: String,
+    pub state: SessionState,
+    pub created_at: DateTime<Utc>,
+    /// When the guest was first held
+    pub opened_at: Option<DateTime<Utc>>,
+    /// Total window, extensions included
+    pub window_secs: u64,
+    pub deadline: Option<DateTime<Utc>>,
+    pub extensions: Vec<ClockExtension>,
+    pub collections: Vec<CollectionRound>,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub closed_by: Option<String>,
+    pub end: Option<SessionEnd>,
+}
+
+impl InteractiveSessionRecord {
+    fn close(&mut self, end: SessionEnd, actor: &str) {
+        self.state = SessionState::Closed;
+        self.end = Some(end);
+        self.closed_by = Some(actor.to_string());
+        self.closed_at = Some(Utc::now());
+    }
+}
+
+/// What `submit_interactive_sample` hands back; the token is shown only here
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractiveSubmission {
+    pub sample_id: String,
+    pub session_id: String,
+    pub session_token: String,
+    pub window_secs: u64,
+}
+
+/// A session shared by the API and the detonation holding its guest
+pub struct InteractiveSession {
+    token_hash: String,
+    record: Mutex<InteractiveSessionRecord>,
+    /// Collection rounds the detonation has yet to run
+    pending: Mutex<Vec<String>>,
+    wake: Notify,
+}
+
+impl InteractiveSession {
+    pub fn record(&self) -> InteractiveSessionRecord {
+        self.record.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn update<T>(&self, f: impl FnOnce(&mut InteractiveSessionRecord) -> T) -> T {
+        f(&mut self.record.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Hold the guest until the session is resumed, terminated or runs out,
+    /// running requested collections into `artifacts`; returns how it ended
+    pub async fn hold(&self, driver: &dyn DetonationDriver, handle: &DetonationHandle, artifacts: &mut Vec<DetonationArtifact>) -> SessionEnd {
+        let opened = self.update(|record| {
+            if let Some(end) = record.end {
+                return Err(end);
+            }
+            let now = Utc::now();
+            record.state = SessionState::Active;
+            record.opened_at.get_or_insert(now);
+            record.deadline = Some(now + ChronoDuration::seconds(record.window_secs as i64));
+            Ok(())
+        });
+        if let Err(end) = opened {
+            return end;
+        }
+
+        loop {
+            let deadline = self.update(|record| record.deadline).unwrap_or_else(Utc::now);
+            let wait = (deadline - Utc::now()).to_std().unwrap_or_default();
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = self.wake.notified() => {}
+            }
+
+            let requested: Vec<String> = std::mem::take(&mut *self.pending.lock().unwrap_or_else(|e| e.into_inner()));
+            for collection_id in requested {
+                let (new_artifacts, error) = match driver.collect_artifacts(handle).await {
+                    Ok(collected) => (merge_artifacts(artifacts, collected), None),
+                    Err(e) => (0, Some(e)),
+                };
+                self.update(|record| {
+                    if let Some(round) = record.collections.iter_mut().find(|round| round.collection_id == collection_id) {
+                        round.completed_at = Some(Utc::now());
+                        round.new_artifacts = new_artifacts;
+                        round.error = error;
+                    }
+                });
+            }
+
+            let end = self.update(|record| match record.end {
+                Some(end) => Some(end),
+                None if record.deadline.is_some_and(|deadline| Utc::now() >= deadline) => {
+                    record.close(SessionEnd::Expired, "system");
+                    Some(SessionEnd::Expired)
+                }
+                None => None,
+            });
+            if let Some(end) = end {
+                return end;
+            }
+        }
+    }
+}
+
+/// Add artifacts not collected before; returns how many were new
+pub fn merge_artifacts(artifacts: &mut Vec<DetonationArtifact>, collected: Vec<DetonationArtifact>) -> usize {
+    let before = artifacts.len();
+    for artifact in collected {
+        let seen = artifacts.iter().any(|known| known.kind == artifact.kind && known.path == artifact.path && known.sha256 == artifact.sha256);
+        if !seen {
+            artifacts.push(artifact);
+        }
+    }
+    artifacts.len() - before
+}
+
+#[derive(Default)]
+pub struct InteractiveSessions {
+    sessions: Mutex<HashMap<String, Arc<InteractiveSession>>>,
+}
+
+impl InteractiveSessions {
+    pub(crate) fn get(&self, session_id: &str) -> Option<Arc<InteractiveSession>> {
+        self.sessions.lock().unwrap_or_else(|e| e.into_inner()).get(session_id).cloned()
+    }
+
+    /// The tenant's session behind `token`
+    fn authenticate(&self, tenant_id: &str, token: &str) -> CoreResult<Arc<InteractiveSession>> {
+        let token_hash = sha256_hex(token.as_bytes());
+        self.sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .find(|session| session.token_hash == token_hash)
+            .filter(|session| session.record().tenant_id == tenant_id)
+            .cloned()
+            .ok_or_else(|| CoreError::not_found("Interactive session not found or already finished"))
+    }
+
+    /// Close a session that is still open and notify its detonation
+    pub(crate) fn close(&self, session_id: &str, end: SessionEnd, actor: &str) -> bool {
+        let Some(session) = self.get(session_id) else { return false };
+        let closed = session.update(|record| {
+            if record.end.is_some() {
+                return false;
+            }
+            record.close(end, actor);
+            true
+        });
+        session.wake.notify_one();
+        closed
+    }
+
+    /// Take a finished analysis's session out of the registry, closing it
+    /// as `no_guest` if it never held one
+    pub(crate) fn finish(&self, session_id: &str) -> Option<InteractiveSessionRecord> {
+        let session = self.sessions.lock().unwrap_or_else(|e| e.into_inner()).remove(session_id)?;
+        session.update(|record| {
+            if record.end.is_none() {
+                record.close(SessionEnd::NoGuest, "system");
+            }
+        });
+        Some(session.record())
+    }
+
+    pub(crate) fn forget_tenant(&self, tenant_id: &str) -> usize {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        let before = sessions.len();
+        sessions.retain(|_, session| {
+            let keep = session.record().tenant_id != tenant_id;
+            if !keep {
+                session.update(|record| record.close(SessionEnd::Terminated, "system"));
+                session.wake.notify_one();
+            }
+            keep
+        });
+        before - sessions.len()
+    }
+}
+
+impl SandboxCore {
+    /// Queue a sample for interactive analysis. Interactive runs always
+    /// detonate afresh, so duplicates are re-analysed.
+    pub async fn submit_interactive_sample(
+        &self,
+        tenant_id: &str,
+        file_data: &[u8],
+        filename: String,
+        priority: AnalysisPriority,
+        tags: Vec<String>,
+        window_secs: Option<u64>,
+    ) -> CoreResult<InteractiveSubmission> {
+        let window_secs = window_secs.unwrap_or(DEFAULT_SESSION_WINDOW_SECS);
+        if window_secs == 0 || window_secs > MAX_SESSION_WINDOW_SECS {
+            return Err(CoreError::validation(format!("Session window must be between 1 and {} seconds", MAX_SESSION_WINDOW_SECS)));
+        }
+        let session_id = format!("isn_{}", Uuid::new_v4().simple());
+        let session_token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let session = Arc::new(InteractiveSession {
+            token_hash: sha256_hex(session_token.as_bytes()),
+            record: Mutex::new(InteractiveSessionRecord {
+                session_id: session_id.clone(),
+                sample_id: String::new(),
+                tenant_id: tenant_id.to_string(),
+                state: SessionState::Pending,
+                created_at: Utc::now(),
+                opened_at: None,
+                window_secs,
+                deadline: None,
+                extensions: Vec::new(),
+                collections: Vec::new(),
+                closed_at: None,
+                closed_by: None,
+                end: None,
+            }),
+            pending: Mutex::new(Vec::new()),
+            wake: Notify::new(),
+        });
+        // Registered first so the job finds it however soon it runs
+        self.interactive.sessions.lock().unwrap_or_else(|e| e.into_inner()).insert(session_id.clone(), session.clone());
+
+        match self.enqueue_sample(tenant_id, file_data, filename, priority, tags, true, None, Some(session_id.clone())).await {
+            Ok(sample_id) => {
+                session.update(|record| record.sample_id = sample_id.clone());
+                Ok(InteractiveSubmission { sample_id, session_id, session_token, window_secs })
+            }
+            Err(e) => {
+                self.interactive.sessions.lock().unwrap_or_else(|e| e.into_inner()).remove(&session_id);
+                Err(e)
+            }
+        }
+    }
+
+    pub fn get_interactive_session(&self, tenant_id: &str, token: &str) -> CoreResult<InteractiveSessionRecord> {
+        Ok(self.interactive.authenticate(tenant_id, token)?.record())
+    }
+
+    /// Add `seconds` to the session window, up to [`MAX_SESSION_WINDOW_SECS`] in total
+    pub fn extend_interactive_session(&self, tenant_id: &str, token: &str, seconds: u64, actor: &str) -> CoreResult<InteractiveSessionRecord> {
+        let session = self.interactive.authenticate(tenant_id, token)?;
+        let record = session.update(|record| {
+            if record.state == SessionState::Closed {
+                return Err(CoreError::validation(format!("Session {} is closed", record.session_id)));
+            }
+            if seconds == 0 || record.window_secs + seconds > MAX_SESSION_WINDOW_SECS {
+                return Err(CoreError::validation(format!(
+                    "Session window is {} seconds and may not exceed {}",
+                    record.window_secs, MAX_SESSION_WINDOW_SECS
+                )));
+            }
+            record.window_secs += seconds;
+            if let Some(deadline) = record.deadline.as_mut() {
+                *deadline += ChronoDuration::seconds(seconds as i64);
+            }
+            record.extensions.push(ClockExtension { actor: actor.to_string(), seconds, requested_at: Utc::now() });
+            Ok(record.clone())
+        })?;
+        session.wake.notify_one();
+        Ok(record)
+    }
+
+    /// Have the held guest's artifacts collected again; the round completes asynchronously
+    pub fn collect_interactive_artifacts(&self, tenant_id: &str, token: &str, actor: &str) -> CoreResult<CollectionRound> {
+        let session = self.interactive.authenticate(tenant_id, token)?;
+        let round = session.update(|record| {
+            if record.state != SessionState::Active {
+                return Err(CoreError::validation(format!("Session {} is not holding a guest", record.session_id)));
+            }
+            let round = CollectionRound {
+                collection_id: format!("col_{}", Uuid::new_v4().simple()),
+                actor: actor.to_string(),
+                requested_at: Utc::now(),
+                completed_at: None,
+                new_artifacts: 0,
+                error: None,
+            };
+            record.collections.push(round.clone());
+            Ok(round)
+        })?;
+        session.pending.lock().unwrap_or_else(|e| e.into_inner()).push(round.collection_id.clone());
+        session.wake.notify_one();
+        Ok(round)
+    }
+
+    /// End the session: `Resumed` hands the guest back to the automatic
+    /// pipeline, `Terminated` reverts it without a final collection
+    pub fn close_interactive_session(&self, tenant_id: &str, token: &str, end: SessionEnd, actor: &str) -> CoreResult<InteractiveSessionRecord> {
+        if !matches!(end, SessionEnd::Resumed | SessionEnd::Terminated) {
+            return Err(CoreError::validation("Sessions can only be resumed or terminated"));
+        }
+        let session = self.interactive.authenticate(tenant_id, token)?;
+        let session_id = session.record().session_id;
+        if !self.interactive.close(&session_id, end, actor) {
+            return Err(CoreError::validation(format!("Session {} is already closed", session_id)));
+        }
+        Ok(session.record())
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// Submit a sample whose guest is held for the analyst after it runs;
+    /// returns the sample id, session id and the session token
+    #[napi]
+    pub async fn submit_interactive_sample(
+        &self,
+        file_data: Buffer,
+        filename: String,
+        priority: Option<String>,
+        tags: Option<Vec<String>>,
+        window_secs: Option<u32>,
+        auth_token: Option<String>,
+    ) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", &filename)?;
+        let tags = tags.unwrap_or_default();
+        let params = json!({ "filename": filename, "size": file_data.len(), "priority": priority, "tags": tags, "window_secs": window_secs });
+        let result = self
+            .inner
+            .submit_interactive_sample(&tenant_id, &file_data, filename.clone(), parse_priority(priority.as_deref()), tags, window_secs.map(u64::from))
+            .await;
+        let resource = result.as_ref().map_or(filename, |submission| submission.sample_id.clone());
+        let submission = self.audit.record(&actor, "submit_interactive_sample", &resource, params, result)
+            .map_err(|e| e.context("Failed to submit interactive sample"))?;
+        serde_json::to_string(&submission)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize interactive submission: {}", e)))
+    }
+
+    #[napi]
+    pub fn get_interactive_session(&self, session_token: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let record = self.inner.get_interactive_session(&tenant_id, &session_token)
+            .map_err(|e| e.context("Failed to get interactive session"))?;
+        serde_json::to_string(&record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize interactive session: {}", e)))
+    }
+
+    /// Extend the time the guest is held by `seconds`
+    #[napi]
+    pub fn extend_interactive_session(&self, session_token: String, seconds: u32, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let session_id = self.inner.get_interactive_session(&tenant_id, &session_token).map(|record| record.session_id).unwrap_or_default();
+        let actor = self.authorize(auth_token, "analysis:submit", &session_id)?;
+        let result = self.inner.extend_interactive_session(&tenant_id, &session_token, u64::from(seconds), &actor);
+        let record = self.audit.record(&actor, "extend_interactive_session", &session_id, json!({ "seconds": seconds }), result)
+            .map_err(|e| e.context("Failed to extend interactive session"))?;
+        serde_json::to_string(&record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize interactive session: {}", e)))
+    }
+
+    /// Collect the held guest's artifacts again
+    #[napi]
+    pub fn collect_interactive_artifacts(&self, session_token: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let session_id = self.inner.get_interactive_session(&tenant_id, &session_token).map(|record| record.session_id).unwrap_or_default();
+        let actor = self.authorize(auth_token, "analysis:submit", &session_id)?;
+        let result = self.inner.collect_interactive_artifacts(&tenant_id, &session_token, &actor);
+        let round = self.audit.record(&actor, "collect_interactive_artifacts", &session_id, json!({}), result)
+            .map_err(|e| e.context("Failed to collect artifacts"))?;
+        serde_json::to_string(&round)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize collection round: {}", e)))
+    }
+
+    /// Hand the guest back to the automatic pipeline
+    #[napi]
+    pub fn resume_interactive_session(&self, session_token: String, auth_token: Option<String>) -> napi::Result<String> {
+        self.end_interactive_session(session_token, SessionEnd::Resumed, "analysis:submit", "resume_interactive_session", auth_token)
+    }
+
+    /// Revert the guest now, skipping the final artifact collection
+    #[napi]
+    pub fn terminate_interactive_session(&self, session_token: String, auth_token: Option<String>) -> napi::Result<String> {
+        self.end_interactive_session(session_token, SessionEnd::Terminated, "analysis:cancel", "terminate_interactive_session", auth_token)
+    }
+}
+
+impl SandboxCoreNapi {
+    fn end_interactive_session(&self, session_token: String, end: SessionEnd, permission: &str, operation: &str, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let session_id = self.inner.get_interactive_session(&tenant_id, &session_token).map(|record| record.session_id).unwrap_or_default();
+        let actor = self.authorize(auth_token, permission, &session_id)?;
+        let result = self.inner.close_interactive_session(&tenant_id, &session_token, end, &actor);
+        let record = self.audit.record(&actor, operation, &session_id, json!({}), result)
+            .map_err(|e| e.context("Failed to close interactive session"))?;
+        serde_json::to_string(&record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize interactive session: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::countermeasures::CountermeasurePlan;
+    use crate::detonation::{detonate, ArtifactKind, ExecutionOutcome};
+    use crate::{tenancy, VMEnvironment};
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Guest whose every collection finds one more dropped file
+    #[derive(Default)]
+    struct HeldGuest {
+        collections: AtomicUsize,
+        reverted: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl DetonationDriver for HeldGuest {
+        fn backend(&self) -> &'static str {
+            "test"
+        }
+
+        async fn start_vm(&self, environment: &VMEnvironment, job_id: &str, _countermeasures: &CountermeasurePlan) -> Result<DetonationHandle, String> {
+            Ok(DetonationHandle {
+                job_id: job_id.to_string(),
+                environment_id: environment.id.clone(),
+                guest_id: "guest".to_string(),
+                snapshot_id: environment.snapshot_id.clone(),
+            })
+        }
+
+        async fn restore_snapshot(&self, _handle: &DetonationHandle) -> Result<(), String> {
+            Ok(())
+        }
+
+        async fn copy_sample(&self, _handle: &DetonationHandle, file_name: &str, _data: &[u8]) -> Result<String, String> {
+            Ok(format!("C:\\{}", file_name))
+        }
+
+        async fn execute_sample(&self, _handle: &DetonationHandle, _guest_path: &str, _timeout: std::time::Duration) -> Result<ExecutionOutcome, String> {
+            Ok(ExecutionOutcome { exit_code: Some(0), ..Default::default() })
+        }
+
+        async fn collect_artifacts(&self, _handle: &DetonationHandle) -> Result<Vec<DetonationArtifact>, String> {
+            let round = self.collections.fetch_add(1, Ordering::SeqCst);
+            Ok((0..=round)
+                .map(|index| DetonationArtifact {
+                    kind: ArtifactKind::FileCreated,
+                    path: format!("C:\\dropped{}.dll", index),
+                    size_bytes: 10,
+                    sha256: None,
+                    artifact_id: None,
+                    content: None,
+                })
+                .collect())
+        }
+
+        async fn revert(&self, _handle: &DetonationHandle) -> Result<(), String> {
+            self.reverted.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_held_guest_is_extended_collected_and_terminated() {
+        let core = SandboxCore::new().unwrap();
+        let tenant = tenancy::DEFAULT_TENANT;
+        let submission = core
+            .submit_interactive_sample(tenant, b"MZ interactive", "dropper.exe".to_string(), AnalysisPriority::High, Vec::new(), Some(60))
+            .await
+            .unwrap();
+        let token = submission.session_token.as_str();
+        assert!(core.get_interactive_session(tenant, "not-a-token").is_err());
+        assert!(core.get_interactive_session("other-tenant", token).is_err());
+        let session = core.interactive.get(&submission.session_id).unwrap();
+
+        let driver = HeldGuest::default();
+        let environment = SandboxCore::initialize_vm_environments().unwrap().remove("win10-x64").unwrap();
+        let analyst = async {
+            while core.get_interactive_session(tenant, token).unwrap().state != SessionState::Active {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+            let extended = core.extend_interactive_session(tenant, token, 120, "analyst").unwrap();
+            assert_eq!(extended.window_secs, 180);
+            assert!(core.extend_interactive_session(tenant, token, MAX_SESSION_WINDOW_SECS, "analyst").is_err());
+
+            core.collect_interactive_artifacts(tenant, token, "analyst").unwrap();
+            while core.get_interactive_session(tenant, token).unwrap().collections[0].completed_at.is_none() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+            core.close_interactive_session(tenant, token, SessionEnd::Terminated, "analyst").unwrap()
+        };
+        let (report, closed) = tokio::join!(
+            detonate(&driver, &environment, "job-1", "dropper.exe", b"MZ", Duration::from_secs(5), None, Some(&session)),
+            analyst
+        );
+
+        assert_eq!(closed.end, Some(SessionEnd::Terminated));
+        // One collection round, no final collection after termination, guest still reverted
+        assert_eq!(report.artifacts.len(), 1);
+        assert_eq!(driver.collections.load(Ordering::SeqCst), 1);
+        assert_eq!(driver.reverted.load(Ordering::SeqCst), 1);
+
+        let record = core.interactive.finish(&submission.session_id).unwrap();
+        assert_eq!(record.collections[0].new_artifacts, 1);
+        assert_eq!(record.extensions[0].seconds, 120);
+        assert!(core.get_interactive_session(tenant, token).is_err());
+    }
+}
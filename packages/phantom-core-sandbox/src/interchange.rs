@@ -0,0 +1,933 @@
+//! OpenIOC and CSV indicator interchange
+//!
+//! Not every partner speaks STIX or MISP; many still trade OpenIOC 1.1
+//! documents or flat CSV lists. Both formats import into the local IOC store
+//! (merged on the same `type:value` key as MISP pulls) and extracted IOCs
+//! export to both. CSV columns are named by a configurable mapping so
+//! existing spreadsheets need no reshaping. Problems with individual rows —
+//! an unknown type, a malformed hash, an OpenIOC item that is not an exact
+//! match — are reported per row and do not stop the rest of the import; only
+//! a document that cannot be read at all fails as a whole.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use uuid::Uuid;
+
+use crate::error::{CoreError, CoreResult};
+use crate::misp::{canonical_ioc_type, ioc_key};
+use crate::{ExtractedIOC, SandboxCore};
+
+/// Largest document accepted for import
+pub const MAX_DOCUMENT_BYTES: usize = 16 * 1024 * 1024;
+
+/// Confidence given to imported indicators that do not carry one
+const DEFAULT_IMPORT_CONFIDENCE: f64 = 0.7;
+
+const DEFAULT_IMPORT_CATEGORY: &str = "imported";
+
+const OPENIOC_NAMESPACE: &str = "http://openioc.org/schemas/OpenIOC_1.1";
+
+/// OpenIOC terms per local IOC type; the first term of a type is used on export
+const OPENIOC_TERMS: &[(&str, &str, &str, &str)] = &[
+    // (local type, Context document, Context search, Content type)
+    ("md5", "FileItem", "FileItem/Md5sum", "md5"),
+    ("sha1", "FileItem", "FileItem/Sha1sum", "sha1"),
+    ("sha256", "FileItem", "FileItem/Sha256sum", "sha256"),
+    ("filename", "FileItem", "FileItem/FileName", "string"),
+    ("ip", "PortItem", "PortItem/remoteIP", "IP"),
+    ("ip", "Network", "Network/IP", "IP"),
+    ("domain", "Network", "Network/DNS", "string"),
+    ("domain", "DnsEntryItem", "DnsEntryItem/Host", "string"),
+    ("domain", "DnsEntryItem", "DnsEntryItem/RecordName", "string"),
+    ("url", "Network", "Network/URI", "string"),
+    ("url", "UrlHistoryItem", "UrlHistoryItem/URL", "string"),
+    ("user_agent", "Network", "Network/UserAgent", "string"),
+    ("email", "Email", "Email/From", "string"),
+    ("mutex", "ProcessItem", "ProcessItem/HandleList/Handle/Name", "string"),
+    ("registry", "RegistryItem", "RegistryItem/Path", "string"),
+    ("registry", "RegistryItem", "RegistryItem/KeyPath", "string"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InterchangeFormat {
+    #[serde(rename = "openioc")]
+    OpenIoc,
+    Csv,
+}
+
+impl InterchangeFormat {
+    pub fn parse(name: &str) -> CoreResult<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "openioc" | "ioc" | "xml" => Ok(Self::OpenIoc),
+            "csv" => Ok(Self::Csv),
+            other => Err(CoreError::validation(format!("Unknown interchange format: {}", other))),
+        }
+    }
+}
+
+/// CSV column names for each IOC field; optional columns that are unset or
+/// missing from an imported file are left at their defaults
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CsvColumnMapping {
+    pub ioc_type: Option<String>,
+    pub value: String,
+    pub category: Option<String>,
+    pub confidence: Option<String>,
+    pub context: Option<String>,
+    pub first_seen: Option<String>,
+    /// Where the indicator came from; stored as its threat intelligence source
+    pub source: Option<String>,
+    /// Type of every row when the file has no type column, e.g. a list of hashes
+    pub default_type: Option<String>,
+    pub delimiter: char,
+}
+
+impl Default for CsvColumnMapping {
+    fn default() -> Self {
+        Self {
+            ioc_type: Some("type".to_string()),
+            value: "value".to_string(),
+            category: Some("category".to_string()),
+            confidence: Some("confidence".to_string()),
+            context: Some("context".to_string()),
+            first_seen: Some("first_seen".to_string()),
+            source: Some("source".to_string()),
+            default_type: None,
+            delimiter: ',',
+        }
+    }
+}
+
+impl CsvColumnMapping {
+    pub fn validate(&self) -> CoreResult<()> {
+        if self.value.trim().is_empty() {
+            return Err(CoreError::validation("CSV mapping needs a value column"));
+        }
+        if matches!(self.delimiter, '"' | '\n' | '\r') {
+            return Err(CoreError::validation(format!("Invalid CSV delimiter: {:?}", self.delimiter)));
+        }
+        if let Some(default_type) = &self.default_type {
+            if canonical_ioc_type(default_type).is_none() {
+                return Err(CoreError::validation(format!("Unknown default IOC type: {}", default_type)));
+            }
+        }
+        if self.ioc_type.is_none() && self.default_type.is_none() {
+            return Err(CoreError::validation("CSV mapping needs a type column or a default type"));
+        }
+        Ok(())
+    }
+
+    /// Mapped columns in export order, with the IOC field each one holds
+    fn columns(&self) -> Vec<(&'static str, &str)> {
+        let optional = [
+            ("ioc_type", &self.ioc_type),
+            ("category", &self.category),
+            ("confidence", &self.confidence),
+            ("context", &self.context),
+            ("first_seen", &self.first_seen),
+            ("source", &self.source),
+        ];
+        let mut columns = vec![("value", self.value.as_str())];
+        columns.extend(optional.into_iter().filter_map(|(field, name)| name.as_deref().map(|name| (field, name))));
+        columns
+    }
+}
+
+// Reports
+
+/// A row that was not imported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportRowError {
+    /// CSV line number, or the 1-based position of the IndicatorItem in an OpenIOC document
+    pub row: usize,
+    pub value: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IocImportReport {
+    pub format: InterchangeFormat,
+    pub rows: usize,
+    pub imported: usize,
+    pub updated: usize,
+    pub errors: Vec<ImportRowError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IocExport {
+    pub format: InterchangeFormat,
+    pub document: String,
+    pub exported: usize,
+    /// IOCs whose type the format has no term for
+    pub skipped_unsupported: usize,
+}
+
+/// Indicators read from a document, with the rows that could not be read
+#[derive(Debug, Default)]
+pub struct ParsedIocs {
+    pub rows: usize,
+    pub iocs: Vec<ExtractedIOC>,
+    pub errors: Vec<ImportRowError>,
+}
+
+impl ParsedIocs {
+    fn reject(&mut self, row: usize, value: Option<&str>, message: impl Into<String>) {
+        self.errors.push(ImportRowError {
+            row,
+            value: value.map(str::to_string),
+            message: message.into(),
+        });
+    }
+}
+
+// Validation
+
+/// Canonical type and normalized value of an indicator, or why it is invalid
+fn validate_indicator(ioc_type: &str, value: &str) -> Result<(&'static str, String), String> {
+    let canonical = canonical_ioc_type(ioc_type).ok_or_else(|| format!("Unknown IOC type: {}", ioc_type))?;
+    let value = value.trim();
+    if value.is_empty() {
+        return Err("Empty indicator value".to_string());
+    }
+    if value.chars().any(char::is_control) {
+        return Err("Indicator value contains control characters".to_string());
+    }
+    let hex_len = |len: usize| value.len() == len && value.chars().all(|c| c.is_ascii_hexdigit());
+    let valid = match canonical {
+        "md5" => hex_len(32),
+        "sha1" => hex_len(40),
+        "sha256" => hex_len(64),
+        "ip" => value.parse::<IpAddr>().is_ok(),
+        "domain" => {
+            value.contains('.')
+                && !value.starts_with('.')
+                && value.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+        }
+        "url" => url::Url::parse(value).map(|url| url.host().is_some()).unwrap_or(false),
+        "email" => match value.split_once('@') {
+            Some((local, domain)) => !local.is_empty() && domain.contains('.') && !domain.contains('@'),
+            None => false,
+        },
+        _ => true,
+    };
+    if !valid {
+        return Err(format!("Invalid {} value", canonical));
+    }
+    let value = match canonical {
+        "md5" | "sha1" | "sha256" | "domain" => value.to_ascii_lowercase(),
+        _ => value.to_string(),
+    };
+    Ok((canonical, value))
+}
+
+fn imported_ioc(ioc_type: &str, value: String, source: &str) -> ExtractedIOC {
+    ExtractedIOC {
+        ioc_type: ioc_type.to_string(),
+        value,
+        category: DEFAULT_IMPORT_CATEGORY.to_string(),
+        confidence: DEFAULT_IMPORT_CONFIDENCE,
+        context: String::new(),
+        first_seen: Utc::now(),
+        threat_intelligence: Some(source.to_string()),
+        enrichments: Vec::new(),
+    }
+}
+
+fn parse_confidence(raw: &str) -> Result<f64, String> {
+    match raw.trim().parse::<f64>() {
+        Ok(confidence) if (0.0..=1.0).contains(&confidence) => Ok(confidence),
+        _ => Err(format!("Confidence must be a number from 0 to 1: {}", raw.trim())),
+    }
+}
+
+// CSV
+
+struct CsvRecord {
+    line: usize,
+    fields: Vec<String>,
+    unterminated: bool,
+}
+
+/// Split CSV text into records with the line each starts on; quoted fields
+/// may hold delimiters, doubled quotes and line breaks
+fn csv_records(text: &str, delimiter: char) -> Vec<CsvRecord> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let (mut line, mut start) = (1, 1);
+    let mut chars = text.trim_start_matches('\u{feff}').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                '\n' => {
+                    line += 1;
+                    field.push(c);
+                }
+                _ => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                in_quotes = true;
+            }
+            '\r' => {}
+            '\n' => {
+                fields.push(std::mem::take(&mut field));
+                records.push(CsvRecord { line: start, fields: std::mem::take(&mut fields), unterminated: false });
+                line += 1;
+                start = line;
+            }
+            c if c == delimiter => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    if in_quotes || !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        records.push(CsvRecord { line: start, fields, unterminated: in_quotes });
+    }
+
+    // Blank and comment lines
+    records.retain(|record| {
+        let first = record.fields[0].trim_start();
+        (record.fields.len() > 1 || !first.is_empty()) && !first.starts_with('#')
+    });
+    records
+}
+
+fn csv_field(value: &str, delimiter: char) -> String {
+    if value.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Read a CSV indicator list; the first non-blank line is the header
+pub fn parse_csv(text: &str, mapping: &CsvColumnMapping, source: &str) -> CoreResult<ParsedIocs> {
+    mapping.validate()?;
+    let mut records = csv_records(text, mapping.delimiter).into_iter();
+    let header = records.next().ok_or_else(|| CoreError::validation("CSV document is empty"))?;
+    let headers: HashMap<String, usize> = header
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(index, name)| (name.trim().to_ascii_lowercase(), index))
+        .collect();
+    let column = |name: &str| headers.get(&name.trim().to_ascii_lowercase()).copied();
+    let optional = |name: &Option<String>| name.as_deref().and_then(column);
+
+    let value_column = column(&mapping.value)
+        .ok_or_else(|| CoreError::validation(format!("CSV header has no '{}' column", mapping.value)))?;
+    let type_column = optional(&mapping.ioc_type);
+    if type_column.is_none() && mapping.default_type.is_none() {
+        return Err(CoreError::validation(format!(
+            "CSV header has no '{}' column and the mapping has no default type",
+            mapping.ioc_type.as_deref().unwrap_or_default()
+        )));
+    }
+    let category_column = optional(&mapping.category);
+    let confidence_column = optional(&mapping.confidence);
+    let context_column = optional(&mapping.context);
+    let first_seen_column = optional(&mapping.first_seen);
+    let source_column = optional(&mapping.source);
+
+    let mut parsed = ParsedIocs::default();
+    for record in records {
+        parsed.rows += 1;
+        let cell = |index: Option<usize>| {
+            index.and_then(|index| record.fields.get(index)).map(|cell| cell.trim()).filter(|cell| !cell.is_empty())
+        };
+        let value = cell(Some(value_column));
+        if record.unterminated {
+            parsed.reject(record.line, value, "Unterminated quoted field");
+            continue;
+        }
+        let Some(value) = value else {
+            parsed.reject(record.line, None, format!("Missing '{}'", mapping.value));
+            continue;
+        };
+        let Some(ioc_type) = cell(type_column).or(mapping.default_type.as_deref()) else {
+            parsed.reject(record.line, Some(value), "Missing IOC type");
+            continue;
+        };
+        let (ioc_type, normalized) = match validate_indicator(ioc_type, value) {
+            Ok(indicator) => indicator,
+            Err(message) => {
+                parsed.reject(record.line, Some(value), message);
+                continue;
+            }
+        };
+
+        let mut ioc = imported_ioc(ioc_type, normalized, cell(source_column).unwrap_or(source));
+        if let Some(raw) = cell(confidence_column) {
+            match parse_confidence(raw) {
+                Ok(confidence) => ioc.confidence = confidence,
+                Err(message) => {
+                    parsed.reject(record.line, Some(value), message);
+                    continue;
+                }
+            }
+        }
+        if let Some(raw) = cell(first_seen_column) {
+            match DateTime::parse_from_rfc3339(raw) {
+                Ok(seen) => ioc.first_seen = seen.with_timezone(&Utc),
+                Err(e) => {
+                    parsed.reject(record.line, Some(value), format!("Invalid first_seen '{}': {}", raw, e));
+                    continue;
+                }
+            }
+        }
+        if let Some(category) = cell(category_column) {
+            ioc.category = category.to_string();
+        }
+        if let Some(context) = cell(context_column) {
+            ioc.context = context.to_string();
+        }
+        parsed.iocs.push(ioc);
+    }
+    Ok(parsed)
+}
+
+/// Write IOCs as CSV with a header of the mapped column names
+pub fn write_csv(iocs: &[ExtractedIOC], mapping: &CsvColumnMapping) -> CoreResult<IocExport> {
+    mapping.validate()?;
+    let columns = mapping.columns();
+    let delimiter = mapping.delimiter.to_string();
+    let row = |cells: Vec<String>| {
+        cells.iter().map(|cell| csv_field(cell, mapping.delimiter)).collect::<Vec<_>>().join(&delimiter)
+    };
+
+    let mut document = row(columns.iter().map(|(_, name)| name.to_string()).collect());
+    document.push('\n');
+    for ioc in iocs {
+        let cells = columns
+            .iter()
+            .map(|(field, _)| match *field {
+                "value" => ioc.value.clone(),
+                "ioc_type" => canonical_ioc_type(&ioc.ioc_type).unwrap_or(ioc.ioc_type.as_str()).to_string(),
+                "category" => ioc.category.clone(),
+                "confidence" => ioc.confidence.to_string(),
+                "context" => ioc.context.clone(),
+                "first_seen" => ioc.first_seen.to_rfc3339(),
+                "source" => ioc.threat_intelligence.clone().unwrap_or_default(),
+                _ => String::new(),
+            })
+            .collect();
+        document.push_str(&row(cells));
+        document.push('\n');
+    }
+    Ok(IocExport { format: InterchangeFormat::Csv, document, exported: iocs.len(), skipped_unsupported: 0 })
+}
+
+// OpenIOC
+
+#[derive(Debug)]
+enum XmlEvent {
+    Start { name: String, attrs: HashMap<String, String>, empty: bool },
+    End(String),
+    Text(String),
+}
+
+fn local_name(name: &str) -> String {
+    name.rsplit(':').next().unwrap_or(name).to_string()
+}
+
+fn xml_unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find('&') {
+        out.push_str(&rest[..at]);
+        rest = &rest[at..];
+        let Some(end) = rest.find(';') else { break };
+        let entity = &rest[1..end];
+        let decoded = match entity {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16).ok())
+                .unwrap_or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&apos;")
+}
+
+/// Parse the attributes of a start tag body (everything after the name)
+fn xml_attributes(body: &str) -> Result<HashMap<String, String>, String> {
+    let mut attrs = HashMap::new();
+    let mut rest = body.trim();
+    while !rest.is_empty() {
+        let eq = rest.find('=').ok_or_else(|| format!("Malformed attribute near '{}'", rest))?;
+        let key = local_name(rest[..eq].trim());
+        rest = rest[eq + 1..].trim_start();
+        let quote = rest.chars().next().filter(|c| matches!(c, '"' | '\'')).ok_or("Unquoted attribute value")?;
+        let close = rest[1..].find(quote).ok_or("Unterminated attribute value")? + 1;
+        attrs.insert(key, xml_unescape(&rest[1..close]));
+        rest = rest[close + 1..].trim_start();
+    }
+    Ok(attrs)
+}
+
+/// A minimal XML tokenizer; enough for OpenIOC, which uses no DTDs or entities of its own
+fn xml_events(document: &str) -> Result<Vec<XmlEvent>, String> {
+    let mut events = Vec::new();
+    let mut rest = document;
+    while let Some(open) = rest.find('<') {
+        let text = &rest[..open];
+        if !text.trim().is_empty() {
+            events.push(XmlEvent::Text(xml_unescape(text)));
+        }
+        rest = &rest[open..];
+
+        let skip_to = |rest: &str, terminator: &str| rest.find(terminator).map(|at| at + terminator.len());
+        if rest.starts_with("<!--") {
+            rest = &rest[skip_to(rest, "-->").ok_or("Unterminated comment")?..];
+        } else if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = cdata.find("]]>").ok_or("Unterminated CDATA section")?;
+            events.push(XmlEvent::Text(cdata[..end].to_string()));
+            rest = &cdata[end + 3..];
+        } else if rest.starts_with("<?") || rest.starts_with("<!") {
+            rest = &rest[skip_to(rest, ">").ok_or("Unterminated declaration")?..];
+        } else if let Some(closing) = rest.strip_prefix("</") {
+            let end = closing.find('>').ok_or("Unterminated end tag")?;
+            events.push(XmlEvent::End(local_name(closing[..end].trim())));
+            rest = &closing[end + 1..];
+        } else {
+            // Find the closing '>' outside quoted attribute values
+            let mut quote = None;
+            let end = rest
+                .char_indices()
+                .skip(1)
+                .find(|&(_, c)| match quote {
+                    Some(q) if c == q => {
+                        quote = None;
+                        false
+                    }
+                    Some(_) => false,
+                    None if c == '"' || c == '\'' => {
+                        quote = Some(c);
+                        false
+                    }
+                    None => c == '>',
+                })
+                .map(|(at, _)| at)
+                .ok_or("Unterminated start tag")?;
+            let tag = &rest[1..end];
+            let (tag, empty) = match tag.strip_suffix('/') {
+                Some(tag) => (tag, true),
+                None => (tag, false),
+            };
+            let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+            let name = local_name(&tag[..name_end]);
+            if name.is_empty() {
+                return Err("Empty tag name".to_string());
+            }
+            events.push(XmlEvent::Start { name, attrs: xml_attributes(&tag[name_end..])?, empty });
+            rest = &rest[end + 1..];
+        }
+    }
+    if !rest.trim().is_empty() {
+        events.push(XmlEvent::Text(xml_unescape(rest)));
+    }
+    Ok(events)
+}
+
+#[derive(Debug, Default)]
+struct IndicatorItem {
+    row: usize,
+    id: Option<String>,
+    condition: String,
+    negate: bool,
+    in_and: bool,
+    search: Option<String>,
+    content: String,
+}
+
+#[derive(Debug, Default)]
+struct Parameter {
+    ref_id: String,
+    name: String,
+    value: String,
+}
+
+/// Read an OpenIOC 1.1 (or 1.0) document. Only exact-match items that stand
+/// on their own — condition `is`, not negated, not inside an AND — are
+/// indicators; the rest are reported as row errors.
+pub fn parse_openioc(document: &str, source: &str) -> CoreResult<ParsedIocs> {
+    let events = xml_events(document).map_err(|e| CoreError::validation(format!("Malformed OpenIOC document: {}", e)))?;
+    let mut items = Vec::new();
+    let mut params = Vec::new();
+    let mut operators: Vec<bool> = Vec::new();
+    let mut item: Option<IndicatorItem> = None;
+    let mut param: Option<Parameter> = None;
+    let mut element = String::new();
+    let mut root_seen = false;
+    let mut item_count = 0;
+
+    for event in events {
+        match event {
+            XmlEvent::Start { name, attrs, empty } => {
+                if !root_seen {
+                    root_seen = true;
+                    if name != "OpenIOC" && name != "ioc" {
+                        return Err(CoreError::validation(format!("Not an OpenIOC document: root element is <{}>", name)));
+                    }
+                }
+                match name.as_str() {
+                    "Indicator" if !empty => {
+                        operators.push(attrs.get("operator").is_some_and(|op| op.eq_ignore_ascii_case("AND")))
+                    }
+                    "IndicatorItem" => {
+                        item_count += 1;
+                        let new = IndicatorItem {
+                            row: item_count,
+                            id: attrs.get("id").cloned(),
+                            condition: attrs.get("condition").cloned().unwrap_or_else(|| "is".to_string()),
+                            negate: attrs.get("negate").is_some_and(|n| n == "true"),
+                            in_and: operators.iter().any(|and| *and),
+                            ..Default::default()
+                        };
+                        if empty {
+                            items.push(new);
+                        } else {
+                            item = Some(new);
+                        }
+                    }
+                    "Context" => {
+                        if let Some(item) = item.as_mut() {
+                            item.search = attrs.get("search").cloned();
+                        }
+                    }
+                    "param" if !empty => {
+                        param = Some(Parameter {
+                            ref_id: attrs.get("ref-id").cloned().unwrap_or_default(),
+                            name: attrs.get("name").cloned().unwrap_or_default(),
+                            value: String::new(),
+                        })
+                    }
+                    _ => {}
+                }
+                if !empty {
+                    element = name;
+                }
+            }
+            XmlEvent::Text(text) => match element.as_str() {
+                "Content" => {
+                    if let Some(item) = item.as_mut() {
+                        item.content.push_str(&text);
+                    }
+                }
+                "value" => {
+                    if let Some(param) = param.as_mut() {
+                        param.value.push_str(&text);
+                    }
+                }
+                _ => {}
+            },
+            XmlEvent::End(name) => {
+                match name.as_str() {
+                    "Indicator" => {
+                        operators.pop();
+                    }
+                    "IndicatorItem" => items.extend(item.take()),
+                    "param" => params.extend(param.take()),
+                    _ => {}
+                }
+                element.clear();
+            }
+        }
+    }
+    if !root_seen {
+        return Err(CoreError::validation("OpenIOC document is empty"));
+    }
+
+    // Parameters written on export carry category and confidence per item
+    let mut item_params: HashMap<&str, Vec<&Parameter>> = HashMap::new();
+    for param in &params {
+        item_params.entry(param.ref_id.as_str()).or_default().push(param);
+    }
+
+    let mut parsed = ParsedIocs { rows: items.len(), ..Default::default() };
+    for item in &items {
+        let content = item.content.trim();
+        let value = Some(content).filter(|content| !content.is_empty());
+        if item.negate {
+            parsed.reject(item.row, value, "Negated items are not indicators");
+            continue;
+        }
+        if !item.condition.eq_ignore_ascii_case("is") {
+            parsed.reject(item.row, value, format!("Condition '{}' is not an exact match", item.condition));
+            continue;
+        }
+        if item.in_and {
+            parsed.reject(item.row, value, "Item is one clause of an AND and not an indicator on its own");
+            continue;
+        }
+        let search = item.search.as_deref().unwrap_or_default();
+        let Some(&(ioc_type, ..)) = OPENIOC_TERMS.iter().find(|(_, _, term, _)| term.eq_ignore_ascii_case(search)) else {
+            parsed.reject(item.row, value, format!("Unsupported search term: {}", search));
+            continue;
+        };
+        let (ioc_type, normalized) = match validate_indicator(ioc_type, content) {
+            Ok(indicator) => indicator,
+            Err(message) => {
+                parsed.reject(item.row, value, message);
+                continue;
+            }
+        };
+
+        let mut ioc = imported_ioc(ioc_type, normalized, source);
+        let item_id = item.id.as_deref().unwrap_or_default();
+        for param in item_params.get(item_id).into_iter().flatten() {
+            match param.name.as_str() {
+                "category" if !param.value.trim().is_empty() => ioc.category = param.value.trim().to_string(),
+                "comment" => ioc.context = param.value.trim().to_string(),
+                "confidence" => {
+                    if let Ok(confidence) = parse_confidence(&param.value) {
+                        ioc.confidence = confidence;
+                    }
+                }
+                _ => {}
+            }
+        }
+        parsed.iocs.push(ioc);
+    }
+    Ok(parsed)
+}
+
+/// Write IOCs as one OpenIOC 1.1 document whose top-level indicator ORs them together
+pub fn write_openioc(iocs: &[ExtractedIOC], description: &str) -> IocExport {
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+    let mut items = String::new();
+    let mut parameters = String::new();
+    let mut exported = 0;
+    for ioc in iocs {
+        let term = canonical_ioc_type(&ioc.ioc_type)
+            .and_then(|ioc_type| OPENIOC_TERMS.iter().find(|(local, ..)| *local == ioc_type));
+        let Some((_, document, search, content_type)) = term else { continue };
+        let id = Uuid::new_v4();
+        items.push_str(&format!(
+            "      <IndicatorItem id=\"{id}\" condition=\"is\" preserve-case=\"false\" negate=\"false\">\n        \
+             <Context document=\"{document}\" search=\"{search}\" type=\"mir\"/>\n        \
+             <Content type=\"{content_type}\">{value}</Content>\n      </IndicatorItem>\n",
+            value = xml_escape(&ioc.value),
+        ));
+        let mut param = |name: &str, value: &str| {
+            parameters.push_str(&format!(
+                "    <param id=\"{}\" ref-id=\"{id}\" name=\"{name}\">\n      <value type=\"string\">{}</value>\n    </param>\n",
+                Uuid::new_v4(),
+                xml_escape(value),
+            ));
+        };
+        param("category", &ioc.category);
+        param("confidence", &ioc.confidence.to_string());
+        if !ioc.context.is_empty() {
+            param("comment", &ioc.context);
+        }
+        exported += 1;
+    }
+
+    let document = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <OpenIOC xmlns=\"{OPENIOC_NAMESPACE}\" id=\"{id}\" last-modified=\"{now}\" published-date=\"{now}\">\n  \
+         <metadata>\n    <short_description>{description}</short_description>\n    \
+         <authored_by>phantom-sandbox</authored_by>\n    <authored_date>{now}</authored_date>\n    <links/>\n  </metadata>\n  \
+         <criteria>\n    <Indicator id=\"{indicator}\" operator=\"OR\">\n{items}    </Indicator>\n  </criteria>\n  \
+         <parameters>\n{parameters}  </parameters>\n</OpenIOC>\n",
+        id = Uuid::new_v4(),
+        description = xml_escape(description),
+        indicator = Uuid::new_v4(),
+    );
+    IocExport {
+        format: InterchangeFormat::OpenIoc,
+        document,
+        exported,
+        skipped_unsupported: iocs.len() - exported,
+    }
+}
+
+pub fn export_iocs(
+    format: InterchangeFormat,
+    iocs: &[ExtractedIOC],
+    mapping: &CsvColumnMapping,
+    description: &str,
+) -> CoreResult<IocExport> {
+    match format {
+        InterchangeFormat::OpenIoc => Ok(write_openioc(iocs, description)),
+        InterchangeFormat::Csv => write_csv(iocs, mapping),
+    }
+}
+
+impl SandboxCore {
+    /// Import an OpenIOC or CSV document into the local IOC store, merging
+    /// indicators already there; `source` is recorded as their origin
+    pub async fn import_iocs(
+        &self,
+        format: InterchangeFormat,
+        document: &str,
+        mapping: &CsvColumnMapping,
+        source: &str,
+    ) -> CoreResult<IocImportReport> {
+        if document.len() > MAX_DOCUMENT_BYTES {
+            return Err(CoreError::validation(format!(
+                "Document of {} bytes exceeds the {} byte import limit",
+                document.len(),
+                MAX_DOCUMENT_BYTES
+            )));
+        }
+        let parsed = match format {
+            InterchangeFormat::OpenIoc => parse_openioc(document, source)?,
+            InterchangeFormat::Csv => parse_csv(document, mapping, source)?,
+        };
+
+        let mut report = IocImportReport { format, rows: parsed.rows, imported: 0, updated: 0, errors: parsed.errors };
+        let mut store = self.ioc_store.write().await;
+        for ioc in parsed.iocs {
+            match store.get_mut(&ioc_key(&ioc.ioc_type, &ioc.value)) {
+                Some(existing) => {
+                    existing.first_seen = existing.first_seen.min(ioc.first_seen);
+                    existing.confidence = existing.confidence.max(ioc.confidence);
+                    if existing.threat_intelligence.is_none() {
+                        existing.threat_intelligence = ioc.threat_intelligence;
+                    }
+                    report.updated += 1;
+                }
+                None => {
+                    store.insert(ioc_key(&ioc.ioc_type, &ioc.value), ioc);
+                    report.imported += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// The IOCs of the tenant's analysis of `sample_id` in an interchange format
+    pub async fn export_analysis_iocs(
+        &self,
+        tenant_id: &str,
+        sample_id: &str,
+        format: InterchangeFormat,
+        mapping: &CsvColumnMapping,
+    ) -> CoreResult<IocExport> {
+        let analysis = self
+            .get_analysis(tenant_id, sample_id)
+            .await?
+            .ok_or_else(|| CoreError::not_found(format!("No completed analysis for sample {}", sample_id)))?;
+        let description = format!("Phantom sandbox: {} ({:?})", analysis.sample_info.file_name, analysis.verdict);
+        export_iocs(format, &analysis.iocs_extracted, mapping, &description)
+    }
+
+    /// The local IOC store in an interchange format
+    pub async fn export_local_iocs(&self, format: InterchangeFormat, mapping: &CsvColumnMapping) -> CoreResult<IocExport> {
+        let mut iocs: Vec<ExtractedIOC> = self.ioc_store.read().await.values().cloned().collect();
+        iocs.sort_by(|a, b| (&a.ioc_type, &a.value).cmp(&(&b.ioc_type, &b.value)));
+        export_iocs(format, &iocs, mapping, "Phantom sandbox local IOC store")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn csv_import_reports_row_errors_and_merges() {
+        let core = SandboxCore::new().unwrap();
+        let csv = "Indicator;Kind;Note\n\
+                   # exported from the partner portal\n\
+                   D41D8CD98F00B204E9800998ECF8427E;md5;\"dropper; stage 1\"\n\
+                   203.0.113.7;ip;c2\n\
+                   not-a-hash;sha256;\n\
+                   evil.example;btc;\n\
+                   \n\
+                   203.0.113.7;ipv4;\"duplicate, \"\"quoted\"\"\"\n";
+        let mapping = CsvColumnMapping {
+            ioc_type: Some("kind".to_string()),
+            value: "indicator".to_string(),
+            context: Some("note".to_string()),
+            delimiter: ';',
+            ..Default::default()
+        };
+
+        let report = core.import_iocs(InterchangeFormat::Csv, csv, &mapping, "partner feed").await.unwrap();
+        assert_eq!((report.rows, report.imported, report.updated), (5, 2, 1));
+        let rows: Vec<usize> = report.errors.iter().map(|e| e.row).collect();
+        assert_eq!(rows, vec![5, 6]);
+        assert!(report.errors[1].message.contains("Unknown IOC type"));
+
+        let store = core.get_local_iocs().await;
+        let hash = &store["md5:d41d8cd98f00b204e9800998ecf8427e"];
+        assert_eq!(hash.context, "dropper; stage 1");
+        assert_eq!(hash.threat_intelligence.as_deref(), Some("partner feed"));
+
+        // A missing value column fails the whole document
+        assert!(core.import_iocs(InterchangeFormat::Csv, "a,b\n1,2\n", &CsvColumnMapping::default(), "x").await.is_err());
+    }
+
+    #[test]
+    fn openioc_round_trips_and_rejects_non_exact_items() {
+        let mut ioc = imported_ioc("domain", "evil.example".to_string(), "test");
+        ioc.category = "c2".to_string();
+        ioc.confidence = 0.95;
+        let unsupported = imported_ioc("bitcoin", "1abc".to_string(), "test");
+        let export = write_openioc(&[ioc, unsupported], "Sample <x> & co");
+        assert_eq!((export.exported, export.skipped_unsupported), (1, 1));
+
+        let parsed = parse_openioc(&export.document, "round trip").unwrap();
+        assert!(parsed.errors.is_empty());
+        assert_eq!(parsed.iocs[0].value, "evil.example");
+        assert_eq!(parsed.iocs[0].category, "c2");
+        assert_eq!(parsed.iocs[0].confidence, 0.95);
+
+        let document = r#"<?xml version="1.0"?>
+            <ioc:OpenIOC xmlns:ioc="http://openioc.org/schemas/OpenIOC_1.1">
+              <ioc:criteria>
+                <ioc:Indicator operator="OR">
+                  <ioc:IndicatorItem condition="is"><ioc:Context search="FileItem/Md5sum"/><ioc:Content type="md5">d41d8cd98f00b204e9800998ecf8427e</ioc:Content></ioc:IndicatorItem>
+                  <ioc:IndicatorItem condition="contains"><ioc:Context search="FileItem/FileName"/><ioc:Content type="string">invoice</ioc:Content></ioc:IndicatorItem>
+                  <ioc:Indicator operator="AND">
+                    <ioc:IndicatorItem condition="is"><ioc:Context search="FileItem/FileName"/><ioc:Content type="string">a.exe</ioc:Content></ioc:IndicatorItem>
+                  </ioc:Indicator>
+                  <ioc:IndicatorItem condition="is"><ioc:Context search="ServiceItem/name"/><ioc:Content type="string">svc</ioc:Content></ioc:IndicatorItem>
+                </ioc:Indicator>
+              </ioc:criteria>
+            </ioc:OpenIOC>"#;
+        let parsed = parse_openioc(document, "partner").unwrap();
+        assert_eq!(parsed.rows, 4);
+        assert_eq!(parsed.iocs.len(), 1);
+        assert_eq!(parsed.errors.iter().map(|e| e.row).collect::<Vec<_>>(), vec![2, 3, 4]);
+
+        assert!(parse_openioc("<stix:Package/>", "x").is_err());
+    }
+}
@@ -0,0 +1,430 @@
+//! Persistent Job Queue
+//!
+//! Pluggable storage for analysis jobs, the raw sample bytes they need and
+//! the analyses they produce, so the queue survives a process restart.
+//! Jobs that were mid-flight when the process died are re-queued on
+//! recovery. Backends: in-memory (default), a plain file directory, and
+//! sled behind the `sled-store` feature.
+
+use crate::dedup;
+use crate::queue::AnalysisQueue;
+use crate::{AnalysisJob, JobStatus, SandboxAnalysis, SandboxCore};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::RwLock;
+
+/// Storage backend for the analysis queue
+pub trait JobStore: Send + Sync {
+    fn save_job(&self, job: &AnalysisJob) -> Result<(), String>;
+    fn load_jobs(&self) -> Result<Vec<AnalysisJob>, String>;
+    fn save_sample(&self, sample_id: &str, data: &[u8]) -> Result<(), String>;
+    fn load_sample(&self, sample_id: &str) -> Result<Option<Vec<u8>>, String>;
+    fn sample_ids(&self) -> Result<Vec<String>, String>;
+    fn save_analysis(&self, sample_id: &str, analysis: &SandboxAnalysis) -> Result<(), String>;
+    fn load_analyses(&self) -> Result<Vec<SandboxAnalysis>, String>;
+    /// Remove a job together with its sample bytes and analysis
+    fn delete_records(&self, job_id: &str, sample_id: &str) -> Result<(), String>;
+}
+
+/// Outcome of restoring the queue from a job store
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueueRecoveryReport {
+    pub restored_jobs: usize,
+    /// Jobs that were pre-processing, running or post-processing at shutdown
+    pub requeued_jobs: Vec<String>,
+    pub restored_analyses: usize,
+    /// Queued jobs whose sample bytes could not be found
+    pub missing_samples: Vec<String>,
+}
+
+fn store_error(e: String) -> napi::Error<String> {
+    napi::Error::new("GenericFailure".to_string(), e)
+}
+
+fn is_in_flight(status: &JobStatus) -> bool {
+    matches!(status, JobStatus::PreProcessing | JobStatus::Running | JobStatus::PostProcessing)
+}
+
+// In-Memory Store
+
+/// Non-persistent store, the default when no path is configured
+#[derive(Default)]
+pub struct MemoryJobStore {
+    jobs: Mutex<HashMap<String, AnalysisJob>>,
+    samples: Mutex<HashMap<String, Vec<u8>>>,
+    analyses: Mutex<HashMap<String, SandboxAnalysis>>,
+}
+
+impl JobStore for MemoryJobStore {
+    fn save_job(&self, job: &AnalysisJob) -> Result<(), String> {
+        self.jobs.lock().map_err(|e| e.to_string())?.insert(job.job_id.clone(), job.clone());
+        Ok(())
+    }
+
+    fn load_jobs(&self) -> Result<Vec<AnalysisJob>, String> {
+        Ok(self.jobs.lock().map_err(|e| e.to_string())?.values().cloned().collect())
+    }
+
+    fn save_sample(&self, sample_id: &str, data: &[u8]) -> Result<(), String> {
+        self.samples.lock().map_err(|e| e.to_string())?.insert(sample_id.to_string(), data.to_vec());
+        Ok(())
+    }
+
+    fn load_sample(&self, sample_id: &str) -> Result<Option<Vec<u8>>, String> {
+        Ok(self.samples.lock().map_err(|e| e.to_string())?.get(sample_id).cloned())
+    }
+
+    fn sample_ids(&self) -> Result<Vec<String>, String> {
+        Ok(self.samples.lock().map_err(|e| e.to_string())?.keys().cloned().collect())
+    }
+
+    fn save_analysis(&self, sample_id: &str, analysis: &SandboxAnalysis) -> Result<(), String> {
+        self.analyses.lock().map_err(|e| e.to_string())?.insert(sample_id.to_string(), analysis.clone());
+        Ok(())
+    }
+
+    fn load_analyses(&self) -> Result<Vec<SandboxAnalysis>, String> {
+        Ok(self.analyses.lock().map_err(|e| e.to_string())?.values().cloned().collect())
+    }
+
+    fn delete_records(&self, job_id: &str, sample_id: &str) -> Result<(), String> {
+        self.jobs.lock().map_err(|e| e.to_string())?.remove(job_id);
+        self.samples.lock().map_err(|e| e.to_string())?.remove(sample_id);
+        self.analyses.lock().map_err(|e| e.to_string())?.remove(sample_id);
+        Ok(())
+    }
+}
+
+// File Store
+
+/// One file per record under `jobs/`, `samples/` and `analyses/`
+///
+/// Writes go to a temporary file that is renamed into place, so a crash
+/// mid-write never leaves a truncated record behind.
+pub struct FileJobStore {
+    root: PathBuf,
+}
+
+impl FileJobStore {
+    pub fn open(root: impl AsRef<Path>) -> Result<Self, String> {
+        let root = root.as_ref().to_path_buf();
+        for dir in ["jobs", "samples", "analyses"] {
+            fs::create_dir_all(root.join(dir))
+                .map_err(|e| format!("Failed to create {}: {}", root.join(dir).display(), e))?;
+        }
+        Ok(Self { root })
+    }
+
+    fn record_path(&self, dir: &str, id: &str, extension: &str) -> Result<PathBuf, String> {
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(format!("Invalid record id {}", id));
+        }
+        Ok(self.root.join(dir).join(format!("{}.{}", id, extension)))
+    }
+
+    fn write_atomic(path: &Path, data: &[u8]) -> Result<(), String> {
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, data).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+        fs::rename(&tmp, path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    fn load_dir<T: DeserializeOwned>(&self, dir: &str) -> Result<Vec<T>, String> {
+        let mut records = Vec::new();
+        let entries = fs::read_dir(self.root.join(dir)).map_err(|e| format!("Failed to read {}: {}", dir, e))?;
+        for entry in entries {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let data = fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            match serde_json::from_slice(&data) {
+                Ok(record) => records.push(record),
+                Err(e) => log::warn!("Skipping unreadable record {}: {}", path.display(), e),
+            }
+        }
+        Ok(records)
+    }
+}
+
+impl JobStore for FileJobStore {
+    fn save_job(&self, job: &AnalysisJob) -> Result<(), String> {
+        let data = serde_json::to_vec(job).map_err(|e| e.to_string())?;
+        Self::write_atomic(&self.record_path("jobs", &job.job_id, "json")?, &data)
+    }
+
+    fn load_jobs(&self) -> Result<Vec<AnalysisJob>, String> {
+        self.load_dir("jobs")
+    }
+
+    fn save_sample(&self, sample_id: &str, data: &[u8]) -> Result<(), String> {
+        Self::write_atomic(&self.record_path("samples", sample_id, "bin")?, data)
+    }
+
+    fn load_sample(&self, sample_id: &str) -> Result<Option<Vec<u8>>, String> {
+        let path = self.record_path("samples", sample_id, "bin")?;
+        match fs::read(&path) {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    fn sample_ids(&self) -> Result<Vec<String>, String> {
+        let entries = fs::read_dir(self.root.join("samples")).map_err(|e| format!("Failed to read samples: {}", e))?;
+        let mut ids = Vec::new();
+        for entry in entries {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path.extension().and_then(|e| e.to_str()) == Some("bin") {
+                if let Some(id) = path.file_stem().and_then(|s| s.to_str()) {
+                    ids.push(id.to_string());
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    fn save_analysis(&self, sample_id: &str, analysis: &SandboxAnalysis) -> Result<(), String> {
+        let data = serde_json::to_vec(analysis).map_err(|e| e.to_string())?;
+        Self::write_atomic(&self.record_path("analyses", sample_id, "json")?, &data)
+    }
+
+    fn load_analyses(&self) -> Result<Vec<SandboxAnalysis>, String> {
+        self.load_dir("analyses")
+    }
+
+    fn delete_records(&self, job_id: &str, sample_id: &str) -> Result<(), String> {
+        let paths = [
+            self.record_path("jobs", job_id, "json")?,
+            self.record_path("samples", sample_id, "bin")?,
+            self.record_path("analyses", sample_id, "json")?,
+        ];
+        for path in paths {
+            match fs::remove_file(&path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(format!("Failed to remove {}: {}", path.display(), e)),
+            }
+        }
+        Ok(())
+    }
+}
+
+// Sled Store
+
+/// Embedded sled database with one tree per record type
+#[cfg(feature = "sled-store")]
+pub struct SledJobStore {
+    jobs: sled::Tree,
+    samples: sled::Tree,
+    analyses: sled::Tree,
+}
+
+#[cfg(feature = "sled-store")]
+impl SledJobStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, String> {
+        let db = sled::open(path).map_err(|e| format!("Failed to open sled store: {}", e))?;
+        let tree = |name: &str| db.open_tree(name).map_err(|e| format!("Failed to open {} tree: {}", name, e));
+        Ok(Self {
+            jobs: tree("jobs")?,
+            samples: tree("samples")?,
+            analyses: tree("analyses")?,
+        })
+    }
+
+    fn insert(tree: &sled::Tree, key: &str, value: &[u8]) -> Result<(), String> {
+        tree.insert(key.as_bytes(), value).map_err(|e| e.to_string())?;
+        tree.flush().map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn load_tree<T: DeserializeOwned>(tree: &sled::Tree) -> Result<Vec<T>, String> {
+        let mut records = Vec::new();
+        for entry in tree.iter() {
+            let (key, value) = entry.map_err(|e| e.to_string())?;
+            match serde_json::from_slice(&value) {
+                Ok(record) => records.push(record),
+                Err(e) => log::warn!("Skipping unreadable record {}: {}", String::from_utf8_lossy(&key), e),
+            }
+        }
+        Ok(records)
+    }
+}
+
+#[cfg(feature = "sled-store")]
+impl JobStore for SledJobStore {
+    fn save_job(&self, job: &AnalysisJob) -> Result<(), String> {
+        let data = serde_json::to_vec(job).map_err(|e| e.to_string())?;
+        Self::insert(&self.jobs, &job.job_id, &data)
+    }
+
+    fn load_jobs(&self) -> Result<Vec<AnalysisJob>, String> {
+        Self::load_tree(&self.jobs)
+    }
+
+    fn save_sample(&self, sample_id: &str, data: &[u8]) -> Result<(), String> {
+        Self::insert(&self.samples, sample_id, data)
+    }
+
+    fn load_sample(&self, sample_id: &str) -> Result<Option<Vec<u8>>, String> {
+        let value = self.samples.get(sample_id.as_bytes()).map_err(|e| e.to_string())?;
+        Ok(value.map(|v| v.to_vec()))
+    }
+
+    fn sample_ids(&self) -> Result<Vec<String>, String> {
+        self.samples
+            .iter()
+            .keys()
+            .map(|key| key.map(|k| String::from_utf8_lossy(&k).into_owned()).map_err(|e| e.to_string()))
+            .collect()
+    }
+
+    fn save_analysis(&self, sample_id: &str, analysis: &SandboxAnalysis) -> Result<(), String> {
+        let data = serde_json::to_vec(analysis).map_err(|e| e.to_string())?;
+        Self::insert(&self.analyses, sample_id, &data)
+    }
+
+    fn load_analyses(&self) -> Result<Vec<SandboxAnalysis>, String> {
+        Self::load_tree(&self.analyses)
+    }
+
+    fn delete_records(&self, job_id: &str, sample_id: &str) -> Result<(), String> {
+        for (tree, key) in [(&self.jobs, job_id), (&self.samples, sample_id), (&self.analyses, sample_id)] {
+            tree.remove(key.as_bytes()).map_err(|e| e.to_string())?;
+            tree.flush().map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+/// Open the persistent backend for `path`: sled when built with
+/// `sled-store`, otherwise a file directory
+pub fn open_job_store(path: &str) -> Result<Arc<dyn JobStore>, String> {
+    #[cfg(feature = "sled-store")]
+    let store: Arc<dyn JobStore> = Arc::new(SledJobStore::open(path)?);
+    #[cfg(not(feature = "sled-store"))]
+    let store: Arc<dyn JobStore> = Arc::new(FileJobStore::open(path)?);
+    Ok(store)
+}
+
+// Recovery
+
+impl SandboxCore {
+    /// Create a core whose queue is persisted to `store`, restoring any jobs
+    /// and analyses it already holds
+    pub fn with_job_store(store: Arc<dyn JobStore>) -> Result<Self, String> {
+        let mut core = Self::new().map_err(|e| e.to_string())?;
+        let mut report = QueueRecoveryReport::default();
+
+        let mut jobs = store.load_jobs()?;
+        let mut samples = HashMap::new();
+        for job in jobs.iter_mut() {
+            if is_in_flight(&job.status) {
+                job.status = JobStatus::Queued;
+                job.analysis_start = None;
+                job.progress = 0.0;
+                job.error_message = Some("Re-queued after interrupted analysis".to_string());
+                store.save_job(job)?;
+                report.requeued_jobs.push(job.job_id.clone());
+            }
+            if matches!(job.status, JobStatus::Queued) {
+                match store.load_sample(&job.sample_id)? {
+                    Some(data) => {
+                        samples.insert(job.sample_id.clone(), Arc::new(data));
+                    }
+                    None => report.missing_samples.push(job.sample_id.clone()),
+                }
+            }
+        }
+        jobs.sort_by_key(|job| job.submission_time);
+        report.restored_jobs = jobs.len();
+        let hash_index = dedup::rebuild_hash_index(store.as_ref(), &jobs)?;
+
+        let analyses: HashMap<String, SandboxAnalysis> = store
+            .load_analyses()?
+            .into_iter()
+            .map(|analysis| (analysis.sample_info.sample_id.clone(), analysis))
+            .collect();
+        report.restored_analyses = analyses.len();
+
+        if let Some(metrics) = Arc::get_mut(&mut core.performance_metrics) {
+            let metrics = metrics.get_mut();
+            metrics.queue_length = jobs.iter().filter(|j| matches!(j.status, JobStatus::Queued)).count() as u32;
+            metrics.total_analyses = jobs.len() as u64;
+            metrics.successful_analyses = jobs.iter().filter(|j| matches!(j.status, JobStatus::Completed)).count() as u64;
+        }
+
+        log::info!(
+            "Restored {} jobs ({} re-queued) and {} analyses from job store",
+            report.restored_jobs,
+            report.requeued_jobs.len(),
+            report.restored_analyses
+        );
+
+        core.analysis_queue = Arc::new(RwLock::new(AnalysisQueue::from_jobs(jobs)));
+        core.sample_data = Arc::new(RwLock::new(samples));
+        core.completed_analyses = Arc::new(RwLock::new(analyses));
+        core.hash_index = Arc::new(RwLock::new(hash_index));
+        core.job_store = store;
+        core.recovery_report = report;
+        Ok(core)
+    }
+
+    pub fn queue_recovery_report(&self) -> &QueueRecoveryReport {
+        &self.recovery_report
+    }
+
+    pub(crate) fn persist_job(&self, job: &AnalysisJob) -> napi::Result<(), String> {
+        self.job_store.save_job(job).map_err(store_error)
+    }
+
+    pub(crate) fn persist_sample(&self, sample_id: &str, data: &[u8]) -> napi::Result<(), String> {
+        self.job_store.save_sample(sample_id, data).map_err(store_error)
+    }
+
+    pub(crate) fn persist_analysis(&self, sample_id: &str, analysis: &SandboxAnalysis) -> napi::Result<(), String> {
+        self.job_store.save_analysis(sample_id, analysis).map_err(store_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AnalysisPriority;
+
+    #[tokio::test]
+    async fn test_file_store_survives_restart_and_requeues_in_flight_jobs() {
+        let dir = std::env::temp_dir().join(format!("phantom-sandbox-queue-{}", uuid::Uuid::new_v4()));
+        let store: Arc<dyn JobStore> = Arc::new(FileJobStore::open(&dir).unwrap());
+
+        let core = SandboxCore::with_job_store(store.clone()).unwrap();
+        let done = core.submit_sample("default", b"MZ first", "first.exe".to_string(), AnalysisPriority::High, vec![], false).await.unwrap();
+        core.process_queue().await.unwrap();
+        assert!(core.get_analysis("default", &done).await.unwrap().is_some());
+        let pending = core.submit_sample("default", b"MZ second", "second.exe".to_string(), AnalysisPriority::Low, vec![], false).await.unwrap();
+
+        // Simulate a crash while the second job was running
+        let mut running = core.get_analysis_status("default", &pending).await.unwrap().unwrap();
+        running.status = JobStatus::Running;
+        store.save_job(&running).unwrap();
+        drop(core);
+
+        let reopened: Arc<dyn JobStore> = Arc::new(FileJobStore::open(&dir).unwrap());
+        let restarted = SandboxCore::with_job_store(reopened).unwrap();
+        let report = restarted.queue_recovery_report().clone();
+        assert_eq!(report.restored_jobs, 2);
+        assert_eq!(report.requeued_jobs, vec![running.job_id.clone()]);
+        assert_eq!(report.restored_analyses, 1);
+        assert!(report.missing_samples.is_empty());
+
+        let job = restarted.get_analysis_status("default", &pending).await.unwrap().unwrap();
+        assert!(matches!(job.status, JobStatus::Queued));
+        assert!(restarted.get_analysis("default", &done).await.unwrap().is_some());
+
+        restarted.process_queue().await.unwrap();
+        assert!(restarted.get_analysis("default", &pending).await.unwrap().is_some());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
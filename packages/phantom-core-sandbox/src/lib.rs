@@ -5,7 +5,7 @@
 
 use napi::bindgen_prelude::*;
 use napi_derive::napi;
-use napi::{Result, Error as NapiError};
+use napi::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use chrono::{DateTime, Utc};
@@ -16,6 +16,79 @@ use md5;
 use sha1::{Sha1, Digest as Sha1Digest};
 use sha2::{Sha256, Digest};
 
+use crate::error::{CoreError, CoreResult};
+
+pub mod access;
+pub mod actors;
+pub mod artifacts;
+pub mod audit;
+pub mod campaign_graph;
+pub mod change_heuristics;
+pub mod clamav;
+pub mod cluster;
+pub mod countermeasures;
+pub mod dedup;
+pub mod detonation;
+pub mod diff;
+pub mod dns_analytics;
+pub mod email;
+pub mod engine_plugins;
+pub mod enrichment;
+pub mod error;
+pub mod export;
+pub mod field_selection;
+pub mod fuzzy;
+pub mod indexing;
+pub mod interactive;
+pub mod interchange;
+pub mod job_store;
+pub mod live_feed;
+pub mod memory;
+pub mod metrics_history;
+pub mod misp;
+pub mod mitre;
+pub mod notifications;
+pub mod objects;
+pub mod packers;
+pub mod personas;
+pub mod phishing;
+pub mod process_tree;
+pub mod prometheus;
+pub mod queue;
+pub mod redaction;
+#[cfg(feature = "phantom-enterprise-standards")]
+pub mod references;
+pub mod retention;
+pub mod sample_store;
+pub mod scheduler;
+pub mod secrets;
+pub mod static_pipeline;
+#[cfg(feature = "phantom-enterprise-standards")]
+pub mod status;
+pub mod string_packs;
+pub mod suppression;
+pub mod syslog;
+pub mod tenancy;
+pub mod timeline;
+pub mod uploads;
+pub mod uri_intake;
+pub mod url_detonation;
+pub mod yara;
+
+// List pagination is shared with the other cores
+pub use phantom_enterprise_standards::pagination;
+
+use dedup::{HashRecord, SubmissionDisposition};
+use detonation::{DetonationDriver, DetonationReport, DriverConfig};
+use job_store::{JobStore, MemoryJobStore, QueueRecoveryReport};
+use misp::{MispConfig, MispState};
+use notifications::{NotificationDispatcher, WebhookEndpoint, WebhookEvent};
+use personas::{collect_observations, DecoyInteractionReport, NetworkPersona};
+pub use process_tree::ProcessTree;
+use retention::{RetentionMetrics, RetentionPolicy, RetentionState};
+use static_pipeline::{StaticPipeline, StaticPipelineConfig, StaticPipelineTimings};
+use yara::YaraRuleSet;
+
 // Enterprise Sandbox Configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SandboxConfig {
@@ -37,6 +110,12 @@ pub struct VMEnvironment {
     pub network_config: String,
     pub snapshot_id: String,
     pub resource_limits: ResourceLimits,
+    /// Backend that detonates samples in this environment
+    #[serde(default)]
+    pub driver: DriverConfig,
+    /// Anti-evasion countermeasure profile applied on every detonation
+    #[serde(default)]
+    pub countermeasure_profile: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -86,6 +165,7 @@ pub enum EngineType {
     YARA,
     Sigma,
     CustomRules,
+    Antivirus,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -129,6 +209,8 @@ pub struct EnterpriseSandboxFeatures {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SandboxAnalysis {
     pub analysis_id: String,
+    #[serde(default = "tenancy::default_tenant")]
+    pub tenant_id: String,
     pub sample_info: SampleInfo,
     pub analysis_metadata: AnalysisMetadata,
     pub verdict: SandboxVerdict,
@@ -148,6 +230,15 @@ pub struct SandboxAnalysis {
     pub threat_intelligence: ThreatIntelligence,
     pub enterprise_insights: EnterpriseSandboxInsights,
     pub performance_metrics: AnalysisPerformanceMetrics,
+    /// Network persona presented to the sample and the decoys it touched
+    #[serde(default)]
+    pub decoy_interactions: Option<DecoyInteractionReport>,
+    /// Real detonation on a hypervisor or container backend, if one ran
+    #[serde(default)]
+    pub detonation: Option<DetonationReport>,
+    /// What each plugin engine contributed
+    #[serde(default)]
+    pub engine_results: Vec<engine_plugins::EngineResult>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -164,6 +255,12 @@ pub struct SampleInfo {
     pub source: String,
     pub priority: AnalysisPriority,
     pub tags: Vec<String>,
+    /// ssdeep digest, `blocksize:hash:hash`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssdeep: Option<String>,
+    /// TLSH digest; absent for inputs under 50 bytes or with too little variation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tlsh: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -175,6 +272,19 @@ pub enum AnalysisPriority {
     Emergency,
 }
 
+impl AnalysisPriority {
+    /// Higher ranks are served first
+    pub fn rank(&self) -> u32 {
+        match self {
+            AnalysisPriority::Emergency => 5,
+            AnalysisPriority::Critical => 4,
+            AnalysisPriority::High => 3,
+            AnalysisPriority::Normal => 2,
+            AnalysisPriority::Low => 1,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnalysisMetadata {
     pub analysis_start: DateTime<Utc>,
@@ -185,6 +295,9 @@ pub struct AnalysisMetadata {
     pub timeout_reached: bool,
     pub errors: Vec<String>,
     pub warnings: Vec<String>,
+    /// Analyst session that held the guest, for interactive submissions
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interactive_session: Option<interactive::InteractiveSessionRecord>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -370,7 +483,7 @@ pub struct StartupEntry {
 }
 
 // Advanced Network Analysis
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct NetworkAnalysis {
     pub network_score: f64,
     pub connections: Vec<NetworkConnection>,
@@ -382,6 +495,12 @@ pub struct NetworkAnalysis {
     pub c2_indicators: Vec<C2Indicator>,
     pub data_exfiltration: Vec<DataExfiltrationEvent>,
     pub botnet_communication: Vec<BotnetCommunication>,
+    /// What capture limits and redaction removed from `http_requests`
+    #[serde(default)]
+    pub capture_audit: Option<redaction::HttpCaptureAudit>,
+    /// Browser capture when the sample is a URL
+    #[serde(default)]
+    pub url_detonation: Option<url_detonation::UrlDetonation>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -438,6 +557,9 @@ pub struct HTTPRequest {
     pub url: String,
     pub headers: HashMap<String, String>,
     pub body: Option<String>,
+    /// The body was cut to the capture limit
+    #[serde(default)]
+    pub body_truncated: bool,
     pub response_code: u16,
     pub response_size: u64,
     pub timestamp: DateTime<Utc>,
@@ -457,6 +579,18 @@ pub struct C2Indicator {
     pub communication_pattern: String,
     pub encryption_used: bool,
     pub protocol: String,
+    /// Measurements that raised the indicator, for detector findings
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub evidence: Vec<IndicatorEvidence>,
+}
+
+/// One measurement behind an indicator and the threshold it crossed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndicatorEvidence {
+    pub signal: String,
+    pub observed: f64,
+    pub threshold: f64,
+    pub detail: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -506,6 +640,8 @@ pub struct SuspiciousFile {
     pub file_type: String,
     pub created_by: String,
     pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub mitre_techniques: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -540,6 +676,8 @@ pub struct SuspiciousRegistryKey {
     pub threat_score: f64,
     pub created_by: String,
     pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub mitre_techniques: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -582,13 +720,6 @@ pub struct ProcessAnalysis {
     pub parent_child_anomalies: Vec<ProcessAnomaly>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ProcessTree {
-    pub root_process: ProcessInfo,
-    pub child_processes: Vec<ProcessInfo>,
-    pub relationships: Vec<ProcessRelationship>,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessInfo {
     pub process_id: u32,
@@ -789,6 +920,9 @@ pub struct StaticAnalysis {
     pub yara_matches: Vec<YARAMatch>,
     pub signature_verification: SignatureVerification,
     pub anti_analysis_features: Vec<AntiAnalysisFeature>,
+    /// ClamAV scan of the sample, when clamd is configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub antivirus: Option<clamav::AntivirusScan>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -885,6 +1019,9 @@ pub struct StringsAnalysis {
     pub file_paths: Vec<String>,
     pub registry_keys: Vec<String>,
     pub api_functions: Vec<String>,
+    /// Threat relevance of the suspicious strings taken together (0.0 - 1.0)
+    #[serde(default)]
+    pub threat_score: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -894,6 +1031,9 @@ pub struct SuspiciousString {
     pub description: String,
     pub threat_score: f64,
     pub encoding: String,
+    /// Pattern pack that classified the string
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern_pack: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1060,6 +1200,8 @@ pub struct ExtractedIOC {
     pub context: String,
     pub first_seen: DateTime<Utc>,
     pub threat_intelligence: Option<String>,
+    #[serde(default)]
+    pub enrichments: Vec<enrichment::IOCEnrichment>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1083,6 +1225,9 @@ pub struct ThreatIntelligence {
     pub attribution_confidence: f64,
     pub geographical_targeting: Vec<String>,
     pub industry_targeting: Vec<String>,
+    /// Ranked actor hypotheses from the knowledge base, see [`actors`]
+    #[serde(default)]
+    pub attribution: Vec<actors::AttributionHypothesis>,
 }
 
 // Enterprise Insights and Reporting
@@ -1203,6 +1348,8 @@ pub struct DataExfiltration {
 pub struct AnalysisJob {
     pub job_id: String,
     pub sample_id: String,
+    #[serde(default = "tenancy::default_tenant")]
+    pub tenant_id: String,
     pub priority: AnalysisPriority,
     pub submission_time: DateTime<Utc>,
     pub analysis_start: Option<DateTime<Utc>>,
@@ -1235,6 +1382,15 @@ pub struct AnalysisConfiguration {
     pub yara_scanning: bool,
     pub memory_dumping: bool,
     pub network_capture: bool,
+    /// Decoy network persona for the simulation layer; defaults to the corporate persona
+    #[serde(default)]
+    pub network_persona: Option<String>,
+    /// URL to load in a headless browser instead of executing the sample
+    #[serde(default)]
+    pub target_url: Option<String>,
+    /// Interactive session holding the guest after the sample ran
+    #[serde(default)]
+    pub interactive_session: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1272,12 +1428,55 @@ pub struct BatchSummary {
 // Enterprise-Grade Sandbox Analysis Engine
 pub struct SandboxCore {
     config: SandboxConfig,
-    analysis_queue: Arc<RwLock<Vec<AnalysisJob>>>,
+    analysis_queue: Arc<RwLock<queue::AnalysisQueue>>,
     completed_analyses: Arc<RwLock<HashMap<String, SandboxAnalysis>>>,
     vm_environments: Arc<RwLock<HashMap<String, VMEnvironment>>>,
     analysis_engines: Arc<RwLock<HashMap<String, AnalysisEngine>>>,
     performance_metrics: Arc<RwLock<SandboxPerformanceMetrics>>,
     threat_intelligence: Arc<RwLock<HashMap<String, ThreatIntelligence>>>,
+    notifications: Arc<NotificationDispatcher>,
+    sample_data: Arc<RwLock<HashMap<String, Arc<Vec<u8>>>>>,
+    static_pipeline: Arc<StaticPipeline>,
+    personas: Arc<RwLock<HashMap<String, NetworkPersona>>>,
+    countermeasure_profiles: Arc<RwLock<HashMap<String, countermeasures::CountermeasureProfile>>>,
+    job_store: Arc<dyn JobStore>,
+    recovery_report: QueueRecoveryReport,
+    yara_rules: Arc<std::sync::RwLock<YaraRuleSet>>,
+    string_packs: Arc<std::sync::RwLock<string_packs::StringPackSet>>,
+    hash_index: Arc<RwLock<HashMap<String, HashRecord>>>,
+    retention: Arc<RwLock<RetentionState>>,
+    misp: Arc<RwLock<MispState>>,
+    secrets: Arc<secrets::SecretStore>,
+    ioc_store: Arc<RwLock<HashMap<String, ExtractedIOC>>>,
+    detonation_drivers: Arc<RwLock<HashMap<String, Arc<dyn DetonationDriver>>>>,
+    cluster: Arc<cluster::ClusterState>,
+    sample_encryption: Option<Arc<sample_store::EncryptedSampleStore>>,
+    #[cfg(feature = "phantom-enterprise-standards")]
+    enrichment: Arc<enrichment::EnrichmentState>,
+    exports: Arc<export::ExportRegistry>,
+    #[cfg(feature = "phantom-enterprise-standards")]
+    syslog: Arc<syslog::SyslogState>,
+    #[cfg(feature = "phantom-enterprise-standards")]
+    live_feed: Arc<live_feed::LiveFeedState>,
+    #[cfg(feature = "phantom-enterprise-standards")]
+    indexing: Arc<indexing::IndexingState>,
+    http_capture: Arc<RwLock<redaction::HttpCaptureState>>,
+    phishing_triages: Arc<RwLock<HashMap<String, phishing::PhishingTriage>>>,
+    url_detonation: Arc<url_detonation::UrlDetonationState>,
+    dns_analytics: Arc<dns_analytics::DnsAnalyticsState>,
+    uri_intake: Arc<uri_intake::UriIntakeState>,
+    metrics_history: Arc<metrics_history::MetricsHistoryState>,
+    prometheus: Arc<prometheus::PrometheusState>,
+    suppression: Arc<suppression::SuppressionState>,
+    artifacts: Arc<RwLock<artifacts::ArtifactStore>>,
+    actors: Arc<RwLock<actors::ActorKnowledgeBase>>,
+    fuzzy: Arc<fuzzy::FuzzyState>,
+    memory_analyzer: Arc<std::sync::RwLock<memory::MemoryAnalyzer>>,
+    engine_plugins: Arc<engine_plugins::EnginePluginState>,
+    scheduler: Arc<scheduler::SchedulerState>,
+    interactive: Arc<interactive::InteractiveSessions>,
+    clamav: Arc<clamav::ClamAvState>,
+    uploads: Arc<uploads::UploadState>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1291,17 +1490,28 @@ pub struct SandboxPerformanceMetrics {
     pub throughput_per_hour: f64,
     pub uptime_hours: f64,
     pub last_reset: DateTime<Utc>,
+    #[serde(default)]
+    pub retention: RetentionMetrics,
+    /// Reserved CPU, memory and VM slots of running analyses
+    #[serde(default)]
+    pub scheduler: scheduler::SchedulerUtilization,
 }
 
 impl SandboxCore {
-    pub fn new() -> Result<Self, String> {
+    pub fn new() -> CoreResult<Self> {
         let config = Self::default_config();
         let vm_environments = Self::initialize_vm_environments()?;
         let analysis_engines = Self::initialize_analysis_engines()?;
+        let yara_rules = Arc::new(std::sync::RwLock::new(YaraRuleSet::default()));
+        let string_packs = Arc::new(std::sync::RwLock::new(string_packs::StringPackSet::default()));
+        let static_pipeline = StaticPipeline::new(StaticPipelineConfig::default())
+            .map_err(CoreError::backend)?
+            .with_yara_scanner(yara::pipeline_scanner(yara_rules.clone()))
+            .with_string_classifier(string_packs::pipeline_classifier(string_packs.clone()));
         
         Ok(Self {
             config,
-            analysis_queue: Arc::new(RwLock::new(Vec::new())),
+            analysis_queue: Arc::new(RwLock::new(queue::AnalysisQueue::default())),
             completed_analyses: Arc::new(RwLock::new(HashMap::new())),
             vm_environments: Arc::new(RwLock::new(vm_environments)),
             analysis_engines: Arc::new(RwLock::new(analysis_engines)),
@@ -1315,8 +1525,57 @@ impl SandboxCore {
                 throughput_per_hour: 0.0,
                 uptime_hours: 0.0,
                 last_reset: Utc::now(),
+                retention: RetentionMetrics::default(),
+                scheduler: scheduler::SchedulerUtilization::default(),
             })),
             threat_intelligence: Arc::new(RwLock::new(HashMap::new())),
+            notifications: Arc::new(NotificationDispatcher::with_default_sender()),
+            sample_data: Arc::new(RwLock::new(HashMap::new())),
+            static_pipeline: Arc::new(static_pipeline),
+            personas: Arc::new(RwLock::new(
+                NetworkPersona::builtin().into_iter().map(|p| (p.persona_id.clone(), p)).collect(),
+            )),
+            countermeasure_profiles: Arc::new(RwLock::new(
+                countermeasures::CountermeasureProfile::builtin().into_iter().map(|p| (p.profile_id.clone(), p)).collect(),
+            )),
+            job_store: Arc::new(MemoryJobStore::default()),
+            recovery_report: QueueRecoveryReport::default(),
+            yara_rules,
+            string_packs,
+            hash_index: Arc::new(RwLock::new(HashMap::new())),
+            retention: Arc::new(RwLock::new(RetentionState::default())),
+            misp: Arc::new(RwLock::new(MispState::default())),
+            secrets: Arc::new(secrets::SecretStore::new(Arc::new(secrets::EnvKey::new(secrets::MASTER_KEY_ENV)))),
+            ioc_store: Arc::new(RwLock::new(HashMap::new())),
+            detonation_drivers: Arc::new(RwLock::new(HashMap::new())),
+            cluster: Arc::new(cluster::ClusterState::default()),
+            sample_encryption: None,
+            #[cfg(feature = "phantom-enterprise-standards")]
+            enrichment: Arc::new(enrichment::EnrichmentState::default()),
+            exports: Arc::new(export::ExportRegistry::default()),
+            #[cfg(feature = "phantom-enterprise-standards")]
+            syslog: Arc::new(syslog::SyslogState::default()),
+            #[cfg(feature = "phantom-enterprise-standards")]
+            live_feed: Arc::new(live_feed::LiveFeedState::default()),
+            #[cfg(feature = "phantom-enterprise-standards")]
+            indexing: Arc::new(indexing::IndexingState::default()),
+            http_capture: Arc::new(RwLock::new(redaction::HttpCaptureState::default())),
+            phishing_triages: Arc::new(RwLock::new(HashMap::new())),
+            url_detonation: Arc::new(url_detonation::UrlDetonationState::default()),
+            dns_analytics: Arc::new(dns_analytics::DnsAnalyticsState::default()),
+            uri_intake: Arc::new(uri_intake::UriIntakeState::default()),
+            metrics_history: Arc::new(metrics_history::MetricsHistoryState::default()),
+            prometheus: Arc::new(prometheus::PrometheusState::default()),
+            suppression: Arc::new(suppression::SuppressionState::default()),
+            artifacts: Arc::new(RwLock::new(artifacts::ArtifactStore::default())),
+            actors: Arc::new(RwLock::new(actors::ActorKnowledgeBase::default())),
+            fuzzy: Arc::new(fuzzy::FuzzyState::default()),
+            memory_analyzer: Arc::new(std::sync::RwLock::new(memory::MemoryAnalyzer::default())),
+            engine_plugins: Arc::new(engine_plugins::EnginePluginState::default()),
+            scheduler: Arc::new(scheduler::SchedulerState::default()),
+            interactive: Arc::new(interactive::InteractiveSessions::default()),
+            clamav: Arc::new(clamav::ClamAvState::default()),
+            uploads: Arc::new(uploads::UploadState::default()),
         })
     }
 
@@ -1383,6 +1642,8 @@ impl SandboxCore {
                 disk_gb: 50,
                 network_bandwidth_mbps: 1000,
             },
+            driver: DriverConfig::Simulated,
+            countermeasure_profile: None,
         });
 
         environments.insert("win11-x64".to_string(), VMEnvironment {
@@ -1406,6 +1667,8 @@ impl SandboxCore {
                 disk_gb: 100,
                 network_bandwidth_mbps: 1000,
             },
+            driver: DriverConfig::Simulated,
+            countermeasure_profile: None,
         });
 
         environments.insert("ubuntu20-x64".to_string(), VMEnvironment {
@@ -1429,6 +1692,8 @@ impl SandboxCore {
                 disk_gb: 40,
                 network_bandwidth_mbps: 1000,
             },
+            driver: DriverConfig::Simulated,
+            countermeasure_profile: None,
         });
 
         Ok(environments)
@@ -1517,13 +1782,31 @@ impl SandboxCore {
         Ok(engines)
     }
 
-    pub async fn submit_sample(&self, file_data: &[u8], filename: String, priority: AnalysisPriority, tags: Vec<String>) -> Result<String, String> {
-        let sample_id = Uuid::new_v4().to_string();
+    /// Queue a sample for analysis and return its sample id.
+    ///
+    /// Bytes that were already submitted resolve to the existing sample (and
+    /// its completed or in-flight analysis) unless `force_reanalyze` is set.
+    pub async fn submit_sample(&self, tenant_id: &str, file_data: &[u8], filename: String, priority: AnalysisPriority, tags: Vec<String>, force_reanalyze: bool) -> CoreResult<String> {
+        self.enqueue_sample(tenant_id, file_data, filename, priority, tags, force_reanalyze, None, None).await
+    }
+
+    /// Queue a file, or with `target_url` a URL whose text is `file_data`;
+    /// `interactive_session` holds the guest for an analyst once it ran
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn enqueue_sample(&self, tenant_id: &str, file_data: &[u8], filename: String, priority: AnalysisPriority, tags: Vec<String>, force_reanalyze: bool, target_url: Option<String>, interactive_session: Option<String>) -> CoreResult<String> {
+        let sha256_hash = format!("{:x}", sha2::Sha256::digest(file_data));
+        let known_hash = self.hash_index.read().await.contains_key(&dedup::hash_key(tenant_id, &sha256_hash));
+        if known_hash && !force_reanalyze {
+            if let Some(existing) = self.attach_duplicate(tenant_id, &sha256_hash, &filename, &priority, &tags).await {
+                return Ok(existing);
+            }
+        }
+
+        let sample_id = format!("smp_{}", Uuid::new_v4().simple());
         
         // Calculate file hashes
         let md5_hash = format!("{:x}", md5::compute(file_data));
         let sha1_hash = format!("{:x}", Sha1::digest(file_data));
-        let sha256_hash = format!("{:x}", sha2::Sha256::digest(file_data));
 
         // Create sample info
         let sample_info = SampleInfo {
@@ -1531,14 +1814,16 @@ impl SandboxCore {
             file_name: filename,
             file_hash_md5: md5_hash,
             file_hash_sha1: sha1_hash,
-            file_hash_sha256: sha256_hash,
+            file_hash_sha256: sha256_hash.clone(),
             file_size: file_data.len() as u64,
-            file_type: self.detect_file_type(file_data),
-            mime_type: self.detect_mime_type(file_data),
+            file_type: if target_url.is_some() { "URL".to_string() } else { self.detect_file_type(file_data) },
+            mime_type: if target_url.is_some() { "text/uri-list".to_string() } else { self.detect_mime_type(file_data) },
             submission_time: Utc::now(),
-            source: "API".to_string(),
+            source: if target_url.is_some() { "URL" } else { "API" }.to_string(),
             priority,
             tags,
+            ssdeep: fuzzy::ssdeep(file_data),
+            tlsh: fuzzy::tlsh(file_data),
         };
 
         // Select appropriate VM environment based on file type
@@ -1548,24 +1833,47 @@ impl SandboxCore {
         let job = AnalysisJob {
             job_id: Uuid::new_v4().to_string(),
             sample_id: sample_id.clone(),
+            tenant_id: tenant_id.to_string(),
             priority: sample_info.priority.clone(),
             submission_time: sample_info.submission_time,
             analysis_start: None,
             analysis_end: None,
             status: JobStatus::Queued,
             vm_environment,
-            analysis_config: self.create_analysis_config(&sample_info),
+            analysis_config: AnalysisConfiguration { target_url, interactive_session, ..self.create_analysis_config(&sample_info) },
             progress: 0.0,
             error_message: None,
         };
 
+        // Persist before queueing so the job survives a restart
+        self.persist_sample(&sample_id, file_data)?;
+        self.persist_job(&job)?;
+        if job.analysis_config.target_url.is_none() {
+            self.scan_on_submission(tenant_id, &sample_id, file_data).await;
+        }
+
+        // Retain the raw bytes for the static pipeline
+        {
+            let mut samples = self.sample_data.write().await;
+            samples.insert(sample_id.clone(), Arc::new(file_data.to_vec()));
+        }
+        self.record_fuzzy_digests(tenant_id, &sample_info);
+
         // Add to queue
         {
             let mut queue = self.analysis_queue.write().await;
             queue.push(job);
-            queue.sort_by(|a, b| self.compare_priority(&a.priority, &b.priority));
+            let queued = queue.iter().filter(|job| job.tenant_id == tenant_id && matches!(job.status, JobStatus::Queued)).count();
+            self.record_queue_length(tenant_id, queued);
         }
 
+        let disposition = if known_hash {
+            SubmissionDisposition::Reanalyzed
+        } else {
+            SubmissionDisposition::Queued
+        };
+        self.record_submission(tenant_id, &sha256_hash, &sample_id, &sample_info.file_name, &sample_info.tags, disposition).await;
+
         // Update metrics
         {
             let mut metrics = self.performance_metrics.write().await;
@@ -1576,7 +1884,7 @@ impl SandboxCore {
         Ok(sample_id)
     }
 
-    pub async fn submit_batch(&self, batch_request: BatchAnalysisRequest) -> Result<String, String> {
+    pub async fn submit_batch(&self, tenant_id: &str, batch_request: BatchAnalysisRequest) -> CoreResult<String> {
         let batch_id = batch_request.batch_id.clone();
         
         // Process each sample in the batch
@@ -1585,105 +1893,272 @@ impl SandboxCore {
             let sample_bytes = sample_data.as_bytes(); // Simplified for demo
             
             self.submit_sample(
+                tenant_id,
                 sample_bytes,
                 format!("batch_sample_{}", Uuid::new_v4()),
                 batch_request.priority.clone(),
                 batch_request.tags.clone(),
+                false,
             ).await?;
         }
 
         Ok(batch_id)
     }
 
-    pub async fn get_analysis(&self, sample_id: &str) -> Result<Option<SandboxAnalysis>, String> {
-        let analyses = self.completed_analyses.read().await;
-        Ok(analyses.get(sample_id).cloned())
+    pub async fn get_analysis(&self, tenant_id: &str, sample_id: &str) -> CoreResult<Option<SandboxAnalysis>> {
+        let analysis = self.completed_analyses.read().await.get(sample_id).cloned();
+        match analysis {
+            Some(analysis) if analysis.tenant_id == tenant_id => {
+                self.touch_retained(sample_id).await;
+                Ok(Some(analysis))
+            }
+            Some(_) => Ok(None),
+            None => Ok(self.load_spilled(sample_id).await.filter(|analysis| analysis.tenant_id == tenant_id)),
+        }
     }
 
-    pub async fn get_analysis_status(&self, sample_id: &str) -> Result<Option<AnalysisJob>, String> {
+    pub async fn get_analysis_status(&self, tenant_id: &str, sample_id: &str) -> CoreResult<Option<AnalysisJob>> {
         let queue = self.analysis_queue.read().await;
-        Ok(queue.iter().find(|job| job.sample_id == sample_id).cloned())
+        Ok(queue.iter().find(|job| job.sample_id == sample_id && job.tenant_id == tenant_id).cloned())
     }
 
-    pub async fn cancel_analysis(&self, sample_id: &str) -> Result<bool, String> {
+    pub async fn cancel_analysis(&self, tenant_id: &str, sample_id: &str) -> CoreResult<bool> {
         let mut queue = self.analysis_queue.write().await;
         
-        if let Some(pos) = queue.iter().position(|job| job.sample_id == sample_id) {
-            let mut job = queue[pos].clone();
+        if let Some(queued) = queue.iter_mut().find(|job| job.sample_id == sample_id && job.tenant_id == tenant_id) {
+            let mut job = queued.clone();
+            // A held guest is released; a job that never ran has no analysis to carry its session
+            if let Some(session_id) = &job.analysis_config.interactive_session {
+                self.interactive.close(session_id, interactive::SessionEnd::Terminated, "system");
+                if matches!(job.status, JobStatus::Queued) {
+                    self.interactive.finish(session_id);
+                }
+            }
             job.status = JobStatus::Cancelled;
-            queue[pos] = job;
+            self.persist_job(&job)?;
+            *queued = job;
             Ok(true)
         } else {
             Ok(false)
         }
     }
 
-    pub async fn process_queue(&self) -> Result<(), String> {
-        // This would be called by a background worker
-        let mut queue = self.analysis_queue.write().await;
-        
-        for job in queue.iter_mut() {
-            if matches!(job.status, JobStatus::Queued) {
-                job.status = JobStatus::PreProcessing;
-                job.analysis_start = Some(Utc::now());
-                
-                // Start analysis (would be async in real implementation)
-                let analysis_result = self.perform_analysis(job).await?;
-                
-                // Store completed analysis
-                {
-                    let mut analyses = self.completed_analyses.write().await;
-                    analyses.insert(job.sample_id.clone(), analysis_result);
+    pub async fn process_queue(&self) -> CoreResult<()> {
+        // A coordinator hands queued jobs to cluster workers instead
+        if let Some(coordinator) = self.cluster_coordinator().await {
+            return Ok(self.process_cluster_queue(&coordinator).await?);
+        }
+
+        // Start every queued job that fits the scheduler budget, then wait for them together
+        let placed = {
+            let mut queue = self.analysis_queue.write().await;
+            self.place_queued_jobs(&mut queue).await?
+        };
+        let outcomes = futures::future::join_all(placed.into_iter().map(|(job, guard)| self.run_scheduled(job, guard))).await;
+
+        let mut processed_tenants = std::collections::BTreeSet::new();
+        let mut first_error = None;
+        for outcome in outcomes {
+            match outcome {
+                Ok(tenant_id) => processed_tenants.extend(tenant_id),
+                Err(e) => {
+                    first_error.get_or_insert(e);
                 }
-                
-                job.status = JobStatus::Completed;
-                job.analysis_end = Some(Utc::now());
-                job.progress = 100.0;
-                
-                // Update metrics
-                {
-                    let mut metrics = self.performance_metrics.write().await;
-                    metrics.successful_analyses += 1;
-                    metrics.queue_length = queue.len() as u32;
+            }
+        }
+        {
+            let queue = self.analysis_queue.read().await;
+            for tenant_id in &processed_tenants {
+                let queued = queue.iter().filter(|job| &job.tenant_id == tenant_id && matches!(job.status, JobStatus::Queued)).count();
+                self.record_queue_length(tenant_id, queued);
+            }
+            self.performance_metrics.write().await.queue_length = queue.len() as u32;
+        }
+        if !processed_tenants.is_empty() {
+            self.enforce_retention().await;
+        }
+        self.submit_url_downloads().await;
+
+        match first_error {
+            Some(e) => Err(CoreError::from(e)),
+            None => Ok(()),
+        }
+    }
+
+    /// Run one placed job until it finishes or an Emergency job preempts it,
+    /// returning the job's tenant when an analysis was stored or failed
+    async fn run_scheduled(&self, job: AnalysisJob, mut guard: scheduler::SlotGuard) -> Result<Option<String>, String> {
+        let result = tokio::select! {
+            result = self.perform_analysis(&job) => result,
+            _ = guard.preempted() => {
+                log::info!("Job {} was preempted and requeued", job.job_id);
+                return Ok(None);
+            }
+        };
+        drop(guard);
+
+        let mut queue = self.analysis_queue.write().await;
+        // Cancelled, purged or preempted jobs are no longer ours to finish
+        let Some(queued) = queue
+            .iter_mut()
+            .find(|queued| queued.job_id == job.job_id && matches!(queued.status, JobStatus::PreProcessing | JobStatus::Running))
+        else {
+            return Ok(None);
+        };
+        match result {
+            Ok(analysis) => {
+                self.store_completed_analysis(queued, analysis).await?;
+                self.performance_metrics.write().await.successful_analyses += 1;
+            }
+            Err(error) => {
+                log::warn!("Analysis of job {} failed: {}", job.job_id, error.reason);
+                if let Some(session_id) = &job.analysis_config.interactive_session {
+                    self.interactive.finish(session_id);
                 }
-                
-                break; // Process one at a time for demo
+                queued.status = JobStatus::Failed;
+                queued.analysis_end = Some(Utc::now());
+                queued.error_message = Some(error.reason);
+                self.persist_job(queued)?;
+                self.performance_metrics.write().await.failed_analyses += 1;
             }
         }
+        Ok(Some(job.tenant_id))
+    }
 
+    /// Notify subscribers, persist and index a finished analysis, and mark its job complete
+    async fn store_completed_analysis(&self, job: &mut AnalysisJob, mut analysis_result: SandboxAnalysis) -> Result<(), String> {
+        self.sanitize_http_capture(&mut analysis_result.network_analysis).await;
+        self.stash_url_downloads(job, &mut analysis_result.network_analysis);
+
+        // Notify webhook subscribers before storing the completed analysis
+        self.notifications.notify(WebhookEvent::AnalysisCompleted, &analysis_result).await;
+        self.forward_verdict(&analysis_result);
+        self.publish_verdict(&analysis_result).await;
+        self.index_analysis_summary(&analysis_result);
+        self.record_completed_metrics(&analysis_result);
+        self.prometheus.observe_analysis(&analysis_result);
+
+        // Store completed analysis
+        self.persist_analysis(&job.sample_id, &analysis_result)?;
+        self.track_retained(&job.sample_id, &analysis_result).await;
+        {
+            let mut analyses = self.completed_analyses.write().await;
+            analyses.insert(job.sample_id.clone(), analysis_result);
+        }
+        
+        job.status = JobStatus::Completed;
+        job.analysis_end = Some(Utc::now());
+        job.progress = 100.0;
+        self.persist_job(job)?;
         Ok(())
     }
 
     async fn perform_analysis(&self, job: &AnalysisJob) -> Result<SandboxAnalysis, String> {
         let start_time = std::time::Instant::now();
-        let analysis_id = Uuid::new_v4().to_string();
+        let analysis_id = format!("anl_{}", Uuid::new_v4().simple());
         
         // Simulate comprehensive analysis
-        let sample_info = self.create_sample_info_from_job(job);
-        let verdict = self.determine_verdict(&sample_info);
+        let mut sample_info = self.create_sample_info_from_job(job);
+        self.attach_fuzzy_digests(&mut sample_info);
+        // An allowlisted hash is known good whatever the analysis observes
+        let allowlisted_by = self.suppression.allowlisted_sample(&job.tenant_id, &sample_info);
+        let verdict = match allowlisted_by {
+            Some(_) => SandboxVerdict::Clean,
+            None => self.determine_verdict(&sample_info),
+        };
         let confidence_score = self.calculate_confidence(&sample_info, &verdict);
         let threat_level = self.determine_threat_level(&verdict, confidence_score);
-        let malware_classification = self.classify_malware(&sample_info, &verdict);
+        let mut malware_classification = self.classify_malware(&sample_info, &verdict);
+        // Close variants of a clustered sample take its family over the placeholder
+        if malware_classification.family.as_deref() == Some(fuzzy::GENERIC_FAMILY) {
+            if let Some(family) = self.fuzzy.nearest_family(&job.tenant_id, &job.sample_id, fuzzy::DEFAULT_FAMILY_THRESHOLD) {
+                malware_classification.family = Some(family);
+            }
+        }
+        // URLs are loaded in a browser rather than executed on a guest
+        let url_detonation = match &job.analysis_config.target_url {
+            Some(url) => Some(self.run_url_detonation(url).await),
+            None => None,
+        };
+        let mut detonation = match url_detonation {
+            Some(_) => None,
+            None => self.run_detonation(job, &sample_info.file_name).await,
+        };
         
         // Perform various analysis components
-        let behavioral_analysis = self.perform_behavioral_analysis(&sample_info).await;
-        let network_analysis = self.perform_network_analysis(&sample_info).await;
-        let file_system_analysis = self.perform_file_system_analysis(&sample_info).await;
-        let registry_analysis = self.perform_registry_analysis(&sample_info).await;
+        let mut behavioral_analysis = self.perform_behavioral_analysis(&sample_info).await;
+        let mut network_analysis = match url_detonation {
+            Some(report) => report.network_analysis(&self.url_detonation_config().user_agent),
+            None => self.perform_network_analysis(&sample_info).await,
+        };
+        self.apply_dns_analytics(&mut network_analysis);
+        let mut file_system_analysis = self.perform_file_system_analysis(&sample_info).await;
+        let mut registry_analysis = self.perform_registry_analysis(&sample_info).await;
         let process_analysis = self.perform_process_analysis(&sample_info).await;
-        let memory_analysis = self.perform_memory_analysis(&sample_info).await;
-        let static_analysis = self.perform_static_analysis(&sample_info).await;
+        change_heuristics::apply(
+            &behavioral_analysis.system_changes,
+            detonation.as_ref(),
+            &process_analysis,
+            &mut file_system_analysis,
+            &mut registry_analysis,
+        );
+        let (memory_analysis, memory_errors) = self.perform_memory_analysis(detonation.as_mut()).await;
+
+        // Decoy interactions are high-signal evidence, surface them as behaviors
+        let observations = collect_observations(&behavioral_analysis, &network_analysis, &file_system_analysis, &process_analysis);
+        let decoy_interactions = self
+            .evaluate_decoy_interactions(job.analysis_config.network_persona.as_deref(), &observations)
+            .await;
+        if let Some(report) = &decoy_interactions {
+            behavioral_analysis.suspicious_behaviors.extend(report.to_behaviors());
+        }
+        let (mut static_analysis, static_timings) = self.perform_static_analysis(&sample_info).await;
+        static_analysis.antivirus = self.antivirus_scan(job).await;
         let evasion_techniques = self.detect_evasion_techniques(&sample_info).await;
-        let iocs_extracted = self.extract_iocs(&sample_info, &network_analysis, &behavioral_analysis).await;
+        let mut iocs_extracted = self.extract_iocs(&sample_info, &network_analysis, &behavioral_analysis).await;
+        if let Some(report) = &network_analysis.url_detonation {
+            iocs_extracted.extend(report.iocs());
+        }
+        let suppressed_iocs = self.suppression.suppress_iocs(&job.tenant_id, &mut iocs_extracted);
+        self.enrich_iocs(&mut iocs_extracted).await;
         let mitre_techniques = self.map_mitre_techniques(&behavioral_analysis, &evasion_techniques).await;
+        let mut engine_runs = self.run_engine_plugins(job, &sample_info).await;
+        if let Some(scan) = &static_analysis.antivirus {
+            engine_runs.insert(0, scan.engine_run());
+        }
         let threat_intelligence = self.gather_threat_intelligence(&sample_info, &iocs_extracted).await;
         let enterprise_insights = self.generate_enterprise_insights(&sample_info, &verdict, &threat_intelligence).await;
         
         let processing_time = start_time.elapsed().as_millis() as u64;
+        let mut errors = Vec::new();
+        let mut timeout_reached = false;
+        let mut warnings = Vec::new();
+        if let Some(report) = &detonation {
+            errors.extend(report.errors.iter().map(|e| format!("{} detonation: {}", report.backend, e)));
+            timeout_reached = report.execution.as_ref().is_some_and(|e| e.timed_out);
+            if let Some(record) = &report.countermeasures {
+                warnings.extend(record.failed().map(|c| format!("Countermeasure {:?} from profile {} failed: {}", c.kind, record.profile_id, c.detail)));
+            }
+        }
+        errors.extend(memory_errors);
+        if let Some(report) = &network_analysis.url_detonation {
+            errors.extend(report.errors.iter().map(|e| format!("{} browser: {}", report.driver, e)));
+        }
+        if let Some(entry_id) = &allowlisted_by {
+            warnings.push(format!("Sample hash is allowlisted by suppression {}; verdict set to Clean", entry_id));
+        }
+        if suppressed_iocs > 0 {
+            warnings.push(format!("{} IOCs suppressed by allowlist", suppressed_iocs));
+        }
+        // Keep exported files and screenshots downloadable after the guest is reverted
+        warnings.extend(
+            self.retain_artifacts(job, &analysis_id, detonation.as_mut(), network_analysis.url_detonation.as_ref())
+                .await,
+        );
         
         let performance_metrics = AnalysisPerformanceMetrics {
             total_analysis_time: processing_time,
-            vm_startup_time: 15000, // 15 seconds
+            vm_startup_time: detonation.as_ref().map_or(15000, |d| d.vm_startup_ms), // 15 seconds when simulated
             sample_execution_time: job.analysis_config.analysis_time * 1000,
             analysis_overhead: 5000, // 5 seconds
             memory_usage_peak: 2048 * 1024 * 1024, // 2GB
@@ -1691,16 +2166,17 @@ impl SandboxCore {
             storage_used: 1024 * 1024 * 100, // 100MB
             network_traffic: 1024 * 1024 * 50, // 50MB
             analysis_engines_time: HashMap::from([
-                ("phantom_static".to_string(), 2000),
+                ("phantom_static".to_string(), static_timings.total_us / 1000),
                 ("phantom_dynamic".to_string(), job.analysis_config.analysis_time * 1000),
                 ("phantom_network".to_string(), 3000),
-                ("phantom_yara".to_string(), 1000),
+                ("phantom_yara".to_string(), static_timings.stage_us.get("yara").copied().unwrap_or(0) / 1000),
                 ("phantom_ml".to_string(), 5000),
             ]),
         };
 
-        let analysis = SandboxAnalysis {
+        let mut analysis = SandboxAnalysis {
             analysis_id,
+            tenant_id: job.tenant_id.clone(),
             sample_info,
             analysis_metadata: AnalysisMetadata {
                 analysis_start: job.analysis_start.unwrap_or(Utc::now()),
@@ -1708,9 +2184,10 @@ impl SandboxCore {
                 analysis_duration: processing_time / 1000,
                 vm_environment: job.vm_environment.clone(),
                 analysis_engines_used: job.analysis_config.analysis_engines.clone(),
-                timeout_reached: false,
-                errors: vec![],
-                warnings: vec![],
+                timeout_reached,
+                errors,
+                warnings,
+                interactive_session: job.analysis_config.interactive_session.as_deref().and_then(|session_id| self.interactive.finish(session_id)),
             },
             verdict,
             confidence_score,
@@ -1729,7 +2206,12 @@ impl SandboxCore {
             threat_intelligence,
             enterprise_insights,
             performance_metrics,
+            decoy_interactions,
+            detonation,
+            engine_results: Vec::new(),
         };
+        self.merge_engine_findings(&mut analysis, engine_runs, allowlisted_by.is_some()).await;
+        self.apply_attribution(&mut analysis).await;
 
         Ok(analysis)
     }
@@ -1785,23 +2267,12 @@ impl SandboxCore {
             yara_scanning: true,
             memory_dumping: matches!(sample_info.priority, AnalysisPriority::High | AnalysisPriority::Critical | AnalysisPriority::Emergency),
             network_capture: true,
+            network_persona: None,
+            target_url: None,
+            interactive_session: None,
         }
     }
 
-    fn compare_priority(&self, a: &AnalysisPriority, b: &AnalysisPriority) -> std::cmp::Ordering {
-        let priority_value = |p: &AnalysisPriority| -> u32 {
-            match p {
-                AnalysisPriority::Emergency => 5,
-                AnalysisPriority::Critical => 4,
-                AnalysisPriority::High => 3,
-                AnalysisPriority::Normal => 2,
-                AnalysisPriority::Low => 1,
-            }
-        };
-        
-        priority_value(b).cmp(&priority_value(a)) // Reverse order for descending priority
-    }
-
     fn create_sample_info_from_job(&self, job: &AnalysisJob) -> SampleInfo {
         // In a real implementation, this would retrieve the actual sample info
         SampleInfo {
@@ -1817,6 +2288,8 @@ impl SandboxCore {
             source: "API".to_string(),
             priority: job.priority.clone(),
             tags: vec!["malware".to_string()],
+            ssdeep: None,
+            tlsh: None,
         }
     }
 
@@ -1857,7 +2330,7 @@ impl SandboxCore {
         let is_malicious = matches!(verdict, SandboxVerdict::Malicious | SandboxVerdict::Suspicious);
         
         MalwareClassification {
-            family: if is_malicious { Some("Generic".to_string()) } else { None },
+            family: if is_malicious { Some(fuzzy::GENERIC_FAMILY.to_string()) } else { None },
             variant: if is_malicious { Some("Unknown".to_string()) } else { None },
             category: if is_malicious { 
                 if sample_info.file_name.contains("trojan") { MalwareCategory::Trojan }
@@ -2057,6 +2530,7 @@ impl SandboxCore {
                         ("Content-Type".to_string(), "application/json".to_string()),
                     ]),
                     body: Some("{\"id\":\"victim123\",\"status\":\"active\"}".to_string()),
+                    body_truncated: false,
                     response_code: 200,
                     response_size: 512,
                     timestamp: Utc::now() - chrono::Duration::minutes(4),
@@ -2086,10 +2560,13 @@ impl SandboxCore {
                     communication_pattern: "HTTP POST requests every 60 seconds".to_string(),
                     encryption_used: true,
                     protocol: "HTTPS".to_string(),
+                    evidence: Vec::new(),
                 },
             ],
             data_exfiltration: vec![],
             botnet_communication: vec![],
+            capture_audit: None,
+            url_detonation: None,
         }
     }
 
@@ -2117,34 +2594,34 @@ impl SandboxCore {
     }
 
     async fn perform_process_analysis(&self, _sample_info: &SampleInfo) -> ProcessAnalysis {
+        let process_tree = ProcessTree::from_processes([ProcessInfo {
+            process_id: 1234,
+            process_name: "sample.exe".to_string(),
+            executable_path: "C:\\temp\\sample.exe".to_string(),
+            command_line: "C:\\temp\\sample.exe".to_string(),
+            parent_process_id: 1000,
+            creation_time: Utc::now() - chrono::Duration::minutes(10),
+            termination_time: None,
+            user_account: "SYSTEM".to_string(),
+            integrity_level: "High".to_string(),
+            is_suspicious: true,
+        }]);
+        let chains = process_tree.suspicious_chains(&process_tree::default_chain_patterns());
+
         ProcessAnalysis {
             processes_monitored: 25,
-            process_tree: ProcessTree {
-                root_process: ProcessInfo {
-                    process_id: 1234,
-                    process_name: "sample.exe".to_string(),
-                    executable_path: "C:\\temp\\sample.exe".to_string(),
-                    command_line: "C:\\temp\\sample.exe".to_string(),
-                    parent_process_id: 1000,
-                    creation_time: Utc::now() - chrono::Duration::minutes(10),
-                    termination_time: None,
-                    user_account: "SYSTEM".to_string(),
-                    integrity_level: "High".to_string(),
-                    is_suspicious: true,
-                },
-                child_processes: vec![],
-                relationships: vec![],
-            },
+            parent_child_anomalies: process_tree::chain_anomalies(&process_tree, &chains),
+            process_tree,
             suspicious_processes: vec![],
             code_injection: vec![],
             process_hollowing: vec![],
             privilege_escalation: vec![],
-            parent_child_anomalies: vec![],
         }
     }
 
-    async fn perform_memory_analysis(&self, _sample_info: &SampleInfo) -> MemoryAnalysis {
-        MemoryAnalysis {
+    /// Run the memory plugins over the dumps the detonation exported
+    async fn perform_memory_analysis(&self, detonation: Option<&mut DetonationReport>) -> (MemoryAnalysis, Vec<String>) {
+        let mut analysis = MemoryAnalysis {
             memory_dumps: vec![],
             injected_code: vec![],
             heap_analysis: HeapAnalysis {
@@ -2161,70 +2638,40 @@ impl SandboxCore {
             shellcode_detection: vec![],
             encryption_keys: vec![],
             obfuscated_strings: vec![],
+        };
+        let mut errors = Vec::new();
+        let Some(report) = detonation else {
+            return (analysis, errors);
+        };
+        let collected_at = report.finished_at;
+        for exported in &mut report.artifacts {
+            if artifacts::ArtifactCategory::from_path(&exported.path) != artifacts::ArtifactCategory::MemoryDump {
+                continue;
+            }
+            let Some(data) = exported.content.take() else { continue };
+            let (result, data) = self.run_memory_plugins(data, memory::DumpOptions::from_path(&exported.path)).await;
+            exported.content = Some(data);
+            match result {
+                Ok(dump) => {
+                    analysis.memory_dumps.push(dump.to_memory_dump(collected_at));
+                    analysis.injected_code.extend(dump.injected_code);
+                    analysis.shellcode_detection.extend(dump.shellcode_detection);
+                    analysis.obfuscated_strings.extend(dump.obfuscated_strings);
+                }
+                Err(e) => errors.push(format!("memory dump {}: {}", exported.path, e)),
+            }
         }
+        (analysis, errors)
     }
 
-    async fn perform_static_analysis(&self, _sample_info: &SampleInfo) -> StaticAnalysis {
-        StaticAnalysis {
-            file_metadata: FileMetadata {
-                file_type: "PE32".to_string(),
-                mime_type: "application/x-msdownload".to_string(),
-                file_size: 1024 * 50,
-                creation_time: Some(Utc::now() - chrono::Duration::days(30)),
-                modification_time: Some(Utc::now() - chrono::Duration::days(30)),
-                magic_bytes: "4D5A".to_string(),
-                file_structure: "PE32 executable".to_string(),
-            },
-            pe_analysis: Some(PEAnalysis {
-                architecture: "x86".to_string(),
-                subsystem: "Windows GUI".to_string(),
-                compilation_time: Utc::now() - chrono::Duration::days(60),
-                entry_point: "0x00401000".to_string(),
-                sections: vec![],
-                imports: vec![],
-                exports: vec![],
-                resources: vec![],
-                certificates: vec![],
-                anomalies: vec![],
-            }),
-            strings_analysis: StringsAnalysis {
-                total_strings: 250,
-                ascii_strings: 200,
-                unicode_strings: 50,
-                suspicious_strings: vec![],
-                urls: vec![],
-                ip_addresses: vec![],
-                file_paths: vec![],
-                registry_keys: vec![],
-                api_functions: vec![],
-            },
-            entropy_analysis: EntropyAnalysis {
-                overall_entropy: 7.2,
-                section_entropies: HashMap::new(),
-                high_entropy_regions: vec![],
-                is_packed: false,
-                packing_probability: 0.3,
-            },
-            packer_detection: PackerDetection {
-                is_packed: false,
-                packer_name: None,
-                packer_version: None,
-                confidence: 0.3,
-                detection_method: "Entropy Analysis".to_string(),
-                unpacking_attempted: false,
-                unpacking_success: false,
-            },
-            yara_matches: vec![],
-            signature_verification: SignatureVerification {
-                is_signed: false,
-                signature_valid: false,
-                signer: None,
-                signature_time: None,
-                certificate_chain: vec![],
-                trust_status: "Unsigned".to_string(),
-            },
-            anti_analysis_features: vec![],
-        }
+    async fn perform_static_analysis(&self, sample_info: &SampleInfo) -> (StaticAnalysis, StaticPipelineTimings) {
+        let data = {
+            let samples = self.sample_data.read().await;
+            samples.get(&sample_info.sample_id).cloned().unwrap_or_default()
+        };
+        let file_type = self.detect_file_type(&data);
+        let mime_type = self.detect_mime_type(&data);
+        self.static_pipeline.run(data, &file_type, &mime_type).await
     }
 
     async fn detect_evasion_techniques(&self, _sample_info: &SampleInfo) -> Vec<EvasionTechnique> {
@@ -2253,7 +2700,8 @@ impl SandboxCore {
                 confidence: 0.9,
                 context: "Malware C2 communication".to_string(),
                 first_seen: connection.first_seen,
-                threat_intelligence: Some("Known malware C2 server".to_string()),
+                threat_intelligence: None,
+                enrichments: Vec::new(),
             });
         }
         
@@ -2265,7 +2713,8 @@ impl SandboxCore {
                 confidence: 0.95,
                 context: "Command and control domain".to_string(),
                 first_seen: Utc::now(),
-                threat_intelligence: Some("Malware family infrastructure".to_string()),
+                threat_intelligence: None,
+                enrichments: Vec::new(),
             });
         }
 
@@ -2293,19 +2742,18 @@ impl SandboxCore {
     }
 
     fn get_mitre_technique_name(&self, technique_id: &str) -> String {
-        match technique_id {
-            "T1055" => "Process Injection".to_string(),
-            "T1547.001" => "Registry Run Keys / Startup Folder".to_string(),
-            _ => "Unknown Technique".to_string(),
-        }
+        mitre::enterprise()
+            .technique(technique_id)
+            .map(|t| t.name.clone())
+            .unwrap_or_else(|| "Unknown Technique".to_string())
     }
 
     fn get_mitre_tactic(&self, technique_id: &str) -> String {
-        match technique_id {
-            "T1055" => "Defense Evasion".to_string(),
-            "T1547.001" => "Persistence".to_string(),
-            _ => "Unknown Tactic".to_string(),
-        }
+        mitre::enterprise()
+            .tactics_for(technique_id)
+            .first()
+            .map(|t| t.name.clone())
+            .unwrap_or_else(|| "Unknown Tactic".to_string())
     }
 
     async fn gather_threat_intelligence(&self, _sample_info: &SampleInfo, _iocs: &[ExtractedIOC]) -> ThreatIntelligence {
@@ -2318,6 +2766,7 @@ impl SandboxCore {
             attribution_confidence: 0.6,
             geographical_targeting: vec!["Global".to_string()],
             industry_targeting: vec!["Financial Services".to_string(), "Healthcare".to_string()],
+            attribution: Vec::new(),
         }
     }
 
@@ -2419,14 +2868,35 @@ impl SandboxCore {
         }
     }
 
-    pub async fn get_performance_metrics(&self) -> Result<SandboxPerformanceMetrics, String> {
-        let metrics = self.performance_metrics.read().await;
-        Ok(metrics.clone())
+    /// Job counts are the tenant's own; VM, throughput and retention figures are platform-wide
+    pub async fn get_performance_metrics(&self, tenant_id: &str) -> CoreResult<SandboxPerformanceMetrics> {
+        let mut metrics = self.performance_metrics.read().await.clone();
+        metrics.scheduler = self.scheduler_utilization().await;
+        metrics.vm_utilization = metrics.scheduler.slot_share();
+        let queue = self.analysis_queue.read().await;
+        let jobs: Vec<&AnalysisJob> = queue.iter().filter(|job| job.tenant_id == tenant_id).collect();
+        metrics.total_analyses = jobs.len() as u64;
+        metrics.successful_analyses = jobs.iter().filter(|job| matches!(job.status, JobStatus::Completed)).count() as u64;
+        metrics.failed_analyses = jobs.iter().filter(|job| matches!(job.status, JobStatus::Failed)).count() as u64;
+        metrics.queue_length = jobs.iter().filter(|job| matches!(job.status, JobStatus::Queued)).count() as u32;
+        Ok(metrics)
     }
 
-    pub async fn get_queue_status(&self) -> Result<Vec<AnalysisJob>, String> {
+    pub async fn get_queue_status(&self, tenant_id: &str) -> CoreResult<Vec<AnalysisJob>> {
         let queue = self.analysis_queue.read().await;
-        Ok(queue.clone())
+        Ok(queue.iter().filter(|job| job.tenant_id == tenant_id).cloned().collect())
+    }
+
+    /// One page of the tenant's jobs, newest submission first by default
+    pub async fn queue_page(&self, tenant_id: &str, request: &pagination::PageRequest) -> CoreResult<pagination::Page<AnalysisJob>> {
+        let jobs = self.get_queue_status(tenant_id).await?;
+        Ok(pagination::paginate(jobs, request, pagination::ListSpec { id_field: "job_id", sort_by: "submission_time", direction: pagination::SortDirection::Desc })?)
+    }
+
+    /// One page of the tenant's completed analyses, most recently started first by default
+    pub async fn analyses_page(&self, tenant_id: &str, request: &pagination::PageRequest) -> CoreResult<pagination::Page<SandboxAnalysis>> {
+        let analyses = self.recent_analyses(tenant_id, None).await;
+        Ok(pagination::paginate(analyses, request, pagination::ListSpec { id_field: "analysis_id", sort_by: "analysis_metadata.analysis_start", direction: pagination::SortDirection::Desc })?)
     }
 }
 
@@ -2442,65 +2912,164 @@ pub struct NetworkBehavior {
     pub bandwidth_used: u64,
 }
 
+/// Priority from its NAPI spelling; unknown values are `Normal`
+pub(crate) fn parse_priority(priority: Option<&str>) -> AnalysisPriority {
+    match priority {
+        Some("low") => AnalysisPriority::Low,
+        Some("high") => AnalysisPriority::High,
+        Some("critical") => AnalysisPriority::Critical,
+        Some("emergency") => AnalysisPriority::Emergency,
+        _ => AnalysisPriority::Normal,
+    }
+}
+
 // Enterprise NAPI Bindings for Phantom Sandbox Core
 #[napi]
 pub struct SandboxCoreNapi {
     inner: Arc<SandboxCore>,
+    access: access::AccessGuard,
+    audit: audit::AuditTrail,
+    tenants: tenancy::TenantGuard,
 }
 
 #[napi]
 impl SandboxCoreNapi {
+    /// Create the core; with `queue_path` the analysis queue is persisted there and restored on startup,
+    /// and with `keyring_path` samples are stored encrypted under the master keys kept there, sealed under
+    /// the key-encryption key in `PHANTOM_SANDBOX_SAMPLE_KEK`
     #[napi(constructor)]
-    pub fn new() -> Result<Self> {
-        let core = SandboxCore::new()
-            .map_err(|e| napi::Error::from_reason(format!("Failed to create Sandbox Core: {}", e)))?;
-        Ok(SandboxCoreNapi { inner: Arc::new(core) })
+    pub fn new(queue_path: Option<String>, keyring_path: Option<String>) -> Result<Self> {
+        let core = match (queue_path, keyring_path) {
+            (Some(path), Some(keyring)) => job_store::open_job_store(&path)
+                .and_then(|store| SandboxCore::with_encrypted_job_store(store, &keyring, &secrets::EnvKey::new(sample_store::KEK_ENV)))
+                .map_err(CoreError::from),
+            (Some(path), None) => job_store::open_job_store(&path).and_then(SandboxCore::with_job_store).map_err(CoreError::from),
+            (None, Some(_)) => Err(CoreError::validation("keyring_path requires queue_path")),
+            (None, None) => SandboxCore::new(),
+        }
+        .map_err(|e| e.context("Failed to create Sandbox Core"))?;
+        Ok(SandboxCoreNapi {
+            inner: Arc::new(core),
+            access: access::access_guard(),
+            audit: audit::audit_trail(),
+            tenants: tenancy::TenantGuard::default(),
+        })
     }
 
     /// Submit a malware sample for comprehensive dynamic analysis
     #[napi]
-    pub async fn submit_sample(&self, file_data: Buffer, filename: String, priority: Option<String>, tags: Option<Vec<String>>) -> Result<String> {
-        let analysis_priority = match priority.as_deref() {
-            Some("low") => AnalysisPriority::Low,
-            Some("high") => AnalysisPriority::High,
-            Some("critical") => AnalysisPriority::Critical,
-            Some("emergency") => AnalysisPriority::Emergency,
-            _ => AnalysisPriority::Normal,
-        };
+    pub async fn submit_sample(&self, file_data: Buffer, filename: String, priority: Option<String>, tags: Option<Vec<String>>, force_reanalyze: Option<bool>, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", &filename)?;
+        let analysis_priority = parse_priority(priority.as_deref());
 
         let sample_tags = tags.unwrap_or_default();
+        let params = serde_json::json!({
+            "filename": filename,
+            "size": file_data.len(),
+            "priority": priority,
+            "tags": sample_tags,
+            "force_reanalyze": force_reanalyze,
+        });
 
-        self.inner.submit_sample(&file_data, filename, analysis_priority, sample_tags).await
-            .map_err(|e| napi::Error::from_reason(format!("Failed to submit sample: {}", e)))
+        let result = self.inner.submit_sample(&tenant_id, &file_data, filename.clone(), analysis_priority, sample_tags, force_reanalyze.unwrap_or(false)).await;
+        let resource = result.as_ref().map_or(filename, |sample_id| sample_id.clone());
+        Ok(self.audit.record(&actor, "submit_sample", &resource, params, result)
+            .map_err(|e| e.context("Failed to submit sample"))?)
     }
 
     /// Submit multiple samples for batch analysis
     #[napi]
-    pub async fn submit_batch(&self, batch_config: String) -> Result<String> {
+    pub async fn submit_batch(&self, batch_config: String, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", "batch")?;
         let batch_request: BatchAnalysisRequest = serde_json::from_str(&batch_config)
             .map_err(|e| napi::Error::from_reason(format!("Failed to parse batch config: {}", e)))?;
 
-        let batch_id = self.inner.submit_batch(batch_request).await
-            .map_err(|e| napi::Error::from_reason(format!("Failed to submit batch: {}", e)))?;
+        let batch_id = self.inner.submit_batch(&tenant_id, batch_request).await;
+        let resource = batch_id.as_ref().map_or("batch".to_string(), |id| id.clone());
+        let batch_id = self.audit.record(&actor, "submit_batch", &resource, serde_json::json!({ "batch": batch_config }), batch_id)
+            .map_err(|e| e.context("Failed to submit batch"))?;
 
         Ok(serde_json::json!({"batch_id": batch_id}).to_string())
     }
 
-    /// Get comprehensive analysis results for a sample
+    /// Export a sample as a zip protected by `password` (default `infected`) for sharing
+    #[napi]
+    pub async fn export_sample(&self, sample_id: String, password: Option<String>, auth_token: Option<String>) -> Result<Buffer> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "sample:export", &sample_id)?;
+        let exported = self.inner.export_sample(&tenant_id, &sample_id, password.as_deref()).await;
+        let params = serde_json::json!({ "default_password": password.is_none() });
+        self.audit.record(&actor, "export_sample", &sample_id, params, exported)
+            .map(Buffer::from)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to export sample: {}", e)))
+    }
+
+    /// Rotate the master key that wraps sample data keys
+    #[napi]
+    pub async fn rotate_sample_master_key(&self, auth_token: Option<String>) -> Result<String> {
+        let actor = self.authorize(auth_token, "access:manage", "sample-keyring")?;
+        let report = self.inner.rotate_sample_master_key();
+        let report = self.audit.record(&actor, "rotate_sample_master_key", "sample-keyring", serde_json::json!({}), report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to rotate sample master key: {}", e)))?;
+
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize rotation report: {}", e)))
+    }
+
+    /// Get whether samples are encrypted at rest and which master keys are held
+    #[napi]
+    pub fn get_sample_encryption_status(&self) -> Result<String> {
+        let status = self.inner.get_sample_encryption_status()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to read sample encryption status: {}", e)))?;
+
+        serde_json::to_string(&status)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize encryption status: {}", e)))
+    }
+
+    /// Look up prior submissions and analyses of a SHA-256
+    #[napi]
+    pub async fn find_submissions_by_hash(&self, sha256: String, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let submissions = self.inner.find_submissions_by_hash(&tenant_id, &sha256).await;
+
+        serde_json::to_string(&submissions)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize submissions: {}", e)))
+    }
+
+    /// Get comprehensive analysis results for a sample; `fields` lists the
+    /// dotted paths and presets (summary, network, behavior, static, full)
+    /// to return, everything when absent
     #[napi]
-    pub async fn get_analysis(&self, sample_id: String) -> Result<String> {
-        let analysis = self.inner.get_analysis(&sample_id).await
-            .map_err(|e| napi::Error::from_reason(format!("Failed to get analysis: {}", e)))?;
+    pub async fn get_analysis(&self, sample_id: String, fields: Option<String>, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let selection = field_selection::FieldSelection::parse(fields.as_deref())
+            .map_err(|e| e.context("Failed to get analysis"))?;
+        let analysis = self.inner.get_analysis_fields(&tenant_id, &sample_id, &selection).await
+            .map_err(|e| e.context("Failed to get analysis"))?;
 
         serde_json::to_string(&analysis)
             .map_err(|e| napi::Error::from_reason(format!("Failed to serialize analysis: {}", e)))
     }
 
+    /// Behavioural events of a completed analysis, for incident timeline reconstruction
+    #[napi]
+    pub async fn get_timeline_events(&self, sample_id: String, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let events = self.inner.get_timeline_events(&tenant_id, &sample_id).await
+            .map_err(|e| e.context("Failed to get timeline events"))?;
+
+        serde_json::to_string(&events)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize timeline events: {}", e)))
+    }
+
     /// Get current analysis status and queue position
     #[napi]
-    pub async fn get_analysis_status(&self, sample_id: String) -> Result<String> {
-        let status = self.inner.get_analysis_status(&sample_id).await
-            .map_err(|e| napi::Error::from_reason(format!("Failed to get analysis status: {}", e)))?;
+    pub async fn get_analysis_status(&self, sample_id: String, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let status = self.inner.get_analysis_status(&tenant_id, &sample_id).await
+            .map_err(|e| e.context("Failed to get analysis status"))?;
 
         serde_json::to_string(&status)
             .map_err(|e| napi::Error::from_reason(format!("Failed to serialize status: {}", e)))
@@ -2508,41 +3077,175 @@ impl SandboxCoreNapi {
 
     /// Cancel a pending analysis
     #[napi]
-    pub async fn cancel_analysis(&self, sample_id: String) -> Result<bool> {
-        self.inner.cancel_analysis(&sample_id).await
-            .map_err(|e| napi::Error::from_reason(format!("Failed to cancel analysis: {}", e)))
+    pub async fn cancel_analysis(&self, sample_id: String, auth_token: Option<String>) -> Result<bool> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:cancel", &sample_id)?;
+        let result = self.inner.cancel_analysis(&tenant_id, &sample_id).await;
+        Ok(self.audit.record(&actor, "cancel_analysis", &sample_id, serde_json::json!({}), result)
+            .map_err(|e| e.context("Failed to cancel analysis"))?)
     }
 
     /// Process the analysis queue (typically called by background workers)
     #[napi]
     pub async fn process_queue(&self) -> Result<()> {
-        self.inner.process_queue().await
-            .map_err(|e| napi::Error::from_reason(format!("Failed to process queue: {}", e)))
+        Ok(self.inner.process_queue().await
+            .map_err(|e| e.context("Failed to process queue"))?)
+    }
+
+    /// Coordinate a cluster of worker cores; with `nats_url` remote workers can join over NATS
+    #[napi]
+    pub async fn start_cluster_coordinator(&self, config_json: Option<String>, nats_url: Option<String>, auth_token: Option<String>) -> Result<String> {
+        let actor = self.authorize_platform(auth_token, "cluster")?;
+        let params = serde_json::json!({ "config": config_json, "nats_url": nats_url });
+        let config = match config_json {
+            Some(cfg) => serde_json::from_str(&cfg)
+                .map_err(|e| napi::Error::from_reason(format!("Failed to parse cluster config: {}", e)))?,
+            None => cluster::ClusterConfig::default(),
+        };
+
+        let status = self.inner.start_cluster_coordinator(config, nats_url).await;
+        let status = self.audit.record(&actor, "start_cluster_coordinator", "cluster", params, status)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to start cluster coordinator: {}", e)))?;
+
+        serde_json::to_string(&status)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize cluster status: {}", e)))
+    }
+
+    /// Stop coordinating; jobs still out with workers return to the local queue
+    #[napi]
+    pub async fn stop_cluster_coordinator(&self, auth_token: Option<String>) -> Result<bool> {
+        let actor = self.authorize_platform(auth_token, "cluster")?;
+        let stopped = self.inner.stop_cluster_coordinator().await;
+        self.audit.record(&actor, "stop_cluster_coordinator", "cluster", serde_json::json!({}), stopped)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to stop cluster coordinator: {}", e)))
+    }
+
+    /// Join a cluster as a worker; returns the worker id
+    #[napi]
+    pub async fn join_cluster(&self, cluster_name: String, nats_url: Option<String>, config_json: Option<String>, auth_token: Option<String>) -> Result<String> {
+        let actor = self.authorize_platform(auth_token, "cluster")?;
+        let params = serde_json::json!({ "nats_url": nats_url, "config": config_json });
+        let config = match config_json {
+            Some(cfg) => serde_json::from_str(&cfg)
+                .map_err(|e| napi::Error::from_reason(format!("Failed to parse worker config: {}", e)))?,
+            None => cluster::WorkerConfig::default(),
+        };
+
+        let worker_id = self.inner.join_cluster(&cluster_name, nats_url.as_deref(), config).await;
+        self.audit.record(&actor, "join_cluster", &cluster_name, params, worker_id)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to join cluster: {}", e)))
+    }
+
+    /// Stop working for the cluster; returns false if this core was not a worker
+    #[napi]
+    pub async fn leave_cluster(&self, auth_token: Option<String>) -> Result<bool> {
+        let actor = self.authorize_platform(auth_token, "cluster")?;
+        let left = self.inner.stop_cluster_worker().await;
+        self.audit.record(&actor, "leave_cluster", "cluster", serde_json::json!({}), Ok::<_, String>(left))
+            .map_err(napi::Error::from_reason)
+    }
+
+    /// Get cluster topology: workers, their jobs and heartbeats, and queue counts
+    #[napi]
+    pub async fn get_cluster_status(&self) -> Result<String> {
+        serde_json::to_string(&self.inner.get_cluster_status().await)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize cluster status: {}", e)))
     }
 
     /// Get detailed performance metrics
     #[napi]
-    pub async fn get_performance_metrics(&self) -> Result<String> {
-        let metrics = self.inner.get_performance_metrics().await
-            .map_err(|e| napi::Error::from_reason(format!("Failed to get performance metrics: {}", e)))?;
+    pub async fn get_performance_metrics(&self, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let metrics = self.inner.get_performance_metrics(&tenant_id).await
+            .map_err(|e| e.context("Failed to get performance metrics"))?;
 
         serde_json::to_string(&metrics)
             .map_err(|e| napi::Error::from_reason(format!("Failed to serialize metrics: {}", e)))
     }
 
+    /// Configure retention limits for completed analyses and apply them immediately
+    #[napi]
+    pub async fn set_retention_policy(&self, policy_json: String, auth_token: Option<String>) -> Result<String> {
+        let actor = self.authorize_platform(auth_token, "retention")?;
+        let policy: RetentionPolicy = serde_json::from_str(&policy_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse retention policy: {}", e)))?;
+
+        let metrics = self.inner.set_retention_policy(policy).await;
+        let metrics = self.audit.record(&actor, "set_retention_policy", "retention", serde_json::json!({ "policy": policy_json }), metrics)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to set retention policy: {}", e)))?;
+
+        serde_json::to_string(&metrics)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize retention metrics: {}", e)))
+    }
+
+    /// Get the active retention policy
+    #[napi]
+    pub async fn get_retention_policy(&self) -> Result<String> {
+        let policy = self.inner.retention_policy().await;
+
+        serde_json::to_string(&policy)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize retention policy: {}", e)))
+    }
+
+    /// Configure HTTP body/header capture limits and redaction rules for analyses completed from now on
+    #[napi]
+    pub async fn set_http_capture_policy(&self, policy_json: String, auth_token: Option<String>) -> Result<()> {
+        let actor = self.authorize_platform(auth_token, "http_capture")?;
+        let policy: redaction::HttpCapturePolicy = serde_json::from_str(&policy_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse HTTP capture policy: {}", e)))?;
+
+        let result = self.inner.set_http_capture_policy(policy).await;
+        self.audit.record(&actor, "set_http_capture_policy", "http_capture", serde_json::json!({ "policy": policy_json }), result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to set HTTP capture policy: {}", e)))
+    }
+
+    /// Get the active HTTP capture policy
+    #[napi]
+    pub async fn get_http_capture_policy(&self) -> Result<String> {
+        let policy = self.inner.http_capture_policy().await;
+
+        serde_json::to_string(&policy)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize HTTP capture policy: {}", e)))
+    }
+
     /// Get current analysis queue status
     #[napi]
-    pub async fn get_queue_status(&self) -> Result<String> {
-        let queue = self.inner.get_queue_status().await
-            .map_err(|e| napi::Error::from_reason(format!("Failed to get queue status: {}", e)))?;
+    pub async fn get_queue_status(&self, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let queue = self.inner.get_queue_status(&tenant_id).await
+            .map_err(|e| e.context("Failed to get queue status"))?;
 
         serde_json::to_string(&queue)
             .map_err(|e| napi::Error::from_reason(format!("Failed to serialize queue: {}", e)))
     }
 
+    /// Page through the analysis queue; `page_json` takes cursor, page_size,
+    /// sort_by, direction, filters and fields
+    #[napi]
+    pub async fn get_queue_page(&self, page_json: Option<String>, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let request = pagination::PageRequest::from_json(page_json.as_deref()).map_err(CoreError::from)?;
+        let page = self.inner.queue_page(&tenant_id, &request).await?.project(&request.fields).map_err(CoreError::from)?;
+
+        serde_json::to_string(&page)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize queue page: {}", e)))
+    }
+
+    /// Page through completed analyses; use `fields` to keep pages small
+    #[napi]
+    pub async fn list_analyses_page(&self, page_json: Option<String>, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let request = pagination::PageRequest::from_json(page_json.as_deref()).map_err(CoreError::from)?;
+        let page = self.inner.analyses_page(&tenant_id, &request).await?.project(&request.fields).map_err(CoreError::from)?;
+
+        serde_json::to_string(&page)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize analyses page: {}", e)))
+    }
+
     /// Generate comprehensive threat analysis report
     #[napi]
-    pub async fn generate_threat_report(&self, sample_ids: Vec<String>, report_config: String) -> Result<String> {
+    pub async fn generate_threat_report(&self, sample_ids: Vec<String>, report_config: String, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
         let config: serde_json::Value = serde_json::from_str(&report_config)
             .map_err(|e| napi::Error::from_reason(format!("Failed to parse report config: {}", e)))?;
 
@@ -2550,7 +3253,7 @@ impl SandboxCoreNapi {
         let mut failed_samples = Vec::new();
 
         for sample_id in &sample_ids {
-            match self.inner.get_analysis(sample_id).await {
+            match self.inner.get_analysis(&tenant_id, sample_id).await {
                 Ok(Some(analysis)) => analyses.push(analysis),
                 Ok(None) => failed_samples.push(format!("{}: Not found", sample_id)),
                 Err(e) => failed_samples.push(format!("{}: {}", sample_id, e)),
@@ -2565,14 +3268,19 @@ impl SandboxCoreNapi {
 
     /// Advanced malware hunting based on behavioral patterns
     #[napi]
-    pub async fn hunt_malware(&self, hunting_config: String) -> Result<String> {
+    pub async fn hunt_malware(&self, hunting_config: String, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
         let config: serde_json::Value = serde_json::from_str(&hunting_config)
             .map_err(|e| napi::Error::from_reason(format!("Failed to parse hunting config: {}", e)))?;
 
         let hunt_id = Uuid::new_v4().to_string();
         
-        // Get all completed analyses for hunting
-        let completed_analyses = self.inner.completed_analyses.read().await;
+        // Get the tenant's completed analyses for hunting
+        let completed_analyses: Vec<SandboxAnalysis> = self.inner.completed_analyses.read().await
+            .values()
+            .filter(|analysis| analysis.tenant_id == tenant_id)
+            .cloned()
+            .collect();
         let mut hunt_results = Vec::new();
 
         // Apply hunting criteria
@@ -2581,7 +3289,7 @@ impl SandboxCoreNapi {
             arr.iter().filter_map(|v| v.as_str().map(|s| s.to_string())).collect::<Vec<_>>()
         }).unwrap_or_default();
 
-        for (_, analysis) in completed_analyses.iter() {
+        for analysis in completed_analyses.iter() {
             let matches_criteria = match min_threat_level {
                 "low" => matches!(analysis.threat_level, ThreatLevel::Low | ThreatLevel::Medium | ThreatLevel::High | ThreatLevel::Critical),
                 "medium" => matches!(analysis.threat_level, ThreatLevel::Medium | ThreatLevel::High | ThreatLevel::Critical),
@@ -2644,12 +3352,13 @@ impl SandboxCoreNapi {
 
     /// Get enterprise health status with detailed metrics
     #[napi]
-    pub async fn get_health_status(&self) -> Result<String> {
-        let performance_metrics = self.inner.get_performance_metrics().await
-            .map_err(|e| napi::Error::from_reason(format!("Failed to get performance metrics: {}", e)))?;
+    pub async fn get_health_status(&self, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let performance_metrics = self.inner.get_performance_metrics(&tenant_id).await
+            .map_err(|e| e.context("Failed to get performance metrics"))?;
         
-        let queue_status = self.inner.get_queue_status().await
-            .map_err(|e| napi::Error::from_reason(format!("Failed to get queue status: {}", e)))?;
+        let queue_status = self.inner.get_queue_status(&tenant_id).await
+            .map_err(|e| e.context("Failed to get queue status"))?;
 
         let vm_environments = self.inner.vm_environments.read().await;
         let analysis_engines = self.inner.analysis_engines.read().await;
@@ -2726,9 +3435,346 @@ impl SandboxCoreNapi {
             .map_err(|e| napi::Error::from_reason(format!("Failed to serialize health status: {}", e)))
     }
 
+    /// Register a signed webhook endpoint for analysis result notifications
+    #[napi]
+    pub async fn register_webhook(&self, endpoint_config: String, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", "webhooks")?;
+        let endpoint: WebhookEndpoint = serde_json::from_str(&endpoint_config)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse webhook endpoint: {}", e)))?;
+
+        let result = self.inner.register_webhook(&tenant_id, endpoint).await;
+        let resource = result.as_ref().map_or("webhooks".to_string(), |id| id.clone());
+        self.audit.record(&actor, "register_webhook", &resource, serde_json::json!({ "endpoint": endpoint_config }), result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to register webhook: {}", e)))
+    }
+
+    /// Remove a registered webhook endpoint
+    #[napi]
+    pub async fn remove_webhook(&self, endpoint_id: String, auth_token: Option<String>) -> Result<bool> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &endpoint_id)?;
+        let removed = self.inner.remove_webhook(&tenant_id, &endpoint_id).await;
+        self.audit.record(&actor, "remove_webhook", &endpoint_id, serde_json::json!({}), Ok::<_, String>(removed))
+            .map_err(napi::Error::from_reason)
+    }
+
+    /// List registered webhook endpoints with secrets redacted
+    #[napi]
+    pub async fn list_webhooks(&self, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let endpoints = self.inner.list_webhooks(&tenant_id).await;
+        serde_json::to_string(&endpoints)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize webhooks: {}", e)))
+    }
+
+    /// Re-send notifications created within an RFC 3339 time range
+    #[napi]
+    pub async fn replay_notifications(&self, from: String, to: String, endpoint_id: Option<String>, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", endpoint_id.as_deref().unwrap_or("webhooks"))?;
+        let params = serde_json::json!({ "from": from, "to": to, "endpoint_id": endpoint_id });
+        let from = DateTime::parse_from_rfc3339(&from)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid replay start: {}", e)))?
+            .with_timezone(&Utc);
+        let to = DateTime::parse_from_rfc3339(&to)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid replay end: {}", e)))?
+            .with_timezone(&Utc);
+
+        let replayed = self.inner.replay_notifications(&tenant_id, from, to, endpoint_id.as_deref()).await;
+        let resource = endpoint_id.as_deref().unwrap_or("webhooks");
+        let replayed = self.audit.record(&actor, "replay_notifications", resource, params, replayed)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to replay notifications: {}", e)))?;
+
+        serde_json::to_string(&replayed)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize notifications: {}", e)))
+    }
+
+    /// Connect to a MISP instance for IOC push/pull
+    #[napi]
+    pub async fn configure_misp(&self, config_json: String, auth_token: Option<String>) -> Result<()> {
+        let actor = self.authorize_platform(auth_token, "misp")?;
+        let config: MispConfig = serde_json::from_str(&config_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse MISP config: {}", e)))?;
+
+        let params = serde_json::json!({ "config": config.redacted() });
+        let result = self.inner.configure_misp(config).await;
+        self.audit.record(&actor, "configure_misp", "misp", params, result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to configure MISP: {}", e)))
+    }
+
+    /// Pull MISP attributes changed since an optional RFC 3339 time into the local IOC store
+    #[napi]
+    pub async fn pull_misp_iocs(&self, since: Option<String>, auth_token: Option<String>) -> Result<String> {
+        let actor = self.authorize_platform(auth_token, "misp")?;
+        let params = serde_json::json!({ "since": since });
+        let since = since
+            .map(|since| DateTime::parse_from_rfc3339(&since).map(|t| t.with_timezone(&Utc)))
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Invalid pull start: {}", e)))?;
+
+        let report = self.inner.pull_misp_iocs(since).await;
+        let report = self.audit.record(&actor, "pull_misp_iocs", "misp", params, report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to pull MISP IOCs: {}", e)))?;
+
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize pull report: {}", e)))
+    }
+
+    /// Push the IOCs of a completed analysis to MISP as an event
+    #[napi]
+    pub async fn push_analysis_to_misp(&self, sample_id: String, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "sample:export", &sample_id)?;
+        let report = self.inner.push_analysis_to_misp(&tenant_id, &sample_id).await;
+        let report = self.audit.record(&actor, "push_analysis_to_misp", &sample_id, serde_json::json!({}), report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to push analysis to MISP: {}", e)))?;
+
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize push report: {}", e)))
+    }
+
+    /// Report a sighting of an indicator value to MISP
+    #[napi]
+    pub async fn submit_misp_sighting(&self, value: String, source: Option<String>, auth_token: Option<String>) -> Result<()> {
+        let actor = self.authorize(auth_token, "rule:manage", "misp")?;
+        let source = source.unwrap_or_else(|| "phantom-sandbox".to_string());
+        let result = self.inner.submit_misp_sighting(&value, &source).await;
+        self.audit.record(&actor, "submit_misp_sighting", &value, serde_json::json!({ "source": source }), result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to submit MISP sighting: {}", e)))
+    }
+
+    /// List indicators in the local IOC store
+    #[napi]
+    pub async fn get_local_iocs(&self) -> Result<String> {
+        let iocs = self.inner.get_local_iocs().await;
+        serde_json::to_string(&iocs)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize IOCs: {}", e)))
+    }
+
+    /// Import an OpenIOC or CSV indicator document into the local IOC store;
+    /// `mapping_json` names the CSV columns
+    #[napi]
+    pub async fn import_iocs(&self, format: String, document: String, mapping_json: Option<String>, source: Option<String>, auth_token: Option<String>) -> Result<String> {
+        let actor = self.authorize_platform(auth_token, "ioc_store")?;
+        let format = interchange::InterchangeFormat::parse(&format).map_err(|e| e.context("Failed to import IOCs"))?;
+        let mapping = Self::parse_csv_mapping(mapping_json.as_deref())?;
+        let source = source.unwrap_or_else(|| match format {
+            interchange::InterchangeFormat::OpenIoc => "OpenIOC import".to_string(),
+            interchange::InterchangeFormat::Csv => "CSV import".to_string(),
+        });
+        let params = serde_json::json!({ "format": format, "source": source, "bytes": document.len(), "mapping": mapping });
+        let report = self.inner.import_iocs(format, &document, &mapping, &source).await;
+        let report = self.audit.record(&actor, "import_iocs", "ioc_store", params, report)
+            .map_err(|e| e.context("Failed to import IOCs"))?;
+
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize import report: {}", e)))
+    }
+
+    /// Export the IOCs of a completed analysis as OpenIOC or CSV
+    #[napi]
+    pub async fn export_analysis_iocs(&self, sample_id: String, format: String, mapping_json: Option<String>, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "sample:export", &sample_id)?;
+        let format = interchange::InterchangeFormat::parse(&format).map_err(|e| e.context("Failed to export IOCs"))?;
+        let mapping = Self::parse_csv_mapping(mapping_json.as_deref())?;
+        let exported = self.inner.export_analysis_iocs(&tenant_id, &sample_id, format, &mapping).await;
+        let exported = self.audit.record(&actor, "export_analysis_iocs", &sample_id, serde_json::json!({ "format": format }), exported)
+            .map_err(|e| e.context("Failed to export IOCs"))?;
+
+        serde_json::to_string(&exported)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize IOC export: {}", e)))
+    }
+
+    /// Export the local IOC store as OpenIOC or CSV
+    #[napi]
+    pub async fn export_local_iocs(&self, format: String, mapping_json: Option<String>) -> Result<String> {
+        let format = interchange::InterchangeFormat::parse(&format).map_err(|e| e.context("Failed to export IOCs"))?;
+        let mapping = Self::parse_csv_mapping(mapping_json.as_deref())?;
+        let exported = self.inner.export_local_iocs(format, &mapping).await
+            .map_err(|e| e.context("Failed to export IOCs"))?;
+
+        serde_json::to_string(&exported)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize IOC export: {}", e)))
+    }
+
+    /// The built-in registry, file and command-line change heuristics
+    #[napi]
+    pub fn list_change_heuristics(&self) -> Result<String> {
+        serde_json::to_string(self.inner.change_heuristics())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize change heuristics: {}", e)))
+    }
+
+    /// Score a JSON array of `ObservedChange` records, e.g. autoruns or file
+    /// listings from live response; only changes that trip a heuristic are returned
+    #[napi]
+    pub fn assess_system_changes(&self, changes_json: String) -> Result<String> {
+        let changes: Vec<change_heuristics::ObservedChange> = serde_json::from_str(&changes_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse changes: {}", e)))?;
+        serde_json::to_string(&self.inner.assess_system_changes(&changes))
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize change assessments: {}", e)))
+    }
+
+    /// Select the detonation backend (simulated, libvirt or docker) for a VM environment
+    #[napi]
+    pub async fn configure_vm_driver(&self, environment_id: String, driver_json: String, auth_token: Option<String>) -> Result<()> {
+        let actor = self.authorize_platform(auth_token, "vm_drivers")?;
+        let driver: DriverConfig = serde_json::from_str(&driver_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse driver config: {}", e)))?;
+
+        let result = self.inner.configure_vm_driver(&environment_id, driver).await;
+        self.audit.record(&actor, "configure_vm_driver", &environment_id, serde_json::json!({ "driver": driver_json }), result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to configure VM driver: {}", e)))
+    }
+
+    /// Register or replace an anti-evasion countermeasure profile
+    #[napi]
+    pub async fn register_countermeasure_profile(&self, profile_json: String, auth_token: Option<String>) -> Result<()> {
+        let actor = self.authorize_platform(auth_token, "countermeasures")?;
+        let profile: countermeasures::CountermeasureProfile = serde_json::from_str(&profile_json)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid countermeasure profile: {}", e)))?;
+
+        let profile_id = profile.profile_id.clone();
+        let result = self.inner.register_countermeasure_profile(profile).await;
+        self.audit.record(&actor, "register_countermeasure_profile", &profile_id, serde_json::json!({ "profile": profile_json }), result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to register countermeasure profile: {}", e)))
+    }
+
+    /// List available anti-evasion countermeasure profiles
+    #[napi]
+    pub async fn list_countermeasure_profiles(&self) -> Result<String> {
+        let profiles = self.inner.list_countermeasure_profiles().await;
+        serde_json::to_string(&profiles)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize countermeasure profiles: {}", e)))
+    }
+
+    /// Attach a countermeasure profile to a VM environment; omit the profile to detach
+    #[napi]
+    pub async fn attach_countermeasure_profile(&self, environment_id: String, profile_id: Option<String>, auth_token: Option<String>) -> Result<()> {
+        let actor = self.authorize_platform(auth_token, "countermeasures")?;
+        let result = self.inner.attach_countermeasure_profile(&environment_id, profile_id.as_deref()).await;
+        self.audit.record(&actor, "attach_countermeasure_profile", &environment_id, serde_json::json!({ "profile_id": profile_id }), result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to attach countermeasure profile: {}", e)))
+    }
+
+    /// Look up an ATT&CK technique or sub-technique by id
+    #[napi]
+    pub fn get_mitre_technique(&self, technique_id: String) -> Result<Option<String>> {
+        mitre::enterprise()
+            .technique(&technique_id)
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize technique: {}", e)))
+    }
+
+    /// Look up an ATT&CK tactic by id, shortname or name, with its techniques
+    #[napi]
+    pub fn get_mitre_tactic(&self, tactic: String) -> Result<Option<String>> {
+        let catalog = mitre::enterprise();
+        let Some(found) = catalog.tactic(&tactic) else { return Ok(None) };
+        let techniques = catalog.techniques_for_tactic(&found.tactic_id);
+        serde_json::to_string(&serde_json::json!({ "tactic": found, "techniques": techniques }))
+            .map(Some)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize tactic: {}", e)))
+    }
+
+    /// Report which ATT&CK tactics are covered by loaded rules and completed analyses
+    #[napi]
+    pub async fn get_attack_coverage(&self, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let coverage = self.inner.get_attack_coverage(&tenant_id).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to compute ATT&CK coverage: {}", e)))?;
+
+        serde_json::to_string(&coverage)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize coverage: {}", e)))
+    }
+
+    /// Register or replace a decoy network persona
+    #[napi]
+    pub async fn register_network_persona(&self, persona_config: String, auth_token: Option<String>) -> Result<()> {
+        let actor = self.authorize_platform(auth_token, "network_personas")?;
+        let persona: NetworkPersona = serde_json::from_str(&persona_config)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid persona config: {}", e)))?;
+
+        let persona_id = persona.persona_id.clone();
+        let result = self.inner.register_network_persona(persona).await;
+        self.audit.record(&actor, "register_network_persona", &persona_id, serde_json::json!({ "persona": persona_config }), result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to register persona: {}", e)))
+    }
+
+    /// List available decoy network personas
+    #[napi]
+    pub async fn list_network_personas(&self) -> Result<String> {
+        let personas = self.inner.list_network_personas().await;
+        serde_json::to_string(&personas)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize personas: {}", e)))
+    }
+
+    /// Select the decoy network persona presented to a queued sample
+    #[napi]
+    pub async fn select_network_persona(&self, sample_id: String, persona_id: String, auth_token: Option<String>) -> Result<()> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", &sample_id)?;
+        let result = self.inner.select_network_persona(&tenant_id, &sample_id, &persona_id).await;
+        self.audit.record(&actor, "select_network_persona", &sample_id, serde_json::json!({ "persona_id": persona_id }), result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to select persona: {}", e)))
+    }
+
+    /// Compile YARA rules into a namespace and use them for every static analysis
+    #[napi]
+    pub async fn add_yara_rules(&self, rules: String, namespace: Option<String>, auth_token: Option<String>) -> Result<String> {
+        let resource = namespace.as_deref().unwrap_or("default");
+        let actor = self.authorize(auth_token, "rule:manage", resource)?;
+        let loaded = self.inner.add_yara_rules(namespace.as_deref(), &rules);
+        let loaded = self.audit.record(&actor, "add_yara_rules", resource, serde_json::json!({ "rules": rules }), loaded)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to compile YARA rules: {}", e)))?;
+        serde_json::to_string(&loaded)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize result: {}", e)))
+    }
+
+    /// Load a YARA rule file; the namespace defaults to the file name
+    #[napi]
+    pub async fn load_yara_rule_file(&self, path: String, namespace: Option<String>, auth_token: Option<String>) -> Result<String> {
+        let actor = self.authorize(auth_token, "rule:manage", &path)?;
+        let loaded = self.inner.load_yara_rule_file(&path, namespace.as_deref());
+        let loaded = self.audit.record(&actor, "load_yara_rule_file", &path, serde_json::json!({ "namespace": namespace }), loaded)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to load YARA rules: {}", e)))?;
+        serde_json::to_string(&loaded)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize result: {}", e)))
+    }
+
+    /// Remove every rule in a YARA namespace
+    #[napi]
+    pub async fn remove_yara_namespace(&self, namespace: String, auth_token: Option<String>) -> Result<bool> {
+        let actor = self.authorize(auth_token, "rule:manage", &namespace)?;
+        let removed = self.inner.remove_yara_namespace(&namespace);
+        self.audit.record(&actor, "remove_yara_namespace", &namespace, serde_json::json!({}), removed)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to remove YARA namespace: {}", e)))
+    }
+
+    /// List loaded YARA rules
+    #[napi]
+    pub async fn list_yara_rules(&self) -> Result<String> {
+        let rules = self.inner.list_yara_rules()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to list YARA rules: {}", e)))?;
+        serde_json::to_string(&rules)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize rules: {}", e)))
+    }
+
+    /// Scan a buffer with the loaded YARA rules
+    #[napi]
+    pub async fn scan_with_yara(&self, file_data: Buffer) -> Result<String> {
+        let matches = self.inner.scan_with_yara(&file_data)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to scan with YARA: {}", e)))?;
+        serde_json::to_string(&matches)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize matches: {}", e)))
+    }
+
     /// Export analysis data for compliance and integration
     #[napi]
-    pub async fn export_analyses(&self, export_config: String) -> Result<String> {
+    pub async fn export_analyses(&self, export_config: String, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
         let config: serde_json::Value = serde_json::from_str(&export_config)
             .map_err(|e| napi::Error::from_reason(format!("Failed to parse export config: {}", e)))?;
 
@@ -2741,6 +3787,7 @@ impl SandboxCoreNapi {
         
         let filtered_analyses: Vec<_> = completed_analyses.values()
             .filter(|analysis| {
+                analysis.tenant_id == tenant_id &&
                 analysis.analysis_metadata.analysis_start >= cutoff_time &&
                 (include_benign || !matches!(analysis.verdict, SandboxVerdict::Clean | SandboxVerdict::Likely_Clean))
             })
@@ -2771,20 +3818,7 @@ impl SandboxCoreNapi {
                 }
             },
             "analyses": if format == "summary" {
-                filtered_analyses.iter().map(|analysis| {
-                    serde_json::json!({
-                        "sample_id": analysis.sample_info.sample_id,
-                        "file_name": analysis.sample_info.file_name,
-                        "file_hash_sha256": analysis.sample_info.file_hash_sha256,
-                        "verdict": format!("{:?}", analysis.verdict),
-                        "threat_level": format!("{:?}", analysis.threat_level),
-                        "confidence_score": analysis.confidence_score,
-                        "analysis_duration": analysis.analysis_metadata.analysis_duration,
-                        "malware_family": analysis.malware_classification.family,
-                        "iocs_count": analysis.iocs_extracted.len(),
-                        "mitre_techniques": analysis.mitre_techniques.iter().map(|t| &t.technique_id).collect::<Vec<_>>()
-                    })
-                }).collect::<Vec<_>>()
+                filtered_analyses.iter().map(export::summary_record).collect::<Vec<_>>()
             } else {
                 // Full export would include complete analysis objects
                 vec![serde_json::json!("Full analysis data (truncated for demo)")]
@@ -2801,6 +3835,63 @@ impl SandboxCoreNapi {
             .map_err(|e| napi::Error::from_reason(format!("Failed to serialize export: {}", e)))
     }
 
+    /// Write the matching analyses to `path` as NDJSON, one analysis per line,
+    /// without holding the whole export in memory
+    #[napi]
+    pub async fn export_analyses_to_file(&self, path: String, filter_json: Option<String>, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "sample:export", &path)?;
+        let filter = Self::parse_export_filter(filter_json.as_deref())?;
+        let params = serde_json::to_value(&filter).unwrap_or_default();
+        let progress = self.inner.export_to_file(&tenant_id, std::path::Path::new(&path), filter).await;
+        let progress = self.audit.record(&actor, "export_analyses_to_file", &path, params, progress)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to export analyses: {}", e)))?;
+        serde_json::to_string(&progress)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize export progress: {}", e)))
+    }
+
+    /// Start a streaming export; JS pulls it with `next_analysis_export_chunk`,
+    /// typically wrapped in an async generator
+    #[napi]
+    pub async fn open_analysis_export(&self, filter_json: Option<String>, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let filter = Self::parse_export_filter(filter_json.as_deref())?;
+        let progress = self.inner.open_export(&tenant_id, filter).await;
+        serde_json::to_string(&progress)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize export progress: {}", e)))
+    }
+
+    /// Next NDJSON chunk of a streaming export with the export's progress
+    #[napi]
+    pub async fn next_analysis_export_chunk(&self, export_id: String, max_lines: Option<u32>, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let max_lines = max_lines.map_or(export::DEFAULT_CHUNK_LINES, |n| n as usize);
+        let chunk = self.inner.next_export_chunk(&tenant_id, &export_id, max_lines).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to read export: {}", e)))?;
+        serde_json::to_string(&chunk)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize export chunk: {}", e)))
+    }
+
+    /// Abandon a streaming export before it is exhausted
+    #[napi]
+    pub async fn close_analysis_export(&self, export_id: String, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let progress = self.inner.close_export(&tenant_id, &export_id).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to close export: {}", e)))?;
+        serde_json::to_string(&progress)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize export progress: {}", e)))
+    }
+
+    /// Progress of a streaming export or of a file export still being written
+    #[napi]
+    pub async fn get_export_progress(&self, export_id: String, auth_token: Option<String>) -> Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let progress = self.inner.export_progress(&tenant_id, &export_id).await
+            .ok_or_else(|| napi::Error::from_reason(format!("Export {} not found", export_id)))?;
+        serde_json::to_string(&progress)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize export progress: {}", e)))
+    }
+
     // Private helper methods for analysis
     async fn generate_comprehensive_report(&self, analyses: &[SandboxAnalysis], failed_samples: &[String], config: &serde_json::Value) -> serde_json::Value {
         let total_samples = analyses.len() + failed_samples.len();
@@ -2892,20 +3983,20 @@ impl SandboxCoreNapi {
     }
 
     fn analyze_infrastructure_overlap(&self, analyses: &[SandboxAnalysis]) -> serde_json::Value {
-        let mut domains = std::collections::HashSet::new();
-        let mut ips = std::collections::HashSet::new();
-        
-        for analysis in analyses {
-            domains.extend(analysis.network_analysis.suspicious_domains.iter().cloned());
-            for connection in &analysis.network_analysis.connections {
-                ips.insert(connection.remote_address.clone());
-            }
-        }
-        
+        let graph = campaign_graph::CampaignGraph::build(analyses);
+        let clusters = graph.clusters(2);
+        let reuse = if clusters.is_empty() {
+            "No infrastructure shared between samples"
+        } else {
+            "Shared infrastructure suggests coordinated campaign"
+        };
+
         serde_json::json!({
-            "shared_domains": domains.into_iter().collect::<Vec<_>>(),
-            "shared_ips": ips.into_iter().collect::<Vec<_>>(),
-            "infrastructure_reuse": "High overlap suggests coordinated campaign"
+            "shared_domains": graph.shared(campaign_graph::IocNodeKind::Domain),
+            "shared_ips": graph.shared(campaign_graph::IocNodeKind::Ip),
+            "campaign_clusters": clusters,
+            "central_infrastructure": graph.central_nodes(5),
+            "infrastructure_reuse": reuse
         })
     }
 
@@ -2953,6 +4044,32 @@ impl SandboxCoreNapi {
         })
     }
 
+    fn authorize(&self, auth_token: Option<String>, permission: &str, resource: &str) -> Result<String> {
+        self.access.check(auth_token.as_deref(), permission, resource)
+            .map_err(napi::Error::from_reason)
+    }
+
+    fn parse_csv_mapping(mapping_json: Option<&str>) -> Result<interchange::CsvColumnMapping> {
+        mapping_json.map_or(Ok(interchange::CsvColumnMapping::default()), |json| {
+            serde_json::from_str(json)
+                .map_err(|e| napi::Error::from_reason(format!("Failed to parse CSV column mapping: {}", e)))
+        })
+    }
+
+    fn parse_export_filter(filter_json: Option<&str>) -> Result<export::ExportFilter> {
+        filter_json.map_or(Ok(export::ExportFilter::default()), |json| {
+            serde_json::from_str(json)
+                .map_err(|e| napi::Error::from_reason(format!("Failed to parse export filter: {}", e)))
+        })
+    }
+
+    /// Tenant the call acts for, refused when the tenant is not active
+    fn tenant(&self, auth_token: Option<&str>) -> Result<String> {
+        let tenant_id = self.access.tenant(auth_token).unwrap_or_else(tenancy::default_tenant);
+        self.tenants.check(&tenant_id).map_err(napi::Error::from_reason)?;
+        Ok(tenant_id)
+    }
+
     fn extract_mitre_techniques(&self, analyses: &[SandboxAnalysis]) -> Vec<String> {
         let mut techniques = std::collections::HashSet::new();
         for analysis in analyses {
@@ -0,0 +1,196 @@
+//! Live Feed
+//!
+//! With `phantom-enterprise-standards` enabled, every completed analysis is
+//! published to the `sandbox_verdicts` topic of the core's live feed hub.
+//! With `live-feed`, `start_live_feed` serves the hub to dashboards over
+//! WebSocket and SSE. The host can relay events of other cores through
+//! `publish_feed_event`, so one server can carry incidents, alerts and hunt
+//! matches as well. Clients only receive events of their own tenant.
+//! Without the feature nothing is published.
+
+use crate::{SandboxAnalysis, SandboxCore};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::SandboxCoreNapi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use napi_derive::napi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::live_feed::{FeedEvent, FeedHub, FeedTopic};
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "live-feed")]
+use crate::tenancy;
+#[cfg(feature = "live-feed")]
+use phantom_enterprise_standards::live_feed::{feed_principal, serve_feed, FeedAuthenticator, FeedConfig};
+#[cfg(feature = "live-feed")]
+use std::net::SocketAddr;
+#[cfg(feature = "live-feed")]
+use std::sync::Mutex;
+
+#[cfg(feature = "phantom-enterprise-standards")]
+const SOURCE: &str = "phantom-sandbox-core";
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[derive(Default)]
+pub struct LiveFeedState {
+    hub: RwLock<Arc<FeedHub>>,
+    #[cfg(feature = "live-feed")]
+    server: Mutex<Option<tokio::task::JoinHandle<()>>>,
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+impl LiveFeedState {
+    pub fn hub(&self) -> Arc<FeedHub> {
+        self.hub.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Feed event for a completed analysis of the sample submitted as
+/// `file_name` with digest `sha256`
+#[cfg(feature = "phantom-enterprise-standards")]
+pub fn verdict_feed_event(analysis: &SandboxAnalysis, file_name: &str, sha256: &str) -> FeedEvent {
+    let payload = serde_json::json!({
+        "analysis_id": analysis.analysis_id,
+        "sample_id": analysis.sample_info.sample_id,
+        "file_name": file_name,
+        "sha256": sha256,
+        "verdict": analysis.verdict,
+        "threat_level": analysis.threat_level,
+        "confidence_score": analysis.confidence_score,
+        "malware_family": analysis.malware_classification.family,
+        "mitre_techniques": analysis.mitre_techniques.iter().map(|t| t.technique_id.as_str()).collect::<Vec<_>>(),
+        "ioc_count": analysis.iocs_extracted.len(),
+    });
+    FeedEvent::new(FeedTopic::SandboxVerdicts, "verdict", &analysis.tenant_id, SOURCE, payload).entity(&analysis.analysis_id)
+}
+
+impl SandboxCore {
+    /// Push a completed analysis to live feed subscribers, named and hashed
+    /// as the sample was submitted
+    pub(crate) async fn publish_verdict(&self, analysis: &SandboxAnalysis) {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            let sample = &analysis.sample_info;
+            let event = match self.submitted_sample(&analysis.tenant_id, &sample.sample_id).await {
+                Some((sha256, submission)) => verdict_feed_event(analysis, &submission.file_name, &sha256),
+                None => verdict_feed_event(analysis, &sample.file_name, &sample.file_hash_sha256),
+            };
+            self.live_feed.hub().publish(event);
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        let _ = analysis;
+    }
+
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn live_feed_hub(&self) -> Arc<FeedHub> {
+        self.live_feed.hub()
+    }
+
+    /// Serve the live feed on `bind`, or stop it with `None`. A running
+    /// server is replaced and its clients are disconnected.
+    #[cfg(feature = "live-feed")]
+    pub async fn start_live_feed(&self, bind: Option<&str>, config: FeedConfig, authenticate: FeedAuthenticator) -> Result<Option<SocketAddr>, String> {
+        if let Some(running) = self.live_feed.server.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            running.abort();
+        }
+        let hub = Arc::new(FeedHub::new(config));
+        let previous = std::mem::replace(&mut *self.live_feed.hub.write().unwrap_or_else(|e| e.into_inner()), hub.clone());
+        previous.close_all();
+
+        let Some(bind) = bind else { return Ok(None) };
+        let (address, handle) = serve_feed(bind, hub, authenticate).await?;
+        *self.live_feed.server.lock().unwrap_or_else(|e| e.into_inner()) = Some(handle);
+        Ok(Some(address))
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[napi]
+impl SandboxCoreNapi {
+    /// Relay an event (JSON `FeedEvent`) from another core to this core's
+    /// live feed subscribers; returns how many clients accepted it
+    #[napi]
+    pub fn publish_feed_event(&self, event_json: String, auth_token: Option<String>) -> napi::Result<u32> {
+        self.authorize_platform(auth_token, "live-feed")?;
+        let event: FeedEvent = serde_json::from_str(&event_json)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid feed event: {}", e)))?;
+        Ok(self.inner.live_feed_hub().publish(event) as u32)
+    }
+
+    /// Connected live feed clients with their queue depth and drop counts
+    #[napi]
+    pub fn get_live_feed_stats(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize_platform(auth_token, "live-feed")?;
+        serde_json::to_string(&self.inner.live_feed_hub().stats())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize live feed stats: {}", e)))
+    }
+}
+
+#[cfg(feature = "live-feed")]
+#[napi]
+impl SandboxCoreNapi {
+    /// Serve the live feed (`/feed/ws` and `/feed/sse`) on `bind`, e.g.
+    /// `0.0.0.0:9465`, replacing any running server; returns the bound
+    /// address. Pass no address to stop
+    #[napi]
+    pub async fn start_live_feed(&self, bind: Option<String>, config_json: Option<String>, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        let actor = self.authorize_platform(auth_token, "live-feed")?;
+        let config: FeedConfig = match &config_json {
+            Some(json) => serde_json::from_str(json)
+                .map_err(|e| napi::Error::from_reason(format!("Invalid live feed config: {}", e)))?,
+            None => FeedConfig::default(),
+        };
+
+        let access = self.access.clone();
+        let tenants = self.tenants.clone();
+        let authenticate: FeedAuthenticator = Arc::new(move |token| {
+            let principal = feed_principal(access.control(), token, tenancy::DEFAULT_TENANT)?;
+            if let Some(tenant_id) = &principal.tenant_id {
+                tenants.check(tenant_id)?;
+            }
+            Ok(principal)
+        });
+
+        let details = serde_json::json!({ "bind": bind, "config": config_json });
+        let started = self.inner.start_live_feed(bind.as_deref(), config, authenticate).await;
+        let address = self.audit.record(&actor, "start_live_feed", "live-feed", details, started)
+            .map_err(napi::Error::from_reason)?;
+        Ok(address.map(|address| address.to_string()))
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use super::*;
+    use crate::AnalysisPriority;
+    use phantom_enterprise_standards::live_feed::{FeedMessage, FeedPrincipal};
+    use sha2::{Digest, Sha256};
+
+    #[tokio::test]
+    async fn completed_analyses_reach_their_tenant_only() {
+        let core = SandboxCore::new().unwrap();
+        let hub = core.live_feed_hub();
+        let subscribe = |tenant_id: &str| {
+            let principal = FeedPrincipal { actor: "dashboard".to_string(), tenant_id: Some(tenant_id.to_string()) };
+            hub.subscribe(principal, FeedTopic::parse_list("sandbox_verdicts").unwrap()).unwrap()
+        };
+        let mut acme = subscribe("acme");
+        let mut globex = subscribe("globex");
+
+        core.submit_sample("acme", b"MZ payload", "a.exe".to_string(), AnalysisPriority::Normal, vec![], false).await.unwrap();
+        core.process_queue().await.unwrap();
+
+        match acme.next().await {
+            Some(FeedMessage::Event(event)) => {
+                assert_eq!(event.topic, FeedTopic::SandboxVerdicts);
+                assert_eq!(event.payload["file_name"], "a.exe");
+                assert_eq!(event.payload["sha256"], format!("{:x}", Sha256::digest(b"MZ payload")));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(hub.stats().clients.iter().map(|client| client.delivered).sum::<u64>(), 1);
+        drop(acme);
+        assert!(tokio::time::timeout(std::time::Duration::from_millis(50), globex.next()).await.is_err());
+    }
+}
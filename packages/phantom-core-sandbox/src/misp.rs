@@ -0,0 +1,591 @@
+//! MISP integration
+//!
+//! Pulls attributes from a MISP instance (REST API, API-key auth) into the
+//! sandbox's local IOC store and pushes extracted IOCs back as MISP events.
+//! Indicators are deduplicated on `type:value` in both directions: pulls merge
+//! into existing entries, and pushes skip IOCs that came from MISP or were
+//! already pushed. Local IOC categories are translated to MISP tags (and back)
+//! through configurable tag mappings. Sightings can be reported for values
+//! observed during detonation.
+//!
+//! The API key may be given as a `secret://` reference into the sandbox's
+//! secret store; it is kept as the reference and resolved on each call.
+
+use async_trait::async_trait;
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::secrets::reference_name;
+use crate::{ExtractedIOC, SandboxCore};
+
+// Configuration
+
+/// Translation between a local IOC category and a MISP tag
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagMapping {
+    pub category: String,
+    pub misp_tag: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MispConfig {
+    pub base_url: String,
+    /// The key itself, or a `secret://` reference resolved on each call
+    pub api_key: String,
+    /// Tags attached to every pushed event, e.g. a TLP marking
+    #[serde(default = "default_event_tags")]
+    pub event_tags: Vec<String>,
+    #[serde(default = "default_tag_mappings")]
+    pub tag_mappings: Vec<TagMapping>,
+    /// Only pull attributes carrying one of these tags; empty pulls everything
+    #[serde(default)]
+    pub pull_tags: Vec<String>,
+    /// MISP distribution level for pushed events (0 = your organisation only)
+    #[serde(default)]
+    pub distribution: u8,
+    #[serde(default = "default_threat_level")]
+    pub threat_level_id: u8,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default = "default_verify_tls")]
+    pub verify_tls: bool,
+}
+
+fn default_event_tags() -> Vec<String> {
+    vec!["tlp:amber".to_string(), "phantom:sandbox".to_string()]
+}
+
+fn default_tag_mappings() -> Vec<TagMapping> {
+    [
+        ("c2", "kill-chain:Command and Control"),
+        ("command_and_control", "kill-chain:Command and Control"),
+        ("exfiltration", "kill-chain:Actions on Objectives"),
+        ("delivery", "kill-chain:Delivery"),
+        ("malware_hash", "malware_classification:malware-category=\"Malware\""),
+        ("phishing", "rsit:fraud=\"phishing\""),
+    ]
+    .into_iter()
+    .map(|(category, misp_tag)| TagMapping {
+        category: category.to_string(),
+        misp_tag: misp_tag.to_string(),
+    })
+    .collect()
+}
+
+fn default_threat_level() -> u8 {
+    2
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+fn default_verify_tls() -> bool {
+    true
+}
+
+impl MispConfig {
+    /// Copy safe to return from status APIs; a `secret://` reference is kept
+    pub fn redacted(&self) -> Self {
+        let api_key = match reference_name(&self.api_key) {
+            Some(_) => self.api_key.clone(),
+            None => "[REDACTED]".to_string(),
+        };
+        Self { api_key, ..self.clone() }
+    }
+
+    fn tag_for_category(&self, category: &str) -> Option<&str> {
+        self.tag_mappings
+            .iter()
+            .find(|m| m.category.eq_ignore_ascii_case(category))
+            .map(|m| m.misp_tag.as_str())
+    }
+
+    fn category_for_tags(&self, tags: &[String]) -> Option<&str> {
+        tags.iter().find_map(|tag| {
+            self.tag_mappings
+                .iter()
+                .find(|m| m.misp_tag.eq_ignore_ascii_case(tag))
+                .map(|m| m.category.as_str())
+        })
+    }
+}
+
+// MISP wire types
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MispTag {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MispAttribute {
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub event_id: String,
+    #[serde(default)]
+    pub category: String,
+    #[serde(rename = "type")]
+    pub attribute_type: String,
+    pub value: String,
+    #[serde(default)]
+    pub to_ids: bool,
+    /// Unix timestamp, sent by MISP as a string
+    #[serde(default)]
+    pub timestamp: String,
+    #[serde(default)]
+    pub comment: String,
+    #[serde(rename = "Tag", default)]
+    pub tags: Vec<MispTag>,
+}
+
+/// MISP attribute type and category for a local IOC type
+fn misp_type_for(ioc_type: &str) -> Option<(&'static str, &'static str)> {
+    match ioc_type.to_ascii_lowercase().as_str() {
+        "ip" | "ipv4" | "ipv6" | "ip-dst" => Some(("ip-dst", "Network activity")),
+        "domain" | "hostname" => Some(("domain", "Network activity")),
+        "url" | "uri" => Some(("url", "Network activity")),
+        "md5" => Some(("md5", "Payload delivery")),
+        "sha1" => Some(("sha1", "Payload delivery")),
+        "sha256" | "hash" => Some(("sha256", "Payload delivery")),
+        "email" | "email-src" => Some(("email-src", "Payload delivery")),
+        "filename" | "file" => Some(("filename", "Artifacts dropped")),
+        "mutex" => Some(("mutex", "Artifacts dropped")),
+        "registry" | "regkey" => Some(("regkey", "Persistence mechanism")),
+        "user_agent" | "user-agent" => Some(("user-agent", "Network activity")),
+        _ => None,
+    }
+}
+
+/// Local IOC type for a MISP attribute type
+fn local_type_for(attribute_type: &str) -> Option<&'static str> {
+    match attribute_type {
+        "ip-dst" | "ip-src" => Some("ip"),
+        "domain" | "hostname" => Some("domain"),
+        "url" | "uri" => Some("url"),
+        "md5" => Some("md5"),
+        "sha1" => Some("sha1"),
+        "sha256" => Some("sha256"),
+        "email-src" | "email-dst" => Some("email"),
+        "filename" => Some("filename"),
+        "mutex" => Some("mutex"),
+        "regkey" => Some("registry"),
+        "user-agent" => Some("user_agent"),
+        _ => None,
+    }
+}
+
+/// Local IOC type for any of the type spellings MISP or the sandbox use
+pub(crate) fn canonical_ioc_type(ioc_type: &str) -> Option<&'static str> {
+    misp_type_for(ioc_type)
+        .and_then(|(attribute_type, _)| local_type_for(attribute_type))
+        .or_else(|| local_type_for(ioc_type))
+}
+
+/// Dedupe key shared by local and MISP indicators
+pub fn ioc_key(ioc_type: &str, value: &str) -> String {
+    let ioc_type = canonical_ioc_type(ioc_type)
+        .map(str::to_string)
+        .unwrap_or_else(|| ioc_type.to_ascii_lowercase());
+    format!("{}:{}", ioc_type, value.trim().to_ascii_lowercase())
+}
+
+// Transport
+
+/// Authenticated JSON requests against the MISP REST API
+#[async_trait]
+pub trait MispTransport: Send + Sync {
+    async fn post(&self, config: &MispConfig, path: &str, body: &Value) -> Result<Value, String>;
+}
+
+#[cfg(feature = "reqwest")]
+pub struct ReqwestMispTransport {
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "reqwest")]
+impl ReqwestMispTransport {
+    pub fn new(config: &MispConfig) -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(config.timeout_secs))
+            .danger_accept_invalid_certs(!config.verify_tls)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(Self { client })
+    }
+}
+
+#[cfg(feature = "reqwest")]
+#[async_trait]
+impl MispTransport for ReqwestMispTransport {
+    async fn post(&self, config: &MispConfig, path: &str, body: &Value) -> Result<Value, String> {
+        let url = format!("{}/{}", config.base_url.trim_end_matches('/'), path.trim_start_matches('/'));
+        let response = self
+            .client
+            .post(&url)
+            .header("Authorization", &config.api_key)
+            .header("Accept", "application/json")
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let status = response.status();
+        let text = response.text().await.map_err(|e| e.to_string())?;
+        if !status.is_success() {
+            return Err(format!("MISP responded with HTTP {}: {}", status.as_u16(), text));
+        }
+        serde_json::from_str(&text).map_err(|e| format!("Invalid MISP response: {}", e))
+    }
+}
+
+// Reports
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MispPullReport {
+    pub fetched: usize,
+    pub imported: usize,
+    pub updated: usize,
+    pub unsupported: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MispPushReport {
+    pub event_id: Option<String>,
+    pub pushed: usize,
+    pub skipped_duplicates: usize,
+    pub skipped_unsupported: usize,
+}
+
+/// MISP connection plus push bookkeeping
+#[derive(Default)]
+pub struct MispState {
+    config: Option<MispConfig>,
+    transport: Option<Arc<dyn MispTransport>>,
+    /// Keys already pushed; MISP-sourced keys are recorded here on pull
+    pushed: HashSet<String>,
+}
+
+fn attribute_seen_at(attribute: &MispAttribute) -> DateTime<Utc> {
+    attribute
+        .timestamp
+        .parse::<i64>()
+        .ok()
+        .and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+        .unwrap_or_else(Utc::now)
+}
+
+impl SandboxCore {
+    /// Configure the MISP connection using the live HTTP transport
+    pub async fn configure_misp(&self, config: MispConfig) -> Result<(), String> {
+        #[cfg(feature = "reqwest")]
+        {
+            let transport = Arc::new(ReqwestMispTransport::new(&config)?);
+            self.configure_misp_with_transport(config, transport).await
+        }
+        #[cfg(not(feature = "reqwest"))]
+        {
+            let _ = config;
+            Err("MISP integration requires the `reqwest` feature".to_string())
+        }
+    }
+
+    pub async fn configure_misp_with_transport(&self, config: MispConfig, transport: Arc<dyn MispTransport>) -> Result<(), String> {
+        let parsed = url::Url::parse(&config.base_url).map_err(|e| format!("Invalid MISP URL: {}", e))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(format!("Unsupported MISP scheme: {}", parsed.scheme()));
+        }
+        if config.api_key.trim().is_empty() {
+            return Err("MISP API key is required".to_string());
+        }
+        let mut state = self.misp.write().await;
+        state.config = Some(config);
+        state.transport = Some(transport);
+        Ok(())
+    }
+
+    pub async fn misp_config(&self) -> Option<MispConfig> {
+        self.misp.read().await.config.as_ref().map(MispConfig::redacted)
+    }
+
+    /// The config with its API key resolved, and the transport to send it over
+    async fn misp_connection(&self) -> Result<(MispConfig, Arc<dyn MispTransport>), String> {
+        let state = self.misp.read().await;
+        let (mut config, transport) = match (&state.config, &state.transport) {
+            (Some(config), Some(transport)) => (config.clone(), transport.clone()),
+            _ => return Err("MISP is not configured".to_string()),
+        };
+        config.api_key = self.secrets.resolve(&config.api_key).map_err(|e| format!("MISP API key: {}", e))?;
+        Ok((config, transport))
+    }
+
+    /// Pull IDS-flagged attributes changed since `since` into the local IOC store
+    pub async fn pull_misp_iocs(&self, since: Option<DateTime<Utc>>) -> Result<MispPullReport, String> {
+        let (config, transport) = self.misp_connection().await?;
+        let mut query = json!({ "returnFormat": "json", "to_ids": true, "includeEventTags": true });
+        if let Some(since) = since {
+            query["timestamp"] = json!(since.timestamp());
+        }
+        if !config.pull_tags.is_empty() {
+            query["tags"] = json!(config.pull_tags);
+        }
+
+        let response = transport.post(&config, "/attributes/restSearch", &query).await?;
+        let attributes: Vec<MispAttribute> = serde_json::from_value(
+            response.pointer("/response/Attribute").cloned().unwrap_or_else(|| json!([])),
+        )
+        .map_err(|e| format!("Invalid MISP attribute list: {}", e))?;
+
+        let mut report = MispPullReport {
+            fetched: attributes.len(),
+            ..Default::default()
+        };
+        let mut store = self.ioc_store.write().await;
+        let mut state = self.misp.write().await;
+        for attribute in attributes {
+            let Some(ioc_type) = local_type_for(&attribute.attribute_type) else {
+                report.unsupported += 1;
+                continue;
+            };
+            let key = ioc_key(ioc_type, &attribute.value);
+            let tags: Vec<String> = attribute.tags.iter().map(|t| t.name.clone()).collect();
+            let seen_at = attribute_seen_at(&attribute);
+            let source = format!("MISP event {}", attribute.event_id);
+            state.pushed.insert(key.clone());
+
+            match store.get_mut(&key) {
+                Some(existing) => {
+                    existing.first_seen = existing.first_seen.min(seen_at);
+                    existing.confidence = existing.confidence.max(0.8);
+                    existing.threat_intelligence.get_or_insert(source);
+                    report.updated += 1;
+                }
+                None => {
+                    store.insert(
+                        key,
+                        ExtractedIOC {
+                            ioc_type: ioc_type.to_string(),
+                            value: attribute.value.trim().to_string(),
+                            category: config
+                                .category_for_tags(&tags)
+                                .map(str::to_string)
+                                .unwrap_or_else(|| attribute.category.clone()),
+                            confidence: 0.8,
+                            context: attribute.comment.clone(),
+                            first_seen: seen_at,
+                            threat_intelligence: Some(source),
+                            enrichments: Vec::new(),
+                        },
+                    );
+                    report.imported += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Push IOCs as one MISP event, skipping duplicates and MISP-sourced values
+    pub async fn push_iocs_to_misp(&self, info: &str, iocs: &[ExtractedIOC]) -> Result<MispPushReport, String> {
+        let (config, transport) = self.misp_connection().await?;
+        let mut report = MispPushReport {
+            event_id: None,
+            pushed: 0,
+            skipped_duplicates: 0,
+            skipped_unsupported: 0,
+        };
+
+        let mut keys = Vec::new();
+        let mut attributes = Vec::new();
+        {
+            let state = self.misp.read().await;
+            let mut in_event = HashSet::new();
+            for ioc in iocs {
+                let Some((attribute_type, category)) = misp_type_for(&ioc.ioc_type) else {
+                    report.skipped_unsupported += 1;
+                    continue;
+                };
+                let key = ioc_key(&ioc.ioc_type, &ioc.value);
+                if state.pushed.contains(&key) || !in_event.insert(key.clone()) {
+                    report.skipped_duplicates += 1;
+                    continue;
+                }
+                let tags: Vec<Value> = config
+                    .tag_for_category(&ioc.category)
+                    .map(|tag| json!({ "name": tag }))
+                    .into_iter()
+                    .collect();
+                attributes.push(json!({
+                    "type": attribute_type,
+                    "category": category,
+                    "value": ioc.value,
+                    "to_ids": ioc.confidence >= 0.5,
+                    "comment": ioc.context,
+                    "Tag": tags,
+                }));
+                keys.push(key);
+            }
+        }
+        if attributes.is_empty() {
+            return Ok(report);
+        }
+
+        let event = json!({
+            "Event": {
+                "info": info,
+                "distribution": config.distribution,
+                "threat_level_id": config.threat_level_id,
+                "analysis": 2,
+                "Attribute": attributes,
+                "Tag": config.event_tags.iter().map(|tag| json!({ "name": tag })).collect::<Vec<_>>(),
+            }
+        });
+        let response = transport.post(&config, "/events/add", &event).await?;
+        report.event_id = response
+            .pointer("/Event/id")
+            .and_then(|id| id.as_str().map(str::to_string).or_else(|| id.as_u64().map(|n| n.to_string())));
+        report.pushed = keys.len();
+        self.misp.write().await.pushed.extend(keys);
+        Ok(report)
+    }
+
+    /// Push the IOCs extracted from a completed analysis as a MISP event
+    pub async fn push_analysis_to_misp(&self, tenant_id: &str, sample_id: &str) -> Result<MispPushReport, String> {
+        let analysis = self
+            .get_analysis(tenant_id, sample_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("No completed analysis for sample {}", sample_id))?;
+        let info = format!(
+            "Phantom sandbox: {} ({:?})",
+            analysis.sample_info.file_name, analysis.verdict
+        );
+        self.push_iocs_to_misp(&info, &analysis.iocs_extracted).await
+    }
+
+    /// Report a sighting of `value` to MISP
+    pub async fn submit_misp_sighting(&self, value: &str, source: &str) -> Result<(), String> {
+        let (config, transport) = self.misp_connection().await?;
+        let sighting = json!({
+            "values": [value],
+            "source": source,
+            "type": "0",
+            "timestamp": Utc::now().timestamp(),
+        });
+        transport.post(&config, "/sightings/add", &sighting).await.map(|_| ())
+    }
+
+    /// Indicators held in the local IOC store, keyed by `type:value`
+    pub async fn get_local_iocs(&self) -> HashMap<String, ExtractedIOC> {
+        self.ioc_store.read().await.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct FakeMisp {
+        requests: Mutex<Vec<(String, Value)>>,
+    }
+
+    #[async_trait]
+    impl MispTransport for FakeMisp {
+        async fn post(&self, config: &MispConfig, path: &str, body: &Value) -> Result<Value, String> {
+            assert_eq!(config.api_key, "secret-key");
+            self.requests.lock().unwrap().push((path.to_string(), body.clone()));
+            Ok(match path {
+                "/attributes/restSearch" => json!({ "response": { "Attribute": [
+                    { "id": "1", "event_id": "42", "category": "Network activity", "type": "ip-dst",
+                      "value": "203.0.113.7", "to_ids": true, "timestamp": "1700000000",
+                      "Tag": [{ "name": "kill-chain:Command and Control" }] },
+                    { "id": "2", "event_id": "42", "category": "Network activity", "type": "ip-dst",
+                      "value": "203.0.113.7 ", "to_ids": true, "timestamp": "1600000000" },
+                    { "id": "3", "event_id": "42", "category": "Other", "type": "btc", "value": "1abc" }
+                ] } }),
+                "/events/add" => json!({ "Event": { "id": "77" } }),
+                _ => json!({}),
+            })
+        }
+    }
+
+    fn config() -> MispConfig {
+        serde_json::from_value(json!({ "base_url": "https://misp.example", "api_key": "secret-key" })).unwrap()
+    }
+
+    fn ioc(ioc_type: &str, value: &str, category: &str) -> ExtractedIOC {
+        ExtractedIOC {
+            ioc_type: ioc_type.to_string(),
+            value: value.to_string(),
+            category: category.to_string(),
+            confidence: 0.9,
+            context: "observed during detonation".to_string(),
+            first_seen: Utc::now(),
+            threat_intelligence: None,
+            enrichments: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pull_dedupes_and_maps_tags() {
+        let core = SandboxCore::new().unwrap();
+        core.configure_misp_with_transport(config(), Arc::new(FakeMisp::default())).await.unwrap();
+
+        let report = core.pull_misp_iocs(None).await.unwrap();
+        assert_eq!((report.fetched, report.imported, report.updated, report.unsupported), (3, 1, 1, 1));
+
+        let store = core.get_local_iocs().await;
+        let pulled = &store["ip:203.0.113.7"];
+        assert_eq!(pulled.category, "c2");
+        assert_eq!(pulled.first_seen.timestamp(), 1_600_000_000);
+        assert_eq!(pulled.threat_intelligence.as_deref(), Some("MISP event 42"));
+        assert_eq!(core.misp_config().await.unwrap().api_key, "[REDACTED]");
+    }
+
+    #[tokio::test]
+    async fn test_push_skips_duplicates_and_misp_sourced_iocs() {
+        let misp = Arc::new(FakeMisp::default());
+        let core = SandboxCore::new().unwrap();
+        core.configure_misp_with_transport(config(), misp.clone()).await.unwrap();
+        core.pull_misp_iocs(None).await.unwrap();
+
+        let iocs = vec![
+            ioc("IP", "203.0.113.7", "c2"),
+            ioc("domain", "evil.example", "c2"),
+            ioc("domain", "EVIL.example", "c2"),
+            ioc("certificate", "abc", "other"),
+        ];
+        let report = core.push_iocs_to_misp("Phantom sandbox: test", &iocs).await.unwrap();
+        assert_eq!(report.event_id.as_deref(), Some("77"));
+        assert_eq!((report.pushed, report.skipped_duplicates, report.skipped_unsupported), (1, 2, 1));
+
+        let (path, body) = misp.requests.lock().unwrap().last().cloned().unwrap();
+        assert_eq!(path, "/events/add");
+        assert_eq!(body["Event"]["Attribute"][0]["type"], "domain");
+        assert_eq!(body["Event"]["Attribute"][0]["Tag"][0]["name"], "kill-chain:Command and Control");
+
+        let again = core.push_iocs_to_misp("Phantom sandbox: test", &iocs[1..2]).await.unwrap();
+        assert_eq!((again.pushed, again.event_id), (0, None));
+
+        core.submit_misp_sighting("evil.example", "phantom-sandbox").await.unwrap();
+        assert_eq!(misp.requests.lock().unwrap().last().unwrap().0, "/sightings/add");
+    }
+
+    #[tokio::test]
+    async fn test_api_key_reference_is_resolved_per_call() {
+        let core = SandboxCore::new().unwrap();
+        core.secrets().rotate_master_key(Arc::new(crate::secrets::StaticKey::new(vec![7u8; 32]))).unwrap();
+        let mut config = config();
+        config.api_key = "secret://misp/api-key".to_string();
+        core.configure_misp_with_transport(config, Arc::new(FakeMisp::default())).await.unwrap();
+
+        assert!(core.pull_misp_iocs(None).await.unwrap_err().contains("not found"));
+        core.secrets().put("misp/api-key", "secret-key").unwrap();
+        assert_eq!(core.pull_misp_iocs(None).await.unwrap().fetched, 3);
+        assert_eq!(core.misp_config().await.unwrap().api_key, "secret://misp/api-key");
+    }
+}
@@ -0,0 +1,399 @@
+//! MITRE ATT&CK catalog
+//!
+//! The Enterprise matrix (tactics, techniques and sub-techniques) shared
+//! through `phantom_enterprise_standards::mitre` is parsed once on first use. Lookups accept
+//! the spellings found in rule metadata and Sigma tags (`T1055.001`,
+//! `t1055/001`, `attack.t1055`, `TA0005`, `defense-evasion`, `Defense
+//! Evasion`). Coverage rolls technique references from detection rules and
+//! completed analyses up to their parent technique and tactics.
+
+use chrono::{DateTime, Utc};
+use phantom_enterprise_standards::mitre::ENTERPRISE_MATRIX_TSV as ENTERPRISE_TSV;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::OnceLock;
+
+use crate::SandboxCore;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MitreTactic {
+    pub tactic_id: String,
+    pub shortname: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MitreTechniqueInfo {
+    pub technique_id: String,
+    pub name: String,
+    /// Tactic ids, primary tactic first
+    pub tactics: Vec<String>,
+    pub parent_id: Option<String>,
+    pub sub_techniques: Vec<String>,
+}
+
+impl MitreTechniqueInfo {
+    pub fn is_sub_technique(&self) -> bool {
+        self.parent_id.is_some()
+    }
+}
+
+/// Parsed ATT&CK matrix
+#[derive(Debug)]
+pub struct MitreCatalog {
+    tactics: Vec<MitreTactic>,
+    techniques: Vec<MitreTechniqueInfo>,
+    technique_index: HashMap<String, usize>,
+}
+
+/// The embedded Enterprise matrix
+pub fn enterprise() -> &'static MitreCatalog {
+    static CATALOG: OnceLock<MitreCatalog> = OnceLock::new();
+    CATALOG.get_or_init(|| MitreCatalog::parse(ENTERPRISE_TSV).expect("embedded ATT&CK dataset is valid"))
+}
+
+/// Canonical `T1234` / `T1234.001` form of a technique reference
+pub fn normalize_technique_id(reference: &str) -> Option<String> {
+    let reference = reference.trim();
+    let reference = reference.strip_prefix("attack.").unwrap_or(reference);
+    let (base, sub) = match reference.split_once(['.', '/']) {
+        Some((base, sub)) => (base, Some(sub)),
+        None => (reference, None),
+    };
+    let digits = base.strip_prefix(['T', 't'])?;
+    if digits.len() != 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    match sub {
+        None => Some(format!("T{}", digits)),
+        Some(sub) if sub.len() == 3 && sub.bytes().all(|b| b.is_ascii_digit()) => Some(format!("T{}.{}", digits, sub)),
+        Some(_) => None,
+    }
+}
+
+/// Every technique id mentioned in free text, e.g. rule metadata values
+pub fn technique_ids_in(text: &str) -> Vec<String> {
+    text.split(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '/'))
+        .filter_map(|token| normalize_technique_id(token.trim_end_matches('.')))
+        .collect()
+}
+
+impl MitreCatalog {
+    fn parse(source: &str) -> Result<Self, String> {
+        let mut catalog = MitreCatalog { tactics: Vec::new(), techniques: Vec::new(), technique_index: HashMap::new() };
+        for (line_no, line) in source.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split('\t').collect();
+            if fields.len() != 4 {
+                return Err(format!("line {}: expected 4 columns", line_no + 1));
+            }
+            let (kind, id, name, extra) = (fields[0], fields[1], fields[2], fields[3]);
+            match kind {
+                "tactic" => catalog.tactics.push(MitreTactic {
+                    tactic_id: id.to_string(),
+                    shortname: extra.to_string(),
+                    name: name.to_string(),
+                }),
+                "technique" => {
+                    let mut tactics = Vec::new();
+                    for shortname in extra.split(',') {
+                        let tactic = catalog
+                            .tactics
+                            .iter()
+                            .find(|t| t.shortname == shortname)
+                            .ok_or_else(|| format!("line {}: unknown tactic {}", line_no + 1, shortname))?;
+                        tactics.push(tactic.tactic_id.clone());
+                    }
+                    catalog.insert(MitreTechniqueInfo {
+                        technique_id: id.to_string(),
+                        name: name.to_string(),
+                        tactics,
+                        parent_id: None,
+                        sub_techniques: Vec::new(),
+                    });
+                }
+                "sub-technique" => {
+                    let parent_id = id.split('.').next().unwrap_or_default().to_string();
+                    let parent = catalog
+                        .technique_index
+                        .get(&parent_id)
+                        .copied()
+                        .ok_or_else(|| format!("line {}: {} has no parent technique", line_no + 1, id))?;
+                    catalog.techniques[parent].sub_techniques.push(id.to_string());
+                    let tactics = catalog.techniques[parent].tactics.clone();
+                    catalog.insert(MitreTechniqueInfo {
+                        technique_id: id.to_string(),
+                        name: name.to_string(),
+                        tactics,
+                        parent_id: Some(parent_id),
+                        sub_techniques: Vec::new(),
+                    });
+                }
+                other => return Err(format!("line {}: unknown row kind {}", line_no + 1, other)),
+            }
+        }
+        Ok(catalog)
+    }
+
+    fn insert(&mut self, technique: MitreTechniqueInfo) {
+        self.technique_index.insert(technique.technique_id.clone(), self.techniques.len());
+        self.techniques.push(technique);
+    }
+
+    /// Tactics in kill-chain order
+    pub fn tactics(&self) -> &[MitreTactic] {
+        &self.tactics
+    }
+
+    pub fn techniques(&self) -> &[MitreTechniqueInfo] {
+        &self.techniques
+    }
+
+    pub fn technique(&self, reference: &str) -> Option<&MitreTechniqueInfo> {
+        let id = normalize_technique_id(reference)?;
+        self.technique_index.get(&id).map(|&index| &self.techniques[index])
+    }
+
+    /// Look up a tactic by id, shortname or display name
+    pub fn tactic(&self, reference: &str) -> Option<&MitreTactic> {
+        let reference = reference.trim();
+        let reference = reference.strip_prefix("attack.").unwrap_or(reference);
+        let shortname = reference.to_ascii_lowercase().replace(['_', ' '], "-");
+        self.tactics.iter().find(|t| {
+            t.tactic_id.eq_ignore_ascii_case(reference) || t.shortname == shortname || t.name.eq_ignore_ascii_case(reference)
+        })
+    }
+
+    pub fn sub_techniques(&self, reference: &str) -> Vec<&MitreTechniqueInfo> {
+        self.technique(reference)
+            .map(|t| t.sub_techniques.iter().filter_map(|id| self.technique(id)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Tactics a technique belongs to, primary tactic first
+    pub fn tactics_for(&self, reference: &str) -> Vec<&MitreTactic> {
+        self.technique(reference)
+            .map(|t| t.tactics.iter().filter_map(|id| self.tactic(id)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Top-level techniques under a tactic
+    pub fn techniques_for_tactic(&self, reference: &str) -> Vec<&MitreTechniqueInfo> {
+        let Some(tactic) = self.tactic(reference) else { return Vec::new() };
+        self.techniques
+            .iter()
+            .filter(|t| !t.is_sub_technique() && t.tactics.contains(&tactic.tactic_id))
+            .collect()
+    }
+
+    /// Per-tactic coverage of the given detection sources
+    pub fn coverage(&self, sources: &[CoverageSource]) -> AttackCoverage {
+        let mut covered: HashMap<&str, BTreeSet<String>> = HashMap::new();
+        let mut covering: HashMap<&str, BTreeSet<String>> = HashMap::new();
+        let mut unknown = BTreeSet::new();
+
+        for source in sources {
+            for reference in &source.technique_ids {
+                let Some(technique) = self.technique(reference) else {
+                    unknown.insert(reference.clone());
+                    continue;
+                };
+                let top_level = technique.parent_id.as_deref().unwrap_or(&technique.technique_id);
+                for tactic_id in &technique.tactics {
+                    covered.entry(tactic_id.as_str()).or_default().insert(top_level.to_string());
+                    covering.entry(tactic_id.as_str()).or_default().insert(source.source_id.clone());
+                }
+            }
+        }
+
+        let tactics: Vec<TacticCoverage> = self
+            .tactics
+            .iter()
+            .map(|tactic| {
+                let total_techniques = self.techniques_for_tactic(&tactic.tactic_id).len();
+                let covered_techniques: Vec<String> =
+                    covered.remove(tactic.tactic_id.as_str()).unwrap_or_default().into_iter().collect();
+                TacticCoverage {
+                    tactic_id: tactic.tactic_id.clone(),
+                    tactic: tactic.name.clone(),
+                    total_techniques,
+                    coverage_percent: if total_techniques == 0 {
+                        0.0
+                    } else {
+                        covered_techniques.len() as f64 * 100.0 / total_techniques as f64
+                    },
+                    covered_techniques,
+                    sources: covering.remove(tactic.tactic_id.as_str()).unwrap_or_default().into_iter().collect(),
+                }
+            })
+            .collect();
+
+        AttackCoverage {
+            generated_at: Utc::now(),
+            covered_tactics: tactics.iter().filter(|t| !t.covered_techniques.is_empty()).map(|t| t.tactic.clone()).collect(),
+            uncovered_tactics: tactics.iter().filter(|t| t.covered_techniques.is_empty()).map(|t| t.tactic.clone()).collect(),
+            rule_count: sources.iter().filter(|s| s.source_type == CoverageSourceType::Rule).count(),
+            analysis_count: sources.iter().filter(|s| s.source_type == CoverageSourceType::Analysis).count(),
+            unknown_techniques: unknown.into_iter().collect(),
+            tactics,
+        }
+    }
+}
+
+// Coverage
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CoverageSourceType {
+    Rule,
+    Analysis,
+}
+
+/// A detection rule or analysis and the techniques it references
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageSource {
+    pub source_type: CoverageSourceType,
+    pub source_id: String,
+    pub technique_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TacticCoverage {
+    pub tactic_id: String,
+    pub tactic: String,
+    /// Top-level techniques in the tactic
+    pub total_techniques: usize,
+    /// Top-level techniques referenced directly or through a sub-technique
+    pub covered_techniques: Vec<String>,
+    pub coverage_percent: f64,
+    /// Rules and analyses contributing to this tactic
+    pub sources: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttackCoverage {
+    pub generated_at: DateTime<Utc>,
+    pub tactics: Vec<TacticCoverage>,
+    pub covered_tactics: Vec<String>,
+    pub uncovered_tactics: Vec<String>,
+    /// References that are not in the catalog
+    pub unknown_techniques: Vec<String>,
+    pub rule_count: usize,
+    pub analysis_count: usize,
+}
+
+/// Metadata keys whose values name ATT&CK techniques
+fn is_attack_meta_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    key.contains("mitre") || key.contains("attack") || key.contains("technique")
+}
+
+impl SandboxCore {
+    /// Tactic coverage of the loaded YARA rules and completed analyses.
+    ///
+    /// Private rules only feed other rules' conditions and are not counted.
+    pub async fn get_attack_coverage(&self, tenant_id: &str) -> Result<AttackCoverage, String> {
+        let mut sources = Vec::new();
+        for rule in self.list_yara_rules()? {
+            if rule.is_private {
+                continue;
+            }
+            let mut technique_ids: Vec<String> = rule.tags.iter().filter_map(|tag| normalize_technique_id(tag)).collect();
+            for (key, value) in &rule.meta {
+                if is_attack_meta_key(key) {
+                    technique_ids.extend(technique_ids_in(value));
+                }
+            }
+            if !technique_ids.is_empty() {
+                sources.push(CoverageSource {
+                    source_type: CoverageSourceType::Rule,
+                    source_id: format!("yara:{}:{}", rule.namespace, rule.name),
+                    technique_ids,
+                });
+            }
+        }
+
+        for analysis in self.completed_analyses.read().await.values() {
+            if analysis.tenant_id != tenant_id || analysis.mitre_techniques.is_empty() {
+                continue;
+            }
+            sources.push(CoverageSource {
+                source_type: CoverageSourceType::Analysis,
+                source_id: format!("analysis:{}", analysis.analysis_id),
+                technique_ids: analysis.mitre_techniques.iter().map(|t| t.technique_id.clone()).collect(),
+            });
+        }
+
+        Ok(enterprise().coverage(&sources))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog_lookups() {
+        let catalog = enterprise();
+        assert_eq!(catalog.tactics().len(), 14);
+        assert!(catalog.techniques().len() > 400);
+
+        let injection = catalog.technique("t1055").unwrap();
+        assert_eq!(injection.name, "Process Injection");
+        assert_eq!(injection.tactics, vec!["TA0005", "TA0004"]);
+        assert!(catalog.sub_techniques("T1055").iter().any(|t| t.name == "Process Hollowing"));
+
+        let run_keys = catalog.technique("attack.t1547/001").unwrap();
+        assert_eq!(run_keys.technique_id, "T1547.001");
+        assert_eq!(run_keys.parent_id.as_deref(), Some("T1547"));
+        assert_eq!(catalog.tactics_for("T1547.001")[0].name, "Persistence");
+
+        assert_eq!(catalog.tactic("attack.defense_evasion").unwrap().tactic_id, "TA0005");
+        assert_eq!(catalog.tactic("Command and Control").unwrap().shortname, "command-and-control");
+        assert!(catalog.techniques_for_tactic("TA0040").iter().any(|t| t.technique_id == "T1486"));
+        assert!(catalog.technique("T99").is_none());
+        assert_eq!(technique_ids_in("T1059.001, T1105 and TA0002"), vec!["T1059.001", "T1105"]);
+    }
+
+    #[test]
+    fn test_coverage_rolls_up_to_parent_and_tactics() {
+        let sources = vec![
+            CoverageSource {
+                source_type: CoverageSourceType::Rule,
+                source_id: "rule-1".to_string(),
+                technique_ids: vec!["T1055.012".to_string(), "T9999".to_string()],
+            },
+            CoverageSource {
+                source_type: CoverageSourceType::Analysis,
+                source_id: "analysis-1".to_string(),
+                technique_ids: vec!["T1055".to_string(), "T1486".to_string()],
+            },
+        ];
+        let coverage = enterprise().coverage(&sources);
+        let evasion = coverage.tactics.iter().find(|t| t.tactic_id == "TA0005").unwrap();
+        assert_eq!(evasion.covered_techniques, vec!["T1055"]);
+        assert_eq!(evasion.sources, vec!["analysis-1", "rule-1"]);
+        assert!(evasion.coverage_percent > 0.0);
+        assert_eq!(coverage.covered_tactics, vec!["Privilege Escalation", "Defense Evasion", "Impact"]);
+        assert_eq!(coverage.uncovered_tactics.len(), 11);
+        assert_eq!(coverage.unknown_techniques, vec!["T9999"]);
+        assert_eq!((coverage.rule_count, coverage.analysis_count), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_attack_coverage_from_yara_rules() {
+        let core = SandboxCore::new().unwrap();
+        core.add_yara_rules(
+            Some("attack"),
+            r#"rule Injector : T1055 { meta: mitre_attack = "T1055.002, T1106" strings: $a = "x" condition: $a }
+               rule Untagged { strings: $a = "y" condition: $a }"#,
+        )
+        .unwrap();
+        let coverage = core.get_attack_coverage("default").await.unwrap();
+        assert_eq!(coverage.rule_count, 1);
+        let execution = coverage.tactics.iter().find(|t| t.tactic == "Execution").unwrap();
+        assert_eq!(execution.covered_techniques, vec!["T1106"]);
+        assert_eq!(execution.sources, vec!["yara:attack:Injector"]);
+    }
+}
@@ -0,0 +1,572 @@
+//! Sandbox result webhooks
+//!
+//! Dispatches analysis results to registered webhook endpoints. Every
+//! delivery carries an explicit `schema_version`, is signed with
+//! HMAC-SHA256 using the endpoint's shared secret, and is rendered as either
+//! the full analysis or a compact summary depending on the endpoint's
+//! negotiated payload format. Sent notifications are retained so they can be
+//! replayed for a time range. Endpoints belong to the tenant that registered
+//! them and only receive, list and replay that tenant's analyses.
+
+use crate::{SandboxAnalysis, SandboxCore};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+/// Version of the webhook envelope; bump on breaking payload changes
+pub const WEBHOOK_SCHEMA_VERSION: &str = "1.0";
+
+pub const SIGNATURE_HEADER: &str = "X-Phantom-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Phantom-Timestamp";
+pub const EVENT_HEADER: &str = "X-Phantom-Event";
+pub const SCHEMA_VERSION_HEADER: &str = "X-Phantom-Schema-Version";
+pub const DELIVERY_HEADER: &str = "X-Phantom-Delivery";
+pub const REPLAY_HEADER: &str = "X-Phantom-Replay";
+
+/// Retained notifications available for replay
+const MAX_NOTIFICATION_HISTORY: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookEvent {
+    AnalysisCompleted,
+    AnalysisFailed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PayloadFormat {
+    Full,
+    Summary,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEndpoint {
+    #[serde(default)]
+    pub endpoint_id: String,
+    /// Set from the registering credential; any value supplied is replaced
+    #[serde(default = "crate::tenancy::default_tenant")]
+    pub tenant_id: String,
+    pub url: String,
+    /// Shared secret used to sign payloads
+    pub secret: String,
+    #[serde(default = "default_payload_format")]
+    pub payload_format: PayloadFormat,
+    /// Events delivered to this endpoint; empty means all events
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_payload_format() -> PayloadFormat {
+    PayloadFormat::Full
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl WebhookEndpoint {
+    fn accepts(&self, event: WebhookEvent, tenant_id: &str) -> bool {
+        self.enabled && self.tenant_id == tenant_id && (self.events.is_empty() || self.events.contains(&event))
+    }
+
+    /// Copy safe to return from status/list APIs
+    pub fn redacted(&self) -> Self {
+        Self {
+            secret: "[REDACTED]".to_string(),
+            ..self.clone()
+        }
+    }
+}
+
+/// Versioned envelope wrapping every webhook payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEnvelope {
+    pub schema_version: String,
+    pub notification_id: String,
+    pub event: WebhookEvent,
+    pub payload_format: PayloadFormat,
+    pub created_at: DateTime<Utc>,
+    pub data: serde_json::Value,
+}
+
+/// Compact payload for consumers that only need the verdict
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisSummaryPayload {
+    pub analysis_id: String,
+    pub sample_id: String,
+    pub file_name: String,
+    pub sha256: String,
+    pub verdict: String,
+    pub threat_level: String,
+    pub confidence_score: f64,
+    pub malware_family: Option<String>,
+    pub ioc_count: usize,
+    pub mitre_techniques: Vec<String>,
+}
+
+impl From<&SandboxAnalysis> for AnalysisSummaryPayload {
+    fn from(analysis: &SandboxAnalysis) -> Self {
+        Self {
+            analysis_id: analysis.analysis_id.clone(),
+            sample_id: analysis.sample_info.sample_id.clone(),
+            file_name: analysis.sample_info.file_name.clone(),
+            sha256: analysis.sample_info.file_hash_sha256.clone(),
+            verdict: format!("{:?}", analysis.verdict),
+            threat_level: format!("{:?}", analysis.threat_level),
+            confidence_score: analysis.confidence_score,
+            malware_family: analysis.malware_classification.family.clone(),
+            ioc_count: analysis.iocs_extracted.len(),
+            mitre_techniques: analysis.mitre_techniques.iter().map(|t| t.technique_id.clone()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum DeliveryStatus {
+    Delivered,
+    Failed,
+    /// No transport configured; the notification is retained for replay
+    Pending,
+}
+
+/// Record of a notification sent (or attempted) to one endpoint
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationRecord {
+    pub notification_id: String,
+    pub endpoint_id: String,
+    #[serde(default = "crate::tenancy::default_tenant")]
+    pub tenant_id: String,
+    pub event: WebhookEvent,
+    pub analysis_id: String,
+    pub envelope: WebhookEnvelope,
+    pub created_at: DateTime<Utc>,
+    pub last_attempt: Option<DateTime<Utc>>,
+    pub attempts: u32,
+    pub status: DeliveryStatus,
+    pub last_response_code: Option<u16>,
+    pub last_error: Option<String>,
+}
+
+/// Outbound HTTP used for webhook delivery
+#[async_trait]
+pub trait WebhookSender: Send + Sync {
+    async fn post(&self, url: &str, headers: &BTreeMap<String, String>, body: &str) -> Result<u16, String>;
+}
+
+#[cfg(feature = "reqwest")]
+pub struct ReqwestWebhookSender {
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "reqwest")]
+impl ReqwestWebhookSender {
+    pub fn new() -> Result<Self, String> {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(Self { client })
+    }
+}
+
+#[cfg(feature = "reqwest")]
+#[async_trait]
+impl WebhookSender for ReqwestWebhookSender {
+    async fn post(&self, url: &str, headers: &BTreeMap<String, String>, body: &str) -> Result<u16, String> {
+        let mut request = self.client.post(url).header("Content-Type", "application/json");
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        let response = request.body(body.to_string()).send().await.map_err(|e| e.to_string())?;
+        Ok(response.status().as_u16())
+    }
+}
+
+/// Compute the `sha256=<hex>` signature over `"{timestamp}.{body}"`
+pub fn sign_payload(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    format!("sha256={:x}", mac.finalize().into_bytes())
+}
+
+/// Constant-time verification helper for consumers and tests
+pub fn verify_signature(secret: &str, timestamp: i64, body: &str, signature: &str) -> bool {
+    let Some(hex_digest) = signature.strip_prefix("sha256=") else {
+        return false;
+    };
+    let Some(expected) = decode_hex(hex_digest) else {
+        return false;
+    };
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    mac.verify_slice(&expected).is_ok()
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&value[i..i + 2], 16).ok())
+        .collect()
+}
+
+/// Fans analysis results out to registered webhook endpoints
+pub struct NotificationDispatcher {
+    endpoints: RwLock<HashMap<String, WebhookEndpoint>>,
+    history: RwLock<VecDeque<NotificationRecord>>,
+    sender: Option<Arc<dyn WebhookSender>>,
+}
+
+impl NotificationDispatcher {
+    pub fn new(sender: Option<Arc<dyn WebhookSender>>) -> Self {
+        Self {
+            endpoints: RwLock::new(HashMap::new()),
+            history: RwLock::new(VecDeque::new()),
+            sender,
+        }
+    }
+
+    /// Dispatcher using the live HTTP sender when the `reqwest` feature is enabled
+    pub fn with_default_sender() -> Self {
+        #[cfg(feature = "reqwest")]
+        {
+            let sender = ReqwestWebhookSender::new().ok().map(|s| Arc::new(s) as Arc<dyn WebhookSender>);
+            Self::new(sender)
+        }
+        #[cfg(not(feature = "reqwest"))]
+        {
+            Self::new(None)
+        }
+    }
+
+    /// Register an endpoint; refused when no sender is configured, since
+    /// nothing could ever be delivered to it
+    pub async fn register_endpoint(&self, mut endpoint: WebhookEndpoint) -> Result<String, String> {
+        if self.sender.is_none() {
+            return Err("Webhook delivery is unavailable: no HTTP sender is configured (build with the `reqwest` feature)".to_string());
+        }
+        if endpoint.secret.is_empty() {
+            return Err("Webhook endpoints require a shared secret".to_string());
+        }
+        let parsed = url::Url::parse(&endpoint.url).map_err(|e| format!("Invalid webhook URL: {}", e))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(format!("Unsupported webhook scheme: {}", parsed.scheme()));
+        }
+        if endpoint.endpoint_id.is_empty() {
+            endpoint.endpoint_id = Uuid::new_v4().to_string();
+        }
+        let endpoint_id = endpoint.endpoint_id.clone();
+        self.endpoints.write().await.insert(endpoint_id.clone(), endpoint);
+        Ok(endpoint_id)
+    }
+
+    pub async fn remove_endpoint(&self, tenant_id: &str, endpoint_id: &str) -> bool {
+        let mut endpoints = self.endpoints.write().await;
+        if endpoints.get(endpoint_id).is_none_or(|e| e.tenant_id != tenant_id) {
+            return false;
+        }
+        endpoints.remove(endpoint_id).is_some()
+    }
+
+    pub async fn list_endpoints(&self, tenant_id: &str) -> Vec<WebhookEndpoint> {
+        self.endpoints.read().await.values().filter(|e| e.tenant_id == tenant_id).map(|e| e.redacted()).collect()
+    }
+
+    /// Deliver `analysis` to every endpoint of its tenant subscribed to `event`
+    pub async fn notify(&self, event: WebhookEvent, analysis: &SandboxAnalysis) -> Vec<NotificationRecord> {
+        let endpoints: Vec<WebhookEndpoint> = self
+            .endpoints
+            .read()
+            .await
+            .values()
+            .filter(|e| e.accepts(event, &analysis.tenant_id))
+            .cloned()
+            .collect();
+
+        let mut records = Vec::with_capacity(endpoints.len());
+        for endpoint in endpoints {
+            let data = match endpoint.payload_format {
+                PayloadFormat::Full => serde_json::to_value(analysis),
+                PayloadFormat::Summary => serde_json::to_value(AnalysisSummaryPayload::from(analysis)),
+            }
+            .unwrap_or(serde_json::Value::Null);
+
+            let notification_id = Uuid::new_v4().to_string();
+            let mut record = NotificationRecord {
+                notification_id: notification_id.clone(),
+                endpoint_id: endpoint.endpoint_id.clone(),
+                tenant_id: endpoint.tenant_id.clone(),
+                event,
+                analysis_id: analysis.analysis_id.clone(),
+                envelope: WebhookEnvelope {
+                    schema_version: WEBHOOK_SCHEMA_VERSION.to_string(),
+                    notification_id,
+                    event,
+                    payload_format: endpoint.payload_format,
+                    created_at: Utc::now(),
+                    data,
+                },
+                created_at: Utc::now(),
+                last_attempt: None,
+                attempts: 0,
+                status: DeliveryStatus::Pending,
+                last_response_code: None,
+                last_error: None,
+            };
+            self.deliver(&endpoint, &mut record, false).await;
+            records.push(record);
+        }
+
+        let mut history = self.history.write().await;
+        history.extend(records.iter().cloned());
+        while history.len() > MAX_NOTIFICATION_HISTORY {
+            history.pop_front();
+        }
+        records
+    }
+
+    /// Re-send the tenant's retained notifications created within `[from, to]`
+    pub async fn replay(&self, tenant_id: &str, from: DateTime<Utc>, to: DateTime<Utc>, endpoint_id: Option<&str>) -> Result<Vec<NotificationRecord>, String> {
+        if from > to {
+            return Err("Replay range start must not be after its end".to_string());
+        }
+        let endpoints = self.endpoints.read().await.clone();
+        let selected: Vec<NotificationRecord> = self
+            .history
+            .read()
+            .await
+            .iter()
+            .filter(|r| r.tenant_id == tenant_id && r.created_at >= from && r.created_at <= to)
+            .filter(|r| endpoint_id.is_none_or(|id| r.endpoint_id == id))
+            .cloned()
+            .collect();
+
+        let mut replayed = Vec::with_capacity(selected.len());
+        for mut record in selected {
+            match endpoints.get(&record.endpoint_id).filter(|e| e.tenant_id == tenant_id) {
+                Some(endpoint) => self.deliver(endpoint, &mut record, true).await,
+                None => {
+                    record.status = DeliveryStatus::Failed;
+                    record.last_error = Some("Endpoint no longer registered".to_string());
+                }
+            }
+            replayed.push(record);
+        }
+
+        let mut history = self.history.write().await;
+        for record in &replayed {
+            if let Some(existing) = history.iter_mut().find(|r| r.notification_id == record.notification_id) {
+                *existing = record.clone();
+            }
+        }
+        Ok(replayed)
+    }
+
+    pub async fn get_history(&self, tenant_id: &str, limit: usize) -> Vec<NotificationRecord> {
+        self.history.read().await.iter().rev().filter(|r| r.tenant_id == tenant_id).take(limit).cloned().collect()
+    }
+
+    /// Registered endpoints of all tenants, for health reporting
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub(crate) async fn endpoint_count(&self) -> usize {
+        self.endpoints.read().await.len()
+    }
+
+    /// Latest notifications of all tenants, newest first, for health reporting
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub(crate) async fn recent_history(&self, limit: usize) -> Vec<NotificationRecord> {
+        self.history.read().await.iter().rev().take(limit).cloned().collect()
+    }
+
+    /// Drop the tenant's endpoints and notification history; returns how many records were removed
+    pub async fn forget_tenant(&self, tenant_id: &str) -> usize {
+        let mut endpoints = self.endpoints.write().await;
+        let mut history = self.history.write().await;
+        let before = endpoints.len() + history.len();
+        endpoints.retain(|_, e| e.tenant_id != tenant_id);
+        history.retain(|r| r.tenant_id != tenant_id);
+        before - endpoints.len() - history.len()
+    }
+
+    async fn deliver(&self, endpoint: &WebhookEndpoint, record: &mut NotificationRecord, replay: bool) {
+        let Some(sender) = &self.sender else {
+            record.status = DeliveryStatus::Pending;
+            return;
+        };
+
+        let body = match serde_json::to_string(&record.envelope) {
+            Ok(body) => body,
+            Err(e) => {
+                record.status = DeliveryStatus::Failed;
+                record.last_error = Some(e.to_string());
+                return;
+            }
+        };
+        let timestamp = Utc::now().timestamp();
+        let mut headers = BTreeMap::from([
+            (SIGNATURE_HEADER.to_string(), sign_payload(&endpoint.secret, timestamp, &body)),
+            (TIMESTAMP_HEADER.to_string(), timestamp.to_string()),
+            (EVENT_HEADER.to_string(), format!("{:?}", record.event)),
+            (SCHEMA_VERSION_HEADER.to_string(), record.envelope.schema_version.clone()),
+            (DELIVERY_HEADER.to_string(), record.notification_id.clone()),
+        ]);
+        if replay {
+            headers.insert(REPLAY_HEADER.to_string(), "true".to_string());
+        }
+
+        record.attempts += 1;
+        record.last_attempt = Some(Utc::now());
+        match sender.post(&endpoint.url, &headers, &body).await {
+            Ok(code) if (200..300).contains(&code) => {
+                record.status = DeliveryStatus::Delivered;
+                record.last_response_code = Some(code);
+                record.last_error = None;
+            }
+            Ok(code) => {
+                record.status = DeliveryStatus::Failed;
+                record.last_response_code = Some(code);
+                record.last_error = Some(format!("Endpoint responded with HTTP {}", code));
+            }
+            Err(e) => {
+                record.status = DeliveryStatus::Failed;
+                record.last_error = Some(e);
+            }
+        }
+    }
+}
+
+// Webhook management on the sandbox core
+impl SandboxCore {
+    /// Register `endpoint` for the analyses of `tenant_id`
+    pub async fn register_webhook(&self, tenant_id: &str, endpoint: WebhookEndpoint) -> Result<String, String> {
+        self.notifications.register_endpoint(WebhookEndpoint { tenant_id: tenant_id.to_string(), ..endpoint }).await
+    }
+
+    pub async fn remove_webhook(&self, tenant_id: &str, endpoint_id: &str) -> bool {
+        self.notifications.remove_endpoint(tenant_id, endpoint_id).await
+    }
+
+    pub async fn list_webhooks(&self, tenant_id: &str) -> Vec<WebhookEndpoint> {
+        self.notifications.list_endpoints(tenant_id).await
+    }
+
+    pub async fn replay_notifications(&self, tenant_id: &str, from: DateTime<Utc>, to: DateTime<Utc>, endpoint_id: Option<&str>) -> Result<Vec<NotificationRecord>, String> {
+        self.notifications.replay(tenant_id, from, to, endpoint_id).await
+    }
+
+    pub async fn get_notification_history(&self, tenant_id: &str, limit: usize) -> Vec<NotificationRecord> {
+        self.notifications.get_history(tenant_id, limit).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct CapturingSender {
+        sent: Mutex<Vec<(BTreeMap<String, String>, String)>>,
+    }
+
+    #[async_trait]
+    impl WebhookSender for CapturingSender {
+        async fn post(&self, _url: &str, headers: &BTreeMap<String, String>, body: &str) -> Result<u16, String> {
+            self.sent.lock().unwrap().push((headers.clone(), body.to_string()));
+            Ok(204)
+        }
+    }
+
+    #[test]
+    fn test_signature_round_trip() {
+        let signature = sign_payload("shared", 1_700_000_000, "{\"a\":1}");
+        assert!(verify_signature("shared", 1_700_000_000, "{\"a\":1}", &signature));
+        assert!(!verify_signature("other", 1_700_000_000, "{\"a\":1}", &signature));
+        assert!(!verify_signature("shared", 1_700_000_001, "{\"a\":1}", &signature));
+    }
+
+    #[tokio::test]
+    async fn test_completed_analysis_is_signed_and_replayable() {
+        let sender = Arc::new(CapturingSender::default());
+        let mut core = SandboxCore::new().unwrap();
+        core.notifications = Arc::new(NotificationDispatcher::new(Some(sender.clone())));
+
+        let endpoint_id = core
+            .register_webhook("default", WebhookEndpoint {
+                endpoint_id: String::new(),
+                tenant_id: String::new(),
+                url: "https://consumer.example/hook".to_string(),
+                secret: "shared".to_string(),
+                payload_format: PayloadFormat::Summary,
+                events: vec![WebhookEvent::AnalysisCompleted],
+                enabled: true,
+            })
+            .await
+            .unwrap();
+
+        core.submit_sample("default", b"MZ sample", "sample.exe".to_string(), crate::AnalysisPriority::High, vec![], false)
+            .await
+            .unwrap();
+        core.process_queue().await.unwrap();
+
+        let (headers, body) = sender.sent.lock().unwrap()[0].clone();
+        let timestamp: i64 = headers[TIMESTAMP_HEADER].parse().unwrap();
+        assert!(verify_signature("shared", timestamp, &body, &headers[SIGNATURE_HEADER]));
+        let envelope: WebhookEnvelope = serde_json::from_str(&body).unwrap();
+        assert_eq!(envelope.schema_version, WEBHOOK_SCHEMA_VERSION);
+        assert_eq!(envelope.payload_format, PayloadFormat::Summary);
+        assert!(envelope.data.get("sha256").is_some());
+
+        let replayed = core
+            .replay_notifications("default", Utc::now() - chrono::Duration::minutes(5), Utc::now(), Some(&endpoint_id))
+            .await
+            .unwrap();
+        assert_eq!(replayed.len(), 1);
+        assert_eq!(replayed[0].attempts, 2);
+        assert_eq!(sender.sent.lock().unwrap()[1].0[REPLAY_HEADER], "true");
+
+        // Other tenants neither receive, see nor replay the endpoint's notifications
+        core.submit_sample("acme", b"MZ other", "other.exe".to_string(), crate::AnalysisPriority::High, vec![], false)
+            .await
+            .unwrap();
+        core.process_queue().await.unwrap();
+        assert_eq!(sender.sent.lock().unwrap().len(), 2);
+        assert!(core.list_webhooks("acme").await.is_empty());
+        assert!(!core.remove_webhook("acme", &endpoint_id).await);
+        let replayed = core.replay_notifications("acme", Utc::now() - chrono::Duration::minutes(5), Utc::now(), None).await.unwrap();
+        assert!(replayed.is_empty());
+        assert_eq!(core.get_notification_history("default", 10).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_register_rejects_missing_secret_or_sender() {
+        let dispatcher = NotificationDispatcher::new(Some(Arc::new(CapturingSender::default())));
+        let endpoint = WebhookEndpoint {
+            endpoint_id: String::new(),
+            tenant_id: String::new(),
+            url: "https://consumer.example/hook".to_string(),
+            secret: String::new(),
+            payload_format: PayloadFormat::Summary,
+            events: vec![],
+            enabled: true,
+        };
+        assert!(dispatcher.register_endpoint(endpoint.clone()).await.is_err());
+
+        let without_sender = NotificationDispatcher::new(None);
+        let error = without_sender.register_endpoint(WebhookEndpoint { secret: "shared".to_string(), ..endpoint }).await.unwrap_err();
+        assert!(error.contains("no HTTP sender"));
+    }
+}
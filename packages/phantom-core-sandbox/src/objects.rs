@@ -0,0 +1,122 @@
+//! Typed NAPI Objects
+//!
+//! `#[napi(object)]` summaries of completed analyses so TypeScript callers
+//! get generated types instead of parsing the JSON string the full
+//! `get_analysis` returns. A summary carries the verdict and the fields
+//! triage acts on; callers needing behaviour, network or memory detail still
+//! use the string methods. Timestamps are RFC 3339 strings.
+
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::{SandboxAnalysis, SandboxCore, SandboxCoreNapi};
+
+/// Verdict and triage fields of one completed analysis
+#[napi(object)]
+#[derive(Debug, Clone)]
+pub struct AnalysisSummary {
+    pub analysis_id: String,
+    pub tenant_id: String,
+    pub sample_id: String,
+    pub file_name: String,
+    pub sha256: String,
+    pub file_size: i64,
+    pub file_type: String,
+    pub tags: Vec<String>,
+    pub submitted_at: String,
+    pub analysis_started_at: String,
+    pub analysis_duration_secs: i64,
+    pub verdict: String,
+    pub threat_level: String,
+    pub confidence_score: f64,
+    pub malware_family: Option<String>,
+    pub ioc_count: u32,
+    pub mitre_techniques: Vec<String>,
+}
+
+impl From<&SandboxAnalysis> for AnalysisSummary {
+    fn from(analysis: &SandboxAnalysis) -> Self {
+        let sample = &analysis.sample_info;
+        Self {
+            analysis_id: analysis.analysis_id.clone(),
+            tenant_id: analysis.tenant_id.clone(),
+            sample_id: sample.sample_id.clone(),
+            file_name: sample.file_name.clone(),
+            sha256: sample.file_hash_sha256.clone(),
+            file_size: i64::try_from(sample.file_size).unwrap_or(i64::MAX),
+            file_type: sample.file_type.clone(),
+            tags: sample.tags.clone(),
+            submitted_at: sample.submission_time.to_rfc3339(),
+            analysis_started_at: analysis.analysis_metadata.analysis_start.to_rfc3339(),
+            analysis_duration_secs: i64::try_from(analysis.analysis_metadata.analysis_duration).unwrap_or(i64::MAX),
+            verdict: format!("{:?}", analysis.verdict),
+            threat_level: format!("{:?}", analysis.threat_level),
+            confidence_score: analysis.confidence_score,
+            malware_family: analysis.malware_classification.family.clone(),
+            ioc_count: u32::try_from(analysis.iocs_extracted.len()).unwrap_or(u32::MAX),
+            mitre_techniques: analysis.mitre_techniques.iter().map(|t| t.technique_id.clone()).collect(),
+        }
+    }
+}
+
+impl SandboxCore {
+    /// In-memory completed analyses of a tenant, most recently started first
+    pub async fn recent_analyses(&self, tenant_id: &str, limit: Option<usize>) -> Vec<SandboxAnalysis> {
+        let mut analyses: Vec<SandboxAnalysis> = self.completed_analyses.read().await
+            .values()
+            .filter(|analysis| analysis.tenant_id == tenant_id)
+            .cloned()
+            .collect();
+        analyses.sort_by_key(|analysis| std::cmp::Reverse(analysis.analysis_metadata.analysis_start));
+        analyses.truncate(limit.unwrap_or(usize::MAX));
+        analyses
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// Get the summary of a sample's completed analysis
+    #[napi]
+    pub async fn get_analysis_summary(&self, sample_id: String, auth_token: Option<String>) -> Result<Option<AnalysisSummary>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let analysis = self.inner.get_analysis(&tenant_id, &sample_id).await
+            .map_err(|e| e.context("Failed to get analysis"))?;
+        Ok(analysis.as_ref().map(AnalysisSummary::from))
+    }
+
+    /// Summaries of the most recent completed analyses
+    #[napi]
+    pub async fn list_analysis_summaries(&self, limit: Option<u32>, auth_token: Option<String>) -> Result<Vec<AnalysisSummary>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let analyses = self.inner.recent_analyses(&tenant_id, limit.map(|l| l as usize)).await;
+        Ok(analyses.iter().map(AnalysisSummary::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AnalysisPriority;
+
+    #[tokio::test]
+    async fn summaries_cover_a_tenants_completed_analyses() {
+        let core = SandboxCore::new().unwrap();
+        let mut samples = Vec::new();
+        for (name, content) in [("first.exe", b"MZ first sample".as_slice()), ("second.exe", b"MZ second sample".as_slice())] {
+            samples.push(core.submit_sample("acme", content, name.to_string(), AnalysisPriority::Normal, vec![], false).await.unwrap());
+            core.process_queue().await.unwrap();
+        }
+
+        let analysis = core.get_analysis("acme", &samples[0]).await.unwrap().unwrap();
+        let summary = AnalysisSummary::from(&analysis);
+        assert_eq!((summary.sample_id.as_str(), summary.tenant_id.as_str()), (samples[0].as_str(), "acme"));
+        assert_eq!(summary.sha256, analysis.sample_info.file_hash_sha256);
+        assert_eq!(summary.submitted_at, analysis.sample_info.submission_time.to_rfc3339());
+        assert_eq!(summary.verdict, format!("{:?}", analysis.verdict));
+        assert_eq!(summary.ioc_count as usize, analysis.iocs_extracted.len());
+
+        assert_eq!(core.recent_analyses("acme", None).await.len(), 2);
+        assert_eq!(core.recent_analyses("acme", Some(1)).await.len(), 1);
+        assert!(core.recent_analyses("globex", None).await.is_empty());
+    }
+}
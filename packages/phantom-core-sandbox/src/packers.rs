@@ -0,0 +1,274 @@
+//! Packer identification
+//!
+//! A small built-in signature database matched against PE section names,
+//! entry-point bytes and packer marker strings. Signature hits identify the
+//! packer by name; without one, the verdict falls back to entropy.
+
+use crate::static_pipeline::{read_u16, read_u32};
+use crate::{EntropyAnalysis, PEAnalysis, PackerDetection};
+
+/// Executable sections at or above this entropy are treated as packed
+const PACKED_SECTION_ENTROPY: f64 = 7.0;
+
+/// One packer signature; any matching criterion identifies the packer
+#[derive(Debug, Clone, Copy)]
+pub struct PackerSignature {
+    pub name: &'static str,
+    /// Section names the packer emits (compared case-insensitively)
+    pub section_names: &'static [&'static str],
+    /// Entry-point byte pattern in hex, `??` for wildcards
+    pub entry_point: Option<&'static str>,
+    /// Byte strings the packer leaves in the image
+    pub markers: &'static [&'static [u8]],
+}
+
+pub const BUILTIN_SIGNATURES: &[PackerSignature] = &[
+    PackerSignature {
+        name: "UPX",
+        section_names: &["UPX0", "UPX1", "UPX2"],
+        entry_point: Some("60 BE ?? ?? ?? ?? 8D BE ?? ?? ?? ??"),
+        markers: &[b"UPX!", b"This file is packed with the UPX"],
+    },
+    PackerSignature {
+        name: "Themida",
+        section_names: &[".themida", ".winlice"],
+        entry_point: Some("B8 ?? ?? ?? ?? 60 0B C0 74 68"),
+        markers: &[b"Themida", b"WinLicense"],
+    },
+    PackerSignature {
+        name: "MPRESS",
+        section_names: &[".MPRESS1", ".MPRESS2"],
+        entry_point: Some("60 E8 00 00 00 00 58 05"),
+        markers: &[],
+    },
+    PackerSignature {
+        name: "ASPack",
+        section_names: &[".aspack", ".adata"],
+        entry_point: Some("60 E8 03 00 00 00 E9 EB"),
+        markers: &[],
+    },
+    PackerSignature {
+        name: "PECompact",
+        section_names: &["PEC2", "PEC2TO", "PECompact2"],
+        entry_point: Some("B8 ?? ?? ?? ?? 50 64 FF 35 00 00 00 00"),
+        markers: &[b"PECompact2"],
+    },
+    PackerSignature {
+        name: "VMProtect",
+        section_names: &[".vmp0", ".vmp1", ".vmp2"],
+        entry_point: None,
+        markers: &[],
+    },
+    PackerSignature {
+        name: "Enigma Protector",
+        section_names: &[".enigma1", ".enigma2"],
+        entry_point: None,
+        markers: &[],
+    },
+    PackerSignature {
+        name: "NsPack",
+        section_names: &[".nsp0", ".nsp1", ".nsp2", "nsp0", "nsp1"],
+        entry_point: Some("9C 60 E8 00 00 00 00 5D B8 07 00 00 00"),
+        markers: &[],
+    },
+    PackerSignature {
+        name: "Petite",
+        section_names: &[".petite"],
+        entry_point: Some("B8 ?? ?? ?? ?? 66 9C 60 50"),
+        markers: &[],
+    },
+    PackerSignature {
+        name: "MEW",
+        section_names: &["MEW"],
+        entry_point: None,
+        markers: &[],
+    },
+];
+
+/// Compile a hex pattern into bytes with wildcards as `None`
+fn parse_pattern(pattern: &str) -> Vec<Option<u8>> {
+    pattern
+        .split_whitespace()
+        .map(|token| u8::from_str_radix(token, 16).ok())
+        .collect()
+}
+
+fn pattern_matches(data: &[u8], pattern: &[Option<u8>]) -> bool {
+    data.len() >= pattern.len()
+        && pattern
+            .iter()
+            .zip(data)
+            .all(|(expected, actual)| expected.is_none_or(|b| b == *actual))
+}
+
+fn find(data: &[u8], needle: &[u8]) -> Option<usize> {
+    if needle.is_empty() {
+        return None;
+    }
+    data.windows(needle.len()).position(|window| window == needle)
+}
+
+/// File offset of the entry point, mapped through the section table
+fn entry_point_offset(data: &[u8]) -> Option<usize> {
+    let pe_offset = read_u32(data, 0x3C)? as usize;
+    let coff = pe_offset + 4;
+    let section_count = read_u16(data, coff + 2)? as usize;
+    let optional_size = read_u16(data, coff + 16)? as usize;
+    let optional = coff + 20;
+    let entry_point = read_u32(data, optional + 16)?;
+    let section_table = optional + optional_size;
+
+    (0..section_count.min(96)).find_map(|index| {
+        let header = section_table + index * 40;
+        let virtual_size = read_u32(data, header + 8)?;
+        let virtual_address = read_u32(data, header + 12)?;
+        let raw_size = read_u32(data, header + 16)?;
+        let raw_pointer = read_u32(data, header + 20)?;
+        let span = virtual_size.max(raw_size);
+        (entry_point >= virtual_address && entry_point < virtual_address.saturating_add(span))
+            .then(|| (entry_point - virtual_address) as usize + raw_pointer as usize)
+    })
+}
+
+/// Version string UPX stores in front of its `UPX!` header, e.g. "3.96"
+fn upx_version(data: &[u8]) -> Option<String> {
+    let marker = find(data, b"UPX!")?;
+    let terminator = marker.checked_sub(1)?;
+    if data[terminator] != 0 {
+        return None;
+    }
+    let start = data[..terminator]
+        .iter()
+        .rposition(|b| !(b.is_ascii_digit() || *b == b'.'))
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let text = std::str::from_utf8(&data[start..terminator]).ok()?;
+    (text.contains('.') && text.starts_with(|c: char| c.is_ascii_digit())).then(|| text.to_string())
+}
+
+/// Identify the packer of a sample from signatures, falling back to entropy
+pub fn detect_packer(data: &[u8], pe: Option<&PEAnalysis>, entropy: &EntropyAnalysis) -> PackerDetection {
+    let entry_bytes = pe.and_then(|_| entry_point_offset(data)).and_then(|offset| data.get(offset..));
+
+    for signature in BUILTIN_SIGNATURES {
+        let section_hit = pe.is_some_and(|pe| {
+            pe.sections.iter().any(|section| {
+                signature
+                    .section_names
+                    .iter()
+                    .any(|name| section.name.eq_ignore_ascii_case(name))
+            })
+        });
+        let entry_hit = match (signature.entry_point, entry_bytes) {
+            (Some(pattern), Some(bytes)) => pattern_matches(bytes, &parse_pattern(pattern)),
+            _ => false,
+        };
+        let marker_hit = signature.markers.iter().any(|marker| find(data, marker).is_some());
+
+        let mut methods = Vec::new();
+        if entry_hit {
+            methods.push("entry point");
+        }
+        if section_hit {
+            methods.push("section names");
+        }
+        if marker_hit {
+            methods.push("marker strings");
+        }
+        if methods.is_empty() {
+            continue;
+        }
+
+        let confidence = match (entry_hit, section_hit, marker_hit) {
+            (true, true, _) | (_, true, true) => 0.98,
+            (true, false, _) => 0.9,
+            (false, true, false) => 0.85,
+            _ => 0.6,
+        };
+        return PackerDetection {
+            is_packed: true,
+            packer_name: Some(signature.name.to_string()),
+            packer_version: match signature.name {
+                "UPX" => upx_version(data),
+                _ => None,
+            },
+            confidence,
+            detection_method: format!("Signature ({})", methods.join(", ")),
+            unpacking_attempted: false,
+            unpacking_success: false,
+        };
+    }
+
+    let packed_section = pe.is_some_and(|pe| {
+        pe.sections.iter().any(|section| {
+            section.characteristics.iter().any(|c| c == "EXECUTE") && section.entropy >= PACKED_SECTION_ENTROPY
+        })
+    });
+    PackerDetection {
+        is_packed: entropy.is_packed || packed_section,
+        packer_name: None,
+        packer_version: None,
+        confidence: entropy.packing_probability,
+        detection_method: "Entropy Analysis".to_string(),
+        unpacking_attempted: false,
+        unpacking_success: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::static_pipeline::{analyze_entropy, parse_pe};
+
+    fn packed_pe(section_names: [&[u8]; 2], entry: &[u8]) -> Vec<u8> {
+        let mut data = vec![0u8; 0x800];
+        data[0..2].copy_from_slice(b"MZ");
+        data[0x3C..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        data[0x80..0x84].copy_from_slice(b"PE\0\0");
+        data[0x84..0x86].copy_from_slice(&0x014Cu16.to_le_bytes());
+        data[0x86..0x88].copy_from_slice(&2u16.to_le_bytes());
+        data[0x94..0x96].copy_from_slice(&0xE0u16.to_le_bytes());
+        data[0x98..0x9A].copy_from_slice(&0x10Bu16.to_le_bytes());
+        data[0xA8..0xAC].copy_from_slice(&0x2000u32.to_le_bytes());
+        for (index, name) in section_names.iter().enumerate() {
+            let header = 0x98 + 0xE0 + index * 40;
+            data[header..header + name.len()].copy_from_slice(name);
+            data[header + 8..header + 12].copy_from_slice(&0x200u32.to_le_bytes());
+            data[header + 12..header + 16].copy_from_slice(&(0x1000u32 * (index as u32 + 1)).to_le_bytes());
+            data[header + 16..header + 20].copy_from_slice(&0x200u32.to_le_bytes());
+            data[header + 20..header + 24].copy_from_slice(&(0x400u32 + 0x200 * index as u32).to_le_bytes());
+            data[header + 36..header + 40].copy_from_slice(&0xE000_0020u32.to_le_bytes());
+        }
+        data[0x600..0x600 + entry.len()].copy_from_slice(entry);
+        data
+    }
+
+    #[test]
+    fn test_identifies_upx_by_sections_entry_point_and_version() {
+        let mut data = packed_pe([b"UPX0", b"UPX1"], &[0x60, 0xBE, 1, 2, 3, 4, 0x8D, 0xBE, 5, 6, 7, 8]);
+        data[0x500..0x509].copy_from_slice(b"3.96\0UPX!");
+
+        let (pe, _) = parse_pe(&data).unwrap();
+        let detection = detect_packer(&data, Some(&pe), &analyze_entropy(&data));
+        assert!(detection.is_packed);
+        assert_eq!(detection.packer_name.as_deref(), Some("UPX"));
+        assert_eq!(detection.packer_version.as_deref(), Some("3.96"));
+        assert_eq!(detection.detection_method, "Signature (entry point, section names, marker strings)");
+        assert!(detection.confidence > 0.95);
+    }
+
+    #[test]
+    fn test_entry_point_pattern_alone_identifies_packer() {
+        let data = packed_pe([b".text", b".data"], &[0x60, 0xE8, 0, 0, 0, 0, 0x58, 0x05]);
+        let (pe, _) = parse_pe(&data).unwrap();
+        let detection = detect_packer(&data, Some(&pe), &analyze_entropy(&data));
+        assert_eq!(detection.packer_name.as_deref(), Some("MPRESS"));
+        assert_eq!(detection.detection_method, "Signature (entry point)");
+
+        let plain = packed_pe([b".text", b".data"], &[0x55, 0x8B, 0xEC]);
+        let (pe, _) = parse_pe(&plain).unwrap();
+        let detection = detect_packer(&plain, Some(&pe), &analyze_entropy(&plain));
+        assert!(!detection.is_packed);
+        assert_eq!(detection.packer_name, None);
+    }
+}
@@ -0,0 +1,555 @@
+//! Decoy network personas
+//!
+//! A persona describes the environment the simulation layer presents to a
+//! sample: a fake Active Directory domain, SMB shares seeded with decoy
+//! documents and realistic user profile directories. Legitimate software has
+//! no reason to touch any of it, so every interaction with a decoy is
+//! recorded as high-signal behavioral evidence.
+
+use crate::{
+    BehaviorSeverity, BehavioralAnalysis, FileSystemAnalysis, NetworkAnalysis, ProcessAnalysis, SandboxCore,
+    SuspiciousBehavior,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Persona applied when a job does not select one
+pub const DEFAULT_PERSONA_ID: &str = "corporate_ad";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecoyHost {
+    pub hostname: String,
+    pub ip_address: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecoyAccount {
+    pub username: String,
+    pub display_name: String,
+    pub department: String,
+    #[serde(default)]
+    pub is_privileged: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecoyDomain {
+    pub domain_name: String,
+    pub netbios_name: String,
+    pub domain_controllers: Vec<DecoyHost>,
+    #[serde(default)]
+    pub accounts: Vec<DecoyAccount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecoyDocument {
+    pub file_name: String,
+    pub size_bytes: u64,
+    /// Unique marker embedded in the document body
+    #[serde(default)]
+    pub canary_token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecoyShare {
+    pub host: DecoyHost,
+    pub share_name: String,
+    #[serde(default)]
+    pub documents: Vec<DecoyDocument>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecoyUserDirectory {
+    pub username: String,
+    pub home_path: String,
+    #[serde(default)]
+    pub files: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkPersona {
+    pub persona_id: String,
+    pub name: String,
+    pub description: String,
+    pub ad_domain: Option<DecoyDomain>,
+    #[serde(default)]
+    pub smb_shares: Vec<DecoyShare>,
+    #[serde(default)]
+    pub user_directories: Vec<DecoyUserDirectory>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DecoyType {
+    Domain,
+    DomainController,
+    Account,
+    SmbShare,
+    Document,
+    UserDirectory,
+}
+
+impl DecoyType {
+    /// How strongly touching this decoy indicates malicious intent
+    fn signal(&self, privileged: bool) -> f64 {
+        match self {
+            DecoyType::Domain => 0.5,
+            DecoyType::DomainController => 0.75,
+            DecoyType::Account if privileged => 0.9,
+            DecoyType::Account => 0.7,
+            DecoyType::SmbShare => 0.8,
+            DecoyType::Document => 0.95,
+            DecoyType::UserDirectory => 0.6,
+        }
+    }
+}
+
+/// Where in the detonation an interaction was observed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ObservationSource {
+    DnsQuery,
+    NetworkConnection,
+    HttpRequest,
+    FileActivity,
+    CommandLine,
+    ApiCall,
+}
+
+/// Raw artifact from the detonation checked against the persona's decoys
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Observation {
+    pub source: ObservationSource,
+    pub value: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecoyInteraction {
+    pub decoy_id: String,
+    pub decoy_type: DecoyType,
+    pub source: ObservationSource,
+    pub observed_value: String,
+    pub first_observed: DateTime<Utc>,
+    pub occurrences: u32,
+    pub signal_score: f64,
+}
+
+/// Persona used for an analysis and the decoys the sample touched
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecoyInteractionReport {
+    pub persona_id: String,
+    pub persona_name: String,
+    pub decoys_deployed: usize,
+    pub interactions: Vec<DecoyInteraction>,
+    /// Combined evidence (0.0 - 1.0) across distinct decoys touched
+    pub evidence_score: f64,
+}
+
+struct DecoyMarker {
+    decoy_id: String,
+    decoy_type: DecoyType,
+    needles: Vec<String>,
+    signal: f64,
+}
+
+impl NetworkPersona {
+    /// Fake corporate environment with an AD domain, file server and user profiles
+    pub fn corporate_ad() -> Self {
+        let file_server = DecoyHost { hostname: "FS01".to_string(), ip_address: "10.10.0.20".to_string() };
+        let document = |file_name: &str, size_bytes: u64| DecoyDocument {
+            file_name: file_name.to_string(),
+            size_bytes,
+            canary_token: format!("pcanary-{}", Uuid::new_v4().simple()),
+        };
+        let account = |username: &str, display_name: &str, department: &str, is_privileged: bool| DecoyAccount {
+            username: username.to_string(),
+            display_name: display_name.to_string(),
+            department: department.to_string(),
+            is_privileged,
+        };
+
+        Self {
+            persona_id: "corporate_ad".to_string(),
+            name: "Corporate AD workstation".to_string(),
+            description: "Domain-joined finance workstation with mapped file shares".to_string(),
+            ad_domain: Some(DecoyDomain {
+                domain_name: "acme-corp.local".to_string(),
+                netbios_name: "ACMECORP".to_string(),
+                domain_controllers: vec![
+                    DecoyHost { hostname: "DC01".to_string(), ip_address: "10.10.0.10".to_string() },
+                    DecoyHost { hostname: "DC02".to_string(), ip_address: "10.10.0.11".to_string() },
+                ],
+                accounts: vec![
+                    account("j.whitfield", "Jenna Whitfield", "Finance", false),
+                    account("m.okafor", "Michael Okafor", "Treasury", false),
+                    account("it-admin", "IT Administrator", "IT", true),
+                    account("svc_backup", "Backup Service", "IT", true),
+                ],
+            }),
+            smb_shares: vec![
+                DecoyShare {
+                    host: file_server.clone(),
+                    share_name: "Finance".to_string(),
+                    documents: vec![
+                        document("Q3_Payroll_Summary.xlsx", 48_312),
+                        document("Wire_Transfer_Approvals.docx", 22_904),
+                    ],
+                },
+                DecoyShare {
+                    host: file_server,
+                    share_name: "IT$".to_string(),
+                    documents: vec![document("domain_admin_passwords.kdbx", 3_416), document("vpn_profiles.txt", 1_207)],
+                },
+            ],
+            user_directories: vec![DecoyUserDirectory {
+                username: "j.whitfield".to_string(),
+                home_path: "C:\\Users\\j.whitfield".to_string(),
+                files: vec![
+                    "Desktop\\Online Banking Logins.txt".to_string(),
+                    "Documents\\Expense Reports 2024.xlsx".to_string(),
+                    "AppData\\Roaming\\Microsoft\\Credentials".to_string(),
+                ],
+            }],
+        }
+    }
+
+    /// Stand-alone home machine with a single user profile and no domain
+    pub fn home_user() -> Self {
+        Self {
+            persona_id: "home_user".to_string(),
+            name: "Home user".to_string(),
+            description: "Non-domain consumer laptop with a populated profile".to_string(),
+            ad_domain: None,
+            smb_shares: vec![],
+            user_directories: vec![DecoyUserDirectory {
+                username: "alex".to_string(),
+                home_path: "C:\\Users\\alex".to_string(),
+                files: vec![
+                    "Documents\\Tax Return 2023.pdf".to_string(),
+                    "Desktop\\crypto wallet seed.txt".to_string(),
+                    "Pictures\\passport_scan.jpg".to_string(),
+                ],
+            }],
+        }
+    }
+
+    pub fn builtin() -> Vec<Self> {
+        vec![Self::corporate_ad(), Self::home_user()]
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.persona_id.trim().is_empty() || self.name.trim().is_empty() {
+            return Err("Persona id and name are required".to_string());
+        }
+        if let Some(domain) = &self.ad_domain {
+            if domain.domain_name.trim().is_empty() || domain.domain_controllers.is_empty() {
+                return Err("AD domain requires a name and at least one domain controller".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// Simulated DNS answer for names the persona owns
+    pub fn resolve(&self, name: &str) -> Option<String> {
+        let name = name.trim_end_matches('.').to_lowercase();
+        if let Some(domain) = &self.ad_domain {
+            let suffix = domain.domain_name.to_lowercase();
+            if name == suffix {
+                return domain.domain_controllers.first().map(|dc| dc.ip_address.clone());
+            }
+            let short = name.strip_suffix(&format!(".{}", suffix)).unwrap_or(&name);
+            let hosts = domain.domain_controllers.iter().chain(self.smb_shares.iter().map(|s| &s.host));
+            for host in hosts {
+                if host.hostname.eq_ignore_ascii_case(short) {
+                    return Some(host.ip_address.clone());
+                }
+            }
+        }
+        None
+    }
+
+    fn markers(&self) -> Vec<DecoyMarker> {
+        let mut markers = Vec::new();
+        let mut push = |decoy_id: String, decoy_type: DecoyType, needles: Vec<String>, privileged: bool| {
+            markers.push(DecoyMarker {
+                decoy_id,
+                decoy_type,
+                needles: needles.into_iter().map(|n| n.to_lowercase()).filter(|n| n.len() >= 4).collect(),
+                signal: decoy_type.signal(privileged),
+            });
+        };
+
+        if let Some(domain) = &self.ad_domain {
+            push(format!("domain:{}", domain.domain_name), DecoyType::Domain, vec![domain.domain_name.clone()], false);
+            for dc in &domain.domain_controllers {
+                push(
+                    format!("dc:{}", dc.hostname),
+                    DecoyType::DomainController,
+                    vec![format!("{}.{}", dc.hostname, domain.domain_name), format!("\\\\{}\\", dc.hostname), dc.ip_address.clone()],
+                    false,
+                );
+            }
+            for account in &domain.accounts {
+                push(
+                    format!("account:{}\\{}", domain.netbios_name, account.username),
+                    DecoyType::Account,
+                    vec![
+                        format!("{}\\{}", domain.netbios_name, account.username),
+                        format!("{}@{}", account.username, domain.domain_name),
+                    ],
+                    account.is_privileged,
+                );
+            }
+        }
+
+        for share in &self.smb_shares {
+            let unc = format!("\\\\{}\\{}", share.host.hostname, share.share_name);
+            push(
+                format!("share:{}", unc),
+                DecoyType::SmbShare,
+                vec![unc.clone(), unc.replace('\\', "/"), format!("\\\\{}\\{}", share.host.ip_address, share.share_name)],
+                false,
+            );
+            for document in &share.documents {
+                let path = format!("{}\\{}", unc, document.file_name);
+                let mut needles = vec![path.clone(), path.replace('\\', "/")];
+                if !document.canary_token.is_empty() {
+                    needles.push(document.canary_token.clone());
+                }
+                push(format!("document:{}", path), DecoyType::Document, needles, false);
+            }
+        }
+
+        for directory in &self.user_directories {
+            push(
+                format!("profile:{}", directory.home_path),
+                DecoyType::UserDirectory,
+                vec![directory.home_path.clone()],
+                false,
+            );
+            for file in &directory.files {
+                let path = format!("{}\\{}", directory.home_path, file);
+                push(format!("document:{}", path), DecoyType::Document, vec![path], false);
+            }
+        }
+
+        markers
+    }
+
+    /// Match detonation observations against this persona's decoys
+    pub fn detect_interactions(&self, observations: &[Observation]) -> DecoyInteractionReport {
+        let markers = self.markers();
+        let mut interactions: HashMap<(String, ObservationSource), DecoyInteraction> = HashMap::new();
+
+        for observation in observations {
+            let value = observation.value.to_lowercase();
+            // A document hit also matches its share and profile prefixes; keep only the most specific decoy
+            let Some(marker) = markers
+                .iter()
+                .filter(|m| m.needles.iter().any(|n| value.contains(n.as_str())))
+                .max_by_key(|m| m.needles.iter().filter(|n| value.contains(n.as_str())).map(|n| n.len()).max())
+            else {
+                continue;
+            };
+
+            interactions
+                .entry((marker.decoy_id.clone(), observation.source))
+                .and_modify(|i| {
+                    i.occurrences += 1;
+                    i.first_observed = i.first_observed.min(observation.timestamp);
+                })
+                .or_insert_with(|| DecoyInteraction {
+                    decoy_id: marker.decoy_id.clone(),
+                    decoy_type: marker.decoy_type,
+                    source: observation.source,
+                    observed_value: observation.value.clone(),
+                    first_observed: observation.timestamp,
+                    occurrences: 1,
+                    signal_score: marker.signal,
+                });
+        }
+
+        let mut interactions: Vec<DecoyInteraction> = interactions.into_values().collect();
+        interactions.sort_by(|a, b| a.first_observed.cmp(&b.first_observed).then_with(|| a.decoy_id.cmp(&b.decoy_id)));
+
+        let mut seen = HashSet::new();
+        let miss_probability: f64 = interactions
+            .iter()
+            .filter(|i| seen.insert(i.decoy_id.clone()))
+            .map(|i| 1.0 - i.signal_score)
+            .product();
+
+        DecoyInteractionReport {
+            persona_id: self.persona_id.clone(),
+            persona_name: self.name.clone(),
+            decoys_deployed: markers.len(),
+            evidence_score: if interactions.is_empty() { 0.0 } else { 1.0 - miss_probability },
+            interactions,
+        }
+    }
+}
+
+impl DecoyInteractionReport {
+    /// Express each touched decoy as a behavioral finding
+    pub fn to_behaviors(&self) -> Vec<SuspiciousBehavior> {
+        let mut grouped: HashMap<&str, Vec<&DecoyInteraction>> = HashMap::new();
+        for interaction in &self.interactions {
+            grouped.entry(interaction.decoy_id.as_str()).or_default().push(interaction);
+        }
+
+        let mut behaviors: Vec<SuspiciousBehavior> = grouped
+            .into_iter()
+            .map(|(decoy_id, hits)| {
+                let signal = hits.iter().map(|h| h.signal_score).fold(0.0, f64::max);
+                let decoy_type = hits[0].decoy_type;
+                SuspiciousBehavior {
+                    behavior_id: format!("decoy-{}", Uuid::new_v4()),
+                    description: format!("Sample interacted with decoy {:?} {}", decoy_type, decoy_id),
+                    severity: if signal >= 0.8 { BehaviorSeverity::High } else { BehaviorSeverity::Medium },
+                    confidence: signal,
+                    evidence: hits.iter().map(|h| format!("{:?}: {}", h.source, h.observed_value)).collect(),
+                    mitre_technique: Some(
+                        match decoy_type {
+                            DecoyType::Domain | DecoyType::DomainController => "T1018",
+                            DecoyType::Account => "T1087.002",
+                            DecoyType::SmbShare => "T1135",
+                            DecoyType::Document | DecoyType::UserDirectory => "T1083",
+                        }
+                        .to_string(),
+                    ),
+                    first_observed: hits.iter().map(|h| h.first_observed).min().unwrap_or_else(Utc::now),
+                    frequency: hits.iter().map(|h| h.occurrences).sum(),
+                }
+            })
+            .collect();
+        behaviors.sort_by_key(|b| b.first_observed);
+        behaviors
+    }
+}
+
+/// Flatten detonation artifacts into observations for decoy matching
+pub fn collect_observations(
+    behavioral: &BehavioralAnalysis,
+    network: &NetworkAnalysis,
+    file_system: &FileSystemAnalysis,
+    process: &ProcessAnalysis,
+) -> Vec<Observation> {
+    let mut observations = Vec::new();
+    let mut observe = |source: ObservationSource, value: &str, timestamp: DateTime<Utc>| {
+        if !value.is_empty() {
+            observations.push(Observation { source, value: value.to_string(), timestamp });
+        }
+    };
+
+    for query in &network.dns_queries {
+        observe(ObservationSource::DnsQuery, &query.domain, query.timestamp);
+    }
+    for connection in &network.connections {
+        observe(ObservationSource::NetworkConnection, &connection.remote_address, connection.first_seen);
+    }
+    for request in &network.http_requests {
+        observe(ObservationSource::HttpRequest, &request.url, request.timestamp);
+    }
+
+    let changes = &behavioral.system_changes;
+    for change in changes.files_created.iter().chain(&changes.files_modified).chain(&changes.files_deleted) {
+        observe(ObservationSource::FileActivity, &change.file_path, change.timestamp);
+    }
+    for file in &file_system.suspicious_files {
+        observe(ObservationSource::FileActivity, &file.file_path, file.timestamp);
+    }
+
+    for info in &process.process_tree.processes {
+        observe(ObservationSource::CommandLine, &info.command_line, info.creation_time);
+    }
+
+    for call in &behavioral.api_calls.call_timeline {
+        for value in call.parameters.values() {
+            observe(ObservationSource::ApiCall, value, call.timestamp);
+        }
+    }
+
+    observations
+}
+
+impl SandboxCore {
+    pub async fn register_network_persona(&self, persona: NetworkPersona) -> Result<(), String> {
+        persona.validate()?;
+        self.personas.write().await.insert(persona.persona_id.clone(), persona);
+        Ok(())
+    }
+
+    pub async fn list_network_personas(&self) -> Vec<NetworkPersona> {
+        let mut personas: Vec<NetworkPersona> = self.personas.read().await.values().cloned().collect();
+        personas.sort_by(|a, b| a.persona_id.cmp(&b.persona_id));
+        personas
+    }
+
+    /// Choose the persona presented to a queued sample
+    pub async fn select_network_persona(&self, tenant_id: &str, sample_id: &str, persona_id: &str) -> Result<(), String> {
+        if !self.personas.read().await.contains_key(persona_id) {
+            return Err(format!("Unknown network persona {}", persona_id));
+        }
+        let mut queue = self.analysis_queue.write().await;
+        let job = queue
+            .iter_mut()
+            .find(|job| job.sample_id == sample_id && job.tenant_id == tenant_id && matches!(job.status, crate::JobStatus::Queued))
+            .ok_or_else(|| format!("No queued job for sample {}", sample_id))?;
+        job.analysis_config.network_persona = Some(persona_id.to_string());
+        self.job_store.save_job(job)
+    }
+
+    pub(crate) async fn evaluate_decoy_interactions(&self, persona_id: Option<&str>, observations: &[Observation]) -> Option<DecoyInteractionReport> {
+        let personas = self.personas.read().await;
+        let persona = personas.get(persona_id.unwrap_or(DEFAULT_PERSONA_ID))?;
+        Some(persona.detect_interactions(observations))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observation(source: ObservationSource, value: &str) -> Observation {
+        Observation { source, value: value.to_string(), timestamp: Utc::now() }
+    }
+
+    #[test]
+    fn test_decoy_interactions_are_attributed_to_most_specific_decoy() {
+        let persona = NetworkPersona::corporate_ad();
+        let observations = vec![
+            observation(ObservationSource::DnsQuery, "DC01.acme-corp.local"),
+            observation(ObservationSource::FileActivity, "\\\\FS01\\Finance\\Q3_Payroll_Summary.xlsx"),
+            observation(ObservationSource::FileActivity, "\\\\FS01\\Finance\\Q3_Payroll_Summary.xlsx"),
+            observation(ObservationSource::CommandLine, "net user it-admin /domain ACMECORP\\it-admin"),
+            observation(ObservationSource::DnsQuery, "www.microsoft.com"),
+        ];
+
+        let report = persona.detect_interactions(&observations);
+        let ids: HashSet<&str> = report.interactions.iter().map(|i| i.decoy_id.as_str()).collect();
+        assert_eq!(
+            ids,
+            HashSet::from([
+                "dc:DC01",
+                "document:\\\\FS01\\Finance\\Q3_Payroll_Summary.xlsx",
+                "account:ACMECORP\\it-admin",
+            ])
+        );
+        let document = report.interactions.iter().find(|i| i.decoy_type == DecoyType::Document).unwrap();
+        assert_eq!(document.occurrences, 2);
+        assert!(report.evidence_score > 0.99);
+        assert_eq!(report.to_behaviors().len(), 3);
+    }
+
+    #[test]
+    fn test_persona_dns_and_clean_run() {
+        let persona = NetworkPersona::corporate_ad();
+        assert_eq!(persona.resolve("dc02.acme-corp.local."), Some("10.10.0.11".to_string()));
+        assert_eq!(persona.resolve("FS01"), Some("10.10.0.20".to_string()));
+        assert_eq!(persona.resolve("example.com"), None);
+
+        let report = NetworkPersona::home_user().detect_interactions(&[observation(ObservationSource::FileActivity, "C:\\Windows\\Temp\\a.tmp")]);
+        assert!(report.interactions.is_empty());
+        assert_eq!(report.evidence_score, 0.0);
+    }
+}
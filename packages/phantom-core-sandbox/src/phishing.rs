@@ -0,0 +1,701 @@
+//! Phishing triage
+//!
+//! Triages a reported email: parses it, submits every attachment for
+//! analysis, extracts IOCs (sender, URLs, domains, hashes), scores the
+//! sender authentication, header and content signals, and produces a
+//! verdict with the evidence behind it. The result carries an incident in
+//! the shape the SecOp core accepts in `create_incident`, or timeline events
+//! for an incident that already exists. Attachment verdicts are picked up
+//! each time the triage is read, so the verdict firms up as analyses finish.
+
+use chrono::{DateTime, Utc};
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use uuid::Uuid;
+
+use crate::email::{self, AuthResult, ParsedEmail};
+use crate::error::{CoreError, CoreResult};
+use crate::pagination::{paginate, ListSpec, Page, PageRequest, SortDirection};
+use crate::timeline::{TimelineEvent, TIMELINE_SOURCE};
+use crate::{AnalysisPriority, ExtractedIOC, SandboxCore, SandboxCoreNapi, SandboxVerdict};
+
+/// Score at or above which an email is phishing
+pub const PHISHING_THRESHOLD: f64 = 0.7;
+/// Score at or above which an email is suspicious
+pub const SUSPICIOUS_THRESHOLD: f64 = 0.35;
+
+/// Attachment extensions that run code when opened
+const RISKY_EXTENSIONS: &[&str] = &[
+    "exe", "scr", "com", "pif", "bat", "cmd", "js", "jse", "vbs", "vbe", "wsf", "hta", "ps1", "lnk", "iso", "img",
+    "docm", "xlsm", "pptm", "dotm", "jar", "msi", "one",
+];
+const LURE_PHRASES: &[&str] = &[
+    "verify your account", "password", "urgent", "suspended", "unusual sign-in", "confirm your identity",
+    "invoice", "payment", "wire transfer", "gift card", "login", "expire",
+];
+const URL_SHORTENERS: &[&str] = &["bit.ly", "tinyurl.com", "t.co", "goo.gl", "ow.ly", "is.gd", "rebrand.ly", "cutt.ly"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhishingVerdict {
+    Phishing,
+    Suspicious,
+    Clean,
+}
+
+impl PhishingVerdict {
+    fn severity(self) -> &'static str {
+        match self {
+            Self::Phishing => "High",
+            Self::Suspicious => "Medium",
+            Self::Clean => "Low",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriageSignal {
+    pub signal: String,
+    pub weight: f64,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentTriage {
+    pub filename: String,
+    pub content_type: String,
+    pub size: u64,
+    pub sha256: String,
+    pub risky_extension: bool,
+    pub sample_id: Option<String>,
+    pub sandbox_verdict: Option<SandboxVerdict>,
+    pub submission_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailTriageOptions {
+    /// Existing incident the triage belongs to; a new incident is drafted otherwise
+    #[serde(default)]
+    pub incident_id: Option<String>,
+    #[serde(default = "default_submit")]
+    pub submit_attachments: bool,
+    #[serde(default = "default_priority")]
+    pub priority: AnalysisPriority,
+    /// Reporter of the email, recorded on the incident
+    #[serde(default)]
+    pub reported_by: Option<String>,
+}
+
+fn default_submit() -> bool {
+    true
+}
+
+fn default_priority() -> AnalysisPriority {
+    AnalysisPriority::High
+}
+
+impl Default for EmailTriageOptions {
+    fn default() -> Self {
+        Self { incident_id: None, submit_attachments: true, priority: default_priority(), reported_by: None }
+    }
+}
+
+/// Indicator in the SecOp incident shape
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentIndicator {
+    pub indicator_id: String,
+    pub indicator_type: String,
+    pub value: String,
+    pub confidence: f64,
+    pub severity: String,
+    pub source: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub context: String,
+}
+
+/// Timeline entry in the SecOp incident shape
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentTimelineEntry {
+    pub event_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub event_type: String,
+    pub description: String,
+    pub source: String,
+    pub severity: String,
+    pub data: HashMap<String, String>,
+}
+
+/// Incident in the shape the SecOp core accepts in `create_incident`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhishingIncident {
+    pub incident_id: String,
+    pub title: String,
+    pub description: String,
+    pub severity: String,
+    pub status: String,
+    pub category: String,
+    pub priority: u32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub assigned_to: String,
+    pub reporter: String,
+    pub affected_systems: Vec<String>,
+    pub indicators: Vec<IncidentIndicator>,
+    pub timeline: Vec<IncidentTimelineEntry>,
+    pub mitigation_actions: Vec<String>,
+    pub estimated_impact: f64,
+    pub containment_status: String,
+    pub evidence: Vec<String>,
+    pub tags: Vec<String>,
+    pub tenant_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhishingTriage {
+    pub triage_id: String,
+    pub tenant_id: String,
+    pub email: ParsedEmail,
+    pub attachments: Vec<AttachmentTriage>,
+    pub iocs: Vec<ExtractedIOC>,
+    pub signals: Vec<TriageSignal>,
+    /// 0.0 - 1.0
+    pub score: f64,
+    pub verdict: PhishingVerdict,
+    /// Attachments still under analysis; the verdict may still rise
+    pub attachments_pending: usize,
+    pub reported_by: Option<String>,
+    /// Existing incident the triage was attached to
+    pub incident_id: Option<String>,
+    /// Drafted incident when no existing one was given and the email is not clean
+    pub incident: Option<PhishingIncident>,
+    /// Events for the incident timeline, in the SecOp external event shape
+    pub timeline: Vec<TimelineEvent>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+fn extension(filename: &str) -> Option<String> {
+    filename.rsplit_once('.').map(|(_, ext)| ext.to_ascii_lowercase())
+}
+
+/// `invoice.pdf.exe`: a document extension hiding the real one
+fn double_extension(filename: &str) -> bool {
+    let parts: Vec<&str> = filename.split('.').collect();
+    parts.len() >= 3
+        && ["pdf", "doc", "docx", "xls", "xlsx", "txt", "jpg", "png"].contains(&parts[parts.len() - 2].to_ascii_lowercase().as_str())
+}
+
+fn is_ip_host(host: &str) -> bool {
+    host.parse::<std::net::IpAddr>().is_ok()
+}
+
+fn ioc(ioc_type: &str, value: String, confidence: f64, context: &str) -> ExtractedIOC {
+    ExtractedIOC {
+        ioc_type: ioc_type.to_string(),
+        value,
+        category: "Phishing".to_string(),
+        confidence,
+        context: context.to_string(),
+        first_seen: Utc::now(),
+        threat_intelligence: None,
+        enrichments: Vec::new(),
+    }
+}
+
+/// IOCs of an email: sender, reply-to, URLs and their hosts, attachment hashes
+pub fn email_iocs(email: &ParsedEmail) -> Vec<ExtractedIOC> {
+    let mut seen = BTreeSet::new();
+    let mut iocs = Vec::new();
+    let mut push = |ioc: ExtractedIOC| {
+        if seen.insert((ioc.ioc_type.clone(), ioc.value.clone())) {
+            iocs.push(ioc);
+        }
+    };
+    for (header, context) in [(&email.from, "Sender address"), (&email.reply_to, "Reply-To address"), (&email.return_path, "Envelope sender")] {
+        if let Some(address) = header.as_deref().and_then(email::address_of) {
+            push(ioc("email", address, 0.7, context));
+        }
+    }
+    for url in &email.urls {
+        push(ioc("url", url.clone(), 0.6, "Linked from email body"));
+        if let Some(host) = email::url_host(url) {
+            let kind = if is_ip_host(&host) { "ip" } else { "domain" };
+            push(ioc(kind, host, 0.5, "Host of a linked URL"));
+        }
+    }
+    for attachment in &email.attachments {
+        push(ioc("sha256", attachment.sha256.clone(), 0.7, "Email attachment"));
+        push(ioc("filename", attachment.filename.clone(), 0.4, "Email attachment"));
+    }
+    iocs
+}
+
+/// Header, authentication and content signals of an email
+pub fn email_signals(email: &ParsedEmail) -> Vec<TriageSignal> {
+    let mut signals = Vec::new();
+    let mut signal = |name: &str, weight: f64, detail: String| {
+        signals.push(TriageSignal { signal: name.to_string(), weight, detail });
+    };
+
+    let auth = &email.authentication;
+    match auth.spf {
+        AuthResult::Fail | AuthResult::PermError => signal("spf_fail", 0.2, "SPF failed".to_string()),
+        AuthResult::SoftFail => signal("spf_softfail", 0.1, "SPF soft-failed".to_string()),
+        _ => {}
+    }
+    if auth.dkim == AuthResult::Fail {
+        signal("dkim_fail", 0.2, "DKIM signature did not verify".to_string());
+    }
+    if matches!(auth.dmarc, AuthResult::Fail | AuthResult::PermError) {
+        signal("dmarc_fail", 0.3, "DMARC alignment failed".to_string());
+    }
+
+    let from_domain = email.from.as_deref().and_then(email::domain_of);
+    if let (Some(from), Some(reply)) = (&from_domain, email.reply_to.as_deref().and_then(email::domain_of)) {
+        if *from != reply {
+            signal("reply_to_mismatch", 0.15, format!("Replies go to {} instead of {}", reply, from));
+        }
+    }
+    if let (Some(from), Some(envelope)) = (&from_domain, email.return_path.as_deref().and_then(email::domain_of)) {
+        if *from != envelope && !envelope.ends_with(&format!(".{}", from)) {
+            signal("return_path_mismatch", 0.1, format!("Envelope sender domain {} differs from {}", envelope, from));
+        }
+    }
+    if let (Some(from), Some(name)) = (&from_domain, email.from.as_deref().and_then(email::display_name_of)) {
+        if let Some(shown) = email::domain_of(&name) {
+            if shown != *from {
+                signal("display_name_spoof", 0.2, format!("Display name shows {} but mail is from {}", shown, from));
+            }
+        }
+    }
+
+    let hosts: Vec<String> = email.urls.iter().filter_map(|url| email::url_host(url)).collect();
+    if let Some(host) = hosts.iter().find(|host| is_ip_host(host)) {
+        signal("ip_url", 0.2, format!("Links to a bare IP address {}", host));
+    }
+    if let Some(host) = hosts.iter().find(|host| host.split('.').any(|label| label.starts_with("xn--"))) {
+        signal("punycode_url", 0.15, format!("Links to an internationalized domain {}", host));
+    }
+    if let Some(host) = hosts.iter().find(|host| URL_SHORTENERS.contains(&host.as_str())) {
+        signal("shortened_url", 0.05, format!("Links through the URL shortener {}", host));
+    }
+
+    let text = format!("{}\n{}\n{}", email.subject, email.text_body.as_deref().unwrap_or_default(), email.html_body.as_deref().unwrap_or_default())
+        .to_ascii_lowercase();
+    let lures: Vec<&str> = LURE_PHRASES.iter().copied().filter(|phrase| text.contains(phrase)).collect();
+    if !lures.is_empty() {
+        signal("lure_language", (0.05 * lures.len() as f64).min(0.15), format!("Lure phrases: {}", lures.join(", ")));
+    }
+
+    for attachment in &email.attachments {
+        if double_extension(&attachment.filename) {
+            signal("double_extension", 0.3, format!("Attachment {} hides its real extension", attachment.filename));
+        } else if extension(&attachment.filename).is_some_and(|ext| RISKY_EXTENSIONS.contains(&ext.as_str())) {
+            signal("risky_attachment", 0.25, format!("Attachment {} can run code", attachment.filename));
+        }
+    }
+    signals
+}
+
+fn verdict_for(score: f64) -> PhishingVerdict {
+    if score >= PHISHING_THRESHOLD {
+        PhishingVerdict::Phishing
+    } else if score >= SUSPICIOUS_THRESHOLD {
+        PhishingVerdict::Suspicious
+    } else {
+        PhishingVerdict::Clean
+    }
+}
+
+fn priority_for(verdict: PhishingVerdict) -> u32 {
+    match verdict {
+        PhishingVerdict::Phishing => 2,
+        PhishingVerdict::Suspicious => 3,
+        PhishingVerdict::Clean => 4,
+    }
+}
+
+impl PhishingTriage {
+    /// Recompute the score, verdict, incident and timeline from the current evidence
+    fn evaluate(&mut self) {
+        let mut signals = email_signals(&self.email);
+        for attachment in &self.attachments {
+            match attachment.sandbox_verdict {
+                Some(SandboxVerdict::Malicious) => signals.push(TriageSignal {
+                    signal: "malicious_attachment".to_string(),
+                    weight: 0.7,
+                    detail: format!("Sandbox found {} malicious", attachment.filename),
+                }),
+                Some(SandboxVerdict::Suspicious) => signals.push(TriageSignal {
+                    signal: "suspicious_attachment".to_string(),
+                    weight: 0.35,
+                    detail: format!("Sandbox found {} suspicious", attachment.filename),
+                }),
+                _ => {}
+            }
+        }
+        self.score = signals.iter().map(|s| s.weight).sum::<f64>().min(1.0);
+        self.signals = signals;
+        self.verdict = verdict_for(self.score);
+        self.attachments_pending = self.attachments.iter().filter(|a| a.sample_id.is_some() && a.sandbox_verdict.is_none()).count();
+        self.timeline = self.timeline_events();
+        self.incident = (self.incident_id.is_none() && self.verdict != PhishingVerdict::Clean).then(|| self.draft_incident());
+        self.updated_at = Utc::now();
+    }
+
+    fn timeline_events(&self) -> Vec<TimelineEvent> {
+        let attributes = |extra: &[(&str, String)]| -> HashMap<String, String> {
+            let mut attributes = HashMap::from([("triage_id".to_string(), self.triage_id.clone())]);
+            if let Some(message_id) = &self.email.message_id {
+                attributes.insert("message_id".to_string(), message_id.clone());
+            }
+            attributes.extend(extra.iter().map(|(k, v)| (k.to_string(), v.clone())));
+            attributes
+        };
+        let mut events = vec![TimelineEvent {
+            timestamp: self.created_at,
+            source: TIMELINE_SOURCE.to_string(),
+            source_ref: self.triage_id.clone(),
+            kind: "email_reported".to_string(),
+            summary: format!("Email \"{}\" from {} reported", self.email.subject, self.email.from.as_deref().unwrap_or("unknown sender")),
+            severity: None,
+            attributes: attributes(&[]),
+        }];
+        for attachment in self.attachments.iter().filter(|a| a.sample_id.is_some()) {
+            let sample_id = attachment.sample_id.clone().unwrap_or_default();
+            events.push(TimelineEvent {
+                timestamp: self.created_at,
+                source: TIMELINE_SOURCE.to_string(),
+                source_ref: sample_id.clone(),
+                kind: "attachment_submitted".to_string(),
+                summary: format!("Attachment {} submitted for analysis", attachment.filename),
+                severity: attachment.sandbox_verdict.as_ref().map(|v| format!("{:?}", v).to_ascii_lowercase()),
+                attributes: attributes(&[("sample_id", sample_id), ("sha256", attachment.sha256.clone())]),
+            });
+        }
+        events.push(TimelineEvent {
+            timestamp: self.updated_at.max(self.created_at),
+            source: TIMELINE_SOURCE.to_string(),
+            source_ref: self.triage_id.clone(),
+            kind: "phishing_verdict".to_string(),
+            summary: format!("Phishing triage verdict {:?} (score {:.2})", self.verdict, self.score),
+            severity: Some(self.verdict.severity().to_ascii_lowercase()),
+            attributes: attributes(&[("verdict", format!("{:?}", self.verdict)), ("score", format!("{:.2}", self.score))]),
+        });
+        events
+    }
+
+    fn draft_incident(&self) -> PhishingIncident {
+        let severity = self.verdict.severity().to_string();
+        let now = Utc::now();
+        let indicators = self
+            .iocs
+            .iter()
+            .filter(|ioc| ioc.ioc_type != "filename")
+            .map(|ioc| IncidentIndicator {
+                indicator_id: format!("ioc_{}", Uuid::new_v4().simple()),
+                indicator_type: ioc.ioc_type.clone(),
+                value: ioc.value.clone(),
+                confidence: ioc.confidence,
+                severity: severity.clone(),
+                source: "sandbox:phishing".to_string(),
+                first_seen: ioc.first_seen,
+                last_seen: now,
+                context: ioc.context.clone(),
+            })
+            .collect();
+        let timeline = self
+            .timeline
+            .iter()
+            .map(|event| IncidentTimelineEntry {
+                event_id: format!("evt_{}", Uuid::new_v4().simple()),
+                timestamp: event.timestamp,
+                event_type: event.kind.clone(),
+                description: event.summary.clone(),
+                source: event.source.clone(),
+                severity: severity.clone(),
+                data: event.attributes.clone(),
+            })
+            .collect();
+        let mut evidence = vec![format!("phishing_triage:{}", self.triage_id)];
+        evidence.extend(self.email.message_id.iter().map(|id| format!("message_id:{}", id)));
+        evidence.extend(self.attachments.iter().filter_map(|a| a.sample_id.as_ref()).map(|id| format!("sample:{}", id)));
+
+        PhishingIncident {
+            // Stable per triage so a re-read never drafts a second incident
+            incident_id: format!("INC-PHISH-{}", self.triage_id.trim_start_matches("phish_")[..12].to_uppercase()),
+            title: format!("Phishing: {}", if self.email.subject.is_empty() { "(no subject)" } else { &self.email.subject }),
+            description: self.signals.iter().map(|s| s.detail.as_str()).collect::<Vec<_>>().join("; "),
+            severity,
+            status: "Open".to_string(),
+            category: "Phishing".to_string(),
+            priority: priority_for(self.verdict),
+            created_at: self.created_at,
+            updated_at: now,
+            assigned_to: String::new(),
+            reporter: self.reported_by.clone().unwrap_or_else(|| "phishing-triage".to_string()),
+            affected_systems: self.email.to.iter().chain(&self.email.cc).cloned().collect(),
+            indicators,
+            timeline,
+            mitigation_actions: vec![
+                "Purge the message from recipient mailboxes".to_string(),
+                "Block the sender and linked domains".to_string(),
+            ],
+            estimated_impact: self.score * 10.0,
+            containment_status: "None".to_string(),
+            evidence,
+            tags: std::iter::once("phishing".to_string()).chain(self.signals.iter().map(|s| s.signal.clone())).collect(),
+            tenant_id: self.tenant_id.clone(),
+        }
+    }
+}
+
+impl SandboxCore {
+    /// Parse and triage a reported email, submitting its attachments for analysis
+    pub async fn triage_email(&self, tenant_id: &str, raw: &[u8], options: EmailTriageOptions) -> Result<PhishingTriage, String> {
+        let mut email = email::parse_email(raw)?;
+        let triage_id = format!("phish_{}", Uuid::new_v4().simple());
+
+        let mut attachments = Vec::new();
+        for attachment in &mut email.attachments {
+            let data = std::mem::take(&mut attachment.data);
+            let mut triage = AttachmentTriage {
+                filename: attachment.filename.clone(),
+                content_type: attachment.content_type.clone(),
+                size: attachment.size,
+                sha256: attachment.sha256.clone(),
+                risky_extension: double_extension(&attachment.filename)
+                    || extension(&attachment.filename).is_some_and(|ext| RISKY_EXTENSIONS.contains(&ext.as_str())),
+                sample_id: None,
+                sandbox_verdict: None,
+                submission_error: None,
+            };
+            if options.submit_attachments {
+                let tags = vec!["email-attachment".to_string(), format!("triage:{}", triage_id)];
+                match self.submit_sample(tenant_id, &data, attachment.filename.clone(), options.priority.clone(), tags, false).await {
+                    Ok(sample_id) => triage.sample_id = Some(sample_id),
+                    Err(e) => triage.submission_error = Some(e.to_string()),
+                }
+            }
+            attachments.push(triage);
+        }
+
+        let now = Utc::now();
+        let mut triage = PhishingTriage {
+            triage_id: triage_id.clone(),
+            tenant_id: tenant_id.to_string(),
+            iocs: email_iocs(&email),
+            email,
+            attachments,
+            signals: Vec::new(),
+            score: 0.0,
+            verdict: PhishingVerdict::Clean,
+            attachments_pending: 0,
+            reported_by: options.reported_by,
+            incident_id: options.incident_id,
+            incident: None,
+            timeline: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        };
+        self.refresh_attachment_verdicts(&mut triage).await;
+        triage.evaluate();
+        self.phishing_triages.write().await.insert(triage_id, triage.clone());
+        Ok(triage)
+    }
+
+    async fn refresh_attachment_verdicts(&self, triage: &mut PhishingTriage) {
+        for attachment in triage.attachments.iter_mut().filter(|a| a.sandbox_verdict.is_none()) {
+            let Some(sample_id) = &attachment.sample_id else {
+                continue;
+            };
+            if let Ok(Some(analysis)) = self.get_analysis(&triage.tenant_id, sample_id).await {
+                attachment.sandbox_verdict = Some(analysis.verdict);
+            }
+        }
+    }
+
+    /// A stored triage, re-evaluated with the attachment analyses finished since
+    pub async fn get_phishing_triage(&self, tenant_id: &str, triage_id: &str) -> Result<PhishingTriage, String> {
+        let mut triage = self.phishing_triages.read().await
+            .get(triage_id)
+            .filter(|triage| triage.tenant_id == tenant_id)
+            .cloned()
+            .ok_or_else(|| format!("Phishing triage {} not found", triage_id))?;
+        if triage.attachments_pending > 0 {
+            self.refresh_attachment_verdicts(&mut triage).await;
+            triage.evaluate();
+            self.phishing_triages.write().await.insert(triage_id.to_string(), triage.clone());
+        }
+        Ok(triage)
+    }
+
+    /// Triages of `tenant_id`, newest first
+    pub async fn list_phishing_triages(&self, tenant_id: &str, limit: Option<usize>) -> Vec<PhishingTriage> {
+        let mut triages: Vec<PhishingTriage> = self.phishing_triages.read().await
+            .values()
+            .filter(|triage| triage.tenant_id == tenant_id)
+            .cloned()
+            .collect();
+        triages.sort_by_key(|triage| std::cmp::Reverse(triage.created_at));
+        triages.truncate(limit.unwrap_or(triages.len()));
+        triages
+    }
+
+    /// One page of the tenant's triages, newest first by default
+    pub async fn phishing_triages_page(&self, tenant_id: &str, request: &PageRequest) -> CoreResult<Page<PhishingTriage>> {
+        let triages = self.list_phishing_triages(tenant_id, None).await;
+        Ok(paginate(triages, request, ListSpec { id_field: "triage_id", sort_by: "created_at", direction: SortDirection::Desc })?)
+    }
+
+    /// Link a triage to an existing incident instead of its drafted one
+    pub async fn attach_phishing_triage(&self, tenant_id: &str, triage_id: &str, incident_id: &str) -> Result<PhishingTriage, String> {
+        let mut triages = self.phishing_triages.write().await;
+        let triage = triages
+            .get_mut(triage_id)
+            .filter(|triage| triage.tenant_id == tenant_id)
+            .ok_or_else(|| format!("Phishing triage {} not found", triage_id))?;
+        triage.incident_id = Some(incident_id.to_string());
+        triage.evaluate();
+        Ok(triage.clone())
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// Triage a reported `.eml` or `.msg` email; attachments are submitted
+    /// for analysis unless `submit_attachments` is false in the options
+    #[napi]
+    pub async fn triage_email(&self, email: Buffer, options_json: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", "email")?;
+        let options: EmailTriageOptions = options_json
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse triage options: {}", e)))?
+            .unwrap_or_default();
+        let params = serde_json::json!({ "size": email.len(), "incident_id": options.incident_id, "submit_attachments": options.submit_attachments });
+
+        let triage = self.inner.triage_email(&tenant_id, &email, options).await;
+        let triage = self.audit.record(&actor, "triage_email", "email", params, triage)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to triage email: {}", e)))?;
+
+        serde_json::to_string(&triage)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize phishing triage: {}", e)))
+    }
+
+    #[napi]
+    pub async fn get_phishing_triage(&self, triage_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let triage = self.inner.get_phishing_triage(&tenant_id, &triage_id).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to get phishing triage: {}", e)))?;
+
+        serde_json::to_string(&triage)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize phishing triage: {}", e)))
+    }
+
+    #[napi]
+    pub async fn list_phishing_triages(&self, limit: Option<u32>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let triages = self.inner.list_phishing_triages(&tenant_id, limit.map(|l| l as usize)).await;
+        serde_json::to_string(&triages)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize phishing triages: {}", e)))
+    }
+
+    #[napi]
+    pub async fn list_phishing_triages_page(&self, page_json: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let request = PageRequest::from_json(page_json.as_deref()).map_err(CoreError::from)?;
+        let page = self.inner.phishing_triages_page(&tenant_id, &request).await?.project(&request.fields).map_err(CoreError::from)?;
+        serde_json::to_string(&page)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize phishing triages: {}", e)))
+    }
+
+    /// Attach a triage to an existing SecOp incident
+    #[napi]
+    pub async fn attach_phishing_triage(&self, triage_id: String, incident_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", &incident_id)?;
+        let triage = self.inner.attach_phishing_triage(&tenant_id, &triage_id, &incident_id).await;
+        let triage = self.audit.record(&actor, "attach_phishing_triage", &triage_id, serde_json::json!({ "incident_id": incident_id }), triage)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to attach phishing triage: {}", e)))?;
+
+        serde_json::to_string(&triage)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize phishing triage: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LURE: &str = "From: \"it@corp.example\" <support@helpdesk-mail.example>\r\n\
+Reply-To: reset@collect.example\r\n\
+To: alice@corp.example\r\n\
+Subject: Urgent: password expires today\r\n\
+Authentication-Results: mx.corp.example; spf=fail; dkim=none; dmarc=fail\r\n\
+Message-ID: <lure-1@helpdesk-mail.example>\r\n\
+Content-Type: multipart/mixed; boundary=\"b\"\r\n\
+\r\n\
+--b\r\n\
+Content-Type: text/plain\r\n\
+\r\n\
+Keep your login: http://203.0.113.50/owa/\r\n\
+--b\r\n\
+Content-Type: application/octet-stream\r\n\
+Content-Disposition: attachment; filename=\"scan.pdf.exe\"\r\n\
+\r\n\
+MZ fake payload\r\n\
+--b--\r\n";
+
+    #[tokio::test]
+    async fn lure_is_phishing_with_submitted_attachment_and_incident() {
+        let core = SandboxCore::new().unwrap();
+        let triage = core.triage_email("acme", LURE.as_bytes(), EmailTriageOptions::default()).await.unwrap();
+
+        assert_eq!(triage.verdict, PhishingVerdict::Phishing);
+        let signals: Vec<&str> = triage.signals.iter().map(|s| s.signal.as_str()).collect();
+        for expected in ["spf_fail", "dmarc_fail", "reply_to_mismatch", "display_name_spoof", "ip_url", "double_extension"] {
+            assert!(signals.contains(&expected), "missing {} in {:?}", expected, signals);
+        }
+        assert!(triage.iocs.iter().any(|i| i.ioc_type == "ip" && i.value == "203.0.113.50"));
+        assert!(triage.iocs.iter().any(|i| i.ioc_type == "email" && i.value == "reset@collect.example"));
+
+        let sample_id = triage.attachments[0].sample_id.clone().unwrap();
+        assert!(core.get_analysis_status("acme", &sample_id).await.unwrap().is_some());
+        assert_eq!(triage.attachments_pending, 1);
+
+        let incident = triage.incident.clone().unwrap();
+        assert_eq!(incident.category, "Phishing");
+        assert_eq!(incident.affected_systems, vec!["alice@corp.example"]);
+        assert!(incident.evidence.contains(&format!("sample:{}", sample_id)));
+        assert!(triage.timeline.iter().any(|e| e.kind == "attachment_submitted"));
+
+        core.process_queue().await.unwrap();
+        let refreshed = core.get_phishing_triage("acme", &triage.triage_id).await.unwrap();
+        assert_eq!(refreshed.attachments_pending, 0);
+        assert!(refreshed.attachments[0].sandbox_verdict.is_some());
+        assert_eq!(refreshed.incident.unwrap().incident_id, incident.incident_id);
+        assert!(core.get_phishing_triage("globex", &triage.triage_id).await.is_err());
+
+        let attached = core.attach_phishing_triage("acme", &triage.triage_id, "INC-7").await.unwrap();
+        assert!(attached.incident.is_none());
+        assert_eq!(attached.incident_id.as_deref(), Some("INC-7"));
+    }
+
+    #[tokio::test]
+    async fn authenticated_newsletter_is_clean() {
+        let core = SandboxCore::new().unwrap();
+        let newsletter = "From: News <news@shop.example>\r\nTo: bob@corp.example\r\nSubject: Spring catalogue\r\n\
+Authentication-Results: mx; spf=pass; dkim=pass; dmarc=pass\r\n\r\nBrowse https://shop.example/spring\r\n";
+        let options = EmailTriageOptions { submit_attachments: false, ..Default::default() };
+        let triage = core.triage_email("acme", newsletter.as_bytes(), options).await.unwrap();
+        assert_eq!(triage.verdict, PhishingVerdict::Clean);
+        assert!(triage.incident.is_none());
+        assert_eq!(core.list_phishing_triages("acme", None).await.len(), 1);
+    }
+}
@@ -0,0 +1,562 @@
+//! Process trees
+//!
+//! The processes observed during an analysis form a forest of arbitrary
+//! depth: the sample is usually the root, but injected or service-started
+//! processes can appear with parents that were never observed. Process ids
+//! are unique within one analysis. The tree answers lineage queries
+//! (children, siblings, descendants, ancestors), finds suspicious spawn
+//! chains such as `winword.exe → cmd.exe → powershell.exe`, and renders as
+//! nested nodes for the front-end tree view.
+//!
+//! Analyses stored before trees were modelled this way carry a root process
+//! and a flat child list; they still deserialize.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+use crate::{ProcessAnomaly, ProcessInfo, ProcessRelationship, SandboxCore, SandboxCoreNapi};
+use napi_derive::napi;
+
+/// Office applications whose children are a common initial-access signal
+pub const OFFICE_APPS: &[&str] = &[
+    "winword.exe", "excel.exe", "powerpnt.exe", "outlook.exe", "msaccess.exe", "mspub.exe", "onenote.exe", "visio.exe",
+];
+
+const SHELLS: &[&str] = &["cmd.exe", "powershell.exe", "pwsh.exe"];
+const SCRIPT_HOSTS: &[&str] = &["wscript.exe", "cscript.exe", "mshta.exe"];
+const LOLBINS: &[&str] = &["rundll32.exe", "regsvr32.exe", "certutil.exe", "bitsadmin.exe", "msiexec.exe", "installutil.exe"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(from = "StoredProcessTree")]
+pub struct ProcessTree {
+    pub processes: Vec<ProcessInfo>,
+    pub relationships: Vec<ProcessRelationship>,
+}
+
+/// Serialized forms of a process tree, current and pre-graph
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredProcessTree {
+    Graph {
+        processes: Vec<ProcessInfo>,
+        #[serde(default)]
+        relationships: Vec<ProcessRelationship>,
+    },
+    Flat {
+        root_process: ProcessInfo,
+        child_processes: Vec<ProcessInfo>,
+        #[serde(default)]
+        relationships: Vec<ProcessRelationship>,
+    },
+}
+
+impl From<StoredProcessTree> for ProcessTree {
+    fn from(stored: StoredProcessTree) -> Self {
+        match stored {
+            StoredProcessTree::Graph { processes, relationships } => Self { processes, relationships },
+            StoredProcessTree::Flat { root_process, child_processes, relationships } => {
+                let mut tree = Self::from_processes(std::iter::once(root_process).chain(child_processes));
+                if !relationships.is_empty() {
+                    tree.relationships = relationships;
+                }
+                tree
+            }
+        }
+    }
+}
+
+/// A sequence of direct parent → child spawns worth flagging
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainPattern {
+    pub name: String,
+    /// Image names allowed at each step, outermost parent first
+    pub steps: Vec<Vec<String>>,
+    pub technique_id: Option<String>,
+    /// 0.0 - 1.0
+    pub score: f64,
+}
+
+impl ChainPattern {
+    fn new(name: &str, steps: &[&[&str]], technique_id: &str, score: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            steps: steps.iter().map(|step| step.iter().map(|s| s.to_string()).collect()).collect(),
+            technique_id: Some(technique_id.to_string()),
+            score,
+        }
+    }
+}
+
+/// Patterns checked for every analysis
+pub fn default_chain_patterns() -> Vec<ChainPattern> {
+    vec![
+        ChainPattern::new("office_shell_powershell", &[OFFICE_APPS, &["cmd.exe"], &["powershell.exe", "pwsh.exe"]], "T1059.001", 0.95),
+        ChainPattern::new("office_shell", &[OFFICE_APPS, SHELLS], "T1204.002", 0.85),
+        ChainPattern::new("office_script_host", &[OFFICE_APPS, SCRIPT_HOSTS], "T1059.005", 0.85),
+        ChainPattern::new("office_lolbin", &[OFFICE_APPS, LOLBINS], "T1218", 0.8),
+        ChainPattern::new("script_host_powershell", &[SCRIPT_HOSTS, &["powershell.exe", "pwsh.exe"]], "T1059.001", 0.75),
+        ChainPattern::new("wmi_shell", &[&["wmiprvse.exe"], SHELLS], "T1047", 0.7),
+    ]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuspiciousChain {
+    pub pattern: String,
+    pub technique_id: Option<String>,
+    pub score: f64,
+    /// Outermost parent first
+    pub process_ids: Vec<u32>,
+    pub process_names: Vec<String>,
+}
+
+/// Lowercase image name of a process, from its name or executable path
+pub fn image_name(process: &ProcessInfo) -> String {
+    let name = if process.process_name.is_empty() { &process.executable_path } else { &process.process_name };
+    name.rsplit(['\\', '/']).next().unwrap_or(name).to_ascii_lowercase()
+}
+
+/// Lineage query over one analysis' process tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProcessTreeQuery {
+    Children { process_id: u32 },
+    Siblings { process_id: u32 },
+    Descendants { process_id: u32 },
+    Ancestors { process_id: u32 },
+    /// Descendants of every process with one of these image names
+    SpawnedBy { image_names: Vec<String> },
+    SpawnedByOfficeApps,
+}
+
+impl ProcessTree {
+    /// Build a tree, linking each process to its parent when the parent was observed
+    pub fn from_processes(processes: impl IntoIterator<Item = ProcessInfo>) -> Self {
+        let mut tree = Self::default();
+        for process in processes {
+            tree.insert(process);
+        }
+        tree
+    }
+
+    /// Add or replace a process, linking it to its parent and its already
+    /// observed children
+    pub fn insert(&mut self, process: ProcessInfo) {
+        let pid = process.process_id;
+        self.processes.retain(|existing| existing.process_id != pid);
+        self.relationships.retain(|r| r.child_id != pid);
+        if self.get(process.parent_process_id).is_some() && process.parent_process_id != pid {
+            self.relationships.push(spawned(process.parent_process_id, pid));
+        }
+        for orphan in self.processes.iter().filter(|p| p.parent_process_id == pid && p.process_id != pid) {
+            if !self.relationships.iter().any(|r| r.child_id == orphan.process_id) {
+                self.relationships.push(spawned(pid, orphan.process_id));
+            }
+        }
+        self.processes.push(process);
+    }
+
+    pub fn len(&self) -> usize {
+        self.processes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.processes.is_empty()
+    }
+
+    pub fn get(&self, process_id: u32) -> Option<&ProcessInfo> {
+        self.processes.iter().find(|p| p.process_id == process_id)
+    }
+
+    fn parent_id(&self, process_id: u32) -> Option<u32> {
+        self.relationships.iter().find(|r| r.child_id == process_id).map(|r| r.parent_id)
+    }
+
+    fn child_ids(&self) -> HashMap<u32, Vec<u32>> {
+        let mut children: HashMap<u32, Vec<u32>> = HashMap::new();
+        for relationship in &self.relationships {
+            children.entry(relationship.parent_id).or_default().push(relationship.child_id);
+        }
+        children
+    }
+
+    fn collect(&self, ids: impl IntoIterator<Item = u32>) -> Vec<&ProcessInfo> {
+        let mut found: Vec<&ProcessInfo> = ids.into_iter().filter_map(|id| self.get(id)).collect();
+        found.sort_by_key(|p| (p.creation_time, p.process_id));
+        found
+    }
+
+    /// Processes whose parent was not observed, oldest first
+    pub fn roots(&self) -> Vec<&ProcessInfo> {
+        self.collect(self.processes.iter().map(|p| p.process_id).filter(|id| self.parent_id(*id).is_none()))
+    }
+
+    pub fn children(&self, process_id: u32) -> Vec<&ProcessInfo> {
+        self.collect(self.relationships.iter().filter(|r| r.parent_id == process_id).map(|r| r.child_id))
+    }
+
+    /// Other children of the same parent; other roots for a root
+    pub fn siblings(&self, process_id: u32) -> Vec<&ProcessInfo> {
+        if self.get(process_id).is_none() {
+            return Vec::new();
+        }
+        let mut siblings = match self.parent_id(process_id) {
+            Some(parent) => self.children(parent),
+            None => self.roots(),
+        };
+        siblings.retain(|p| p.process_id != process_id);
+        siblings
+    }
+
+    /// Every process below `process_id`, breadth first
+    pub fn descendants(&self, process_id: u32) -> Vec<&ProcessInfo> {
+        let children = self.child_ids();
+        let mut seen = HashSet::from([process_id]);
+        let mut queue = VecDeque::from([process_id]);
+        let mut found = Vec::new();
+        while let Some(id) = queue.pop_front() {
+            for child in children.get(&id).into_iter().flatten() {
+                if seen.insert(*child) {
+                    found.extend(self.get(*child));
+                    queue.push_back(*child);
+                }
+            }
+        }
+        found
+    }
+
+    /// Parent, grandparent and so on up to the root
+    pub fn ancestors(&self, process_id: u32) -> Vec<&ProcessInfo> {
+        let mut seen = HashSet::from([process_id]);
+        let mut found = Vec::new();
+        let mut current = process_id;
+        while let Some(parent) = self.parent_id(current) {
+            if !seen.insert(parent) {
+                break;
+            }
+            found.extend(self.get(parent));
+            current = parent;
+        }
+        found
+    }
+
+    /// Descendants of every process whose image name is in `image_names`
+    pub fn spawned_by(&self, image_names: &[&str]) -> Vec<&ProcessInfo> {
+        let mut ids = BTreeSet::new();
+        for parent in self.processes.iter().filter(|p| image_names.iter().any(|name| name.eq_ignore_ascii_case(&image_name(p)))) {
+            ids.extend(self.descendants(parent.process_id).iter().map(|p| p.process_id));
+        }
+        self.collect(ids)
+    }
+
+    pub fn spawned_by_office_apps(&self) -> Vec<&ProcessInfo> {
+        self.spawned_by(OFFICE_APPS)
+    }
+
+    pub fn query(&self, query: &ProcessTreeQuery) -> Vec<&ProcessInfo> {
+        match query {
+            ProcessTreeQuery::Children { process_id } => self.children(*process_id),
+            ProcessTreeQuery::Siblings { process_id } => self.siblings(*process_id),
+            ProcessTreeQuery::Descendants { process_id } => self.descendants(*process_id),
+            ProcessTreeQuery::Ancestors { process_id } => self.ancestors(*process_id),
+            ProcessTreeQuery::SpawnedBy { image_names } => {
+                self.spawned_by(&image_names.iter().map(String::as_str).collect::<Vec<_>>())
+            }
+            ProcessTreeQuery::SpawnedByOfficeApps => self.spawned_by_office_apps(),
+        }
+    }
+
+    /// Spawn chains matching `patterns`, each ending at the process that
+    /// completed it
+    pub fn suspicious_chains(&self, patterns: &[ChainPattern]) -> Vec<SuspiciousChain> {
+        let mut chains = Vec::new();
+        for process in &self.processes {
+            let lineage: Vec<&ProcessInfo> = std::iter::once(process).chain(self.ancestors(process.process_id)).collect();
+            for pattern in patterns {
+                if pattern.steps.is_empty() || lineage.len() < pattern.steps.len() {
+                    continue;
+                }
+                let mut path: Vec<&ProcessInfo> = lineage[..pattern.steps.len()].to_vec();
+                path.reverse();
+                let matched = path.iter().zip(&pattern.steps).all(|(p, step)| {
+                    let name = image_name(p);
+                    step.iter().any(|allowed| allowed.eq_ignore_ascii_case(&name))
+                });
+                if matched {
+                    chains.push(SuspiciousChain {
+                        pattern: pattern.name.clone(),
+                        technique_id: pattern.technique_id.clone(),
+                        score: pattern.score,
+                        process_ids: path.iter().map(|p| p.process_id).collect(),
+                        process_names: path.iter().map(|p| image_name(p)).collect(),
+                    });
+                }
+            }
+        }
+        chains.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.process_ids.cmp(&b.process_ids)));
+        chains
+    }
+
+    /// Length of the longest root-to-leaf path
+    pub fn depth(&self) -> usize {
+        let children = self.child_ids();
+        self.roots().iter().map(|root| self.node_depth(root.process_id, &children, &mut HashSet::new())).max().unwrap_or(0)
+    }
+
+    fn node_depth(&self, process_id: u32, children: &HashMap<u32, Vec<u32>>, seen: &mut HashSet<u32>) -> usize {
+        if !seen.insert(process_id) {
+            return 0;
+        }
+        1 + children.get(&process_id).into_iter().flatten().map(|child| self.node_depth(*child, children, seen)).max().unwrap_or(0)
+    }
+
+    /// Nested nodes for front-end rendering, flagging processes in a
+    /// suspicious chain
+    pub fn render(&self, chains: &[SuspiciousChain]) -> ProcessTreeView {
+        let mut flags: HashMap<u32, BTreeSet<String>> = HashMap::new();
+        for chain in chains {
+            for pid in &chain.process_ids {
+                flags.entry(*pid).or_default().insert(chain.pattern.clone());
+            }
+        }
+        let children = self.child_ids();
+        let mut seen = HashSet::new();
+        let roots = self.roots().into_iter().map(|root| self.render_node(root, 0, &children, &flags, &mut seen)).collect();
+        ProcessTreeView {
+            process_count: self.len(),
+            depth: self.depth(),
+            roots,
+            suspicious_chains: chains.to_vec(),
+        }
+    }
+
+    fn render_node(
+        &self,
+        process: &ProcessInfo,
+        depth: usize,
+        children: &HashMap<u32, Vec<u32>>,
+        flags: &HashMap<u32, BTreeSet<String>>,
+        seen: &mut HashSet<u32>,
+    ) -> ProcessTreeNode {
+        seen.insert(process.process_id);
+        let chain_flags: Vec<String> = flags.get(&process.process_id).map(|f| f.iter().cloned().collect()).unwrap_or_default();
+        let child_ids: Vec<u32> = children.get(&process.process_id).cloned().unwrap_or_default();
+        let mut rendered_children = Vec::new();
+        for child in self.collect(child_ids) {
+            if !seen.contains(&child.process_id) {
+                rendered_children.push(self.render_node(child, depth + 1, children, flags, seen));
+            }
+        }
+        ProcessTreeNode {
+            id: process.process_id,
+            parent_id: process.parent_process_id,
+            label: image_name(process),
+            command_line: process.command_line.clone(),
+            executable_path: process.executable_path.clone(),
+            user_account: process.user_account.clone(),
+            integrity_level: process.integrity_level.clone(),
+            started_at: process.creation_time,
+            ended_at: process.termination_time,
+            depth,
+            suspicious: process.is_suspicious || !chain_flags.is_empty(),
+            chain_flags,
+            children: rendered_children,
+        }
+    }
+}
+
+fn spawned(parent_id: u32, child_id: u32) -> ProcessRelationship {
+    ProcessRelationship { parent_id, child_id, relationship_type: "spawned".to_string(), confidence: 1.0 }
+}
+
+/// One process of the rendered tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessTreeNode {
+    pub id: u32,
+    pub parent_id: u32,
+    pub label: String,
+    pub command_line: String,
+    pub executable_path: String,
+    pub user_account: String,
+    pub integrity_level: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub depth: usize,
+    pub suspicious: bool,
+    /// Names of the suspicious chains the process is part of
+    pub chain_flags: Vec<String>,
+    pub children: Vec<ProcessTreeNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessTreeView {
+    pub process_count: usize,
+    pub depth: usize,
+    pub roots: Vec<ProcessTreeNode>,
+    pub suspicious_chains: Vec<SuspiciousChain>,
+}
+
+/// Parent-child anomalies for the chains found in `tree`, one per chain end
+pub fn chain_anomalies(tree: &ProcessTree, chains: &[SuspiciousChain]) -> Vec<ProcessAnomaly> {
+    let mut strongest: BTreeMap<u32, &SuspiciousChain> = BTreeMap::new();
+    for chain in chains {
+        let Some(&end) = chain.process_ids.last() else { continue };
+        let entry = strongest.entry(end).or_insert(chain);
+        if chain.score > entry.score {
+            *entry = chain;
+        }
+    }
+    strongest
+        .into_iter()
+        .filter_map(|(pid, chain)| {
+            let process = tree.get(pid)?;
+            Some(ProcessAnomaly {
+                anomaly_type: chain.pattern.clone(),
+                process_id: pid,
+                parent_process_id: process.parent_process_id,
+                description: format!("Suspicious spawn chain {}", chain.process_names.join(" → ")),
+                anomaly_score: chain.score,
+                timestamp: process.creation_time,
+            })
+        })
+        .collect()
+}
+
+impl SandboxCore {
+    async fn process_tree(&self, tenant_id: &str, sample_id: &str) -> Result<ProcessTree, String> {
+        self.get_analysis(tenant_id, sample_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .map(|analysis| analysis.process_analysis.process_tree)
+            .ok_or_else(|| format!("No completed analysis for sample {}", sample_id))
+    }
+
+    /// Process tree of a completed analysis, rendered for display
+    pub async fn process_tree_view(&self, tenant_id: &str, sample_id: &str) -> Result<ProcessTreeView, String> {
+        let tree = self.process_tree(tenant_id, sample_id).await?;
+        Ok(tree.render(&tree.suspicious_chains(&default_chain_patterns())))
+    }
+
+    pub async fn query_process_tree(&self, tenant_id: &str, sample_id: &str, query: &ProcessTreeQuery) -> Result<Vec<ProcessInfo>, String> {
+        let tree = self.process_tree(tenant_id, sample_id).await?;
+        if let ProcessTreeQuery::Children { process_id }
+        | ProcessTreeQuery::Siblings { process_id }
+        | ProcessTreeQuery::Descendants { process_id }
+        | ProcessTreeQuery::Ancestors { process_id } = query
+        {
+            tree.get(*process_id).ok_or_else(|| format!("Process {} not found in analysis {}", process_id, sample_id))?;
+        }
+        Ok(tree.query(query).into_iter().cloned().collect())
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// Process tree of a completed analysis as nested nodes, with the
+    /// suspicious spawn chains found in it
+    #[napi]
+    pub async fn get_process_tree(&self, sample_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let view = self.inner.process_tree_view(&tenant_id, &sample_id).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to get process tree: {}", e)))?;
+
+        serde_json::to_string(&view)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize process tree: {}", e)))
+    }
+
+    /// Processes matching a lineage query, e.g.
+    /// `{"kind":"descendants","process_id":1234}`
+    #[napi]
+    pub async fn query_process_tree(&self, sample_id: String, query_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let query: ProcessTreeQuery = serde_json::from_str(&query_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse process tree query: {}", e)))?;
+        let processes = self.inner.query_process_tree(&tenant_id, &sample_id, &query).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to query process tree: {}", e)))?;
+
+        serde_json::to_string(&processes)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize processes: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: u32, ppid: u32, name: &str, minute: i64) -> ProcessInfo {
+        ProcessInfo {
+            process_id: pid,
+            process_name: name.to_string(),
+            executable_path: format!("C:\\Windows\\System32\\{}", name),
+            command_line: name.to_string(),
+            parent_process_id: ppid,
+            creation_time: DateTime::<Utc>::from_timestamp(1_700_000_000 + minute * 60, 0).unwrap(),
+            termination_time: None,
+            user_account: "analyst".to_string(),
+            integrity_level: "Medium".to_string(),
+            is_suspicious: false,
+        }
+    }
+
+    fn macro_tree() -> ProcessTree {
+        // explorer → winword → cmd → powershell → rundll32, with children
+        // inserted before their parents
+        ProcessTree::from_processes([
+            process(40, 30, "powershell.exe", 3),
+            process(10, 4, "explorer.exe", 0),
+            process(20, 10, "WINWORD.EXE", 1),
+            process(30, 20, "cmd.exe", 2),
+            process(50, 40, "rundll32.exe", 4),
+            process(60, 10, "notepad.exe", 5),
+        ])
+    }
+
+    #[test]
+    fn lineage_queries_cover_arbitrary_depth() {
+        let tree = macro_tree();
+        let ids = |found: Vec<&ProcessInfo>| found.iter().map(|p| p.process_id).collect::<Vec<_>>();
+
+        assert_eq!(ids(tree.roots()), vec![10]);
+        assert_eq!(ids(tree.descendants(20)), vec![30, 40, 50]);
+        assert_eq!(ids(tree.ancestors(50)), vec![40, 30, 20, 10]);
+        assert_eq!(ids(tree.siblings(20)), vec![60]);
+        assert_eq!(ids(tree.spawned_by_office_apps()), vec![30, 40, 50]);
+        assert_eq!(tree.depth(), 5);
+
+        let view = tree.render(&[]);
+        assert_eq!(view.roots.len(), 1);
+        assert_eq!(view.roots[0].children.iter().map(|n| n.id).collect::<Vec<_>>(), vec![20, 60]);
+        assert_eq!(view.roots[0].children[0].children[0].children[0].depth, 3);
+    }
+
+    #[test]
+    fn office_spawn_chains_are_flagged() {
+        let tree = macro_tree();
+        let chains = tree.suspicious_chains(&default_chain_patterns());
+        assert_eq!(chains[0].pattern, "office_shell_powershell");
+        assert_eq!(chains[0].process_names, vec!["winword.exe", "cmd.exe", "powershell.exe"]);
+        assert!(chains.iter().any(|c| c.pattern == "office_shell" && c.process_ids == vec![20, 30]));
+        assert!(!chains.iter().any(|c| c.process_ids.contains(&60)));
+
+        let anomalies = chain_anomalies(&tree, &chains);
+        assert_eq!(anomalies.iter().map(|a| a.process_id).collect::<Vec<_>>(), vec![30, 40]);
+
+        let view = tree.render(&chains);
+        let word = &view.roots[0].children[0];
+        assert!(word.suspicious && !view.roots[0].children[1].suspicious);
+        assert_eq!(word.chain_flags, vec!["office_shell", "office_shell_powershell"]);
+    }
+
+    #[test]
+    fn flat_trees_from_older_analyses_still_load() {
+        let root = process(1234, 1000, "sample.exe", 0);
+        let child = process(1300, 1234, "cmd.exe", 1);
+        let stored = serde_json::json!({ "root_process": root, "child_processes": [child], "relationships": [] });
+        let tree: ProcessTree = serde_json::from_value(stored).unwrap();
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree.children(1234)[0].process_id, 1300);
+
+        let round_trip: ProcessTree = serde_json::from_str(&serde_json::to_string(&tree).unwrap()).unwrap();
+        assert_eq!(round_trip.relationships.len(), 1);
+    }
+}
@@ -0,0 +1,149 @@
+//! Prometheus Metrics
+//!
+//! With `phantom-enterprise-standards` enabled, job counts, completed
+//! analyses and analysis durations are exposed in the Prometheus text format,
+//! either through `render_prometheus_metrics` or an embedded `/metrics`
+//! endpoint. Samples carry `core="sandbox"`, the `tenant` and, for analyses,
+//! the VM environment as `engine`. Without the feature nothing is collected.
+
+use crate::SandboxAnalysis;
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::{SandboxCore, SandboxCoreNapi};
+#[cfg(feature = "phantom-enterprise-standards")]
+use napi_derive::napi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::prometheus::{labels, serve_metrics, CounterSet, HistogramSet, PrometheusWriter, RenderFn};
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::collections::BTreeMap;
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+pub struct PrometheusState {
+    #[cfg(feature = "phantom-enterprise-standards")]
+    analyses: CounterSet,
+    #[cfg(feature = "phantom-enterprise-standards")]
+    durations: HistogramSet,
+    #[cfg(feature = "phantom-enterprise-standards")]
+    endpoint: Mutex<Option<tokio::task::JoinHandle<()>>>,
+}
+
+impl PrometheusState {
+    pub(crate) fn observe_analysis(&self, analysis: &SandboxAnalysis) {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            let tenant = analysis.tenant_id.as_str();
+            let engine = analysis.analysis_metadata.vm_environment.as_str();
+            let verdict = format!("{:?}", analysis.verdict).to_lowercase();
+            self.analyses.inc(labels(&[("tenant", tenant), ("engine", engine), ("verdict", &verdict)]), 1.0);
+            self.durations.observe(labels(&[("tenant", tenant), ("engine", engine)]), analysis.analysis_metadata.analysis_duration as f64);
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        let _ = analysis;
+    }
+
+    pub(crate) fn forget_tenant(&self, tenant_id: &str) -> usize {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            self.analyses.forget("tenant", tenant_id) + self.durations.forget("tenant", tenant_id)
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        {
+            let _ = tenant_id;
+            0
+        }
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+impl SandboxCore {
+    /// All tenants' sandbox metrics in the Prometheus text format
+    pub async fn render_prometheus_metrics(&self) -> String {
+        let mut writer = PrometheusWriter::for_core("sandbox");
+
+        let mut jobs: BTreeMap<(String, String), usize> = BTreeMap::new();
+        for job in self.analysis_queue.read().await.iter() {
+            *jobs.entry((job.tenant_id.clone(), format!("{:?}", job.status).to_lowercase())).or_default() += 1;
+        }
+        for ((tenant, status), count) in &jobs {
+            writer.gauge("phantom_sandbox_jobs", "Analysis jobs by status", &[("tenant", tenant), ("status", status)], *count as f64);
+        }
+
+        writer.counter_set("phantom_sandbox_analyses_total", "Completed analyses by verdict", &self.prometheus.analyses);
+        writer.histogram_set("phantom_sandbox_analysis_duration_seconds", "Analysis duration", &self.prometheus.durations);
+
+        let scheduler = self.scheduler_utilization().await;
+        writer.gauge("phantom_sandbox_vm_utilization", "Share of analysis VMs in use", &[], scheduler.slot_share());
+        for environment in &scheduler.environments {
+            writer.gauge("phantom_sandbox_vm_slots_in_use", "VM slots in use by environment", &[("environment", &environment.environment_id)], f64::from(environment.in_use));
+        }
+        writer.gauge("phantom_sandbox_cpu_cores_reserved", "CPU cores reserved by running analyses", &[], f64::from(scheduler.cpu_cores_reserved));
+        writer.gauge("phantom_sandbox_memory_mb_reserved", "Memory reserved by running analyses", &[], f64::from(scheduler.memory_mb_reserved));
+
+        let performance = self.performance_metrics.read().await;
+        writer.gauge("phantom_sandbox_uptime_hours", "Hours since the metrics were last reset", &[], performance.uptime_hours);
+        writer.finish()
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[napi]
+impl SandboxCoreNapi {
+    /// Platform-wide metrics in the Prometheus text exposition format
+    #[napi]
+    pub async fn render_prometheus_metrics(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize_platform(auth_token, "metrics")?;
+        Ok(self.inner.render_prometheus_metrics().await)
+    }
+
+    /// Serve `GET /metrics` on `bind`, e.g. `0.0.0.0:9464`, replacing any
+    /// running endpoint; returns the bound address. Pass no address to stop
+    #[napi]
+    pub async fn start_metrics_endpoint(&self, bind: Option<String>, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        let actor = self.authorize_platform(auth_token, "metrics")?;
+        if let Some(running) = self.inner.prometheus.endpoint.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            running.abort();
+        }
+        let details = serde_json::json!({ "bind": bind });
+        let Some(bind) = bind else {
+            return self.audit.record(&actor, "start_metrics_endpoint", "metrics", details, Ok(None));
+        };
+        let core = self.inner.clone();
+        let render: RenderFn = Arc::new(move || {
+            let core = core.clone();
+            Box::pin(async move { core.render_prometheus_metrics().await })
+        });
+        let served = serve_metrics(&bind, render).await;
+        let (address, handle) = self.audit.record(&actor, "start_metrics_endpoint", "metrics", details, served)
+            .map_err(napi::Error::from_reason)?;
+        *self.inner.prometheus.endpoint.lock().unwrap_or_else(|e| e.into_inner()) = Some(handle);
+        Ok(Some(address.to_string()))
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use super::*;
+    use crate::AnalysisPriority;
+
+    #[tokio::test]
+    async fn analyses_are_exposed_with_core_tenant_and_engine_labels() {
+        let core = SandboxCore::new().unwrap();
+        for name in ["a.exe", "b.exe"] {
+            core.submit_sample("acme", name.as_bytes(), name.to_string(), AnalysisPriority::Normal, vec![], false).await.unwrap();
+        }
+        core.process_queue().await.unwrap();
+
+        let text = core.render_prometheus_metrics().await;
+        assert!(text.contains("# TYPE phantom_sandbox_analyses_total counter\n"));
+        assert!(text.contains("phantom_sandbox_jobs{core=\"sandbox\",tenant=\"acme\",status=\"completed\"} 2\n"));
+        assert!(text.contains("phantom_sandbox_vm_slots_in_use{core=\"sandbox\",environment=\"win10-x64\"} 0\n"), "{}", text);
+        let engine = core.get_queue_status("acme").await.unwrap()[0].vm_environment.clone();
+        assert!(text.contains(&format!("phantom_sandbox_analysis_duration_seconds_count{{core=\"sandbox\",tenant=\"acme\",engine=\"{}\"}} 2\n", engine)), "{}", text);
+
+        core.purge_tenant("acme").await.unwrap();
+        let text = core.render_prometheus_metrics().await;
+        assert!(!text.contains("tenant=\"acme\""));
+    }
+}
@@ -0,0 +1,413 @@
+//! Analysis job queue
+//!
+//! The queue holds every job the core tracks, in submission order, and keeps
+//! the waiting ones in one binary heap per priority, oldest submission on
+//! top. Taking the next job compares only the five heap heads, so pushing,
+//! popping and reprioritizing are O(log n).
+//!
+//! Waiting jobs age so a steady stream of urgent work cannot starve the
+//! rest: every `promote_after_secs` a queued job has waited, it competes one
+//! priority higher, up to Critical. Aging never makes a job Emergency, so
+//! only jobs submitted as Emergency preempt running analyses. Among jobs
+//! competing at the same priority the one waiting longest goes first.
+//!
+//! Heap entries are dropped lazily: a job that leaves the Queued state
+//! through `iter_mut` or `get_mut` is skipped when its entry reaches the top.
+//! Putting a job back in the queue goes through `requeue` or `restore`, and
+//! changing its priority through `reprioritize`.
+
+use chrono::{DateTime, Utc};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::{AnalysisJob, AnalysisPriority, JobStatus, SandboxCore, SandboxCoreNapi};
+
+const LEVELS: usize = 5;
+/// Highest rank aging promotes to; Emergency is reserved for submissions
+const AGING_CEILING: u32 = 4;
+
+fn default_promote_after_secs() -> u64 {
+    600
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueueConfig {
+    /// Wait after which a queued job competes one priority higher; 0 turns aging off
+    #[serde(default = "default_promote_after_secs")]
+    pub promote_after_secs: u64,
+}
+
+impl Default for QueueConfig {
+    fn default() -> Self {
+        Self { promote_after_secs: default_promote_after_secs() }
+    }
+}
+
+/// Queued jobs of one priority
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriorityDepth {
+    pub priority: AnalysisPriority,
+    pub depth: usize,
+    /// Jobs currently competing above this priority because of their wait
+    pub promoted: usize,
+    pub oldest_submission: Option<DateTime<Utc>>,
+    pub oldest_wait_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueStats {
+    pub queued: usize,
+    /// Highest priority first
+    pub priorities: Vec<PriorityDepth>,
+    pub promote_after_secs: u64,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Heap entry of a waiting job; the oldest submission sorts highest
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Waiting {
+    since: DateTime<Utc>,
+    seq: u64,
+    job_id: String,
+}
+
+impl Ord for Waiting {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.since.cmp(&self.since).then(other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for Waiting {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn priority_of_level(level: usize) -> AnalysisPriority {
+    match level {
+        0 => AnalysisPriority::Low,
+        1 => AnalysisPriority::Normal,
+        2 => AnalysisPriority::High,
+        3 => AnalysisPriority::Critical,
+        _ => AnalysisPriority::Emergency,
+    }
+}
+
+fn level_of(priority: &AnalysisPriority) -> usize {
+    priority.rank() as usize - 1
+}
+
+#[derive(Default)]
+pub struct AnalysisQueue {
+    jobs: Vec<AnalysisJob>,
+    positions: HashMap<String, usize>,
+    levels: [BinaryHeap<Waiting>; LEVELS],
+    /// Sequence number of each waiting job's live heap entry
+    waiting: HashMap<String, u64>,
+    next_seq: u64,
+    config: QueueConfig,
+}
+
+impl AnalysisQueue {
+    pub fn from_jobs(jobs: Vec<AnalysisJob>) -> Self {
+        let mut queue = Self::default();
+        for job in jobs {
+            queue.push(job);
+        }
+        queue
+    }
+
+    pub fn config(&self) -> &QueueConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: QueueConfig) {
+        self.config = config;
+    }
+
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// Every job, in submission order
+    pub fn iter(&self) -> std::slice::Iter<'_, AnalysisJob> {
+        self.jobs.iter()
+    }
+
+    /// Jobs may leave the Queued state here; entering it goes through
+    /// `requeue` and priority changes through `reprioritize`
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, AnalysisJob> {
+        self.jobs.iter_mut()
+    }
+
+    pub fn get(&self, job_id: &str) -> Option<&AnalysisJob> {
+        self.positions.get(job_id).map(|&index| &self.jobs[index])
+    }
+
+    pub fn get_mut(&mut self, job_id: &str) -> Option<&mut AnalysisJob> {
+        self.positions.get(job_id).map(|&index| &mut self.jobs[index])
+    }
+
+    /// Track a new job, waiting if it is Queued
+    pub fn push(&mut self, job: AnalysisJob) {
+        let job_id = job.job_id.clone();
+        let queued = matches!(job.status, JobStatus::Queued);
+        match self.positions.get(&job_id) {
+            Some(&index) => self.jobs[index] = job,
+            None => {
+                self.positions.insert(job_id.clone(), self.jobs.len());
+                self.jobs.push(job);
+            }
+        }
+        if queued {
+            self.enqueue(&job_id);
+        }
+    }
+
+    fn enqueue(&mut self, job_id: &str) {
+        let Some(job) = self.get(job_id) else { return };
+        let entry = Waiting { since: job.submission_time, seq: self.next_seq, job_id: job_id.to_string() };
+        let level = level_of(&job.priority);
+        self.next_seq += 1;
+        self.waiting.insert(entry.job_id.clone(), entry.seq);
+        self.levels[level].push(entry);
+    }
+
+    fn is_live(&self, entry: &Waiting) -> bool {
+        self.waiting.get(&entry.job_id) == Some(&entry.seq)
+            && self.get(&entry.job_id).is_some_and(|job| matches!(job.status, JobStatus::Queued))
+    }
+
+    fn discard_stale(&mut self, level: usize) {
+        loop {
+            let live = match self.levels[level].peek() {
+                Some(head) => self.is_live(head),
+                None => return,
+            };
+            if live {
+                return;
+            }
+            if let Some(stale) = self.levels[level].pop() {
+                if self.waiting.get(&stale.job_id) == Some(&stale.seq) {
+                    self.waiting.remove(&stale.job_id);
+                }
+            }
+        }
+    }
+
+    /// Priority a job submitted at `rank` competes at after waiting since `since`
+    fn effective_rank(&self, rank: u32, since: DateTime<Utc>, now: DateTime<Utc>) -> u32 {
+        if rank >= AGING_CEILING || self.config.promote_after_secs == 0 {
+            return rank;
+        }
+        let waited = (now - since).num_seconds().max(0) as u64;
+        let steps = (waited / self.config.promote_after_secs).min(u64::from(AGING_CEILING));
+        (rank + steps as u32).min(AGING_CEILING)
+    }
+
+    /// Take the job to start next. It stays Queued in the table; a job that
+    /// cannot start after all goes back with `restore`.
+    pub fn pop(&mut self, now: DateTime<Utc>) -> Option<String> {
+        for level in 0..LEVELS {
+            self.discard_stale(level);
+        }
+        let level = (0..LEVELS)
+            .filter_map(|level| self.levels[level].peek().map(|head| (level, head.since)))
+            .max_by(|(a, a_since), (b, b_since)| {
+                let a_rank = self.effective_rank(*a as u32 + 1, *a_since, now);
+                let b_rank = self.effective_rank(*b as u32 + 1, *b_since, now);
+                a_rank.cmp(&b_rank).then(b_since.cmp(a_since)).then(a.cmp(b))
+            })?
+            .0;
+        let entry = self.levels[level].pop()?;
+        self.waiting.remove(&entry.job_id);
+        Some(entry.job_id)
+    }
+
+    /// Put a popped job that did not start back where it was
+    pub fn restore(&mut self, job_id: &str) {
+        if self.get(job_id).is_some_and(|job| matches!(job.status, JobStatus::Queued)) {
+            self.enqueue(job_id);
+        }
+    }
+
+    /// Send a started job back to the queue to start over
+    pub fn requeue(&mut self, job_id: &str) -> Option<&AnalysisJob> {
+        let job = self.get_mut(job_id)?;
+        job.status = JobStatus::Queued;
+        job.analysis_start = None;
+        job.progress = 0.0;
+        self.enqueue(job_id);
+        self.get(job_id)
+    }
+
+    pub fn reprioritize(&mut self, job_id: &str, priority: AnalysisPriority) -> Option<&AnalysisJob> {
+        let job = self.get_mut(job_id)?;
+        job.priority = priority;
+        if matches!(job.status, JobStatus::Queued) {
+            // The new entry supersedes the one at the old priority
+            self.enqueue(job_id);
+        }
+        self.get(job_id)
+    }
+
+    /// Remove and return the jobs matching `predicate`
+    pub fn remove_where(&mut self, predicate: impl Fn(&AnalysisJob) -> bool) -> Vec<AnalysisJob> {
+        let (removed, kept): (Vec<AnalysisJob>, Vec<AnalysisJob>) = std::mem::take(&mut self.jobs).into_iter().partition(|job| predicate(job));
+        self.jobs = kept;
+        self.positions = self.jobs.iter().enumerate().map(|(index, job)| (job.job_id.clone(), index)).collect();
+        for job in &removed {
+            self.waiting.remove(&job.job_id);
+        }
+        removed
+    }
+
+    /// Depth, promotions and oldest wait per priority, of one tenant's jobs
+    /// or of all of them
+    pub fn stats(&self, tenant_id: Option<&str>, now: DateTime<Utc>) -> QueueStats {
+        let mut priorities: Vec<PriorityDepth> = (0..LEVELS)
+            .map(|level| PriorityDepth { priority: priority_of_level(level), depth: 0, promoted: 0, oldest_submission: None, oldest_wait_secs: 0 })
+            .collect();
+        let waiting = self
+            .waiting
+            .keys()
+            .filter_map(|job_id| self.get(job_id))
+            .filter(|job| matches!(job.status, JobStatus::Queued) && tenant_id.is_none_or(|tenant| job.tenant_id == tenant));
+        for job in waiting {
+            let rank = job.priority.rank();
+            let depth = &mut priorities[level_of(&job.priority)];
+            depth.depth += 1;
+            if self.effective_rank(rank, job.submission_time, now) > rank {
+                depth.promoted += 1;
+            }
+            if depth.oldest_submission.is_none_or(|oldest| job.submission_time < oldest) {
+                depth.oldest_submission = Some(job.submission_time);
+                depth.oldest_wait_secs = (now - job.submission_time).num_seconds().max(0) as u64;
+            }
+        }
+        priorities.reverse();
+        QueueStats {
+            queued: priorities.iter().map(|depth| depth.depth).sum(),
+            priorities,
+            promote_after_secs: self.config.promote_after_secs,
+            generated_at: now,
+        }
+    }
+}
+
+impl SandboxCore {
+    pub async fn configure_queue(&self, config: QueueConfig) {
+        self.analysis_queue.write().await.set_config(config);
+    }
+
+    pub async fn queue_config(&self) -> QueueConfig {
+        self.analysis_queue.read().await.config().clone()
+    }
+
+    pub async fn queue_stats(&self, tenant_id: &str) -> QueueStats {
+        self.analysis_queue.read().await.stats(Some(tenant_id), Utc::now())
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// Set how long a queued job waits before it competes one priority
+    /// higher; the queue is shared, so this is a platform setting
+    #[napi]
+    pub async fn configure_queue(&self, config_json: String, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize_platform(auth_token, "analysis_queue")?;
+        let config: QueueConfig = serde_json::from_str(&config_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse queue config: {}", e)))?;
+
+        self.inner.configure_queue(config).await;
+        self.audit.record(&actor, "configure_queue", "analysis_queue", serde_json::json!({ "config": config_json }), Ok::<_, String>(()))
+            .map_err(napi::Error::from_reason)
+    }
+
+    #[napi]
+    pub async fn get_queue_config(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.queue_config().await)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize queue config: {}", e)))
+    }
+
+    /// Queued jobs of the caller's tenant per priority, with their oldest wait
+    #[napi]
+    pub async fn get_queue_stats(&self, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        serde_json::to_string(&self.inner.queue_stats(&tenant_id).await)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize queue stats: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AnalysisConfiguration;
+
+    fn job(job_id: &str, priority: AnalysisPriority, minutes_ago: i64) -> AnalysisJob {
+        AnalysisJob {
+            job_id: job_id.to_string(),
+            sample_id: format!("sample-{}", job_id),
+            tenant_id: "acme".to_string(),
+            priority,
+            submission_time: Utc::now() - chrono::Duration::minutes(minutes_ago),
+            analysis_start: None,
+            analysis_end: None,
+            status: JobStatus::Queued,
+            vm_environment: "win10-x64".to_string(),
+            analysis_config: AnalysisConfiguration {
+                analysis_time: 300,
+                vm_environment: "win10-x64".to_string(),
+                analysis_engines: Vec::new(),
+                network_simulation: false,
+                deep_analysis: false,
+                yara_scanning: false,
+                memory_dumping: false,
+                network_capture: false,
+                network_persona: None,
+                target_url: None,
+                interactive_session: None,
+            },
+            progress: 0.0,
+            error_message: None,
+        }
+    }
+
+    #[test]
+    fn aged_jobs_overtake_fresh_higher_priority_work() {
+        let mut queue = AnalysisQueue::default();
+        queue.set_config(QueueConfig { promote_after_secs: 600 });
+        queue.push(job("low-old", AnalysisPriority::Low, 25));
+        queue.push(job("high-new", AnalysisPriority::High, 1));
+        queue.push(job("normal", AnalysisPriority::Normal, 2));
+        queue.push(job("emergency", AnalysisPriority::Emergency, 0));
+        queue.push(job("cancelled", AnalysisPriority::Critical, 5));
+        queue.get_mut("cancelled").unwrap().status = JobStatus::Cancelled;
+
+        let stats = queue.stats(Some("acme"), Utc::now());
+        assert_eq!(stats.queued, 4);
+        let low = stats.priorities.iter().find(|depth| matches!(depth.priority, AnalysisPriority::Low)).unwrap();
+        assert_eq!((low.depth, low.promoted), (1, 1));
+        assert!(low.oldest_wait_secs >= 25 * 60);
+
+        // Low has waited two promotion periods, so it competes as High and is older
+        let now = Utc::now();
+        assert_eq!(queue.pop(now).as_deref(), Some("emergency"));
+        assert_eq!(queue.pop(now).as_deref(), Some("low-old"));
+        let next = queue.pop(now).unwrap();
+        assert_eq!(next, "high-new");
+        queue.restore(&next);
+        queue.reprioritize("normal", AnalysisPriority::Critical);
+        assert_eq!(queue.pop(now).as_deref(), Some("normal"));
+        assert_eq!(queue.pop(now).as_deref(), Some("high-new"));
+        assert_eq!(queue.pop(now), None);
+        assert_eq!(queue.remove_where(|job| job.job_id == "cancelled").len(), 1);
+        assert_eq!(queue.len(), 4);
+    }
+}
@@ -0,0 +1,374 @@
+//! HTTP capture limits and redaction
+//!
+//! HTTP requests captured during detonation can carry credentials or
+//! multi-megabyte uploads. Before a completed analysis is notified, stored
+//! or indexed, its captured requests are cut to the capture limits and run
+//! through the redaction rules: sensitive headers are masked outright and
+//! regex rules mask bearer tokens, API keys and card numbers (PANs,
+//! confirmed with a Luhn check) in URLs, header values and bodies. Every
+//! analysis carries a capture audit counting what was truncated and how
+//! often each rule matched; the matched values themselves are never kept.
+
+use chrono::{DateTime, Utc};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{HTTPRequest, NetworkAnalysis, SandboxCore};
+
+/// Value written in place of a redacted header
+pub const REDACTED_HEADER: &str = "[REDACTED]";
+
+/// Bytes beyond the body limit still scanned, so a secret straddling the
+/// cut is redacted rather than left half visible
+const REDACTION_LOOKAHEAD: usize = 512;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionRule {
+    pub name: String,
+    /// Regex to mask; with a `secret` named group only that group is
+    /// replaced, so keys stay readable (`api_key=[REDACTED:api_key]`)
+    pub pattern: String,
+    /// Only mask digit runs that pass the Luhn check (card numbers)
+    #[serde(default)]
+    pub luhn_check: bool,
+}
+
+impl RedactionRule {
+    fn new(name: &str, pattern: &str) -> Self {
+        Self { name: name.to_string(), pattern: pattern.to_string(), luhn_check: false }
+    }
+}
+
+fn default_rules() -> Vec<RedactionRule> {
+    vec![
+        RedactionRule::new("auth_token", r"(?i)\b(?:bearer|basic|token)\s+(?P<secret>[A-Za-z0-9\-._~+/]{8,}=*)"),
+        RedactionRule::new(
+            "api_key",
+            r#"(?i)\b(?:api[_-]?key|apikey|access[_-]?token|auth[_-]?token|client[_-]?secret|secret|password|passwd|pwd)\b["']?\s*[:=]\s*["']?(?P<secret>[^"'&\s,;}]+)"#,
+        ),
+        RedactionRule::new("aws_access_key", r"\b(?:AKIA|ASIA)[0-9A-Z]{16}\b"),
+        RedactionRule::new("jwt", r"\beyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+"),
+        RedactionRule { luhn_check: true, ..RedactionRule::new("pan", r"\b\d(?:[ -]?\d){12,18}\b") },
+    ]
+}
+
+fn default_redacted_headers() -> Vec<String> {
+    ["authorization", "proxy-authorization", "cookie", "set-cookie", "x-api-key", "x-auth-token"]
+        .iter()
+        .map(|header| header.to_string())
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpCapturePolicy {
+    /// Bytes of each request body kept
+    #[serde(default = "default_max_body_bytes")]
+    pub max_body_bytes: usize,
+    /// Bytes of each header value kept
+    #[serde(default = "default_max_header_bytes")]
+    pub max_header_bytes: usize,
+    /// Requests kept per analysis; later ones are dropped
+    #[serde(default = "default_max_requests")]
+    pub max_requests: usize,
+    /// Headers whose values are masked entirely (case-insensitive)
+    #[serde(default = "default_redacted_headers")]
+    pub redacted_headers: Vec<String>,
+    #[serde(default = "default_rules")]
+    pub rules: Vec<RedactionRule>,
+}
+
+fn default_max_body_bytes() -> usize {
+    64 * 1024
+}
+
+fn default_max_header_bytes() -> usize {
+    4096
+}
+
+fn default_max_requests() -> usize {
+    1000
+}
+
+impl Default for HttpCapturePolicy {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: default_max_body_bytes(),
+            max_header_bytes: default_max_header_bytes(),
+            max_requests: default_max_requests(),
+            redacted_headers: default_redacted_headers(),
+            rules: default_rules(),
+        }
+    }
+}
+
+/// What capture limits and redaction removed from one analysis
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HttpCaptureAudit {
+    pub requests_kept: usize,
+    pub requests_dropped: usize,
+    pub bodies_truncated: usize,
+    pub bytes_truncated: u64,
+    pub headers_redacted: usize,
+    /// Matches masked per rule name
+    pub redactions: BTreeMap<String, usize>,
+    pub applied_at: Option<DateTime<Utc>>,
+}
+
+impl HttpCaptureAudit {
+    pub fn total_redactions(&self) -> usize {
+        self.headers_redacted + self.redactions.values().sum::<usize>()
+    }
+}
+
+/// Capture policy with its rules compiled
+#[derive(Debug)]
+pub struct HttpCaptureState {
+    policy: HttpCapturePolicy,
+    rules: Vec<(RedactionRule, Regex)>,
+}
+
+impl Default for HttpCaptureState {
+    fn default() -> Self {
+        Self::new(HttpCapturePolicy::default()).expect("default redaction rules compile")
+    }
+}
+
+impl HttpCaptureState {
+    pub fn new(policy: HttpCapturePolicy) -> Result<Self, String> {
+        let rules = policy
+            .rules
+            .iter()
+            .map(|rule| {
+                Regex::new(&rule.pattern)
+                    .map(|regex| (rule.clone(), regex))
+                    .map_err(|e| format!("Invalid redaction rule {}: {}", rule.name, e))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { policy, rules })
+    }
+
+    pub fn policy(&self) -> &HttpCapturePolicy {
+        &self.policy
+    }
+
+    /// Apply the capture limits and redaction rules to every captured request
+    pub fn apply(&self, network: &mut NetworkAnalysis) -> HttpCaptureAudit {
+        let mut audit = HttpCaptureAudit::default();
+        if network.http_requests.len() > self.policy.max_requests {
+            audit.requests_dropped = network.http_requests.len() - self.policy.max_requests;
+            network.http_requests.truncate(self.policy.max_requests);
+        }
+        for request in &mut network.http_requests {
+            self.apply_request(request, &mut audit);
+        }
+        audit.requests_kept = network.http_requests.len();
+        audit.applied_at = Some(Utc::now());
+        audit
+    }
+
+    fn apply_request(&self, request: &mut HTTPRequest, audit: &mut HttpCaptureAudit) {
+        request.url = self.redact(&request.url, audit);
+        for (name, value) in request.headers.iter_mut() {
+            if self.policy.redacted_headers.iter().any(|header| header.eq_ignore_ascii_case(name)) {
+                if value != REDACTED_HEADER {
+                    *value = REDACTED_HEADER.to_string();
+                    audit.headers_redacted += 1;
+                }
+            } else {
+                *value = self.capture(value, self.policy.max_header_bytes, audit).0;
+            }
+        }
+        request.user_agent = self.capture(&request.user_agent, self.policy.max_header_bytes, audit).0;
+        if let Some(body) = request.body.take() {
+            let (body, truncated) = self.capture(&body, self.policy.max_body_bytes, audit);
+            if truncated {
+                request.body_truncated = true;
+                audit.bodies_truncated += 1;
+            }
+            request.body = Some(body);
+        }
+    }
+
+    /// Redact `text` and cut it to `limit` bytes; true when it was cut
+    fn capture(&self, text: &str, limit: usize, audit: &mut HttpCaptureAudit) -> (String, bool) {
+        let scanned = truncate_utf8(text, limit.saturating_add(REDACTION_LOOKAHEAD));
+        let redacted = self.redact(scanned, audit);
+        if text.len() <= limit && redacted.len() <= limit {
+            return (redacted, false);
+        }
+        let kept = truncate_utf8(&redacted, limit);
+        audit.bytes_truncated += text.len().saturating_sub(kept.len()) as u64;
+        (kept.to_string(), true)
+    }
+
+    fn redact(&self, text: &str, audit: &mut HttpCaptureAudit) -> String {
+        let mut text = text.to_string();
+        for (rule, regex) in &self.rules {
+            let mut matched = 0;
+            let replaced = regex.replace_all(&text, |caps: &Captures| {
+                let whole = caps.get(0).expect("match has group 0");
+                if rule.luhn_check && !luhn_valid(whole.as_str()) {
+                    return whole.as_str().to_string();
+                }
+                matched += 1;
+                let mask = format!("[REDACTED:{}]", rule.name);
+                match caps.name("secret") {
+                    Some(secret) => {
+                        let start = secret.start() - whole.start();
+                        let end = secret.end() - whole.start();
+                        format!("{}{}{}", &whole.as_str()[..start], mask, &whole.as_str()[end..])
+                    }
+                    None => mask,
+                }
+            });
+            if matched > 0 {
+                text = replaced.into_owned();
+                *audit.redactions.entry(rule.name.clone()).or_default() += matched;
+            }
+        }
+        text
+    }
+}
+
+/// Longest prefix of `text` of at most `limit` bytes ending on a char boundary
+fn truncate_utf8(text: &str, limit: usize) -> &str {
+    if text.len() <= limit {
+        return text;
+    }
+    let mut end = limit;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+fn luhn_valid(candidate: &str) -> bool {
+    let digits: Vec<u32> = candidate.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &digit)| match (i % 2, digit * 2) {
+            (1, doubled) if doubled > 9 => doubled - 9,
+            (1, doubled) => doubled,
+            _ => digit,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+impl SandboxCore {
+    pub async fn http_capture_policy(&self) -> HttpCapturePolicy {
+        self.http_capture.read().await.policy().clone()
+    }
+
+    /// Replace the capture policy; applies to analyses completed from now on
+    pub async fn set_http_capture_policy(&self, policy: HttpCapturePolicy) -> Result<(), String> {
+        *self.http_capture.write().await = HttpCaptureState::new(policy)?;
+        Ok(())
+    }
+
+    /// Limit and redact the captured HTTP traffic of a completed analysis
+    pub(crate) async fn sanitize_http_capture(&self, network: &mut NetworkAnalysis) {
+        let audit = self.http_capture.read().await.apply(network);
+        if audit.total_redactions() > 0 || audit.bodies_truncated > 0 || audit.requests_dropped > 0 {
+            log::debug!(
+                "HTTP capture: {} redactions, {} bodies truncated, {} requests dropped",
+                audit.total_redactions(),
+                audit.bodies_truncated,
+                audit.requests_dropped
+            );
+        }
+        network.capture_audit = Some(audit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AnalysisPriority;
+    use std::collections::HashMap;
+
+    fn request(headers: &[(&str, &str)], body: &str) -> HTTPRequest {
+        HTTPRequest {
+            request_id: "req-1".to_string(),
+            method: "POST".to_string(),
+            url: "https://c2.example/upload?apikey=0123456789abcdef&id=7".to_string(),
+            headers: headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<HashMap<_, _>>(),
+            body: Some(body.to_string()),
+            body_truncated: false,
+            response_code: 200,
+            response_size: 0,
+            timestamp: Utc::now(),
+            process_name: "sample.exe".to_string(),
+            process_id: 1,
+            user_agent: "curl/8.0".to_string(),
+            is_suspicious: true,
+        }
+    }
+
+    #[test]
+    fn secrets_are_masked_and_counted() {
+        let state = HttpCaptureState::default();
+        let mut network = NetworkAnalysis { http_requests: vec![request(
+            &[("Authorization", "Bearer abcdefghijklmnop"), ("X-Trace", "token 1234567890abcdef")],
+            r#"{"password":"hunter22","card":"4111 1111 1111 1111","order":"1234567890123","note":"ok"}"#,
+        )], ..NetworkAnalysis::default() };
+
+        let audit = state.apply(&mut network);
+        let captured = &network.http_requests[0];
+        assert_eq!(captured.headers["Authorization"], REDACTED_HEADER);
+        assert_eq!(captured.headers["X-Trace"], "token [REDACTED:auth_token]");
+        assert_eq!(captured.url, "https://c2.example/upload?apikey=[REDACTED:api_key]&id=7");
+        let body = captured.body.as_deref().unwrap();
+        assert!(body.contains(r#""password":"[REDACTED:api_key]""#), "{}", body);
+        assert!(body.contains(r#""card":"[REDACTED:pan]""#), "{}", body);
+        assert!(body.contains("1234567890123"), "numbers failing the Luhn check stay: {}", body);
+
+        assert_eq!(audit.headers_redacted, 1);
+        assert_eq!(audit.redactions["api_key"], 2);
+        assert_eq!(audit.redactions["pan"], 1);
+        assert_eq!(audit.redactions["auth_token"], 1);
+        let serialized = serde_json::to_string(&audit).unwrap();
+        assert!(!serialized.contains("hunter22"));
+    }
+
+    #[test]
+    fn capture_limits_bound_bodies_and_request_counts() {
+        let policy = HttpCapturePolicy { max_body_bytes: 16, max_requests: 2, ..HttpCapturePolicy::default() };
+        let state = HttpCaptureState::new(policy).unwrap();
+        let body = format!("{}é secret=abcdef", "x".repeat(15));
+        let mut network = NetworkAnalysis {
+            http_requests: (0..3).map(|_| request(&[], &body)).collect(),
+            ..NetworkAnalysis::default()
+        };
+
+        let audit = state.apply(&mut network);
+        assert_eq!(audit.requests_dropped, 1);
+        assert_eq!(audit.bodies_truncated, 2);
+        let captured = &network.http_requests[0];
+        assert!(captured.body_truncated);
+        assert_eq!(captured.body.as_deref(), Some("x".repeat(15).as_str()));
+
+        let invalid = HttpCapturePolicy { rules: vec![RedactionRule::new("broken", "(")], ..HttpCapturePolicy::default() };
+        assert!(HttpCaptureState::new(invalid).unwrap_err().contains("broken"));
+    }
+
+    #[tokio::test]
+    async fn stored_analyses_carry_the_capture_audit() {
+        let core = SandboxCore::new().unwrap();
+        core.set_http_capture_policy(HttpCapturePolicy { max_body_bytes: 8, ..HttpCapturePolicy::default() }).await.unwrap();
+        let sample_id = core.submit_sample("acme", b"MZ beacon", "beacon.exe".to_string(), AnalysisPriority::High, vec![], false).await.unwrap();
+        core.process_queue().await.unwrap();
+
+        let analysis = core.get_analysis("acme", &sample_id).await.unwrap().unwrap();
+        let audit = analysis.network_analysis.capture_audit.unwrap();
+        assert_eq!(audit.requests_kept, analysis.network_analysis.http_requests.len());
+        assert!(analysis.network_analysis.http_requests.iter().all(|r| r.body.as_ref().is_none_or(|b| b.len() <= 8)));
+        assert_eq!(core.http_capture_policy().await.max_body_bytes, 8);
+    }
+}
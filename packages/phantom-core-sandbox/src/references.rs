@@ -0,0 +1,112 @@
+//! Cross-core reference resolution for samples and analyses
+//!
+//! Lets an enterprise `ReferenceResolver` turn `smp_…`/`anl_…` ids (or legacy
+//! bare UUIDs) into summaries owned by the sandbox core. Summaries carry the
+//! owning tenant; the NAPI lookup only resolves samples of the caller's tenant.
+
+use async_trait::async_trait;
+use napi_derive::napi;
+use phantom_enterprise_standards::{EntityId, EntityKind, EntityResolver, EntitySummary};
+use std::collections::HashMap;
+
+use crate::{SandboxAnalysis, SandboxCore, SandboxCoreNapi};
+
+const OWNER: &str = "phantom-sandbox-core";
+
+fn analysis_summary(kind: EntityKind, analysis: &SandboxAnalysis) -> EntitySummary {
+    let id = match kind {
+        EntityKind::Sample => analysis.sample_info.sample_id.clone(),
+        _ => analysis.analysis_id.clone(),
+    };
+    let mut attributes = HashMap::new();
+    attributes.insert("tenant_id".to_string(), serde_json::json!(analysis.tenant_id));
+    attributes.insert("sample_id".to_string(), serde_json::json!(analysis.sample_info.sample_id));
+    attributes.insert("analysis_id".to_string(), serde_json::json!(analysis.analysis_id));
+    attributes.insert("sha256".to_string(), serde_json::json!(analysis.sample_info.file_hash_sha256));
+    attributes.insert("confidence_score".to_string(), serde_json::json!(analysis.confidence_score));
+
+    EntitySummary {
+        id,
+        kind,
+        owner: OWNER.to_string(),
+        title: analysis.sample_info.file_name.clone(),
+        status: Some(format!("{:?}", analysis.verdict)),
+        created_at: Some(match kind {
+            EntityKind::Sample => analysis.sample_info.submission_time,
+            _ => analysis.analysis_metadata.analysis_start,
+        }),
+        attributes,
+    }
+}
+
+#[async_trait]
+impl EntityResolver for SandboxCore {
+    fn owner(&self) -> &str {
+        OWNER
+    }
+
+    fn kinds(&self) -> Vec<EntityKind> {
+        vec![EntityKind::Sample, EntityKind::Analysis]
+    }
+
+    async fn resolve_entity(&self, id: &EntityId) -> Option<EntitySummary> {
+        self.resolve_reference(None, id).await
+    }
+}
+
+impl SandboxCore {
+    /// Resolve `id` among the samples of `tenant_id`, or of every tenant
+    pub async fn resolve_reference(&self, tenant_id: Option<&str>, id: &EntityId) -> Option<EntitySummary> {
+        let wants = |kind| id.kind().is_none_or(|k| k == kind);
+        let visible = |owner: &str| tenant_id.is_none_or(|t| t == owner);
+
+        {
+            let analyses = self.completed_analyses.read().await;
+            for analysis in analyses.values().filter(|analysis| visible(&analysis.tenant_id)) {
+                if wants(EntityKind::Sample) && id.matches(&analysis.sample_info.sample_id) {
+                    return Some(analysis_summary(EntityKind::Sample, analysis));
+                }
+                if wants(EntityKind::Analysis) && id.matches(&analysis.analysis_id) {
+                    return Some(analysis_summary(EntityKind::Analysis, analysis));
+                }
+            }
+        }
+
+        if !wants(EntityKind::Sample) {
+            return None;
+        }
+        let queue = self.analysis_queue.read().await;
+        queue.iter().find(|job| visible(&job.tenant_id) && id.matches(&job.sample_id)).map(|job| {
+            let mut attributes = HashMap::new();
+            attributes.insert("tenant_id".to_string(), serde_json::json!(job.tenant_id));
+            attributes.insert("job_id".to_string(), serde_json::json!(job.job_id));
+            attributes.insert("progress".to_string(), serde_json::json!(job.progress));
+            EntitySummary {
+                id: job.sample_id.clone(),
+                kind: EntityKind::Sample,
+                owner: OWNER.to_string(),
+                title: format!("Queued sample {}", job.sample_id),
+                status: Some(format!("{:?}", job.status)),
+                created_at: Some(job.submission_time),
+                attributes,
+            }
+        })
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// Resolve a sample or analysis reference to its summary
+    #[napi]
+    pub async fn resolve_reference(&self, id: String, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let entity_id = EntityId::parse(&id)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to resolve reference: {}", e)))?;
+        match self.inner.resolve_reference(Some(&tenant_id), &entity_id).await {
+            Some(summary) => serde_json::to_string(&summary)
+                .map(Some)
+                .map_err(|e| napi::Error::from_reason(format!("Failed to serialize summary: {}", e))),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,307 @@
+//! Retention and eviction for completed analyses
+//!
+//! Completed analyses are held in memory for fast lookup. The retention
+//! policy bounds that cache by entry count, age and approximate serialized
+//! size: entries older than `max_age_secs` expire first (TTL), then the least
+//! recently accessed entries are evicted until the count and byte limits hold
+//! (LRU). Evicted analyses can optionally be spilled to disk, from where
+//! `get_analysis` still serves them.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::{SandboxAnalysis, SandboxCore};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    pub max_entries: Option<usize>,
+    pub max_age_secs: Option<u64>,
+    pub max_bytes: Option<u64>,
+    /// Directory evicted analyses are written to; `None` discards them
+    #[serde(default)]
+    pub spill_dir: Option<PathBuf>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_entries: Some(10_000),
+            max_age_secs: None,
+            max_bytes: Some(1 << 30),
+            spill_dir: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvictionReason {
+    Expired,
+    MaxEntries,
+    MaxBytes,
+}
+
+/// Eviction counters reported with the performance metrics
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetentionMetrics {
+    pub retained_entries: u64,
+    pub retained_bytes: u64,
+    pub evicted_total: u64,
+    pub evicted_expired: u64,
+    pub evicted_max_entries: u64,
+    pub evicted_max_bytes: u64,
+    pub evicted_bytes: u64,
+    pub spilled_to_disk: u64,
+    pub spill_failures: u64,
+    pub spill_reads: u64,
+    pub last_eviction: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone)]
+struct RetainedEntry {
+    stored_at: DateTime<Utc>,
+    last_access: DateTime<Utc>,
+    size_bytes: u64,
+}
+
+/// Access bookkeeping for the completed-analysis cache
+#[derive(Debug, Default)]
+pub struct RetentionState {
+    policy: RetentionPolicy,
+    entries: HashMap<String, RetainedEntry>,
+}
+
+fn analysis_size(analysis: &SandboxAnalysis) -> u64 {
+    serde_json::to_vec(analysis).map(|bytes| bytes.len() as u64).unwrap_or(0)
+}
+
+fn spill_path(dir: &std::path::Path, sample_id: &str) -> PathBuf {
+    let safe: String = sample_id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    dir.join(format!("{}.json", safe))
+}
+
+impl SandboxCore {
+    pub async fn retention_policy(&self) -> RetentionPolicy {
+        self.retention.read().await.policy.clone()
+    }
+
+    /// Replace the retention policy and apply it immediately
+    pub async fn set_retention_policy(&self, policy: RetentionPolicy) -> Result<RetentionMetrics, String> {
+        if let Some(dir) = &policy.spill_dir {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create spill directory {}: {}", dir.display(), e))?;
+        }
+        self.retention.write().await.policy = policy;
+        Ok(self.enforce_retention().await)
+    }
+
+    /// Record that a completed analysis was stored
+    pub(crate) async fn track_retained(&self, sample_id: &str, analysis: &SandboxAnalysis) {
+        let now = Utc::now();
+        self.retention.write().await.entries.insert(
+            sample_id.to_string(),
+            RetainedEntry {
+                stored_at: now,
+                last_access: now,
+                size_bytes: analysis_size(analysis),
+            },
+        );
+    }
+
+    /// Refresh the LRU position of an analysis
+    pub(crate) async fn touch_retained(&self, sample_id: &str) {
+        if let Some(entry) = self.retention.write().await.entries.get_mut(sample_id) {
+            entry.last_access = Utc::now();
+        }
+    }
+
+    /// Load an analysis that was evicted to the spill directory
+    pub(crate) async fn load_spilled(&self, sample_id: &str) -> Option<SandboxAnalysis> {
+        let dir = self.retention.read().await.policy.spill_dir.clone()?;
+        let bytes = fs::read(spill_path(&dir, sample_id)).ok()?;
+        let analysis = serde_json::from_slice(&bytes).ok()?;
+        self.performance_metrics.write().await.retention.spill_reads += 1;
+        Some(analysis)
+    }
+
+    /// Every analysis file in the spill directory
+    pub(crate) async fn spill_files(&self) -> Vec<PathBuf> {
+        let Some(dir) = self.retention.read().await.policy.spill_dir.clone() else {
+            return Vec::new();
+        };
+        let Ok(entries) = fs::read_dir(&dir) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect()
+    }
+
+    /// Drop the retention entry of a sample and any analysis spilled for it
+    pub(crate) async fn forget_retained(&self, sample_id: &str) {
+        let mut retention = self.retention.write().await;
+        retention.entries.remove(sample_id);
+        if let Some(dir) = &retention.policy.spill_dir {
+            if let Err(e) = fs::remove_file(spill_path(dir, sample_id)) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    log::warn!("Failed to remove spilled analysis {}: {}", sample_id, e);
+                }
+            }
+        }
+    }
+
+    /// Evict expired and excess analyses; returns the updated counters
+    pub async fn enforce_retention(&self) -> RetentionMetrics {
+        let now = Utc::now();
+        let mut analyses = self.completed_analyses.write().await;
+        let mut retention = self.retention.write().await;
+
+        // Analyses restored or inserted without tracking start their clock now
+        for (sample_id, analysis) in analyses.iter() {
+            if !retention.entries.contains_key(sample_id) {
+                retention.entries.insert(
+                    sample_id.clone(),
+                    RetainedEntry {
+                        stored_at: now,
+                        last_access: now,
+                        size_bytes: analysis_size(analysis),
+                    },
+                );
+            }
+        }
+        retention.entries.retain(|sample_id, _| analyses.contains_key(sample_id));
+
+        let policy = retention.policy.clone();
+        let mut evictions: Vec<(String, EvictionReason)> = Vec::new();
+
+        if let Some(max_age) = policy.max_age_secs {
+            let cutoff = now - chrono::Duration::seconds(max_age.min(i64::MAX as u64) as i64);
+            evictions.extend(
+                retention
+                    .entries
+                    .iter()
+                    .filter(|(_, entry)| entry.stored_at < cutoff)
+                    .map(|(id, _)| (id.clone(), EvictionReason::Expired)),
+            );
+        }
+
+        let mut lru: Vec<(&String, &RetainedEntry)> = retention
+            .entries
+            .iter()
+            .filter(|(id, _)| !evictions.iter().any(|(evicted, _)| evicted == *id))
+            .collect();
+        lru.sort_by_key(|(id, entry)| (entry.last_access, (*id).clone()));
+        let mut count = lru.len();
+        let mut bytes: u64 = lru.iter().map(|(_, entry)| entry.size_bytes).sum();
+        for (id, entry) in lru {
+            let reason = if policy.max_entries.is_some_and(|max| count > max) {
+                EvictionReason::MaxEntries
+            } else if policy.max_bytes.is_some_and(|max| bytes > max) {
+                EvictionReason::MaxBytes
+            } else {
+                break;
+            };
+            count -= 1;
+            bytes -= entry.size_bytes;
+            evictions.push((id.clone(), reason));
+        }
+
+        let mut metrics = self.performance_metrics.write().await;
+        for (sample_id, reason) in evictions {
+            let Some(analysis) = analyses.remove(&sample_id) else { continue };
+            let size = retention.entries.remove(&sample_id).map(|e| e.size_bytes).unwrap_or(0);
+
+            if let Some(dir) = &policy.spill_dir {
+                let written = serde_json::to_vec(&analysis)
+                    .map_err(|e| e.to_string())
+                    .and_then(|bytes| fs::write(spill_path(dir, &sample_id), bytes).map_err(|e| e.to_string()));
+                match written {
+                    Ok(()) => metrics.retention.spilled_to_disk += 1,
+                    Err(e) => {
+                        metrics.retention.spill_failures += 1;
+                        log::warn!("Failed to spill analysis {}: {}", sample_id, e);
+                    }
+                }
+            }
+
+            metrics.retention.evicted_total += 1;
+            metrics.retention.evicted_bytes += size;
+            match reason {
+                EvictionReason::Expired => metrics.retention.evicted_expired += 1,
+                EvictionReason::MaxEntries => metrics.retention.evicted_max_entries += 1,
+                EvictionReason::MaxBytes => metrics.retention.evicted_max_bytes += 1,
+            }
+            metrics.retention.last_eviction = Some(now);
+        }
+
+        metrics.retention.retained_entries = retention.entries.len() as u64;
+        metrics.retention.retained_bytes = retention.entries.values().map(|e| e.size_bytes).sum();
+        metrics.retention.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AnalysisPriority;
+
+    #[tokio::test]
+    async fn test_lru_eviction_spills_and_serves_from_disk() {
+        let core = SandboxCore::new().unwrap();
+        let spill_dir = std::env::temp_dir().join(format!("phantom-retention-{}", uuid::Uuid::new_v4()));
+
+        let mut sample_ids = Vec::new();
+        for index in 0..3 {
+            let data = format!("MZ retention sample {}", index);
+            let id = core
+                .submit_sample("default", data.as_bytes(), format!("s{}.exe", index), AnalysisPriority::Normal, vec![], false)
+                .await
+                .unwrap();
+            core.process_queue().await.unwrap();
+            sample_ids.push(id);
+        }
+
+        // Reading the oldest analysis makes the second one least recently used
+        assert!(core.get_analysis("default", &sample_ids[0]).await.unwrap().is_some());
+
+        let metrics = core
+            .set_retention_policy(RetentionPolicy {
+                max_entries: Some(2),
+                max_age_secs: None,
+                max_bytes: None,
+                spill_dir: Some(spill_dir.clone()),
+            })
+            .await
+            .unwrap();
+        assert_eq!(metrics.evicted_max_entries, 1);
+        assert_eq!(metrics.spilled_to_disk, 1);
+        assert_eq!(metrics.retained_entries, 2);
+        assert!(!core.completed_analyses.read().await.contains_key(&sample_ids[1]));
+
+        let restored = core.get_analysis("default", &sample_ids[1]).await.unwrap().unwrap();
+        assert_eq!(restored.sample_info.sample_id, sample_ids[1]);
+
+        let metrics = core.get_performance_metrics("default").await.unwrap();
+        assert_eq!(metrics.retention.spill_reads, 1);
+
+        let expired = core
+            .set_retention_policy(RetentionPolicy {
+                max_entries: None,
+                max_age_secs: Some(0),
+                max_bytes: None,
+                spill_dir: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(expired.evicted_expired, 2);
+        assert_eq!(expired.retained_entries, 0);
+
+        let _ = fs::remove_dir_all(spill_dir);
+    }
+}
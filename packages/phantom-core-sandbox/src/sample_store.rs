@@ -0,0 +1,835 @@
+//! Encrypted Sample Store
+//!
+//! Envelope encryption for sample bytes at rest. Each sample is sealed with
+//! its own AES-256-GCM data key, and that data key is wrapped by the active
+//! master key of a keyring kept outside the job store. Rotating the master
+//! key only rewraps the data keys; sample ciphertext is rewritten unchanged.
+//! Samples leave the sandbox as password-protected zips (the `infected`
+//! convention) so they are not picked up by scanners on the way.
+//!
+//! The keyring file never holds a master key in the clear: each is sealed
+//! under a key-encryption key from a `KeyProvider` (shared with the connector
+//! secret stores), by default the `PHANTOM_SANDBOX_SAMPLE_KEK` environment
+//! variable (64 hex digits), or a callback into a KMS. Keyrings written with plaintext keys are sealed the
+//! first time they are opened with a KEK.
+//!
+//! The ciphers come from `ring` and `zip` behind the `sample-encryption`
+//! feature; without it opening an encrypted store and exporting fail.
+
+use crate::job_store::JobStore;
+use crate::secrets::KeyProvider;
+use crate::{AnalysisJob, SandboxAnalysis, SandboxCore};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Password used for exports unless the caller picks another
+pub const DEFAULT_EXPORT_PASSWORD: &str = "infected";
+
+/// Prefix marking an encrypted sample record; anything else is plaintext
+/// written before encryption was enabled
+const ENVELOPE_MAGIC: &[u8; 4] = b"PSE1";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+/// Environment variable holding the default key-encryption key, as 64 hex digits
+pub const KEK_ENV: &str = "PHANTOM_SANDBOX_SAMPLE_KEK";
+
+// Keyring
+
+#[derive(Clone, Serialize, Deserialize)]
+struct MasterKey {
+    key_id: String,
+    /// Key sealed under the key-encryption key, hex-encoded
+    #[serde(default)]
+    wrapped_key: String,
+    #[serde(default)]
+    key_nonce: String,
+    /// Plaintext hex key of keyrings written before keys were sealed
+    #[serde(default, skip_serializing)]
+    key: Option<String>,
+    created_at: DateTime<Utc>,
+}
+
+/// Binds a sealed master key to its id
+fn master_key_aad(key_id: &str) -> Vec<u8> {
+    format!("keyring:{}", key_id).into_bytes()
+}
+
+#[derive(Serialize, Deserialize)]
+struct KeyringFile {
+    active_key_id: String,
+    keys: Vec<MasterKey>,
+}
+
+/// Master keys wrapping the per-sample data keys
+///
+/// The newest key is active and wraps every new data key; older keys are
+/// kept until a rotation has rewrapped everything they protect.
+pub struct Keyring {
+    path: PathBuf,
+    kek_name: String,
+    kek: Vec<u8>,
+    active_key_id: String,
+    keys: BTreeMap<String, MasterKey>,
+    /// Unsealed master keys by id
+    material: BTreeMap<String, Vec<u8>>,
+}
+
+impl Keyring {
+    /// Load the keyring at `path`, unsealing its master keys with `kek`, or
+    /// create it with a fresh master key if it does not exist yet
+    pub fn open(path: impl AsRef<Path>, kek: &dyn KeyProvider) -> Result<Self, String> {
+        let kek_name = kek.name().to_string();
+        let kek = kek.key()?;
+        if kek.len() != KEY_LEN {
+            return Err(format!("{}: expected a {}-byte key, found {}", kek_name, KEY_LEN, kek.len()));
+        }
+        let mut keyring = Self {
+            path: path.as_ref().to_path_buf(),
+            kek_name,
+            kek,
+            active_key_id: String::new(),
+            keys: BTreeMap::new(),
+            material: BTreeMap::new(),
+        };
+        if !keyring.path.exists() {
+            keyring.add_key()?;
+            keyring.save()?;
+            return Ok(keyring);
+        }
+
+        let path = keyring.path.display().to_string();
+        let data = fs::read(&keyring.path).map_err(|e| format!("Failed to read keyring {}: {}", path, e))?;
+        let file: KeyringFile = serde_json::from_slice(&data)
+            .map_err(|e| format!("Failed to parse keyring {}: {}", path, e))?;
+        if !file.keys.iter().any(|k| k.key_id == file.active_key_id) {
+            return Err(format!("Keyring {} has no key {}", path, file.active_key_id));
+        }
+
+        let mut sealed_plaintext = false;
+        for mut key in file.keys {
+            let material = match key.key.take() {
+                Some(plaintext) => {
+                    let material = decode_key(&plaintext).map_err(|e| format!("Master key {}: {}", key.key_id, e))?;
+                    (key.wrapped_key, key.key_nonce) = keyring.seal_key(&key.key_id, &material)?;
+                    sealed_plaintext = true;
+                    material
+                }
+                None => keyring.unseal_key(&key)?,
+            };
+            keyring.material.insert(key.key_id.clone(), material);
+            keyring.keys.insert(key.key_id.clone(), key);
+        }
+        keyring.active_key_id = file.active_key_id;
+        if sealed_plaintext {
+            keyring.save()?;
+            log::info!("Sealed the plaintext master keys of {} under {}", path, keyring.kek_name);
+        }
+        Ok(keyring)
+    }
+
+    pub fn active_key_id(&self) -> &str {
+        &self.active_key_id
+    }
+
+    fn key(&self, key_id: &str) -> Result<Vec<u8>, String> {
+        self.material.get(key_id).cloned().ok_or_else(|| format!("Unknown master key {}", key_id))
+    }
+
+    fn seal_key(&self, key_id: &str, key: &[u8]) -> Result<(String, String), String> {
+        let nonce = crypto::random_bytes(NONCE_LEN)?;
+        let sealed = crypto::seal(&self.kek, &nonce, &master_key_aad(key_id), key)?;
+        Ok((to_hex(&sealed), to_hex(&nonce)))
+    }
+
+    fn unseal_key(&self, key: &MasterKey) -> Result<Vec<u8>, String> {
+        let material = crypto::open(
+            &self.kek,
+            &from_hex(&key.key_nonce)?,
+            &master_key_aad(&key.key_id),
+            &from_hex(&key.wrapped_key)?,
+        )
+        .map_err(|_| format!("Master key {} cannot be unsealed with {}", key.key_id, self.kek_name))?;
+        if material.len() != KEY_LEN {
+            return Err(format!("Master key {}: expected a {}-byte key, found {}", key.key_id, KEY_LEN, material.len()));
+        }
+        Ok(material)
+    }
+
+    /// Generate a master key and make it the active one
+    fn add_key(&mut self) -> Result<String, String> {
+        let key = crypto::random_bytes(KEY_LEN)?;
+        let key_id = Uuid::new_v4().to_string();
+        let (wrapped_key, key_nonce) = self.seal_key(&key_id, &key)?;
+        self.keys.insert(
+            key_id.clone(),
+            MasterKey { key_id: key_id.clone(), wrapped_key, key_nonce, key: None, created_at: Utc::now() },
+        );
+        self.material.insert(key_id.clone(), key);
+        self.active_key_id = key_id.clone();
+        Ok(key_id)
+    }
+
+    /// Drop every key but the active one, returning the ids dropped
+    fn retire_inactive(&mut self) -> Vec<String> {
+        let active = self.active_key_id.clone();
+        let retired = self.keys.keys().filter(|id| **id != active).cloned().collect();
+        self.keys.retain(|id, _| *id == active);
+        self.material.retain(|id, _| *id == active);
+        retired
+    }
+
+    /// Written owner-only and renamed into place, so a crash never leaves
+    /// a keyring that has lost keys
+    fn save(&self) -> Result<(), String> {
+        let file = KeyringFile {
+            active_key_id: self.active_key_id.clone(),
+            keys: self.keys.values().cloned().collect(),
+        };
+        let data = serde_json::to_vec_pretty(&file).map_err(|e| e.to_string())?;
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+
+        let tmp = self.path.with_extension("tmp");
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut out = options.open(&tmp).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+        std::io::Write::write_all(&mut out, &data).map_err(|e| format!("Failed to write {}: {}", tmp.display(), e))?;
+        out.sync_all().map_err(|e| e.to_string())?;
+        fs::rename(&tmp, &self.path).map_err(|e| format!("Failed to write {}: {}", self.path.display(), e))
+    }
+
+    fn summary(&self) -> Vec<MasterKeyInfo> {
+        self.keys
+            .values()
+            .map(|k| MasterKeyInfo {
+                key_id: k.key_id.clone(),
+                created_at: k.created_at,
+                active: k.key_id == self.active_key_id,
+            })
+            .collect()
+    }
+}
+
+// Envelope
+
+#[derive(Serialize, Deserialize)]
+struct EnvelopeHeader {
+    key_id: String,
+    wrapped_key: String,
+    key_nonce: String,
+    data_nonce: String,
+}
+
+/// Record layout: magic, little-endian header length, JSON header, then
+/// the sample ciphertext with its GCM tag
+fn write_envelope(header: &EnvelopeHeader, ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+    let header = serde_json::to_vec(header).map_err(|e| e.to_string())?;
+    let mut record = Vec::with_capacity(8 + header.len() + ciphertext.len());
+    record.extend_from_slice(ENVELOPE_MAGIC);
+    record.extend_from_slice(&(header.len() as u32).to_le_bytes());
+    record.extend_from_slice(&header);
+    record.extend_from_slice(ciphertext);
+    Ok(record)
+}
+
+/// Split an encrypted record, or `None` for a plaintext one
+fn read_envelope(record: &[u8]) -> Result<Option<(EnvelopeHeader, &[u8])>, String> {
+    if record.len() < 8 || &record[..4] != ENVELOPE_MAGIC {
+        return Ok(None);
+    }
+    let header_len = u32::from_le_bytes([record[4], record[5], record[6], record[7]]) as usize;
+    let body = &record[8..];
+    if header_len > body.len() {
+        return Err("Truncated sample envelope".to_string());
+    }
+    let header = serde_json::from_slice(&body[..header_len]).map_err(|e| format!("Invalid sample envelope: {}", e))?;
+    Ok(Some((header, &body[header_len..])))
+}
+
+/// Binds a wrapped data key to both its sample and its master key
+fn key_aad(sample_id: &str, key_id: &str) -> Vec<u8> {
+    format!("{}:{}", sample_id, key_id).into_bytes()
+}
+
+fn wrap_data_key(keyring: &Keyring, sample_id: &str, data_key: &[u8]) -> Result<(String, String, String), String> {
+    let key_id = keyring.active_key_id().to_string();
+    let key_nonce = crypto::random_bytes(NONCE_LEN)?;
+    let wrapped = crypto::seal(&keyring.key(&key_id)?, &key_nonce, &key_aad(sample_id, &key_id), data_key)?;
+    Ok((key_id, to_hex(&wrapped), to_hex(&key_nonce)))
+}
+
+fn unwrap_data_key(keyring: &Keyring, sample_id: &str, header: &EnvelopeHeader) -> Result<Vec<u8>, String> {
+    crypto::open(
+        &keyring.key(&header.key_id)?,
+        &from_hex(&header.key_nonce)?,
+        &key_aad(sample_id, &header.key_id),
+        &from_hex(&header.wrapped_key)?,
+    )
+    .map_err(|_| format!("Failed to unwrap data key for sample {}", sample_id))
+}
+
+fn seal_sample(keyring: &Keyring, sample_id: &str, data: &[u8]) -> Result<Vec<u8>, String> {
+    let data_key = crypto::random_bytes(KEY_LEN)?;
+    let data_nonce = crypto::random_bytes(NONCE_LEN)?;
+    let ciphertext = crypto::seal(&data_key, &data_nonce, sample_id.as_bytes(), data)?;
+    let (key_id, wrapped_key, key_nonce) = wrap_data_key(keyring, sample_id, &data_key)?;
+    let header = EnvelopeHeader { key_id, wrapped_key, key_nonce, data_nonce: to_hex(&data_nonce) };
+    write_envelope(&header, &ciphertext)
+}
+
+fn open_sample(keyring: &Keyring, sample_id: &str, record: &[u8]) -> Result<Vec<u8>, String> {
+    let Some((header, ciphertext)) = read_envelope(record)? else {
+        return Ok(record.to_vec());
+    };
+    let data_key = unwrap_data_key(keyring, sample_id, &header)?;
+    crypto::open(&data_key, &from_hex(&header.data_nonce)?, sample_id.as_bytes(), ciphertext)
+        .map_err(|_| format!("Sample {} failed integrity check", sample_id))
+}
+
+enum Rewrap {
+    Current,
+    Rewrapped(Vec<u8>),
+    Migrated(Vec<u8>),
+}
+
+/// Rewrap a record's data key under the active master key, encrypting
+/// plaintext records on the way
+fn rewrap_sample(keyring: &Keyring, sample_id: &str, record: &[u8]) -> Result<Rewrap, String> {
+    let Some((header, ciphertext)) = read_envelope(record)? else {
+        return Ok(Rewrap::Migrated(seal_sample(keyring, sample_id, record)?));
+    };
+    if header.key_id == keyring.active_key_id() {
+        return Ok(Rewrap::Current);
+    }
+    let data_key = unwrap_data_key(keyring, sample_id, &header)?;
+    let (key_id, wrapped_key, key_nonce) = wrap_data_key(keyring, sample_id, &data_key)?;
+    let header = EnvelopeHeader { key_id, wrapped_key, key_nonce, data_nonce: header.data_nonce };
+    Ok(Rewrap::Rewrapped(write_envelope(&header, ciphertext)?))
+}
+
+// Store
+
+/// Master key as reported to callers, without the key material
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MasterKeyInfo {
+    pub key_id: String,
+    pub created_at: DateTime<Utc>,
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SampleEncryptionStatus {
+    pub enabled: bool,
+    pub keyring_path: Option<String>,
+    /// Where the key-encryption key sealing the keyring comes from
+    pub key_encryption_key: Option<String>,
+    pub active_key_id: Option<String>,
+    pub master_keys: Vec<MasterKeyInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyRotationReport {
+    pub previous_key_id: String,
+    pub active_key_id: String,
+    /// Samples whose data key now sits under the new master key
+    pub rewrapped: usize,
+    /// Plaintext samples encrypted during the rotation
+    pub migrated: usize,
+    /// Samples that could not be rewrapped; their master keys are kept
+    pub failed: Vec<String>,
+    pub retired_keys: Vec<String>,
+}
+
+/// Job store decorator that encrypts sample bytes before they reach the
+/// wrapped store; jobs and analyses pass through unchanged
+pub struct EncryptedSampleStore {
+    inner: Arc<dyn JobStore>,
+    keyring: Mutex<Keyring>,
+}
+
+impl EncryptedSampleStore {
+    pub fn open(inner: Arc<dyn JobStore>, keyring_path: impl AsRef<Path>, kek: &dyn KeyProvider) -> Result<Self, String> {
+        crypto::ensure_available()?;
+        let keyring = Keyring::open(keyring_path, kek)?;
+        Ok(Self { inner, keyring: Mutex::new(keyring) })
+    }
+
+    /// Add a master key, rewrap every sample under it and retire the old
+    /// keys once nothing depends on them
+    pub fn rotate_master_key(&self) -> Result<KeyRotationReport, String> {
+        let mut keyring = self.keyring.lock().map_err(|e| e.to_string())?;
+        let previous_key_id = keyring.active_key_id().to_string();
+        let active_key_id = keyring.add_key()?;
+        // Persist first: records rewrapped below are unreadable without it
+        keyring.save()?;
+
+        let mut report = KeyRotationReport {
+            previous_key_id,
+            active_key_id: active_key_id.clone(),
+            rewrapped: 0,
+            migrated: 0,
+            failed: Vec::new(),
+            retired_keys: Vec::new(),
+        };
+        for sample_id in self.inner.sample_ids()? {
+            let outcome = self
+                .inner
+                .load_sample(&sample_id)
+                .and_then(|record| record.ok_or_else(|| "sample vanished".to_string()))
+                .and_then(|record| rewrap_sample(&keyring, &sample_id, &record));
+            let stored = match outcome {
+                Ok(Rewrap::Current) => Ok(()),
+                Ok(Rewrap::Rewrapped(record)) => self.inner.save_sample(&sample_id, &record).map(|_| report.rewrapped += 1),
+                Ok(Rewrap::Migrated(record)) => self.inner.save_sample(&sample_id, &record).map(|_| report.migrated += 1),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = stored {
+                log::warn!("Failed to rewrap sample {}: {}", sample_id, e);
+                report.failed.push(sample_id);
+            }
+        }
+
+        if report.failed.is_empty() {
+            report.retired_keys = keyring.retire_inactive();
+            keyring.save()?;
+        }
+        log::info!(
+            "Rotated sample master key to {}: {} rewrapped, {} migrated, {} failed",
+            active_key_id,
+            report.rewrapped,
+            report.migrated,
+            report.failed.len()
+        );
+        Ok(report)
+    }
+
+    pub fn status(&self) -> Result<SampleEncryptionStatus, String> {
+        let keyring = self.keyring.lock().map_err(|e| e.to_string())?;
+        Ok(SampleEncryptionStatus {
+            enabled: true,
+            keyring_path: Some(keyring.path.display().to_string()),
+            key_encryption_key: Some(keyring.kek_name.clone()),
+            active_key_id: Some(keyring.active_key_id().to_string()),
+            master_keys: keyring.summary(),
+        })
+    }
+}
+
+impl JobStore for EncryptedSampleStore {
+    fn save_job(&self, job: &AnalysisJob) -> Result<(), String> {
+        self.inner.save_job(job)
+    }
+
+    fn load_jobs(&self) -> Result<Vec<AnalysisJob>, String> {
+        self.inner.load_jobs()
+    }
+
+    fn save_sample(&self, sample_id: &str, data: &[u8]) -> Result<(), String> {
+        let record = {
+            let keyring = self.keyring.lock().map_err(|e| e.to_string())?;
+            seal_sample(&keyring, sample_id, data)?
+        };
+        self.inner.save_sample(sample_id, &record)
+    }
+
+    fn load_sample(&self, sample_id: &str) -> Result<Option<Vec<u8>>, String> {
+        let Some(record) = self.inner.load_sample(sample_id)? else {
+            return Ok(None);
+        };
+        let keyring = self.keyring.lock().map_err(|e| e.to_string())?;
+        open_sample(&keyring, sample_id, &record).map(Some)
+    }
+
+    fn sample_ids(&self) -> Result<Vec<String>, String> {
+        self.inner.sample_ids()
+    }
+
+    fn save_analysis(&self, sample_id: &str, analysis: &SandboxAnalysis) -> Result<(), String> {
+        self.inner.save_analysis(sample_id, analysis)
+    }
+
+    fn load_analyses(&self) -> Result<Vec<SandboxAnalysis>, String> {
+        self.inner.load_analyses()
+    }
+
+    fn delete_records(&self, job_id: &str, sample_id: &str) -> Result<(), String> {
+        self.inner.delete_records(job_id, sample_id)
+    }
+}
+
+// Export
+
+/// Describes an exported sample; written next to it inside the zip
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SampleExportManifest {
+    pub sample_id: String,
+    pub sha256: String,
+    pub size: u64,
+    pub file_name: Option<String>,
+    pub verdict: Option<String>,
+    pub exported_at: DateTime<Utc>,
+}
+
+impl SandboxCore {
+    /// Create a core whose job store keeps samples encrypted under the
+    /// keyring at `keyring_path`, its master keys sealed under `kek`
+    pub fn with_encrypted_job_store(store: Arc<dyn JobStore>, keyring_path: &str, kek: &dyn KeyProvider) -> Result<Self, String> {
+        let encrypted = Arc::new(EncryptedSampleStore::open(store, keyring_path, kek)?);
+        let mut core = Self::with_job_store(encrypted.clone())?;
+        core.sample_encryption = Some(encrypted);
+        Ok(core)
+    }
+
+    pub fn rotate_sample_master_key(&self) -> Result<KeyRotationReport, String> {
+        self.sample_encryption
+            .as_ref()
+            .ok_or_else(|| "Sample encryption is not enabled".to_string())?
+            .rotate_master_key()
+    }
+
+    pub fn get_sample_encryption_status(&self) -> Result<SampleEncryptionStatus, String> {
+        match &self.sample_encryption {
+            Some(store) => store.status(),
+            None => Ok(SampleEncryptionStatus::default()),
+        }
+    }
+
+    /// Package a sample as a password-protected zip holding the sample,
+    /// named by its SHA-256, and a JSON manifest
+    pub async fn export_sample(&self, tenant_id: &str, sample_id: &str, password: Option<&str>) -> Result<Vec<u8>, String> {
+        let password = password.unwrap_or(DEFAULT_EXPORT_PASSWORD);
+        if password.is_empty() {
+            return Err("Samples are only exported password-protected".to_string());
+        }
+        if !self.owns_sample(tenant_id, sample_id).await {
+            return Err(format!("Sample {} not found", sample_id));
+        }
+
+        let cached = self.sample_data.read().await.get(sample_id).cloned();
+        let data = match cached {
+            Some(data) => data,
+            None => Arc::new(
+                self.job_store
+                    .load_sample(sample_id)?
+                    .ok_or_else(|| format!("Sample {} not found", sample_id))?,
+            ),
+        };
+
+        let sha256 = format!("{:x}", Sha256::digest(data.as_slice()));
+        let (file_name, verdict) = match self.completed_analyses.read().await.get(sample_id) {
+            Some(analysis) => (
+                Some(analysis.sample_info.file_name.clone()),
+                Some(format!("{:?}", analysis.verdict).to_lowercase()),
+            ),
+            None => (None, None),
+        };
+        let manifest = SampleExportManifest {
+            sample_id: sample_id.to_string(),
+            sha256: sha256.clone(),
+            size: data.len() as u64,
+            file_name,
+            verdict,
+            exported_at: Utc::now(),
+        };
+        let manifest = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+
+        crypto::protected_zip(
+            &[(sha256.clone(), data.as_slice()), (format!("{}.json", sha256), manifest.as_slice())],
+            password,
+        )
+    }
+}
+
+// Encoding
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>, String> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return Err("Invalid hex encoding".to_string());
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|e| format!("Invalid hex encoding: {}", e)))
+        .collect()
+}
+
+fn decode_key(hex: &str) -> Result<Vec<u8>, String> {
+    let key = from_hex(hex)?;
+    if key.len() != KEY_LEN {
+        return Err(format!("expected a {}-byte key, found {}", KEY_LEN, key.len()));
+    }
+    Ok(key)
+}
+
+// Ciphers
+
+#[cfg(feature = "sample-encryption")]
+mod crypto {
+    use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM};
+    use ring::rand::{SecureRandom, SystemRandom};
+    use std::io::{Cursor, Write};
+    use zip::unstable::write::FileOptionsExt;
+    use zip::write::SimpleFileOptions;
+    use zip::{CompressionMethod, ZipWriter};
+
+    pub fn ensure_available() -> Result<(), String> {
+        Ok(())
+    }
+
+    pub fn random_bytes(len: usize) -> Result<Vec<u8>, String> {
+        let mut bytes = vec![0u8; len];
+        SystemRandom::new().fill(&mut bytes).map_err(|_| "System RNG unavailable".to_string())?;
+        Ok(bytes)
+    }
+
+    fn aead_key(key: &[u8]) -> Result<LessSafeKey, String> {
+        UnboundKey::new(&AES_256_GCM, key)
+            .map(LessSafeKey::new)
+            .map_err(|_| "Invalid AES-256 key".to_string())
+    }
+
+    fn nonce(bytes: &[u8]) -> Result<Nonce, String> {
+        Nonce::try_assume_unique_for_key(bytes).map_err(|_| "Invalid GCM nonce".to_string())
+    }
+
+    pub fn seal(key: &[u8], nonce_bytes: &[u8], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let mut in_out = plaintext.to_vec();
+        aead_key(key)?
+            .seal_in_place_append_tag(nonce(nonce_bytes)?, Aad::from(aad), &mut in_out)
+            .map_err(|_| "Encryption failed".to_string())?;
+        Ok(in_out)
+    }
+
+    pub fn open(key: &[u8], nonce_bytes: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+        let mut in_out = ciphertext.to_vec();
+        let plaintext_len = aead_key(key)?
+            .open_in_place(nonce(nonce_bytes)?, Aad::from(aad), &mut in_out)
+            .map_err(|_| "Decryption failed".to_string())?
+            .len();
+        in_out.truncate(plaintext_len);
+        Ok(in_out)
+    }
+
+    /// ZipCrypto rather than AES so the archive opens in every unzip tool;
+    /// the password keeps scanners off the sample, it does not keep secrets
+    pub fn protected_zip(entries: &[(String, &[u8])], password: &str) -> Result<Vec<u8>, String> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .with_deprecated_encryption(password.as_bytes());
+        for (name, data) in entries {
+            zip.start_file(name.as_str(), options).map_err(|e| e.to_string())?;
+            zip.write_all(data).map_err(|e| e.to_string())?;
+        }
+        Ok(zip.finish().map_err(|e| e.to_string())?.into_inner())
+    }
+}
+
+#[cfg(not(feature = "sample-encryption"))]
+mod crypto {
+    const UNAVAILABLE: &str = "Sample encryption requires the `sample-encryption` feature";
+
+    pub fn ensure_available() -> Result<(), String> {
+        Err(UNAVAILABLE.to_string())
+    }
+
+    pub fn random_bytes(_len: usize) -> Result<Vec<u8>, String> {
+        Err(UNAVAILABLE.to_string())
+    }
+
+    pub fn seal(_key: &[u8], _nonce: &[u8], _aad: &[u8], _plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        Err(UNAVAILABLE.to_string())
+    }
+
+    pub fn open(_key: &[u8], _nonce: &[u8], _aad: &[u8], _ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+        Err(UNAVAILABLE.to_string())
+    }
+
+    pub fn protected_zip(_entries: &[(String, &[u8])], _password: &str) -> Result<Vec<u8>, String> {
+        Err(UNAVAILABLE.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::secrets::{EnvKey, KmsKey, StaticKey};
+    #[cfg(feature = "sample-encryption")]
+    use crate::job_store::FileJobStore;
+    #[cfg(feature = "sample-encryption")]
+    use serde_json::json;
+    #[cfg(feature = "sample-encryption")]
+    use std::io::Read;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("phantom-sample-store-{}", Uuid::new_v4()))
+    }
+
+    fn test_kek() -> StaticKey {
+        StaticKey::new(vec![7u8; KEY_LEN])
+    }
+
+    #[test]
+    fn envelopes_round_trip_and_plaintext_passes_through() {
+        let header = EnvelopeHeader {
+            key_id: "k1".to_string(),
+            wrapped_key: "00ff".to_string(),
+            key_nonce: "01".to_string(),
+            data_nonce: "02".to_string(),
+        };
+        let record = write_envelope(&header, b"ciphertext").unwrap();
+        let (parsed, body) = read_envelope(&record).unwrap().unwrap();
+        assert_eq!((parsed.key_id.as_str(), parsed.wrapped_key.as_str()), ("k1", "00ff"));
+        assert_eq!(body, b"ciphertext");
+
+        assert!(read_envelope(b"MZ\x90\x00 plaintext sample").unwrap().is_none());
+        assert!(read_envelope(&record[..12]).is_err());
+
+        assert_eq!(from_hex(&to_hex(&[0, 1, 0xab, 0xff])).unwrap(), vec![0, 1, 0xab, 0xff]);
+        assert!(from_hex("abc").is_err());
+        assert!(decode_key(&to_hex(&[1u8; 16])).is_err());
+    }
+
+    #[test]
+    fn kek_providers_reject_missing_and_malformed_keys() {
+        let var = format!("PHANTOM_SANDBOX_TEST_KEK_{}", Uuid::new_v4().simple());
+        let error = EnvKey::new(var.clone()).key().unwrap_err();
+        assert!(error.contains("is not set"));
+
+        let kms = KmsKey::new("kms:sample-kek", || Ok(vec![3u8; 8]));
+        assert_eq!(kms.name(), "kms:sample-kek");
+        let path = temp_dir().join("keyring.json");
+        assert!(Keyring::open(&path, &kms).err().unwrap().contains("32-byte"));
+        assert!(Keyring::open(&path, &EnvKey::new(var)).is_err());
+        // Nothing is written without a usable KEK
+        assert!(!path.exists());
+    }
+
+    #[cfg(not(feature = "sample-encryption"))]
+    #[tokio::test]
+    async fn encryption_fails_closed_without_the_feature() {
+        let dir = temp_dir();
+        let inner: Arc<dyn JobStore> = Arc::new(crate::job_store::MemoryJobStore::default());
+        let error = EncryptedSampleStore::open(inner, dir.join("keyring.json"), &test_kek()).err().unwrap();
+        assert!(error.contains("sample-encryption"));
+        assert!(!dir.join("keyring.json").exists());
+
+        let core = SandboxCore::new().unwrap();
+        assert!(!core.get_sample_encryption_status().unwrap().enabled);
+        assert!(core.rotate_sample_master_key().is_err());
+    }
+
+    #[cfg(feature = "sample-encryption")]
+    #[test]
+    fn samples_are_encrypted_and_survive_rotation() {
+        let dir = temp_dir();
+        let inner: Arc<dyn JobStore> = Arc::new(FileJobStore::open(dir.join("queue")).unwrap());
+        inner.save_sample("legacy", b"plaintext from before encryption").unwrap();
+
+        let store = EncryptedSampleStore::open(inner.clone(), dir.join("keyring.json"), &test_kek()).unwrap();
+        let sample = b"MZ\x90\x00 malicious payload".to_vec();
+        store.save_sample("s1", &sample).unwrap();
+
+        let raw = inner.load_sample("s1").unwrap().unwrap();
+        assert!(raw.starts_with(ENVELOPE_MAGIC));
+        assert!(!raw.windows(9).any(|w| w == b"malicious"));
+        assert_eq!(store.load_sample("s1").unwrap().unwrap(), sample);
+
+        let report = store.rotate_master_key().unwrap();
+        assert_eq!(report.rewrapped, 1);
+        assert_eq!(report.migrated, 1);
+        assert_eq!(report.retired_keys, vec![report.previous_key_id.clone()]);
+
+        // A fresh process only holds the rotated keyring
+        let reopened = EncryptedSampleStore::open(inner.clone(), dir.join("keyring.json"), &test_kek()).unwrap();
+        assert_eq!(reopened.status().unwrap().master_keys.len(), 1);
+        assert_eq!(reopened.load_sample("s1").unwrap().unwrap(), sample);
+        assert_eq!(reopened.load_sample("legacy").unwrap().unwrap(), b"plaintext from before encryption");
+
+        // Tampered ciphertext is rejected rather than returned
+        let mut tampered = inner.load_sample("s1").unwrap().unwrap();
+        *tampered.last_mut().unwrap() ^= 0xff;
+        inner.save_sample("s1", &tampered).unwrap();
+        assert!(reopened.load_sample("s1").is_err());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[cfg(feature = "sample-encryption")]
+    #[test]
+    fn keyring_holds_master_keys_sealed_under_the_kek() {
+        let dir = temp_dir();
+        let path = dir.join("keyring.json");
+        let keyring = Keyring::open(&path, &test_kek()).unwrap();
+        let active = keyring.active_key_id().to_string();
+        let master = to_hex(&keyring.key(&active).unwrap());
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(!written.contains(&master));
+
+        assert_eq!(Keyring::open(&path, &test_kek()).unwrap().key(&active).unwrap(), keyring.key(&active).unwrap());
+        let wrong = StaticKey::new(vec![9u8; KEY_LEN]);
+        assert!(Keyring::open(&path, &wrong).err().unwrap().contains("cannot be unsealed with static"));
+
+        // Keyrings written before sealing are sealed on open
+        let legacy = dir.join("legacy.json");
+        let file = json!({
+            "active_key_id": "old",
+            "keys": [{ "key_id": "old", "key": master, "created_at": Utc::now() }],
+        });
+        fs::write(&legacy, serde_json::to_vec(&file).unwrap()).unwrap();
+        let migrated = Keyring::open(&legacy, &test_kek()).unwrap();
+        assert_eq!(to_hex(&migrated.key("old").unwrap()), master);
+        let written = fs::read_to_string(&legacy).unwrap();
+        assert!(!written.contains(&master));
+        assert!(written.contains("wrapped_key"));
+        assert_eq!(Keyring::open(&legacy, &test_kek()).unwrap().key("old").unwrap(), migrated.key("old").unwrap());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+
+    #[cfg(feature = "sample-encryption")]
+    #[tokio::test]
+    async fn export_sample_produces_password_protected_zip() {
+        let dir = temp_dir();
+        let core = SandboxCore::with_encrypted_job_store(
+            Arc::new(FileJobStore::open(dir.join("queue")).unwrap()),
+            dir.join("keyring.json").to_str().unwrap(),
+            &test_kek(),
+        )
+        .unwrap();
+        let data = b"sample bytes for export".to_vec();
+        let sample_id = core
+            .submit_sample("default", &data, "dropper.exe".to_string(), crate::AnalysisPriority::Normal, vec![], false)
+            .await
+            .unwrap();
+        core.sample_data.write().await.clear();
+
+        let exported = core.export_sample("default", &sample_id, None).await.unwrap();
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(exported)).unwrap();
+        let sha256 = format!("{:x}", Sha256::digest(&data));
+        assert!(archive.by_name(&sha256).is_err());
+
+        let mut contents = Vec::new();
+        archive
+            .by_name_decrypt(&sha256, DEFAULT_EXPORT_PASSWORD.as_bytes())
+            .unwrap()
+            .read_to_end(&mut contents)
+            .unwrap();
+        assert_eq!(contents, data);
+        assert!(core.export_sample("default", &sample_id, Some("")).await.is_err());
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
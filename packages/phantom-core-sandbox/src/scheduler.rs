@@ -0,0 +1,421 @@
+//! Resource-aware analysis scheduling
+//!
+//! Local analyses run concurrently while they fit the host: each job
+//! reserves its VM environment's CPU cores and memory against the configured
+//! budget, plus one of that environment's VM slots, and gives them back when
+//! it finishes. Queued jobs are placed in the order the analysis queue hands
+//! them out, with smaller jobs filling room a larger one cannot use. A
+//! queued Emergency job that does not fit preempts running jobs of lower
+//! priority, least important and most recently started first; they go back
+//! to the queue and start over later.
+
+use chrono::{DateTime, Utc};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::watch;
+
+use crate::queue::AnalysisQueue;
+use crate::{AnalysisJob, AnalysisPriority, JobStatus, ResourceLimits, SandboxCore, SandboxCoreNapi};
+
+/// Host budget shared by concurrently running analyses
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchedulerConfig {
+    pub cpu_cores: u32,
+    pub memory_mb: u32,
+    /// Concurrent VMs per environment; environments not listed get `default_slots`
+    #[serde(default)]
+    pub environment_slots: HashMap<String, u32>,
+    #[serde(default = "default_slots")]
+    pub default_slots: u32,
+    /// Let Emergency jobs preempt running jobs of lower priority
+    #[serde(default = "default_preemption")]
+    pub preemption: bool,
+}
+
+fn default_slots() -> u32 {
+    2
+}
+
+fn default_preemption() -> bool {
+    true
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            cpu_cores: 8,
+            memory_mb: 16384,
+            environment_slots: HashMap::new(),
+            default_slots: default_slots(),
+            preemption: default_preemption(),
+        }
+    }
+}
+
+impl SchedulerConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.cpu_cores == 0 || self.memory_mb == 0 {
+            return Err("Scheduler budget needs at least one CPU core and some memory".to_string());
+        }
+        if self.default_slots == 0 {
+            return Err("default_slots must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    fn slots(&self, environment: &str) -> u32 {
+        self.environment_slots.get(environment).copied().unwrap_or(self.default_slots)
+    }
+}
+
+/// What one job holds while it runs
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Demand {
+    pub environment: String,
+    pub cpu_cores: u32,
+    pub memory_mb: u32,
+    pub rank: u32,
+}
+
+impl Demand {
+    /// Jobs for an environment that has been removed reserve a single core
+    pub(crate) fn for_job(job: &AnalysisJob, limits: Option<&ResourceLimits>) -> Self {
+        Self {
+            environment: job.vm_environment.clone(),
+            cpu_cores: limits.map_or(1, |limits| limits.cpu_cores),
+            memory_mb: limits.map_or(0, |limits| limits.memory_mb),
+            rank: job.priority.rank(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Reservation {
+    token: u64,
+    demand: Demand,
+    started_at: DateTime<Utc>,
+    preempt: watch::Sender<bool>,
+}
+
+/// VM slots of one environment
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SlotUtilization {
+    pub environment_id: String,
+    pub slots: u32,
+    pub in_use: u32,
+}
+
+/// Reserved share of the scheduler budget
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SchedulerUtilization {
+    pub cpu_cores_budget: u32,
+    pub cpu_cores_reserved: u32,
+    pub memory_mb_budget: u32,
+    pub memory_mb_reserved: u32,
+    pub running_jobs: usize,
+    /// Jobs sent back to the queue for Emergency jobs since startup
+    pub preemptions: u64,
+    pub environments: Vec<SlotUtilization>,
+}
+
+impl SchedulerUtilization {
+    /// Share of VM slots in use across environments
+    pub fn slot_share(&self) -> f64 {
+        let slots: u32 = self.environments.iter().map(|e| e.slots).sum();
+        let in_use: u32 = self.environments.iter().map(|e| e.in_use).sum();
+        if slots == 0 {
+            0.0
+        } else {
+            f64::from(in_use) / f64::from(slots)
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SchedulerState {
+    config: RwLock<SchedulerConfig>,
+    running: Mutex<HashMap<String, Reservation>>,
+    next_token: AtomicU64,
+    preemptions: AtomicU64,
+}
+
+/// Reservation of a running job; dropping it frees the job's resources
+pub(crate) struct SlotGuard {
+    state: Arc<SchedulerState>,
+    job_id: String,
+    token: u64,
+    preempted: watch::Receiver<bool>,
+}
+
+impl SlotGuard {
+    /// Resolves once an Emergency job has taken this job's resources
+    pub(crate) async fn preempted(&mut self) {
+        // The sender only goes away with the reservation, so without a
+        // preemption this never resolves
+        if self.preempted.wait_for(|preempted| *preempted).await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    }
+}
+
+impl Drop for SlotGuard {
+    fn drop(&mut self) {
+        let mut running = self.state.running.lock().unwrap_or_else(|e| e.into_inner());
+        // A preempted job may already be running again under a new reservation
+        if running.get(&self.job_id).is_some_and(|reservation| reservation.token == self.token) {
+            running.remove(&self.job_id);
+        }
+    }
+}
+
+impl SchedulerState {
+    pub(crate) fn config(&self) -> SchedulerConfig {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn fits(config: &SchedulerConfig, running: &[&Reservation], demand: &Demand) -> bool {
+        let cpu: u32 = running.iter().map(|r| r.demand.cpu_cores).sum();
+        let memory: u32 = running.iter().map(|r| r.demand.memory_mb).sum();
+        let in_environment = running.iter().filter(|r| r.demand.environment == demand.environment).count() as u32;
+        // A job larger than the whole budget still runs, alone
+        let within_budget = running.is_empty() || (cpu + demand.cpu_cores <= config.cpu_cores && memory + demand.memory_mb <= config.memory_mb);
+        within_budget && in_environment < config.slots(&demand.environment)
+    }
+
+    fn insert(self: &Arc<Self>, running: &mut HashMap<String, Reservation>, job_id: &str, demand: Demand) -> SlotGuard {
+        let token = self.next_token.fetch_add(1, Ordering::SeqCst);
+        let (preempt, preempted) = watch::channel(false);
+        running.insert(job_id.to_string(), Reservation { token, demand, started_at: Utc::now(), preempt });
+        SlotGuard { state: Arc::clone(self), job_id: job_id.to_string(), token, preempted }
+    }
+
+    /// Reserve room for a job if the budget and its environment's slots allow
+    pub(crate) fn reserve(self: &Arc<Self>, job_id: &str, demand: Demand) -> Option<SlotGuard> {
+        let config = self.config();
+        let mut running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+        if running.contains_key(job_id) || !Self::fits(&config, &running.values().collect::<Vec<_>>(), &demand) {
+            return None;
+        }
+        Some(self.insert(&mut running, job_id, demand))
+    }
+
+    /// Make room for an Emergency job by preempting lower-priority jobs.
+    /// Returns the reservation and the preempted job ids, or `None` without
+    /// preempting anything when even that would not make the job fit.
+    pub(crate) fn preempt_for(self: &Arc<Self>, job_id: &str, demand: Demand) -> Option<(SlotGuard, Vec<String>)> {
+        let config = self.config();
+        if !config.preemption {
+            return None;
+        }
+        let mut running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+        let mut candidates: Vec<(&String, &Reservation)> = running.iter().filter(|(_, r)| r.demand.rank < demand.rank).collect();
+        candidates.sort_by(|(_, a), (_, b)| a.demand.rank.cmp(&b.demand.rank).then(b.started_at.cmp(&a.started_at)));
+
+        let mut victims: Vec<String> = Vec::new();
+        let remaining = |victims: &[String]| running.iter().filter(|(id, _)| !victims.contains(*id)).map(|(_, r)| r).collect::<Vec<_>>();
+        for (victim, _) in candidates {
+            if Self::fits(&config, &remaining(&victims), &demand) {
+                break;
+            }
+            victims.push(victim.clone());
+        }
+        if !Self::fits(&config, &remaining(&victims), &demand) {
+            return None;
+        }
+
+        for victim in &victims {
+            if let Some(reservation) = running.remove(victim) {
+                let _ = reservation.preempt.send(true);
+                self.preemptions.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        Some((self.insert(&mut running, job_id, demand), victims))
+    }
+
+    /// Reservations against the budget, with slots for `environments`
+    pub(crate) fn utilization<'a>(&self, environments: impl IntoIterator<Item = &'a String>) -> SchedulerUtilization {
+        let config = self.config();
+        let running = self.running.lock().unwrap_or_else(|e| e.into_inner());
+        let mut slots: BTreeMap<String, SlotUtilization> = environments
+            .into_iter()
+            .map(|id| (id.clone(), SlotUtilization { environment_id: id.clone(), slots: config.slots(id), in_use: 0 }))
+            .collect();
+        for reservation in running.values() {
+            let environment = &reservation.demand.environment;
+            slots
+                .entry(environment.clone())
+                .or_insert_with(|| SlotUtilization { environment_id: environment.clone(), slots: config.slots(environment), in_use: 0 })
+                .in_use += 1;
+        }
+        SchedulerUtilization {
+            cpu_cores_budget: config.cpu_cores,
+            cpu_cores_reserved: running.values().map(|r| r.demand.cpu_cores).sum(),
+            memory_mb_budget: config.memory_mb,
+            memory_mb_reserved: running.values().map(|r| r.demand.memory_mb).sum(),
+            running_jobs: running.len(),
+            preemptions: self.preemptions.load(Ordering::SeqCst),
+            environments: slots.into_values().collect(),
+        }
+    }
+}
+
+impl SandboxCore {
+    /// Set the CPU, memory and VM slot budget of local analyses. Running
+    /// jobs keep their reservations; the new budget applies to placement
+    pub fn configure_scheduler(&self, config: SchedulerConfig) -> Result<(), String> {
+        config.validate()?;
+        *self.scheduler.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+        Ok(())
+    }
+
+    pub fn scheduler_config(&self) -> SchedulerConfig {
+        self.scheduler.config()
+    }
+
+    pub async fn scheduler_utilization(&self) -> SchedulerUtilization {
+        let environments = self.vm_environments.read().await;
+        self.scheduler.utilization(environments.keys())
+    }
+
+    /// Reserve room for queued jobs in the queue's order, preempting for
+    /// Emergency jobs, and mark the placed jobs as started
+    pub(crate) async fn place_queued_jobs(&self, queue: &mut AnalysisQueue) -> Result<Vec<(AnalysisJob, SlotGuard)>, String> {
+        let environments = self.vm_environments.read().await;
+        let now = Utc::now();
+        let mut placed = Vec::new();
+        let mut waiting = Vec::new();
+        let mut requeue = Vec::new();
+        let mut failure = None;
+        while let Some(job_id) = queue.pop(now) {
+            let Some(job) = queue.get_mut(&job_id) else { continue };
+            let demand = Demand::for_job(job, environments.get(&job.vm_environment).map(|env| &env.resource_limits));
+            let guard = match self.scheduler.reserve(&job.job_id, demand.clone()) {
+                Some(guard) => guard,
+                None if matches!(job.priority, AnalysisPriority::Emergency) => match self.scheduler.preempt_for(&job.job_id, demand) {
+                    Some((guard, victims)) => {
+                        log::info!("Emergency job {} preempted {}", job.job_id, victims.join(", "));
+                        requeue.extend(victims);
+                        guard
+                    }
+                    None => {
+                        waiting.push(job_id);
+                        continue;
+                    }
+                },
+                None => {
+                    waiting.push(job_id);
+                    continue;
+                }
+            };
+            job.status = JobStatus::PreProcessing;
+            job.analysis_start = Some(now);
+            if let Err(e) = self.persist_job(job) {
+                failure = Some(e.reason);
+                break;
+            }
+            placed.push((job.clone(), guard));
+        }
+
+        // Jobs without room keep their place; preempted jobs start over once there is room again
+        for job_id in &waiting {
+            queue.restore(job_id);
+        }
+        for job_id in &requeue {
+            if let Some(job) = queue.requeue(job_id) {
+                self.persist_job(job).map_err(|e| e.reason)?;
+            }
+        }
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(placed),
+        }
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// Set the CPU, memory and per-environment VM slot budget of concurrent analyses
+    #[napi]
+    pub fn configure_scheduler(&self, config_json: String, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize_platform(auth_token, "scheduler")?;
+        let config: SchedulerConfig = serde_json::from_str(&config_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse scheduler config: {}", e)))?;
+
+        let result = self.inner.configure_scheduler(config);
+        self.audit.record(&actor, "configure_scheduler", "scheduler", serde_json::json!({ "config": config_json }), result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to configure scheduler: {}", e)))
+    }
+
+    #[napi]
+    pub fn get_scheduler_config(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.scheduler_config())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize scheduler config: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn demand(environment: &str, cpu_cores: u32, priority: AnalysisPriority) -> Demand {
+        Demand { environment: environment.to_string(), cpu_cores, memory_mb: 1024, rank: priority.rank() }
+    }
+
+    #[tokio::test]
+    async fn emergency_jobs_preempt_the_least_important_work() {
+        let state = Arc::new(SchedulerState::default());
+        *state.config.write().unwrap() = SchedulerConfig {
+            cpu_cores: 4,
+            environment_slots: HashMap::from([("ubuntu20-x64".to_string(), 1)]),
+            ..Default::default()
+        };
+
+        let mut low = state.reserve("low", demand("win10-x64", 2, AnalysisPriority::Low)).unwrap();
+        let _normal = state.reserve("normal", demand("win11-x64", 2, AnalysisPriority::Normal)).unwrap();
+        let urgent = demand("ubuntu20-x64", 2, AnalysisPriority::Emergency);
+        assert!(state.reserve("urgent", urgent.clone()).is_none());
+        let (_urgent, victims) = state.preempt_for("urgent", urgent).unwrap();
+        assert_eq!(victims, ["low"]);
+        tokio::time::timeout(std::time::Duration::from_secs(1), low.preempted()).await.unwrap();
+        // Preempting the other job would not free the only Ubuntu slot, so nothing is preempted
+        assert!(state.preempt_for("second", demand("ubuntu20-x64", 1, AnalysisPriority::Emergency)).is_none());
+
+        // The requeued job runs again under a new reservation the old guard must not free
+        assert!(state.reserve("low", demand("win10-x64", 2, AnalysisPriority::Low)).is_none());
+        drop(low);
+        let usage = state.utilization(&["win10-x64".to_string()]);
+        assert_eq!((usage.cpu_cores_reserved, usage.running_jobs, usage.preemptions), (4, 2, 1));
+        assert_eq!(usage.environments.iter().find(|e| e.environment_id == "win10-x64").unwrap().in_use, 0);
+    }
+
+    #[tokio::test]
+    async fn process_queue_runs_what_fits_the_budget() {
+        let core = SandboxCore::new().unwrap();
+        // Room for two 2-core Windows 10 guests
+        core.configure_scheduler(SchedulerConfig { cpu_cores: 4, memory_mb: 8192, ..Default::default() }).unwrap();
+        let mut samples = Vec::new();
+        for index in 0..3 {
+            let name = format!("batch{}.exe", index);
+            samples.push(core.submit_sample("acme", format!("MZ {}", name).as_bytes(), name, AnalysisPriority::Normal, vec![], false).await.unwrap());
+        }
+
+        core.process_queue().await.unwrap();
+        let mut done = 0;
+        for sample_id in &samples {
+            done += usize::from(core.get_analysis("acme", sample_id).await.unwrap().is_some());
+        }
+        assert_eq!(done, 2);
+        assert_eq!(core.scheduler_utilization().await.running_jobs, 0);
+
+        core.process_queue().await.unwrap();
+        for sample_id in &samples {
+            assert!(core.get_analysis("acme", sample_id).await.unwrap().is_some());
+        }
+        let metrics = core.get_performance_metrics("acme").await.unwrap();
+        assert_eq!(metrics.scheduler.cpu_cores_budget, 4);
+        assert!(metrics.scheduler.environments.iter().any(|e| e.environment_id == "win10-x64" && e.slots == 2));
+    }
+}
@@ -0,0 +1,107 @@
+//! Connector secrets
+//!
+//! Credentials for connectors are kept in the shared `SecretStore` from
+//! phantom-enterprise-standards, sealed under a master key from the
+//! `PHANTOM_SANDBOX_MASTER_KEY` environment variable (64 hex digits) unless the
+//! host rotates in one of its own. This key is separate from the sample
+//! keyring's key-encryption key.
+//!
+//! Connector configs refer to secrets as `secret://name`; the MISP API key is
+//! kept as a reference and resolved each time the sandbox talks to MISP, so
+//! it never sits in the config in the clear.
+
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::error::CoreError;
+use crate::{SandboxCore, SandboxCoreNapi};
+
+pub use phantom_enterprise_standards::secrets::{
+    redact, redacted, reference_name, EnvKey, KeyProvider, KmsKey, SealedSecret, SecretError, SecretMetadata, SecretStore,
+    SecretStoreStatus, StaticKey, SECRET_SCHEME,
+};
+
+/// Environment variable holding the default master key, as 64 hex digits
+pub const MASTER_KEY_ENV: &str = "PHANTOM_SANDBOX_MASTER_KEY";
+
+impl SandboxCore {
+    pub fn secrets(&self) -> &SecretStore {
+        &self.secrets
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// Store a connector secret; configs refer to it as `secret://<name>`
+    #[napi]
+    pub fn put_secret(&self, name: String, value: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, &name)?;
+        let metadata = self.inner.secrets().put(&name, &value).map_err(CoreError::from);
+        let metadata = self.audit.record(&actor, "put_secret", &name, json!({}), metadata)
+            .map_err(|e| e.context("Failed to store secret"))?;
+        serde_json::to_string(&metadata)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize secret metadata: {}", e)))
+    }
+
+    /// Replace the value of an existing secret
+    #[napi]
+    pub fn rotate_secret(&self, name: String, value: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, &name)?;
+        let metadata = self.inner.secrets().rotate(&name, &value).map_err(CoreError::from);
+        let metadata = self.audit.record(&actor, "rotate_secret", &name, json!({}), metadata)
+            .map_err(|e| e.context("Failed to rotate secret"))?;
+        serde_json::to_string(&metadata)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize secret metadata: {}", e)))
+    }
+
+    #[napi]
+    pub fn remove_secret(&self, name: String, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize_platform(auth_token, &name)?;
+        let outcome = self.inner.secrets().remove(&name).map_err(CoreError::from);
+        self.audit.record(&actor, "remove_secret", &name, json!({}), outcome)
+            .map_err(|e| e.context("Failed to remove secret"))?;
+        Ok(())
+    }
+
+    /// Secret names and versions; values are never returned
+    #[napi]
+    pub fn list_secrets(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize_platform(auth_token, "secrets")?;
+        serde_json::to_string(&self.inner.secrets().list())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize secrets: {}", e)))
+    }
+
+    /// Re-seal every connector secret under a new 32-byte master key, e.g. one
+    /// the host unwrapped through its KMS. The sample keyring is rotated
+    /// separately with `rotate_sample_master_key`.
+    #[napi]
+    pub fn rotate_secrets_master_key(&self, master_key: Buffer, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, "secrets")?;
+        let status = self.inner.secrets().rotate_master_key(Arc::new(StaticKey::new(master_key.to_vec()))).map_err(CoreError::from);
+        let status = self.audit.record(&actor, "rotate_secrets_master_key", "secrets", json!({}), status)
+            .map_err(|e| e.context("Failed to rotate master key"))?;
+        serde_json::to_string(&status)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize secret store status: {}", e)))
+    }
+
+    /// Sealed secrets for the host to persist
+    #[napi]
+    pub fn export_sealed_secrets(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize_platform(auth_token, "secrets")?;
+        serde_json::to_string(&self.inner.secrets().export_sealed())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize sealed secrets: {}", e)))
+    }
+
+    #[napi]
+    pub fn import_sealed_secrets(&self, sealed_json: String, auth_token: Option<String>) -> napi::Result<u32> {
+        let actor = self.authorize_platform(auth_token, "secrets")?;
+        let records: Vec<SealedSecret> = serde_json::from_str(&sealed_json)
+            .map_err(|e| CoreError::from(e).context("Failed to parse sealed secrets"))?;
+        let imported = self.inner.secrets().import_sealed(records).map_err(CoreError::from);
+        let imported = self.audit.record(&actor, "import_sealed_secrets", "secrets", json!({}), imported)
+            .map_err(|e| e.context("Failed to import sealed secrets"))?;
+        Ok(imported as u32)
+    }
+}
@@ -0,0 +1,843 @@
+//! Parallel static analysis pipeline
+//!
+//! Static analysis is split into independent stages (strings, entropy, PE
+//! parsing, YARA) that only read the sample buffer. Stages are dispatched onto
+//! a shared work-stealing rayon pool whose thread count is the global limit;
+//! a per-sample semaphore bounds how many stages of one sample may occupy the
+//! pool at once so a single large sample cannot starve the rest. Results that
+//! depend on several stages (packer verdict, anti-analysis features, section
+//! entropies) are assembled once all stages have reported.
+
+use crate::packers;
+use crate::string_packs;
+use crate::{
+    AntiAnalysisFeature, EntropyAnalysis, FileMetadata, HighEntropyRegion, PEAnalysis, PEAnomaly, PESection,
+    SignatureVerification, StaticAnalysis, StringsAnalysis, SuspiciousString, YARAMatch,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+use tokio::sync::{oneshot, Semaphore};
+
+/// Shortest printable run reported by the strings stage
+const MIN_STRING_LENGTH: usize = 4;
+/// Cap on each extracted string category to bound result size
+const MAX_STRINGS_PER_CATEGORY: usize = 500;
+/// Window used to locate high-entropy regions
+const ENTROPY_WINDOW: usize = 4096;
+/// Distance the entropy window slides between measurements
+const ENTROPY_STEP: usize = 1024;
+const HIGH_ENTROPY_THRESHOLD: f64 = 7.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum StaticStage {
+    Strings,
+    Entropy,
+    PeParse,
+    Yara,
+}
+
+impl StaticStage {
+    pub const ALL: [StaticStage; 4] = [StaticStage::Strings, StaticStage::Entropy, StaticStage::PeParse, StaticStage::Yara];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StaticStage::Strings => "strings",
+            StaticStage::Entropy => "entropy",
+            StaticStage::PeParse => "pe_parse",
+            StaticStage::Yara => "yara",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StaticPipelineConfig {
+    /// Worker threads shared by every sample (global limit)
+    pub max_global_workers: usize,
+    /// Stages of a single sample allowed to run at once; 1 runs stages sequentially
+    pub max_parallel_stages_per_sample: usize,
+    pub enabled_stages: Vec<StaticStage>,
+}
+
+impl Default for StaticPipelineConfig {
+    fn default() -> Self {
+        Self {
+            max_global_workers: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4),
+            max_parallel_stages_per_sample: StaticStage::ALL.len(),
+            enabled_stages: StaticStage::ALL.to_vec(),
+        }
+    }
+}
+
+/// Wall-clock timings for one pipeline run, in microseconds
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StaticPipelineTimings {
+    pub total_us: u64,
+    pub stage_us: HashMap<String, u64>,
+}
+
+/// Scanner invoked by the YARA stage
+pub type YaraScanner = Arc<dyn Fn(&[u8]) -> Vec<YARAMatch> + Send + Sync>;
+
+/// Classifier the strings stage applies to every extracted string and its encoding
+pub type StringClassifier = Arc<dyn Fn(&str, &str) -> Option<SuspiciousString> + Send + Sync>;
+
+enum StageOutput {
+    Strings(StringsAnalysis),
+    Entropy(EntropyAnalysis),
+    PeParse(Option<PEAnalysis>, SignatureVerification),
+    Yara(Vec<YARAMatch>),
+}
+
+pub struct StaticPipeline {
+    config: StaticPipelineConfig,
+    pool: rayon::ThreadPool,
+    yara_scanner: Option<YaraScanner>,
+    string_classifier: Option<StringClassifier>,
+}
+
+impl StaticPipeline {
+    pub fn new(config: StaticPipelineConfig) -> Result<Self, String> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(config.max_global_workers.max(1))
+            .thread_name(|i| format!("phantom-static-{}", i))
+            .build()
+            .map_err(|e| format!("Failed to build static analysis pool: {}", e))?;
+        Ok(Self { config, pool, yara_scanner: None, string_classifier: None })
+    }
+
+    pub fn with_yara_scanner(mut self, scanner: YaraScanner) -> Self {
+        self.yara_scanner = Some(scanner);
+        self
+    }
+
+    pub fn with_string_classifier(mut self, classifier: StringClassifier) -> Self {
+        self.string_classifier = Some(classifier);
+        self
+    }
+
+    pub fn config(&self) -> &StaticPipelineConfig {
+        &self.config
+    }
+
+    /// Run every enabled stage over `data` and assemble the static analysis
+    pub async fn run(&self, data: Arc<Vec<u8>>, file_type: &str, mime_type: &str) -> (StaticAnalysis, StaticPipelineTimings) {
+        let started = Instant::now();
+        let permits = Arc::new(Semaphore::new(self.config.max_parallel_stages_per_sample.max(1)));
+        let mut pending = Vec::with_capacity(self.config.enabled_stages.len());
+
+        for &stage in &self.config.enabled_stages {
+            let permit = permits.clone().acquire_owned().await.expect("stage semaphore is never closed");
+            let (tx, rx) = oneshot::channel();
+            let data = data.clone();
+            let yara_scanner = self.yara_scanner.clone();
+            let string_classifier = self.string_classifier.clone();
+            self.pool.spawn(move || {
+                let stage_started = Instant::now();
+                let output = run_stage(stage, &data, yara_scanner.as_ref(), string_classifier.as_ref());
+                let _ = tx.send((output, stage_started.elapsed().as_micros() as u64));
+                drop(permit);
+            });
+            pending.push((stage, rx));
+        }
+
+        let mut timings = StaticPipelineTimings::default();
+        let mut strings_analysis = None;
+        let mut entropy_analysis = None;
+        let mut pe_result = None;
+        let mut yara_matches = Vec::new();
+
+        for (stage, rx) in pending {
+            let Ok((output, elapsed)) = rx.await else {
+                log::warn!("Static stage {} did not report a result", stage.name());
+                continue;
+            };
+            timings.stage_us.insert(stage.name().to_string(), elapsed);
+            match output {
+                StageOutput::Strings(result) => strings_analysis = Some(result),
+                StageOutput::Entropy(result) => entropy_analysis = Some(result),
+                StageOutput::PeParse(pe, signature) => pe_result = Some((pe, signature)),
+                StageOutput::Yara(matches) => yara_matches = matches,
+            }
+        }
+
+        let (pe_analysis, signature_verification) = pe_result.unwrap_or_else(|| (None, unsigned()));
+        let analysis = assemble(
+            &data,
+            file_type,
+            mime_type,
+            strings_analysis.unwrap_or_else(|| extract_strings(&[])),
+            entropy_analysis.unwrap_or_else(|| analyze_entropy(&[])),
+            pe_analysis,
+            signature_verification,
+            yara_matches,
+        );
+        timings.total_us = started.elapsed().as_micros() as u64;
+        (analysis, timings)
+    }
+}
+
+fn run_stage(
+    stage: StaticStage,
+    data: &[u8],
+    yara_scanner: Option<&YaraScanner>,
+    string_classifier: Option<&StringClassifier>,
+) -> StageOutput {
+    match stage {
+        StaticStage::Strings => StageOutput::Strings(classify_strings(data, string_classifier)),
+        StaticStage::Entropy => StageOutput::Entropy(analyze_entropy(data)),
+        StaticStage::PeParse => {
+            let pe = parse_pe(data);
+            let signature = pe.as_ref().map(|(_, sig)| sig.clone()).unwrap_or_else(unsigned);
+            StageOutput::PeParse(pe.map(|(analysis, _)| analysis), signature)
+        }
+        StaticStage::Yara => StageOutput::Yara(yara_scanner.map(|scan| scan(data)).unwrap_or_default()),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn assemble(
+    data: &[u8],
+    file_type: &str,
+    mime_type: &str,
+    strings_analysis: StringsAnalysis,
+    mut entropy_analysis: EntropyAnalysis,
+    pe_analysis: Option<PEAnalysis>,
+    signature_verification: SignatureVerification,
+    yara_matches: Vec<YARAMatch>,
+) -> StaticAnalysis {
+    if let Some(pe) = &pe_analysis {
+        for section in &pe.sections {
+            entropy_analysis.section_entropies.insert(section.name.clone(), section.entropy);
+        }
+    }
+
+    // A packed stub keeps overall entropy moderate, so a compressed
+    // executable section raises the packing probability on its own
+    if let Some(pe) = &pe_analysis {
+        let section_probability = pe
+            .sections
+            .iter()
+            .filter(|section| section.characteristics.iter().any(|c| c == "EXECUTE"))
+            .map(|section| ((section.entropy - 6.0) / 2.0).clamp(0.0, 1.0))
+            .fold(0.0, f64::max);
+        entropy_analysis.packing_probability = entropy_analysis.packing_probability.max(section_probability);
+    }
+
+    let packer_detection = packers::detect_packer(data, pe_analysis.as_ref(), &entropy_analysis);
+    entropy_analysis.is_packed |= packer_detection.is_packed;
+
+    StaticAnalysis {
+        file_metadata: FileMetadata {
+            file_type: file_type.to_string(),
+            mime_type: mime_type.to_string(),
+            file_size: data.len() as u64,
+            creation_time: None,
+            modification_time: None,
+            magic_bytes: data.iter().take(4).map(|b| format!("{:02X}", b)).collect(),
+            file_structure: pe_analysis
+                .as_ref()
+                .map(|pe| format!("PE {} executable", pe.architecture))
+                .unwrap_or_else(|| file_type.to_string()),
+        },
+        anti_analysis_features: anti_analysis_features(&strings_analysis),
+        pe_analysis,
+        strings_analysis,
+        entropy_analysis,
+        packer_detection,
+        yara_matches,
+        signature_verification,
+        antivirus: None,
+    }
+}
+
+// Strings Stage
+
+pub(crate) const SUSPICIOUS_APIS: &[(&str, &str)] = &[
+    ("VirtualAlloc", "Memory Manipulation"),
+    ("VirtualAllocEx", "Process Injection"),
+    ("VirtualProtect", "Memory Manipulation"),
+    ("WriteProcessMemory", "Process Injection"),
+    ("ReadProcessMemory", "Process Injection"),
+    ("CreateRemoteThread", "Process Injection"),
+    ("NtUnmapViewOfSection", "Process Hollowing"),
+    ("SetWindowsHookEx", "Keylogging"),
+    ("GetAsyncKeyState", "Keylogging"),
+    ("IsDebuggerPresent", "Anti-Debugging"),
+    ("CheckRemoteDebuggerPresent", "Anti-Debugging"),
+    ("NtQueryInformationProcess", "Anti-Debugging"),
+    ("URLDownloadToFile", "Download"),
+    ("InternetOpenUrl", "Network"),
+    ("WinExec", "Execution"),
+    ("ShellExecute", "Execution"),
+    ("CryptEncrypt", "Cryptography"),
+    ("AdjustTokenPrivileges", "Privilege Escalation"),
+    ("RegSetValueEx", "Persistence"),
+];
+
+const VM_ARTIFACTS: &[&str] = &["vmware", "virtualbox", "vbox", "qemu", "sandboxie", "vmtoolsd"];
+
+fn url_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)\b(?:https?|ftp)://[^\s/$.?#][^\s\x22'<>]*").expect("valid url regex"))
+}
+
+fn ip_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\b(?:(?:25[0-5]|2[0-4]\d|1?\d?\d)\.){3}(?:25[0-5]|2[0-4]\d|1?\d?\d)\b").expect("valid ip regex"))
+}
+
+fn path_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"(?i)(?:\b[a-z]:\\|%[a-z]+%\\)[^\x00-\x1f<>|\x22*?]+").expect("valid path regex"))
+}
+
+fn registry_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| {
+        Regex::new(r"(?i)\b(?:HKEY_[A-Z_]+|HKLM|HKCU|HKCR|HKU)\\[^\x00-\x1f]+|\bSoftware\\Microsoft\\Windows\\CurrentVersion\\Run[^\x00-\x1f]*")
+            .expect("valid registry regex")
+    })
+}
+
+/// Extract printable ASCII and UTF-16LE runs and classify them
+pub fn extract_strings(data: &[u8]) -> StringsAnalysis {
+    classify_strings(data, None)
+}
+
+/// Extract strings as `extract_strings` does, also classifying each one with
+/// `classifier` (the pattern packs)
+pub fn classify_strings(data: &[u8], classifier: Option<&StringClassifier>) -> StringsAnalysis {
+    let ascii = ascii_runs(data);
+    let unicode = utf16le_runs(data);
+
+    let mut urls = BTreeSet::new();
+    let mut ip_addresses = BTreeSet::new();
+    let mut file_paths = BTreeSet::new();
+    let mut registry_keys = BTreeSet::new();
+    let mut api_functions = BTreeSet::new();
+    let mut suspicious = HashMap::new();
+
+    let tagged = ascii.iter().map(|s| (s, "ASCII")).chain(unicode.iter().map(|s| (s, "UTF-16LE")));
+    for (value, encoding) in tagged {
+        collect_matches(url_regex(), value, &mut urls);
+        collect_matches(ip_regex(), value, &mut ip_addresses);
+        collect_matches(path_regex(), value, &mut file_paths);
+        collect_matches(registry_regex(), value, &mut registry_keys);
+
+        for (api, category) in SUSPICIOUS_APIS {
+            if value.contains(api) && api_functions.len() < MAX_STRINGS_PER_CATEGORY {
+                api_functions.insert(api.to_string());
+                suspicious.entry(api.to_string()).or_insert_with(|| SuspiciousString {
+                    string_value: api.to_string(),
+                    category: category.to_string(),
+                    description: format!("Reference to {} API", category.to_lowercase()),
+                    threat_score: 0.6,
+                    encoding: encoding.to_string(),
+                    pattern_pack: None,
+                });
+            }
+        }
+
+        let lowered = value.to_lowercase();
+        if let Some(artifact) = VM_ARTIFACTS.iter().find(|artifact| lowered.contains(*artifact)) {
+            suspicious.entry(artifact.to_string()).or_insert_with(|| SuspiciousString {
+                string_value: value.clone(),
+                category: "Anti-VM".to_string(),
+                description: "Virtualization artifact referenced".to_string(),
+                threat_score: 0.5,
+                encoding: encoding.to_string(),
+                pattern_pack: None,
+            });
+        }
+
+        if suspicious.len() < MAX_STRINGS_PER_CATEGORY {
+            if let Some(hit) = classifier.and_then(|classify| classify(value, encoding)) {
+                let existing = suspicious.entry(hit.string_value.clone()).or_insert_with(|| hit.clone());
+                if hit.threat_score > existing.threat_score {
+                    *existing = hit;
+                }
+            }
+        }
+    }
+
+    let mut suspicious_strings: Vec<SuspiciousString> = suspicious.into_values().collect();
+    suspicious_strings.sort_by(|a, b| a.string_value.cmp(&b.string_value));
+    let strongest = suspicious_strings.iter().map(|s| s.threat_score).fold(0.0, f64::max);
+    let categories: BTreeSet<&str> = suspicious_strings.iter().map(|s| s.category.as_str()).collect();
+    let threat_score = if categories.is_empty() { 0.0 } else { string_packs::corroborated(strongest, categories.len() - 1) };
+
+    StringsAnalysis {
+        total_strings: (ascii.len() + unicode.len()) as u32,
+        ascii_strings: ascii.len() as u32,
+        unicode_strings: unicode.len() as u32,
+        suspicious_strings,
+        urls: urls.into_iter().collect(),
+        ip_addresses: ip_addresses.into_iter().collect(),
+        file_paths: file_paths.into_iter().collect(),
+        registry_keys: registry_keys.into_iter().collect(),
+        api_functions: api_functions.into_iter().collect(),
+        threat_score,
+    }
+}
+
+fn collect_matches(re: &Regex, value: &str, into: &mut BTreeSet<String>) {
+    if into.len() >= MAX_STRINGS_PER_CATEGORY {
+        return;
+    }
+    for m in re.find_iter(value) {
+        into.insert(m.as_str().trim_end().to_string());
+    }
+}
+
+pub(crate) fn is_printable(byte: u8) -> bool {
+    byte == b'\t' || (0x20..0x7f).contains(&byte)
+}
+
+fn ascii_runs(data: &[u8]) -> Vec<String> {
+    data.split(|b| !is_printable(*b))
+        .filter(|run| run.len() >= MIN_STRING_LENGTH)
+        .map(|run| String::from_utf8_lossy(run).into_owned())
+        .collect()
+}
+
+fn utf16le_runs(data: &[u8]) -> Vec<String> {
+    let mut runs = Vec::new();
+    for start in 0..2.min(data.len()) {
+        let mut current = String::new();
+        for pair in data[start..].chunks_exact(2) {
+            if pair[1] == 0 && is_printable(pair[0]) {
+                current.push(pair[0] as char);
+            } else if current.len() >= MIN_STRING_LENGTH {
+                runs.push(std::mem::take(&mut current));
+            } else {
+                current.clear();
+            }
+        }
+        if current.len() >= MIN_STRING_LENGTH {
+            runs.push(current);
+        }
+    }
+    runs
+}
+
+fn anti_analysis_features(strings: &StringsAnalysis) -> Vec<AntiAnalysisFeature> {
+    strings
+        .suspicious_strings
+        .iter()
+        .filter(|s| s.category == "Anti-Debugging" || s.category == "Anti-VM")
+        .map(|s| AntiAnalysisFeature {
+            feature_type: s.category.clone(),
+            description: s.description.clone(),
+            implementation: s.string_value.clone(),
+            effectiveness: s.threat_score,
+            detection_method: "Static string analysis".to_string(),
+        })
+        .collect()
+}
+
+// Entropy Stage
+
+/// Shannon entropy in bits per byte (0.0 - 8.0)
+pub fn shannon_entropy(data: &[u8]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    let mut counts = [0u64; 256];
+    for &byte in data {
+        counts[byte as usize] += 1;
+    }
+    let len = data.len() as f64;
+    counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+fn entropy_from_counts(counts: &[u64; 256], len: usize) -> f64 {
+    if len == 0 {
+        return 0.0;
+    }
+    let len = len as f64;
+    counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Entropy of each `window`-byte span, sliding `step` bytes at a time.
+///
+/// Returns `(offset, entropy)` pairs; the histogram is updated incrementally
+/// so the cost is linear in the buffer size. Buffers shorter than `window`
+/// yield a single measurement over the whole buffer.
+pub fn sliding_window_entropy(data: &[u8], window: usize, step: usize) -> Vec<(usize, f64)> {
+    let window = window.max(1);
+    let step = step.clamp(1, window);
+    if data.len() <= window {
+        return if data.is_empty() { Vec::new() } else { vec![(0, shannon_entropy(data))] };
+    }
+
+    let mut counts = [0u64; 256];
+    for &byte in &data[..window] {
+        counts[byte as usize] += 1;
+    }
+    let mut measurements = vec![(0, entropy_from_counts(&counts, window))];
+    let mut offset = 0;
+    while offset + step + window <= data.len() {
+        for &byte in &data[offset..offset + step] {
+            counts[byte as usize] -= 1;
+        }
+        for &byte in &data[offset + window..offset + window + step] {
+            counts[byte as usize] += 1;
+        }
+        offset += step;
+        measurements.push((offset, entropy_from_counts(&counts, window)));
+    }
+    measurements
+}
+
+pub fn analyze_entropy(data: &[u8]) -> EntropyAnalysis {
+    let overall_entropy = shannon_entropy(data);
+
+    // Merge overlapping high-entropy windows into regions
+    let mut spans: Vec<(usize, usize)> = Vec::new();
+    for (offset, entropy) in sliding_window_entropy(data, ENTROPY_WINDOW, ENTROPY_STEP) {
+        if entropy < HIGH_ENTROPY_THRESHOLD {
+            continue;
+        }
+        let end = (offset + ENTROPY_WINDOW).min(data.len());
+        match spans.last_mut() {
+            Some(last) if offset <= last.1 => last.1 = end,
+            _ => spans.push((offset, end)),
+        }
+    }
+    let high_entropy_regions: Vec<HighEntropyRegion> = spans
+        .into_iter()
+        .map(|(start, end)| HighEntropyRegion {
+            offset: start as u64,
+            size: (end - start) as u64,
+            entropy: shannon_entropy(&data[start..end]),
+            reason: "Compressed or encrypted data".to_string(),
+        })
+        .collect();
+
+    let packing_probability = ((overall_entropy - 6.0) / 2.0).clamp(0.0, 1.0);
+    EntropyAnalysis {
+        overall_entropy,
+        section_entropies: HashMap::new(),
+        high_entropy_regions,
+        is_packed: overall_entropy >= 7.0,
+        packing_probability,
+    }
+}
+
+// PE Parse Stage
+
+pub(crate) fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+pub(crate) fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+const IMAGE_SCN_MEM_EXECUTE: u32 = 0x2000_0000;
+const IMAGE_SCN_MEM_READ: u32 = 0x4000_0000;
+const IMAGE_SCN_MEM_WRITE: u32 = 0x8000_0000;
+const IMAGE_SCN_CNT_CODE: u32 = 0x0000_0020;
+
+/// Parse PE headers and section table; returns `None` for non-PE input
+pub fn parse_pe(data: &[u8]) -> Option<(PEAnalysis, SignatureVerification)> {
+    if data.get(0..2)? != b"MZ" {
+        return None;
+    }
+    let pe_offset = read_u32(data, 0x3C)? as usize;
+    if data.get(pe_offset..pe_offset + 4)? != b"PE\0\0" {
+        return None;
+    }
+
+    let coff = pe_offset + 4;
+    let machine = read_u16(data, coff)?;
+    let section_count = read_u16(data, coff + 2)? as usize;
+    let timestamp = read_u32(data, coff + 4)?;
+    let optional_size = read_u16(data, coff + 16)? as usize;
+    let optional = coff + 20;
+    let magic = read_u16(data, optional)?;
+    let is_pe32_plus = magic == 0x20B;
+    let entry_point = read_u32(data, optional + 16)?;
+    let subsystem = read_u16(data, optional + 68).unwrap_or(0);
+    let data_directories = optional + if is_pe32_plus { 112 } else { 96 };
+
+    let mut anomalies = Vec::new();
+    let compilation_time: DateTime<Utc> = Utc.timestamp_opt(timestamp as i64, 0).single().unwrap_or_else(Utc::now);
+    if timestamp == 0 || compilation_time > Utc::now() {
+        anomalies.push(PEAnomaly {
+            anomaly_type: "TimestampAnomaly".to_string(),
+            description: "Compilation timestamp is zeroed or in the future".to_string(),
+            severity: "Low".to_string(),
+            location: format!("0x{:X}", coff + 4),
+        });
+    }
+
+    let mut sections = Vec::new();
+    let section_table = optional + optional_size;
+    let mut entry_in_section = false;
+    for index in 0..section_count.min(96) {
+        let header = section_table + index * 40;
+        let Some(raw_name) = data.get(header..header + 8) else {
+            break;
+        };
+        let name = String::from_utf8_lossy(raw_name).trim_end_matches('\0').to_string();
+        let virtual_size = read_u32(data, header + 8)?;
+        let virtual_address = read_u32(data, header + 12)?;
+        let raw_size = read_u32(data, header + 16)?;
+        let raw_pointer = read_u32(data, header + 20)? as usize;
+        let characteristics = read_u32(data, header + 36)?;
+
+        let raw_end = raw_pointer.saturating_add(raw_size as usize).min(data.len());
+        let entropy = data.get(raw_pointer..raw_end).map(shannon_entropy).unwrap_or(0.0);
+        let writable_executable = characteristics & IMAGE_SCN_MEM_WRITE != 0 && characteristics & IMAGE_SCN_MEM_EXECUTE != 0;
+        if writable_executable {
+            anomalies.push(PEAnomaly {
+                anomaly_type: "WritableExecutableSection".to_string(),
+                description: format!("Section {} is both writable and executable", name),
+                severity: "High".to_string(),
+                location: format!("0x{:X}", header),
+            });
+        }
+        if raw_size == 0 && virtual_size > 0 {
+            anomalies.push(PEAnomaly {
+                anomaly_type: "EmptyRawSection".to_string(),
+                description: format!("Section {} has no raw data but a virtual size of {}", name, virtual_size),
+                severity: "Medium".to_string(),
+                location: format!("0x{:X}", header),
+            });
+        }
+        if entry_point >= virtual_address && entry_point < virtual_address.saturating_add(virtual_size.max(raw_size)) {
+            entry_in_section = true;
+        }
+
+        let mut flags = Vec::new();
+        if characteristics & IMAGE_SCN_CNT_CODE != 0 {
+            flags.push("CODE".to_string());
+        }
+        if characteristics & IMAGE_SCN_MEM_READ != 0 {
+            flags.push("READ".to_string());
+        }
+        if characteristics & IMAGE_SCN_MEM_WRITE != 0 {
+            flags.push("WRITE".to_string());
+        }
+        if characteristics & IMAGE_SCN_MEM_EXECUTE != 0 {
+            flags.push("EXECUTE".to_string());
+        }
+
+        sections.push(PESection {
+            name,
+            virtual_address: format!("0x{:08X}", virtual_address),
+            virtual_size: virtual_size as u64,
+            raw_size: raw_size as u64,
+            entropy,
+            characteristics: flags,
+            is_suspicious: writable_executable || entropy >= HIGH_ENTROPY_THRESHOLD,
+        });
+    }
+
+    if !sections.is_empty() && !entry_in_section {
+        anomalies.push(PEAnomaly {
+            anomaly_type: "EntryPointOutsideSections".to_string(),
+            description: "Entry point does not fall inside any section".to_string(),
+            severity: "High".to_string(),
+            location: format!("0x{:08X}", entry_point),
+        });
+    }
+
+    // Security directory (index 4) holds the Authenticode blob
+    let security_size = read_u32(data, data_directories + 4 * 8 + 4).unwrap_or(0);
+    let signature_verification = if security_size > 0 {
+        SignatureVerification {
+            is_signed: true,
+            signature_valid: false,
+            signer: None,
+            signature_time: None,
+            certificate_chain: vec![],
+            trust_status: "Signed (not verified)".to_string(),
+        }
+    } else {
+        unsigned()
+    };
+
+    let analysis = PEAnalysis {
+        architecture: match machine {
+            0x014C => "x86",
+            0x8664 => "x64",
+            0x01C4 => "ARM",
+            0xAA64 => "ARM64",
+            _ => "Unknown",
+        }
+        .to_string(),
+        subsystem: match subsystem {
+            1 => "Native",
+            2 => "Windows GUI",
+            3 => "Windows Console",
+            9 => "Windows CE GUI",
+            10..=13 => "EFI",
+            _ => "Unknown",
+        }
+        .to_string(),
+        compilation_time,
+        entry_point: format!("0x{:08X}", entry_point),
+        sections,
+        imports: vec![],
+        exports: vec![],
+        resources: vec![],
+        certificates: vec![],
+        anomalies,
+    };
+
+    Some((analysis, signature_verification))
+}
+
+fn unsigned() -> SignatureVerification {
+    SignatureVerification {
+        is_signed: false,
+        signature_valid: false,
+        signer: None,
+        signature_time: None,
+        certificate_chain: vec![],
+        trust_status: "Unsigned".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_pe() -> Vec<u8> {
+        let mut data = vec![0u8; 0x600];
+        data[0..2].copy_from_slice(b"MZ");
+        data[0x3C..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        data[0x80..0x84].copy_from_slice(b"PE\0\0");
+        data[0x84..0x86].copy_from_slice(&0x014Cu16.to_le_bytes());
+        data[0x86..0x88].copy_from_slice(&1u16.to_le_bytes());
+        data[0x88..0x8C].copy_from_slice(&1_600_000_000u32.to_le_bytes());
+        data[0x94..0x96].copy_from_slice(&0xE0u16.to_le_bytes());
+        data[0x98..0x9A].copy_from_slice(&0x10Bu16.to_le_bytes());
+        data[0xA8..0xAC].copy_from_slice(&0x1000u32.to_le_bytes());
+        data[0xDC..0xDE].copy_from_slice(&2u16.to_le_bytes());
+        let section = 0x98 + 0xE0;
+        data[section..section + 5].copy_from_slice(b".text");
+        data[section + 8..section + 12].copy_from_slice(&0x200u32.to_le_bytes());
+        data[section + 12..section + 16].copy_from_slice(&0x1000u32.to_le_bytes());
+        data[section + 16..section + 20].copy_from_slice(&0x200u32.to_le_bytes());
+        data[section + 20..section + 24].copy_from_slice(&0x400u32.to_le_bytes());
+        data[section + 36..section + 40].copy_from_slice(&(IMAGE_SCN_CNT_CODE | IMAGE_SCN_MEM_READ | IMAGE_SCN_MEM_EXECUTE).to_le_bytes());
+        let payload = b"IsDebuggerPresent\0http://evil.example.com/gate.php\0";
+        data[0x400..0x400 + payload.len()].copy_from_slice(payload);
+        data
+    }
+
+    #[test]
+    fn test_parse_pe_headers_and_sections() {
+        let (pe, signature) = parse_pe(&sample_pe()).expect("valid PE");
+        assert_eq!(pe.architecture, "x86");
+        assert_eq!(pe.subsystem, "Windows GUI");
+        assert_eq!(pe.sections.len(), 1);
+        assert_eq!(pe.sections[0].name, ".text");
+        assert!(pe.anomalies.is_empty());
+        assert!(!signature.is_signed);
+        assert!(parse_pe(b"not a pe").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_parallel_and_sequential_runs_agree() {
+        let data = Arc::new(sample_pe());
+        let parallel = StaticPipeline::new(StaticPipelineConfig::default()).unwrap();
+        let sequential = StaticPipeline::new(StaticPipelineConfig {
+            max_global_workers: 1,
+            max_parallel_stages_per_sample: 1,
+            ..StaticPipelineConfig::default()
+        })
+        .unwrap();
+
+        let (a, timings) = parallel.run(data.clone(), "PE", "application/x-msdownload").await;
+        let (b, _) = sequential.run(data, "PE", "application/x-msdownload").await;
+
+        assert_eq!(timings.stage_us.len(), StaticStage::ALL.len());
+        assert_eq!(a.strings_analysis.urls, vec!["http://evil.example.com/gate.php".to_string()]);
+        assert_eq!(a.strings_analysis.api_functions, b.strings_analysis.api_functions);
+        assert_eq!(a.anti_analysis_features.len(), 1);
+        assert_eq!(a.entropy_analysis.section_entropies.get(".text"), b.entropy_analysis.section_entropies.get(".text"));
+    }
+
+    #[test]
+    fn test_sliding_window_locates_high_entropy_region() {
+        let mut data = vec![0u8; 32 * 1024];
+        let mut state: u32 = 0x9E37_79B9;
+        for byte in &mut data[10_000..20_000] {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            *byte = state as u8;
+        }
+
+        let windows = sliding_window_entropy(&data, ENTROPY_WINDOW, ENTROPY_STEP);
+        assert_eq!(windows.len(), (data.len() - ENTROPY_WINDOW) / ENTROPY_STEP + 1);
+
+        let analysis = analyze_entropy(&data);
+        assert_eq!(analysis.high_entropy_regions.len(), 1);
+        let region = &analysis.high_entropy_regions[0];
+        let end = region.offset + region.size;
+        assert!((8 * 1024..=12 * 1024).contains(&region.offset), "region starts at {}", region.offset);
+        assert!((18 * 1024..=22 * 1024).contains(&end), "region ends at {}", end);
+        assert!(region.entropy > 6.0);
+    }
+
+    /// Sequential (previous behaviour) versus concurrent stages. Run with
+    /// `cargo test --release static_pipeline_latency -- --ignored --nocapture`.
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore]
+    async fn bench_static_pipeline_latency() {
+        let mut data = sample_pe();
+        let mut state: u32 = 0x1234_5678;
+        while data.len() < 8 << 20 {
+            data.extend_from_slice(b"VirtualAllocEx\0http://update.example.net/payload.bin\0C:\\Users\\Public\\svc.exe\0");
+            for _ in 0..256 {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                data.push(state as u8);
+            }
+        }
+        let data = Arc::new(data);
+
+        for per_sample in [1, StaticStage::ALL.len()] {
+            let pipeline = StaticPipeline::new(StaticPipelineConfig {
+                max_parallel_stages_per_sample: per_sample,
+                ..StaticPipelineConfig::default()
+            })
+            .unwrap();
+            let iterations = 10;
+            let started = Instant::now();
+            for _ in 0..iterations {
+                pipeline.run(data.clone(), "PE", "application/x-msdownload").await;
+            }
+            println!(
+                "stages_per_sample={} workers={} mean={:?}",
+                per_sample,
+                pipeline.config().max_global_workers,
+                started.elapsed() / iterations
+            );
+        }
+    }
+}
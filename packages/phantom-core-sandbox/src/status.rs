@@ -0,0 +1,154 @@
+//! Sandbox health in the unified platform status model
+//!
+//! Reports the job store, detonation environments, analysis engines, the
+//! ClamAV daemon, the cluster worker and webhook delivery as dependencies of
+//! the sandbox core, and exposes `get_platform_health` to roll them up with
+//! the reports of other cores.
+
+use async_trait::async_trait;
+use napi_derive::napi;
+use phantom_enterprise_standards::{ComponentHealth, DependencyHealth, HealthReporter, StatusRegistry};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::notifications::DeliveryStatus;
+use crate::{JobStatus, SandboxCore, SandboxCoreNapi};
+
+const COMPONENT: &str = "phantom-sandbox-core";
+
+/// Recent deliveries inspected for failing webhook endpoints
+const WEBHOOK_WINDOW: usize = 100;
+
+#[async_trait]
+impl HealthReporter for SandboxCore {
+    fn component(&self) -> &str {
+        COMPONENT
+    }
+
+    async fn check_health(&self) -> ComponentHealth {
+        self.component_health().await
+    }
+}
+
+impl SandboxCore {
+    /// Health of the sandbox and the dependencies analyses rely on
+    pub async fn component_health(&self) -> ComponentHealth {
+        let mut health = ComponentHealth::new(COMPONENT).with_version(env!("CARGO_PKG_VERSION"));
+
+        let started = Instant::now();
+        let job_store = match self.job_store.sample_ids() {
+            Ok(_) if !self.recovery_report.missing_samples.is_empty() => DependencyHealth::degraded(
+                "job_store",
+                "missing_samples",
+                format!("{} queued jobs lost their sample bytes", self.recovery_report.missing_samples.len()),
+            ),
+            Ok(_) => DependencyHealth::healthy("job_store"),
+            Err(e) => DependencyHealth::unhealthy("job_store", "job_store_unavailable", e),
+        };
+        health.dependency(job_store.with_latency(started.elapsed()));
+
+        let environments = self.vm_environments.read().await.len();
+        health.dependency(if environments == 0 {
+            DependencyHealth::unhealthy("vm_environments", "no_vm_environments", "No detonation environments are configured")
+        } else {
+            DependencyHealth::healthy("vm_environments")
+        });
+
+        let enabled_engines = self.analysis_engines.read().await.values().filter(|engine| engine.enabled).count();
+        health.dependency(if enabled_engines == 0 {
+            DependencyHealth::unhealthy("analysis_engines", "no_enabled_engines", "Every analysis engine is disabled")
+        } else {
+            DependencyHealth::healthy("analysis_engines")
+        });
+
+        if let Some(clamav) = self.clamav_status().await {
+            let dependency = match &clamav.error {
+                Some(e) => DependencyHealth::unhealthy("clamav", "clamd_unavailable", e.clone()),
+                None if clamav.signatures_stale() => DependencyHealth::degraded(
+                    "clamav",
+                    "clamav_signatures_stale",
+                    format!(
+                        "Signature database {} is {:.0} hours old",
+                        clamav.version.as_ref().and_then(|v| v.signature_version).unwrap_or_default(),
+                        clamav.signature_age_hours.unwrap_or_default()
+                    ),
+                ),
+                None => DependencyHealth::healthy("clamav"),
+            };
+            health.dependency(dependency.with_latency(Duration::from_millis(clamav.latency_ms)).optional());
+            health.detail("clamav", serde_json::json!(clamav));
+        }
+
+        if let Some(worker) = self.get_cluster_status().await.worker {
+            health.dependency(match worker.last_error {
+                Some(e) => DependencyHealth::degraded("cluster_worker", "cluster_worker_error", e),
+                None => DependencyHealth::healthy("cluster_worker"),
+            }.optional());
+        }
+
+        let endpoints = self.notifications.endpoint_count().await;
+        if endpoints > 0 {
+            // History is newest first, so the first record per endpoint is its latest delivery
+            let mut seen = HashSet::new();
+            let failing: Vec<_> = self
+                .notifications
+                .recent_history(WEBHOOK_WINDOW)
+                .await
+                .into_iter()
+                .filter(|record| seen.insert(record.endpoint_id.clone()))
+                .filter(|record| matches!(record.status, DeliveryStatus::Failed))
+                .collect();
+            health.dependency(match failing.first() {
+                Some(record) => DependencyHealth::degraded(
+                    "webhooks",
+                    "webhook_delivery_failing",
+                    format!(
+                        "{} of {} endpoints failing, last error: {}",
+                        failing.len(),
+                        endpoints,
+                        record.last_error.as_deref().unwrap_or("unknown")
+                    ),
+                ),
+                None => DependencyHealth::healthy("webhooks"),
+            }.optional());
+        }
+
+        let queue = self.analysis_queue.read().await;
+        let queued = queue.iter().filter(|job| matches!(job.status, JobStatus::Queued)).count();
+        health.detail("queue_length", serde_json::json!(queued));
+        health.detail("vm_environments", serde_json::json!(environments));
+        health.detail("enabled_engines", serde_json::json!(enabled_engines));
+        health
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// Health of this core in the unified component model
+    #[napi]
+    pub async fn get_component_health(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.tenant(auth_token.as_deref())?;
+        serde_json::to_string(&self.inner.component_health().await)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize component health: {}", e)))
+    }
+
+    /// Platform health rolled up from this core and the component reports of its peers
+    ///
+    /// `peer_health_json` is a JSON array of `get_component_health` results
+    /// from the other cores loaded in the process.
+    #[napi]
+    pub async fn get_platform_health(&self, peer_health_json: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        self.tenant(auth_token.as_deref())?;
+        let peers: Vec<ComponentHealth> = match peer_health_json {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| napi::Error::from_reason(format!("Invalid peer health: {}", e)))?,
+            None => Vec::new(),
+        };
+
+        let mut registry = StatusRegistry::new();
+        registry.register(self.inner.clone() as Arc<dyn HealthReporter>);
+        serde_json::to_string(&registry.platform_health(peers).await)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize platform health: {}", e)))
+    }
+}
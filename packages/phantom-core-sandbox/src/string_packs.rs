@@ -0,0 +1,383 @@
+//! Suspicious string pattern packs
+//!
+//! The strings stage of the static pipeline extracts printable ASCII and
+//! UTF-16LE runs; pattern packs decide which of them matter. A pack is a
+//! named list of regular expressions, each with a category and a base threat
+//! score. Bundled packs cover C2 URLs and hosts, persistence and defense
+//! evasion registry keys, cryptocurrency wallet addresses, hard-coded user
+//! agents and ransom note phrasing. Analysts add their own packs as JSON;
+//! bundled packs can be disabled but not replaced.
+//!
+//! A string matched by several patterns keeps its strongest hit and gains
+//! 0.1 for every further pack it hits, since a ransom note quoting a wallet
+//! address is more telling than either alone. The strings analysis as a
+//! whole scores its strongest suspicious string plus 0.1 for every further
+//! category among them.
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use crate::static_pipeline::StringClassifier;
+use crate::{SandboxCore, SandboxCoreNapi, SuspiciousString};
+use napi_derive::napi;
+
+/// Compiled size limit for user-supplied patterns
+const MAX_PATTERN_SIZE: usize = 1 << 20;
+/// Longest matched text kept on a suspicious string
+const MAX_MATCH_LENGTH: usize = 256;
+/// Score added for each further pack corroborating a string's strongest hit
+const CORROBORATION_BONUS: f64 = 0.1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StringPattern {
+    pub pattern_id: String,
+    pub category: String,
+    pub regex: String,
+    pub description: String,
+    /// Base threat score of a hit (0.0 - 1.0)
+    pub threat_score: f64,
+    #[serde(default)]
+    pub case_insensitive: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PatternPack {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    pub patterns: Vec<StringPattern>,
+    /// Shipped with the sandbox; set by the sandbox, never by callers
+    #[serde(default, skip_deserializing)]
+    pub builtin: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn pattern(pattern_id: &str, category: &str, regex: &str, description: &str, threat_score: f64) -> StringPattern {
+    StringPattern {
+        pattern_id: pattern_id.to_string(),
+        category: category.to_string(),
+        regex: regex.to_string(),
+        description: description.to_string(),
+        threat_score,
+        case_insensitive: true,
+    }
+}
+
+impl PatternPack {
+    /// Packs shipped with the sandbox
+    pub fn builtin() -> Vec<PatternPack> {
+        let pack = |name: &str, description: &str, patterns: Vec<StringPattern>| PatternPack {
+            name: name.to_string(),
+            description: description.to_string(),
+            enabled: true,
+            patterns,
+            builtin: true,
+        };
+        vec![
+            pack("network", "URLs and hosts typical of command and control", vec![
+                pattern("onion_host", "C2", r"\b[a-z2-7]{16}(?:[a-z2-7]{40})?\.onion\b", "Tor hidden service address", 0.8),
+                pattern("raw_ip_url", "C2", r"\b(?:https?|ftp)://(?:\d{1,3}\.){3}\d{1,3}(?::\d{1,5})?(?:/[^\s\x22'<>]*)?", "URL with a raw IP host", 0.7),
+                pattern("webhook_exfil", "Exfiltration", r"\b(?:discord(?:app)?\.com/api/webhooks|api\.telegram\.org/bot)[^\s\x22'<>]*", "Chat webhook commonly used for exfiltration", 0.75),
+                pattern("paste_service", "Staging", r"\b(?:pastebin\.com|paste\.ee|hastebin\.com|transfer\.sh|anonfiles\.com|ngrok(?:-free)?\.(?:io|app))\b[^\s\x22'<>]*", "Paste or file sharing service used for staging", 0.6),
+                pattern("dynamic_dns", "C2", r"\b[a-z0-9-]+\.(?:duckdns\.org|no-ip\.(?:org|com|biz)|ddns\.net|hopto\.org|servebeer\.com)\b", "Dynamic DNS host", 0.55),
+                pattern("url", "Network", r"\b(?:https?|ftp)://[^\s/$.?#][^\s\x22'<>]*", "Embedded URL", 0.3),
+            ]),
+            pack("registry", "Registry keys used for persistence and defense evasion", vec![
+                pattern("run_key", "Persistence", r"\\Windows\\CurrentVersion\\(?:Run|RunOnce|RunServices|Policies\\Explorer\\Run)\b", "Autorun registry key", 0.75),
+                pattern("winlogon", "Persistence", r"\\Windows NT\\CurrentVersion\\Winlogon\\?(?:Shell|Userinit|Notify)?", "Winlogon hijack key", 0.7),
+                pattern("ifeo", "Persistence", r"\\Image File Execution Options\b", "Image File Execution Options debugger hijack", 0.7),
+                pattern("defender_off", "Defense Evasion", r"\\Windows Defender\\(?:Real-Time Protection\\)?\w*Disable\w*", "Windows Defender disabling value", 0.85),
+                pattern("service_key", "Persistence", r"\\CurrentControlSet\\Services\\[^\\\s]+", "Service registration key", 0.45),
+            ]),
+            pack("crypto_wallets", "Cryptocurrency wallet addresses", vec![
+                StringPattern { case_insensitive: false, ..pattern("btc_bech32", "Cryptocurrency", r"\bbc1[ac-hj-np-z02-9]{39,59}\b", "Bitcoin bech32 address", 0.6) },
+                StringPattern { case_insensitive: false, ..pattern("btc_legacy", "Cryptocurrency", r"\b[13][a-km-zA-HJ-NP-Z1-9]{25,34}\b", "Bitcoin legacy address", 0.5) },
+                StringPattern { case_insensitive: false, ..pattern("eth", "Cryptocurrency", r"\b0x[a-fA-F0-9]{40}\b", "Ethereum address", 0.5) },
+                StringPattern { case_insensitive: false, ..pattern("xmr", "Cryptocurrency", r"\b4[0-9AB][1-9A-HJ-NP-Za-km-z]{93}\b", "Monero address", 0.7) },
+                pattern("mining_pool", "Cryptomining", r"\bstratum\+(?:tcp|ssl)://[^\s\x22'<>]+", "Mining pool connection string", 0.75),
+            ]),
+            pack("user_agents", "User agents hard-coded into HTTP clients", vec![
+                pattern("tool_agent", "User Agent", r"\b(?:python-requests|python-urllib|curl|wget|go-http-client|winhttp|powershell)/[\d.]+", "Scripting or tooling user agent", 0.45),
+                pattern("legacy_msie", "User Agent", r"\bMozilla/4\.0 \(compatible; MSIE [5-8]\.0[^)]*\)", "Obsolete Internet Explorer user agent", 0.5),
+                pattern("browser_agent", "User Agent", r"\bMozilla/5\.0 \([^)]{8,}\)", "Hard-coded browser user agent", 0.3),
+            ]),
+            pack("ransom_notes", "Phrasing and file names of ransom notes", vec![
+                pattern("ransom_phrase", "Ransomware", r"\b(?:(?:all )?your (?:files|documents|data|network) (?:have|has) been (?:encrypted|locked)|(?:to )?decrypt your files|pay (?:the )?ransom|private (?:decryption )?key will be (?:deleted|destroyed)|do not (?:rename|modify|try to decrypt) (?:the )?(?:encrypted )?files)", "Ransom note phrasing", 0.85),
+                pattern("ransom_payment", "Ransomware", r"\b(?:send|pay|transfer) [\d.]+ ?(?:btc|bitcoins?|xmr|monero)\b", "Ransom payment demand", 0.8),
+                pattern("ransom_note_file", "Ransomware", r"\b(?:how_to_(?:decrypt|restore|recover)|decrypt_instructions?|restore_(?:my_)?files|recovery_instructions)[\w-]*\.(?:txt|html?|hta)\b", "Ransom note file name", 0.8),
+            ]),
+        ]
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Pattern pack name is required".to_string());
+        }
+        if self.patterns.is_empty() {
+            return Err(format!("Pattern pack {} has no patterns", self.name));
+        }
+        for (index, pattern) in self.patterns.iter().enumerate() {
+            if pattern.pattern_id.trim().is_empty() {
+                return Err(format!("Pattern {} of pack {} has no id", index, self.name));
+            }
+            if self.patterns[..index].iter().any(|p| p.pattern_id == pattern.pattern_id) {
+                return Err(format!("Duplicate pattern {} in pack {}", pattern.pattern_id, self.name));
+            }
+            if !(0.0..=1.0).contains(&pattern.threat_score) {
+                return Err(format!("Pattern {} threat_score must be between 0.0 and 1.0", pattern.pattern_id));
+            }
+        }
+        Ok(())
+    }
+
+    fn compile(self) -> Result<CompiledPack, String> {
+        self.validate()?;
+        let regexes = self
+            .patterns
+            .iter()
+            .map(|p| {
+                RegexBuilder::new(&p.regex)
+                    .case_insensitive(p.case_insensitive)
+                    .size_limit(MAX_PATTERN_SIZE)
+                    .build()
+                    .map_err(|e| format!("Pattern {} of pack {}: {}", p.pattern_id, self.name, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(CompiledPack { pack: self, regexes })
+    }
+}
+
+struct CompiledPack {
+    pack: PatternPack,
+    regexes: Vec<Regex>,
+}
+
+/// Pattern packs by name, compiled and ready to classify strings
+pub struct StringPackSet {
+    packs: BTreeMap<String, CompiledPack>,
+}
+
+impl Default for StringPackSet {
+    fn default() -> Self {
+        let packs = PatternPack::builtin()
+            .into_iter()
+            .map(|pack| (pack.name.clone(), pack.compile().expect("bundled pattern packs compile")))
+            .collect();
+        Self { packs }
+    }
+}
+
+impl StringPackSet {
+    /// Add or replace a user pack
+    pub fn add(&mut self, mut pack: PatternPack) -> Result<(), String> {
+        if self.packs.get(&pack.name).is_some_and(|existing| existing.pack.builtin) {
+            return Err(format!("Pattern pack {} is bundled and cannot be replaced", pack.name));
+        }
+        pack.builtin = false;
+        let compiled = pack.compile()?;
+        self.packs.insert(compiled.pack.name.clone(), compiled);
+        Ok(())
+    }
+
+    /// Remove a user pack; bundled packs can only be disabled
+    pub fn remove(&mut self, name: &str) -> Result<bool, String> {
+        match self.packs.get(name) {
+            Some(compiled) if compiled.pack.builtin => Err(format!("Pattern pack {} is bundled; disable it instead", name)),
+            Some(_) => Ok(self.packs.remove(name).is_some()),
+            None => Ok(false),
+        }
+    }
+
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> Result<(), String> {
+        let compiled = self.packs.get_mut(name).ok_or_else(|| format!("Pattern pack {} not found", name))?;
+        compiled.pack.enabled = enabled;
+        Ok(())
+    }
+
+    pub fn list(&self) -> Vec<PatternPack> {
+        self.packs.values().map(|compiled| compiled.pack.clone()).collect()
+    }
+
+    /// Classify one extracted string against every enabled pack
+    pub fn classify(&self, value: &str, encoding: &str) -> Option<SuspiciousString> {
+        let mut hits: Vec<(&PatternPack, &StringPattern, &str)> = Vec::new();
+        for compiled in self.packs.values().filter(|compiled| compiled.pack.enabled) {
+            for (pattern, regex) in compiled.pack.patterns.iter().zip(&compiled.regexes) {
+                if let Some(m) = regex.find(value) {
+                    hits.push((&compiled.pack, pattern, m.as_str()));
+                }
+            }
+        }
+        let (pack, strongest, matched) = *hits.iter().max_by(|a, b| a.1.threat_score.total_cmp(&b.1.threat_score))?;
+        // Patterns of one pack overlap (a C2 URL is also a URL); only other packs corroborate
+        let mut other_packs: Vec<&str> = hits.iter().map(|(p, _, _)| p.name.as_str()).filter(|name| *name != pack.name).collect();
+        other_packs.sort_unstable();
+        other_packs.dedup();
+        let mut others: Vec<&str> = hits
+            .iter()
+            .filter(|(p, _, _)| p.name != pack.name)
+            .map(|(_, pattern, _)| pattern.category.as_str())
+            .collect();
+        others.sort_unstable();
+        others.dedup();
+
+        // Phrases are only meaningful in context, so keep the whole note line
+        let shown = if strongest.category == "Ransomware" { value } else { matched };
+        let mut description = strongest.description.clone();
+        if !others.is_empty() {
+            description.push_str(&format!(" (also {})", others.join(", ")));
+        }
+        Some(SuspiciousString {
+            string_value: shown.trim().chars().take(MAX_MATCH_LENGTH).collect(),
+            category: strongest.category.clone(),
+            description,
+            threat_score: corroborated(strongest.threat_score, other_packs.len()),
+            encoding: encoding.to_string(),
+            pattern_pack: Some(pack.name.clone()),
+        })
+    }
+}
+
+/// Strongest score raised by `CORROBORATION_BONUS` per further pack or category
+pub(crate) fn corroborated(strongest: f64, further: usize) -> f64 {
+    (strongest + CORROBORATION_BONUS * further as f64).min(1.0)
+}
+
+/// Classifier for the static pipeline's strings stage backed by shared packs
+pub fn pipeline_classifier(packs: Arc<RwLock<StringPackSet>>) -> StringClassifier {
+    Arc::new(move |value: &str, encoding: &str| packs.read().ok().and_then(|packs| packs.classify(value, encoding)))
+}
+
+impl SandboxCore {
+    /// Add or replace a user pattern pack used by every static analysis
+    pub fn add_string_pack(&self, pack: PatternPack) -> Result<PatternPack, String> {
+        self.string_packs.write().map_err(|e| e.to_string())?.add(pack.clone())?;
+        Ok(PatternPack { builtin: false, ..pack })
+    }
+
+    pub fn remove_string_pack(&self, name: &str) -> Result<bool, String> {
+        self.string_packs.write().map_err(|e| e.to_string())?.remove(name)
+    }
+
+    pub fn set_string_pack_enabled(&self, name: &str, enabled: bool) -> Result<(), String> {
+        self.string_packs.write().map_err(|e| e.to_string())?.set_enabled(name, enabled)
+    }
+
+    pub fn list_string_packs(&self) -> Result<Vec<PatternPack>, String> {
+        Ok(self.string_packs.read().map_err(|e| e.to_string())?.list())
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// Add or replace a suspicious string pattern pack (JSON `PatternPack`)
+    #[napi]
+    pub async fn add_string_pack(&self, pack_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let pack: PatternPack = serde_json::from_str(&pack_json)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid pattern pack: {}", e)))?;
+        let name = pack.name.clone();
+        let actor = self.authorize(auth_token, "rule:manage", &name)?;
+        let added = self.inner.add_string_pack(pack);
+        let added = self.audit.record(&actor, "add_string_pack", &name, serde_json::json!({ "pack": pack_json }), added)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to add pattern pack: {}", e)))?;
+        serde_json::to_string(&added)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize pattern pack: {}", e)))
+    }
+
+    /// Remove a user pattern pack
+    #[napi]
+    pub async fn remove_string_pack(&self, name: String, auth_token: Option<String>) -> napi::Result<bool> {
+        let actor = self.authorize(auth_token, "rule:manage", &name)?;
+        let removed = self.inner.remove_string_pack(&name);
+        self.audit.record(&actor, "remove_string_pack", &name, serde_json::json!({}), removed)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to remove pattern pack: {}", e)))
+    }
+
+    /// Enable or disable a bundled or user pattern pack
+    #[napi]
+    pub async fn set_string_pack_enabled(&self, name: String, enabled: bool, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize(auth_token, "rule:manage", &name)?;
+        let result = self.inner.set_string_pack_enabled(&name, enabled);
+        self.audit.record(&actor, "set_string_pack_enabled", &name, serde_json::json!({ "enabled": enabled }), result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to update pattern pack: {}", e)))
+    }
+
+    /// List bundled and user pattern packs
+    #[napi]
+    pub async fn list_string_packs(&self) -> napi::Result<String> {
+        let packs = self.inner.list_string_packs()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to list pattern packs: {}", e)))?;
+        serde_json::to_string(&packs)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize pattern packs: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_packs_classify_and_corroborate() {
+        let packs = StringPackSet::default();
+        let wallet = packs.classify("bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq", "ASCII").unwrap();
+        assert_eq!((wallet.category.as_str(), wallet.pattern_pack.as_deref()), ("Cryptocurrency", Some("crypto_wallets")));
+
+        let note = packs
+            .classify("All your files have been encrypted! Send 0.5 BTC to bc1qar0srrr7xfkvy5l643lydnw9re59gtzzwf5mdq", "UTF-16LE")
+            .unwrap();
+        assert_eq!(note.category, "Ransomware");
+        assert!(note.string_value.starts_with("All your files"));
+        assert!(note.description.contains("also Cryptocurrency"));
+        assert!((note.threat_score - 0.95).abs() < 1e-9);
+
+        let c2 = packs.classify("http://185.220.101.4:8080/gate.php", "ASCII").unwrap();
+        assert_eq!((c2.category.as_str(), c2.threat_score), ("C2", 0.7));
+        assert!(packs.classify("Copyright (c) Example Corp", "ASCII").is_none());
+    }
+
+    #[test]
+    fn user_packs_are_validated_and_bundled_packs_protected() {
+        let mut packs = StringPackSet::default();
+        let custom: PatternPack = serde_json::from_value(serde_json::json!({
+            "name": "acme", "builtin": true,
+            "patterns": [{ "pattern_id": "mutex", "category": "Mutex", "regex": "Global\\\\AcmeLoader_[0-9a-f]{8}",
+                           "description": "Acme loader mutex", "threat_score": 0.9 }]
+        }))
+        .unwrap();
+        assert!(!custom.builtin);
+        packs.add(custom.clone()).unwrap();
+        assert_eq!(packs.classify("Global\\AcmeLoader_1a2b3c4d", "ASCII").unwrap().threat_score, 0.9);
+
+        let broken = PatternPack { name: "broken".to_string(), patterns: vec![StringPattern { regex: "(".to_string(), ..custom.patterns[0].clone() }], ..custom.clone() };
+        assert!(packs.add(broken).unwrap_err().contains("mutex"));
+        assert!(packs.add(PatternPack { name: "network".to_string(), ..custom }).is_err());
+        assert!(packs.remove("network").is_err());
+
+        packs.set_enabled("network", false).unwrap();
+        assert!(packs.classify("http://185.220.101.4/gate.php", "ASCII").is_none());
+        assert!(packs.remove("acme").unwrap());
+    }
+
+    #[test]
+    fn strings_stage_scores_classified_strings() {
+        let classifier = pipeline_classifier(Arc::new(RwLock::new(StringPackSet::default())));
+        let note: Vec<u8> = "Your files have been encrypted".encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+        let mut data = b"CreateRemoteThread\0stratum+tcp://pool.example.net:3333\0\0".to_vec();
+        data.extend_from_slice(&note);
+
+        let analysis = crate::static_pipeline::classify_strings(&data, Some(&classifier));
+        let ransom = analysis.suspicious_strings.iter().find(|s| s.category == "Ransomware").unwrap();
+        assert_eq!(ransom.encoding, "UTF-16LE");
+        assert!(analysis.suspicious_strings.iter().any(|s| s.pattern_pack.as_deref() == Some("crypto_wallets")));
+        // Ransomware 0.85 corroborated by Cryptomining and Process Injection
+        assert!((analysis.threat_score - 1.0).abs() < 1e-9);
+        assert_eq!(crate::static_pipeline::extract_strings(&data).suspicious_strings.len(), 1);
+    }
+}
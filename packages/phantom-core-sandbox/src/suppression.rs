@@ -0,0 +1,228 @@
+//! IOC Suppression
+//!
+//! With `phantom-enterprise-standards` enabled, each tenant keeps allowlists
+//! of known-good domains, IPs, hashes and process paths. Extracted IOCs that
+//! match an entry are dropped before enrichment, URLs by their host, and a
+//! sample whose hash is allowlisted is scored `Clean`; both are noted in the
+//! analysis warnings. Without the feature nothing is suppressed.
+
+use crate::{ExtractedIOC, SampleInfo};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::{SandboxCore, SandboxCoreNapi};
+#[cfg(feature = "phantom-enterprise-standards")]
+use napi_derive::napi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::suppression::{SuppressionEntry, SuppressionKind, SuppressionList, SuppressionRule};
+
+#[derive(Default)]
+pub struct SuppressionState {
+    #[cfg(feature = "phantom-enterprise-standards")]
+    list: SuppressionList,
+}
+
+impl SuppressionState {
+    /// Drop the IOCs an allowlist entry covers, returning how many were dropped
+    pub(crate) fn suppress_iocs(&self, tenant_id: &str, iocs: &mut Vec<ExtractedIOC>) -> usize {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            let before = iocs.len();
+            iocs.retain(|ioc| !self.suppresses_ioc(tenant_id, ioc));
+            before - iocs.len()
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        {
+            let _ = (tenant_id, iocs);
+            0
+        }
+    }
+
+    /// Id of the entry allowlisting one of the sample's hashes
+    pub(crate) fn allowlisted_sample(&self, tenant_id: &str, sample: &SampleInfo) -> Option<String> {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            [&sample.file_hash_sha256, &sample.file_hash_sha1, &sample.file_hash_md5]
+                .into_iter()
+                .find_map(|hash| self.list.check(tenant_id, SuppressionKind::Hash, hash))
+                .map(|entry| entry.id)
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        {
+            let _ = (tenant_id, sample);
+            None
+        }
+    }
+
+    #[cfg(feature = "phantom-enterprise-standards")]
+    fn suppresses_ioc(&self, tenant_id: &str, ioc: &ExtractedIOC) -> bool {
+        if let Some(kind) = SuppressionKind::from_ioc_type(&ioc.ioc_type) {
+            return self.list.check(tenant_id, kind, &ioc.value).is_some();
+        }
+        if !matches!(ioc.ioc_type.to_lowercase().as_str(), "url" | "uri") {
+            return false;
+        }
+        match url::Url::parse(&ioc.value).ok().and_then(|url| url.host().map(|host| host.to_owned())) {
+            Some(url::Host::Domain(domain)) => self.list.check(tenant_id, SuppressionKind::Domain, &domain).is_some(),
+            Some(url::Host::Ipv4(ip)) => self.list.check(tenant_id, SuppressionKind::Ip, &ip.to_string()).is_some(),
+            Some(url::Host::Ipv6(ip)) => self.list.check(tenant_id, SuppressionKind::Ip, &ip.to_string()).is_some(),
+            None => false,
+        }
+    }
+
+    pub(crate) fn forget_tenant(&self, tenant_id: &str) -> usize {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            self.list.forget_scope(tenant_id)
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        {
+            let _ = tenant_id;
+            0
+        }
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+impl SandboxCore {
+    pub fn add_suppression(&self, tenant_id: &str, rule: SuppressionRule, created_by: &str) -> Result<SuppressionEntry, String> {
+        self.suppression.list.add(tenant_id, rule, created_by)
+    }
+
+    pub fn update_suppression(&self, tenant_id: &str, id: &str, rule: SuppressionRule) -> Result<SuppressionEntry, String> {
+        self.suppression.list.update(tenant_id, id, rule)
+    }
+
+    pub fn remove_suppression(&self, tenant_id: &str, id: &str) -> bool {
+        self.suppression.list.remove(tenant_id, id)
+    }
+
+    pub fn get_suppression(&self, tenant_id: &str, id: &str) -> Option<SuppressionEntry> {
+        self.suppression.list.get(tenant_id, id)
+    }
+
+    /// The tenant's entries with their hit counters, expired ones included
+    pub fn list_suppressions(&self, tenant_id: &str) -> Vec<SuppressionEntry> {
+        self.suppression.list.list(tenant_id)
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[napi]
+impl SandboxCoreNapi {
+    /// Allowlist a domain, IP, hash or process path, e.g.
+    /// `{"kind": "domain", "mode": "glob", "pattern": "*.cdn.example.com"}`
+    #[napi]
+    pub fn add_suppression(&self, rule_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", "suppressions")?;
+        let rule: SuppressionRule = serde_json::from_str(&rule_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse suppression: {}", e)))?;
+        let entry = self.inner.add_suppression(&tenant_id, rule, &actor);
+        let entry = self.audit.record(&actor, "add_suppression", "suppressions", serde_json::json!({ "rule": rule_json }), entry)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to add suppression: {}", e)))?;
+        serde_json::to_string(&entry)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize suppression: {}", e)))
+    }
+
+    /// Replace a suppression's rule; its hit counters are kept
+    #[napi]
+    pub fn update_suppression(&self, id: String, rule_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &id)?;
+        let rule: SuppressionRule = serde_json::from_str(&rule_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse suppression: {}", e)))?;
+        let entry = self.inner.update_suppression(&tenant_id, &id, rule);
+        let entry = self.audit.record(&actor, "update_suppression", &id, serde_json::json!({ "rule": rule_json }), entry)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to update suppression: {}", e)))?;
+        serde_json::to_string(&entry)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize suppression: {}", e)))
+    }
+
+    #[napi]
+    pub fn remove_suppression(&self, id: String, auth_token: Option<String>) -> napi::Result<bool> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &id)?;
+        let removed = self.inner.remove_suppression(&tenant_id, &id);
+        self.audit.record(&actor, "remove_suppression", &id, serde_json::json!({ "removed": removed }), Ok::<_, String>(removed))
+            .map_err(napi::Error::from_reason)
+    }
+
+    #[napi]
+    pub fn get_suppression(&self, id: String, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        self.inner
+            .get_suppression(&tenant_id, &id)
+            .map(|entry| serde_json::to_string(&entry))
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize suppression: {}", e)))
+    }
+
+    #[napi]
+    pub fn list_suppressions(&self, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        serde_json::to_string(&self.inner.list_suppressions(&tenant_id))
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize suppressions: {}", e)))
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use super::*;
+    use crate::{AnalysisPriority, SandboxVerdict};
+    use phantom_enterprise_standards::suppression::MatchMode;
+
+    fn rule(kind: SuppressionKind, mode: MatchMode, pattern: &str) -> SuppressionRule {
+        SuppressionRule { kind, mode, pattern: pattern.to_string(), reason: "known good".to_string(), expires_at: None }
+    }
+
+    fn ioc(ioc_type: &str, value: &str) -> ExtractedIOC {
+        ExtractedIOC {
+            ioc_type: ioc_type.to_string(),
+            value: value.to_string(),
+            category: "Network".to_string(),
+            confidence: 0.9,
+            context: "test".to_string(),
+            first_seen: chrono::Utc::now(),
+            threat_intelligence: None,
+            enrichments: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn allowlisted_iocs_are_dropped() {
+        let core = SandboxCore::new().unwrap();
+        core.add_suppression("acme", rule(SuppressionKind::Domain, MatchMode::Glob, "*.cdn.example.com"), "analyst").unwrap();
+        core.add_suppression("acme", rule(SuppressionKind::Ip, MatchMode::Cidr, "192.0.2.0/24"), "analyst").unwrap();
+
+        let mut iocs = vec![
+            ioc("Domain", "img.cdn.example.com"),
+            ioc("URL", "https://static.cdn.example.com/app.js"),
+            ioc("IP", "192.0.2.44"),
+            ioc("IP", "203.0.113.9"),
+            ioc("Domain", "c2.evil.test"),
+        ];
+        assert_eq!(core.suppression.suppress_iocs("acme", &mut iocs), 3);
+        let kept: Vec<&str> = iocs.iter().map(|ioc| ioc.value.as_str()).collect();
+        assert_eq!(kept, ["203.0.113.9", "c2.evil.test"]);
+        assert_eq!(core.list_suppressions("acme").iter().map(|e| e.hit_count).sum::<u64>(), 3);
+
+        let mut other = vec![ioc("IP", "192.0.2.44")];
+        assert_eq!(core.suppression.suppress_iocs("globex", &mut other), 0);
+    }
+
+    #[tokio::test]
+    async fn allowlisted_hash_scores_clean() {
+        let core = SandboxCore::new().unwrap();
+        let sha256 = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+        let entry = core.add_suppression("acme", rule(SuppressionKind::Hash, MatchMode::Exact, sha256), "analyst").unwrap();
+        let sample_id = core.submit_sample("acme", b"MZ agent", "agent.exe".to_string(), AnalysisPriority::Normal, vec![], false).await.unwrap();
+        core.process_queue().await.unwrap();
+
+        let analysis = core.get_analysis("acme", &sample_id).await.unwrap().unwrap();
+        assert!(matches!(analysis.verdict, SandboxVerdict::Clean));
+        assert!(analysis.analysis_metadata.warnings.iter().any(|w| w.contains(&entry.id)));
+
+        core.purge_tenant("acme").await.unwrap();
+        assert!(core.list_suppressions("acme").is_empty());
+    }
+}
@@ -0,0 +1,176 @@
+//! SIEM Forwarding
+//!
+//! With `phantom-enterprise-standards` enabled and a collector configured,
+//! every completed analysis with a malicious verdict is forwarded as a CEF
+//! or LEEF syslog event. Delivery happens in the background and is buffered
+//! while the collector is down, so analysis throughput does not depend on
+//! the SIEM. Events of every tenant go to the same collector, so only
+//! platform administrators can configure it. Without the feature nothing is
+//! forwarded.
+
+use crate::{SandboxAnalysis, SandboxCore};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::{SandboxCoreNapi, SandboxVerdict, ThreatLevel};
+#[cfg(feature = "phantom-enterprise-standards")]
+use napi_derive::napi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::syslog::{ForwarderMetrics, SecurityEvent, SecurityEventKind, SyslogConfig, SyslogForwarder};
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[derive(Default)]
+pub struct SyslogState {
+    forwarder: RwLock<Option<Arc<SyslogForwarder>>>,
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+impl SyslogState {
+    pub fn forwarder(&self) -> Option<Arc<SyslogForwarder>> {
+        self.forwarder.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// SIEM event for a malicious verdict
+#[cfg(feature = "phantom-enterprise-standards")]
+pub fn verdict_event(analysis: &SandboxAnalysis) -> SecurityEvent {
+    let severity = match analysis.threat_level {
+        ThreatLevel::Critical => 10,
+        ThreatLevel::High => 8,
+        ThreatLevel::Medium => 6,
+        ThreatLevel::Low => 3,
+        ThreatLevel::None => 1,
+    };
+    let sample = &analysis.sample_info;
+    let family = analysis.malware_classification.family.as_deref().unwrap_or_default();
+    SecurityEvent::new(
+        SecurityEventKind::Verdict,
+        "sandbox-verdict-malicious",
+        &format!("Malicious sample {}", sample.file_name),
+        severity,
+    )
+    .field("external_id", &sample.sample_id)
+    .field("file_name", &sample.file_name)
+    .field("file_hash", &sample.file_hash_sha256)
+    .field("category", format!("{:?}", analysis.malware_classification.category))
+    .field("malware_family", family)
+    .field("confidence", format!("{:.2}", analysis.confidence_score))
+    .field(
+        "mitre_techniques",
+        analysis.mitre_techniques.iter().map(|t| t.technique_id.as_str()).collect::<Vec<_>>().join(","),
+    )
+    .field("ioc_count", analysis.iocs_extracted.len())
+    .field("tenant_id", &analysis.tenant_id)
+}
+
+impl SandboxCore {
+    /// Forward `analysis` to the SIEM when its verdict is malicious
+    pub(crate) fn forward_verdict(&self, analysis: &SandboxAnalysis) {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        if matches!(analysis.verdict, SandboxVerdict::Malicious) {
+            if let Some(forwarder) = self.syslog.forwarder() {
+                forwarder.forward(&verdict_event(analysis));
+            }
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        let _ = analysis;
+    }
+
+    /// Start forwarding to the collector in `config`, or stop with `None`.
+    /// Messages still buffered for a replaced collector are discarded.
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn configure_syslog(&self, config: Option<SyslogConfig>) -> Result<Option<ForwarderMetrics>, String> {
+        let forwarder = config.map(SyslogForwarder::new).transpose()?.map(Arc::new);
+        let metrics = forwarder.as_ref().map(|forwarder| forwarder.metrics());
+        *self.syslog.forwarder.write().unwrap_or_else(|e| e.into_inner()) = forwarder;
+        Ok(metrics)
+    }
+
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn syslog_forwarder(&self) -> Option<Arc<SyslogForwarder>> {
+        self.syslog.forwarder()
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[napi]
+impl SandboxCoreNapi {
+    /// Forward malicious verdicts to a syslog collector (UDP/TCP/TLS, CEF or
+    /// LEEF); pass no config to stop forwarding
+    #[napi]
+    pub fn configure_syslog_forwarding(&self, config_json: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, "syslog")?;
+        let config: Option<SyslogConfig> = config_json
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse syslog config: {}", e)))?;
+
+        let metrics = self.inner.configure_syslog(config);
+        let metrics = self.audit.record(&actor, "configure_syslog_forwarding", "syslog", serde_json::json!({ "config": config_json }), metrics)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to configure syslog forwarding: {}", e)))?;
+
+        serde_json::to_string(&metrics)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize forwarding metrics: {}", e)))
+    }
+
+    /// Delivery counters of the syslog forwarder; `null` when not configured
+    #[napi]
+    pub fn get_syslog_forwarding_metrics(&self) -> napi::Result<String> {
+        let metrics = self.inner.syslog_forwarder().map(|forwarder| forwarder.metrics());
+        serde_json::to_string(&metrics)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize forwarding metrics: {}", e)))
+    }
+
+    /// Retry delivery of buffered events now
+    #[napi]
+    pub async fn flush_syslog_forwarding(&self) -> napi::Result<String> {
+        let Some(forwarder) = self.inner.syslog_forwarder() else {
+            return Err(napi::Error::from_reason("Syslog forwarding is not configured"));
+        };
+        forwarder.flush().await;
+        serde_json::to_string(&forwarder.metrics())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize forwarding metrics: {}", e)))
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use super::*;
+    use crate::AnalysisPriority;
+    use tokio::io::AsyncReadExt;
+
+    #[tokio::test]
+    async fn malicious_verdicts_are_forwarded_as_cef() {
+        let core = SandboxCore::new().unwrap();
+        let sample_id = core.submit_sample("acme", b"MZ dropper", "dropper.exe".to_string(), AnalysisPriority::High, vec![], false).await.unwrap();
+        core.process_queue().await.unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = serde_json::json!({ "host": "127.0.0.1", "port": listener.local_addr().unwrap().port() });
+        core.configure_syslog(Some(serde_json::from_value(config).unwrap())).unwrap();
+        let mut analysis = core.get_analysis("acme", &sample_id).await.unwrap().unwrap();
+        analysis.verdict = SandboxVerdict::Clean;
+        core.forward_verdict(&analysis);
+        analysis.verdict = SandboxVerdict::Malicious;
+        analysis.threat_level = ThreatLevel::Critical;
+        core.forward_verdict(&analysis);
+
+        let forwarder = core.syslog_forwarder().unwrap();
+        forwarder.flush().await;
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut received = String::new();
+        while !received.contains("tenant_id=acme") {
+            let mut buf = [0; 1024];
+            let read = stream.read(&mut buf).await.unwrap();
+            assert!(read > 0, "collector connection closed early");
+            received.push_str(&String::from_utf8_lossy(&buf[..read]));
+        }
+        assert_eq!(forwarder.metrics().sent, 1, "clean verdicts are not forwarded");
+        let expected = format!("|sandbox-verdict-malicious|Malicious sample {}|10|", analysis.sample_info.file_name);
+        assert!(received.contains(&expected), "{}", received);
+        assert!(received.contains(&format!("externalId={}", sample_id)));
+        assert!(received.contains("tenant_id=acme"));
+    }
+}
@@ -0,0 +1,252 @@
+//! Tenant partitioning
+//!
+//! Jobs, samples and analyses belong to the tenant of the credential that
+//! submitted them, and reads through `SandboxCoreNapi` only see the caller's
+//! tenant; another tenant's samples behave as if they did not exist. Calls
+//! without a tenant-bound credential act for the default tenant. VM
+//! environments, YARA rules, personas and the MISP connection are shared.
+//! With `phantom-enterprise-standards` enabled tenants have a lifecycle
+//! (create, suspend, resume, delete with data purge) and calls for unknown
+//! or suspended tenants are refused.
+
+use std::collections::{BTreeMap, HashSet};
+
+use crate::{SandboxCore, SandboxCoreNapi};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use napi_derive::napi;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::multi_tenancy::TenantDirectory;
+#[cfg(feature = "phantom-enterprise-standards")]
+use serde_json::json;
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::sync::Arc;
+
+/// Tenant that owns samples submitted without a tenant-bound credential
+pub const DEFAULT_TENANT: &str = "default";
+
+pub fn default_tenant() -> String {
+    DEFAULT_TENANT.to_string()
+}
+
+#[derive(Clone, Default)]
+pub struct TenantGuard {
+    #[cfg(feature = "phantom-enterprise-standards")]
+    directory: Arc<TenantDirectory>,
+}
+
+impl TenantGuard {
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn directory(&self) -> &TenantDirectory {
+        &self.directory
+    }
+
+    /// Check that calls may be served for `tenant_id`
+    pub fn check(&self, tenant_id: &str) -> Result<(), String> {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            self.directory.ensure_active(tenant_id).map_err(|e| e.to_string())
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        {
+            let _ = tenant_id;
+            Ok(())
+        }
+    }
+}
+
+impl SandboxCore {
+    /// Whether `sample_id` was submitted by `tenant_id`
+    pub(crate) async fn owns_sample(&self, tenant_id: &str, sample_id: &str) -> bool {
+        if let Some(job) = self.analysis_queue.read().await.iter().find(|job| job.sample_id == sample_id) {
+            return job.tenant_id == tenant_id;
+        }
+        self.completed_analyses
+            .read()
+            .await
+            .get(sample_id)
+            .is_some_and(|analysis| analysis.tenant_id == tenant_id)
+    }
+
+    /// Remove every job, sample and analysis of `tenant_id`, in memory and in
+    /// the job store; returns how many records of each kind were removed
+    pub async fn purge_tenant(&self, tenant_id: &str) -> Result<BTreeMap<String, usize>, String> {
+        let jobs = {
+            let mut queue = self.analysis_queue.write().await;
+            queue.remove_where(|job| job.tenant_id == tenant_id)
+        };
+        let mut sample_ids: HashSet<String> = jobs.iter().map(|job| job.sample_id.clone()).collect();
+
+        let analyses = {
+            let mut completed = self.completed_analyses.write().await;
+            let owned: Vec<String> = completed
+                .iter()
+                .filter(|(_, analysis)| analysis.tenant_id == tenant_id)
+                .map(|(sample_id, _)| sample_id.clone())
+                .collect();
+            for sample_id in &owned {
+                completed.remove(sample_id);
+            }
+            sample_ids.extend(owned.iter().cloned());
+            owned.len()
+        };
+
+        for job in &jobs {
+            self.job_store.delete_records(&job.job_id, &job.sample_id)?;
+        }
+        let mut samples = self.sample_data.write().await;
+        for sample_id in &sample_ids {
+            samples.remove(sample_id);
+            self.forget_retained(sample_id).await;
+        }
+        drop(samples);
+
+        let hash_records = {
+            let mut index = self.hash_index.write().await;
+            let before = index.len();
+            index.retain(|_, record| record.tenant_id != tenant_id);
+            before - index.len()
+        };
+        let phishing_triages = {
+            let mut triages = self.phishing_triages.write().await;
+            let before = triages.len();
+            triages.retain(|_, triage| triage.tenant_id != tenant_id);
+            before - triages.len()
+        };
+
+        Ok(BTreeMap::from([
+            ("jobs".to_string(), jobs.len()),
+            ("samples".to_string(), sample_ids.len()),
+            ("analyses".to_string(), analyses),
+            ("hash_records".to_string(), hash_records),
+            ("phishing_triages".to_string(), phishing_triages),
+            ("metric_series".to_string(), self.metrics_history.forget_tenant(tenant_id)),
+            ("prometheus_series".to_string(), self.prometheus.forget_tenant(tenant_id)),
+            ("suppressions".to_string(), self.suppression.forget_tenant(tenant_id)),
+            ("fuzzy_digests".to_string(), self.fuzzy.forget_tenant(tenant_id)),
+            ("artifacts".to_string(), self.forget_artifacts(tenant_id).await),
+            ("interactive_sessions".to_string(), self.interactive.forget_tenant(tenant_id)),
+            ("antivirus_scans".to_string(), self.clamav.forget_tenant(tenant_id)),
+            ("upload_sessions".to_string(), self.uploads.forget_tenant(tenant_id)),
+            ("webhooks".to_string(), self.notifications.forget_tenant(tenant_id).await),
+        ]))
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[napi]
+impl SandboxCoreNapi {
+    #[napi]
+    pub fn create_tenant(&self, tenant_id: String, organization_name: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, &tenant_id)?;
+        let record = self.tenants.directory().create(&tenant_id, &organization_name).map_err(|e| e.to_string());
+        let record = self.audit.record(&actor, "create_tenant", &tenant_id, json!({ "organization_name": organization_name }), record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to create tenant: {}", e)))?;
+        serde_json::to_string(&record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize tenant: {}", e)))
+    }
+
+    /// Refuse every call for the tenant until it is resumed; its samples are kept
+    #[napi]
+    pub fn suspend_tenant(&self, tenant_id: String, reason: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, &tenant_id)?;
+        let record = self.tenants.directory().suspend(&tenant_id, reason.clone()).map_err(|e| e.to_string());
+        let record = self.audit.record(&actor, "suspend_tenant", &tenant_id, json!({ "reason": reason }), record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to suspend tenant: {}", e)))?;
+        serde_json::to_string(&record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize tenant: {}", e)))
+    }
+
+    #[napi]
+    pub fn resume_tenant(&self, tenant_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, &tenant_id)?;
+        let record = self.tenants.directory().resume(&tenant_id).map_err(|e| e.to_string());
+        let record = self.audit.record(&actor, "resume_tenant", &tenant_id, json!({}), record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to resume tenant: {}", e)))?;
+        serde_json::to_string(&record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize tenant: {}", e)))
+    }
+
+    /// Suspend the tenant, purge its jobs, samples and analyses and forget it
+    #[napi]
+    pub async fn delete_tenant(&self, tenant_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        use phantom_enterprise_standards::multi_tenancy::TenantPurgeReport;
+
+        let actor = self.authorize_platform(auth_token, &tenant_id)?;
+        let report = async {
+            if tenant_id == DEFAULT_TENANT {
+                return Err("the default tenant cannot be deleted".to_string());
+            }
+            let directory = self.tenants.directory();
+            directory.suspend(&tenant_id, Some("deleting".to_string())).map_err(|e| e.to_string())?;
+            let purged = self.inner.purge_tenant(&tenant_id).await?;
+            directory.remove(&tenant_id).map_err(|e| e.to_string())?;
+            Ok(TenantPurgeReport { tenant_id: tenant_id.clone(), purged, deleted_at: chrono::Utc::now() })
+        }
+        .await;
+        let report = self.audit.record(&actor, "delete_tenant", &tenant_id, json!({}), report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to delete tenant: {}", e)))?;
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize purge report: {}", e)))
+    }
+
+    #[napi]
+    pub fn list_tenants(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize_platform(auth_token, "tenants")?;
+        serde_json::to_string(&self.tenants.directory().list())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize tenants: {}", e)))
+    }
+}
+
+impl SandboxCoreNapi {
+    /// Tenant lifecycle and settings that span tenants are reserved for
+    /// platform administrators, i.e. credentials with `access:manage` that
+    /// are not bound to a tenant
+    pub(crate) fn authorize_platform(&self, auth_token: Option<String>, resource: &str) -> napi::Result<String> {
+        if self.access.tenant(auth_token.as_deref()).is_some() {
+            return Err(napi::Error::from_reason("Tenant-bound credentials cannot manage platform settings"));
+        }
+        self.authorize(auth_token, "access:manage", resource)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AnalysisPriority;
+
+    #[tokio::test]
+    async fn tenants_only_see_their_own_samples() {
+        let core = SandboxCore::new().unwrap();
+        let data = b"MZ shared payload";
+        let acme = core.submit_sample("acme", data, "a.exe".to_string(), AnalysisPriority::Normal, vec![], false).await.unwrap();
+        let globex = core.submit_sample("globex", data, "g.exe".to_string(), AnalysisPriority::Normal, vec![], false).await.unwrap();
+        assert_ne!(acme, globex, "identical bytes are not deduplicated across tenants");
+
+        core.process_queue().await.unwrap();
+        core.process_queue().await.unwrap();
+        assert!(core.get_analysis("acme", &acme).await.unwrap().is_some());
+        assert!(core.get_analysis("globex", &acme).await.unwrap().is_none());
+        assert!(core.get_analysis_status("globex", &acme).await.unwrap().is_none());
+        assert!(core.export_sample("globex", &acme, None).await.is_err());
+        assert_eq!(core.get_queue_status("acme").await.unwrap().len(), 1);
+        assert_eq!(core.get_performance_metrics("globex").await.unwrap().successful_analyses, 1);
+    }
+
+    #[tokio::test]
+    async fn purge_removes_only_the_tenant() {
+        let core = SandboxCore::new().unwrap();
+        let acme = core.submit_sample("acme", b"MZ acme", "a.exe".to_string(), AnalysisPriority::High, vec![], false).await.unwrap();
+        let globex = core.submit_sample("globex", b"MZ globex", "g.exe".to_string(), AnalysisPriority::Low, vec![], false).await.unwrap();
+        core.process_queue().await.unwrap();
+
+        let purged = core.purge_tenant("acme").await.unwrap();
+        assert_eq!(purged["jobs"], 1);
+        assert_eq!(purged["analyses"], 1);
+        assert_eq!(purged["hash_records"], 1);
+        assert!(core.get_analysis("acme", &acme).await.unwrap().is_none());
+        assert!(core.job_store.load_sample(&acme).unwrap().is_none());
+        assert!(core.get_queue_status("acme").await.unwrap().is_empty());
+        assert!(core.get_analysis_status("globex", &globex).await.unwrap().is_some());
+    }
+}
@@ -0,0 +1,187 @@
+//! Timeline events for incident reconstruction
+//!
+//! Flattens an analysis' behavioural record (API calls, file and registry
+//! changes, network activity) into timestamped events, in the shape the
+//! SecOp core accepts as external events when building an incident
+//! timeline.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::error::{CoreError, CoreResult};
+use crate::{SandboxAnalysis, SandboxCore, ThreatLevel};
+
+/// Value of `source` on every event produced here
+pub const TIMELINE_SOURCE: &str = "sandbox";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineEvent {
+    pub timestamp: DateTime<Utc>,
+    pub source: String,
+    /// Sample the event was observed for
+    pub source_ref: String,
+    pub kind: String,
+    pub summary: String,
+    pub severity: Option<String>,
+    pub attributes: HashMap<String, String>,
+}
+
+struct EventBuilder<'a> {
+    sample_id: &'a str,
+    events: Vec<TimelineEvent>,
+}
+
+impl EventBuilder<'_> {
+    fn push(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        kind: &str,
+        summary: String,
+        severity: Option<&str>,
+        attributes: &[(&str, String)],
+    ) {
+        self.events.push(TimelineEvent {
+            timestamp,
+            source: TIMELINE_SOURCE.to_string(),
+            source_ref: self.sample_id.to_string(),
+            kind: kind.to_string(),
+            summary,
+            severity: severity.map(str::to_string),
+            attributes: attributes
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.clone()))
+                .collect(),
+        });
+    }
+}
+
+fn threat_severity(level: &ThreatLevel) -> Option<&'static str> {
+    match level {
+        ThreatLevel::None => None,
+        ThreatLevel::Low => Some("low"),
+        ThreatLevel::Medium => Some("medium"),
+        ThreatLevel::High => Some("high"),
+        ThreatLevel::Critical => Some("critical"),
+    }
+}
+
+/// Every timestamped observation in an analysis, oldest first
+pub fn analysis_timeline(analysis: &SandboxAnalysis) -> Vec<TimelineEvent> {
+    let sample = &analysis.sample_info;
+    let mut builder = EventBuilder { sample_id: &sample.sample_id, events: Vec::new() };
+
+    builder.push(
+        sample.submission_time,
+        "sample_submitted",
+        format!("{} submitted for analysis", sample.file_name),
+        None,
+        &[("sha256", sample.file_hash_sha256.clone())],
+    );
+
+    let behavior = &analysis.behavioral_analysis;
+    for call in &behavior.api_calls.call_timeline {
+        builder.push(
+            call.timestamp,
+            "api_call",
+            call.api_name.clone(),
+            None,
+            &[("process_id", call.process_id.to_string()), ("return_value", call.return_value.clone())],
+        );
+    }
+
+    let changes = &behavior.system_changes;
+    for (kind, files) in [
+        ("file_created", &changes.files_created),
+        ("file_modified", &changes.files_modified),
+        ("file_deleted", &changes.files_deleted),
+    ] {
+        for file in files {
+            let severity = (file.is_executable || file.is_system_file).then_some("medium");
+            builder.push(
+                file.timestamp,
+                kind,
+                file.file_path.clone(),
+                severity,
+                &[("process", file.process_name.clone()), ("process_id", file.process_id.to_string())],
+            );
+        }
+    }
+    for change in &changes.registry_changes {
+        builder.push(
+            change.timestamp,
+            "registry_change",
+            format!("{} {}\\{}", change.operation, change.key_path, change.value_name),
+            None,
+            &[
+                ("process", change.process_name.clone()),
+                ("new_value", change.new_value.clone().unwrap_or_default()),
+            ],
+        );
+    }
+
+    for connection in &analysis.network_analysis.connections {
+        builder.push(
+            connection.first_seen,
+            "network_connection",
+            format!("{} {}:{}", connection.protocol, connection.remote_address, connection.remote_port),
+            None,
+            &[("process", connection.process_name.clone()), ("bytes_sent", connection.bytes_sent.to_string())],
+        );
+    }
+    for query in &analysis.network_analysis.dns_queries {
+        builder.push(
+            query.timestamp,
+            "dns_query",
+            query.domain.clone(),
+            None,
+            &[("query_type", query.query_type.clone()), ("process", query.process_name.clone())],
+        );
+    }
+
+    builder.push(
+        analysis.analysis_metadata.analysis_end,
+        "analysis_completed",
+        format!("Verdict {:?} ({:.0}% confidence)", analysis.verdict, analysis.confidence_score * 100.0),
+        threat_severity(&analysis.threat_level),
+        &[("analysis_id", analysis.analysis_id.clone())],
+    );
+
+    let mut events = builder.events;
+    events.sort_by_key(|event| event.timestamp);
+    events
+}
+
+impl SandboxCore {
+    /// Timeline events for a completed analysis
+    pub async fn get_timeline_events(&self, tenant_id: &str, sample_id: &str) -> CoreResult<Vec<TimelineEvent>> {
+        let analysis = self
+            .get_analysis(tenant_id, sample_id)
+            .await?
+            .ok_or_else(|| CoreError::not_found(format!("No completed analysis for sample {}", sample_id)))?;
+        Ok(analysis_timeline(&analysis))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AnalysisPriority;
+
+    #[tokio::test]
+    async fn test_events_are_ordered_and_include_submission_and_verdict() {
+        let core = SandboxCore::new().unwrap();
+        let sample_id = core
+            .submit_sample("default", b"MZ timeline", "timeline.exe".to_string(), AnalysisPriority::High, vec![], false)
+            .await
+            .unwrap();
+        assert!(core.get_timeline_events("default", &sample_id).await.is_err());
+        core.process_queue().await.unwrap();
+
+        let events = core.get_timeline_events("default", &sample_id).await.unwrap();
+        assert!(events.windows(2).all(|pair| pair[0].timestamp <= pair[1].timestamp));
+        assert!(events.iter().all(|event| event.source == TIMELINE_SOURCE && event.source_ref == sample_id));
+        assert!(events.iter().any(|event| event.kind == "sample_submitted"));
+        assert!(events.iter().any(|event| event.kind == "analysis_completed"));
+    }
+}
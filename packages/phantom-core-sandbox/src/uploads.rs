@@ -0,0 +1,498 @@
+//! Chunked uploads
+//!
+//! A sample handed over in one `Buffer` is capped by what Node can allocate
+//! and sits in memory on both sides of the call. Multi-gigabyte samples and
+//! memory dumps are uploaded in chunks instead: `begin_upload` opens a
+//! session, `append_chunk` writes each chunk at its offset to an owner-only
+//! spool file and feeds it to a running SHA-256, and `finish_upload` checks
+//! the declared size and checksum before anything is analyzed. A retried
+//! chunk the session already holds is acknowledged without being written
+//! twice.
+//!
+//! A finished sample is queued like any submission. A finished memory dump
+//! is memory-mapped from the spool file and run through the memory plugins
+//! without being copied onto the heap. Sessions idle for longer than
+//! `idle_timeout_secs` are dropped together with their spool file.
+
+use chrono::{DateTime, Duration, Utc};
+use memmap2::Mmap;
+use napi::bindgen_prelude::Buffer;
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::io::AsyncWriteExt;
+
+use crate::error::{CoreError, CoreResult};
+use crate::memory::{DumpOptions, MemoryDumpReport};
+use crate::{parse_priority, SandboxCore, SandboxCoreNapi};
+
+fn default_spool_dir() -> PathBuf {
+    std::env::temp_dir().join("phantom-sandbox-uploads")
+}
+
+fn default_max_upload_bytes() -> u64 {
+    16 * 1024 * 1024 * 1024
+}
+
+fn default_max_chunk_bytes() -> u64 {
+    64 * 1024 * 1024
+}
+
+fn default_idle_timeout_secs() -> u64 {
+    3600
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadConfig {
+    #[serde(default = "default_spool_dir")]
+    pub spool_dir: PathBuf,
+    #[serde(default = "default_max_upload_bytes")]
+    pub max_upload_bytes: u64,
+    #[serde(default = "default_max_chunk_bytes")]
+    pub max_chunk_bytes: u64,
+    #[serde(default = "default_idle_timeout_secs")]
+    pub idle_timeout_secs: u64,
+}
+
+impl Default for UploadConfig {
+    fn default() -> Self {
+        Self {
+            spool_dir: default_spool_dir(),
+            max_upload_bytes: default_max_upload_bytes(),
+            max_chunk_bytes: default_max_chunk_bytes(),
+            idle_timeout_secs: default_idle_timeout_secs(),
+        }
+    }
+}
+
+impl UploadConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_upload_bytes == 0 || self.max_chunk_bytes == 0 {
+            return Err("max_upload_bytes and max_chunk_bytes must be positive".to_string());
+        }
+        if self.idle_timeout_secs == 0 {
+            return Err("idle_timeout_secs must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadKind {
+    #[default]
+    Sample,
+    MemoryDump,
+}
+
+/// What is being uploaded and what to do with it once complete
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadRequest {
+    pub file_name: String,
+    #[serde(default)]
+    pub kind: UploadKind,
+    /// Total size, when the client knows it up front; checked on finish
+    #[serde(default)]
+    pub size: Option<u64>,
+    /// `low`, `normal`, `high`, `critical` or `emergency`, for samples
+    #[serde(default)]
+    pub priority: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub force_reanalyze: bool,
+    /// Describes raw memory dumps
+    #[serde(default)]
+    pub dump_options: Option<DumpOptions>,
+}
+
+/// Progress of an upload session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadSession {
+    pub upload_id: String,
+    pub tenant_id: String,
+    pub file_name: String,
+    pub kind: UploadKind,
+    pub declared_size: Option<u64>,
+    /// Bytes written so far; the offset the next chunk starts at
+    pub received_bytes: u64,
+    pub chunks: u64,
+    pub max_chunk_bytes: u64,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What a finished upload turned into
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UploadOutcome {
+    pub upload_id: String,
+    pub kind: UploadKind,
+    pub size: u64,
+    pub sha256: String,
+    /// Sample queued for analysis, or the existing sample with these bytes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_report: Option<MemoryDumpReport>,
+}
+
+struct OpenUpload {
+    session: UploadSession,
+    request: UploadRequest,
+    path: PathBuf,
+    file: Option<tokio::fs::File>,
+    hasher: Sha256,
+}
+
+/// Open session; the mutex is held while a chunk is written
+#[derive(Clone)]
+struct UploadHandle {
+    tenant_id: String,
+    path: PathBuf,
+    open: Arc<tokio::sync::Mutex<OpenUpload>>,
+}
+
+#[derive(Default)]
+pub struct UploadState {
+    config: RwLock<UploadConfig>,
+    /// Open sessions by upload id
+    sessions: Mutex<HashMap<String, UploadHandle>>,
+}
+
+impl UploadState {
+    fn config(&self) -> UploadConfig {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn session(&self, tenant_id: &str, upload_id: &str) -> CoreResult<UploadHandle> {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(upload_id)
+            .filter(|upload| upload.tenant_id == tenant_id)
+            .cloned()
+            .ok_or_else(|| CoreError::not_found(format!("Upload {} not found", upload_id)))
+    }
+
+    fn take(&self, tenant_id: &str, upload_id: &str) -> Option<UploadHandle> {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        if sessions.get(upload_id)?.tenant_id != tenant_id {
+            return None;
+        }
+        sessions.remove(upload_id)
+    }
+
+    /// Drop sessions idle for longer than the configured timeout; a session
+    /// writing a chunk is not idle
+    async fn expire(&self) {
+        let cutoff = Utc::now() - Duration::seconds(self.config().idle_timeout_secs as i64);
+        let idle: Vec<UploadHandle> = {
+            let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+            let expired: Vec<String> = sessions
+                .iter()
+                .filter(|(_, upload)| upload.open.try_lock().is_ok_and(|open| open.session.updated_at < cutoff))
+                .map(|(upload_id, _)| upload_id.clone())
+                .collect();
+            expired.iter().filter_map(|upload_id| sessions.remove(upload_id)).collect()
+        };
+        for upload in idle {
+            let open = upload.open.lock().await;
+            log::info!("Dropping idle upload {}", open.session.upload_id);
+            remove_spool(&open.path).await;
+        }
+    }
+
+    pub(crate) fn forget_tenant(&self, tenant_id: &str) -> usize {
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        let before = sessions.len();
+        sessions.retain(|_, upload| {
+            if upload.tenant_id != tenant_id {
+                return true;
+            }
+            // A chunk still being written lands in the unlinked file
+            if let Err(e) = std::fs::remove_file(&upload.path) {
+                log::warn!("Failed to remove upload spool {}: {}", upload.path.display(), e);
+            }
+            false
+        });
+        before - sessions.len()
+    }
+}
+
+async fn remove_spool(path: &Path) {
+    if let Err(e) = tokio::fs::remove_file(path).await {
+        if e.kind() != std::io::ErrorKind::NotFound {
+            log::warn!("Failed to remove upload spool {}: {}", path.display(), e);
+        }
+    }
+}
+
+impl SandboxCore {
+    /// Replace the upload limits and spool directory; open sessions keep their spool files
+    pub fn configure_uploads(&self, config: UploadConfig) -> CoreResult<()> {
+        config.validate().map_err(CoreError::validation)?;
+        *self.uploads.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+        Ok(())
+    }
+
+    pub async fn begin_upload(&self, tenant_id: &str, request: UploadRequest) -> CoreResult<UploadSession> {
+        if request.file_name.trim().is_empty() {
+            return Err(CoreError::validation("Upload file name is required"));
+        }
+        let config = self.uploads.config();
+        if let Some(size) = request.size {
+            if size == 0 || size > config.max_upload_bytes {
+                return Err(CoreError::validation(format!("Upload size must be between 1 and {} bytes", config.max_upload_bytes)));
+            }
+        }
+        self.uploads.expire().await;
+
+        let upload_id = format!("upl_{}", uuid::Uuid::new_v4().simple());
+        tokio::fs::create_dir_all(&config.spool_dir)
+            .await
+            .map_err(|e| CoreError::backend(format!("Failed to create spool directory {}: {}", config.spool_dir.display(), e)))?;
+        let path = config.spool_dir.join(format!("{}.part", upload_id));
+        let mut options = tokio::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let file = options.open(&path).await.map_err(|e| CoreError::backend(format!("Failed to create {}: {}", path.display(), e)))?;
+
+        let now = Utc::now();
+        let session = UploadSession {
+            upload_id: upload_id.clone(),
+            tenant_id: tenant_id.to_string(),
+            file_name: request.file_name.clone(),
+            kind: request.kind,
+            declared_size: request.size,
+            received_bytes: 0,
+            chunks: 0,
+            max_chunk_bytes: config.max_chunk_bytes,
+            started_at: now,
+            updated_at: now,
+        };
+        let open = OpenUpload { session: session.clone(), request, path: path.clone(), file: Some(file), hasher: Sha256::new() };
+        let handle = UploadHandle { tenant_id: tenant_id.to_string(), path, open: Arc::new(tokio::sync::Mutex::new(open)) };
+        self.uploads.sessions.lock().unwrap_or_else(|e| e.into_inner()).insert(upload_id, handle);
+        Ok(session)
+    }
+
+    /// Write `data` at `offset`, which has to be where the previous chunk ended
+    pub async fn append_chunk(&self, tenant_id: &str, upload_id: &str, offset: u64, data: &[u8]) -> CoreResult<UploadSession> {
+        let config = self.uploads.config();
+        if data.is_empty() {
+            return Err(CoreError::validation("Chunk is empty"));
+        }
+        if data.len() as u64 > config.max_chunk_bytes {
+            return Err(CoreError::validation(format!("Chunk of {} bytes exceeds the {} byte limit", data.len(), config.max_chunk_bytes)));
+        }
+        let upload = self.uploads.session(tenant_id, upload_id)?;
+        let mut open = upload.open.lock().await;
+        let received = open.session.received_bytes;
+        let end = offset.saturating_add(data.len() as u64);
+        if end <= received {
+            // A chunk whose acknowledgement was lost
+            return Ok(open.session.clone());
+        }
+        if offset != received {
+            return Err(CoreError::validation(format!("Upload {} expects the chunk at offset {}, got {}", upload_id, received, offset)));
+        }
+        let limit = open.session.declared_size.unwrap_or(config.max_upload_bytes).min(config.max_upload_bytes);
+        if end > limit {
+            return Err(CoreError::validation(format!("Upload {} would exceed {} bytes", upload_id, limit)));
+        }
+
+        let path = open.path.clone();
+        let file = open.file.as_mut().ok_or_else(|| CoreError::backend(format!("Upload {} is closed", upload_id)))?;
+        file.write_all(data).await.map_err(|e| CoreError::backend(format!("Failed to write {}: {}", path.display(), e)))?;
+        open.hasher.update(data);
+        open.session.received_bytes = end;
+        open.session.chunks += 1;
+        open.session.updated_at = Utc::now();
+        Ok(open.session.clone())
+    }
+
+    pub async fn upload_status(&self, tenant_id: &str, upload_id: &str) -> CoreResult<UploadSession> {
+        let upload = self.uploads.session(tenant_id, upload_id)?;
+        let session = upload.open.lock().await.session.clone();
+        Ok(session)
+    }
+
+    /// Drop an upload and its spool file; false when there was none
+    pub async fn abort_upload(&self, tenant_id: &str, upload_id: &str) -> bool {
+        let Some(upload) = self.uploads.take(tenant_id, upload_id) else { return false };
+        let path = upload.open.lock().await.path.clone();
+        remove_spool(&path).await;
+        true
+    }
+
+    /// Verify the upload against the client's SHA-256, then queue the sample
+    /// or analyze the memory dump. The spool file is removed either way.
+    pub async fn finish_upload(&self, tenant_id: &str, upload_id: &str, sha256: &str) -> CoreResult<UploadOutcome> {
+        let upload = self.uploads.take(tenant_id, upload_id).ok_or_else(|| CoreError::not_found(format!("Upload {} not found", upload_id)))?;
+        let mut open = upload.open.lock().await;
+        let outcome = self.complete_upload(tenant_id, &mut open, sha256).await;
+        remove_spool(&open.path).await;
+        outcome
+    }
+
+    async fn complete_upload(&self, tenant_id: &str, open: &mut OpenUpload, sha256: &str) -> CoreResult<UploadOutcome> {
+        let upload_id = open.session.upload_id.clone();
+        if let Some(file) = open.file.take() {
+            file.sync_all().await.map_err(|e| CoreError::backend(format!("Failed to flush {}: {}", open.path.display(), e)))?;
+        }
+        let size = open.session.received_bytes;
+        if size == 0 {
+            return Err(CoreError::validation(format!("Upload {} has no data", upload_id)));
+        }
+        if let Some(declared) = open.session.declared_size.filter(|declared| *declared != size) {
+            return Err(CoreError::validation(format!("Upload {} received {} of {} bytes", upload_id, size, declared)));
+        }
+        let digest = format!("{:x}", std::mem::take(&mut open.hasher).finalize());
+        if !digest.eq_ignore_ascii_case(sha256.trim()) {
+            return Err(CoreError::validation(format!("Upload {} checksum mismatch: received data hashes to {}", upload_id, digest)));
+        }
+
+        let file = std::fs::File::open(&open.path).map_err(|e| CoreError::backend(format!("Failed to open {}: {}", open.path.display(), e)))?;
+        // SAFETY: the spool file is private to this session, which no longer
+        // accepts chunks, and is only removed after the map is dropped
+        let map = unsafe { Mmap::map(&file) }.map_err(|e| CoreError::backend(format!("Failed to map {}: {}", open.path.display(), e)))?;
+        if map.len() as u64 != size {
+            return Err(CoreError::backend(format!("Spool file of upload {} holds {} bytes, expected {}", upload_id, map.len(), size)));
+        }
+
+        let request = &open.request;
+        let mut outcome = UploadOutcome { upload_id, kind: request.kind, size, sha256: digest, sample_id: None, memory_report: None };
+        match request.kind {
+            UploadKind::Sample => {
+                let priority = parse_priority(request.priority.as_deref());
+                let sample_id = self
+                    .enqueue_sample(tenant_id, &map, request.file_name.clone(), priority, request.tags.clone(), request.force_reanalyze, None, None)
+                    .await?;
+                outcome.sample_id = Some(sample_id);
+            }
+            UploadKind::MemoryDump => {
+                let analyzer = self.memory_analyzer.read().map_err(|e| CoreError::backend(e.to_string()))?.clone();
+                let options = request.dump_options.clone().unwrap_or_else(|| DumpOptions::from_path(&request.file_name));
+                let report = tokio::task::spawn_blocking(move || analyzer.analyze(&map, &options))
+                    .await
+                    .map_err(|e| CoreError::backend(format!("Memory analysis task failed: {}", e)))?
+                    .map_err(CoreError::validation)?;
+                outcome.memory_report = Some(report);
+            }
+        }
+        log::info!("Finished upload {} ({} bytes, {:?})", outcome.upload_id, size, outcome.kind);
+        Ok(outcome)
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// Open a chunked upload (`{"file_name": "mem.dmp", "kind": "memory_dump",
+    /// "size": 4294967296}`) for files too large to submit in one buffer
+    #[napi]
+    pub async fn begin_upload(&self, request_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let request: UploadRequest = serde_json::from_str(&request_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse upload request: {}", e)))?;
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", &request.file_name)?;
+        let params = serde_json::json!({ "file_name": request.file_name, "kind": request.kind, "size": request.size });
+        let file_name = request.file_name.clone();
+        let session = self.inner.begin_upload(&tenant_id, request).await;
+        let resource = session.as_ref().map_or(file_name, |session| session.upload_id.clone());
+        let session = self.audit.record(&actor, "begin_upload", &resource, params, session)
+            .map_err(|e| e.context("Failed to begin upload"))?;
+        serde_json::to_string(&session)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize upload session: {}", e)))
+    }
+
+    /// Append the next chunk; `offset` is the session's `received_bytes`
+    #[napi]
+    pub async fn append_chunk(&self, upload_id: String, offset: i64, chunk: Buffer, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        self.authorize(auth_token, "analysis:submit", &upload_id)?;
+        let offset = u64::try_from(offset).map_err(|_| napi::Error::from_reason("Chunk offset must not be negative"))?;
+        let session = self.inner.append_chunk(&tenant_id, &upload_id, offset, &chunk).await?;
+        serde_json::to_string(&session)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize upload session: {}", e)))
+    }
+
+    #[napi]
+    pub async fn get_upload_status(&self, upload_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let session = self.inner.upload_status(&tenant_id, &upload_id).await?;
+        serde_json::to_string(&session)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize upload session: {}", e)))
+    }
+
+    /// Verify the upload against its hex SHA-256 and queue or analyze it
+    #[napi]
+    pub async fn finish_upload(&self, upload_id: String, sha256: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", &upload_id)?;
+        let outcome = self.inner.finish_upload(&tenant_id, &upload_id, &sha256).await;
+        let params = serde_json::json!({ "sha256": sha256 });
+        let outcome = self.audit.record(&actor, "finish_upload", &upload_id, params, outcome)
+            .map_err(|e| e.context("Failed to finish upload"))?;
+        serde_json::to_string(&outcome)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize upload outcome: {}", e)))
+    }
+
+    #[napi]
+    pub async fn abort_upload(&self, upload_id: String, auth_token: Option<String>) -> napi::Result<bool> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", &upload_id)?;
+        let aborted = self.inner.abort_upload(&tenant_id, &upload_id).await;
+        self.audit.record(&actor, "abort_upload", &upload_id, serde_json::json!({}), Ok::<_, String>(aborted))
+            .map_err(|e| napi::Error::from_reason(format!("Failed to abort upload: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_chunked_upload_verifies_checksum_and_queues_sample() {
+        let core = SandboxCore::new().unwrap();
+        let spool_dir = std::env::temp_dir().join(format!("phantom-uploads-{}", uuid::Uuid::new_v4()));
+        core.configure_uploads(UploadConfig { spool_dir: spool_dir.clone(), max_chunk_bytes: 4, ..UploadConfig::default() }).unwrap();
+        let data = b"MZ\x90\x00chunked sample";
+        let sha256 = format!("{:x}", Sha256::digest(data));
+        let request = || UploadRequest {
+            file_name: "big.exe".to_string(),
+            kind: UploadKind::Sample,
+            size: Some(data.len() as u64),
+            priority: Some("high".to_string()),
+            tags: vec![],
+            force_reanalyze: false,
+            dump_options: None,
+        };
+
+        let session = core.begin_upload("default", request()).await.unwrap();
+        assert!(core.upload_status("acme", &session.upload_id).await.is_err());
+        for (index, chunk) in data.chunks(4).enumerate() {
+            core.append_chunk("default", &session.upload_id, index as u64 * 4, chunk).await.unwrap();
+        }
+        // A resent chunk is acknowledged, one past the end of the data is not
+        assert_eq!(core.append_chunk("default", &session.upload_id, 4, &data[4..8]).await.unwrap().received_bytes, data.len() as u64);
+        assert!(core.append_chunk("default", &session.upload_id, 40, b"x").await.is_err());
+
+        let outcome = core.finish_upload("default", &session.upload_id, &sha256.to_uppercase()).await.unwrap();
+        let sample_id = outcome.sample_id.unwrap();
+        let job = core.get_analysis_status("default", &sample_id).await.unwrap().unwrap();
+        assert!(matches!(job.priority, crate::AnalysisPriority::High));
+        assert!(core.upload_status("default", &session.upload_id).await.is_err());
+        assert_eq!(std::fs::read_dir(&spool_dir).unwrap().count(), 0);
+
+        let corrupted = core.begin_upload("default", request()).await.unwrap();
+        core.append_chunk("default", &corrupted.upload_id, 0, b"MZ").await.unwrap();
+        let error = core.finish_upload("default", &corrupted.upload_id, &sha256).await.unwrap_err();
+        assert!(error.to_string().contains("received 2 of"));
+        assert_eq!(std::fs::read_dir(&spool_dir).unwrap().count(), 0);
+        let _ = std::fs::remove_dir_all(&spool_dir);
+    }
+}
@@ -0,0 +1,905 @@
+//! URL detonation
+//!
+//! URLs are submitted like samples (`submit_url`) and analysed by loading them
+//! in a headless browser instead of executing a file. A `BrowserDriver` loads
+//! the page and reports the redirect chain, the final DOM, a screenshot, the
+//! files the page served and the TLS certificate of the final host. The
+//! result lands in `NetworkAnalysis::url_detonation`; served files are queued
+//! as samples of their own once the URL analysis is stored.
+//!
+//! The command driver runs an external browser harness (Playwright,
+//! Puppeteer, ...) that prints a `BrowserCapture` as JSON on stdout, so any
+//! browser can be plugged in without linking it into the core.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use napi_derive::napi;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::dedup::sha256_hex;
+use crate::detonation::{CommandRunner, SystemCommandRunner};
+use crate::email::decode_base64;
+use crate::{
+    AnalysisJob, AnalysisPriority, DNSQuery, ExtractedIOC, HTTPRequest, NetworkAnalysis, SandboxCore, SandboxCoreNapi,
+};
+
+/// Extra time the harness gets to exit after its own page timeout
+const HARNESS_GRACE: Duration = Duration::from_secs(15);
+
+// Configuration
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "driver", rename_all = "snake_case")]
+pub enum BrowserDriverConfig {
+    #[default]
+    Simulated,
+    Command(BrowserCommandConfig),
+}
+
+/// External browser harness; `{url}`, `{user_agent}`, `{viewport}` and
+/// `{timeout_ms}` in `args` are replaced per detonation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrowserCommandConfig {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UrlDetonationConfig {
+    #[serde(default)]
+    pub driver: BrowserDriverConfig,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default = "default_user_agent")]
+    pub user_agent: String,
+    #[serde(default = "default_viewport")]
+    pub viewport: String,
+    #[serde(default = "default_max_dom_bytes")]
+    pub max_dom_bytes: usize,
+    #[serde(default = "default_max_screenshot_bytes")]
+    pub max_screenshot_bytes: usize,
+    #[serde(default = "default_max_download_bytes")]
+    pub max_download_bytes: usize,
+    #[serde(default = "default_max_downloads")]
+    pub max_downloads: usize,
+    /// Queue served files for file analysis
+    #[serde(default = "default_submit_downloads")]
+    pub submit_downloads: bool,
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+fn default_user_agent() -> String {
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/124.0 Safari/537.36".to_string()
+}
+
+fn default_viewport() -> String {
+    "1366x768".to_string()
+}
+
+fn default_max_dom_bytes() -> usize {
+    1024 * 1024
+}
+
+fn default_max_screenshot_bytes() -> usize {
+    4 * 1024 * 1024
+}
+
+fn default_max_download_bytes() -> usize {
+    32 * 1024 * 1024
+}
+
+fn default_max_downloads() -> usize {
+    10
+}
+
+fn default_submit_downloads() -> bool {
+    true
+}
+
+impl Default for UrlDetonationConfig {
+    fn default() -> Self {
+        Self {
+            driver: BrowserDriverConfig::default(),
+            timeout_secs: default_timeout_secs(),
+            user_agent: default_user_agent(),
+            viewport: default_viewport(),
+            max_dom_bytes: default_max_dom_bytes(),
+            max_screenshot_bytes: default_max_screenshot_bytes(),
+            max_download_bytes: default_max_download_bytes(),
+            max_downloads: default_max_downloads(),
+            submit_downloads: default_submit_downloads(),
+        }
+    }
+}
+
+impl UrlDetonationConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if let BrowserDriverConfig::Command(command) = &self.driver {
+            if command.program.trim().is_empty() {
+                return Err("Browser harness program is required".to_string());
+            }
+            if !command.args.iter().any(|arg| arg.contains("{url}")) {
+                return Err("Browser harness arguments must include {url}".to_string());
+            }
+        }
+        if self.timeout_secs == 0 {
+            return Err("URL detonation timeout must be positive".to_string());
+        }
+        Ok(())
+    }
+
+    /// Build the driver for this config
+    pub fn build(&self, runner: Arc<dyn CommandRunner>) -> Arc<dyn BrowserDriver> {
+        match &self.driver {
+            BrowserDriverConfig::Simulated => Arc::new(SimulatedBrowser),
+            BrowserDriverConfig::Command(command) => Arc::new(CommandBrowser::new(command.clone(), runner)),
+        }
+    }
+}
+
+// Driver interface
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaptureOptions {
+    pub user_agent: String,
+    pub viewport: String,
+    pub timeout: Duration,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedirectHop {
+    pub url: String,
+    #[serde(default)]
+    pub status_code: Option<u16>,
+    /// `http`, `meta` or `javascript`
+    #[serde(default = "default_redirect_via")]
+    pub via: String,
+}
+
+fn default_redirect_via() -> String {
+    "http".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsCertificate {
+    pub subject: String,
+    pub issuer: String,
+    #[serde(default)]
+    pub serial_number: String,
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+    #[serde(default)]
+    pub subject_alt_names: Vec<String>,
+    #[serde(default)]
+    pub fingerprint_sha256: String,
+    #[serde(default)]
+    pub protocol: Option<String>,
+}
+
+impl TlsCertificate {
+    pub fn self_signed(&self) -> bool {
+        self.subject == self.issuer
+    }
+
+    pub fn expired_at(&self, at: DateTime<Utc>) -> bool {
+        at < self.not_before || at > self.not_after
+    }
+
+    /// The certificate names `host`, directly or through a one-label wildcard
+    pub fn covers(&self, host: &str) -> bool {
+        let host = host.to_ascii_lowercase();
+        self.subject_alt_names.iter().map(|name| name.to_ascii_lowercase()).any(|name| match name.strip_prefix("*.") {
+            Some(suffix) => host.split_once('.').is_some_and(|(_, rest)| rest == suffix),
+            None => name == host,
+        })
+    }
+}
+
+/// A request the page made while loading
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageRequest {
+    pub url: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    #[serde(default)]
+    pub status_code: Option<u16>,
+    #[serde(default)]
+    pub resource_type: Option<String>,
+    #[serde(default)]
+    pub remote_address: Option<String>,
+    #[serde(default)]
+    pub response_size: u64,
+}
+
+fn default_method() -> String {
+    "GET".to_string()
+}
+
+/// A file the page served as a download, base64 encoded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapturedDownload {
+    pub url: String,
+    pub filename: String,
+    pub content: String,
+}
+
+/// What a browser driver reports for one page load
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrowserCapture {
+    pub final_url: String,
+    /// Every hop after the requested URL, ending with the final URL
+    #[serde(default)]
+    pub redirect_chain: Vec<RedirectHop>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub dom: String,
+    /// PNG, base64 encoded
+    #[serde(default)]
+    pub screenshot_png: Option<String>,
+    #[serde(default)]
+    pub downloads: Vec<CapturedDownload>,
+    #[serde(default)]
+    pub tls: Option<TlsCertificate>,
+    #[serde(default)]
+    pub requests: Vec<PageRequest>,
+}
+
+/// Loads a URL in a headless browser
+#[async_trait]
+pub trait BrowserDriver: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn capture(&self, url: &str, options: &CaptureOptions) -> Result<BrowserCapture, String>;
+}
+
+/// Answers without a browser, for environments with no harness installed
+pub struct SimulatedBrowser;
+
+#[async_trait]
+impl BrowserDriver for SimulatedBrowser {
+    fn name(&self) -> &'static str {
+        "simulated"
+    }
+
+    async fn capture(&self, url: &str, _options: &CaptureOptions) -> Result<BrowserCapture, String> {
+        let parsed = url::Url::parse(url).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+        let host = parsed.host_str().unwrap_or_default().to_string();
+        let now = Utc::now();
+        let tls = (parsed.scheme() == "https").then(|| TlsCertificate {
+            subject: format!("CN={}", host),
+            issuer: "CN=Phantom Simulated CA".to_string(),
+            serial_number: "01".to_string(),
+            not_before: now - chrono::Duration::days(30),
+            not_after: now + chrono::Duration::days(60),
+            subject_alt_names: vec![host.clone()],
+            fingerprint_sha256: sha256_hex(host.as_bytes()),
+            protocol: Some("TLSv1.3".to_string()),
+        });
+        Ok(BrowserCapture {
+            final_url: url.to_string(),
+            redirect_chain: Vec::new(),
+            title: Some(host.clone()),
+            dom: format!("<html><head><title>{}</title></head><body></body></html>", host),
+            screenshot_png: None,
+            downloads: Vec::new(),
+            tls,
+            requests: vec![PageRequest {
+                url: url.to_string(),
+                method: default_method(),
+                status_code: Some(200),
+                resource_type: Some("document".to_string()),
+                remote_address: None,
+                response_size: 0,
+            }],
+        })
+    }
+}
+
+/// Runs an external browser harness that prints a `BrowserCapture` as JSON
+pub struct CommandBrowser {
+    config: BrowserCommandConfig,
+    runner: Arc<dyn CommandRunner>,
+}
+
+impl CommandBrowser {
+    pub fn new(config: BrowserCommandConfig, runner: Arc<dyn CommandRunner>) -> Self {
+        Self { config, runner }
+    }
+}
+
+#[async_trait]
+impl BrowserDriver for CommandBrowser {
+    fn name(&self) -> &'static str {
+        "command"
+    }
+
+    async fn capture(&self, url: &str, options: &CaptureOptions) -> Result<BrowserCapture, String> {
+        let timeout_ms = options.timeout.as_millis().to_string();
+        let args: Vec<String> = self
+            .config
+            .args
+            .iter()
+            .map(|arg| {
+                arg.replace("{url}", url)
+                    .replace("{user_agent}", &options.user_agent)
+                    .replace("{viewport}", &options.viewport)
+                    .replace("{timeout_ms}", &timeout_ms)
+            })
+            .collect();
+        let output = self.runner.run(&self.config.program, &args, options.timeout + HARNESS_GRACE).await?;
+        if output.timed_out {
+            return Err(format!("Browser harness timed out loading {}", url));
+        }
+        if !output.success() {
+            return Err(format!("Browser harness failed: {}", output.stderr.trim()));
+        }
+        serde_json::from_str(&output.stdout).map_err(|e| format!("Browser harness returned invalid capture: {}", e))
+    }
+}
+
+// Report
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Screenshot {
+    /// PNG, base64 encoded
+    pub png: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadedPayload {
+    pub url: String,
+    pub filename: String,
+    pub size: u64,
+    pub sha256: String,
+    /// Sample the payload was queued as
+    #[serde(default)]
+    pub sample_id: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Payload bytes, base64 encoded, until they are queued for analysis;
+    /// kept in the report so cluster workers hand them to the coordinator
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UrlDetonation {
+    pub requested_url: String,
+    pub final_url: String,
+    pub driver: String,
+    pub redirect_chain: Vec<RedirectHop>,
+    pub page_title: Option<String>,
+    pub dom: String,
+    pub dom_truncated: bool,
+    pub dom_sha256: String,
+    pub screenshot: Option<Screenshot>,
+    pub downloads: Vec<DownloadedPayload>,
+    pub tls: Option<TlsCertificate>,
+    pub requests: Vec<PageRequest>,
+    /// `cross_domain_redirect`, `credential_form`, `self_signed_certificate`, ...
+    pub findings: Vec<String>,
+    pub errors: Vec<String>,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+}
+
+fn host_of(url: &str) -> Option<String> {
+    url::Url::parse(url).ok()?.host_str().map(|host| host.trim_matches(['[', ']']).to_ascii_lowercase())
+}
+
+/// Last two labels; good enough to tell a redirect left the site
+fn site_of(host: &str) -> String {
+    let labels: Vec<&str> = host.split('.').collect();
+    labels[labels.len().saturating_sub(2)..].join(".")
+}
+
+fn truncate_utf8(text: &mut String, max_bytes: usize) -> bool {
+    if text.len() <= max_bytes {
+        return false;
+    }
+    let mut cut = max_bytes;
+    while !text.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    text.truncate(cut);
+    true
+}
+
+impl UrlDetonation {
+    /// Bound a capture to the configured limits and derive findings
+    pub fn from_capture(
+        requested_url: &str,
+        driver: &str,
+        capture: BrowserCapture,
+        config: &UrlDetonationConfig,
+        started_at: DateTime<Utc>,
+    ) -> Self {
+        let mut errors = Vec::new();
+        let mut dom = capture.dom;
+        let dom_sha256 = sha256_hex(dom.as_bytes());
+        let dom_truncated = truncate_utf8(&mut dom, config.max_dom_bytes);
+
+        let screenshot = capture.screenshot_png.and_then(|png| {
+            let bytes = decode_base64(png.as_bytes());
+            if bytes.len() > config.max_screenshot_bytes {
+                errors.push(format!("Screenshot of {} bytes exceeds the {} byte limit", bytes.len(), config.max_screenshot_bytes));
+                return None;
+            }
+            Some(Screenshot { size: bytes.len() as u64, sha256: sha256_hex(&bytes), png })
+        });
+
+        if capture.downloads.len() > config.max_downloads {
+            errors.push(format!("{} downloads beyond the limit of {} were dropped", capture.downloads.len() - config.max_downloads, config.max_downloads));
+        }
+        let downloads = capture
+            .downloads
+            .into_iter()
+            .take(config.max_downloads)
+            .map(|download| {
+                let bytes = decode_base64(download.content.as_bytes());
+                let oversized = bytes.len() > config.max_download_bytes;
+                DownloadedPayload {
+                    url: download.url,
+                    filename: download.filename,
+                    size: bytes.len() as u64,
+                    sha256: sha256_hex(&bytes),
+                    sample_id: None,
+                    error: oversized.then(|| format!("Exceeds the {} byte download limit", config.max_download_bytes)),
+                    content: (!oversized && config.submit_downloads).then_some(download.content),
+                }
+            })
+            .collect();
+
+        let mut detonation = Self {
+            requested_url: requested_url.to_string(),
+            final_url: capture.final_url,
+            driver: driver.to_string(),
+            redirect_chain: capture.redirect_chain,
+            page_title: capture.title,
+            dom,
+            dom_truncated,
+            dom_sha256,
+            screenshot,
+            downloads,
+            tls: capture.tls,
+            requests: capture.requests,
+            findings: Vec::new(),
+            errors,
+            started_at,
+            duration_ms: (Utc::now() - started_at).num_milliseconds().max(0) as u64,
+        };
+        detonation.findings = detonation.derive_findings();
+        detonation
+    }
+
+    /// A detonation that could not load the page
+    pub fn failed(requested_url: &str, driver: &str, error: String, started_at: DateTime<Utc>) -> Self {
+        Self {
+            requested_url: requested_url.to_string(),
+            final_url: requested_url.to_string(),
+            driver: driver.to_string(),
+            redirect_chain: Vec::new(),
+            page_title: None,
+            dom: String::new(),
+            dom_truncated: false,
+            dom_sha256: sha256_hex(b""),
+            screenshot: None,
+            downloads: Vec::new(),
+            tls: None,
+            requests: Vec::new(),
+            findings: Vec::new(),
+            errors: vec![error],
+            started_at,
+            duration_ms: (Utc::now() - started_at).num_milliseconds().max(0) as u64,
+        }
+    }
+
+    fn derive_findings(&self) -> Vec<String> {
+        let mut findings = Vec::new();
+        let requested_site = host_of(&self.requested_url).map(|host| site_of(&host));
+        let final_host = host_of(&self.final_url);
+        if self.redirect_chain.iter().filter_map(|hop| host_of(&hop.url)).any(|host| Some(site_of(&host)) != requested_site) {
+            findings.push("cross_domain_redirect".to_string());
+        }
+        if self.redirect_chain.iter().any(|hop| hop.via != "http") {
+            findings.push("client_side_redirect".to_string());
+        }
+        let dom = self.dom.to_ascii_lowercase();
+        if dom.contains("type=\"password\"") || dom.contains("type='password'") || dom.contains("type=password") {
+            findings.push("credential_form".to_string());
+        }
+        if self.final_url.starts_with("http://") {
+            findings.push("plaintext_http".to_string());
+        }
+        if let Some(tls) = &self.tls {
+            if tls.self_signed() {
+                findings.push("self_signed_certificate".to_string());
+            }
+            if tls.expired_at(self.started_at) {
+                findings.push("expired_certificate".to_string());
+            }
+            if final_host.as_deref().is_some_and(|host| !tls.covers(host)) {
+                findings.push("certificate_name_mismatch".to_string());
+            }
+        }
+        if !self.downloads.is_empty() {
+            findings.push("served_download".to_string());
+        }
+        findings
+    }
+
+    /// Requests and lookups of the page load in the `NetworkAnalysis` shape
+    pub fn network_analysis(self, user_agent: &str) -> NetworkAnalysis {
+        let started_at = self.started_at;
+        let requested_host = host_of(&self.requested_url);
+        let hosts: BTreeSet<String> = self.requests.iter().filter_map(|request| host_of(&request.url)).collect();
+        let suspicious = !self.findings.is_empty();
+        let suspicious_domains = if self.findings.iter().any(|f| f == "cross_domain_redirect") {
+            self.redirect_chain.iter().filter_map(|hop| host_of(&hop.url)).filter(|host| Some(host) != requested_host.as_ref()).collect::<BTreeSet<_>>().into_iter().collect()
+        } else {
+            Vec::new()
+        };
+
+        let http_requests = self
+            .requests
+            .iter()
+            .map(|request| HTTPRequest {
+                request_id: Uuid::new_v4().to_string(),
+                method: request.method.clone(),
+                url: request.url.clone(),
+                headers: HashMap::new(),
+                body: None,
+                body_truncated: false,
+                response_code: request.status_code.unwrap_or_default(),
+                response_size: request.response_size,
+                timestamp: started_at,
+                process_name: "browser".to_string(),
+                process_id: 0,
+                user_agent: user_agent.to_string(),
+                is_suspicious: suspicious && request.resource_type.as_deref() == Some("document"),
+            })
+            .collect();
+        let dns_queries = hosts
+            .iter()
+            .map(|host| DNSQuery {
+                query_id: Uuid::new_v4().to_string(),
+                domain: host.clone(),
+                query_type: "A".to_string(),
+                response: self
+                    .requests
+                    .iter()
+                    .filter(|request| host_of(&request.url).as_ref() == Some(host))
+                    .filter_map(|request| request.remote_address.clone())
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .collect(),
+                response_code: "NOERROR".to_string(),
+                timestamp: started_at,
+                process_name: "browser".to_string(),
+                process_id: 0,
+                is_suspicious: suspicious_domains.contains(host),
+                threat_category: None,
+            })
+            .collect();
+        let mut protocol_distribution = HashMap::new();
+        for request in &self.requests {
+            let scheme = url::Url::parse(&request.url).map(|url| url.scheme().to_ascii_uppercase()).unwrap_or_default();
+            *protocol_distribution.entry(scheme).or_insert(0) += 1;
+        }
+
+        NetworkAnalysis {
+            network_score: (self.findings.len() as f64 * 2.0).min(10.0),
+            dns_queries,
+            http_requests,
+            protocol_distribution,
+            suspicious_domains,
+            url_detonation: Some(self),
+            ..NetworkAnalysis::default()
+        }
+    }
+
+    /// Requested and final URL, redirect hosts and served payloads
+    pub fn iocs(&self) -> Vec<ExtractedIOC> {
+        let ioc = |ioc_type: &str, value: String, confidence: f64, context: &str| ExtractedIOC {
+            ioc_type: ioc_type.to_string(),
+            value,
+            category: "URL".to_string(),
+            confidence,
+            context: context.to_string(),
+            first_seen: self.started_at,
+            threat_intelligence: None,
+            enrichments: Vec::new(),
+        };
+        let mut iocs = vec![ioc("URL", self.requested_url.clone(), 0.8, "Detonated URL")];
+        if self.final_url != self.requested_url {
+            iocs.push(ioc("URL", self.final_url.clone(), 0.8, "Final URL after redirects"));
+        }
+        let hosts: BTreeSet<String> = std::iter::once(&self.requested_url)
+            .chain(self.redirect_chain.iter().map(|hop| &hop.url))
+            .filter_map(|url| host_of(url))
+            .collect();
+        for host in hosts {
+            let kind = if host.parse::<std::net::IpAddr>().is_ok() { "IP" } else { "Domain" };
+            iocs.push(ioc(kind, host, 0.6, "Host in the redirect chain"));
+        }
+        for download in &self.downloads {
+            iocs.push(ioc("SHA256", download.sha256.clone(), 0.9, &format!("Served by {}", download.url)));
+        }
+        iocs
+    }
+}
+
+// SandboxCore integration
+
+struct PendingDownload {
+    tenant_id: String,
+    parent_sample_id: String,
+    index: usize,
+    filename: String,
+    data: Vec<u8>,
+}
+
+#[derive(Default)]
+pub struct UrlDetonationState {
+    config: RwLock<UrlDetonationConfig>,
+    driver: RwLock<Option<Arc<dyn BrowserDriver>>>,
+    pending: Mutex<Vec<PendingDownload>>,
+}
+
+impl UrlDetonationState {
+    fn config(&self) -> UrlDetonationConfig {
+        self.config.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn driver(&self, config: &UrlDetonationConfig) -> Arc<dyn BrowserDriver> {
+        if let Some(driver) = self.driver.read().unwrap_or_else(|e| e.into_inner()).clone() {
+            return driver;
+        }
+        config.build(Arc::new(SystemCommandRunner))
+    }
+}
+
+/// Only web URLs can be detonated
+pub fn validate_url(url: &str) -> Result<url::Url, String> {
+    let parsed = url::Url::parse(url.trim()).map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(format!("Unsupported URL scheme {}", parsed.scheme()));
+    }
+    if parsed.host_str().is_none_or(str::is_empty) {
+        return Err(format!("URL {} has no host", url));
+    }
+    Ok(parsed)
+}
+
+impl SandboxCore {
+    /// Select the browser driver and limits for URL detonation
+    pub fn configure_url_detonation(&self, config: UrlDetonationConfig) -> Result<(), String> {
+        config.validate()?;
+        *self.url_detonation.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+        *self.url_detonation.driver.write().unwrap_or_else(|e| e.into_inner()) = None;
+        Ok(())
+    }
+
+    pub fn url_detonation_config(&self) -> UrlDetonationConfig {
+        self.url_detonation.config()
+    }
+
+    /// Use a specific browser driver instance, overriding the configured one
+    pub fn set_browser_driver(&self, driver: Arc<dyn BrowserDriver>) {
+        *self.url_detonation.driver.write().unwrap_or_else(|e| e.into_inner()) = Some(driver);
+    }
+
+    /// Queue a URL for detonation and return its sample id
+    pub async fn submit_url(&self, tenant_id: &str, url: &str, priority: AnalysisPriority, tags: Vec<String>, force_reanalyze: bool) -> Result<String, String> {
+        let url = validate_url(url)?.to_string();
+        self.enqueue_sample(tenant_id, url.as_bytes(), url.clone(), priority, tags, force_reanalyze, Some(url.clone()), None)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    /// Load the job's URL in the browser
+    pub(crate) async fn run_url_detonation(&self, url: &str) -> UrlDetonation {
+        let config = self.url_detonation.config();
+        let driver = self.url_detonation.driver(&config);
+        let options = CaptureOptions {
+            user_agent: config.user_agent.clone(),
+            viewport: config.viewport.clone(),
+            timeout: Duration::from_secs(config.timeout_secs),
+        };
+        let started_at = Utc::now();
+        match driver.capture(url, &options).await {
+            Ok(capture) => UrlDetonation::from_capture(url, driver.name(), capture, &config, started_at),
+            Err(e) => UrlDetonation::failed(url, driver.name(), e, started_at),
+        }
+    }
+
+    /// Take served payloads out of a finished URL analysis for `submit_url_downloads`
+    pub(crate) fn stash_url_downloads(&self, job: &AnalysisJob, network: &mut NetworkAnalysis) {
+        let Some(detonation) = network.url_detonation.as_mut() else {
+            return;
+        };
+        let mut pending = self.url_detonation.pending.lock().unwrap_or_else(|e| e.into_inner());
+        for (index, download) in detonation.downloads.iter_mut().enumerate() {
+            if let Some(content) = download.content.take() {
+                pending.push(PendingDownload {
+                    tenant_id: job.tenant_id.clone(),
+                    parent_sample_id: job.sample_id.clone(),
+                    index,
+                    filename: download.filename.clone(),
+                    data: decode_base64(content.as_bytes()),
+                });
+            }
+        }
+    }
+
+    /// Queue stashed payloads as samples and link them from their URL analysis.
+    /// Must run without the analysis queue locked.
+    pub(crate) async fn submit_url_downloads(&self) {
+        let pending = std::mem::take(&mut *self.url_detonation.pending.lock().unwrap_or_else(|e| e.into_inner()));
+        for download in pending {
+            let tags = vec!["url-download".to_string(), format!("parent:{}", download.parent_sample_id)];
+            let submitted = self
+                .submit_sample(&download.tenant_id, &download.data, download.filename, AnalysisPriority::High, tags, false)
+                .await
+                .map_err(|e| e.to_string());
+
+            let mut analyses = self.completed_analyses.write().await;
+            let Some(analysis) = analyses.get_mut(&download.parent_sample_id) else {
+                continue;
+            };
+            if let Some(payload) = analysis
+                .network_analysis
+                .url_detonation
+                .as_mut()
+                .and_then(|detonation| detonation.downloads.get_mut(download.index))
+            {
+                match submitted {
+                    Ok(sample_id) => payload.sample_id = Some(sample_id),
+                    Err(e) => payload.error = Some(e),
+                }
+            }
+            if let Err(e) = self.persist_analysis(&download.parent_sample_id, analysis) {
+                log::warn!("Failed to persist download links of {}: {}", download.parent_sample_id, e.reason);
+            }
+        }
+    }
+}
+
+#[napi]
+impl SandboxCoreNapi {
+    /// Queue a URL for detonation in a headless browser
+    #[napi]
+    pub async fn submit_url(&self, url: String, priority: Option<String>, tags: Option<Vec<String>>, force_reanalyze: Option<bool>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "analysis:submit", &url)?;
+        let tags = tags.unwrap_or_default();
+        let params = serde_json::json!({ "url": url, "priority": priority, "tags": tags, "force_reanalyze": force_reanalyze });
+
+        let result = self.inner.submit_url(&tenant_id, &url, crate::parse_priority(priority.as_deref()), tags, force_reanalyze.unwrap_or(false)).await;
+        let resource = result.as_ref().map_or(url, |sample_id| sample_id.clone());
+        self.audit.record(&actor, "submit_url", &resource, params, result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to submit URL: {}", e)))
+    }
+
+    /// Select the browser driver (simulated or command) and capture limits
+    #[napi]
+    pub fn configure_url_detonation(&self, config_json: String, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize_platform(auth_token, "url_detonation")?;
+        let config: UrlDetonationConfig = serde_json::from_str(&config_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse URL detonation config: {}", e)))?;
+
+        let result = self.inner.configure_url_detonation(config);
+        self.audit.record(&actor, "configure_url_detonation", "url_detonation", serde_json::json!({ "config": config_json }), result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to configure URL detonation: {}", e)))
+    }
+
+    #[napi]
+    pub fn get_url_detonation_config(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.url_detonation_config())
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize URL detonation config: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns one canned capture and records the URL it was asked for
+    struct CannedBrowser {
+        capture: BrowserCapture,
+        requested: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl BrowserDriver for CannedBrowser {
+        fn name(&self) -> &'static str {
+            "canned"
+        }
+
+        async fn capture(&self, url: &str, _options: &CaptureOptions) -> Result<BrowserCapture, String> {
+            self.requested.lock().unwrap().push(url.to_string());
+            Ok(self.capture.clone())
+        }
+    }
+
+    fn phishing_capture() -> BrowserCapture {
+        let now = Utc::now();
+        serde_json::from_value(serde_json::json!({
+            "final_url": "https://login.evil.example/owa/",
+            "redirect_chain": [
+                { "url": "https://track.example/r?id=1", "status_code": 302 },
+                { "url": "https://login.evil.example/owa/", "via": "meta" }
+            ],
+            "title": "Sign in",
+            "dom": "<form><input type=\"password\" name=\"pw\"></form>",
+            "screenshot_png": "iVBORw0KGgo=",
+            "downloads": [{ "url": "https://login.evil.example/invoice.exe", "filename": "invoice.exe", "content": "TVpQAA==" }],
+            "tls": {
+                "subject": "CN=login.evil.example", "issuer": "CN=login.evil.example",
+                "not_before": now - chrono::Duration::days(1), "not_after": now + chrono::Duration::days(1),
+                "subject_alt_names": ["*.other.example"]
+            },
+            "requests": [
+                { "url": "https://login.evil.example/owa/", "status_code": 200, "resource_type": "document", "remote_address": "198.51.100.7" }
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn url_detonation_captures_page_and_queues_downloads() {
+        let core = SandboxCore::new().unwrap();
+        let browser = Arc::new(CannedBrowser { capture: phishing_capture(), requested: Mutex::new(Vec::new()) });
+        core.set_browser_driver(browser.clone());
+        assert!(core.submit_url("acme", "ftp://files.example/x", AnalysisPriority::Normal, vec![], false).await.is_err());
+
+        let sample_id = core.submit_url("acme", "https://track.example/r?id=1", AnalysisPriority::Normal, vec![], false).await.unwrap();
+        core.process_queue().await.unwrap();
+        assert_eq!(*browser.requested.lock().unwrap(), vec!["https://track.example/r?id=1"]);
+
+        let analysis = core.get_analysis("acme", &sample_id).await.unwrap().unwrap();
+        let network = &analysis.network_analysis;
+        let detonation = network.url_detonation.as_ref().unwrap();
+        assert_eq!(detonation.final_url, "https://login.evil.example/owa/");
+        assert_eq!(detonation.redirect_chain.len(), 2);
+        assert_eq!(detonation.screenshot.as_ref().unwrap().size, 8);
+        for finding in ["cross_domain_redirect", "client_side_redirect", "credential_form", "self_signed_certificate", "certificate_name_mismatch", "served_download"] {
+            assert!(detonation.findings.iter().any(|f| f == finding), "missing {} in {:?}", finding, detonation.findings);
+        }
+        assert_eq!(network.suspicious_domains, vec!["login.evil.example"]);
+        assert_eq!(network.dns_queries[0].response, vec!["198.51.100.7"]);
+        assert!(analysis.iocs_extracted.iter().any(|i| i.ioc_type == "URL" && i.value == "https://login.evil.example/owa/"));
+
+        let download = &detonation.downloads[0];
+        assert!(download.content.is_none());
+        let payload_id = download.sample_id.clone().unwrap();
+        assert_eq!(download.sha256, sha256_hex(b"MZP\0"));
+        assert!(core.get_analysis_status("acme", &payload_id).await.unwrap().is_some());
+    }
+
+    #[test]
+    fn limits_and_harness_arguments() {
+        let config = UrlDetonationConfig { max_dom_bytes: 10, max_download_bytes: 2, ..UrlDetonationConfig::default() };
+        let detonation = UrlDetonation::from_capture("https://track.example/r?id=1", "canned", phishing_capture(), &config, Utc::now());
+        assert!(detonation.dom_truncated);
+        assert_eq!(detonation.dom.len(), 10);
+        assert!(detonation.downloads[0].content.is_none());
+        assert!(detonation.downloads[0].error.is_some());
+
+        let missing_url = UrlDetonationConfig {
+            driver: BrowserDriverConfig::Command(BrowserCommandConfig { program: "node".to_string(), args: vec!["harness.js".to_string()] }),
+            ..UrlDetonationConfig::default()
+        };
+        assert!(missing_url.validate().unwrap_err().contains("{url}"));
+    }
+}
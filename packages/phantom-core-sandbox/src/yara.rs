@@ -0,0 +1,1158 @@
+//! YARA Rule Engine
+//!
+//! Pure-Rust compiler and matcher for the commonly used subset of the YARA
+//! language: text strings (`nocase`, `wide`, `ascii`, `fullword`), hex
+//! strings with wildcards, jumps and alternatives, regular expressions, and
+//! conditions over string matches, counts, offsets, `of` quantifiers,
+//! `filesize`, integer reads and references to other rules. Rules are grouped
+//! into namespaces; `global` and `private` rules behave as in YARA. Modules
+//! (`import "pe"`) are not supported and are rejected at compile time.
+
+use crate::static_pipeline::YaraScanner;
+use crate::{SandboxCore, YARAMatch, YARAStringMatch};
+use regex::bytes::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Matches recorded per string before the scanner stops looking
+const MAX_MATCHES_PER_STRING: usize = 1000;
+/// Namespace used when rules are loaded without one
+pub const DEFAULT_NAMESPACE: &str = "default";
+/// Bytes of matched data echoed back in `YARAStringMatch::matched_data`
+const MAX_MATCH_DATA: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YaraCompileError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for YaraCompileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+// Lexer
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    StringId(String),
+    StringCount(String),
+    StringOffset(String),
+    Number(i64),
+    Text(Vec<u8>),
+    Hex(String),
+    Regex(String, String),
+    Punct(&'static str),
+}
+
+struct Lexer<'a> {
+    src: &'a [u8],
+    pos: usize,
+    line: usize,
+}
+
+const PUNCTS: [&str; 18] = ["..", "==", "!=", "<=", ">=", "{", "}", "(", ")", "[", "]", ":", "=", "<", ">", ",", "+", "-"];
+
+impl<'a> Lexer<'a> {
+    fn error(&self, message: impl Into<String>) -> YaraCompileError {
+        YaraCompileError { line: self.line, message: message.into() }
+    }
+
+    fn skip_trivia(&mut self) {
+        while self.pos < self.src.len() {
+            match self.src[self.pos] {
+                b'\n' => {
+                    self.line += 1;
+                    self.pos += 1;
+                }
+                c if c.is_ascii_whitespace() => self.pos += 1,
+                b'/' if self.src.get(self.pos + 1) == Some(&b'/') => {
+                    while self.pos < self.src.len() && self.src[self.pos] != b'\n' {
+                        self.pos += 1;
+                    }
+                }
+                b'/' if self.src.get(self.pos + 1) == Some(&b'*') => {
+                    self.pos += 2;
+                    while self.pos < self.src.len() && !self.src[self.pos..].starts_with(b"*/") {
+                        if self.src[self.pos] == b'\n' {
+                            self.line += 1;
+                        }
+                        self.pos += 1;
+                    }
+                    self.pos = (self.pos + 2).min(self.src.len());
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn ident(&mut self) -> String {
+        let start = self.pos;
+        while self.pos < self.src.len() && (self.src[self.pos].is_ascii_alphanumeric() || self.src[self.pos] == b'_' || self.src[self.pos] == b'*') {
+            self.pos += 1;
+        }
+        String::from_utf8_lossy(&self.src[start..self.pos]).into_owned()
+    }
+
+    fn number(&mut self) -> Result<i64, YaraCompileError> {
+        let start = self.pos;
+        let value = if self.src[self.pos..].starts_with(b"0x") {
+            self.pos += 2;
+            while self.pos < self.src.len() && self.src[self.pos].is_ascii_hexdigit() {
+                self.pos += 1;
+            }
+            i64::from_str_radix(std::str::from_utf8(&self.src[start + 2..self.pos]).unwrap_or(""), 16)
+        } else {
+            while self.pos < self.src.len() && self.src[self.pos].is_ascii_digit() {
+                self.pos += 1;
+            }
+            std::str::from_utf8(&self.src[start..self.pos]).unwrap_or("").parse()
+        }
+        .map_err(|_| self.error("Invalid number"))?;
+
+        let multiplier = if self.src[self.pos..].starts_with(b"KB") {
+            1024
+        } else if self.src[self.pos..].starts_with(b"MB") {
+            1024 * 1024
+        } else {
+            1
+        };
+        if multiplier > 1 {
+            self.pos += 2;
+        }
+        Ok(value * multiplier)
+    }
+
+    fn text(&mut self) -> Result<Vec<u8>, YaraCompileError> {
+        self.pos += 1;
+        let mut out = Vec::new();
+        loop {
+            let c = *self.src.get(self.pos).ok_or_else(|| self.error("Unterminated string"))?;
+            self.pos += 1;
+            match c {
+                b'"' => return Ok(out),
+                b'\n' => return Err(self.error("Unterminated string")),
+                b'\\' => {
+                    let escaped = *self.src.get(self.pos).ok_or_else(|| self.error("Unterminated string"))?;
+                    self.pos += 1;
+                    match escaped {
+                        b'n' => out.push(b'\n'),
+                        b't' => out.push(b'\t'),
+                        b'r' => out.push(b'\r'),
+                        b'"' | b'\\' => out.push(escaped),
+                        b'x' => {
+                            let hex = self.src.get(self.pos..self.pos + 2).ok_or_else(|| self.error("Invalid \\x escape"))?;
+                            let value = u8::from_str_radix(std::str::from_utf8(hex).unwrap_or(""), 16)
+                                .map_err(|_| self.error("Invalid \\x escape"))?;
+                            out.push(value);
+                            self.pos += 2;
+                        }
+                        other => return Err(self.error(format!("Unknown escape \\{}", other as char))),
+                    }
+                }
+                other => out.push(other),
+            }
+        }
+    }
+
+    fn delimited(&mut self, close: u8, what: &str) -> Result<String, YaraCompileError> {
+        self.pos += 1;
+        let start = self.pos;
+        while self.pos < self.src.len() && self.src[self.pos] != close {
+            if self.src[self.pos] == b'\\' && close == b'/' {
+                self.pos += 1;
+            } else if self.src[self.pos] == b'\n' {
+                self.line += 1;
+            }
+            self.pos += 1;
+        }
+        if self.pos >= self.src.len() {
+            return Err(self.error(format!("Unterminated {}", what)));
+        }
+        let body = String::from_utf8_lossy(&self.src[start..self.pos]).into_owned();
+        self.pos += 1;
+        Ok(body)
+    }
+
+    fn tokenize(mut self) -> Result<Vec<(Token, usize)>, YaraCompileError> {
+        let mut tokens: Vec<(Token, usize)> = Vec::new();
+        loop {
+            self.skip_trivia();
+            let Some(&c) = self.src.get(self.pos) else {
+                return Ok(tokens);
+            };
+            let line = self.line;
+            let after_assign = matches!(tokens.last(), Some((Token::Punct("="), _)));
+            let token = match c {
+                b'{' if after_assign => Token::Hex(self.delimited(b'}', "hex string")?),
+                b'/' if after_assign => {
+                    let pattern = self.delimited(b'/', "regular expression")?;
+                    let start = self.pos;
+                    while self.pos < self.src.len() && matches!(self.src[self.pos], b'i' | b's') {
+                        self.pos += 1;
+                    }
+                    Token::Regex(pattern, String::from_utf8_lossy(&self.src[start..self.pos]).into_owned())
+                }
+                b'"' => Token::Text(self.text()?),
+                b'$' | b'#' | b'@' => {
+                    self.pos += 1;
+                    let name = self.ident();
+                    match c {
+                        b'$' => Token::StringId(name),
+                        b'#' => Token::StringCount(name),
+                        _ => Token::StringOffset(name),
+                    }
+                }
+                c if c.is_ascii_digit() => Token::Number(self.number()?),
+                c if c.is_ascii_alphabetic() || c == b'_' => Token::Ident(self.ident()),
+                _ => {
+                    let punct = PUNCTS
+                        .iter()
+                        .find(|p| self.src[self.pos..].starts_with(p.as_bytes()))
+                        .ok_or_else(|| self.error(format!("Unexpected character '{}'", c as char)))?;
+                    self.pos += punct.len();
+                    Token::Punct(punct)
+                }
+            };
+            tokens.push((token, line));
+        }
+    }
+}
+
+// Rule Model
+
+#[derive(Debug, Clone)]
+struct CompiledString {
+    identifier: String,
+    regex: Regex,
+    fullword: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IntRead {
+    U8,
+    U16,
+    U32,
+    I8,
+    I16,
+    I32,
+    U16Be,
+    U32Be,
+}
+
+impl IntRead {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "uint8" => IntRead::U8,
+            "uint16" => IntRead::U16,
+            "uint32" => IntRead::U32,
+            "int8" => IntRead::I8,
+            "int16" => IntRead::I16,
+            "int32" => IntRead::I32,
+            "uint16be" => IntRead::U16Be,
+            "uint32be" => IntRead::U32Be,
+            _ => return None,
+        })
+    }
+
+    fn read(&self, data: &[u8], offset: i64) -> Option<i64> {
+        let offset = usize::try_from(offset).ok()?;
+        let bytes = |n: usize| data.get(offset..offset.checked_add(n)?);
+        Some(match self {
+            IntRead::U8 => bytes(1)?[0] as i64,
+            IntRead::I8 => bytes(1)?[0] as i8 as i64,
+            IntRead::U16 => u16::from_le_bytes(bytes(2)?.try_into().ok()?) as i64,
+            IntRead::I16 => i16::from_le_bytes(bytes(2)?.try_into().ok()?) as i64,
+            IntRead::U32 => u32::from_le_bytes(bytes(4)?.try_into().ok()?) as i64,
+            IntRead::I32 => i32::from_le_bytes(bytes(4)?.try_into().ok()?) as i64,
+            IntRead::U16Be => u16::from_be_bytes(bytes(2)?.try_into().ok()?) as i64,
+            IntRead::U32Be => u32::from_be_bytes(bytes(4)?.try_into().ok()?) as i64,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+enum NumExpr {
+    Literal(i64),
+    Filesize,
+    Count(String),
+    Offset(String, Box<NumExpr>),
+    Read(IntRead, Box<NumExpr>),
+    Add(Box<NumExpr>, Box<NumExpr>),
+    Sub(Box<NumExpr>, Box<NumExpr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Quantifier {
+    Any,
+    All,
+    None,
+    AtLeast(usize),
+}
+
+#[derive(Debug, Clone)]
+enum Condition {
+    Bool(bool),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+    Matched(String),
+    MatchedAt(String, NumExpr),
+    MatchedIn(String, NumExpr, NumExpr),
+    Of(Quantifier, Vec<String>),
+    Compare(NumExpr, CompareOp, NumExpr),
+    RuleRef(String),
+}
+
+/// A compiled rule
+#[derive(Debug, Clone)]
+pub struct YaraRule {
+    pub name: String,
+    pub namespace: String,
+    pub tags: Vec<String>,
+    pub meta: HashMap<String, String>,
+    pub is_private: bool,
+    pub is_global: bool,
+    strings: Vec<CompiledString>,
+    condition: Condition,
+}
+
+/// Public description of a loaded rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YaraRuleInfo {
+    pub namespace: String,
+    pub name: String,
+    pub tags: Vec<String>,
+    pub meta: HashMap<String, String>,
+    pub string_count: usize,
+    pub is_private: bool,
+    pub is_global: bool,
+}
+
+// Parser
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    namespace: String,
+}
+
+impl Parser {
+    fn line(&self) -> usize {
+        self.tokens.get(self.pos).or(self.tokens.last()).map(|t| t.1).unwrap_or(1)
+    }
+
+    fn error(&self, message: impl Into<String>) -> YaraCompileError {
+        YaraCompileError { line: self.line(), message: message.into() }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|t| &t.0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset).map(|t| &t.0)
+    }
+
+    fn next(&mut self) -> Result<Token, YaraCompileError> {
+        let token = self.tokens.get(self.pos).map(|t| t.0.clone()).ok_or_else(|| self.error("Unexpected end of rules"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn is_ident(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(w)) if w == word)
+    }
+
+    fn is_punct(&self, punct: &str) -> bool {
+        matches!(self.peek(), Some(Token::Punct(p)) if *p == punct)
+    }
+
+    fn expect_punct(&mut self, punct: &str) -> Result<(), YaraCompileError> {
+        match self.next()? {
+            Token::Punct(p) if p == punct => Ok(()),
+            other => Err(self.error(format!("Expected '{}', found {:?}", punct, other))),
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<String, YaraCompileError> {
+        match self.next()? {
+            Token::Ident(name) => Ok(name),
+            other => Err(self.error(format!("Expected identifier, found {:?}", other))),
+        }
+    }
+
+    fn parse_rule(&mut self, earlier: &[YaraRule]) -> Result<YaraRule, YaraCompileError> {
+        let (mut is_private, mut is_global) = (false, false);
+        loop {
+            match self.expect_ident()?.as_str() {
+                "private" => is_private = true,
+                "global" => is_global = true,
+                "rule" => break,
+                other => return Err(self.error(format!("Expected 'rule', found '{}'", other))),
+            }
+        }
+        let name = self.expect_ident()?;
+
+        let mut tags = Vec::new();
+        if self.is_punct(":") {
+            self.pos += 1;
+            while let Some(Token::Ident(tag)) = self.peek() {
+                tags.push(tag.clone());
+                self.pos += 1;
+            }
+        }
+        self.expect_punct("{")?;
+
+        let mut meta = HashMap::new();
+        let mut strings = Vec::new();
+        let mut condition = None;
+        while !self.is_punct("}") {
+            let section = self.expect_ident()?;
+            self.expect_punct(":")?;
+            match section.as_str() {
+                "meta" => self.parse_meta(&mut meta)?,
+                "strings" => strings = self.parse_strings()?,
+                "condition" => condition = Some(self.parse_or(&strings, earlier)?),
+                other => return Err(self.error(format!("Unknown section '{}'", other))),
+            }
+        }
+        self.expect_punct("}")?;
+
+        let condition = condition.ok_or_else(|| self.error(format!("Rule {} has no condition", name)))?;
+        Ok(YaraRule { name, namespace: self.namespace.clone(), tags, meta, is_private, is_global, strings, condition })
+    }
+
+    fn parse_meta(&mut self, meta: &mut HashMap<String, String>) -> Result<(), YaraCompileError> {
+        while matches!(self.peek(), Some(Token::Ident(w)) if !matches!(w.as_str(), "strings" | "condition"))
+            && matches!(self.peek_at(1), Some(Token::Punct("=")))
+        {
+            let key = self.expect_ident()?;
+            self.expect_punct("=")?;
+            let value = match self.next()? {
+                Token::Text(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+                Token::Number(n) => n.to_string(),
+                Token::Punct("-") => match self.next()? {
+                    Token::Number(n) => (-n).to_string(),
+                    other => return Err(self.error(format!("Invalid meta value {:?}", other))),
+                },
+                Token::Ident(word) if word == "true" || word == "false" => word,
+                other => return Err(self.error(format!("Invalid meta value {:?}", other))),
+            };
+            meta.insert(key, value);
+        }
+        Ok(())
+    }
+
+    fn parse_strings(&mut self) -> Result<Vec<CompiledString>, YaraCompileError> {
+        let mut strings: Vec<CompiledString> = Vec::new();
+        while let Some(Token::StringId(name)) = self.peek().cloned() {
+            self.pos += 1;
+            self.expect_punct("=")?;
+            let identifier = if name.is_empty() { format!("$_anon{}", strings.len()) } else { format!("${}", name) };
+            if strings.iter().any(|s| s.identifier == identifier) {
+                return Err(self.error(format!("Duplicate string {}", identifier)));
+            }
+
+            let value = self.next()?;
+            let mut modifiers = Vec::new();
+            while let Some(Token::Ident(word)) = self.peek() {
+                if !matches!(word.as_str(), "nocase" | "wide" | "ascii" | "fullword" | "private") {
+                    break;
+                }
+                modifiers.push(word.clone());
+                self.pos += 1;
+            }
+            let has = |m: &str| modifiers.iter().any(|x| x == m);
+
+            let pattern = match value {
+                Token::Text(bytes) => {
+                    let escaped = |bytes: &[u8]| bytes.iter().map(|b| format!("\\x{:02x}", b)).collect::<String>();
+                    let wide: Vec<u8> = bytes.iter().flat_map(|&b| [b, 0]).collect();
+                    let body = match (has("wide"), has("ascii")) {
+                        (true, true) => format!("(?:{}|{})", escaped(&bytes), escaped(&wide)),
+                        (true, false) => escaped(&wide),
+                        _ => escaped(&bytes),
+                    };
+                    format!("(?s{}-u){}", if has("nocase") { "i" } else { "" }, body)
+                }
+                Token::Hex(body) => format!("(?s-u){}", hex_to_regex(&body).map_err(|e| self.error(e))?),
+                Token::Regex(body, flags) => {
+                    let mut enabled = String::new();
+                    if flags.contains('i') || has("nocase") {
+                        enabled.push('i');
+                    }
+                    if flags.contains('s') {
+                        enabled.push('s');
+                    }
+                    format!("(?{}-u){}", enabled, body.replace("\\/", "/"))
+                }
+                other => return Err(self.error(format!("Invalid string value {:?}", other))),
+            };
+            let regex = Regex::new(&pattern).map_err(|e| self.error(format!("Invalid pattern for {}: {}", identifier, e)))?;
+            strings.push(CompiledString { identifier, regex, fullword: has("fullword") });
+        }
+        Ok(strings)
+    }
+
+    fn string_ref(&self, name: &str, strings: &[CompiledString]) -> Result<String, YaraCompileError> {
+        let identifier = format!("${}", name);
+        if strings.iter().any(|s| s.identifier == identifier) {
+            Ok(identifier)
+        } else {
+            Err(self.error(format!("Undefined string {}", identifier)))
+        }
+    }
+
+    fn parse_or(&mut self, strings: &[CompiledString], rules: &[YaraRule]) -> Result<Condition, YaraCompileError> {
+        let mut left = self.parse_and(strings, rules)?;
+        while self.is_ident("or") {
+            self.pos += 1;
+            left = Condition::Or(Box::new(left), Box::new(self.parse_and(strings, rules)?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self, strings: &[CompiledString], rules: &[YaraRule]) -> Result<Condition, YaraCompileError> {
+        let mut left = self.parse_not(strings, rules)?;
+        while self.is_ident("and") {
+            self.pos += 1;
+            left = Condition::And(Box::new(left), Box::new(self.parse_not(strings, rules)?));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self, strings: &[CompiledString], rules: &[YaraRule]) -> Result<Condition, YaraCompileError> {
+        if self.is_ident("not") {
+            self.pos += 1;
+            return Ok(Condition::Not(Box::new(self.parse_not(strings, rules)?)));
+        }
+        self.parse_primary(strings, rules)
+    }
+
+    fn parse_primary(&mut self, strings: &[CompiledString], rules: &[YaraRule]) -> Result<Condition, YaraCompileError> {
+        match self.peek().cloned() {
+            Some(Token::Punct("(")) => {
+                self.pos += 1;
+                let inner = self.parse_or(strings, rules)?;
+                self.expect_punct(")")?;
+                Ok(inner)
+            }
+            Some(Token::Ident(word)) if word == "true" || word == "false" => {
+                self.pos += 1;
+                Ok(Condition::Bool(word == "true"))
+            }
+            Some(Token::Ident(word)) if matches!(word.as_str(), "any" | "all" | "none") => {
+                self.pos += 1;
+                let quantifier = match word.as_str() {
+                    "any" => Quantifier::Any,
+                    "all" => Quantifier::All,
+                    _ => Quantifier::None,
+                };
+                self.parse_of(quantifier, strings)
+            }
+            Some(Token::Number(n)) if matches!(self.peek_at(1), Some(Token::Ident(w)) if w == "of") => {
+                self.pos += 1;
+                self.parse_of(Quantifier::AtLeast(n.max(0) as usize), strings)
+            }
+            Some(Token::StringId(name)) => {
+                self.pos += 1;
+                let identifier = self.string_ref(&name, strings)?;
+                if self.is_ident("at") {
+                    self.pos += 1;
+                    return Ok(Condition::MatchedAt(identifier, self.parse_num(strings)?));
+                }
+                if self.is_ident("in") {
+                    self.pos += 1;
+                    self.expect_punct("(")?;
+                    let from = self.parse_num(strings)?;
+                    self.expect_punct("..")?;
+                    let to = self.parse_num(strings)?;
+                    self.expect_punct(")")?;
+                    return Ok(Condition::MatchedIn(identifier, from, to));
+                }
+                Ok(Condition::Matched(identifier))
+            }
+            Some(Token::Ident(word))
+                if word != "filesize" && IntRead::parse(&word).is_none() =>
+            {
+                self.pos += 1;
+                if rules.iter().any(|r| r.name == word) {
+                    Ok(Condition::RuleRef(word))
+                } else {
+                    Err(self.error(format!("Undefined identifier '{}'", word)))
+                }
+            }
+            _ => {
+                let left = self.parse_num(strings)?;
+                let op = match self.next()? {
+                    Token::Punct("==") => CompareOp::Eq,
+                    Token::Punct("!=") => CompareOp::Ne,
+                    Token::Punct("<") => CompareOp::Lt,
+                    Token::Punct("<=") => CompareOp::Le,
+                    Token::Punct(">") => CompareOp::Gt,
+                    Token::Punct(">=") => CompareOp::Ge,
+                    other => return Err(self.error(format!("Expected comparison, found {:?}", other))),
+                };
+                Ok(Condition::Compare(left, op, self.parse_num(strings)?))
+            }
+        }
+    }
+
+    fn parse_of(&mut self, quantifier: Quantifier, strings: &[CompiledString]) -> Result<Condition, YaraCompileError> {
+        if self.expect_ident()? != "of" {
+            return Err(self.error("Expected 'of'"));
+        }
+        let selected: Vec<String> = if self.is_ident("them") {
+            self.pos += 1;
+            strings.iter().map(|s| s.identifier.clone()).collect()
+        } else {
+            self.expect_punct("(")?;
+            let mut selected = Vec::new();
+            loop {
+                let Token::StringId(name) = self.next()? else {
+                    return Err(self.error("Expected string identifier in set"));
+                };
+                match name.strip_suffix('*') {
+                    Some(prefix) => {
+                        let prefix = format!("${}", prefix);
+                        let before = selected.len();
+                        selected.extend(strings.iter().filter(|s| s.identifier.starts_with(&prefix)).map(|s| s.identifier.clone()));
+                        if selected.len() == before {
+                            return Err(self.error(format!("No strings match {}*", prefix)));
+                        }
+                    }
+                    None => selected.push(self.string_ref(&name, strings)?),
+                }
+                if self.is_punct(",") {
+                    self.pos += 1;
+                    continue;
+                }
+                self.expect_punct(")")?;
+                break;
+            }
+            selected
+        };
+        Ok(Condition::Of(quantifier, selected))
+    }
+
+    fn parse_num(&mut self, strings: &[CompiledString]) -> Result<NumExpr, YaraCompileError> {
+        let mut left = self.parse_num_atom(strings)?;
+        loop {
+            if self.is_punct("+") {
+                self.pos += 1;
+                left = NumExpr::Add(Box::new(left), Box::new(self.parse_num_atom(strings)?));
+            } else if self.is_punct("-") {
+                self.pos += 1;
+                left = NumExpr::Sub(Box::new(left), Box::new(self.parse_num_atom(strings)?));
+            } else {
+                return Ok(left);
+            }
+        }
+    }
+
+    fn parse_num_atom(&mut self, strings: &[CompiledString]) -> Result<NumExpr, YaraCompileError> {
+        match self.next()? {
+            Token::Number(n) => Ok(NumExpr::Literal(n)),
+            Token::Punct("-") => match self.next()? {
+                Token::Number(n) => Ok(NumExpr::Literal(-n)),
+                other => Err(self.error(format!("Expected number, found {:?}", other))),
+            },
+            Token::Punct("(") => {
+                let inner = self.parse_num(strings)?;
+                self.expect_punct(")")?;
+                Ok(inner)
+            }
+            Token::Ident(word) if word == "filesize" => Ok(NumExpr::Filesize),
+            Token::Ident(word) => {
+                let read = IntRead::parse(&word).ok_or_else(|| self.error(format!("Unknown function '{}'", word)))?;
+                self.expect_punct("(")?;
+                let offset = self.parse_num(strings)?;
+                self.expect_punct(")")?;
+                Ok(NumExpr::Read(read, Box::new(offset)))
+            }
+            Token::StringCount(name) => Ok(NumExpr::Count(self.string_ref(&name, strings)?)),
+            Token::StringOffset(name) => {
+                let identifier = self.string_ref(&name, strings)?;
+                let index = if self.is_punct("[") {
+                    self.pos += 1;
+                    let index = self.parse_num(strings)?;
+                    self.expect_punct("]")?;
+                    index
+                } else {
+                    NumExpr::Literal(1)
+                };
+                Ok(NumExpr::Offset(identifier, Box::new(index)))
+            }
+            other => Err(self.error(format!("Expected numeric expression, found {:?}", other))),
+        }
+    }
+}
+
+/// Translate a YARA hex string body into a byte regex
+fn hex_to_regex(body: &str) -> Result<String, String> {
+    let chars: Vec<char> = body.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut out = String::new();
+    let mut i = 0;
+    let mut bytes = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '(' => {
+                out.push_str("(?:");
+                i += 1;
+            }
+            ')' => {
+                out.push(')');
+                i += 1;
+            }
+            '|' => {
+                out.push('|');
+                i += 1;
+            }
+            '[' => {
+                let end = chars[i..].iter().position(|&c| c == ']').ok_or("Unterminated jump")? + i;
+                let range: String = chars[i + 1..end].iter().collect();
+                let (low, high) = match range.split_once('-') {
+                    Some((low, high)) => (low.to_string(), high.to_string()),
+                    None => (range.clone(), range.clone()),
+                };
+                let low: usize = if low.is_empty() { 0 } else { low.parse().map_err(|_| format!("Invalid jump [{}]", range))? };
+                if high.is_empty() {
+                    out.push_str(&format!(".{{{},}}", low));
+                } else {
+                    let high: usize = high.parse().map_err(|_| format!("Invalid jump [{}]", range))?;
+                    if high < low {
+                        return Err(format!("Invalid jump [{}]", range));
+                    }
+                    out.push_str(&format!(".{{{},{}}}", low, high));
+                }
+                i = end + 1;
+            }
+            _ => {
+                let (hi, lo) = (chars[i], *chars.get(i + 1).ok_or("Incomplete hex byte")?);
+                out.push_str(&match (hi, lo) {
+                    ('?', '?') => ".".to_string(),
+                    ('?', lo) => {
+                        let lo = lo.to_digit(16).ok_or_else(|| format!("Invalid hex digit '{}'", lo))?;
+                        format!("[{}]", (0..16).map(|h| format!("\\x{:02x}", h * 16 + lo)).collect::<String>())
+                    }
+                    (hi, '?') => {
+                        let hi = hi.to_digit(16).ok_or_else(|| format!("Invalid hex digit '{}'", hi))?;
+                        format!("[\\x{:02x}-\\x{:02x}]", hi * 16, hi * 16 + 15)
+                    }
+                    (hi, lo) => {
+                        let value = u8::from_str_radix(&format!("{}{}", hi, lo), 16)
+                            .map_err(|_| format!("Invalid hex byte '{}{}'", hi, lo))?;
+                        format!("\\x{:02x}", value)
+                    }
+                });
+                bytes += 1;
+                i += 2;
+            }
+        }
+    }
+    if bytes == 0 {
+        return Err("Empty hex string".to_string());
+    }
+    Ok(out)
+}
+
+/// Compile YARA source into rules belonging to `namespace`
+pub fn compile_rules(source: &str, namespace: &str) -> Result<Vec<YaraRule>, YaraCompileError> {
+    compile_with_context(source, namespace, &[])
+}
+
+// Scanning
+
+struct ScanContext<'a> {
+    data: &'a [u8],
+    matches: HashMap<&'a str, Vec<(usize, usize)>>,
+}
+
+impl<'a> ScanContext<'a> {
+    fn new(data: &'a [u8], rule: &'a YaraRule) -> Self {
+        let matches = rule
+            .strings
+            .iter()
+            .map(|s| (s.identifier.as_str(), find_matches(s, data)))
+            .collect();
+        Self { data, matches }
+    }
+
+    fn hits(&self, identifier: &str) -> &[(usize, usize)] {
+        self.matches.get(identifier).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    fn num(&self, expr: &NumExpr) -> Option<i64> {
+        Some(match expr {
+            NumExpr::Literal(n) => *n,
+            NumExpr::Filesize => self.data.len() as i64,
+            NumExpr::Count(id) => self.hits(id).len() as i64,
+            NumExpr::Offset(id, index) => {
+                let index = usize::try_from(self.num(index)?).ok()?.checked_sub(1)?;
+                self.hits(id).get(index)?.0 as i64
+            }
+            NumExpr::Read(read, offset) => read.read(self.data, self.num(offset)?)?,
+            NumExpr::Add(a, b) => self.num(a)?.checked_add(self.num(b)?)?,
+            NumExpr::Sub(a, b) => self.num(a)?.checked_sub(self.num(b)?)?,
+        })
+    }
+
+    fn eval(&self, condition: &Condition, rule_results: &HashMap<&str, bool>) -> bool {
+        match condition {
+            Condition::Bool(b) => *b,
+            Condition::And(a, b) => self.eval(a, rule_results) && self.eval(b, rule_results),
+            Condition::Or(a, b) => self.eval(a, rule_results) || self.eval(b, rule_results),
+            Condition::Not(inner) => !self.eval(inner, rule_results),
+            Condition::Matched(id) => !self.hits(id).is_empty(),
+            Condition::MatchedAt(id, at) => self
+                .num(at)
+                .is_some_and(|at| self.hits(id).iter().any(|(offset, _)| *offset as i64 == at)),
+            Condition::MatchedIn(id, from, to) => match (self.num(from), self.num(to)) {
+                (Some(from), Some(to)) => self.hits(id).iter().any(|(offset, _)| (from..=to).contains(&(*offset as i64))),
+                _ => false,
+            },
+            Condition::Of(quantifier, ids) => {
+                let matched = ids.iter().filter(|id| !self.hits(id).is_empty()).count();
+                match quantifier {
+                    Quantifier::Any => matched >= 1,
+                    Quantifier::All => matched == ids.len(),
+                    Quantifier::None => matched == 0,
+                    Quantifier::AtLeast(n) => matched >= *n,
+                }
+            }
+            Condition::Compare(a, op, b) => match (self.num(a), self.num(b)) {
+                (Some(a), Some(b)) => match op {
+                    CompareOp::Eq => a == b,
+                    CompareOp::Ne => a != b,
+                    CompareOp::Lt => a < b,
+                    CompareOp::Le => a <= b,
+                    CompareOp::Gt => a > b,
+                    CompareOp::Ge => a >= b,
+                },
+                _ => false,
+            },
+            Condition::RuleRef(name) => rule_results.get(name.as_str()).copied().unwrap_or(false),
+        }
+    }
+}
+
+fn is_word_byte(byte: Option<&u8>) -> bool {
+    byte.is_some_and(|b| b.is_ascii_alphanumeric())
+}
+
+fn find_matches(string: &CompiledString, data: &[u8]) -> Vec<(usize, usize)> {
+    string
+        .regex
+        .find_iter(data)
+        .filter(|m| {
+            !string.fullword
+                || (!is_word_byte(m.start().checked_sub(1).and_then(|i| data.get(i))) && !is_word_byte(data.get(m.end())))
+        })
+        .take(MAX_MATCHES_PER_STRING)
+        .map(|m| (m.start(), m.end() - m.start()))
+        .collect()
+}
+
+fn render_match_data(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .take(MAX_MATCH_DATA)
+        .map(|&b| if b.is_ascii_graphic() || b == b' ' { (b as char).to_string() } else { format!("\\x{:02x}", b) })
+        .collect()
+}
+
+fn rule_confidence(meta: &HashMap<String, String>) -> f64 {
+    match meta.get("confidence").and_then(|c| c.parse::<f64>().ok()) {
+        Some(c) if c > 1.0 => (c / 100.0).min(1.0),
+        Some(c) => c.max(0.0),
+        None => 0.75,
+    }
+}
+
+/// Loaded rules, grouped by namespace in load order
+#[derive(Debug, Clone, Default)]
+pub struct YaraRuleSet {
+    namespaces: Vec<(String, Vec<YaraRule>)>,
+}
+
+impl YaraRuleSet {
+    /// Compile `source` and add it to `namespace`, returning the new rule names
+    pub fn add_rules(&mut self, namespace: &str, source: &str) -> Result<Vec<String>, YaraCompileError> {
+        let compiled = compile_with_context(source, namespace, self.rules_in(namespace))?;
+        let names = compiled.iter().map(|r| r.name.clone()).collect();
+        match self.namespaces.iter_mut().find(|(ns, _)| ns == namespace) {
+            Some((_, rules)) => rules.extend(compiled),
+            None => self.namespaces.push((namespace.to_string(), compiled)),
+        }
+        Ok(names)
+    }
+
+    pub fn remove_namespace(&mut self, namespace: &str) -> bool {
+        let before = self.namespaces.len();
+        self.namespaces.retain(|(ns, _)| ns != namespace);
+        self.namespaces.len() != before
+    }
+
+    pub fn rule_count(&self) -> usize {
+        self.namespaces.iter().map(|(_, rules)| rules.len()).sum()
+    }
+
+    pub fn list(&self) -> Vec<YaraRuleInfo> {
+        self.namespaces
+            .iter()
+            .flat_map(|(_, rules)| rules.iter())
+            .map(|rule| YaraRuleInfo {
+                namespace: rule.namespace.clone(),
+                name: rule.name.clone(),
+                tags: rule.tags.clone(),
+                meta: rule.meta.clone(),
+                string_count: rule.strings.len(),
+                is_private: rule.is_private,
+                is_global: rule.is_global,
+            })
+            .collect()
+    }
+
+    fn rules_in(&self, namespace: &str) -> &[YaraRule] {
+        self.namespaces
+            .iter()
+            .find(|(ns, _)| ns == namespace)
+            .map(|(_, rules)| rules.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Scan a buffer against every namespace and return the matching public rules
+    pub fn scan(&self, data: &[u8]) -> Vec<YARAMatch> {
+        let mut results = Vec::new();
+        for (_, rules) in &self.namespaces {
+            let mut rule_results: HashMap<&str, bool> = HashMap::new();
+            let mut matched = Vec::new();
+            let mut globals_hold = true;
+
+            for rule in rules {
+                let context = ScanContext::new(data, rule);
+                let hit = context.eval(&rule.condition, &rule_results);
+                rule_results.insert(rule.name.as_str(), hit);
+                if rule.is_global && !hit {
+                    globals_hold = false;
+                }
+                if hit && !rule.is_private {
+                    matched.push((rule, context));
+                }
+            }
+            if !globals_hold {
+                continue;
+            }
+
+            for (rule, context) in matched {
+                let mut string_matches = Vec::new();
+                for string in &rule.strings {
+                    for &(offset, length) in context.hits(&string.identifier) {
+                        string_matches.push(YARAStringMatch {
+                            string_identifier: string.identifier.clone(),
+                            matched_data: render_match_data(&data[offset..offset + length]),
+                            offset: offset as u64,
+                            length: length as u32,
+                        });
+                    }
+                }
+                results.push(YARAMatch {
+                    rule_name: format!("{}:{}", rule.namespace, rule.name),
+                    rule_author: rule.meta.get("author").cloned().unwrap_or_default(),
+                    rule_description: rule.meta.get("description").cloned().unwrap_or_default(),
+                    tags: rule.tags.clone(),
+                    matches: string_matches,
+                    severity: rule.meta.get("severity").cloned().unwrap_or_else(|| "medium".to_string()),
+                    confidence: rule_confidence(&rule.meta),
+                });
+            }
+        }
+        results
+    }
+}
+
+/// Compile with earlier rules of the namespace visible to rule references
+fn compile_with_context(source: &str, namespace: &str, existing: &[YaraRule]) -> Result<Vec<YaraRule>, YaraCompileError> {
+    let tokens = Lexer { src: source.as_bytes(), pos: 0, line: 1 }.tokenize()?;
+    let mut parser = Parser { tokens, pos: 0, namespace: namespace.to_string() };
+    let mut all: Vec<YaraRule> = existing.to_vec();
+    let start = all.len();
+    while parser.peek().is_some() {
+        if parser.is_ident("import") || parser.is_ident("include") {
+            return Err(parser.error("Modules and includes are not supported"));
+        }
+        let rule = parser.parse_rule(&all)?;
+        if all.iter().any(|r| r.name == rule.name) {
+            return Err(parser.error(format!("Duplicate rule {}", rule.name)));
+        }
+        all.push(rule);
+    }
+    Ok(all.split_off(start))
+}
+
+/// Result of loading rules into a namespace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YaraRulesLoaded {
+    pub namespace: String,
+    pub rules_added: Vec<String>,
+    pub total_rules: usize,
+}
+
+impl SandboxCore {
+    /// Compile YARA source into `namespace` (default "default")
+    pub fn add_yara_rules(&self, namespace: Option<&str>, source: &str) -> Result<YaraRulesLoaded, String> {
+        let namespace = namespace.unwrap_or(DEFAULT_NAMESPACE);
+        let mut rules = self.yara_rules.write().map_err(|e| e.to_string())?;
+        let rules_added = rules.add_rules(namespace, source).map_err(|e| e.to_string())?;
+        Ok(YaraRulesLoaded { namespace: namespace.to_string(), rules_added, total_rules: rules.rule_count() })
+    }
+
+    /// Load a rule file; the namespace defaults to the file stem
+    pub fn load_yara_rule_file(&self, path: &str, namespace: Option<&str>) -> Result<YaraRulesLoaded, String> {
+        let source = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+        let stem = std::path::Path::new(path).file_stem().and_then(|s| s.to_str()).unwrap_or(DEFAULT_NAMESPACE);
+        self.add_yara_rules(Some(namespace.unwrap_or(stem)), &source)
+            .map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn remove_yara_namespace(&self, namespace: &str) -> Result<bool, String> {
+        Ok(self.yara_rules.write().map_err(|e| e.to_string())?.remove_namespace(namespace))
+    }
+
+    pub fn list_yara_rules(&self) -> Result<Vec<YaraRuleInfo>, String> {
+        Ok(self.yara_rules.read().map_err(|e| e.to_string())?.list())
+    }
+
+    pub fn scan_with_yara(&self, data: &[u8]) -> Result<Vec<YARAMatch>, String> {
+        Ok(self.yara_rules.read().map_err(|e| e.to_string())?.scan(data))
+    }
+}
+
+/// Scanner for the static pipeline's YARA stage backed by a shared rule set
+pub fn pipeline_scanner(rules: Arc<RwLock<YaraRuleSet>>) -> YaraScanner {
+    Arc::new(move |data: &[u8]| match rules.read() {
+        Ok(rules) => rules.scan(data),
+        Err(_) => Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: &str = r#"
+        rule MZ_Header : pe {
+            condition:
+                uint16(0) == 0x5A4D
+        }
+
+        rule Suspicious_Loader : loader windows {
+            meta:
+                author = "phantom"
+                description = "Injects via remote thread"
+                severity = "high"
+                confidence = 90
+            strings:
+                $api1 = "VirtualAllocEx" ascii wide
+                $api2 = "createremotethread" nocase
+                $stub = { E8 ?? ?? ?? ?? 5? [0-2] (C3 | C2 04 00) }
+                $url = /https?:\/\/[a-z0-9.]+\/gate\.php/
+            condition:
+                MZ_Header and 2 of ($api*) and ($stub or $url) and filesize < 1MB
+        }
+
+        private rule Helper { strings: $a = "helper" fullword condition: $a }
+        rule Uses_Helper { condition: Helper and not Suspicious_Loader }
+    "#;
+
+    fn sample() -> Vec<u8> {
+        let mut data = b"MZ\x90\x00".to_vec();
+        data.extend_from_slice(b"....VirtualAllocEx....");
+        data.extend(b"CreateRemoteThread".iter().flat_map(|&b| [b, 0]));
+        data.extend_from_slice(b"CreateRemoteThread");
+        data.extend_from_slice(&[0xE8, 1, 2, 3, 4, 0x55, 0x90, 0xC3]);
+        data.extend_from_slice(b" http://evil.example/gate.php ");
+        data
+    }
+
+    #[test]
+    fn test_scan_reports_rules_with_string_offsets() {
+        let mut rules = YaraRuleSet::default();
+        let names = rules.add_rules("malware", RULES).unwrap();
+        assert_eq!(names, vec!["MZ_Header", "Suspicious_Loader", "Helper", "Uses_Helper"]);
+
+        let data = sample();
+        let matches = rules.scan(&data);
+        let names: Vec<_> = matches.iter().map(|m| m.rule_name.as_str()).collect();
+        assert_eq!(names, vec!["malware:MZ_Header", "malware:Suspicious_Loader"]);
+
+        let loader = &matches[1];
+        assert_eq!(loader.severity, "high");
+        assert_eq!(loader.confidence, 0.9);
+        assert_eq!(loader.tags, vec!["loader", "windows"]);
+        let api1 = loader.matches.iter().find(|m| m.string_identifier == "$api1").unwrap();
+        assert_eq!(api1.offset, 8);
+        assert_eq!(api1.matched_data, "VirtualAllocEx");
+        let stub = loader.matches.iter().find(|m| m.string_identifier == "$stub").unwrap();
+        assert_eq!(&data[stub.offset as usize], &0xE8);
+        assert_eq!(stub.length, 8);
+        assert!(loader.matches.iter().any(|m| m.string_identifier == "$url"));
+
+        // Private rules feed references but are not reported themselves
+        let helper = rules.scan(b"a helper b");
+        assert_eq!(helper.len(), 1);
+        assert_eq!(helper[0].rule_name, "malware:Uses_Helper");
+        assert!(rules.scan(b"helpers").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_static_pipeline_reports_loaded_rules() {
+        let core = SandboxCore::new().unwrap();
+        core.add_yara_rules(Some("malware"), RULES).unwrap();
+        assert_eq!(core.scan_with_yara(&sample()).unwrap().len(), 2);
+
+        let (analysis, _) = core.static_pipeline.run(Arc::new(sample()), "PE32", "application/x-dosexec").await;
+        assert_eq!(analysis.yara_matches.len(), 2);
+        assert!(core.remove_yara_namespace("malware").unwrap());
+        let (analysis, _) = core.static_pipeline.run(Arc::new(sample()), "PE32", "application/x-dosexec").await;
+        assert!(analysis.yara_matches.is_empty());
+    }
+
+    #[test]
+    fn test_namespaces_globals_and_compile_errors() {
+        let mut rules = YaraRuleSet::default();
+        rules.add_rules("gate", "global rule Small { condition: filesize < 16 } rule Any { condition: true }").unwrap();
+        rules.add_rules("other", "rule Any { condition: #a == 2 and @a[2] == 4 and $a in (0..2) strings: $a = \"ab\" }").unwrap_err();
+        rules.add_rules("other", "rule Any { strings: $a = \"ab\" condition: #a == 2 and @a[2] == 4 and $a at 0 }").unwrap();
+        rules.add_rules("other", "rule Later { condition: Any }").unwrap();
+
+        let names = |data: &[u8]| rules.scan(data).into_iter().map(|m| m.rule_name).collect::<Vec<_>>();
+        assert_eq!(names(b"abxxab"), vec!["gate:Small", "gate:Any", "other:Any", "other:Later"]);
+        assert_eq!(names(b"abxxab with enough padding"), vec!["other:Any", "other:Later"]);
+
+        let err = rules.add_rules("other", "rule Any { condition: true }").unwrap_err();
+        assert!(err.message.contains("Duplicate rule"));
+        let err = compile_rules("import \"pe\"\nrule X { condition: true }", "default").unwrap_err();
+        assert_eq!(err.line, 1);
+        let err = compile_rules("rule X {\n strings: $a = \"x\"\n condition: $b }", "default").unwrap_err();
+        assert_eq!(err.line, 3);
+        assert!(compile_rules("rule X { strings: $a = { } condition: $a }", "default").is_err());
+        assert!(rules.remove_namespace("gate"));
+        assert_eq!(rules.rule_count(), 2);
+    }
+}
@@ -0,0 +1,94 @@
+//! Per-call authorization for the NAPI surface
+//!
+//! Mutating calls are checked with the shared
+//! `phantom_enterprise_standards::guard::AccessGuard`, whose docs describe
+//! how builds without `phantom-enterprise-standards` behave.
+
+pub use phantom_enterprise_standards::guard::AccessGuard;
+
+/// Guard for this build: RBAC with `phantom-enterprise-standards`, open without
+pub fn access_guard() -> AccessGuard {
+    if cfg!(feature = "phantom-enterprise-standards") {
+        AccessGuard::rbac()
+    } else {
+        AccessGuard::open()
+    }
+}
+
+#[cfg(all(test, feature = "napi", feature = "phantom-enterprise-standards"))]
+mod tests {
+    use crate::secop_core::SecOpCoreNapi;
+    use phantom_enterprise_standards::rbac::{Principal, Role};
+
+    #[tokio::test]
+    async fn viewers_cannot_change_shared_settings() {
+        let napi = SecOpCoreNapi::new();
+        let control = napi.access.control();
+        control.set_enforced(true);
+        let viewer = control.issue_api_key(Principal::new("viewer", vec![Role::Viewer]), None).token;
+        let mut tenant_admin = Principal::new("acme-admin", vec![Role::Admin]);
+        tenant_admin.tenant_id = Some("acme".to_string());
+        let tenant_admin = control.issue_api_key(tenant_admin, None).token;
+        let admin = control.issue_api_key(Principal::new("admin", vec![Role::Admin]), None).token;
+
+        assert!(napi.configure_triage("{}".to_string(), Some(viewer.clone())).await.is_err());
+        assert!(napi.configure_correlation("{}".to_string(), Some(viewer.clone())).await.is_err());
+        assert!(napi.configure_sla("{}".to_string(), Some(viewer.clone())).await.is_err());
+        assert!(napi.configure_readiness("{}".to_string(), Some(viewer.clone())).await.is_err());
+        assert!(napi.correlate_alerts(Some(viewer.clone())).await.is_err());
+        assert!(napi.delete_business_calendar("cal-1".to_string(), Some(viewer)).await.is_err());
+
+        assert!(napi.delete_business_calendar("cal-1".to_string(), Some(tenant_admin)).await.is_err());
+        assert!(!napi.delete_business_calendar("cal-1".to_string(), Some(admin)).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn tenant_admins_only_manage_their_own_credentials() {
+        let napi = SecOpCoreNapi::new();
+        let control = napi.access.control();
+        control.set_enforced(true);
+        let mut tenant_admin = Principal::new("acme-admin", vec![Role::Admin]);
+        tenant_admin.tenant_id = Some("acme".to_string());
+        let tenant_admin = control.issue_api_key(tenant_admin, None).token;
+        let mut other = Principal::new("globex-viewer", vec![Role::Viewer]);
+        other.tenant_id = Some("globex".to_string());
+        let other = control.issue_api_key(other, None);
+        let admin = control.issue_api_key(Principal::new("admin", vec![Role::Admin]), None).token;
+        napi.create_tenant("globex".to_string(), "Globex".to_string(), Some(admin.clone())).unwrap();
+        assert!(napi.create_incident("{}".to_string(), Some(other.token.clone())).await.is_err());
+
+        let count = |json: String| serde_json::from_str::<Vec<serde_json::Value>>(&json).unwrap().len();
+        assert_eq!(count(napi.list_credentials(Some(tenant_admin.clone())).unwrap()), 1);
+        assert_eq!(count(napi.list_access_denials(None, Some(tenant_admin.clone())).unwrap()), 0);
+        assert!(!napi.revoke_credential(other.credential_id.clone(), Some(tenant_admin.clone())).unwrap());
+        assert!(napi.set_access_enforced(false, Some(tenant_admin)).is_err());
+
+        assert_eq!(count(napi.list_credentials(Some(admin.clone())).unwrap()), 3);
+        assert_eq!(count(napi.list_access_denials(None, Some(admin.clone())).unwrap()), 1);
+        assert!(napi.revoke_credential(other.credential_id, Some(admin)).unwrap());
+    }
+
+    #[tokio::test]
+    async fn tenant_secrets_stay_with_their_tenant() {
+        let napi = SecOpCoreNapi::new();
+        let control = napi.access.control();
+        control.set_enforced(true);
+        let admin = control.issue_api_key(Principal::new("admin", vec![Role::Admin]), None).token;
+        napi.create_tenant("acme".to_string(), "Acme".to_string(), Some(admin.clone())).unwrap();
+        let mut tenant_admin = Principal::new("acme-admin", vec![Role::Admin]);
+        tenant_admin.tenant_id = Some("acme".to_string());
+        let tenant_admin = control.issue_api_key(tenant_admin, None).token;
+        let key = napi::bindgen_prelude::Buffer::from(vec![7u8; 32]);
+        assert!(napi.rotate_master_key(key.clone(), Some(tenant_admin.clone())).is_err());
+        napi.rotate_master_key(key, Some(admin.clone())).unwrap();
+
+        let stored: serde_json::Value = serde_json::from_str(&napi.put_secret("jira".to_string(), "acme-token".to_string(), Some(tenant_admin.clone())).unwrap()).unwrap();
+        assert_eq!(stored["reference"], "secret://jira");
+        assert_eq!(napi.inner.secrets().get("tenants/acme/jira").unwrap(), "acme-token");
+        assert!(napi.export_sealed_secrets(Some(tenant_admin.clone())).is_err());
+
+        let names = |json: String| serde_json::from_str::<Vec<serde_json::Value>>(&json).unwrap().into_iter().map(|m| m["name"].clone()).collect::<Vec<_>>();
+        assert_eq!(names(napi.list_secrets(Some(tenant_admin)).unwrap()), vec!["jira"]);
+        assert!(names(napi.list_secrets(Some(admin)).unwrap()).is_empty());
+    }
+}
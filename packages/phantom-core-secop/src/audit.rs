@@ -0,0 +1,19 @@
+//! Audit trail for mutating NAPI operations
+//!
+//! Every mutating call made through `SecOpCoreNapi` is recorded with the shared
+//! `phantom_enterprise_standards::guard::AuditTrail`, whose docs describe
+//! how builds without `phantom-enterprise-standards` behave.
+
+pub use phantom_enterprise_standards::guard::AuditTrail;
+
+/// Name recorded as the `source` of every entry
+pub const AUDIT_SOURCE: &str = "secop";
+
+/// Trail for this build: recording with `phantom-enterprise-standards`, disabled without
+pub fn audit_trail() -> AuditTrail {
+    if cfg!(feature = "phantom-enterprise-standards") {
+        AuditTrail::new(AUDIT_SOURCE)
+    } else {
+        AuditTrail::disabled(AUDIT_SOURCE)
+    }
+}
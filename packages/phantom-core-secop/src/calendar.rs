@@ -0,0 +1,367 @@
+//! Business calendars for SLA timing
+//!
+//! A `BusinessCalendar` describes when a team is working: weekly working
+//! windows in the team's timezone plus holidays. SLA clocks either run on
+//! wall-clock time or only while the calendar is in working hours, pausing
+//! across nights, weekends and holidays and resuming at the next working
+//! window.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Upper bound on how far calendar arithmetic walks forward or back
+const MAX_CALENDAR_SPAN_DAYS: i64 = 3 * 366;
+
+/// Working window on a given weekday, in the calendar's local time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkingWindow {
+    pub weekday: Weekday,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Holiday {
+    pub date: NaiveDate,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BusinessCalendar {
+    #[serde(default)]
+    pub calendar_id: String,
+    pub name: String,
+    /// IANA timezone name, e.g. "Europe/London"
+    pub timezone: String,
+    pub working_hours: Vec<WorkingWindow>,
+    #[serde(default)]
+    pub holidays: Vec<Holiday>,
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+}
+
+/// How an SLA clock measures elapsed time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode")]
+pub enum SlaTiming {
+    WallClock,
+    /// Only working hours count; without a calendar id the team's calendar is used
+    BusinessHours { calendar_id: Option<String> },
+}
+
+/// Point-in-time state of an SLA clock
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlaClockStatus {
+    pub timing: SlaTiming,
+    pub started_at: DateTime<Utc>,
+    pub target_minutes: i64,
+    pub elapsed_minutes: i64,
+    pub remaining_minutes: i64,
+    pub deadline: DateTime<Utc>,
+    /// False while the calendar is outside working hours
+    pub running: bool,
+    pub resumes_at: Option<DateTime<Utc>>,
+    pub breached: bool,
+}
+
+impl BusinessCalendar {
+    /// Monday to Friday, 09:00 - 17:00 local time
+    pub fn standard(name: &str, timezone: &str) -> Self {
+        let start = NaiveTime::from_hms_opt(9, 0, 0).expect("valid time");
+        let end = NaiveTime::from_hms_opt(17, 0, 0).expect("valid time");
+        Self {
+            calendar_id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            timezone: timezone.to_string(),
+            working_hours: [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri]
+                .into_iter()
+                .map(|weekday| WorkingWindow { weekday, start, end })
+                .collect(),
+            holidays: vec![],
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Calendar name is required".to_string());
+        }
+        self.tz()?;
+        if self.working_hours.is_empty() {
+            return Err("Calendar must define at least one working window".to_string());
+        }
+        if let Some(window) = self.working_hours.iter().find(|w| w.end <= w.start) {
+            return Err(format!(
+                "Working window on {} must end after it starts; split overnight shifts into two windows",
+                window.weekday
+            ));
+        }
+        Ok(())
+    }
+
+    fn tz(&self) -> Result<Tz, String> {
+        self.timezone.parse::<Tz>().map_err(|_| format!("Unknown timezone '{}'", self.timezone))
+    }
+
+    fn is_holiday(&self, date: NaiveDate) -> bool {
+        self.holidays.iter().any(|h| h.date == date)
+    }
+
+    /// Working intervals on one local date, in UTC and in chronological order
+    fn intervals_on(&self, tz: &Tz, date: NaiveDate) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        if self.is_holiday(date) {
+            return vec![];
+        }
+        let mut intervals: Vec<(DateTime<Utc>, DateTime<Utc>)> = self
+            .working_hours
+            .iter()
+            .filter(|w| w.weekday == date.weekday())
+            .filter_map(|w| {
+                let start = tz.from_local_datetime(&date.and_time(w.start)).earliest()?;
+                let end = tz.from_local_datetime(&date.and_time(w.end)).latest()?;
+                Some((start.with_timezone(&Utc), end.with_timezone(&Utc)))
+            })
+            .filter(|(start, end)| end > start)
+            .collect();
+        intervals.sort();
+        intervals
+    }
+
+    fn local_date(tz: &Tz, at: DateTime<Utc>) -> NaiveDate {
+        at.with_timezone(tz).date_naive()
+    }
+
+    pub fn is_working_time(&self, at: DateTime<Utc>) -> Result<bool, String> {
+        let tz = self.tz()?;
+        let date = Self::local_date(&tz, at);
+        Ok(self.intervals_on(&tz, date).iter().any(|(start, end)| *start <= at && at < *end))
+    }
+
+    /// Start of the next working window at or after `at`
+    pub fn next_working_time(&self, at: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+        let tz = self.tz()?;
+        let first = Self::local_date(&tz, at);
+        for offset in 0..MAX_CALENDAR_SPAN_DAYS {
+            let date = first + Duration::days(offset);
+            for (start, end) in self.intervals_on(&tz, date) {
+                if at < end {
+                    return Ok(start.max(at));
+                }
+            }
+        }
+        Err(format!("Calendar '{}' has no working time in the next {} days", self.name, MAX_CALENDAR_SPAN_DAYS))
+    }
+
+    /// Working time between `start` and `end`
+    pub fn working_duration_between(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<Duration, String> {
+        if end <= start {
+            return Ok(Duration::zero());
+        }
+        let tz = self.tz()?;
+        let first = Self::local_date(&tz, start);
+        let last = Self::local_date(&tz, end);
+        if (last - first).num_days() > MAX_CALENDAR_SPAN_DAYS {
+            return Err(format!("Span exceeds {} days", MAX_CALENDAR_SPAN_DAYS));
+        }
+
+        let mut total = Duration::zero();
+        let mut date = first;
+        while date <= last {
+            for (window_start, window_end) in self.intervals_on(&tz, date) {
+                let from = window_start.max(start);
+                let to = window_end.min(end);
+                if to > from {
+                    total += to - from;
+                }
+            }
+            date += Duration::days(1);
+        }
+        Ok(total)
+    }
+
+    /// Instant at which `duration` of working time has elapsed after `start`
+    pub fn add_working_duration(&self, start: DateTime<Utc>, duration: Duration) -> Result<DateTime<Utc>, String> {
+        let tz = self.tz()?;
+        let mut remaining = duration;
+        let first = Self::local_date(&tz, start);
+        for offset in 0..MAX_CALENDAR_SPAN_DAYS {
+            let date = first + Duration::days(offset);
+            for (window_start, window_end) in self.intervals_on(&tz, date) {
+                let from = window_start.max(start);
+                if window_end <= from {
+                    continue;
+                }
+                let available = window_end - from;
+                if remaining <= available {
+                    return Ok(from + remaining);
+                }
+                remaining -= available;
+            }
+        }
+        Err(format!("Calendar '{}' cannot satisfy the SLA within {} days", self.name, MAX_CALENDAR_SPAN_DAYS))
+    }
+}
+
+/// Calendars and the team assignments that select them
+#[derive(Debug, Default)]
+pub struct CalendarStore {
+    calendars: HashMap<String, BusinessCalendar>,
+    team_calendars: HashMap<String, String>,
+}
+
+impl CalendarStore {
+    pub fn create(&mut self, mut calendar: BusinessCalendar) -> Result<BusinessCalendar, String> {
+        calendar.validate()?;
+        if calendar.calendar_id.is_empty() {
+            calendar.calendar_id = Uuid::new_v4().to_string();
+        }
+        if self.calendars.contains_key(&calendar.calendar_id) {
+            return Err(format!("Calendar {} already exists", calendar.calendar_id));
+        }
+        calendar.created_at = Utc::now();
+        calendar.updated_at = calendar.created_at;
+        self.calendars.insert(calendar.calendar_id.clone(), calendar.clone());
+        Ok(calendar)
+    }
+
+    pub fn update(&mut self, mut calendar: BusinessCalendar) -> Result<BusinessCalendar, String> {
+        calendar.validate()?;
+        let existing = self
+            .calendars
+            .get(&calendar.calendar_id)
+            .ok_or_else(|| format!("Calendar {} not found", calendar.calendar_id))?;
+        calendar.created_at = existing.created_at;
+        calendar.updated_at = Utc::now();
+        self.calendars.insert(calendar.calendar_id.clone(), calendar.clone());
+        Ok(calendar)
+    }
+
+    /// Remove a calendar; teams using it fall back to wall-clock timing
+    pub fn delete(&mut self, calendar_id: &str) -> bool {
+        self.team_calendars.retain(|_, id| id != calendar_id);
+        self.calendars.remove(calendar_id).is_some()
+    }
+
+    pub fn get(&self, calendar_id: &str) -> Option<&BusinessCalendar> {
+        self.calendars.get(calendar_id)
+    }
+
+    pub fn list(&self) -> Vec<BusinessCalendar> {
+        let mut calendars: Vec<BusinessCalendar> = self.calendars.values().cloned().collect();
+        calendars.sort_by(|a, b| a.name.cmp(&b.name));
+        calendars
+    }
+
+    pub fn assign_team(&mut self, team: &str, calendar_id: &str) -> Result<(), String> {
+        if !self.calendars.contains_key(calendar_id) {
+            return Err(format!("Calendar {} not found", calendar_id));
+        }
+        self.team_calendars.insert(team.to_string(), calendar_id.to_string());
+        Ok(())
+    }
+
+    pub fn team_calendar(&self, team: &str) -> Option<&BusinessCalendar> {
+        self.team_calendars.get(team).and_then(|id| self.calendars.get(id))
+    }
+
+    /// Calendar an SLA clock runs on, or `None` for wall-clock timing
+    fn resolve(&self, timing: &SlaTiming, team: Option<&str>) -> Result<Option<&BusinessCalendar>, String> {
+        match timing {
+            SlaTiming::WallClock => Ok(None),
+            SlaTiming::BusinessHours { calendar_id: Some(id) } => {
+                self.get(id).map(Some).ok_or_else(|| format!("Calendar {} not found", id))
+            }
+            SlaTiming::BusinessHours { calendar_id: None } => Ok(team.and_then(|t| self.team_calendar(t))),
+        }
+    }
+
+    /// Evaluate an SLA clock started at `started_at` with a `target_minutes` budget
+    pub fn evaluate_clock(
+        &self,
+        timing: &SlaTiming,
+        team: Option<&str>,
+        started_at: DateTime<Utc>,
+        target_minutes: i64,
+        now: DateTime<Utc>,
+    ) -> Result<SlaClockStatus, String> {
+        let target = Duration::minutes(target_minutes);
+        let (elapsed, deadline, running, resumes_at) = match self.resolve(timing, team)? {
+            None => (now - started_at, started_at + target, true, None),
+            Some(calendar) => {
+                let elapsed = calendar.working_duration_between(started_at, now)?;
+                let deadline = calendar.add_working_duration(started_at, target)?;
+                let running = calendar.is_working_time(now)?;
+                let resumes_at = if running { None } else { Some(calendar.next_working_time(now)?) };
+                (elapsed, deadline, running, resumes_at)
+            }
+        };
+        let elapsed_minutes = elapsed.num_minutes().max(0);
+
+        Ok(SlaClockStatus {
+            timing: timing.clone(),
+            started_at,
+            target_minutes,
+            elapsed_minutes,
+            remaining_minutes: (target_minutes - elapsed_minutes).max(0),
+            deadline,
+            running,
+            resumes_at,
+            breached: now >= deadline,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_clock_pauses_over_weekend_and_holiday() {
+        let mut calendar = BusinessCalendar::standard("EU SOC", "Europe/Berlin");
+        calendar.holidays.push(Holiday { date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(), name: "New Year".to_string() });
+
+        // Friday 2023-12-29 16:00 CET; one hour left on Friday, Monday is a holiday
+        let start = utc("2023-12-29T15:00:00Z");
+        let deadline = calendar.add_working_duration(start, Duration::hours(2)).unwrap();
+        assert_eq!(deadline, utc("2024-01-02T09:00:00Z"));
+
+        assert_eq!(calendar.working_duration_between(start, deadline).unwrap(), Duration::hours(2));
+        assert!(!calendar.is_working_time(utc("2023-12-30T12:00:00Z")).unwrap());
+        assert_eq!(calendar.next_working_time(utc("2023-12-30T12:00:00Z")).unwrap(), utc("2024-01-02T08:00:00Z"));
+    }
+
+    #[test]
+    fn test_team_calendar_and_wall_clock_timing() {
+        let mut store = CalendarStore::default();
+        let calendar = store.create(BusinessCalendar::standard("US SOC", "America/New_York")).unwrap();
+        store.assign_team("tier1", &calendar.calendar_id).unwrap();
+
+        // Saturday: wall clock keeps running, business hours are paused
+        let start = utc("2024-03-09T15:00:00Z");
+        let now = utc("2024-03-09T19:00:00Z");
+        let wall = store.evaluate_clock(&SlaTiming::WallClock, Some("tier1"), start, 60, now).unwrap();
+        assert!(wall.breached);
+        assert_eq!(wall.elapsed_minutes, 240);
+
+        let business = store
+            .evaluate_clock(&SlaTiming::BusinessHours { calendar_id: None }, Some("tier1"), start, 60, now)
+            .unwrap();
+        assert!(!business.breached);
+        assert!(!business.running);
+        assert_eq!(business.elapsed_minutes, 0);
+        // Monday 09:00 EDT after the DST change
+        assert_eq!(business.resumes_at, Some(utc("2024-03-11T13:00:00Z")));
+
+        assert!(store.create(BusinessCalendar::standard("Bad", "Mars/Olympus")).is_err());
+    }
+}
@@ -0,0 +1,595 @@
+//! Scheduled compliance reporting
+//!
+//! Generates SOC 2, PCI DSS and GDPR evidence packs for a tenant and period
+//! from the SOC's own records:
+//!
+//! - incident handling: every incident with activity in the period and its
+//!   timeline, with SLA breaches as exceptions
+//! - evidence custody: every evidence reference on those incidents with the
+//!   audited operations on the incident up to the period end
+//! - access audit: audited activity on the tenant's incidents and alerts
+//!   per actor, and the integrity of the audit chain
+//!
+//! Packs are sealed into a hash-chained archive (see
+//! `phantom_enterprise_standards::compliance_reports`) that can be backed by
+//! a file for auditors. Schedules generate a pack for each tenant and
+//! framework once per cadence, covering the last full period.
+
+use crate::audit::AuditTrail;
+use crate::error::{CoreError, CoreResult};
+use crate::secop_core::SecOpCore;
+use crate::SecurityIncident;
+use chrono::{DateTime, Utc};
+use phantom_enterprise_standards::audit_log::{AuditOutcome, AuditQuery, AuditRecord};
+use phantom_enterprise_standards::compliance::ComplianceFramework;
+use phantom_enterprise_standards::compliance_reports::{
+    ControlCatalog, ControlDefinition, EvidenceArchive, EvidenceItem, EvidenceKind, EvidencePack, EvidencePackSummary,
+    ReportCadence,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::{watch, Mutex, RwLock};
+use tokio::task::JoinHandle;
+
+#[cfg(feature = "napi")]
+use crate::secop_core::SecOpCoreNapi;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+
+/// Timeline event raised when an SLA timer breaches
+const SLA_BREACHED: &str = "SlaBreached";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComplianceSchedule {
+    pub schedule_id: String,
+    /// Set from the caller's tenant when registered
+    #[serde(default)]
+    pub tenant_id: String,
+    pub framework: ComplianceFramework,
+    pub cadence: ReportCadence,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// End of the last period a pack was generated for
+    #[serde(default)]
+    pub last_period_end: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_report_id: Option<String>,
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+struct SchedulerRun {
+    stop: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+}
+
+pub struct ComplianceReportState {
+    schedules: RwLock<HashMap<String, ComplianceSchedule>>,
+    catalog: RwLock<ControlCatalog>,
+    archive: std::sync::RwLock<Arc<EvidenceArchive>>,
+    scheduler: Mutex<Option<SchedulerRun>>,
+}
+
+impl Default for ComplianceReportState {
+    fn default() -> Self {
+        Self {
+            schedules: RwLock::new(HashMap::new()),
+            catalog: RwLock::new(ControlCatalog::default()),
+            archive: std::sync::RwLock::new(Arc::new(EvidenceArchive::new())),
+            scheduler: Mutex::new(None),
+        }
+    }
+}
+
+impl ComplianceReportState {
+    fn archive(&self) -> Arc<EvidenceArchive> {
+        self.archive.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Whether the incident was open or changed during `[start, end)`
+fn active_in(incident: &SecurityIncident, start: DateTime<Utc>, end: DateTime<Utc>) -> bool {
+    incident.created_at < end
+        && (incident.created_at >= start || incident.timeline.iter().any(|event| event.timestamp >= start && event.timestamp < end))
+}
+
+fn first_event(incident: &SecurityIncident, event_types: &[&str]) -> Option<DateTime<Utc>> {
+    incident.timeline.iter().filter(|event| event_types.contains(&event.event_type.as_str())).map(|event| event.timestamp).min()
+}
+
+fn handling_evidence(incident: &SecurityIncident, start: DateTime<Utc>, end: DateTime<Utc>) -> EvidenceItem {
+    let in_period: Vec<_> = incident.timeline.iter().filter(|event| event.timestamp >= start && event.timestamp < end).collect();
+    let breaches: Vec<&str> =
+        in_period.iter().filter(|event| event.event_type == SLA_BREACHED).map(|event| event.description.as_str()).collect();
+    let acknowledged_at = first_event(incident, crate::sla::ACKNOWLEDGE_EVENTS);
+    let contained_at = first_event(incident, crate::sla::CONTAIN_EVENTS);
+    let resolved_at = first_event(incident, crate::sla::RESOLVE_EVENTS);
+
+    EvidenceItem {
+        evidence_id: format!("incident:{}", incident.incident_id),
+        kind: EvidenceKind::IncidentHandling,
+        reference: incident.incident_id.clone(),
+        occurred_at: incident.created_at,
+        summary: format!(
+            "{} incident \"{}\" is {}; {} timeline event(s) in the period",
+            incident.severity,
+            incident.title,
+            incident.status,
+            in_period.len()
+        ),
+        exception: (!breaches.is_empty()).then(|| format!("SLA breached: {}", breaches.join("; "))),
+        data: json!({
+            "severity": incident.severity,
+            "category": incident.category,
+            "status": incident.status,
+            "assigned_to": incident.assigned_to,
+            "created_at": incident.created_at,
+            "acknowledged_at": acknowledged_at,
+            "contained_at": contained_at,
+            "resolved_at": resolved_at,
+            "merged_into": incident.merged_into,
+            "timeline": in_period
+                .iter()
+                .map(|event| json!({ "timestamp": event.timestamp, "event_type": event.event_type, "source": event.source }))
+                .collect::<Vec<_>>(),
+        }),
+    }
+}
+
+fn custody_evidence(incident: &SecurityIncident, trail: &[&AuditRecord]) -> Vec<EvidenceItem> {
+    let custody: Vec<_> = trail
+        .iter()
+        .map(|record| {
+            json!({
+                "sequence": record.sequence,
+                "timestamp": record.timestamp,
+                "actor": record.actor,
+                "operation": record.operation,
+                "outcome": record.outcome,
+                "hash": record.hash,
+            })
+        })
+        .collect();
+    incident
+        .evidence
+        .iter()
+        .map(|reference| EvidenceItem {
+            evidence_id: format!("custody:{}:{}", incident.incident_id, reference),
+            kind: EvidenceKind::EvidenceCustody,
+            reference: reference.clone(),
+            occurred_at: trail.first().map_or(incident.created_at, |record| record.timestamp),
+            summary: format!("Attached to incident {}; {} audited operation(s)", incident.incident_id, trail.len()),
+            exception: trail.is_empty().then(|| format!("No audited handling of incident {}", incident.incident_id)),
+            data: json!({ "incident_id": incident.incident_id, "custody": custody }),
+        })
+        .collect()
+}
+
+impl SecOpCore {
+    /// Evidence of the tenant's activity in `[start, end)`
+    pub async fn collect_compliance_evidence(
+        &self,
+        tenant_id: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        audit: &AuditTrail,
+    ) -> Vec<EvidenceItem> {
+        let mut incidents: Vec<SecurityIncident> = self
+            .incidents
+            .read()
+            .await
+            .values()
+            .filter(|incident| incident.tenant_id == tenant_id && active_in(incident, start, end))
+            .cloned()
+            .collect();
+        incidents.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.incident_id.cmp(&b.incident_id)));
+
+        let mut resources: HashSet<String> =
+            self.incidents.read().await.values().filter(|i| i.tenant_id == tenant_id).map(|i| i.incident_id.clone()).collect();
+        resources.extend(self.alerts.read().await.values().filter(|a| a.tenant_id == tenant_id).map(|a| a.alert_id.clone()));
+
+        let log = audit.log();
+        let records = log.entries(&AuditQuery { until: Some(end), ..AuditQuery::default() });
+        let mut evidence = Vec::new();
+        for incident in &incidents {
+            evidence.push(handling_evidence(incident, start, end));
+            let trail: Vec<&AuditRecord> = records.iter().filter(|record| record.resource == incident.incident_id).collect();
+            evidence.extend(custody_evidence(incident, &trail));
+        }
+
+        // Per-actor activity on the tenant's records; the log is shared by
+        // all tenants, so nothing else is disclosed
+        let mut by_actor: BTreeMap<&str, BTreeMap<&str, usize>> = BTreeMap::new();
+        for record in records.iter().filter(|record| record.timestamp >= start && resources.contains(&record.resource)) {
+            *by_actor.entry(&record.actor).or_default().entry(record.outcome.as_str()).or_default() += 1;
+        }
+        for (actor, outcomes) in by_actor {
+            let total: usize = outcomes.values().sum();
+            evidence.push(EvidenceItem {
+                evidence_id: format!("access:{}", actor),
+                kind: EvidenceKind::AccessAudit,
+                reference: actor.to_string(),
+                occurred_at: start,
+                summary: format!("{} audited operation(s) by {}", total, actor),
+                exception: None,
+                data: json!({ "outcomes": outcomes, "denied": outcomes.get(AuditOutcome::Denied.as_str()).copied().unwrap_or(0) }),
+            });
+        }
+
+        let verification = log.verify();
+        evidence.push(EvidenceItem {
+            evidence_id: "access:audit-chain".to_string(),
+            kind: EvidenceKind::AccessAudit,
+            reference: "audit_log".to_string(),
+            occurred_at: end,
+            summary: format!("Audit chain of {} entries verified", verification.entries_checked),
+            exception: verification.reason.as_ref().map(|reason| format!("Audit chain broken: {}", reason)),
+            data: json!(verification),
+        });
+        evidence
+    }
+
+    /// Generate, seal and archive an evidence pack for `[start, end)`
+    #[allow(clippy::too_many_arguments)]
+    pub async fn generate_compliance_report(
+        &self,
+        tenant_id: &str,
+        framework: ComplianceFramework,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        generated_by: &str,
+        schedule_id: Option<String>,
+        audit: &AuditTrail,
+    ) -> CoreResult<EvidencePack> {
+        if start >= end {
+            return Err(CoreError::validation("Report period must end after it starts"));
+        }
+        let controls = self.compliance.catalog.read().await.controls(&framework);
+        if controls.is_empty() {
+            return Err(CoreError::validation(format!("No control mapping for {:?}", framework)));
+        }
+        let evidence = self.collect_compliance_evidence(tenant_id, start, end, audit).await;
+        let pack = EvidencePack::new(framework, tenant_id, start, end, generated_by, schedule_id, &controls, evidence);
+        Ok(self.compliance.archive().append(pack)?)
+    }
+
+    pub async fn get_compliance_report(&self, tenant_id: &str, report_id: &str) -> Option<EvidencePack> {
+        self.compliance.archive().get(report_id).filter(|pack| pack.tenant_id == tenant_id)
+    }
+
+    /// Archived packs of the tenant, newest first
+    pub async fn list_compliance_reports(&self, tenant_id: &str, framework: Option<&ComplianceFramework>) -> Vec<EvidencePackSummary> {
+        self.compliance.archive().list(tenant_id, framework)
+    }
+
+    pub fn compliance_archive(&self) -> Arc<EvidenceArchive> {
+        self.compliance.archive()
+    }
+
+    /// Switch to a file-backed archive, continuing the chain already in that file
+    pub fn open_compliance_archive(&self, path: &str) -> CoreResult<()> {
+        let archive = EvidenceArchive::open(path)?;
+        *self.compliance.archive.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(archive);
+        Ok(())
+    }
+
+    /// Replace the control mapping of a framework; an empty list restores the built-in one
+    pub async fn set_compliance_controls(&self, framework: &ComplianceFramework, controls: Vec<ControlDefinition>) {
+        self.compliance.catalog.write().await.set(framework, controls);
+    }
+
+    /// Add or replace a schedule; its first pack covers the last full period
+    pub async fn schedule_compliance_report(&self, tenant_id: &str, mut schedule: ComplianceSchedule) -> CoreResult<()> {
+        if schedule.schedule_id.trim().is_empty() {
+            return Err(CoreError::validation("Schedule id is required"));
+        }
+        if self.compliance.catalog.read().await.controls(&schedule.framework).is_empty() {
+            return Err(CoreError::validation(format!("No control mapping for {:?}", schedule.framework)));
+        }
+        let mut schedules = self.compliance.schedules.write().await;
+        if schedules.get(&schedule.schedule_id).is_some_and(|existing| existing.tenant_id != tenant_id) {
+            return Err(CoreError::validation(format!("Schedule id {} is already in use", schedule.schedule_id)));
+        }
+        schedule.tenant_id = tenant_id.to_string();
+        schedules.insert(schedule.schedule_id.clone(), schedule);
+        Ok(())
+    }
+
+    pub async fn remove_compliance_schedule(&self, tenant_id: &str, schedule_id: &str) -> bool {
+        let mut schedules = self.compliance.schedules.write().await;
+        if schedules.get(schedule_id).is_some_and(|schedule| schedule.tenant_id == tenant_id) {
+            schedules.remove(schedule_id);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub async fn list_compliance_schedules(&self, tenant_id: &str) -> Vec<ComplianceSchedule> {
+        let mut schedules: Vec<ComplianceSchedule> =
+            self.compliance.schedules.read().await.values().filter(|s| s.tenant_id == tenant_id).cloned().collect();
+        schedules.sort_by(|a, b| a.schedule_id.cmp(&b.schedule_id));
+        schedules
+    }
+
+    /// Generate a pack for every enabled schedule whose last full period has
+    /// not been reported yet
+    pub async fn run_due_compliance_reports(&self, now: DateTime<Utc>, audit: &AuditTrail) -> Vec<EvidencePackSummary> {
+        let mut due: Vec<(ComplianceSchedule, DateTime<Utc>, DateTime<Utc>)> = self
+            .compliance
+            .schedules
+            .read()
+            .await
+            .values()
+            .filter(|schedule| schedule.enabled)
+            .filter_map(|schedule| {
+                let (start, end) = schedule.cadence.last_full_period(now);
+                schedule.last_period_end.is_none_or(|last| last < end).then(|| (schedule.clone(), start, end))
+            })
+            .collect();
+        due.sort_by(|a, b| a.0.schedule_id.cmp(&b.0.schedule_id));
+
+        let mut generated = Vec::with_capacity(due.len());
+        for (schedule, start, end) in due {
+            let generated_by = format!("scheduler:{}", schedule.schedule_id);
+            let result = self
+                .generate_compliance_report(&schedule.tenant_id, schedule.framework.clone(), start, end, &generated_by, Some(schedule.schedule_id.clone()), audit)
+                .await;
+            let mut schedules = self.compliance.schedules.write().await;
+            let Some(entry) = schedules.get_mut(&schedule.schedule_id) else { continue };
+            match result {
+                Ok(pack) => {
+                    entry.last_period_end = Some(end);
+                    entry.last_report_id = Some(pack.report_id.clone());
+                    entry.last_error = None;
+                    generated.push(pack.summary());
+                }
+                Err(e) => {
+                    log::warn!("Compliance schedule {} failed: {}", schedule.schedule_id, e);
+                    entry.last_error = Some(e.to_string());
+                }
+            }
+        }
+        generated
+    }
+
+    /// Run due schedules every `interval`; fails if the loop is already running
+    pub async fn start_compliance_scheduler(self: &Arc<Self>, interval: std::time::Duration, audit: AuditTrail) -> CoreResult<()> {
+        let mut scheduler = self.compliance.scheduler.lock().await;
+        if scheduler.as_ref().is_some_and(|s| !s.handle.is_finished()) {
+            return Err(CoreError::validation("Compliance report scheduler is already running"));
+        }
+
+        let (stop, mut stopped) = watch::channel(false);
+        let core = Arc::clone(self);
+        let interval = interval.max(std::time::Duration::from_secs(1));
+        let handle = tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                tokio::select! {
+                    _ = ticks.tick() => {
+                        core.run_due_compliance_reports(Utc::now(), &audit).await;
+                    }
+                    changed = stopped.changed() => {
+                        if changed.is_err() || *stopped.borrow() {
+                            break;
+                        }
+                    }
+                }
+            }
+            log::info!("Compliance report scheduler stopped");
+        });
+
+        *scheduler = Some(SchedulerRun { stop, handle });
+        log::info!("Compliance report scheduler started (every {:?})", interval);
+        Ok(())
+    }
+
+    /// Stop the scheduler; a run in progress is allowed to finish
+    pub async fn stop_compliance_scheduler(&self) -> bool {
+        let Some(run) = self.compliance.scheduler.lock().await.take() else {
+            return false;
+        };
+        let _ = run.stop.send(true);
+        let _ = run.handle.await;
+        true
+    }
+}
+
+#[cfg(feature = "napi")]
+fn parse_framework(framework: &str) -> napi::Result<ComplianceFramework> {
+    serde_json::from_value(json!(framework))
+        .map_err(|_| napi::Error::from_reason(format!("Unknown compliance framework: {}", framework)))
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl SecOpCoreNapi {
+    /// Generate and archive an evidence pack for `framework` (`SOC2`,
+    /// `PCI_DSS`, `GDPR`) over an RFC 3339 period; returns the sealed pack
+    #[napi]
+    pub async fn generate_compliance_report(
+        &self,
+        framework: String,
+        period_start: String,
+        period_end: String,
+        auth_token: Option<String>,
+    ) -> napi::Result<String> {
+        let actor = self.authorize(auth_token.clone(), "audit:read", "compliance")?;
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let parsed = parse_framework(&framework)?;
+        let parse_time = |value: &str| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| napi::Error::from_reason(format!("Invalid report period {}: {}", value, e)))
+        };
+        let (start, end) = (parse_time(&period_start)?, parse_time(&period_end)?);
+
+        let params = json!({ "framework": framework, "period_start": period_start, "period_end": period_end });
+        let pack = self.inner.generate_compliance_report(&tenant_id, parsed, start, end, &actor, None, &self.audit).await;
+        let pack = self.audit.record(&actor, "generate_compliance_report", "compliance", params, pack)
+            .map_err(|e| e.context("Failed to generate compliance report"))?;
+        serde_json::to_string(&pack)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Archived evidence packs of the caller's tenant, newest first
+    #[napi]
+    pub async fn list_compliance_reports(&self, framework: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token.clone(), "audit:read", "compliance")?;
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let framework = framework.as_deref().map(parse_framework).transpose()?;
+        serde_json::to_string(&self.inner.list_compliance_reports(&tenant_id, framework.as_ref()).await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn get_compliance_report(&self, report_id: String, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        self.authorize(auth_token.clone(), "audit:read", &report_id)?;
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        match self.inner.get_compliance_report(&tenant_id, &report_id).await {
+            Some(pack) => serde_json::to_string(&pack)
+                .map(Some)
+                .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e))),
+            None => Ok(None),
+        }
+    }
+
+    /// Walk the report archive's hash chain and report the first altered pack, if any
+    #[napi]
+    pub fn verify_compliance_reports(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token, "audit:read", "compliance")?;
+        serde_json::to_string(&self.inner.compliance_archive().verify())
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Persist the report archive to a JSON-lines file, resuming any chain already in it
+    #[napi]
+    pub fn open_compliance_archive(&self, path: String, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize_platform(auth_token, &path)?;
+        let result = self.inner.open_compliance_archive(&path);
+        Ok(self.audit.record(&actor, "open_compliance_archive", &path, json!({}), result)
+            .map_err(|e| e.context("Failed to open compliance archive"))?)
+    }
+
+    /// Replace the control mapping of a framework with a JSON list of
+    /// `ControlDefinition`; an empty list restores the built-in mapping
+    #[napi]
+    pub async fn set_compliance_controls(&self, framework: String, controls_json: String, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize_platform(auth_token, "compliance")?;
+        let parsed = parse_framework(&framework)?;
+        let controls: Vec<ControlDefinition> = serde_json::from_str(&controls_json)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid control mapping: {}", e)))?;
+        let params = json!({ "framework": framework, "controls": controls });
+        self.inner.set_compliance_controls(&parsed, controls).await;
+        self.audit.record(&actor, "set_compliance_controls", "compliance", params, Ok::<_, String>(()))
+            .map_err(napi::Error::from_reason)
+    }
+
+    /// Add or replace a report schedule for the caller's tenant
+    #[napi]
+    pub async fn schedule_compliance_report(&self, schedule_json: String, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize(auth_token.clone(), "audit:read", "compliance")?;
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let schedule: ComplianceSchedule = serde_json::from_str(&schedule_json)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid compliance schedule: {}", e)))?;
+        let schedule_id = schedule.schedule_id.clone();
+        let result = self.inner.schedule_compliance_report(&tenant_id, schedule).await;
+        Ok(self.audit.record(&actor, "schedule_compliance_report", &schedule_id, json!({ "schedule": schedule_json }), result)
+            .map_err(|e| e.context("Failed to schedule compliance report"))?)
+    }
+
+    #[napi]
+    pub async fn remove_compliance_schedule(&self, schedule_id: String, auth_token: Option<String>) -> napi::Result<bool> {
+        let actor = self.authorize(auth_token.clone(), "audit:read", &schedule_id)?;
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let removed = self.inner.remove_compliance_schedule(&tenant_id, &schedule_id).await;
+        self.audit.record(&actor, "remove_compliance_schedule", &schedule_id, json!({}), Ok::<_, String>(removed))
+            .map_err(napi::Error::from_reason)
+    }
+
+    #[napi]
+    pub async fn list_compliance_schedules(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token.clone(), "audit:read", "compliance")?;
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        serde_json::to_string(&self.inner.list_compliance_schedules(&tenant_id).await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Run due report schedules every `interval_secs`
+    #[napi]
+    pub async fn start_compliance_scheduler(&self, interval_secs: u32, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize_platform(auth_token, "compliance")?;
+        let interval = std::time::Duration::from_secs(u64::from(interval_secs));
+        let result = self.inner.start_compliance_scheduler(interval, self.audit.clone()).await;
+        let params = json!({ "interval_secs": interval_secs });
+        Ok(self.audit.record(&actor, "start_compliance_scheduler", "compliance", params, result)
+            .map_err(|e| e.context("Failed to start compliance scheduler"))?)
+    }
+
+    /// Stop the report scheduler; `false` when it was not running
+    #[napi]
+    pub async fn stop_compliance_scheduler(&self, auth_token: Option<String>) -> napi::Result<bool> {
+        let actor = self.authorize_platform(auth_token, "compliance")?;
+        let stopped = self.inner.stop_compliance_scheduler().await;
+        self.audit.record(&actor, "stop_compliance_scheduler", "compliance", json!({}), Ok::<_, String>(stopped))
+            .map_err(napi::Error::from_reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use phantom_enterprise_standards::compliance_reports::ControlStatus;
+
+    #[tokio::test]
+    async fn scheduled_packs_cover_the_last_period_once() {
+        let core = SecOpCore::new();
+        let audit = crate::audit::audit_trail();
+        let now = Utc::now();
+        let created = now - Duration::days(10);
+
+        let mut incident: SecurityIncident = serde_json::from_value(json!({
+            "incident_id": "INC-1", "title": "Card data exfiltration", "description": "d", "severity": "Critical",
+            "status": "Open", "category": "DataBreach", "priority": 1, "created_at": created, "updated_at": created,
+            "assigned_to": "soc", "reporter": "dlp", "affected_systems": [], "indicators": [], "timeline": [],
+            "mitigation_actions": [], "estimated_impact": 1.0, "containment_status": "None",
+            "evidence": ["sha256:abc"]
+        }))
+        .unwrap();
+        incident.timeline.push(serde_json::from_value(json!({
+            "event_id": "e-1", "timestamp": created + Duration::hours(5), "event_type": "SlaBreached",
+            "description": "Time to acknowledge breached", "source": "sla", "severity": "Critical", "data": {}
+        })).unwrap());
+        core.create_incident("acme", incident).await.unwrap();
+        audit.record("alice", "acknowledge_incident", "INC-1", json!({}), Ok::<_, String>(())).unwrap();
+
+        let schedule: ComplianceSchedule =
+            serde_json::from_value(json!({ "schedule_id": "pci-monthly", "framework": "PCI_DSS", "cadence": "monthly" })).unwrap();
+        core.schedule_compliance_report("acme", schedule).await.unwrap();
+
+        let generated = core.run_due_compliance_reports(now, &audit).await;
+        assert_eq!(generated.len(), 1);
+        assert!(core.run_due_compliance_reports(now + Duration::days(1), &audit).await.is_empty());
+
+        let pack = core.get_compliance_report("acme", &generated[0].report_id).await.unwrap();
+        assert_eq!((pack.period_start, pack.period_end), ReportCadence::Monthly.last_full_period(now));
+        assert_eq!(pack.schedule_id.as_deref(), Some("pci-monthly"));
+
+        let pack = core.generate_compliance_report("acme", ComplianceFramework::PCI_DSS, created, Utc::now() + Duration::minutes(1), "auditor", None, &audit).await.unwrap();
+        let incident_response = pack.controls.iter().find(|c| c.control_id == "12.10").unwrap();
+        assert_eq!(incident_response.status, ControlStatus::Exceptions);
+        assert_eq!(incident_response.exceptions, ["INC-1: SLA breached: Time to acknowledge breached"]);
+        assert!(pack.evidence.iter().any(|item| item.evidence_id == "custody:INC-1:sha256:abc" && item.exception.is_none()));
+        assert!(core.list_compliance_reports("globex", None).await.is_empty());
+        assert!(core.compliance_archive().verify().valid);
+    }
+}
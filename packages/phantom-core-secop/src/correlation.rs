@@ -0,0 +1,457 @@
+//! Alert-to-incident correlation
+//!
+//! Groups related alerts into clusters using configurable correlation rules.
+//! A rule links two alerts raised within its time window when every one of
+//! its criteria holds (shared indicator, shared asset, same detection rule,
+//! same source); linked alerts are merged transitively into clusters. Each
+//! cluster is then matched against open incidents by overlapping alerts,
+//! indicators and assets: a match attaches the cluster to that incident,
+//! otherwise a new incident is proposed for analyst confirmation.
+
+use crate::{SecurityAlert, SecurityIncident, ThreatIndicator};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CorrelationCriterion {
+    SharedIndicator,
+    SharedAsset,
+    SameRule,
+    SameSource,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrelationRule {
+    pub rule_id: String,
+    pub name: String,
+    pub enabled: bool,
+    /// All criteria must hold for two alerts to be linked
+    pub criteria: Vec<CorrelationCriterion>,
+    /// Maximum distance between the alerts' creation times
+    pub time_window_minutes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrelationConfig {
+    pub rules: Vec<CorrelationRule>,
+    /// Clusters smaller than this are not reported
+    pub min_cluster_size: usize,
+    /// Only alerts created within this many hours are correlated
+    pub lookback_hours: i64,
+    pub include_closed_alerts: bool,
+    /// Overlap score (shared alerts, indicators and assets) needed to attach
+    /// a cluster to an existing incident
+    pub incident_match_threshold: usize,
+    /// Attach matching clusters to their incident instead of only reporting
+    pub auto_attach: bool,
+}
+
+impl Default for CorrelationConfig {
+    fn default() -> Self {
+        Self {
+            rules: vec![
+                CorrelationRule {
+                    rule_id: "shared-indicator".to_string(),
+                    name: "Shared indicator".to_string(),
+                    enabled: true,
+                    criteria: vec![CorrelationCriterion::SharedIndicator],
+                    time_window_minutes: 24 * 60,
+                },
+                CorrelationRule {
+                    rule_id: "asset-rule-burst".to_string(),
+                    name: "Same detection on one asset".to_string(),
+                    enabled: true,
+                    criteria: vec![CorrelationCriterion::SharedAsset, CorrelationCriterion::SameRule],
+                    time_window_minutes: 60,
+                },
+                CorrelationRule {
+                    rule_id: "asset-activity".to_string(),
+                    name: "Activity on one asset".to_string(),
+                    enabled: true,
+                    criteria: vec![CorrelationCriterion::SharedAsset],
+                    time_window_minutes: 15,
+                },
+            ],
+            min_cluster_size: 2,
+            lookback_hours: 72,
+            include_closed_alerts: false,
+            incident_match_threshold: 1,
+            auto_attach: true,
+        }
+    }
+}
+
+impl CorrelationConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for rule in &self.rules {
+            if rule.criteria.is_empty() {
+                return Err(format!("Correlation rule {} has no criteria", rule.rule_id));
+            }
+            if rule.time_window_minutes <= 0 {
+                return Err(format!("Correlation rule {} needs a positive time window", rule.rule_id));
+            }
+        }
+        if self.min_cluster_size == 0 {
+            return Err("Minimum cluster size must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Incident the correlator would create for an unmatched cluster
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentProposal {
+    pub title: String,
+    pub description: String,
+    pub severity: String,
+    pub affected_systems: Vec<String>,
+    pub indicators: Vec<ThreatIndicator>,
+    pub related_alerts: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ClusterAction {
+    AttachToIncident { incident_id: String, overlap_score: usize, attached: bool },
+    ProposeIncident { proposal: IncidentProposal },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrelationCluster {
+    pub cluster_id: String,
+    pub alert_ids: Vec<String>,
+    /// Correlation rules that produced at least one link in the cluster
+    pub matched_rules: Vec<String>,
+    pub shared_indicators: Vec<String>,
+    pub shared_assets: Vec<String>,
+    pub detection_rules: Vec<String>,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub action: ClusterAction,
+}
+
+fn indicator_keys(alert: &SecurityAlert) -> BTreeSet<String> {
+    alert
+        .indicators
+        .iter()
+        .map(|i| format!("{}:{}", i.indicator_type.to_lowercase(), i.value.trim().to_lowercase()))
+        .collect()
+}
+
+fn asset_keys(alert: &SecurityAlert) -> BTreeSet<String> {
+    alert.affected_assets.iter().map(|a| a.to_lowercase()).collect()
+}
+
+fn criterion_holds(criterion: CorrelationCriterion, a: &SecurityAlert, b: &SecurityAlert) -> bool {
+    match criterion {
+        CorrelationCriterion::SharedIndicator => !indicator_keys(a).is_disjoint(&indicator_keys(b)),
+        CorrelationCriterion::SharedAsset => !asset_keys(a).is_disjoint(&asset_keys(b)),
+        CorrelationCriterion::SameRule => !a.rule_id.is_empty() && a.rule_id == b.rule_id,
+        CorrelationCriterion::SameSource => a.source.eq_ignore_ascii_case(&b.source),
+    }
+}
+
+fn find(parent: &mut [usize], index: usize) -> usize {
+    let mut root = index;
+    while parent[root] != root {
+        root = parent[root];
+    }
+    let mut current = index;
+    while parent[current] != root {
+        let next = parent[current];
+        parent[current] = root;
+        current = next;
+    }
+    root
+}
+
+fn priority_rank(priority: &str) -> u8 {
+    match priority.to_ascii_lowercase().as_str() {
+        "critical" => 4,
+        "high" => 3,
+        "medium" => 2,
+        "low" => 1,
+        _ => 0,
+    }
+}
+
+/// Values that appear on more than one alert of the cluster
+fn shared_values(alerts: &[&SecurityAlert], keys: fn(&SecurityAlert) -> BTreeSet<String>) -> Vec<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for alert in alerts {
+        for key in keys(alert) {
+            *counts.entry(key).or_insert(0) += 1;
+        }
+    }
+    let mut shared: Vec<String> = counts.into_iter().filter(|(_, n)| *n > 1).map(|(k, _)| k).collect();
+    shared.sort();
+    shared
+}
+
+/// Overlap between a cluster and an incident: shared alerts, indicators and assets
+fn incident_overlap(alerts: &[&SecurityAlert], incident: &SecurityIncident) -> usize {
+    let incident_indicators: BTreeSet<String> = incident
+        .indicators
+        .iter()
+        .map(|i| format!("{}:{}", i.indicator_type.to_lowercase(), i.value.trim().to_lowercase()))
+        .collect();
+    let incident_assets: BTreeSet<String> = incident.affected_systems.iter().map(|a| a.to_lowercase()).collect();
+
+    let linked_alerts = alerts
+        .iter()
+        .filter(|a| {
+            incident.related_alerts.contains(&a.alert_id) || a.correlation_id.as_deref() == Some(incident.incident_id.as_str())
+        })
+        .count();
+    let cluster_indicators: BTreeSet<String> = alerts.iter().flat_map(|a| indicator_keys(a)).collect();
+    let cluster_assets: BTreeSet<String> = alerts.iter().flat_map(|a| asset_keys(a)).collect();
+
+    linked_alerts
+        + cluster_indicators.intersection(&incident_indicators).count()
+        + cluster_assets.intersection(&incident_assets).count()
+}
+
+fn propose_incident(alerts: &[&SecurityAlert], matched_rules: &[String]) -> IncidentProposal {
+    let lead = alerts
+        .iter()
+        .max_by_key(|a| (priority_rank(&a.priority), std::cmp::Reverse(a.created_at)))
+        .expect("clusters are never empty");
+
+    let mut affected_systems: Vec<String> = Vec::new();
+    let mut indicators: Vec<ThreatIndicator> = Vec::new();
+    for alert in alerts {
+        for asset in &alert.affected_assets {
+            if !affected_systems.contains(asset) {
+                affected_systems.push(asset.clone());
+            }
+        }
+        for indicator in &alert.indicators {
+            if !indicators
+                .iter()
+                .any(|i| i.indicator_type.eq_ignore_ascii_case(&indicator.indicator_type) && i.value.eq_ignore_ascii_case(&indicator.value))
+            {
+                indicators.push(indicator.clone());
+            }
+        }
+    }
+
+    IncidentProposal {
+        title: format!("{} (+{} correlated alerts)", lead.title, alerts.len() - 1),
+        description: format!("Correlated {} alerts via {}", alerts.len(), matched_rules.join(", ")),
+        severity: lead.priority.clone(),
+        affected_systems,
+        indicators,
+        related_alerts: alerts.iter().map(|a| a.alert_id.clone()).collect(),
+    }
+}
+
+/// Cluster related alerts and decide, per cluster, whether it belongs to an
+/// open incident or should become a new one. Nothing is mutated here.
+pub fn correlate(
+    alerts: &[SecurityAlert],
+    incidents: &[SecurityIncident],
+    config: &CorrelationConfig,
+    now: DateTime<Utc>,
+) -> Vec<CorrelationCluster> {
+    let cutoff = now - Duration::hours(config.lookback_hours);
+    let mut candidates: Vec<&SecurityAlert> = alerts
+        .iter()
+        .filter(|a| a.created_at >= cutoff)
+        .filter(|a| config.include_closed_alerts || !a.status.eq_ignore_ascii_case("closed"))
+        .collect();
+    candidates.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.alert_id.cmp(&b.alert_id)));
+
+    let rules: Vec<&CorrelationRule> = config.rules.iter().filter(|r| r.enabled && !r.criteria.is_empty()).collect();
+    let mut parent: Vec<usize> = (0..candidates.len()).collect();
+    let mut link_rules: Vec<(usize, String)> = Vec::new();
+
+    for i in 0..candidates.len() {
+        for j in (i + 1)..candidates.len() {
+            let gap = candidates[j].created_at - candidates[i].created_at;
+            for rule in &rules {
+                if gap > Duration::minutes(rule.time_window_minutes) {
+                    continue;
+                }
+                if rule.criteria.iter().all(|c| criterion_holds(*c, candidates[i], candidates[j])) {
+                    let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+                    if a != b {
+                        parent[b] = a;
+                    }
+                    link_rules.push((i, rule.rule_id.clone()));
+                }
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for index in 0..candidates.len() {
+        let root = find(&mut parent, index);
+        groups.entry(root).or_default().push(index);
+    }
+    let mut rules_by_root: HashMap<usize, BTreeSet<String>> = HashMap::new();
+    for (index, rule_id) in link_rules {
+        let root = find(&mut parent, index);
+        rules_by_root.entry(root).or_default().insert(rule_id);
+    }
+
+    let open_incidents: Vec<&SecurityIncident> = incidents
+        .iter()
+        .filter(|i| !i.status.eq_ignore_ascii_case("closed") && i.merged_into.is_none())
+        .collect();
+
+    let mut clusters: Vec<CorrelationCluster> = groups
+        .into_iter()
+        .filter(|(_, members)| members.len() >= config.min_cluster_size)
+        .map(|(root, members)| {
+            let members: Vec<&SecurityAlert> = members.into_iter().map(|i| candidates[i]).collect();
+            let matched_rules: Vec<String> = rules_by_root.remove(&root).unwrap_or_default().into_iter().collect();
+            let detection_rules: BTreeSet<String> = members.iter().filter(|a| !a.rule_id.is_empty()).map(|a| a.rule_id.clone()).collect();
+
+            let best_incident = open_incidents
+                .iter()
+                .map(|incident| (incident_overlap(&members, incident), *incident))
+                .filter(|(score, _)| *score > 0 && *score >= config.incident_match_threshold)
+                .max_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.updated_at.cmp(&b.1.updated_at)));
+            let action = match best_incident {
+                Some((overlap_score, incident)) => ClusterAction::AttachToIncident {
+                    incident_id: incident.incident_id.clone(),
+                    overlap_score,
+                    attached: false,
+                },
+                None => ClusterAction::ProposeIncident {
+                    proposal: propose_incident(&members, &matched_rules),
+                },
+            };
+
+            CorrelationCluster {
+                cluster_id: Uuid::new_v4().to_string(),
+                alert_ids: members.iter().map(|a| a.alert_id.clone()).collect(),
+                matched_rules,
+                shared_indicators: shared_values(&members, indicator_keys),
+                shared_assets: shared_values(&members, asset_keys),
+                detection_rules: detection_rules.into_iter().collect(),
+                first_seen: members.first().map(|a| a.created_at).unwrap_or(now),
+                last_seen: members.last().map(|a| a.created_at).unwrap_or(now),
+                action,
+            }
+        })
+        .collect();
+
+    clusters.sort_by_key(|c| std::cmp::Reverse(c.last_seen));
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(id: &str, minutes_ago: i64, rule_id: &str, asset: &str, ip: Option<&str>) -> SecurityAlert {
+        let created_at = Utc::now() - Duration::minutes(minutes_ago);
+        SecurityAlert {
+            alert_id: id.to_string(),
+            title: format!("Alert {}", id),
+            description: String::new(),
+            priority: "High".to_string(),
+            status: "Open".to_string(),
+            source: "EDR".to_string(),
+            created_at,
+            updated_at: created_at,
+            rule_id: rule_id.to_string(),
+            rule_name: rule_id.to_string(),
+            affected_assets: vec![asset.to_string()],
+            indicators: ip
+                .map(|ip| ThreatIndicator {
+                    indicator_id: format!("ioc-{}", id),
+                    indicator_type: "IP".to_string(),
+                    value: ip.to_string(),
+                    confidence: 0.8,
+                    severity: "High".to_string(),
+                    source: "EDR".to_string(),
+                    first_seen: created_at,
+                    last_seen: created_at,
+                    context: String::new(),
+                })
+                .into_iter()
+                .collect(),
+            raw_data: String::new(),
+            false_positive_probability: 0.1,
+            correlation_id: None,
+            tenant_id: "default".to_string(),
+            external_id: None,
+        }
+    }
+
+    #[test]
+    fn test_clusters_by_shared_indicator_and_asset_window() {
+        let alerts = vec![
+            alert("a1", 300, "r1", "host-1", Some("203.0.113.7")),
+            alert("a2", 10, "r2", "host-2", Some("203.0.113.7")),
+            alert("b1", 50, "r3", "host-9", None),
+            alert("b2", 45, "r4", "host-9", None),
+            alert("c1", 200, "r5", "host-5", None),
+            alert("c2", 20, "r5", "host-5", None),
+        ];
+        let clusters = correlate(&alerts, &[], &CorrelationConfig::default(), Utc::now());
+        assert_eq!(clusters.len(), 2);
+
+        let by_indicator = clusters.iter().find(|c| c.alert_ids.contains(&"a1".to_string())).unwrap();
+        assert_eq!(by_indicator.alert_ids, vec!["a1", "a2"]);
+        assert_eq!(by_indicator.matched_rules, vec!["shared-indicator"]);
+        assert_eq!(by_indicator.shared_indicators, vec!["ip:203.0.113.7"]);
+        assert!(matches!(by_indicator.action, ClusterAction::ProposeIncident { .. }));
+
+        let by_asset = clusters.iter().find(|c| c.alert_ids.contains(&"b1".to_string())).unwrap();
+        assert_eq!(by_asset.matched_rules, vec!["asset-activity"]);
+        assert_eq!(by_asset.shared_assets, vec!["host-9"]);
+    }
+
+    #[test]
+    fn test_cluster_matching_open_incident_is_attached() {
+        let alerts = vec![
+            alert("a1", 30, "r1", "host-1", Some("198.51.100.4")),
+            alert("a2", 20, "r1", "host-1", Some("198.51.100.4")),
+        ];
+        let now = Utc::now();
+        let incident = SecurityIncident {
+            incident_id: "INC-7".to_string(),
+            title: "C2 beaconing".to_string(),
+            description: String::new(),
+            severity: "High".to_string(),
+            status: "Investigating".to_string(),
+            category: "Malware".to_string(),
+            priority: 2,
+            created_at: now,
+            updated_at: now,
+            assigned_to: "soc".to_string(),
+            reporter: "edr".to_string(),
+            affected_systems: vec!["HOST-1".to_string()],
+            indicators: Vec::new(),
+            timeline: Vec::new(),
+            mitigation_actions: Vec::new(),
+            estimated_impact: 0.0,
+            containment_status: "None".to_string(),
+            evidence: Vec::new(),
+            related_alerts: Vec::new(),
+            tags: Vec::new(),
+            merged_into: None,
+            sla: None,
+            tenant_id: "default".to_string(),
+            external_id: None,
+            ticket: None,
+        };
+
+        let clusters = correlate(&alerts, &[incident], &CorrelationConfig::default(), now);
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].matched_rules.len(), 3);
+        match &clusters[0].action {
+            ClusterAction::AttachToIncident { incident_id, overlap_score, .. } => {
+                assert_eq!(incident_id, "INC-7");
+                assert_eq!(*overlap_score, 1);
+            }
+            other => panic!("expected attachment, got {:?}", other),
+        }
+    }
+}
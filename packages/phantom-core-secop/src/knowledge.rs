@@ -0,0 +1,424 @@
+//! Incident knowledge extraction
+//!
+//! When an incident is closed its confirmed indicators, observed ATT&CK
+//! techniques and timeline-confirmed detection gaps are harvested into a
+//! pending review. Nothing reaches the shared stores (IOC repository, ATT&CK
+//! coverage model, detection gap report) until an analyst approves the
+//! individual artifacts, so noisy or mistaken findings stay out.
+
+use crate::{IncidentEvent, SecurityIncident, ThreatIndicator};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::OnceLock;
+use uuid::Uuid;
+
+/// Event data keys that carry an ATT&CK technique id
+const TECHNIQUE_KEYS: &[&str] = &["mitre_technique", "technique_id", "technique"];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArtifactStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HarvestStatus {
+    PendingReview,
+    Reviewed,
+}
+
+/// Where a harvested artifact came from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentProvenance {
+    pub incident_id: String,
+    pub incident_title: String,
+    pub closed_at: DateTime<Utc>,
+    /// Timeline events supporting the artifact
+    pub event_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectionGap {
+    pub gap_id: String,
+    pub technique_id: Option<String>,
+    pub description: String,
+    pub affected_asset: Option<String>,
+    pub observed_at: DateTime<Utc>,
+    pub incident_id: String,
+    pub source_event_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value")]
+pub enum ArtifactPayload {
+    Indicator(ThreatIndicator),
+    Technique { technique_id: String, detected: bool },
+    DetectionGap(DetectionGap),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeArtifact {
+    pub artifact_id: String,
+    pub status: ArtifactStatus,
+    pub payload: ArtifactPayload,
+    pub provenance: IncidentProvenance,
+}
+
+/// Artifacts harvested from one closed incident
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeHarvest {
+    pub harvest_id: String,
+    pub incident_id: String,
+    #[serde(default = "crate::tenancy::default_tenant")]
+    pub tenant_id: String,
+    pub created_at: DateTime<Utc>,
+    pub status: HarvestStatus,
+    pub artifacts: Vec<KnowledgeArtifact>,
+    pub reviewed_by: Option<String>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+}
+
+/// Analyst decision on a harvest; artifacts not listed stay pending
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HarvestReview {
+    pub reviewer: String,
+    #[serde(default)]
+    pub approved: Vec<String>,
+    #[serde(default)]
+    pub rejected: Vec<String>,
+}
+
+/// ATT&CK coverage entry built from approved incident techniques
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TechniqueCoverage {
+    pub technique_id: String,
+    pub observed_count: u32,
+    pub detected_count: u32,
+    pub incidents: Vec<String>,
+    pub last_observed: DateTime<Utc>,
+}
+
+/// Approved knowledge to push into shared stores
+#[derive(Debug, Clone, Default)]
+pub struct ApprovedKnowledge {
+    pub indicators: Vec<ThreatIndicator>,
+    pub techniques: usize,
+    pub gaps: usize,
+}
+
+fn technique_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\bT\d{4}(?:\.\d{3})?\b").expect("valid technique regex"))
+}
+
+fn event_techniques(event: &IncidentEvent) -> Vec<String> {
+    let mut techniques: Vec<String> = TECHNIQUE_KEYS
+        .iter()
+        .filter_map(|key| event.data.get(*key))
+        .flat_map(|value| technique_regex().find_iter(value).map(|m| m.as_str().to_string()).collect::<Vec<_>>())
+        .collect();
+    techniques.extend(technique_regex().find_iter(&event.description).map(|m| m.as_str().to_string()));
+    techniques.sort();
+    techniques.dedup();
+    techniques
+}
+
+/// An event the timeline confirms happened but no detection fired for
+fn is_detection_gap(event: &IncidentEvent) -> bool {
+    event.event_type.eq_ignore_ascii_case("detection_gap")
+        || event.data.get("detected").is_some_and(|v| v.eq_ignore_ascii_case("false"))
+}
+
+/// Harvest candidate artifacts from a closed incident
+pub fn harvest_incident(incident: &SecurityIncident, closed_at: DateTime<Utc>) -> KnowledgeHarvest {
+    let provenance = |event_ids: Vec<String>| IncidentProvenance {
+        incident_id: incident.incident_id.clone(),
+        incident_title: incident.title.clone(),
+        closed_at,
+        event_ids,
+    };
+    let artifact = |payload: ArtifactPayload, event_ids: Vec<String>| KnowledgeArtifact {
+        artifact_id: Uuid::new_v4().to_string(),
+        status: ArtifactStatus::Pending,
+        payload,
+        provenance: provenance(event_ids),
+    };
+
+    let mut artifacts = Vec::new();
+
+    let mut seen_indicators = HashSet::new();
+    for indicator in &incident.indicators {
+        if !seen_indicators.insert(indicator.value.to_lowercase()) {
+            continue;
+        }
+        let mut indicator = indicator.clone();
+        indicator.source = format!("incident:{}", incident.incident_id);
+        indicator.context = if indicator.context.is_empty() {
+            format!("Confirmed in incident '{}'", incident.title)
+        } else {
+            format!("{} (confirmed in incident '{}')", indicator.context, incident.title)
+        };
+        artifacts.push(artifact(ArtifactPayload::Indicator(indicator), vec![]));
+    }
+
+    // technique -> (supporting events, detected by at least one event)
+    let mut techniques: BTreeMap<String, (Vec<String>, bool)> = BTreeMap::new();
+    for event in &incident.timeline {
+        let gap = is_detection_gap(event);
+        let event_techniques = event_techniques(event);
+        for technique_id in &event_techniques {
+            let entry = techniques.entry(technique_id.clone()).or_default();
+            entry.0.push(event.event_id.clone());
+            entry.1 |= !gap;
+        }
+
+        if gap {
+            let detection_gap = DetectionGap {
+                gap_id: Uuid::new_v4().to_string(),
+                technique_id: event_techniques.first().cloned(),
+                description: event.description.clone(),
+                affected_asset: event.data.get("asset").or_else(|| event.data.get("host")).cloned(),
+                observed_at: event.timestamp,
+                incident_id: incident.incident_id.clone(),
+                source_event_id: event.event_id.clone(),
+            };
+            artifacts.push(artifact(ArtifactPayload::DetectionGap(detection_gap), vec![event.event_id.clone()]));
+        }
+    }
+    for (technique_id, (event_ids, detected)) in techniques {
+        artifacts.push(artifact(ArtifactPayload::Technique { technique_id, detected }, event_ids));
+    }
+
+    KnowledgeHarvest {
+        harvest_id: Uuid::new_v4().to_string(),
+        incident_id: incident.incident_id.clone(),
+        tenant_id: incident.tenant_id.clone(),
+        created_at: closed_at,
+        status: HarvestStatus::PendingReview,
+        artifacts,
+        reviewed_by: None,
+        reviewed_at: None,
+    }
+}
+
+/// Pending harvests plus the shared stores fed by approved artifacts
+#[derive(Debug, Default)]
+pub struct KnowledgeBase {
+    harvests: HashMap<String, KnowledgeHarvest>,
+    coverage: HashMap<String, TechniqueCoverage>,
+    gaps: Vec<DetectionGap>,
+}
+
+impl KnowledgeBase {
+    pub fn add_harvest(&mut self, harvest: KnowledgeHarvest) {
+        self.harvests.insert(harvest.harvest_id.clone(), harvest);
+    }
+
+    pub fn get_harvest(&self, harvest_id: &str) -> Option<&KnowledgeHarvest> {
+        self.harvests.get(harvest_id)
+    }
+
+    pub fn pending_harvests(&self, tenant_id: &str) -> Vec<KnowledgeHarvest> {
+        let mut pending: Vec<KnowledgeHarvest> = self
+            .harvests
+            .values()
+            .filter(|h| h.tenant_id == tenant_id && h.status == HarvestStatus::PendingReview)
+            .cloned()
+            .collect();
+        pending.sort_by_key(|h| h.created_at);
+        pending
+    }
+
+    /// Drop a tenant's harvests. Artifacts already committed to the shared
+    /// coverage model and gap report are kept.
+    pub fn remove_harvests(&mut self, tenant_id: &str) -> usize {
+        let before = self.harvests.len();
+        self.harvests.retain(|_, h| h.tenant_id != tenant_id);
+        before - self.harvests.len()
+    }
+
+    /// Apply an analyst review, committing approved artifacts to the
+    /// coverage model and gap report. Approved indicators are returned for
+    /// the caller to load into the IOC repository.
+    pub fn apply_review(&mut self, harvest_id: &str, review: &HarvestReview) -> Result<(KnowledgeHarvest, ApprovedKnowledge), String> {
+        if review.reviewer.trim().is_empty() {
+            return Err("Reviewer is required".to_string());
+        }
+        let harvest = self.harvests.get_mut(harvest_id).ok_or_else(|| format!("Harvest {} not found", harvest_id))?;
+
+        let known: HashSet<&str> = harvest.artifacts.iter().map(|a| a.artifact_id.as_str()).collect();
+        if let Some(unknown) = review.approved.iter().chain(&review.rejected).find(|id| !known.contains(id.as_str())) {
+            return Err(format!("Artifact {} is not part of harvest {}", unknown, harvest_id));
+        }
+        if let Some(conflict) = review.approved.iter().find(|id| review.rejected.contains(id)) {
+            return Err(format!("Artifact {} cannot be both approved and rejected", conflict));
+        }
+
+        let mut approved = ApprovedKnowledge::default();
+        for artifact in harvest.artifacts.iter_mut().filter(|a| a.status == ArtifactStatus::Pending) {
+            if review.rejected.contains(&artifact.artifact_id) {
+                artifact.status = ArtifactStatus::Rejected;
+                continue;
+            }
+            if !review.approved.contains(&artifact.artifact_id) {
+                continue;
+            }
+            artifact.status = ArtifactStatus::Approved;
+
+            match &artifact.payload {
+                ArtifactPayload::Indicator(indicator) => approved.indicators.push(indicator.clone()),
+                ArtifactPayload::Technique { technique_id, detected } => {
+                    let entry = self.coverage.entry(technique_id.clone()).or_insert_with(|| TechniqueCoverage {
+                        technique_id: technique_id.clone(),
+                        observed_count: 0,
+                        detected_count: 0,
+                        incidents: Vec::new(),
+                        last_observed: artifact.provenance.closed_at,
+                    });
+                    entry.observed_count += 1;
+                    if *detected {
+                        entry.detected_count += 1;
+                    }
+                    if !entry.incidents.contains(&artifact.provenance.incident_id) {
+                        entry.incidents.push(artifact.provenance.incident_id.clone());
+                    }
+                    entry.last_observed = entry.last_observed.max(artifact.provenance.closed_at);
+                    approved.techniques += 1;
+                }
+                ArtifactPayload::DetectionGap(gap) => {
+                    self.gaps.push(gap.clone());
+                    approved.gaps += 1;
+                }
+            }
+        }
+
+        if harvest.artifacts.iter().all(|a| a.status != ArtifactStatus::Pending) {
+            harvest.status = HarvestStatus::Reviewed;
+        }
+        harvest.reviewed_by = Some(review.reviewer.clone());
+        harvest.reviewed_at = Some(Utc::now());
+
+        Ok((harvest.clone(), approved))
+    }
+
+    pub fn technique_coverage(&self) -> Vec<TechniqueCoverage> {
+        let mut coverage: Vec<TechniqueCoverage> = self.coverage.values().cloned().collect();
+        coverage.sort_by(|a, b| a.technique_id.cmp(&b.technique_id));
+        coverage
+    }
+
+    pub fn detection_gaps(&self) -> Vec<DetectionGap> {
+        self.gaps.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn incident() -> SecurityIncident {
+        let event = |description: &str, data: &[(&str, &str)]| IncidentEvent {
+            event_id: Uuid::new_v4().to_string(),
+            timestamp: Utc::now(),
+            event_type: "Observation".to_string(),
+            description: description.to_string(),
+            source: "analyst".to_string(),
+            severity: "High".to_string(),
+            data: data.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+        };
+        SecurityIncident {
+            incident_id: "INC-1".to_string(),
+            title: "Credential theft".to_string(),
+            description: String::new(),
+            severity: "High".to_string(),
+            status: "Investigating".to_string(),
+            category: "Intrusion".to_string(),
+            priority: 1,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            assigned_to: "SOC Team".to_string(),
+            reporter: "System".to_string(),
+            affected_systems: vec!["host-1".to_string()],
+            indicators: vec![ThreatIndicator {
+                indicator_id: "ioc-1".to_string(),
+                indicator_type: "IP".to_string(),
+                value: "203.0.113.7".to_string(),
+                confidence: 0.9,
+                severity: "High".to_string(),
+                source: "EDR".to_string(),
+                first_seen: Utc::now(),
+                last_seen: Utc::now(),
+                context: String::new(),
+            }],
+            timeline: vec![
+                event("Phishing payload executed (T1204.002)", &[]),
+                event("LSASS memory read", &[("mitre_technique", "T1003.001"), ("detected", "false"), ("host", "host-1")]),
+            ],
+            mitigation_actions: vec![],
+            estimated_impact: 0.5,
+            containment_status: "Contained".to_string(),
+            evidence: vec![],
+            related_alerts: vec![],
+            tags: vec![],
+            merged_into: None,
+            sla: None,
+            tenant_id: "default".to_string(),
+            external_id: None,
+            ticket: None,
+        }
+    }
+
+    #[test]
+    fn test_harvest_extracts_iocs_techniques_and_gaps() {
+        let harvest = harvest_incident(&incident(), Utc::now());
+        assert_eq!(harvest.status, HarvestStatus::PendingReview);
+
+        let indicator = harvest.artifacts.iter().find_map(|a| match &a.payload {
+            ArtifactPayload::Indicator(i) => Some(i),
+            _ => None,
+        });
+        assert_eq!(indicator.unwrap().source, "incident:INC-1");
+
+        let techniques: Vec<(&str, bool)> = harvest
+            .artifacts
+            .iter()
+            .filter_map(|a| match &a.payload {
+                ArtifactPayload::Technique { technique_id, detected } => Some((technique_id.as_str(), *detected)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(techniques, vec![("T1003.001", false), ("T1204.002", true)]);
+
+        let gap = harvest.artifacts.iter().find_map(|a| match &a.payload {
+            ArtifactPayload::DetectionGap(g) => Some(g),
+            _ => None,
+        });
+        assert_eq!(gap.unwrap().affected_asset.as_deref(), Some("host-1"));
+    }
+
+    #[test]
+    fn test_only_approved_artifacts_reach_shared_stores() {
+        let mut kb = KnowledgeBase::default();
+        let harvest = harvest_incident(&incident(), Utc::now());
+        let harvest_id = harvest.harvest_id.clone();
+        let ids: Vec<String> = harvest.artifacts.iter().map(|a| a.artifact_id.clone()).collect();
+        kb.add_harvest(harvest);
+
+        assert!(kb.technique_coverage().is_empty());
+        assert!(kb.detection_gaps().is_empty());
+
+        // Approve the indicator and reject everything else
+        let review = HarvestReview { reviewer: "alice".to_string(), approved: vec![ids[0].clone()], rejected: ids[1..].to_vec() };
+        let (reviewed, approved) = kb.apply_review(&harvest_id, &review).unwrap();
+
+        assert_eq!(reviewed.status, HarvestStatus::Reviewed);
+        assert_eq!(approved.indicators.len(), 1);
+        assert!(kb.technique_coverage().is_empty());
+        assert!(kb.detection_gaps().is_empty());
+        assert!(kb.pending_harvests("default").is_empty());
+        assert!(kb.apply_review(&harvest_id, &HarvestReview { reviewer: String::new(), approved: vec![], rejected: vec![] }).is_err());
+    }
+}
@@ -13,6 +13,45 @@ use napi_derive::napi;
 #[cfg(feature = "napi")]
 use napi::{bindgen_prelude::*, Result as NapiResult};
 
+pub mod access;
+pub mod audit;
+pub mod calendar;
+#[cfg(feature = "phantom-enterprise-standards")]
+pub mod compliance_reports;
+pub mod correlation;
+pub mod error;
+pub mod import;
+pub mod intel_feeds;
+pub mod ioc_matching;
+pub mod knowledge;
+pub mod live_feed;
+pub mod merge;
+pub mod metrics_history;
+pub mod objects;
+pub mod playbook_actions;
+#[cfg(feature = "phantom-enterprise-standards")]
+pub mod playbook_sync;
+pub mod playbooks;
+pub mod prometheus;
+pub mod readiness;
+#[cfg(feature = "phantom-enterprise-standards")]
+pub mod references;
+pub mod search;
+pub mod secop_core;
+pub mod secrets;
+pub mod sla;
+pub mod syslog;
+pub mod tasks;
+pub mod tenancy;
+pub mod ticketing;
+pub mod timeline;
+pub mod triage;
+
+// List pagination is shared with the other cores
+pub use phantom_enterprise_standards::pagination;
+
+pub use secop_core::SecOpCore;
+
 /// Core Security Operations data structures
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SecurityIncident {
@@ -33,6 +72,27 @@ pub struct SecurityIncident {
     pub mitigation_actions: Vec<String>,
     pub estimated_impact: f64,
     pub containment_status: String,
+    /// Evidence references (file hashes, artifact or case ids)
+    #[serde(default)]
+    pub evidence: Vec<String>,
+    #[serde(default)]
+    pub related_alerts: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Primary incident this one was merged into
+    #[serde(default)]
+    pub merged_into: Option<String>,
+    /// SLA timers, computed when the incident is read
+    #[serde(default)]
+    pub sla: Option<sla::IncidentSlaState>,
+    #[serde(default = "tenancy::default_tenant")]
+    pub tenant_id: String,
+    /// Id in the system the incident was imported from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+    /// Linked Jira or ServiceNow ticket
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ticket: Option<ticketing::TicketLink>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -76,6 +136,11 @@ pub struct SecurityAlert {
     pub raw_data: String,
     pub false_positive_probability: f64,
     pub correlation_id: Option<String>,
+    #[serde(default = "tenancy::default_tenant")]
+    pub tenant_id: String,
+    /// Id in the system the alert was imported from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -226,7 +291,7 @@ pub fn create_security_incident(incident_data: String, context: String) -> NapiR
     let input: serde_json::Value = serde_json::from_str(&incident_data)
         .map_err(|e| napi::Error::from_reason(format!("Invalid incident data: {}", e)))?;
 
-    let incident_id = Uuid::new_v4().to_string();
+    let incident_id = format!("inc_{}", Uuid::new_v4().simple());
 
     // Create comprehensive security incident
     let incident = SecurityIncident {
@@ -249,7 +314,7 @@ pub fn create_security_incident(incident_data: String, context: String) -> NapiR
             .collect(),
         indicators: vec![
             ThreatIndicator {
-                indicator_id: Uuid::new_v4().to_string(),
+                indicator_id: format!("ioc_{}", Uuid::new_v4().simple()),
                 indicator_type: "IP".to_string(),
                 value: "192.168.1.100".to_string(),
                 confidence: 0.85,
@@ -278,6 +343,14 @@ pub fn create_security_incident(incident_data: String, context: String) -> NapiR
         ],
         estimated_impact: input.get("estimated_impact").and_then(|v| v.as_f64()).unwrap_or(5.0),
         containment_status: "Initial".to_string(),
+        evidence: Vec::new(),
+        related_alerts: Vec::new(),
+        tags: Vec::new(),
+        merged_into: None,
+        sla: None,
+        tenant_id: tenancy::default_tenant(),
+        external_id: None,
+        ticket: None,
     };
 
     let processing_time = start_time.elapsed();
@@ -313,7 +386,7 @@ pub fn process_security_alert(alert_data: String, processing_rules: String) -> N
     let input: serde_json::Value = serde_json::from_str(&alert_data)
         .map_err(|e| napi::Error::from_reason(format!("Invalid alert data: {}", e)))?;
 
-    let alert_id = Uuid::new_v4().to_string();
+    let alert_id = format!("alrt_{}", Uuid::new_v4().simple());
 
     // Advanced alert processing with false positive detection
     let false_positive_probability = calculate_false_positive_probability(&input);
@@ -339,6 +412,8 @@ pub fn process_security_alert(alert_data: String, processing_rules: String) -> N
         raw_data: serde_json::to_string(&input).unwrap_or_default(),
         false_positive_probability,
         correlation_id: generate_correlation_id(&input),
+        tenant_id: tenancy::default_tenant(),
+        external_id: None,
     };
 
     let processing_time = start_time.elapsed();
@@ -816,7 +891,7 @@ fn determine_alert_priority(input: &serde_json::Value) -> String {
 fn extract_threat_indicators(input: &serde_json::Value) -> Vec<ThreatIndicator> {
     vec![
         ThreatIndicator {
-            indicator_id: Uuid::new_v4().to_string(),
+            indicator_id: format!("ioc_{}", Uuid::new_v4().simple()),
             indicator_type: "Hash".to_string(),
             value: "a1b2c3d4e5f6789012345678901234567890".to_string(),
             confidence: 0.92,
@@ -0,0 +1,238 @@
+//! Live Feed
+//!
+//! With `phantom-enterprise-standards` enabled, every alert that enters the
+//! SOC is published to the `alerts` topic of the core's live feed hub once
+//! triage has set its disposition, and every incident change (creation,
+//! acknowledgement, containment, merge, closure) to `incidents`. With
+//! `live-feed`, `start_live_feed` serves the hub to dashboards over WebSocket
+//! and SSE. The host can relay events of other cores through
+//! `publish_feed_event`, so one server can carry hunt matches and sandbox
+//! verdicts as well. Clients only receive events of their own tenant.
+//! Without the feature nothing is published.
+
+use crate::secop_core::SecOpCore;
+use crate::{SecurityAlert, SecurityIncident};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::live_feed::{FeedEvent, FeedHub, FeedTopic};
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "live-feed")]
+use phantom_enterprise_standards::live_feed::{serve_feed, FeedAuthenticator, FeedConfig};
+#[cfg(feature = "live-feed")]
+use std::net::SocketAddr;
+#[cfg(feature = "live-feed")]
+use std::sync::Mutex;
+
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+use crate::secop_core::SecOpCoreNapi;
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+use napi_derive::napi;
+#[cfg(all(feature = "napi", feature = "live-feed"))]
+use crate::tenancy;
+#[cfg(all(feature = "napi", feature = "live-feed"))]
+use phantom_enterprise_standards::live_feed::feed_principal;
+#[cfg(all(feature = "napi", feature = "live-feed"))]
+use serde_json::json;
+
+#[cfg(feature = "phantom-enterprise-standards")]
+const SOURCE: &str = "phantom-secop-core";
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[derive(Default)]
+pub struct LiveFeedState {
+    hub: RwLock<Arc<FeedHub>>,
+    #[cfg(feature = "live-feed")]
+    server: Mutex<Option<tokio::task::JoinHandle<()>>>,
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+impl LiveFeedState {
+    pub fn hub(&self) -> Arc<FeedHub> {
+        self.hub.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Feed event type for an incident timeline event, e.g. `IncidentClosed`
+/// becomes `incident_closed`
+pub fn feed_event_type(timeline_event_type: &str) -> String {
+    let mut event_type = String::with_capacity(timeline_event_type.len() + 4);
+    for (i, c) in timeline_event_type.chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            event_type.push('_');
+        }
+        event_type.push(c.to_ascii_lowercase());
+    }
+    event_type
+}
+
+/// Feed event for a triaged alert
+#[cfg(feature = "phantom-enterprise-standards")]
+pub fn alert_feed_event(alert: &SecurityAlert) -> FeedEvent {
+    let payload = serde_json::json!({
+        "alert_id": alert.alert_id,
+        "title": alert.title,
+        "priority": alert.priority,
+        "status": alert.status,
+        "source": alert.source,
+        "rule_id": alert.rule_id,
+        "rule_name": alert.rule_name,
+        "affected_assets": alert.affected_assets,
+        "correlation_id": alert.correlation_id,
+    });
+    FeedEvent::new(FeedTopic::Alerts, "alert_created", &alert.tenant_id, SOURCE, payload).entity(&alert.alert_id)
+}
+
+/// Feed event for a change to an incident
+#[cfg(feature = "phantom-enterprise-standards")]
+pub fn incident_feed_event(incident: &SecurityIncident, timeline_event_type: &str) -> FeedEvent {
+    let payload = serde_json::json!({
+        "incident_id": incident.incident_id,
+        "title": incident.title,
+        "severity": incident.severity,
+        "status": incident.status,
+        "priority": incident.priority,
+        "assigned_to": incident.assigned_to,
+        "containment_status": incident.containment_status,
+        "merged_into": incident.merged_into,
+        "updated_at": incident.updated_at,
+    });
+    FeedEvent::new(FeedTopic::Incidents, &feed_event_type(timeline_event_type), &incident.tenant_id, SOURCE, payload)
+        .entity(&incident.incident_id)
+}
+
+impl SecOpCore {
+    /// Push a triaged alert to live feed subscribers
+    pub(crate) fn publish_alert(&self, alert: &SecurityAlert) {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        self.live_feed.hub().publish(alert_feed_event(alert));
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        let _ = alert;
+    }
+
+    /// Push an incident change, named after its timeline event type, to
+    /// live feed subscribers
+    pub(crate) fn publish_incident(&self, incident: &SecurityIncident, timeline_event_type: &str) {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        self.live_feed.hub().publish(incident_feed_event(incident, timeline_event_type));
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        let _ = (incident, timeline_event_type);
+    }
+
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn live_feed_hub(&self) -> Arc<FeedHub> {
+        self.live_feed.hub()
+    }
+
+    /// Serve the live feed on `bind`, or stop it with `None`. A running
+    /// server is replaced and its clients are disconnected.
+    #[cfg(feature = "live-feed")]
+    pub async fn start_live_feed(&self, bind: Option<&str>, config: FeedConfig, authenticate: FeedAuthenticator) -> Result<Option<SocketAddr>, String> {
+        if let Some(running) = self.live_feed.server.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            running.abort();
+        }
+        let hub = Arc::new(FeedHub::new(config));
+        let previous = std::mem::replace(&mut *self.live_feed.hub.write().unwrap_or_else(|e| e.into_inner()), hub.clone());
+        previous.close_all();
+
+        let Some(bind) = bind else { return Ok(None) };
+        let (address, handle) = serve_feed(bind, hub, authenticate).await?;
+        *self.live_feed.server.lock().unwrap_or_else(|e| e.into_inner()) = Some(handle);
+        Ok(Some(address))
+    }
+}
+
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+#[napi]
+impl SecOpCoreNapi {
+    /// Relay an event (JSON `FeedEvent`) from another core to this core's
+    /// live feed subscribers; returns how many clients accepted it
+    #[napi]
+    pub fn publish_feed_event(&self, event_json: String, auth_token: Option<String>) -> napi::Result<u32> {
+        self.authorize_platform(auth_token, "live-feed")?;
+        let event: FeedEvent = serde_json::from_str(&event_json)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid feed event: {}", e)))?;
+        Ok(self.inner.live_feed_hub().publish(event) as u32)
+    }
+
+    /// Connected live feed clients with their queue depth and drop counts
+    #[napi]
+    pub fn get_live_feed_stats(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize_platform(auth_token, "live-feed")?;
+        serde_json::to_string(&self.inner.live_feed_hub().stats())
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+}
+
+#[cfg(all(feature = "napi", feature = "live-feed"))]
+#[napi]
+impl SecOpCoreNapi {
+    /// Serve the live feed (`/feed/ws` and `/feed/sse`) on `bind`, e.g.
+    /// `0.0.0.0:9465`, replacing any running server; returns the bound
+    /// address. Pass no address to stop
+    #[napi]
+    pub async fn start_live_feed(&self, bind: Option<String>, config_json: Option<String>, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        let actor = self.authorize_platform(auth_token, "live-feed")?;
+        let config: FeedConfig = match &config_json {
+            Some(data) => serde_json::from_str(data)
+                .map_err(|e| napi::Error::from_reason(format!("Invalid live feed config: {}", e)))?,
+            None => FeedConfig::default(),
+        };
+
+        let access = self.access.clone();
+        let tenants = self.tenants.clone();
+        let authenticate: FeedAuthenticator = Arc::new(move |token| {
+            let principal = feed_principal(access.control(), token, tenancy::DEFAULT_TENANT)?;
+            if let Some(tenant_id) = &principal.tenant_id {
+                tenants.check(tenant_id)?;
+            }
+            Ok(principal)
+        });
+
+        let details = json!({ "bind": bind, "config": config_json });
+        let started = self.inner.start_live_feed(bind.as_deref(), config, authenticate).await;
+        let address = self.audit.record(&actor, "start_live_feed", "live-feed", details, started)
+            .map_err(napi::Error::from_reason)?;
+        Ok(address.map(|address| address.to_string()))
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use super::*;
+    use phantom_enterprise_standards::live_feed::{FeedMessage, FeedPrincipal};
+
+    #[tokio::test]
+    async fn incident_changes_are_published_under_timeline_event_names() {
+        assert_eq!(feed_event_type("MergedIntoIncident"), "merged_into_incident");
+
+        let core = SecOpCore::new();
+        let principal = FeedPrincipal { actor: "dashboard".to_string(), tenant_id: Some("acme".to_string()) };
+        let mut subscription = core.live_feed_hub().subscribe(principal, FeedTopic::parse_list("incidents").unwrap()).unwrap();
+
+        let incident: SecurityIncident = serde_json::from_value(serde_json::json!({
+            "incident_id": "INC-1", "title": "Ransomware", "description": "d", "severity": "Critical",
+            "status": "Open", "category": "Malware", "priority": 1,
+            "created_at": "2024-05-01T00:00:00Z", "updated_at": "2024-05-01T00:00:00Z",
+            "assigned_to": "soc", "reporter": "edr", "affected_systems": [], "indicators": [], "timeline": [],
+            "mitigation_actions": [], "estimated_impact": 1.0, "containment_status": "None"
+        }))
+        .unwrap();
+        core.create_incident("acme", incident.clone()).await.unwrap();
+        core.create_incident("globex", SecurityIncident { incident_id: "INC-2".to_string(), ..incident }).await.unwrap();
+        core.close_incident("acme", "INC-1", "done", "analyst").await.unwrap();
+
+        let mut received = Vec::new();
+        for _ in 0..2 {
+            match subscription.next().await {
+                Some(FeedMessage::Event(event)) => received.push((event.event_type, event.entity_id.unwrap_or_default())),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+        assert_eq!(
+            received,
+            vec![("incident_created".to_string(), "INC-1".to_string()), ("incident_closed".to_string(), "INC-1".to_string())]
+        );
+    }
+}
@@ -0,0 +1,323 @@
+//! Incident merge and deduplication
+//!
+//! When several alert sources open incidents for the same activity, analysts
+//! fold the duplicates into one primary incident. The primary absorbs their
+//! timelines, indicators, evidence, related alerts, affected assets and tags;
+//! each duplicate is closed with a link to the primary, and both sides record
+//! the merge in their timelines.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::{IncidentEvent, SecOpCore, SecurityIncident, ThreatIndicator};
+
+/// What a merge consolidated into the primary incident
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentMergeReport {
+    pub primary_id: String,
+    pub merged_ids: Vec<String>,
+    pub merged_at: DateTime<Utc>,
+    pub timeline_events_added: usize,
+    pub indicators_added: usize,
+    pub evidence_added: usize,
+    pub related_alerts_added: usize,
+    pub affected_systems_added: usize,
+    pub tags_added: usize,
+    /// Stored alerts whose correlation id was re-pointed at the primary
+    pub alerts_relinked: usize,
+}
+
+fn severity_rank(severity: &str) -> u8 {
+    match severity.to_ascii_lowercase().as_str() {
+        "critical" => 4,
+        "high" => 3,
+        "medium" => 2,
+        "low" => 1,
+        _ => 0,
+    }
+}
+
+/// Append the values of `source` missing from `target`; returns how many were added
+fn union_into(target: &mut Vec<String>, source: &[String]) -> usize {
+    let before = target.len();
+    for value in source {
+        if !target.iter().any(|existing| existing == value) {
+            target.push(value.clone());
+        }
+    }
+    target.len() - before
+}
+
+fn indicator_key(indicator: &ThreatIndicator) -> (String, String) {
+    (
+        indicator.indicator_type.to_ascii_lowercase(),
+        indicator.value.trim().to_ascii_lowercase(),
+    )
+}
+
+/// Fold `duplicate` into `primary`, keeping the stronger of overlapping indicators
+fn absorb(primary: &mut SecurityIncident, duplicate: &SecurityIncident, report: &mut IncidentMergeReport) {
+    let known_events: HashSet<String> = primary.timeline.iter().map(|e| e.event_id.clone()).collect();
+    for event in &duplicate.timeline {
+        if known_events.contains(&event.event_id) {
+            continue;
+        }
+        let mut event = event.clone();
+        event.data.entry("merged_from".to_string()).or_insert_with(|| duplicate.incident_id.clone());
+        primary.timeline.push(event);
+        report.timeline_events_added += 1;
+    }
+
+    for indicator in &duplicate.indicators {
+        let key = indicator_key(indicator);
+        match primary.indicators.iter_mut().find(|existing| indicator_key(existing) == key) {
+            Some(existing) => {
+                existing.confidence = existing.confidence.max(indicator.confidence);
+                existing.first_seen = existing.first_seen.min(indicator.first_seen);
+                existing.last_seen = existing.last_seen.max(indicator.last_seen);
+                if severity_rank(&indicator.severity) > severity_rank(&existing.severity) {
+                    existing.severity = indicator.severity.clone();
+                }
+            }
+            None => {
+                primary.indicators.push(indicator.clone());
+                report.indicators_added += 1;
+            }
+        }
+    }
+
+    report.evidence_added += union_into(&mut primary.evidence, &duplicate.evidence);
+    report.related_alerts_added += union_into(&mut primary.related_alerts, &duplicate.related_alerts);
+    report.affected_systems_added += union_into(&mut primary.affected_systems, &duplicate.affected_systems);
+    report.tags_added += union_into(&mut primary.tags, &duplicate.tags);
+    union_into(&mut primary.mitigation_actions, &duplicate.mitigation_actions);
+
+    if severity_rank(&duplicate.severity) > severity_rank(&primary.severity) {
+        primary.severity = duplicate.severity.clone();
+    }
+    // Priority 1 is the most urgent
+    if duplicate.priority > 0 && (primary.priority == 0 || duplicate.priority < primary.priority) {
+        primary.priority = duplicate.priority;
+    }
+    primary.estimated_impact = primary.estimated_impact.max(duplicate.estimated_impact);
+}
+
+impl SecOpCore {
+    /// Merge duplicate incidents of one tenant into `primary_id`.
+    ///
+    /// Every incident is validated before anything changes, so a failed merge
+    /// leaves all incidents untouched.
+    pub async fn merge_incidents(
+        &self,
+        tenant_id: &str,
+        primary_id: &str,
+        duplicate_ids: &[String],
+        merged_by: &str,
+    ) -> Result<IncidentMergeReport, String> {
+        let mut duplicate_ids: Vec<String> = duplicate_ids.to_vec();
+        let mut seen = HashSet::new();
+        duplicate_ids.retain(|id| seen.insert(id.clone()));
+        if duplicate_ids.is_empty() {
+            return Err("At least one duplicate incident is required".to_string());
+        }
+        if duplicate_ids.iter().any(|id| id == primary_id) {
+            return Err(format!("Incident {} cannot be merged into itself", primary_id));
+        }
+
+        let merged_at = Utc::now();
+        let mut incidents = self.incidents.write().await;
+
+        let mut primary = incidents
+            .get(primary_id)
+            .filter(|i| i.tenant_id == tenant_id)
+            .cloned()
+            .ok_or_else(|| format!("Incident {} not found", primary_id))?;
+        if let Some(target) = &primary.merged_into {
+            return Err(format!("Incident {} was already merged into {}", primary_id, target));
+        }
+        let mut duplicates = Vec::with_capacity(duplicate_ids.len());
+        for id in &duplicate_ids {
+            let duplicate = incidents
+                .get(id)
+                .filter(|i| i.tenant_id == tenant_id)
+                .ok_or_else(|| format!("Incident {} not found", id))?;
+            if let Some(target) = &duplicate.merged_into {
+                return Err(format!("Incident {} was already merged into {}", id, target));
+            }
+            duplicates.push(duplicate.clone());
+        }
+
+        let mut report = IncidentMergeReport {
+            primary_id: primary_id.to_string(),
+            merged_ids: duplicate_ids.clone(),
+            merged_at,
+            timeline_events_added: 0,
+            indicators_added: 0,
+            evidence_added: 0,
+            related_alerts_added: 0,
+            affected_systems_added: 0,
+            tags_added: 0,
+            alerts_relinked: 0,
+        };
+        for duplicate in &duplicates {
+            absorb(&mut primary, duplicate, &mut report);
+        }
+        primary.timeline.sort_by_key(|event| event.timestamp);
+
+        let mut data = HashMap::new();
+        data.insert("merged_incidents".to_string(), duplicate_ids.join(","));
+        data.insert("timeline_events_added".to_string(), report.timeline_events_added.to_string());
+        data.insert("indicators_added".to_string(), report.indicators_added.to_string());
+        data.insert("related_alerts_added".to_string(), report.related_alerts_added.to_string());
+        primary.timeline.push(IncidentEvent {
+            event_id: Uuid::new_v4().to_string(),
+            timestamp: merged_at,
+            event_type: "IncidentMerged".to_string(),
+            description: format!("Merged {} duplicate incident(s): {}", duplicate_ids.len(), duplicate_ids.join(", ")),
+            source: merged_by.to_string(),
+            severity: primary.severity.clone(),
+            data,
+        });
+        primary.updated_at = merged_at;
+
+        self.search.index_incident(&primary)?;
+        self.publish_incident(&primary, "IncidentMerged");
+        for mut duplicate in duplicates {
+            duplicate.status = "Closed".to_string();
+            duplicate.merged_into = Some(primary_id.to_string());
+            duplicate.updated_at = merged_at;
+            duplicate.timeline.push(IncidentEvent {
+                event_id: Uuid::new_v4().to_string(),
+                timestamp: merged_at,
+                event_type: "MergedIntoIncident".to_string(),
+                description: format!("Closed as duplicate of {}", primary_id),
+                source: merged_by.to_string(),
+                severity: duplicate.severity.clone(),
+                data: HashMap::from([("primary_incident".to_string(), primary_id.to_string())]),
+            });
+            self.search.index_incident(&duplicate)?;
+            self.publish_incident(&duplicate, "MergedIntoIncident");
+            incidents.insert(duplicate.incident_id.clone(), duplicate);
+        }
+        incidents.insert(primary_id.to_string(), primary);
+        drop(incidents);
+
+        let mut alerts = self.alerts.write().await;
+        for alert in alerts.values_mut().filter(|a| a.tenant_id == tenant_id) {
+            if alert.correlation_id.as_ref().is_some_and(|id| duplicate_ids.contains(id)) {
+                alert.correlation_id = Some(primary_id.to_string());
+                alert.updated_at = merged_at;
+                report.alerts_relinked += 1;
+                self.search.index_alert(alert)?;
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn incident(id: &str, severity: &str, indicator: (&str, f64), tags: &[&str]) -> SecurityIncident {
+        let now = Utc::now();
+        SecurityIncident {
+            incident_id: id.to_string(),
+            title: format!("Incident {}", id),
+            description: String::new(),
+            severity: severity.to_string(),
+            status: "Open".to_string(),
+            category: "Malware".to_string(),
+            priority: 3,
+            created_at: now,
+            updated_at: now,
+            assigned_to: "soc".to_string(),
+            reporter: "edr".to_string(),
+            affected_systems: vec![format!("host-{}", id)],
+            indicators: vec![ThreatIndicator {
+                indicator_id: format!("ioc-{}", id),
+                indicator_type: "IP".to_string(),
+                value: indicator.0.to_string(),
+                confidence: indicator.1,
+                severity: severity.to_string(),
+                source: "edr".to_string(),
+                first_seen: now,
+                last_seen: now,
+                context: String::new(),
+            }],
+            timeline: vec![IncidentEvent {
+                event_id: format!("evt-{}", id),
+                timestamp: now,
+                event_type: "Detection".to_string(),
+                description: "Beacon observed".to_string(),
+                source: "edr".to_string(),
+                severity: severity.to_string(),
+                data: HashMap::new(),
+            }],
+            mitigation_actions: Vec::new(),
+            estimated_impact: 1.0,
+            containment_status: "None".to_string(),
+            evidence: vec![format!("sha256:{}", id)],
+            related_alerts: vec![format!("alert-{}", id)],
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            merged_into: None,
+            sla: None,
+            tenant_id: "default".to_string(),
+            external_id: None,
+            ticket: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_merge_consolidates_duplicates_and_closes_them() {
+        let core = SecOpCore::new();
+        core.create_incident("default", incident("A", "Medium", ("203.0.113.7", 0.5), &["beacon"])).await.unwrap();
+        core.create_incident("default", incident("B", "High", ("203.0.113.7", 0.9), &["beacon", "c2"])).await.unwrap();
+        core.create_incident("default", incident("C", "Low", ("198.51.100.4", 0.6), &[])).await.unwrap();
+
+        let report = core
+            .merge_incidents("default", "A", &["B".to_string(), "C".to_string(), "B".to_string()], "analyst")
+            .await
+            .unwrap();
+        assert_eq!(report.merged_ids, vec!["B", "C"]);
+        assert_eq!(report.indicators_added, 1);
+        assert_eq!(report.tags_added, 1);
+
+        let primary = core.get_incident("default", "A").await.unwrap();
+        assert_eq!(primary.severity, "High");
+        assert_eq!(primary.indicators.len(), 2);
+        assert_eq!(primary.indicators[0].confidence, 0.9);
+        assert_eq!(primary.evidence.len(), 3);
+        assert_eq!(primary.related_alerts, vec!["alert-A", "alert-B", "alert-C"]);
+        assert_eq!(primary.affected_systems.len(), 3);
+        assert_eq!(primary.timeline.len(), 4);
+        let merge_event = primary.timeline.last().unwrap();
+        assert_eq!(merge_event.event_type, "IncidentMerged");
+        assert_eq!(merge_event.data["merged_incidents"], "B,C");
+        assert!(primary.timeline.iter().any(|e| e.data.get("merged_from").map(String::as_str) == Some("B")));
+
+        let duplicate = core.get_incident("default", "B").await.unwrap();
+        assert_eq!(duplicate.status, "Closed");
+        assert_eq!(duplicate.merged_into.as_deref(), Some("A"));
+        assert_eq!(duplicate.timeline.last().unwrap().data["primary_incident"], "A");
+
+        let again = core.merge_incidents("default", "A", &["B".to_string()], "analyst").await;
+        assert!(again.unwrap_err().contains("already merged"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_merge_leaves_incidents_untouched() {
+        let core = SecOpCore::new();
+        core.create_incident("default", incident("A", "Medium", ("203.0.113.7", 0.5), &[])).await.unwrap();
+        core.create_incident("default", incident("B", "High", ("203.0.113.8", 0.5), &[])).await.unwrap();
+
+        let result = core.merge_incidents("default", "A", &["B".to_string(), "missing".to_string()], "analyst").await;
+        assert!(result.is_err());
+        assert_eq!(core.get_incident("default", "A").await.unwrap().timeline.len(), 1);
+        assert_eq!(core.get_incident("default", "B").await.unwrap().status, "Open");
+        assert!(core.merge_incidents("default", "A", &["A".to_string()], "analyst").await.is_err());
+    }
+}
@@ -0,0 +1,131 @@
+//! Metrics History
+//!
+//! With `phantom-enterprise-standards` enabled, alert intake, opened
+//! incidents and time to resolve (MTTR) are recorded per tenant into
+//! 1-minute, 1-hour and 1-day rollups, so they can be charted over time.
+//! Without the feature nothing is recorded.
+
+use crate::secop_core::SecOpCore;
+use crate::SecurityIncident;
+use chrono::{DateTime, Utc};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::metrics_history::{MetricSeries, MetricsHistory, Resolution};
+
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+use crate::secop_core::SecOpCoreNapi;
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+use napi_derive::napi;
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+use phantom_enterprise_standards::metrics_history::parse_range;
+
+/// Alerts ingested; chart the bucket `sum`
+pub const ALERTS_INGESTED: &str = "alerts_ingested";
+pub const INCIDENTS_OPENED: &str = "incidents_opened";
+/// Minutes from creation to close for each closed incident; the bucket
+/// `mean` is the MTTR
+pub const TIME_TO_RESOLVE_MINUTES: &str = "time_to_resolve_minutes";
+
+#[derive(Default)]
+pub struct MetricsHistoryState {
+    #[cfg(feature = "phantom-enterprise-standards")]
+    history: MetricsHistory,
+}
+
+impl MetricsHistoryState {
+    pub(crate) fn record(&self, tenant_id: &str, metric: &str, value: f64) {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        self.history.record(tenant_id, metric, value);
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        let _ = (tenant_id, metric, value);
+    }
+
+    pub(crate) fn forget_tenant(&self, tenant_id: &str) -> usize {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            self.history.forget_scope(tenant_id)
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        {
+            let _ = tenant_id;
+            0
+        }
+    }
+}
+
+impl SecOpCore {
+    pub(crate) fn record_resolution_metrics(&self, incident: &SecurityIncident, closed_at: DateTime<Utc>) {
+        let minutes = (closed_at - incident.created_at).num_seconds().max(0) as f64 / 60.0;
+        self.metrics_history.record(&incident.tenant_id, TIME_TO_RESOLVE_MINUTES, minutes);
+    }
+
+    /// History of `metric` over the last `range` for charting
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn metrics_history(&self, tenant_id: &str, metric: &str, range: chrono::Duration, resolution: Option<Resolution>) -> Result<MetricSeries, String> {
+        self.metrics_history.history.recent(tenant_id, metric, range, resolution)
+    }
+
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn recorded_metrics(&self, tenant_id: &str) -> Vec<String> {
+        self.metrics_history.history.metrics(tenant_id)
+    }
+}
+
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+#[napi]
+impl SecOpCoreNapi {
+    /// Bucketed history of a metric, e.g. `("time_to_resolve_minutes", "30d", "1d")`;
+    /// the resolution is picked from the range when omitted
+    #[napi]
+    pub fn get_metrics_history(&self, metric: String, range: String, resolution: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let range = parse_range(&range).map_err(napi::Error::from_reason)?;
+        let resolution = resolution.as_deref().map(str::parse).transpose().map_err(napi::Error::from_reason)?;
+        let series = self.inner.metrics_history(&tenant_id, &metric, range, resolution)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to get metrics history: {}", e)))?;
+        serde_json::to_string(&series)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to serialize metrics history: {}", e)))
+    }
+
+    /// Metrics with recorded history for the caller's tenant
+    #[napi]
+    pub fn list_history_metrics(&self, auth_token: Option<String>) -> napi::Result<Vec<String>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        Ok(self.inner.recorded_metrics(&tenant_id))
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use super::*;
+
+    fn incident(id: &str, age_minutes: i64) -> SecurityIncident {
+        let created_at = Utc::now() - chrono::Duration::minutes(age_minutes);
+        serde_json::from_value(serde_json::json!({
+            "incident_id": id, "title": "Beacon to known C2", "description": "d", "severity": "High",
+            "status": "Open", "category": "Malware", "priority": 1,
+            "created_at": created_at, "updated_at": created_at, "assigned_to": "soc", "reporter": "edr",
+            "affected_systems": ["web-01"], "indicators": [], "timeline": [],
+            "mitigation_actions": [], "estimated_impact": 1.0, "containment_status": "None"
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn closing_incidents_records_time_to_resolve() {
+        let core = SecOpCore::new();
+        core.create_incident("acme", incident("INC-1", 30)).await.unwrap();
+        core.create_incident("acme", incident("INC-2", 90)).await.unwrap();
+        core.close_incident("acme", "INC-1", "done", "analyst").await.unwrap();
+        core.close_incident("acme", "INC-2", "done", "analyst").await.unwrap();
+
+        let mttr = core.metrics_history("acme", TIME_TO_RESOLVE_MINUTES, chrono::Duration::days(1), Some(Resolution::Day)).unwrap();
+        assert_eq!(mttr.points.len(), 1);
+        assert!((mttr.points[0].mean - 60.0).abs() < 0.1, "{:?}", mttr.points);
+        let opened = core.metrics_history("acme", INCIDENTS_OPENED, chrono::Duration::hours(1), None).unwrap();
+        assert_eq!(opened.points.iter().map(|p| p.sum).sum::<f64>(), 2.0);
+
+        assert_eq!(core.purge_tenant("acme").await.unwrap()["metric_series"], 2);
+        assert!(core.recorded_metrics("acme").is_empty());
+    }
+}
@@ -0,0 +1,251 @@
+//! Typed NAPI objects
+//!
+//! `#[napi(object)]` views of incidents and alerts for the `*_typed` method
+//! variants, so TypeScript callers get generated types rather than parsing
+//! JSON strings. Timeline, raw alert data and full SLA timers stay on the
+//! string methods; the views flag SLA breach and risk and link the ticket.
+//! Timestamps are RFC 3339 strings.
+
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+
+#[cfg(feature = "napi")]
+use napi::Result as NapiResult;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "napi")]
+use crate::secop_core::SecOpCoreNapi;
+use crate::{SecurityAlert, SecurityIncident, ThreatIndicator};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct Indicator {
+    pub indicator_id: String,
+    pub indicator_type: String,
+    pub value: String,
+    pub confidence: f64,
+    pub severity: String,
+    pub source: String,
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+impl From<&ThreatIndicator> for Indicator {
+    fn from(indicator: &ThreatIndicator) -> Self {
+        Self {
+            indicator_id: indicator.indicator_id.clone(),
+            indicator_type: indicator.indicator_type.clone(),
+            value: indicator.value.clone(),
+            confidence: indicator.confidence,
+            severity: indicator.severity.clone(),
+            source: indicator.source.clone(),
+            first_seen: indicator.first_seen.to_rfc3339(),
+            last_seen: indicator.last_seen.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct Incident {
+    pub incident_id: String,
+    pub tenant_id: String,
+    pub title: String,
+    pub description: String,
+    pub severity: String,
+    pub status: String,
+    pub category: String,
+    pub priority: u32,
+    pub created_at: String,
+    pub updated_at: String,
+    pub assigned_to: String,
+    pub reporter: String,
+    pub affected_systems: Vec<String>,
+    pub indicators: Vec<Indicator>,
+    pub mitigation_actions: Vec<String>,
+    pub estimated_impact: f64,
+    pub containment_status: String,
+    pub evidence: Vec<String>,
+    pub related_alerts: Vec<String>,
+    pub tags: Vec<String>,
+    pub merged_into: Option<String>,
+    /// Whether any SLA timer has breached; absent when no SLA policy applies
+    pub sla_breached: Option<bool>,
+    pub sla_at_risk: Option<bool>,
+    pub ticket_url: Option<String>,
+}
+
+impl From<&SecurityIncident> for Incident {
+    fn from(incident: &SecurityIncident) -> Self {
+        Self {
+            incident_id: incident.incident_id.clone(),
+            tenant_id: incident.tenant_id.clone(),
+            title: incident.title.clone(),
+            description: incident.description.clone(),
+            severity: incident.severity.clone(),
+            status: incident.status.clone(),
+            category: incident.category.clone(),
+            priority: incident.priority,
+            created_at: incident.created_at.to_rfc3339(),
+            updated_at: incident.updated_at.to_rfc3339(),
+            assigned_to: incident.assigned_to.clone(),
+            reporter: incident.reporter.clone(),
+            affected_systems: incident.affected_systems.clone(),
+            indicators: incident.indicators.iter().map(Indicator::from).collect(),
+            mitigation_actions: incident.mitigation_actions.clone(),
+            estimated_impact: incident.estimated_impact,
+            containment_status: incident.containment_status.clone(),
+            evidence: incident.evidence.clone(),
+            related_alerts: incident.related_alerts.clone(),
+            tags: incident.tags.clone(),
+            merged_into: incident.merged_into.clone(),
+            sla_breached: incident.sla.as_ref().map(|sla| sla.breached),
+            sla_at_risk: incident.sla.as_ref().map(|sla| sla.at_risk),
+            ticket_url: incident.ticket.as_ref().map(|ticket| ticket.url.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "napi", napi(object))]
+pub struct Alert {
+    pub alert_id: String,
+    pub tenant_id: String,
+    pub title: String,
+    pub description: String,
+    pub priority: String,
+    pub status: String,
+    pub source: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub rule_id: String,
+    pub rule_name: String,
+    pub affected_assets: Vec<String>,
+    pub indicators: Vec<Indicator>,
+    pub false_positive_probability: f64,
+    pub correlation_id: Option<String>,
+}
+
+impl From<&SecurityAlert> for Alert {
+    fn from(alert: &SecurityAlert) -> Self {
+        Self {
+            alert_id: alert.alert_id.clone(),
+            tenant_id: alert.tenant_id.clone(),
+            title: alert.title.clone(),
+            description: alert.description.clone(),
+            priority: alert.priority.clone(),
+            status: alert.status.clone(),
+            source: alert.source.clone(),
+            created_at: alert.created_at.to_rfc3339(),
+            updated_at: alert.updated_at.to_rfc3339(),
+            rule_id: alert.rule_id.clone(),
+            rule_name: alert.rule_name.clone(),
+            affected_assets: alert.affected_assets.clone(),
+            indicators: alert.indicators.iter().map(Indicator::from).collect(),
+            false_positive_probability: alert.false_positive_probability,
+            correlation_id: alert.correlation_id.clone(),
+        }
+    }
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl SecOpCoreNapi {
+    /// Fetch an incident as a typed object
+    #[napi]
+    pub async fn get_incident_typed(&self, incident_id: String, auth_token: Option<String>) -> NapiResult<Option<Incident>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        Ok(self.inner.get_incident(&tenant_id, &incident_id).await.as_ref().map(Incident::from))
+    }
+
+    #[napi]
+    pub async fn list_incidents_typed(&self, auth_token: Option<String>) -> NapiResult<Vec<Incident>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        Ok(self.inner.list_incidents(&tenant_id).await.iter().map(Incident::from).collect())
+    }
+
+    #[napi]
+    pub async fn get_alert_typed(&self, alert_id: String, auth_token: Option<String>) -> NapiResult<Option<Alert>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        Ok(self.inner.get_alert(&tenant_id, &alert_id).await.as_ref().map(Alert::from))
+    }
+
+    /// Alerts newest first, as typed objects
+    #[napi]
+    pub async fn list_alerts_typed(&self, auth_token: Option<String>) -> NapiResult<Vec<Alert>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        Ok(self.inner.list_alerts(&tenant_id).await.iter().map(Alert::from).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IncidentEvent, SecOpCore};
+    use chrono::Utc;
+
+    fn incident(id: &str) -> SecurityIncident {
+        let now = Utc::now();
+        SecurityIncident {
+            incident_id: id.to_string(),
+            title: format!("Incident {}", id),
+            description: "Beacon to known C2".to_string(),
+            severity: "High".to_string(),
+            status: "Open".to_string(),
+            category: "Malware".to_string(),
+            priority: 2,
+            created_at: now,
+            updated_at: now,
+            assigned_to: "soc".to_string(),
+            reporter: "edr".to_string(),
+            affected_systems: vec!["host-1".to_string()],
+            indicators: vec![ThreatIndicator {
+                indicator_id: "ioc-1".to_string(),
+                indicator_type: "IP".to_string(),
+                value: "203.0.113.7".to_string(),
+                confidence: 0.9,
+                severity: "High".to_string(),
+                source: "edr".to_string(),
+                first_seen: now,
+                last_seen: now,
+                context: String::new(),
+            }],
+            timeline: vec![IncidentEvent {
+                event_id: "evt-1".to_string(),
+                timestamp: now,
+                event_type: "Detection".to_string(),
+                description: "Beacon observed".to_string(),
+                source: "edr".to_string(),
+                severity: "High".to_string(),
+                data: Default::default(),
+            }],
+            mitigation_actions: Vec::new(),
+            estimated_impact: 1.0,
+            containment_status: "None".to_string(),
+            evidence: Vec::new(),
+            related_alerts: Vec::new(),
+            tags: vec!["beacon".to_string()],
+            merged_into: None,
+            sla: None,
+            tenant_id: "default".to_string(),
+            external_id: None,
+            ticket: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn incident_view_carries_sla_flags_and_indicators() {
+        let core = SecOpCore::new();
+        core.create_incident("acme", incident("INC-1")).await.unwrap();
+
+        let stored = core.get_incident("acme", "INC-1").await.unwrap();
+        let typed = Incident::from(&stored);
+        assert_eq!((typed.incident_id.as_str(), typed.tenant_id.as_str()), ("INC-1", "acme"));
+        assert_eq!(typed.created_at, stored.created_at.to_rfc3339());
+        assert_eq!(typed.indicators[0].value, "203.0.113.7");
+        assert_eq!(typed.sla_breached, stored.sla.as_ref().map(|sla| sla.breached));
+        assert_eq!(typed.ticket_url, None);
+        assert!(core.list_incidents("globex").await.iter().map(Incident::from).next().is_none());
+    }
+}
@@ -0,0 +1,685 @@
+//! Custom playbook actions and playbook runs
+//!
+//! A playbook step names the action it performs in `action_type`. Embedders
+//! add their own actions, registered from Rust as a `PlaybookAction` or from
+//! Node through `registerPlaybookAction` with an async handler. Each action
+//! describes its parameters with a JSON Schema, of which `type`,
+//! `properties`, `required`, `enum`, `minimum`, `maximum` and `items` are
+//! checked before the handler is called.
+//!
+//! `run_playbook` executes one of the tenant's playbooks, optionally against
+//! an incident. Step parameters are rendered from the run's context
+//! (`{{incident.severity}}`, `{{alert.source}}` or keys the caller passes),
+//! checked against the action's schema and handed to its handler under the
+//! action's timeout, or the step's own `timeout` when it sets one. Failed
+//! and timed-out attempts are retried with exponential backoff up to the
+//! action's `max_retries`; parameters that fail the schema are not. Every
+//! invocation is kept as an `ActionExecution` with its attempts and output.
+//! Steps whose action is not registered are left to an analyst, and a failed
+//! required step ends the run. Actions are shared by all tenants; action
+//! executions belong to the tenant that ran the playbook.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use crate::error::{CoreError, CoreResult};
+use crate::secop_core::SecOpCore;
+use crate::{ExecutionMetrics, PlaybookExecution, StepResult};
+
+#[cfg(feature = "napi")]
+use crate::secop_core::SecOpCoreNapi;
+#[cfg(feature = "napi")]
+use napi::bindgen_prelude::Promise;
+#[cfg(feature = "napi")]
+use napi::threadsafe_function::{ErrorStrategy, ThreadsafeFunction};
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+#[cfg(feature = "napi")]
+use serde_json::json;
+
+/// Action executions kept in memory, oldest dropped first
+pub const MAX_ACTION_EXECUTIONS: usize = 10_000;
+/// Longest wait between two attempts of an action
+const MAX_RETRY_BACKOFF_MS: u64 = 30_000;
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+fn default_retry_backoff_ms() -> u64 {
+    1_000
+}
+
+fn default_parameters() -> Value {
+    serde_json::json!({ "type": "object" })
+}
+
+/// How an action is described to the playbook engine
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionDefinition {
+    /// Matched against `PlaybookStep::action_type`, e.g. `edr.isolate`
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// JSON Schema of the step parameters
+    #[serde(default = "default_parameters")]
+    pub parameters: Value,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    #[serde(default)]
+    pub max_retries: u32,
+    /// Wait before the first retry, doubled for each further one
+    #[serde(default = "default_retry_backoff_ms")]
+    pub retry_backoff_ms: u64,
+}
+
+/// What a handler is called with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionInvocation {
+    pub action_execution_id: String,
+    pub execution_id: String,
+    pub tenant_id: String,
+    pub playbook_id: String,
+    pub step_id: String,
+    pub incident_id: Option<String>,
+    /// 1 for the first attempt
+    pub attempt: u32,
+    pub parameters: Value,
+}
+
+#[async_trait]
+pub trait PlaybookAction: Send + Sync {
+    fn definition(&self) -> &ActionDefinition;
+
+    /// Perform the action; the returned value is kept as its output
+    async fn invoke(&self, invocation: &ActionInvocation) -> Result<Value, String>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionStatus {
+    Succeeded,
+    Failed,
+    TimedOut,
+    /// Parameters did not match the action's schema; the handler was not called
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionAttempt {
+    pub attempt: u32,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub status: ActionStatus,
+    pub error: Option<String>,
+}
+
+/// One step's invocation of a registered action, across its attempts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionExecution {
+    pub action_execution_id: String,
+    pub execution_id: String,
+    pub tenant_id: String,
+    pub playbook_id: String,
+    pub step_id: String,
+    pub action: String,
+    pub incident_id: Option<String>,
+    pub status: ActionStatus,
+    /// Rendered parameters the handler received
+    pub parameters: Value,
+    pub output: Option<Value>,
+    pub error: Option<String>,
+    pub attempts: Vec<ActionAttempt>,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+}
+
+/// Result of `run_playbook`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaybookRun {
+    pub execution: PlaybookExecution,
+    pub actions: Vec<ActionExecution>,
+}
+
+#[derive(Default)]
+pub struct PlaybookActionState {
+    actions: RwLock<HashMap<String, Arc<dyn PlaybookAction>>>,
+    executions: RwLock<VecDeque<ActionExecution>>,
+}
+
+impl PlaybookActionState {
+    fn action(&self, name: &str) -> Option<Arc<dyn PlaybookAction>> {
+        self.actions.read().unwrap_or_else(|e| e.into_inner()).get(name).cloned()
+    }
+
+    fn record(&self, execution: ActionExecution) {
+        let mut executions = self.executions.write().unwrap_or_else(|e| e.into_inner());
+        if executions.len() >= MAX_ACTION_EXECUTIONS {
+            executions.pop_front();
+        }
+        executions.push_back(execution);
+    }
+
+    pub(crate) fn forget_tenant(&self, tenant_id: &str) -> usize {
+        let mut executions = self.executions.write().unwrap_or_else(|e| e.into_inner());
+        let before = executions.len();
+        executions.retain(|execution| execution.tenant_id != tenant_id);
+        before - executions.len()
+    }
+}
+
+// Parameter schemas
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "boolean" => value.is_boolean(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+/// Problems of `value` against the supported subset of JSON Schema, with the
+/// path of each offending value
+pub fn validate_parameters(schema: &Value, value: &Value) -> Vec<String> {
+    let mut problems = Vec::new();
+    check_schema(schema, value, "parameters", &mut problems);
+    problems
+}
+
+fn check_schema(schema: &Value, value: &Value, path: &str, problems: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else { return };
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|name| type_matches(name, value)) {
+            problems.push(format!("{} must be {}", path, allowed.join(" or ")));
+            return;
+        }
+    }
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            problems.push(format!("{} must be one of {}", path, Value::Array(options.clone())));
+        }
+    }
+    if let Some(number) = value.as_f64() {
+        if schema.get("minimum").and_then(Value::as_f64).is_some_and(|minimum| number < minimum) {
+            problems.push(format!("{} must be at least {}", path, schema["minimum"]));
+        }
+        if schema.get("maximum").and_then(Value::as_f64).is_some_and(|maximum| number > maximum) {
+            problems.push(format!("{} must be at most {}", path, schema["maximum"]));
+        }
+    }
+    if let Value::Object(fields) = value {
+        for name in schema.get("required").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str) {
+            if !fields.contains_key(name) {
+                problems.push(format!("{}.{} is required", path, name));
+            }
+        }
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (name, field) in fields {
+                if let Some(property) = properties.get(name) {
+                    check_schema(property, field, &format!("{}.{}", path, name), problems);
+                }
+            }
+        }
+    }
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (index, item) in items.iter().enumerate() {
+            check_schema(item_schema, item, &format!("{}[{}]", path, index), problems);
+        }
+    }
+}
+
+/// Step parameters are strings; ones the schema types otherwise are parsed
+fn coerce(schema: &Value, name: &str, value: Value) -> Value {
+    let Value::String(text) = &value else { return value };
+    let declared = schema.pointer(&format!("/properties/{}/type", name)).and_then(Value::as_str);
+    let parsed = match declared {
+        Some("integer") => text.trim().parse::<i64>().ok().map(Value::from),
+        Some("number") => text.trim().parse::<f64>().ok().map(Value::from),
+        Some("boolean") => text.trim().parse::<bool>().ok().map(Value::from),
+        Some("object") | Some("array") => serde_json::from_str(text).ok(),
+        _ => None,
+    };
+    parsed.unwrap_or(value)
+}
+
+// Templates
+
+fn lookup<'a>(context: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(context, |value, key| match value {
+        Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+        _ => value.get(key),
+    })
+}
+
+fn text_of(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Replace `{{path}}` placeholders with values from `context`. A parameter
+/// that is a single placeholder keeps the value's JSON type.
+pub fn render_parameter(template: &str, context: &Value) -> Result<Value, String> {
+    let trimmed = template.trim();
+    if let Some(path) = trimmed.strip_prefix("{{").and_then(|rest| rest.strip_suffix("}}")).filter(|path| !path.contains("{{")) {
+        let path = path.trim();
+        return lookup(context, path).cloned().ok_or_else(|| format!("{{{{{}}}}} does not resolve", path));
+    }
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else { break };
+        let path = rest[start + 2..start + end].trim();
+        let value = lookup(context, path).ok_or_else(|| format!("{{{{{}}}}} does not resolve", path))?;
+        rendered.push_str(&rest[..start]);
+        rendered.push_str(&text_of(value));
+        rest = &rest[start + end + 2..];
+    }
+    rendered.push_str(rest);
+    Ok(Value::String(rendered))
+}
+
+fn step_result(step_id: &str, status: &str, started_at: DateTime<Utc>, output: String, error_message: Option<String>, automation_used: bool) -> StepResult {
+    StepResult {
+        step_id: step_id.to_string(),
+        status: status.to_string(),
+        started_at,
+        completed_at: Some(Utc::now()),
+        output,
+        error_message,
+        automation_used,
+    }
+}
+
+impl SecOpCore {
+    /// Add an action playbook steps can run, replacing one of the same name
+    pub fn register_playbook_action(&self, action: Arc<dyn PlaybookAction>) -> CoreResult<()> {
+        let definition = action.definition();
+        if definition.name.trim().is_empty() {
+            return Err(CoreError::validation("Action name is required"));
+        }
+        if !definition.parameters.is_object() {
+            return Err(CoreError::validation(format!("Parameters of action {} must be a JSON Schema object", definition.name)));
+        }
+        if definition.timeout_secs == 0 {
+            return Err(CoreError::validation(format!("Timeout of action {} must be positive", definition.name)));
+        }
+        let name = definition.name.clone();
+        self.playbook_actions.actions.write().unwrap_or_else(|e| e.into_inner()).insert(name, action);
+        Ok(())
+    }
+
+    pub fn unregister_playbook_action(&self, name: &str) -> bool {
+        self.playbook_actions.actions.write().unwrap_or_else(|e| e.into_inner()).remove(name).is_some()
+    }
+
+    pub fn list_playbook_actions(&self) -> Vec<ActionDefinition> {
+        let mut definitions: Vec<ActionDefinition> = self
+            .playbook_actions
+            .actions
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .map(|action| action.definition().clone())
+            .collect();
+        definitions.sort_by(|a, b| a.name.cmp(&b.name));
+        definitions
+    }
+
+    /// Action executions of the tenant, newest first, optionally of one playbook run
+    pub fn list_action_executions(&self, tenant_id: &str, execution_id: Option<&str>) -> Vec<ActionExecution> {
+        self.playbook_actions
+            .executions
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .rev()
+            .filter(|execution| execution.tenant_id == tenant_id && execution_id.is_none_or(|id| execution.execution_id == id))
+            .cloned()
+            .collect()
+    }
+
+    /// Run a playbook of the tenant step by step. `context` is an object
+    /// whose keys step parameters may reference; with `incident_id` it also
+    /// holds the incident as `incident` and its first alert as `alert`.
+    pub async fn run_playbook(&self, tenant_id: &str, playbook_id: &str, incident_id: Option<&str>, context: Value, executed_by: &str) -> CoreResult<PlaybookRun> {
+        let playbook = self
+            .get_playbook(tenant_id, playbook_id)
+            .await
+            .ok_or_else(|| CoreError::not_found(format!("Playbook {} not found", playbook_id)))?;
+        let mut scope = match context {
+            Value::Object(fields) => fields,
+            Value::Null => Map::new(),
+            _ => return Err(CoreError::validation("Playbook context must be an object")),
+        };
+        if let Some(incident_id) = incident_id {
+            let incident = self
+                .get_incident(tenant_id, incident_id)
+                .await
+                .ok_or_else(|| CoreError::not_found(format!("Incident {} not found", incident_id)))?;
+            let alert = {
+                let alerts = self.alerts.read().await;
+                incident.related_alerts.iter().find_map(|id| alerts.get(id).filter(|alert| alert.tenant_id == tenant_id).cloned())
+            };
+            if let Some(alert) = alert {
+                scope.entry("alert").or_insert(serde_json::to_value(alert)?);
+            }
+            scope.insert("incident".to_string(), serde_json::to_value(incident)?);
+        }
+        let context = Value::Object(scope);
+
+        let execution_id = uuid::Uuid::new_v4().to_string();
+        let started_at = Utc::now();
+        let clock = Instant::now();
+        let mut step_results = Vec::new();
+        let mut actions = Vec::new();
+        let mut halted_by: Option<String> = None;
+        for step in &playbook.steps {
+            let step_started = Utc::now();
+            if let Some(failed) = &halted_by {
+                step_results.push(step_result(&step.step_id, "Skipped", step_started, String::new(), Some(format!("Required step {} failed", failed)), false));
+                continue;
+            }
+            let Some(action) = self.playbook_actions.action(&step.action_type) else {
+                step_results.push(step_result(&step.step_id, "Manual", step_started, format!("No registered action runs {}", step.action_type), None, false));
+                continue;
+            };
+
+            let definition = action.definition().clone();
+            let mut parameters = Map::new();
+            let mut problems = Vec::new();
+            for (name, template) in &step.parameters {
+                match render_parameter(template, &context) {
+                    Ok(value) => {
+                        parameters.insert(name.clone(), coerce(&definition.parameters, name, value));
+                    }
+                    Err(e) => problems.push(format!("parameters.{}: {}", name, e)),
+                }
+            }
+            let parameters = Value::Object(parameters);
+            problems.extend(validate_parameters(&definition.parameters, &parameters));
+
+            let mut execution = ActionExecution {
+                action_execution_id: uuid::Uuid::new_v4().to_string(),
+                execution_id: execution_id.clone(),
+                tenant_id: tenant_id.to_string(),
+                playbook_id: playbook.playbook_id.clone(),
+                step_id: step.step_id.clone(),
+                action: definition.name.clone(),
+                incident_id: incident_id.map(str::to_string),
+                status: ActionStatus::Rejected,
+                parameters,
+                output: None,
+                error: None,
+                attempts: Vec::new(),
+                started_at: step_started,
+                completed_at: step_started,
+            };
+            if problems.is_empty() {
+                let timeout = Duration::from_secs(if step.timeout > 0 { u64::from(step.timeout) } else { definition.timeout_secs });
+                self.invoke_action(action.as_ref(), &definition, timeout, &mut execution).await;
+            } else {
+                execution.error = Some(problems.join("; "));
+            }
+            execution.completed_at = Utc::now();
+
+            let succeeded = execution.status == ActionStatus::Succeeded;
+            let output = execution.output.as_ref().map(text_of).unwrap_or_default();
+            step_results.push(step_result(&step.step_id, if succeeded { "Completed" } else { "Failed" }, step_started, output, execution.error.clone(), true));
+            if !succeeded && step.required {
+                halted_by = Some(step.step_id.clone());
+            }
+            self.playbook_actions.record(execution.clone());
+            actions.push(execution);
+        }
+
+        let automated = step_results.iter().filter(|result| result.automation_used).count();
+        let completed = step_results.iter().filter(|result| result.status == "Completed").count();
+        let failed_steps = step_results.iter().filter(|result| result.status == "Failed").count();
+        let total = step_results.len().max(1) as f64;
+        let execution = PlaybookExecution {
+            execution_id,
+            playbook_id: playbook.playbook_id.clone(),
+            incident_id: incident_id.unwrap_or_default().to_string(),
+            status: if halted_by.is_some() { "Failed" } else { "Completed" }.to_string(),
+            started_at,
+            completed_at: Some(Utc::now()),
+            executed_by: executed_by.to_string(),
+            overall_success: halted_by.is_none() && failed_steps == 0,
+            execution_metrics: ExecutionMetrics {
+                total_duration: clock.elapsed().as_secs() as u32,
+                automation_percentage: automated as f64 / total * 100.0,
+                success_rate: completed as f64 / automated.max(1) as f64 * 100.0,
+                manual_interventions: step_results.iter().filter(|result| result.status == "Manual").count() as u32,
+                errors_encountered: actions.iter().flat_map(|action| &action.attempts).filter(|attempt| attempt.status != ActionStatus::Succeeded).count() as u32
+                    + actions.iter().filter(|action| action.status == ActionStatus::Rejected).count() as u32,
+            },
+            step_results,
+        };
+        log::info!(
+            "Playbook {} run {} for tenant {}: {} ({} actions)",
+            playbook.playbook_id, execution.execution_id, tenant_id, execution.status, actions.len()
+        );
+        Ok(PlaybookRun { execution, actions })
+    }
+
+    async fn invoke_action(&self, action: &dyn PlaybookAction, definition: &ActionDefinition, timeout: Duration, execution: &mut ActionExecution) {
+        let attempts = definition.max_retries.saturating_add(1);
+        for attempt in 1..=attempts {
+            if attempt > 1 {
+                let backoff = definition.retry_backoff_ms.saturating_mul(1 << (attempt - 2).min(16)).min(MAX_RETRY_BACKOFF_MS);
+                tokio::time::sleep(Duration::from_millis(backoff)).await;
+            }
+            let invocation = ActionInvocation {
+                action_execution_id: execution.action_execution_id.clone(),
+                execution_id: execution.execution_id.clone(),
+                tenant_id: execution.tenant_id.clone(),
+                playbook_id: execution.playbook_id.clone(),
+                step_id: execution.step_id.clone(),
+                incident_id: execution.incident_id.clone(),
+                attempt,
+                parameters: execution.parameters.clone(),
+            };
+            let started_at = Utc::now();
+            let clock = Instant::now();
+            let (status, output, error) = match tokio::time::timeout(timeout, action.invoke(&invocation)).await {
+                Ok(Ok(output)) => (ActionStatus::Succeeded, Some(output), None),
+                Ok(Err(e)) => (ActionStatus::Failed, None, Some(e)),
+                Err(_) => (ActionStatus::TimedOut, None, Some(format!("No result within {}s", timeout.as_secs()))),
+            };
+            execution.attempts.push(ActionAttempt { attempt, started_at, duration_ms: clock.elapsed().as_millis() as u64, status, error: error.clone() });
+            execution.status = status;
+            execution.output = output;
+            execution.error = error;
+            if status == ActionStatus::Succeeded {
+                return;
+            }
+            log::warn!("Action {} of step {} failed attempt {}/{}: {}", execution.action, execution.step_id, attempt, attempts, execution.error.as_deref().unwrap_or_default());
+        }
+    }
+}
+
+/// Node handler of a JS action: `(invocationJson) => Promise<outputJson>`
+#[cfg(feature = "napi")]
+pub type JsActionHandler = ThreadsafeFunction<String, ErrorStrategy::Fatal>;
+
+/// Action backed by a Node handler
+#[cfg(feature = "napi")]
+pub struct JsPlaybookAction {
+    definition: ActionDefinition,
+    handler: JsActionHandler,
+}
+
+#[cfg(feature = "napi")]
+#[async_trait]
+impl PlaybookAction for JsPlaybookAction {
+    fn definition(&self) -> &ActionDefinition {
+        &self.definition
+    }
+
+    async fn invoke(&self, invocation: &ActionInvocation) -> Result<Value, String> {
+        let payload = serde_json::to_string(invocation).map_err(|e| e.to_string())?;
+        let promise: Promise<String> = self.handler.call_async(payload).await.map_err(|e| e.to_string())?;
+        let output = promise.await.map_err(|e| e.to_string())?;
+        // Handlers resolving to plain text keep it as a string output
+        Ok(serde_json::from_str(&output).unwrap_or(Value::String(output)))
+    }
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl SecOpCoreNapi {
+    /// Register a JS playbook action. `definition_json` is `{"name":
+    /// "edr.isolate", "parameters": <JSON Schema>, "timeout_secs": 30,
+    /// "max_retries": 2, "retry_backoff_ms": 1000}`; `handler` receives the
+    /// invocation as JSON and resolves to the action's output as JSON
+    #[napi(ts_args_type = "definitionJson: string, handler: (invocationJson: string) => Promise<string>, authToken?: string | undefined | null")]
+    pub async fn register_playbook_action(&self, definition_json: String, handler: JsActionHandler, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize(auth_token, "rule:manage", "playbook_actions")?;
+        let definition: ActionDefinition = serde_json::from_str(&definition_json)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to parse action definition: {}", e)))?;
+        let params = json!({ "definition": definition });
+        let name = definition.name.clone();
+        let registered = self.inner.register_playbook_action(Arc::new(JsPlaybookAction { definition, handler }));
+        Ok(self.audit.record(&actor, "register_playbook_action", &name, params, registered)
+            .map_err(|e| e.context("Failed to register playbook action"))?)
+    }
+
+    #[napi]
+    pub async fn unregister_playbook_action(&self, name: String, auth_token: Option<String>) -> napi::Result<bool> {
+        let actor = self.authorize(auth_token, "rule:manage", &name)?;
+        let removed = self.inner.unregister_playbook_action(&name);
+        self.audit.record(&actor, "unregister_playbook_action", &name, json!({}), Ok::<_, String>(removed))
+            .map_err(napi::Error::from_reason)
+    }
+
+    #[napi]
+    pub async fn list_playbook_actions(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token, "read", "playbook_actions")?;
+        serde_json::to_string(&self.inner.list_playbook_actions())
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Run a playbook, optionally against an incident, with extra template
+    /// context as a JSON object
+    #[napi]
+    pub async fn run_playbook(&self, playbook_id: String, incident_id: Option<String>, context_json: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", &playbook_id)?;
+        let context: Value = match context_json.as_deref() {
+            Some(json) => serde_json::from_str(json).map_err(|e| napi::Error::from_reason(format!("Invalid playbook context: {}", e)))?,
+            None => Value::Null,
+        };
+        let params = json!({ "incident_id": incident_id });
+        let run = self.inner.run_playbook(&tenant_id, &playbook_id, incident_id.as_deref(), context, &actor).await;
+        let run = self.audit.record(&actor, "run_playbook", &playbook_id, params, run)
+            .map_err(|e| e.context("Failed to run playbook"))?;
+        serde_json::to_string(&run)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn list_action_executions(&self, execution_id: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        self.authorize(auth_token, "read", "action_executions")?;
+        serde_json::to_string(&self.inner.list_action_executions(&tenant_id, execution_id.as_deref()))
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SecurityPlaybook;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Fails its first `failures` calls, then echoes the host it isolated
+    struct Isolate {
+        definition: ActionDefinition,
+        failures: u32,
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl PlaybookAction for Isolate {
+        fn definition(&self) -> &ActionDefinition {
+            &self.definition
+        }
+
+        async fn invoke(&self, invocation: &ActionInvocation) -> Result<Value, String> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err("EDR busy".to_string());
+            }
+            Ok(serde_json::json!({ "isolated": invocation.parameters["host"], "attempt": invocation.attempt }))
+        }
+    }
+
+    fn playbook(host: &str) -> SecurityPlaybook {
+        serde_json::from_value(serde_json::json!({
+            "playbook_id": "contain", "name": "Contain host", "description": "", "version": "1.0", "category": "containment",
+            "trigger_conditions": [],
+            "steps": [
+                { "step_id": "isolate", "name": "Isolate", "action_type": "edr.isolate", "description": "",
+                  "parameters": { "host": host, "duration_minutes": "60" }, "timeout": 0, "required": true, "automation_supported": true },
+                { "step_id": "notify", "name": "Notify owner", "action_type": "email.send", "description": "",
+                  "parameters": {}, "timeout": 0, "required": false, "automation_supported": false }
+            ],
+            "automation_level": "semi", "estimated_duration": 10, "success_rate": 0.9, "last_updated": "2025-01-01T00:00:00Z"
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn js_style_actions_run_with_retries_and_schema_checks() {
+        let core = SecOpCore::new();
+        let definition: ActionDefinition = serde_json::from_value(serde_json::json!({
+            "name": "edr.isolate",
+            "parameters": {
+                "type": "object",
+                "required": ["host"],
+                "properties": { "host": { "type": "string" }, "duration_minutes": { "type": "integer", "minimum": 1 } }
+            },
+            "max_retries": 2,
+            "retry_backoff_ms": 1
+        }))
+        .unwrap();
+        let action = Arc::new(Isolate { definition, failures: 1, calls: AtomicU32::new(0) });
+        core.register_playbook_action(action.clone()).unwrap();
+
+        core.save_playbook("acme", playbook("{{host}}")).await.unwrap();
+        let run = core.run_playbook("acme", "contain", None, serde_json::json!({ "host": "wks-042" }), "analyst").await.unwrap();
+        assert!(run.execution.overall_success);
+        let statuses: Vec<&str> = run.execution.step_results.iter().map(|r| r.status.as_str()).collect();
+        assert_eq!(statuses, ["Completed", "Manual"]);
+        let isolate = &run.actions[0];
+        assert_eq!(isolate.parameters["duration_minutes"], 60);
+        assert_eq!(isolate.attempts.iter().map(|a| a.status).collect::<Vec<_>>(), [ActionStatus::Failed, ActionStatus::Succeeded]);
+        assert_eq!(isolate.output.as_ref().unwrap()["isolated"], "wks-042");
+        assert_eq!(core.list_action_executions("acme", Some(&run.execution.execution_id)).len(), 1);
+        assert!(core.list_action_executions("other", None).is_empty());
+
+        // An unresolved placeholder is rejected before the handler runs
+        core.save_playbook("acme", playbook("{{asset.hostname}}")).await.unwrap();
+        let run = core.run_playbook("acme", "contain", None, Value::Null, "analyst").await.unwrap();
+        assert_eq!(run.actions[0].status, ActionStatus::Rejected);
+        assert_eq!(run.execution.step_results[1].status, "Skipped");
+        assert!(!run.execution.overall_success);
+        assert_eq!(action.calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,373 @@
+//! Playbooks as code
+//!
+//! A tenant registers Git repositories of playbooks. Syncing one checks out
+//! its pinned commit or branch head and loads every YAML and JSON file under
+//! `playbooks/` (below the repository's content path) as `SecurityPlaybook`s;
+//! a YAML file may hold several documents and a JSON file an array.
+//! `last_updated` may be left out of the source.
+//!
+//! A playbook needs an id unique across the revision, a name, and at least
+//! one step; step ids must be unique within the playbook. Failures are
+//! reported per file and document. A strict repository loads nothing when
+//! any file fails; otherwise the valid playbooks are saved. Playbooks an
+//! earlier sync saved that the revision no longer contains are deleted once
+//! a revision loads cleanly. The commit whose playbooks are active is
+//! recorded per repository.
+
+use crate::error::{CoreError, CoreResult};
+use crate::secop_core::SecOpCore;
+use crate::SecurityPlaybook;
+use chrono::Utc;
+use phantom_enterprise_standards::rule_sync::{
+    collect_files, FileError, GitCli, GitClient, LoadedItem, RepositoryStatus, RuleRepository, SourceFile, SourceFormat, SyncReport,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+#[cfg(feature = "napi")]
+use crate::secop_core::SecOpCoreNapi;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+#[cfg(feature = "napi")]
+use serde_json::json;
+
+/// Directory of the repository content holding playbooks
+pub const PLAYBOOKS_DIR: &str = "playbooks";
+
+pub struct PlaybookSyncState {
+    git: RwLock<Arc<dyn GitClient>>,
+    /// Registered repositories keyed by tenant and repository id. Held for
+    /// the whole of a sync, so syncs never share a working copy.
+    repositories: tokio::sync::Mutex<HashMap<(String, String), RepositoryStatus>>,
+}
+
+impl Default for PlaybookSyncState {
+    fn default() -> Self {
+        Self { git: RwLock::new(Arc::new(GitCli::default())), repositories: tokio::sync::Mutex::new(HashMap::new()) }
+    }
+}
+
+impl PlaybookSyncState {
+    fn git(&self) -> Arc<dyn GitClient> {
+        self.git.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+fn playbook_from_value(mut item: Value) -> Result<SecurityPlaybook, String> {
+    let Some(fields) = item.as_object_mut() else {
+        return Err("Playbook must be a mapping".to_string());
+    };
+    fields.entry("last_updated").or_insert_with(|| Value::String(Utc::now().to_rfc3339()));
+    let playbook: SecurityPlaybook = serde_json::from_value(item).map_err(|e| format!("Invalid playbook: {}", e))?;
+
+    if playbook.playbook_id.trim().is_empty() {
+        return Err("Playbook id is required".to_string());
+    }
+    if playbook.name.trim().is_empty() {
+        return Err(format!("Playbook {} has no name", playbook.playbook_id));
+    }
+    if playbook.steps.is_empty() {
+        return Err(format!("Playbook {} has no steps", playbook.playbook_id));
+    }
+    let mut step_ids = HashSet::new();
+    for step in &playbook.steps {
+        if step.step_id.trim().is_empty() {
+            return Err(format!("Playbook {} has a step without an id", playbook.playbook_id));
+        }
+        if !step_ids.insert(step.step_id.as_str()) {
+            return Err(format!("Playbook {} repeats step {}", playbook.playbook_id, step.step_id));
+        }
+    }
+    Ok(playbook)
+}
+
+/// Playbooks of one content file, each with its document index or the
+/// reason it could not be read
+fn parse_playbook_file(file: &SourceFile) -> Vec<(Option<usize>, Result<SecurityPlaybook, String>)> {
+    match file.format {
+        SourceFormat::Json => match serde_json::from_str::<Value>(&file.content) {
+            Ok(Value::Array(items)) => {
+                items.into_iter().enumerate().map(|(index, item)| (Some(index), playbook_from_value(item))).collect()
+            }
+            Ok(item) => vec![(None, playbook_from_value(item))],
+            Err(e) => vec![(None, Err(format!("Invalid JSON: {}", e)))],
+        },
+        SourceFormat::Yaml => {
+            let mut playbooks = Vec::new();
+            for (index, document) in serde_yaml::Deserializer::from_str(&file.content).enumerate() {
+                match Value::deserialize(document) {
+                    Ok(Value::Null) => continue,
+                    Ok(item) => playbooks.push((Some(index), playbook_from_value(item))),
+                    Err(e) => {
+                        playbooks.push((Some(index), Err(format!("Invalid YAML: {}", e))));
+                        break;
+                    }
+                }
+            }
+            playbooks
+        }
+    }
+}
+
+impl SecOpCore {
+    /// Replace the Git client syncs check out repositories with
+    pub fn set_git_client(&self, git: Arc<dyn GitClient>) {
+        *self.playbook_sync.git.write().unwrap_or_else(|e| e.into_inner()) = git;
+    }
+
+    /// Register a playbook repository, or update its settings keeping the active commit
+    pub async fn register_playbook_repository(&self, tenant_id: &str, repository: RuleRepository) -> CoreResult<RepositoryStatus> {
+        repository.validate().map_err(CoreError::validation)?;
+        let mut repositories = self.playbook_sync.repositories.lock().await;
+        let status = repositories
+            .entry((tenant_id.to_string(), repository.repository_id.clone()))
+            .or_insert_with(|| RepositoryStatus::new(repository.clone(), tenant_id));
+        status.repository = repository;
+        Ok(status.clone())
+    }
+
+    /// Stop tracking a repository; playbooks it saved stay in place
+    pub async fn remove_playbook_repository(&self, tenant_id: &str, repository_id: &str) -> bool {
+        self.playbook_sync.repositories.lock().await.remove(&(tenant_id.to_string(), repository_id.to_string())).is_some()
+    }
+
+    pub async fn list_playbook_repositories(&self, tenant_id: &str) -> Vec<RepositoryStatus> {
+        let mut statuses: Vec<RepositoryStatus> =
+            self.playbook_sync.repositories.lock().await.values().filter(|status| status.tenant_id == tenant_id).cloned().collect();
+        statuses.sort_by(|a, b| a.repository.repository_id.cmp(&b.repository.repository_id));
+        statuses
+    }
+
+    /// Check out a repository and load its playbooks. Checkout failures and
+    /// invalid files are reported, not returned as errors.
+    pub async fn sync_playbook_repository(&self, tenant_id: &str, repository_id: &str) -> CoreResult<SyncReport> {
+        let mut repositories = self.playbook_sync.repositories.lock().await;
+        let status = repositories
+            .get_mut(&(tenant_id.to_string(), repository_id.to_string()))
+            .ok_or_else(|| CoreError::not_found(format!("Playbook repository {} not found", repository_id)))?;
+        let repository = status.repository.clone();
+        let mut report = SyncReport::new(&repository, status.active_commit.clone());
+
+        let checkout = repository.working_dir(&format!("secop-{}", tenant_id));
+        match self.playbook_sync.git().checkout(&repository, &checkout).await {
+            Ok(commit) => report.commit_sha = Some(commit),
+            Err(e) => {
+                log::warn!("Failed to check out playbook repository {} for tenant {}: {}", repository_id, tenant_id, e);
+                report.error = Some(e);
+                let report = report.finish(false);
+                status.last_sync = Some(report.clone());
+                return Ok(report);
+            }
+        }
+
+        let (files, errors) = collect_files(&repository.content_dir(&checkout, PLAYBOOKS_DIR));
+        report.errors = errors;
+        let mut parsed: Vec<(String, SecurityPlaybook)> = Vec::new();
+        let mut defined_in: HashMap<String, String> = HashMap::new();
+        for file in &files {
+            for (document, playbook) in parse_playbook_file(file) {
+                let playbook = playbook.and_then(|playbook| match defined_in.get(&playbook.playbook_id) {
+                    Some(first) => Err(format!("Playbook {} is already defined in {}", playbook.playbook_id, first)),
+                    None => Ok(playbook),
+                });
+                match playbook {
+                    Ok(playbook) => {
+                        defined_in.insert(playbook.playbook_id.clone(), file.path.clone());
+                        parsed.push((file.path.clone(), playbook));
+                    }
+                    Err(error) => report.errors.push(FileError::new(&file.path, document, error)),
+                }
+            }
+        }
+        if !report.may_apply(&repository) {
+            let report = report.finish(false);
+            status.last_sync = Some(report.clone());
+            return Ok(report);
+        }
+
+        let mut managed: HashSet<String> = HashSet::new();
+        {
+            let mut library = self.playbooks.write().await;
+            for (path, playbook) in parsed {
+                let id = playbook.playbook_id.clone();
+                let replaced = library.get(tenant_id, &id).is_some();
+                match library.save(tenant_id, playbook) {
+                    Ok(_) => {
+                        report.loaded.push(LoadedItem { path, id: id.clone(), replaced });
+                        managed.insert(id);
+                    }
+                    Err(error) => report.errors.push(FileError::new(&path, None, error)),
+                }
+            }
+            for playbook_id in std::mem::take(&mut status.managed_ids) {
+                if managed.contains(&playbook_id) {
+                    continue;
+                }
+                if !report.errors.is_empty() {
+                    managed.insert(playbook_id);
+                } else if library.remove(tenant_id, &playbook_id) {
+                    report.removed.push(playbook_id);
+                }
+            }
+        }
+
+        let report = report.finish(true);
+        log::info!(
+            "Synced playbook repository {} at {} for tenant {}: {} loaded, {} removed, {} errors",
+            repository_id,
+            report.commit_sha.as_deref().unwrap_or_default(),
+            tenant_id,
+            report.loaded.len(),
+            report.removed.len(),
+            report.errors.len()
+        );
+        status.managed_ids = managed.into_iter().collect();
+        status.managed_ids.sort();
+        status.active_commit = report.commit_sha.clone();
+        status.synced_at = Some(Utc::now());
+        status.last_sync = Some(report.clone());
+        Ok(report)
+    }
+
+    pub(crate) async fn forget_playbook_repositories(&self, tenant_id: &str) -> usize {
+        let mut repositories = self.playbook_sync.repositories.lock().await;
+        let before = repositories.len();
+        repositories.retain(|(tenant, _), _| tenant != tenant_id);
+        before - repositories.len()
+    }
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl SecOpCoreNapi {
+    /// Register a Git repository of playbooks (JSON `RuleRepository`)
+    #[napi]
+    pub async fn register_playbook_repository(&self, repository_json: String, auth_token: Option<String>) -> napi::Result<String> {
+        let repository: RuleRepository = serde_json::from_str(&repository_json)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid playbook repository: {}", e)))?;
+        let repository_id = repository.repository_id.clone();
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &repository_id)?;
+        let params = json!({ "url": repository.url, "branch": repository.branch, "pinned_commit": repository.pinned_commit });
+        let status = self.inner.register_playbook_repository(&tenant_id, repository).await;
+        let status = self.audit.record(&actor, "register_playbook_repository", &repository_id, params, status)
+            .map_err(|e| e.context("Failed to register playbook repository"))?;
+        serde_json::to_string(&status)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Stop syncing a repository; the playbooks it saved are kept
+    #[napi]
+    pub async fn remove_playbook_repository(&self, repository_id: String, auth_token: Option<String>) -> napi::Result<bool> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &repository_id)?;
+        let removed = self.inner.remove_playbook_repository(&tenant_id, &repository_id).await;
+        self.audit.record(&actor, "remove_playbook_repository", &repository_id, json!({}), Ok::<_, String>(removed))
+            .map_err(napi::Error::from_reason)
+    }
+
+    /// Registered repositories with the commit active in the engine and the last sync report
+    #[napi]
+    pub async fn list_playbook_repositories(&self, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        self.authorize(auth_token, "read", "playbook_repositories")?;
+        serde_json::to_string(&self.inner.list_playbook_repositories(&tenant_id).await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Pull a repository and load its playbooks; the report lists every file that failed validation
+    #[napi]
+    pub async fn sync_playbook_repository(&self, repository_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &repository_id)?;
+        let report = self.inner.sync_playbook_repository(&tenant_id, &repository_id).await;
+        let params = json!({
+            "commit_sha": report.as_ref().ok().and_then(|report| report.commit_sha.clone()),
+            "outcome": report.as_ref().ok().map(|report| report.outcome),
+        });
+        let report = self.audit.record(&actor, "sync_playbook_repository", &repository_id, params, report)
+            .map_err(|e| e.context("Failed to sync playbook repository"))?;
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use phantom_enterprise_standards::rule_sync::SyncOutcome;
+    use std::path::Path;
+
+    struct FakeGit;
+
+    #[async_trait]
+    impl GitClient for FakeGit {
+        async fn checkout(&self, repository: &RuleRepository, dir: &Path) -> Result<String, String> {
+            let playbooks = dir.join("content").join(PLAYBOOKS_DIR);
+            std::fs::create_dir_all(&playbooks).unwrap();
+            std::fs::write(
+                playbooks.join("phishing.yml"),
+                r#"
+playbook_id: phishing_response
+name: Phishing Response
+description: Contain a reported phishing email
+version: "2.1"
+category: phishing
+trigger_conditions: [phishing_reported]
+steps:
+  - { step_id: quarantine, name: Quarantine, action_type: email_quarantine, description: Pull the message,
+      parameters: {}, timeout: 300, required: true, automation_supported: true }
+automation_level: high
+estimated_duration: 30
+success_rate: 0.9
+---
+playbook_id: broken
+name: Broken
+description: Repeats a step
+version: "1"
+category: test
+trigger_conditions: []
+steps:
+  - { step_id: a, name: A, action_type: noop, description: "", parameters: {}, timeout: 1, required: false, automation_supported: false }
+  - { step_id: a, name: A, action_type: noop, description: "", parameters: {}, timeout: 1, required: false, automation_supported: false }
+automation_level: low
+estimated_duration: 1
+success_rate: 0.0
+"#,
+            )
+            .unwrap();
+            Ok(repository.pinned_commit.clone().unwrap_or_else(|| "c".repeat(40)))
+        }
+    }
+
+    #[tokio::test]
+    async fn strict_repositories_load_nothing_when_a_playbook_is_invalid() {
+        let core = SecOpCore::new();
+        core.set_git_client(Arc::new(FakeGit));
+        let checkout = std::env::temp_dir().join(format!("secop-playbook-sync-{}", uuid::Uuid::new_v4()));
+        let mut repository: RuleRepository = serde_json::from_value(serde_json::json!({
+            "repository_id": "playbooks", "url": "https://git.example/soc.git", "path": "content",
+            "pinned_commit": "d".repeat(40), "strict": true, "checkout_dir": checkout,
+        }))
+        .unwrap();
+        core.register_playbook_repository("acme", repository.clone()).await.unwrap();
+
+        let report = core.sync_playbook_repository("acme", "playbooks").await.unwrap();
+        assert_eq!(report.outcome, SyncOutcome::Rejected);
+        assert_eq!((report.errors[0].path.as_str(), report.errors[0].document), ("phishing.yml", Some(1)));
+        assert!(core.get_playbook("acme", "phishing_response").await.is_none());
+        assert!(core.list_playbook_repositories("acme").await[0].active_commit.is_none());
+
+        repository.strict = false;
+        core.register_playbook_repository("acme", repository).await.unwrap();
+        let report = core.sync_playbook_repository("acme", "playbooks").await.unwrap();
+        assert_eq!(report.outcome, SyncOutcome::PartiallyApplied);
+        assert_eq!(report.loaded[0].id, "phishing_response");
+        assert_eq!(core.get_playbook("acme", "phishing_response").await.unwrap().steps.len(), 1);
+        assert_eq!(core.list_playbook_repositories("acme").await[0].active_commit, Some("d".repeat(40)));
+        std::fs::remove_dir_all(&checkout).unwrap();
+    }
+}
@@ -0,0 +1,631 @@
+//! Playbook library and marketplace bundles
+//!
+//! Playbooks are stored per tenant. With the `playbook-bundles` feature a
+//! playbook, with its trigger conditions and steps, can be exported as a
+//! portable bundle: a JSON document carrying the playbook, its publisher and
+//! an ed25519 signature over the canonical JSON of both. Importing checks
+//! the signature and that the signing key belongs to a trusted publisher,
+//! then resolves version conflicts with the tenant's playbook of the same
+//! id. The signing key and the trusted publishers are shared by all tenants.
+
+use crate::secop_core::SecOpCore;
+use crate::SecurityPlaybook;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(feature = "playbook-bundles")]
+use base64::Engine;
+#[cfg(feature = "playbook-bundles")]
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+#[cfg(feature = "playbook-bundles")]
+use serde_json::Value;
+
+#[cfg(feature = "napi")]
+use crate::secop_core::SecOpCoreNapi;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+#[cfg(feature = "napi")]
+use serde_json::json;
+
+pub const BUNDLE_FORMAT: &str = "phantom-playbook-bundle/1";
+pub const SIGNATURE_ALGORITHM: &str = "ed25519";
+
+/// Key allowed to sign imported bundles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedPublisher {
+    pub key_id: String,
+    pub name: String,
+    /// Raw ed25519 public key, base64
+    pub public_key: String,
+    pub trusted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleSignature {
+    pub algorithm: String,
+    pub key_id: String,
+    pub public_key: String,
+    pub value: String,
+}
+
+/// Portable, signed playbook
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaybookBundle {
+    pub format: String,
+    pub publisher: String,
+    pub exported_at: DateTime<Utc>,
+    pub playbook: SecurityPlaybook,
+    pub signature: BundleSignature,
+}
+
+/// What to do when the tenant already has a playbook with the bundle's id
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictResolution {
+    /// Replace the playbook only when the bundle carries a higher version
+    #[default]
+    KeepNewer,
+    Overwrite,
+    Skip,
+    /// Import alongside under a new id
+    Copy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStatus {
+    Created,
+    Updated,
+    /// The tenant already has this exact playbook
+    Unchanged,
+    Skipped,
+    Copied,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportOutcome {
+    pub playbook_id: String,
+    pub status: ImportStatus,
+    pub imported_version: String,
+    pub existing_version: Option<String>,
+    pub publisher: String,
+    pub key_id: String,
+}
+
+#[cfg(feature = "playbook-bundles")]
+struct SigningKey {
+    publisher: String,
+    pair: Ed25519KeyPair,
+}
+
+#[derive(Default)]
+pub struct PlaybookLibrary {
+    playbooks: HashMap<String, BTreeMap<String, SecurityPlaybook>>,
+    trusted: BTreeMap<String, TrustedPublisher>,
+    #[cfg(feature = "playbook-bundles")]
+    signing_key: Option<SigningKey>,
+}
+
+impl PlaybookLibrary {
+    pub fn get(&self, tenant_id: &str, playbook_id: &str) -> Option<&SecurityPlaybook> {
+        self.playbooks.get(tenant_id)?.get(playbook_id)
+    }
+
+    pub fn list(&self, tenant_id: &str) -> Vec<SecurityPlaybook> {
+        self.playbooks.get(tenant_id).map(|playbooks| playbooks.values().cloned().collect()).unwrap_or_default()
+    }
+
+    pub fn save(&mut self, tenant_id: &str, playbook: SecurityPlaybook) -> Result<SecurityPlaybook, String> {
+        if playbook.playbook_id.trim().is_empty() {
+            return Err("Playbook id is required".to_string());
+        }
+        self.playbooks.entry(tenant_id.to_string()).or_default().insert(playbook.playbook_id.clone(), playbook.clone());
+        Ok(playbook)
+    }
+
+    pub fn remove(&mut self, tenant_id: &str, playbook_id: &str) -> bool {
+        self.playbooks.get_mut(tenant_id).is_some_and(|playbooks| playbooks.remove(playbook_id).is_some())
+    }
+
+    pub fn forget_tenant(&mut self, tenant_id: &str) -> usize {
+        self.playbooks.remove(tenant_id).map_or(0, |playbooks| playbooks.len())
+    }
+
+    pub fn trusted_publishers(&self) -> Vec<TrustedPublisher> {
+        self.trusted.values().cloned().collect()
+    }
+
+    pub fn revoke_publisher(&mut self, key_id: &str) -> bool {
+        self.trusted.remove(key_id).is_some()
+    }
+
+    /// Store an imported playbook according to `resolution`
+    #[cfg(feature = "playbook-bundles")]
+    fn resolve_import(&mut self, tenant_id: &str, mut playbook: SecurityPlaybook, resolution: ConflictResolution) -> (String, ImportStatus, Option<String>) {
+        let existing = self.get(tenant_id, &playbook.playbook_id).cloned();
+        let status = match &existing {
+            None => ImportStatus::Created,
+            Some(current) if same_content(current, &playbook) => ImportStatus::Unchanged,
+            Some(current) => match resolution {
+                ConflictResolution::KeepNewer if compare_versions(&playbook.version, &current.version) == Ordering::Greater => ImportStatus::Updated,
+                ConflictResolution::KeepNewer | ConflictResolution::Skip => ImportStatus::Skipped,
+                ConflictResolution::Overwrite => ImportStatus::Updated,
+                ConflictResolution::Copy => {
+                    playbook.playbook_id = self.copy_id(tenant_id, &playbook.playbook_id, &playbook.version);
+                    ImportStatus::Copied
+                }
+            },
+        };
+        if matches!(status, ImportStatus::Created | ImportStatus::Updated | ImportStatus::Copied) {
+            self.playbooks.entry(tenant_id.to_string()).or_default().insert(playbook.playbook_id.clone(), playbook.clone());
+        }
+        (playbook.playbook_id, status, existing.map(|current| current.version))
+    }
+
+    #[cfg(feature = "playbook-bundles")]
+    fn copy_id(&self, tenant_id: &str, playbook_id: &str, version: &str) -> String {
+        let base = format!("{}@{}", playbook_id, version);
+        std::iter::once(base.clone())
+            .chain((2..).map(|n| format!("{}-{}", base, n)))
+            .find(|id| self.get(tenant_id, id).is_none())
+            .expect("unbounded candidate ids")
+    }
+}
+
+#[cfg(feature = "playbook-bundles")]
+fn same_content(a: &SecurityPlaybook, b: &SecurityPlaybook) -> bool {
+    match (serde_json::to_value(a), serde_json::to_value(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Compare dotted versions numerically (`1.10` > `1.9`), falling back to
+/// text for non-numeric parts
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let parts = |v: &str| v.trim_start_matches(['v', 'V']).split(['.', '-']).map(str::to_string).collect::<Vec<_>>();
+    let (a, b) = (parts(a), parts(b));
+    for i in 0..a.len().max(b.len()) {
+        let (x, y) = (a.get(i).map(String::as_str).unwrap_or("0"), b.get(i).map(String::as_str).unwrap_or("0"));
+        let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            _ => x.cmp(y),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    Ordering::Equal
+}
+
+/// JSON with object keys sorted at every level, so the signed bytes do not
+/// depend on map iteration order
+#[cfg(feature = "playbook-bundles")]
+pub fn canonical_json(value: &Value) -> String {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let fields: Vec<String> = keys
+                .into_iter()
+                .map(|key| format!("{}:{}", Value::String(key.clone()), canonical_json(&map[key])))
+                .collect();
+            format!("{{{}}}", fields.join(","))
+        }
+        Value::Array(items) => format!("[{}]", items.iter().map(canonical_json).collect::<Vec<_>>().join(",")),
+        scalar => scalar.to_string(),
+    }
+}
+
+#[cfg(feature = "playbook-bundles")]
+fn signed_payload(format: &str, publisher: &str, exported_at: &DateTime<Utc>, playbook: &SecurityPlaybook) -> String {
+    let payload = serde_json::json!({
+        "format": format,
+        "publisher": publisher,
+        "exported_at": exported_at,
+        "playbook": playbook,
+    });
+    canonical_json(&payload)
+}
+
+#[cfg(feature = "playbook-bundles")]
+fn b64() -> base64::engine::GeneralPurpose {
+    base64::engine::general_purpose::STANDARD
+}
+
+/// Short, stable id of a public key: the first 8 bytes of its SHA-256, hex
+#[cfg(feature = "playbook-bundles")]
+pub fn key_id(public_key: &[u8]) -> String {
+    ring::digest::digest(&ring::digest::SHA256, public_key).as_ref()[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// New ed25519 signing key as base64 PKCS#8, to be stored by the operator
+#[cfg(feature = "playbook-bundles")]
+pub fn generate_signing_key() -> Result<String, String> {
+    let pkcs8 = Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new())
+        .map_err(|_| "Failed to generate signing key".to_string())?;
+    Ok(b64().encode(pkcs8.as_ref()))
+}
+
+#[cfg(feature = "playbook-bundles")]
+impl PlaybookLibrary {
+    /// Sign exported bundles with `pkcs8` (base64) as `publisher`; the key is
+    /// also trusted for imports
+    pub fn set_signing_key(&mut self, publisher: &str, pkcs8: &str) -> Result<TrustedPublisher, String> {
+        let der = b64().decode(pkcs8.trim()).map_err(|e| format!("Invalid signing key encoding: {}", e))?;
+        let pair = Ed25519KeyPair::from_pkcs8_maybe_unchecked(&der).map_err(|e| format!("Invalid ed25519 signing key: {}", e))?;
+        let public_key = b64().encode(pair.public_key().as_ref());
+        let trusted = self.trust_publisher(publisher, &public_key)?;
+        self.signing_key = Some(SigningKey { publisher: publisher.to_string(), pair });
+        Ok(trusted)
+    }
+
+    pub fn signing_publisher(&self) -> Option<TrustedPublisher> {
+        let key = self.signing_key.as_ref()?;
+        self.trusted.get(&key_id(key.pair.public_key().as_ref())).cloned()
+    }
+
+    /// Accept bundles signed by `public_key` (raw ed25519, base64)
+    pub fn trust_publisher(&mut self, name: &str, public_key: &str) -> Result<TrustedPublisher, String> {
+        let raw = b64().decode(public_key.trim()).map_err(|e| format!("Invalid public key encoding: {}", e))?;
+        if raw.len() != 32 {
+            return Err(format!("An ed25519 public key has 32 bytes, got {}", raw.len()));
+        }
+        let publisher = TrustedPublisher {
+            key_id: key_id(&raw),
+            name: name.to_string(),
+            public_key: b64().encode(&raw),
+            trusted_at: Utc::now(),
+        };
+        self.trusted.insert(publisher.key_id.clone(), publisher.clone());
+        Ok(publisher)
+    }
+
+    pub fn export_bundle(&self, tenant_id: &str, playbook_id: &str) -> Result<PlaybookBundle, String> {
+        let key = self.signing_key.as_ref().ok_or("No playbook signing key is configured")?;
+        let playbook = self.get(tenant_id, playbook_id).cloned().ok_or_else(|| format!("Playbook {} not found", playbook_id))?;
+        let exported_at = Utc::now();
+        let payload = signed_payload(BUNDLE_FORMAT, &key.publisher, &exported_at, &playbook);
+        let public_key = key.pair.public_key().as_ref();
+        Ok(PlaybookBundle {
+            format: BUNDLE_FORMAT.to_string(),
+            publisher: key.publisher.clone(),
+            exported_at,
+            playbook,
+            signature: BundleSignature {
+                algorithm: SIGNATURE_ALGORITHM.to_string(),
+                key_id: key_id(public_key),
+                public_key: b64().encode(public_key),
+                value: b64().encode(key.pair.sign(payload.as_bytes()).as_ref()),
+            },
+        })
+    }
+
+    /// Check that `bundle` is intact and signed by a trusted publisher
+    pub fn verify_bundle(&self, bundle: &PlaybookBundle) -> Result<&TrustedPublisher, String> {
+        if bundle.format != BUNDLE_FORMAT {
+            return Err(format!("Unsupported bundle format: {}", bundle.format));
+        }
+        let signature = &bundle.signature;
+        if signature.algorithm != SIGNATURE_ALGORITHM {
+            return Err(format!("Unsupported signature algorithm: {}", signature.algorithm));
+        }
+        let public_key = b64().decode(&signature.public_key).map_err(|e| format!("Invalid bundle public key: {}", e))?;
+        let publisher = self
+            .trusted
+            .get(&key_id(&public_key))
+            .filter(|publisher| publisher.key_id == signature.key_id)
+            .ok_or_else(|| format!("Bundle is signed by untrusted key {}", signature.key_id))?;
+        let value = b64().decode(&signature.value).map_err(|e| format!("Invalid bundle signature: {}", e))?;
+        let payload = signed_payload(&bundle.format, &bundle.publisher, &bundle.exported_at, &bundle.playbook);
+        UnparsedPublicKey::new(&ED25519, &public_key)
+            .verify(payload.as_bytes(), &value)
+            .map_err(|_| "Bundle signature does not match its contents".to_string())?;
+        Ok(publisher)
+    }
+
+    pub fn import_bundle(&mut self, tenant_id: &str, bundle: PlaybookBundle, resolution: ConflictResolution) -> Result<ImportOutcome, String> {
+        let key_id = self.verify_bundle(&bundle)?.key_id.clone();
+        if bundle.playbook.playbook_id.trim().is_empty() {
+            return Err("Playbook id is required".to_string());
+        }
+        let imported_version = bundle.playbook.version.clone();
+        let (playbook_id, status, existing_version) = self.resolve_import(tenant_id, bundle.playbook, resolution);
+        Ok(ImportOutcome { playbook_id, status, imported_version, existing_version, publisher: bundle.publisher, key_id })
+    }
+}
+
+impl SecOpCore {
+    /// Create or replace a playbook of `tenant_id`
+    pub async fn save_playbook(&self, tenant_id: &str, mut playbook: SecurityPlaybook) -> Result<SecurityPlaybook, String> {
+        playbook.last_updated = Utc::now();
+        self.playbooks.write().await.save(tenant_id, playbook)
+    }
+
+    pub async fn get_playbook(&self, tenant_id: &str, playbook_id: &str) -> Option<SecurityPlaybook> {
+        self.playbooks.read().await.get(tenant_id, playbook_id).cloned()
+    }
+
+    pub async fn list_playbooks(&self, tenant_id: &str) -> Vec<SecurityPlaybook> {
+        self.playbooks.read().await.list(tenant_id)
+    }
+
+    pub async fn delete_playbook(&self, tenant_id: &str, playbook_id: &str) -> bool {
+        self.playbooks.write().await.remove(tenant_id, playbook_id)
+    }
+
+    pub async fn list_trusted_publishers(&self) -> Vec<TrustedPublisher> {
+        self.playbooks.read().await.trusted_publishers()
+    }
+
+    pub async fn revoke_playbook_publisher(&self, key_id: &str) -> bool {
+        self.playbooks.write().await.revoke_publisher(key_id)
+    }
+}
+
+#[cfg(feature = "playbook-bundles")]
+impl SecOpCore {
+    pub async fn set_playbook_signing_key(&self, publisher: &str, pkcs8: &str) -> Result<TrustedPublisher, String> {
+        self.playbooks.write().await.set_signing_key(publisher, pkcs8)
+    }
+
+    pub async fn trust_playbook_publisher(&self, name: &str, public_key: &str) -> Result<TrustedPublisher, String> {
+        self.playbooks.write().await.trust_publisher(name, public_key)
+    }
+
+    pub async fn export_playbook_bundle(&self, tenant_id: &str, playbook_id: &str) -> Result<PlaybookBundle, String> {
+        self.playbooks.read().await.export_bundle(tenant_id, playbook_id)
+    }
+
+    pub async fn import_playbook_bundle(&self, tenant_id: &str, bundle: PlaybookBundle, resolution: ConflictResolution) -> Result<ImportOutcome, String> {
+        self.playbooks.write().await.import_bundle(tenant_id, bundle, resolution)
+    }
+}
+
+/// Request body for `configure_playbook_signing`
+#[cfg(all(feature = "napi", feature = "playbook-bundles"))]
+#[derive(Deserialize)]
+struct SigningKeyRequest {
+    publisher: String,
+    /// Base64 PKCS#8; a new key is generated when absent
+    #[serde(default)]
+    private_key: Option<String>,
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl SecOpCoreNapi {
+    /// Create or replace a playbook
+    #[napi]
+    pub async fn save_playbook(&self, playbook_data: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let playbook: SecurityPlaybook = serde_json::from_str(&playbook_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid playbook data: {}", e)))?;
+        let actor = self.authorize(auth_token, "rule:manage", &playbook.playbook_id)?;
+        let playbook_id = playbook.playbook_id.clone();
+        let saved = self.inner.save_playbook(&tenant_id, playbook).await;
+        let saved = self.audit.record(&actor, "save_playbook", &playbook_id, json!({ "playbook": playbook_data }), saved)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to save playbook: {}", e)))?;
+
+        serde_json::to_string(&saved)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn get_playbook(&self, playbook_id: String, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        self.authorize(auth_token, "read", &playbook_id)?;
+        self.inner
+            .get_playbook(&tenant_id, &playbook_id)
+            .await
+            .map(|playbook| serde_json::to_string(&playbook))
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn list_playbooks(&self, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        self.authorize(auth_token, "read", "playbooks")?;
+        serde_json::to_string(&self.inner.list_playbooks(&tenant_id).await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn delete_playbook(&self, playbook_id: String, auth_token: Option<String>) -> napi::Result<bool> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", &playbook_id)?;
+        let deleted = self.inner.delete_playbook(&tenant_id, &playbook_id).await;
+        self.audit.record(&actor, "delete_playbook", &playbook_id, json!({}), Ok::<_, String>(deleted))
+            .map_err(napi::Error::from_reason)
+    }
+
+    /// Publishers whose signed bundles are accepted on import
+    #[napi]
+    pub async fn list_trusted_playbook_publishers(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize(auth_token, "read", "playbook_publishers")?;
+        serde_json::to_string(&self.inner.list_trusted_publishers().await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn revoke_playbook_publisher(&self, key_id: String, auth_token: Option<String>) -> napi::Result<bool> {
+        let actor = self.authorize(auth_token, "access:manage", "playbook_publishers")?;
+        let revoked = self.inner.revoke_playbook_publisher(&key_id).await;
+        self.audit.record(&actor, "revoke_playbook_publisher", &key_id, json!({}), Ok::<_, String>(revoked))
+            .map_err(napi::Error::from_reason)
+    }
+}
+
+#[cfg(all(feature = "napi", feature = "playbook-bundles"))]
+#[napi]
+impl SecOpCoreNapi {
+    /// Set the key exported bundles are signed with; with no `private_key`
+    /// a new key is generated and returned once, for the operator to keep
+    #[napi]
+    pub async fn configure_playbook_signing(&self, key_data: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize(auth_token, "access:manage", "playbook_publishers")?;
+        let request: SigningKeyRequest = serde_json::from_str(&key_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid signing key request: {}", e)))?;
+        let generated = request.private_key.is_none();
+        let result = async {
+            let private_key = match request.private_key {
+                Some(key) => key,
+                None => generate_signing_key()?,
+            };
+            let publisher = self.inner.set_playbook_signing_key(&request.publisher, &private_key).await?;
+            Ok::<_, String>(json!({
+                "publisher": publisher,
+                "private_key": generated.then_some(private_key),
+            }))
+        }
+        .await;
+        let params = json!({ "publisher": request.publisher, "generated": generated });
+        let result = self.audit.record(&actor, "configure_playbook_signing", "playbook_publishers", params, result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to configure playbook signing: {}", e)))?;
+
+        serde_json::to_string(&result)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Accept bundles signed with an ed25519 public key (base64)
+    #[napi]
+    pub async fn trust_playbook_publisher(&self, name: String, public_key: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize(auth_token, "access:manage", "playbook_publishers")?;
+        let publisher = self.inner.trust_playbook_publisher(&name, &public_key).await;
+        let params = json!({ "name": name, "public_key": public_key });
+        let publisher = self.audit.record(&actor, "trust_playbook_publisher", "playbook_publishers", params, publisher)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to trust publisher: {}", e)))?;
+
+        serde_json::to_string(&publisher)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Export a playbook as a signed bundle
+    #[napi]
+    pub async fn export_playbook_bundle(&self, playbook_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "read", &playbook_id)?;
+        let bundle = self.inner.export_playbook_bundle(&tenant_id, &playbook_id).await;
+        let bundle = self.audit.record(&actor, "export_playbook_bundle", &playbook_id, json!({}), bundle)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to export playbook: {}", e)))?;
+
+        serde_json::to_string_pretty(&bundle)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Verify and import a signed bundle; `resolution` is one of
+    /// `keep_newer` (default), `overwrite`, `skip` or `copy`
+    #[napi]
+    pub async fn import_playbook_bundle(&self, bundle_data: String, resolution: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "rule:manage", "playbooks")?;
+        let bundle: PlaybookBundle = serde_json::from_str(&bundle_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid playbook bundle: {}", e)))?;
+        let resolution: ConflictResolution = resolution
+            .map(|r| serde_json::from_value(serde_json::Value::String(r)))
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Invalid conflict resolution: {}", e)))?
+            .unwrap_or_default();
+        let params = json!({
+            "playbook_id": bundle.playbook.playbook_id,
+            "version": bundle.playbook.version,
+            "publisher": bundle.publisher,
+            "key_id": bundle.signature.key_id,
+            "resolution": resolution,
+        });
+        let outcome = self.inner.import_playbook_bundle(&tenant_id, bundle, resolution).await;
+        let outcome = self.audit.record(&actor, "import_playbook_bundle", "playbooks", params, outcome)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to import playbook bundle: {}", e)))?;
+
+        serde_json::to_string(&outcome)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "playbook-bundles")]
+    fn playbook(id: &str, version: &str) -> SecurityPlaybook {
+        serde_json::from_value(serde_json::json!({
+            "playbook_id": id, "name": "Contain ransomware", "description": "Isolate and collect",
+            "version": version, "category": "containment",
+            "trigger_conditions": ["alert.rule_id == 'R-RANSOM'"],
+            "steps": [{
+                "step_id": "isolate", "name": "Isolate host", "action_type": "edr.isolate",
+                "description": "Network isolate the host",
+                "parameters": { "host": "{{alert.asset}}", "mode": "full", "notify": "soc" },
+                "timeout": 300, "required": true, "automation_supported": true
+            }],
+            "automation_level": "semi", "estimated_duration": 30, "success_rate": 0.93,
+            "last_updated": "2025-01-01T00:00:00Z"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn versions_compare_numerically() {
+        assert_eq!(compare_versions("1.10.0", "1.9"), Ordering::Greater);
+        assert_eq!(compare_versions("v2.0", "2"), Ordering::Equal);
+        assert_eq!(compare_versions("1.0-beta", "1.0-alpha"), Ordering::Greater);
+    }
+
+    #[cfg(feature = "playbook-bundles")]
+    #[tokio::test]
+    async fn signed_bundles_move_between_deployments() {
+        let source = SecOpCore::new();
+        let publisher = source.set_playbook_signing_key("Acme SOC", &generate_signing_key().unwrap()).await.unwrap();
+        source.save_playbook("acme", playbook("PB-RANSOM", "1.2.0")).await.unwrap();
+        let bundle = source.export_playbook_bundle("acme", "PB-RANSOM").await.unwrap();
+        let wire = serde_json::to_string(&bundle).unwrap();
+
+        let target = SecOpCore::new();
+        let untrusted = target.import_playbook_bundle("globex", serde_json::from_str(&wire).unwrap(), ConflictResolution::KeepNewer).await;
+        assert!(untrusted.unwrap_err().contains("untrusted key"));
+
+        target.trust_playbook_publisher("Acme SOC", &publisher.public_key).await.unwrap();
+        let outcome = target.import_playbook_bundle("globex", serde_json::from_str(&wire).unwrap(), ConflictResolution::KeepNewer).await.unwrap();
+        assert_eq!(outcome.status, ImportStatus::Created);
+        assert_eq!(outcome.key_id, publisher.key_id);
+        let imported = target.get_playbook("globex", "PB-RANSOM").await.unwrap();
+        assert_eq!(imported.steps[0].parameters["host"], "{{alert.asset}}");
+        assert_eq!(imported.trigger_conditions, bundle.playbook.trigger_conditions);
+
+        let mut tampered = bundle.clone();
+        tampered.playbook.steps[0].action_type = "shell.exec".to_string();
+        let rejected = target.import_playbook_bundle("globex", tampered, ConflictResolution::Overwrite).await;
+        assert!(rejected.unwrap_err().contains("does not match"));
+
+        target.revoke_playbook_publisher(&publisher.key_id).await;
+        assert!(target.import_playbook_bundle("globex", bundle, ConflictResolution::Overwrite).await.is_err());
+    }
+
+    #[cfg(feature = "playbook-bundles")]
+    #[tokio::test]
+    async fn version_conflicts_follow_the_resolution() {
+        let core = SecOpCore::new();
+        core.set_playbook_signing_key("Acme SOC", &generate_signing_key().unwrap()).await.unwrap();
+        core.save_playbook("acme", playbook("PB-1", "1.0.0")).await.unwrap();
+        let v1 = core.export_playbook_bundle("acme", "PB-1").await.unwrap();
+        core.save_playbook("acme", playbook("PB-1", "1.1.0")).await.unwrap();
+        let v1_1 = core.export_playbook_bundle("acme", "PB-1").await.unwrap();
+        core.save_playbook("globex", v1.playbook.clone()).await.unwrap();
+
+        let import = |bundle: &PlaybookBundle, resolution| core.import_playbook_bundle("acme", bundle.clone(), resolution);
+        assert_eq!(import(&v1_1, ConflictResolution::KeepNewer).await.unwrap().status, ImportStatus::Unchanged);
+        let older = import(&v1, ConflictResolution::KeepNewer).await.unwrap();
+        assert_eq!((older.status, older.existing_version.as_deref()), (ImportStatus::Skipped, Some("1.1.0")));
+
+        let copied = import(&v1, ConflictResolution::Copy).await.unwrap();
+        assert_eq!((copied.status, copied.playbook_id.as_str()), (ImportStatus::Copied, "PB-1@1.0.0"));
+        assert_eq!(import(&v1, ConflictResolution::Copy).await.unwrap().playbook_id, "PB-1@1.0.0-2");
+
+        assert_eq!(import(&v1, ConflictResolution::Overwrite).await.unwrap().status, ImportStatus::Updated);
+        assert_eq!(core.get_playbook("acme", "PB-1").await.unwrap().version, "1.0.0");
+        assert_eq!(core.list_playbooks("globex").await.len(), 1, "imports stay in the caller's tenant");
+    }
+}
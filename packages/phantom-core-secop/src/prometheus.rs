@@ -0,0 +1,208 @@
+//! Prometheus Metrics
+//!
+//! With `phantom-enterprise-standards` enabled, alert intake by triage
+//! disposition, open alerts and incidents, time to resolve, and indicator
+//! matcher lookups and benchmark throughput are exposed in
+//! the Prometheus text format, either through `render_prometheus_metrics` or
+//! an embedded `/metrics` endpoint. Samples carry `core="secop"`, the
+//! `tenant` and, for alerts, the detection source as `engine`. Without the
+//! feature nothing is collected.
+
+use crate::triage::TriageDisposition;
+use crate::{SecurityAlert, SecurityIncident};
+use chrono::{DateTime, Utc};
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::secop_core::SecOpCore;
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::prometheus::{labels, CounterSet, HistogramSet, PrometheusWriter};
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::collections::BTreeMap;
+
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+use crate::secop_core::SecOpCoreNapi;
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+use napi_derive::napi;
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+use phantom_enterprise_standards::prometheus::{serve_metrics, RenderFn};
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+use std::sync::Arc;
+
+/// Time-to-resolve buckets in seconds, from 5 minutes to a week
+pub const RESOLVE_BUCKETS: &[f64] = &[300.0, 900.0, 1800.0, 3600.0, 14400.0, 28800.0, 86400.0, 259200.0, 604800.0];
+
+pub struct PrometheusState {
+    #[cfg(feature = "phantom-enterprise-standards")]
+    alerts: CounterSet,
+    #[cfg(feature = "phantom-enterprise-standards")]
+    time_to_resolve: HistogramSet,
+    #[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+    endpoint: std::sync::Mutex<Option<tokio::task::JoinHandle<()>>>,
+}
+
+// Derivable only when every field is compiled out
+#[cfg_attr(not(feature = "phantom-enterprise-standards"), allow(clippy::derivable_impls))]
+impl Default for PrometheusState {
+    fn default() -> Self {
+        Self {
+            #[cfg(feature = "phantom-enterprise-standards")]
+            alerts: CounterSet::default(),
+            #[cfg(feature = "phantom-enterprise-standards")]
+            time_to_resolve: HistogramSet::new(RESOLVE_BUCKETS),
+            #[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+            endpoint: Default::default(),
+        }
+    }
+}
+
+impl PrometheusState {
+    pub(crate) fn observe_alert(&self, alert: &SecurityAlert, disposition: &TriageDisposition) {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            let disposition = disposition_label(disposition);
+            self.alerts.inc(labels(&[("tenant", &alert.tenant_id), ("engine", &alert.source), ("disposition", disposition)]), 1.0);
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        let _ = (alert, disposition);
+    }
+
+    pub(crate) fn observe_resolution(&self, incident: &SecurityIncident, closed_at: DateTime<Utc>) {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            let seconds = (closed_at - incident.created_at).num_seconds().max(0) as f64;
+            self.time_to_resolve.observe(labels(&[("tenant", &incident.tenant_id)]), seconds);
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        let _ = (incident, closed_at);
+    }
+
+    pub(crate) fn forget_tenant(&self, tenant_id: &str) -> usize {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            self.alerts.forget("tenant", tenant_id) + self.time_to_resolve.forget("tenant", tenant_id)
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        {
+            let _ = tenant_id;
+            0
+        }
+    }
+}
+
+pub fn disposition_label(disposition: &TriageDisposition) -> &'static str {
+    match disposition {
+        TriageDisposition::AutoClose => "auto_close",
+        TriageDisposition::AnalystQueue => "analyst_queue",
+        TriageDisposition::AutoEscalate => "auto_escalate",
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+impl SecOpCore {
+    /// All tenants' SecOp metrics in the Prometheus text format
+    pub async fn render_prometheus_metrics(&self) -> String {
+        let mut writer = PrometheusWriter::for_core("secop");
+        writer.counter_set("phantom_secop_alerts_ingested_total", "Alerts ingested by triage disposition", &self.prometheus.alerts);
+
+        let mut alerts: BTreeMap<(String, String), usize> = BTreeMap::new();
+        for alert in self.alerts.read().await.values() {
+            *alerts.entry((alert.tenant_id.clone(), alert.status.to_lowercase())).or_default() += 1;
+        }
+        for ((tenant, status), count) in &alerts {
+            writer.gauge("phantom_secop_alerts", "Stored alerts by status", &[("tenant", tenant), ("status", status)], *count as f64);
+        }
+
+        let mut incidents: BTreeMap<(String, String, String), usize> = BTreeMap::new();
+        for incident in self.incidents.read().await.values() {
+            let key = (incident.tenant_id.clone(), incident.status.to_lowercase(), incident.severity.to_lowercase());
+            *incidents.entry(key).or_default() += 1;
+        }
+        for ((tenant, status, severity), count) in &incidents {
+            let labels = [("tenant", tenant.as_str()), ("status", status.as_str()), ("severity", severity.as_str())];
+            writer.gauge("phantom_secop_incidents", "Incidents by status and severity", &labels, *count as f64);
+        }
+
+        writer.histogram_set("phantom_secop_time_to_resolve_seconds", "Time from incident creation to close", &self.prometheus.time_to_resolve);
+
+        let matcher = self.indicator_matcher_stats().await;
+        writer.gauge("phantom_secop_ioc_matcher_indicators", "Indicators compiled into the matcher", &[], matcher.indicators as f64);
+        let totals = &matcher.totals;
+        writer.counter("phantom_secop_ioc_lookups_total", "Values looked up by batch matching", &[], totals.lookups as f64);
+        writer.counter("phantom_secop_ioc_matches_total", "Looked up values that matched an indicator", &[], totals.matched as f64);
+        writer.counter("phantom_secop_ioc_bloom_rejections_total", "Probes answered by the bloom filter alone", &[], totals.bloom_rejected as f64);
+        writer.counter("phantom_secop_ioc_bloom_false_positives_total", "Probes the bloom filter passed but verification refused", &[], totals.false_positives as f64);
+        if let Some(benchmark) = &matcher.last_benchmark {
+            writer.gauge("phantom_secop_ioc_matcher_lookups_per_second", "Lookup throughput of the last matcher benchmark", &[], benchmark.lookups_per_sec);
+        }
+        writer.finish()
+    }
+}
+
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+#[napi]
+impl SecOpCoreNapi {
+    /// Platform-wide metrics in the Prometheus text exposition format
+    #[napi]
+    pub async fn render_prometheus_metrics(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize_platform(auth_token, "metrics")?;
+        Ok(self.inner.render_prometheus_metrics().await)
+    }
+
+    /// Serve `GET /metrics` on `bind`, e.g. `0.0.0.0:9464`, replacing any
+    /// running endpoint; returns the bound address. Pass no address to stop
+    #[napi]
+    pub async fn start_metrics_endpoint(&self, bind: Option<String>, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        let actor = self.authorize_platform(auth_token, "metrics")?;
+        if let Some(running) = self.inner.prometheus.endpoint.lock().unwrap_or_else(|e| e.into_inner()).take() {
+            running.abort();
+        }
+        let details = serde_json::json!({ "bind": bind });
+        let Some(bind) = bind else {
+            return self.audit.record(&actor, "start_metrics_endpoint", "metrics", details, Ok(None));
+        };
+        let core = self.inner.clone();
+        let render: RenderFn = Arc::new(move || {
+            let core = core.clone();
+            Box::pin(async move { core.render_prometheus_metrics().await })
+        });
+        let served = serve_metrics(&bind, render).await;
+        let (address, handle) = self.audit.record(&actor, "start_metrics_endpoint", "metrics", details, served)
+            .map_err(napi::Error::from_reason)?;
+        *self.inner.prometheus.endpoint.lock().unwrap_or_else(|e| e.into_inner()) = Some(handle);
+        Ok(Some(address.to_string()))
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use super::*;
+
+    fn incident(id: &str, age_minutes: i64) -> SecurityIncident {
+        let created_at = Utc::now() - chrono::Duration::minutes(age_minutes);
+        serde_json::from_value(serde_json::json!({
+            "incident_id": id, "title": "Beacon to known C2", "description": "d", "severity": "High",
+            "status": "Open", "category": "Malware", "priority": 1,
+            "created_at": created_at, "updated_at": created_at, "assigned_to": "soc", "reporter": "edr",
+            "affected_systems": ["web-01"], "indicators": [], "timeline": [],
+            "mitigation_actions": [], "estimated_impact": 1.0, "containment_status": "None"
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn incidents_are_exposed_per_tenant() {
+        let core = SecOpCore::new();
+        core.create_incident("acme", incident("INC-1", 30)).await.unwrap();
+        core.create_incident("acme", incident("INC-2", 90)).await.unwrap();
+        core.close_incident("acme", "INC-1", "done", "analyst").await.unwrap();
+
+        let text = core.render_prometheus_metrics().await;
+        assert!(text.contains("phantom_secop_incidents{core=\"secop\",tenant=\"acme\",status=\"open\",severity=\"high\"} 1\n"), "{}", text);
+        assert!(text.contains("phantom_secop_time_to_resolve_seconds_bucket{core=\"secop\",tenant=\"acme\",le=\"900\"} 0\n"));
+        assert!(text.contains("phantom_secop_time_to_resolve_seconds_bucket{core=\"secop\",tenant=\"acme\",le=\"3600\"} 1\n"));
+        assert!(text.contains("# TYPE phantom_secop_alerts_ingested_total counter\n"));
+
+        core.purge_tenant("acme").await.unwrap();
+        assert!(!core.render_prometheus_metrics().await.contains("tenant=\"acme\""));
+    }
+}
@@ -0,0 +1,553 @@
+//! Incident response readiness
+//!
+//! A point-in-time maturity snapshot of a tenant's deployment, in the spirit
+//! of the NIST SP 800-61 preparation phase. Five dimensions are scored from
+//! 0 to 100: the detection data sources that feed alerts, automated
+//! containment actions, playbook coverage of incident categories, the health
+//! of the open incident and task backlog, and SLA attainment. The overall
+//! score is their weighted mean, and every shortfall is reported as a gap,
+//! most urgent and costliest first, with what to do about it.
+//!
+//! What the assessment expects (data sources, containment actions, incident
+//! categories, backlog thresholds) is configured once for all tenants.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::secop_core::SecOpCore;
+use crate::sla::SlaMetrics;
+use crate::SecurityPlaybook;
+
+#[cfg(feature = "napi")]
+use crate::secop_core::SecOpCoreNapi;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+#[cfg(feature = "napi")]
+use serde_json::json;
+
+/// What a ready deployment is expected to have
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReadinessConfig {
+    /// Alert sources detection relies on; an alert source matches when it contains the name
+    pub expected_data_sources: Vec<String>,
+    /// Days without alerts after which a source counts as silent
+    #[serde(default = "default_data_source_window_days")]
+    pub data_source_window_days: i64,
+    /// Playbook step action types that contain a threat, matched by prefix
+    pub containment_actions: Vec<String>,
+    /// Categories playbooks should cover, besides those of the tenant's incidents
+    pub incident_categories: Vec<String>,
+    /// Hours without an update after which an open incident is stale
+    #[serde(default = "default_stale_after_hours")]
+    pub stale_after_hours: i64,
+}
+
+fn default_data_source_window_days() -> i64 {
+    7
+}
+
+fn default_stale_after_hours() -> i64 {
+    72
+}
+
+impl Default for ReadinessConfig {
+    fn default() -> Self {
+        Self {
+            expected_data_sources: ["edr", "network", "email", "identity"].map(String::from).to_vec(),
+            data_source_window_days: default_data_source_window_days(),
+            containment_actions: ["edr.isolate", "firewall.block", "identity.disable", "email.quarantine"].map(String::from).to_vec(),
+            incident_categories: ["Malware", "Phishing", "Ransomware", "Intrusion", "Data Exfiltration"].map(String::from).to_vec(),
+            stale_after_hours: default_stale_after_hours(),
+        }
+    }
+}
+
+impl ReadinessConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.data_source_window_days <= 0 || self.stale_after_hours <= 0 {
+            return Err("data_source_window_days and stale_after_hours must be positive".to_string());
+        }
+        if self.expected_data_sources.iter().chain(&self.containment_actions).chain(&self.incident_categories).any(|v| v.trim().is_empty()) {
+            return Err("Expected data sources, containment actions and categories cannot be blank".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadinessDimension {
+    DataSources,
+    Containment,
+    PlaybookCoverage,
+    Backlog,
+    SlaAttainment,
+}
+
+impl ReadinessDimension {
+    /// Share of the overall score
+    pub fn weight(&self) -> f64 {
+        match self {
+            ReadinessDimension::DataSources => 0.25,
+            ReadinessDimension::Containment => 0.2,
+            ReadinessDimension::PlaybookCoverage => 0.2,
+            ReadinessDimension::Backlog => 0.15,
+            ReadinessDimension::SlaAttainment => 0.2,
+        }
+    }
+}
+
+/// Maturity tier of the overall score
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MaturityLevel {
+    Initial,
+    Developing,
+    Defined,
+    Managed,
+    Optimized,
+}
+
+impl MaturityLevel {
+    pub fn from_score(score: f64) -> Self {
+        match score {
+            s if s >= 90.0 => MaturityLevel::Optimized,
+            s if s >= 75.0 => MaturityLevel::Managed,
+            s if s >= 60.0 => MaturityLevel::Defined,
+            s if s >= 40.0 => MaturityLevel::Developing,
+            _ => MaturityLevel::Initial,
+        }
+    }
+}
+
+/// Gaps are listed most urgent first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum GapPriority {
+    Critical,
+    High,
+    Medium,
+    Low,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadinessGap {
+    pub dimension: ReadinessDimension,
+    pub priority: GapPriority,
+    pub description: String,
+    pub recommendation: String,
+    /// Overall score points the gap costs
+    pub score_impact: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DimensionScore {
+    pub dimension: ReadinessDimension,
+    pub score: f64,
+    pub weight: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataSourceReadiness {
+    pub name: String,
+    /// Alerts from matching sources within the window
+    pub recent_alerts: usize,
+    pub last_alert_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainmentReadiness {
+    pub action: String,
+    /// Playbooks with an automated step for the action
+    pub automated_in: Vec<String>,
+    /// Playbooks where the action is a manual step
+    pub manual_in: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryCoverage {
+    pub category: String,
+    pub playbooks: Vec<String>,
+    pub open_incidents: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BacklogReadiness {
+    pub open_incidents: usize,
+    pub stale_incidents: usize,
+    pub unassigned_incidents: usize,
+    pub open_tasks: usize,
+    pub overdue_tasks: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadinessAssessment {
+    pub assessment_id: String,
+    pub tenant_id: String,
+    pub assessed_at: DateTime<Utc>,
+    pub overall_score: f64,
+    pub maturity: MaturityLevel,
+    pub dimensions: Vec<DimensionScore>,
+    pub data_sources: Vec<DataSourceReadiness>,
+    /// Enabled threat intel feeds whose last run succeeded, platform-wide
+    pub healthy_intel_feeds: usize,
+    pub containment: Vec<ContainmentReadiness>,
+    pub playbook_coverage: Vec<CategoryCoverage>,
+    pub backlog: BacklogReadiness,
+    pub sla: SlaMetrics,
+    pub gaps: Vec<ReadinessGap>,
+}
+
+fn matches_name(value: &str, name: &str) -> bool {
+    value.to_lowercase().contains(&name.to_lowercase())
+}
+
+fn covers_category(playbook: &SecurityPlaybook, category: &str) -> bool {
+    std::iter::once(&playbook.category).chain(&playbook.trigger_conditions).any(|c| c.eq_ignore_ascii_case(category))
+}
+
+fn share(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        100.0
+    } else {
+        part as f64 * 100.0 / whole as f64
+    }
+}
+
+/// Overall score points lost when `missing` of `total` items of a dimension are missing
+fn impact(dimension: ReadinessDimension, missing: f64, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        dimension.weight() * 100.0 * missing / total as f64
+    }
+}
+
+impl SecOpCore {
+    pub async fn get_readiness_config(&self) -> ReadinessConfig {
+        self.readiness.read().await.clone()
+    }
+
+    pub async fn configure_readiness(&self, config: ReadinessConfig) -> Result<(), String> {
+        config.validate()?;
+        *self.readiness.write().await = config;
+        Ok(())
+    }
+
+    /// Score the tenant's incident response readiness and list its gaps
+    pub async fn get_readiness_assessment(&self, tenant_id: &str) -> Result<ReadinessAssessment, String> {
+        let config = self.get_readiness_config().await;
+        let now = Utc::now();
+        let mut gaps = Vec::new();
+
+        // Detection: alerts from every expected source recently
+        let since = now - Duration::days(config.data_source_window_days);
+        let alerts = self.list_alerts(tenant_id).await;
+        let data_sources: Vec<DataSourceReadiness> = config
+            .expected_data_sources
+            .iter()
+            .map(|name| {
+                let matching: Vec<DateTime<Utc>> = alerts.iter().filter(|a| matches_name(&a.source, name)).map(|a| a.created_at).collect();
+                DataSourceReadiness {
+                    name: name.clone(),
+                    recent_alerts: matching.iter().filter(|at| **at >= since).count(),
+                    last_alert_at: matching.into_iter().max(),
+                }
+            })
+            .collect();
+        let silent: Vec<&DataSourceReadiness> = data_sources.iter().filter(|s| s.recent_alerts == 0).collect();
+        for source in &silent {
+            let description = match source.last_alert_at {
+                Some(at) => format!("No alerts from {} since {}", source.name, at.format("%Y-%m-%d")),
+                None => format!("No alerts have ever come from {}", source.name),
+            };
+            gaps.push(ReadinessGap {
+                dimension: ReadinessDimension::DataSources,
+                priority: GapPriority::High,
+                description,
+                recommendation: format!("Connect or repair the {} integration so its detections reach the SOC", source.name),
+                score_impact: impact(ReadinessDimension::DataSources, 1.0, data_sources.len()),
+            });
+        }
+        let healthy_intel_feeds = self
+            .list_intel_feeds()
+            .await
+            .iter()
+            .filter(|status| status.feed.enabled && status.last_run.as_ref().is_some_and(|run| run.error.is_none()))
+            .count();
+
+        // Containment: each action automated by some playbook
+        let playbooks = self.list_playbooks(tenant_id).await;
+        let containment: Vec<ContainmentReadiness> = config
+            .containment_actions
+            .iter()
+            .map(|action| {
+                let mut readiness = ContainmentReadiness { action: action.clone(), automated_in: Vec::new(), manual_in: Vec::new() };
+                for playbook in &playbooks {
+                    let steps: Vec<_> = playbook.steps.iter().filter(|step| step.action_type.starts_with(action.as_str())).collect();
+                    if steps.iter().any(|step| step.automation_supported) {
+                        readiness.automated_in.push(playbook.playbook_id.clone());
+                    } else if !steps.is_empty() {
+                        readiness.manual_in.push(playbook.playbook_id.clone());
+                    }
+                }
+                readiness
+            })
+            .collect();
+        // A manual-only action counts half
+        let containment_points: f64 = containment
+            .iter()
+            .map(|c| if !c.automated_in.is_empty() { 1.0 } else if !c.manual_in.is_empty() { 0.5 } else { 0.0 })
+            .sum();
+        for action in containment.iter().filter(|c| c.automated_in.is_empty()) {
+            let manual = !action.manual_in.is_empty();
+            gaps.push(ReadinessGap {
+                dimension: ReadinessDimension::Containment,
+                priority: if manual { GapPriority::Medium } else { GapPriority::High },
+                description: if manual {
+                    format!("{} is only a manual step ({})", action.action, action.manual_in.join(", "))
+                } else {
+                    format!("No playbook can run {}", action.action)
+                },
+                recommendation: format!("Add an automated {} step to the containment playbooks", action.action),
+                score_impact: impact(ReadinessDimension::Containment, if manual { 0.5 } else { 1.0 }, containment.len()),
+            });
+        }
+
+        // Playbook coverage of the expected categories and those seen in incidents
+        let incidents = self.list_incidents(tenant_id).await;
+        let open: Vec<_> = incidents.iter().filter(|i| i.status != "Closed" && i.merged_into.is_none()).collect();
+        let mut categories: BTreeMap<String, String> = BTreeMap::new();
+        for category in config.incident_categories.iter().chain(incidents.iter().map(|i| &i.category)).filter(|c| !c.trim().is_empty()) {
+            categories.entry(category.to_lowercase()).or_insert_with(|| category.clone());
+        }
+        let playbook_coverage: Vec<CategoryCoverage> = categories
+            .into_values()
+            .map(|category| CategoryCoverage {
+                playbooks: playbooks.iter().filter(|p| covers_category(p, &category)).map(|p| p.playbook_id.clone()).collect(),
+                open_incidents: open.iter().filter(|i| i.category.eq_ignore_ascii_case(&category)).count(),
+                category,
+            })
+            .collect();
+        let covered = playbook_coverage.iter().filter(|c| !c.playbooks.is_empty()).count();
+        for coverage in playbook_coverage.iter().filter(|c| c.playbooks.is_empty()) {
+            gaps.push(ReadinessGap {
+                dimension: ReadinessDimension::PlaybookCoverage,
+                // Analysts are improvising on open incidents right now
+                priority: if coverage.open_incidents > 0 { GapPriority::Critical } else { GapPriority::Medium },
+                description: match coverage.open_incidents {
+                    0 => format!("No playbook covers {} incidents", coverage.category),
+                    n => format!("No playbook covers {} incidents ({} open)", coverage.category, n),
+                },
+                recommendation: format!("Write or import a {} response playbook", coverage.category),
+                score_impact: impact(ReadinessDimension::PlaybookCoverage, 1.0, playbook_coverage.len()),
+            });
+        }
+
+        // Backlog: open work that is stale, unowned or overdue
+        let stale_before = now - Duration::hours(config.stale_after_hours);
+        let tasks = self.list_tasks(tenant_id, true).await;
+        let backlog = BacklogReadiness {
+            open_incidents: open.len(),
+            stale_incidents: open.iter().filter(|i| i.updated_at < stale_before).count(),
+            unassigned_incidents: open.iter().filter(|i| i.assigned_to.trim().is_empty()).count(),
+            open_tasks: tasks.len(),
+            overdue_tasks: tasks.iter().filter(|t| t.is_overdue(now)).count(),
+        };
+        let unhealthy = open
+            .iter()
+            .filter(|i| i.updated_at < stale_before || i.assigned_to.trim().is_empty())
+            .count()
+            + backlog.overdue_tasks;
+        let work_items = backlog.open_incidents + backlog.open_tasks;
+        for (count, description, recommendation, priority) in [
+            (backlog.stale_incidents, "open incidents without an update", "Review stale incidents and close or re-prioritise them", GapPriority::High),
+            (backlog.unassigned_incidents, "open incidents without an owner", "Assign an owner to every open incident", GapPriority::High),
+            (backlog.overdue_tasks, "overdue SOC tasks", "Reschedule or reassign overdue tasks", GapPriority::Low),
+        ] {
+            if count > 0 {
+                gaps.push(ReadinessGap {
+                    dimension: ReadinessDimension::Backlog,
+                    priority,
+                    description: format!("{} {}", count, description),
+                    recommendation: recommendation.to_string(),
+                    score_impact: impact(ReadinessDimension::Backlog, count as f64, work_items),
+                });
+            }
+        }
+
+        // SLA attainment; without applicable policies there is nothing to attain
+        let sla = self.get_sla_metrics(tenant_id).await?;
+        let policies = self.get_sla_config().await.policies.iter().filter(|p| p.enabled).count();
+        let sla_score = if policies == 0 { 0.0 } else { sla.sla_compliance_rate };
+        if policies == 0 {
+            gaps.push(ReadinessGap {
+                dimension: ReadinessDimension::SlaAttainment,
+                priority: GapPriority::High,
+                description: "No SLA policies are enabled".to_string(),
+                recommendation: "Set acknowledge, contain and resolve targets per severity".to_string(),
+                score_impact: impact(ReadinessDimension::SlaAttainment, 1.0, 1),
+            });
+        } else if sla.incidents_breached > 0 {
+            gaps.push(ReadinessGap {
+                dimension: ReadinessDimension::SlaAttainment,
+                priority: if sla.sla_compliance_rate < 75.0 { GapPriority::Critical } else { GapPriority::Medium },
+                description: format!(
+                    "{} of {} tracked incidents breached an SLA ({:.0}% compliance)",
+                    sla.incidents_breached, sla.incidents_tracked, sla.sla_compliance_rate
+                ),
+                recommendation: "Check staffing and escalation for the breached severities".to_string(),
+                score_impact: impact(ReadinessDimension::SlaAttainment, 100.0 - sla.sla_compliance_rate, 100),
+            });
+        }
+
+        let dimensions: Vec<DimensionScore> = [
+            (ReadinessDimension::DataSources, share(data_sources.len() - silent.len(), data_sources.len())),
+            (ReadinessDimension::Containment, if containment.is_empty() { 100.0 } else { containment_points * 100.0 / containment.len() as f64 }),
+            (ReadinessDimension::PlaybookCoverage, share(covered, playbook_coverage.len())),
+            (ReadinessDimension::Backlog, share(work_items.saturating_sub(unhealthy), work_items)),
+            (ReadinessDimension::SlaAttainment, sla_score),
+        ]
+        .into_iter()
+        .map(|(dimension, score)| DimensionScore { dimension, score: (score * 10.0).round() / 10.0, weight: dimension.weight() })
+        .collect();
+        let overall_score = (dimensions.iter().map(|d| d.score * d.weight).sum::<f64>() * 10.0).round() / 10.0;
+
+        gaps.sort_by(|a, b| a.priority.cmp(&b.priority).then(b.score_impact.total_cmp(&a.score_impact)));
+        Ok(ReadinessAssessment {
+            assessment_id: format!("readiness_{}", Uuid::new_v4().simple()),
+            tenant_id: tenant_id.to_string(),
+            assessed_at: now,
+            overall_score,
+            maturity: MaturityLevel::from_score(overall_score),
+            dimensions,
+            data_sources,
+            healthy_intel_feeds,
+            containment,
+            playbook_coverage,
+            backlog,
+            sla,
+            gaps,
+        })
+    }
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl SecOpCoreNapi {
+    /// Readiness score, maturity level and prioritized gaps of the caller's tenant
+    #[napi]
+    pub async fn get_readiness_assessment(&self, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        self.authorize(auth_token, "read", "readiness")?;
+        let assessment = self.inner.get_readiness_assessment(&tenant_id).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to assess readiness: {}", e)))?;
+        serde_json::to_string(&assessment)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn configure_readiness(&self, config_data: String, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize_platform(auth_token, "readiness")?;
+        let config: ReadinessConfig = serde_json::from_str(&config_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid readiness config: {}", e)))?;
+        let result = self.inner.configure_readiness(config).await;
+        self.audit.record(&actor, "configure_readiness", "readiness", json!({ "config": config_data }), result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to configure readiness assessment: {}", e)))
+    }
+
+    #[napi]
+    pub async fn get_readiness_config(&self) -> napi::Result<String> {
+        serde_json::to_string(&self.inner.get_readiness_config().await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SecurityAlert, SecurityIncident};
+
+    fn incident(id: &str, category: &str, assigned_to: &str, idle_hours: i64) -> SecurityIncident {
+        let updated_at = Utc::now() - Duration::hours(idle_hours);
+        serde_json::from_value(serde_json::json!({
+            "incident_id": id, "title": id, "description": "", "severity": "High", "status": "Open",
+            "category": category, "priority": 2, "created_at": updated_at, "updated_at": updated_at,
+            "assigned_to": assigned_to, "reporter": "edr", "affected_systems": [], "indicators": [],
+            "timeline": [], "mitigation_actions": [], "estimated_impact": 1.0, "containment_status": "None",
+            "evidence": [], "related_alerts": [], "tags": [], "merged_into": null, "tenant_id": "acme"
+        }))
+        .unwrap()
+    }
+
+    fn alert(source: &str, age_days: i64) -> SecurityAlert {
+        let created_at = Utc::now() - Duration::days(age_days);
+        serde_json::from_value(serde_json::json!({
+            "alert_id": Uuid::new_v4().to_string(), "title": "Test", "description": "", "priority": "Medium",
+            "status": "Open", "source": source, "created_at": created_at, "updated_at": created_at,
+            "rule_id": "RULE-1", "rule_name": "Test Rule", "affected_assets": [], "indicators": [],
+            "raw_data": "", "false_positive_probability": 0.1, "correlation_id": null, "tenant_id": "acme"
+        }))
+        .unwrap()
+    }
+
+    fn playbook(id: &str, category: &str, action_type: &str, automated: bool) -> SecurityPlaybook {
+        serde_json::from_value(serde_json::json!({
+            "playbook_id": id, "name": id, "description": "", "version": "1.0", "category": category,
+            "trigger_conditions": [],
+            "steps": [{
+                "step_id": "contain", "name": "Contain", "action_type": action_type, "description": "",
+                "parameters": {}, "timeout": 300, "required": true, "automation_supported": automated
+            }],
+            "automation_level": "semi", "estimated_duration": 30, "success_rate": 0.9,
+            "last_updated": "2025-01-01T00:00:00Z"
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn assessment_scores_dimensions_and_ranks_gaps() {
+        let core = SecOpCore::new();
+        core.configure_readiness(ReadinessConfig {
+            expected_data_sources: vec!["edr".to_string(), "email".to_string()],
+            containment_actions: vec!["edr.isolate".to_string(), "identity.disable".to_string()],
+            incident_categories: vec!["Malware".to_string(), "Phishing".to_string()],
+            ..ReadinessConfig::default()
+        })
+        .await
+        .unwrap();
+        {
+            let mut alerts = core.alerts.write().await;
+            for alert in [alert("CrowdStrike EDR", 1), alert("Email Gateway", 30)] {
+                alerts.insert(alert.alert_id.clone(), alert);
+            }
+        }
+        core.save_playbook("acme", playbook("PB-MAL", "malware", "edr.isolate.host", true)).await.unwrap();
+        core.save_playbook("acme", playbook("PB-ACC", "Access", "identity.disable", false)).await.unwrap();
+        core.create_incident("acme", incident("INC-1", "Malware", "alice", 1)).await.unwrap();
+        core.create_incident("acme", incident("INC-2", "Phishing", "", 200)).await.unwrap();
+
+        let assessment = core.get_readiness_assessment("acme").await.unwrap();
+        let score = |dimension| assessment.dimensions.iter().find(|d| d.dimension == dimension).unwrap().score;
+        assert_eq!(score(ReadinessDimension::DataSources), 50.0);
+        assert_eq!(score(ReadinessDimension::Containment), 75.0);
+        // The Access playbook covers no expected category
+        assert_eq!(assessment.playbook_coverage.len(), 2);
+        assert_eq!(score(ReadinessDimension::PlaybookCoverage), 50.0);
+        assert_eq!(score(ReadinessDimension::Backlog), 50.0);
+        assert_eq!((assessment.backlog.stale_incidents, assessment.backlog.unassigned_incidents), (1, 1));
+
+        assert_eq!(assessment.gaps[0].priority, GapPriority::Critical);
+        let phishing = assessment.gaps.iter().find(|g| g.dimension == ReadinessDimension::PlaybookCoverage).unwrap();
+        assert_eq!(phishing.priority, GapPriority::Critical);
+        assert!(phishing.description.contains("Phishing incidents (1 open)"));
+        assert!(assessment.gaps.iter().any(|g| g.description.contains("email") && g.priority == GapPriority::High));
+        assert!(assessment.gaps.iter().any(|g| g.description.contains("identity.disable") && g.priority == GapPriority::Medium));
+        assert!(assessment.gaps.windows(2).all(|w| w[0].priority <= w[1].priority));
+        assert_eq!(assessment.maturity, MaturityLevel::from_score(assessment.overall_score));
+
+        assert_eq!(core.get_readiness_assessment("globex").await.unwrap().backlog.open_incidents, 0);
+        let invalid = ReadinessConfig { stale_after_hours: 0, ..ReadinessConfig::default() };
+        assert!(core.configure_readiness(invalid).await.is_err());
+    }
+}
@@ -0,0 +1,176 @@
+//! Cross-core reference resolution for incidents, alerts and IOCs
+//!
+//! Lets an enterprise `ReferenceResolver` turn `inc_…`, `alrt_…` and `ioc_…`
+//! ids (or legacy bare UUIDs) into summaries owned by the SecOp core.
+//! Summaries carry the owning tenant; the NAPI lookup only resolves records
+//! of the caller's tenant.
+
+use async_trait::async_trait;
+use phantom_enterprise_standards::{EntityId, EntityKind, EntityResolver, EntitySummary};
+use std::collections::HashMap;
+
+use crate::secop_core::SecOpCore;
+use crate::{SecurityAlert, SecurityIncident, ThreatIndicator};
+
+const OWNER: &str = "phantom-secop-core";
+
+fn indicator_summary(indicator: &ThreatIndicator, referenced_by: &str, tenant_id: &str) -> EntitySummary {
+    let mut attributes = HashMap::new();
+    attributes.insert("tenant_id".to_string(), serde_json::json!(tenant_id));
+    attributes.insert("indicator_type".to_string(), serde_json::json!(indicator.indicator_type));
+    attributes.insert("confidence".to_string(), serde_json::json!(indicator.confidence));
+    attributes.insert("referenced_by".to_string(), serde_json::json!(referenced_by));
+
+    EntitySummary {
+        id: indicator.indicator_id.clone(),
+        kind: EntityKind::Ioc,
+        owner: OWNER.to_string(),
+        title: indicator.value.clone(),
+        status: Some(indicator.severity.clone()),
+        created_at: Some(indicator.first_seen),
+        attributes,
+    }
+}
+
+#[async_trait]
+impl EntityResolver for SecOpCore {
+    fn owner(&self) -> &str {
+        OWNER
+    }
+
+    fn kinds(&self) -> Vec<EntityKind> {
+        vec![EntityKind::Incident, EntityKind::Alert, EntityKind::Ioc]
+    }
+
+    async fn resolve_entity(&self, id: &EntityId) -> Option<EntitySummary> {
+        self.resolve_reference(None, id).await
+    }
+}
+
+impl SecOpCore {
+    /// Resolve `id` among the records of `tenant_id`, or of every tenant
+    pub async fn resolve_reference(&self, tenant_id: Option<&str>, id: &EntityId) -> Option<EntitySummary> {
+        let wants = |kind| id.kind().is_none_or(|k| k == kind);
+        let visible = |owner: &str| tenant_id.is_none_or(|t| t == owner);
+        let incidents: Vec<SecurityIncident> =
+            self.incidents.read().await.values().filter(|i| visible(&i.tenant_id)).cloned().collect();
+        let alerts: Vec<SecurityAlert> =
+            self.alerts.read().await.values().filter(|a| visible(&a.tenant_id)).cloned().collect();
+
+        if wants(EntityKind::Incident) {
+            if let Some(incident) = incidents.iter().find(|i| id.matches(&i.incident_id)) {
+                let mut attributes = HashMap::new();
+                attributes.insert("severity".to_string(), serde_json::json!(incident.severity));
+                attributes.insert("assigned_to".to_string(), serde_json::json!(incident.assigned_to));
+                attributes.insert("indicator_count".to_string(), serde_json::json!(incident.indicators.len()));
+                attributes.insert("tenant_id".to_string(), serde_json::json!(incident.tenant_id));
+                return Some(EntitySummary {
+                    id: incident.incident_id.clone(),
+                    kind: EntityKind::Incident,
+                    owner: OWNER.to_string(),
+                    title: incident.title.clone(),
+                    status: Some(incident.status.clone()),
+                    created_at: Some(incident.created_at),
+                    attributes,
+                });
+            }
+        }
+
+        if wants(EntityKind::Alert) {
+            if let Some(alert) = alerts.iter().find(|a| id.matches(&a.alert_id)) {
+                let mut attributes = HashMap::new();
+                attributes.insert("priority".to_string(), serde_json::json!(alert.priority));
+                attributes.insert("rule_id".to_string(), serde_json::json!(alert.rule_id));
+                attributes.insert("tenant_id".to_string(), serde_json::json!(alert.tenant_id));
+                return Some(EntitySummary {
+                    id: alert.alert_id.clone(),
+                    kind: EntityKind::Alert,
+                    owner: OWNER.to_string(),
+                    title: alert.title.clone(),
+                    status: Some(alert.status.clone()),
+                    created_at: Some(alert.created_at),
+                    attributes,
+                });
+            }
+        }
+
+        if wants(EntityKind::Ioc) {
+            let from_incidents =
+                incidents.iter().flat_map(|i| i.indicators.iter().map(move |ioc| (ioc, &i.incident_id, &i.tenant_id)));
+            let from_alerts = alerts.iter().flat_map(|a| a.indicators.iter().map(move |ioc| (ioc, &a.alert_id, &a.tenant_id)));
+            if let Some((indicator, owner_id, tenant)) =
+                from_incidents.chain(from_alerts).find(|(ioc, _, _)| id.matches(&ioc.indicator_id))
+            {
+                return Some(indicator_summary(indicator, owner_id, tenant));
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SecurityIncident;
+    use chrono::Utc;
+
+    #[tokio::test]
+    async fn test_resolves_prefixed_and_legacy_incident_references() {
+        let core = SecOpCore::new();
+        let legacy_id = uuid::Uuid::new_v4().to_string();
+        let indicator_id = EntityId::new(EntityKind::Ioc).to_string();
+        let incident = SecurityIncident {
+            incident_id: legacy_id.clone(),
+            title: "Legacy incident".to_string(),
+            description: String::new(),
+            severity: "High".to_string(),
+            status: "Open".to_string(),
+            category: "Malware".to_string(),
+            priority: 1,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            assigned_to: "soc".to_string(),
+            reporter: "edr".to_string(),
+            affected_systems: Vec::new(),
+            indicators: vec![ThreatIndicator {
+                indicator_id: indicator_id.clone(),
+                indicator_type: "IP".to_string(),
+                value: "203.0.113.7".to_string(),
+                confidence: 0.9,
+                severity: "High".to_string(),
+                source: "edr".to_string(),
+                first_seen: Utc::now(),
+                last_seen: Utc::now(),
+                context: String::new(),
+            }],
+            timeline: Vec::new(),
+            mitigation_actions: Vec::new(),
+            estimated_impact: 0.0,
+            containment_status: "None".to_string(),
+            evidence: Vec::new(),
+            related_alerts: Vec::new(),
+            tags: Vec::new(),
+            merged_into: None,
+            sla: None,
+            tenant_id: "default".to_string(),
+            external_id: None,
+            ticket: None,
+        };
+        core.create_incident("default", incident).await.unwrap();
+
+        let legacy = EntityId::parse(&legacy_id).unwrap();
+        let summary = core.resolve_entity(&legacy).await.unwrap();
+        assert_eq!(summary.kind, EntityKind::Incident);
+
+        let prefixed = EntityId::from_parts(Some(EntityKind::Incident), legacy.uuid());
+        assert_eq!(core.resolve_entity(&prefixed).await.unwrap().id, legacy_id);
+
+        let ioc = core.resolve_entity(&EntityId::parse(&indicator_id).unwrap()).await.unwrap();
+        assert_eq!(ioc.title, "203.0.113.7");
+        assert_eq!(ioc.attributes["referenced_by"], serde_json::json!(legacy_id));
+
+        let wrong_kind = EntityId::from_parts(Some(EntityKind::Alert), legacy.uuid());
+        assert!(core.resolve_entity(&wrong_kind).await.is_none());
+    }
+}
@@ -0,0 +1,514 @@
+//! Full-text search over SOC records
+//!
+//! Incidents, alerts, incident tasks (mitigation actions) and evidence
+//! references are indexed into an in-memory tantivy index. Queries use the
+//! tantivy query syntax for boolean operators (`AND`, `OR`, `NOT`, `-term`,
+//! phrases, `field:value`), with terms required by default, and can be
+//! narrowed with exact field filters and a date range. Hits are ranked by
+//! BM25 relevance with matches in titles weighted higher. Every document
+//! carries its tenant and a search only ever sees the caller's tenant.
+
+use crate::{SecurityAlert, SecurityIncident};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Bound;
+use std::sync::Mutex;
+use tantivy::collector::{Count, TopDocs};
+use tantivy::query::{AllQuery, BooleanQuery, Occur, Query, QueryParser, RangeQuery, TermQuery};
+use tantivy::schema::{Field, IndexRecordOption, Schema, Value, FAST, INDEXED, STORED, STRING, TEXT};
+use tantivy::{Index, IndexReader, IndexWriter, ReloadPolicy, TantivyDocument, Term};
+
+/// Writer heap; tantivy's minimum for a single indexing thread
+const WRITER_MEMORY_BYTES: usize = 15_000_000;
+const DEFAULT_LIMIT: usize = 20;
+const MAX_LIMIT: usize = 1_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchDocumentKind {
+    Incident,
+    Alert,
+    Task,
+    Evidence,
+}
+
+impl SearchDocumentKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Incident => "incident",
+            Self::Alert => "alert",
+            Self::Task => "task",
+            Self::Evidence => "evidence",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "incident" => Some(Self::Incident),
+            "alert" => Some(Self::Alert),
+            "task" => Some(Self::Task),
+            "evidence" => Some(Self::Evidence),
+            _ => None,
+        }
+    }
+}
+
+/// One indexed record
+#[derive(Debug, Clone)]
+pub struct SearchDocument {
+    pub doc_id: String,
+    pub tenant_id: String,
+    pub kind: SearchDocumentKind,
+    pub incident_id: Option<String>,
+    pub title: String,
+    pub body: String,
+    pub severity: String,
+    pub status: String,
+    pub source: String,
+    pub tags: Vec<String>,
+    pub assets: Vec<String>,
+    pub indicators: Vec<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl SearchDocument {
+    /// The incident itself plus one document per task and evidence reference
+    pub fn from_incident(incident: &SecurityIncident) -> Vec<SearchDocument> {
+        let timeline: Vec<&str> = incident.timeline.iter().map(|e| e.description.as_str()).collect();
+        let base = SearchDocument {
+            doc_id: incident.incident_id.clone(),
+            tenant_id: incident.tenant_id.clone(),
+            kind: SearchDocumentKind::Incident,
+            incident_id: Some(incident.incident_id.clone()),
+            title: incident.title.clone(),
+            body: format!("{}\n{}\n{}", incident.description, incident.category, timeline.join("\n")),
+            severity: incident.severity.clone(),
+            status: incident.status.clone(),
+            source: incident.reporter.clone(),
+            tags: incident.tags.clone(),
+            assets: incident.affected_systems.clone(),
+            indicators: incident.indicators.iter().map(|i| i.value.clone()).collect(),
+            timestamp: incident.created_at,
+        };
+
+        let mut documents = Vec::with_capacity(1 + incident.mitigation_actions.len() + incident.evidence.len());
+        for (index, action) in incident.mitigation_actions.iter().enumerate() {
+            documents.push(SearchDocument {
+                doc_id: format!("{}/task/{}", incident.incident_id, index),
+                kind: SearchDocumentKind::Task,
+                title: action.clone(),
+                body: format!("Task for incident {}: {}", incident.incident_id, incident.title),
+                source: incident.assigned_to.clone(),
+                indicators: Vec::new(),
+                ..base.clone()
+            });
+        }
+        for (index, reference) in incident.evidence.iter().enumerate() {
+            documents.push(SearchDocument {
+                doc_id: format!("{}/evidence/{}", incident.incident_id, index),
+                kind: SearchDocumentKind::Evidence,
+                title: reference.clone(),
+                body: format!("Evidence for incident {}: {}", incident.incident_id, incident.title),
+                indicators: vec![reference.clone()],
+                ..base.clone()
+            });
+        }
+        documents.insert(0, base);
+        documents
+    }
+
+    pub fn from_alert(alert: &SecurityAlert) -> SearchDocument {
+        SearchDocument {
+            doc_id: alert.alert_id.clone(),
+            tenant_id: alert.tenant_id.clone(),
+            kind: SearchDocumentKind::Alert,
+            incident_id: alert.correlation_id.clone(),
+            title: alert.title.clone(),
+            body: format!("{}\n{}", alert.description, alert.rule_name),
+            severity: alert.priority.clone(),
+            status: alert.status.clone(),
+            source: alert.source.clone(),
+            tags: vec![alert.rule_id.clone()],
+            assets: alert.affected_assets.clone(),
+            indicators: alert.indicators.iter().map(|i| i.value.clone()).collect(),
+            timestamp: alert.created_at,
+        }
+    }
+}
+
+/// Search request accepted by `search`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchQuery {
+    /// Free text in tantivy query syntax; empty matches everything
+    #[serde(default)]
+    pub query: String,
+    /// Restrict to these record kinds; empty searches all
+    #[serde(default)]
+    pub kinds: Vec<SearchDocumentKind>,
+    /// Exact filters on severity, status, source, tag, asset or incident_id
+    #[serde(default)]
+    pub filters: HashMap<String, String>,
+    #[serde(default)]
+    pub from: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHit {
+    pub doc_id: String,
+    pub kind: SearchDocumentKind,
+    pub incident_id: Option<String>,
+    pub title: String,
+    pub severity: String,
+    pub status: String,
+    pub timestamp: DateTime<Utc>,
+    pub score: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub total: usize,
+    pub hits: Vec<SearchHit>,
+}
+
+#[derive(Clone, Copy)]
+struct SearchFields {
+    doc_id: Field,
+    owner: Field,
+    tenant: Field,
+    kind: Field,
+    incident_id: Field,
+    title: Field,
+    body: Field,
+    severity: Field,
+    status: Field,
+    source: Field,
+    tag: Field,
+    asset: Field,
+    assets_text: Field,
+    indicators: Field,
+    timestamp: Field,
+}
+
+/// In-memory inverted index over SOC records
+pub struct SearchIndex {
+    index: Index,
+    reader: IndexReader,
+    writer: Mutex<IndexWriter>,
+    fields: SearchFields,
+}
+
+fn normalize(value: &str) -> String {
+    value.trim().to_lowercase()
+}
+
+fn to_index_date(value: DateTime<Utc>) -> tantivy::DateTime {
+    tantivy::DateTime::from_timestamp_micros(value.timestamp_micros())
+}
+
+impl SearchIndex {
+    pub fn new() -> Result<Self, String> {
+        let mut builder = Schema::builder();
+        let fields = SearchFields {
+            doc_id: builder.add_text_field("doc_id", STRING | STORED),
+            owner: builder.add_text_field("owner", STRING),
+            tenant: builder.add_text_field("tenant", STRING),
+            kind: builder.add_text_field("kind", STRING | STORED),
+            incident_id: builder.add_text_field("incident_id", STRING | STORED),
+            title: builder.add_text_field("title", TEXT | STORED),
+            body: builder.add_text_field("body", TEXT),
+            severity: builder.add_text_field("severity", STRING | STORED),
+            status: builder.add_text_field("status", STRING | STORED),
+            source: builder.add_text_field("source", STRING),
+            tag: builder.add_text_field("tag", STRING),
+            asset: builder.add_text_field("asset", STRING),
+            assets_text: builder.add_text_field("assets", TEXT),
+            indicators: builder.add_text_field("indicators", TEXT),
+            timestamp: builder.add_date_field("timestamp", INDEXED | STORED | FAST),
+        };
+        let index = Index::create_in_ram(builder.build());
+        let writer = index
+            .writer_with_num_threads(1, WRITER_MEMORY_BYTES)
+            .map_err(|e| format!("Failed to create search index writer: {}", e))?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()
+            .map_err(|e| format!("Failed to open search index reader: {}", e))?;
+        Ok(Self {
+            index,
+            reader,
+            writer: Mutex::new(writer),
+            fields,
+        })
+    }
+
+    fn to_tantivy(&self, owner: &str, document: &SearchDocument) -> TantivyDocument {
+        let f = self.fields;
+        let mut doc = TantivyDocument::default();
+        doc.add_text(f.doc_id, &document.doc_id);
+        doc.add_text(f.owner, owner);
+        doc.add_text(f.tenant, &document.tenant_id);
+        doc.add_text(f.kind, document.kind.as_str());
+        if let Some(incident_id) = &document.incident_id {
+            doc.add_text(f.incident_id, incident_id);
+        }
+        doc.add_text(f.title, &document.title);
+        doc.add_text(f.body, &document.body);
+        doc.add_text(f.severity, normalize(&document.severity));
+        doc.add_text(f.status, normalize(&document.status));
+        doc.add_text(f.source, normalize(&document.source));
+        for tag in &document.tags {
+            doc.add_text(f.tag, normalize(tag));
+        }
+        for asset in &document.assets {
+            doc.add_text(f.asset, normalize(asset));
+            doc.add_text(f.assets_text, asset);
+        }
+        for indicator in &document.indicators {
+            doc.add_text(f.indicators, indicator);
+        }
+        doc.add_date(f.timestamp, to_index_date(document.timestamp));
+        doc
+    }
+
+    /// Replace every document indexed for `owner` (e.g. `incident:INC-1`)
+    pub fn replace(&self, owner: &str, documents: &[SearchDocument]) -> Result<(), String> {
+        let mut writer = self.writer.lock().map_err(|_| "Search index writer poisoned".to_string())?;
+        writer.delete_term(Term::from_field_text(self.fields.owner, owner));
+        for document in documents {
+            writer
+                .add_document(self.to_tantivy(owner, document))
+                .map_err(|e| format!("Failed to index {}: {}", document.doc_id, e))?;
+        }
+        writer.commit().map_err(|e| format!("Failed to commit search index: {}", e))?;
+        self.reader.reload().map_err(|e| format!("Failed to reload search index: {}", e))
+    }
+
+    pub fn index_incident(&self, incident: &SecurityIncident) -> Result<(), String> {
+        self.replace(&format!("incident:{}", incident.incident_id), &SearchDocument::from_incident(incident))
+    }
+
+    pub fn index_alert(&self, alert: &SecurityAlert) -> Result<(), String> {
+        self.replace(&format!("alert:{}", alert.alert_id), &[SearchDocument::from_alert(alert)])
+    }
+
+    /// Drop every document belonging to `tenant_id`
+    pub fn purge_tenant(&self, tenant_id: &str) -> Result<usize, String> {
+        let mut writer = self.writer.lock().map_err(|_| "Search index writer poisoned".to_string())?;
+        let term = Term::from_field_text(self.fields.tenant, tenant_id);
+        let count = self
+            .reader
+            .searcher()
+            .search(&TermQuery::new(term.clone(), IndexRecordOption::Basic), &Count)
+            .map_err(|e| format!("Search failed: {}", e))?;
+        writer.delete_term(term);
+        writer.commit().map_err(|e| format!("Failed to commit search index: {}", e))?;
+        self.reader.reload().map_err(|e| format!("Failed to reload search index: {}", e))?;
+        Ok(count)
+    }
+
+    fn filter_field(&self, name: &str) -> Option<Field> {
+        let f = self.fields;
+        match name {
+            "severity" | "priority" => Some(f.severity),
+            "status" => Some(f.status),
+            "source" => Some(f.source),
+            "tag" | "tags" => Some(f.tag),
+            "asset" | "assets" => Some(f.asset),
+            "incident_id" => Some(f.incident_id),
+            _ => None,
+        }
+    }
+
+    fn term_query(field: Field, value: &str) -> Box<dyn Query> {
+        Box::new(TermQuery::new(Term::from_field_text(field, value), IndexRecordOption::Basic))
+    }
+
+    /// Search the documents of `tenant_id`
+    pub fn search(&self, tenant_id: &str, request: &SearchQuery) -> Result<SearchResults, String> {
+        let f = self.fields;
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = vec![(Occur::Must, Self::term_query(f.tenant, tenant_id))];
+
+        if request.query.trim().is_empty() {
+            clauses.push((Occur::Must, Box::new(AllQuery)));
+        } else {
+            let mut parser = QueryParser::for_index(&self.index, vec![f.title, f.body, f.assets_text, f.indicators]);
+            parser.set_conjunction_by_default();
+            parser.set_field_boost(f.title, 2.0);
+            let parsed = parser
+                .parse_query(&request.query)
+                .map_err(|e| format!("Invalid search query: {}", e))?;
+            clauses.push((Occur::Must, parsed));
+        }
+
+        if !request.kinds.is_empty() {
+            let kinds = request
+                .kinds
+                .iter()
+                .map(|kind| (Occur::Should, Self::term_query(f.kind, kind.as_str())))
+                .collect();
+            clauses.push((Occur::Must, Box::new(BooleanQuery::new(kinds))));
+        }
+
+        let mut filters: Vec<(&String, &String)> = request.filters.iter().collect();
+        filters.sort();
+        for (name, value) in filters {
+            let field = self
+                .filter_field(name)
+                .ok_or_else(|| format!("Unsupported search filter: {}", name))?;
+            let value = if name == "incident_id" { value.clone() } else { normalize(value) };
+            clauses.push((Occur::Must, Self::term_query(field, &value)));
+        }
+
+        if request.from.is_some() || request.to.is_some() {
+            let lower = request.from.map_or(Bound::Unbounded, |from| Bound::Included(to_index_date(from)));
+            let upper = request.to.map_or(Bound::Unbounded, |to| Bound::Included(to_index_date(to)));
+            clauses.push((Occur::Must, Box::new(RangeQuery::new_date_bounds("timestamp".to_string(), lower, upper))));
+        }
+
+        let query = BooleanQuery::new(clauses);
+        let limit = request.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        let searcher = self.reader.searcher();
+        let (top_docs, total) = searcher
+            .search(&query, &(TopDocs::with_limit(limit).and_offset(request.offset), Count))
+            .map_err(|e| format!("Search failed: {}", e))?;
+
+        let text = |doc: &TantivyDocument, field: Field| {
+            doc.get_first(field).and_then(|v| v.as_str()).unwrap_or_default().to_string()
+        };
+        let mut hits = Vec::with_capacity(top_docs.len());
+        for (score, address) in top_docs {
+            let doc: TantivyDocument = searcher.doc(address).map_err(|e| format!("Failed to load search hit: {}", e))?;
+            let Some(kind) = SearchDocumentKind::parse(&text(&doc, f.kind)) else { continue };
+            let incident_id = text(&doc, f.incident_id);
+            let timestamp = doc
+                .get_first(f.timestamp)
+                .and_then(|v| v.as_datetime())
+                .and_then(|t| DateTime::from_timestamp_micros(t.into_timestamp_micros()))
+                .unwrap_or_default();
+            hits.push(SearchHit {
+                doc_id: text(&doc, f.doc_id),
+                kind,
+                incident_id: (!incident_id.is_empty()).then_some(incident_id),
+                title: text(&doc, f.title),
+                severity: text(&doc, f.severity),
+                status: text(&doc, f.status),
+                timestamp,
+                score,
+            });
+        }
+        Ok(SearchResults { total, hits })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ThreatIndicator;
+    use chrono::Duration;
+
+    fn incident(id: &str, title: &str, severity: &str, days_ago: i64) -> SecurityIncident {
+        let created_at = Utc::now() - Duration::days(days_ago);
+        SecurityIncident {
+            incident_id: id.to_string(),
+            title: title.to_string(),
+            description: "Investigation opened by the SOC".to_string(),
+            severity: severity.to_string(),
+            status: "Open".to_string(),
+            category: "Malware".to_string(),
+            priority: 2,
+            created_at,
+            updated_at: created_at,
+            assigned_to: "soc".to_string(),
+            reporter: "EDR".to_string(),
+            affected_systems: vec!["web-01".to_string()],
+            indicators: vec![ThreatIndicator {
+                indicator_id: "ioc-1".to_string(),
+                indicator_type: "Domain".to_string(),
+                value: "evil.example".to_string(),
+                confidence: 0.9,
+                severity: "High".to_string(),
+                source: "EDR".to_string(),
+                first_seen: created_at,
+                last_seen: created_at,
+                context: String::new(),
+            }],
+            timeline: Vec::new(),
+            mitigation_actions: vec!["Reimage web-01".to_string()],
+            estimated_impact: 1.0,
+            containment_status: "None".to_string(),
+            evidence: vec!["sha256:deadbeef".to_string()],
+            related_alerts: Vec::new(),
+            tags: vec!["ransomware".to_string()],
+            merged_into: None,
+            sla: None,
+            tenant_id: "default".to_string(),
+            external_id: None,
+            ticket: None,
+        }
+    }
+
+    #[test]
+    fn test_boolean_queries_filters_and_date_range() {
+        let index = SearchIndex::new().unwrap();
+        index.index_incident(&incident("INC-1", "Ransomware on web server", "High", 1)).unwrap();
+        index.index_incident(&incident("INC-2", "Phishing campaign", "Low", 30)).unwrap();
+
+        let results = index
+            .search("default", &SearchQuery { query: "ransomware OR phishing".to_string(), ..Default::default() })
+            .unwrap();
+        assert_eq!(results.total, 6);
+        assert_eq!(results.hits[0].kind, SearchDocumentKind::Incident);
+
+        let results = index
+            .search("default", &SearchQuery { query: "web -phishing".to_string(), kinds: vec![SearchDocumentKind::Incident], ..Default::default() })
+            .unwrap();
+        assert_eq!(results.hits.iter().map(|h| h.doc_id.as_str()).collect::<Vec<_>>(), vec!["INC-1"]);
+
+        let results = index
+            .search("default", &SearchQuery {
+                filters: HashMap::from([("severity".to_string(), "LOW".to_string())]),
+                kinds: vec![SearchDocumentKind::Incident],
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(results.hits[0].doc_id, "INC-2");
+
+        let results = index
+            .search("default", &SearchQuery { from: Some(Utc::now() - Duration::days(7)), ..Default::default() })
+            .unwrap();
+        assert_eq!(results.total, 3);
+        assert!(results.hits.iter().all(|h| h.incident_id.as_deref() == Some("INC-1")));
+    }
+
+    #[test]
+    fn test_reindex_replaces_tasks_and_evidence() {
+        let index = SearchIndex::new().unwrap();
+        let mut record = incident("INC-3", "Credential theft", "High", 0);
+        index.index_incident(&record).unwrap();
+
+        let evidence = index
+            .search("default", &SearchQuery { query: "deadbeef".to_string(), kinds: vec![SearchDocumentKind::Evidence], ..Default::default() })
+            .unwrap();
+        assert_eq!(evidence.hits[0].doc_id, "INC-3/evidence/0");
+
+        record.mitigation_actions = vec!["Reset domain passwords".to_string()];
+        index.index_incident(&record).unwrap();
+        let tasks = index
+            .search("default", &SearchQuery { kinds: vec![SearchDocumentKind::Task], ..Default::default() })
+            .unwrap();
+        assert_eq!(tasks.total, 1);
+        assert_eq!(tasks.hits[0].title, "Reset domain passwords");
+
+        assert!(index.search("default", &SearchQuery { query: "title:(".to_string(), ..Default::default() }).is_err());
+        let unknown = SearchQuery { filters: HashMap::from([("owner".to_string(), "x".to_string())]), ..Default::default() };
+        assert!(index.search("default", &unknown).is_err());
+    }
+}
@@ -0,0 +1,1082 @@
+//! Stateful Security Operations core
+//!
+//! Holds alerts and incidents in memory and runs the intake pipeline (triage
+//! scoring and auto-disposition) for every alert that enters the SOC. Related
+//! alerts are correlated into clusters and attached to open incidents. Closing
+//! an incident harvests its knowledge for analyst review; duplicates can be
+//! merged into a primary incident. SLA clocks are evaluated against per-team
+//! business calendars, and incidents carry their SLA timer state. Every change to an alert or incident is reflected in
+//! the full-text search index. Recurring SOC tasks and shift handovers are
+//! kept alongside, and history from legacy tools can be bulk imported.
+//! Incidents can be linked to Jira or ServiceNow tickets and kept in sync,
+//! and indicators from threat intel feeds are matched against recent activity.
+//! A readiness assessment scores each tenant's incident response maturity.
+//!
+//! Alerts, incidents, harvests and SLA events belong to a tenant and every
+//! query is scoped to the caller's tenant; records of other tenants are
+//! reported as not found. Triage, correlation, SLA and readiness
+//! configuration, business calendars and the reviewed knowledge stores are
+//! shared by all tenants.
+
+use crate::calendar::{BusinessCalendar, CalendarStore, SlaClockStatus, SlaTiming};
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::compliance_reports::ComplianceReportState;
+use crate::correlation::{correlate, ClusterAction, CorrelationCluster, CorrelationConfig};
+use crate::error::{CoreError, CoreResult};
+use crate::intel_feeds::IntelFeedState;
+use crate::knowledge::{harvest_incident, DetectionGap, HarvestReview, KnowledgeBase, KnowledgeHarvest, TechniqueCoverage};
+use crate::metrics_history::{MetricsHistoryState, ALERTS_INGESTED, INCIDENTS_OPENED};
+use crate::pagination::{paginate, ListSpec, Page, PageRequest, SortDirection};
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::playbook_sync::PlaybookSyncState;
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::live_feed::LiveFeedState;
+use crate::playbook_actions::PlaybookActionState;
+use crate::playbooks::PlaybookLibrary;
+use crate::prometheus::PrometheusState;
+use crate::readiness::ReadinessConfig;
+use crate::search::{SearchIndex, SearchQuery, SearchResults};
+use crate::secrets::{self, EnvKey, SecretStore};
+use crate::sla::SlaTracker;
+#[cfg(feature = "phantom-enterprise-standards")]
+use crate::syslog::SyslogState;
+use crate::tasks::TaskBoard;
+use crate::ticketing::TicketingState;
+use crate::triage::{TriageConfig, TriageDisposition, TriageEngine, TriageResult};
+use crate::{IncidentEvent, SecurityAlert, SecurityIncident, ThreatIndicator};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+#[cfg(feature = "napi")]
+use crate::access::AccessGuard;
+#[cfg(feature = "napi")]
+use crate::audit::AuditTrail;
+#[cfg(feature = "napi")]
+use crate::tenancy::{TenantGuard, DEFAULT_TENANT};
+#[cfg(feature = "napi")]
+use crate::sla::SlaConfig;
+#[cfg(feature = "napi")]
+use crate::timeline::{TimelineEntry, TimelineOptions};
+#[cfg(feature = "napi")]
+use serde_json::json;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+
+#[cfg(feature = "napi")]
+use napi::Result as NapiResult;
+
+pub struct SecOpCore {
+    pub(crate) alerts: Arc<RwLock<HashMap<String, SecurityAlert>>>,
+    pub(crate) incidents: Arc<RwLock<HashMap<String, SecurityIncident>>>,
+    triage: Arc<RwLock<TriageEngine>>,
+    triage_results: Arc<RwLock<HashMap<String, TriageResult>>>,
+    knowledge: Arc<RwLock<KnowledgeBase>>,
+    pub(crate) calendars: Arc<RwLock<CalendarStore>>,
+    correlation: Arc<RwLock<CorrelationConfig>>,
+    pub(crate) search: Arc<SearchIndex>,
+    pub(crate) sla: Arc<RwLock<SlaTracker>>,
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub(crate) syslog: Arc<SyslogState>,
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub(crate) live_feed: Arc<LiveFeedState>,
+    pub(crate) playbooks: Arc<RwLock<PlaybookLibrary>>,
+    pub(crate) playbook_actions: Arc<PlaybookActionState>,
+    pub(crate) metrics_history: Arc<MetricsHistoryState>,
+    pub(crate) prometheus: Arc<PrometheusState>,
+    pub(crate) tasks: Arc<RwLock<TaskBoard>>,
+    pub(crate) ticketing: Arc<TicketingState>,
+    pub(crate) secrets: Arc<SecretStore>,
+    pub(crate) intel_feeds: Arc<IntelFeedState>,
+    pub(crate) readiness: Arc<RwLock<ReadinessConfig>>,
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub(crate) compliance: Arc<ComplianceReportState>,
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub(crate) playbook_sync: Arc<PlaybookSyncState>,
+}
+
+impl Default for SecOpCore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SecOpCore {
+    pub fn new() -> Self {
+        Self {
+            alerts: Arc::new(RwLock::new(HashMap::new())),
+            incidents: Arc::new(RwLock::new(HashMap::new())),
+            triage: Arc::new(RwLock::new(TriageEngine::new(TriageConfig::default()))),
+            triage_results: Arc::new(RwLock::new(HashMap::new())),
+            knowledge: Arc::new(RwLock::new(KnowledgeBase::default())),
+            calendars: Arc::new(RwLock::new(CalendarStore::default())),
+            correlation: Arc::new(RwLock::new(CorrelationConfig::default())),
+            search: Arc::new(SearchIndex::new().expect("in-memory search index")),
+            sla: Arc::new(RwLock::new(SlaTracker::default())),
+            #[cfg(feature = "phantom-enterprise-standards")]
+            syslog: Arc::new(SyslogState::default()),
+            #[cfg(feature = "phantom-enterprise-standards")]
+            live_feed: Arc::new(LiveFeedState::default()),
+            playbooks: Arc::new(RwLock::new(PlaybookLibrary::default())),
+            playbook_actions: Arc::new(PlaybookActionState::default()),
+            metrics_history: Arc::new(MetricsHistoryState::default()),
+            prometheus: Arc::new(PrometheusState::default()),
+            tasks: Arc::new(RwLock::new(TaskBoard::default())),
+            ticketing: Arc::new(TicketingState::default()),
+            secrets: Arc::new(SecretStore::new(Arc::new(EnvKey::new(secrets::MASTER_KEY_ENV)))),
+            intel_feeds: Arc::new(IntelFeedState::default()),
+            readiness: Arc::new(RwLock::new(ReadinessConfig::default())),
+            #[cfg(feature = "phantom-enterprise-standards")]
+            compliance: Arc::new(ComplianceReportState::default()),
+            #[cfg(feature = "phantom-enterprise-standards")]
+            playbook_sync: Arc::new(PlaybookSyncState::default()),
+        }
+    }
+
+    /// Triage an inbound alert for `tenant_id`, apply its auto-disposition
+    /// and store it
+    pub async fn ingest_alert(&self, tenant_id: &str, mut alert: SecurityAlert) -> CoreResult<TriageResult> {
+        if alert.alert_id.is_empty() {
+            return Err(CoreError::validation("Alert id is required"));
+        }
+        if self.alerts.read().await.get(&alert.alert_id).is_some_and(|a| a.tenant_id != tenant_id) {
+            return Err(CoreError::validation(format!("Alert id {} is already in use", alert.alert_id)));
+        }
+        alert.tenant_id = tenant_id.to_string();
+
+        let result = {
+            let mut triage = self.triage.write().await;
+            triage.score_alert(&alert)
+        };
+
+        alert.status = match result.disposition {
+            TriageDisposition::AutoClose => "Closed".to_string(),
+            TriageDisposition::AutoEscalate => "Escalated".to_string(),
+            TriageDisposition::AnalystQueue => "Open".to_string(),
+        };
+        if let Some(duplicate_of) = &result.duplicate_of {
+            alert.correlation_id = Some(duplicate_of.clone());
+        }
+        alert.updated_at = Utc::now();
+
+        self.search.index_alert(&alert)?;
+        self.forward_alert(&alert);
+        self.publish_alert(&alert);
+        self.metrics_history.record(tenant_id, ALERTS_INGESTED, 1.0);
+        self.prometheus.observe_alert(&alert, &result.disposition);
+        self.alerts.write().await.insert(alert.alert_id.clone(), alert);
+        self.triage_results.write().await.insert(result.alert_id.clone(), result.clone());
+
+        Ok(result)
+    }
+
+    pub async fn get_alert(&self, tenant_id: &str, alert_id: &str) -> Option<SecurityAlert> {
+        self.alerts.read().await.get(alert_id).filter(|a| a.tenant_id == tenant_id).cloned()
+    }
+
+    pub async fn list_alerts(&self, tenant_id: &str) -> Vec<SecurityAlert> {
+        let mut alerts: Vec<SecurityAlert> =
+            self.alerts.read().await.values().filter(|a| a.tenant_id == tenant_id).cloned().collect();
+        alerts.sort_by_key(|a| std::cmp::Reverse(a.created_at));
+        alerts
+    }
+
+    /// Score breakdown recorded when the alert was ingested
+    pub async fn get_triage_result(&self, tenant_id: &str, alert_id: &str) -> Option<TriageResult> {
+        self.get_alert(tenant_id, alert_id).await?;
+        self.triage_results.read().await.get(alert_id).cloned()
+    }
+
+    pub async fn get_triage_config(&self) -> TriageConfig {
+        self.triage.read().await.config().clone()
+    }
+
+    pub async fn configure_triage(&self, config: TriageConfig) {
+        self.triage.write().await.set_config(config);
+    }
+
+    /// Load indicators into the IOC repository consulted during triage
+    pub async fn load_ioc_repository(&self, indicators: Vec<ThreatIndicator>) -> usize {
+        self.triage.write().await.load_indicators(indicators)
+    }
+
+    pub async fn get_correlation_config(&self) -> CorrelationConfig {
+        self.correlation.read().await.clone()
+    }
+
+    pub async fn configure_correlation(&self, config: CorrelationConfig) -> CoreResult<()> {
+        config.validate().map_err(CoreError::validation)?;
+        *self.correlation.write().await = config;
+        Ok(())
+    }
+
+    /// Cluster a tenant's related alerts, attaching clusters that match one
+    /// of its open incidents when auto-attach is enabled and proposing
+    /// incidents for the rest
+    pub async fn correlate_alerts(&self, tenant_id: &str) -> CoreResult<Vec<CorrelationCluster>> {
+        let config = self.correlation.read().await.clone();
+        let now = Utc::now();
+        let alerts = self.list_alerts(tenant_id).await;
+        let incidents: Vec<SecurityIncident> =
+            self.incidents.read().await.values().filter(|i| i.tenant_id == tenant_id).cloned().collect();
+        let mut clusters = correlate(&alerts, &incidents, &config, now);
+        if !config.auto_attach {
+            return Ok(clusters);
+        }
+
+        let mut incidents = self.incidents.write().await;
+        let mut alerts = self.alerts.write().await;
+        for cluster in &mut clusters {
+            let ClusterAction::AttachToIncident { incident_id, attached, .. } = &mut cluster.action else { continue };
+            let Some(incident) = incidents.get_mut(incident_id.as_str()) else { continue };
+
+            let new_alerts: Vec<String> = cluster
+                .alert_ids
+                .iter()
+                .filter(|id| !incident.related_alerts.contains(id))
+                .cloned()
+                .collect();
+            for alert_id in &cluster.alert_ids {
+                if let Some(alert) = alerts.get_mut(alert_id) {
+                    alert.correlation_id = Some(incident_id.clone());
+                    alert.updated_at = now;
+                }
+            }
+            if !new_alerts.is_empty() {
+                incident.related_alerts.extend(new_alerts.iter().cloned());
+                incident.updated_at = now;
+                incident.timeline.push(IncidentEvent {
+                    event_id: uuid::Uuid::new_v4().to_string(),
+                    timestamp: now,
+                    event_type: "AlertsCorrelated".to_string(),
+                    description: format!("Attached {} correlated alert(s)", new_alerts.len()),
+                    source: "correlation".to_string(),
+                    severity: incident.severity.clone(),
+                    data: HashMap::from([
+                        ("cluster_id".to_string(), cluster.cluster_id.clone()),
+                        ("alert_ids".to_string(), new_alerts.join(",")),
+                        ("matched_rules".to_string(), cluster.matched_rules.join(",")),
+                    ]),
+                });
+            }
+            *attached = true;
+
+            self.search.index_incident(incident)?;
+            for alert_id in &cluster.alert_ids {
+                if let Some(alert) = alerts.get(alert_id) {
+                    self.search.index_alert(alert)?;
+                }
+            }
+        }
+        Ok(clusters)
+    }
+
+    pub async fn create_incident(&self, tenant_id: &str, mut incident: SecurityIncident) -> CoreResult<String> {
+        let incident_id = {
+            let mut incidents = self.incidents.write().await;
+            if incidents.contains_key(&incident.incident_id) {
+                return Err(CoreError::validation(format!("Incident {} already exists", incident.incident_id)));
+            }
+            incident.tenant_id = tenant_id.to_string();
+            incident.ticket = None;
+            let incident_id = incident.incident_id.clone();
+            self.search.index_incident(&incident)?;
+            self.publish_incident(&incident, "IncidentCreated");
+            incidents.insert(incident_id.clone(), incident);
+            incident_id
+        };
+        self.metrics_history.record(tenant_id, INCIDENTS_OPENED, 1.0);
+        self.auto_open_ticket(tenant_id, &incident_id).await;
+        Ok(incident_id)
+    }
+
+    /// Fetch an incident with its current SLA state
+    pub async fn get_incident(&self, tenant_id: &str, incident_id: &str) -> Option<SecurityIncident> {
+        let incident = self.incidents.read().await.get(incident_id).filter(|i| i.tenant_id == tenant_id).cloned()?;
+        Some(self.with_sla(incident).await)
+    }
+
+    pub async fn list_incidents(&self, tenant_id: &str) -> Vec<SecurityIncident> {
+        let incidents: Vec<SecurityIncident> =
+            self.incidents.read().await.values().filter(|i| i.tenant_id == tenant_id).cloned().collect();
+        let mut listed = Vec::with_capacity(incidents.len());
+        for incident in incidents {
+            listed.push(self.with_sla(incident).await);
+        }
+        listed
+    }
+
+    /// One page of the tenant's alerts, newest first by default
+    pub async fn alerts_page(&self, tenant_id: &str, request: &PageRequest) -> CoreResult<Page<SecurityAlert>> {
+        let alerts = self.list_alerts(tenant_id).await;
+        Ok(paginate(alerts, request, ListSpec { id_field: "alert_id", sort_by: "created_at", direction: SortDirection::Desc })?)
+    }
+
+    /// One page of the tenant's incidents with their SLA state, newest first by default
+    pub async fn incidents_page(&self, tenant_id: &str, request: &PageRequest) -> CoreResult<Page<SecurityIncident>> {
+        let incidents = self.list_incidents(tenant_id).await;
+        Ok(paginate(incidents, request, ListSpec { id_field: "incident_id", sort_by: "created_at", direction: SortDirection::Desc })?)
+    }
+
+    async fn with_sla(&self, mut incident: SecurityIncident) -> SecurityIncident {
+        incident.sla = match self.evaluate_sla(&incident, Utc::now()).await {
+            Ok(state) => state,
+            Err(e) => {
+                log::warn!("Failed to evaluate SLA for incident {}: {}", incident.incident_id, e);
+                None
+            }
+        };
+        incident
+    }
+
+    /// Close an incident and harvest its knowledge for analyst review
+    pub async fn close_incident(
+        &self,
+        tenant_id: &str,
+        incident_id: &str,
+        resolution: &str,
+        closed_by: &str,
+    ) -> CoreResult<KnowledgeHarvest> {
+        let closed_at = Utc::now();
+        let incident = {
+            let mut incidents = self.incidents.write().await;
+            let incident = incidents
+                .get_mut(incident_id)
+                .filter(|i| i.tenant_id == tenant_id)
+                .ok_or_else(|| CoreError::not_found(format!("Incident {} not found", incident_id)))?;
+            if incident.status == "Closed" {
+                return Err(CoreError::validation(format!("Incident {} is already closed", incident_id)));
+            }
+            incident.status = "Closed".to_string();
+            incident.updated_at = closed_at;
+            incident.timeline.push(IncidentEvent {
+                event_id: uuid::Uuid::new_v4().to_string(),
+                timestamp: closed_at,
+                event_type: "IncidentClosed".to_string(),
+                description: resolution.to_string(),
+                source: closed_by.to_string(),
+                severity: incident.severity.clone(),
+                data: HashMap::new(),
+            });
+            incident.clone()
+        };
+        self.search.index_incident(&incident)?;
+        self.publish_incident(&incident, "IncidentClosed");
+        self.record_resolution_metrics(&incident, closed_at);
+        self.prometheus.observe_resolution(&incident, closed_at);
+
+        let harvest = harvest_incident(&incident, closed_at);
+        self.knowledge.write().await.add_harvest(harvest.clone());
+        Ok(harvest)
+    }
+
+    /// Full-text search across a tenant's incidents, alerts, tasks and evidence
+    pub async fn search(&self, tenant_id: &str, query: SearchQuery) -> CoreResult<SearchResults> {
+        let search = Arc::clone(&self.search);
+        let tenant_id = tenant_id.to_string();
+        let results = tokio::task::spawn_blocking(move || search.search(&tenant_id, &query))
+            .await
+            .map_err(|e| CoreError::backend("Search task failed").with_source(e))?;
+        Ok(results?)
+    }
+
+    pub async fn get_knowledge_harvest(&self, tenant_id: &str, harvest_id: &str) -> Option<KnowledgeHarvest> {
+        self.knowledge.read().await.get_harvest(harvest_id).filter(|h| h.tenant_id == tenant_id).cloned()
+    }
+
+    pub async fn list_pending_harvests(&self, tenant_id: &str) -> Vec<KnowledgeHarvest> {
+        self.knowledge.read().await.pending_harvests(tenant_id)
+    }
+
+    /// Commit analyst-approved artifacts to the IOC repository, ATT&CK
+    /// coverage model and detection gap report
+    pub async fn review_knowledge_harvest(
+        &self,
+        tenant_id: &str,
+        harvest_id: &str,
+        review: HarvestReview,
+    ) -> CoreResult<KnowledgeHarvest> {
+        let (harvest, approved) = {
+            let mut knowledge = self.knowledge.write().await;
+            if knowledge.get_harvest(harvest_id).is_none_or(|h| h.tenant_id != tenant_id) {
+                return Err(CoreError::not_found(format!("Harvest {} not found", harvest_id)));
+            }
+            knowledge.apply_review(harvest_id, &review).map_err(CoreError::validation)?
+        };
+        if !approved.indicators.is_empty() {
+            self.triage.write().await.load_indicators(approved.indicators);
+        }
+        Ok(harvest)
+    }
+
+    pub async fn get_technique_coverage(&self) -> Vec<TechniqueCoverage> {
+        self.knowledge.read().await.technique_coverage()
+    }
+
+    pub async fn get_detection_gap_report(&self) -> Vec<DetectionGap> {
+        self.knowledge.read().await.detection_gaps()
+    }
+
+    pub async fn create_calendar(&self, calendar: BusinessCalendar) -> CoreResult<BusinessCalendar> {
+        self.calendars.write().await.create(calendar).map_err(CoreError::validation)
+    }
+
+    pub async fn update_calendar(&self, calendar: BusinessCalendar) -> CoreResult<BusinessCalendar> {
+        let mut calendars = self.calendars.write().await;
+        if calendars.get(&calendar.calendar_id).is_none() {
+            return Err(CoreError::not_found(format!("Calendar {} not found", calendar.calendar_id)));
+        }
+        calendars.update(calendar).map_err(CoreError::validation)
+    }
+
+    pub async fn delete_calendar(&self, calendar_id: &str) -> bool {
+        self.calendars.write().await.delete(calendar_id)
+    }
+
+    pub async fn get_calendar(&self, calendar_id: &str) -> Option<BusinessCalendar> {
+        self.calendars.read().await.get(calendar_id).cloned()
+    }
+
+    pub async fn list_calendars(&self) -> Vec<BusinessCalendar> {
+        self.calendars.read().await.list()
+    }
+
+    /// Use `calendar_id` for SLA clocks of `team` that run on business hours
+    pub async fn assign_team_calendar(&self, team: &str, calendar_id: &str) -> CoreResult<()> {
+        let mut calendars = self.calendars.write().await;
+        if calendars.get(calendar_id).is_none() {
+            return Err(CoreError::not_found(format!("Calendar {} not found", calendar_id)));
+        }
+        calendars.assign_team(team, calendar_id).map_err(CoreError::validation)
+    }
+
+    pub async fn evaluate_sla_clock(
+        &self,
+        timing: &SlaTiming,
+        team: Option<&str>,
+        started_at: DateTime<Utc>,
+        target_minutes: i64,
+    ) -> CoreResult<SlaClockStatus> {
+        self.calendars.read().await.evaluate_clock(timing, team, started_at, target_minutes, Utc::now()).map_err(CoreError::validation)
+    }
+
+    /// Delete everything stored for a tenant; returns counts by record kind
+    pub async fn purge_tenant(&self, tenant_id: &str) -> CoreResult<BTreeMap<String, usize>> {
+        let mut purged = BTreeMap::new();
+
+        let alert_ids: Vec<String> = {
+            let mut alerts = self.alerts.write().await;
+            let ids: Vec<String> = alerts.values().filter(|a| a.tenant_id == tenant_id).map(|a| a.alert_id.clone()).collect();
+            for id in &ids {
+                alerts.remove(id);
+            }
+            ids
+        };
+        {
+            let mut results = self.triage_results.write().await;
+            for id in &alert_ids {
+                results.remove(id);
+            }
+        }
+        self.triage.write().await.forget_tenant(tenant_id);
+        purged.insert("alerts".to_string(), alert_ids.len());
+
+        let incidents = {
+            let mut incidents = self.incidents.write().await;
+            let before = incidents.len();
+            incidents.retain(|_, i| i.tenant_id != tenant_id);
+            before - incidents.len()
+        };
+        purged.insert("incidents".to_string(), incidents);
+        purged.insert("knowledge_harvests".to_string(), self.knowledge.write().await.remove_harvests(tenant_id));
+        purged.insert("sla_events".to_string(), self.sla.write().await.forget_tenant(tenant_id));
+        purged.insert("playbooks".to_string(), self.playbooks.write().await.forget_tenant(tenant_id));
+        purged.insert("action_executions".to_string(), self.playbook_actions.forget_tenant(tenant_id));
+        purged.insert("search_documents".to_string(), self.search.purge_tenant(tenant_id)?);
+        purged.insert("metric_series".to_string(), self.metrics_history.forget_tenant(tenant_id));
+        purged.insert("prometheus_series".to_string(), self.prometheus.forget_tenant(tenant_id));
+        purged.extend(self.forget_tasks(tenant_id).await);
+        purged.insert("ticketing_connections".to_string(), self.forget_ticketing(tenant_id).await);
+        #[cfg(feature = "phantom-enterprise-standards")]
+        purged.insert("playbook_repositories".to_string(), self.forget_playbook_repositories(tenant_id).await);
+        Ok(purged)
+    }
+}
+
+/// Request body for `evaluate_sla_clock`
+#[cfg(feature = "napi")]
+#[derive(serde::Deserialize)]
+struct SlaClockRequest {
+    timing: SlaTiming,
+    team: Option<String>,
+    started_at: DateTime<Utc>,
+    target_minutes: i64,
+}
+
+/// NAPI wrapper around the stateful SecOp core
+#[cfg(feature = "napi")]
+#[napi]
+pub struct SecOpCoreNapi {
+    pub(crate) inner: Arc<SecOpCore>,
+    pub(crate) access: AccessGuard,
+    pub(crate) audit: AuditTrail,
+    pub(crate) tenants: TenantGuard,
+}
+
+#[cfg(feature = "napi")]
+impl Default for SecOpCoreNapi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl SecOpCoreNapi {
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        SecOpCoreNapi {
+            inner: Arc::new(SecOpCore::new()),
+            access: crate::access::access_guard(),
+            audit: crate::audit::audit_trail(),
+            tenants: TenantGuard::default(),
+        }
+    }
+
+    /// Triage an inbound alert and return the score breakdown
+    #[napi]
+    pub async fn triage_alert(&self, alert_data: String, auth_token: Option<String>) -> NapiResult<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", "alerts")?;
+        let alert: SecurityAlert = serde_json::from_str(&alert_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid alert data: {}", e)))?;
+
+        let alert_id = alert.alert_id.clone();
+        let result = self.inner.ingest_alert(&tenant_id, alert).await;
+        let result = self.audit.record(&actor, "triage_alert", &alert_id, json!({ "alert": alert_data }), result)
+            .map_err(|e| e.context("Failed to triage alert"))?;
+
+        serde_json::to_string(&result)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Explain how a previously ingested alert was scored
+    #[napi]
+    pub async fn get_triage_result(&self, alert_id: String, auth_token: Option<String>) -> NapiResult<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let result = self.inner.get_triage_result(&tenant_id, &alert_id).await
+            .ok_or_else(|| napi::Error::from_reason(format!("No triage result for alert {}", alert_id)))?;
+
+        serde_json::to_string(&result)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Replace the triage configuration (weights, thresholds, reliability and criticality maps)
+    #[napi]
+    pub async fn configure_triage(&self, config_data: String, auth_token: Option<String>) -> NapiResult<()> {
+        let actor = self.authorize_platform(auth_token, "triage")?;
+        let config: TriageConfig = serde_json::from_str(&config_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid triage config: {}", e)))?;
+        self.inner.configure_triage(config).await;
+        self.audit.record(&actor, "configure_triage", "triage", json!({ "config": config_data }), Ok::<_, String>(()))
+            .map_err(napi::Error::from_reason)
+    }
+
+    /// Load indicators into the IOC repository used for triage
+    #[napi]
+    pub async fn load_ioc_repository(&self, indicators_data: String, auth_token: Option<String>) -> NapiResult<u32> {
+        let actor = self.authorize_platform(auth_token, "ioc_repository")?;
+        let indicators: Vec<ThreatIndicator> = serde_json::from_str(&indicators_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid indicator data: {}", e)))?;
+        let loaded = self.inner.load_ioc_repository(indicators).await as u32;
+        self.audit.record(&actor, "load_ioc_repository", "ioc_repository", json!({ "indicators": indicators_data }), Ok::<_, String>(loaded))
+            .map_err(napi::Error::from_reason)
+    }
+
+    #[napi]
+    pub async fn list_alerts(&self, auth_token: Option<String>) -> NapiResult<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let alerts = self.inner.list_alerts(&tenant_id).await;
+        serde_json::to_string(&alerts)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Page through alerts with an opaque cursor, sort and filters
+    #[napi]
+    pub async fn list_alerts_page(&self, page_json: Option<String>, auth_token: Option<String>) -> NapiResult<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let request = PageRequest::from_json(page_json.as_deref()).map_err(CoreError::from)?;
+        let page = self.inner.alerts_page(&tenant_id, &request).await?.project(&request.fields).map_err(CoreError::from)?;
+        serde_json::to_string(&page)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Page through incidents with an opaque cursor, sort and filters
+    #[napi]
+    pub async fn list_incidents_page(&self, page_json: Option<String>, auth_token: Option<String>) -> NapiResult<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let request = PageRequest::from_json(page_json.as_deref()).map_err(CoreError::from)?;
+        let page = self.inner.incidents_page(&tenant_id, &request).await?.project(&request.fields).map_err(CoreError::from)?;
+        serde_json::to_string(&page)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Correlate stored alerts and return the resulting clusters
+    #[napi]
+    pub async fn correlate_alerts(&self, auth_token: Option<String>) -> NapiResult<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", "alerts")?;
+        let clusters = self.inner.correlate_alerts(&tenant_id).await;
+        let clusters = self.audit.record(&actor, "correlate_alerts", "alerts", json!({}), clusters)
+            .map_err(|e| e.context("Failed to correlate alerts"))?;
+
+        serde_json::to_string(&clusters)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Replace the correlation rules and attachment settings
+    #[napi]
+    pub async fn configure_correlation(&self, config_data: String, auth_token: Option<String>) -> NapiResult<()> {
+        let actor = self.authorize_platform(auth_token, "correlation")?;
+        let config: CorrelationConfig = serde_json::from_str(&config_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid correlation config: {}", e)))?;
+        let result = self.inner.configure_correlation(config).await;
+        Ok(self.audit.record(&actor, "configure_correlation", "correlation", json!({ "config": config_data }), result)
+            .map_err(|e| e.context("Failed to configure correlation"))?)
+    }
+
+    #[napi]
+    pub async fn get_correlation_config(&self) -> NapiResult<String> {
+        let config = self.inner.get_correlation_config().await;
+        serde_json::to_string(&config)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn create_incident(&self, incident_data: String, auth_token: Option<String>) -> NapiResult<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", "incidents")?;
+        let incident: SecurityIncident = serde_json::from_str(&incident_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid incident data: {}", e)))?;
+        let incident_id = incident.incident_id.clone();
+        let result = self.inner.create_incident(&tenant_id, incident).await;
+        let incident_id = result.as_ref().map_or(incident_id, |id| id.clone());
+        Ok(self.audit.record(&actor, "create_incident", &incident_id, json!({ "incident": incident_data }), result)
+            .map_err(|e| e.context("Failed to create incident"))?)
+    }
+
+    /// Close an incident and return the knowledge harvest awaiting review
+    #[napi]
+    pub async fn close_incident(&self, incident_id: String, resolution: String, closed_by: String, auth_token: Option<String>) -> NapiResult<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", &incident_id)?;
+        let harvest = self.inner.close_incident(&tenant_id, &incident_id, &resolution, &closed_by).await;
+        let params = json!({ "resolution": resolution, "closed_by": closed_by });
+        let harvest = self.audit.record(&actor, "close_incident", &incident_id, params, harvest)
+            .map_err(|e| e.context("Failed to close incident"))?;
+
+        serde_json::to_string(&harvest)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Merge duplicate incidents into a primary and return the merge report
+    #[napi]
+    pub async fn merge_incidents(&self, primary_id: String, duplicate_ids: Vec<String>, merged_by: Option<String>, auth_token: Option<String>) -> NapiResult<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", &primary_id)?;
+        let merged_by = merged_by.unwrap_or_else(|| "analyst".to_string());
+        let report = self.inner.merge_incidents(&tenant_id, &primary_id, &duplicate_ids, &merged_by).await;
+        let params = json!({ "duplicate_ids": duplicate_ids, "merged_by": merged_by });
+        let report = self.audit.record(&actor, "merge_incidents", &primary_id, params, report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to merge incidents: {}", e)))?;
+
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Run a search DSL query (JSON `SearchQuery`) and return ranked hits
+    #[napi]
+    pub async fn search(&self, query_dsl: String, auth_token: Option<String>) -> NapiResult<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let query: SearchQuery = serde_json::from_str(&query_dsl)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid search query: {}", e)))?;
+        let results = self.inner.search(&tenant_id, query).await
+            .map_err(|e| e.context("Failed to search"))?;
+
+        serde_json::to_string(&results)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Fetch an incident, including its SLA timers
+    #[napi]
+    pub async fn get_incident(&self, incident_id: String, auth_token: Option<String>) -> NapiResult<Option<String>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        match self.inner.get_incident(&tenant_id, &incident_id).await {
+            Some(incident) => serde_json::to_string(&incident)
+                .map(Some)
+                .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e))),
+            None => Ok(None),
+        }
+    }
+
+    /// Merge incident, alert, sandbox and hunting events into one annotated timeline
+    #[napi]
+    pub async fn build_incident_timeline(
+        &self,
+        incident_id: String,
+        external_events: Option<String>,
+        options: Option<String>,
+        auth_token: Option<String>,
+    ) -> NapiResult<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let external: Vec<TimelineEntry> = match external_events {
+            Some(data) => serde_json::from_str(&data)
+                .map_err(|e| napi::Error::from_reason(format!("Invalid timeline events: {}", e)))?,
+            None => Vec::new(),
+        };
+        let options: TimelineOptions = match options {
+            Some(data) => serde_json::from_str(&data)
+                .map_err(|e| napi::Error::from_reason(format!("Invalid timeline options: {}", e)))?,
+            None => TimelineOptions::default(),
+        };
+
+        let timeline = self.inner.build_incident_timeline(&tenant_id, &incident_id, external, &options).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to build timeline: {}", e)))?;
+
+        serde_json::to_string(&timeline)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn acknowledge_incident(&self, incident_id: String, acknowledged_by: String, auth_token: Option<String>) -> NapiResult<()> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", &incident_id)?;
+        let result = self.inner.acknowledge_incident(&tenant_id, &incident_id, &acknowledged_by).await;
+        let params = json!({ "acknowledged_by": acknowledged_by });
+        self.audit.record(&actor, "acknowledge_incident", &incident_id, params, result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to acknowledge incident: {}", e)))
+    }
+
+    #[napi]
+    pub async fn contain_incident(&self, incident_id: String, containment_status: String, contained_by: String, auth_token: Option<String>) -> NapiResult<()> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", &incident_id)?;
+        let result = self.inner.contain_incident(&tenant_id, &incident_id, &containment_status, &contained_by).await;
+        let params = json!({ "containment_status": containment_status, "contained_by": contained_by });
+        self.audit.record(&actor, "contain_incident", &incident_id, params, result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to contain incident: {}", e)))
+    }
+
+    #[napi]
+    pub async fn configure_sla(&self, config_data: String, auth_token: Option<String>) -> NapiResult<()> {
+        let actor = self.authorize_platform(auth_token, "sla")?;
+        let config: SlaConfig = serde_json::from_str(&config_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid SLA config: {}", e)))?;
+        let result = self.inner.configure_sla(config).await;
+        self.audit.record(&actor, "configure_sla", "sla", json!({ "config": config_data }), result)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to configure SLA: {}", e)))
+    }
+
+    #[napi]
+    pub async fn get_sla_config(&self) -> NapiResult<String> {
+        serde_json::to_string(&self.inner.get_sla_config().await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Evaluate open incidents and return newly raised at-risk and breach events
+    #[napi]
+    pub async fn check_slas(&self, auth_token: Option<String>) -> NapiResult<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let events = self.inner.check_slas(&tenant_id).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to check SLAs: {}", e)))?;
+
+        serde_json::to_string(&events)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn list_sla_events(&self, incident_id: Option<String>, auth_token: Option<String>) -> NapiResult<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let events = self.inner.list_sla_events(&tenant_id, incident_id.as_deref()).await;
+        serde_json::to_string(&events)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn get_sla_metrics(&self, auth_token: Option<String>) -> NapiResult<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let metrics = self.inner.get_sla_metrics(&tenant_id).await
+            .map_err(|e| napi::Error::from_reason(format!("Failed to compute SLA metrics: {}", e)))?;
+
+        serde_json::to_string(&metrics)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn list_pending_harvests(&self, auth_token: Option<String>) -> NapiResult<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let harvests = self.inner.list_pending_harvests(&tenant_id).await;
+        serde_json::to_string(&harvests)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Approve or reject harvested artifacts before they reach shared stores
+    #[napi]
+    pub async fn review_knowledge_harvest(&self, harvest_id: String, review_data: String, auth_token: Option<String>) -> NapiResult<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", &harvest_id)?;
+        let review: HarvestReview = serde_json::from_str(&review_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid review data: {}", e)))?;
+
+        let harvest = self.inner.review_knowledge_harvest(&tenant_id, &harvest_id, review).await;
+        let harvest = self.audit.record(&actor, "review_knowledge_harvest", &harvest_id, json!({ "review": review_data }), harvest)
+            .map_err(|e| e.context("Failed to review harvest"))?;
+
+        serde_json::to_string(&harvest)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn get_technique_coverage(&self) -> NapiResult<String> {
+        let coverage = self.inner.get_technique_coverage().await;
+        serde_json::to_string(&coverage)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn get_detection_gap_report(&self) -> NapiResult<String> {
+        let gaps = self.inner.get_detection_gap_report().await;
+        serde_json::to_string(&gaps)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Create a business calendar (working hours, holidays, timezone)
+    #[napi]
+    pub async fn create_business_calendar(&self, calendar_data: String, auth_token: Option<String>) -> NapiResult<String> {
+        let actor = self.authorize_platform(auth_token, "calendars")?;
+        let calendar: BusinessCalendar = serde_json::from_str(&calendar_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid calendar data: {}", e)))?;
+
+        let calendar_id = calendar.calendar_id.clone();
+        let calendar = self.inner.create_calendar(calendar).await;
+        let calendar_id = calendar.as_ref().map_or(calendar_id, |c| c.calendar_id.clone());
+        let calendar = self.audit.record(&actor, "create_business_calendar", &calendar_id, json!({ "calendar": calendar_data }), calendar)
+            .map_err(|e| e.context("Failed to create calendar"))?;
+
+        serde_json::to_string(&calendar)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn update_business_calendar(&self, calendar_data: String, auth_token: Option<String>) -> NapiResult<String> {
+        let actor = self.authorize_platform(auth_token, "calendars")?;
+        let calendar: BusinessCalendar = serde_json::from_str(&calendar_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid calendar data: {}", e)))?;
+
+        let calendar_id = calendar.calendar_id.clone();
+        let calendar = self.inner.update_calendar(calendar).await;
+        let calendar = self.audit.record(&actor, "update_business_calendar", &calendar_id, json!({ "calendar": calendar_data }), calendar)
+            .map_err(|e| e.context("Failed to update calendar"))?;
+
+        serde_json::to_string(&calendar)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn delete_business_calendar(&self, calendar_id: String, auth_token: Option<String>) -> NapiResult<bool> {
+        let actor = self.authorize_platform(auth_token, "calendars")?;
+        let deleted = self.inner.delete_calendar(&calendar_id).await;
+        self.audit.record(&actor, "delete_business_calendar", &calendar_id, json!({}), Ok::<_, String>(deleted))
+            .map_err(napi::Error::from_reason)
+    }
+
+    #[napi]
+    pub async fn list_business_calendars(&self) -> NapiResult<String> {
+        let calendars = self.inner.list_calendars().await;
+        serde_json::to_string(&calendars)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn assign_team_calendar(&self, team: String, calendar_id: String, auth_token: Option<String>) -> NapiResult<()> {
+        let actor = self.authorize_platform(auth_token, "calendars")?;
+        let result = self.inner.assign_team_calendar(&team, &calendar_id).await;
+        Ok(self.audit.record(&actor, "assign_team_calendar", &team, json!({ "calendar_id": calendar_id }), result)
+            .map_err(|e| e.context("Failed to assign calendar"))?)
+    }
+
+    /// Evaluate an SLA clock on wall-clock or business-hours timing
+    #[napi]
+    pub async fn evaluate_sla_clock(&self, request_data: String) -> NapiResult<String> {
+        let request: SlaClockRequest = serde_json::from_str(&request_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid SLA clock request: {}", e)))?;
+
+        let status = self.inner
+            .evaluate_sla_clock(&request.timing, request.team.as_deref(), request.started_at, request.target_minutes)
+            .await
+            .map_err(|e| e.context("Failed to evaluate SLA clock"))?;
+
+        serde_json::to_string(&status)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+}
+
+#[cfg(feature = "napi")]
+impl SecOpCoreNapi {
+    pub(crate) fn authorize(&self, auth_token: Option<String>, permission: &str, resource: &str) -> NapiResult<String> {
+        self.access.check(auth_token.as_deref(), permission, resource)
+            .map_err(napi::Error::from_reason)
+    }
+
+    /// Tenant lifecycle and settings that span tenants are reserved for
+    /// platform administrators, i.e. credentials with `access:manage` that
+    /// are not bound to a tenant
+    pub(crate) fn authorize_platform(&self, auth_token: Option<String>, resource: &str) -> NapiResult<String> {
+        if self.access.tenant(auth_token.as_deref()).is_some() {
+            return Err(napi::Error::from_reason("Tenant-bound credentials cannot manage platform settings"));
+        }
+        self.access.check(auth_token.as_deref(), "access:manage", resource)
+            .map_err(napi::Error::from_reason)
+    }
+
+    /// Tenant the call acts for, refused when the tenant is not active
+    pub(crate) fn tenant(&self, auth_token: Option<&str>) -> NapiResult<String> {
+        let tenant_id = self.access.tenant(auth_token).unwrap_or_else(|| DEFAULT_TENANT.to_string());
+        self.tenants.check(&tenant_id).map_err(napi::Error::from_reason)?;
+        Ok(tenant_id)
+    }
+}
+
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+#[napi]
+impl SecOpCoreNapi {
+    /// Resolve an incident, alert or IOC reference to its summary
+    #[napi]
+    pub async fn resolve_reference(&self, id: String, auth_token: Option<String>) -> NapiResult<Option<String>> {
+        use phantom_enterprise_standards::EntityId;
+
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let entity_id = EntityId::parse(&id)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to resolve reference: {}", e)))?;
+        match self.inner.resolve_reference(Some(&tenant_id), &entity_id).await {
+            Some(summary) => serde_json::to_string(&summary)
+                .map(Some)
+                .map_err(|e| napi::Error::from_reason(format!("Failed to serialize summary: {}", e))),
+            None => Ok(None),
+        }
+    }
+
+    /// Turn permission enforcement on or off
+    #[napi]
+    pub fn set_access_enforced(&self, enforced: bool, auth_token: Option<String>) -> NapiResult<()> {
+        let actor = self.authorize_platform(auth_token, "access")?;
+        self.access.control().set_enforced(enforced);
+        self.audit.record(&actor, "set_access_enforced", "access", json!({ "enforced": enforced }), Ok::<_, String>(()))
+            .map_err(napi::Error::from_reason)
+    }
+
+    /// Issue an API key for a principal; the token is only returned here
+    #[napi]
+    pub fn issue_api_key(&self, principal_data: String, ttl_secs: Option<i64>, auth_token: Option<String>) -> NapiResult<String> {
+        let caller_tenant = self.access.tenant(auth_token.as_deref());
+        let actor = self.authorize(auth_token, "access:manage", "access")?;
+        let mut principal = serde_json::from_str(&principal_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid principal: {}", e)))?;
+        phantom_enterprise_standards::guard::bind_principal_tenant(caller_tenant, &mut principal)
+            .map_err(napi::Error::from_reason)?;
+        let issued = self.access.control().issue_api_key(principal, ttl_secs.map(chrono::Duration::seconds));
+        let params = json!({ "principal": principal_data, "ttl_secs": ttl_secs });
+        let issued = self.audit.record(&actor, "issue_api_key", &issued.credential_id.clone(), params, Ok::<_, String>(issued))
+            .map_err(napi::Error::from_reason)?;
+        serde_json::to_string(&issued)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Start a session that expires after `ttl_secs`
+    #[napi]
+    pub fn start_session(&self, principal_data: String, ttl_secs: i64, auth_token: Option<String>) -> NapiResult<String> {
+        let caller_tenant = self.access.tenant(auth_token.as_deref());
+        let actor = self.authorize(auth_token, "access:manage", "access")?;
+        let mut principal = serde_json::from_str(&principal_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid principal: {}", e)))?;
+        phantom_enterprise_standards::guard::bind_principal_tenant(caller_tenant, &mut principal)
+            .map_err(napi::Error::from_reason)?;
+        let issued = self.access.control().start_session(principal, chrono::Duration::seconds(ttl_secs));
+        let params = json!({ "principal": principal_data, "ttl_secs": ttl_secs });
+        let issued = self.audit.record(&actor, "start_session", &issued.credential_id.clone(), params, Ok::<_, String>(issued))
+            .map_err(napi::Error::from_reason)?;
+        serde_json::to_string(&issued)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub fn revoke_credential(&self, credential_id: String, auth_token: Option<String>) -> NapiResult<bool> {
+        let caller_tenant = self.access.tenant(auth_token.as_deref());
+        let actor = self.authorize(auth_token, "access:manage", "access")?;
+        let revoked = self.access.control().revoke_in(caller_tenant.as_deref(), &credential_id);
+        self.audit.record(&actor, "revoke_credential", &credential_id, json!({}), Ok::<_, String>(revoked))
+            .map_err(napi::Error::from_reason)
+    }
+
+    #[napi]
+    pub fn list_credentials(&self, auth_token: Option<String>) -> NapiResult<String> {
+        let caller_tenant = self.access.tenant(auth_token.as_deref());
+        self.authorize(auth_token, "access:manage", "access")?;
+        serde_json::to_string(&self.access.control().list_credentials_in(caller_tenant.as_deref()))
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Most recent access denials, newest first
+    #[napi]
+    pub fn list_access_denials(&self, limit: Option<u32>, auth_token: Option<String>) -> NapiResult<String> {
+        let caller_tenant = self.access.tenant(auth_token.as_deref());
+        self.authorize(auth_token, "access:manage", "access")?;
+        let denials = self.access.control().denials_in(caller_tenant.as_deref(), limit.unwrap_or(100) as usize);
+        serde_json::to_string(&denials)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Persist the audit log to a JSON-lines file, resuming any chain already in it
+    #[napi]
+    pub fn open_audit_log(&self, path: String, auth_token: Option<String>) -> NapiResult<()> {
+        self.authorize(auth_token, "access:manage", &path)?;
+        self.audit.open(&path)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to open audit log: {}", e)))
+    }
+
+    /// Walk the hash chain and report the first broken entry, if any
+    #[napi]
+    pub fn verify_audit_log(&self, auth_token: Option<String>) -> NapiResult<String> {
+        self.authorize(auth_token, "audit:read", "audit")?;
+        serde_json::to_string(&self.audit.log().verify())
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// List audit entries matching a JSON `phantom_enterprise_standards::AuditQuery`
+    #[napi]
+    pub fn list_audit_entries(&self, query: Option<String>, auth_token: Option<String>) -> NapiResult<String> {
+        self.authorize(auth_token, "audit:read", "audit")?;
+        let query = parse_query(query)?;
+        serde_json::to_string(&self.audit.log().entries(&query))
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Export audit entries as JSON lines
+    #[napi]
+    pub fn export_audit_log(&self, query: Option<String>, auth_token: Option<String>) -> NapiResult<String> {
+        self.authorize(auth_token, "audit:read", "audit")?;
+        let query = parse_query(query)?;
+        self.audit.log().export_jsonl(&query)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to export audit log: {}", e)))
+    }
+}
+
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+fn parse_query(query: Option<String>) -> NapiResult<phantom_enterprise_standards::AuditQuery> {
+    match query {
+        Some(data) => serde_json::from_str(&data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid audit query: {}", e))),
+        None => Ok(phantom_enterprise_standards::AuditQuery::default()),
+    }
+}
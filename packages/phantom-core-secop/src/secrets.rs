@@ -0,0 +1,144 @@
+//! Connector secrets
+//!
+//! Credentials for connectors are kept in the shared `SecretStore` from
+//! phantom-enterprise-standards, sealed under a master key from the
+//! `PHANTOM_SECOP_MASTER_KEY` environment variable (64 hex digits) unless the
+//! host rotates in one of its own.
+//!
+//! Connectors are configured per tenant, so secrets are too: a tenant's
+//! secrets are stored as `tenants/<tenant id>/<name>`, and a `secret://name`
+//! reference in that tenant's config resolves only among them. Tenant admins
+//! manage their own tenant's secrets; rotating the master key and moving the
+//! sealed records in and out is left to platform admins.
+
+use crate::secop_core::SecOpCore;
+
+pub use phantom_enterprise_standards::secrets::{
+    redact, redacted, reference_name, EnvKey, KeyProvider, KmsKey, SealedSecret, SecretError, SecretMetadata, SecretStore,
+    SecretStoreStatus, StaticKey, SECRET_SCHEME,
+};
+
+#[cfg(feature = "napi")]
+use crate::error::CoreError;
+#[cfg(feature = "napi")]
+use crate::secop_core::SecOpCoreNapi;
+#[cfg(feature = "napi")]
+use napi::bindgen_prelude::Buffer;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+#[cfg(feature = "napi")]
+use serde_json::json;
+#[cfg(feature = "napi")]
+use std::sync::Arc;
+
+/// Environment variable holding the default master key, as 64 hex digits
+pub const MASTER_KEY_ENV: &str = "PHANTOM_SECOP_MASTER_KEY";
+
+/// Store prefix of a tenant's secrets
+pub fn tenant_prefix(tenant_id: &str) -> String {
+    format!("tenants/{}/", tenant_id)
+}
+
+impl SecOpCore {
+    pub fn secrets(&self) -> &SecretStore {
+        &self.secrets
+    }
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl SecOpCoreNapi {
+    /// Store a secret for the caller's tenant; its configs refer to it as
+    /// `secret://<name>`
+    #[napi]
+    pub fn put_secret(&self, name: String, value: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "access:manage", &name)?;
+        let metadata = self.inner.secrets().put(&format!("{}{}", tenant_prefix(&tenant_id), name), &value).map_err(CoreError::from);
+        let metadata = self.audit.record(&actor, "put_secret", &name, json!({}), metadata)
+            .map_err(|e| e.context("Failed to store secret"))?;
+        serde_json::to_string(&tenant_view(&tenant_id, metadata))
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Replace the value of an existing secret of the caller's tenant
+    #[napi]
+    pub fn rotate_secret(&self, name: String, value: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "access:manage", &name)?;
+        let metadata = self.inner.secrets().rotate(&format!("{}{}", tenant_prefix(&tenant_id), name), &value).map_err(CoreError::from);
+        let metadata = self.audit.record(&actor, "rotate_secret", &name, json!({}), metadata)
+            .map_err(|e| e.context("Failed to rotate secret"))?;
+        serde_json::to_string(&tenant_view(&tenant_id, metadata))
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub fn remove_secret(&self, name: String, auth_token: Option<String>) -> napi::Result<()> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "access:manage", &name)?;
+        let outcome = self.inner.secrets().remove(&format!("{}{}", tenant_prefix(&tenant_id), name)).map_err(CoreError::from);
+        self.audit.record(&actor, "remove_secret", &name, json!({}), outcome)
+            .map_err(|e| e.context("Failed to remove secret"))?;
+        Ok(())
+    }
+
+    /// Names and versions of the caller's tenant's secrets; values are never returned
+    #[napi]
+    pub fn list_secrets(&self, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        self.authorize(auth_token, "access:manage", "secrets")?;
+        let prefix = tenant_prefix(&tenant_id);
+        let listed: Vec<SecretMetadata> = self
+            .inner
+            .secrets()
+            .list()
+            .into_iter()
+            .filter(|metadata| metadata.name.starts_with(&prefix))
+            .map(|metadata| tenant_view(&tenant_id, metadata))
+            .collect();
+        serde_json::to_string(&listed)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Re-seal every secret under a new 32-byte master key, e.g. one the host
+    /// unwrapped through its KMS
+    #[napi]
+    pub fn rotate_master_key(&self, master_key: Buffer, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, "secrets")?;
+        let status = self.inner.secrets().rotate_master_key(Arc::new(StaticKey::new(master_key.to_vec()))).map_err(CoreError::from);
+        let status = self.audit.record(&actor, "rotate_master_key", "secrets", json!({}), status)
+            .map_err(|e| e.context("Failed to rotate master key"))?;
+        serde_json::to_string(&status)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Sealed secrets of every tenant for the host to persist
+    #[napi]
+    pub fn export_sealed_secrets(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize_platform(auth_token, "secrets")?;
+        serde_json::to_string(&self.inner.secrets().export_sealed())
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub fn import_sealed_secrets(&self, sealed_json: String, auth_token: Option<String>) -> napi::Result<u32> {
+        let actor = self.authorize_platform(auth_token, "secrets")?;
+        let records: Vec<SealedSecret> = serde_json::from_str(&sealed_json)
+            .map_err(|e| CoreError::from(e).context("Failed to parse sealed secrets"))?;
+        let imported = self.inner.secrets().import_sealed(records).map_err(CoreError::from);
+        let imported = self.audit.record(&actor, "import_sealed_secrets", "secrets", json!({}), imported)
+            .map_err(|e| e.context("Failed to import sealed secrets"))?;
+        Ok(imported as u32)
+    }
+}
+
+/// Metadata as the tenant sees it: the name and reference without the tenant prefix
+#[cfg(feature = "napi")]
+fn tenant_view(tenant_id: &str, mut metadata: SecretMetadata) -> SecretMetadata {
+    if let Some(name) = metadata.name.strip_prefix(&tenant_prefix(tenant_id)).map(str::to_string) {
+        metadata.reference = format!("{}{}", SECRET_SCHEME, name);
+        metadata.name = name;
+    }
+    metadata
+}
@@ -0,0 +1,660 @@
+//! Incident SLA tracking
+//!
+//! SLA policies set time-to-acknowledge, time-to-contain and time-to-resolve
+//! targets per severity and category. Timers start when the incident is
+//! created and stop at the first timeline event that completes them; they are
+//! measured on wall-clock or business-hours timing through the business
+//! calendars. A periodic check raises an internal event (and a timeline
+//! entry) once per timer when it enters the warning window and again when it
+//! breaches.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use crate::calendar::{CalendarStore, SlaClockStatus, SlaTiming};
+use crate::{IncidentEvent, SecOpCore, SecurityIncident};
+
+/// Timeline event types that complete each SLA timer
+pub const ACKNOWLEDGE_EVENTS: &[&str] = &["IncidentAcknowledged"];
+pub const CONTAIN_EVENTS: &[&str] = &["IncidentContained"];
+pub const RESOLVE_EVENTS: &[&str] = &["IncidentResolved", "IncidentClosed", "MergedIntoIncident"];
+
+/// Retained SLA events; older ones are dropped first
+const MAX_SLA_EVENTS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlaMetric {
+    TimeToAcknowledge,
+    TimeToContain,
+    TimeToResolve,
+}
+
+impl SlaMetric {
+    pub const ALL: [SlaMetric; 3] = [SlaMetric::TimeToAcknowledge, SlaMetric::TimeToContain, SlaMetric::TimeToResolve];
+
+    fn completing_events(&self) -> &'static [&'static str] {
+        match self {
+            SlaMetric::TimeToAcknowledge => ACKNOWLEDGE_EVENTS,
+            SlaMetric::TimeToContain => CONTAIN_EVENTS,
+            SlaMetric::TimeToResolve => RESOLVE_EVENTS,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlaPolicy {
+    pub policy_id: String,
+    pub name: String,
+    /// Severities this policy covers; empty matches any
+    #[serde(default)]
+    pub severities: Vec<String>,
+    /// Categories this policy covers; empty matches any
+    #[serde(default)]
+    pub categories: Vec<String>,
+    pub time_to_acknowledge_minutes: i64,
+    pub time_to_contain_minutes: i64,
+    pub time_to_resolve_minutes: i64,
+    #[serde(default = "default_timing")]
+    pub timing: SlaTiming,
+    /// Fraction of the target after which a running timer is at risk
+    #[serde(default = "default_warning_threshold")]
+    pub warning_threshold: f64,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_timing() -> SlaTiming {
+    SlaTiming::WallClock
+}
+
+fn default_warning_threshold() -> f64 {
+    0.8
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl SlaPolicy {
+    fn severity_policy(severity: &str, acknowledge: i64, contain: i64, resolve: i64) -> Self {
+        Self {
+            policy_id: format!("sla-{}", severity.to_lowercase()),
+            name: format!("{} incidents", severity),
+            severities: vec![severity.to_string()],
+            categories: Vec::new(),
+            time_to_acknowledge_minutes: acknowledge,
+            time_to_contain_minutes: contain,
+            time_to_resolve_minutes: resolve,
+            timing: default_timing(),
+            warning_threshold: default_warning_threshold(),
+            enabled: true,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.policy_id.trim().is_empty() {
+            return Err("SLA policy id is required".to_string());
+        }
+        for (metric, minutes) in [
+            ("time_to_acknowledge_minutes", self.time_to_acknowledge_minutes),
+            ("time_to_contain_minutes", self.time_to_contain_minutes),
+            ("time_to_resolve_minutes", self.time_to_resolve_minutes),
+        ] {
+            if minutes <= 0 {
+                return Err(format!("SLA policy {}: {} must be positive", self.policy_id, metric));
+            }
+        }
+        if !(0.0..1.0).contains(&self.warning_threshold) {
+            return Err(format!("SLA policy {}: warning_threshold must be in [0, 1)", self.policy_id));
+        }
+        Ok(())
+    }
+
+    pub fn target_minutes(&self, metric: SlaMetric) -> i64 {
+        match metric {
+            SlaMetric::TimeToAcknowledge => self.time_to_acknowledge_minutes,
+            SlaMetric::TimeToContain => self.time_to_contain_minutes,
+            SlaMetric::TimeToResolve => self.time_to_resolve_minutes,
+        }
+    }
+
+    /// Match score; category matches outrank severity matches
+    fn specificity(&self, incident: &SecurityIncident) -> Option<u8> {
+        let matches = |values: &[String], value: &str| values.iter().any(|v| v.eq_ignore_ascii_case(value));
+        if !self.enabled
+            || (!self.severities.is_empty() && !matches(&self.severities, &incident.severity))
+            || (!self.categories.is_empty() && !matches(&self.categories, &incident.category))
+        {
+            return None;
+        }
+        Some(u8::from(!self.categories.is_empty()) * 2 + u8::from(!self.severities.is_empty()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlaConfig {
+    pub policies: Vec<SlaPolicy>,
+}
+
+impl Default for SlaConfig {
+    fn default() -> Self {
+        Self {
+            policies: vec![
+                SlaPolicy::severity_policy("Critical", 15, 60, 240),
+                SlaPolicy::severity_policy("High", 60, 240, 480),
+                SlaPolicy::severity_policy("Medium", 240, 1440, 2880),
+                SlaPolicy::severity_policy("Low", 480, 2880, 10080),
+            ],
+        }
+    }
+}
+
+impl SlaConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let mut ids = HashSet::new();
+        for policy in &self.policies {
+            policy.validate()?;
+            if !ids.insert(policy.policy_id.as_str()) {
+                return Err(format!("Duplicate SLA policy id {}", policy.policy_id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Most specific enabled policy for an incident; earlier policies win ties
+    pub fn policy_for(&self, incident: &SecurityIncident) -> Option<&SlaPolicy> {
+        let mut best: Option<(u8, &SlaPolicy)> = None;
+        for policy in &self.policies {
+            if let Some(score) = policy.specificity(incident) {
+                if best.is_none_or(|(top, _)| score > top) {
+                    best = Some((score, policy));
+                }
+            }
+        }
+        best.map(|(_, policy)| policy)
+    }
+}
+
+// Timer state
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlaTimerStatus {
+    Running,
+    AtRisk,
+    Breached,
+    Met,
+    /// Completed after the deadline
+    Missed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlaTimer {
+    pub metric: SlaMetric,
+    pub status: SlaTimerStatus,
+    pub target_minutes: i64,
+    pub elapsed_minutes: i64,
+    pub remaining_minutes: i64,
+    pub deadline: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// False while a business-hours clock is paused
+    pub running: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentSlaState {
+    pub policy_id: String,
+    pub evaluated_at: DateTime<Utc>,
+    pub timers: Vec<SlaTimer>,
+    pub breached: bool,
+    pub at_risk: bool,
+}
+
+fn completion_time(incident: &SecurityIncident, metric: SlaMetric) -> Option<DateTime<Utc>> {
+    let from_timeline = incident
+        .timeline
+        .iter()
+        .filter(|event| metric.completing_events().contains(&event.event_type.as_str()))
+        .map(|event| event.timestamp)
+        .min();
+    // Resolving an incident also contains and acknowledges it
+    let later = match metric {
+        SlaMetric::TimeToAcknowledge => completion_time(incident, SlaMetric::TimeToContain),
+        SlaMetric::TimeToContain => completion_time(incident, SlaMetric::TimeToResolve),
+        SlaMetric::TimeToResolve => None,
+    };
+    match (from_timeline, later) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Compute every SLA timer of an incident under `policy`
+pub fn evaluate_incident(
+    incident: &SecurityIncident,
+    policy: &SlaPolicy,
+    calendars: &CalendarStore,
+    now: DateTime<Utc>,
+) -> Result<IncidentSlaState, String> {
+    let team = (!incident.assigned_to.is_empty()).then_some(incident.assigned_to.as_str());
+    let mut timers = Vec::with_capacity(SlaMetric::ALL.len());
+    for metric in SlaMetric::ALL {
+        let target_minutes = policy.target_minutes(metric);
+        let completed_at = completion_time(incident, metric).map(|at| at.max(incident.created_at));
+        let clock: SlaClockStatus =
+            calendars.evaluate_clock(&policy.timing, team, incident.created_at, target_minutes, completed_at.unwrap_or(now))?;
+        let status = match completed_at {
+            Some(_) if clock.breached => SlaTimerStatus::Missed,
+            Some(_) => SlaTimerStatus::Met,
+            None if clock.breached => SlaTimerStatus::Breached,
+            None if clock.elapsed_minutes as f64 >= target_minutes as f64 * policy.warning_threshold => SlaTimerStatus::AtRisk,
+            None => SlaTimerStatus::Running,
+        };
+        timers.push(SlaTimer {
+            metric,
+            status,
+            target_minutes,
+            elapsed_minutes: clock.elapsed_minutes,
+            remaining_minutes: clock.remaining_minutes,
+            deadline: clock.deadline,
+            completed_at,
+            running: completed_at.is_none() && clock.running,
+        });
+    }
+    Ok(IncidentSlaState {
+        policy_id: policy.policy_id.clone(),
+        evaluated_at: now,
+        breached: timers.iter().any(|t| matches!(t.status, SlaTimerStatus::Breached | SlaTimerStatus::Missed)),
+        at_risk: timers.iter().any(|t| t.status == SlaTimerStatus::AtRisk),
+        timers,
+    })
+}
+
+// Events and metrics
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlaEventKind {
+    AtRisk,
+    Breached,
+}
+
+/// Internal event raised when a timer nears or passes its deadline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlaEvent {
+    pub event_id: String,
+    pub incident_id: String,
+    #[serde(default = "crate::tenancy::default_tenant")]
+    pub tenant_id: String,
+    pub policy_id: String,
+    pub metric: SlaMetric,
+    pub kind: SlaEventKind,
+    pub deadline: DateTime<Utc>,
+    pub remaining_minutes: i64,
+    pub raised_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SlaMetricSummary {
+    pub completed: usize,
+    pub met: usize,
+    pub breached: usize,
+    pub at_risk: usize,
+    pub compliance_rate: f64,
+    pub average_elapsed_minutes: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlaMetrics {
+    pub evaluated_at: DateTime<Utc>,
+    pub incidents_tracked: usize,
+    pub incidents_breached: usize,
+    pub incidents_at_risk: usize,
+    /// Share of completed or breached timers that met their target, in percent
+    pub sla_compliance_rate: f64,
+    pub by_metric: HashMap<SlaMetric, SlaMetricSummary>,
+}
+
+/// SLA configuration plus the events already raised
+#[derive(Debug, Default)]
+pub struct SlaTracker {
+    pub config: SlaConfig,
+    raised: HashSet<(String, SlaMetric, SlaEventKind)>,
+    events: Vec<SlaEvent>,
+}
+
+impl SlaTracker {
+    fn record(&mut self, event: SlaEvent) -> bool {
+        if !self.raised.insert((event.incident_id.clone(), event.metric, event.kind)) {
+            return false;
+        }
+        if self.events.len() >= MAX_SLA_EVENTS {
+            self.events.remove(0);
+        }
+        self.events.push(event);
+        true
+    }
+
+    pub fn events(&self, tenant_id: &str, incident_id: Option<&str>) -> Vec<SlaEvent> {
+        self.events
+            .iter()
+            .filter(|e| e.tenant_id == tenant_id && incident_id.is_none_or(|id| e.incident_id == id))
+            .cloned()
+            .collect()
+    }
+
+    /// Drop a tenant's events; returns how many were removed
+    pub fn forget_tenant(&mut self, tenant_id: &str) -> usize {
+        let before = self.events.len();
+        let (removed, kept): (Vec<SlaEvent>, Vec<SlaEvent>) =
+            std::mem::take(&mut self.events).into_iter().partition(|e| e.tenant_id == tenant_id);
+        for event in &removed {
+            self.raised.remove(&(event.incident_id.clone(), event.metric, event.kind));
+        }
+        self.events = kept;
+        before - self.events.len()
+    }
+}
+
+impl SecOpCore {
+    pub async fn get_sla_config(&self) -> SlaConfig {
+        self.sla.read().await.config.clone()
+    }
+
+    pub async fn configure_sla(&self, config: SlaConfig) -> Result<(), String> {
+        config.validate()?;
+        self.sla.write().await.config = config;
+        Ok(())
+    }
+
+    /// Current SLA state of an incident; `None` when no policy applies
+    pub async fn get_incident_sla(&self, tenant_id: &str, incident_id: &str) -> Result<Option<IncidentSlaState>, String> {
+        let incident = self
+            .incidents
+            .read()
+            .await
+            .get(incident_id)
+            .filter(|i| i.tenant_id == tenant_id)
+            .cloned()
+            .ok_or_else(|| format!("Incident {} not found", incident_id))?;
+        self.evaluate_sla(&incident, Utc::now()).await
+    }
+
+    pub(crate) async fn evaluate_sla(
+        &self,
+        incident: &SecurityIncident,
+        now: DateTime<Utc>,
+    ) -> Result<Option<IncidentSlaState>, String> {
+        let sla = self.sla.read().await;
+        let Some(policy) = sla.config.policy_for(incident) else { return Ok(None) };
+        let calendars = self.calendars.read().await;
+        evaluate_incident(incident, policy, &calendars, now).map(Some)
+    }
+
+    /// Record an analyst acknowledging the incident
+    pub async fn acknowledge_incident(&self, tenant_id: &str, incident_id: &str, acknowledged_by: &str) -> Result<(), String> {
+        self.record_incident_event(tenant_id, incident_id, "IncidentAcknowledged", "Incident acknowledged", acknowledged_by, None)
+            .await
+    }
+
+    /// Record that the incident's spread has been stopped
+    pub async fn contain_incident(
+        &self,
+        tenant_id: &str,
+        incident_id: &str,
+        containment_status: &str,
+        contained_by: &str,
+    ) -> Result<(), String> {
+        self.record_incident_event(
+            tenant_id,
+            incident_id,
+            "IncidentContained",
+            &format!("Containment status set to {}", containment_status),
+            contained_by,
+            Some(containment_status),
+        )
+        .await
+    }
+
+    async fn record_incident_event(
+        &self,
+        tenant_id: &str,
+        incident_id: &str,
+        event_type: &str,
+        description: &str,
+        source: &str,
+        containment_status: Option<&str>,
+    ) -> Result<(), String> {
+        let now = Utc::now();
+        let mut incidents = self.incidents.write().await;
+        let incident = incidents
+            .get_mut(incident_id)
+            .filter(|i| i.tenant_id == tenant_id)
+            .ok_or_else(|| format!("Incident {} not found", incident_id))?;
+        if incident.status == "Closed" {
+            return Err(format!("Incident {} is closed", incident_id));
+        }
+        if let Some(status) = containment_status {
+            incident.containment_status = status.to_string();
+        }
+        incident.updated_at = now;
+        incident.timeline.push(IncidentEvent {
+            event_id: Uuid::new_v4().to_string(),
+            timestamp: now,
+            event_type: event_type.to_string(),
+            description: description.to_string(),
+            source: source.to_string(),
+            severity: incident.severity.clone(),
+            data: HashMap::new(),
+        });
+        self.publish_incident(incident, event_type);
+        self.search.index_incident(incident)
+    }
+
+    /// Evaluate a tenant's open incidents and raise each at-risk or breach
+    /// event once.
+    ///
+    /// Returns only the events raised by this check.
+    pub async fn check_slas(&self, tenant_id: &str) -> Result<Vec<SlaEvent>, String> {
+        let now = Utc::now();
+        let open: Vec<SecurityIncident> = self
+            .incidents
+            .read()
+            .await
+            .values()
+            .filter(|i| i.tenant_id == tenant_id && i.status != "Closed" && i.merged_into.is_none())
+            .cloned()
+            .collect();
+
+        let mut raised = Vec::new();
+        for incident in &open {
+            let Some(state) = self.evaluate_sla(incident, now).await? else { continue };
+            let mut sla = self.sla.write().await;
+            for timer in &state.timers {
+                let kind = match timer.status {
+                    SlaTimerStatus::AtRisk => SlaEventKind::AtRisk,
+                    SlaTimerStatus::Breached => SlaEventKind::Breached,
+                    _ => continue,
+                };
+                let event = SlaEvent {
+                    event_id: Uuid::new_v4().to_string(),
+                    incident_id: incident.incident_id.clone(),
+                    tenant_id: incident.tenant_id.clone(),
+                    policy_id: state.policy_id.clone(),
+                    metric: timer.metric,
+                    kind,
+                    deadline: timer.deadline,
+                    remaining_minutes: timer.remaining_minutes,
+                    raised_at: now,
+                };
+                if sla.record(event.clone()) {
+                    raised.push(event);
+                }
+            }
+        }
+
+        if !raised.is_empty() {
+            let mut incidents = self.incidents.write().await;
+            for event in &raised {
+                let Some(incident) = incidents.get_mut(&event.incident_id) else { continue };
+                let (event_type, verb) = match event.kind {
+                    SlaEventKind::AtRisk => ("SlaAtRisk", "at risk"),
+                    SlaEventKind::Breached => ("SlaBreached", "breached"),
+                };
+                incident.timeline.push(IncidentEvent {
+                    event_id: event.event_id.clone(),
+                    timestamp: now,
+                    event_type: event_type.to_string(),
+                    description: format!("{:?} SLA {} (deadline {})", event.metric, verb, event.deadline.to_rfc3339()),
+                    source: "sla".to_string(),
+                    severity: incident.severity.clone(),
+                    data: HashMap::from([
+                        ("policy_id".to_string(), event.policy_id.clone()),
+                        ("remaining_minutes".to_string(), event.remaining_minutes.to_string()),
+                    ]),
+                });
+            }
+        }
+        Ok(raised)
+    }
+
+    pub async fn list_sla_events(&self, tenant_id: &str, incident_id: Option<&str>) -> Vec<SlaEvent> {
+        self.sla.read().await.events(tenant_id, incident_id)
+    }
+
+    /// SLA compliance across a tenant's incidents with an applicable policy
+    pub async fn get_sla_metrics(&self, tenant_id: &str) -> Result<SlaMetrics, String> {
+        let now = Utc::now();
+        let incidents: Vec<SecurityIncident> =
+            self.incidents.read().await.values().filter(|i| i.tenant_id == tenant_id).cloned().collect();
+        let mut metrics = SlaMetrics {
+            evaluated_at: now,
+            incidents_tracked: 0,
+            incidents_breached: 0,
+            incidents_at_risk: 0,
+            sla_compliance_rate: 100.0,
+            by_metric: HashMap::new(),
+        };
+        let mut elapsed_totals: HashMap<SlaMetric, i64> = HashMap::new();
+        for incident in incidents.iter().filter(|i| i.merged_into.is_none()) {
+            let Some(state) = self.evaluate_sla(incident, now).await? else { continue };
+            metrics.incidents_tracked += 1;
+            metrics.incidents_breached += usize::from(state.breached);
+            metrics.incidents_at_risk += usize::from(state.at_risk);
+            for timer in &state.timers {
+                let summary = metrics.by_metric.entry(timer.metric).or_default();
+                match timer.status {
+                    SlaTimerStatus::Met => summary.met += 1,
+                    SlaTimerStatus::Missed | SlaTimerStatus::Breached => summary.breached += 1,
+                    SlaTimerStatus::AtRisk => summary.at_risk += 1,
+                    SlaTimerStatus::Running => {}
+                }
+                if timer.completed_at.is_some() {
+                    summary.completed += 1;
+                    *elapsed_totals.entry(timer.metric).or_default() += timer.elapsed_minutes;
+                }
+            }
+        }
+
+        let (mut met, mut decided) = (0, 0);
+        for (metric, summary) in metrics.by_metric.iter_mut() {
+            let judged = summary.met + summary.breached;
+            summary.compliance_rate = if judged == 0 { 100.0 } else { summary.met as f64 * 100.0 / judged as f64 };
+            if summary.completed > 0 {
+                summary.average_elapsed_minutes = elapsed_totals[metric] as f64 / summary.completed as f64;
+            }
+            met += summary.met;
+            decided += judged;
+        }
+        if decided > 0 {
+            metrics.sla_compliance_rate = met as f64 * 100.0 / decided as f64;
+        }
+        Ok(metrics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn incident(id: &str, severity: &str, category: &str, age_minutes: i64) -> SecurityIncident {
+        let created_at = Utc::now() - Duration::minutes(age_minutes);
+        SecurityIncident {
+            incident_id: id.to_string(),
+            title: format!("Incident {}", id),
+            description: String::new(),
+            severity: severity.to_string(),
+            status: "Open".to_string(),
+            category: category.to_string(),
+            priority: 1,
+            created_at,
+            updated_at: created_at,
+            assigned_to: "soc".to_string(),
+            reporter: "edr".to_string(),
+            affected_systems: Vec::new(),
+            indicators: Vec::new(),
+            timeline: Vec::new(),
+            mitigation_actions: Vec::new(),
+            estimated_impact: 1.0,
+            containment_status: "None".to_string(),
+            evidence: Vec::new(),
+            related_alerts: Vec::new(),
+            tags: Vec::new(),
+            merged_into: None,
+            sla: None,
+            tenant_id: "default".to_string(),
+            external_id: None,
+            ticket: None,
+        }
+    }
+
+    #[test]
+    fn test_policy_selection_prefers_category_and_validates() {
+        let mut config = SlaConfig::default();
+        let mut ransomware = SlaPolicy::severity_policy("Critical", 5, 30, 120);
+        ransomware.policy_id = "ransomware".to_string();
+        ransomware.categories = vec!["Ransomware".to_string()];
+        config.policies.push(ransomware);
+        config.validate().unwrap();
+
+        assert_eq!(config.policy_for(&incident("A", "critical", "ransomware", 0)).unwrap().policy_id, "ransomware");
+        assert_eq!(config.policy_for(&incident("B", "Critical", "Phishing", 0)).unwrap().policy_id, "sla-critical");
+        assert!(config.policy_for(&incident("C", "Informational", "Phishing", 0)).is_none());
+
+        config.policies[0].time_to_contain_minutes = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_timers_from_timeline_events_and_metrics() {
+        let core = SecOpCore::new();
+        // High: acknowledge 60, contain 240, resolve 480
+        core.create_incident("default", incident("INC-1", "High", "Malware", 50)).await.unwrap();
+        core.create_incident("default", incident("INC-2", "High", "Malware", 90)).await.unwrap();
+        core.acknowledge_incident("default", "INC-1", "analyst").await.unwrap();
+
+        let state = core.get_incident_sla("default", "INC-1").await.unwrap().unwrap();
+        assert_eq!(state.policy_id, "sla-high");
+        assert_eq!(state.timers[0].status, SlaTimerStatus::Met);
+        assert_eq!(state.timers[1].status, SlaTimerStatus::Running);
+        assert!(!state.breached);
+
+        let events = core.check_slas("default").await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].incident_id.as_str(), events[0].kind), ("INC-2", SlaEventKind::Breached));
+        assert!(core.check_slas("default").await.unwrap().is_empty());
+        let breached = core.get_incident("default", "INC-2").await.unwrap();
+        assert_eq!(breached.timeline.last().unwrap().event_type, "SlaBreached");
+        assert!(breached.sla.unwrap().breached);
+
+        let metrics = core.get_sla_metrics("default").await.unwrap();
+        assert_eq!(metrics.incidents_tracked, 2);
+        assert_eq!(metrics.incidents_breached, 1);
+        assert_eq!(metrics.sla_compliance_rate, 50.0);
+        assert_eq!(metrics.by_metric[&SlaMetric::TimeToAcknowledge].completed, 1);
+    }
+}
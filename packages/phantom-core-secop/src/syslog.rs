@@ -0,0 +1,173 @@
+//! SIEM Forwarding
+//!
+//! With `phantom-enterprise-standards` enabled and a collector configured,
+//! every alert that enters the SOC is forwarded as a CEF or LEEF syslog
+//! event once triage has set its disposition. Delivery happens in the
+//! background and is buffered while the collector is down. Events of every
+//! tenant go to the same collector, so only platform administrators can
+//! configure it. Without the feature nothing is forwarded.
+
+use crate::secop_core::SecOpCore;
+use crate::SecurityAlert;
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::syslog::{ForwarderMetrics, SecurityEvent, SecurityEventKind, SyslogConfig, SyslogForwarder};
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::sync::{Arc, RwLock};
+
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+use crate::secop_core::SecOpCoreNapi;
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+use napi_derive::napi;
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+use serde_json::json;
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[derive(Default)]
+pub struct SyslogState {
+    forwarder: RwLock<Option<Arc<SyslogForwarder>>>,
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+impl SyslogState {
+    pub fn forwarder(&self) -> Option<Arc<SyslogForwarder>> {
+        self.forwarder.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// SIEM event for a triaged alert
+#[cfg(feature = "phantom-enterprise-standards")]
+pub fn alert_event(alert: &SecurityAlert) -> SecurityEvent {
+    let severity = match alert.priority.to_lowercase().as_str() {
+        "critical" | "p1" => 10,
+        "high" | "p2" => 8,
+        "medium" | "p3" => 5,
+        "low" | "p4" => 3,
+        _ => 5,
+    };
+    let indicators = |kind: &str| {
+        alert
+            .indicators
+            .iter()
+            .filter(|indicator| indicator.indicator_type.eq_ignore_ascii_case(kind))
+            .map(|indicator| indicator.value.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+    SecurityEvent::new(SecurityEventKind::Alert, &alert.rule_id, &alert.title, severity)
+        .field("external_id", &alert.alert_id)
+        .field("message", &alert.description)
+        .field("category", &alert.rule_name)
+        .field("outcome", &alert.status)
+        .field("source", &alert.source)
+        .field("destination_host", alert.affected_assets.join(","))
+        .field("source_ip", indicators("ip"))
+        .field("file_hash", indicators("hash"))
+        .field("correlation_id", alert.correlation_id.as_deref().unwrap_or_default())
+        .field("tenant_id", &alert.tenant_id)
+}
+
+impl SecOpCore {
+    /// Forward a triaged alert to the SIEM
+    pub(crate) fn forward_alert(&self, alert: &SecurityAlert) {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        if let Some(forwarder) = self.syslog.forwarder() {
+            forwarder.forward(&alert_event(alert));
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        let _ = alert;
+    }
+
+    /// Start forwarding to the collector in `config`, or stop with `None`.
+    /// Messages still buffered for a replaced collector are discarded.
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn configure_syslog(&self, config: Option<SyslogConfig>) -> Result<Option<ForwarderMetrics>, String> {
+        let forwarder = config.map(SyslogForwarder::new).transpose()?.map(Arc::new);
+        let metrics = forwarder.as_ref().map(|forwarder| forwarder.metrics());
+        *self.syslog.forwarder.write().unwrap_or_else(|e| e.into_inner()) = forwarder;
+        Ok(metrics)
+    }
+
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn syslog_forwarder(&self) -> Option<Arc<SyslogForwarder>> {
+        self.syslog.forwarder()
+    }
+}
+
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+#[napi]
+impl SecOpCoreNapi {
+    /// Forward triaged alerts to a syslog collector (UDP/TCP/TLS, CEF or
+    /// LEEF); pass no config to stop forwarding
+    #[napi]
+    pub fn configure_syslog_forwarding(&self, config_json: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, "syslog")?;
+        let config: Option<SyslogConfig> = config_json
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Invalid syslog config: {}", e)))?;
+
+        let metrics = self.inner.configure_syslog(config);
+        let metrics = self.audit.record(&actor, "configure_syslog_forwarding", "syslog", json!({ "config": config_json }), metrics)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to configure syslog forwarding: {}", e)))?;
+
+        serde_json::to_string(&metrics)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Delivery counters of the syslog forwarder; `null` when not configured
+    #[napi]
+    pub fn get_syslog_forwarding_metrics(&self) -> napi::Result<String> {
+        let metrics = self.inner.syslog_forwarder().map(|forwarder| forwarder.metrics());
+        serde_json::to_string(&metrics)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Retry delivery of buffered events now
+    #[napi]
+    pub async fn flush_syslog_forwarding(&self) -> napi::Result<String> {
+        let Some(forwarder) = self.inner.syslog_forwarder() else {
+            return Err(napi::Error::from_reason("Syslog forwarding is not configured"));
+        };
+        forwarder.flush().await;
+        serde_json::to_string(&forwarder.metrics())
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+}
+
+#[cfg(all(test, feature = "phantom-enterprise-standards"))]
+mod tests {
+    use super::*;
+    use phantom_enterprise_standards::syslog::{format_cef, SyslogFormat};
+
+    #[tokio::test]
+    async fn triaged_alerts_are_queued_for_the_siem() {
+        let core = SecOpCore::new();
+        let config: SyslogConfig = serde_json::from_value(serde_json::json!({
+            "host": "127.0.0.1", "port": 9, "format": SyslogFormat::Cef, "product": "secop"
+        }))
+        .unwrap();
+        core.configure_syslog(Some(config.clone())).unwrap();
+
+        let alert: SecurityAlert = serde_json::from_value(serde_json::json!({
+            "alert_id": "ALR-1", "title": "Beacon to known C2", "description": "Periodic HTTPS beacon",
+            "priority": "High", "status": "New", "source": "edr",
+            "created_at": chrono::Utc::now(), "updated_at": chrono::Utc::now(),
+            "rule_id": "R-C2", "rule_name": "C2 beaconing", "affected_assets": ["web-01"],
+            "indicators": [], "raw_data": "{}", "false_positive_probability": 0.1, "correlation_id": null
+        }))
+        .unwrap();
+        core.ingest_alert("acme", alert).await.unwrap();
+
+        let metrics = core.syslog_forwarder().unwrap().metrics();
+        assert_eq!(metrics.forwarded, 1);
+
+        let stored = core.get_alert("acme", "ALR-1").await.unwrap();
+        let cef = format_cef(&config, &alert_event(&stored));
+        assert!(cef.starts_with("CEF:0|Phantom Spire|secop|1.0|R-C2|Beacon to known C2|8|"));
+        assert!(cef.contains(" dhost=web-01"));
+        assert!(cef.contains(&format!(" outcome={}", stored.status)));
+        assert!(cef.contains(" tenant_id=acme"));
+    }
+}
@@ -0,0 +1,902 @@
+//! SOC tasks and shift handover
+//!
+//! Tasks are per-tenant work items, optionally tied to an incident. A task
+//! can recur by an RRULE-like rule (`FREQ=WEEKLY;BYDAY=MO,TH;COUNT=10`);
+//! completing an occurrence instantiates the next one of its series, skipping
+//! occurrences that already passed. Cancelling a task ends its series.
+//!
+//! A shift handover snapshots what the incoming shift inherits: open
+//! incidents with their SLA state, open tasks, knowledge harvests awaiting
+//! review and the hunts the outgoing shift reports as still running (hunts
+//! are executed by the hunting core, so they are supplied by the caller).
+
+use crate::error::CoreResult;
+use crate::knowledge::ArtifactStatus;
+use crate::pagination::{paginate, ListSpec, Page, PageRequest, SortDirection};
+use crate::secop_core::SecOpCore;
+use crate::sla::IncidentSlaState;
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, Utc, Weekday};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+use uuid::Uuid;
+
+#[cfg(feature = "napi")]
+use crate::error::CoreError;
+#[cfg(feature = "napi")]
+use crate::secop_core::SecOpCoreNapi;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+#[cfg(feature = "napi")]
+use serde_json::json;
+
+// Recurrence
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// Subset of RFC 5545 RRULE: `FREQ`, `INTERVAL`, `BYDAY`, `COUNT` and
+/// `UNTIL`, serialized as the rule string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    /// Weekdays occurrences fall on; only used with daily and weekly rules
+    pub by_day: Vec<Weekday>,
+    /// Total occurrences in the series, the first one included
+    pub count: Option<u32>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+const WEEKDAYS: [(Weekday, &str); 7] = [
+    (Weekday::Mon, "MO"),
+    (Weekday::Tue, "TU"),
+    (Weekday::Wed, "WE"),
+    (Weekday::Thu, "TH"),
+    (Weekday::Fri, "FR"),
+    (Weekday::Sat, "SA"),
+    (Weekday::Sun, "SU"),
+];
+
+fn parse_positive(key: &str, value: &str) -> Result<u32, String> {
+    value
+        .parse::<u32>()
+        .ok()
+        .filter(|n| *n > 0)
+        .ok_or_else(|| format!("Recurrence {} must be a positive integer, got '{}'", key, value))
+}
+
+fn parse_until(value: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(at) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ") {
+        return Ok(at.and_utc());
+    }
+    // A bare date includes the whole day
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y%m%d") {
+        return Ok(date.and_hms_opt(23, 59, 59).unwrap_or_default().and_utc());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|at| at.with_timezone(&Utc))
+        .map_err(|_| format!("Invalid recurrence UNTIL '{}'", value))
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, String> {
+        let rule = rule.trim();
+        let rule = rule.strip_prefix("RRULE:").unwrap_or(rule);
+        let mut frequency = None;
+        let mut parsed = RecurrenceRule { frequency: Frequency::Daily, interval: 1, by_day: Vec::new(), count: None, until: None };
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part.split_once('=').ok_or_else(|| format!("Invalid recurrence part '{}'", part))?;
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.to_ascii_uppercase().as_str() {
+                        "HOURLY" => Frequency::Hourly,
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        _ => return Err(format!("Unsupported recurrence frequency '{}'", value)),
+                    })
+                }
+                "INTERVAL" => parsed.interval = parse_positive("INTERVAL", value)?,
+                "COUNT" => parsed.count = Some(parse_positive("COUNT", value)?),
+                "UNTIL" => parsed.until = Some(parse_until(value)?),
+                "BYDAY" => {
+                    parsed.by_day = value
+                        .split(',')
+                        .map(|day| {
+                            WEEKDAYS
+                                .iter()
+                                .find(|(_, code)| code.eq_ignore_ascii_case(day.trim()))
+                                .map(|(weekday, _)| *weekday)
+                                .ok_or_else(|| format!("Invalid recurrence weekday '{}'", day))
+                        })
+                        .collect::<Result<_, _>>()?;
+                }
+                other => return Err(format!("Unsupported recurrence part '{}'", other)),
+            }
+        }
+        parsed.frequency = frequency.ok_or("Recurrence rule requires FREQ")?;
+        if !parsed.by_day.is_empty() && !matches!(parsed.frequency, Frequency::Daily | Frequency::Weekly) {
+            return Err("BYDAY is only supported with daily and weekly rules".to_string());
+        }
+        Ok(parsed)
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Hourly => "HOURLY",
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+        };
+        write!(f, "FREQ={}", frequency)?;
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<&str> = self
+                .by_day
+                .iter()
+                .filter_map(|day| WEEKDAYS.iter().find(|(weekday, _)| weekday == day).map(|(_, code)| *code))
+                .collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format("%Y%m%dT%H%M%SZ"))?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for RecurrenceRule {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for RecurrenceRule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl RecurrenceRule {
+    /// First occurrence of the series starting at `start` that falls after `after`
+    pub fn next_after(&self, start: DateTime<Utc>, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let next = if self.by_day.is_empty() {
+            self.next_step(start, after)?
+        } else {
+            self.next_matching_day(start, after)?
+        };
+        match self.until {
+            Some(until) if next > until => None,
+            _ => Some(next),
+        }
+    }
+
+    fn next_step(&self, start: DateTime<Utc>, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if start > after {
+            return Some(start);
+        }
+        let interval = self.interval as i64;
+        let step_secs = match self.frequency {
+            Frequency::Hourly => 3600 * interval,
+            Frequency::Daily => 86400 * interval,
+            Frequency::Weekly => 7 * 86400 * interval,
+            Frequency::Monthly => {
+                // Counted from the start so short months do not shift later occurrences
+                let elapsed = (after.year() - start.year()) as i64 * 12 + after.month() as i64 - start.month() as i64;
+                let mut periods = (elapsed.max(0) / interval) as u32;
+                loop {
+                    let candidate = start.checked_add_months(Months::new(periods.checked_mul(self.interval)?))?;
+                    if candidate > after {
+                        return Some(candidate);
+                    }
+                    periods += 1;
+                }
+            }
+        };
+        let periods = (after - start).num_seconds() / step_secs + 1;
+        start.checked_add_signed(Duration::seconds(periods.checked_mul(step_secs)?))
+    }
+
+    /// Walk forward day by day to the next allowed weekday of an active period
+    fn next_matching_day(&self, start: DateTime<Utc>, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start_date = start.date_naive();
+        let start_week = start_date - Duration::days(start_date.weekday().num_days_from_monday() as i64);
+        let first = after.date_naive().max(start_date);
+        let interval = self.interval as i64;
+        (0..=7 * interval + 7).find_map(|offset| {
+            let date = first + Duration::days(offset);
+            let candidate = date.and_time(start.time()).and_utc();
+            let period = match self.frequency {
+                Frequency::Weekly => {
+                    let week = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+                    (week - start_week).num_weeks()
+                }
+                _ => (date - start_date).num_days(),
+            };
+            let due = candidate > after && candidate >= start && period % interval == 0 && self.by_day.contains(&date.weekday());
+            due.then_some(candidate)
+        })
+    }
+}
+
+// Tasks
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TaskStatus {
+    #[default]
+    Open,
+    InProgress,
+    Completed,
+    Cancelled,
+}
+
+impl TaskStatus {
+    pub fn is_open(&self) -> bool {
+        matches!(self, TaskStatus::Open | TaskStatus::InProgress)
+    }
+}
+
+fn first_occurrence() -> u32 {
+    1
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SocTask {
+    #[serde(default)]
+    pub task_id: String,
+    #[serde(default = "crate::tenancy::default_tenant")]
+    pub tenant_id: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub assignee: Option<String>,
+    #[serde(default)]
+    pub team: Option<String>,
+    #[serde(default)]
+    pub incident_id: Option<String>,
+    #[serde(default)]
+    pub status: TaskStatus,
+    #[serde(default)]
+    pub due_at: Option<DateTime<Utc>>,
+    /// Recurring tasks need a `due_at` for their first occurrence
+    #[serde(default)]
+    pub recurrence: Option<RecurrenceRule>,
+    /// Id of the first task of a recurring series
+    #[serde(default)]
+    pub series_id: Option<String>,
+    /// Due time of the series' first occurrence, which the rule counts from
+    #[serde(default)]
+    pub series_start: Option<DateTime<Utc>>,
+    #[serde(default = "first_occurrence")]
+    pub occurrence: u32,
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+    #[serde(default = "Utc::now")]
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub completed_by: Option<String>,
+    /// Occurrence instantiated when this one was completed
+    #[serde(default)]
+    pub next_task_id: Option<String>,
+    /// Id in the system the task was imported from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
+}
+
+impl SocTask {
+    pub fn is_overdue(&self, now: DateTime<Utc>) -> bool {
+        self.status.is_open() && self.due_at.is_some_and(|due| due < now)
+    }
+
+    /// The series' next occurrence, due after both this one and `now`
+    fn next_occurrence(&self, now: DateTime<Utc>) -> Option<SocTask> {
+        let rule = self.recurrence.as_ref()?;
+        if rule.count.is_some_and(|count| self.occurrence >= count) {
+            return None;
+        }
+        let due = self.due_at?;
+        let start = self.series_start.unwrap_or(due);
+        let due_at = rule.next_after(start, due.max(now))?;
+        Some(SocTask {
+            task_id: format!("task_{}", Uuid::new_v4().simple()),
+            status: TaskStatus::Open,
+            due_at: Some(due_at),
+            series_start: Some(start),
+            occurrence: self.occurrence + 1,
+            created_at: now,
+            updated_at: now,
+            completed_at: None,
+            completed_by: None,
+            next_task_id: None,
+            external_id: None,
+            ..self.clone()
+        })
+    }
+}
+
+/// A completed task and the occurrence that replaces it, if it recurs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskCompletion {
+    pub completed: SocTask,
+    pub next: Option<SocTask>,
+}
+
+// Shift handover
+
+/// A hunt the outgoing shift hands over
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveHunt {
+    pub hunt_id: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(default)]
+    pub notes: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HandoverRequest {
+    /// Analyst or team taking over
+    #[serde(default)]
+    pub incoming: Option<String>,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub active_hunts: Vec<ActiveHunt>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandoverIncident {
+    pub incident_id: String,
+    pub title: String,
+    pub severity: String,
+    pub status: String,
+    pub priority: u32,
+    pub assigned_to: String,
+    pub containment_status: String,
+    pub created_at: DateTime<Utc>,
+    pub sla: Option<IncidentSlaState>,
+}
+
+/// A knowledge harvest whose artifacts still await analyst review
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingApproval {
+    pub harvest_id: String,
+    pub incident_id: String,
+    pub pending_artifacts: usize,
+    pub requested_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HandoverSummary {
+    pub open_incidents: usize,
+    pub breached_slas: usize,
+    pub open_tasks: usize,
+    pub overdue_tasks: usize,
+    pub pending_approvals: usize,
+    pub active_hunts: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShiftHandover {
+    pub handover_id: String,
+    pub tenant_id: String,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub incoming: Option<String>,
+    pub notes: String,
+    pub summary: HandoverSummary,
+    pub open_incidents: Vec<HandoverIncident>,
+    pub open_tasks: Vec<SocTask>,
+    pub pending_approvals: Vec<PendingApproval>,
+    pub active_hunts: Vec<ActiveHunt>,
+    pub acknowledged_by: Option<String>,
+    pub acknowledged_at: Option<DateTime<Utc>>,
+}
+
+/// Tasks and handovers of all tenants
+#[derive(Debug, Default)]
+pub struct TaskBoard {
+    tasks: HashMap<String, SocTask>,
+    handovers: HashMap<String, ShiftHandover>,
+}
+
+impl TaskBoard {
+    pub fn create(&mut self, tenant_id: &str, mut task: SocTask, now: DateTime<Utc>) -> Result<SocTask, String> {
+        if task.title.trim().is_empty() {
+            return Err("Task title is required".to_string());
+        }
+        if task.recurrence.is_some() && task.due_at.is_none() {
+            return Err("Recurring tasks require due_at".to_string());
+        }
+        if task.task_id.is_empty() {
+            task.task_id = format!("task_{}", Uuid::new_v4().simple());
+        }
+        if self.tasks.contains_key(&task.task_id) {
+            return Err(format!("Task {} already exists", task.task_id));
+        }
+        task.tenant_id = tenant_id.to_string();
+        task.status = TaskStatus::Open;
+        task.occurrence = 1;
+        task.created_at = now;
+        task.updated_at = now;
+        task.completed_at = None;
+        task.completed_by = None;
+        task.next_task_id = None;
+        if task.recurrence.is_some() {
+            task.series_id = Some(task.task_id.clone());
+            task.series_start = task.due_at;
+        } else {
+            task.series_id = None;
+            task.series_start = None;
+        }
+        self.tasks.insert(task.task_id.clone(), task.clone());
+        Ok(task)
+    }
+
+    /// Store a task migrated from another system as is, keeping its status
+    /// and timestamps; imported tasks do not recur
+    pub(crate) fn import(&mut self, tenant_id: &str, mut task: SocTask) -> Result<SocTask, String> {
+        if task.task_id.is_empty() {
+            task.task_id = format!("task_{}", Uuid::new_v4().simple());
+        }
+        if self.tasks.contains_key(&task.task_id) {
+            return Err(format!("Task {} already exists", task.task_id));
+        }
+        task.tenant_id = tenant_id.to_string();
+        task.recurrence = None;
+        task.series_id = None;
+        task.series_start = None;
+        task.occurrence = 1;
+        task.next_task_id = None;
+        self.tasks.insert(task.task_id.clone(), task.clone());
+        Ok(task)
+    }
+
+    pub fn get(&self, tenant_id: &str, task_id: &str) -> Option<&SocTask> {
+        self.tasks.get(task_id).filter(|task| task.tenant_id == tenant_id)
+    }
+
+    /// The tenant's tasks, soonest due first
+    pub fn list(&self, tenant_id: &str, open_only: bool) -> Vec<SocTask> {
+        let mut tasks: Vec<SocTask> = self
+            .tasks
+            .values()
+            .filter(|task| task.tenant_id == tenant_id && (!open_only || task.status.is_open()))
+            .cloned()
+            .collect();
+        tasks.sort_by(|a, b| {
+            (a.due_at.is_none(), a.due_at, a.created_at, &a.task_id).cmp(&(b.due_at.is_none(), b.due_at, b.created_at, &b.task_id))
+        });
+        tasks
+    }
+
+    fn open_task_mut(&mut self, tenant_id: &str, task_id: &str) -> Result<&mut SocTask, String> {
+        let task = self
+            .tasks
+            .get_mut(task_id)
+            .filter(|task| task.tenant_id == tenant_id)
+            .ok_or_else(|| format!("Task {} not found", task_id))?;
+        if !task.status.is_open() {
+            return Err(format!("Task {} is already {:?}", task_id, task.status));
+        }
+        Ok(task)
+    }
+
+    /// Mark a task in progress, assigning it to `assignee`
+    pub fn start(&mut self, tenant_id: &str, task_id: &str, assignee: &str, now: DateTime<Utc>) -> Result<SocTask, String> {
+        let task = self.open_task_mut(tenant_id, task_id)?;
+        task.status = TaskStatus::InProgress;
+        task.assignee = Some(assignee.to_string());
+        task.updated_at = now;
+        Ok(task.clone())
+    }
+
+    pub fn complete(&mut self, tenant_id: &str, task_id: &str, completed_by: &str, now: DateTime<Utc>) -> Result<TaskCompletion, String> {
+        let task = self.open_task_mut(tenant_id, task_id)?;
+        task.status = TaskStatus::Completed;
+        task.completed_at = Some(now);
+        task.completed_by = Some(completed_by.to_string());
+        task.updated_at = now;
+        let next = task.next_occurrence(now);
+        task.next_task_id = next.as_ref().map(|next| next.task_id.clone());
+        let completed = task.clone();
+        if let Some(next) = &next {
+            self.tasks.insert(next.task_id.clone(), next.clone());
+        }
+        Ok(TaskCompletion { completed, next })
+    }
+
+    /// Cancel a task; a recurring series ends with it
+    pub fn cancel(&mut self, tenant_id: &str, task_id: &str, now: DateTime<Utc>) -> Result<SocTask, String> {
+        let task = self.open_task_mut(tenant_id, task_id)?;
+        task.status = TaskStatus::Cancelled;
+        task.updated_at = now;
+        Ok(task.clone())
+    }
+
+    pub fn forget_tenant(&mut self, tenant_id: &str) -> (usize, usize) {
+        let tasks = self.tasks.len();
+        self.tasks.retain(|_, task| task.tenant_id != tenant_id);
+        let handovers = self.handovers.len();
+        self.handovers.retain(|_, handover| handover.tenant_id != tenant_id);
+        (tasks - self.tasks.len(), handovers - self.handovers.len())
+    }
+}
+
+impl SecOpCore {
+    pub async fn create_task(&self, tenant_id: &str, task: SocTask) -> Result<SocTask, String> {
+        self.tasks.write().await.create(tenant_id, task, Utc::now())
+    }
+
+    pub async fn get_task(&self, tenant_id: &str, task_id: &str) -> Option<SocTask> {
+        self.tasks.read().await.get(tenant_id, task_id).cloned()
+    }
+
+    pub async fn list_tasks(&self, tenant_id: &str, open_only: bool) -> Vec<SocTask> {
+        self.tasks.read().await.list(tenant_id, open_only)
+    }
+
+    /// One page of the tenant's tasks, open and closed, soonest due first by
+    /// default; filter on `status` to narrow it down
+    pub async fn tasks_page(&self, tenant_id: &str, request: &PageRequest) -> CoreResult<Page<SocTask>> {
+        let tasks = self.list_tasks(tenant_id, false).await;
+        Ok(paginate(tasks, request, ListSpec { id_field: "task_id", sort_by: "due_at", direction: SortDirection::Asc })?)
+    }
+
+    pub async fn start_task(&self, tenant_id: &str, task_id: &str, assignee: &str) -> Result<SocTask, String> {
+        self.tasks.write().await.start(tenant_id, task_id, assignee, Utc::now())
+    }
+
+    /// Complete a task, instantiating the next occurrence of a recurring one
+    pub async fn complete_task(&self, tenant_id: &str, task_id: &str, completed_by: &str) -> Result<TaskCompletion, String> {
+        self.tasks.write().await.complete(tenant_id, task_id, completed_by, Utc::now())
+    }
+
+    pub async fn cancel_task(&self, tenant_id: &str, task_id: &str) -> Result<SocTask, String> {
+        self.tasks.write().await.cancel(tenant_id, task_id, Utc::now())
+    }
+
+    /// Snapshot what the incoming shift inherits
+    pub async fn create_shift_handover(&self, tenant_id: &str, created_by: &str, request: HandoverRequest) -> ShiftHandover {
+        let now = Utc::now();
+        let mut open_incidents: Vec<HandoverIncident> = self
+            .list_incidents(tenant_id)
+            .await
+            .into_iter()
+            .filter(|incident| incident.status != "Closed" && incident.merged_into.is_none())
+            .map(|incident| HandoverIncident {
+                incident_id: incident.incident_id,
+                title: incident.title,
+                severity: incident.severity,
+                status: incident.status,
+                priority: incident.priority,
+                assigned_to: incident.assigned_to,
+                containment_status: incident.containment_status,
+                created_at: incident.created_at,
+                sla: incident.sla,
+            })
+            .collect();
+        open_incidents.sort_by_key(|incident| (incident.priority, incident.created_at));
+
+        let pending_approvals: Vec<PendingApproval> = self
+            .list_pending_harvests(tenant_id)
+            .await
+            .into_iter()
+            .map(|harvest| PendingApproval {
+                pending_artifacts: harvest.artifacts.iter().filter(|a| a.status == ArtifactStatus::Pending).count(),
+                harvest_id: harvest.harvest_id,
+                incident_id: harvest.incident_id,
+                requested_at: harvest.created_at,
+            })
+            .collect();
+
+        let open_tasks = self.list_tasks(tenant_id, true).await;
+        let summary = HandoverSummary {
+            open_incidents: open_incidents.len(),
+            breached_slas: open_incidents.iter().filter(|i| i.sla.as_ref().is_some_and(|sla| sla.breached)).count(),
+            open_tasks: open_tasks.len(),
+            overdue_tasks: open_tasks.iter().filter(|task| task.is_overdue(now)).count(),
+            pending_approvals: pending_approvals.len(),
+            active_hunts: request.active_hunts.len(),
+        };
+        let handover = ShiftHandover {
+            handover_id: format!("handover_{}", Uuid::new_v4().simple()),
+            tenant_id: tenant_id.to_string(),
+            created_by: created_by.to_string(),
+            created_at: now,
+            incoming: request.incoming,
+            notes: request.notes,
+            summary,
+            open_incidents,
+            open_tasks,
+            pending_approvals,
+            active_hunts: request.active_hunts,
+            acknowledged_by: None,
+            acknowledged_at: None,
+        };
+        self.tasks.write().await.handovers.insert(handover.handover_id.clone(), handover.clone());
+        handover
+    }
+
+    pub async fn get_shift_handover(&self, tenant_id: &str, handover_id: &str) -> Option<ShiftHandover> {
+        self.tasks.read().await.handovers.get(handover_id).filter(|h| h.tenant_id == tenant_id).cloned()
+    }
+
+    /// The tenant's handovers, most recent first
+    pub async fn list_shift_handovers(&self, tenant_id: &str) -> Vec<ShiftHandover> {
+        let mut handovers: Vec<ShiftHandover> =
+            self.tasks.read().await.handovers.values().filter(|h| h.tenant_id == tenant_id).cloned().collect();
+        handovers.sort_by_key(|handover| std::cmp::Reverse(handover.created_at));
+        handovers
+    }
+
+    /// Record that the incoming shift took over
+    pub async fn acknowledge_shift_handover(&self, tenant_id: &str, handover_id: &str, acknowledged_by: &str) -> Result<ShiftHandover, String> {
+        let mut board = self.tasks.write().await;
+        let handover = board
+            .handovers
+            .get_mut(handover_id)
+            .filter(|h| h.tenant_id == tenant_id)
+            .ok_or_else(|| format!("Handover {} not found", handover_id))?;
+        if let Some(by) = &handover.acknowledged_by {
+            return Err(format!("Handover {} was already acknowledged by {}", handover_id, by));
+        }
+        handover.acknowledged_by = Some(acknowledged_by.to_string());
+        handover.acknowledged_at = Some(Utc::now());
+        Ok(handover.clone())
+    }
+
+    pub(crate) async fn forget_tasks(&self, tenant_id: &str) -> BTreeMap<String, usize> {
+        let (tasks, handovers) = self.tasks.write().await.forget_tenant(tenant_id);
+        BTreeMap::from([("tasks".to_string(), tasks), ("shift_handovers".to_string(), handovers)])
+    }
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl SecOpCoreNapi {
+    /// Create a task; `recurrence` takes an RRULE such as `FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR`
+    #[napi]
+    pub async fn create_task(&self, task_data: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let task: SocTask = serde_json::from_str(&task_data)
+            .map_err(|e| napi::Error::from_reason(format!("Invalid task data: {}", e)))?;
+        let actor = self.authorize(auth_token, "incident:write", "tasks")?;
+        let created = self.inner.create_task(&tenant_id, task).await;
+        let created = self.audit.record(&actor, "create_task", "tasks", json!({ "task": task_data }), created)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to create task: {}", e)))?;
+
+        serde_json::to_string(&created)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn get_task(&self, task_id: String, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        self.authorize(auth_token, "read", &task_id)?;
+        self.inner
+            .get_task(&tenant_id, &task_id)
+            .await
+            .map(|task| serde_json::to_string(&task))
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn list_tasks(&self, open_only: Option<bool>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        self.authorize(auth_token, "read", "tasks")?;
+        serde_json::to_string(&self.inner.list_tasks(&tenant_id, open_only.unwrap_or(false)).await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn list_tasks_page(&self, page_json: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        self.authorize(auth_token, "read", "tasks")?;
+        let request = PageRequest::from_json(page_json.as_deref()).map_err(CoreError::from)?;
+        let page = self.inner.tasks_page(&tenant_id, &request).await?.project(&request.fields).map_err(CoreError::from)?;
+        serde_json::to_string(&page)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn start_task(&self, task_id: String, assignee: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", &task_id)?;
+        let started = self.inner.start_task(&tenant_id, &task_id, &assignee).await;
+        let started = self.audit.record(&actor, "start_task", &task_id, json!({ "assignee": assignee }), started)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to start task: {}", e)))?;
+
+        serde_json::to_string(&started)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Complete a task; the result carries the next occurrence of a recurring task
+    #[napi]
+    pub async fn complete_task(&self, task_id: String, completed_by: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", &task_id)?;
+        let completion = self.inner.complete_task(&tenant_id, &task_id, &completed_by).await;
+        let completion = self.audit.record(&actor, "complete_task", &task_id, json!({ "completed_by": completed_by }), completion)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to complete task: {}", e)))?;
+
+        serde_json::to_string(&completion)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn cancel_task(&self, task_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", &task_id)?;
+        let cancelled = self.inner.cancel_task(&tenant_id, &task_id).await;
+        let cancelled = self.audit.record(&actor, "cancel_task", &task_id, json!({}), cancelled)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to cancel task: {}", e)))?;
+
+        serde_json::to_string(&cancelled)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Snapshot open incidents, tasks, pending approvals and the reported
+    /// active hunts for the incoming shift
+    #[napi]
+    pub async fn create_shift_handover(&self, request_data: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let request: HandoverRequest = match &request_data {
+            Some(data) => serde_json::from_str(data).map_err(|e| napi::Error::from_reason(format!("Invalid handover request: {}", e)))?,
+            None => HandoverRequest::default(),
+        };
+        let actor = self.authorize(auth_token, "incident:write", "shift_handovers")?;
+        let handover = self.inner.create_shift_handover(&tenant_id, &actor, request).await;
+        let params = json!({ "handover_id": handover.handover_id, "summary": handover.summary });
+        let handover = self.audit.record(&actor, "create_shift_handover", "shift_handovers", params, Ok::<_, String>(handover))
+            .map_err(napi::Error::from_reason)?;
+
+        serde_json::to_string(&handover)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn get_shift_handover(&self, handover_id: String, auth_token: Option<String>) -> napi::Result<Option<String>> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        self.authorize(auth_token, "read", &handover_id)?;
+        self.inner
+            .get_shift_handover(&tenant_id, &handover_id)
+            .await
+            .map(|handover| serde_json::to_string(&handover))
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn list_shift_handovers(&self, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        self.authorize(auth_token, "read", "shift_handovers")?;
+        serde_json::to_string(&self.inner.list_shift_handovers(&tenant_id).await)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub async fn acknowledge_shift_handover(&self, handover_id: String, acknowledged_by: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", &handover_id)?;
+        let acknowledged = self.inner.acknowledge_shift_handover(&tenant_id, &handover_id, &acknowledged_by).await;
+        let acknowledged = self.audit.record(&actor, "acknowledge_shift_handover", &handover_id, json!({ "acknowledged_by": acknowledged_by }), acknowledged)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to acknowledge handover: {}", e)))?;
+
+        serde_json::to_string(&acknowledged)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(y: i32, m: u32, d: u32, h: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, m, d, h, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn rules_round_trip_and_step_from_the_series_start() {
+        let rule: RecurrenceRule = "RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH;COUNT=6".parse().unwrap();
+        assert_eq!(rule.to_string(), "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH;COUNT=6");
+        assert!("FREQ=YEARLY".parse::<RecurrenceRule>().is_err());
+        assert!("FREQ=MONTHLY;BYDAY=MO".parse::<RecurrenceRule>().is_err());
+
+        // Monday 2026-01-05; every other week on Monday and Thursday
+        let start = at(2026, 1, 5, 9);
+        assert_eq!(rule.next_after(start, start), Some(at(2026, 1, 8, 9)));
+        assert_eq!(rule.next_after(start, at(2026, 1, 8, 9)), Some(at(2026, 1, 19, 9)));
+
+        let monthly: RecurrenceRule = "FREQ=MONTHLY;UNTIL=20260430".parse().unwrap();
+        let start = at(2026, 1, 31, 8);
+        assert_eq!(monthly.next_after(start, start), Some(at(2026, 2, 28, 8)));
+        assert_eq!(monthly.next_after(start, at(2026, 2, 28, 8)), Some(at(2026, 3, 31, 8)));
+        assert_eq!(monthly.next_after(start, at(2026, 4, 30, 8)), None);
+
+        let hourly: RecurrenceRule = "FREQ=HOURLY;INTERVAL=4".parse().unwrap();
+        assert_eq!(hourly.next_after(at(2026, 1, 1, 0), at(2026, 1, 1, 9)), Some(at(2026, 1, 1, 12)));
+    }
+
+    #[test]
+    fn completing_a_recurring_task_instantiates_the_next_occurrence() {
+        let mut board = TaskBoard::default();
+        let task: SocTask = serde_json::from_value(serde_json::json!({
+            "title": "Review firewall change log",
+            "due_at": at(2026, 3, 2, 9),
+            "recurrence": "FREQ=DAILY;BYDAY=MO,TU,WE,TH,FR;COUNT=3"
+        }))
+        .unwrap();
+        let first = board.create("acme", task, at(2026, 3, 1, 12)).unwrap();
+        assert_eq!(first.series_id.as_deref(), Some(first.task_id.as_str()));
+
+        let done = board.complete("acme", &first.task_id, "alice", at(2026, 3, 2, 10)).unwrap();
+        let second = done.next.unwrap();
+        assert_eq!((second.occurrence, second.due_at), (2, Some(at(2026, 3, 3, 9))));
+        assert_eq!(done.completed.next_task_id.as_deref(), Some(second.task_id.as_str()));
+
+        // Completed late on Friday: the missed occurrences are skipped
+        let done = board.complete("acme", &second.task_id, "bob", at(2026, 3, 6, 18)).unwrap();
+        let third = done.next.unwrap();
+        assert_eq!(third.due_at, Some(at(2026, 3, 9, 9)));
+        assert!(board.complete("acme", &third.task_id, "bob", at(2026, 3, 9, 10)).unwrap().next.is_none());
+        assert!(board.complete("acme", &third.task_id, "bob", at(2026, 3, 9, 11)).is_err());
+        assert!(board.get("globex", &first.task_id).is_none());
+    }
+
+    #[tokio::test]
+    async fn handover_snapshots_open_work() {
+        let core = SecOpCore::new();
+        let incident: crate::SecurityIncident = serde_json::from_value(serde_json::json!({
+            "incident_id": "INC-7", "title": "Credential stuffing", "description": "d", "severity": "High",
+            "status": "Open", "category": "Access", "priority": 1,
+            "created_at": Utc::now(), "updated_at": Utc::now(), "assigned_to": "soc", "reporter": "waf",
+            "affected_systems": [], "indicators": [], "timeline": [],
+            "mitigation_actions": [], "estimated_impact": 1.0, "containment_status": "None"
+        }))
+        .unwrap();
+        core.create_incident("acme", incident).await.unwrap();
+        let overdue = SocTask { due_at: Some(Utc::now() - Duration::hours(1)), ..serde_json::from_value(serde_json::json!({ "title": "Rotate VPN PSK" })).unwrap() };
+        core.create_task("acme", overdue).await.unwrap();
+
+        let request = HandoverRequest {
+            incoming: Some("night shift".to_string()),
+            notes: "Watch the VPN concentrator".to_string(),
+            active_hunts: vec![ActiveHunt { hunt_id: "hunt-1".to_string(), name: "Kerberoasting".to_string(), owner: None, notes: String::new() }],
+        };
+        let handover = core.create_shift_handover("acme", "day lead", request).await;
+        assert_eq!(handover.open_incidents[0].incident_id, "INC-7");
+        assert_eq!((handover.summary.open_tasks, handover.summary.overdue_tasks, handover.summary.active_hunts), (1, 1, 1));
+        assert!(core.list_shift_handovers("globex").await.is_empty());
+
+        let acknowledged = core.acknowledge_shift_handover("acme", &handover.handover_id, "night lead").await.unwrap();
+        assert_eq!(acknowledged.acknowledged_by.as_deref(), Some("night lead"));
+        assert!(core.acknowledge_shift_handover("acme", &handover.handover_id, "someone").await.is_err());
+
+        let purged = core.purge_tenant("acme").await.unwrap();
+        assert_eq!((purged["tasks"], purged["shift_handovers"]), (1, 1));
+    }
+}
@@ -0,0 +1,171 @@
+//! Tenant partitioning
+//!
+//! Alerts, incidents, knowledge harvests and SLA events belong to the tenant
+//! of the credential that created them, and reads through `SecOpCoreNapi`
+//! only see the caller's tenant; another tenant's records behave as if they
+//! did not exist. Calls without a tenant-bound credential act for the
+//! default tenant. With `phantom-enterprise-standards` enabled tenants have
+//! a lifecycle (create, suspend, resume, delete with data purge) and calls
+//! for unknown or suspended tenants are refused.
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::multi_tenancy::TenantDirectory;
+#[cfg(feature = "phantom-enterprise-standards")]
+use std::sync::Arc;
+
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+use crate::secop_core::SecOpCoreNapi;
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+use napi_derive::napi;
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+use serde_json::json;
+
+/// Tenant that owns records created without a tenant-bound credential
+pub const DEFAULT_TENANT: &str = "default";
+
+pub fn default_tenant() -> String {
+    DEFAULT_TENANT.to_string()
+}
+
+#[derive(Clone, Default)]
+pub struct TenantGuard {
+    #[cfg(feature = "phantom-enterprise-standards")]
+    directory: Arc<TenantDirectory>,
+}
+
+impl TenantGuard {
+    #[cfg(feature = "phantom-enterprise-standards")]
+    pub fn directory(&self) -> &TenantDirectory {
+        &self.directory
+    }
+
+    /// Check that calls may be served for `tenant_id`
+    pub fn check(&self, tenant_id: &str) -> Result<(), String> {
+        #[cfg(feature = "phantom-enterprise-standards")]
+        {
+            self.directory.ensure_active(tenant_id).map_err(|e| e.to_string())
+        }
+        #[cfg(not(feature = "phantom-enterprise-standards"))]
+        {
+            let _ = tenant_id;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(all(feature = "napi", feature = "phantom-enterprise-standards"))]
+#[napi]
+impl SecOpCoreNapi {
+    #[napi]
+    pub fn create_tenant(&self, tenant_id: String, organization_name: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, &tenant_id)?;
+        let record = self.tenants.directory().create(&tenant_id, &organization_name).map_err(|e| e.to_string());
+        let record = self.audit.record(&actor, "create_tenant", &tenant_id, json!({ "organization_name": organization_name }), record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to create tenant: {}", e)))?;
+        serde_json::to_string(&record)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Refuse every call for the tenant until it is resumed; its data is kept
+    #[napi]
+    pub fn suspend_tenant(&self, tenant_id: String, reason: Option<String>, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, &tenant_id)?;
+        let record = self.tenants.directory().suspend(&tenant_id, reason.clone()).map_err(|e| e.to_string());
+        let record = self.audit.record(&actor, "suspend_tenant", &tenant_id, json!({ "reason": reason }), record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to suspend tenant: {}", e)))?;
+        serde_json::to_string(&record)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub fn resume_tenant(&self, tenant_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let actor = self.authorize_platform(auth_token, &tenant_id)?;
+        let record = self.tenants.directory().resume(&tenant_id).map_err(|e| e.to_string());
+        let record = self.audit.record(&actor, "resume_tenant", &tenant_id, json!({}), record)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to resume tenant: {}", e)))?;
+        serde_json::to_string(&record)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Suspend the tenant, purge all of its records and forget it
+    #[napi]
+    pub async fn delete_tenant(&self, tenant_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        use phantom_enterprise_standards::multi_tenancy::TenantPurgeReport;
+
+        let actor = self.authorize_platform(auth_token, &tenant_id)?;
+        let report = async {
+            if tenant_id == DEFAULT_TENANT {
+                return Err("the default tenant cannot be deleted".to_string());
+            }
+            let directory = self.tenants.directory();
+            directory.suspend(&tenant_id, Some("deleting".to_string())).map_err(|e| e.to_string())?;
+            let purged = self.inner.purge_tenant(&tenant_id).await.map_err(|e| e.to_string())?;
+            directory.remove(&tenant_id).map_err(|e| e.to_string())?;
+            Ok(TenantPurgeReport { tenant_id: tenant_id.clone(), purged, deleted_at: chrono::Utc::now() })
+        }
+        .await;
+        let report = self.audit.record(&actor, "delete_tenant", &tenant_id, json!({}), report)
+            .map_err(|e| napi::Error::from_reason(format!("Failed to delete tenant: {}", e)))?;
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    #[napi]
+    pub fn list_tenants(&self, auth_token: Option<String>) -> napi::Result<String> {
+        self.authorize_platform(auth_token, "tenants")?;
+        serde_json::to_string(&self.tenants.directory().list())
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::secop_core::SecOpCore;
+    use crate::search::SearchQuery;
+    use crate::SecurityIncident;
+
+    fn incident(id: &str) -> SecurityIncident {
+        serde_json::from_value(serde_json::json!({
+            "incident_id": id, "title": "Beacon to known C2", "description": "d", "severity": "High",
+            "status": "Open", "category": "Malware", "priority": 1,
+            "created_at": chrono::Utc::now(), "updated_at": chrono::Utc::now(), "assigned_to": "soc", "reporter": "edr",
+            "affected_systems": ["web-01"], "indicators": [], "timeline": [],
+            "mitigation_actions": [], "estimated_impact": 1.0, "containment_status": "None"
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn tenants_only_see_their_own_records() {
+        let core = SecOpCore::new();
+        core.create_incident("acme", incident("INC-A")).await.unwrap();
+        core.create_incident("globex", incident("INC-G")).await.unwrap();
+
+        assert_eq!(core.get_incident("acme", "INC-A").await.unwrap().tenant_id, "acme");
+        assert!(core.get_incident("globex", "INC-A").await.is_none());
+        assert_eq!(core.list_incidents("globex").await.len(), 1);
+        assert!(core.close_incident("globex", "INC-A", "done", "analyst").await.is_err());
+        assert!(core.merge_incidents("acme", "INC-A", &["INC-G".to_string()], "analyst").await.is_err());
+
+        let hits = core.search("acme", SearchQuery { query: "beacon".to_string(), ..Default::default() }).await.unwrap();
+        assert_eq!(hits.total, 1);
+        assert_eq!(hits.hits[0].doc_id, "INC-A");
+    }
+
+    #[tokio::test]
+    async fn purge_removes_only_the_tenant() {
+        let core = SecOpCore::new();
+        core.create_incident("acme", incident("INC-A")).await.unwrap();
+        core.create_incident("globex", incident("INC-G")).await.unwrap();
+        core.close_incident("acme", "INC-A", "done", "analyst").await.unwrap();
+
+        let purged = core.purge_tenant("acme").await.unwrap();
+        assert_eq!(purged["incidents"], 1);
+        assert_eq!(purged["knowledge_harvests"], 1);
+        assert_eq!(purged["search_documents"], 1);
+        assert!(core.list_incidents("acme").await.is_empty());
+        assert_eq!(core.list_incidents("globex").await.len(), 1);
+        let hits = core.search("globex", SearchQuery::default()).await.unwrap();
+        assert_eq!(hits.total, 1);
+    }
+}
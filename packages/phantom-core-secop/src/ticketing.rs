@@ -0,0 +1,1199 @@
+//! External ticketing sync
+//!
+//! Mirrors incidents into Jira or ServiceNow. Each tenant configures one
+//! ticketing system; with `auto_create` set, every incident created for the
+//! tenant opens a linked ticket, otherwise tickets are opened on request.
+//! The link (system, ticket key and the state seen on the last sync) is
+//! stored on the incident as `ticket`.
+//!
+//! A sync pass, run on demand or by the background polling loop, fetches
+//! every linked ticket. Comments added on either side are copied to the
+//! other; incident comments are `Comment` timeline events. Status changes
+//! are mirrored through `status_map` (incident status to ticket status), and
+//! when both sides changed since the last pass the ticket wins. A ticket
+//! moved to the status mapped from `Closed` closes the incident.
+//!
+//! The API token may be a `secret://` reference to one of the tenant's
+//! secrets. The config keeps the reference; the live connector gets the
+//! value, resolved when ticketing is configured, so a rotated secret is
+//! picked up by configuring ticketing again.
+//!
+//! Connectors implement [`TicketingConnector`]. The Jira (REST API v2) and
+//! ServiceNow (Table API) connectors need `phantom-enterprise-standards` for
+//! the HTTP transport, and the `ticketing-sync` feature for live HTTP.
+
+use crate::error::{CoreError, CoreResult};
+use crate::secop_core::SecOpCore;
+use crate::secrets::tenant_prefix;
+use crate::{IncidentEvent, SecurityIncident};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use tokio::sync::{watch, Mutex, RwLock};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+#[cfg(feature = "phantom-enterprise-standards")]
+use phantom_enterprise_standards::connectors::{ConnectorRequest, ConnectorResponse, HttpTransport};
+#[cfg(feature = "phantom-enterprise-standards")]
+use serde_json::{json, Value};
+
+#[cfg(feature = "napi")]
+use crate::secop_core::SecOpCoreNapi;
+#[cfg(feature = "napi")]
+use napi_derive::napi;
+
+/// Timeline event type of incident comments
+pub const COMMENT_EVENT: &str = "Comment";
+/// Event data key holding the id of the ticket comment an event was copied from
+const TICKET_COMMENT_ID: &str = "ticket_comment_id";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TicketingSystem {
+    Jira,
+    ServiceNow,
+}
+
+impl TicketingSystem {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TicketingSystem::Jira => "jira",
+            TicketingSystem::ServiceNow => "servicenow",
+        }
+    }
+
+    /// Ticket status for each incident status when `status_map` is empty;
+    /// ServiceNow states are the numeric `state` values
+    fn default_status_map(&self) -> BTreeMap<String, String> {
+        let pairs: &[(&str, &str)] = match self {
+            TicketingSystem::Jira => &[("Open", "To Do"), ("Investigating", "In Progress"), ("Closed", "Done")],
+            TicketingSystem::ServiceNow => &[("Open", "1"), ("Investigating", "2"), ("Closed", "7")],
+        };
+        pairs.iter().map(|(local, remote)| (local.to_string(), remote.to_string())).collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TicketingConfig {
+    pub system: TicketingSystem,
+    pub base_url: String,
+    /// Account the API token belongs to; Jira personal access tokens are
+    /// sent as bearer tokens when it is empty
+    #[serde(default)]
+    pub username: String,
+    /// The token itself, or a `secret://` reference to one of the tenant's secrets
+    #[serde(default, skip_serializing)]
+    pub api_token: String,
+    /// Jira project key
+    #[serde(default)]
+    pub project: String,
+    /// Jira issue type (`Task` by default) or ServiceNow table (`incident`)
+    #[serde(default)]
+    pub issue_type: Option<String>,
+    /// Open a ticket for every new incident
+    #[serde(default = "default_auto_create")]
+    pub auto_create: bool,
+    /// Incident status to ticket status; empty uses the system's defaults
+    #[serde(default)]
+    pub status_map: BTreeMap<String, String>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_auto_create() -> bool {
+    true
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+impl TicketingConfig {
+    fn validate(&self) -> CoreResult<()> {
+        let url = url::Url::parse(&self.base_url)
+            .map_err(|e| CoreError::validation(format!("Invalid ticketing base_url {}: {}", self.base_url, e)))?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(CoreError::validation("Ticketing base_url must be http or https"));
+        }
+        if self.system == TicketingSystem::Jira && self.project.is_empty() {
+            return Err(CoreError::validation("Jira ticketing needs a project key"));
+        }
+        Ok(())
+    }
+
+    pub fn status_map(&self) -> BTreeMap<String, String> {
+        if self.status_map.is_empty() {
+            self.system.default_status_map()
+        } else {
+            self.status_map.clone()
+        }
+    }
+
+    /// Incident status a ticket status maps back to
+    fn local_status(&self, remote: &str) -> Option<String> {
+        self.status_map()
+            .into_iter()
+            .find(|(_, mapped)| mapped.eq_ignore_ascii_case(remote))
+            .map(|(local, _)| local)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteComment {
+    pub id: String,
+    pub author: String,
+    pub body: String,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoteTicket {
+    /// Id the API addresses the ticket by (Jira key, ServiceNow `sys_id`)
+    pub key: String,
+    /// Number shown to people, e.g. `SEC-42` or `INC0010001`
+    pub reference: String,
+    pub url: String,
+    pub status: String,
+    #[serde(default)]
+    pub comments: Vec<RemoteComment>,
+}
+
+/// Link between an incident and its ticket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TicketLink {
+    pub system: TicketingSystem,
+    pub key: String,
+    pub reference: String,
+    pub url: String,
+    pub linked_at: DateTime<Utc>,
+    #[serde(default)]
+    pub last_synced_at: Option<DateTime<Utc>>,
+    /// Ticket status seen on the last sync
+    pub remote_status: String,
+    /// Incident status on the last sync
+    pub local_status: String,
+    /// Ticket comments already on the incident timeline, including the ones
+    /// copied from the incident
+    #[serde(default)]
+    pub known_comments: BTreeSet<String>,
+    /// Incident comment events copied to the ticket
+    #[serde(default)]
+    pub exported_events: BTreeSet<String>,
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+/// Talks to one external ticketing system
+#[async_trait]
+pub trait TicketingConnector: Send + Sync {
+    fn system(&self) -> TicketingSystem;
+
+    async fn create_ticket(&self, incident: &SecurityIncident) -> Result<RemoteTicket, String>;
+
+    /// The ticket with its current status and all of its comments
+    async fn fetch_ticket(&self, key: &str) -> Result<RemoteTicket, String>;
+
+    async fn set_status(&self, key: &str, status: &str) -> Result<(), String>;
+
+    /// Add a comment and return its id
+    async fn add_comment(&self, key: &str, body: &str) -> Result<String, String>;
+}
+
+#[derive(Clone)]
+struct TenantTicketing {
+    config: TicketingConfig,
+    connector: Arc<dyn TicketingConnector>,
+}
+
+struct SyncRun {
+    stop: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+}
+
+#[derive(Default)]
+pub struct TicketingState {
+    tenants: RwLock<HashMap<String, TenantTicketing>>,
+    run: Mutex<Option<SyncRun>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TicketSyncError {
+    pub incident_id: String,
+    pub ticket: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TicketSyncReport {
+    pub tickets: usize,
+    pub comments_imported: usize,
+    pub comments_exported: usize,
+    pub statuses_pulled: usize,
+    pub statuses_pushed: usize,
+    pub errors: Vec<TicketSyncError>,
+}
+
+impl TicketSyncReport {
+    fn merge(&mut self, other: TicketSyncReport) {
+        self.tickets += other.tickets;
+        self.comments_imported += other.comments_imported;
+        self.comments_exported += other.comments_exported;
+        self.statuses_pulled += other.statuses_pulled;
+        self.statuses_pushed += other.statuses_pushed;
+        self.errors.extend(other.errors);
+    }
+}
+
+/// Outcome of talking to the ticketing system about one incident, applied
+/// to the incident afterwards
+struct TicketUpdate {
+    remote: RemoteTicket,
+    pulled_status: Option<String>,
+    pushed_status: Option<String>,
+    exported: Vec<(String, String)>,
+}
+
+fn comment_event(severity: &str, author: &str, body: &str, data: HashMap<String, String>, at: DateTime<Utc>) -> IncidentEvent {
+    IncidentEvent {
+        event_id: Uuid::new_v4().to_string(),
+        timestamp: at,
+        event_type: COMMENT_EVENT.to_string(),
+        description: body.to_string(),
+        source: author.to_string(),
+        severity: severity.to_string(),
+        data,
+    }
+}
+
+impl SecOpCore {
+    /// Connect a tenant to a ticketing system over live HTTP; `None`
+    /// disconnects it (existing links are kept)
+    pub async fn configure_ticketing(&self, tenant_id: &str, config: Option<TicketingConfig>) -> CoreResult<()> {
+        let Some(config) = config else {
+            self.ticketing.tenants.write().await.remove(tenant_id);
+            return Ok(());
+        };
+        config.validate()?;
+        let api_token = self
+            .secrets
+            .resolve_under(&tenant_prefix(tenant_id), &config.api_token)
+            .map_err(|e| CoreError::from(e).context("Failed to resolve the ticketing API token"))?;
+        let connector = live_connector(&TicketingConfig { api_token, ..config.clone() })?;
+        self.configure_ticketing_with_connector(tenant_id, config, connector).await
+    }
+
+    /// Connect a tenant to a ticketing system through `connector`
+    pub async fn configure_ticketing_with_connector(
+        &self,
+        tenant_id: &str,
+        config: TicketingConfig,
+        connector: Arc<dyn TicketingConnector>,
+    ) -> CoreResult<()> {
+        config.validate()?;
+        if connector.system() != config.system {
+            return Err(CoreError::validation(format!(
+                "Connector is for {}, config is for {}",
+                connector.system().as_str(),
+                config.system.as_str()
+            )));
+        }
+        self.ticketing.tenants.write().await.insert(tenant_id.to_string(), TenantTicketing { config, connector });
+        Ok(())
+    }
+
+    pub async fn ticketing_config(&self, tenant_id: &str) -> Option<TicketingConfig> {
+        self.ticketing.tenants.read().await.get(tenant_id).map(|tenant| tenant.config.clone())
+    }
+
+    pub(crate) async fn forget_ticketing(&self, tenant_id: &str) -> usize {
+        usize::from(self.ticketing.tenants.write().await.remove(tenant_id).is_some())
+    }
+
+    /// Open the ticket of a newly created incident when the tenant asks for
+    /// it; a failure is logged and leaves the incident unlinked
+    pub(crate) async fn auto_open_ticket(&self, tenant_id: &str, incident_id: &str) {
+        let auto_create = self.ticketing.tenants.read().await.get(tenant_id).is_some_and(|tenant| tenant.config.auto_create);
+        if auto_create {
+            if let Err(e) = self.open_ticket(tenant_id, incident_id).await {
+                log::warn!("Failed to open ticket for incident {}: {}", incident_id, e);
+            }
+        }
+    }
+
+    /// Open a ticket for an incident and link the two
+    pub async fn open_ticket(&self, tenant_id: &str, incident_id: &str) -> CoreResult<TicketLink> {
+        let tenant = self
+            .ticketing
+            .tenants
+            .read()
+            .await
+            .get(tenant_id)
+            .cloned()
+            .ok_or_else(|| CoreError::validation("No ticketing system is configured"))?;
+        let incident = self
+            .incidents
+            .read()
+            .await
+            .get(incident_id)
+            .filter(|i| i.tenant_id == tenant_id)
+            .cloned()
+            .ok_or_else(|| CoreError::not_found(format!("Incident {} not found", incident_id)))?;
+        if let Some(link) = &incident.ticket {
+            return Err(CoreError::validation(format!("Incident {} is already linked to {}", incident_id, link.reference)));
+        }
+
+        let remote = tenant.connector.create_ticket(&incident).await.map_err(CoreError::backend)?;
+        let now = Utc::now();
+        let link = TicketLink {
+            system: tenant.config.system,
+            key: remote.key.clone(),
+            reference: remote.reference.clone(),
+            url: remote.url.clone(),
+            linked_at: now,
+            last_synced_at: Some(now),
+            remote_status: remote.status.clone(),
+            local_status: incident.status.clone(),
+            known_comments: remote.comments.iter().map(|comment| comment.id.clone()).collect(),
+            exported_events: BTreeSet::new(),
+            last_error: None,
+        };
+
+        let mut incidents = self.incidents.write().await;
+        let incident = incidents
+            .get_mut(incident_id)
+            .filter(|i| i.tenant_id == tenant_id)
+            .ok_or_else(|| CoreError::not_found(format!("Incident {} not found", incident_id)))?;
+        incident.ticket = Some(link.clone());
+        incident.timeline.push(IncidentEvent {
+            event_id: Uuid::new_v4().to_string(),
+            timestamp: now,
+            event_type: "TicketLinked".to_string(),
+            description: format!("Linked to {} ticket {}", link.system.as_str(), link.reference),
+            source: link.system.as_str().to_string(),
+            severity: incident.severity.clone(),
+            data: HashMap::from([("ticket_url".to_string(), link.url.clone())]),
+        });
+        self.search.index_incident(incident)?;
+        Ok(link)
+    }
+
+    /// Add an analyst comment to an incident's timeline; it reaches the
+    /// linked ticket on the next sync
+    pub async fn add_incident_comment(&self, tenant_id: &str, incident_id: &str, author: &str, body: &str) -> CoreResult<IncidentEvent> {
+        if body.trim().is_empty() {
+            return Err(CoreError::validation("Comment is empty"));
+        }
+        let mut incidents = self.incidents.write().await;
+        let incident = incidents
+            .get_mut(incident_id)
+            .filter(|i| i.tenant_id == tenant_id)
+            .ok_or_else(|| CoreError::not_found(format!("Incident {} not found", incident_id)))?;
+        let now = Utc::now();
+        let event = comment_event(&incident.severity, author, body, HashMap::new(), now);
+        incident.timeline.push(event.clone());
+        incident.updated_at = now;
+        self.search.index_incident(incident)?;
+        Ok(event)
+    }
+
+    /// Sync every linked incident of a tenant with its ticket
+    pub async fn sync_tickets(&self, tenant_id: &str) -> CoreResult<TicketSyncReport> {
+        let tenant = self
+            .ticketing
+            .tenants
+            .read()
+            .await
+            .get(tenant_id)
+            .cloned()
+            .ok_or_else(|| CoreError::validation("No ticketing system is configured"))?;
+        let linked: Vec<SecurityIncident> = self
+            .incidents
+            .read()
+            .await
+            .values()
+            .filter(|i| i.tenant_id == tenant_id && i.merged_into.is_none())
+            .filter(|i| i.ticket.as_ref().is_some_and(|link| link.system == tenant.config.system))
+            .cloned()
+            .collect();
+
+        let mut report = TicketSyncReport::default();
+        for incident in linked {
+            let Some(link) = incident.ticket.clone() else { continue };
+            report.tickets += 1;
+            match self.exchange(&tenant, &incident, &link).await {
+                Ok(update) => {
+                    if let Err(e) = self.apply_ticket_update(tenant_id, &incident.incident_id, &tenant.config, update, &mut report).await {
+                        report.errors.push(TicketSyncError { incident_id: incident.incident_id.clone(), ticket: link.reference.clone(), message: e.to_string() });
+                    }
+                }
+                Err(message) => {
+                    self.record_sync_error(&incident.incident_id, &message).await;
+                    report.errors.push(TicketSyncError { incident_id: incident.incident_id.clone(), ticket: link.reference.clone(), message });
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Sync the tickets of every tenant with a ticketing system
+    pub async fn sync_all_tickets(&self) -> TicketSyncReport {
+        let tenants: Vec<String> = self.ticketing.tenants.read().await.keys().cloned().collect();
+        let mut report = TicketSyncReport::default();
+        for tenant_id in tenants {
+            match self.sync_tickets(&tenant_id).await {
+                Ok(tenant_report) => report.merge(tenant_report),
+                Err(e) => log::warn!("Ticket sync for tenant {} failed: {}", tenant_id, e),
+            }
+        }
+        report
+    }
+
+    /// Talk to the ticketing system: fetch the ticket, push a local status
+    /// change and copy new incident comments
+    async fn exchange(&self, tenant: &TenantTicketing, incident: &SecurityIncident, link: &TicketLink) -> Result<TicketUpdate, String> {
+        let connector = &tenant.connector;
+        let mut remote = connector.fetch_ticket(&link.key).await?;
+
+        let mut pulled_status = None;
+        let mut pushed_status = None;
+        if !remote.status.eq_ignore_ascii_case(&link.remote_status) {
+            pulled_status = tenant.config.local_status(&remote.status).filter(|status| *status != incident.status);
+        } else if incident.status != link.local_status {
+            if let Some(status) = tenant.config.status_map().get(&incident.status).filter(|status| !status.eq_ignore_ascii_case(&remote.status)) {
+                connector.set_status(&link.key, status).await?;
+                remote.status = status.clone();
+                pushed_status = Some(status.clone());
+            }
+        }
+
+        let mut exported = Vec::new();
+        let pending = incident
+            .timeline
+            .iter()
+            .filter(|event| event.event_type == COMMENT_EVENT && !event.data.contains_key(TICKET_COMMENT_ID))
+            .filter(|event| !link.exported_events.contains(&event.event_id));
+        for event in pending {
+            let comment_id = connector.add_comment(&link.key, &format!("{}: {}", event.source, event.description)).await?;
+            exported.push((event.event_id.clone(), comment_id));
+        }
+        Ok(TicketUpdate { remote, pulled_status, pushed_status, exported })
+    }
+
+    async fn apply_ticket_update(
+        &self,
+        tenant_id: &str,
+        incident_id: &str,
+        config: &TicketingConfig,
+        update: TicketUpdate,
+        report: &mut TicketSyncReport,
+    ) -> CoreResult<()> {
+        let system = config.system.as_str();
+        let now = Utc::now();
+        let close = {
+            let mut incidents = self.incidents.write().await;
+            let incident = incidents
+                .get_mut(incident_id)
+                .filter(|i| i.tenant_id == tenant_id)
+                .ok_or_else(|| CoreError::not_found(format!("Incident {} not found", incident_id)))?;
+            let severity = incident.severity.clone();
+            let Some(link) = incident.ticket.as_mut() else {
+                return Ok(());
+            };
+
+            for (event_id, comment_id) in update.exported {
+                link.exported_events.insert(event_id);
+                link.known_comments.insert(comment_id);
+                report.comments_exported += 1;
+            }
+            let mut imported = Vec::new();
+            for comment in &update.remote.comments {
+                if link.known_comments.insert(comment.id.clone()) {
+                    let data = HashMap::from([
+                        (TICKET_COMMENT_ID.to_string(), comment.id.clone()),
+                        ("ticket_system".to_string(), system.to_string()),
+                    ]);
+                    imported.push(comment_event(&severity, &comment.author, &comment.body, data, comment.created_at.unwrap_or(now)));
+                }
+            }
+            link.remote_status = update.remote.status.clone();
+            link.last_synced_at = Some(now);
+            link.last_error = None;
+            let reference = link.reference.clone();
+
+            report.comments_imported += imported.len();
+            let changed = !imported.is_empty() || update.pulled_status.is_some();
+            incident.timeline.extend(imported);
+            if update.pushed_status.is_some() {
+                report.statuses_pushed += 1;
+            }
+
+            let mut close = None;
+            if let Some(status) = update.pulled_status {
+                report.statuses_pulled += 1;
+                if status == "Closed" {
+                    close = Some(format!("Closed in {} ticket {}", system, reference));
+                } else {
+                    incident.timeline.push(IncidentEvent {
+                        event_id: Uuid::new_v4().to_string(),
+                        timestamp: now,
+                        event_type: "TicketStatusChanged".to_string(),
+                        description: format!("Status set to {} from {} ticket {}", status, system, reference),
+                        source: system.to_string(),
+                        severity: severity.clone(),
+                        data: HashMap::from([("ticket_status".to_string(), update.remote.status.clone())]),
+                    });
+                    incident.status = status;
+                }
+            }
+            if let Some(link) = incident.ticket.as_mut() {
+                link.local_status = if close.is_some() { "Closed".to_string() } else { incident.status.clone() };
+            }
+            if changed {
+                incident.updated_at = now;
+                self.search.index_incident(incident)?;
+            }
+            close
+        };
+
+        if let Some(resolution) = close {
+            self.close_incident(tenant_id, incident_id, &resolution, system).await?;
+        }
+        Ok(())
+    }
+
+    async fn record_sync_error(&self, incident_id: &str, message: &str) {
+        if let Some(link) = self.incidents.write().await.get_mut(incident_id).and_then(|i| i.ticket.as_mut()) {
+            link.last_error = Some(message.to_string());
+        }
+    }
+
+    /// Poll the ticketing systems of all tenants every `interval`; fails if
+    /// the loop is already running
+    pub async fn start_ticket_sync(self: &Arc<Self>, interval: std::time::Duration) -> CoreResult<()> {
+        let mut run = self.ticketing.run.lock().await;
+        if run.as_ref().is_some_and(|r| !r.handle.is_finished()) {
+            return Err(CoreError::validation("Ticket sync is already running"));
+        }
+
+        let (stop, mut stopped) = watch::channel(false);
+        let core = Arc::clone(self);
+        let interval = interval.max(std::time::Duration::from_secs(1));
+        let handle = tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                tokio::select! {
+                    _ = ticks.tick() => {
+                        let report = core.sync_all_tickets().await;
+                        for error in &report.errors {
+                            log::warn!("Ticket sync of incident {} ({}) failed: {}", error.incident_id, error.ticket, error.message);
+                        }
+                    }
+                    changed = stopped.changed() => {
+                        if changed.is_err() || *stopped.borrow() {
+                            break;
+                        }
+                    }
+                }
+            }
+            log::info!("Ticket sync stopped");
+        });
+
+        *run = Some(SyncRun { stop, handle });
+        log::info!("Ticket sync started (every {:?})", interval);
+        Ok(())
+    }
+
+    /// Stop the polling loop; a pass in progress is allowed to finish
+    pub async fn stop_ticket_sync(&self) -> bool {
+        let Some(run) = self.ticketing.run.lock().await.take() else {
+            return false;
+        };
+        let _ = run.stop.send(true);
+        let _ = run.handle.await;
+        true
+    }
+}
+
+#[cfg(feature = "ticketing-sync")]
+fn live_connector(config: &TicketingConfig) -> CoreResult<Arc<dyn TicketingConnector>> {
+    let transport = phantom_enterprise_standards::connectors::ReqwestTransport::new(std::time::Duration::from_secs(config.timeout_secs))
+        .map_err(|e| CoreError::backend(e.to_string()))?;
+    Ok(http_connector(config.clone(), Arc::new(transport)))
+}
+
+#[cfg(not(feature = "ticketing-sync"))]
+fn live_connector(config: &TicketingConfig) -> CoreResult<Arc<dyn TicketingConnector>> {
+    Err(CoreError::validation(format!("Live {} connectors need the `ticketing-sync` feature", config.system.as_str())))
+}
+
+// HTTP connectors
+
+/// The Jira or ServiceNow connector for `config`, sending requests through `transport`
+#[cfg(feature = "phantom-enterprise-standards")]
+pub fn http_connector(config: TicketingConfig, transport: Arc<dyn HttpTransport>) -> Arc<dyn TicketingConnector> {
+    match config.system {
+        TicketingSystem::Jira => Arc::new(JiraConnector { config, transport }),
+        TicketingSystem::ServiceNow => Arc::new(ServiceNowConnector { config, transport }),
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+fn base64_encode(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let bytes = [chunk[0], chunk.get(1).copied().unwrap_or(0), chunk.get(2).copied().unwrap_or(0)];
+        let n = (u32::from(bytes[0]) << 16) | (u32::from(bytes[1]) << 8) | u32::from(bytes[2]);
+        for (i, shift) in [18, 12, 6, 0].into_iter().enumerate() {
+            out.push(if i <= chunk.len() { ALPHABET[(n >> shift) as usize & 63] as char } else { '=' });
+        }
+    }
+    out
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+async fn send_json(transport: &dyn HttpTransport, config: &TicketingConfig, request: ConnectorRequest) -> Result<Value, String> {
+    let authorization = if config.username.is_empty() {
+        format!("Bearer {}", config.api_token)
+    } else {
+        format!("Basic {}", base64_encode(format!("{}:{}", config.username, config.api_token).as_bytes()))
+    };
+    let request = request.header("Authorization", &authorization).header("Accept", "application/json");
+    let response: ConnectorResponse = transport.send(&request).await.map_err(|e| e.to_string())?;
+    if !response.is_success() {
+        let body: String = response.body.chars().take(200).collect();
+        return Err(format!("{} {} returned HTTP {}: {}", request.method, request.url, response.status, body));
+    }
+    if response.body.trim().is_empty() {
+        return Ok(Value::Null);
+    }
+    response.json().map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+fn text(value: &Value, pointer: &str) -> String {
+    match value.pointer(pointer) {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Null) | None => String::new(),
+        Some(other) => other.to_string(),
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+fn ticket_summary(incident: &SecurityIncident) -> (String, String) {
+    let summary = format!("[{}] {}", incident.severity, incident.title);
+    let description = format!("{}\n\nPhantom SecOp incident {}", incident.description, incident.incident_id);
+    (summary, description)
+}
+
+/// Jira Cloud or Data Center through REST API v2
+#[cfg(feature = "phantom-enterprise-standards")]
+pub struct JiraConnector {
+    config: TicketingConfig,
+    transport: Arc<dyn HttpTransport>,
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+impl JiraConnector {
+    fn url(&self, path: &str) -> String {
+        format!("{}/rest/api/2/{}", self.config.base_url.trim_end_matches('/'), path)
+    }
+
+    async fn send(&self, request: ConnectorRequest) -> Result<Value, String> {
+        send_json(self.transport.as_ref(), &self.config, request).await
+    }
+
+    /// Jira timestamps carry the offset without a colon (`+0000`)
+    fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f%z")
+            .or_else(|_| DateTime::parse_from_rfc3339(value))
+            .ok()
+            .map(|at| at.with_timezone(&Utc))
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[async_trait]
+impl TicketingConnector for JiraConnector {
+    fn system(&self) -> TicketingSystem {
+        TicketingSystem::Jira
+    }
+
+    async fn create_ticket(&self, incident: &SecurityIncident) -> Result<RemoteTicket, String> {
+        let (summary, description) = ticket_summary(incident);
+        let body = json!({
+            "fields": {
+                "project": { "key": self.config.project },
+                "summary": summary,
+                "description": description,
+                "issuetype": { "name": self.config.issue_type.as_deref().unwrap_or("Task") },
+                "labels": ["phantom-secop"],
+            }
+        });
+        let created = self.send(ConnectorRequest::new("POST", &self.url("issue")).json_body(&body)).await?;
+        let key = text(&created, "/key");
+        if key.is_empty() {
+            return Err("Jira did not return an issue key".to_string());
+        }
+        self.fetch_ticket(&key).await
+    }
+
+    async fn fetch_ticket(&self, key: &str) -> Result<RemoteTicket, String> {
+        let issue = self.send(ConnectorRequest::new("GET", &self.url(&format!("issue/{}?fields=status,comment", key)))).await?;
+        let comments = issue
+            .pointer("/fields/comment/comments")
+            .and_then(Value::as_array)
+            .map(|comments| {
+                comments
+                    .iter()
+                    .map(|comment| RemoteComment {
+                        id: text(comment, "/id"),
+                        author: text(comment, "/author/displayName"),
+                        body: text(comment, "/body"),
+                        created_at: Self::parse_time(&text(comment, "/created")),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(RemoteTicket {
+            key: key.to_string(),
+            reference: key.to_string(),
+            url: format!("{}/browse/{}", self.config.base_url.trim_end_matches('/'), key),
+            status: text(&issue, "/fields/status/name"),
+            comments,
+        })
+    }
+
+    /// Statuses change through workflow transitions; the transition leading
+    /// to `status` is looked up by its target or its own name
+    async fn set_status(&self, key: &str, status: &str) -> Result<(), String> {
+        let transitions_url = self.url(&format!("issue/{}/transitions", key));
+        let available = self.send(ConnectorRequest::new("GET", &transitions_url)).await?;
+        let transition = available
+            .get("transitions")
+            .and_then(Value::as_array)
+            .and_then(|transitions| {
+                transitions.iter().find(|transition| {
+                    text(transition, "/to/name").eq_ignore_ascii_case(status) || text(transition, "/name").eq_ignore_ascii_case(status)
+                })
+            })
+            .map(|transition| text(transition, "/id"))
+            .ok_or_else(|| format!("No transition of {} leads to status {}", key, status))?;
+        self.send(ConnectorRequest::new("POST", &transitions_url).json_body(&json!({ "transition": { "id": transition } }))).await?;
+        Ok(())
+    }
+
+    async fn add_comment(&self, key: &str, body: &str) -> Result<String, String> {
+        let url = self.url(&format!("issue/{}/comment", key));
+        let comment = self.send(ConnectorRequest::new("POST", &url).json_body(&json!({ "body": body }))).await?;
+        Ok(text(&comment, "/id"))
+    }
+}
+
+/// ServiceNow through the Table API; comments are the ticket's
+/// customer-visible `comments` journal
+#[cfg(feature = "phantom-enterprise-standards")]
+pub struct ServiceNowConnector {
+    config: TicketingConfig,
+    transport: Arc<dyn HttpTransport>,
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+impl ServiceNowConnector {
+    fn table(&self) -> &str {
+        self.config.issue_type.as_deref().unwrap_or("incident")
+    }
+
+    fn url(&self, path: &str, query: &[(&str, &str)]) -> Result<String, String> {
+        let base = format!("{}/api/now/table/{}", self.config.base_url.trim_end_matches('/'), path);
+        let mut url = url::Url::parse(&base).map_err(|e| e.to_string())?;
+        if !query.is_empty() {
+            url.query_pairs_mut().extend_pairs(query);
+        }
+        Ok(url.to_string())
+    }
+
+    async fn send(&self, request: ConnectorRequest) -> Result<Value, String> {
+        send_json(self.transport.as_ref(), &self.config, request).await
+    }
+
+    async fn comments(&self, sys_id: &str) -> Result<Vec<RemoteComment>, String> {
+        let query = format!("element_id={}^element=comments^ORDERBYsys_created_on", sys_id);
+        let url = self.url(
+            "sys_journal_field",
+            &[("sysparm_query", query.as_str()), ("sysparm_fields", "sys_id,value,sys_created_by,sys_created_on")],
+        )?;
+        let entries = self.send(ConnectorRequest::new("GET", &url)).await?;
+        Ok(entries
+            .get("result")
+            .and_then(Value::as_array)
+            .map(|entries| {
+                entries
+                    .iter()
+                    .map(|entry| RemoteComment {
+                        id: text(entry, "/sys_id"),
+                        author: text(entry, "/sys_created_by"),
+                        body: text(entry, "/value"),
+                        created_at: chrono::NaiveDateTime::parse_from_str(&text(entry, "/sys_created_on"), "%Y-%m-%d %H:%M:%S")
+                            .ok()
+                            .map(|at| at.and_utc()),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
+
+#[cfg(feature = "phantom-enterprise-standards")]
+#[async_trait]
+impl TicketingConnector for ServiceNowConnector {
+    fn system(&self) -> TicketingSystem {
+        TicketingSystem::ServiceNow
+    }
+
+    async fn create_ticket(&self, incident: &SecurityIncident) -> Result<RemoteTicket, String> {
+        let (summary, description) = ticket_summary(incident);
+        let impact = match incident.severity.as_str() {
+            "Critical" => "1",
+            "High" => "2",
+            _ => "3",
+        };
+        let body = json!({
+            "short_description": summary,
+            "description": description,
+            "impact": impact,
+            "urgency": impact,
+            "correlation_id": incident.incident_id,
+            "correlation_display": "phantom-secop",
+        });
+        let created = self.send(ConnectorRequest::new("POST", &self.url(self.table(), &[])?).json_body(&body)).await?;
+        let sys_id = text(&created, "/result/sys_id");
+        if sys_id.is_empty() {
+            return Err("ServiceNow did not return a sys_id".to_string());
+        }
+        self.fetch_ticket(&sys_id).await
+    }
+
+    async fn fetch_ticket(&self, key: &str) -> Result<RemoteTicket, String> {
+        let url = self.url(&format!("{}/{}", self.table(), key), &[("sysparm_fields", "sys_id,number,state")])?;
+        let record = self.send(ConnectorRequest::new("GET", &url)).await?;
+        Ok(RemoteTicket {
+            key: key.to_string(),
+            reference: text(&record, "/result/number"),
+            url: format!("{}/nav_to.do?uri={}.do?sys_id={}", self.config.base_url.trim_end_matches('/'), self.table(), key),
+            status: text(&record, "/result/state"),
+            comments: self.comments(key).await?,
+        })
+    }
+
+    async fn set_status(&self, key: &str, status: &str) -> Result<(), String> {
+        let mut body = json!({ "state": status });
+        if self.config.status_map().get("Closed").is_some_and(|closed| closed == status) {
+            body["close_code"] = json!("Solved (Permanently)");
+            body["close_notes"] = json!("Closed in Phantom SecOp");
+        }
+        let url = self.url(&format!("{}/{}", self.table(), key), &[])?;
+        self.send(ConnectorRequest::new("PATCH", &url).json_body(&body)).await?;
+        Ok(())
+    }
+
+    /// The Table API does not return journal ids, so the new entry is looked
+    /// up among the ticket's comments
+    async fn add_comment(&self, key: &str, body: &str) -> Result<String, String> {
+        let url = self.url(&format!("{}/{}", self.table(), key), &[])?;
+        self.send(ConnectorRequest::new("PATCH", &url).json_body(&json!({ "comments": body }))).await?;
+        self.comments(key)
+            .await?
+            .into_iter()
+            .rev()
+            .find(|comment| comment.body.trim() == body.trim())
+            .map(|comment| comment.id)
+            .ok_or_else(|| format!("Comment added to {} was not found in its journal", key))
+    }
+}
+
+#[cfg(feature = "napi")]
+#[napi]
+impl SecOpCoreNapi {
+    /// Connect the caller's tenant to Jira or ServiceNow; pass no config to
+    /// disconnect
+    #[napi]
+    pub async fn configure_ticketing(&self, config_json: Option<String>, auth_token: Option<String>) -> napi::Result<()> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "access:manage", "ticketing")?;
+        let config: Option<TicketingConfig> = config_json
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()
+            .map_err(|e| napi::Error::from_reason(format!("Invalid ticketing config: {}", e)))?;
+        // The API token is not serialized, so it stays out of the audit trail
+        let params = serde_json::json!({ "config": config });
+        let result = self.inner.configure_ticketing(&tenant_id, config).await;
+        Ok(self.audit.record(&actor, "configure_ticketing", "ticketing", params, result)
+            .map_err(|e| e.context("Failed to configure ticketing"))?)
+    }
+
+    /// Open a ticket for an incident and return the link
+    #[napi]
+    pub async fn open_incident_ticket(&self, incident_id: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", &incident_id)?;
+        let link = self.inner.open_ticket(&tenant_id, &incident_id).await;
+        let link = self.audit.record(&actor, "open_incident_ticket", &incident_id, serde_json::json!({}), link)
+            .map_err(|e| e.context("Failed to open ticket"))?;
+
+        serde_json::to_string(&link)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Comment on an incident and return the timeline event
+    #[napi]
+    pub async fn add_incident_comment(&self, incident_id: String, author: String, body: String, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", &incident_id)?;
+        let event = self.inner.add_incident_comment(&tenant_id, &incident_id, &author, &body).await;
+        let params = serde_json::json!({ "author": author, "body": body });
+        let event = self.audit.record(&actor, "add_incident_comment", &incident_id, params, event)
+            .map_err(|e| e.context("Failed to add comment"))?;
+
+        serde_json::to_string(&event)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Sync the caller's linked incidents with their tickets now
+    #[napi]
+    pub async fn sync_tickets(&self, auth_token: Option<String>) -> napi::Result<String> {
+        let tenant_id = self.tenant(auth_token.as_deref())?;
+        let actor = self.authorize(auth_token, "incident:write", "ticketing")?;
+        let report = self.inner.sync_tickets(&tenant_id).await;
+        let report = self.audit.record(&actor, "sync_tickets", "ticketing", serde_json::json!({}), report)
+            .map_err(|e| e.context("Failed to sync tickets"))?;
+
+        serde_json::to_string(&report)
+            .map_err(|e| napi::Error::from_reason(format!("Serialization error: {}", e)))
+    }
+
+    /// Poll the ticketing systems of all tenants every `interval_secs`
+    #[napi]
+    pub async fn start_ticket_sync(&self, interval_secs: u32, auth_token: Option<String>) -> napi::Result<()> {
+        let actor = self.authorize(auth_token, "access:manage", "ticketing")?;
+        let result = self.inner.start_ticket_sync(std::time::Duration::from_secs(u64::from(interval_secs))).await;
+        let params = serde_json::json!({ "interval_secs": interval_secs });
+        Ok(self.audit.record(&actor, "start_ticket_sync", "ticketing", params, result)
+            .map_err(|e| e.context("Failed to start ticket sync"))?)
+    }
+
+    /// Stop the ticket polling loop; `false` when it was not running
+    #[napi]
+    pub async fn stop_ticket_sync(&self, auth_token: Option<String>) -> napi::Result<bool> {
+        let actor = self.authorize(auth_token, "access:manage", "ticketing")?;
+        let stopped = self.inner.stop_ticket_sync().await;
+        self.audit.record(&actor, "stop_ticket_sync", "ticketing", serde_json::json!({}), Ok::<_, String>(stopped))
+            .map_err(napi::Error::from_reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex as StdMutex;
+
+    #[derive(Default)]
+    struct FakeTracker {
+        tickets: StdMutex<HashMap<String, RemoteTicket>>,
+    }
+
+    impl FakeTracker {
+        fn edit(&self, key: &str, edit: impl FnOnce(&mut RemoteTicket)) {
+            edit(self.tickets.lock().unwrap().get_mut(key).unwrap());
+        }
+    }
+
+    #[async_trait]
+    impl TicketingConnector for FakeTracker {
+        fn system(&self) -> TicketingSystem {
+            TicketingSystem::Jira
+        }
+
+        async fn create_ticket(&self, incident: &SecurityIncident) -> Result<RemoteTicket, String> {
+            let mut tickets = self.tickets.lock().unwrap();
+            let key = format!("SEC-{}", tickets.len() + 1);
+            let ticket = RemoteTicket {
+                key: key.clone(),
+                reference: key.clone(),
+                url: format!("https://jira.example/browse/{}", key),
+                status: "To Do".to_string(),
+                comments: vec![RemoteComment { id: "c0".to_string(), author: "bot".to_string(), body: incident.title.clone(), created_at: None }],
+            };
+            tickets.insert(key, ticket.clone());
+            Ok(ticket)
+        }
+
+        async fn fetch_ticket(&self, key: &str) -> Result<RemoteTicket, String> {
+            self.tickets.lock().unwrap().get(key).cloned().ok_or_else(|| format!("{} not found", key))
+        }
+
+        async fn set_status(&self, key: &str, status: &str) -> Result<(), String> {
+            self.edit(key, |ticket| ticket.status = status.to_string());
+            Ok(())
+        }
+
+        async fn add_comment(&self, key: &str, body: &str) -> Result<String, String> {
+            let id = Uuid::new_v4().to_string();
+            let comment = RemoteComment { id: id.clone(), author: "integration".to_string(), body: body.to_string(), created_at: None };
+            self.edit(key, |ticket| ticket.comments.push(comment));
+            Ok(id)
+        }
+    }
+
+    fn config() -> TicketingConfig {
+        serde_json::from_value(serde_json::json!({
+            "system": "jira", "base_url": "https://jira.example", "username": "soc@example.com",
+            "api_token": "secret", "project": "SEC"
+        }))
+        .unwrap()
+    }
+
+    fn incident(id: &str) -> SecurityIncident {
+        serde_json::from_value(serde_json::json!({
+            "incident_id": id, "title": "Ransomware on file server", "description": "Encrypted shares",
+            "severity": "Critical", "status": "Open", "category": "Malware", "priority": 1,
+            "created_at": Utc::now(), "updated_at": Utc::now(), "assigned_to": "", "reporter": "edr",
+            "affected_systems": [], "indicators": [], "timeline": [], "mitigation_actions": [],
+            "estimated_impact": 0.0, "containment_status": "Unknown"
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn comments_and_status_flow_both_ways() {
+        let core = SecOpCore::new();
+        let jira = Arc::new(FakeTracker::default());
+        core.configure_ticketing_with_connector("acme", config(), jira.clone()).await.unwrap();
+        assert!(core.configure_ticketing("acme", Some(TicketingConfig { project: String::new(), ..config() })).await.is_err());
+
+        core.create_incident("acme", incident("inc-1")).await.unwrap();
+        let link = core.get_incident("acme", "inc-1").await.unwrap().ticket.unwrap();
+        assert_eq!((link.reference.as_str(), link.remote_status.as_str()), ("SEC-1", "To Do"));
+        assert_eq!(core.open_ticket("acme", "inc-1").await.unwrap_err().code(), "VALIDATION");
+
+        // An analyst comment goes out, a ticket comment comes in, and neither echoes back
+        core.add_incident_comment("acme", "inc-1", "alice", "Isolated the host").await.unwrap();
+        jira.edit("SEC-1", |ticket| {
+            ticket.comments.push(RemoteComment { id: "c9".to_string(), author: "bob".to_string(), body: "Backups verified".to_string(), created_at: None })
+        });
+        let report = core.sync_tickets("acme").await.unwrap();
+        assert_eq!((report.tickets, report.comments_exported, report.comments_imported), (1, 1, 1));
+        assert_eq!(jira.fetch_ticket("SEC-1").await.unwrap().comments.last().unwrap().body, "alice: Isolated the host");
+        let report = core.sync_tickets("acme").await.unwrap();
+        assert_eq!((report.comments_exported, report.comments_imported), (0, 0));
+
+        let comments: Vec<String> = core.get_incident("acme", "inc-1").await.unwrap().timeline.into_iter()
+            .filter(|event| event.event_type == COMMENT_EVENT)
+            .map(|event| format!("{}: {}", event.source, event.description))
+            .collect();
+        assert_eq!(comments, ["alice: Isolated the host", "bob: Backups verified"]);
+
+        // A ticket moved to Done closes the incident
+        jira.edit("SEC-1", |ticket| ticket.status = "Done".to_string());
+        let report = core.sync_tickets("acme").await.unwrap();
+        assert_eq!(report.statuses_pulled, 1);
+        let closed = core.get_incident("acme", "inc-1").await.unwrap();
+        assert_eq!(closed.status, "Closed");
+        assert_eq!(closed.ticket.unwrap().local_status, "Closed");
+        assert_eq!(core.sync_tickets("acme").await.unwrap().statuses_pulled, 0);
+    }
+
+    #[tokio::test]
+    async fn local_status_changes_reach_the_ticket() {
+        let core = SecOpCore::new();
+        let jira = Arc::new(FakeTracker::default());
+        core.configure_ticketing_with_connector("acme", TicketingConfig { auto_create: false, ..config() }, jira.clone()).await.unwrap();
+        core.create_incident("acme", incident("inc-2")).await.unwrap();
+        assert!(core.get_incident("acme", "inc-2").await.unwrap().ticket.is_none());
+        let link = core.open_ticket("acme", "inc-2").await.unwrap();
+
+        core.close_incident("acme", "inc-2", "Contained", "alice").await.unwrap();
+        let report = core.sync_tickets("acme").await.unwrap();
+        assert_eq!(report.statuses_pushed, 1);
+        assert_eq!(jira.fetch_ticket(&link.key).await.unwrap().status, "Done");
+
+        jira.tickets.lock().unwrap().clear();
+        let report = core.sync_tickets("acme").await.unwrap();
+        assert_eq!(report.errors.len(), 1);
+        assert!(core.get_incident("acme", "inc-2").await.unwrap().ticket.unwrap().last_error.is_some());
+        assert!(core.sync_tickets("globex").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn api_token_references_resolve_among_the_tenants_secrets() {
+        let core = SecOpCore::new();
+        core.secrets().rotate_master_key(Arc::new(crate::secrets::StaticKey::new(vec![7u8; 32]))).unwrap();
+        core.secrets().put("tenants/acme/jira", "acme-token").unwrap();
+        let referencing = |reference: &str| TicketingConfig { api_token: reference.to_string(), ..config() };
+
+        for reference in ["secret://jira", "secret://../acme/jira", "secret://tenants/acme/jira"] {
+            assert!(core.configure_ticketing("globex", Some(referencing(reference))).await.is_err());
+        }
+        let missing = core.configure_ticketing("acme", Some(referencing("secret://servicenow"))).await.unwrap_err();
+        assert_eq!(missing.code(), "NOT_FOUND");
+
+        let configured = core.configure_ticketing("acme", Some(referencing("secret://jira"))).await;
+        if cfg!(feature = "ticketing-sync") {
+            configured.unwrap();
+            assert_eq!(core.ticketing_config("acme").await.unwrap().api_token, "secret://jira");
+        } else {
+            assert!(configured.unwrap_err().to_string().contains("ticketing-sync"));
+        }
+    }
+
+    #[cfg(feature = "phantom-enterprise-standards")]
+    #[tokio::test]
+    async fn jira_connector_speaks_rest_v2() {
+        use phantom_enterprise_standards::connectors::ConnectorError;
+
+        #[derive(Default)]
+        struct FakeJira {
+            requests: StdMutex<Vec<ConnectorRequest>>,
+        }
+
+        #[async_trait]
+        impl HttpTransport for FakeJira {
+            async fn send(&self, request: &ConnectorRequest) -> Result<ConnectorResponse, ConnectorError> {
+                self.requests.lock().unwrap().push(request.clone());
+                let body = match (request.method.as_str(), request.url.as_str()) {
+                    ("POST", url) if url.ends_with("/issue") => json!({ "id": "10001", "key": "SEC-7" }),
+                    ("GET", url) if url.contains("/transitions") => json!({ "transitions": [
+                        { "id": "21", "name": "Start", "to": { "name": "In Progress" } },
+                        { "id": "31", "name": "Resolve", "to": { "name": "Done" } }
+                    ] }),
+                    ("GET", _) => json!({ "key": "SEC-7", "fields": {
+                        "status": { "name": "To Do" },
+                        "comment": { "comments": [{ "id": "5", "author": { "displayName": "Bob" }, "body": "On it", "created": "2024-01-05T10:00:00.000+0000" }] }
+                    } }),
+                    _ => json!({ "id": "6" }),
+                };
+                Ok(ConnectorResponse { status: 200, headers: Default::default(), body: body.to_string() })
+            }
+        }
+
+        let transport = Arc::new(FakeJira::default());
+        let jira = http_connector(config(), transport.clone());
+        let ticket = jira.create_ticket(&incident("inc-3")).await.unwrap();
+        assert_eq!((ticket.key.as_str(), ticket.url.as_str()), ("SEC-7", "https://jira.example/browse/SEC-7"));
+        assert_eq!(ticket.comments[0].created_at.unwrap().to_rfc3339(), "2024-01-05T10:00:00+00:00");
+        jira.set_status("SEC-7", "Done").await.unwrap();
+        assert_eq!(jira.add_comment("SEC-7", "Contained").await.unwrap(), "6");
+
+        let requests = transport.requests.lock().unwrap();
+        let created: Value = serde_json::from_str(requests[0].body.as_deref().unwrap()).unwrap();
+        assert_eq!(created["fields"]["summary"], "[Critical] Ransomware on file server");
+        assert_eq!(requests[0].headers["Authorization"], format!("Basic {}", base64_encode(b"soc@example.com:secret")));
+        assert_eq!(requests[3].body.as_deref(), Some(r#"{"transition":{"id":"31"}}"#));
+    }
+}
@@ -0,0 +1,340 @@
+//! Incident timeline reconstruction
+//!
+//! Merges an incident's own timeline, the alerts linked to it and events
+//! supplied by other cores (sandbox behaviour, hunting matches) into one
+//! chronologically ordered, source-tagged timeline. Entries are annotated
+//! with the response phase they fall into, derived from the incident's
+//! milestones, and quiet stretches longer than a threshold are reported as
+//! gaps.
+
+use crate::secop_core::SecOpCore;
+use crate::{SecurityAlert, SecurityIncident};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineSource {
+    Incident,
+    Alert,
+    Sandbox,
+    Hunting,
+}
+
+/// Response phases, in the order an incident moves through them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IncidentPhase {
+    Detection,
+    Analysis,
+    Containment,
+    Recovery,
+    PostIncident,
+}
+
+impl IncidentPhase {
+    /// Phase entered when an incident timeline event of this type is recorded
+    fn entered_by(event_type: &str) -> Option<Self> {
+        match event_type {
+            "IncidentAcknowledged" => Some(IncidentPhase::Analysis),
+            "IncidentContained" => Some(IncidentPhase::Containment),
+            "IncidentResolved" => Some(IncidentPhase::Recovery),
+            "IncidentClosed" | "MergedIntoIncident" => Some(IncidentPhase::PostIncident),
+            _ => None,
+        }
+    }
+}
+
+/// One entry in a reconstructed timeline.
+///
+/// Other cores produce entries in this shape (`source` set to `sandbox` or
+/// `hunting`); `phase` is filled in during reconstruction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineEntry {
+    pub timestamp: DateTime<Utc>,
+    pub source: TimelineSource,
+    /// Id of the record the entry came from (event, alert, sample, match)
+    pub source_ref: String,
+    /// Event category, e.g. `api_call`, `file_created`, `hunt_match`
+    pub kind: String,
+    pub summary: String,
+    #[serde(default)]
+    pub severity: Option<String>,
+    #[serde(default)]
+    pub phase: Option<IncidentPhase>,
+    #[serde(default)]
+    pub attributes: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimelineOptions {
+    /// Quiet periods longer than this are reported as gaps
+    pub gap_threshold_minutes: i64,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl Default for TimelineOptions {
+    fn default() -> Self {
+        Self {
+            gap_threshold_minutes: 60,
+            start: None,
+            end: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineGap {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub duration_minutes: i64,
+    /// Phase the incident was in when the gap opened
+    pub phase: IncidentPhase,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseSpan {
+    pub phase: IncidentPhase,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub entry_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentTimeline {
+    pub incident_id: String,
+    pub entries: Vec<TimelineEntry>,
+    pub phases: Vec<PhaseSpan>,
+    pub gaps: Vec<TimelineGap>,
+    pub source_counts: HashMap<TimelineSource, usize>,
+    pub start: Option<DateTime<Utc>>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+/// Reconstruct a timeline from an incident, its alerts and external events
+pub fn reconstruct(
+    incident: &SecurityIncident,
+    alerts: &[SecurityAlert],
+    external: Vec<TimelineEntry>,
+    options: &TimelineOptions,
+) -> IncidentTimeline {
+    let mut entries: Vec<TimelineEntry> = incident
+        .timeline
+        .iter()
+        .map(|event| TimelineEntry {
+            timestamp: event.timestamp,
+            source: TimelineSource::Incident,
+            source_ref: event.event_id.clone(),
+            kind: event.event_type.clone(),
+            summary: event.description.clone(),
+            severity: Some(event.severity.clone()),
+            phase: None,
+            attributes: event.data.clone(),
+        })
+        .collect();
+
+    entries.extend(alerts.iter().map(|alert| {
+        let mut attributes = HashMap::from([
+            ("rule_id".to_string(), alert.rule_id.clone()),
+            ("alert_source".to_string(), alert.source.clone()),
+        ]);
+        if !alert.affected_assets.is_empty() {
+            attributes.insert("assets".to_string(), alert.affected_assets.join(","));
+        }
+        TimelineEntry {
+            timestamp: alert.created_at,
+            source: TimelineSource::Alert,
+            source_ref: alert.alert_id.clone(),
+            kind: "alert".to_string(),
+            summary: alert.title.clone(),
+            severity: Some(alert.priority.clone()),
+            phase: None,
+            attributes,
+        }
+    }));
+    entries.extend(external);
+
+    entries.retain(|entry| {
+        options.start.is_none_or(|start| entry.timestamp >= start)
+            && options.end.is_none_or(|end| entry.timestamp <= end)
+    });
+    // Stable sort keeps same-instant entries in source order
+    entries.sort_by_key(|entry| entry.timestamp);
+
+    // Milestones come from the incident's own timeline, never from external events
+    let mut milestones: Vec<(DateTime<Utc>, IncidentPhase)> = incident
+        .timeline
+        .iter()
+        .filter_map(|event| IncidentPhase::entered_by(&event.event_type).map(|phase| (event.timestamp, phase)))
+        .collect();
+    milestones.sort_by_key(|(timestamp, _)| *timestamp);
+
+    let phase_at = |timestamp: DateTime<Utc>| {
+        milestones
+            .iter()
+            .take_while(|(at, _)| *at <= timestamp)
+            .map(|(_, phase)| *phase)
+            .max()
+            .unwrap_or(IncidentPhase::Detection)
+    };
+    for entry in &mut entries {
+        entry.phase = Some(phase_at(entry.timestamp));
+    }
+
+    let mut phases: Vec<PhaseSpan> = Vec::new();
+    for entry in &entries {
+        let phase = entry.phase.unwrap_or(IncidentPhase::Detection);
+        match phases.last_mut() {
+            Some(span) if span.phase == phase => {
+                span.end = entry.timestamp;
+                span.entry_count += 1;
+            }
+            _ => phases.push(PhaseSpan {
+                phase,
+                start: entry.timestamp,
+                end: entry.timestamp,
+                entry_count: 1,
+            }),
+        }
+    }
+
+    let threshold = options.gap_threshold_minutes.max(1);
+    let gaps = entries
+        .windows(2)
+        .filter_map(|pair| {
+            let duration_minutes = (pair[1].timestamp - pair[0].timestamp).num_minutes();
+            (duration_minutes > threshold).then(|| TimelineGap {
+                start: pair[0].timestamp,
+                end: pair[1].timestamp,
+                duration_minutes,
+                phase: pair[0].phase.unwrap_or(IncidentPhase::Detection),
+            })
+        })
+        .collect();
+
+    let mut source_counts = HashMap::new();
+    for entry in &entries {
+        *source_counts.entry(entry.source).or_insert(0) += 1;
+    }
+
+    IncidentTimeline {
+        incident_id: incident.incident_id.clone(),
+        start: entries.first().map(|entry| entry.timestamp),
+        end: entries.last().map(|entry| entry.timestamp),
+        entries,
+        phases,
+        gaps,
+        source_counts,
+    }
+}
+
+impl SecOpCore {
+    /// Build the merged timeline for an incident.
+    ///
+    /// `external` carries events from other cores; alerts are those linked
+    /// to the incident through `related_alerts`.
+    pub async fn build_incident_timeline(
+        &self,
+        tenant_id: &str,
+        incident_id: &str,
+        external: Vec<TimelineEntry>,
+        options: &TimelineOptions,
+    ) -> Result<IncidentTimeline, String> {
+        let incident = self
+            .incidents
+            .read()
+            .await
+            .get(incident_id)
+            .filter(|i| i.tenant_id == tenant_id)
+            .cloned()
+            .ok_or_else(|| format!("Incident {} not found", incident_id))?;
+
+        let alerts: Vec<SecurityAlert> = {
+            let alerts = self.alerts.read().await;
+            incident
+                .related_alerts
+                .iter()
+                .filter_map(|alert_id| alerts.get(alert_id).filter(|a| a.tenant_id == tenant_id).cloned())
+                .collect()
+        };
+
+        Ok(reconstruct(&incident, &alerts, external, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IncidentEvent;
+    use chrono::Duration;
+
+    fn event(event_type: &str, at: DateTime<Utc>) -> IncidentEvent {
+        IncidentEvent {
+            event_id: format!("ev-{}", event_type),
+            timestamp: at,
+            event_type: event_type.to_string(),
+            description: event_type.to_string(),
+            source: "analyst".to_string(),
+            severity: "high".to_string(),
+            data: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn merges_sources_and_annotates_phases_and_gaps() {
+        let t0 = Utc::now() - Duration::hours(10);
+        let mut incident: SecurityIncident =
+            serde_json::from_value(serde_json::json!({
+                "incident_id": "inc-1", "title": "t", "description": "d", "severity": "high",
+                "status": "open", "category": "malware", "priority": 1,
+                "created_at": t0, "updated_at": t0, "assigned_to": "", "reporter": "",
+                "affected_systems": [], "indicators": [], "timeline": [],
+                "mitigation_actions": [], "estimated_impact": 0.0, "containment_status": "none"
+            }))
+            .unwrap();
+        incident.timeline = vec![
+            event("Incident Created", t0),
+            event("IncidentAcknowledged", t0 + Duration::minutes(20)),
+            event("IncidentContained", t0 + Duration::hours(5)),
+        ];
+
+        let external = vec![
+            TimelineEntry {
+                timestamp: t0 + Duration::minutes(30),
+                source: TimelineSource::Sandbox,
+                source_ref: "smp-1".to_string(),
+                kind: "file_created".to_string(),
+                summary: "C:\\evil.exe".to_string(),
+                severity: None,
+                phase: None,
+                attributes: HashMap::new(),
+            },
+            TimelineEntry {
+                timestamp: t0 - Duration::minutes(5),
+                source: TimelineSource::Hunting,
+                source_ref: "m-1".to_string(),
+                kind: "hunt_match".to_string(),
+                summary: "beacon".to_string(),
+                severity: Some("high".to_string()),
+                phase: None,
+                attributes: HashMap::new(),
+            },
+        ];
+
+        let timeline = reconstruct(&incident, &[], external, &TimelineOptions::default());
+        let kinds: Vec<&str> = timeline.entries.iter().map(|e| e.kind.as_str()).collect();
+        assert_eq!(kinds, ["hunt_match", "Incident Created", "IncidentAcknowledged", "file_created", "IncidentContained"]);
+        assert_eq!(timeline.entries[3].phase, Some(IncidentPhase::Analysis));
+
+        let phases: Vec<IncidentPhase> = timeline.phases.iter().map(|span| span.phase).collect();
+        assert_eq!(phases, [IncidentPhase::Detection, IncidentPhase::Analysis, IncidentPhase::Containment]);
+
+        assert_eq!(timeline.gaps.len(), 1);
+        assert_eq!(timeline.gaps[0].phase, IncidentPhase::Analysis);
+        assert_eq!(timeline.gaps[0].duration_minutes, 270);
+        assert_eq!(timeline.source_counts[&TimelineSource::Incident], 3);
+    }
+}
@@ -0,0 +1,426 @@
+//! Intake triage scoring for inbound alerts
+//!
+//! Scores every ingested `SecurityAlert` from five weighted factors (source
+//! reliability, asset criticality, IOC repository hits, prevalence and
+//! duplicate history), applies configurable auto-dispositions and keeps a
+//! per-factor breakdown so analysts can see why an alert landed where it did.
+
+use crate::{SecurityAlert, ThreatIndicator};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriageWeights {
+    pub source_reliability: f64,
+    pub asset_criticality: f64,
+    pub ioc_hits: f64,
+    pub prevalence: f64,
+    pub duplicate_history: f64,
+}
+
+impl Default for TriageWeights {
+    fn default() -> Self {
+        Self {
+            source_reliability: 0.15,
+            asset_criticality: 0.25,
+            ioc_hits: 0.35,
+            prevalence: 0.10,
+            duplicate_history: 0.15,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriageConfig {
+    pub weights: TriageWeights,
+    /// Reliability (0.0 - 1.0) per alert source
+    pub source_reliability: HashMap<String, f64>,
+    pub default_source_reliability: f64,
+    /// Criticality (0.0 - 1.0) per asset identifier
+    pub asset_criticality: HashMap<String, f64>,
+    pub default_asset_criticality: f64,
+    /// Look-back window for prevalence and duplicate history
+    pub history_window_hours: i64,
+    pub auto_close_enabled: bool,
+    /// Alerts scoring below this with an auto-close priority are closed
+    pub auto_close_threshold: f64,
+    pub auto_close_priorities: Vec<String>,
+    pub auto_escalate_enabled: bool,
+    /// Alerts scoring at or above this are escalated regardless of priority
+    pub auto_escalate_threshold: f64,
+    /// Critical-priority alerts are escalated once they reach this score
+    pub critical_escalate_floor: f64,
+}
+
+impl Default for TriageConfig {
+    fn default() -> Self {
+        Self {
+            weights: TriageWeights::default(),
+            source_reliability: HashMap::from([
+                ("EDR".to_string(), 0.9),
+                ("Sandbox".to_string(), 0.85),
+                ("SIEM".to_string(), 0.7),
+                ("IDS".to_string(), 0.6),
+                ("User Report".to_string(), 0.4),
+            ]),
+            default_source_reliability: 0.5,
+            asset_criticality: HashMap::new(),
+            default_asset_criticality: 0.3,
+            history_window_hours: 24,
+            auto_close_enabled: true,
+            auto_close_threshold: 40.0,
+            auto_close_priorities: vec!["Informational".to_string(), "Info".to_string()],
+            auto_escalate_enabled: true,
+            auto_escalate_threshold: 80.0,
+            critical_escalate_floor: 50.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TriageDisposition {
+    AutoClose,
+    AnalystQueue,
+    AutoEscalate,
+}
+
+/// One factor's contribution to the final score
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriageFactor {
+    pub name: String,
+    /// Normalised factor value (0.0 - 1.0)
+    pub value: f64,
+    pub weight: f64,
+    /// Points contributed to the 0 - 100 score
+    pub contribution: f64,
+    pub explanation: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriageResult {
+    pub alert_id: String,
+    pub score: f64,
+    pub disposition: TriageDisposition,
+    pub disposition_reasons: Vec<String>,
+    pub factors: Vec<TriageFactor>,
+    pub ioc_hits: Vec<ThreatIndicator>,
+    pub duplicate_of: Option<String>,
+    pub scored_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+struct TriageHistoryEntry {
+    alert_id: String,
+    tenant_id: String,
+    rule_id: String,
+    fingerprint: String,
+    observed_at: DateTime<Utc>,
+    disposition: TriageDisposition,
+}
+
+/// Stateful triage scorer; remembers recent alerts for prevalence and duplicates
+#[derive(Debug, Default)]
+pub struct TriageEngine {
+    config: TriageConfig,
+    ioc_repository: HashMap<String, ThreatIndicator>,
+    history: Vec<TriageHistoryEntry>,
+}
+
+impl TriageEngine {
+    pub fn new(config: TriageConfig) -> Self {
+        Self {
+            config,
+            ioc_repository: HashMap::new(),
+            history: Vec::new(),
+        }
+    }
+
+    pub fn config(&self) -> &TriageConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: TriageConfig) {
+        self.config = config;
+    }
+
+    /// Add or replace indicators in the IOC repository used for hit scoring
+    pub fn load_indicators(&mut self, indicators: Vec<ThreatIndicator>) -> usize {
+        let count = indicators.len();
+        for indicator in indicators {
+            self.ioc_repository.insert(indicator.value.to_lowercase(), indicator);
+        }
+        count
+    }
+
+    /// Drop a tenant's alerts from the prevalence and duplicate history
+    pub fn forget_tenant(&mut self, tenant_id: &str) -> usize {
+        let before = self.history.len();
+        self.history.retain(|h| h.tenant_id != tenant_id);
+        before - self.history.len()
+    }
+
+    pub fn score_alert(&mut self, alert: &SecurityAlert) -> TriageResult {
+        let window_start = alert.created_at - Duration::hours(self.config.history_window_hours);
+        self.history.retain(|h| h.observed_at >= window_start);
+
+        let weights = self.config.weights.clone();
+        let fingerprint = alert_fingerprint(alert);
+        let mut factors = Vec::new();
+
+        // Source reliability
+        let reliability = self
+            .config
+            .source_reliability
+            .get(&alert.source)
+            .copied()
+            .unwrap_or(self.config.default_source_reliability);
+        factors.push(factor(
+            "source_reliability",
+            reliability,
+            weights.source_reliability,
+            format!("Source '{}' reliability {:.2}", alert.source, reliability),
+        ));
+
+        // Asset criticality
+        let (criticality, critical_asset) = alert
+            .affected_assets
+            .iter()
+            .map(|asset| {
+                let value = self
+                    .config
+                    .asset_criticality
+                    .get(asset)
+                    .copied()
+                    .unwrap_or(self.config.default_asset_criticality);
+                (value, Some(asset.clone()))
+            })
+            .fold((self.config.default_asset_criticality, None), |best, item| if item.0 > best.0 { item } else { best });
+        factors.push(factor(
+            "asset_criticality",
+            criticality,
+            weights.asset_criticality,
+            match critical_asset {
+                Some(asset) => format!("Most critical affected asset '{}' rated {:.2}", asset, criticality),
+                None => format!("No rated assets affected; default criticality {:.2}", criticality),
+            },
+        ));
+
+        // IOC repository hits
+        let ioc_hits: Vec<ThreatIndicator> = alert
+            .indicators
+            .iter()
+            .filter_map(|i| self.ioc_repository.get(&i.value.to_lowercase()).cloned())
+            .collect();
+        let ioc_value = ioc_hits.iter().map(|i| i.confidence).fold(0.0, f64::max);
+        factors.push(factor(
+            "ioc_hits",
+            ioc_value,
+            weights.ioc_hits,
+            if ioc_hits.is_empty() {
+                "No indicators matched the IOC repository".to_string()
+            } else {
+                format!(
+                    "{} indicator(s) matched the IOC repository: {}",
+                    ioc_hits.len(),
+                    ioc_hits.iter().map(|i| i.value.as_str()).collect::<Vec<_>>().join(", ")
+                )
+            },
+        ));
+
+        // Prevalence: rules that fire constantly are less likely to be interesting
+        let rule_occurrences = self
+            .history
+            .iter()
+            .filter(|h| h.tenant_id == alert.tenant_id && h.rule_id == alert.rule_id)
+            .count();
+        let rarity = 1.0 / (1.0 + (1.0 + rule_occurrences as f64).ln());
+        factors.push(factor(
+            "prevalence",
+            rarity,
+            weights.prevalence,
+            format!(
+                "Rule '{}' fired {} time(s) in the last {}h",
+                alert.rule_id, rule_occurrences, self.config.history_window_hours
+            ),
+        ));
+
+        // Duplicate history
+        let previous = self.history.iter().rev().find(|h| h.fingerprint == fingerprint).cloned();
+        let novelty = match &previous {
+            None => 1.0,
+            Some(p) if p.disposition == TriageDisposition::AutoClose => 0.0,
+            Some(_) => 0.3,
+        };
+        factors.push(factor(
+            "duplicate_history",
+            novelty,
+            weights.duplicate_history,
+            match &previous {
+                None => "No duplicate in history window".to_string(),
+                Some(p) => format!("Duplicate of alert {} ({:?})", p.alert_id, p.disposition),
+            },
+        ));
+
+        let total_weight: f64 = factors.iter().map(|f| f.weight).sum();
+        let score = if total_weight > 0.0 {
+            (factors.iter().map(|f| f.value * f.weight).sum::<f64>() / total_weight * 100.0).clamp(0.0, 100.0)
+        } else {
+            0.0
+        };
+        for f in &mut factors {
+            if total_weight > 0.0 {
+                f.contribution = f.value * f.weight / total_weight * 100.0;
+            }
+        }
+
+        let (disposition, disposition_reasons) = self.decide(alert, score, previous.is_some());
+
+        self.history.push(TriageHistoryEntry {
+            alert_id: alert.alert_id.clone(),
+            tenant_id: alert.tenant_id.clone(),
+            rule_id: alert.rule_id.clone(),
+            fingerprint,
+            observed_at: alert.created_at,
+            disposition: disposition.clone(),
+        });
+
+        TriageResult {
+            alert_id: alert.alert_id.clone(),
+            score,
+            disposition,
+            disposition_reasons,
+            factors,
+            ioc_hits,
+            duplicate_of: previous.map(|p| p.alert_id),
+            scored_at: Utc::now(),
+        }
+    }
+
+    fn decide(&self, alert: &SecurityAlert, score: f64, duplicate: bool) -> (TriageDisposition, Vec<String>) {
+        let config = &self.config;
+        let is_critical = alert.priority.eq_ignore_ascii_case("critical");
+
+        if config.auto_escalate_enabled {
+            if score >= config.auto_escalate_threshold {
+                return (
+                    TriageDisposition::AutoEscalate,
+                    vec![format!("Score {:.1} >= escalation threshold {:.1}", score, config.auto_escalate_threshold)],
+                );
+            }
+            if is_critical && score >= config.critical_escalate_floor {
+                return (
+                    TriageDisposition::AutoEscalate,
+                    vec![format!("Critical priority with score {:.1} >= {:.1}", score, config.critical_escalate_floor)],
+                );
+            }
+        }
+
+        if config.auto_close_enabled && score < config.auto_close_threshold && !is_critical {
+            let closable_priority = config
+                .auto_close_priorities
+                .iter()
+                .any(|p| p.eq_ignore_ascii_case(&alert.priority));
+            if closable_priority || duplicate {
+                let reason = if closable_priority {
+                    format!("Priority '{}' is auto-closable", alert.priority)
+                } else {
+                    "Duplicate of a recent alert".to_string()
+                };
+                return (
+                    TriageDisposition::AutoClose,
+                    vec![format!("Score {:.1} < auto-close threshold {:.1}", score, config.auto_close_threshold), reason],
+                );
+            }
+        }
+
+        (TriageDisposition::AnalystQueue, vec![format!("Score {:.1} requires analyst review", score)])
+    }
+}
+
+fn factor(name: &str, value: f64, weight: f64, explanation: String) -> TriageFactor {
+    TriageFactor {
+        name: name.to_string(),
+        value: value.clamp(0.0, 1.0),
+        weight,
+        contribution: 0.0,
+        explanation,
+    }
+}
+
+/// Alerts of one tenant with the same rule, assets and indicator values are duplicates
+fn alert_fingerprint(alert: &SecurityAlert) -> String {
+    let mut assets: Vec<String> = alert.affected_assets.iter().map(|a| a.to_lowercase()).collect();
+    assets.sort();
+    let mut indicators: Vec<String> = alert.indicators.iter().map(|i| i.value.to_lowercase()).collect();
+    indicators.sort();
+    format!("{}|{}|{}|{}", alert.tenant_id, alert.rule_id, assets.join(","), indicators.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn alert(priority: &str, source: &str, assets: &[&str], iocs: &[&str]) -> SecurityAlert {
+        SecurityAlert {
+            alert_id: Uuid::new_v4().to_string(),
+            title: "Test".to_string(),
+            description: String::new(),
+            priority: priority.to_string(),
+            status: "Open".to_string(),
+            source: source.to_string(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            rule_id: "RULE-1".to_string(),
+            rule_name: "Test Rule".to_string(),
+            affected_assets: assets.iter().map(|s| s.to_string()).collect(),
+            indicators: iocs.iter().map(|v| indicator(v, 0.5)).collect(),
+            raw_data: String::new(),
+            false_positive_probability: 0.1,
+            correlation_id: None,
+            tenant_id: "default".to_string(),
+            external_id: None,
+        }
+    }
+
+    fn indicator(value: &str, confidence: f64) -> ThreatIndicator {
+        ThreatIndicator {
+            indicator_id: Uuid::new_v4().to_string(),
+            indicator_type: "IP".to_string(),
+            value: value.to_string(),
+            confidence,
+            severity: "High".to_string(),
+            source: "repo".to_string(),
+            first_seen: Utc::now(),
+            last_seen: Utc::now(),
+            context: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_critical_asset_with_ioc_hit_escalates() {
+        let mut config = TriageConfig::default();
+        config.asset_criticality.insert("dc01".to_string(), 1.0);
+        let mut engine = TriageEngine::new(config);
+        engine.load_indicators(vec![indicator("203.0.113.9", 0.95)]);
+
+        let result = engine.score_alert(&alert("High", "EDR", &["dc01"], &["203.0.113.9"]));
+        assert_eq!(result.disposition, TriageDisposition::AutoEscalate);
+        assert_eq!(result.ioc_hits.len(), 1);
+        assert_eq!(result.factors.len(), 5);
+        let total: f64 = result.factors.iter().map(|f| f.contribution).sum();
+        assert!((total - result.score).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_informational_noise_auto_closes_and_duplicates_are_tracked() {
+        let mut engine = TriageEngine::new(TriageConfig::default());
+        let first = engine.score_alert(&alert("Informational", "User Report", &["ws-1"], &[]));
+        assert_eq!(first.disposition, TriageDisposition::AutoClose);
+
+        let second = engine.score_alert(&alert("Medium", "User Report", &["ws-1"], &[]));
+        assert_eq!(second.duplicate_of, Some(first.alert_id));
+        assert_eq!(second.disposition, TriageDisposition::AutoClose);
+    }
+}